@@ -0,0 +1,4 @@
+-- Migration: 000013_build_step_cancelled
+-- Description: Add 'cancelled' to step_status so runners can mark steps skipped by a build cancellation
+
+ALTER TYPE step_status ADD VALUE IF NOT EXISTS 'cancelled';
@@ -0,0 +1,15 @@
+-- Migration: 000014_build_step_resource_usage
+-- Description: Store per-step resource usage reported by runners (used to right-size Docker resource limits)
+
+ALTER TABLE build_steps
+ADD COLUMN IF NOT EXISTS peak_memory_bytes BIGINT;
+
+ALTER TABLE build_steps
+ADD COLUMN IF NOT EXISTS cpu_time_ms BIGINT;
+
+ALTER TABLE build_steps
+ADD COLUMN IF NOT EXISTS disk_written_bytes BIGINT;
+
+COMMENT ON COLUMN build_steps.peak_memory_bytes IS 'Peak memory of the step process tree or container, in bytes';
+COMMENT ON COLUMN build_steps.cpu_time_ms IS 'Total CPU time consumed by the step, in milliseconds';
+COMMENT ON COLUMN build_steps.disk_written_bytes IS 'Bytes written to disk by the step';
@@ -0,0 +1,27 @@
+-- Migration: 000015_build_logs
+-- Description: Chunked build log storage keyed by task/step with per-step sequence numbers
+
+CREATE TABLE IF NOT EXISTS build_logs (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES build_jobs(id) ON DELETE CASCADE,
+    task_id UUID NOT NULL,
+    step_id VARCHAR(255) NOT NULL,
+    chunk_index BIGINT NOT NULL,
+    byte_offset BIGINT NOT NULL DEFAULT 0,
+    level VARCHAR(16) NOT NULL DEFAULT 'info',
+    content TEXT NOT NULL,
+    is_final BOOLEAN NOT NULL DEFAULT FALSE,
+    logged_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 幂等写入：同一任务同一步骤的块序号只保存一次
+CREATE UNIQUE INDEX IF NOT EXISTS idx_build_logs_chunk
+ON build_logs(task_id, step_id, chunk_index);
+
+CREATE INDEX IF NOT EXISTS idx_build_logs_job_id ON build_logs(job_id, id);
+CREATE INDEX IF NOT EXISTS idx_build_logs_created_at ON build_logs(created_at);
+
+COMMENT ON TABLE build_logs IS 'Build log chunks published by runners; purged together with artifacts by the retention policy';
+COMMENT ON COLUMN build_logs.chunk_index IS 'Per task/step sequence number assigned by the runner';
+COMMENT ON COLUMN build_logs.byte_offset IS 'Byte offset of this chunk within the step log';
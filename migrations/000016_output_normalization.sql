@@ -0,0 +1,11 @@
+-- Migration: 000016_output_normalization
+-- Description: Record whether stored task output / build log content was modified by ANSI and control-character normalization
+
+ALTER TABLE tasks
+ADD COLUMN IF NOT EXISTS output_normalized BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE build_logs
+ADD COLUMN IF NOT EXISTS content_modified BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN tasks.output_normalized IS 'Whether ANSI escape sequences or control characters were stripped/converted from the stored output';
+COMMENT ON COLUMN build_logs.content_modified IS 'Whether the runner stripped/converted ANSI escape sequences or control characters from this chunk';
@@ -0,0 +1,46 @@
+-- Migration: 000017_ssh_host_keys
+-- Description: Centrally managed SSH known_hosts and structured host key verification failures
+
+ALTER TYPE failure_reason ADD VALUE IF NOT EXISTS 'host_key_mismatch';
+
+-- 主机级密钥验证策略与 known_hosts（模型已有字段，此前未建列）
+ALTER TABLE assets_hosts
+    ADD COLUMN IF NOT EXISTS host_key_verification VARCHAR(20),
+    ADD COLUMN IF NOT EXISTS known_hosts JSONB;
+
+CREATE TABLE IF NOT EXISTS ssh_known_hosts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    host VARCHAR(255) NOT NULL,
+    port INT NOT NULL DEFAULT 22,
+    key_type VARCHAR(64) NOT NULL,
+    public_key TEXT NOT NULL,
+    fingerprint VARCHAR(128) NOT NULL,
+    pinned_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (host, port)
+);
+
+CREATE TABLE IF NOT EXISTS ssh_host_key_failures (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    host_id UUID REFERENCES assets_hosts(id) ON DELETE CASCADE,
+    task_id UUID REFERENCES tasks(id) ON DELETE SET NULL,
+    host VARCHAR(255) NOT NULL,
+    port INT NOT NULL,
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('mismatch', 'unknown')),
+    expected_fingerprint VARCHAR(128),
+    presented_fingerprint VARCHAR(128) NOT NULL,
+    key_type VARCHAR(64) NOT NULL,
+    public_key TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ssh_host_key_failures_unresolved
+ON ssh_host_key_failures(created_at DESC) WHERE resolved_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_ssh_host_key_failures_host ON ssh_host_key_failures(host, port);
+
+COMMENT ON TABLE ssh_known_hosts IS 'Centrally pinned SSH host keys; used by the SSH executor and pushed to runners via heartbeat';
+COMMENT ON COLUMN ssh_known_hosts.fingerprint IS 'OpenSSH SHA256 fingerprint (SHA256:<base64>) of the public key';
+COMMENT ON TABLE ssh_host_key_failures IS 'Host key verification failures with expected vs presented fingerprints, resolved by re-pinning';
@@ -0,0 +1,18 @@
+-- Migration: 000018_approval_auto_approval
+-- Description: Auto-approval of low-risk repeat template jobs referencing the original approval
+
+-- 模板作业的审批指纹（模板 + 参数 + 目标主机）
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS approval_fingerprint VARCHAR(64);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_approval_fingerprint
+ON jobs(approval_fingerprint) WHERE approval_fingerprint IS NOT NULL;
+
+-- 自动审批记录：标记为自动并引用原始审批请求
+ALTER TABLE approval_records
+    ADD COLUMN IF NOT EXISTS is_automatic BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS source_approval_id UUID REFERENCES approval_requests(id) ON DELETE SET NULL;
+
+COMMENT ON COLUMN jobs.approval_fingerprint IS 'SHA-256 of template id, canonical parameters and sorted target host ids; set for template jobs only';
+COMMENT ON COLUMN approval_records.is_automatic IS 'Synthetic record created by the auto-approval policy';
+COMMENT ON COLUMN approval_records.source_approval_id IS 'Original human approval this automatic record was derived from';
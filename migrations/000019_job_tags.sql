@@ -0,0 +1,27 @@
+-- Migration: 000019_job_tags
+-- Description: Managed namespaced job tags (namespace:value) for tag-based reporting
+
+CREATE TABLE IF NOT EXISTS job_tag_namespaces (
+    name VARCHAR(64) PRIMARY KEY,
+    description TEXT,
+    -- 取值正则；为空时只允许 job_tags 中登记的值
+    value_pattern TEXT,
+    created_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS job_tags (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    namespace VARCHAR(64) NOT NULL REFERENCES job_tag_namespaces(name) ON DELETE CASCADE,
+    value VARCHAR(128) NOT NULL,
+    description TEXT,
+    created_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (namespace, value)
+);
+
+COMMENT ON TABLE job_tag_namespaces IS 'Tag namespaces (e.g. team, change); namespaced job tags must use a registered namespace';
+COMMENT ON COLUMN job_tag_namespaces.value_pattern IS 'Regex for free values (e.g. ^CHG-[0-9]+$); when NULL only values registered in job_tags are accepted';
+COMMENT ON TABLE job_tags IS 'Registered values for tag namespaces without a value pattern (e.g. team:payments)';
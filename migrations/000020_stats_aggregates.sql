@@ -0,0 +1,87 @@
+-- Migration: 000020_stats_aggregates
+-- Description: Pre-aggregated statistics tables for operations dashboards, refreshed by a background task
+
+-- 记录执行构建的 Runner，用于统计 Runner 负载
+ALTER TABLE build_jobs
+    ADD COLUMN IF NOT EXISTS runner_name VARCHAR(255);
+
+CREATE INDEX IF NOT EXISTS idx_build_jobs_runner_name
+ON build_jobs(runner_name) WHERE runner_name IS NOT NULL;
+
+-- 每日作业数量（按类型与状态）
+CREATE TABLE IF NOT EXISTS stats_jobs_daily (
+    day DATE NOT NULL,
+    job_type job_type NOT NULL,
+    status job_status NOT NULL,
+    job_count BIGINT NOT NULL,
+    avg_duration_secs DOUBLE PRECISION,
+    refreshed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (day, job_type, status)
+);
+
+-- 每日失败作业的恢复时间（按失败日期）
+CREATE TABLE IF NOT EXISTS stats_job_recovery_daily (
+    day DATE NOT NULL,
+    job_type job_type NOT NULL,
+    failed_jobs BIGINT NOT NULL,
+    recovered_jobs BIGINT NOT NULL,
+    avg_recovery_secs DOUBLE PRECISION,
+    refreshed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (day, job_type)
+);
+
+-- 每日主机任务失败数
+CREATE TABLE IF NOT EXISTS stats_host_failures_daily (
+    day DATE NOT NULL,
+    host_id UUID NOT NULL REFERENCES assets_hosts(id) ON DELETE CASCADE,
+    total_tasks BIGINT NOT NULL,
+    failed_tasks BIGINT NOT NULL,
+    refreshed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (day, host_id)
+);
+
+-- 每日 Runner 构建负载
+CREATE TABLE IF NOT EXISTS stats_runner_daily (
+    day DATE NOT NULL,
+    runner_name VARCHAR(255) NOT NULL,
+    build_count BIGINT NOT NULL,
+    succeeded_count BIGINT NOT NULL,
+    failed_count BIGINT NOT NULL,
+    busy_secs DOUBLE PRECISION NOT NULL DEFAULT 0,
+    refreshed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (day, runner_name)
+);
+
+-- 每日审批耗时分位数（不含自动审批）
+CREATE TABLE IF NOT EXISTS stats_approval_daily (
+    day DATE PRIMARY KEY,
+    completed_requests BIGINT NOT NULL,
+    approved_requests BIGINT NOT NULL,
+    rejected_requests BIGINT NOT NULL,
+    avg_mins DOUBLE PRECISION,
+    p50_mins DOUBLE PRECISION,
+    p90_mins DOUBLE PRECISION,
+    p99_mins DOUBLE PRECISION,
+    refreshed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 并发使用率采样
+CREATE TABLE IF NOT EXISTS stats_concurrency_samples (
+    id BIGSERIAL PRIMARY KEY,
+    sampled_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    global_limit INT NOT NULL,
+    global_used INT NOT NULL,
+    utilization_percent REAL NOT NULL,
+    scopes JSONB NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX IF NOT EXISTS idx_stats_concurrency_samples_sampled_at
+ON stats_concurrency_samples(sampled_at);
+
+COMMENT ON COLUMN build_jobs.runner_name IS 'Runner that reported status for this build';
+COMMENT ON TABLE stats_jobs_daily IS 'Jobs per UTC day by type and status; recomputed for the refresh window';
+COMMENT ON TABLE stats_job_recovery_daily IS 'Failed jobs and time until the next successful job with the same name and type';
+COMMENT ON TABLE stats_host_failures_daily IS 'Per-host task totals and failures (failed or timeout) per UTC day';
+COMMENT ON TABLE stats_runner_daily IS 'Per-runner build counts and busy time per UTC day';
+COMMENT ON TABLE stats_approval_daily IS 'Approval turnaround (requested to completed) percentiles, excluding auto-approvals';
+COMMENT ON TABLE stats_concurrency_samples IS 'Periodic snapshots of concurrency controller utilization';
@@ -0,0 +1,20 @@
+-- Migration: 000021_host_maintenance
+-- Description: Per-host maintenance windows; tasks targeting a host under maintenance are held until it ends
+
+-- 挂起等待主机维护结束的任务
+ALTER TYPE task_status ADD VALUE IF NOT EXISTS 'waiting_maintenance';
+
+-- 维护窗口（status = 'maintenance' 时生效）
+ALTER TABLE assets_hosts
+    ADD COLUMN IF NOT EXISTS maintenance_until TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS maintenance_reason TEXT,
+    ADD COLUMN IF NOT EXISTS maintenance_started_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS maintenance_set_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_assets_hosts_maintenance_until
+ON assets_hosts(maintenance_until) WHERE status = 'maintenance';
+
+COMMENT ON COLUMN assets_hosts.maintenance_until IS 'Planned end of maintenance; NULL means until cleared manually';
+COMMENT ON COLUMN assets_hosts.maintenance_reason IS 'Why the host is under maintenance';
+COMMENT ON COLUMN assets_hosts.maintenance_started_at IS 'When maintenance was set';
+COMMENT ON COLUMN assets_hosts.maintenance_set_by IS 'User who set maintenance';
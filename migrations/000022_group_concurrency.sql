@@ -0,0 +1,12 @@
+-- Migration: 000022_group_concurrency
+-- Description: Per-group default concurrency limit and production flag used by the concurrency controller
+
+ALTER TABLE assets_groups
+    ADD COLUMN IF NOT EXISTS concurrent_limit INT CHECK (concurrent_limit IS NULL OR concurrent_limit > 0),
+    ADD COLUMN IF NOT EXISTS is_production BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 已有的 prod 分组默认视为生产分组
+UPDATE assets_groups SET is_production = TRUE WHERE environment = 'prod';
+
+COMMENT ON COLUMN assets_groups.concurrent_limit IS 'Max concurrent tasks on hosts of this group; NULL falls back to the global group/production limit';
+COMMENT ON COLUMN assets_groups.is_production IS 'Production group: uses the stricter production limit when concurrent_limit is NULL';
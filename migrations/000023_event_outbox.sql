@@ -0,0 +1,26 @@
+-- Migration: 000023_event_outbox
+-- Description: Transactional outbox; events are written with the state change and relayed to the event bus
+
+CREATE TABLE IF NOT EXISTS event_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    correlation_id VARCHAR(128),
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ
+);
+
+-- 中继按写入顺序扫描未投递的事件
+CREATE INDEX IF NOT EXISTS idx_event_outbox_pending
+ON event_outbox(id) WHERE delivered_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_event_outbox_delivered_at
+ON event_outbox(delivered_at) WHERE delivered_at IS NOT NULL;
+
+COMMENT ON TABLE event_outbox IS 'Events written in the same transaction as the state change they describe';
+COMMENT ON COLUMN event_outbox.payload IS 'Serialized RealtimeEvent ({"type": ..., "data": ...})';
+COMMENT ON COLUMN event_outbox.correlation_id IS 'request_id of the request that produced the event';
+COMMENT ON COLUMN event_outbox.attempts IS 'Failed relay attempts; rows stop being retried after the limit';
+COMMENT ON COLUMN event_outbox.delivered_at IS 'When the relay published the event; NULL while pending';
@@ -0,0 +1,22 @@
+-- Migration: 000024_build_status_sequence
+-- Description: Track runner message sequence numbers for exactly-once build status ingestion
+
+-- 每个任务已处理的最大状态消息序号；序号不大于该值的状态消息视为重复或过期
+CREATE TABLE IF NOT EXISTS build_status_sequences (
+    task_id UUID PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES build_jobs(id) ON DELETE CASCADE,
+    last_sequence BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_build_status_sequences_job_id ON build_status_sequences(job_id);
+
+ALTER TABLE build_steps
+ADD COLUMN IF NOT EXISTS status_sequence BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE build_logs
+ADD COLUMN IF NOT EXISTS sequence BIGINT NOT NULL DEFAULT 0;
+
+COMMENT ON TABLE build_status_sequences IS 'Highest status message sequence applied per build task';
+COMMENT ON COLUMN build_steps.status_sequence IS 'Sequence of the status message that last updated this step; older updates are ignored';
+COMMENT ON COLUMN build_logs.sequence IS 'Runner message sequence; 0 for runners that do not send one';
@@ -0,0 +1,13 @@
+-- Migration: 000025_job_singleton_key
+-- Description: At most one pending/running job per singleton key
+
+-- 互斥键：同一互斥键同时只允许一个作业执行；排队中的作业保持 pending，前序作业结束后再调度
+ALTER TABLE jobs
+ADD COLUMN IF NOT EXISTS singleton_key VARCHAR(255),
+ADD COLUMN IF NOT EXISTS singleton_waiting BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS idx_jobs_singleton_active ON jobs(singleton_key, created_at)
+WHERE singleton_key IS NOT NULL AND status IN ('pending', 'running');
+
+COMMENT ON COLUMN jobs.singleton_key IS 'Jobs sharing this key never run concurrently';
+COMMENT ON COLUMN jobs.singleton_waiting IS 'Job is queued behind an active job with the same singleton key';
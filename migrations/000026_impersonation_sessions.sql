@@ -0,0 +1,45 @@
+-- Migration: 000026_impersonation_sessions
+-- Description: Admin impersonation sessions for support and the permission that guards them
+
+INSERT INTO permissions (resource, action, description) VALUES
+    ('user', 'impersonate', 'Obtain short-lived tokens acting as another user')
+ON CONFLICT (resource, action) DO NOTHING;
+
+-- 仅授予 admin 角色
+DO $$
+DECLARE
+    admin_role_id UUID;
+BEGIN
+    SELECT id INTO admin_role_id FROM roles WHERE name = 'admin';
+
+    IF admin_role_id IS NOT NULL THEN
+        INSERT INTO role_permissions (role_id, permission_id)
+        SELECT admin_role_id, p.id FROM permissions p
+        WHERE p.resource = 'user' AND p.action = 'impersonate'
+        ON CONFLICT DO NOTHING;
+    END IF;
+END $$;
+
+-- 模拟会话：每次签发模拟令牌创建一条记录，终止后令牌立即失效
+CREATE TABLE IF NOT EXISTS impersonation_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    impersonator_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    impersonator_name VARCHAR(100) NOT NULL,
+    target_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_username VARCHAR(100) NOT NULL,
+    reason TEXT NOT NULL,
+    source_ip VARCHAR(45),
+    user_agent TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    terminated_at TIMESTAMPTZ,
+    terminated_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_impersonation_sessions_active
+    ON impersonation_sessions(expires_at)
+    WHERE terminated_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_impersonation_sessions_target
+    ON impersonation_sessions(target_user_id, created_at DESC);
+
+COMMENT ON TABLE impersonation_sessions IS 'Support impersonation sessions issued to administrators';
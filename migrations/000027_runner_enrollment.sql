@@ -0,0 +1,30 @@
+-- Migration: 000027_runner_enrollment
+-- Description: One-time enrollment tokens for password-less runner bootstrap and per-runner API keys
+
+-- 注册令牌只保存哈希；首次使用后即失效
+CREATE TABLE IF NOT EXISTS runner_enrollment_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    runner_name VARCHAR(255) NOT NULL,
+    labels JSONB NOT NULL DEFAULT '[]',
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    used_by_runner_id UUID REFERENCES runners(id) ON DELETE SET NULL,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_runner_enrollment_tokens_pending
+    ON runner_enrollment_tokens(expires_at)
+    WHERE used_at IS NULL AND revoked_at IS NULL;
+
+-- Runner 专属凭据（SHA-256 哈希），通过注册令牌换取
+ALTER TABLE runners
+ADD COLUMN IF NOT EXISTS api_key_hash VARCHAR(64);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_runners_api_key_hash
+    ON runners(api_key_hash)
+    WHERE api_key_hash IS NOT NULL;
+
+COMMENT ON TABLE runner_enrollment_tokens IS 'One-time runner enrollment tokens bound to an expected runner name and labels';
@@ -0,0 +1,24 @@
+-- Migration: 000028_watches
+-- Description: Per-user watches on jobs, hosts and templates for targeted status notifications
+
+CREATE TYPE watch_target_type AS ENUM ('job', 'host', 'template');
+
+-- 记录模板作业的来源模板，关注模板的用户据此收到其作业的状态通知
+ALTER TABLE jobs
+ADD COLUMN IF NOT EXISTS template_id UUID REFERENCES job_templates(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_jobs_template_id ON jobs(template_id) WHERE template_id IS NOT NULL;
+
+-- 关注对象不设外键（目标分属不同表），对象删除后关注记录不再产生通知
+CREATE TABLE IF NOT EXISTS watches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_type watch_target_type NOT NULL,
+    target_id UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, target_type, target_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_watches_target ON watches(target_type, target_id);
+
+COMMENT ON TABLE watches IS 'User subscriptions to job, host or template status changes';
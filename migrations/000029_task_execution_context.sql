@@ -0,0 +1,8 @@
+-- Migration: 000029_task_execution_context
+-- Description: Sanitized snapshot of the effective execution context per task
+
+-- 执行用户、认证方式类型（不含凭据）、SSH 选项、解释器与环境变量（值已掩码）
+ALTER TABLE tasks
+ADD COLUMN IF NOT EXISTS execution_context JSONB;
+
+COMMENT ON COLUMN tasks.execution_context IS 'Sanitized execution context captured when the task started; never contains credentials';
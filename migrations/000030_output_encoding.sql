@@ -0,0 +1,11 @@
+-- Migration: 000030_output_encoding
+-- Description: Per-host output encoding (utf-8 / gbk / auto) and the detected encoding per task
+
+-- 为空时按 UTF-8 处理
+ALTER TABLE assets_hosts
+ADD COLUMN IF NOT EXISTS output_encoding VARCHAR(16)
+    CHECK (output_encoding IN ('utf-8', 'gbk', 'auto'));
+
+-- 输出的原始编码（存储与推送的输出均已转换为 UTF-8）
+ALTER TABLE tasks
+ADD COLUMN IF NOT EXISTS output_encoding VARCHAR(16);
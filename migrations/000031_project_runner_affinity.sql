@@ -0,0 +1,18 @@
+-- Migration: 000031_project_runner_affinity
+-- Description: Last successful runner per repository/branch for cache-friendly sticky scheduling
+
+-- 每个仓库分支只保留最近一次成功构建的 Runner
+CREATE TABLE IF NOT EXISTS project_runner_affinity (
+    repository VARCHAR(500) NOT NULL,
+    branch VARCHAR(255) NOT NULL,
+    runner_id UUID NOT NULL REFERENCES runners(id) ON DELETE CASCADE,
+    runner_name VARCHAR(255) NOT NULL,
+    last_build_job_id UUID REFERENCES build_jobs(id) ON DELETE SET NULL,
+    last_success_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (repository, branch)
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_runner_affinity_runner
+    ON project_runner_affinity(runner_id);
+
+COMMENT ON TABLE project_runner_affinity IS 'Runner that last built each repository/branch successfully; used as a scheduling preference within a TTL';
@@ -0,0 +1,25 @@
+-- Migration: 000032_job_archive
+-- Description: Cold storage tables for completed jobs moved out of the hot jobs/tasks tables
+
+-- 归档表与热表列结构一致（归档以 INSERT ... SELECT * 搬移），
+-- 之后给 jobs/tasks 新增列时必须同步给归档表新增同名列
+CREATE TABLE IF NOT EXISTS jobs_archive (LIKE jobs INCLUDING DEFAULTS);
+
+ALTER TABLE jobs_archive ADD PRIMARY KEY (id);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_archive_created_at ON jobs_archive(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_jobs_archive_created_by ON jobs_archive(created_by);
+
+-- 任务完整输出（output_detail）不保留在数据库中，打包压缩后写入对象存储
+CREATE TABLE IF NOT EXISTS tasks_archive (LIKE tasks INCLUDING DEFAULTS);
+
+ALTER TABLE tasks_archive
+    ADD PRIMARY KEY (id),
+    ADD COLUMN IF NOT EXISTS output_location VARCHAR(500),
+    ADD COLUMN IF NOT EXISTS archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE INDEX IF NOT EXISTS idx_tasks_archive_job_id ON tasks_archive(job_id);
+
+COMMENT ON TABLE jobs_archive IS 'Completed jobs moved out of the hot jobs table after the archive retention period';
+COMMENT ON TABLE tasks_archive IS 'Tasks of archived jobs; full output lives in object storage at output_location';
+COMMENT ON COLUMN tasks_archive.output_location IS 'Object storage location of the gzipped output bundle holding this task''s output_detail';
@@ -0,0 +1,32 @@
+-- Migration: 000034_security_anomalies
+-- Description: Anomalies flagged by the background analyzer over login and job activity
+
+-- kind: new_login_ip / failed_auth_spike / job_creation_spike / off_hours_production
+-- dedup_key 由检测器按异常对象与时间窗口生成，重复扫描同一窗口不会产生重复记录
+CREATE TABLE IF NOT EXISTS security_anomalies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind VARCHAR(50) NOT NULL,
+    severity VARCHAR(20) NOT NULL CHECK (severity IN ('low', 'medium', 'high')),
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    username VARCHAR(100),
+    source_ip VARCHAR(45),
+    summary TEXT NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    dedup_key VARCHAR(500) NOT NULL UNIQUE,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    acknowledged_at TIMESTAMPTZ,
+    acknowledged_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    acknowledge_note TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_security_anomalies_detected ON security_anomalies(detected_at DESC);
+CREATE INDEX IF NOT EXISTS idx_security_anomalies_kind ON security_anomalies(kind, detected_at DESC);
+CREATE INDEX IF NOT EXISTS idx_security_anomalies_user ON security_anomalies(user_id, detected_at DESC) WHERE user_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_security_anomalies_open ON security_anomalies(detected_at DESC) WHERE acknowledged_at IS NULL;
+
+-- 新 IP 登录检测按 (user_id, source_ip) 回查历史成功登录；失败激增按来源 IP 聚合
+CREATE INDEX IF NOT EXISTS idx_login_events_user_ip ON login_events(user_id, source_ip, occurred_at DESC) WHERE event_type = 'login_success';
+CREATE INDEX IF NOT EXISTS idx_login_events_failure_ip ON login_events(source_ip, occurred_at DESC) WHERE event_type = 'login_failure';
+
+COMMENT ON TABLE security_anomalies IS 'Suspicious login/job activity flagged by the anomaly analyzer';
+COMMENT ON COLUMN security_anomalies.dedup_key IS 'Detector-specific identity of the anomaly; repeated scans of the same window are ignored';
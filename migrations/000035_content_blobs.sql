@@ -0,0 +1,41 @@
+-- Migration: 000035_content_blobs
+-- Description: Content-addressable (sha256) blob store shared by script jobs and build artifacts
+
+-- 内容以 sha256 寻址，相同内容只在对象存储中保存一份
+-- ref_count 与 content_blob_refs 的行数保持一致（由 BlobStore 在同一事务内维护）；
+-- ref_count 为 0 且 touched_at 超过宽限期的 blob 由后台 GC 回收
+CREATE TABLE IF NOT EXISTS content_blobs (
+    sha256 CHAR(64) PRIMARY KEY CHECK (sha256 ~ '^[0-9a-f]{64}$'),
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    location VARCHAR(1000) NOT NULL,
+    ref_count INT NOT NULL DEFAULT 0 CHECK (ref_count >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    touched_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_content_blobs_unreferenced ON content_blobs(touched_at) WHERE ref_count = 0;
+
+-- 引用方：owner_type 为 job（脚本内容）/ artifact（构建产物）
+CREATE TABLE IF NOT EXISTS content_blob_refs (
+    sha256 CHAR(64) NOT NULL REFERENCES content_blobs(sha256) ON DELETE RESTRICT,
+    owner_type VARCHAR(50) NOT NULL,
+    owner_id UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (sha256, owner_type, owner_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_content_blob_refs_owner ON content_blob_refs(owner_type, owner_id);
+
+-- 脚本作业的脚本内容改为按哈希引用（写入 blob 存储后 jobs.script 为空）
+-- 归档表需同步新增同名列，保持与热表列结构一致
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS script_sha256 CHAR(64);
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS script_sha256 CHAR(64);
+
+ALTER TABLE build_artifacts ADD COLUMN IF NOT EXISTS blob_sha256 CHAR(64);
+CREATE INDEX IF NOT EXISTS idx_build_artifacts_blob ON build_artifacts(blob_sha256) WHERE blob_sha256 IS NOT NULL;
+
+COMMENT ON TABLE content_blobs IS 'Deduplicated content keyed by sha256; the object lives in storage at location';
+COMMENT ON COLUMN content_blobs.touched_at IS 'Last upload or release; unreferenced blobs are only collected after the GC grace period';
+COMMENT ON TABLE content_blob_refs IS 'Owners referencing a blob; a blob is collectable once it has no references';
+COMMENT ON COLUMN jobs.script_sha256 IS 'Script content stored in content_blobs (jobs.script is NULL when set)';
+COMMENT ON COLUMN build_artifacts.blob_sha256 IS 'Artifact content uploaded to the blob store, when available';
@@ -0,0 +1,29 @@
+-- Migration: 000036_evidence_exports
+-- Description: Signed compliance evidence archives (job, task logs, approvals, audit logs) generated asynchronously
+
+CREATE TYPE evidence_export_status AS ENUM ('pending', 'running', 'completed', 'failed');
+
+-- job_id 不设外键：作业可能已归档到 jobs_archive，证据包仍需可追溯
+-- 归档文件写入对象存储（location），sha256/signature 供审计方核验完整性
+CREATE TABLE IF NOT EXISTS evidence_exports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    job_id UUID NOT NULL,
+    requested_by UUID NOT NULL REFERENCES users(id),
+    status evidence_export_status NOT NULL DEFAULT 'pending',
+    progress INT NOT NULL DEFAULT 0 CHECK (progress BETWEEN 0 AND 100),
+    stage VARCHAR(50),
+    location VARCHAR(1000),
+    size_bytes BIGINT,
+    sha256 CHAR(64),
+    signature VARCHAR(128),
+    error_message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_evidence_exports_job ON evidence_exports(job_id, created_at DESC);
+
+COMMENT ON TABLE evidence_exports IS 'Asynchronously generated, signed evidence archives for a job';
+COMMENT ON COLUMN evidence_exports.sha256 IS 'sha256 of the stored (gzip) archive';
+COMMENT ON COLUMN evidence_exports.signature IS 'HMAC-SHA256 of the archive payload, also embedded in the archive';
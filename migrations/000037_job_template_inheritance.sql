@@ -0,0 +1,24 @@
+-- Migration: 000037_job_template_inheritance
+-- Description: Job template inheritance (extends a base template) and composition of snippet templates
+
+-- 继承：子模板继承基础模板的默认配置与参数定义，template_content 为空时沿用基础模板内容，
+-- prepend_content / append_content 拼接在继承内容前后
+ALTER TABLE job_templates
+ADD COLUMN IF NOT EXISTS extends_template_id UUID REFERENCES job_templates(id) ON DELETE SET NULL;
+
+ALTER TABLE job_templates
+ADD COLUMN IF NOT EXISTS prepend_content TEXT;
+
+ALTER TABLE job_templates
+ADD COLUMN IF NOT EXISTS append_content TEXT;
+
+-- 组合：按顺序引用的片段模板 ID 列表，片段内容依次拼接在本模板内容之前
+ALTER TABLE job_templates
+ADD COLUMN IF NOT EXISTS includes JSONB NOT NULL DEFAULT '[]';
+
+CREATE INDEX IF NOT EXISTS idx_job_templates_extends ON job_templates(extends_template_id) WHERE extends_template_id IS NOT NULL;
+
+COMMENT ON COLUMN job_templates.extends_template_id IS 'Base template this template inherits defaults, parameter schema and content from';
+COMMENT ON COLUMN job_templates.prepend_content IS 'Fragment placed before the inherited/own content';
+COMMENT ON COLUMN job_templates.append_content IS 'Fragment placed after the inherited/own content';
+COMMENT ON COLUMN job_templates.includes IS 'Ordered ids of snippet templates composed into this template';
@@ -0,0 +1,8 @@
+-- Migration: 000038_approval_quorum_rules
+-- Description: Quorum rules on approval requests (M-of-N across approval groups or named approvers)
+
+-- 法定人数规则：所有规则同时满足才批准，每个审批人只计入一条规则
+ALTER TABLE approval_requests
+ADD COLUMN IF NOT EXISTS quorum_rules JSONB;
+
+COMMENT ON COLUMN approval_requests.quorum_rules IS 'Quorum rules (all must be met); each rule requires min_approvals from an approval group and/or named users, an approver counts toward one rule only';
@@ -0,0 +1,11 @@
+-- Migration: 000039_task_diagnostics
+-- Description: Structured failure diagnostics per task
+
+-- 失败时记录到达的连接阶段、各阶段耗时（DNS/TCP/握手/认证/执行）与 stderr 末尾若干行
+ALTER TABLE tasks
+ADD COLUMN IF NOT EXISTS diagnostics JSONB;
+
+ALTER TABLE tasks_archive
+ADD COLUMN IF NOT EXISTS diagnostics JSONB;
+
+COMMENT ON COLUMN tasks.diagnostics IS 'Failure diagnostics: SSH phase reached, per-phase timings and stderr tail';
@@ -0,0 +1,24 @@
+-- Migration: 000040_connection_test_runs
+-- Description: Stored results of batch (per group) staged host connection tests
+
+CREATE TYPE connection_test_run_status AS ENUM ('running', 'completed', 'failed');
+
+-- results 为各主机的分阶段测试报告，summary 为按结果分类的计数（可达、认证失败、密钥不一致等）
+CREATE TABLE IF NOT EXISTS connection_test_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    group_id UUID NOT NULL REFERENCES assets_groups(id) ON DELETE CASCADE,
+    requested_by UUID NOT NULL REFERENCES users(id),
+    status connection_test_run_status NOT NULL DEFAULT 'running',
+    concurrency INT NOT NULL,
+    total_hosts INT NOT NULL,
+    completed_hosts INT NOT NULL DEFAULT 0,
+    summary JSONB,
+    results JSONB,
+    error_message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_connection_test_runs_group ON connection_test_runs(group_id, created_at DESC);
+
+COMMENT ON TABLE connection_test_runs IS 'Batch staged connection tests across all hosts of a group';
@@ -0,0 +1,29 @@
+-- Migration: 000041_job_chaining
+-- Description: On-success / on-failure follow-up jobs launched from templates when a job finishes
+
+-- on_*_job_template 为后续作业配置（模板、参数及从父作业结果映射的参数、目标、嵌套的后续作业）
+-- 子作业记录 parent_job_id/chain_trigger/chain_depth；不设外键，父作业可能已归档
+-- chain_status：父作业结束且命中后续作业时置为 pending，由后台派发为 launched/skipped/failed
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS on_success_job_template JSONB;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS on_failure_job_template JSONB;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS parent_job_id UUID;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS chain_trigger VARCHAR(20);
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS chain_depth INT NOT NULL DEFAULT 0;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS chain_status VARCHAR(20);
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS chain_error TEXT;
+
+-- 归档表需同步新增同名列，保持与热表列结构一致
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS on_success_job_template JSONB;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS on_failure_job_template JSONB;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS parent_job_id UUID;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS chain_trigger VARCHAR(20);
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS chain_depth INT NOT NULL DEFAULT 0;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS chain_status VARCHAR(20);
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS chain_error TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_jobs_parent_job_id ON jobs(parent_job_id) WHERE parent_job_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_jobs_chain_pending ON jobs(completed_at) WHERE chain_status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_jobs_archive_parent_job_id ON jobs_archive(parent_job_id) WHERE parent_job_id IS NOT NULL;
+
+COMMENT ON COLUMN jobs.parent_job_id IS 'Job whose completion launched this follow-up job';
+COMMENT ON COLUMN jobs.chain_status IS 'Follow-up dispatch state: pending, launched, skipped or failed (NULL when no follow-up applies)';
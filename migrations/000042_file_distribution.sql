@@ -0,0 +1,21 @@
+-- Migration: 000042_file_distribution
+-- Description: Inline file content distribution jobs with backup, per-host diff and validated rollback
+
+-- 文件分发作业：内容写入目标主机指定路径
+ALTER TYPE job_type ADD VALUE IF NOT EXISTS 'file';
+
+-- 分发选项（路径、内容、权限/属主、是否备份、校验命令）
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS file_spec JSONB;
+
+-- 归档表需同步新增同名列，保持与热表列结构一致
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS file_spec JSONB;
+
+-- 每台主机的分发结果（是否变化、备份路径、diff、校验与回滚情况）
+ALTER TABLE tasks
+ADD COLUMN IF NOT EXISTS file_result JSONB;
+
+ALTER TABLE tasks_archive
+ADD COLUMN IF NOT EXISTS file_result JSONB;
+
+COMMENT ON COLUMN jobs.file_spec IS 'File distribution spec: target path, content, mode/owner, backup flag and validation command';
+COMMENT ON COLUMN tasks.file_result IS 'Per-host file distribution result: changed, backup path, diff, validation and rollback';
@@ -0,0 +1,19 @@
+-- Migration: 000043_job_target_sets
+-- Description: Host sets derived from job results, usable as targets of follow-up jobs
+
+-- 按作业结果筛选出的主机集合（如失败主机、输出匹配正则的主机），创建作业时通过 target_set_id 引用
+-- 不对 source_job_id 设外键，源作业可能已归档
+CREATE TABLE IF NOT EXISTS job_target_sets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_job_id UUID NOT NULL,
+    name VARCHAR(200),
+    filter JSONB NOT NULL,
+    host_ids JSONB NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_target_sets_source_job_id ON job_target_sets(source_job_id);
+
+COMMENT ON TABLE job_target_sets IS 'Ad-hoc host sets derived from a finished job''s results';
+COMMENT ON COLUMN job_target_sets.filter IS 'Result filter: succeeded, failed, non_zero_exit, output_matches or output_not_matches';
@@ -0,0 +1,23 @@
+-- Migration: 000044_approval_reminders
+-- Description: Reminder schedule for pending approvals and the reminders sent
+
+-- 生效的提醒进度（占审批时限的百分比，如 [50, 80]），未设置超时或关闭提醒时为 NULL
+ALTER TABLE approval_requests ADD COLUMN IF NOT EXISTS reminder_percents JSONB;
+
+-- 已发送的提醒，每个进度点只发送一次
+CREATE TABLE IF NOT EXISTS approval_reminders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    approval_request_id UUID NOT NULL REFERENCES approval_requests(id) ON DELETE CASCADE,
+    percent INTEGER NOT NULL,
+    recipients JSONB NOT NULL DEFAULT '[]',
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (approval_request_id, percent)
+);
+
+CREATE INDEX IF NOT EXISTS idx_approval_requests_pending_reminders
+    ON approval_requests(expires_at)
+    WHERE status = 'pending' AND reminder_percents IS NOT NULL;
+
+COMMENT ON COLUMN approval_requests.reminder_percents IS 'Percentages of the approval window at which outstanding approvers are reminded';
+COMMENT ON TABLE approval_reminders IS 'Reminders sent to outstanding approvers of pending approval requests';
+COMMENT ON COLUMN approval_reminders.recipients IS 'Approvers who had not decided when the reminder was sent';
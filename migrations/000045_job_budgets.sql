@@ -0,0 +1,11 @@
+-- Migration: 000045_job_budgets
+-- Description: Per-job execution budgets (output bytes, events, task count)
+
+-- 超出预算后被停止的任务
+ALTER TYPE failure_reason ADD VALUE IF NOT EXISTS 'budget_exceeded';
+
+-- 作业超出的执行预算（预算项、上限、实际值），未超出时为 NULL
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS budget_exceeded JSONB;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS budget_exceeded JSONB;
+
+COMMENT ON COLUMN jobs.budget_exceeded IS 'Execution budget breached by the job: limit (output_bytes, events or tasks), max, observed and breached_at';
@@ -0,0 +1,9 @@
+-- Migration: 000046_streamed_scripts
+-- Description: Script jobs referencing large uploaded scripts / binary payloads streamed to hosts over SFTP
+
+-- 脚本以 multipart 流式上传到 blob 存储，作业通过 script_sha256 引用；
+-- 执行时从存储分块下载并经 SFTP 传输到目标主机，不回填 jobs.script，也不解析主机变量
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS script_streamed BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS script_streamed BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN jobs.script_streamed IS 'Script content (script_sha256) is transferred to hosts as a file over SFTP instead of being loaded inline';
@@ -0,0 +1,23 @@
+-- Migration: 000047_exit_code_rules
+-- Description: Per-template / per-job exit-code classification rules and the warning task status
+
+-- 退出码被规则归为告警的任务（如 rsync 24：传输期间源文件消失）
+ALTER TYPE task_status ADD VALUE IF NOT EXISTS 'warning';
+
+-- 退出码分类规则：{"tool": "rsync", "rules": [{"codes": [24], "outcome": "warning"}]}
+-- 作业优先使用自身规则，模板作业未指定时继承模板（含基础模板）的规则
+ALTER TABLE job_templates ADD COLUMN IF NOT EXISTS exit_code_rules JSONB;
+
+-- 归档表需同步新增同名列，保持与热表列结构一致
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS exit_code_rules JSONB;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS warning_tasks INT NOT NULL DEFAULT 0;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS exit_code_rules JSONB;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS warning_tasks INT NOT NULL DEFAULT 0;
+
+-- 看板按主机统计告警任务
+ALTER TABLE stats_host_failures_daily ADD COLUMN IF NOT EXISTS warning_tasks BIGINT NOT NULL DEFAULT 0;
+
+COMMENT ON COLUMN job_templates.exit_code_rules IS 'Exit-code classification (built-in tool table and custom rules) inherited by jobs created from the template';
+COMMENT ON COLUMN jobs.exit_code_rules IS 'Exit-code classification mapping codes to succeeded, warning or failed; NULL means only 0 succeeds';
+COMMENT ON COLUMN jobs.warning_tasks IS 'Tasks whose exit code was classified as a warning (count towards job success)';
+COMMENT ON COLUMN stats_host_failures_daily.warning_tasks IS 'Tasks finished with the warning status';
@@ -0,0 +1,25 @@
+-- Migration: 000048_host_packages
+-- Description: Built-in OS package inventory job and structured per-host package storage
+
+-- 软件包清单采集作业：按操作系统包管理器列出已安装的软件包
+ALTER TYPE job_type ADD VALUE IF NOT EXISTS 'inventory';
+
+-- 主机已安装的软件包（每次采集成功后整体替换该主机的记录）
+-- 同名软件包可能同时安装多个版本或架构（如内核、multilib）
+CREATE TABLE IF NOT EXISTS host_packages (
+    host_id UUID NOT NULL REFERENCES assets_hosts(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    version VARCHAR(255) NOT NULL,
+    arch VARCHAR(64) NOT NULL DEFAULT '',
+    package_format VARCHAR(16) NOT NULL,
+    job_id UUID,
+    collected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (host_id, name, version, arch)
+);
+
+-- 按包名跨主机查询（如"哪些主机的 openssl 低于某版本"）
+CREATE INDEX IF NOT EXISTS idx_host_packages_name ON host_packages(name);
+
+COMMENT ON TABLE host_packages IS 'Installed OS packages per host, replaced on each successful inventory job';
+COMMENT ON COLUMN host_packages.package_format IS 'Package manager the list was collected from: dpkg, rpm, apk or pacman';
+COMMENT ON COLUMN host_packages.job_id IS 'Inventory job that collected the list (not a foreign key: jobs may be archived)';
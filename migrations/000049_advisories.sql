@@ -0,0 +1,54 @@
+-- Migration: 000049_advisories
+-- Description: Security advisories imported from OSV data and their matches against host package inventory
+
+-- 安全公告（OSV 记录，以 OSV id 为主键，CVE 编号记录在 aliases 中）
+CREATE TABLE IF NOT EXISTS advisories (
+    id VARCHAR(128) PRIMARY KEY,
+    aliases TEXT[] NOT NULL DEFAULT '{}',
+    summary TEXT,
+    severity VARCHAR(32),
+    source VARCHAR(32) NOT NULL,
+    published TIMESTAMPTZ,
+    modified TIMESTAMPTZ NOT NULL,
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 按 CVE 编号查询公告
+CREATE INDEX IF NOT EXISTS idx_advisories_aliases ON advisories USING GIN (aliases);
+
+-- 公告影响的软件包版本范围（introduced 为空表示自最早版本起受影响；
+-- fixed_version 与 last_affected 均为空表示尚无修复版本）
+-- ecosystem_release 为发行版版本（如 Debian:12 中的 12），用于按主机 os_version 区分同一软件包在不同发行版上的修复版本
+CREATE TABLE IF NOT EXISTS advisory_packages (
+    id BIGSERIAL PRIMARY KEY,
+    advisory_id VARCHAR(128) NOT NULL REFERENCES advisories(id) ON DELETE CASCADE,
+    package_format VARCHAR(16) NOT NULL,
+    package_name VARCHAR(255) NOT NULL,
+    ecosystem_release VARCHAR(64) NOT NULL DEFAULT '',
+    introduced VARCHAR(255),
+    fixed_version VARCHAR(255),
+    last_affected VARCHAR(255)
+);
+
+CREATE INDEX IF NOT EXISTS idx_advisory_packages_advisory ON advisory_packages(advisory_id);
+CREATE INDEX IF NOT EXISTS idx_advisory_packages_name ON advisory_packages(package_format, package_name);
+
+-- 公告与主机已安装软件包的匹配结果（后台任务每轮及手动导入后整体重算）
+CREATE TABLE IF NOT EXISTS advisory_matches (
+    advisory_id VARCHAR(128) NOT NULL REFERENCES advisories(id) ON DELETE CASCADE,
+    host_id UUID NOT NULL REFERENCES assets_hosts(id) ON DELETE CASCADE,
+    package_name VARCHAR(255) NOT NULL,
+    installed_version VARCHAR(255) NOT NULL,
+    package_format VARCHAR(16) NOT NULL,
+    fixed_version VARCHAR(255),
+    matched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (advisory_id, host_id, package_name, installed_version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_advisory_matches_host ON advisory_matches(host_id);
+
+COMMENT ON TABLE advisories IS 'Security advisories imported from OSV records';
+COMMENT ON COLUMN advisories.aliases IS 'Other identifiers of the advisory, typically CVE ids';
+COMMENT ON COLUMN advisories.source IS 'Ecosystem family the advisory was published for, e.g. Debian, Ubuntu, Alpine';
+COMMENT ON TABLE advisory_packages IS 'Affected package version ranges of an advisory, mapped to the inventory package format';
+COMMENT ON TABLE advisory_matches IS 'Installed packages affected by an advisory, recomputed periodically and after each import';
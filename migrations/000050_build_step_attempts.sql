@@ -0,0 +1,28 @@
+-- Migration: 000050_build_step_attempts
+-- Description: Per-step retry attempts reported by runners, so each attempt of a retried step stays visible
+
+-- 步骤超时状态（可按重试策略重试）
+ALTER TYPE step_status ADD VALUE IF NOT EXISTS 'timeout';
+
+-- 步骤当前尝试序号（旧版 Runner 不上报，视为第 1 次）
+ALTER TABLE build_steps
+ADD COLUMN IF NOT EXISTS attempt INTEGER NOT NULL DEFAULT 1;
+
+-- 步骤每次尝试的状态历史（按尝试序号记录最新状态）
+CREATE TABLE IF NOT EXISTS build_step_attempts (
+    job_id UUID NOT NULL REFERENCES build_jobs(id) ON DELETE CASCADE,
+    step_id VARCHAR(255) NOT NULL,
+    attempt INTEGER NOT NULL,
+    status VARCHAR(32) NOT NULL,
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    exit_code INTEGER,
+    retrying BOOLEAN NOT NULL DEFAULT FALSE,
+    status_sequence BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, step_id, attempt)
+);
+
+COMMENT ON COLUMN build_steps.attempt IS 'Attempt number of the latest status applied to this step, starting at 1';
+COMMENT ON TABLE build_step_attempts IS 'Status history of each attempt of a build step retried by its retry policy';
+COMMENT ON COLUMN build_step_attempts.retrying IS 'Whether the runner retried the step after this attempt ended';
@@ -0,0 +1,8 @@
+-- Migration: 000051_runner_image_cache
+-- Description: Store the Docker image pull and cache statistics reported in runner heartbeats
+
+-- 最近一次心跳上报的镜像拉取与缓存统计（Runner 启动以来的累计值）
+ALTER TABLE runners
+ADD COLUMN IF NOT EXISTS image_cache JSONB;
+
+COMMENT ON COLUMN runners.image_cache IS 'Docker image pull counts, pull times and cache hits reported by the runner since it started';
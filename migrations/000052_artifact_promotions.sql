@@ -0,0 +1,37 @@
+-- Migration: 000052_artifact_promotions
+-- Description: Approval-gated artifact promotion between environments and artifact-bound jobs
+
+-- 产物晋级记录：每条记录为晋级链中的一环（from_environment -> to_environment）
+-- status: pending（等待审批）/ promoted / rejected（审批被拒绝、取消或超时）
+CREATE TABLE IF NOT EXISTS artifact_promotions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    artifact_id UUID NOT NULL REFERENCES build_artifacts(id) ON DELETE CASCADE,
+    from_environment VARCHAR(64),
+    to_environment VARCHAR(64) NOT NULL,
+    target VARCHAR(1000),
+    status VARCHAR(32) NOT NULL DEFAULT 'pending',
+    approval_request_id UUID REFERENCES approval_requests(id) ON DELETE SET NULL,
+    note TEXT,
+    requested_by UUID NOT NULL,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    promoted_by UUID,
+    promoted_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 同一产物同一目标环境只能有一条进行中或已完成的晋级
+CREATE UNIQUE INDEX IF NOT EXISTS idx_artifact_promotions_active
+    ON artifact_promotions(artifact_id, to_environment)
+    WHERE status IN ('pending', 'promoted');
+
+CREATE INDEX IF NOT EXISTS idx_artifact_promotions_approval
+    ON artifact_promotions(approval_request_id);
+
+-- 作业部署的产物（受限环境的主机只允许部署已晋级到该环境的产物）
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS artifact_id UUID;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS artifact_id UUID;
+
+COMMENT ON TABLE artifact_promotions IS 'Promotion chain of build artifacts between environments, each step gated by an approval request';
+COMMENT ON COLUMN artifact_promotions.target IS 'Registry or storage prefix location the artifact is promoted to';
+COMMENT ON COLUMN artifact_promotions.status IS 'pending, promoted or rejected (approval rejected, cancelled or timed out)';
+COMMENT ON COLUMN jobs.artifact_id IS 'Build artifact deployed by the job, checked against promotions for restricted environments';
@@ -0,0 +1,63 @@
+-- Migration: 000053_runner_config_rollouts
+-- Description: Runner config versioning, canary rollouts and heartbeat-reported applied versions
+
+-- 配置版本：直接更新或灰度发布完成时递增
+ALTER TABLE runner_docker_configs ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE runner_config_history ADD COLUMN IF NOT EXISTS version BIGINT;
+
+-- 与模型（i64）及其他资源限制列一致
+ALTER TABLE runner_docker_configs ALTER COLUMN default_timeout_secs TYPE BIGINT;
+
+-- Runner 通过心跳上报的已应用配置版本
+ALTER TABLE runners ADD COLUMN IF NOT EXISTS applied_config_version BIGINT;
+ALTER TABLE runners ADD COLUMN IF NOT EXISTS applied_config_at TIMESTAMPTZ;
+
+-- 配置灰度发布：候选配置先下发给金丝雀 Runner，观察期通过后写入配置
+-- status: applying（等待金丝雀应用）/ baking（观察中）/ completed / rolled_back
+CREATE TABLE IF NOT EXISTS runner_config_rollouts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    config_id UUID NOT NULL REFERENCES runner_docker_configs(id) ON DELETE CASCADE,
+    base_version BIGINT NOT NULL,
+    target_version BIGINT NOT NULL,
+    candidate_config JSONB NOT NULL,
+    change_reason TEXT,
+
+    -- 金丝雀选择：按比例和/或能力标签，创建时固定为 Runner 名称列表
+    percent INTEGER,
+    labels JSONB NOT NULL DEFAULT '[]',
+    canary_runners JSONB NOT NULL DEFAULT '[]',
+
+    -- 观察期与回滚阈值
+    bake_secs BIGINT NOT NULL,
+    max_error_rate DOUBLE PRECISION NOT NULL,
+    min_jobs BIGINT NOT NULL DEFAULT 0,
+    auto_promote BOOLEAN NOT NULL DEFAULT TRUE,
+
+    status VARCHAR(32) NOT NULL DEFAULT 'applying',
+    status_reason TEXT,
+    baking_started_at TIMESTAMPTZ,
+    observed_jobs BIGINT NOT NULL DEFAULT 0,
+    observed_failures BIGINT NOT NULL DEFAULT 0,
+
+    created_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+
+    CONSTRAINT check_rollout_percent CHECK (percent IS NULL OR (percent > 0 AND percent <= 100)),
+    CONSTRAINT check_rollout_error_rate CHECK (max_error_rate >= 0 AND max_error_rate <= 1)
+);
+
+-- 同一配置同时只能有一个进行中的灰度发布
+CREATE UNIQUE INDEX IF NOT EXISTS idx_runner_config_rollouts_active
+    ON runner_config_rollouts(config_id)
+    WHERE status IN ('applying', 'baking');
+
+CREATE INDEX IF NOT EXISTS idx_runner_config_rollouts_config
+    ON runner_config_rollouts(config_id, created_at DESC);
+
+COMMENT ON COLUMN runner_docker_configs.version IS 'Incremented on every direct update or completed rollout';
+COMMENT ON COLUMN runners.applied_config_version IS 'Docker config version the runner reported as applied in its last heartbeat';
+COMMENT ON TABLE runner_config_rollouts IS 'Canary rollouts of runner Docker config changes with bake period and automatic rollback';
+COMMENT ON COLUMN runner_config_rollouts.canary_runners IS 'Runner names selected at creation that receive the candidate config';
+COMMENT ON COLUMN runner_config_rollouts.status IS 'applying, baking, completed or rolled_back';
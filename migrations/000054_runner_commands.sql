@@ -0,0 +1,34 @@
+-- Migration: 000054_runner_commands
+-- Description: Control-plane-initiated runner commands with acknowledgment tracking and timeouts
+
+-- 控制指令经 Runner 控制队列下发，执行结果随心跳回报
+-- status: pending（已下发，等待回报）/ succeeded / failed / timed_out
+CREATE TABLE IF NOT EXISTS runner_commands (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    runner_id UUID NOT NULL REFERENCES runners(id) ON DELETE CASCADE,
+    action VARCHAR(32) NOT NULL,
+    status VARCHAR(32) NOT NULL DEFAULT 'pending',
+    result_message TEXT,
+    requested_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    acknowledged_at TIMESTAMPTZ,
+
+    CONSTRAINT check_runner_command_action CHECK (
+        action IN ('pause', 'resume', 'cleanup_workspaces', 'reregister', 'refresh_config')
+    ),
+    CONSTRAINT check_runner_command_status CHECK (
+        status IN ('pending', 'succeeded', 'failed', 'timed_out')
+    )
+);
+
+CREATE INDEX IF NOT EXISTS idx_runner_commands_runner
+    ON runner_commands(runner_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_runner_commands_pending
+    ON runner_commands(expires_at)
+    WHERE status = 'pending';
+
+COMMENT ON TABLE runner_commands IS 'Control commands sent to a specific runner through its control queue';
+COMMENT ON COLUMN runner_commands.status IS 'pending, succeeded, failed or timed_out';
+COMMENT ON COLUMN runner_commands.expires_at IS 'Pending commands not acknowledged by this time are marked timed_out';
@@ -0,0 +1,7 @@
+-- Migration: 000055_runner_running_tasks
+-- Description: Running task inventory reported by runners in heartbeats
+
+-- NULL 表示 Runner 版本较旧、未上报运行中任务
+ALTER TABLE runners ADD COLUMN IF NOT EXISTS running_tasks JSONB;
+
+COMMENT ON COLUMN runners.running_tasks IS 'Tasks the runner reported as executing in its last heartbeat (job_id, task_id, started_at)';
@@ -0,0 +1,18 @@
+-- Migration: 000056_job_file_manifests
+-- Description: Declared job file manifests with per-host undeclared modification reports
+
+-- 作业声明将读写的文件或目录，以及检查未声明修改的监视目录
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS file_manifest JSONB;
+
+-- 归档表需同步新增同名列，保持与热表列结构一致
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS file_manifest JSONB;
+
+-- 每台主机的清单报告（声明路径的变化、未声明修改、是否合规）
+ALTER TABLE tasks
+ADD COLUMN IF NOT EXISTS file_manifest_report JSONB;
+
+ALTER TABLE tasks_archive
+ADD COLUMN IF NOT EXISTS file_manifest_report JSONB;
+
+COMMENT ON COLUMN jobs.file_manifest IS 'Declared file paths the job reads or writes, plus directories watched for undeclared modifications';
+COMMENT ON COLUMN tasks.file_manifest_report IS 'Per-host manifest report: declared path changes, undeclared modifications and compliance';
@@ -0,0 +1,5 @@
+-- Migration: 000057_output_limit_failure_reason
+-- Description: Failure reason for tasks terminated after exceeding the SSH output size limit
+
+-- 输出超过上限后被终止的任务（输出只保留开头与末尾）
+ALTER TYPE failure_reason ADD VALUE IF NOT EXISTS 'output_limit_exceeded';
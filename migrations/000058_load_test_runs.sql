@@ -0,0 +1,21 @@
+-- Migration: 000058_load_test_runs
+-- Description: Platform load-test runs with synthetic jobs and mock execution
+
+CREATE TYPE load_test_run_status AS ENUM ('running', 'completed', 'failed');
+
+-- parameters 为发起压测的请求参数，report 为调度吞吐、数据库写放大、事件扇出与 SSE 延迟的测量结果
+CREATE TABLE IF NOT EXISTS load_test_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    requested_by UUID NOT NULL REFERENCES users(id),
+    status load_test_run_status NOT NULL DEFAULT 'running',
+    parameters JSONB NOT NULL,
+    report JSONB,
+    error_message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_load_test_runs_created ON load_test_runs(created_at DESC);
+
+COMMENT ON TABLE load_test_runs IS 'Internal load tests: synthetic jobs against a mock executor to size the platform';
+COMMENT ON COLUMN load_test_runs.report IS 'Scheduler throughput, database write amplification, event fan-out and SSE latency';
@@ -0,0 +1,33 @@
+-- Migration: 000059_credential_rotations
+-- Description: Host SSH credential rotation runs (push new key, verify, swap, retire old key)
+
+CREATE TYPE credential_rotation_status AS ENUM ('running', 'completed', 'partially_completed', 'failed');
+
+CREATE TYPE credential_rotation_phase AS ENUM ('push', 'verify', 'swap', 'retire', 'done');
+
+-- 新公钥与指纹可公开记录，私钥只写入主机凭据；results 为各主机的结果（已轮换、已回滚等）
+CREATE TABLE IF NOT EXISTS credential_rotations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    requested_by UUID NOT NULL REFERENCES users(id),
+    status credential_rotation_status NOT NULL DEFAULT 'running',
+    phase credential_rotation_phase NOT NULL DEFAULT 'push',
+    key_source VARCHAR(16) NOT NULL,
+    public_key TEXT NOT NULL,
+    key_fingerprint VARCHAR(128) NOT NULL,
+    retire_old_key BOOLEAN NOT NULL,
+    total_hosts INT NOT NULL,
+    -- 推送新公钥、删除旧公钥、回滚（删除新公钥）所用的作业
+    push_job_id UUID REFERENCES jobs(id) ON DELETE SET NULL,
+    retire_job_ids UUID[] NOT NULL DEFAULT '{}',
+    rollback_job_id UUID REFERENCES jobs(id) ON DELETE SET NULL,
+    results JSONB,
+    error_message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_credential_rotations_created ON credential_rotations(created_at DESC);
+
+COMMENT ON TABLE credential_rotations IS 'Guided host SSH key rotations with per-host outcome and rollback';
+COMMENT ON COLUMN credential_rotations.key_source IS 'generated (Ed25519 created by the service) or provided';
+COMMENT ON COLUMN credential_rotations.results IS 'Per-host rotation outcome, failed phase and error';
@@ -0,0 +1,20 @@
+-- Migration: 000060_environment_policies
+-- Description: Per-environment job default policies (defaults, forced approval, allowed job types, blackout windows)
+
+-- 作业目标涉及多个环境时取最保守值：默认超时与并发取最小值，任一环境要求审批即需审批，
+-- 允许的作业类型取交集，任一环境处于禁止变更窗口即拒绝创建
+CREATE TABLE IF NOT EXISTS environment_policies (
+    environment VARCHAR(50) PRIMARY KEY,
+    default_timeout_secs INT CHECK (default_timeout_secs > 0),
+    default_concurrent_limit INT CHECK (default_concurrent_limit > 0),
+    require_approval BOOLEAN NOT NULL DEFAULT FALSE,
+    allowed_job_types JSONB,
+    blackout_windows JSONB NOT NULL DEFAULT '[]',
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE environment_policies IS 'Job creation defaults and restrictions attached to an environment';
+COMMENT ON COLUMN environment_policies.allowed_job_types IS 'Allowed job types (NULL allows all)';
+COMMENT ON COLUMN environment_policies.blackout_windows IS 'Weekly recurring windows during which job creation is rejected';
@@ -0,0 +1,35 @@
+-- Migration: 000061_output_baselines
+-- Description: Golden output baselines per template or host with per-task drift results
+
+-- 基线输出：按模板、按主机或按模板+主机配置，匹配时优先使用更具体的基线
+CREATE TABLE IF NOT EXISTS output_baselines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    template_id UUID REFERENCES job_templates(id) ON DELETE CASCADE,
+    host_id UUID REFERENCES assets_hosts(id) ON DELETE CASCADE,
+    expected_output TEXT NOT NULL,
+    description TEXT,
+    created_by UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT output_baselines_scope_check CHECK (template_id IS NOT NULL OR host_id IS NOT NULL),
+    CONSTRAINT output_baselines_scope_unique UNIQUE NULLS NOT DISTINCT (template_id, host_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_output_baselines_host ON output_baselines(host_id);
+
+-- 作业是否与基线比对输出
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS drift_check BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 归档表需同步新增同名列，保持与热表列结构一致
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS drift_check BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 每台主机的比对结果（使用的基线、是否偏离、差异行）
+ALTER TABLE tasks
+ADD COLUMN IF NOT EXISTS output_drift JSONB;
+
+ALTER TABLE tasks_archive
+ADD COLUMN IF NOT EXISTS output_drift JSONB;
+
+COMMENT ON TABLE output_baselines IS 'Expected (golden) job output per template, per host, or per template and host';
+COMMENT ON COLUMN jobs.drift_check IS 'Whether task outputs are compared against output baselines after execution';
+COMMENT ON COLUMN tasks.output_drift IS 'Per-host baseline comparison: baseline used, drift state and line diff';
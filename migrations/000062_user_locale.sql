@@ -0,0 +1,7 @@
+-- Migration: 000062_user_locale
+-- Description: Per-user language preference for localized API messages
+
+-- 用户语言偏好（为空时按 Accept-Language 与默认语言）
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locale VARCHAR(16);
+
+COMMENT ON COLUMN users.locale IS 'Preferred language tag (en-US / zh-CN) for API messages, notifications and report headers';
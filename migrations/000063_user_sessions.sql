@@ -0,0 +1,34 @@
+-- Migration: 000063_user_sessions
+-- Description: Login session registry with device info for listing and revoking active sessions
+
+-- 登录会话：每次登录创建一条记录，刷新令牌与访问令牌通过 sid 关联到会话，撤销后立即失效
+CREATE TABLE IF NOT EXISTS user_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    device_id VARCHAR(255),
+    user_agent TEXT,
+    ip_address VARCHAR(45) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- 最近一次刷新令牌的时间与地址
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_ip VARCHAR(45),
+    -- 随刷新令牌顺延
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    revoked_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    revoke_reason VARCHAR(50)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_sessions_user_active
+    ON user_sessions(user_id, last_seen_at DESC)
+    WHERE revoked_at IS NULL;
+
+-- 刷新令牌所属会话（会话登记之前签发的令牌为空）
+ALTER TABLE refresh_tokens
+    ADD COLUMN IF NOT EXISTS session_id UUID REFERENCES user_sessions(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_session ON refresh_tokens(session_id);
+
+COMMENT ON TABLE user_sessions IS 'Login sessions per device; revoking a session invalidates its access and refresh tokens';
+COMMENT ON COLUMN user_sessions.revoke_reason IS 'logout, logout_all, user_revoked or admin_revoked';
+COMMENT ON COLUMN refresh_tokens.session_id IS 'Login session the refresh token belongs to';
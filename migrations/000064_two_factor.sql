@@ -0,0 +1,34 @@
+-- Migration: 000064_two_factor
+-- Description: TOTP second factor with recovery codes and step-up verification on login sessions
+
+-- TOTP 密钥：enabled_at 为空表示已发起绑定但尚未确认
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret VARCHAR(64) NOT NULL,
+    enabled_at TIMESTAMPTZ,
+    -- 最近一次通过校验的时间步（防重放）
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 恢复码：仅保存哈希，使用后标记
+CREATE TABLE IF NOT EXISTS user_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_recovery_codes_user
+    ON user_recovery_codes(user_id)
+    WHERE used_at IS NULL;
+
+-- 会话的二次验证有效期（高风险操作前需在有效期内）
+ALTER TABLE user_sessions
+    ADD COLUMN IF NOT EXISTS step_up_until TIMESTAMPTZ;
+
+COMMENT ON TABLE user_totp IS 'TOTP secrets; enabled_at is NULL while enrollment is pending confirmation';
+COMMENT ON TABLE user_recovery_codes IS 'Single-use 2FA recovery codes (SHA-256 hashes)';
+COMMENT ON COLUMN user_sessions.step_up_until IS 'Step-up verification for high-risk actions is valid until this time';
@@ -0,0 +1,30 @@
+-- Migration: 000065_approval_pending_changes
+-- Description: Pending resource changes wrapped in approval requests, applied on approval and discarded otherwise
+
+-- 待审批的资源变更：审批通过后应用，被拒绝、取消或超时后丢弃；处理后清空变更内容（可能包含凭据）
+CREATE TABLE IF NOT EXISTS approval_pending_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    approval_request_id UUID NOT NULL UNIQUE REFERENCES approval_requests(id) ON DELETE CASCADE,
+    resource_type VARCHAR(50) NOT NULL,
+    resource_id UUID NOT NULL,
+    change_kind VARCHAR(50) NOT NULL,
+    payload JSONB,
+    -- 不含敏感信息的变更说明
+    summary TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'applied', 'discarded', 'failed')),
+    error_message TEXT,
+    requested_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- 同一资源同时只能有一个待审批变更
+CREATE UNIQUE INDEX IF NOT EXISTS idx_approval_pending_changes_resource
+    ON approval_pending_changes(resource_type, resource_id)
+    WHERE status = 'pending';
+
+COMMENT ON TABLE approval_pending_changes IS 'Resource mutations awaiting approval; applied when the approval request is approved, discarded on rejection, cancellation or timeout';
+COMMENT ON COLUMN approval_pending_changes.payload IS 'Serialized change; cleared once the change is resolved';
+COMMENT ON COLUMN approval_pending_changes.change_kind IS 'host_credential_update or group_delete';
@@ -0,0 +1,36 @@
+-- Migration: 000066_soft_delete
+-- Description: Uniform soft delete with restore and retention-based purge for hosts, job templates and users
+
+-- 主机
+ALTER TABLE assets_hosts
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_assets_hosts_deleted_at
+    ON assets_hosts(deleted_at)
+    WHERE deleted_at IS NOT NULL;
+
+-- 作业模板（删除时同时置 is_active = false，已有查询据此过滤）
+ALTER TABLE job_templates
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+-- 此前已软删除的模板以最后更新时间作为删除时间
+UPDATE job_templates SET deleted_at = updated_at WHERE is_active = false AND deleted_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_job_templates_deleted_at
+    ON job_templates(deleted_at)
+    WHERE deleted_at IS NOT NULL;
+
+-- 用户
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at
+    ON users(deleted_at)
+    WHERE deleted_at IS NOT NULL;
+
+COMMENT ON COLUMN assets_hosts.deleted_at IS 'Soft delete time; deleted hosts are hidden from listings and cannot be targeted by new jobs';
+COMMENT ON COLUMN job_templates.deleted_at IS 'Soft delete time; is_active is cleared at the same time';
+COMMENT ON COLUMN users.deleted_at IS 'Soft delete time; deleted users cannot log in';
@@ -0,0 +1,26 @@
+-- Migration: 000067_job_template_deprecation
+-- Description: Job template deprecation state with optional replacement template, plus indexes for usage analytics
+
+-- 弃用状态：弃用的模板仍可执行，但执行时返回警告并提示替代模板
+ALTER TABLE job_templates
+    ADD COLUMN IF NOT EXISTS deprecated_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deprecated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS deprecation_reason TEXT,
+    ADD COLUMN IF NOT EXISTS replacement_template_id UUID REFERENCES job_templates(id) ON DELETE SET NULL;
+
+ALTER TABLE job_templates
+    ADD CONSTRAINT job_templates_replacement_not_self
+    CHECK (replacement_template_id IS NULL OR replacement_template_id <> id);
+
+CREATE INDEX IF NOT EXISTS idx_job_templates_deprecated_at
+    ON job_templates(deprecated_at)
+    WHERE deprecated_at IS NOT NULL;
+
+-- 模板使用统计按模板与创建时间查询作业
+CREATE INDEX IF NOT EXISTS idx_jobs_template_created_at
+    ON jobs(template_id, created_at DESC)
+    WHERE template_id IS NOT NULL;
+
+COMMENT ON COLUMN job_templates.deprecated_at IS 'When the template was deprecated; deprecated templates still run but warn on use';
+COMMENT ON COLUMN job_templates.deprecation_reason IS 'Why the template was deprecated';
+COMMENT ON COLUMN job_templates.replacement_template_id IS 'Template users should migrate to instead of this deprecated one';
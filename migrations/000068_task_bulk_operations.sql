@@ -0,0 +1,13 @@
+-- Migration: 000068_task_bulk_operations
+-- Description: Skipped task status and job skipped counter for bulk task operations (cancel, skip, re-run selected tasks)
+
+-- 跳过：待执行的任务被标记为不执行
+ALTER TYPE task_status ADD VALUE IF NOT EXISTS 'skipped';
+
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS skipped_tasks INTEGER NOT NULL DEFAULT 0;
+
+-- 归档表与 jobs 保持相同的列顺序（归档以 SELECT * 复制）
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS skipped_tasks INTEGER NOT NULL DEFAULT 0;
+
+COMMENT ON COLUMN jobs.skipped_tasks IS 'Tasks skipped without running; excluded when deciding the final job status';
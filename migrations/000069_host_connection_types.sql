@@ -0,0 +1,21 @@
+-- Migration: 000069_host_connection_types
+-- Description: Host connection types so a host can represent a Docker container (docker exec over SSH) or a Kubernetes pod (kubectl exec)
+
+-- 连接方式：ssh（默认）、docker（经 SSH 在 address 所指的 Docker 主机上执行 docker exec）、
+-- kubernetes（通过 API Server 执行 kubectl exec）
+ALTER TABLE assets_hosts
+    ADD COLUMN IF NOT EXISTS connection_type VARCHAR(20) NOT NULL DEFAULT 'ssh',
+    ADD COLUMN IF NOT EXISTS connection_options JSONB,
+    ADD COLUMN IF NOT EXISTS connection_token TEXT;
+
+ALTER TABLE assets_hosts
+    ADD CONSTRAINT assets_hosts_connection_type_check
+    CHECK (connection_type IN ('ssh', 'docker', 'kubernetes'));
+
+CREATE INDEX IF NOT EXISTS idx_assets_hosts_connection_type
+    ON assets_hosts(connection_type)
+    WHERE connection_type <> 'ssh';
+
+COMMENT ON COLUMN assets_hosts.connection_type IS 'How commands reach the target: ssh, docker (docker exec over SSH on the host address) or kubernetes (kubectl exec)';
+COMMENT ON COLUMN assets_hosts.connection_options IS 'Target details for non-SSH hosts: container name, or API server, namespace, pod and container';
+COMMENT ON COLUMN assets_hosts.connection_token IS 'Bearer token for the Kubernetes API server (stored like other host credentials)';
@@ -0,0 +1,8 @@
+-- Migration: 000070_concurrency_timeline
+-- Description: Record concurrency acquire failures (timeouts/rejections) per sample for the concurrency timeline
+
+-- 采样周期内获取许可失败的次数（Wait 策略为超时，Reject 策略为拒绝），按作用域的明细记录在 scopes 中
+ALTER TABLE stats_concurrency_samples
+    ADD COLUMN IF NOT EXISTS acquire_failures INT NOT NULL DEFAULT 0;
+
+COMMENT ON COLUMN stats_concurrency_samples.acquire_failures IS 'Concurrency permit acquire timeouts or rejections since the previous sample';
@@ -0,0 +1,5 @@
+-- Migration: 000071_job_approval_gate
+-- Description: Hold jobs that require approval until the linked approval request is decided
+
+-- 需要审批的作业创建后停留在该状态，审批通过后转为 pending 派发，被拒绝、取消或超时则取消
+ALTER TYPE job_status ADD VALUE IF NOT EXISTS 'awaiting_approval';
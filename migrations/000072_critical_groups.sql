@@ -0,0 +1,11 @@
+-- Migration: 000072_critical_groups
+-- Description: Make assets_groups.is_critical the only source for critical-group approvals
+
+-- 审批此前还按分组名称（含 prod / critical）判定关键分组，改为只读取 is_critical 标记；
+-- 回填按名称命中的分组（含种子数据中的 prod-* 分组），保持升级前的审批行为
+UPDATE assets_groups
+SET is_critical = TRUE, updated_at = NOW()
+WHERE NOT is_critical
+  AND (name ILIKE '%prod%' OR name ILIKE '%critical%');
+
+COMMENT ON COLUMN assets_groups.is_critical IS '标记是否为关键分组，目标主机属于关键分组的作业需要审批（通过资产组接口维护）';
@@ -0,0 +1,33 @@
+-- Migration: 000073_job_dispatch_queue
+-- Description: Persistent job dispatch queue claimed by a bounded worker pool
+
+-- 作业执行此前在创建时直接派生后台任务，进程重启会丢失派发，突发创建也会派生无上限的执行；
+-- 改为写入派发队列，由各实例的工作池以 SKIP LOCKED 认领，认领后定期续约，
+-- 租约过期（实例崩溃）的记录重新入队
+CREATE TABLE IF NOT EXISTS job_dispatch_queue (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    enqueued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- 认领的实例与时间，未认领时为 NULL
+    claimed_by VARCHAR(100),
+    claimed_at TIMESTAMPTZ,
+    heartbeat_at TIMESTAMPTZ,
+    -- 已认领次数（含租约过期后的重新认领）
+    attempts INT NOT NULL DEFAULT 0
+);
+
+-- 每个作业至多一条待认领记录，重复派发合并
+CREATE UNIQUE INDEX IF NOT EXISTS idx_job_dispatch_queue_unclaimed
+    ON job_dispatch_queue(job_id) WHERE claimed_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_job_dispatch_queue_enqueued
+    ON job_dispatch_queue(enqueued_at) WHERE claimed_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_job_dispatch_queue_heartbeat
+    ON job_dispatch_queue(heartbeat_at) WHERE claimed_at IS NOT NULL;
+
+-- 升级前已创建但尚未执行的作业
+INSERT INTO job_dispatch_queue (job_id)
+SELECT id FROM jobs
+WHERE status = 'pending' AND NOT singleton_waiting
+ON CONFLICT DO NOTHING;
+
+COMMENT ON TABLE job_dispatch_queue IS 'Jobs waiting for or claimed by a dispatch worker; stale claims are requeued';
@@ -0,0 +1,22 @@
+-- Migration: 000074_host_environment_promotion
+-- Description: Permission guarding changes to a host's environment
+
+-- 变更主机环境（如 staging → production）改变其并发配额与审批要求，
+-- 除 asset:write 外还需目标环境的 asset:promote 权限；默认仅授予 admin 角色
+INSERT INTO permissions (resource, action, description) VALUES
+    ('asset', 'promote', 'Change the environment of hosts')
+ON CONFLICT (resource, action) DO NOTHING;
+
+DO $$
+DECLARE
+    admin_role_id UUID;
+BEGIN
+    SELECT id INTO admin_role_id FROM roles WHERE name = 'admin';
+
+    IF admin_role_id IS NOT NULL THEN
+        INSERT INTO role_permissions (role_id, permission_id)
+        SELECT admin_role_id, p.id FROM permissions p
+        WHERE p.resource = 'asset' AND p.action = 'promote'
+        ON CONFLICT DO NOTHING;
+    END IF;
+END $$;
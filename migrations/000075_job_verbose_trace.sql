@@ -0,0 +1,26 @@
+-- Migration: 000075_job_verbose_trace
+-- Description: Per-job verbose execution trace for debugging
+
+-- 作业是否记录详细追踪（目标主机含生产环境时创建作业自动关闭，管理员创建的作业除外）
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS verbose_trace BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 归档表需同步新增同名列，保持与热表列结构一致
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS verbose_trace BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 详细追踪记录：SSH 协商日志、各阶段耗时、并发许可等待时间与事件发布结果
+CREATE TABLE IF NOT EXISTS job_trace_entries (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    task_id UUID REFERENCES tasks(id) ON DELETE CASCADE,
+    -- ssh / phase / concurrency / event
+    category VARCHAR(20) NOT NULL,
+    message TEXT NOT NULL,
+    duration_ms BIGINT,
+    details JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_trace_entries_job ON job_trace_entries(job_id, id);
+
+COMMENT ON COLUMN jobs.verbose_trace IS 'Whether extra execution trace is recorded in job_trace_entries';
+COMMENT ON TABLE job_trace_entries IS 'Verbose execution trace of jobs created with verbose_trace enabled';
@@ -0,0 +1,19 @@
+-- Migration: 000076_artifact_variants
+-- Description: Multi-arch artifact variants and host architecture fact
+
+-- 产物的目标平台（空字符串表示与平台无关，旧产物保持为空）
+ALTER TABLE build_artifacts ADD COLUMN IF NOT EXISTS arch VARCHAR(32) NOT NULL DEFAULT '';
+ALTER TABLE build_artifacts ADD COLUMN IF NOT EXISTS os VARCHAR(32) NOT NULL DEFAULT '';
+
+-- 不可变约束改为按平台区分：同版本号 + artifact_type 下每个平台各一个变体
+DROP INDEX IF EXISTS idx_build_artifacts_unique_version;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_build_artifacts_unique_variant
+ON build_artifacts(version, artifact_type, arch, os)
+WHERE version IS NOT NULL;
+
+-- 主机 CPU 架构（清单采集作业通过 uname -m 收集，部署时据此选择产物变体）
+ALTER TABLE assets_hosts ADD COLUMN IF NOT EXISTS arch VARCHAR(32);
+
+COMMENT ON COLUMN build_artifacts.arch IS 'Target CPU architecture of this variant, empty when platform independent';
+COMMENT ON COLUMN build_artifacts.os IS 'Target operating system of this variant, empty when platform independent';
+COMMENT ON COLUMN assets_hosts.arch IS 'Host CPU architecture collected by inventory jobs';
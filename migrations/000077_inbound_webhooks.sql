@@ -0,0 +1,51 @@
+-- Migration: 000077_inbound_webhooks
+-- Description: Inbound webhooks that let external systems trigger bound job templates
+
+-- 入站 Webhook：绑定作业模板与固定目标范围，外部系统（监控告警、ITSM）凭令牌触发
+CREATE TABLE IF NOT EXISTS inbound_webhooks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT,
+    -- 仅保存令牌的 SHA-256 哈希，明文只在创建/轮换时返回一次
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    template_id UUID NOT NULL REFERENCES job_templates(id) ON DELETE CASCADE,
+    -- 固定目标范围，请求体无法修改
+    target_hosts JSONB NOT NULL DEFAULT '[]',
+    target_groups JSONB NOT NULL DEFAULT '[]',
+    -- 固定参数（优先于请求体中提取的参数）
+    fixed_parameters JSONB NOT NULL DEFAULT '{}',
+    -- 参数白名单：参数名 -> 提取位置与校验规则
+    parameter_rules JSONB NOT NULL DEFAULT '{}',
+    rate_limit_per_minute INTEGER NOT NULL DEFAULT 10 CHECK (rate_limit_per_minute > 0),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    -- 触发的作业以创建者身份提交，审批流程与手动执行一致
+    created_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_triggered_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_inbound_webhooks_template ON inbound_webhooks(template_id);
+
+-- 投递记录：保存完整请求体用于审计与限流计数
+CREATE TABLE IF NOT EXISTS inbound_webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    webhook_id UUID NOT NULL REFERENCES inbound_webhooks(id) ON DELETE CASCADE,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    source_ip VARCHAR(64),
+    user_agent TEXT,
+    payload JSONB NOT NULL,
+    -- received / triggered / rejected / rate_limited / failed
+    status VARCHAR(20) NOT NULL,
+    error TEXT,
+    parameters JSONB,
+    job_id UUID REFERENCES jobs(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_inbound_webhook_deliveries_webhook
+ON inbound_webhook_deliveries(webhook_id, received_at DESC);
+
+COMMENT ON TABLE inbound_webhooks IS 'Token-authenticated inbound webhooks bound to a job template and fixed target scope';
+COMMENT ON COLUMN inbound_webhooks.parameter_rules IS 'Allowlisted parameters: name -> JSON pointer into the payload and validation rules';
+COMMENT ON COLUMN inbound_webhooks.created_by IS 'Jobs triggered by this webhook are submitted on behalf of this user';
+COMMENT ON TABLE inbound_webhook_deliveries IS 'Every authenticated inbound webhook request with its payload and outcome';
@@ -0,0 +1,35 @@
+-- Migration: 000078_notification_templates
+-- Description: Versioned per-event, per-channel, per-locale notification templates
+
+-- 通知模板：按事件类型、投递渠道与语言覆盖内置通知文案
+CREATE TABLE IF NOT EXISTS notification_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event_type VARCHAR(50) NOT NULL,
+    channel VARCHAR(30) NOT NULL,
+    locale VARCHAR(10) NOT NULL,
+    -- 当前生效的版本号（每次修改或回滚生成新版本）
+    current_version INTEGER NOT NULL DEFAULT 1,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (event_type, channel, locale)
+);
+
+-- 模板版本历史（只追加）
+CREATE TABLE IF NOT EXISTS notification_template_versions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    template_id UUID NOT NULL REFERENCES notification_templates(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    comment TEXT,
+    -- 回滚生成的版本记录来源版本号
+    rolled_back_from INTEGER,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (template_id, version)
+);
+
+COMMENT ON TABLE notification_templates IS 'Custom notification templates overriding built-in messages per event type, channel and locale';
+COMMENT ON COLUMN notification_templates.current_version IS 'Version of notification_template_versions currently used for rendering';
+COMMENT ON TABLE notification_template_versions IS 'Append-only history of notification template bodies';
+COMMENT ON COLUMN notification_template_versions.rolled_back_from IS 'Version whose body was restored when this version was created by a rollback';
@@ -0,0 +1,17 @@
+-- Migration: 000079_host_key_drift
+-- Description: Scheduled host key drift check; hosts presenting a key that differs from the pinned fingerprint are blocked until re-pinned
+
+-- 失败记录来源：task（任务执行时验证失败）或 drift_check（定期漂移检查）
+ALTER TABLE ssh_host_key_failures
+    ADD COLUMN IF NOT EXISTS source VARCHAR(20) NOT NULL DEFAULT 'task'
+        CHECK (source IN ('task', 'drift_check'));
+
+-- 发现密钥漂移的时间；非空时主机禁止执行任务，重新固定密钥后清除
+ALTER TABLE assets_hosts
+    ADD COLUMN IF NOT EXISTS host_key_blocked_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_assets_hosts_host_key_blocked
+ON assets_hosts(host_key_blocked_at) WHERE host_key_blocked_at IS NOT NULL;
+
+COMMENT ON COLUMN ssh_host_key_failures.source IS 'Where the failure was detected: task execution or the scheduled drift check';
+COMMENT ON COLUMN assets_hosts.host_key_blocked_at IS 'When the drift check found a changed host key; tasks fail without connecting until the key is re-pinned';
@@ -0,0 +1,18 @@
+-- Migration: 000080_task_journal
+-- Description: Per-task append-only execution journal recording when each execution phase was reached
+
+-- 执行日志：[阶段, 毫秒时间戳] 数组，按发生顺序追加
+-- 先以空数组添加列（已有任务没有日志），再将新任务的默认值改为含创建时间的首条记录
+ALTER TABLE tasks
+    ADD COLUMN IF NOT EXISTS journal JSONB NOT NULL DEFAULT '[]'::JSONB;
+
+ALTER TABLE tasks
+    ALTER COLUMN journal SET DEFAULT jsonb_build_array(
+        jsonb_build_array('created', (EXTRACT(EPOCH FROM clock_timestamp()) * 1000)::BIGINT)
+    );
+
+-- 归档时随任务一并搬移
+ALTER TABLE tasks_archive
+    ADD COLUMN IF NOT EXISTS journal JSONB NOT NULL DEFAULT '[]'::JSONB;
+
+COMMENT ON COLUMN tasks.journal IS 'Append-only execution journal as [phase, unix_millis] pairs: created, permit_acquired, connected, authenticated, exec_started, output_complete, status_persisted, events_published';
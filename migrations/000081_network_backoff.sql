@@ -0,0 +1,11 @@
+-- Migration: 000081_network_backoff
+-- Description: Pause job dispatch when a large share of tasks fail as unreachable, until an operator continues or aborts
+
+-- 暂停派发的时间（等待确认期间非空）与最近一次暂停的原因（窗口内不可达任务数、样本数、比例）
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS dispatch_paused_at TIMESTAMPTZ;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS network_backoff JSONB;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS dispatch_paused_at TIMESTAMPTZ;
+ALTER TABLE jobs_archive ADD COLUMN IF NOT EXISTS network_backoff JSONB;
+
+COMMENT ON COLUMN jobs.dispatch_paused_at IS 'When dispatching was paused because too many tasks failed as unreachable; NULL when not waiting for an operator decision';
+COMMENT ON COLUMN jobs.network_backoff IS 'Last unreachable-host backoff trip: unreachable_tasks, sample_size, failure_rate and paused_at';
@@ -0,0 +1,5 @@
+-- Migration: 000082_dispatch_lease_failure_reason
+-- Description: Failure reason for running tasks interrupted when their dispatcher lease expired
+
+-- 认领作业的实例崩溃、租约过期后无法确认结果的执行中任务
+ALTER TYPE failure_reason ADD VALUE IF NOT EXISTS 'dispatch_lease_expired';
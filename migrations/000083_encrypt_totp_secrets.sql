@@ -0,0 +1,8 @@
+-- Migration: 000083_encrypt_totp_secrets
+-- Description: Store TOTP secrets encrypted with the credential encryption key
+
+-- 密文（版本前缀 + Base64 编码的 nonce 与密文）超过原列长度；
+-- 已有的明文密钥由服务启动时加密
+ALTER TABLE user_totp ALTER COLUMN secret TYPE TEXT;
+
+COMMENT ON COLUMN user_totp.secret IS 'TOTP secret encrypted with AES-256-GCM (v1:base64(nonce || ciphertext))';
@@ -0,0 +1,285 @@
+//! HTTP API 公共类型
+//!
+//! ops-service 与 ops-client 共用的请求、响应与状态枚举，保证两端 JSON 格式一致。
+//! 启用 `sqlx` 特性时状态枚举同时映射到数据库枚举类型（仅服务端启用）
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// 登录请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+    /// TOTP 验证码或恢复码（已启用双因素认证的用户必填）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp_code: Option<String>,
+}
+
+/// 登录响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// 访问令牌有效期（秒）
+    pub expires_in: u64,
+    pub user: UserWithRoles,
+}
+
+/// 刷新令牌请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+/// 令牌对（刷新令牌响应）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// 访问令牌有效期（秒）
+    pub expires_in: u64,
+}
+
+/// 用户信息（不含敏感字段）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserResponse {
+    pub id: Uuid,
+    pub username: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    pub status: String,
+    #[serde(default)]
+    pub full_name: Option<String>,
+    #[serde(default)]
+    pub department: Option<String>,
+    #[serde(default)]
+    pub must_change_password: bool,
+    #[serde(default)]
+    pub locale: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 用户信息及其角色与权限范围
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserWithRoles {
+    #[serde(flatten)]
+    pub user: UserResponse,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+/// 作业类型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "job_type", rename_all = "snake_case")
+)]
+pub enum JobType {
+    /// 命令作业
+    Command,
+    /// 脚本作业
+    Script,
+    /// 构建作业
+    Build,
+    /// 文件分发作业（将内容写入目标主机上的文件）
+    File,
+    /// 软件包清单采集作业（解析结果写入主机软件包清单）
+    Inventory,
+}
+
+/// 作业状态
+///
+/// JSON 中为变体名（如 `PartiallySucceeded`），
+/// 实时事件中为 snake_case（如 `partially_succeeded`），后者用 [`FromStr`] 解析
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "job_status", rename_all = "snake_case")
+)]
+pub enum JobStatus {
+    /// 待执行
+    Pending,
+    /// 执行中
+    Running,
+    /// 已完成
+    Completed,
+    /// 已失败
+    Failed,
+    /// 已取消
+    Cancelled,
+    /// 部分成功（部分任务成功，部分失败）
+    PartiallySucceeded,
+    /// 等待审批（审批通过后派发执行）
+    AwaitingApproval,
+}
+
+impl JobStatus {
+    /// 作业是否已结束
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, JobStatus::Pending | JobStatus::Running | JobStatus::AwaitingApproval)
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::PartiallySucceeded => "partially_succeeded",
+            JobStatus::AwaitingApproval => "awaiting_approval",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(JobStatus::Pending),
+            "running" => Ok(JobStatus::Running),
+            "completed" => Ok(JobStatus::Completed),
+            "failed" => Ok(JobStatus::Failed),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            "partially_succeeded" => Ok(JobStatus::PartiallySucceeded),
+            "awaiting_approval" => Ok(JobStatus::AwaitingApproval),
+            _ => Err(format!("Unknown job status: {}", s)),
+        }
+    }
+}
+
+/// 任务状态（单个主机执行状态）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "sqlx",
+    sqlx(type_name = "task_status", rename_all = "snake_case")
+)]
+pub enum TaskStatus {
+    /// 待执行
+    Pending,
+    /// 执行中
+    Running,
+    /// 成功
+    Succeeded,
+    /// 退出码被归为告警（计入作业成功）
+    Warning,
+    /// 失败
+    Failed,
+    /// 超时
+    Timeout,
+    /// 已取消
+    Cancelled,
+    /// 等待主机维护结束
+    WaitingMaintenance,
+    /// 已跳过（未执行）
+    Skipped,
+}
+
+impl TaskStatus {
+    /// 任务是否已结束
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, TaskStatus::Pending | TaskStatus::Running | TaskStatus::WaitingMaintenance)
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            TaskStatus::Pending => "pending",
+            TaskStatus::Running => "running",
+            TaskStatus::Succeeded => "succeeded",
+            TaskStatus::Warning => "warning",
+            TaskStatus::Failed => "failed",
+            TaskStatus::Timeout => "timeout",
+            TaskStatus::Cancelled => "cancelled",
+            TaskStatus::WaitingMaintenance => "waiting_maintenance",
+            TaskStatus::Skipped => "skipped",
+        };
+        f.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_status_event_names() {
+        for status in [
+            JobStatus::Pending,
+            JobStatus::Running,
+            JobStatus::Completed,
+            JobStatus::Failed,
+            JobStatus::Cancelled,
+            JobStatus::PartiallySucceeded,
+            JobStatus::AwaitingApproval,
+        ] {
+            assert_eq!(status.to_string().parse::<JobStatus>().unwrap(), status);
+        }
+        assert!("Completed".parse::<JobStatus>().is_err());
+        assert!(!JobStatus::Running.is_terminal());
+        assert!(!JobStatus::AwaitingApproval.is_terminal());
+        assert!(JobStatus::PartiallySucceeded.is_terminal());
+    }
+
+    #[test]
+    fn test_login_request_omits_missing_totp_code() {
+        let request = LoginRequest {
+            username: "alice".to_string(),
+            password: "secret".to_string(),
+            totp_code: None,
+        };
+        let value = serde_json::to_value(&request).unwrap();
+        assert!(value.get("totp_code").is_none());
+
+        let parsed: LoginRequest =
+            serde_json::from_value(serde_json::json!({"username": "alice", "password": "x"}))
+                .unwrap();
+        assert!(parsed.totp_code.is_none());
+    }
+
+    #[test]
+    fn test_login_response_round_trip() {
+        let response = LoginResponse {
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            expires_in: 900,
+            user: UserWithRoles {
+                user: UserResponse {
+                    id: Uuid::new_v4(),
+                    username: "alice".to_string(),
+                    email: None,
+                    status: "active".to_string(),
+                    full_name: Some("Alice".to_string()),
+                    department: None,
+                    must_change_password: false,
+                    locale: None,
+                    created_at: Utc::now(),
+                },
+                roles: vec!["operator".to_string()],
+                scopes: vec!["job:execute".to_string()],
+            },
+        };
+        let value = serde_json::to_value(&response).unwrap();
+        // 用户字段与角色平铺在同一层
+        assert_eq!(value["user"]["username"], "alice");
+        assert_eq!(value["user"]["roles"][0], "operator");
+
+        let parsed: LoginResponse = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.user.user.username, "alice");
+        assert_eq!(parsed.user.scopes, vec!["job:execute"]);
+    }
+}
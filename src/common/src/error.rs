@@ -1,7 +1,203 @@
 //! 统一错误模型
 //! 定义所有错误类型和错误响应格式
 //!
-//! 此模块提供的错误类型可被 ops-service 和 ops-runner 共享使用
+//! 此模块提供的错误类型可被 ops-service 和 ops-runner 共享使用。
+//! `ErrorKind` 是两个服务共享的错误分类：HTTP 状态码、是否可重试、
+//! 构建错误分类（`ErrorCategory`）与任务失败原因（`FailureReason`）均由其派生
+
+use serde::{Deserialize, Serialize};
+
+use crate::execution::FailureReason;
+use crate::messages::ErrorCategory;
+
+/// 共享错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// 未认证或凭证无效
+    Unauthenticated,
+    /// 无权访问
+    PermissionDenied,
+    /// 资源不存在
+    NotFound,
+    /// 请求或输入不合法
+    InvalidInput,
+    /// 请求体超过大小上限
+    PayloadTooLarge,
+    /// 请求字段超出长度或数量限制
+    LimitExceeded,
+    /// 触发速率或并发限制
+    RateLimited,
+    /// 操作超时
+    Timeout,
+    /// 服务暂不可用（如维护模式）
+    Unavailable,
+    /// 网络不可达或连接失败
+    Network,
+    /// 目标主机认证失败
+    HostAuth,
+    /// 目标主机密钥验证失败
+    HostKey,
+    /// 命令或步骤执行失败
+    Execution,
+    /// 依赖获取或安装失败
+    Dependency,
+    /// 测试失败
+    Test,
+    /// 资源不足（磁盘、内存等）
+    Resource,
+    /// 存储错误（数据库、文件系统）
+    Storage,
+    /// 配置错误
+    Configuration,
+    /// 内部错误
+    Internal,
+}
+
+impl ErrorKind {
+    /// 分类名称（与序列化一致）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Unauthenticated => "unauthenticated",
+            ErrorKind::PermissionDenied => "permission_denied",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::InvalidInput => "invalid_input",
+            ErrorKind::PayloadTooLarge => "payload_too_large",
+            ErrorKind::LimitExceeded => "limit_exceeded",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Unavailable => "unavailable",
+            ErrorKind::Network => "network",
+            ErrorKind::HostAuth => "host_auth",
+            ErrorKind::HostKey => "host_key",
+            ErrorKind::Execution => "execution",
+            ErrorKind::Dependency => "dependency",
+            ErrorKind::Test => "test",
+            ErrorKind::Resource => "resource",
+            ErrorKind::Storage => "storage",
+            ErrorKind::Configuration => "configuration",
+            ErrorKind::Internal => "internal",
+        }
+    }
+
+    /// 对应的 HTTP 状态码
+    pub fn http_status(&self) -> u16 {
+        match self {
+            ErrorKind::Unauthenticated => 401,
+            ErrorKind::PermissionDenied => 403,
+            ErrorKind::NotFound => 404,
+            ErrorKind::InvalidInput => 400,
+            ErrorKind::PayloadTooLarge => 413,
+            ErrorKind::LimitExceeded => 422,
+            ErrorKind::RateLimited => 429,
+            ErrorKind::Timeout => 408,
+            ErrorKind::Unavailable => 503,
+            ErrorKind::Network
+            | ErrorKind::HostAuth
+            | ErrorKind::HostKey
+            | ErrorKind::Execution
+            | ErrorKind::Dependency
+            | ErrorKind::Test
+            | ErrorKind::Resource
+            | ErrorKind::Storage
+            | ErrorKind::Configuration
+            | ErrorKind::Internal => 500,
+        }
+    }
+
+    /// 错误详情是否面向用户（调用方错误与服务暂不可用），其余分类的详情只记录在日志中
+    pub fn exposes_detail(&self) -> bool {
+        (400..500).contains(&self.http_status()) || *self == ErrorKind::Unavailable
+    }
+
+    /// 相同操作稍后重试是否可能成功
+    pub fn retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::RateLimited
+                | ErrorKind::Timeout
+                | ErrorKind::Unavailable
+                | ErrorKind::Network
+                | ErrorKind::Resource
+                | ErrorKind::Storage
+        )
+    }
+
+    /// 构建错误分类（Runner 上报构建失败时使用）
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ErrorKind::Unauthenticated | ErrorKind::HostAuth | ErrorKind::HostKey => {
+                ErrorCategory::Auth
+            }
+            ErrorKind::PermissionDenied => ErrorCategory::Permission,
+            ErrorKind::Network => ErrorCategory::Network,
+            ErrorKind::Timeout => ErrorCategory::Timeout,
+            ErrorKind::RateLimited | ErrorKind::Resource => ErrorCategory::Resource,
+            ErrorKind::Execution => ErrorCategory::Build,
+            ErrorKind::Dependency => ErrorCategory::Dependency,
+            ErrorKind::Test => ErrorCategory::Test,
+            ErrorKind::Unavailable | ErrorKind::Storage | ErrorKind::Configuration => {
+                ErrorCategory::Infrastructure
+            }
+            ErrorKind::NotFound
+            | ErrorKind::InvalidInput
+            | ErrorKind::PayloadTooLarge
+            | ErrorKind::LimitExceeded
+            | ErrorKind::Internal => ErrorCategory::Unknown,
+        }
+    }
+
+    /// 任务失败原因（执行前的错误；命令超时与退出码由执行结果判断）
+    pub fn failure_reason(&self) -> FailureReason {
+        match self {
+            ErrorKind::Network => FailureReason::NetworkError,
+            ErrorKind::Unauthenticated | ErrorKind::HostAuth => FailureReason::AuthFailed,
+            ErrorKind::HostKey => FailureReason::HostKeyMismatch,
+            ErrorKind::Timeout => FailureReason::ConnectionTimeout,
+            ErrorKind::Execution => FailureReason::CommandFailed,
+            _ => FailureReason::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<ErrorKind> for ErrorCategory {
+    fn from(kind: ErrorKind) -> Self {
+        kind.category()
+    }
+}
+
+impl From<ErrorKind> for FailureReason {
+    fn from(kind: ErrorKind) -> Self {
+        kind.failure_reason()
+    }
+}
+
+impl From<std::io::ErrorKind> for ErrorKind {
+    fn from(kind: std::io::ErrorKind) -> Self {
+        match kind {
+            std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            std::io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => {
+                ErrorKind::InvalidInput
+            }
+            std::io::ErrorKind::ConnectionRefused
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::NotConnected
+            | std::io::ErrorKind::AddrNotAvailable
+            | std::io::ErrorKind::BrokenPipe => ErrorKind::Network,
+            std::io::ErrorKind::OutOfMemory => ErrorKind::Resource,
+            _ => ErrorKind::Storage,
+        }
+    }
+}
 
 /// 应用错误类型 - 简化版本，不依赖 Axum
 /// ops-service 可以使用 AppError 包装为 HTTP 响应
@@ -57,25 +253,46 @@ pub enum AppError {
 }
 
 impl AppError {
+    /// 错误分类
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AppError::Unauthorized | AppError::Authentication(_) => ErrorKind::Unauthenticated,
+            AppError::Forbidden => ErrorKind::PermissionDenied,
+            AppError::NotFound(_) => ErrorKind::NotFound,
+            AppError::BadRequest(_) | AppError::Validation(_) => ErrorKind::InvalidInput,
+            AppError::RateLimitExceeded => ErrorKind::RateLimited,
+            AppError::Timeout(_) => ErrorKind::Timeout,
+            AppError::SshConnectionError(_) | AppError::NetworkError(_) => ErrorKind::Network,
+            AppError::SshAuthenticationError(_) => ErrorKind::HostAuth,
+            AppError::SshExecutionError(_) => ErrorKind::Execution,
+            AppError::Database(_) | AppError::IoError(_) => ErrorKind::Storage,
+            AppError::Config(_) => ErrorKind::Configuration,
+            AppError::Internal(_) => ErrorKind::Internal,
+        }
+    }
+
     /// 获取 HTTP 状态码（作为数字）
     pub fn status_code(&self) -> u16 {
+        self.kind().http_status()
+    }
+
+    /// 稍后重试是否可能成功
+    pub fn retryable(&self) -> bool {
+        self.kind().retryable()
+    }
+
+    /// 可安全返回给用户的错误详情（仅调用方错误携带，内部错误的详情不外泄）
+    pub fn detail(&self) -> Option<String> {
+        if !self.kind().exposes_detail() {
+            return None;
+        }
         match self {
-            AppError::Unauthorized => 401,
-            AppError::Authentication(_) => 401,
-            AppError::Forbidden => 403,
-            AppError::NotFound(_) => 404,
-            AppError::BadRequest(_) => 400,
-            AppError::Validation(_) => 400,
-            AppError::RateLimitExceeded => 429,
-            AppError::Timeout(_) => 408,
-            AppError::SshConnectionError(_)
-            | AppError::SshAuthenticationError(_)
-            | AppError::SshExecutionError(_)
-            | AppError::Database(_)
-            | AppError::Config(_)
-            | AppError::Internal(_)
-            | AppError::NetworkError(_)
-            | AppError::IoError(_) => 500,
+            AppError::Authentication(msg)
+            | AppError::NotFound(msg)
+            | AppError::BadRequest(msg)
+            | AppError::Validation(msg)
+            | AppError::Timeout(msg) => Some(msg.clone()),
+            _ => None,
         }
     }
 
@@ -251,6 +468,39 @@ mod tests {
         assert!(!error_response.error.request_id.is_empty());
     }
 
+    #[test]
+    fn test_error_kind_derivations() {
+        let kind = AppError::SshAuthenticationError("denied".to_string()).kind();
+        assert_eq!(kind, ErrorKind::HostAuth);
+        assert_eq!(kind.category(), ErrorCategory::Auth);
+        assert_eq!(kind.failure_reason(), FailureReason::AuthFailed);
+        assert!(!kind.retryable());
+
+        let kind = AppError::network("connection reset").kind();
+        assert_eq!(ErrorCategory::from(kind), ErrorCategory::Network);
+        assert_eq!(FailureReason::from(kind), FailureReason::NetworkError);
+        assert!(AppError::network("connection reset").retryable());
+
+        assert_eq!(ErrorKind::Unavailable.http_status(), 503);
+        assert_eq!(ErrorKind::PayloadTooLarge.http_status(), 413);
+        assert_eq!(ErrorKind::LimitExceeded.http_status(), 422);
+        assert!(ErrorKind::LimitExceeded.exposes_detail());
+        assert_eq!(ErrorKind::from(std::io::ErrorKind::TimedOut), ErrorKind::Timeout);
+        assert_eq!(serde_json::to_string(&ErrorKind::HostKey).unwrap(), "\"host_key\"");
+    }
+
+    #[test]
+    fn test_detail_only_for_client_errors() {
+        assert_eq!(
+            AppError::validation("name is required").detail().as_deref(),
+            Some("name is required")
+        );
+        assert!(AppError::Database("password=secret".to_string())
+            .detail()
+            .is_none());
+        assert!(AppError::Internal("stack".to_string()).detail().is_none());
+    }
+
     #[test]
     fn test_string_conversion() {
         let error: AppError = "test error".into();
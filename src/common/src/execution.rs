@@ -21,6 +21,14 @@ pub struct ExecutionResult {
 
     /// 是否超时
     pub timed_out: bool,
+
+    /// 远端输出的原始编码（已转换为 UTF-8；未知时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_encoding: Option<String>,
+
+    /// 输出超过上限被提前终止（stdout/stderr 只保留开头与末尾）
+    #[serde(default)]
+    pub output_limit_exceeded: bool,
 }
 
 impl ExecutionResult {
@@ -32,6 +40,8 @@ impl ExecutionResult {
             stderr: String::new(),
             duration_secs,
             timed_out: false,
+            output_encoding: None,
+            output_limit_exceeded: false,
         }
     }
 
@@ -43,6 +53,8 @@ impl ExecutionResult {
             stderr,
             duration_secs,
             timed_out: false,
+            output_encoding: None,
+            output_limit_exceeded: false,
         }
     }
 
@@ -54,12 +66,14 @@ impl ExecutionResult {
             stderr: "Execution timed out".to_string(),
             duration_secs,
             timed_out: true,
+            output_encoding: None,
+            output_limit_exceeded: false,
         }
     }
 
     /// 判断是否成功
     pub fn is_success(&self) -> bool {
-        self.exit_code == 0 && !self.timed_out
+        self.exit_code == 0 && !self.timed_out && !self.output_limit_exceeded
     }
 
     /// 判断是否失败
@@ -93,6 +107,8 @@ impl Default for ExecutionResult {
             stderr: String::new(),
             duration_secs: 0.0,
             timed_out: false,
+            output_encoding: None,
+            output_limit_exceeded: false,
         }
     }
 }
@@ -149,6 +165,8 @@ pub enum FailureReason {
     CommandTimeout,
     /// 命令执行失败（非零退出码）
     CommandFailed,
+    /// 主机密钥验证失败（密钥不匹配或未知主机）
+    HostKeyMismatch,
     /// 未知错误
     Unknown,
 }
@@ -226,6 +244,9 @@ pub struct FailureReasonStats {
     pub command_timeout: i32,
     /// 命令执行失败数量
     pub command_failed: i32,
+    /// 主机密钥验证失败数量
+    #[serde(default)]
+    pub host_key_mismatch: i32,
     /// 未知错误数量
     pub unknown: i32,
 }
@@ -239,6 +260,7 @@ impl FailureReasonStats {
             + self.handshake_timeout
             + self.command_timeout
             + self.command_failed
+            + self.host_key_mismatch
             + self.unknown
     }
 
@@ -251,6 +273,7 @@ impl FailureReasonStats {
             FailureReason::HandshakeTimeout => self.handshake_timeout += 1,
             FailureReason::CommandTimeout => self.command_timeout += 1,
             FailureReason::CommandFailed => self.command_failed += 1,
+            FailureReason::HostKeyMismatch => self.host_key_mismatch += 1,
             FailureReason::Unknown => self.unknown += 1,
         }
     }
@@ -293,6 +316,8 @@ mod tests {
             stderr: "error".to_string(),
             duration_secs: 1.0,
             timed_out: false,
+            output_encoding: None,
+            output_limit_exceeded: false,
         };
 
         let full = result.full_output();
@@ -361,6 +386,7 @@ mod tests {
             (FailureReason::HandshakeTimeout, "handshake_timeout"),
             (FailureReason::CommandTimeout, "command_timeout"),
             (FailureReason::CommandFailed, "command_failed"),
+            (FailureReason::HostKeyMismatch, "host_key_mismatch"),
             (FailureReason::Unknown, "unknown"),
         ];
 
@@ -431,9 +457,11 @@ mod tests {
 
     #[test]
     fn test_failure_reason_stats_total() {
-        let mut stats = FailureReasonStats::default();
-        stats.network_error = 5;
-        stats.auth_failed = 2;
+        let stats = FailureReasonStats {
+            network_error: 5,
+            auth_failed: 2,
+            ..Default::default()
+        };
 
         assert_eq!(stats.total(), 7);
     }
@@ -5,17 +5,23 @@ pub fn version() -> &'static str {
 }
 
 // 导出所有模块
+pub mod api;
 pub mod docker;
 pub mod error;
 pub mod execution;
 pub mod messages;
 pub mod ssh;
+pub mod terminal;
 
 // 重新导出常用的类型和常量
-pub use error::{AppError, ErrorDetail, ErrorResponse, Result as CommonResult};
+pub use error::{AppError, ErrorDetail, ErrorKind, ErrorResponse, Result as CommonResult};
 pub use messages::{
+    normalize_arch,
+    normalize_os,
+    ArtifactPlatform,
     AuthInfo,
     BuildArtifact,
+    BuildCancelMessage,
     BuildLogMessage,
     BuildParameters,
     BuildStatus,
@@ -25,18 +31,25 @@ pub use messages::{
     BuildTaskMessage,
     ErrorCategory,
     Exchanges,
+    KnownHostsSync,
     LogLevel,
+    MessageHeaders,
     // 数据结构
     ProjectInfo,
     PublishTarget,
     QueueTypes,
     // 常量
     RoutingKeys,
+    RunnerCommandAck,
+    RunnerControlAction,
+    RunnerControlMessage,
     RunnerHeartbeatMessage,
 
     RunnerRegistrationMessage,
     RunnerStatus,
+    RunningTaskInfo,
 
+    StepResourceUsage,
     StepStatus,
     StepStatusUpdate,
     // 枚举
@@ -49,6 +62,11 @@ pub use execution::{
     TaskExecutionStatus,
 };
 
-pub use ssh::{HostKeyVerification, SshAuth, SshConfig, SshConfigSettings, SshExecOptions};
+pub use ssh::{
+    known_hosts_digest, render_known_hosts, HostKeyVerification, KnownHostEntry, SshAuth,
+    SshConfig, SshConfigSettings, SshExecOptions,
+};
 
 pub use docker::{ContainerResult, DockerConfig, DockerResourceLimits, DockerSecurityConfig};
+
+pub use terminal::{normalize_output, normalize_output_with, AnsiMode, NormalizedOutput};
@@ -5,6 +5,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 use uuid::Uuid;
 
 /// 构建任务消息（控制面 -> Runner）
@@ -47,6 +48,85 @@ pub struct ProjectInfo {
 
     /// 触发者
     pub triggered_by: Uuid,
+
+    /// 仓库凭据（私有仓库）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<GitCredentials>,
+
+    /// 代码检出选项
+    #[serde(default)]
+    pub checkout: GitCheckoutOptions,
+}
+
+/// Git 仓库凭据
+///
+/// 仅随任务消息下发给 Runner；`Debug` 输出不包含密钥内容
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GitCredentials {
+    /// HTTPS 访问令牌
+    Token {
+        /// 用户名（默认 `x-access-token`）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        username: Option<String>,
+        /// 访问令牌
+        token: String,
+    },
+    /// SSH 部署密钥
+    DeployKey {
+        /// 私钥内容（OpenSSH/PEM 格式，不支持口令）
+        private_key: String,
+    },
+}
+
+impl GitCredentials {
+    /// 需要从输出中清除的密钥内容
+    pub fn secrets(&self) -> Vec<&str> {
+        match self {
+            GitCredentials::Token { token, .. } => vec![token.as_str()],
+            GitCredentials::DeployKey { private_key } => private_key
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with("-----"))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Debug for GitCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitCredentials::Token { username, .. } => f
+                .debug_struct("Token")
+                .field("username", username)
+                .field("token", &"***")
+                .finish(),
+            GitCredentials::DeployKey { .. } => f
+                .debug_struct("DeployKey")
+                .field("private_key", &"***")
+                .finish(),
+        }
+    }
+}
+
+/// 代码检出选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GitCheckoutOptions {
+    /// 递归拉取子模块
+    #[serde(default)]
+    pub submodules: bool,
+
+    /// 拉取 Git LFS 对象
+    #[serde(default)]
+    pub lfs: bool,
+
+    /// 浅克隆深度（默认 1，0 表示完整克隆）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+
+    /// 只拉取该时间之后的提交（`git clone --shallow-since`，设置后忽略 depth）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shallow_since: Option<String>,
 }
 
 /// 构建参数
@@ -96,15 +176,102 @@ pub struct BuildStep {
     #[serde(default)]
     pub continue_on_failure: bool,
 
+    /// 重试策略（未设置时失败不重试）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<StepRetryPolicy>,
+
     /// 是否产生产物
     #[serde(default)]
     pub produces_artifact: bool,
 
+    /// 产物的目标平台（交叉编译时指定，未指定时为 Runner 自身平台）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_platform: Option<ArtifactPlatform>,
+
     /// 指定的 Docker 镜像（可选）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub docker_image: Option<String>,
 }
 
+/// 步骤最大尝试次数上限
+pub const MAX_STEP_ATTEMPTS: u32 = 10;
+
+/// 步骤重试策略
+///
+/// exit_codes 为空且 on_timeout 为 false 时，任何失败或超时都会重试；
+/// 否则只在退出码命中 exit_codes 或（on_timeout 为 true 时）超时时重试。取消的步骤不重试
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StepRetryPolicy {
+    /// 最大尝试次数（含首次执行）
+    pub max_attempts: u32,
+
+    /// 首次重试前的等待时间（秒）
+    #[serde(default)]
+    pub backoff_secs: u64,
+
+    /// 退避倍数（每次重试的等待时间乘以该倍数）
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+
+    /// 最长等待时间（秒）
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+
+    /// 只在这些退出码时重试
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_codes: Vec<i32>,
+
+    /// 超时时重试
+    #[serde(default)]
+    pub on_timeout: bool,
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_backoff_secs() -> u64 {
+    300
+}
+
+impl StepRetryPolicy {
+    /// 第 attempt 次尝试（从 1 开始）以该状态结束后是否还应重试
+    pub fn should_retry(&self, attempt: u32, status: &StepStatus, exit_code: Option<i32>) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        let retry_any = self.exit_codes.is_empty() && !self.on_timeout;
+        match status {
+            StepStatus::Failed => {
+                retry_any || exit_code.is_some_and(|code| self.exit_codes.contains(&code))
+            }
+            StepStatus::Timeout => retry_any || self.on_timeout,
+            _ => false,
+        }
+    }
+
+    /// 第 attempt 次尝试失败后，下一次尝试前的等待时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(MAX_STEP_ATTEMPTS) as i32;
+        let secs = self.backoff_secs as f64 * self.backoff_multiplier.max(1.0).powi(exponent);
+        Duration::from_secs_f64(secs.min(self.max_backoff_secs as f64))
+    }
+
+    /// 校验策略参数
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_attempts == 0 || self.max_attempts > MAX_STEP_ATTEMPTS {
+            return Err(format!("max_attempts must be between 1 and {}", MAX_STEP_ATTEMPTS));
+        }
+        if !self.backoff_multiplier.is_finite() || self.backoff_multiplier < 1.0 {
+            return Err("backoff_multiplier must be at least 1.0".to_string());
+        }
+        if self.backoff_secs > self.max_backoff_secs {
+            return Err("backoff_secs must not exceed max_backoff_secs".to_string());
+        }
+        Ok(())
+    }
+}
+
 /// 步骤类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -123,10 +290,25 @@ pub enum StepType {
     Package,
     /// 发布
     Publish,
-    /// 自定义
+    /// 自定义（`plugin/<name>` 表示由 Runner 插件执行）
     Custom(String),
 }
 
+/// 插件步骤类型前缀，同时也是 Runner 上报的插件能力标签前缀
+pub const PLUGIN_STEP_PREFIX: &str = "plugin/";
+
+impl StepType {
+    /// 插件步骤对应的插件名称（非插件步骤返回 None）
+    pub fn plugin_name(&self) -> Option<&str> {
+        match self {
+            StepType::Custom(custom) => custom
+                .strip_prefix(PLUGIN_STEP_PREFIX)
+                .filter(|name| !name.is_empty()),
+            _ => None,
+        }
+    }
+}
+
 /// 发布目标
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublishTarget {
@@ -191,6 +373,10 @@ pub struct BuildStatusMessage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_category: Option<ErrorCategory>,
 
+    /// 消息序号（同一任务内单调递增，用于去重和乱序检测；0 表示旧版 Runner 未提供）
+    #[serde(default)]
+    pub sequence: u64,
+
     /// 时间戳
     pub timestamp: DateTime<Utc>,
 }
@@ -238,6 +424,33 @@ pub struct StepStatusUpdate {
     /// 是否产生产物
     #[serde(skip_serializing_if = "Option::is_none")]
     pub artifact: Option<BuildArtifact>,
+
+    /// 资源使用情况（步骤结束时上报）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_usage: Option<StepResourceUsage>,
+
+    /// 尝试序号（从 1 开始；0 表示旧版 Runner 未提供）
+    #[serde(default)]
+    pub attempt: u32,
+
+    /// 本次尝试失败后还会按重试策略重新执行（不是步骤的最终状态）
+    #[serde(default)]
+    pub retrying: bool,
+}
+
+/// 步骤资源使用情况
+///
+/// Docker 模式来自容器 stats，原生模式来自进程树采样（/proc）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StepResourceUsage {
+    /// 峰值内存（字节）
+    pub peak_memory_bytes: u64,
+
+    /// CPU 时间（毫秒）
+    pub cpu_time_ms: u64,
+
+    /// 磁盘写入量（字节）
+    pub disk_written_bytes: u64,
 }
 
 /// 步骤状态
@@ -256,6 +469,8 @@ pub enum StepStatus {
     Timeout,
     /// 跳过
     Skipped,
+    /// 已取消
+    Cancelled,
 }
 
 /// 构建产物
@@ -278,6 +493,75 @@ pub struct BuildArtifact {
 
     /// 版本
     pub version: String,
+
+    /// CPU 架构（如 x86_64、aarch64；为空表示与平台无关，旧版 Runner 不上报）
+    #[serde(default)]
+    pub arch: String,
+
+    /// 操作系统（如 linux、windows；为空表示与平台无关）
+    #[serde(default)]
+    pub os: String,
+}
+
+/// 产物目标平台
+///
+/// 同一版本的多平台产物按平台区分为变体，部署时按目标主机的架构与操作系统选择
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArtifactPlatform {
+    /// CPU 架构
+    #[serde(default)]
+    pub arch: String,
+
+    /// 操作系统
+    #[serde(default)]
+    pub os: String,
+}
+
+impl ArtifactPlatform {
+    /// 当前进程运行的平台
+    pub fn current() -> Self {
+        Self {
+            arch: normalize_arch(std::env::consts::ARCH),
+            os: normalize_os(std::env::consts::OS),
+        }
+    }
+
+    /// 规范化架构与操作系统名称
+    pub fn normalized(&self) -> Self {
+        Self {
+            arch: normalize_arch(&self.arch),
+            os: normalize_os(&self.os),
+        }
+    }
+}
+
+/// 规范化 CPU 架构名称（amd64/x64 -> x86_64，arm64 -> aarch64 等），未知名称转为小写保留
+pub fn normalize_arch(arch: &str) -> String {
+    let arch = arch.trim().to_ascii_lowercase();
+    match arch.as_str() {
+        "amd64" | "x64" | "x86-64" => "x86_64".to_string(),
+        "arm64" | "armv8" | "aarch64_be" => "aarch64".to_string(),
+        "i386" | "i486" | "i586" | "i686" | "386" => "x86".to_string(),
+        "armhf" | "armv7" | "armv7l" => "arm".to_string(),
+        _ => arch,
+    }
+}
+
+/// 规范化操作系统名称（Linux 发行版名称归为 linux，macos/osx -> darwin），空值保持为空
+pub fn normalize_os(os: &str) -> String {
+    let os = os.trim().to_ascii_lowercase();
+    if os.is_empty() {
+        return os;
+    }
+    if os.starts_with("windows") {
+        "windows".to_string()
+    } else if matches!(os.as_str(), "darwin" | "macos" | "osx" | "mac os x") {
+        "darwin".to_string()
+    } else if os.starts_with("freebsd") {
+        "freebsd".to_string()
+    } else {
+        "linux".to_string()
+    }
 }
 
 /// 构建日志消息（Runner -> 控制面）
@@ -306,6 +590,10 @@ pub struct BuildLogMessage {
     /// 日志内容（支持增量）
     pub content: String,
 
+    /// 内容是否在规范化时被修改（剥离/转换了 ANSI 序列或控制字符）
+    #[serde(default)]
+    pub content_modified: bool,
+
     /// 字节偏移量（用于增量日志拼接）
     pub offset: u64,
 
@@ -317,6 +605,10 @@ pub struct BuildLogMessage {
     #[serde(default)]
     pub is_final: bool,
 
+    /// 消息序号（与状态消息共享同一任务内的递增序列；0 表示旧版 Runner 未提供）
+    #[serde(default)]
+    pub sequence: u64,
+
     /// 时间戳
     pub timestamp: DateTime<Utc>,
 }
@@ -352,10 +644,97 @@ pub enum ErrorCategory {
     Resource,
     /// 权限错误
     Permission,
+    /// 基础设施故障（如 Runner 进程异常退出导致任务中断）
+    Infrastructure,
     /// 未知错误
     Unknown,
 }
 
+/// 构建取消消息（控制面 -> Runner）
+///
+/// 以 `build.cancel.<job_id>` 路由键广播，未执行该作业的 Runner 直接忽略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildCancelMessage {
+    /// 构建作业 ID
+    pub job_id: Uuid,
+
+    /// 取消原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// 发起取消的用户
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_by: Option<Uuid>,
+
+    /// 时间戳
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Runner 控制指令（控制面 -> Runner）
+///
+/// 以 `runner.control.<runner_name>` 路由键定向发送到该 Runner 的控制队列，
+/// 执行结果随下一次心跳回报
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunnerControlMessage {
+    /// 指令 ID（回报执行结果时使用）
+    pub command_id: Uuid,
+
+    /// 指令类型
+    pub action: RunnerControlAction,
+
+    /// 发起指令的用户
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_by: Option<Uuid>,
+
+    /// 时间戳
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Runner 控制指令类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunnerControlAction {
+    /// 暂停消费构建任务（执行中的任务不受影响）
+    Pause,
+    /// 恢复消费构建任务
+    Resume,
+    /// 清理过期工作空间
+    CleanupWorkspaces,
+    /// 重新向控制面注册
+    Reregister,
+    /// 立即从控制面拉取配置
+    RefreshConfig,
+}
+
+impl RunnerControlAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pause => "pause",
+            Self::Resume => "resume",
+            Self::CleanupWorkspaces => "cleanup_workspaces",
+            Self::Reregister => "reregister",
+            Self::RefreshConfig => "refresh_config",
+        }
+    }
+}
+
+/// Runner 控制指令执行结果（随心跳回报，Runner -> 控制面）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunnerCommandAck {
+    /// 指令 ID
+    pub command_id: Uuid,
+
+    /// 是否执行成功
+    pub success: bool,
+
+    /// 执行结果说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// 执行完成时间
+    pub timestamp: DateTime<Utc>,
+}
+
 /// Runner 注册消息（Runner -> 控制面）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunnerRegistrationMessage {
@@ -443,6 +822,84 @@ pub struct RunnerHeartbeatMessage {
 
     /// 时间戳
     pub timestamp: DateTime<Utc>,
+
+    /// 本地 known_hosts 文件摘要（未启用 known_hosts 同步的 Runner 不上报）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_hosts_digest: Option<String>,
+
+    /// Docker 镜像拉取与缓存统计（不支持 Docker 的 Runner 不上报）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_cache: Option<ImageCacheStats>,
+
+    /// 已应用的控制面 Docker 配置版本（尚未收到带版本的配置时不上报）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_config_version: Option<i64>,
+
+    /// 自上次心跳以来执行完成的控制指令
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command_acks: Vec<RunnerCommandAck>,
+
+    /// 正在执行的构建任务（空列表同样上报，控制面据此区分未上报的旧版本 Runner）
+    #[serde(default)]
+    pub running_tasks: Vec<RunningTaskInfo>,
+}
+
+/// Runner 正在执行的构建任务（随心跳上报）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningTaskInfo {
+    /// 构建作业 ID
+    pub job_id: Uuid,
+
+    /// 任务 ID
+    pub task_id: Uuid,
+
+    /// Runner 开始执行的时间
+    pub started_at: DateTime<Utc>,
+}
+
+/// Docker 镜像拉取与缓存统计（Runner 启动以来的累计值）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImageCacheStats {
+    /// 镜像拉取次数（含后台预拉取）
+    pub pulls: u64,
+
+    /// 拉取失败次数
+    pub pull_failures: u64,
+
+    /// 拉取总耗时（毫秒）
+    pub pull_time_ms: u64,
+
+    /// 单次拉取最长耗时（毫秒）
+    pub max_pull_time_ms: u64,
+
+    /// 步骤镜像命中本地缓存的次数（无需下载镜像层）
+    pub cache_hits: u64,
+
+    /// 步骤镜像需要下载镜像层的次数
+    pub cache_misses: u64,
+}
+
+impl ImageCacheStats {
+    /// 缓存命中率（0-1，尚无步骤使用镜像时为 None）
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.cache_hits + self.cache_misses;
+        (total > 0).then(|| self.cache_hits as f64 / total as f64)
+    }
+
+    /// 平均拉取耗时（毫秒，尚未拉取过镜像时为 None）
+    pub fn avg_pull_time_ms(&self) -> Option<f64> {
+        (self.pulls > 0).then(|| self.pull_time_ms as f64 / self.pulls as f64)
+    }
+}
+
+/// 集中管理的 known_hosts 同步数据（控制面 -> Runner，随心跳响应下发）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownHostsSync {
+    /// 渲染后文件内容的摘要
+    pub digest: String,
+
+    /// 全部已知主机密钥
+    pub entries: Vec<crate::ssh::KnownHostEntry>,
 }
 
 /// Runner 状态
@@ -491,11 +948,17 @@ impl RoutingKeys {
     /// 构建日志路由
     pub const BUILD_LOG: &'static str = "build.log";
 
+    /// 构建取消路由
+    pub const BUILD_CANCEL: &'static str = "build.cancel";
+
     /// Runner 注册路由
     pub const RUNNER_REGISTER: &'static str = "runner.register";
 
     /// Runner 心跳路由
     pub const RUNNER_HEARTBEAT: &'static str = "runner.heartbeat";
+
+    /// Runner 控制指令路由（`runner.control.<runner_name>`）
+    pub const RUNNER_CONTROL: &'static str = "runner.control";
 }
 
 /// Exchange names
@@ -520,6 +983,14 @@ impl QueueTypes {
     pub const RETRY_SUFFIX: &'static str = ".retry";
 }
 
+/// Message headers
+pub struct MessageHeaders;
+
+impl MessageHeaders {
+    /// 请求关联 ID（与 HTTP 的 X-Request-Id 保持一致）
+    pub const REQUEST_ID: &'static str = "x-request-id";
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -544,6 +1015,9 @@ mod tests {
 
         let json = serde_json::to_string(&ErrorCategory::Auth).unwrap();
         assert_eq!(json, "\"auth\"");
+
+        let json = serde_json::to_string(&ErrorCategory::Infrastructure).unwrap();
+        assert_eq!(json, "\"infrastructure\"");
     }
 
     #[test]
@@ -584,6 +1058,7 @@ mod tests {
         assert_eq!(RoutingKeys::BUILD_TASK, "build.task");
         assert_eq!(RoutingKeys::BUILD_STATUS, "build.status");
         assert_eq!(RoutingKeys::BUILD_LOG, "build.log");
+        assert_eq!(RoutingKeys::BUILD_CANCEL, "build.cancel");
         assert_eq!(RoutingKeys::RUNNER_REGISTER, "runner.register");
         assert_eq!(RoutingKeys::RUNNER_HEARTBEAT, "runner.heartbeat");
     }
@@ -609,6 +1084,8 @@ mod tests {
             size: 1024000,
             sha256: "abc123".to_string(),
             version: "1.0.0".to_string(),
+            arch: "aarch64".to_string(),
+            os: "linux".to_string(),
         };
 
         let json = serde_json::to_string(&artifact).unwrap();
@@ -617,5 +1094,139 @@ mod tests {
 
         let deserialized: BuildArtifact = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.size, 1024000);
+        assert_eq!(deserialized.arch, "aarch64");
+
+        // 旧版 Runner 不上报平台
+        let legacy = r#"{"path":"/app","name":"app","artifact_type":"binary","size":1,
+            "sha256":"abc","version":"1.0.0"}"#;
+        let legacy: BuildArtifact = serde_json::from_str(legacy).unwrap();
+        assert!(legacy.arch.is_empty() && legacy.os.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_platform() {
+        assert_eq!(normalize_arch("amd64"), "x86_64");
+        assert_eq!(normalize_arch(" ARM64 "), "aarch64");
+        assert_eq!(normalize_arch("riscv64"), "riscv64");
+        assert_eq!(normalize_os("Ubuntu"), "linux");
+        assert_eq!(normalize_os("macos"), "darwin");
+        assert_eq!(normalize_os("Windows Server 2022"), "windows");
+        assert_eq!(normalize_os(""), "");
+    }
+
+    #[test]
+    fn test_project_info_git_options_optional() {
+        // 旧版控制面不下发凭据和检出选项
+        let json = r#"{"name":"app","repository_url":"https://example.com/app.git",
+            "branch":"main","commit":"","triggered_by":"1b4e28ba-2fa1-11d2-883f-0016d3cca427"}"#;
+        let project: ProjectInfo = serde_json::from_str(json).unwrap();
+        assert!(project.credentials.is_none());
+        assert_eq!(project.checkout, GitCheckoutOptions::default());
+
+        let credentials: GitCredentials =
+            serde_json::from_str(r#"{"type":"token","token":"ghp_secret"}"#).unwrap();
+        assert_eq!(credentials.secrets(), vec!["ghp_secret"]);
+        assert!(!format!("{:?}", credentials).contains("ghp_secret"));
+    }
+
+    #[test]
+    fn test_build_status_message_sequence_optional() {
+        // 旧版 Runner 不携带序号
+        let json = r#"{"task_id":"7f1c7a4e-8c1e-4c8f-9d55-0f4c2f1f1a11",
+            "job_id":"1b4e28ba-2fa1-11d2-883f-0016d3cca427","runner_name":"runner-1",
+            "status":"running","timestamp":"2024-01-01T00:00:00Z"}"#;
+        let message: BuildStatusMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(message.sequence, 0);
+
+        let message = BuildStatusMessage {
+            sequence: 42,
+            ..message
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        assert!(json.contains("\"sequence\":42"));
+    }
+
+    #[test]
+    fn test_step_status_update_resource_usage_optional() {
+        // 旧版 Runner 不上报资源使用情况
+        let json =
+            r#"{"step_id":"build","status":"succeeded","started_at":"2024-01-01T00:00:00Z"}"#;
+        let update: StepStatusUpdate = serde_json::from_str(json).unwrap();
+        assert!(update.resource_usage.is_none());
+
+        let update = StepStatusUpdate {
+            resource_usage: Some(StepResourceUsage {
+                peak_memory_bytes: 512,
+                cpu_time_ms: 20,
+                disk_written_bytes: 4096,
+            }),
+            ..update
+        };
+        let json = serde_json::to_string(&update).unwrap();
+        assert!(json.contains("\"peak_memory_bytes\":512"));
+    }
+
+    #[test]
+    fn test_step_status_update_attempt_optional() {
+        // 旧版 Runner 不上报尝试序号
+        let json = r#"{"step_id":"build","status":"failed","started_at":"2024-01-01T00:00:00Z"}"#;
+        let update: StepStatusUpdate = serde_json::from_str(json).unwrap();
+        assert_eq!(update.attempt, 0);
+        assert!(!update.retrying);
+    }
+
+    #[test]
+    fn test_step_retry_policy() {
+        let policy: StepRetryPolicy =
+            serde_json::from_str(r#"{"max_attempts":3,"backoff_secs":10}"#).unwrap();
+        assert!(policy.validate().is_ok());
+        assert!(policy.should_retry(1, &StepStatus::Failed, Some(1)));
+        assert!(policy.should_retry(2, &StepStatus::Timeout, None));
+        assert!(!policy.should_retry(3, &StepStatus::Failed, Some(1)));
+        assert!(!policy.should_retry(1, &StepStatus::Cancelled, None));
+        assert_eq!(policy.backoff(1), Duration::from_secs(10));
+        assert_eq!(policy.backoff(2), Duration::from_secs(20));
+
+        // 只在指定退出码时重试，超时不重试
+        let policy = StepRetryPolicy {
+            exit_codes: vec![75],
+            max_backoff_secs: 15,
+            ..policy
+        };
+        assert!(policy.should_retry(1, &StepStatus::Failed, Some(75)));
+        assert!(!policy.should_retry(1, &StepStatus::Failed, Some(1)));
+        assert!(!policy.should_retry(1, &StepStatus::Timeout, None));
+        assert_eq!(policy.backoff(2), Duration::from_secs(15));
+
+        let policy = StepRetryPolicy {
+            max_attempts: 0,
+            ..policy
+        };
+        assert!(policy.validate().is_err());
+    }
+
+    #[test]
+    fn test_step_type_plugin_name() {
+        assert_eq!(StepType::Custom("plugin/codesign".to_string()).plugin_name(), Some("codesign"));
+        assert_eq!(StepType::Custom("plugin/".to_string()).plugin_name(), None);
+        assert_eq!(StepType::Custom("deploy".to_string()).plugin_name(), None);
+        assert_eq!(StepType::Build.plugin_name(), None);
+    }
+
+    #[test]
+    fn test_runner_control_action_serialization() {
+        let message = RunnerControlMessage {
+            command_id: Uuid::new_v4(),
+            action: RunnerControlAction::CleanupWorkspaces,
+            requested_by: None,
+            timestamp: Utc::now(),
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        assert!(json.contains("\"action\":\"cleanup_workspaces\""));
+        assert!(!json.contains("requested_by"));
+
+        let parsed: RunnerControlAction = serde_json::from_str("\"refresh_config\"").unwrap();
+        assert_eq!(parsed, RunnerControlAction::RefreshConfig);
+        assert_eq!(parsed.as_str(), "refresh_config");
     }
 }
@@ -4,6 +4,7 @@
 
 use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 
 /// 主机密钥验证策略
@@ -32,6 +33,44 @@ impl std::str::FromStr for HostKeyVerification {
     }
 }
 
+/// 远端输出编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum OutputEncoding {
+    /// UTF-8（无效字节按替换字符处理）
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// GBK（兼容 GB2312，常见于中文 Windows 与旧版系统）
+    #[serde(rename = "gbk")]
+    Gbk,
+    /// 自动检测：合法 UTF-8 按 UTF-8 处理，否则尝试 GBK
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+impl OutputEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Gbk => "gbk",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+impl std::str::FromStr for OutputEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "gbk" | "gb2312" => Ok(Self::Gbk),
+            "auto" => Ok(Self::Auto),
+            _ => Err(format!("Unknown output encoding: {}", s)),
+        }
+    }
+}
+
 /// SSH 认证方式
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -82,6 +121,14 @@ pub struct SshConfig {
     /// 已知的主机密钥（known_hosts 格式的简化存储）
     #[serde(default)]
     pub known_hosts: Option<HashMap<String, String>>,
+
+    /// 远端输出编码
+    #[serde(default)]
+    pub output_encoding: OutputEncoding,
+
+    /// 命令输出（stdout + stderr）上限（字节），超过时终止命令；0 表示不限制
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: u64,
 }
 
 fn default_ssh_port() -> u16 {
@@ -96,6 +143,11 @@ fn default_command_timeout() -> u64 {
     300
 }
 
+/// 默认输出上限：64 MiB
+pub fn default_max_output_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
 impl SshConfig {
     /// 创建新的 SSH 配置
     pub fn new(host: String, username: String, auth: SshAuth) -> Self {
@@ -109,6 +161,8 @@ impl SshConfig {
             command_timeout_secs: default_command_timeout(),
             host_key_verification: HostKeyVerification::default(),
             known_hosts: None,
+            output_encoding: OutputEncoding::default(),
+            max_output_bytes: default_max_output_bytes(),
         }
     }
 
@@ -229,8 +283,94 @@ impl SshConfigSettings {
             command_timeout_secs: self.command_timeout_secs,
             host_key_verification: HostKeyVerification::default(),
             known_hosts: None,
+            output_encoding: OutputEncoding::default(),
+            max_output_bytes: default_max_output_bytes(),
+        }
+    }
+}
+
+/// 集中管理的已知主机密钥（对应 OpenSSH known_hosts 中的一条记录）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnownHostEntry {
+    /// 主机地址
+    pub host: String,
+    /// 端口
+    pub port: u16,
+    /// 密钥类型（如 ssh-ed25519）
+    pub key_type: String,
+    /// 公钥（base64）
+    pub public_key: String,
+}
+
+impl KnownHostEntry {
+    /// `SshConfig::known_hosts` 使用的查找键（host:port）
+    pub fn host_key(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// 渲染为 known_hosts 行，非 22 端口使用 `[host]:port` 形式
+    pub fn to_line(&self) -> String {
+        if self.port == 22 {
+            format!("{} {} {}", self.host, self.key_type, self.public_key)
+        } else {
+            format!("[{}]:{} {} {}", self.host, self.port, self.key_type, self.public_key)
         }
     }
+
+    /// 解析 known_hosts 行
+    ///
+    /// 一行可包含多个逗号分隔的主机模式，每个模式生成一条记录；
+    /// 哈希主机名、通配符、否定模式和 @cert-authority/@revoked 标记行无法映射到具体主机，直接跳过
+    pub fn parse_line(line: &str) -> Vec<KnownHostEntry> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            return Vec::new();
+        }
+
+        let mut parts = line.split_whitespace();
+        let (Some(patterns), Some(key_type), Some(public_key)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Vec::new();
+        };
+
+        patterns
+            .split(',')
+            .filter(|p| !p.starts_with('|') && !p.starts_with('!') && !p.contains(['*', '?']))
+            .filter_map(|pattern| {
+                let (host, port) = match pattern.strip_prefix('[') {
+                    Some(rest) => {
+                        let (host, port) = rest.split_once("]:")?;
+                        (host, port.parse().ok()?)
+                    }
+                    None => (pattern, 22),
+                };
+                (!host.is_empty()).then(|| KnownHostEntry {
+                    host: host.to_string(),
+                    port,
+                    key_type: key_type.to_string(),
+                    public_key: public_key.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// 渲染 known_hosts 文件内容
+///
+/// 按主机、端口排序，保证同一集合的渲染结果（及其摘要）稳定
+pub fn render_known_hosts(entries: &[KnownHostEntry]) -> String {
+    let mut sorted: Vec<&KnownHostEntry> = entries.iter().collect();
+    sorted.sort_by(|a, b| (&a.host, a.port, &a.key_type).cmp(&(&b.host, b.port, &b.key_type)));
+    sorted
+        .iter()
+        .map(|entry| format!("{}\n", entry.to_line()))
+        .collect()
+}
+
+/// known_hosts 文件内容的摘要（SHA-256 十六进制），用于判断 Runner 是否需要同步
+pub fn known_hosts_digest(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
 }
 
 /// SSH 执行选项
@@ -392,6 +532,8 @@ mod tests {
             command_timeout_secs: 300,
             host_key_verification: HostKeyVerification::Strict,
             known_hosts: None,
+            output_encoding: OutputEncoding::Gbk,
+            max_output_bytes: 1024,
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -402,5 +544,40 @@ mod tests {
         let deserialized: SshConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.host, "test.com");
         assert_eq!(deserialized.port, 2222);
+        assert_eq!(deserialized.output_encoding, OutputEncoding::Gbk);
+    }
+
+    #[test]
+    fn test_output_encoding_parse() {
+        assert_eq!("UTF8".parse::<OutputEncoding>(), Ok(OutputEncoding::Utf8));
+        assert_eq!("gb2312".parse::<OutputEncoding>(), Ok(OutputEncoding::Gbk));
+        assert_eq!("auto".parse::<OutputEncoding>(), Ok(OutputEncoding::Auto));
+        assert!("latin1".parse::<OutputEncoding>().is_err());
+        assert_eq!(serde_json::to_string(&OutputEncoding::Utf8).unwrap(), "\"utf-8\"");
+    }
+
+    #[test]
+    fn test_known_host_entry_parse_and_render() {
+        let entries = KnownHostEntry::parse_line(
+            "web1,[10.0.0.5]:2222,|1|hashed= ssh-ed25519 AAAAC3NzaC1lZDI1NTE5 comment",
+        );
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].host_key(), "web1:22");
+        assert_eq!(entries[1].host_key(), "10.0.0.5:2222");
+        assert_eq!(entries[1].to_line(), "[10.0.0.5]:2222 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5");
+
+        assert!(KnownHostEntry::parse_line("# comment").is_empty());
+        assert!(KnownHostEntry::parse_line("*.example.com ssh-rsa AAAA").is_empty());
+        assert!(KnownHostEntry::parse_line("@revoked host ssh-rsa AAAA").is_empty());
+
+        // 渲染结果与输入顺序无关
+        let reversed: Vec<_> = entries.iter().rev().cloned().collect();
+        let content = render_known_hosts(&entries);
+        assert_eq!(content, render_known_hosts(&reversed));
+        assert_eq!(
+            known_hosts_digest(&content),
+            known_hosts_digest(&render_known_hosts(&reversed))
+        );
+        assert_eq!(content.lines().count(), 2);
     }
 }
@@ -0,0 +1,408 @@
+//! 终端输出规范化
+//!
+//! 任务和构建输出常带有 ANSI 转义序列（颜色、光标移动、窗口标题等）和控制字符，
+//! 直接存储会破坏前端渲染并干扰脱敏正则。本模块按配置剥离或转换 ANSI 序列，
+//! 移除危险控制字符，并记录内容是否被修改。控制面和 Runner 使用同一套规则。
+
+use serde::{Deserialize, Serialize};
+
+/// ANSI 转义序列处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnsiMode {
+    /// 剥离全部转义序列，仅保留纯文本
+    #[default]
+    Strip,
+    /// 将 SGR 颜色/样式转换为带 `ansi-*` class 的 HTML `<span>`，文本做 HTML 转义，
+    /// 其余转义序列剥离
+    Html,
+}
+
+impl std::str::FromStr for AnsiMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "strip" => Ok(Self::Strip),
+            "html" => Ok(Self::Html),
+            other => Err(format!("unknown ANSI mode: {}", other)),
+        }
+    }
+}
+
+/// 规范化结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedOutput {
+    /// 规范化后的内容
+    pub content: String,
+    /// 是否移除或转换了转义序列、控制字符
+    pub modified: bool,
+}
+
+/// 规范化输出
+pub fn normalize_output(input: &str, mode: AnsiMode) -> NormalizedOutput {
+    normalize_output_with(input, mode, |text| text.to_string())
+}
+
+/// 规范化输出，并对纯文本应用 `transform`（如脱敏）
+///
+/// `transform` 作用于剥离转义序列后的文本、HTML 转义之前：Strip 模式下对整段文本调用一次，
+/// Html 模式下对每个样式区间分别调用。`modified` 不反映 `transform` 自身的改动。
+pub fn normalize_output_with<F>(input: &str, mode: AnsiMode, transform: F) -> NormalizedOutput
+where
+    F: Fn(&str) -> String,
+{
+    let mut writer = Writer::new(mode, transform);
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | '\t' => writer.text.push(c),
+            '\r' => {
+                // CRLF 归一为 LF，单独的 CR（进度条覆盖）视为换行
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                writer.text.push('\n');
+                writer.modified = true;
+            }
+            '\x1b' => {
+                writer.modified = true;
+                match chars.peek().copied() {
+                    Some('[') => {
+                        chars.next();
+                        if let Some(params) = read_csi(&mut chars) {
+                            writer.apply_sgr(&params);
+                        }
+                    }
+                    Some(']' | 'P' | 'X' | '^' | '_') => {
+                        chars.next();
+                        skip_string(&mut chars);
+                    }
+                    Some(next) if ('\x20'..='\x7e').contains(&next) => {
+                        skip_escape(&mut chars);
+                    }
+                    _ => {}
+                }
+            }
+            // C1 形式的 CSI / 字符串序列
+            '\u{9b}' => {
+                writer.modified = true;
+                if let Some(params) = read_csi(&mut chars) {
+                    writer.apply_sgr(&params);
+                }
+            }
+            '\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}' => {
+                writer.modified = true;
+                skip_string(&mut chars);
+            }
+            c if is_dangerous_control(c) => writer.modified = true,
+            c => writer.text.push(c),
+        }
+    }
+
+    writer.finish()
+}
+
+/// 需要移除的控制字符：C0/C1 控制符（保留换行和制表符）及 Unicode 双向覆盖字符
+fn is_dangerous_control(c: char) -> bool {
+    matches!(c,
+        '\0'..='\x08' | '\x0b'..='\x1f' | '\x7f'..='\u{9f}'
+        | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// 读取 CSI 序列（已消费 `ESC [`），是 SGR 时返回其参数
+///
+/// 遇到非法字符时停止，非法字符留给调用方按普通字符处理
+fn read_csi<I>(chars: &mut std::iter::Peekable<I>) -> Option<String>
+where
+    I: Iterator<Item = char>,
+{
+    let mut params = String::new();
+    let mut has_intermediate = false;
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\x30'..='\x3f' if !has_intermediate => params.push(c),
+            '\x20'..='\x2f' => has_intermediate = true,
+            '\x40'..='\x7e' => {
+                chars.next();
+                return (c == 'm' && !has_intermediate).then_some(params);
+            }
+            _ => return None,
+        }
+        chars.next();
+    }
+    None
+}
+
+/// 跳过 OSC/DCS/SOS/PM/APC 字符串，直到 BEL 或 ST（`ESC \` / U+009C）
+fn skip_string<I>(chars: &mut std::iter::Peekable<I>)
+where
+    I: Iterator<Item = char>,
+{
+    while let Some(c) = chars.next() {
+        match c {
+            '\x07' | '\u{9c}' => return,
+            '\x1b' => {
+                if chars.peek() == Some(&'\\') {
+                    chars.next();
+                }
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 跳过其余两字符及带中间字节的 ESC 序列（如字符集切换 `ESC ( B`）
+fn skip_escape<I>(chars: &mut std::iter::Peekable<I>)
+where
+    I: Iterator<Item = char>,
+{
+    while let Some(&c) = chars.peek() {
+        chars.next();
+        if !('\x20'..='\x2f').contains(&c) {
+            return;
+        }
+    }
+}
+
+/// SGR 样式状态
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SgrStyle {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    fg: Option<u8>,
+    bg: Option<u8>,
+}
+
+impl SgrStyle {
+    /// 应用 SGR 参数（`;` 或 `:` 分隔，空参数视为 0）
+    fn apply(&mut self, params: &str) {
+        let mut codes = params
+            .split([';', ':'])
+            .map(|p| p.parse::<u16>().unwrap_or(0));
+
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.fg = Some((code - 30) as u8),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some((code - 40) as u8),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some((code - 90 + 8) as u8),
+                100..=107 => self.bg = Some((code - 100 + 8) as u8),
+                38 | 48 => {
+                    // 256 色取调色板序号，真彩色不支持，仅消费参数
+                    let color = match codes.next() {
+                        Some(5) => codes.next().and_then(|n| u8::try_from(n).ok()),
+                        Some(2) => {
+                            codes.by_ref().take(3).for_each(drop);
+                            None
+                        }
+                        _ => None,
+                    };
+                    if let Some(color) = color {
+                        if code == 38 {
+                            self.fg = Some(color);
+                        } else {
+                            self.bg = Some(color);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 对应的 CSS class，默认样式返回 None
+    fn classes(&self) -> Option<String> {
+        let mut classes = Vec::new();
+        if self.bold {
+            classes.push("ansi-bold".to_string());
+        }
+        if self.dim {
+            classes.push("ansi-dim".to_string());
+        }
+        if self.italic {
+            classes.push("ansi-italic".to_string());
+        }
+        if self.underline {
+            classes.push("ansi-underline".to_string());
+        }
+        if let Some(fg) = self.fg {
+            classes.push(format!("ansi-fg-{}", fg));
+        }
+        if let Some(bg) = self.bg {
+            classes.push(format!("ansi-bg-{}", bg));
+        }
+        (!classes.is_empty()).then(|| classes.join(" "))
+    }
+}
+
+/// 输出拼装器
+struct Writer<F> {
+    mode: AnsiMode,
+    transform: F,
+    out: String,
+    /// 当前样式下尚未输出的文本
+    text: String,
+    style: SgrStyle,
+    /// 已输出但未闭合的 span class
+    open_span: Option<String>,
+    modified: bool,
+}
+
+impl<F> Writer<F>
+where
+    F: Fn(&str) -> String,
+{
+    fn new(mode: AnsiMode, transform: F) -> Self {
+        Self {
+            mode,
+            transform,
+            out: String::new(),
+            text: String::new(),
+            style: SgrStyle::default(),
+            open_span: None,
+            modified: false,
+        }
+    }
+
+    /// 应用 SGR 序列，样式实际变化时才切分文本，避免无意义的切分干扰 `transform`
+    fn apply_sgr(&mut self, params: &str) {
+        if self.mode != AnsiMode::Html {
+            return;
+        }
+        let mut style = self.style.clone();
+        style.apply(params);
+        if style != self.style {
+            self.flush_text();
+            self.style = style;
+        }
+    }
+
+    /// 以当前样式输出缓冲的文本（仅 Html 模式）
+    fn flush_text(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        let classes = self.style.classes();
+        if self.open_span != classes {
+            if self.open_span.is_some() {
+                self.out.push_str("</span>");
+            }
+            if let Some(classes) = &classes {
+                self.out.push_str(&format!("<span class=\"{}\">", classes));
+            }
+            self.open_span = classes;
+        }
+        let rendered = (self.transform)(&self.text);
+        self.out.push_str(&escape_html(&rendered));
+        self.text.clear();
+    }
+
+    fn finish(mut self) -> NormalizedOutput {
+        match self.mode {
+            AnsiMode::Strip => self.out = (self.transform)(&self.text),
+            AnsiMode::Html => {
+                self.flush_text();
+                if self.open_span.is_some() {
+                    self.out.push_str("</span>");
+                }
+            }
+        }
+        NormalizedOutput {
+            content: self.out,
+            modified: self.modified,
+        }
+    }
+}
+
+/// HTML 转义
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi_sequences() {
+        let input = "\x1b[1;32mok\x1b[0m \x1b]0;title\x07done\x1b[2K\x1b(B";
+        let result = normalize_output(input, AnsiMode::Strip);
+        assert_eq!(result.content, "ok done");
+        assert!(result.modified);
+    }
+
+    #[test]
+    fn test_plain_text_is_unmodified() {
+        let result = normalize_output("line 1\n\tline 2 <b>", AnsiMode::Strip);
+        assert_eq!(result.content, "line 1\n\tline 2 <b>");
+        assert!(!result.modified);
+    }
+
+    #[test]
+    fn test_remove_dangerous_control_characters() {
+        let input = "a\0b\x07c\x08d\r\ne\rf\u{202e}g\u{9b}31mh";
+        let result = normalize_output(input, AnsiMode::Strip);
+        assert_eq!(result.content, "abcd\ne\nfgh");
+        assert!(result.modified);
+    }
+
+    #[test]
+    fn test_html_conversion() {
+        let input = "\x1b[31merror\x1b[0m: <tag> \x1b[1;38;5;208mwarn\x1b[m";
+        let result = normalize_output(input, AnsiMode::Html);
+        assert_eq!(
+            result.content,
+            "<span class=\"ansi-fg-1\">error</span>: &lt;tag&gt; \
+             <span class=\"ansi-bold ansi-fg-208\">warn</span>"
+        );
+        assert!(result.modified);
+    }
+
+    #[test]
+    fn test_transform_sees_text_without_escapes() {
+        let sanitize = |text: &str| text.replace("password=secret", "password=***");
+        let input = "password=\x1b[0msecret";
+
+        for mode in [AnsiMode::Strip, AnsiMode::Html] {
+            let result = normalize_output_with(input, mode, sanitize);
+            assert_eq!(result.content, "password=***");
+        }
+    }
+
+    #[test]
+    fn test_ansi_mode_deserialize() {
+        let mode: AnsiMode = serde_json::from_str("\"html\"").unwrap();
+        assert_eq!(mode, AnsiMode::Html);
+        assert_eq!("Strip".parse::<AnsiMode>(), Ok(AnsiMode::Strip));
+        assert!("color".parse::<AnsiMode>().is_err());
+        assert_eq!(AnsiMode::default(), AnsiMode::Strip);
+    }
+}
@@ -0,0 +1,118 @@
+//! 认证凭证与访问令牌状态
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::models::TokenPair;
+
+/// 访问令牌到期前提前刷新的时间
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// 客户端凭证
+#[derive(Clone)]
+pub enum Credentials {
+    /// 用户名与密码：首次请求时登录，刷新令牌失效时重新登录
+    ///
+    /// 重新登录无法提供一次性验证码，启用双因素认证的账号请先调用 `OpsClient::login`
+    Password { username: String, password: String },
+    /// 已签发的令牌：访问令牌过期或被拒绝时用刷新令牌换取新令牌
+    Tokens {
+        access_token: String,
+        refresh_token: String,
+    },
+}
+
+impl Credentials {
+    pub fn password(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Credentials::Password {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    pub fn tokens(access_token: impl Into<String>, refresh_token: impl Into<String>) -> Self {
+        Credentials::Tokens {
+            access_token: access_token.into(),
+            refresh_token: refresh_token.into(),
+        }
+    }
+}
+
+// 不在日志中输出密码与令牌
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Credentials::Password { username, .. } => f
+                .debug_struct("Password")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Credentials::Tokens { .. } => f.debug_struct("Tokens").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// 当前持有的令牌
+#[derive(Clone)]
+pub(crate) struct TokenState {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// 访问令牌过期时间（外部传入的令牌未知，直到被服务端拒绝）
+    pub expires_at: Option<Instant>,
+}
+
+impl TokenState {
+    pub fn issued(access_token: String, refresh_token: String, expires_in: u64) -> Self {
+        Self {
+            access_token,
+            refresh_token,
+            expires_at: Instant::now().checked_add(Duration::from_secs(expires_in)),
+        }
+    }
+
+    pub fn from_pair(pair: TokenPair) -> Self {
+        Self::issued(pair.access_token, pair.refresh_token, pair.expires_in)
+    }
+
+    /// 访问令牌是否需要刷新
+    pub fn needs_refresh(&self, now: Instant) -> bool {
+        match self.expires_at {
+            Some(expires_at) => now + REFRESH_MARGIN >= expires_at,
+            None => false,
+        }
+    }
+
+    /// 标记访问令牌已失效（服务端拒绝时），下次请求前刷新
+    pub fn expire(&mut self) {
+        self.expires_at = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_needs_refresh() {
+        let now = Instant::now();
+        let state = TokenState::issued("a".into(), "r".into(), 900);
+        assert!(!state.needs_refresh(now));
+        assert!(state.needs_refresh(now + Duration::from_secs(875)));
+
+        let mut external = TokenState {
+            access_token: "a".into(),
+            refresh_token: "r".into(),
+            expires_at: None,
+        };
+        assert!(!external.needs_refresh(now + Duration::from_secs(86400)));
+        external.expire();
+        assert!(external.needs_refresh(Instant::now()));
+    }
+
+    #[test]
+    fn test_credentials_debug_hides_secrets() {
+        let debug = format!("{:?}", Credentials::password("deployer", "s3cret"));
+        assert!(debug.contains("deployer"));
+        assert!(!debug.contains("s3cret"));
+        assert!(!format!("{:?}", Credentials::tokens("at", "rt")).contains("rt"));
+    }
+}
@@ -0,0 +1,535 @@
+//! ops-service API 客户端
+
+use common::ErrorKind;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
+use reqwest::{Method, Response, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::auth::{Credentials, TokenState};
+use crate::error::{ClientError, Result};
+use crate::models::{
+    CancelJobRequest, CreateCommandJobRequest, CreateScriptJobRequest, CurrentUser, Host, HostList,
+    HostListQuery, Job, JobListQuery, JobStatus, LoginRequest, LoginResponse, RefreshTokenRequest,
+    Task, TokenPair,
+};
+use crate::retry::RetryPolicy;
+use crate::sse::{EventStream, JobEvent};
+
+/// 默认请求超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// 默认连接超时
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 客户端构建器
+#[derive(Debug)]
+pub struct OpsClientBuilder {
+    base_url: String,
+    credentials: Option<Credentials>,
+    retry: RetryPolicy,
+    timeout: Duration,
+    connect_timeout: Duration,
+    user_agent: String,
+}
+
+impl OpsClientBuilder {
+    /// 认证凭证（未设置时只能调用无需认证的接口，或先调用 `login`）
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 单次请求超时（不限制 SSE 事件流）
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn build(self) -> Result<OpsClient> {
+        let mut base_url = Url::parse(&self.base_url).map_err(|e| {
+            ClientError::Config(format!("Invalid base URL {}: {}", self.base_url, e))
+        })?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::Config(format!("Invalid base URL {}", self.base_url)));
+        }
+        // 以 / 结尾，拼接接口路径时保留部署前缀（如 https://host/ops/）
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+
+        let http = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .user_agent(self.user_agent)
+            .build()?;
+
+        let tokens = match &self.credentials {
+            Some(Credentials::Tokens {
+                access_token,
+                refresh_token,
+            }) => Some(TokenState {
+                access_token: access_token.clone(),
+                refresh_token: refresh_token.clone(),
+                expires_at: None,
+            }),
+            _ => None,
+        };
+
+        Ok(OpsClient {
+            inner: Arc::new(Inner {
+                http,
+                base_url,
+                credentials: self.credentials,
+                tokens: Mutex::new(tokens),
+                retry: self.retry,
+                timeout: self.timeout,
+            }),
+        })
+    }
+}
+
+/// ops-service API 客户端
+///
+/// 可廉价克隆，克隆之间共享连接池与令牌
+#[derive(Clone)]
+pub struct OpsClient {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    http: reqwest::Client,
+    base_url: Url,
+    credentials: Option<Credentials>,
+    /// 当前令牌（持锁刷新，避免并发请求重复刷新导致刷新令牌轮换冲突）
+    tokens: Mutex<Option<TokenState>>,
+    retry: RetryPolicy,
+    timeout: Duration,
+}
+
+/// 单个 API 调用
+struct Call {
+    method: Method,
+    url: Url,
+    body: Option<serde_json::Value>,
+    headers: HeaderMap,
+    /// 为空时不限制（SSE 事件流）
+    timeout: Option<Duration>,
+    authenticated: bool,
+}
+
+impl OpsClient {
+    /// 创建构建器，`base_url` 为服务地址（如 `https://ops.example.com`）
+    pub fn builder(base_url: impl Into<String>) -> OpsClientBuilder {
+        OpsClientBuilder {
+            base_url: base_url.into(),
+            credentials: None,
+            retry: RetryPolicy::default(),
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            user_agent: format!("ops-client/{}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.inner.retry
+    }
+
+    // ==================== 认证 ====================
+
+    /// 用户名密码登录（启用双因素认证时需提供验证码），之后的请求使用签发的令牌
+    pub async fn login(
+        &self,
+        username: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<LoginResponse> {
+        let request = LoginRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+            totp_code: totp_code.map(str::to_string),
+        };
+        let call = self
+            .call(Method::POST, "/api/v1/auth/login", &[], Some(&request))?
+            .anonymous();
+        let response: LoginResponse = self.json(call).await?;
+        *self.inner.tokens.lock().await = Some(TokenState::issued(
+            response.access_token.clone(),
+            response.refresh_token.clone(),
+            response.expires_in,
+        ));
+        Ok(response)
+    }
+
+    /// 注销当前会话（吊销刷新令牌）
+    pub async fn logout(&self) -> Result<()> {
+        let Some(state) = self.inner.tokens.lock().await.clone() else {
+            return Ok(());
+        };
+        let request = RefreshTokenRequest {
+            refresh_token: state.refresh_token,
+        };
+        self.execute(&self.call(Method::POST, "/api/v1/auth/logout", &[], Some(&request))?)
+            .await?;
+        *self.inner.tokens.lock().await = None;
+        Ok(())
+    }
+
+    /// 当前登录用户
+    pub async fn current_user(&self) -> Result<CurrentUser> {
+        self.get("/api/v1/auth/me", &[]).await
+    }
+
+    // ==================== 作业 ====================
+
+    pub async fn list_jobs(&self, query: &JobListQuery) -> Result<Vec<Job>> {
+        self.get("/api/v1/jobs", &query.pairs()).await
+    }
+
+    pub async fn get_job(&self, job_id: Uuid) -> Result<Job> {
+        self.get(&format!("/api/v1/jobs/{}", job_id), &[]).await
+    }
+
+    pub async fn create_command_job(&self, request: &CreateCommandJobRequest) -> Result<Job> {
+        self.post("/api/v1/jobs/command", request).await
+    }
+
+    pub async fn create_script_job(&self, request: &CreateScriptJobRequest) -> Result<Job> {
+        self.post("/api/v1/jobs/script", request).await
+    }
+
+    /// 作业的任务列表（无输出明细权限时不含完整输出）
+    pub async fn get_job_tasks(&self, job_id: Uuid) -> Result<Vec<Task>> {
+        self.get(&format!("/api/v1/jobs/{}/tasks", job_id), &[])
+            .await
+    }
+
+    pub async fn cancel_job(&self, job_id: Uuid, reason: Option<&str>) -> Result<()> {
+        let request = CancelJobRequest {
+            reason: reason.map(str::to_string),
+        };
+        self.post(&format!("/api/v1/jobs/{}/cancel", job_id), &request)
+            .await
+    }
+
+    /// 等待作业结束，返回最终状态的作业
+    pub async fn wait_for_job(&self, job_id: Uuid) -> Result<Job> {
+        // 先订阅再查询，避免错过两者之间的状态变更
+        let mut events = self.subscribe_job(job_id).await?;
+        let job = self.get_job(job_id).await?;
+        if job.status.is_terminal() {
+            return Ok(job);
+        }
+        loop {
+            if let JobEvent::JobStatusChanged { new_status, .. } = events.next_job_event().await? {
+                let finished = new_status
+                    .parse::<JobStatus>()
+                    .map(|status| status.is_terminal())
+                    .unwrap_or(false);
+                if finished {
+                    return self.get_job(job_id).await;
+                }
+            }
+        }
+    }
+
+    // ==================== 主机 ====================
+
+    pub async fn list_hosts(&self, query: &HostListQuery) -> Result<HostList> {
+        self.get("/api/v1/hosts", &query.pairs()).await
+    }
+
+    pub async fn get_host(&self, host_id: Uuid) -> Result<Host> {
+        self.get(&format!("/api/v1/hosts/{}", host_id), &[]).await
+    }
+
+    // ==================== 事件订阅 ====================
+
+    /// 订阅作业事件（作业与任务状态、任务输出）
+    pub async fn subscribe_job(&self, job_id: Uuid) -> Result<EventStream> {
+        EventStream::connect(self.clone(), format!("/api/v1/stream/jobs/{}", job_id)).await
+    }
+
+    /// 订阅审批事件
+    pub async fn subscribe_approvals(&self) -> Result<EventStream> {
+        EventStream::connect(self.clone(), "/api/v1/stream/approvals".to_string()).await
+    }
+
+    /// 订阅当前用户的关注通知
+    pub async fn subscribe_notifications(&self) -> Result<EventStream> {
+        EventStream::connect(self.clone(), "/api/v1/stream/notifications".to_string()).await
+    }
+
+    // ==================== 通用请求 ====================
+
+    /// GET 请求（用于尚未封装的接口）
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        self.json(self.call::<()>(Method::GET, path, query, None)?)
+            .await
+    }
+
+    /// POST JSON 请求（用于尚未封装的接口）
+    pub async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        self.json(self.call(Method::POST, path, &[], Some(body))?)
+            .await
+    }
+
+    /// PUT JSON 请求（用于尚未封装的接口）
+    pub async fn put<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        self.json(self.call(Method::PUT, path, &[], Some(body))?)
+            .await
+    }
+
+    /// DELETE 请求（用于尚未封装的接口）
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        self.execute(&self.call::<()>(Method::DELETE, path, &[], None)?)
+            .await?;
+        Ok(())
+    }
+
+    /// 打开 SSE 事件流连接
+    pub(crate) async fn open_stream(
+        &self,
+        path: &str,
+        last_event_id: Option<&str>,
+    ) -> Result<Response> {
+        let mut call = self.call::<()>(Method::GET, path, &[], None)?;
+        call.timeout = None;
+        call.headers
+            .insert(ACCEPT, HeaderValue::from_static("text/event-stream"));
+        if let Some(value) = last_event_id.and_then(|id| HeaderValue::from_str(id).ok()) {
+            call.headers.insert("Last-Event-ID", value);
+        }
+        self.execute(&call).await
+    }
+
+    fn call<B: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<&B>,
+    ) -> Result<Call> {
+        let mut url = self
+            .inner
+            .base_url
+            .join(path.trim_start_matches('/'))
+            .map_err(|e| ClientError::Config(format!("Invalid request path {}: {}", path, e)))?;
+        if !query.is_empty() {
+            url.query_pairs_mut()
+                .extend_pairs(query.iter().map(|(k, v)| (*k, v.as_str())));
+        }
+        let body = body.map(serde_json::to_value).transpose()?;
+        Ok(Call {
+            method,
+            url,
+            body,
+            headers: HeaderMap::new(),
+            timeout: Some(self.inner.timeout),
+            authenticated: true,
+        })
+    }
+
+    async fn json<T: DeserializeOwned>(&self, call: Call) -> Result<T> {
+        let response = self.execute(&call).await?;
+        let body = response.bytes().await?;
+        // 无响应体（204）按 null 解析，适用于 () 与 Option
+        if body.is_empty() {
+            return Ok(serde_json::from_str("null")?);
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// 发送请求：附带访问令牌，令牌被拒绝时刷新后重试一次，可重试的错误按重试策略退避重试
+    async fn execute(&self, call: &Call) -> Result<Response> {
+        let mut attempt = 0;
+        let mut reauthenticated = false;
+        loop {
+            let token = if call.authenticated {
+                // 刷新与重新登录本身是匿名请求，装箱以打断 execute 与 access_token 的递归
+                Some(Box::pin(self.access_token()).await?)
+            } else {
+                None
+            };
+
+            let mut request = self
+                .inner
+                .http
+                .request(call.method.clone(), call.url.clone())
+                .headers(call.headers.clone());
+            if let Some(timeout) = call.timeout {
+                request = request.timeout(timeout);
+            }
+            if let Some(token) = &token {
+                request = request.bearer_auth(token);
+            }
+            if let Some(body) = &call.body {
+                request = request.json(body);
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => ClientError::from_response(response).await,
+                Err(e) => ClientError::Http(e),
+            };
+
+            // 访问令牌过期或被吊销
+            if let Some(token) = &token {
+                if !reauthenticated && error.api().map(|e| e.status) == Some(401) {
+                    debug!(path = call.url.path(), "Access token rejected, refreshing");
+                    reauthenticated = true;
+                    self.expire_token(token).await;
+                    continue;
+                }
+            }
+
+            if !self.inner.retry.should_retry(&call.method, &error, attempt) {
+                return Err(error);
+            }
+            let delay = error
+                .retry_after()
+                .unwrap_or_else(|| self.inner.retry.backoff(attempt));
+            warn!(
+                method = %call.method,
+                path = call.url.path(),
+                attempt = attempt + 1,
+                delay_ms = delay.as_millis() as u64,
+                error = %error,
+                "Request failed, retrying"
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// 当前有效的访问令牌（即将过期时先刷新，刷新令牌失效时用密码凭证重新登录）
+    async fn access_token(&self) -> Result<String> {
+        let mut tokens = self.inner.tokens.lock().await;
+        if let Some(state) = tokens.as_ref() {
+            if !state.needs_refresh(Instant::now()) {
+                return Ok(state.access_token.clone());
+            }
+        }
+
+        let refreshed = match tokens.as_ref() {
+            Some(state) => match self.refresh(&state.refresh_token).await {
+                Ok(refreshed) => refreshed,
+                Err(e) if e.kind() == ErrorKind::Unauthenticated && self.has_password() => {
+                    warn!(error = %e, "Token refresh rejected, logging in again");
+                    self.password_login().await?
+                }
+                Err(e) => return Err(e),
+            },
+            None => self.password_login().await?,
+        };
+        let access_token = refreshed.access_token.clone();
+        *tokens = Some(refreshed);
+        Ok(access_token)
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenState> {
+        let request = RefreshTokenRequest {
+            refresh_token: refresh_token.to_string(),
+        };
+        let call = self
+            .call(Method::POST, "/api/v1/auth/refresh", &[], Some(&request))?
+            .anonymous();
+        let pair: TokenPair = self.json(call).await?;
+        Ok(TokenState::from_pair(pair))
+    }
+
+    async fn password_login(&self) -> Result<TokenState> {
+        let Some(Credentials::Password { username, password }) = &self.inner.credentials else {
+            return Err(ClientError::Auth("Not logged in".to_string()));
+        };
+        let request = LoginRequest {
+            username: username.clone(),
+            password: password.clone(),
+            totp_code: None,
+        };
+        let call = self
+            .call(Method::POST, "/api/v1/auth/login", &[], Some(&request))?
+            .anonymous();
+        let response: LoginResponse = self.json(call).await?;
+        debug!(username = %username, "Logged in");
+        Ok(TokenState::issued(
+            response.access_token,
+            response.refresh_token,
+            response.expires_in,
+        ))
+    }
+
+    fn has_password(&self) -> bool {
+        matches!(self.inner.credentials, Some(Credentials::Password { .. }))
+    }
+
+    /// 标记访问令牌失效（已被其他请求刷新时忽略）
+    async fn expire_token(&self, access_token: &str) {
+        if let Some(state) = self.inner.tokens.lock().await.as_mut() {
+            if state.access_token == access_token {
+                state.expire();
+            }
+        }
+    }
+}
+
+impl Call {
+    /// 不附带访问令牌（登录与刷新令牌）
+    fn anonymous(mut self) -> Self {
+        self.authenticated = false;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_keeps_base_path_prefix() {
+        let client = OpsClient::builder("https://ops.example.com/ops")
+            .build()
+            .unwrap();
+        let call = client
+            .call::<()>(Method::GET, "/api/v1/jobs", &[("search", "a b&c".to_string())], None)
+            .unwrap();
+        assert_eq!(call.url.as_str(), "https://ops.example.com/ops/api/v1/jobs?search=a+b%26c");
+        assert!(call.authenticated);
+        assert_eq!(call.timeout, Some(DEFAULT_TIMEOUT));
+    }
+
+    #[test]
+    fn test_invalid_base_url() {
+        assert!(matches!(OpsClient::builder("not a url").build(), Err(ClientError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_request_without_credentials() {
+        let client = OpsClient::builder("http://127.0.0.1:9").build().unwrap();
+        let err = client.current_user().await.unwrap_err();
+        assert!(matches!(err, ClientError::Auth(_)));
+    }
+}
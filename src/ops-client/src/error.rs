@@ -0,0 +1,240 @@
+//! 客户端错误类型
+
+use common::ErrorKind;
+use reqwest::header::RETRY_AFTER;
+use reqwest::Response;
+use serde::Deserialize;
+use std::time::Duration;
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// 客户端错误
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// 请求未得到响应（连接失败、超时等）
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// 服务端返回错误响应
+    #[error("API error: {0}")]
+    Api(ApiError),
+    /// 响应体无法解析
+    #[error("Failed to decode response: {0}")]
+    Decode(#[from] serde_json::Error),
+    /// 未配置凭证或凭证已失效
+    #[error("Authentication required: {0}")]
+    Auth(String),
+    /// 客户端配置错误（如无效的服务地址）
+    #[error("Invalid client configuration: {0}")]
+    Config(String),
+}
+
+impl ClientError {
+    /// 错误分类（服务端错误按错误码，请求错误按失败阶段）
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ClientError::Http(e) if e.is_timeout() => ErrorKind::Timeout,
+            ClientError::Http(e) if e.is_decode() => ErrorKind::Internal,
+            ClientError::Http(_) => ErrorKind::Network,
+            ClientError::Api(e) => e.kind(),
+            ClientError::Decode(_) => ErrorKind::Internal,
+            ClientError::Auth(_) => ErrorKind::Unauthenticated,
+            ClientError::Config(_) => ErrorKind::Configuration,
+        }
+    }
+
+    /// 相同请求稍后重试是否可能成功
+    pub fn is_retryable(&self) -> bool {
+        self.kind().retryable()
+    }
+
+    /// 请求是否确定未到达服务端（连接阶段失败），此时非幂等请求也可安全重试
+    pub fn is_connect(&self) -> bool {
+        matches!(self, ClientError::Http(e) if e.is_connect())
+    }
+
+    /// 服务端要求的重试等待时间
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ClientError::Api(e) => e.retry_after,
+            _ => None,
+        }
+    }
+
+    /// 服务端返回的错误
+    pub fn api(&self) -> Option<&ApiError> {
+        match self {
+            ClientError::Api(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// 从错误响应构造（响应体不是标准错误格式时按状态码推断）
+    pub(crate) async fn from_response(response: Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = match response.text().await {
+            Ok(body) => body,
+            Err(e) => return ClientError::Http(e),
+        };
+        let mut error = ApiError::parse(status, &body);
+        error.retry_after = retry_after;
+        ClientError::Api(error)
+    }
+}
+
+/// 服务端错误响应
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{status} {code}: {message}")]
+pub struct ApiError {
+    /// HTTP 状态码
+    pub status: u16,
+    /// 机器可读错误码（如 `RESOURCE_NOT_FOUND`）
+    pub code: String,
+    pub message: String,
+    /// 错误详情（仅客户端错误）
+    pub detail: Option<String>,
+    /// 关联 ID（用于服务端日志排查）
+    pub correlation_id: Option<String>,
+    /// `Retry-After` 响应头
+    pub retry_after: Option<Duration>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: ErrorBodyDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorBodyDetail {
+    code: String,
+    message: String,
+    #[serde(default)]
+    detail: Option<String>,
+    #[serde(default)]
+    correlation_id: Option<String>,
+}
+
+impl ApiError {
+    /// 解析错误响应体
+    pub fn parse(status: u16, body: &str) -> Self {
+        match serde_json::from_str::<ErrorBody>(body) {
+            Ok(ErrorBody { error }) => ApiError {
+                status,
+                code: error.code,
+                message: error.message,
+                detail: error.detail,
+                correlation_id: error.correlation_id,
+                retry_after: None,
+            },
+            Err(_) => {
+                let message = body.trim();
+                ApiError {
+                    status,
+                    code: format!("HTTP_{}", status),
+                    message: if message.is_empty() {
+                        format!("HTTP {}", status)
+                    } else {
+                        message.chars().take(512).collect()
+                    },
+                    detail: None,
+                    correlation_id: None,
+                    retry_after: None,
+                }
+            }
+        }
+    }
+
+    /// 错误分类（与服务端错误码目录一致，未知错误码按状态码推断）
+    pub fn kind(&self) -> ErrorKind {
+        match self.code.as_str() {
+            "UNAUTHENTICATED" | "AUTHENTICATION_FAILED" => ErrorKind::Unauthenticated,
+            "PERMISSION_DENIED" | "TWO_FACTOR_REQUIRED" => ErrorKind::PermissionDenied,
+            "RESOURCE_NOT_FOUND" => ErrorKind::NotFound,
+            "BAD_REQUEST" | "VALIDATION_FAILED" => ErrorKind::InvalidInput,
+            "PAYLOAD_TOO_LARGE" => ErrorKind::PayloadTooLarge,
+            "PAYLOAD_LIMIT_EXCEEDED" => ErrorKind::LimitExceeded,
+            "RATE_LIMITED" => ErrorKind::RateLimited,
+            "TIMEOUT" => ErrorKind::Timeout,
+            "MAINTENANCE_MODE" => ErrorKind::Unavailable,
+            "SSH_CONNECTION_FAILED" => ErrorKind::Network,
+            "SSH_AUTHENTICATION_FAILED" => ErrorKind::HostAuth,
+            "SSH_HOST_KEY_VERIFICATION_FAILED" => ErrorKind::HostKey,
+            "SSH_EXECUTION_FAILED" => ErrorKind::Execution,
+            "DATABASE_ERROR" => ErrorKind::Storage,
+            "CONFIGURATION_ERROR" => ErrorKind::Configuration,
+            "INTERNAL_ERROR" => ErrorKind::Internal,
+            _ => match self.status {
+                401 => ErrorKind::Unauthenticated,
+                403 => ErrorKind::PermissionDenied,
+                404 => ErrorKind::NotFound,
+                408 | 504 => ErrorKind::Timeout,
+                413 => ErrorKind::PayloadTooLarge,
+                422 => ErrorKind::LimitExceeded,
+                429 => ErrorKind::RateLimited,
+                502 | 503 => ErrorKind::Unavailable,
+                400..=499 => ErrorKind::InvalidInput,
+                _ => ErrorKind::Internal,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_body() {
+        let body = serde_json::json!({
+            "error": {
+                "code": "RESOURCE_NOT_FOUND",
+                "status": 404,
+                "message": "Job not found",
+                "correlation_id": "req-1"
+            }
+        });
+        let error = ApiError::parse(404, &body.to_string());
+        assert_eq!(error.code, "RESOURCE_NOT_FOUND");
+        assert_eq!(error.message, "Job not found");
+        assert_eq!(error.correlation_id.as_deref(), Some("req-1"));
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(!ClientError::Api(error).is_retryable());
+    }
+
+    #[test]
+    fn test_parse_non_standard_body() {
+        let error = ApiError::parse(502, "Bad Gateway\n");
+        assert_eq!(error.code, "HTTP_502");
+        assert_eq!(error.message, "Bad Gateway");
+        assert_eq!(error.kind(), ErrorKind::Unavailable);
+        assert!(ClientError::Api(error).is_retryable());
+
+        let error = ApiError::parse(429, "");
+        assert_eq!(error.message, "HTTP 429");
+        assert_eq!(error.kind(), ErrorKind::RateLimited);
+    }
+
+    #[test]
+    fn test_error_code_kinds() {
+        let cases = [
+            ("MAINTENANCE_MODE", 503, ErrorKind::Unavailable, true),
+            ("DATABASE_ERROR", 500, ErrorKind::Storage, true),
+            ("VALIDATION_FAILED", 400, ErrorKind::InvalidInput, false),
+            ("PAYLOAD_LIMIT_EXCEEDED", 422, ErrorKind::LimitExceeded, false),
+            ("TWO_FACTOR_REQUIRED", 403, ErrorKind::PermissionDenied, false),
+            ("SSH_HOST_KEY_VERIFICATION_FAILED", 500, ErrorKind::HostKey, false),
+        ];
+        for (code, status, kind, retryable) in cases {
+            let body =
+                format!(r#"{{"error":{{"code":"{}","status":{},"message":"m"}}}}"#, code, status);
+            let error = ClientError::Api(ApiError::parse(status, &body));
+            assert_eq!(error.kind(), kind, "{}", code);
+            assert_eq!(error.is_retryable(), retryable, "{}", code);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! ops-client - ops-service REST API 客户端
+//!
+//! 为内部 Rust 工具提供带类型的 API 封装：请求/响应模型、访问令牌的自动刷新、
+//! SSE 事件订阅（断线按 `Last-Event-ID` 续传）以及按错误分类的重试策略。
+//!
+//! ```no_run
+//! use ops_client::{models::CreateCommandJobRequest, Credentials, OpsClient};
+//!
+//! # async fn run() -> ops_client::Result<()> {
+//! let client = OpsClient::builder("https://ops.example.com")
+//!     .credentials(Credentials::password("deployer", "secret"))
+//!     .build()?;
+//!
+//! let job = client
+//!     .create_command_job(&CreateCommandJobRequest::new("uptime", "uptime", vec![]))
+//!     .await?;
+//! let finished = client.wait_for_job(job.id).await?;
+//! println!("{} -> {:?}", finished.name, finished.status);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod auth;
+pub mod client;
+pub mod error;
+pub mod models;
+pub mod retry;
+pub mod sse;
+
+pub use auth::Credentials;
+pub use client::{OpsClient, OpsClientBuilder};
+pub use error::{ApiError, ClientError, Result};
+pub use retry::RetryPolicy;
+pub use sse::{EventStream, JobEvent, SseEvent};
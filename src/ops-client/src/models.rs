@@ -0,0 +1,406 @@
+//! API 请求与响应模型
+//!
+//! 认证请求响应与状态枚举来自 common::api，与服务端共用；其余服务端模型依赖数据库类型，
+//! 客户端只保留稳定的公开字段，未知字段忽略，新增字段缺省时取默认值。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub use common::api::{
+    JobStatus, JobType, LoginRequest, LoginResponse, RefreshTokenRequest, TaskStatus, TokenPair,
+    UserResponse, UserWithRoles,
+};
+
+/// 当前登录用户（`/auth/me`）
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurrentUser {
+    pub id: Uuid,
+    pub username: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// 模拟会话的发起人
+    #[serde(default)]
+    pub impersonator: Option<Uuid>,
+}
+
+/// 作业
+#[derive(Debug, Clone, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub job_type: JobType,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub status: JobStatus,
+    #[serde(default)]
+    pub target_hosts: Vec<Uuid>,
+    #[serde(default)]
+    pub target_groups: Vec<Uuid>,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub script_path: Option<String>,
+    #[serde(default)]
+    pub concurrent_limit: Option<i32>,
+    #[serde(default)]
+    pub timeout_secs: Option<i32>,
+    #[serde(default)]
+    pub retry_times: Option<i32>,
+    #[serde(default)]
+    pub execute_user: Option<String>,
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    #[serde(default)]
+    pub singleton_key: Option<String>,
+    #[serde(default)]
+    pub total_tasks: i32,
+    #[serde(default)]
+    pub succeeded_tasks: i32,
+    #[serde(default)]
+    pub warning_tasks: i32,
+    #[serde(default)]
+    pub failed_tasks: i32,
+    #[serde(default)]
+    pub timeout_tasks: i32,
+    #[serde(default)]
+    pub cancelled_tasks: i32,
+    #[serde(default)]
+    pub skipped_tasks: i32,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub template_id: Option<Uuid>,
+    #[serde(default)]
+    pub parent_job_id: Option<Uuid>,
+}
+
+/// 任务（作业在单个主机上的执行）
+///
+/// 无输出明细权限时服务端只返回摘要，`output_detail` 为空
+#[derive(Debug, Clone, Deserialize)]
+pub struct Task {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub host_id: Uuid,
+    pub status: TaskStatus,
+    /// 失败原因分类（snake_case，如 `network_error`）
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+    #[serde(default)]
+    pub failure_message: Option<String>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub duration_secs: Option<i64>,
+    #[serde(default)]
+    pub output_summary: Option<String>,
+    #[serde(default)]
+    pub output_detail: Option<String>,
+    #[serde(default)]
+    pub retry_count: i32,
+    #[serde(default)]
+    pub host_identifier: Option<String>,
+    #[serde(default)]
+    pub host_address: Option<String>,
+    #[serde(default)]
+    pub host_display_name: Option<String>,
+}
+
+/// 主机
+#[derive(Debug, Clone, Deserialize)]
+pub struct Host {
+    pub id: Uuid,
+    pub identifier: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub address: String,
+    pub port: i32,
+    pub group_id: Uuid,
+    pub environment: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub owner_id: Option<Uuid>,
+    pub status: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub os_type: Option<String>,
+    #[serde(default)]
+    pub os_version: Option<String>,
+    /// CPU 架构（清单采集作业收集）
+    #[serde(default)]
+    pub arch: Option<String>,
+    /// 连接方式（ssh、docker、kubernetes）
+    #[serde(default)]
+    pub connection_type: Option<String>,
+    #[serde(default)]
+    pub maintenance_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 主机列表响应
+#[derive(Debug, Clone, Deserialize)]
+pub struct HostList {
+    pub hosts: Vec<Host>,
+    pub count: i64,
+    pub total: i64,
+}
+
+/// 主机列表查询
+#[derive(Debug, Clone, Default)]
+pub struct HostListQuery {
+    pub group_id: Option<Uuid>,
+    pub environment: Option<String>,
+    pub status: Option<String>,
+    /// 按标识、名称或地址搜索
+    pub search: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl HostListQuery {
+    pub(crate) fn pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        if let Some(group_id) = self.group_id {
+            pairs.push(("group_id", group_id.to_string()));
+        }
+        if let Some(environment) = &self.environment {
+            pairs.push(("environment", environment.clone()));
+        }
+        if let Some(status) = &self.status {
+            pairs.push(("status", status.clone()));
+        }
+        if let Some(search) = &self.search {
+            pairs.push(("search", search.clone()));
+        }
+        if let Some(limit) = self.limit {
+            pairs.push(("limit", limit.to_string()));
+        }
+        if let Some(offset) = self.offset {
+            pairs.push(("offset", offset.to_string()));
+        }
+        pairs
+    }
+}
+
+/// 作业列表查询
+#[derive(Debug, Clone, Default)]
+pub struct JobListQuery {
+    pub job_type: Option<JobType>,
+    pub status: Option<JobStatus>,
+    pub created_by: Option<Uuid>,
+    /// 按名称或描述搜索
+    pub search: Option<String>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+    /// 是否包含已归档的作业
+    pub include_archived: bool,
+}
+
+impl JobListQuery {
+    pub(crate) fn pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        if let Some(job_type) = &self.job_type {
+            pairs.push(("job_type", format!("{:?}", job_type)));
+        }
+        if let Some(status) = &self.status {
+            pairs.push(("status", format!("{:?}", status)));
+        }
+        if let Some(created_by) = self.created_by {
+            pairs.push(("created_by", created_by.to_string()));
+        }
+        if let Some(search) = &self.search {
+            pairs.push(("search", search.clone()));
+        }
+        if let Some(date_from) = self.date_from {
+            pairs.push(("date_from", date_from.to_rfc3339()));
+        }
+        if let Some(date_to) = self.date_to {
+            pairs.push(("date_to", date_to.to_rfc3339()));
+        }
+        if self.include_archived {
+            pairs.push(("include_archived", "true".to_string()));
+        }
+        pairs
+    }
+}
+
+/// 创建命令作业请求
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateCommandJobRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub target_hosts: Vec<Uuid>,
+    pub target_groups: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_set_id: Option<Uuid>,
+    pub command: String,
+    pub concurrent_limit: Option<i32>,
+    pub timeout_secs: Option<i32>,
+    pub retry_times: Option<i32>,
+    pub execute_user: Option<String>,
+    /// 幂等键（相同键重复提交返回已创建的作业，使创建请求可以安全重试）
+    pub idempotency_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub singleton_key: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl CreateCommandJobRequest {
+    pub fn new(name: impl Into<String>, command: impl Into<String>, hosts: Vec<Uuid>) -> Self {
+        Self {
+            name: name.into(),
+            command: command.into(),
+            target_hosts: hosts,
+            ..Self::default()
+        }
+    }
+}
+
+/// 创建脚本作业请求
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateScriptJobRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub target_hosts: Vec<Uuid>,
+    pub target_groups: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_set_id: Option<Uuid>,
+    /// 脚本内容（与 script_sha256 二选一）
+    pub script: String,
+    /// 已上传到 blob 存储的脚本内容哈希
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_sha256: Option<String>,
+    pub script_path: Option<String>,
+    pub concurrent_limit: Option<i32>,
+    pub timeout_secs: Option<i32>,
+    pub retry_times: Option<i32>,
+    pub execute_user: Option<String>,
+    /// 幂等键（相同键重复提交返回已创建的作业，使创建请求可以安全重试）
+    pub idempotency_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub singleton_key: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl CreateScriptJobRequest {
+    pub fn new(name: impl Into<String>, script: impl Into<String>, hosts: Vec<Uuid>) -> Self {
+        Self {
+            name: name.into(),
+            script: script.into(),
+            target_hosts: hosts,
+            ..Self::default()
+        }
+    }
+}
+
+/// 取消作业请求
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CancelJobRequest {
+    pub reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_deserialize() {
+        let value = serde_json::json!({
+            "id": "6a1c9d0e-0000-4000-8000-000000000001",
+            "job_type": "Command",
+            "name": "uptime",
+            "description": null,
+            "status": "PartiallySucceeded",
+            "target_hosts": ["6a1c9d0e-0000-4000-8000-000000000002"],
+            "target_groups": [],
+            "command": "uptime",
+            "script": null,
+            "total_tasks": 2,
+            "succeeded_tasks": 1,
+            "failed_tasks": 1,
+            "created_by": "6a1c9d0e-0000-4000-8000-000000000003",
+            "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-01-01T00:01:00Z",
+            "tags": ["ops"],
+            "chain_depth": 0
+        });
+        let job: Job = serde_json::from_value(value).unwrap();
+        assert_eq!(job.job_type, JobType::Command);
+        assert_eq!(job.status, JobStatus::PartiallySucceeded);
+        assert!(job.status.is_terminal());
+        assert_eq!(job.target_hosts.len(), 1);
+        assert_eq!(job.skipped_tasks, 0);
+        assert_eq!(job.tags, vec!["ops"]);
+    }
+
+    #[test]
+    fn test_task_deserialize_summary() {
+        let value = serde_json::json!({
+            "id": "6a1c9d0e-0000-4000-8000-000000000001",
+            "job_id": "6a1c9d0e-0000-4000-8000-000000000002",
+            "host_id": "6a1c9d0e-0000-4000-8000-000000000003",
+            "status": "WaitingMaintenance",
+            "failure_reason": null,
+            "exit_code": null,
+            "output_summary": null,
+            "output_detail_truncated": false,
+            "host_identifier": "web-01",
+            "host_address": "10.0.0.1",
+            "host_display_name": null
+        });
+        let task: Task = serde_json::from_value(value).unwrap();
+        assert_eq!(task.status, TaskStatus::WaitingMaintenance);
+        assert!(!task.status.is_terminal());
+        assert_eq!(task.host_identifier.as_deref(), Some("web-01"));
+        assert!(task.output_detail.is_none());
+    }
+
+    #[test]
+    fn test_create_command_job_request_serialize() {
+        let mut request = CreateCommandJobRequest::new("uptime", "uptime", vec![]);
+        request.idempotency_key = Some("deploy-42".to_string());
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["command"], "uptime");
+        assert_eq!(value["target_groups"], serde_json::json!([]));
+        assert_eq!(value["idempotency_key"], "deploy-42");
+        assert!(value.get("tags").is_none());
+        assert!(value.get("singleton_key").is_none());
+    }
+
+    #[test]
+    fn test_job_list_query_pairs() {
+        let query = JobListQuery {
+            status: Some(JobStatus::Running),
+            search: Some("deploy".to_string()),
+            include_archived: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            query.pairs(),
+            vec![
+                ("status", "Running".to_string()),
+                ("search", "deploy".to_string()),
+                ("include_archived", "true".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! 请求重试策略
+
+use reqwest::Method;
+use std::time::Duration;
+
+use crate::error::ClientError;
+
+/// 重试策略
+///
+/// 幂等请求（GET/PUT/DELETE 等）在可重试的错误（限流、超时、维护模式、网络错误等）后
+/// 按指数退避重试；
+/// 非幂等请求只在请求未到达服务端（连接失败）或被服务端直接拒绝（限流、维护模式）时重试。
+/// 创建作业时设置 `idempotency_key` 可避免响应丢失后重复提交。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（含首次请求）
+    pub max_attempts: u32,
+    /// 首次重试前的等待时间
+    pub initial_backoff: Duration,
+    /// 单次等待时间上限
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// 第 `attempt` 次失败（从 0 开始）后的等待时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.min(16));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// 第 `attempt` 次失败（从 0 开始）后是否重试
+    pub fn should_retry(&self, method: &Method, error: &ClientError, attempt: u32) -> bool {
+        if attempt + 1 >= self.max_attempts {
+            return false;
+        }
+        if error.is_connect() {
+            return true;
+        }
+        if is_idempotent(method) {
+            return error.is_retryable();
+        }
+        // 限流与维护模式在处理请求前拒绝，非幂等请求同样可以重试
+        matches!(error.api(), Some(e) if e.status == 429 || e.status == 503)
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ApiError;
+
+    fn api_error(status: u16, code: &str) -> ClientError {
+        let body =
+            format!(r#"{{"error":{{"code":"{}","status":{},"message":"m"}}}}"#, code, status);
+        ClientError::Api(ApiError::parse(status, &body))
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(4), Duration::from_secs(1));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::default();
+        let unavailable = api_error(503, "MAINTENANCE_MODE");
+        let storage = api_error(500, "DATABASE_ERROR");
+        let not_found = api_error(404, "RESOURCE_NOT_FOUND");
+
+        assert!(policy.should_retry(&Method::GET, &storage, 0));
+        assert!(policy.should_retry(&Method::GET, &unavailable, 1));
+        assert!(!policy.should_retry(&Method::GET, &unavailable, 2));
+        assert!(!policy.should_retry(&Method::GET, &not_found, 0));
+
+        // 非幂等请求只在服务端拒绝处理时重试
+        assert!(policy.should_retry(&Method::POST, &unavailable, 0));
+        assert!(!policy.should_retry(&Method::POST, &storage, 0));
+
+        assert!(!RetryPolicy::none().should_retry(&Method::GET, &storage, 0));
+    }
+}
@@ -0,0 +1,438 @@
+//! SSE 事件订阅
+//!
+//! 服务端事件格式为 `id: <序号>`、`event: <类型>`、`data: {"type": ..., "data": {...}}`，
+//! 断线重连时携带最后收到的 `Last-Event-ID`，由服务端补发缓冲区中的后续事件。
+
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::client::OpsClient;
+use crate::error::{ClientError, Result};
+
+/// SSE 事件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// 事件序号（`id:` 字段）
+    pub id: Option<String>,
+    /// 事件类型（`event:` 字段，缺省为 `message`）
+    pub event: String,
+    pub data: String,
+}
+
+impl SseEvent {
+    /// 按 JSON 解析事件数据
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_str(&self.data)?)
+    }
+}
+
+/// SSE 增量解析器（按任意字节边界输入）
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    id: Option<String>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一段数据，返回其中完整的事件
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            let line = String::from_utf8_lossy(&line);
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        // 注释行（服务端保活）
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "id" => self.id = Some(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(SseEvent {
+            id: self.id.clone(),
+            event: event.unwrap_or_else(|| "message".to_string()),
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+/// 作业事件流中的事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobEvent {
+    /// 作业状态变更（状态为 snake_case，如 `partially_succeeded`）
+    JobStatusChanged {
+        job_id: Uuid,
+        old_status: String,
+        new_status: String,
+    },
+    /// 任务状态变更
+    TaskStatusChanged {
+        task_id: Uuid,
+        job_id: Uuid,
+        old_status: String,
+        new_status: String,
+    },
+    /// 任务增量输出
+    TaskOutputUpdate {
+        task_id: Uuid,
+        job_id: Uuid,
+        output: String,
+        is_complete: bool,
+    },
+    /// 任务输出超过阈值，之后只在任务结束时推送输出摘要
+    TaskOutputSummarized {
+        task_id: Uuid,
+        job_id: Uuid,
+        output_bytes: u64,
+        threshold_bytes: u64,
+    },
+    /// 主机维护状态变更
+    HostMaintenanceChanged {
+        host_id: Uuid,
+        job_ids: Vec<Uuid>,
+        in_maintenance: bool,
+    },
+    /// 心跳
+    Heartbeat,
+    /// 客户端未识别的事件类型
+    Other { event_type: String },
+}
+
+#[derive(Deserialize)]
+struct RawEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct JobStatusChange {
+    job_id: Uuid,
+    old_status: String,
+    new_status: String,
+}
+
+#[derive(Deserialize)]
+struct TaskStatusChange {
+    task_id: Uuid,
+    job_id: Uuid,
+    old_status: String,
+    new_status: String,
+}
+
+#[derive(Deserialize)]
+struct OutputUpdate {
+    task_id: Uuid,
+    job_id: Uuid,
+    output: String,
+    is_complete: bool,
+}
+
+#[derive(Deserialize)]
+struct OutputSummarized {
+    task_id: Uuid,
+    job_id: Uuid,
+    output_bytes: u64,
+    threshold_bytes: u64,
+}
+
+#[derive(Deserialize)]
+struct MaintenanceChange {
+    host_id: Uuid,
+    #[serde(default)]
+    job_ids: Vec<Uuid>,
+    in_maintenance: bool,
+}
+
+impl JobEvent {
+    /// 解析 SSE 事件数据
+    pub fn parse(event: &SseEvent) -> Result<Self> {
+        let raw: RawEvent = event.json()?;
+        let parsed = match raw.event_type.as_str() {
+            "job_status_changed" => {
+                let change: JobStatusChange = serde_json::from_value(raw.data)?;
+                JobEvent::JobStatusChanged {
+                    job_id: change.job_id,
+                    old_status: change.old_status,
+                    new_status: change.new_status,
+                }
+            }
+            "task_status_changed" => {
+                let change: TaskStatusChange = serde_json::from_value(raw.data)?;
+                JobEvent::TaskStatusChanged {
+                    task_id: change.task_id,
+                    job_id: change.job_id,
+                    old_status: change.old_status,
+                    new_status: change.new_status,
+                }
+            }
+            "task_output_update" => {
+                let update: OutputUpdate = serde_json::from_value(raw.data)?;
+                JobEvent::TaskOutputUpdate {
+                    task_id: update.task_id,
+                    job_id: update.job_id,
+                    output: update.output,
+                    is_complete: update.is_complete,
+                }
+            }
+            "task_output_summarized" => {
+                let summarized: OutputSummarized = serde_json::from_value(raw.data)?;
+                JobEvent::TaskOutputSummarized {
+                    task_id: summarized.task_id,
+                    job_id: summarized.job_id,
+                    output_bytes: summarized.output_bytes,
+                    threshold_bytes: summarized.threshold_bytes,
+                }
+            }
+            "host_maintenance_changed" => {
+                let change: MaintenanceChange = serde_json::from_value(raw.data)?;
+                JobEvent::HostMaintenanceChanged {
+                    host_id: change.host_id,
+                    job_ids: change.job_ids,
+                    in_maintenance: change.in_maintenance,
+                }
+            }
+            "heartbeat" => JobEvent::Heartbeat,
+            _ => JobEvent::Other {
+                event_type: raw.event_type,
+            },
+        };
+        Ok(parsed)
+    }
+}
+
+/// 自动重连的 SSE 事件流
+///
+/// 连接断开后按客户端的重试策略重连并携带 `Last-Event-ID`；连续重连失败次数超过策略上限时返回错误
+pub struct EventStream {
+    client: OpsClient,
+    path: String,
+    response: Option<Response>,
+    parser: SseParser,
+    pending: VecDeque<SseEvent>,
+    last_event_id: Option<String>,
+    failures: u32,
+}
+
+impl EventStream {
+    pub(crate) fn new(client: OpsClient, path: String) -> Self {
+        Self {
+            client,
+            path,
+            response: None,
+            parser: SseParser::new(),
+            pending: VecDeque::new(),
+            last_event_id: None,
+            failures: 0,
+        }
+    }
+
+    /// 建立连接（订阅前的事件不会推送，调用方可在连接后查询当前状态）
+    pub(crate) async fn connect(client: OpsClient, path: String) -> Result<Self> {
+        let response = client.open_stream(&path, None).await?;
+        let mut stream = Self::new(client, path);
+        stream.response = Some(response);
+        Ok(stream)
+    }
+
+    /// 最后收到的事件序号
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// 等待下一个事件
+    pub async fn next(&mut self) -> Result<SseEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                if event.id.is_some() {
+                    self.last_event_id = event.id.clone();
+                }
+                return Ok(event);
+            }
+
+            let chunk = match self.response.as_mut() {
+                Some(response) => response.chunk().await,
+                None => {
+                    match self
+                        .client
+                        .open_stream(&self.path, self.last_event_id.as_deref())
+                        .await
+                    {
+                        Ok(response) => {
+                            debug!(path = %self.path, "Event stream connected");
+                            // 丢弃上一个连接中不完整的事件
+                            self.parser = SseParser::new();
+                            self.response = Some(response);
+                        }
+                        Err(e) => self.backoff(e).await?,
+                    }
+                    continue;
+                }
+            };
+
+            match chunk {
+                Ok(Some(chunk)) => {
+                    self.failures = 0;
+                    self.pending.extend(self.parser.push(&chunk));
+                }
+                Ok(None) => {
+                    debug!(path = %self.path, "Event stream closed by server, reconnecting");
+                    self.response = None;
+                    tokio::time::sleep(self.client.retry_policy().initial_backoff).await;
+                }
+                Err(e) => {
+                    self.response = None;
+                    self.backoff(ClientError::Http(e)).await?;
+                }
+            }
+        }
+    }
+
+    /// 等待下一个作业事件
+    pub async fn next_job_event(&mut self) -> Result<JobEvent> {
+        let event = self.next().await?;
+        JobEvent::parse(&event)
+    }
+
+    async fn backoff(&mut self, error: ClientError) -> Result<()> {
+        let policy = self.client.retry_policy();
+        if !error.is_retryable() || self.failures + 1 >= policy.max_attempts {
+            return Err(error);
+        }
+        let delay = error
+            .retry_after()
+            .unwrap_or_else(|| policy.backoff(self.failures));
+        warn!(
+            path = %self.path,
+            error = %error,
+            delay_ms = delay.as_millis() as u64,
+            "Event stream disconnected, reconnecting"
+        );
+        self.failures += 1;
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parser_splits_across_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b"id: 7\nevent: job_status").is_empty());
+        assert!(parser.push(b"_changed\r\ndata: {\"a\":").is_empty());
+        let events = parser.push(b"1}\r\n\r\nid: 8\ndata: x\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    id: Some("7".to_string()),
+                    event: "job_status_changed".to_string(),
+                    data: "{\"a\":1}".to_string(),
+                },
+                SseEvent {
+                    id: Some("8".to_string()),
+                    event: "message".to_string(),
+                    data: "x".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_multiline_data_and_comments() {
+        let mut parser = SseParser::new();
+        let events = parser.push(b": keep-alive\n\ndata: line1\ndata:line2\n\n");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "line1\nline2");
+        assert_eq!(events[0].id, None);
+    }
+
+    #[test]
+    fn test_job_event_parse() {
+        let job_id = Uuid::new_v4();
+        let task_id = Uuid::new_v4();
+        let event = SseEvent {
+            id: Some("3".to_string()),
+            event: "task_status_changed".to_string(),
+            data: serde_json::json!({
+                "type": "task_status_changed",
+                "data": {
+                    "task_id": task_id,
+                    "job_id": job_id,
+                    "old_status": "running",
+                    "new_status": "succeeded",
+                }
+            })
+            .to_string(),
+        };
+        assert_eq!(
+            JobEvent::parse(&event).unwrap(),
+            JobEvent::TaskStatusChanged {
+                task_id,
+                job_id,
+                old_status: "running".to_string(),
+                new_status: "succeeded".to_string(),
+            }
+        );
+
+        let other = SseEvent {
+            data: r#"{"type":"approval_reminder","data":{}}"#.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            JobEvent::parse(&other).unwrap(),
+            JobEvent::Other {
+                event_type: "approval_reminder".to_string()
+            }
+        );
+        let heartbeat = SseEvent {
+            data: r#"{"type":"heartbeat"}"#.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(JobEvent::parse(&heartbeat).unwrap(), JobEvent::Heartbeat);
+    }
+}
@@ -11,9 +11,13 @@ use tokio::sync::watch;
 use tracing::{debug, info, warn};
 
 use crate::config::RunnerConfig;
+use crate::image_cache;
 use crate::messages::{
-    RunnerDockerConfig, RunnerHeartbeatMessage, RunnerRegistrationMessage, RunnerStatus, SystemInfo,
+    KnownHostsSync, RunnerCommandAck, RunnerControlAction, RunnerDockerConfig,
+    RunnerHeartbeatMessage, RunnerRegistrationMessage, RunnerStatus, SystemInfo,
 };
+use crate::worker::RunningBuilds;
+use common::ssh::{known_hosts_digest, render_known_hosts};
 
 /// 控制面 API 客户端
 pub struct ControlPlaneClient {
@@ -22,10 +26,18 @@ pub struct ControlPlaneClient {
     runner_id: Option<String>,
     /// 从控制面接收的 Docker 配置
     docker_config: Arc<TokioMutex<Option<RunnerDockerConfig>>>,
+    /// 已应用的 Docker 配置版本（随心跳回报，用于控制面跟踪配置灰度发布）
+    applied_config_version: Arc<TokioMutex<Option<i64>>>,
     /// 当前正在执行的作业数（由 Worker 更新）
     current_jobs: Arc<AtomicUsize>,
+    /// 正在执行的构建任务（与 Worker 共享，随心跳上报）
+    running_builds: Arc<RunningBuilds>,
     /// 配置变更通知通道 (心跳 -> executor)
     config_update_tx: watch::Sender<Option<RunnerDockerConfig>>,
+    /// 已安装插件的能力标签（`plugin/<name>`），注册时随能力一并上报
+    plugin_capabilities: Vec<String>,
+    /// 原生执行沙箱的能力标签（`sandbox/<feature>`）
+    sandbox_capabilities: Vec<String>,
 }
 
 impl ControlPlaneClient {
@@ -43,14 +55,38 @@ impl ControlPlaneClient {
             config,
             runner_id: None,
             docker_config: Arc::new(TokioMutex::new(None)),
+            applied_config_version: Arc::new(TokioMutex::new(None)),
             current_jobs: Arc::new(AtomicUsize::new(0)),
+            running_builds: Arc::new(RunningBuilds::default()),
             config_update_tx,
+            plugin_capabilities: Vec::new(),
+            sandbox_capabilities: Vec::new(),
         }
     }
 
-    /// 设置当前作业数（由 Worker 调用）
-    pub fn set_current_jobs(&self, count: usize) {
-        self.current_jobs.store(count, Ordering::Relaxed);
+    /// 设置插件能力标签
+    pub fn set_plugin_capabilities(&mut self, capabilities: Vec<String>) {
+        self.plugin_capabilities = capabilities;
+    }
+
+    /// 设置原生执行沙箱能力标签
+    pub fn set_sandbox_capabilities(&mut self, capabilities: Vec<String>) {
+        self.sandbox_capabilities = capabilities;
+    }
+
+    /// 上报给控制面的能力：配置的能力标签 + 插件能力 + 沙箱能力
+    fn advertised_capabilities(&self) -> Vec<String> {
+        let mut capabilities = self.config.runner.capabilities.clone();
+        for capability in self
+            .plugin_capabilities
+            .iter()
+            .chain(&self.sandbox_capabilities)
+        {
+            if !capabilities.contains(capability) {
+                capabilities.push(capability.clone());
+            }
+        }
+        capabilities
     }
 
     /// 获取配置变更通知接收端
@@ -63,6 +99,11 @@ impl ControlPlaneClient {
         &mut self.current_jobs
     }
 
+    /// 设置与 Worker 共享的运行中任务登记表
+    pub fn set_running_builds(&mut self, running_builds: Arc<RunningBuilds>) {
+        self.running_builds = running_builds;
+    }
+
     /// 获取 Docker 配置（从控制面接收）
     pub async fn get_docker_config(&self) -> Option<RunnerDockerConfig> {
         self.docker_config.lock().await.clone()
@@ -75,6 +116,79 @@ impl ControlPlaneClient {
         let _ = self.config_update_tx.send(config);
     }
 
+    /// 确保持有控制面凭据
+    ///
+    /// 优先使用配置的 API Key，其次读取凭据文件；都没有时使用一次性注册令牌换取
+    /// Runner 专属 API Key，并写入凭据文件供重启后复用
+    pub async fn ensure_credentials(&mut self) -> Result<()> {
+        if !self.config.control_plane.api_key.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(path) = &self.config.control_plane.credentials_file {
+            match tokio::fs::read_to_string(path).await {
+                Ok(key) if !key.trim().is_empty() => {
+                    info!("Loaded runner credentials from {}", path);
+                    self.config.control_plane.api_key = key.trim().to_string();
+                    return Ok(());
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).context("Failed to read runner credentials file"),
+            }
+        }
+
+        let Some(token) = self.config.control_plane.enrollment_token.clone() else {
+            anyhow::bail!(
+                "No runner credentials: set RUNNER_API_KEY, RUNNER_CREDENTIALS_FILE or RUNNER_ENROLLMENT_TOKEN"
+            );
+        };
+
+        info!("Enrolling runner with one-time enrollment token");
+        let response = self
+            .client
+            .post(format!("{}/api/v1/runners/enroll", self.config.control_plane.api_url))
+            .json(&serde_json::json!({
+                "enrollment_token": token,
+                "name": self.config.runner.name,
+                "capabilities": self.advertised_capabilities(),
+            }))
+            .send()
+            .await
+            .context("Failed to send enrollment request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Enrollment failed with status {}: {}", status, body);
+        }
+
+        #[derive(serde::Deserialize)]
+        struct EnrollResponse {
+            runner_id: String,
+            api_key: String,
+        }
+
+        let resp: EnrollResponse = response
+            .json()
+            .await
+            .context("Failed to parse enrollment response")?;
+
+        match &self.config.control_plane.credentials_file {
+            Some(path) => {
+                save_credentials(path, &resp.api_key).await?;
+                info!("Saved runner credentials to {}", path);
+            }
+            None => warn!(
+                "RUNNER_CREDENTIALS_FILE not set, enrolled credentials will be lost on restart"
+            ),
+        }
+
+        info!("Runner enrolled with ID: {}", resp.runner_id);
+        self.config.control_plane.api_key = resp.api_key;
+        Ok(())
+    }
+
     /// 注册 Runner
     pub async fn register(&mut self) -> Result<String> {
         info!("Registering runner with control plane");
@@ -84,7 +198,7 @@ impl ControlPlaneClient {
 
         let msg = RunnerRegistrationMessage {
             name: self.config.runner.name.clone(),
-            capabilities: self.config.runner.capabilities.clone(),
+            capabilities: self.advertised_capabilities(),
             docker_supported: self.config.runner.docker_supported,
             max_concurrent_jobs: self.config.runner.max_concurrent_jobs,
             outbound_allowlist: self.config.runner.outbound_allowlist.clone(),
@@ -145,10 +259,10 @@ impl ControlPlaneClient {
         Ok(resp.runner_id)
     }
 
-    /// 发送心跳
+    /// 发送心跳（附带控制指令执行结果）
     ///
     /// 返回是否收到了 Docker 配置更新
-    pub async fn send_heartbeat(&self) -> Result<bool> {
+    pub async fn send_heartbeat(&self, command_acks: Vec<RunnerCommandAck>) -> Result<bool> {
         let mut sys = System::new_all();
         sys.refresh_all();
 
@@ -184,6 +298,18 @@ impl ControlPlaneClient {
             last_error: None,
             system: system_info,
             timestamp: Utc::now(),
+            known_hosts_digest: match &self.config.execution.known_hosts_file {
+                Some(path) => Some(local_known_hosts_digest(path).await),
+                None => None,
+            },
+            image_cache: self
+                .config
+                .runner
+                .docker_supported
+                .then(image_cache::image_cache_stats),
+            applied_config_version: *self.applied_config_version.lock().await,
+            command_acks,
+            running_tasks: self.running_builds.snapshot(),
         };
 
         let response = self
@@ -208,6 +334,10 @@ impl ControlPlaneClient {
         struct HeartbeatResponse {
             #[serde(default)]
             docker: Option<RunnerDockerConfig>,
+            #[serde(default)]
+            known_hosts: Option<KnownHostsSync>,
+            #[serde(default)]
+            config_version: Option<i64>,
         }
 
         let mut config_updated = false;
@@ -219,14 +349,52 @@ impl ControlPlaneClient {
                     docker_cfg.enabled
                 );
                 self.set_docker_config(docker_cfg).await;
+                if resp.config_version.is_some() {
+                    *self.applied_config_version.lock().await = resp.config_version;
+                }
                 config_updated = true;
             }
+
+            // 同步集中管理的 known_hosts
+            if let (Some(sync), Some(path)) =
+                (resp.known_hosts, &self.config.execution.known_hosts_file)
+            {
+                match write_known_hosts(path, &sync).await {
+                    Ok(()) => {
+                        info!("Synced {} known_hosts entries to {}", sync.entries.len(), path)
+                    }
+                    Err(e) => warn!("Failed to sync known_hosts to {}: {:#}", path, e),
+                }
+            }
         }
 
         debug!("Heartbeat sent successfully");
         Ok(config_updated)
     }
 
+    /// 执行需要控制面客户端的控制指令，返回执行结果说明
+    pub async fn execute_control_command(&mut self, action: RunnerControlAction) -> Result<String> {
+        match action {
+            RunnerControlAction::Reregister => {
+                let runner_id = self.register().await?;
+                Ok(format!("Re-registered with ID {}", runner_id))
+            }
+            RunnerControlAction::RefreshConfig => {
+                if !self.send_heartbeat(Vec::new()).await? {
+                    return Ok("No Docker configuration received from control plane".to_string());
+                }
+                let version = *self.applied_config_version.lock().await;
+                Ok(match version {
+                    Some(version) => {
+                        format!("Docker configuration refreshed (version {})", version)
+                    }
+                    None => "Docker configuration refreshed".to_string(),
+                })
+            }
+            other => anyhow::bail!("Unsupported control action: {}", other.as_str()),
+        }
+    }
+
     /// 获取 Runner ID
     #[allow(dead_code)]
     pub fn runner_id(&self) -> Option<&str> {
@@ -234,6 +402,28 @@ impl ControlPlaneClient {
     }
 }
 
+/// 保存 Runner 凭据（仅当前用户可读写）
+async fn save_credentials(path: &str, api_key: &str) -> Result<()> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .context("Failed to create credentials directory")?;
+    }
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(path)
+        .await
+        .context("Failed to open runner credentials file")?;
+    tokio::io::AsyncWriteExt::write_all(&mut file, api_key.as_bytes())
+        .await
+        .context("Failed to write runner credentials file")?;
+    Ok(())
+}
+
 /// 获取本地 IP 地址
 fn get_local_ips() -> Result<Vec<String>> {
     let mut ips = Vec::new();
@@ -253,6 +443,35 @@ fn get_local_ips() -> Result<Vec<String>> {
     Ok(ips)
 }
 
+/// 计算本地 known_hosts 文件摘要（文件不存在时视为空）
+async fn local_known_hosts_digest(path: &str) -> String {
+    let content = tokio::fs::read_to_string(path).await.unwrap_or_default();
+    known_hosts_digest(&content)
+}
+
+/// 写入控制面下发的 known_hosts（先写临时文件再重命名，避免步骤读到半个文件）
+async fn write_known_hosts(path: &str, sync: &KnownHostsSync) -> Result<()> {
+    let content = render_known_hosts(&sync.entries);
+    if known_hosts_digest(&content) != sync.digest {
+        anyhow::bail!("known_hosts digest mismatch");
+    }
+
+    let path = std::path::Path::new(path);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .context("Failed to create known_hosts dir")?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    tokio::fs::write(&tmp_path, content)
+        .await
+        .context("Failed to write known_hosts")?;
+    tokio::fs::rename(&tmp_path, path)
+        .await
+        .context("Failed to replace known_hosts")?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,6 +491,8 @@ mod tests {
                 api_url: "http://localhost:3000".to_string(),
                 api_key: "test-key".to_string(),
                 heartbeat_interval_secs: 30,
+                enrollment_token: None,
+                credentials_file: None,
             },
             message_queue: crate::config::MessageQueueConfig {
                 amqp_url: "amqp://localhost:5672".to_string(),
@@ -287,6 +508,13 @@ mod tests {
                 cleanup_workspace: true,
                 cache_dir: None,
                 docker: None,
+                log_ansi_mode: Default::default(),
+                known_hosts_file: None,
+                repo_cache: None,
+                state_dir: "/tmp/test-state".to_string(),
+                plugins: None,
+                docker_cache: Default::default(),
+                native_sandbox: None,
             },
         };
 
@@ -310,6 +538,8 @@ mod tests {
                 api_url: "https://api.example.com".to_string(),
                 api_key: "secret-key".to_string(),
                 heartbeat_interval_secs: 60,
+                enrollment_token: None,
+                credentials_file: None,
             },
             message_queue: crate::config::MessageQueueConfig {
                 amqp_url: "amqp://mq.example.com".to_string(),
@@ -325,6 +555,13 @@ mod tests {
                 cleanup_workspace: false,
                 cache_dir: Some("/cache".to_string()),
                 docker: None,
+                log_ansi_mode: Default::default(),
+                known_hosts_file: None,
+                repo_cache: None,
+                state_dir: "/tmp/test-state".to_string(),
+                plugins: None,
+                docker_cache: Default::default(),
+                native_sandbox: None,
             },
         };
 
@@ -333,6 +570,11 @@ mod tests {
         assert_eq!(client.config.runner.capabilities, vec!["rust"]);
         assert_eq!(client.config.control_plane.api_url, "https://api.example.com");
         assert_eq!(client.config.control_plane.heartbeat_interval_secs, 60);
+
+        // 插件能力追加到配置的能力之后上报
+        let mut client = client;
+        client.set_plugin_capabilities(vec!["plugin/codesign".to_string(), "rust".to_string()]);
+        assert_eq!(client.advertised_capabilities(), vec!["rust", "plugin/codesign"]);
     }
 
     #[test]
@@ -452,6 +694,11 @@ mod tests {
                 available_disk_gb: 50.0,
             },
             timestamp: chrono::Utc::now(),
+            known_hosts_digest: None,
+            image_cache: None,
+            applied_config_version: None,
+            command_acks: Vec::new(),
+            running_tasks: Vec::new(),
         };
 
         assert_eq!(msg.name, "test-runner");
@@ -483,6 +730,11 @@ mod tests {
                 available_disk_gb: 0.0,
             },
             timestamp: chrono::Utc::now(),
+            known_hosts_digest: None,
+            image_cache: None,
+            applied_config_version: None,
+            command_acks: Vec::new(),
+            running_tasks: Vec::new(),
         };
 
         assert_eq!(msg.status, RunnerStatus::Offline);
@@ -536,6 +788,8 @@ mod tests {
                 api_url: "http://localhost:3000".to_string(),
                 api_key: "test-key".to_string(),
                 heartbeat_interval_secs: 30,
+                enrollment_token: None,
+                credentials_file: None,
             },
             message_queue: crate::config::MessageQueueConfig {
                 amqp_url: "amqp://localhost:5672".to_string(),
@@ -551,6 +805,13 @@ mod tests {
                 cleanup_workspace: true,
                 cache_dir: None,
                 docker: None,
+                log_ansi_mode: Default::default(),
+                known_hosts_file: None,
+                repo_cache: None,
+                state_dir: "/tmp/test-state".to_string(),
+                plugins: None,
+                docker_cache: Default::default(),
+                native_sandbox: None,
             },
         };
 
@@ -558,4 +819,74 @@ mod tests {
         // 初始状态下 runner_id 应该为 None
         assert!(client.runner_id().is_none());
     }
+
+    #[tokio::test]
+    async fn test_write_known_hosts_roundtrip() {
+        use common::ssh::KnownHostEntry;
+
+        let dir =
+            std::env::temp_dir().join(format!("ops-runner-known-hosts-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("ssh/known_hosts");
+        let path = path.to_str().unwrap();
+
+        let entries = vec![KnownHostEntry {
+            host: "10.0.0.5".to_string(),
+            port: 2222,
+            key_type: "ssh-ed25519".to_string(),
+            public_key: "AAAAC3NzaC1lZDI1NTE5".to_string(),
+        }];
+        let digest = known_hosts_digest(&render_known_hosts(&entries));
+        assert_ne!(local_known_hosts_digest(path).await, digest);
+
+        let sync = KnownHostsSync {
+            digest: digest.clone(),
+            entries,
+        };
+        write_known_hosts(path, &sync).await.unwrap();
+        assert_eq!(local_known_hosts_digest(path).await, digest);
+
+        // 摘要与内容不一致时拒绝写入
+        let tampered = KnownHostsSync {
+            digest: "bad".to_string(),
+            entries: sync.entries,
+        };
+        assert!(write_known_hosts(path, &tampered).await.is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_credentials_from_file() {
+        let dir =
+            std::env::temp_dir().join(format!("ops-runner-credentials-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("runner/credentials");
+        let path = path.to_str().unwrap().to_string();
+
+        let mut config: RunnerConfig = serde_json::from_value(serde_json::json!({
+            "runner": { "name": "enrolled-runner", "environment": "test" },
+            "control_plane": { "api_url": "http://localhost:3000" },
+            "message_queue": { "amqp_url": "amqp://localhost:5672" },
+            "execution": {}
+        }))
+        .unwrap();
+
+        // 没有任何凭据来源时直接失败
+        let mut client = ControlPlaneClient::new(config.clone());
+        assert!(client.ensure_credentials().await.is_err());
+
+        config.control_plane.credentials_file = Some(path.clone());
+        save_credentials(&path, "ops_ak_saved").await.unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let mut client = ControlPlaneClient::new(config);
+        client.ensure_credentials().await.unwrap();
+        assert_eq!(client.config.control_plane.api_key, "ops_ak_saved");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
 
+use crate::messages::RoutingKeys;
+use common::terminal::AnsiMode;
+
 /// Runner 配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunnerConfig {
@@ -62,8 +65,19 @@ pub struct ControlPlaneConfig {
     pub api_url: String,
 
     /// Runner API Key（用于注册和状态更新）
+    ///
+    /// 未配置时从 `credentials_file` 读取，或使用 `enrollment_token` 向控制面换取
+    #[serde(default)]
     pub api_key: String,
 
+    /// 一次性注册令牌（由管理员签发，首次启动时换取 Runner 专属 API Key）
+    #[serde(default)]
+    pub enrollment_token: Option<String>,
+
+    /// 保存换取到的 API Key 的文件，重启后直接复用
+    #[serde(default)]
+    pub credentials_file: Option<String>,
+
     /// 心跳间隔（秒）
     #[serde(default = "default_heartbeat_interval")]
     pub heartbeat_interval_secs: u64,
@@ -138,6 +152,243 @@ pub struct ExecutionConfig {
     /// Docker 配置
     #[serde(default)]
     pub docker: Option<DockerConfig>,
+
+    /// 日志中 ANSI 转义序列的处理方式（strip / html）
+    #[serde(default)]
+    pub log_ansi_mode: AnsiMode,
+
+    /// 控制面集中管理的 known_hosts 写入路径
+    ///
+    /// 配置后 Runner 通过心跳同步该文件，原生模式步骤中的 git/ssh 使用它严格校验主机密钥
+    #[serde(default)]
+    pub known_hosts_file: Option<String>,
+
+    /// Git 仓库镜像缓存（未配置时每次构建直接克隆）
+    #[serde(default)]
+    pub repo_cache: Option<RepoCacheConfig>,
+
+    /// 运行状态目录（进行中任务的日志，用于崩溃后上报中断的任务）
+    #[serde(default = "default_state_dir")]
+    pub state_dir: String,
+
+    /// 步骤插件（未配置时不支持 `plugin/<name>` 步骤）
+    #[serde(default)]
+    pub plugins: Option<PluginConfig>,
+
+    /// Docker 镜像与构建缓存（Runner 本地配置，不随控制面下发的 Docker 配置变化）
+    #[serde(default)]
+    pub docker_cache: DockerCacheConfig,
+
+    /// 原生执行沙箱（未配置时不使用 Docker 的步骤以 Runner 用户身份直接执行）
+    #[serde(default)]
+    pub native_sandbox: Option<NativeSandboxConfig>,
+}
+
+/// 步骤插件配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfig {
+    /// 插件目录，目录中的每个可执行文件是一个插件，文件名即插件名
+    pub dir: String,
+
+    /// 插件步骤默认超时（秒）；步骤与插件声明的超时优先，均未指定时使用步骤超时
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    /// 沙箱选项
+    #[serde(default)]
+    pub sandbox: PluginSandboxConfig,
+}
+
+/// 插件沙箱配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginSandboxConfig {
+    /// 不继承 Runner 进程的环境变量，只传入 `env_allowlist` 中的变量和构建环境变量
+    #[serde(default = "default_true")]
+    pub clear_env: bool,
+
+    /// `clear_env` 时仍透传给插件的 Runner 环境变量
+    #[serde(default = "default_plugin_env_allowlist")]
+    pub env_allowlist: Vec<String>,
+
+    /// 在独立的网络命名空间中运行（`unshare --net --map-root-user`，需要内核允许非特权用户命名空间）
+    #[serde(default)]
+    pub isolate_network: bool,
+
+    /// 启动插件时前置的包装命令及参数（例如 bwrap、firejail）
+    #[serde(default)]
+    pub wrapper: Vec<String>,
+}
+
+impl Default for PluginSandboxConfig {
+    fn default() -> Self {
+        Self {
+            clear_env: true,
+            env_allowlist: default_plugin_env_allowlist(),
+            isolate_network: false,
+            wrapper: Vec::new(),
+        }
+    }
+}
+
+/// 原生执行沙箱配置
+///
+/// 依赖 util-linux 的 setpriv、prlimit 与 unshare，Runner 启动时检查主机是否支持
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NativeSandboxConfig {
+    /// 以该用户身份执行步骤（需要 Runner 以 root 运行），执行前工作空间归属该用户
+    #[serde(default)]
+    pub user: Option<String>,
+
+    /// 委派给 Runner 的 cgroup v2 目录，每个步骤在其下的子 cgroup 中执行
+    ///
+    /// 该目录中不能有进程（Runner 自身须位于其他 cgroup）；未配置时内存与进程数上限
+    /// 通过 rlimit 施加，不支持 CPU 限制
+    #[serde(default)]
+    pub cgroup_root: Option<String>,
+
+    /// CPU 上限（核数，如 1.5；需要 cgroup_root）
+    #[serde(default)]
+    pub cpu_limit: Option<f64>,
+
+    /// 内存上限（MB；无 cgroup 时为虚拟内存上限）
+    #[serde(default)]
+    pub memory_limit_mb: Option<u64>,
+
+    /// 进程数上限（无 cgroup 时为步骤用户的进程数上限）
+    #[serde(default)]
+    pub pids_limit: Option<u64>,
+
+    /// 打开文件数上限
+    #[serde(default)]
+    pub open_files_limit: Option<u64>,
+
+    /// 在独立的网络命名空间中执行（只有回环接口）
+    ///
+    /// 需要按目标放行出站访问时，可在主机防火墙中按步骤用户（`-m owner --uid-owner`）匹配
+    #[serde(default)]
+    pub isolate_network: bool,
+
+    /// 传给步骤的 Runner 环境变量，其余变量（如 Runner 凭据）不传给步骤
+    #[serde(default = "default_plugin_env_allowlist")]
+    pub env_allowlist: Vec<String>,
+}
+
+impl NativeSandboxConfig {
+    /// 从环境变量加载（未设置任何沙箱变量时不启用）
+    fn from_env() -> Option<Self> {
+        let config = Self {
+            user: std::env::var("RUNNER_SANDBOX_USER").ok(),
+            cgroup_root: std::env::var("RUNNER_SANDBOX_CGROUP_ROOT").ok(),
+            cpu_limit: parse_env("RUNNER_SANDBOX_CPU_LIMIT"),
+            memory_limit_mb: parse_env("RUNNER_SANDBOX_MEMORY_LIMIT_MB"),
+            pids_limit: parse_env("RUNNER_SANDBOX_PIDS_LIMIT"),
+            open_files_limit: parse_env("RUNNER_SANDBOX_OPEN_FILES_LIMIT"),
+            isolate_network: parse_env("RUNNER_SANDBOX_ISOLATE_NETWORK").unwrap_or(false),
+            env_allowlist: std::env::var("RUNNER_SANDBOX_ENV_ALLOWLIST")
+                .ok()
+                .map(|v| {
+                    v.split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_else(default_plugin_env_allowlist),
+        };
+        let enabled = config.user.is_some()
+            || config.cgroup_root.is_some()
+            || config.cpu_limit.is_some()
+            || config.memory_limit_mb.is_some()
+            || config.pids_limit.is_some()
+            || config.open_files_limit.is_some()
+            || config.isolate_network;
+        enabled.then_some(config)
+    }
+}
+
+fn parse_env<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok().and_then(|v| v.parse().ok())
+}
+
+/// Git 仓库镜像缓存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoCacheConfig {
+    /// 镜像存放目录
+    pub dir: String,
+
+    /// 缓存总大小上限（MB），超出后按最近使用时间淘汰镜像
+    #[serde(default = "default_repo_cache_max_size_mb")]
+    pub max_size_mb: u64,
+
+    /// 等待同一仓库镜像锁的最长时间（秒）；锁文件存在超过该时间视为残留锁
+    #[serde(default = "default_repo_cache_lock_timeout")]
+    pub lock_timeout_secs: u64,
+}
+
+/// Docker 镜像拉取策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImagePullPolicy {
+    /// 每个步骤执行前都拉取（本地已有的镜像层不会重复下载）
+    #[default]
+    Always,
+    /// 本地没有镜像时才拉取
+    IfNotPresent,
+    /// 从不拉取，只使用本地镜像
+    Never,
+}
+
+impl std::str::FromStr for ImagePullPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "always" => Ok(Self::Always),
+            "if_not_present" => Ok(Self::IfNotPresent),
+            "never" => Ok(Self::Never),
+            other => Err(format!("unknown image pull policy: {}", other)),
+        }
+    }
+}
+
+/// Docker 镜像与构建缓存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockerCacheConfig {
+    /// 镜像拉取策略
+    #[serde(default)]
+    pub pull_policy: ImagePullPolicy,
+
+    /// 后台预拉取的镜像
+    #[serde(default)]
+    pub pre_pull: Vec<String>,
+
+    /// 预拉取刷新间隔（秒，0 表示只在启动时拉取一次）
+    #[serde(default = "default_prepull_interval")]
+    pub prepull_interval_secs: u64,
+
+    /// Docker Hub 镜像加速地址（pull-through 缓存，如 `mirror.example.com:5000`）
+    #[serde(default)]
+    pub registry_mirror: Option<String>,
+
+    /// 在任务之间复用的命名缓存卷（未设置时不挂载）
+    #[serde(default)]
+    pub cache_volume: Option<String>,
+
+    /// 缓存卷在容器中的挂载路径，同时设置为容器的 `XDG_CACHE_HOME`
+    #[serde(default = "default_cache_mount_path")]
+    pub cache_mount_path: String,
+}
+
+impl Default for DockerCacheConfig {
+    fn default() -> Self {
+        Self {
+            pull_policy: ImagePullPolicy::default(),
+            pre_pull: Vec::new(),
+            prepull_interval_secs: default_prepull_interval(),
+            registry_mirror: None,
+            cache_volume: None,
+            cache_mount_path: default_cache_mount_path(),
+        }
+    }
 }
 
 /// Docker 容器执行配置
@@ -288,6 +539,10 @@ fn default_workspace_dir() -> String {
     "/tmp/ops-runner/workspace".to_string()
 }
 
+fn default_state_dir() -> String {
+    "/tmp/ops-runner/state".to_string()
+}
+
 fn default_task_timeout() -> u64 {
     3600 // 1小时
 }
@@ -304,6 +559,29 @@ fn default_docker_timeout() -> u64 {
     1800 // 30分钟
 }
 
+fn default_repo_cache_max_size_mb() -> u64 {
+    20 * 1024 // 20GB
+}
+
+fn default_repo_cache_lock_timeout() -> u64 {
+    1800 // 30分钟
+}
+
+fn default_prepull_interval() -> u64 {
+    3600
+}
+
+fn default_cache_mount_path() -> String {
+    "/cache".to_string()
+}
+
+fn default_plugin_env_allowlist() -> Vec<String> {
+    ["PATH", "HOME", "LANG", "TMPDIR"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
 impl RunnerConfig {
     /// 从环境变量加载配置
     pub fn from_env() -> Result<Self> {
@@ -336,7 +614,9 @@ impl RunnerConfig {
             control_plane: ControlPlaneConfig {
                 api_url: std::env::var("CONTROL_PLANE_API_URL")
                     .context("CONTROL_PLANE_API_URL must be set")?,
-                api_key: std::env::var("RUNNER_API_KEY").context("RUNNER_API_KEY must be set")?,
+                api_key: std::env::var("RUNNER_API_KEY").unwrap_or_default(),
+                enrollment_token: std::env::var("RUNNER_ENROLLMENT_TOKEN").ok(),
+                credentials_file: std::env::var("RUNNER_CREDENTIALS_FILE").ok(),
                 heartbeat_interval_secs: std::env::var("RUNNER_HEARTBEAT_INTERVAL_SECS")
                     .ok()
                     .and_then(|v| v.parse().ok())
@@ -377,6 +657,81 @@ impl RunnerConfig {
                     .unwrap_or(true),
                 cache_dir: std::env::var("RUNNER_CACHE_DIR").ok(),
                 docker: None,
+                log_ansi_mode: std::env::var("RUNNER_LOG_ANSI_MODE")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_default(),
+                known_hosts_file: std::env::var("RUNNER_KNOWN_HOSTS_FILE").ok(),
+                repo_cache: std::env::var("RUNNER_REPO_CACHE_DIR").ok().map(|dir| {
+                    RepoCacheConfig {
+                        dir,
+                        max_size_mb: std::env::var("RUNNER_REPO_CACHE_MAX_SIZE_MB")
+                            .ok()
+                            .and_then(|v| v.parse().ok())
+                            .unwrap_or_else(default_repo_cache_max_size_mb),
+                        lock_timeout_secs: std::env::var("RUNNER_REPO_CACHE_LOCK_TIMEOUT_SECS")
+                            .ok()
+                            .and_then(|v| v.parse().ok())
+                            .unwrap_or_else(default_repo_cache_lock_timeout),
+                    }
+                }),
+                state_dir: std::env::var("RUNNER_STATE_DIR")
+                    .ok()
+                    .unwrap_or_else(default_state_dir),
+                plugins: std::env::var("RUNNER_PLUGINS_DIR")
+                    .ok()
+                    .map(|dir| PluginConfig {
+                        dir,
+                        timeout_secs: std::env::var("RUNNER_PLUGIN_TIMEOUT_SECS")
+                            .ok()
+                            .and_then(|v| v.parse().ok()),
+                        sandbox: PluginSandboxConfig {
+                            clear_env: std::env::var("RUNNER_PLUGIN_CLEAR_ENV")
+                                .ok()
+                                .and_then(|v| v.parse().ok())
+                                .unwrap_or(true),
+                            env_allowlist: std::env::var("RUNNER_PLUGIN_ENV_ALLOWLIST")
+                                .ok()
+                                .map(|v| {
+                                    v.split(',')
+                                        .map(|s| s.trim().to_string())
+                                        .filter(|s| !s.is_empty())
+                                        .collect()
+                                })
+                                .unwrap_or_else(default_plugin_env_allowlist),
+                            isolate_network: std::env::var("RUNNER_PLUGIN_ISOLATE_NETWORK")
+                                .ok()
+                                .and_then(|v| v.parse().ok())
+                                .unwrap_or(false),
+                            wrapper: std::env::var("RUNNER_PLUGIN_WRAPPER")
+                                .ok()
+                                .map(|v| v.split_whitespace().map(|s| s.to_string()).collect())
+                                .unwrap_or_default(),
+                        },
+                    }),
+                docker_cache: DockerCacheConfig {
+                    pull_policy: std::env::var("RUNNER_DOCKER_PULL_POLICY")
+                        .ok()
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or_default(),
+                    pre_pull: std::env::var("RUNNER_DOCKER_PREPULL_IMAGES")
+                        .ok()
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect(),
+                    prepull_interval_secs: std::env::var("RUNNER_DOCKER_PREPULL_INTERVAL_SECS")
+                        .ok()
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or_else(default_prepull_interval),
+                    registry_mirror: std::env::var("RUNNER_DOCKER_REGISTRY_MIRROR").ok(),
+                    cache_volume: std::env::var("RUNNER_DOCKER_CACHE_VOLUME").ok(),
+                    cache_mount_path: std::env::var("RUNNER_DOCKER_CACHE_MOUNT_PATH")
+                        .ok()
+                        .unwrap_or_else(default_cache_mount_path),
+                },
+                native_sandbox: NativeSandboxConfig::from_env(),
             },
         })
     }
@@ -433,6 +788,15 @@ impl RunnerConfig {
         )
     }
 
+    /// 生成控制队列名称（接收取消等控制消息）
+    pub fn control_queue_name(&self) -> String {
+        format!(
+            "{}.{}.control",
+            self.message_queue.queue_prefix,
+            self.runner.name.replace('-', "_")
+        )
+    }
+
     /// 生成广播模式 routing key（向后兼容）
     pub fn routing_key(&self, capability: &str) -> String {
         format!("build.{}", capability)
@@ -442,6 +806,11 @@ impl RunnerConfig {
     pub fn routing_key_for_runner(&self, capability: &str) -> String {
         format!("build.{}.{}", capability, self.runner.name)
     }
+
+    /// 生成控制指令 routing key（仅本 Runner 接收）
+    pub fn control_routing_key(&self) -> String {
+        format!("{}.{}", RoutingKeys::RUNNER_CONTROL, self.runner.name)
+    }
 }
 
 #[cfg(test)]
@@ -468,6 +837,8 @@ mod tests {
                 api_url: "http://localhost:3000".to_string(),
                 api_key: "test-key".to_string(),
                 heartbeat_interval_secs: 60,
+                enrollment_token: None,
+                credentials_file: None,
             },
             message_queue: MessageQueueConfig {
                 amqp_url: "amqp://localhost:5672".to_string(),
@@ -483,6 +854,13 @@ mod tests {
                 cleanup_workspace: true,
                 cache_dir: Some("/tmp/cache".to_string()),
                 docker: None,
+                log_ansi_mode: Default::default(),
+                known_hosts_file: None,
+                repo_cache: None,
+                state_dir: "/tmp/test-state".to_string(),
+                plugins: None,
+                docker_cache: Default::default(),
+                native_sandbox: None,
             },
         }
     }
@@ -500,6 +878,12 @@ mod tests {
         assert_eq!(config.queue_name(), "test-runner.my_test_runner_01.queue");
     }
 
+    #[test]
+    fn test_control_queue_name_generation() {
+        let config = create_test_config();
+        assert_eq!(config.control_queue_name(), "test-runner.test_runner.control");
+    }
+
     #[test]
     fn test_routing_key_generation() {
         let config = create_test_config();
@@ -656,4 +1040,62 @@ mod tests {
         std::env::remove_var("RUNNER_HEARTBEAT_INTERVAL_SECS");
         std::env::remove_var("RUNNER_WORKSPACE_DIR");
     }
+
+    #[test]
+    fn test_from_env_docker_cache() {
+        let _guard = env_lock().lock().unwrap();
+        std::env::set_var("RUNNER_NAME", "test");
+        std::env::set_var("CONTROL_PLANE_API_URL", "http://localhost:3000");
+        std::env::set_var("RABBITMQ_AMQP_URL", "amqp://localhost:5672");
+        std::env::set_var("RUNNER_DOCKER_PULL_POLICY", "if-not-present");
+        std::env::set_var("RUNNER_DOCKER_PREPULL_IMAGES", "rust:1.75, node:20,");
+        std::env::set_var("RUNNER_DOCKER_CACHE_VOLUME", "ops-runner-cache");
+
+        let config = RunnerConfig::from_env().unwrap();
+        let cache = &config.execution.docker_cache;
+        assert_eq!(cache.pull_policy, ImagePullPolicy::IfNotPresent);
+        assert_eq!(cache.pre_pull, vec!["rust:1.75".to_string(), "node:20".to_string()]);
+        assert_eq!(cache.prepull_interval_secs, 3600);
+        assert_eq!(cache.cache_volume.as_deref(), Some("ops-runner-cache"));
+        assert_eq!(cache.cache_mount_path, "/cache");
+
+        // 清理
+        std::env::remove_var("RUNNER_NAME");
+        std::env::remove_var("CONTROL_PLANE_API_URL");
+        std::env::remove_var("RABBITMQ_AMQP_URL");
+        std::env::remove_var("RUNNER_DOCKER_PULL_POLICY");
+        std::env::remove_var("RUNNER_DOCKER_PREPULL_IMAGES");
+        std::env::remove_var("RUNNER_DOCKER_CACHE_VOLUME");
+    }
+
+    #[test]
+    fn test_from_env_native_sandbox() {
+        let _guard = env_lock().lock().unwrap();
+        std::env::set_var("RUNNER_NAME", "test");
+        std::env::set_var("CONTROL_PLANE_API_URL", "http://localhost:3000");
+        std::env::set_var("RABBITMQ_AMQP_URL", "amqp://localhost:5672");
+
+        let config = RunnerConfig::from_env().unwrap();
+        assert!(config.execution.native_sandbox.is_none());
+
+        std::env::set_var("RUNNER_SANDBOX_USER", "builder");
+        std::env::set_var("RUNNER_SANDBOX_MEMORY_LIMIT_MB", "2048");
+        std::env::set_var("RUNNER_SANDBOX_ISOLATE_NETWORK", "true");
+
+        let config = RunnerConfig::from_env().unwrap();
+        let sandbox = config.execution.native_sandbox.unwrap();
+        assert_eq!(sandbox.user.as_deref(), Some("builder"));
+        assert_eq!(sandbox.memory_limit_mb, Some(2048));
+        assert_eq!(sandbox.cpu_limit, None);
+        assert!(sandbox.isolate_network);
+        assert_eq!(sandbox.env_allowlist, default_plugin_env_allowlist());
+
+        // 清理
+        std::env::remove_var("RUNNER_NAME");
+        std::env::remove_var("CONTROL_PLANE_API_URL");
+        std::env::remove_var("RABBITMQ_AMQP_URL");
+        std::env::remove_var("RUNNER_SANDBOX_USER");
+        std::env::remove_var("RUNNER_SANDBOX_MEMORY_LIMIT_MB");
+        std::env::remove_var("RUNNER_SANDBOX_ISOLATE_NETWORK");
+    }
 }
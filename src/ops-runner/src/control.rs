@@ -0,0 +1,138 @@
+//! 控制面下发的 Runner 控制指令
+//!
+//! - 暂停/恢复、清理工作空间由 Worker 的控制消费者直接执行
+//! - 重新注册、刷新配置需要控制面客户端，转交心跳任务执行
+//! - 执行结果暂存在此，随下一次心跳回报；有待回报结果时立即唤醒心跳任务
+
+use chrono::Utc;
+use std::sync::Mutex;
+use tokio::sync::{watch, Notify};
+use uuid::Uuid;
+
+use crate::messages::{RunnerCommandAck, RunnerControlMessage};
+
+/// Runner 控制状态（进程级，Worker 重建后保持）
+pub struct RunnerControl {
+    paused: watch::Sender<bool>,
+    deferred: Mutex<Vec<RunnerControlMessage>>,
+    acks: Mutex<Vec<RunnerCommandAck>>,
+    wake: Notify,
+}
+
+impl Default for RunnerControl {
+    fn default() -> Self {
+        Self {
+            paused: watch::channel(false).0,
+            deferred: Mutex::new(Vec::new()),
+            acks: Mutex::new(Vec::new()),
+            wake: Notify::new(),
+        }
+    }
+}
+
+impl RunnerControl {
+    /// 是否已暂停消费构建任务
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// 设置暂停状态，返回状态是否发生变化
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.send_if_modified(|current| {
+            let changed = *current != paused;
+            *current = paused;
+            changed
+        })
+    }
+
+    /// 等待暂停状态变为指定值
+    pub async fn wait_paused(&self, paused: bool) {
+        let mut rx = self.paused.subscribe();
+        let _ = rx.wait_for(|current| *current == paused).await;
+    }
+
+    /// 转交心跳任务执行的指令
+    pub fn defer(&self, message: RunnerControlMessage) {
+        self.deferred.lock().unwrap().push(message);
+        self.wake.notify_one();
+    }
+
+    /// 取出待心跳任务执行的指令
+    pub fn take_deferred(&self) -> Vec<RunnerControlMessage> {
+        std::mem::take(&mut *self.deferred.lock().unwrap())
+    }
+
+    /// 记录指令执行结果
+    pub fn ack(&self, command_id: Uuid, success: bool, message: impl Into<String>) {
+        self.acks.lock().unwrap().push(RunnerCommandAck {
+            command_id,
+            success,
+            message: Some(message.into()),
+            timestamp: Utc::now(),
+        });
+        self.wake.notify_one();
+    }
+
+    /// 取出待回报的执行结果
+    pub fn take_acks(&self) -> Vec<RunnerCommandAck> {
+        std::mem::take(&mut *self.acks.lock().unwrap())
+    }
+
+    /// 心跳发送失败时放回执行结果，等待下一次心跳
+    pub fn restore_acks(&self, acks: Vec<RunnerCommandAck>) {
+        let mut pending = self.acks.lock().unwrap();
+        let newer = std::mem::replace(&mut *pending, acks);
+        pending.extend(newer);
+    }
+
+    /// 等待新的指令或执行结果
+    pub async fn notified(&self) {
+        self.wake.notified().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::RunnerControlAction;
+
+    #[test]
+    fn test_pause_state_changes() {
+        let control = RunnerControl::default();
+        assert!(!control.is_paused());
+        assert!(control.set_paused(true));
+        assert!(!control.set_paused(true));
+        assert!(control.is_paused());
+        assert!(control.set_paused(false));
+    }
+
+    #[test]
+    fn test_acks_restored_in_order() {
+        let control = RunnerControl::default();
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+
+        control.ack(first, true, "paused");
+        let acks = control.take_acks();
+        assert!(control.take_acks().is_empty());
+
+        // 心跳失败期间产生的新结果排在放回的结果之后
+        control.ack(second, false, "failed");
+        control.restore_acks(acks);
+        let ids: Vec<_> = control.take_acks().iter().map(|a| a.command_id).collect();
+        assert_eq!(ids, vec![first, second]);
+    }
+
+    #[test]
+    fn test_deferred_commands_drained() {
+        let control = RunnerControl::default();
+        control.defer(RunnerControlMessage {
+            command_id: Uuid::new_v4(),
+            action: RunnerControlAction::RefreshConfig,
+            requested_by: None,
+            timestamp: Utc::now(),
+        });
+        assert_eq!(control.take_deferred().len(), 1);
+        assert!(control.take_deferred().is_empty());
+    }
+}
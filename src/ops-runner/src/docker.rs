@@ -4,8 +4,10 @@
 //! - 容器生命周期管理（创建、启动、停止、删除）
 //! - 日志流收集
 //! - 资源限制（CPU、内存）
-//! - 卷挂载和工作目录映射
+//! - 卷挂载和工作目录映射（含任务之间复用的命名缓存卷）
 //! - 环境变量注入
+//!
+//! 镜像拉取与缓存见 `image_cache` 模块
 
 #![allow(deprecated)]
 
@@ -13,23 +15,26 @@ use anyhow::{anyhow, Context, Result};
 use bollard::{
     container::LogOutput,
     models::{
-        ContainerCreateBody as ContainerConfig, HostConfig, Mount, MountTypeEnum, ResourcesUlimits,
+        ContainerCreateBody as ContainerConfig, ContainerStatsResponse, HostConfig, Mount,
+        MountTypeEnum, ResourcesUlimits,
     },
     query_parameters::{
-        CreateContainerOptions, CreateImageOptions, ListContainersOptions, LogsOptions,
-        RemoveContainerOptions, StartContainerOptions, StopContainerOptions, WaitContainerOptions,
+        CreateContainerOptions, ListContainersOptions, LogsOptions, RemoveContainerOptions,
+        StartContainerOptions, StatsOptions, StopContainerOptions, WaitContainerOptions,
     },
     Docker,
 };
 use futures_util::StreamExt;
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
-use crate::config::{DockerConfig, ExecutionConfig};
-use crate::messages::{BuildStep, StepType};
+use crate::config::{DockerCacheConfig, DockerConfig, ExecutionConfig};
+use crate::image_cache::ImageManager;
+use crate::messages::{BuildStep, StepResourceUsage, StepType};
 
 /// Docker 容器执行器
 pub struct DockerExecutor {
@@ -37,13 +42,17 @@ pub struct DockerExecutor {
     docker: Docker,
     /// Docker 配置
     config: DockerConfig,
+    /// 镜像与构建缓存配置
+    cache: DockerCacheConfig,
+    /// 镜像管理
+    images: ImageManager,
     /// 是否连接到 Docker
     is_connected: bool,
 }
 
 impl DockerExecutor {
     /// 创建新的 Docker 执行器
-    pub async fn new(config: DockerConfig) -> Result<Self> {
+    pub async fn new(config: DockerConfig, cache: DockerCacheConfig) -> Result<Self> {
         // 尝试连接到 Docker
         let docker = match Docker::connect_with_local_defaults() {
             Ok(client) => {
@@ -57,9 +66,12 @@ impl DockerExecutor {
                 }
                 // 如果未启用，创建一个未连接的执行器
                 warn!("Docker not available, running in native mode");
+                let docker = Docker::connect_with_local_defaults()?;
                 return Ok(Self {
-                    docker: Docker::connect_with_local_defaults()?,
+                    images: ImageManager::new(docker.clone(), cache.clone()),
+                    docker,
                     config,
+                    cache,
                     is_connected: false,
                 });
             }
@@ -74,8 +86,10 @@ impl DockerExecutor {
         info!("Docker executor initialized successfully");
 
         Ok(Self {
+            images: ImageManager::new(docker.clone(), cache.clone()),
             docker,
             config,
+            cache,
             is_connected: true,
         })
     }
@@ -85,34 +99,6 @@ impl DockerExecutor {
         self.is_connected && self.config.enabled
     }
 
-    /// 拉取 Docker 镜像
-    pub async fn pull_image(&self, image: &str) -> Result<()> {
-        info!("Pulling Docker image: {}", image);
-
-        let options = CreateImageOptions {
-            from_image: Some(image.to_string()),
-            ..Default::default()
-        };
-
-        let mut stream = self.docker.create_image(Some(options), None, None);
-
-        while let Some(next) = stream.next().await {
-            match next {
-                Ok(progress) => {
-                    if let Some(status) = progress.status {
-                        debug!("Pull progress: {}", status);
-                    }
-                }
-                Err(e) => {
-                    return Err(anyhow!("Failed to pull image: {}", e));
-                }
-            }
-        }
-
-        info!("Successfully pulled image: {}", image);
-        Ok(())
-    }
-
     /// 为构建步骤获取镜像名称
     fn get_image_for_step(&self, step: &BuildStep) -> String {
         // 首先检查步骤是否指定了镜像
@@ -141,16 +127,19 @@ impl DockerExecutor {
     }
 
     /// 执行单个构建步骤
+    ///
+    /// `cancel` 被触发时停止容器，返回已收集到的部分日志
     pub async fn execute_step(
         &self,
         step: &BuildStep,
         workspace_dir: &Path,
         env_vars: HashMap<String, String>,
+        cancel: &CancellationToken,
     ) -> Result<StepResult> {
         let image = self.get_image_for_step(step);
 
-        // 确保镜像存在
-        self.pull_image(&image).await?;
+        // 按拉取策略确保镜像存在
+        self.images.ensure_image(&image).await?;
 
         // 准备容器配置
         let container_name = format!("ops-runner-{}-{}", step.id, uuid::Uuid::new_v4());
@@ -159,6 +148,12 @@ impl DockerExecutor {
         let command = self.build_command(step)?;
 
         // 准备环境变量
+        let mut env_vars = env_vars;
+        if self.cache.cache_volume.is_some() {
+            env_vars
+                .entry("XDG_CACHE_HOME".to_string())
+                .or_insert_with(|| self.cache.cache_mount_path.clone());
+        }
         let container_env = self.build_environment(env_vars);
 
         // 准备卷挂载
@@ -179,8 +174,10 @@ impl DockerExecutor {
         };
 
         // 创建容器配置
+        let mut binds = vec![format!("{}:/workspace:rw", workspace_dir.display())];
+        binds.extend(self.cache_volume_bind());
         let host_config = HostConfig {
-            binds: Some(vec![format!("{}:/workspace:rw", workspace_dir.display())]),
+            binds: Some(binds),
             // 资源限制
             memory: self
                 .config
@@ -263,8 +260,11 @@ impl DockerExecutor {
             .await
             .context("Failed to start container")?;
 
-        // 创建取消令牌用于超时控制
-        let cancel_token = CancellationToken::new();
+        // 后台采集容器资源统计
+        let stats = self.spawn_stats_collector(&container_name);
+
+        // 创建取消令牌用于超时控制（外部取消同样会触发）
+        let cancel_token = cancel.child_token();
         let cancel_token_clone = cancel_token.clone();
 
         let timeout = step.timeout_secs.unwrap_or(self.config.default_timeout);
@@ -294,6 +294,7 @@ impl DockerExecutor {
 
         // 等待容器退出
         let exit_code = self.wait_for_container(&container_name).await?;
+        let resource_usage = stats.finish();
 
         // 移除容器
         self.remove_container(&container_name).await;
@@ -311,7 +312,9 @@ impl DockerExecutor {
                     exit_code,
                     stdout: logs.clone(),
                     stderr: String::new(),
-                    success: exit_code == 0,
+                    success: exit_code == 0 && !cancel.is_cancelled(),
+                    cancelled: cancel.is_cancelled(),
+                    resource_usage: Some(resource_usage.clone()),
                 })
             }
             Err(e) => {
@@ -320,7 +323,9 @@ impl DockerExecutor {
                     exit_code,
                     stdout: String::new(),
                     stderr: e.to_string(),
-                    success: exit_code == 0,
+                    success: exit_code == 0 && !cancel.is_cancelled(),
+                    cancelled: cancel.is_cancelled(),
+                    resource_usage: Some(resource_usage.clone()),
                 })
             }
         }
@@ -376,6 +381,24 @@ impl DockerExecutor {
         }]
     }
 
+    /// 命名缓存卷的挂载（卷不存在时由 Docker 自动创建，在任务之间保留）
+    fn cache_volume_bind(&self) -> Option<String> {
+        let name = self.cache.cache_volume.as_deref()?.trim();
+        // 只接受卷名，避免把宿主机路径挂载进容器
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+        if !valid || !self.cache.cache_mount_path.starts_with('/') {
+            warn!("Ignoring invalid cache volume {}:{}", name, self.cache.cache_mount_path);
+            return None;
+        }
+        Some(format!("{}:{}:rw", name, self.cache.cache_mount_path))
+    }
+
     /// 构建 ulimit 配置
     fn build_ulimits(&self) -> Option<Vec<ResourcesUlimits>> {
         let mut ulimits = Vec::new();
@@ -423,10 +446,14 @@ impl DockerExecutor {
         let mut output = String::new();
         let mut stream = Box::pin(stream);
 
-        while let Some(result) = stream.next().await {
-            if cancel_token.is_cancelled() {
-                break;
-            }
+        loop {
+            let result = tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                next = stream.next() => match next {
+                    Some(result) => result,
+                    None => break,
+                },
+            };
 
             match result {
                 Ok(log_bytes) => {
@@ -450,6 +477,27 @@ impl DockerExecutor {
         Ok(output)
     }
 
+    /// 后台采集容器 stats，直到容器退出或调用 finish
+    fn spawn_stats_collector(&self, container_name: &str) -> StatsCollector {
+        let usage = Arc::new(Mutex::new(StepResourceUsage::default()));
+        let docker = self.docker.clone();
+        let container_name = container_name.to_string();
+        let shared = usage.clone();
+
+        let handle = tokio::spawn(async move {
+            let options = Some(StatsOptions {
+                stream: true,
+                one_shot: false,
+            });
+            let mut stream = Box::pin(docker.stats(&container_name, options));
+            while let Some(Ok(stats)) = stream.next().await {
+                record_container_stats(&mut shared.lock().unwrap(), &stats);
+            }
+        });
+
+        StatsCollector { usage, handle }
+    }
+
     /// 等待容器退出并返回退出码
     async fn wait_for_container(&self, container_name: &str) -> Result<i32> {
         let options = Some(WaitContainerOptions {
@@ -529,6 +577,57 @@ impl DockerExecutor {
     }
 }
 
+/// 容器资源统计采集任务
+struct StatsCollector {
+    usage: Arc<Mutex<StepResourceUsage>>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl StatsCollector {
+    /// 停止采集并返回汇总结果
+    fn finish(self) -> StepResourceUsage {
+        self.handle.abort();
+        self.usage.lock().unwrap().clone()
+    }
+}
+
+/// 将一次容器 stats 合并到资源使用汇总中
+///
+/// 各项均取最大值：容器停止后 Docker 可能返回清零的统计
+fn record_container_stats(usage: &mut StepResourceUsage, stats: &ContainerStatsResponse) {
+    if let Some(memory) = &stats.memory_stats {
+        // cgroup v2 没有 max_usage，退化为当前用量
+        if let Some(bytes) = memory.max_usage.or(memory.usage) {
+            usage.peak_memory_bytes = usage.peak_memory_bytes.max(bytes);
+        }
+    }
+
+    if let Some(total_ns) = stats
+        .cpu_stats
+        .as_ref()
+        .and_then(|cpu| cpu.cpu_usage.as_ref())
+        .and_then(|cpu| cpu.total_usage)
+    {
+        usage.cpu_time_ms = usage.cpu_time_ms.max(total_ns / 1_000_000);
+    }
+
+    if let Some(entries) = stats
+        .blkio_stats
+        .as_ref()
+        .and_then(|blkio| blkio.io_service_bytes_recursive.as_ref())
+    {
+        let written: u64 = entries
+            .iter()
+            .filter(|e| {
+                e.op.as_deref()
+                    .is_some_and(|op| op.eq_ignore_ascii_case("write"))
+            })
+            .filter_map(|e| e.value)
+            .sum();
+        usage.disk_written_bytes = usage.disk_written_bytes.max(written);
+    }
+}
+
 /// 步骤执行结果
 #[derive(Debug, Clone)]
 pub struct StepResult {
@@ -540,6 +639,10 @@ pub struct StepResult {
     pub stderr: String,
     /// 是否成功
     pub success: bool,
+    /// 是否因构建取消而中止
+    pub cancelled: bool,
+    /// 资源使用情况
+    pub resource_usage: Option<StepResourceUsage>,
 }
 
 /// 为 ExecutionConfig 添加 Docker 配置
@@ -625,6 +728,8 @@ mod tests {
             stdout: "hello world".to_string(),
             stderr: String::new(),
             success: true,
+            cancelled: false,
+            resource_usage: None,
         };
 
         assert!(result.success);
@@ -647,4 +752,51 @@ mod tests {
         assert!(env.contains(&"PATH=/usr/bin".to_string()));
         assert!(env.contains(&"HOME=/root".to_string()));
     }
+
+    #[test]
+    fn test_record_container_stats_keeps_maximum() {
+        use bollard::models::{
+            ContainerBlkioStatEntry, ContainerBlkioStats, ContainerCpuStats, ContainerCpuUsage,
+            ContainerMemoryStats,
+        };
+
+        let stats = ContainerStatsResponse {
+            memory_stats: Some(ContainerMemoryStats {
+                usage: Some(2048),
+                ..Default::default()
+            }),
+            cpu_stats: Some(ContainerCpuStats {
+                cpu_usage: Some(ContainerCpuUsage {
+                    total_usage: Some(1_500_000_000),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            blkio_stats: Some(ContainerBlkioStats {
+                io_service_bytes_recursive: Some(vec![
+                    ContainerBlkioStatEntry {
+                        op: Some("write".to_string()),
+                        value: Some(4096),
+                        ..Default::default()
+                    },
+                    ContainerBlkioStatEntry {
+                        op: Some("read".to_string()),
+                        value: Some(9999),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut usage = StepResourceUsage::default();
+        record_container_stats(&mut usage, &stats);
+        // 容器停止后的清零统计不应覆盖已有值
+        record_container_stats(&mut usage, &ContainerStatsResponse::default());
+
+        assert_eq!(usage.peak_memory_bytes, 2048);
+        assert_eq!(usage.cpu_time_ms, 1500);
+        assert_eq!(usage.disk_written_bytes, 4096);
+    }
 }
@@ -9,14 +9,24 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio::sync::OnceCell;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::config::RunnerConfig;
 use crate::docker::DockerExecutor;
+use crate::git;
+use crate::journal::TaskJournal;
 use crate::messages::*;
+use crate::plugin::{PluginOutcome, PluginRegistry, PluginResponse};
 use crate::publisher::{ArtifactStorage, MessagePublisher};
+use crate::repo_cache::RepoCache;
+use crate::resource::ProcessTreeSampler;
+use crate::sandbox::NativeSandbox;
+use common::error::ErrorKind;
 
 /// 工作空间管理器
 pub struct WorkspaceManager {
@@ -252,7 +262,7 @@ impl WorkspaceManager {
                     })
                     .collect();
 
-                entries_with_time.sort_by(|a, b| b.1.cmp(&a.1));
+                entries_with_time.sort_by_key(|e| std::cmp::Reverse(e.1));
 
                 // 删除旧的工作空间
                 let mut cleaned = 0;
@@ -327,6 +337,13 @@ pub struct BuildExecutor {
     workspace_manager: WorkspaceManager,
     artifact_storage: Option<ArtifactStorage>,
     docker_executor: OnceCell<DockerExecutor>,
+    repo_cache: Option<Arc<RepoCache>>,
+    /// 进行中任务日志（状态目录不可用时为 None）
+    journal: Option<TaskJournal>,
+    /// 步骤插件（首次执行插件步骤时扫描插件目录）
+    plugins: OnceCell<PluginRegistry>,
+    /// 原生执行沙箱（未配置时原生步骤以 Runner 用户身份执行）
+    native_sandbox: Option<NativeSandbox>,
 }
 
 impl BuildExecutor {
@@ -351,11 +368,47 @@ impl BuildExecutor {
             warn!("Failed to cleanup old workspaces on startup: {}", e);
         }
 
+        // 初始化仓库镜像缓存
+        let repo_cache = config
+            .execution
+            .repo_cache
+            .as_ref()
+            .and_then(|cache_config| match RepoCache::new(cache_config) {
+                Ok(cache) => {
+                    info!("Repository cache initialized: {}", cache_config.dir);
+                    Some(Arc::new(cache))
+                }
+                Err(e) => {
+                    warn!("Failed to initialize repository cache: {}, cloning directly", e);
+                    None
+                }
+            });
+
+        let journal = match TaskJournal::new(&config.execution.state_dir) {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                warn!("Failed to open task journal: {}, crash recovery disabled", e);
+                None
+            }
+        };
+
+        let native_sandbox = match &config.execution.native_sandbox {
+            Some(sandbox_config) => Some(
+                NativeSandbox::new(sandbox_config.clone())
+                    .context("Native sandbox is not supported on this host")?,
+            ),
+            None => None,
+        };
+
         Ok(Self {
             config,
             workspace_manager,
             artifact_storage,
             docker_executor: OnceCell::new(),
+            repo_cache,
+            journal,
+            plugins: OnceCell::new(),
+            native_sandbox,
         })
     }
 
@@ -371,9 +424,10 @@ impl BuildExecutor {
         let docker_cfg = self.config.execution.docker_config()?;
 
         let docker_cfg = docker_cfg.clone();
+        let cache_cfg = self.config.execution.docker_cache.clone();
         match self
             .docker_executor
-            .get_or_try_init(|| async { DockerExecutor::new(docker_cfg).await })
+            .get_or_try_init(|| async { DockerExecutor::new(docker_cfg, cache_cfg).await })
             .await
         {
             Ok(executor) => {
@@ -390,6 +444,20 @@ impl BuildExecutor {
         }
     }
 
+    async fn plugin_registry(&self) -> Option<&PluginRegistry> {
+        let plugin_config = self.config.execution.plugins.as_ref()?;
+        Some(
+            self.plugins
+                .get_or_init(|| PluginRegistry::discover(plugin_config.clone()))
+                .await,
+        )
+    }
+
+    /// 按当前策略清理旧工作空间（控制面下发的清理指令）
+    pub fn cleanup_workspaces(&self) -> Result<()> {
+        self.workspace_manager.cleanup_old_workspaces()
+    }
+
     /// 清理资源（用于关闭时调用）
     #[allow(dead_code)]
     pub async fn cleanup(&self) -> Result<()> {
@@ -412,10 +480,13 @@ impl BuildExecutor {
     }
 
     /// 执行构建任务
+    ///
+    /// `cancel` 被触发时中止当前步骤，剩余步骤标记为已取消，最终状态为 Cancelled
     pub async fn execute(
         &self,
         task: BuildTaskMessage,
         publisher: &MessagePublisher,
+        cancel: &CancellationToken,
     ) -> Result<()> {
         info!("Starting build execution: job={}, task={}", task.job_id, task.task_id);
 
@@ -429,6 +500,9 @@ impl BuildExecutor {
             .workspace_manager
             .create_workspace(task.job_id, task.task_id)?;
 
+        // 登记进行中任务，任何方式返回时删除；进程崩溃时残留，重启后上报为中断
+        let mut active = self.journal.as_ref().map(|j| j.begin(&task, &workspace));
+
         // 发送准备中状态
         publisher
             .publish_build_status(&task, BuildStatus::Preparing, None, None, None)
@@ -440,12 +514,18 @@ impl BuildExecutor {
             .await
         {
             let _ = publisher
-                .publish_error(&task, &e.to_string(), ErrorCategory::Network)
+                .publish_error(&task, &e.to_string(), ErrorKind::Network)
                 .await;
             self.cleanup_workspace(&workspace).await;
             return Err(e);
         }
 
+        if cancel.is_cancelled() {
+            self.publish_remaining_cancelled(&task, &task.steps, publisher)
+                .await;
+            return self.finish_cancelled(&task, &workspace, publisher).await;
+        }
+
         // 发送执行中状态
         publisher
             .publish_build_status(&task, BuildStatus::Running, None, None, None)
@@ -455,8 +535,20 @@ impl BuildExecutor {
         let mut all_succeeded = true;
         let mut artifacts = Vec::new();
 
-        for step in &task.steps {
-            let step_result = self.execute_step(&workspace, &task, step, publisher).await;
+        for (index, step) in task.steps.iter().enumerate() {
+            if cancel.is_cancelled() {
+                self.publish_remaining_cancelled(&task, &task.steps[index..], publisher)
+                    .await;
+                break;
+            }
+
+            if let Some(active) = active.as_mut() {
+                active.step(index, &step.id);
+            }
+
+            let step_result = self
+                .execute_step_with_retry(&workspace, &task, step, publisher, cancel)
+                .await;
 
             match step_result {
                 Ok(Some(artifact)) => {
@@ -476,6 +568,10 @@ impl BuildExecutor {
             }
         }
 
+        if cancel.is_cancelled() {
+            return self.finish_cancelled(&task, &workspace, publisher).await;
+        }
+
         // 清理 workspace
         self.cleanup_workspace(&workspace).await;
 
@@ -501,6 +597,47 @@ impl BuildExecutor {
         Ok(())
     }
 
+    /// 将尚未执行的步骤标记为已取消
+    async fn publish_remaining_cancelled(
+        &self,
+        task: &BuildTaskMessage,
+        steps: &[BuildStep],
+        publisher: &MessagePublisher,
+    ) {
+        for step in steps {
+            let now = Utc::now();
+            if let Err(e) = publisher
+                .publish_step_status(task, step, StepStatus::Cancelled, now, Some(now), None, None)
+                .await
+            {
+                warn!("Failed to publish cancelled status for step {}: {}", step.name, e);
+            }
+        }
+    }
+
+    /// 结束已取消的构建：清理工作空间并发送 Cancelled 状态
+    async fn finish_cancelled(
+        &self,
+        task: &BuildTaskMessage,
+        workspace: &Path,
+        publisher: &MessagePublisher,
+    ) -> Result<()> {
+        self.cleanup_workspace(workspace).await;
+
+        publisher
+            .publish_build_status(
+                task,
+                BuildStatus::Cancelled,
+                None,
+                Some("Build cancelled".to_string()),
+                None,
+            )
+            .await?;
+
+        info!("Build execution cancelled: job={}, task={}", task.job_id, task.task_id);
+        Ok(())
+    }
+
     /// 克隆代码
     async fn clone_code(
         &self,
@@ -509,53 +646,73 @@ impl BuildExecutor {
         _publisher: &MessagePublisher,
         _task: &BuildTaskMessage,
     ) -> Result<()> {
-        info!("Cloning repository: {} (branch: {})", project.repository_url, project.branch);
+        info!(
+            "Cloning repository: {} (branch: {})",
+            git::scrub_credentials(&project.repository_url, project.credentials.as_ref()),
+            project.branch
+        );
 
-        // 处理 file:// 协议：去掉前缀使用本地路径
-        let repo_url = if project.repository_url.starts_with("file://") {
-            project.repository_url.strip_prefix("file://").unwrap()
-        } else {
-            &project.repository_url
-        };
+        // 子模块与 LFS 拉取耗时较长，放到阻塞线程执行
+        let project = project.clone();
+        let workspace = workspace.to_path_buf();
+        let known_hosts_file = self.config.execution.known_hosts_file.clone();
+        let repo_cache = self.repo_cache.clone();
+        tokio::task::spawn_blocking(move || {
+            git::checkout(&project, &workspace, known_hosts_file.as_deref(), repo_cache.as_deref())
+        })
+        .await
+        .context("Git checkout task failed")??;
 
-        let output = Command::new("git")
-            .args([
-                "clone",
-                "--depth",
-                "1",
-                "--branch",
-                &project.branch,
-                repo_url,
-                workspace.to_str().unwrap(),
-            ])
-            .output()
-            .context("Failed to execute git clone")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Git clone failed: {}", stderr);
-        }
+        info!("Repository cloned successfully");
+        Ok(())
+    }
+
+    /// 按步骤的重试策略执行步骤
+    ///
+    /// 每次尝试的步骤状态都带尝试序号；失败或超时且策略允许时，等待退避间隔后重新执行
+    async fn execute_step_with_retry(
+        &self,
+        workspace: &Path,
+        task: &BuildTaskMessage,
+        step: &BuildStep,
+        publisher: &MessagePublisher,
+        cancel: &CancellationToken,
+    ) -> Result<Option<BuildArtifact>> {
+        loop {
+            let attempt = publisher.begin_step_attempt(task, step);
+            let result = self
+                .execute_step(workspace, task, step, publisher, cancel)
+                .await;
+            if cancel.is_cancelled() || !publisher.step_will_retry(task, step) {
+                return result;
+            }
 
-        // 检出指定 commit
-        if !project.commit.is_empty() {
-            let output = Command::new("git")
-                .args([
-                    "-C",
-                    workspace.to_str().unwrap(),
-                    "checkout",
-                    &project.commit,
-                ])
-                .output()
-                .context("Failed to checkout commit")?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                warn!("Failed to checkout commit {}: {}", project.commit, stderr);
+            let delay = step
+                .retry
+                .as_ref()
+                .map(|policy| policy.backoff(attempt))
+                .unwrap_or_default();
+            warn!(
+                "Step {} attempt {} failed, retrying in {}s",
+                step.name,
+                attempt,
+                delay.as_secs()
+            );
+            publisher
+                .publish_log(
+                    task,
+                    step,
+                    &format!("Attempt {} failed, retrying in {}s", attempt, delay.as_secs()),
+                    LogLevel::Warn,
+                    false,
+                )
+                .await?;
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = cancel.cancelled() => return result,
             }
         }
-
-        info!("Repository cloned successfully");
-        Ok(())
     }
 
     /// 执行单个构建步骤
@@ -565,6 +722,7 @@ impl BuildExecutor {
         task: &BuildTaskMessage,
         step: &BuildStep,
         publisher: &MessagePublisher,
+        cancel: &CancellationToken,
     ) -> Result<Option<BuildArtifact>> {
         info!("Executing step: {}", step.name);
 
@@ -581,37 +739,49 @@ impl BuildExecutor {
             envs.insert(k.clone(), v.clone());
         }
 
-        let docker_executor = self.try_get_docker_executor().await;
-
-        let (status, artifact) = if let Some(docker_executor) = docker_executor {
-            match docker_executor.execute_step(step, workspace, envs).await {
+        let (status, artifact) = if let Some(plugin_name) = step.step_type.plugin_name() {
+            // 插件是 Runner 主机上的可执行文件，不进入 Docker 容器
+            self.execute_plugin_step(
+                plugin_name,
+                workspace,
+                task,
+                step,
+                publisher,
+                started_at,
+                cancel,
+            )
+            .await?
+        } else if let Some(docker_executor) = self.try_get_docker_executor().await {
+            match docker_executor
+                .execute_step(step, workspace, envs, cancel)
+                .await
+            {
                 Ok(step_result) => {
                     let completed_at = Utc::now();
-                    if step_result.success {
+                    if step_result.cancelled {
+                        let output = format!("{}{}", step_result.stdout, step_result.stderr);
+                        self.publish_step_cancelled(
+                            task,
+                            step,
+                            publisher,
+                            started_at,
+                            &output,
+                            step_result.resource_usage.clone(),
+                        )
+                        .await?;
+
+                        (StepStatus::Cancelled, None)
+                    } else if step_result.success {
                         // 发布标准输出
                         if !step_result.stdout.is_empty() {
                             publisher
-                                .publish_log(
-                                    task,
-                                    step,
-                                    &step_result.stdout,
-                                    LogLevel::Info,
-                                    0,
-                                    true,
-                                )
+                                .publish_log(task, step, &step_result.stdout, LogLevel::Info, true)
                                 .await?;
                         }
                         // 发布标准错误（如果有）
                         if !step_result.stderr.is_empty() {
                             publisher
-                                .publish_log(
-                                    task,
-                                    step,
-                                    &step_result.stderr,
-                                    LogLevel::Warn,
-                                    0,
-                                    true,
-                                )
+                                .publish_log(task, step, &step_result.stderr, LogLevel::Warn, true)
                                 .await?;
                         }
 
@@ -623,7 +793,7 @@ impl BuildExecutor {
                         };
 
                         publisher
-                            .publish_step_status(
+                            .publish_step_status_with_usage(
                                 task,
                                 step,
                                 StepStatus::Succeeded,
@@ -631,6 +801,7 @@ impl BuildExecutor {
                                 Some(completed_at),
                                 Some(step_result.exit_code),
                                 artifact.clone(),
+                                step_result.resource_usage.clone(),
                             )
                             .await?;
 
@@ -645,11 +816,11 @@ impl BuildExecutor {
                             format!("{}\n{}", step_result.stdout, step_result.stderr)
                         };
                         publisher
-                            .publish_log(task, step, &output, LogLevel::Error, 0, true)
+                            .publish_log(task, step, &output, LogLevel::Error, true)
                             .await?;
 
                         publisher
-                            .publish_step_status(
+                            .publish_step_status_with_usage(
                                 task,
                                 step,
                                 StepStatus::Failed,
@@ -657,6 +828,7 @@ impl BuildExecutor {
                                 Some(completed_at),
                                 Some(step_result.exit_code),
                                 None,
+                                step_result.resource_usage.clone(),
                             )
                             .await?;
 
@@ -668,7 +840,7 @@ impl BuildExecutor {
                     error!("Docker step execution failed: {}", e);
                     let error_msg = format!("Execution error: {}", e);
                     publisher
-                        .publish_log(task, step, &error_msg, LogLevel::Error, 0, true)
+                        .publish_log(task, step, &error_msg, LogLevel::Error, true)
                         .await?;
 
                     publisher
@@ -718,39 +890,47 @@ impl BuildExecutor {
                 .map(Duration::from_secs)
                 .unwrap_or_else(|| self.config.step_timeout());
 
-            let result = tokio::time::timeout(
-                timeout,
-                tokio::task::spawn_blocking({
-                    let command = command.clone();
-                    let work_dir = work_dir.clone();
-                    move || {
-                        let mut cmd = Command::new("sh");
-                        cmd.args(["-c", &command])
-                            .current_dir(&work_dir)
-                            .envs(&envs);
-                        cmd.output()
-                    }
-                }),
+            // 沙箱中不传入 Runner 自身的环境变量，工作空间交给步骤用户
+            let mut envs = match &self.native_sandbox {
+                Some(sandbox) => {
+                    sandbox
+                        .prepare_workspace(workspace)
+                        .await
+                        .context("Failed to prepare workspace for native sandbox")?;
+                    sandbox.step_env(&task.build.env_vars, workspace)
+                }
+                None => envs,
+            };
+
+            // 让 git/ssh 使用控制面同步的 known_hosts 严格校验主机密钥
+            if let Some(known_hosts_file) = &self.config.execution.known_hosts_file {
+                envs.entry("GIT_SSH_COMMAND".to_string())
+                    .or_insert_with(|| {
+                        format!(
+                            "ssh -o UserKnownHostsFile={} -o StrictHostKeyChecking=yes",
+                            known_hosts_file
+                        )
+                    });
+            }
+
+            let sandbox = self.native_sandbox.as_ref();
+            let (exec_result, resource_usage) = match run_native_command(
+                &command, &work_dir, &envs, timeout, cancel, sandbox,
             )
-            .await;
+            .await
+            {
+                Ok(run) => (Ok(run.outcome), run.resource_usage),
+                Err(e) => (Err(e), None),
+            };
 
             let completed_at = Utc::now();
 
-            let exec_result: std::result::Result<std::process::Output, std::io::Error> =
-                match result {
-                    Ok(inner_result) => inner_result.map_err(std::io::Error::other)?,
-                    Err(_) => Err(std::io::Error::new(
-                        std::io::ErrorKind::TimedOut,
-                        "Command execution timed out",
-                    )),
-                };
-
             match exec_result {
-                Ok(output) => {
+                Ok(NativeOutcome::Exited(output)) => {
                     if output.status.success() {
                         let stdout = String::from_utf8_lossy(&output.stdout);
                         publisher
-                            .publish_log(task, step, &stdout, LogLevel::Info, 0, true)
+                            .publish_log(task, step, &stdout, LogLevel::Info, true)
                             .await?;
 
                         let artifact = if step.produces_artifact {
@@ -761,7 +941,7 @@ impl BuildExecutor {
                         };
 
                         publisher
-                            .publish_step_status(
+                            .publish_step_status_with_usage(
                                 task,
                                 step,
                                 StepStatus::Succeeded,
@@ -769,6 +949,7 @@ impl BuildExecutor {
                                 Some(completed_at),
                                 Some(output.status.code().unwrap_or(0)),
                                 artifact.clone(),
+                                resource_usage,
                             )
                             .await?;
 
@@ -778,11 +959,11 @@ impl BuildExecutor {
                         let stderr = String::from_utf8_lossy(&output.stderr);
                         let log_content = format!("{}\n{}", stdout, stderr);
                         publisher
-                            .publish_log(task, step, &log_content, LogLevel::Error, 0, true)
+                            .publish_log(task, step, &log_content, LogLevel::Error, true)
                             .await?;
 
                         publisher
-                            .publish_step_status(
+                            .publish_step_status_with_usage(
                                 task,
                                 step,
                                 StepStatus::Failed,
@@ -790,21 +971,40 @@ impl BuildExecutor {
                                 Some(completed_at),
                                 Some(output.status.code().unwrap_or(1)),
                                 None,
+                                resource_usage,
                             )
                             .await?;
 
                         (StepStatus::Failed, None)
                     }
                 }
-                Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                Ok(NativeOutcome::Cancelled { stdout, stderr }) => {
+                    let output = format!(
+                        "{}{}",
+                        String::from_utf8_lossy(&stdout),
+                        String::from_utf8_lossy(&stderr)
+                    );
+                    self.publish_step_cancelled(
+                        task,
+                        step,
+                        publisher,
+                        started_at,
+                        &output,
+                        resource_usage,
+                    )
+                    .await?;
+
+                    (StepStatus::Cancelled, None)
+                }
+                Ok(NativeOutcome::TimedOut) => {
                     warn!("Step execution timed out after {:?}", timeout);
                     let timeout_msg = format!("Execution timed out after {:?}", timeout);
                     publisher
-                        .publish_log(task, step, &timeout_msg, LogLevel::Error, 0, true)
+                        .publish_log(task, step, &timeout_msg, LogLevel::Error, true)
                         .await?;
 
                     publisher
-                        .publish_step_status(
+                        .publish_step_status_with_usage(
                             task,
                             step,
                             StepStatus::Timeout,
@@ -812,16 +1012,17 @@ impl BuildExecutor {
                             Some(completed_at),
                             None,
                             None,
+                            resource_usage,
                         )
                         .await?;
 
                     (StepStatus::Timeout, None)
                 }
-                Err(ref e) => {
+                Err(e) => {
                     error!("Command execution failed: {}", e);
                     let error_msg = format!("Execution error: {}", e);
                     publisher
-                        .publish_log(task, step, &error_msg, LogLevel::Error, 0, true)
+                        .publish_log(task, step, &error_msg, LogLevel::Error, true)
                         .await?;
 
                     publisher
@@ -841,13 +1042,233 @@ impl BuildExecutor {
             }
         };
 
-        if status == StepStatus::Failed && !step.continue_on_failure {
-            anyhow::bail!("Step {} failed and continue_on_failure is false", step.name);
+        if matches!(status, StepStatus::Failed | StepStatus::Timeout) && !step.continue_on_failure {
+            anyhow::bail!(
+                "Step {} ended with {:?} and continue_on_failure is false",
+                step.name,
+                status
+            );
         }
 
         Ok(artifact)
     }
 
+    /// 执行插件步骤
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_plugin_step(
+        &self,
+        plugin_name: &str,
+        workspace: &Path,
+        task: &BuildTaskMessage,
+        step: &BuildStep,
+        publisher: &MessagePublisher,
+        started_at: chrono::DateTime<Utc>,
+        cancel: &CancellationToken,
+    ) -> Result<(StepStatus, Option<BuildArtifact>)> {
+        let registry = self.plugin_registry().await;
+        let Some((registry, plugin)) =
+            registry.and_then(|registry| registry.get(plugin_name).map(|p| (registry, p)))
+        else {
+            let error_msg = format!("Plugin {} is not installed on this runner", plugin_name);
+            error!("{}", error_msg);
+            publisher
+                .publish_log(task, step, &error_msg, LogLevel::Error, true)
+                .await?;
+            publisher
+                .publish_step_status(
+                    task,
+                    step,
+                    StepStatus::Failed,
+                    started_at,
+                    Some(Utc::now()),
+                    None,
+                    None,
+                )
+                .await?;
+            return Ok((StepStatus::Failed, None));
+        };
+
+        let timeout = registry.timeout_for(plugin, step, self.config.step_timeout());
+
+        let run = match registry
+            .execute(plugin, task, step, workspace, timeout, cancel)
+            .await
+        {
+            Ok(run) => run,
+            Err(e) => {
+                error!("Plugin {} execution failed: {}", plugin_name, e);
+                let error_msg = format!("Execution error: {}", e);
+                publisher
+                    .publish_log(task, step, &error_msg, LogLevel::Error, true)
+                    .await?;
+                publisher
+                    .publish_step_status(
+                        task,
+                        step,
+                        StepStatus::Failed,
+                        started_at,
+                        Some(Utc::now()),
+                        None,
+                        None,
+                    )
+                    .await?;
+                return Ok((StepStatus::Failed, None));
+            }
+        };
+
+        let completed_at = Utc::now();
+        let (exit_code, stdout) = match run.outcome {
+            PluginOutcome::Exited { exit_code, stdout } => (exit_code, stdout),
+            PluginOutcome::Cancelled => {
+                self.publish_step_cancelled(
+                    task,
+                    step,
+                    publisher,
+                    started_at,
+                    &run.stderr,
+                    run.resource_usage,
+                )
+                .await?;
+                return Ok((StepStatus::Cancelled, None));
+            }
+            PluginOutcome::TimedOut => {
+                warn!("Plugin {} timed out after {:?}", plugin_name, timeout);
+                let timeout_msg = format!("Execution timed out after {:?}", timeout);
+                publisher
+                    .publish_log(task, step, &timeout_msg, LogLevel::Error, true)
+                    .await?;
+                publisher
+                    .publish_step_status_with_usage(
+                        task,
+                        step,
+                        StepStatus::Timeout,
+                        started_at,
+                        Some(completed_at),
+                        None,
+                        None,
+                        run.resource_usage,
+                    )
+                    .await?;
+                return Ok((StepStatus::Timeout, None));
+            }
+        };
+
+        // 插件 stderr 是其运行日志
+        if !run.stderr.is_empty() {
+            publisher
+                .publish_log(task, step, &run.stderr, LogLevel::Info, true)
+                .await?;
+        }
+
+        match PluginResponse::parse(&stdout) {
+            Ok(response) if response.success && exit_code == Some(0) => {
+                if !response.output.is_empty() {
+                    publisher
+                        .publish_log(task, step, &response.output, LogLevel::Info, true)
+                        .await?;
+                }
+
+                let artifact = if step.produces_artifact {
+                    self.create_and_upload_artifact(workspace, task, step, publisher)
+                        .await?
+                } else {
+                    None
+                };
+
+                publisher
+                    .publish_step_status_with_usage(
+                        task,
+                        step,
+                        StepStatus::Succeeded,
+                        started_at,
+                        Some(completed_at),
+                        Some(response.exit_code.unwrap_or(0)),
+                        artifact.clone(),
+                        run.resource_usage,
+                    )
+                    .await?;
+
+                Ok((StepStatus::Succeeded, artifact))
+            }
+            result => {
+                let (log_content, reported_exit_code) = match result {
+                    Ok(response) => {
+                        let error = response.error.unwrap_or_else(|| {
+                            if response.success {
+                                format!("Plugin exited with status {:?}", exit_code)
+                            } else {
+                                "Plugin reported failure".to_string()
+                            }
+                        });
+                        let content = if response.output.is_empty() {
+                            error
+                        } else {
+                            format!("{}\n{}", response.output, error)
+                        };
+                        (content, response.exit_code)
+                    }
+                    Err(e) => (e, None),
+                };
+                publisher
+                    .publish_log(task, step, &log_content, LogLevel::Error, true)
+                    .await?;
+
+                let exit_code = reported_exit_code
+                    .or(exit_code.filter(|code| *code != 0))
+                    .unwrap_or(1);
+                publisher
+                    .publish_step_status_with_usage(
+                        task,
+                        step,
+                        StepStatus::Failed,
+                        started_at,
+                        Some(completed_at),
+                        Some(exit_code),
+                        None,
+                        run.resource_usage,
+                    )
+                    .await?;
+
+                Ok((StepStatus::Failed, None))
+            }
+        }
+    }
+
+    /// 发布步骤取消状态（附带取消前已产生的部分日志）
+    async fn publish_step_cancelled(
+        &self,
+        task: &BuildTaskMessage,
+        step: &BuildStep,
+        publisher: &MessagePublisher,
+        started_at: chrono::DateTime<Utc>,
+        partial_output: &str,
+        resource_usage: Option<StepResourceUsage>,
+    ) -> Result<()> {
+        warn!("Step {} cancelled", step.name);
+
+        let mut content = partial_output.to_string();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str("Step cancelled");
+        publisher
+            .publish_log(task, step, &content, LogLevel::Warn, true)
+            .await?;
+
+        publisher
+            .publish_step_status_with_usage(
+                task,
+                step,
+                StepStatus::Cancelled,
+                started_at,
+                Some(Utc::now()),
+                None,
+                None,
+                resource_usage,
+            )
+            .await
+    }
+
     /// 创建并上传构建产物
     async fn create_and_upload_artifact(
         &self,
@@ -903,14 +1324,21 @@ impl BuildExecutor {
         let artifact_name = step.name.clone();
         let artifact_type = task.build.build_type.clone();
         let version = task.project.commit.clone();
+        let platform = step
+            .artifact_platform
+            .as_ref()
+            .map(ArtifactPlatform::normalized)
+            .unwrap_or_else(ArtifactPlatform::current);
 
-        // 尝试上传到存储
+        // 尝试上传到存储（同版本的多平台变体按平台分目录）
         let _download_url = if let Some(storage) = &self.artifact_storage {
             let remote_path = format!(
-                "{}/{}/{}/{}",
+                "{}/{}/{}/{}-{}/{}",
                 task.project.name,
                 task.build.build_type,
                 version,
+                platform.os,
+                platform.arch,
                 artifact_path.file_name().unwrap().to_string_lossy()
             );
 
@@ -938,6 +1366,8 @@ impl BuildExecutor {
             size,
             sha256,
             version,
+            arch: platform.arch,
+            os: platform.os,
         };
 
         info!("Created artifact: {:?} ({} bytes)", artifact.name, artifact.size);
@@ -956,6 +1386,179 @@ impl BuildExecutor {
     }
 }
 
+/// 取消或超时后等待输出管道关闭的最长时间
+///
+/// 被终止的 shell 可能遗留仍持有管道的子进程，超过该时间后只保留已读取的部分输出
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 原生命令执行结果
+#[derive(Debug)]
+enum NativeOutcome {
+    /// 命令正常退出
+    Exited(std::process::Output),
+    /// 执行超时，进程已被终止
+    TimedOut,
+    /// 构建被取消，进程已被终止（附带部分输出）
+    Cancelled { stdout: Vec<u8>, stderr: Vec<u8> },
+}
+
+/// 原生命令执行记录
+struct NativeRun {
+    outcome: NativeOutcome,
+    /// 进程树资源使用情况（无法获取 PID 时为空）
+    resource_usage: Option<StepResourceUsage>,
+}
+
+/// 以原生方式执行 shell 命令，支持超时、取消与沙箱
+async fn run_native_command(
+    command: &str,
+    work_dir: &Path,
+    envs: &HashMap<String, String>,
+    timeout: Duration,
+    cancel: &CancellationToken,
+    sandbox: Option<&NativeSandbox>,
+) -> std::io::Result<NativeRun> {
+    let cgroup = match sandbox {
+        Some(sandbox) => sandbox.create_cgroup()?,
+        None => None,
+    };
+    let mut process = match sandbox {
+        Some(sandbox) => sandbox.command(command, cgroup.is_some()),
+        None => {
+            let mut process = tokio::process::Command::new("sh");
+            process.args(["-c", command]);
+            process
+        }
+    };
+    process
+        .current_dir(work_dir)
+        .envs(envs)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    if cgroup.is_some() {
+        process.stdin(std::process::Stdio::piped());
+    }
+
+    let Some(cgroup) = cgroup else {
+        return wait_native_command(process.spawn()?, timeout, cancel).await;
+    };
+    let result = match process.spawn() {
+        Ok(mut child) => match join_cgroup(&mut child, &cgroup).await {
+            Ok(()) => wait_native_command(child, timeout, cancel).await,
+            Err(e) => {
+                let _ = child.kill().await;
+                Err(e)
+            }
+        },
+        Err(e) => Err(e),
+    };
+    cgroup.release().await;
+    result
+}
+
+/// 将步骤进程加入 cgroup 后放行（shell 在读到一行输入前不执行命令）
+async fn join_cgroup(
+    child: &mut tokio::process::Child,
+    cgroup: &crate::sandbox::StepCgroup,
+) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let pid = child
+        .id()
+        .ok_or_else(|| std::io::Error::other("Step process exited before joining cgroup"))?;
+    cgroup.add_process(pid)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(b"\n").await?;
+    }
+    Ok(())
+}
+
+/// 等待原生命令结束，超时或取消时终止进程
+async fn wait_native_command(
+    mut child: tokio::process::Child,
+    timeout: Duration,
+    cancel: &CancellationToken,
+) -> std::io::Result<NativeRun> {
+    let sampler = child.id().map(ProcessTreeSampler::start);
+    let stdout = PipeReader::spawn(child.stdout.take());
+    let stderr = PipeReader::spawn(child.stderr.take());
+
+    let outcome = tokio::select! {
+        status = child.wait() => {
+            let status = status?;
+            NativeOutcome::Exited(std::process::Output {
+                status,
+                stdout: stdout.collect().await,
+                stderr: stderr.collect().await,
+            })
+        }
+        _ = tokio::time::sleep(timeout) => {
+            let _ = child.kill().await;
+            NativeOutcome::TimedOut
+        }
+        _ = cancel.cancelled() => {
+            let _ = child.kill().await;
+            NativeOutcome::Cancelled {
+                stdout: stdout.collect().await,
+                stderr: stderr.collect().await,
+            }
+        }
+    };
+
+    let resource_usage = match sampler {
+        Some(sampler) => Some(sampler.finish().await),
+        None => None,
+    };
+
+    Ok(NativeRun {
+        outcome,
+        resource_usage,
+    })
+}
+
+/// 后台读取子进程输出管道，进程被终止时仍可取回已读取的部分
+pub(crate) struct PipeReader {
+    buffer: Arc<std::sync::Mutex<Vec<u8>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PipeReader {
+    pub(crate) fn spawn<R>(pipe: Option<R>) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let buffer = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handle = pipe.map(|mut pipe| {
+            let buffer = buffer.clone();
+            tokio::spawn(async move {
+                let mut chunk = [0u8; 8192];
+                while let Ok(n) = pipe.read(&mut chunk).await {
+                    if n == 0 {
+                        break;
+                    }
+                    buffer.lock().unwrap().extend_from_slice(&chunk[..n]);
+                }
+            })
+        });
+        Self { buffer, handle }
+    }
+
+    /// 等待管道关闭（最多 PIPE_DRAIN_TIMEOUT）并返回读取到的内容
+    pub(crate) async fn collect(self) -> Vec<u8> {
+        if let Some(handle) = self.handle {
+            let abort = handle.abort_handle();
+            if tokio::time::timeout(PIPE_DRAIN_TIMEOUT, handle)
+                .await
+                .is_err()
+            {
+                abort.abort();
+            }
+        }
+        std::mem::take(&mut *self.buffer.lock().unwrap())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -975,6 +1578,8 @@ mod tests {
                 api_url: "http://localhost:3000".to_string(),
                 api_key: "test-key".to_string(),
                 heartbeat_interval_secs: 30,
+                enrollment_token: None,
+                credentials_file: None,
             },
             message_queue: crate::config::MessageQueueConfig {
                 amqp_url: "amqp://localhost:5672".to_string(),
@@ -990,6 +1595,13 @@ mod tests {
                 cleanup_workspace: true,
                 cache_dir: None,
                 docker: None,
+                log_ansi_mode: Default::default(),
+                known_hosts_file: None,
+                repo_cache: None,
+                state_dir: "/tmp/test-state".to_string(),
+                plugins: None,
+                docker_cache: Default::default(),
+                native_sandbox: None,
             },
         }
     }
@@ -1079,4 +1691,56 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_native_command_cancel_keeps_partial_output() {
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            trigger.cancel();
+        });
+
+        let outcome = run_native_command(
+            "echo started; exec sleep 30",
+            Path::new("/tmp"),
+            &HashMap::new(),
+            Duration::from_secs(30),
+            &cancel,
+            None,
+        )
+        .await
+        .unwrap()
+        .outcome;
+
+        match outcome {
+            NativeOutcome::Cancelled { stdout, .. } => {
+                assert_eq!(String::from_utf8_lossy(&stdout), "started\n");
+            }
+            other => panic!("Expected cancelled outcome, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_native_command_exit() {
+        let run = run_native_command(
+            "echo ok; exit 3",
+            Path::new("/tmp"),
+            &HashMap::new(),
+            Duration::from_secs(30),
+            &CancellationToken::new(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(run.resource_usage.is_some());
+        match run.outcome {
+            NativeOutcome::Exited(output) => {
+                assert_eq!(output.status.code(), Some(3));
+                assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+            }
+            other => panic!("Expected exited outcome, got {:?}", other),
+        }
+    }
 }
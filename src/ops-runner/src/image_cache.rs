@@ -0,0 +1,310 @@
+//! Docker 镜像管理
+//!
+//! - 按拉取策略确保步骤镜像可用，本地已有全部镜像层时视为缓存命中
+//! - 配置镜像加速地址时，Docker Hub 镜像经 pull-through 缓存拉取后打回原始标签，
+//!   加速地址不可用时直接从 Docker Hub 拉取
+//! - 后台定期预拉取常用镜像，任务开始时镜像层已在本地
+//! - 拉取耗时与缓存命中统计，随心跳上报控制面
+
+use anyhow::{anyhow, Result};
+use bollard::{
+    query_parameters::{CreateImageOptions, TagImageOptions},
+    Docker,
+};
+use futures_util::StreamExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::config::{DockerCacheConfig, ImagePullPolicy};
+use crate::messages::ImageCacheStats;
+
+/// 镜像拉取统计（进程级，Worker 重建后继续累计）
+struct ImageMetrics {
+    pulls: AtomicU64,
+    pull_failures: AtomicU64,
+    pull_time_ms: AtomicU64,
+    max_pull_time_ms: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+static METRICS: ImageMetrics = ImageMetrics {
+    pulls: AtomicU64::new(0),
+    pull_failures: AtomicU64::new(0),
+    pull_time_ms: AtomicU64::new(0),
+    max_pull_time_ms: AtomicU64::new(0),
+    cache_hits: AtomicU64::new(0),
+    cache_misses: AtomicU64::new(0),
+};
+
+/// 当前镜像拉取与缓存统计
+pub fn image_cache_stats() -> ImageCacheStats {
+    ImageCacheStats {
+        pulls: METRICS.pulls.load(Ordering::Relaxed),
+        pull_failures: METRICS.pull_failures.load(Ordering::Relaxed),
+        pull_time_ms: METRICS.pull_time_ms.load(Ordering::Relaxed),
+        max_pull_time_ms: METRICS.max_pull_time_ms.load(Ordering::Relaxed),
+        cache_hits: METRICS.cache_hits.load(Ordering::Relaxed),
+        cache_misses: METRICS.cache_misses.load(Ordering::Relaxed),
+    }
+}
+
+fn record_pull(elapsed: Duration, success: bool) {
+    let elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+    METRICS.pulls.fetch_add(1, Ordering::Relaxed);
+    METRICS
+        .pull_time_ms
+        .fetch_add(elapsed_ms, Ordering::Relaxed);
+    METRICS
+        .max_pull_time_ms
+        .fetch_max(elapsed_ms, Ordering::Relaxed);
+    if !success {
+        METRICS.pull_failures.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn record_cache(hit: bool) {
+    let counter = if hit {
+        &METRICS.cache_hits
+    } else {
+        &METRICS.cache_misses
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 拆分镜像引用为仓库与标签（未指定标签时为 latest，摘要引用返回 None）
+fn split_reference(image: &str) -> Option<(&str, &str)> {
+    if image.contains('@') {
+        return None;
+    }
+    let name_start = image.rfind('/').map_or(0, |i| i + 1);
+    match image[name_start..].rfind(':') {
+        Some(i) => Some((&image[..name_start + i], &image[name_start + i + 1..])),
+        None => Some((image, "latest")),
+    }
+}
+
+/// Docker Hub 镜像经加速地址拉取时使用的引用（其他仓库的镜像和摘要引用返回 None）
+pub fn mirrored_reference(image: &str, mirror: &str) -> Option<String> {
+    let mirror = mirror
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+    if mirror.is_empty() || image.contains('@') {
+        return None;
+    }
+
+    // 首段包含 `.`、`:` 或为 localhost 时是仓库地址
+    let path = match image.split_once('/') {
+        Some((host, rest)) if host.contains('.') || host.contains(':') || host == "localhost" => {
+            if host != "docker.io" && host != "registry-1.docker.io" {
+                return None;
+            }
+            rest
+        }
+        _ => image,
+    };
+    if path.contains('/') {
+        Some(format!("{}/{}", mirror, path))
+    } else {
+        Some(format!("{}/library/{}", mirror, path))
+    }
+}
+
+/// Docker 镜像管理器
+#[derive(Clone)]
+pub struct ImageManager {
+    docker: Docker,
+    config: DockerCacheConfig,
+}
+
+impl ImageManager {
+    pub fn new(docker: Docker, config: DockerCacheConfig) -> Self {
+        Self { docker, config }
+    }
+
+    /// 按拉取策略确保步骤镜像可用，并记录缓存命中
+    pub async fn ensure_image(&self, image: &str) -> Result<()> {
+        let present = self.docker.inspect_image(image).await.is_ok();
+        match self.config.pull_policy {
+            ImagePullPolicy::Never if !present => {
+                Err(anyhow!("Image {} is not present locally and pull policy is never", image))
+            }
+            ImagePullPolicy::Never | ImagePullPolicy::IfNotPresent if present => {
+                debug!("Using local image: {}", image);
+                record_cache(true);
+                Ok(())
+            }
+            _ => {
+                let downloaded = self.pull(image).await?;
+                record_cache(present && !downloaded);
+                Ok(())
+            }
+        }
+    }
+
+    /// 拉取镜像（配置了加速地址时优先经加速地址拉取），返回是否下载了新的镜像层
+    pub async fn pull(&self, image: &str) -> Result<bool> {
+        if let Some(mirrored) = self
+            .config
+            .registry_mirror
+            .as_deref()
+            .and_then(|mirror| mirrored_reference(image, mirror))
+        {
+            match self.pull_reference(&mirrored).await {
+                Ok(downloaded) => {
+                    self.tag(&mirrored, image).await?;
+                    return Ok(downloaded);
+                }
+                Err(e) => warn!("Failed to pull {} from mirror, pulling directly: {}", image, e),
+            }
+        }
+
+        self.pull_reference(image).await
+    }
+
+    /// 拉取指定引用并记录耗时
+    async fn pull_reference(&self, reference: &str) -> Result<bool> {
+        info!("Pulling Docker image: {}", reference);
+        let started = Instant::now();
+
+        let (from_image, tag) = match split_reference(reference) {
+            Some((repo, tag)) => (repo, Some(tag.to_string())),
+            None => (reference, None),
+        };
+        let options = CreateImageOptions {
+            from_image: Some(from_image.to_string()),
+            tag,
+            ..Default::default()
+        };
+
+        let mut downloaded = false;
+        let mut stream = self.docker.create_image(Some(options), None, None);
+        while let Some(next) = stream.next().await {
+            match next {
+                Ok(progress) => {
+                    if let Some(status) = progress.status {
+                        debug!("Pull progress: {}", status);
+                        if status.starts_with("Pull complete")
+                            || status.starts_with("Status: Downloaded newer image")
+                        {
+                            downloaded = true;
+                        }
+                    }
+                }
+                Err(e) => {
+                    record_pull(started.elapsed(), false);
+                    return Err(anyhow!("Failed to pull image {}: {}", reference, e));
+                }
+            }
+        }
+
+        let elapsed = started.elapsed();
+        record_pull(elapsed, true);
+        info!(
+            "Pulled image {} in {}ms (new layers: {})",
+            reference,
+            elapsed.as_millis(),
+            downloaded
+        );
+        Ok(downloaded)
+    }
+
+    /// 为经加速地址拉取的镜像打上原始标签，步骤容器仍按原始名称创建
+    async fn tag(&self, source: &str, target: &str) -> Result<()> {
+        let (repo, tag) = split_reference(target)
+            .ok_or_else(|| anyhow!("Cannot tag digest reference {}", target))?;
+        self.docker
+            .tag_image(
+                source,
+                Some(TagImageOptions {
+                    repo: Some(repo.to_string()),
+                    tag: Some(tag.to_string()),
+                }),
+            )
+            .await
+            .map_err(|e| anyhow!("Failed to tag {} as {}: {}", source, target, e))?;
+        Ok(())
+    }
+
+    /// 拉取全部预拉取镜像（单个镜像失败不影响其他镜像）
+    pub async fn prepull(&self) {
+        for image in &self.config.pre_pull {
+            if let Err(e) = self.pull(image).await {
+                warn!("Failed to pre-pull image {}: {}", image, e);
+            }
+        }
+    }
+}
+
+/// 启动后台预拉取任务（未配置预拉取镜像或无法连接 Docker 时不启动）
+pub fn spawn_prepull(config: DockerCacheConfig) -> Option<JoinHandle<()>> {
+    if config.pre_pull.is_empty() {
+        return None;
+    }
+    let docker = match Docker::connect_with_local_defaults() {
+        Ok(docker) => docker,
+        Err(e) => {
+            warn!("Image pre-pull disabled, failed to connect to Docker: {}", e);
+            return None;
+        }
+    };
+
+    let interval = config.prepull_interval_secs;
+    let manager = ImageManager::new(docker, config);
+    Some(tokio::spawn(async move {
+        loop {
+            manager.prepull().await;
+            info!("Pre-pulled {} image(s)", manager.config.pre_pull.len());
+            if interval == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_reference() {
+        assert_eq!(split_reference("rust:1.75"), Some(("rust", "1.75")));
+        assert_eq!(split_reference("node"), Some(("node", "latest")));
+        assert_eq!(
+            split_reference("registry.local:5000/team/app"),
+            Some(("registry.local:5000/team/app", "latest"))
+        );
+        assert_eq!(
+            split_reference("registry.local:5000/team/app:v2"),
+            Some(("registry.local:5000/team/app", "v2"))
+        );
+        assert_eq!(split_reference("rust@sha256:abc"), None);
+    }
+
+    #[test]
+    fn test_mirrored_reference() {
+        let mirror = "https://mirror.local:5000/";
+        assert_eq!(
+            mirrored_reference("rust:1.75", mirror),
+            Some("mirror.local:5000/library/rust:1.75".to_string())
+        );
+        assert_eq!(
+            mirrored_reference("bitnami/redis:7", mirror),
+            Some("mirror.local:5000/bitnami/redis:7".to_string())
+        );
+        assert_eq!(
+            mirrored_reference("docker.io/library/node:20", mirror),
+            Some("mirror.local:5000/library/node:20".to_string())
+        );
+        // 其他仓库的镜像与摘要引用直接拉取
+        assert_eq!(mirrored_reference("ghcr.io/org/tool:1", mirror), None);
+        assert_eq!(mirrored_reference("localhost/app", mirror), None);
+        assert_eq!(mirrored_reference("rust@sha256:abc", mirror), None);
+        assert_eq!(mirrored_reference("rust:1.75", ""), None);
+    }
+}
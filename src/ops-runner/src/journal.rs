@@ -0,0 +1,265 @@
+//! 进行中任务日志（崩溃恢复）
+//!
+//! 每个执行中的构建任务在状态目录下保留一个 JSON 日志（任务 ID、当前步骤、工作空间路径），
+//! 步骤推进时以临时文件 + rename 原子覆盖，任务结束后删除。
+//! Runner 启动时残留的日志说明上次进程异常退出，据此把中断的任务上报为基础设施失败，
+//! 避免控制面上的作业一直停留在运行中
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::messages::BuildTaskMessage;
+
+/// 日志文件扩展名
+const JOURNAL_EXTENSION: &str = "json";
+
+/// 进行中任务的日志记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub task_id: Uuid,
+    pub job_id: Uuid,
+    pub project: String,
+    pub workspace: PathBuf,
+    /// 正在执行的步骤序号（从 0 开始；尚未进入步骤阶段时为 None）
+    pub step_index: Option<usize>,
+    pub step_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl JournalEntry {
+    /// 上报中断任务时使用的错误信息
+    pub fn interruption_message(&self) -> String {
+        let stage = match (self.step_index, &self.step_id) {
+            (Some(index), Some(id)) => format!("step {} ({})", index + 1, id),
+            _ => "preparation".to_string(),
+        };
+        format!(
+            "Runner terminated unexpectedly during {}; task interrupted (workspace: {}, started at {})",
+            stage,
+            self.workspace.display(),
+            self.started_at.to_rfc3339()
+        )
+    }
+}
+
+/// 任务日志目录
+pub struct TaskJournal {
+    dir: PathBuf,
+}
+
+/// 执行中任务的日志句柄，Drop 时删除日志（任务已正常结束或已上报失败）
+pub struct ActiveTask<'a> {
+    journal: &'a TaskJournal,
+    entry: JournalEntry,
+}
+
+impl ActiveTask<'_> {
+    /// 记录开始执行的步骤
+    pub fn step(&mut self, index: usize, step_id: &str) {
+        self.entry.step_index = Some(index);
+        self.entry.step_id = Some(step_id.to_string());
+        self.entry.updated_at = Utc::now();
+        if let Err(e) = self.journal.write(&self.entry) {
+            warn!("Failed to update task journal for {}: {}", self.entry.task_id, e);
+        }
+    }
+}
+
+impl Drop for ActiveTask<'_> {
+    fn drop(&mut self) {
+        self.journal.remove(self.entry.task_id);
+    }
+}
+
+impl TaskJournal {
+    /// 打开日志目录（不存在时自动创建）
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).context("Failed to create runner state directory")?;
+        Ok(Self { dir })
+    }
+
+    /// 登记开始执行的任务
+    ///
+    /// 写入失败只记录警告，不影响构建本身
+    pub fn begin(&self, task: &BuildTaskMessage, workspace: &Path) -> ActiveTask<'_> {
+        let now = Utc::now();
+        let entry = JournalEntry {
+            task_id: task.task_id,
+            job_id: task.job_id,
+            project: task.project.name.clone(),
+            workspace: workspace.to_path_buf(),
+            step_index: None,
+            step_id: None,
+            started_at: now,
+            updated_at: now,
+        };
+        if let Err(e) = self.write(&entry) {
+            warn!("Failed to write task journal for {}: {}", entry.task_id, e);
+        }
+        ActiveTask {
+            journal: self,
+            entry,
+        }
+    }
+
+    /// 读取残留的任务日志（上次运行中断的任务）
+    ///
+    /// 无法解析的日志直接删除
+    pub fn pending(&self) -> Vec<JournalEntry> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to read runner state directory {:?}: {}", self.dir, e);
+                return Vec::new();
+            }
+        };
+
+        let mut pending = Vec::new();
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some(JOURNAL_EXTENSION) {
+                continue;
+            }
+            let parsed = fs::read(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| Ok(serde_json::from_slice::<JournalEntry>(&data)?));
+            match parsed {
+                Ok(entry) => pending.push(entry),
+                Err(e) => {
+                    warn!("Discarding unreadable task journal {:?}: {}", path, e);
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        pending.sort_by_key(|e| e.started_at);
+        pending
+    }
+
+    /// 删除任务日志
+    pub fn remove(&self, task_id: Uuid) {
+        let path = self.entry_path(task_id);
+        if let Err(e) = fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove task journal {:?}: {}", path, e);
+            }
+        }
+    }
+
+    fn entry_path(&self, task_id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.{}", task_id, JOURNAL_EXTENSION))
+    }
+
+    /// 原子写入：先写临时文件并落盘，再 rename 覆盖
+    fn write(&self, entry: &JournalEntry) -> Result<()> {
+        let path = self.entry_path(entry.task_id);
+        let tmp = path.with_extension("tmp");
+        let data = serde_json::to_vec(entry).context("Failed to serialize task journal")?;
+
+        let mut file = fs::File::create(&tmp).context("Failed to create task journal")?;
+        file.write_all(&data)
+            .and_then(|_| file.sync_all())
+            .context("Failed to write task journal")?;
+        fs::rename(&tmp, &path).context("Failed to commit task journal")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{BuildParameters, ProjectInfo};
+    use std::collections::HashMap;
+
+    fn journal() -> (TaskJournal, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("ops-runner-journal-{}", Uuid::new_v4()));
+        (TaskJournal::new(&dir).unwrap(), dir)
+    }
+
+    fn task() -> BuildTaskMessage {
+        BuildTaskMessage {
+            task_id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            project: ProjectInfo {
+                name: "app".to_string(),
+                repository_url: "https://git.example.com/team/app.git".to_string(),
+                branch: "main".to_string(),
+                commit: "abc123".to_string(),
+                triggered_by: Uuid::new_v4(),
+                credentials: None,
+                checkout: Default::default(),
+            },
+            build: BuildParameters {
+                build_type: "rust".to_string(),
+                env_vars: HashMap::new(),
+                parameters: HashMap::new(),
+            },
+            steps: vec![],
+            publish_target: None,
+        }
+    }
+
+    #[test]
+    fn test_active_task_is_removed_on_drop() {
+        let (journal, dir) = journal();
+        let task = task();
+
+        let mut active = journal.begin(&task, Path::new("/tmp/ws"));
+        active.step(1, "build");
+        let pending = journal.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].task_id, task.task_id);
+        assert_eq!(pending[0].step_index, Some(1));
+        assert_eq!(pending[0].step_id.as_deref(), Some("build"));
+
+        drop(active);
+        assert!(journal.pending().is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_leftover_journal_survives_crash() {
+        let (journal, dir) = journal();
+        let task = task();
+
+        // 模拟进程崩溃：登记后未执行 Drop
+        std::mem::forget(journal.begin(&task, Path::new("/tmp/ws")));
+        fs::write(dir.join("corrupt.json"), b"{").unwrap();
+
+        let reopened = TaskJournal::new(&dir).unwrap();
+        let pending = reopened.pending();
+        assert_eq!(pending.len(), 1);
+        assert!(pending[0]
+            .interruption_message()
+            .contains("during preparation"));
+        assert!(pending[0].interruption_message().contains("/tmp/ws"));
+        assert!(!dir.join("corrupt.json").exists());
+
+        reopened.remove(task.task_id);
+        assert!(reopened.pending().is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_interruption_message_names_step() {
+        let entry = JournalEntry {
+            task_id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            project: "app".to_string(),
+            workspace: PathBuf::from("/tmp/ws"),
+            step_index: Some(0),
+            step_id: Some("install".to_string()),
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert!(entry
+            .interruption_message()
+            .contains("during step 1 (install)"));
+    }
+}
@@ -5,15 +5,25 @@
 
 mod client;
 mod config;
+mod control;
 mod docker;
 mod executor;
+mod git;
+mod image_cache;
+mod journal;
 mod messages;
+mod plugin;
 mod publisher;
+mod repo_cache;
+mod resource;
+mod sandbox;
+mod service;
 mod worker;
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::Path;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time;
@@ -22,7 +32,11 @@ use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 use client::ControlPlaneClient;
 use config::RunnerConfig;
-use worker::TaskWorker;
+use control::RunnerControl;
+use journal::TaskJournal;
+use plugin::PluginRegistry;
+use sandbox::NativeSandbox;
+use worker::{RunningBuilds, TaskWorker};
 
 /// ops-runner - 构建作业执行代理
 #[derive(Parser, Debug)]
@@ -38,6 +52,14 @@ struct Args {
     /// 详细模式
     #[arg(short, long)]
     verbose: bool,
+
+    /// 生成并写入 systemd unit 后退出
+    #[arg(long)]
+    install_service: bool,
+
+    /// systemd unit 写入路径（配合 --install-service）
+    #[arg(long, default_value = service::DEFAULT_SERVICE_FILE)]
+    service_file: String,
 }
 
 fn print_version() {
@@ -54,6 +76,13 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args.install_service {
+        let path = service::install(Path::new(&args.service_file), args.config.as_deref())?;
+        println!("Installed systemd service: {}", path.display());
+        println!("Enable it with: systemctl daemon-reload && systemctl enable --now ops-runner");
+        return Ok(());
+    }
+
     // 初始化日志
     let log_level = if args.verbose {
         "debug".to_string()
@@ -89,6 +118,24 @@ async fn main() -> Result<()> {
     // 创建控制面客户端
     let mut client = ControlPlaneClient::new(config.clone());
 
+    // 探测已安装的插件，以 plugin/<name> 能力上报控制面
+    if let Some(plugin_config) = &config.execution.plugins {
+        let plugins = PluginRegistry::discover(plugin_config.clone()).await;
+        info!("Plugins: {:?}", plugins.capabilities());
+        client.set_plugin_capabilities(plugins.capabilities());
+    }
+
+    // 原生执行沙箱：主机不支持配置的隔离方式时拒绝启动，避免步骤在未隔离的情况下执行
+    if let Some(sandbox_config) = &config.execution.native_sandbox {
+        let sandbox = NativeSandbox::new(sandbox_config.clone())
+            .context("Native sandbox is not supported on this host")?;
+        info!("Native sandbox: {:?}", sandbox.capabilities());
+        client.set_sandbox_capabilities(sandbox.capabilities());
+    }
+
+    // 获取控制面凭据（必要时使用注册令牌换取）
+    client.ensure_credentials().await?;
+
     // 注册 Runner
     let mut config = config;
     let _runner_id = loop {
@@ -110,6 +157,11 @@ async fn main() -> Result<()> {
         }
     };
 
+    // 后台预拉取常用镜像（Docker 配置由控制面在注册时下发）
+    if config.runner.docker_supported && config.execution.is_docker_enabled() {
+        image_cache::spawn_prepull(config.execution.docker_cache.clone());
+    }
+
     // 获取心跳间隔
     let heartbeat_interval = config.heartbeat_interval();
 
@@ -119,17 +171,37 @@ async fn main() -> Result<()> {
     // 配置更新通知通道
     let _config_update_rx = client.config_update_receiver();
 
+    // 控制面下发的控制指令状态与运行中任务登记表（Worker 与心跳任务共享）
+    let control = Arc::new(RunnerControl::default());
+    let running = Arc::new(RunningBuilds::default());
+
     // 启动心跳任务
     let config_for_heartbeat = config.clone();
     let current_jobs_hb = current_jobs.clone();
+    let control_hb = control.clone();
+    let running_hb = running.clone();
     let heartbeat_handle = tokio::spawn(async move {
         let mut client = ControlPlaneClient::new(config_for_heartbeat);
         *client.current_jobs_mut() = current_jobs_hb;
+        client.set_running_builds(running_hb);
         let mut interval = time::interval(heartbeat_interval);
 
         loop {
-            interval.tick().await;
-            match client.send_heartbeat().await {
+            // 有待执行的指令或待回报的结果时立即发送心跳
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = control_hb.notified() => {}
+            }
+
+            for command in control_hb.take_deferred() {
+                match client.execute_control_command(command.action).await {
+                    Ok(result) => control_hb.ack(command.command_id, true, result),
+                    Err(e) => control_hb.ack(command.command_id, false, format!("{:#}", e)),
+                }
+            }
+
+            let acks = control_hb.take_acks();
+            match client.send_heartbeat(acks.clone()).await {
                 Ok(config_updated) => {
                     if config_updated {
                         info!("Docker configuration was updated from heartbeat");
@@ -137,19 +209,38 @@ async fn main() -> Result<()> {
                 }
                 Err(e) => {
                     error!("Heartbeat failed: {}", e);
+                    control_hb.restore_acks(acks);
                 }
             }
         }
     });
 
+    // 上次运行中断的任务：只在进程启动时读取一次，
+    // Worker 重建时不再扫描，避免把仍在执行的任务误报为中断
+    let journal = match TaskJournal::new(&config.execution.state_dir) {
+        Ok(journal) => Some(journal),
+        Err(e) => {
+            warn!("Failed to open task journal: {}", e);
+            None
+        }
+    };
+    let mut interrupted = journal.as_ref().map(|j| j.pending()).unwrap_or_default();
+    if !interrupted.is_empty() {
+        warn!("Found {} task(s) interrupted by a previous runner exit", interrupted.len());
+    }
+
     // 启动任务 Worker
     let config_arc = Arc::new(config);
     let worker_handle = tokio::spawn(async move {
         loop {
-            match TaskWorker::new(config_arc.clone()).await {
+            match TaskWorker::new(config_arc.clone(), running.clone(), control.clone()).await {
                 Ok(worker) => {
                     info!("Task worker started");
 
+                    if let Some(journal) = journal.as_ref() {
+                        worker.report_interrupted(journal, &mut interrupted).await;
+                    }
+
                     if let Err(e) = worker.run().await {
                         error!("Worker error: {}", e);
                     }
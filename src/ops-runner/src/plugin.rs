@@ -0,0 +1,655 @@
+//! 步骤插件
+//!
+//! 插件是插件目录中的外部可执行文件，文件名即插件名，构建步骤以 `plugin/<name>` 类型声明。
+//!
+//! 调用约定：Runner 向插件 stdin 写入一个 JSON 请求后关闭 stdin，插件在 stdout 输出一个
+//! JSON 响应后退出，stderr 作为步骤日志。请求的 `action` 字段为：
+//! - `describe`：启动时探测插件名称、版本与协议版本，探测成功的插件以 `plugin/<name>` 能力上报控制面
+//! - `execute`：执行步骤，响应为 `{"success": bool, "output": "...", "error": "...", "exit_code": 0}`
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::{PluginConfig, PluginSandboxConfig};
+use crate::executor::PipeReader;
+use crate::messages::{BuildStep, BuildTaskMessage, StepResourceUsage, PLUGIN_STEP_PREFIX};
+use crate::resource::ProcessTreeSampler;
+
+/// 插件协议版本
+pub const PLUGIN_PROTOCOL_VERSION: u32 = 1;
+
+/// describe 探测超时
+const DESCRIBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 插件请求（写入 stdin）
+#[derive(Debug, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum PluginRequest<'a> {
+    /// 探测插件元信息
+    Describe { protocol_version: u32 },
+    /// 执行构建步骤
+    Execute {
+        protocol_version: u32,
+        job_id: Uuid,
+        task_id: Uuid,
+        step: &'a BuildStep,
+        workspace: &'a Path,
+        work_dir: &'a Path,
+        /// 构建环境变量
+        env: &'a HashMap<String, String>,
+    },
+}
+
+/// describe 响应
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+    /// 插件名称（必须与文件名一致）
+    pub name: String,
+
+    #[serde(default)]
+    pub version: Option<String>,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// 插件实现的协议版本
+    #[serde(default = "default_protocol_version")]
+    pub protocol_version: u32,
+
+    /// 插件建议的步骤超时（秒）
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+fn default_protocol_version() -> u32 {
+    PLUGIN_PROTOCOL_VERSION
+}
+
+/// execute 响应
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginResponse {
+    /// 步骤是否成功
+    pub success: bool,
+
+    /// 步骤输出（发布为步骤日志）
+    #[serde(default)]
+    pub output: String,
+
+    /// 失败原因
+    #[serde(default)]
+    pub error: Option<String>,
+
+    /// 上报的退出码（未提供时使用插件进程退出码）
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+}
+
+impl PluginResponse {
+    /// 解析插件 stdout
+    pub fn parse(stdout: &[u8]) -> std::result::Result<Self, String> {
+        serde_json::from_slice(stdout).map_err(|e| format!("Invalid plugin response: {}", e))
+    }
+}
+
+/// 已发现的插件
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub path: PathBuf,
+    pub manifest: PluginManifest,
+}
+
+/// 插件调用结果
+#[derive(Debug)]
+pub enum PluginOutcome {
+    /// 插件进程已退出
+    Exited {
+        exit_code: Option<i32>,
+        stdout: Vec<u8>,
+    },
+    /// 执行超时，进程已被终止
+    TimedOut,
+    /// 构建被取消，进程已被终止
+    Cancelled,
+}
+
+/// 插件调用记录
+#[derive(Debug)]
+pub struct PluginRun {
+    pub outcome: PluginOutcome,
+    /// 插件 stderr（步骤日志）
+    pub stderr: String,
+    /// 进程树资源使用情况（无法获取 PID 时为空）
+    pub resource_usage: Option<StepResourceUsage>,
+}
+
+/// 插件注册表
+pub struct PluginRegistry {
+    config: PluginConfig,
+    plugins: HashMap<String, Plugin>,
+}
+
+impl PluginRegistry {
+    /// 扫描插件目录并探测每个插件
+    ///
+    /// 目录不存在、插件探测失败或协议版本不匹配时只记录警告，不影响 Runner 启动
+    pub async fn discover(config: PluginConfig) -> Self {
+        let mut registry = Self {
+            config,
+            plugins: HashMap::new(),
+        };
+
+        let entries = match std::fs::read_dir(&registry.config.dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to read plugins directory {}: {}", registry.config.dir, e);
+                return registry;
+            }
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| is_executable(path))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .filter(|name| is_valid_plugin_name(name))
+                .map(str::to_string)
+            else {
+                warn!("Skipping plugin with invalid name: {}", path.display());
+                continue;
+            };
+
+            match registry.describe(&path).await {
+                Ok(manifest) if manifest.name != name => {
+                    warn!(
+                        "Skipping plugin {}: describe returned name {:?}",
+                        path.display(),
+                        manifest.name
+                    );
+                }
+                Ok(manifest) if manifest.protocol_version != PLUGIN_PROTOCOL_VERSION => {
+                    warn!(
+                        "Skipping plugin {}: unsupported protocol version {} (expected {})",
+                        name, manifest.protocol_version, PLUGIN_PROTOCOL_VERSION
+                    );
+                }
+                Ok(manifest) => {
+                    info!(
+                        "Discovered plugin: {} {} {}",
+                        name,
+                        manifest.version.as_deref().unwrap_or(""),
+                        manifest.description.as_deref().unwrap_or("")
+                    );
+                    registry.plugins.insert(name, Plugin { path, manifest });
+                }
+                Err(e) => warn!("Failed to describe plugin {}: {:#}", path.display(), e),
+            }
+        }
+
+        registry
+    }
+
+    /// 按名称查找插件
+    pub fn get(&self, name: &str) -> Option<&Plugin> {
+        self.plugins.get(name)
+    }
+
+    /// 上报给控制面的能力标签（`plugin/<name>`）
+    pub fn capabilities(&self) -> Vec<String> {
+        let mut capabilities: Vec<String> = self
+            .plugins
+            .keys()
+            .map(|name| format!("{}{}", PLUGIN_STEP_PREFIX, name))
+            .collect();
+        capabilities.sort();
+        capabilities
+    }
+
+    /// 步骤超时：步骤配置 > 插件声明 > 插件默认配置 > Runner 步骤超时
+    pub fn timeout_for(&self, plugin: &Plugin, step: &BuildStep, default: Duration) -> Duration {
+        step.timeout_secs
+            .or(plugin.manifest.timeout_secs)
+            .or(self.config.timeout_secs)
+            .map(Duration::from_secs)
+            .unwrap_or(default)
+    }
+
+    /// 执行插件步骤
+    pub async fn execute(
+        &self,
+        plugin: &Plugin,
+        task: &BuildTaskMessage,
+        step: &BuildStep,
+        workspace: &Path,
+        timeout: Duration,
+        cancel: &CancellationToken,
+    ) -> std::io::Result<PluginRun> {
+        let work_dir = match &step.working_dir {
+            Some(dir) => workspace.join(dir),
+            None => workspace.to_path_buf(),
+        };
+        let request = PluginRequest::Execute {
+            protocol_version: PLUGIN_PROTOCOL_VERSION,
+            job_id: task.job_id,
+            task_id: task.task_id,
+            step,
+            workspace,
+            work_dir: &work_dir,
+            env: &task.build.env_vars,
+        };
+        self.invoke(&plugin.path, &request, &work_dir, &task.build.env_vars, timeout, cancel)
+            .await
+    }
+
+    /// 探测插件元信息
+    async fn describe(&self, path: &Path) -> Result<PluginManifest> {
+        let request = PluginRequest::Describe {
+            protocol_version: PLUGIN_PROTOCOL_VERSION,
+        };
+        let run = self
+            .invoke(
+                path,
+                &request,
+                Path::new(&self.config.dir),
+                &HashMap::new(),
+                DESCRIBE_TIMEOUT,
+                &CancellationToken::new(),
+            )
+            .await
+            .context("Failed to start plugin")?;
+
+        match run.outcome {
+            PluginOutcome::Exited {
+                exit_code: Some(0),
+                stdout,
+            } => serde_json::from_slice(&stdout).context("Invalid describe response"),
+            PluginOutcome::Exited { exit_code, .. } => {
+                anyhow::bail!("describe exited with {:?}: {}", exit_code, run.stderr.trim())
+            }
+            _ => anyhow::bail!("describe timed out after {:?}", DESCRIBE_TIMEOUT),
+        }
+    }
+
+    /// 启动插件进程，写入请求并等待退出，支持超时和取消
+    async fn invoke(
+        &self,
+        path: &Path,
+        request: &PluginRequest<'_>,
+        work_dir: &Path,
+        env: &HashMap<String, String>,
+        timeout: Duration,
+        cancel: &CancellationToken,
+    ) -> std::io::Result<PluginRun> {
+        let payload = serde_json::to_vec(request).map_err(std::io::Error::other)?;
+        let mut child = self.command(path, work_dir, env).spawn()?;
+
+        let sampler = child.id().map(ProcessTreeSampler::start);
+        let stdout = PipeReader::spawn(child.stdout.take());
+        let stderr = PipeReader::spawn(child.stderr.take());
+
+        // 后台写入请求，插件不读取 stdin 时不会阻塞等待
+        if let Some(mut stdin) = child.stdin.take() {
+            tokio::spawn(async move {
+                let _ = stdin.write_all(&payload).await;
+                let _ = stdin.shutdown().await;
+            });
+        }
+
+        let outcome = tokio::select! {
+            status = child.wait() => {
+                let status = status?;
+                PluginOutcome::Exited {
+                    exit_code: status.code(),
+                    stdout: stdout.collect().await,
+                }
+            }
+            _ = tokio::time::sleep(timeout) => {
+                let _ = child.kill().await;
+                PluginOutcome::TimedOut
+            }
+            _ = cancel.cancelled() => {
+                let _ = child.kill().await;
+                PluginOutcome::Cancelled
+            }
+        };
+
+        let stderr = String::from_utf8_lossy(&stderr.collect().await).into_owned();
+        let resource_usage = match sampler {
+            Some(sampler) => Some(sampler.finish().await),
+            None => None,
+        };
+
+        Ok(PluginRun {
+            outcome,
+            stderr,
+            resource_usage,
+        })
+    }
+
+    /// 按沙箱配置构造插件进程
+    fn command(
+        &self,
+        path: &Path,
+        work_dir: &Path,
+        env: &HashMap<String, String>,
+    ) -> tokio::process::Command {
+        let sandbox = &self.config.sandbox;
+        let argv = sandbox_argv(sandbox, path);
+
+        let mut command = tokio::process::Command::new(&argv[0]);
+        command.args(&argv[1..]).current_dir(work_dir);
+        if sandbox.clear_env {
+            command.env_clear();
+            for key in &sandbox.env_allowlist {
+                if let Some(value) = std::env::var_os(key) {
+                    command.env(key, value);
+                }
+            }
+        }
+        command
+            .envs(env)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+        command
+    }
+}
+
+/// 插件进程命令行：网络隔离 -> 包装命令 -> 插件本身
+fn sandbox_argv(sandbox: &PluginSandboxConfig, plugin: &Path) -> Vec<OsString> {
+    let mut argv: Vec<OsString> = Vec::new();
+    if sandbox.isolate_network {
+        argv.extend(["unshare", "--net", "--map-root-user"].map(OsString::from));
+    }
+    argv.extend(sandbox.wrapper.iter().map(OsString::from));
+    argv.push(plugin.as_os_str().to_owned());
+    argv
+}
+
+/// 插件名只允许小写字母、数字、`-` 与 `_`，且以字母或数字开头（排除隐藏文件）
+///
+/// 控制面解析步骤类型时统一转为小写，大写文件名无法被步骤引用
+fn is_valid_plugin_name(name: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    name.chars().next().is_some_and(allowed)
+        && name.chars().all(|c| allowed(c) || c == '-' || c == '_')
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::messages::{BuildParameters, ProjectInfo, StepType};
+    use std::os::unix::fs::PermissionsExt;
+
+    /// 测试插件：describe 返回清单，execute 回显构建环境变量
+    const ECHO_PLUGIN: &str = r#"#!/bin/sh
+request=$(cat)
+case "$request" in
+  *'"action":"describe"'*)
+    echo '{"name":"echo","version":"1.0.0","protocol_version":1,"timeout_secs":30}' ;;
+  *)
+    echo "running echo plugin" >&2
+    printf '{"success":true,"output":"greeting=%s secret=%s"}' "$GREETING" "$RUNNER_SECRET" ;;
+esac
+"#;
+
+    /// 测试插件：execute 时不响应
+    const SLOW_PLUGIN: &str = r#"#!/bin/sh
+request=$(cat)
+case "$request" in
+  *'"action":"describe"'*) echo '{"name":"slow"}' ;;
+  *) sleep 30 ;;
+esac
+"#;
+
+    fn write_plugin(dir: &Path, name: &str, content: &str, mode: u32) {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    /// 通过 `sh` 包装命令启动插件，避免刚写入的文件直接 exec 时的 ETXTBSY
+    fn plugin_config(dir: &Path) -> PluginConfig {
+        PluginConfig {
+            dir: dir.display().to_string(),
+            timeout_secs: Some(120),
+            sandbox: PluginSandboxConfig {
+                wrapper: vec!["sh".to_string()],
+                ..PluginSandboxConfig::default()
+            },
+        }
+    }
+
+    fn test_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ops-plugins-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn create_test_task() -> BuildTaskMessage {
+        BuildTaskMessage {
+            task_id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            project: ProjectInfo {
+                name: "app".to_string(),
+                repository_url: "https://git.example.com/team/app.git".to_string(),
+                branch: "main".to_string(),
+                commit: "abc123".to_string(),
+                triggered_by: Uuid::new_v4(),
+                credentials: None,
+                checkout: Default::default(),
+            },
+            build: BuildParameters {
+                build_type: "rust".to_string(),
+                env_vars: HashMap::from([("GREETING".to_string(), "hello".to_string())]),
+                parameters: HashMap::new(),
+            },
+            steps: vec![],
+            publish_target: None,
+        }
+    }
+
+    fn plugin_step(name: &str) -> BuildStep {
+        BuildStep {
+            id: "sign".to_string(),
+            name: "Sign".to_string(),
+            step_type: StepType::Custom(format!("plugin/{}", name)),
+            command: None,
+            script: None,
+            working_dir: None,
+            timeout_secs: None,
+            continue_on_failure: false,
+            retry: None,
+            produces_artifact: false,
+            artifact_platform: None,
+            docker_image: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover_skips_invalid_plugins() {
+        let dir = test_dir();
+        write_plugin(&dir, "echo", ECHO_PLUGIN, 0o755);
+        // 不可执行的文件不是插件
+        write_plugin(&dir, "README", "not a plugin", 0o644);
+        // describe 返回的名称与文件名不一致
+        write_plugin(&dir, "renamed", ECHO_PLUGIN, 0o755);
+        write_plugin(&dir, ".hidden", ECHO_PLUGIN, 0o755);
+        write_plugin(
+            &dir,
+            "future",
+            "#!/bin/sh\ncat >/dev/null\necho '{\"name\":\"future\",\"protocol_version\":2}'\n",
+            0o755,
+        );
+
+        let registry = PluginRegistry::discover(plugin_config(&dir)).await;
+        assert_eq!(registry.capabilities(), vec!["plugin/echo".to_string()]);
+        let plugin = registry.get("echo").unwrap();
+        assert_eq!(plugin.manifest.version.as_deref(), Some("1.0.0"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_discover_missing_directory() {
+        let dir = std::env::temp_dir().join(format!("ops-plugins-missing-{}", Uuid::new_v4()));
+        let registry = PluginRegistry::discover(plugin_config(&dir)).await;
+        assert!(registry.capabilities().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_uses_json_contract_and_clears_env() {
+        let dir = test_dir();
+        write_plugin(&dir, "echo", ECHO_PLUGIN, 0o755);
+        std::env::set_var("RUNNER_SECRET", "leaked");
+
+        let registry = PluginRegistry::discover(plugin_config(&dir)).await;
+        let plugin = registry.get("echo").unwrap();
+        let task = create_test_task();
+        let run = registry
+            .execute(
+                plugin,
+                &task,
+                &plugin_step("echo"),
+                &dir,
+                Duration::from_secs(10),
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap();
+
+        let PluginOutcome::Exited { exit_code, stdout } = run.outcome else {
+            panic!("plugin did not exit: {:?}", run.outcome);
+        };
+        assert_eq!(exit_code, Some(0));
+        let response = PluginResponse::parse(&stdout).unwrap();
+        assert!(response.success);
+        // 构建环境变量可见，Runner 自身的环境变量被清除
+        assert_eq!(response.output, "greeting=hello secret=");
+        assert!(run.stderr.contains("running echo plugin"));
+
+        std::env::remove_var("RUNNER_SECRET");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_execute_timeout_and_cancel() {
+        let dir = test_dir();
+        write_plugin(&dir, "slow", SLOW_PLUGIN, 0o755);
+
+        let registry = PluginRegistry::discover(plugin_config(&dir)).await;
+        let plugin = registry.get("slow").unwrap();
+        let task = create_test_task();
+        let step = plugin_step("slow");
+
+        let run = registry
+            .execute(
+                plugin,
+                &task,
+                &step,
+                &dir,
+                Duration::from_millis(200),
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(run.outcome, PluginOutcome::TimedOut));
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let run = registry
+            .execute(plugin, &task, &step, &dir, Duration::from_secs(10), &cancel)
+            .await
+            .unwrap();
+        assert!(matches!(run.outcome, PluginOutcome::Cancelled));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_precedence() {
+        let dir = test_dir();
+        write_plugin(&dir, "echo", ECHO_PLUGIN, 0o755);
+        let registry = PluginRegistry::discover(plugin_config(&dir)).await;
+        let plugin = registry.get("echo").unwrap().clone();
+        let default = Duration::from_secs(600);
+
+        let mut step = plugin_step("echo");
+        assert_eq!(registry.timeout_for(&plugin, &step, default), Duration::from_secs(30));
+        step.timeout_secs = Some(5);
+        assert_eq!(registry.timeout_for(&plugin, &step, default), Duration::from_secs(5));
+
+        let mut undeclared = plugin.clone();
+        undeclared.manifest.timeout_secs = None;
+        step.timeout_secs = None;
+        assert_eq!(registry.timeout_for(&undeclared, &step, default), Duration::from_secs(120));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_sandbox_argv() {
+        let plugin = Path::new("/opt/plugins/sign");
+        assert_eq!(
+            sandbox_argv(&PluginSandboxConfig::default(), plugin),
+            vec![OsString::from("/opt/plugins/sign")]
+        );
+
+        let sandbox = PluginSandboxConfig {
+            isolate_network: true,
+            wrapper: vec!["firejail".to_string(), "--quiet".to_string()],
+            ..PluginSandboxConfig::default()
+        };
+        let argv: Vec<String> = sandbox_argv(&sandbox, plugin)
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect();
+        assert_eq!(
+            argv,
+            vec![
+                "unshare",
+                "--net",
+                "--map-root-user",
+                "firejail",
+                "--quiet",
+                "/opt/plugins/sign"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plugin_name_validation() {
+        assert!(is_valid_plugin_name("codesign"));
+        assert!(is_valid_plugin_name("helm_3-deploy"));
+        assert!(!is_valid_plugin_name(".hidden"));
+        assert!(!is_valid_plugin_name("sign.sh"));
+        assert!(!is_valid_plugin_name("CodeSign"));
+        assert!(!is_valid_plugin_name(""));
+    }
+}
@@ -4,20 +4,164 @@ use anyhow::{Context, Result};
 use lapin::types::FieldTable;
 use lapin::types::ShortString;
 use lapin::{options::*, BasicProperties, Channel, ExchangeKind};
+use std::collections::HashMap;
+use std::sync::Mutex;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 use crate::config::RunnerConfig;
+use crate::journal::JournalEntry;
 use crate::messages::*;
+use common::error::ErrorKind;
+use common::terminal::{normalize_output, AnsiMode};
 
 fn short_string(value: impl Into<String>) -> ShortString {
     value.into().into()
 }
 
+/// 单个日志块在步骤日志中的位置
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct LogPosition {
+    chunk_index: u64,
+    offset: u64,
+}
+
+/// 日志序列分配器
+///
+/// 为每个任务/步骤分配连续的块序号和字节偏移，控制面据此做幂等写入和分段查询
+#[derive(Debug, Default)]
+struct LogSequencer {
+    positions: Mutex<HashMap<(Uuid, String), LogPosition>>,
+}
+
+impl LogSequencer {
+    /// 分配下一个日志块的位置
+    fn next(&self, task_id: Uuid, step_id: &str, len: usize) -> LogPosition {
+        let mut positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        let position = positions.entry((task_id, step_id.to_string())).or_default();
+        let current = *position;
+        position.chunk_index += 1;
+        position.offset += len as u64;
+        current
+    }
+
+    /// 任务结束后释放其全部步骤的序列状态
+    fn finish_task(&self, task_id: Uuid) {
+        let mut positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        positions.retain(|(id, _), _| *id != task_id);
+    }
+}
+
+/// 步骤的当前尝试
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct StepAttempt {
+    attempt: u32,
+    retrying: bool,
+}
+
+/// 步骤尝试记录
+///
+/// 记录每个任务/步骤当前的尝试序号，步骤状态消息据此携带尝试序号；结束状态按步骤的
+/// 重试策略判断是否还会重试，执行器依据同一判断决定是否重新执行
+#[derive(Debug, Default)]
+struct StepAttempts {
+    attempts: Mutex<HashMap<(Uuid, String), StepAttempt>>,
+}
+
+impl StepAttempts {
+    /// 开始下一次尝试，返回尝试序号（从 1 开始）
+    fn begin(&self, task_id: Uuid, step_id: &str) -> u32 {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        let current = attempts.entry((task_id, step_id.to_string())).or_default();
+        current.attempt += 1;
+        current.retrying = false;
+        current.attempt
+    }
+
+    /// 记录步骤状态，返回当前尝试序号（未开始过的步骤视为第 1 次）和是否还会重试
+    fn record(
+        &self,
+        task_id: Uuid,
+        step: &BuildStep,
+        status: &StepStatus,
+        exit_code: Option<i32>,
+    ) -> StepAttempt {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        let current = attempts.entry((task_id, step.id.clone())).or_default();
+        current.attempt = current.attempt.max(1);
+        current.retrying = step
+            .retry
+            .as_ref()
+            .is_some_and(|policy| policy.should_retry(current.attempt, status, exit_code));
+        *current
+    }
+
+    /// 当前尝试（未开始过的步骤视为第 1 次）
+    fn current(&self, task_id: Uuid, step_id: &str) -> StepAttempt {
+        let attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        let current = attempts
+            .get(&(task_id, step_id.to_string()))
+            .copied()
+            .unwrap_or_default();
+        StepAttempt {
+            attempt: current.attempt.max(1),
+            ..current
+        }
+    }
+
+    /// 任务结束后释放其全部步骤的尝试记录
+    fn finish_task(&self, task_id: Uuid) {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        attempts.retain(|(id, _), _| *id != task_id);
+    }
+}
+
+/// 消息序号分配器
+///
+/// 为状态与日志消息分配单调递增的序号（同一任务内必然递增），控制面据此丢弃重复投递和
+/// 过期的状态。序号不低于当前微秒时间戳，Runner 重启后仍大于之前发出的序号
+#[derive(Debug, Default)]
+struct MessageSequencer {
+    last: Mutex<u64>,
+}
+
+impl MessageSequencer {
+    /// 分配下一个序号
+    fn next(&self) -> u64 {
+        let floor = u64::try_from(chrono::Utc::now().timestamp_micros()).unwrap_or(0);
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        *last = (*last + 1).max(floor);
+        *last
+    }
+}
+
+/// 按 UTF-8 字符边界把日志切分为不超过 `max_len` 字节的块
+fn split_log_chunks(content: &str, max_len: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = content;
+    while rest.len() > max_len {
+        let mut end = max_len;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks.push(rest);
+    chunks
+}
+
 /// 消息发布器
 pub struct MessagePublisher {
     channel: Channel,
     runner_name: String,
     exchange: String,
+    log_sequencer: LogSequencer,
+    message_sequencer: MessageSequencer,
+    step_attempts: StepAttempts,
+    /// 日志 ANSI 序列处理方式
+    log_ansi_mode: AnsiMode,
 }
 
 impl MessagePublisher {
@@ -47,6 +191,10 @@ impl MessagePublisher {
             channel,
             runner_name,
             exchange,
+            log_sequencer: LogSequencer::default(),
+            message_sequencer: MessageSequencer::default(),
+            step_attempts: StepAttempts::default(),
+            log_ansi_mode: config.execution.log_ansi_mode,
         })
     }
 
@@ -58,24 +206,59 @@ impl MessagePublisher {
         step_status: Option<StepStatusUpdate>,
         error: Option<String>,
         error_category: Option<ErrorCategory>,
+    ) -> Result<()> {
+        self.publish_status(task.job_id, task.task_id, status, step_status, error, error_category)
+            .await
+    }
+
+    /// 上报已中断任务的失败状态（任务消息已不可用，仅凭 ID 上报）
+    pub async fn publish_interrupted(&self, entry: &JournalEntry) -> Result<()> {
+        let error = entry.interruption_message();
+        self.publish_status(
+            entry.job_id,
+            entry.task_id,
+            BuildStatus::Failed,
+            None,
+            Some(error.clone()),
+            Some(ErrorCategory::Infrastructure),
+        )
+        .await?;
+
+        warn!(
+            "Reported interrupted task: job={}, task={}, error={}",
+            entry.job_id, entry.task_id, error
+        );
+
+        Ok(())
+    }
+
+    async fn publish_status(
+        &self,
+        job_id: Uuid,
+        task_id: Uuid,
+        status: BuildStatus,
+        step_status: Option<StepStatusUpdate>,
+        error: Option<String>,
+        error_category: Option<ErrorCategory>,
     ) -> Result<()> {
         let status_str = format!("{:?}", status);
 
         let message = BuildStatusMessage {
-            task_id: task.task_id,
-            job_id: task.job_id,
+            task_id,
+            job_id,
             runner_name: self.runner_name.clone(),
             attempt_id: None,
             status: status.clone(),
             step_status,
             error,
             error_category,
+            sequence: self.message_sequencer.next(),
             timestamp: chrono::Utc::now(),
         };
 
         // 使用与控制面消费者一致的 routing key 格式: build.status.{job_id}.{task_id}
         // 控制面消费者绑定到 "build.status.#"
-        let routing_key = format!("build.status.{}.{}", task.job_id, task.task_id);
+        let routing_key = format!("build.status.{}.{}", job_id, task_id);
         let payload = serde_json::to_vec(&message).context("Failed to serialize status message")?;
 
         self.channel
@@ -91,14 +274,35 @@ impl MessagePublisher {
             .await
             .context("Failed to publish status message")?;
 
+        if matches!(
+            status,
+            BuildStatus::Succeeded
+                | BuildStatus::Failed
+                | BuildStatus::Timeout
+                | BuildStatus::Cancelled
+        ) {
+            self.log_sequencer.finish_task(task_id);
+            self.step_attempts.finish_task(task_id);
+        }
+
         debug!(
             "Published status: job={}, task={}, status={}, routing_key={}",
-            task.job_id, task.task_id, status_str, routing_key
+            job_id, task_id, status_str, routing_key
         );
 
         Ok(())
     }
 
+    /// 开始步骤的下一次尝试，返回尝试序号（从 1 开始）
+    pub fn begin_step_attempt(&self, task: &BuildTaskMessage, step: &BuildStep) -> u32 {
+        self.step_attempts.begin(task.task_id, &step.id)
+    }
+
+    /// 步骤最近发布的结束状态是否还会按重试策略重新执行
+    pub fn step_will_retry(&self, task: &BuildTaskMessage, step: &BuildStep) -> bool {
+        self.step_attempts.current(task.task_id, &step.id).retrying
+    }
+
     /// 发布步骤状态
     #[allow(clippy::too_many_arguments)]
     pub async fn publish_step_status(
@@ -110,8 +314,37 @@ impl MessagePublisher {
         completed_at: Option<chrono::DateTime<chrono::Utc>>,
         exit_code: Option<i32>,
         artifact: Option<BuildArtifact>,
+    ) -> Result<()> {
+        self.publish_step_status_with_usage(
+            task,
+            step,
+            step_status,
+            started_at,
+            completed_at,
+            exit_code,
+            artifact,
+            None,
+        )
+        .await
+    }
+
+    /// 发布步骤状态（附带资源使用情况）
+    #[allow(clippy::too_many_arguments)]
+    pub async fn publish_step_status_with_usage(
+        &self,
+        task: &BuildTaskMessage,
+        step: &BuildStep,
+        step_status: StepStatus,
+        started_at: chrono::DateTime<chrono::Utc>,
+        completed_at: Option<chrono::DateTime<chrono::Utc>>,
+        exit_code: Option<i32>,
+        artifact: Option<BuildArtifact>,
+        resource_usage: Option<StepResourceUsage>,
     ) -> Result<()> {
         let step_status_str = format!("{:?}", step_status);
+        let current = self
+            .step_attempts
+            .record(task.task_id, step, &step_status, exit_code);
 
         let step_update = StepStatusUpdate {
             step_id: step.id.clone(),
@@ -120,72 +353,67 @@ impl MessagePublisher {
             completed_at,
             exit_code,
             artifact,
+            resource_usage,
+            attempt: current.attempt,
+            retrying: current.retrying,
         };
 
         self.publish_build_status(task, BuildStatus::Running, Some(step_update), None, None)
             .await?;
 
         debug!(
-            "Published step status: job={}, task={}, step={}, status={}",
-            task.job_id, task.task_id, step.name, step_status_str
+            "Published step status: job={}, task={}, step={}, status={}, attempt={}",
+            task.job_id, task.task_id, step.name, step_status_str, current.attempt
         );
 
         Ok(())
     }
 
     /// 发布日志
+    ///
+    /// 内容先按配置规范化（ANSI 序列、控制字符），块序号和字节偏移由发布器按任务/步骤自动分配
     pub async fn publish_log(
         &self,
         task: &BuildTaskMessage,
         step: &BuildStep,
         content: &str,
         level: LogLevel,
-        offset: u64,
         is_final: bool,
     ) -> Result<()> {
         // 分割大日志以避免超过 RabbitMQ 消息大小限制
         const MAX_CHUNK_SIZE: usize = 256 * 1024; // 256KB
 
-        let content_bytes = content.as_bytes();
-
-        if content_bytes.len() <= MAX_CHUNK_SIZE {
-            self.publish_log_chunk(task, step, content, level.clone(), offset, is_final)
-                .await?;
-        } else {
-            // 分块发送
-            let chunks: Vec<&str> = content
-                .as_bytes()
-                .chunks(MAX_CHUNK_SIZE)
-                .map(|chunk| std::str::from_utf8(chunk).unwrap_or(""))
-                .collect();
-
-            let total_chunks = chunks.len();
-            for (i, chunk) in chunks.iter().enumerate() {
-                let is_last_chunk = i == total_chunks - 1;
-                let chunk_offset = offset + (i * MAX_CHUNK_SIZE) as u64;
-                self.publish_log_chunk(
-                    task,
-                    step,
-                    chunk,
-                    level.clone(),
-                    chunk_offset,
-                    is_final && is_last_chunk,
-                )
-                .await?;
-            }
+        let normalized = normalize_output(content, self.log_ansi_mode);
+        let chunks = split_log_chunks(&normalized.content, MAX_CHUNK_SIZE);
+        let total_chunks = chunks.len();
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let is_last_chunk = i == total_chunks - 1;
+            let position = self.log_sequencer.next(task.task_id, &step.id, chunk.len());
+            self.publish_log_chunk(
+                task,
+                step,
+                chunk,
+                level.clone(),
+                position,
+                normalized.modified,
+                is_final && is_last_chunk,
+            )
+            .await?;
         }
 
         Ok(())
     }
 
     /// 发布单个日志块
+    #[allow(clippy::too_many_arguments)]
     async fn publish_log_chunk(
         &self,
         task: &BuildTaskMessage,
         step: &BuildStep,
         content: &str,
         level: LogLevel,
-        offset: u64,
+        position: LogPosition,
+        content_modified: bool,
         is_final: bool,
     ) -> Result<()> {
         let message = BuildLogMessage {
@@ -196,9 +424,11 @@ impl MessagePublisher {
             attempt_id: None,
             level,
             content: content.to_string(),
-            offset,
-            chunk_index: 0,
+            content_modified,
+            offset: position.offset,
+            chunk_index: position.chunk_index,
             is_final,
+            sequence: self.message_sequencer.next(),
             timestamp: chrono::Utc::now(),
         };
 
@@ -222,25 +452,27 @@ impl MessagePublisher {
             .context("Failed to publish log message")?;
 
         debug!(
-            "Published log: job={}, task={}, step={}, bytes={}, offset={}, routing_key={}",
+            "Published log: job={}, task={}, step={}, bytes={}, chunk={}, offset={}, routing_key={}",
             task.job_id,
             task.task_id,
             step.name,
             content.len(),
-            offset,
+            position.chunk_index,
+            position.offset,
             routing_key
         );
 
         Ok(())
     }
 
-    /// 发布错误信息
+    /// 发布错误信息（错误分类由共享错误类型派生）
     pub async fn publish_error(
         &self,
         task: &BuildTaskMessage,
         error: &str,
-        category: ErrorCategory,
+        kind: ErrorKind,
     ) -> Result<()> {
+        let category = kind.category();
         let category_str = format!("{:?}", category);
 
         self.publish_build_status(
@@ -275,6 +507,9 @@ impl MessagePublisher {
             completed_at: Some(chrono::Utc::now()),
             exit_code: Some(0),
             artifact: Some(artifact.clone()),
+            resource_usage: None,
+            attempt: self.step_attempts.current(task.task_id, &step.id).attempt,
+            retrying: false,
         };
 
         let message = BuildStatusMessage {
@@ -286,6 +521,7 @@ impl MessagePublisher {
             step_status: Some(step_update),
             error: None,
             error_category: None,
+            sequence: self.message_sequencer.next(),
             timestamp: chrono::Utc::now(),
         };
 
@@ -584,4 +820,74 @@ mod tests {
         assert_eq!(result.size, 1024);
         assert_eq!(result.url, "https://storage.example.com/artifact.bin");
     }
+
+    #[test]
+    fn test_log_sequencer_assigns_per_step_positions() {
+        let sequencer = LogSequencer::default();
+        let task_id = Uuid::new_v4();
+
+        assert_eq!(sequencer.next(task_id, "build", 10), LogPosition::default());
+        assert_eq!(
+            sequencer.next(task_id, "build", 5),
+            LogPosition {
+                chunk_index: 1,
+                offset: 10
+            }
+        );
+        // 不同步骤独立计数
+        assert_eq!(sequencer.next(task_id, "test", 3), LogPosition::default());
+
+        sequencer.finish_task(task_id);
+        assert_eq!(sequencer.next(task_id, "build", 1), LogPosition::default());
+    }
+
+    #[test]
+    fn test_step_attempts_follow_retry_policy() {
+        let attempts = StepAttempts::default();
+        let task_id = Uuid::new_v4();
+        let step: BuildStep = serde_json::from_value(serde_json::json!({
+            "id": "test",
+            "name": "Test",
+            "step_type": "test",
+            "retry": {"max_attempts": 2}
+        }))
+        .unwrap();
+
+        // 未开始过的步骤（如被取消的剩余步骤）视为第 1 次
+        assert_eq!(attempts.current(task_id, "test").attempt, 1);
+
+        assert_eq!(attempts.begin(task_id, "test"), 1);
+        let current = attempts.record(task_id, &step, &StepStatus::Failed, Some(1));
+        assert!(current.retrying);
+        assert_eq!(attempts.begin(task_id, "test"), 2);
+        let current = attempts.record(task_id, &step, &StepStatus::Failed, Some(1));
+        assert_eq!(current.attempt, 2);
+        assert!(!current.retrying);
+
+        attempts.finish_task(task_id);
+        assert_eq!(attempts.begin(task_id, "test"), 1);
+    }
+
+    #[test]
+    fn test_message_sequencer_is_monotonic() {
+        let sequencer = MessageSequencer::default();
+        let before = chrono::Utc::now().timestamp_micros() as u64;
+
+        let first = sequencer.next();
+        let second = sequencer.next();
+        assert!(first >= before);
+        assert!(second > first);
+
+        // 新的分配器（Runner 重启）以时间戳为下限，不会回到更小的序号
+        assert!(MessageSequencer::default().next() >= before);
+    }
+
+    #[test]
+    fn test_split_log_chunks_respects_char_boundaries() {
+        let content = "日志日志";
+        let chunks = split_log_chunks(content, 4);
+        assert_eq!(chunks, vec!["日", "志", "日", "志"]);
+        assert_eq!(split_log_chunks("", 4), vec![""]);
+        assert_eq!(split_log_chunks("abc", 4), vec!["abc"]);
+    }
 }
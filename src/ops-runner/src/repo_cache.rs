@@ -0,0 +1,278 @@
+//! Git 仓库镜像缓存
+//!
+//! 每个仓库在 Runner 本地维护一个裸镜像（首次 `git clone --mirror`，之后增量 fetch），
+//! 工作空间从镜像本地克隆。同一仓库的并发构建通过锁文件串行访问镜像，
+//! 缓存总大小超过上限时按最近使用时间淘汰其他镜像
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{info, warn};
+
+use crate::config::RepoCacheConfig;
+
+/// 等待镜像锁时的轮询间隔
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 记录镜像最近使用时间的文件（位于镜像目录内）
+const LAST_USED_FILE: &str = "ops-last-used";
+
+/// 仓库镜像缓存
+pub struct RepoCache {
+    root: PathBuf,
+    max_size_bytes: u64,
+    lock_timeout: Duration,
+}
+
+/// 镜像锁，Drop 时释放
+pub struct MirrorLock {
+    path: PathBuf,
+}
+
+impl Drop for MirrorLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("Failed to release repository cache lock {:?}: {}", self.path, e);
+        }
+    }
+}
+
+impl RepoCache {
+    /// 创建缓存（目录不存在时自动创建）
+    pub fn new(config: &RepoCacheConfig) -> Result<Self> {
+        let root = PathBuf::from(&config.dir);
+        fs::create_dir_all(&root).context("Failed to create repository cache directory")?;
+
+        Ok(Self {
+            root,
+            max_size_bytes: config.max_size_mb.saturating_mul(1024 * 1024),
+            lock_timeout: Duration::from_secs(config.lock_timeout_secs),
+        })
+    }
+
+    /// 仓库对应的镜像目录
+    ///
+    /// 目录名由仓库名和地址哈希组成；调用方应传入已清除凭据的地址
+    pub fn mirror_path(&self, repository_url: &str) -> PathBuf {
+        let hash = hex::encode(Sha256::digest(repository_url.as_bytes()));
+        let name: String = repository_url
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.root.join(format!("{}-{}.git", name, &hash[..16]))
+    }
+
+    /// 获取镜像锁，最多等待 `lock_timeout`
+    ///
+    /// 锁文件存在超过 `lock_timeout` 时视为异常退出遗留的锁并清除
+    pub fn lock(&self, mirror: &Path) -> Result<MirrorLock> {
+        let deadline = Instant::now() + self.lock_timeout;
+        loop {
+            if let Some(lock) = self.try_lock(mirror)? {
+                return Ok(lock);
+            }
+            if Instant::now() >= deadline {
+                anyhow::bail!("Timed out waiting for repository cache lock {:?}", mirror);
+            }
+            std::thread::sleep(LOCK_POLL_INTERVAL);
+        }
+    }
+
+    /// 尝试获取镜像锁，已被占用时返回 None
+    fn try_lock(&self, mirror: &Path) -> Result<Option<MirrorLock>> {
+        let path = lock_path(mirror);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                let _ = writeln!(file, "{}", std::process::id());
+                Ok(Some(MirrorLock { path }))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let stale = fs::metadata(&path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age > self.lock_timeout);
+                if stale {
+                    warn!("Removing stale repository cache lock {:?}", path);
+                    let _ = fs::remove_file(&path);
+                }
+                Ok(None)
+            }
+            Err(e) => Err(e).context("Failed to create repository cache lock"),
+        }
+    }
+
+    /// 记录镜像最近使用时间
+    pub fn touch(&self, mirror: &Path) {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        if let Err(e) = fs::write(mirror.join(LAST_USED_FILE), now.to_string()) {
+            warn!("Failed to record repository cache usage for {:?}: {}", mirror, e);
+        }
+    }
+
+    /// 缓存超过大小上限时按最近使用时间淘汰镜像，返回淘汰的镜像数
+    ///
+    /// `keep` 为本次构建使用的镜像，不参与淘汰；正在被其他构建使用（已加锁）的镜像同样跳过
+    pub fn evict(&self, keep: &Path) -> Result<usize> {
+        let mut mirrors = Vec::new();
+        let mut total = 0u64;
+        for entry in fs::read_dir(&self.root).context("Failed to read repository cache")? {
+            let path = entry?.path();
+            if !path.is_dir() || path.extension().map_or(true, |ext| ext != "git") {
+                continue;
+            }
+            let size = dir_size(&path);
+            total += size;
+            if path != keep {
+                mirrors.push((last_used(&path), size, path));
+            }
+        }
+
+        if total <= self.max_size_bytes {
+            return Ok(0);
+        }
+
+        mirrors.sort_by_key(|(used, _, _)| *used);
+        let mut evicted = 0;
+        for (_, size, path) in mirrors {
+            if total <= self.max_size_bytes {
+                break;
+            }
+            let Some(_lock) = self.try_lock(&path)? else {
+                continue;
+            };
+            match fs::remove_dir_all(&path) {
+                Ok(()) => {
+                    info!("Evicted repository mirror {:?} ({} bytes)", path, size);
+                    total = total.saturating_sub(size);
+                    evicted += 1;
+                }
+                Err(e) => warn!("Failed to evict repository mirror {:?}: {}", path, e),
+            }
+        }
+
+        if total > self.max_size_bytes {
+            warn!(
+                "Repository cache still exceeds limit after eviction: {} > {} bytes",
+                total, self.max_size_bytes
+            );
+        }
+        Ok(evicted)
+    }
+}
+
+fn lock_path(mirror: &Path) -> PathBuf {
+    mirror.with_extension("lock")
+}
+
+/// 镜像最近使用时间（未记录时取目录修改时间）
+fn last_used(mirror: &Path) -> SystemTime {
+    fs::read_to_string(mirror.join(LAST_USED_FILE))
+        .ok()
+        .and_then(|content| content.trim().parse::<u64>().ok())
+        .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+        .or_else(|| fs::metadata(mirror).and_then(|m| m.modified()).ok())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// 计算目录大小（字节，不跟随符号链接）
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn cache(max_size_mb: u64, lock_timeout_secs: u64) -> (RepoCache, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("ops-runner-repo-cache-{}", Uuid::new_v4()));
+        let cache = RepoCache::new(&RepoCacheConfig {
+            dir: dir.to_string_lossy().to_string(),
+            max_size_mb,
+            lock_timeout_secs,
+        })
+        .unwrap();
+        (cache, dir)
+    }
+
+    #[test]
+    fn test_mirror_path_is_stable_per_repository() {
+        let (cache, dir) = cache(1, 1);
+        let a = cache.mirror_path("https://git.example.com/team/app.git");
+        assert_eq!(a, cache.mirror_path("https://git.example.com/team/app.git"));
+        assert_ne!(a, cache.mirror_path("https://git.example.com/other/app.git"));
+        assert!(a.file_name().unwrap().to_str().unwrap().starts_with("app-"));
+        assert!(cache
+            .mirror_path("git@git.example.com:team/api.git")
+            .to_str()
+            .unwrap()
+            .contains("/api-"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_lock_is_exclusive_and_released_on_drop() {
+        let (cache, dir) = cache(1, 1);
+        let mirror = cache.mirror_path("https://git.example.com/app.git");
+
+        let lock = cache.lock(&mirror).unwrap();
+        assert!(cache.try_lock(&mirror).unwrap().is_none());
+        drop(lock);
+        assert!(cache.try_lock(&mirror).unwrap().is_some());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_evict_removes_least_recently_used() {
+        let (cache, dir) = cache(1, 60);
+        let old = cache.mirror_path("https://git.example.com/old.git");
+        let busy = cache.mirror_path("https://git.example.com/busy.git");
+        let current = cache.mirror_path("https://git.example.com/current.git");
+        for mirror in [&old, &busy, &current] {
+            fs::create_dir_all(mirror).unwrap();
+            fs::write(mirror.join("pack"), vec![0u8; 600 * 1024]).unwrap();
+        }
+        fs::write(old.join(LAST_USED_FILE), "0").unwrap();
+        fs::write(busy.join(LAST_USED_FILE), "1").unwrap();
+        let _busy_lock = cache.lock(&busy).unwrap();
+
+        // 被加锁的镜像和本次使用的镜像不会被淘汰
+        assert_eq!(cache.evict(&current).unwrap(), 1);
+        assert!(!old.exists());
+        assert!(busy.exists());
+        assert!(current.exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
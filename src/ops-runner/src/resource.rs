@@ -0,0 +1,163 @@
+//! 步骤资源使用采样
+//!
+//! 原生模式下定期采样步骤进程树（根进程及其全部子孙进程）的内存、CPU 时间和磁盘写入量。
+//! 采样基于 /proc，进程退出后保留其最后一次观测值，因此结果是下限估计。
+
+use std::collections::HashMap;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::messages::StepResourceUsage;
+
+/// 采样间隔
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 单个进程的一次采样
+#[derive(Debug, Clone, Copy)]
+struct ProcessSample {
+    pid: Pid,
+    memory_bytes: u64,
+    cpu_time_ms: u64,
+    written_bytes: u64,
+}
+
+/// 资源使用累加器
+#[derive(Debug, Default)]
+struct UsageAccumulator {
+    peak_memory_bytes: u64,
+    /// 每个进程最后一次观测到的累计 CPU 时间和磁盘写入量
+    per_process: HashMap<Pid, (u64, u64)>,
+}
+
+impl UsageAccumulator {
+    /// 记录一次进程树采样
+    fn record(&mut self, samples: &[ProcessSample]) {
+        let tree_memory: u64 = samples.iter().map(|s| s.memory_bytes).sum();
+        self.peak_memory_bytes = self.peak_memory_bytes.max(tree_memory);
+
+        for sample in samples {
+            let entry = self.per_process.entry(sample.pid).or_default();
+            entry.0 = entry.0.max(sample.cpu_time_ms);
+            entry.1 = entry.1.max(sample.written_bytes);
+        }
+    }
+
+    fn usage(&self) -> StepResourceUsage {
+        StepResourceUsage {
+            peak_memory_bytes: self.peak_memory_bytes,
+            cpu_time_ms: self.per_process.values().map(|(cpu, _)| cpu).sum(),
+            disk_written_bytes: self.per_process.values().map(|(_, written)| written).sum(),
+        }
+    }
+}
+
+/// 进程树资源采样器
+pub struct ProcessTreeSampler {
+    stop: CancellationToken,
+    handle: JoinHandle<StepResourceUsage>,
+}
+
+impl ProcessTreeSampler {
+    /// 开始采样以 `root_pid` 为根的进程树
+    pub fn start(root_pid: u32) -> Self {
+        let stop = CancellationToken::new();
+        let stop_clone = stop.clone();
+        let root = Pid::from_u32(root_pid);
+
+        let handle = tokio::spawn(async move {
+            let mut system = System::new();
+            let mut accumulator = UsageAccumulator::default();
+            loop {
+                accumulator.record(&sample_tree(&mut system, root));
+
+                tokio::select! {
+                    _ = stop_clone.cancelled() => break,
+                    _ = tokio::time::sleep(SAMPLE_INTERVAL) => {}
+                }
+            }
+            accumulator.usage()
+        });
+
+        Self { stop, handle }
+    }
+
+    /// 停止采样并返回汇总结果
+    pub async fn finish(self) -> StepResourceUsage {
+        self.stop.cancel();
+        self.handle.await.unwrap_or_default()
+    }
+}
+
+/// 采样进程树中所有存活的进程
+fn sample_tree(system: &mut System, root: Pid) -> Vec<ProcessSample> {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing()
+            .with_memory()
+            .with_cpu()
+            .with_disk_usage(),
+    );
+
+    let processes = system.processes();
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (pid, process) in processes {
+        if let Some(parent) = process.parent() {
+            children.entry(parent).or_default().push(*pid);
+        }
+    }
+
+    let mut samples = Vec::new();
+    let mut pending = vec![root];
+    while let Some(pid) = pending.pop() {
+        if let Some(process) = processes.get(&pid) {
+            samples.push(ProcessSample {
+                pid,
+                memory_bytes: process.memory(),
+                cpu_time_ms: process.accumulated_cpu_time(),
+                written_bytes: process.disk_usage().total_written_bytes,
+            });
+        }
+        if let Some(kids) = children.get(&pid) {
+            pending.extend(kids.iter().copied());
+        }
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(pid: u32, memory_bytes: u64, cpu_time_ms: u64, written_bytes: u64) -> ProcessSample {
+        ProcessSample {
+            pid: Pid::from_u32(pid),
+            memory_bytes,
+            cpu_time_ms,
+            written_bytes,
+        }
+    }
+
+    #[test]
+    fn test_accumulator_keeps_exited_processes() {
+        let mut accumulator = UsageAccumulator::default();
+        accumulator.record(&[sample(1, 100, 10, 0), sample(2, 300, 5, 1000)]);
+        // 进程 2 已退出，进程 1 继续运行
+        accumulator.record(&[sample(1, 200, 30, 50)]);
+
+        let usage = accumulator.usage();
+        assert_eq!(usage.peak_memory_bytes, 400);
+        assert_eq!(usage.cpu_time_ms, 35);
+        assert_eq!(usage.disk_written_bytes, 1050);
+    }
+
+    #[tokio::test]
+    async fn test_sampler_observes_running_process() {
+        let sampler = ProcessTreeSampler::start(std::process::id());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let usage = sampler.finish().await;
+        assert!(usage.peak_memory_bytes > 0);
+    }
+}
@@ -0,0 +1,465 @@
+//! 原生执行沙箱
+//!
+//! Docker 不可用时，步骤在 Runner 主机上执行。沙箱按配置依次叠加：
+//! 网络命名空间（unshare）-> 资源上限（prlimit）-> 切换到专用用户（setpriv）-> shell。
+//! 配置了 cgroup 时每个步骤在独立的子 cgroup 中执行，结束后终止其中残留的进程。
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::NativeSandboxConfig;
+
+/// cgroup v2 CPU 配额周期（微秒）
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// 步骤 shell 在加入 cgroup 之前等待 Runner 的信号（从标准输入读取一行）
+const CGROUP_READY_PREFIX: &str = "read -r ops_cgroup_ready || exit 1\n";
+
+/// 原生执行沙箱
+#[derive(Debug)]
+pub struct NativeSandbox {
+    config: NativeSandboxConfig,
+    /// 步骤用户的 uid 与 gid
+    user_ids: Option<(u32, u32)>,
+    cgroup_root: Option<PathBuf>,
+}
+
+impl NativeSandbox {
+    /// 检查配置与主机支持情况（缺少工具、用户不存在或 cgroup 不可用时返回错误）
+    pub fn new(config: NativeSandboxConfig) -> Result<Self> {
+        if let Some(cpu) = config.cpu_limit {
+            if config.cgroup_root.is_none() {
+                bail!("Sandbox cpu_limit requires cgroup_root");
+            }
+            if cpu.is_nan() || cpu <= 0.0 {
+                bail!("Sandbox cpu_limit must be positive");
+            }
+        }
+        for (name, value) in [
+            ("memory_limit_mb", config.memory_limit_mb),
+            ("pids_limit", config.pids_limit),
+            ("open_files_limit", config.open_files_limit),
+        ] {
+            if value == Some(0) {
+                bail!("Sandbox {} must be positive", name);
+            }
+        }
+
+        let mut tools = vec!["sh"];
+        if config.isolate_network {
+            tools.push("unshare");
+        }
+        if config.user.is_some() {
+            tools.extend(["setpriv", "chown"]);
+        }
+        let sandbox = Self {
+            config,
+            user_ids: None,
+            cgroup_root: None,
+        };
+        if !sandbox.rlimit_args().is_empty() {
+            tools.push("prlimit");
+        }
+        for tool in tools {
+            if !in_path(tool) {
+                bail!("Sandbox requires `{}` in PATH", tool);
+            }
+        }
+
+        let user_ids = match &sandbox.config.user {
+            Some(user) => Some((lookup_id(user, "-u")?, lookup_id(user, "-g")?)),
+            None => None,
+        };
+        let cgroup_root = match &sandbox.config.cgroup_root {
+            Some(root) => Some(sandbox.prepare_cgroup_root(Path::new(root))?),
+            None => None,
+        };
+
+        Ok(Self {
+            user_ids,
+            cgroup_root,
+            ..sandbox
+        })
+    }
+
+    /// 上报给控制面的能力标签
+    pub fn capabilities(&self) -> Vec<String> {
+        let mut capabilities = vec!["sandbox/native".to_string()];
+        if self.config.user.is_some() {
+            capabilities.push("sandbox/user".to_string());
+        }
+        if self.config.cgroup_root.is_some() {
+            capabilities.push("sandbox/cgroup".to_string());
+        }
+        if self.config.cpu_limit.is_some()
+            || self.config.memory_limit_mb.is_some()
+            || self.config.pids_limit.is_some()
+            || self.config.open_files_limit.is_some()
+        {
+            capabilities.push("sandbox/limits".to_string());
+        }
+        if self.config.isolate_network {
+            capabilities.push("sandbox/network".to_string());
+        }
+        capabilities
+    }
+
+    /// 步骤环境变量：白名单中的 Runner 环境变量 + 构建环境变量
+    ///
+    /// 切换用户时 HOME 指向工作空间，避免写入 Runner 用户的主目录
+    pub fn step_env(
+        &self,
+        build_env: &HashMap<String, String>,
+        workspace: &Path,
+    ) -> HashMap<String, String> {
+        let mut env: HashMap<String, String> = self
+            .config
+            .env_allowlist
+            .iter()
+            .filter_map(|key| std::env::var(key).ok().map(|value| (key.clone(), value)))
+            .collect();
+        if self.config.user.is_some() {
+            env.insert("HOME".to_string(), workspace.display().to_string());
+        }
+        env.extend(build_env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        env
+    }
+
+    /// 将工作空间交给步骤用户（之前的步骤与 Git 检出以 Runner 用户身份写入）
+    pub async fn prepare_workspace(&self, workspace: &Path) -> Result<()> {
+        let Some((uid, gid)) = self.user_ids else {
+            return Ok(());
+        };
+        let output = tokio::process::Command::new("chown")
+            .arg("-R")
+            .arg(format!("{}:{}", uid, gid))
+            .arg(workspace)
+            .output()
+            .await
+            .context("Failed to run chown")?;
+        if !output.status.success() {
+            bail!(
+                "Failed to hand workspace to sandbox user: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// 构造沙箱中执行 shell 命令的进程（不继承 Runner 环境变量）
+    ///
+    /// `wait_for_cgroup` 时 shell 先从标准输入读取一行，Runner 将进程加入 cgroup 后再放行
+    pub fn command(&self, script: &str, wait_for_cgroup: bool) -> tokio::process::Command {
+        let script = if wait_for_cgroup {
+            format!("{}{}", CGROUP_READY_PREFIX, script)
+        } else {
+            script.to_string()
+        };
+        let argv = self.argv(&script);
+        let mut command = tokio::process::Command::new(&argv[0]);
+        command.args(&argv[1..]).env_clear();
+        command
+    }
+
+    /// 进程命令行：网络隔离 -> 资源上限 -> 切换用户 -> shell
+    fn argv(&self, script: &str) -> Vec<OsString> {
+        let mut argv: Vec<OsString> = Vec::new();
+        if self.config.isolate_network {
+            argv.extend(["unshare", "--net"].map(OsString::from));
+            // 非 root 的 Runner 需要用户命名空间才能创建网络命名空间
+            if self.config.user.is_none() {
+                argv.push("--map-root-user".into());
+            }
+        }
+        let rlimits = self.rlimit_args();
+        if !rlimits.is_empty() {
+            argv.push("prlimit".into());
+            argv.extend(rlimits.into_iter().map(OsString::from));
+        }
+        if let Some((uid, gid)) = self.user_ids {
+            argv.push("setpriv".into());
+            argv.push(format!("--reuid={}", uid).into());
+            argv.push(format!("--regid={}", gid).into());
+            argv.extend(["--init-groups", "--no-new-privs"].map(OsString::from));
+        }
+        argv.extend(["sh", "-c", script].map(OsString::from));
+        argv
+    }
+
+    /// prlimit 参数（由 cgroup 施加的限制不再重复设置）
+    fn rlimit_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(limit) = self.config.open_files_limit {
+            args.push(format!("--nofile={}", limit));
+        }
+        if self.config.cgroup_root.is_none() {
+            if let Some(limit) = self.config.pids_limit {
+                args.push(format!("--nproc={}", limit));
+            }
+            if let Some(limit) = self.config.memory_limit_mb {
+                args.push(format!("--as={}", limit * 1024 * 1024));
+            }
+        }
+        args
+    }
+
+    /// 子 cgroup 的限制文件与内容
+    fn cgroup_limits(&self) -> Vec<(&'static str, String)> {
+        let mut limits = Vec::new();
+        if let Some(cpu) = self.config.cpu_limit {
+            let quota = ((cpu * CPU_PERIOD_US as f64) as u64).max(1000);
+            limits.push(("cpu.max", format!("{} {}", quota, CPU_PERIOD_US)));
+        }
+        if let Some(limit) = self.config.memory_limit_mb {
+            limits.push(("memory.max", (limit * 1024 * 1024).to_string()));
+        }
+        if let Some(limit) = self.config.pids_limit {
+            limits.push(("pids.max", limit.to_string()));
+        }
+        limits
+    }
+
+    /// 检查 cgroup v2 目录并为子 cgroup 启用所需的控制器
+    fn prepare_cgroup_root(&self, root: &Path) -> Result<PathBuf> {
+        let controllers = std::fs::read_to_string(root.join("cgroup.controllers"))
+            .with_context(|| format!("{} is not a cgroup v2 directory", root.display()))?;
+        let mut enable = Vec::new();
+        for (file, _) in self.cgroup_limits() {
+            let controller = file.split('.').next().unwrap_or(file);
+            if !controllers.split_whitespace().any(|c| c == controller) {
+                bail!("cgroup controller {} is not available in {}", controller, root.display());
+            }
+            let entry = format!("+{}", controller);
+            if !enable.contains(&entry) {
+                enable.push(entry);
+            }
+        }
+        if !enable.is_empty() {
+            std::fs::write(root.join("cgroup.subtree_control"), enable.join(" ")).with_context(
+                || format!("Failed to enable cgroup controllers in {}", root.display()),
+            )?;
+        }
+        Ok(root.to_path_buf())
+    }
+
+    /// 为步骤创建子 cgroup（未配置 cgroup 时返回 None）
+    pub fn create_cgroup(&self) -> std::io::Result<Option<StepCgroup>> {
+        let Some(root) = &self.cgroup_root else {
+            return Ok(None);
+        };
+        let path = root.join(format!("step-{}", Uuid::new_v4()));
+        std::fs::create_dir(&path)?;
+        let cgroup = StepCgroup { path };
+        for (file, value) in self.cgroup_limits() {
+            if let Err(e) = std::fs::write(cgroup.path.join(file), value) {
+                let _ = std::fs::remove_dir(&cgroup.path);
+                return Err(e);
+            }
+        }
+        Ok(Some(cgroup))
+    }
+}
+
+/// 步骤所在的子 cgroup
+#[derive(Debug)]
+pub struct StepCgroup {
+    path: PathBuf,
+}
+
+impl StepCgroup {
+    /// 将进程加入 cgroup（之后派生的子进程随之位于该 cgroup）
+    pub fn add_process(&self, pid: u32) -> std::io::Result<()> {
+        std::fs::write(self.path.join("cgroup.procs"), pid.to_string())
+    }
+
+    /// 终止 cgroup 中残留的进程并删除 cgroup
+    pub async fn release(self) {
+        let _ = std::fs::write(self.path.join("cgroup.kill"), "1");
+        for _ in 0..20 {
+            if std::fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        warn!("Failed to remove step cgroup {}", self.path.display());
+    }
+}
+
+/// 查询用户的 uid（`-u`）或主组 gid（`-g`）
+fn lookup_id(user: &str, flag: &str) -> Result<u32> {
+    let output = std::process::Command::new("id")
+        .args([flag, user])
+        .output()
+        .context("Failed to run id")?;
+    if !output.status.success() {
+        bail!("Sandbox user {} does not exist", user);
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .with_context(|| format!("Unexpected id output for user {}", user))
+}
+
+fn in_path(tool: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(tool).is_file()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox(config: NativeSandboxConfig) -> NativeSandbox {
+        NativeSandbox {
+            cgroup_root: config.cgroup_root.as_ref().map(PathBuf::from),
+            config,
+            user_ids: None,
+        }
+    }
+
+    fn config() -> NativeSandboxConfig {
+        NativeSandboxConfig {
+            user: None,
+            cgroup_root: None,
+            cpu_limit: None,
+            memory_limit_mb: None,
+            pids_limit: None,
+            open_files_limit: None,
+            isolate_network: false,
+            env_allowlist: vec!["PATH".to_string()],
+        }
+    }
+
+    fn argv_strings(sandbox: &NativeSandbox, script: &str) -> Vec<String> {
+        sandbox
+            .argv(script)
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_argv_without_options() {
+        assert_eq!(argv_strings(&sandbox(config()), "make"), vec!["sh", "-c", "make"]);
+    }
+
+    #[test]
+    fn test_argv_with_user_limits_and_network() {
+        let mut sandbox = sandbox(NativeSandboxConfig {
+            user: Some("builder".to_string()),
+            memory_limit_mb: Some(512),
+            pids_limit: Some(256),
+            open_files_limit: Some(1024),
+            isolate_network: true,
+            ..config()
+        });
+        sandbox.user_ids = Some((1500, 1500));
+        assert_eq!(
+            argv_strings(&sandbox, "make"),
+            vec![
+                "unshare",
+                "--net",
+                "prlimit",
+                "--nofile=1024",
+                "--nproc=256",
+                "--as=536870912",
+                "setpriv",
+                "--reuid=1500",
+                "--regid=1500",
+                "--init-groups",
+                "--no-new-privs",
+                "sh",
+                "-c",
+                "make",
+            ]
+        );
+        assert_eq!(
+            sandbox.capabilities(),
+            vec![
+                "sandbox/native",
+                "sandbox/user",
+                "sandbox/limits",
+                "sandbox/network"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cgroup_limits_replace_rlimits() {
+        let sandbox = sandbox(NativeSandboxConfig {
+            cgroup_root: Some("/sys/fs/cgroup/ops-runner".to_string()),
+            cpu_limit: Some(1.5),
+            memory_limit_mb: Some(1024),
+            pids_limit: Some(128),
+            isolate_network: true,
+            ..config()
+        });
+        assert_eq!(
+            argv_strings(&sandbox, "make"),
+            vec!["unshare", "--net", "--map-root-user", "sh", "-c", "make"]
+        );
+        assert_eq!(
+            sandbox.cgroup_limits(),
+            vec![
+                ("cpu.max", "150000 100000".to_string()),
+                ("memory.max", "1073741824".to_string()),
+                ("pids.max", "128".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let err = NativeSandbox::new(NativeSandboxConfig {
+            cpu_limit: Some(2.0),
+            ..config()
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("cgroup_root"));
+
+        let err = NativeSandbox::new(NativeSandboxConfig {
+            pids_limit: Some(0),
+            ..config()
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("pids_limit"));
+    }
+
+    #[test]
+    fn test_step_env_excludes_runner_env() {
+        std::env::set_var("OPS_SANDBOX_TEST_SECRET", "secret");
+        let sandbox = NativeSandbox {
+            user_ids: Some((1500, 1500)),
+            ..sandbox(NativeSandboxConfig {
+                user: Some("builder".to_string()),
+                ..config()
+            })
+        };
+        let build_env = HashMap::from([("CI".to_string(), "true".to_string())]);
+        let env = sandbox.step_env(&build_env, Path::new("/work/job"));
+        assert!(!env.contains_key("OPS_SANDBOX_TEST_SECRET"));
+        assert_eq!(env.get("CI").map(String::as_str), Some("true"));
+        assert_eq!(env.get("HOME").map(String::as_str), Some("/work/job"));
+        std::env::remove_var("OPS_SANDBOX_TEST_SECRET");
+    }
+
+    #[tokio::test]
+    async fn test_command_runs_script() {
+        std::env::set_var("OPS_SANDBOX_TEST_VAR", "leaked");
+        let output = sandbox(config())
+            .command("echo ${OPS_SANDBOX_TEST_VAR:-unset}", false)
+            .env("PATH", std::env::var("PATH").unwrap_or_default())
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "unset\n");
+        std::env::remove_var("OPS_SANDBOX_TEST_VAR");
+    }
+}
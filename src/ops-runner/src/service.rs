@@ -0,0 +1,112 @@
+//! systemd 服务安装
+//!
+//! `--install-service` 根据当前可执行文件与配置文件生成 systemd unit 并写入目标路径，
+//! 之后由管理员执行 `systemctl daemon-reload && systemctl enable --now ops-runner`
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 默认 unit 文件路径
+pub const DEFAULT_SERVICE_FILE: &str = "/etc/systemd/system/ops-runner.service";
+
+/// systemd 管理的状态目录（StateDirectory=ops-runner）
+const SYSTEMD_STATE_DIR: &str = "/var/lib/ops-runner";
+
+/// 生成 systemd unit 内容
+///
+/// 未指定配置文件时从 /etc/ops-runner/env 读取环境变量配置，
+/// 并把任务日志放在 systemd 管理的状态目录下，保证重启后仍能发现中断的任务
+pub fn render_unit(executable: &Path, config: Option<&Path>) -> String {
+    let mut exec_start = executable.display().to_string();
+    if let Some(config) = config {
+        exec_start.push_str(&format!(" --config {}", config.display()));
+    }
+
+    let mut environment = vec!["Environment=\"RUST_LOG=info\"".to_string()];
+    if config.is_none() {
+        environment.push(format!("Environment=\"RUNNER_STATE_DIR={}/state\"", SYSTEMD_STATE_DIR));
+        environment.push("EnvironmentFile=-/etc/ops-runner/env".to_string());
+    }
+
+    format!(
+        "[Unit]
+Description=ops-runner build job execution agent
+After=network-online.target docker.service
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart={exec_start}
+Restart=always
+RestartSec=5
+# 停止时先通知主进程，留出时间上报状态
+KillMode=mixed
+TimeoutStopSec=30
+StateDirectory=ops-runner
+WorkingDirectory={state_dir}
+StandardOutput=journal
+StandardError=journal
+SyslogIdentifier=ops-runner
+LimitNOFILE=65536
+{environment}
+
+[Install]
+WantedBy=multi-user.target
+",
+        exec_start = exec_start,
+        state_dir = SYSTEMD_STATE_DIR,
+        environment = environment.join("\n"),
+    )
+}
+
+/// 写入 systemd unit，返回写入路径
+pub fn install(service_file: &Path, config: Option<&str>) -> Result<PathBuf> {
+    let executable = std::env::current_exe().context("Failed to resolve runner executable")?;
+    let config = config
+        .map(|path| fs::canonicalize(path).context(format!("Config file not found: {}", path)))
+        .transpose()?;
+
+    let unit = render_unit(&executable, config.as_deref());
+    if let Some(parent) = service_file.parent() {
+        fs::create_dir_all(parent).context("Failed to create service directory")?;
+    }
+    fs::write(service_file, unit)
+        .context(format!("Failed to write service file: {}", service_file.display()))?;
+    Ok(service_file.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_unit_with_config_file() {
+        let unit = render_unit(
+            Path::new("/usr/local/bin/ops-runner"),
+            Some(Path::new("/etc/ops-runner/runner.toml")),
+        );
+        assert!(unit
+            .contains("ExecStart=/usr/local/bin/ops-runner --config /etc/ops-runner/runner.toml"));
+        assert!(unit.contains("Restart=always"));
+        assert!(!unit.contains("EnvironmentFile"));
+        assert!(unit.ends_with("WantedBy=multi-user.target\n"));
+    }
+
+    #[test]
+    fn test_render_unit_from_env() {
+        let unit = render_unit(Path::new("/usr/local/bin/ops-runner"), None);
+        assert!(unit.contains("ExecStart=/usr/local/bin/ops-runner\n"));
+        assert!(unit.contains("EnvironmentFile=-/etc/ops-runner/env"));
+        assert!(unit.contains("RUNNER_STATE_DIR=/var/lib/ops-runner/state"));
+    }
+
+    #[test]
+    fn test_install_writes_unit() {
+        let dir = std::env::temp_dir().join(format!("ops-runner-service-{}", uuid::Uuid::new_v4()));
+        let path = install(&dir.join("ops-runner.service"), None).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("[Service]"));
+        assert!(install(&path, Some("/nonexistent/runner.toml")).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
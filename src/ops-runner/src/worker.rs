@@ -1,17 +1,24 @@
 //! 构建任务执行引擎
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use futures_util::StreamExt;
 use lapin::{options::*, Channel, Connection, ConnectionProperties, ExchangeKind, Queue};
 use lapin::types::ShortString;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tokio::sync::Semaphore;
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn, Instrument};
+use uuid::Uuid;
 
 use crate::config::RunnerConfig;
+use crate::control::RunnerControl;
 use crate::executor::BuildExecutor;
+use crate::journal::{JournalEntry, TaskJournal};
 use crate::messages::*;
 use crate::publisher::MessagePublisher;
+use common::error::ErrorKind;
 
 // 导入 lapin 的类型
 use lapin::types::FieldTable;
@@ -20,20 +27,108 @@ fn short_string(value: impl Into<String>) -> ShortString {
     value.into().into()
 }
 
+/// 从消息属性中提取控制面传递的 request_id
+///
+/// 优先读取 x-request-id 消息头，缺失时回退到 AMQP correlation_id
+fn extract_request_id(properties: &lapin::BasicProperties) -> Option<String> {
+    properties
+        .headers()
+        .as_ref()
+        .and_then(|headers| headers.inner().get(MessageHeaders::REQUEST_ID))
+        .and_then(|value| value.as_long_string())
+        .map(|value| value.to_string())
+        .or_else(|| {
+            properties
+                .correlation_id()
+                .as_ref()
+                .map(|id| id.to_string())
+        })
+}
+
+/// 正在执行的构建任务（按 job_id 索引）
+///
+/// 进程级共享：Worker 重建后仍在执行的任务可继续取消，并随心跳上报
+#[derive(Default)]
+pub struct RunningBuilds {
+    builds: Mutex<HashMap<Uuid, RunningBuild>>,
+}
+
+struct RunningBuild {
+    task_id: Uuid,
+    started_at: DateTime<Utc>,
+    token: CancellationToken,
+}
+
+impl RunningBuilds {
+    /// 登记构建任务并返回其取消令牌
+    pub fn register(&self, job_id: Uuid, task_id: Uuid) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.builds.lock().unwrap().insert(
+            job_id,
+            RunningBuild {
+                task_id,
+                started_at: Utc::now(),
+                token: token.clone(),
+            },
+        );
+        token
+    }
+
+    /// 构建结束后移除登记
+    pub fn remove(&self, job_id: &Uuid) {
+        self.builds.lock().unwrap().remove(job_id);
+    }
+
+    /// 取消构建任务，返回本 Runner 是否正在执行该任务
+    pub fn cancel(&self, job_id: &Uuid) -> bool {
+        match self.builds.lock().unwrap().get(job_id) {
+            Some(build) => {
+                build.token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 正在执行的任务（按开始时间排序）
+    pub fn snapshot(&self) -> Vec<RunningTaskInfo> {
+        let mut tasks: Vec<_> = self
+            .builds
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(job_id, build)| RunningTaskInfo {
+                job_id: *job_id,
+                task_id: build.task_id,
+                started_at: build.started_at,
+            })
+            .collect();
+        tasks.sort_by_key(|task| task.started_at);
+        tasks
+    }
+}
+
 /// 任务 Worker
 pub struct TaskWorker {
     #[allow(dead_code)]
     config: Arc<RunnerConfig>,
     channel: Channel,
     queue: Queue,
+    control_queue: Queue,
     executor: Arc<BuildExecutor>,
     publisher: Arc<MessagePublisher>,
     semaphore: Arc<Semaphore>,
+    running: Arc<RunningBuilds>,
+    control: Arc<RunnerControl>,
 }
 
 impl TaskWorker {
     /// 创建新的 Worker
-    pub async fn new(config: Arc<RunnerConfig>) -> Result<Self> {
+    pub async fn new(
+        config: Arc<RunnerConfig>,
+        running: Arc<RunningBuilds>,
+        control: Arc<RunnerControl>,
+    ) -> Result<Self> {
         // 连接到 RabbitMQ
         let conn =
             Connection::connect(&config.message_queue.amqp_url, ConnectionProperties::default())
@@ -116,6 +211,54 @@ impl TaskWorker {
             );
         }
 
+        // 声明控制队列（独占、随连接删除）
+        // 接收所有取消消息（按 job_id 过滤）及本 Runner 的控制指令
+        let control_queue_name = config.control_queue_name();
+        let control_queue = channel
+            .queue_declare(
+                short_string(control_queue_name.clone()),
+                QueueDeclareOptions {
+                    exclusive: true,
+                    auto_delete: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await
+            .context("Failed to declare control queue")?;
+
+        let cancel_routing_key = format!("{}.#", RoutingKeys::BUILD_CANCEL);
+        channel
+            .queue_bind(
+                short_string(control_queue_name.clone()),
+                short_string(config.message_queue.exchange.clone()),
+                short_string(cancel_routing_key.clone()),
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .context("Failed to bind control queue")?;
+        debug!(
+            "Bound control queue {} with routing key: {}",
+            control_queue_name, cancel_routing_key
+        );
+
+        let control_routing_key = config.control_routing_key();
+        channel
+            .queue_bind(
+                short_string(control_queue_name.clone()),
+                short_string(config.message_queue.exchange.clone()),
+                short_string(control_routing_key.clone()),
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .context("Failed to bind control queue (runner control)")?;
+        debug!(
+            "Bound control queue {} with routing key: {}",
+            control_queue_name, control_routing_key
+        );
+
         // 创建执行引擎
         let executor = Arc::new(BuildExecutor::new(config.clone())?);
 
@@ -129,9 +272,12 @@ impl TaskWorker {
             config,
             channel,
             queue,
+            control_queue,
             executor,
             publisher,
             semaphore,
+            running,
+            control,
         })
     }
 
@@ -139,8 +285,26 @@ impl TaskWorker {
     pub async fn run(&self) -> Result<()> {
         info!("Starting task worker");
 
-        // 创建消费者
-        let consumer = self
+        // 启动控制消息消费者
+        self.start_control_consumer().await?;
+
+        loop {
+            // 暂停期间不创建消费者，任务留在队列中由其他 Runner 或恢复后消费
+            if self.control.is_paused() {
+                info!("Build consumption paused, waiting for resume");
+                self.control.wait_paused(false).await;
+                info!("Build consumption resumed");
+            }
+
+            if !self.consume().await? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// 消费构建任务，直到暂停（返回 true）或消费流结束（返回 false）
+    async fn consume(&self) -> Result<bool> {
+        let mut consumer = self
             .channel
             .basic_consume(
                 self.queue.name().clone(),
@@ -153,45 +317,236 @@ impl TaskWorker {
 
         info!("Consumer created for queue: {}", self.queue.name());
 
-        // 处理消息
-        let mut consumer = consumer;
-        while let Some(delivery) = consumer.next().await {
+        loop {
+            let delivery = tokio::select! {
+                delivery = consumer.next() => delivery,
+                _ = self.control.wait_paused(true) => {
+                    self.stop_consuming(consumer).await?;
+                    return Ok(true);
+                }
+            };
+            let Some(delivery) = delivery else {
+                return Ok(false);
+            };
             let delivery = delivery.context("Failed to get delivery")?;
 
             // 获取信号量许可
             let permit = self.semaphore.clone().acquire_owned().await.unwrap();
 
+            // 等待许可期间收到暂停指令：退回任务
+            if self.control.is_paused() {
+                self.requeue(delivery.delivery_tag).await?;
+                continue;
+            }
+
             let executor = self.executor.clone();
             let publisher = self.publisher.clone();
             let channel = self.channel.clone();
+            let running = self.running.clone();
 
-            tokio::spawn(async move {
-                let task_id = delivery.routing_key.clone();
+            let request_id = extract_request_id(&delivery.properties);
+            let span = tracing::info_span!(
+                "build_task",
+                request_id = %request_id.as_deref().unwrap_or("-"),
+            );
 
-                // 处理消息
-                match Self::process_message(delivery, executor, publisher, channel).await {
-                    Ok(_) => {
-                        info!("Task processed successfully: {}", task_id);
+            tokio::spawn(
+                async move {
+                    let task_id = delivery.routing_key.clone();
+
+                    // 处理消息
+                    match Self::process_message(delivery, executor, publisher, channel, running)
+                        .await
+                    {
+                        Ok(_) => {
+                            info!("Task processed successfully: {}", task_id);
+                        }
+                        Err(e) => {
+                            error!("Failed to process task {}: {}", task_id, e);
+                        }
                     }
+
+                    // 释放许可
+                    drop(permit);
+                }
+                .instrument(span),
+            );
+        }
+    }
+
+    /// 取消消费者，已预取但尚未处理的任务退回队列
+    async fn stop_consuming(&self, mut consumer: lapin::Consumer) -> Result<()> {
+        self.channel
+            .basic_cancel(consumer.tag(), BasicCancelOptions::default())
+            .await
+            .context("Failed to cancel consumer")?;
+
+        while let Some(Ok(delivery)) = consumer.next().await {
+            self.requeue(delivery.delivery_tag).await?;
+        }
+
+        info!("Consumer cancelled for queue: {}", self.queue.name());
+        Ok(())
+    }
+
+    /// 将未处理的任务退回队列
+    async fn requeue(&self, delivery_tag: u64) -> Result<()> {
+        self.channel
+            .basic_nack(
+                delivery_tag,
+                BasicNackOptions {
+                    requeue: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .context("Failed to requeue task")
+    }
+
+    /// 将上次运行中断的任务上报为基础设施失败
+    ///
+    /// 上报成功的任务删除其日志，失败的保留在 `entries` 中等待 Worker 重建后重试
+    pub async fn report_interrupted(&self, journal: &TaskJournal, entries: &mut Vec<JournalEntry>) {
+        let mut remaining = Vec::new();
+        for entry in entries.drain(..) {
+            match self.publisher.publish_interrupted(&entry).await {
+                Ok(()) => journal.remove(entry.task_id),
+                Err(e) => {
+                    error!("Failed to report interrupted task {}: {}", entry.task_id, e);
+                    remaining.push(entry);
+                }
+            }
+        }
+        *entries = remaining;
+    }
+
+    /// 启动控制消息消费者
+    ///
+    /// 控制消息为非持久化的即时指令，解析后立即确认
+    async fn start_control_consumer(&self) -> Result<()> {
+        let mut consumer = self
+            .channel
+            .basic_consume(
+                self.control_queue.name().clone(),
+                short_string(""),
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .context("Failed to create control consumer")?;
+
+        info!("Control consumer created for queue: {}", self.control_queue.name());
+
+        let channel = self.channel.clone();
+        let running = self.running.clone();
+        let executor = self.executor.clone();
+        let control = self.control.clone();
+        tokio::spawn(async move {
+            while let Some(delivery) = consumer.next().await {
+                let delivery = match delivery {
+                    Ok(delivery) => delivery,
                     Err(e) => {
-                        error!("Failed to process task {}: {}", task_id, e);
+                        error!("Control consumer error: {}", e);
+                        break;
                     }
+                };
+
+                if delivery
+                    .routing_key
+                    .as_str()
+                    .starts_with(RoutingKeys::RUNNER_CONTROL)
+                {
+                    Self::handle_runner_control(&delivery.data, &executor, &control);
+                } else {
+                    Self::handle_control_message(&delivery.data, &running);
                 }
 
-                // 释放许可
-                drop(permit);
-            });
-        }
+                if let Err(e) = channel
+                    .basic_ack(delivery.delivery_tag, BasicAckOptions::default())
+                    .await
+                {
+                    error!("Failed to ack control message: {}", e);
+                }
+            }
+        });
 
         Ok(())
     }
 
+    /// 处理构建取消消息
+    fn handle_control_message(data: &[u8], running: &RunningBuilds) {
+        let message: BuildCancelMessage = match serde_json::from_slice(data) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Failed to parse control message: {}", e);
+                return;
+            }
+        };
+
+        if running.cancel(&message.job_id) {
+            info!(
+                "Cancelling build: job={}, reason={}",
+                message.job_id,
+                message.reason.as_deref().unwrap_or("-")
+            );
+        } else {
+            debug!("Ignoring cancel for job not running here: {}", message.job_id);
+        }
+    }
+
+    /// 处理控制面下发的 Runner 控制指令
+    ///
+    /// 需要控制面客户端的指令转交心跳任务执行，其余在此执行并记录结果
+    fn handle_runner_control(data: &[u8], executor: &BuildExecutor, control: &RunnerControl) {
+        let message: RunnerControlMessage = match serde_json::from_slice(data) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Failed to parse runner control message: {}", e);
+                return;
+            }
+        };
+
+        info!(
+            "Received runner control command: id={}, action={}",
+            message.command_id,
+            message.action.as_str()
+        );
+
+        let command_id = message.command_id;
+        match message.action {
+            RunnerControlAction::Pause => {
+                let result = match control.set_paused(true) {
+                    true => "Build consumption paused",
+                    false => "Build consumption already paused",
+                };
+                control.ack(command_id, true, result);
+            }
+            RunnerControlAction::Resume => {
+                let result = match control.set_paused(false) {
+                    true => "Build consumption resumed",
+                    false => "Build consumption was not paused",
+                };
+                control.ack(command_id, true, result);
+            }
+            RunnerControlAction::CleanupWorkspaces => match executor.cleanup_workspaces() {
+                Ok(()) => control.ack(command_id, true, "Workspace cleanup completed"),
+                Err(e) => {
+                    control.ack(command_id, false, format!("Workspace cleanup failed: {}", e))
+                }
+            },
+            RunnerControlAction::Reregister | RunnerControlAction::RefreshConfig => {
+                control.defer(message);
+            }
+        }
+    }
+
     /// 处理单条消息
     async fn process_message(
         delivery: lapin::message::Delivery,
         executor: Arc<BuildExecutor>,
         publisher: Arc<MessagePublisher>,
         channel: Channel,
+        running: Arc<RunningBuilds>,
     ) -> Result<()> {
         // 解析消息
         let task: BuildTaskMessage =
@@ -211,7 +566,13 @@ impl TaskWorker {
             .await?;
 
         // 执行构建
-        match executor.execute(task.clone(), publisher.as_ref()).await {
+        let cancel = running.register(task.job_id, task.task_id);
+        let result = executor
+            .execute(task.clone(), publisher.as_ref(), &cancel)
+            .await;
+        running.remove(&task.job_id);
+
+        match result {
             Ok(_) => {
                 info!("Build completed successfully");
             }
@@ -219,7 +580,7 @@ impl TaskWorker {
                 error!("Build failed: {}", e);
                 // 发送失败状态
                 let _ = publisher
-                    .publish_error(&task, &e.to_string(), ErrorCategory::Network)
+                    .publish_error(&task, &e.to_string(), classify_build_error(&e))
                     .await;
             }
         }
@@ -228,6 +589,14 @@ impl TaskWorker {
     }
 }
 
+/// 按错误链中的 IO 错误归类构建失败，其余视为执行失败
+fn classify_build_error(error: &anyhow::Error) -> ErrorKind {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .map_or(ErrorKind::Execution, |io| ErrorKind::from(io.kind()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -250,6 +619,8 @@ mod tests {
                 api_url: "http://localhost:3000".to_string(),
                 api_key: "test-key".to_string(),
                 heartbeat_interval_secs: 30,
+                enrollment_token: None,
+                credentials_file: None,
             },
             message_queue: crate::config::MessageQueueConfig {
                 amqp_url: "amqp://localhost:5672".to_string(),
@@ -265,6 +636,13 @@ mod tests {
                 cleanup_workspace: true,
                 cache_dir: None,
                 docker: None,
+                log_ansi_mode: Default::default(),
+                known_hosts_file: None,
+                repo_cache: None,
+                state_dir: "/tmp/test-state".to_string(),
+                plugins: None,
+                docker_cache: Default::default(),
+                native_sandbox: None,
             },
         }
     }
@@ -280,6 +658,8 @@ mod tests {
                 branch: "main".to_string(),
                 commit: "abc123".to_string(),
                 triggered_by: Uuid::new_v4(),
+                credentials: None,
+                checkout: Default::default(),
             },
             build: BuildParameters {
                 build_type: "node".to_string(),
@@ -299,13 +679,58 @@ mod tests {
                 working_dir: None,
                 timeout_secs: Some(300),
                 continue_on_failure: false,
+                retry: None,
                 produces_artifact: false,
+                artifact_platform: None,
                 docker_image: None,
             }],
             publish_target: None,
         }
     }
 
+    #[test]
+    fn test_extract_request_id() {
+        let mut headers = FieldTable::default();
+        headers.insert(
+            short_string(MessageHeaders::REQUEST_ID),
+            lapin::types::AMQPValue::LongString("req-header".into()),
+        );
+        let properties = lapin::BasicProperties::default()
+            .with_correlation_id(short_string("req-correlation"))
+            .with_headers(headers);
+        assert_eq!(extract_request_id(&properties), Some("req-header".to_string()));
+
+        // 无消息头时回退到 correlation_id
+        let properties =
+            lapin::BasicProperties::default().with_correlation_id(short_string("req-correlation"));
+        assert_eq!(extract_request_id(&properties), Some("req-correlation".to_string()));
+
+        assert_eq!(extract_request_id(&lapin::BasicProperties::default()), None);
+    }
+
+    #[test]
+    fn test_running_builds_cancel() {
+        let running = RunningBuilds::default();
+        let job_id = Uuid::new_v4();
+        let task_id = Uuid::new_v4();
+        let token = running.register(job_id, task_id);
+
+        let tasks = running.snapshot();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].job_id, job_id);
+        assert_eq!(tasks[0].task_id, task_id);
+
+        assert!(!running.cancel(&Uuid::new_v4()));
+        assert!(!token.is_cancelled());
+
+        assert!(running.cancel(&job_id));
+        assert!(token.is_cancelled());
+
+        running.remove(&job_id);
+        assert!(!running.cancel(&job_id));
+        assert!(running.snapshot().is_empty());
+    }
+
     #[test]
     fn test_task_message_creation() {
         let msg = create_test_task_message();
@@ -354,7 +779,9 @@ mod tests {
                 working_dir: None,
                 timeout_secs: None,
                 continue_on_failure: false,
+                retry: None,
                 produces_artifact: false,
+                artifact_platform: None,
                 docker_image: None,
             };
 
@@ -388,7 +815,9 @@ mod tests {
             working_dir: None,
             timeout_secs: None,
             continue_on_failure: true,
+            retry: None,
             produces_artifact: false,
+            artifact_platform: None,
             docker_image: None,
         };
 
@@ -413,7 +842,9 @@ mod tests {
             working_dir: None,
             timeout_secs: Some(600),
             continue_on_failure: false,
+            retry: None,
             produces_artifact: true,
+            artifact_platform: None,
             docker_image: Some("rust:1.75".to_string()),
         };
 
@@ -434,6 +865,8 @@ mod tests {
             branch: "develop".to_string(),
             commit: "def456".to_string(),
             triggered_by: Uuid::new_v4(),
+            credentials: None,
+            checkout: Default::default(),
         };
 
         assert_eq!(project.name, "my-project");
@@ -488,6 +921,7 @@ mod tests {
         assert_eq!(config.routing_key("rust"), "build.rust");
         assert_eq!(config.routing_key("java"), "build.java");
         assert_eq!(config.routing_key("python"), "build.python");
+        assert_eq!(config.control_routing_key(), "runner.control.test-worker");
     }
 
     #[test]
@@ -565,6 +999,7 @@ mod tests {
             ErrorCategory::Timeout,
             ErrorCategory::Resource,
             ErrorCategory::Permission,
+            ErrorCategory::Infrastructure,
             ErrorCategory::Unknown,
         ];
 
@@ -575,6 +1010,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_classify_build_error() {
+        let refused =
+            anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+                .context("Failed to pull image");
+        assert_eq!(classify_build_error(&refused), ErrorKind::Network);
+        assert!(matches!(classify_build_error(&refused).category(), ErrorCategory::Network));
+
+        let failed = anyhow::anyhow!("Step build failed with exit code 2");
+        assert_eq!(classify_build_error(&failed), ErrorKind::Execution);
+        assert!(matches!(classify_build_error(&failed).category(), ErrorCategory::Build));
+    }
+
     #[test]
     fn test_full_task_message_with_all_fields() {
         let task = create_test_task_message();
@@ -605,7 +1053,9 @@ mod tests {
                 working_dir: None,
                 timeout_secs: Some(300),
                 continue_on_failure: false,
+                retry: None,
                 produces_artifact: false,
+                artifact_platform: None,
                 docker_image: None,
             },
             BuildStep {
@@ -617,7 +1067,9 @@ mod tests {
                 working_dir: None,
                 timeout_secs: Some(600),
                 continue_on_failure: false,
+                retry: None,
                 produces_artifact: true,
+                artifact_platform: None,
                 docker_image: None,
             },
             BuildStep {
@@ -629,7 +1081,9 @@ mod tests {
                 working_dir: None,
                 timeout_secs: Some(300),
                 continue_on_failure: true,
+                retry: None,
                 produces_artifact: false,
+                artifact_platform: None,
                 docker_image: None,
             },
         ];
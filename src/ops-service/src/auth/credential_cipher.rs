@@ -0,0 +1,122 @@
+//! 凭证加密
+//!
+//! 落库的敏感凭证（TOTP 密钥等）使用 AES-256-GCM 加密。密钥取自
+//! security.credential_encryption_key，未配置时由 JWT 密钥派生；
+//! 密文格式为 `v1:` 加 Base64 编码的随机 nonce 与密文
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::SecurityConfig, error::AppError, middleware::webhook_hmac::compute_hmac_sha256,
+};
+
+/// 由 JWT 密钥派生加密密钥时使用的上下文
+const DERIVED_KEY_CONTEXT: &[u8] = b"ops-service/credential-encryption";
+
+/// 密文版本前缀
+const CIPHERTEXT_PREFIX: &str = "v1:";
+
+/// GCM nonce 长度（字节）
+const NONCE_BYTES: usize = 12;
+
+/// 凭证加解密
+#[derive(Clone)]
+pub struct CredentialCipher {
+    cipher: Aes256Gcm,
+}
+
+impl CredentialCipher {
+    /// 使用任意长度的密钥材料创建（经 SHA-256 得到 256 位密钥）
+    pub fn new(key_material: &[u8]) -> Self {
+        let key: [u8; 32] = Sha256::digest(key_material).into();
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        }
+    }
+
+    /// 按安全配置创建
+    pub fn from_config(config: &SecurityConfig) -> Self {
+        match &config.credential_encryption_key {
+            Some(key) => Self::new(key.expose_secret().as_bytes()),
+            None => Self::new(&compute_hmac_sha256(
+                config.jwt_secret.expose_secret().as_bytes(),
+                DERIVED_KEY_CONTEXT,
+            )),
+        }
+    }
+
+    /// 是否为本模块生成的密文（升级前保存的明文返回 false）
+    pub fn is_encrypted(value: &str) -> bool {
+        value.starts_with(CIPHERTEXT_PREFIX)
+    }
+
+    /// 加密明文
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, AppError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| AppError::Internal("Failed to encrypt credential".to_string()))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", CIPHERTEXT_PREFIX, STANDARD.encode(payload)))
+    }
+
+    /// 解密密文（密钥不匹配或数据被篡改时返回错误）
+    pub fn decrypt(&self, value: &str) -> Result<String, AppError> {
+        let invalid = || AppError::Internal("Invalid encrypted credential".to_string());
+        let payload = value
+            .strip_prefix(CIPHERTEXT_PREFIX)
+            .and_then(|encoded| STANDARD.decode(encoded).ok())
+            .filter(|payload| payload.len() > NONCE_BYTES)
+            .ok_or_else(invalid)?;
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_BYTES);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+        String::from_utf8(plaintext).map_err(|_| invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_uses_random_nonce() {
+        let cipher = CredentialCipher::new(b"credential-key");
+        let first = cipher.encrypt("JBSWY3DPEHPK3PXP").unwrap();
+        let second = cipher.encrypt("JBSWY3DPEHPK3PXP").unwrap();
+
+        assert!(CredentialCipher::is_encrypted(&first));
+        assert!(!first.contains("JBSWY3DPEHPK3PXP"));
+        assert_ne!(first, second);
+        assert_eq!(cipher.decrypt(&first).unwrap(), "JBSWY3DPEHPK3PXP");
+        assert_eq!(cipher.decrypt(&second).unwrap(), "JBSWY3DPEHPK3PXP");
+    }
+
+    #[test]
+    fn test_rejects_wrong_key_and_tampering() {
+        let cipher = CredentialCipher::new(b"credential-key");
+        let encrypted = cipher.encrypt("secret").unwrap();
+
+        assert!(CredentialCipher::new(b"other-key")
+            .decrypt(&encrypted)
+            .is_err());
+        let mut payload = STANDARD
+            .decode(&encrypted[CIPHERTEXT_PREFIX.len()..])
+            .unwrap();
+        payload[NONCE_BYTES] ^= 1;
+        let tampered = format!("{}{}", CIPHERTEXT_PREFIX, STANDARD.encode(payload));
+        assert!(cipher.decrypt(&tampered).is_err());
+        assert!(cipher.decrypt("JBSWY3DPEHPK3PXP").is_err());
+        assert!(!CredentialCipher::is_encrypted("JBSWY3DPEHPK3PXP"));
+    }
+}
@@ -1,10 +1,10 @@
 //! JWT token generation and validation
 //! Implements access token + refresh token pattern
 
+use super::signing_keys::KeyRing;
 use crate::{config::AppConfig, error::AppError};
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
-use secrecy::ExposeSecret;
+use jsonwebtoken::jwk::JwkSet;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -34,20 +34,35 @@ pub struct Claims {
 
     /// JWT ID (unique token identifier)
     pub jti: String,
+
+    /// Impersonator (set only on impersonation tokens)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<ImpersonatorClaims>,
+
+    /// Login session ID (absent on impersonation tokens and tokens issued before
+    /// session tracking)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
 }
 
-/// Token pair response
-#[derive(Debug, Serialize)]
-pub struct TokenPair {
-    pub access_token: String,
-    pub refresh_token: String,
-    pub expires_in: u64, // seconds until access token expires
+/// Identity of the administrator behind an impersonation token
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImpersonatorClaims {
+    /// Impersonator user ID
+    pub sub: String,
+
+    /// Impersonator username
+    pub username: String,
+
+    /// Impersonation session ID
+    pub session_id: String,
 }
 
+pub use common::api::TokenPair;
+
 /// JWT service
 pub struct JwtService {
-    encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    keys: KeyRing,
     access_token_exp_secs: u64,
     refresh_token_exp_secs: u64,
 }
@@ -55,19 +70,8 @@ pub struct JwtService {
 impl JwtService {
     /// Create JWT service from config
     pub fn from_config(config: &AppConfig) -> Result<Self, AppError> {
-        let secret = config.security.jwt_secret.expose_secret();
-
-        // Ensure secret is at least 32 bytes for HS256
-        if secret.len() < 32 {
-            return Err(AppError::Config("JWT secret too short (min 32 chars)".to_string()));
-        }
-
-        let encoding_key = EncodingKey::from_secret(secret.as_bytes());
-        let decoding_key = DecodingKey::from_secret(secret.as_bytes());
-
         Ok(Self {
-            encoding_key,
-            decoding_key,
+            keys: KeyRing::from_config(config)?,
             access_token_exp_secs: config.security.access_token_exp_secs,
             refresh_token_exp_secs: config.security.refresh_token_exp_secs,
         })
@@ -80,6 +84,17 @@ impl JwtService {
         username: &str,
         roles: Vec<String>,
         scopes: Vec<String>,
+    ) -> Result<String, AppError> {
+        self.session_access_token(user_id, username, roles, scopes, None)
+    }
+
+    fn session_access_token(
+        &self,
+        user_id: &Uuid,
+        username: &str,
+        roles: Vec<String>,
+        scopes: Vec<String>,
+        session_id: Option<Uuid>,
     ) -> Result<String, AppError> {
         let now = Utc::now();
         let expiration = now + Duration::seconds(self.access_token_exp_secs as i64);
@@ -93,9 +108,11 @@ impl JwtService {
             iat: now.timestamp(),
             exp: expiration.timestamp(),
             jti: Uuid::new_v4().to_string(),
+            impersonator: None,
+            sid: session_id.map(|id| id.to_string()),
         };
 
-        encode(&Header::default(), &claims, &self.encoding_key).map_err(|e| {
+        self.keys.encode(&claims, now).map_err(|e| {
             tracing::error!("Failed to encode access token: {:?}", e);
             AppError::Internal(format!("Failed to encode access token: {}", e))
         })
@@ -106,6 +123,15 @@ impl JwtService {
         &self,
         user_id: &Uuid,
         username: &str,
+    ) -> Result<String, AppError> {
+        self.session_refresh_token(user_id, username, None)
+    }
+
+    fn session_refresh_token(
+        &self,
+        user_id: &Uuid,
+        username: &str,
+        session_id: Option<Uuid>,
     ) -> Result<String, AppError> {
         let now = Utc::now();
         let expiration = now + Duration::seconds(self.refresh_token_exp_secs as i64);
@@ -119,14 +145,51 @@ impl JwtService {
             iat: now.timestamp(),
             exp: expiration.timestamp(),
             jti: Uuid::new_v4().to_string(),
+            impersonator: None,
+            sid: session_id.map(|id| id.to_string()),
         };
 
-        encode(&Header::default(), &claims, &self.encoding_key).map_err(|e| {
+        self.keys.encode(&claims, now).map_err(|e| {
             tracing::error!("Failed to encode refresh token: {:?}", e);
             AppError::Internal(format!("Failed to encode refresh token: {}", e))
         })
     }
 
+    /// Generate impersonation access token
+    ///
+    /// The token carries the target user's identity, roles and scopes, and is flagged
+    /// with the impersonator's identity. No refresh token is issued.
+    pub fn generate_impersonation_token(
+        &self,
+        user_id: &Uuid,
+        username: &str,
+        roles: Vec<String>,
+        scopes: Vec<String>,
+        impersonator: ImpersonatorClaims,
+        exp_secs: u64,
+    ) -> Result<String, AppError> {
+        let now = Utc::now();
+        let expiration = now + Duration::seconds(exp_secs as i64);
+
+        let claims = Claims {
+            sub: user_id.to_string(),
+            username: username.to_string(),
+            token_type: "access".to_string(),
+            roles,
+            scopes,
+            iat: now.timestamp(),
+            exp: expiration.timestamp(),
+            jti: Uuid::new_v4().to_string(),
+            impersonator: Some(impersonator),
+            sid: None,
+        };
+
+        self.keys.encode(&claims, now).map_err(|e| {
+            tracing::error!("Failed to encode impersonation token: {:?}", e);
+            AppError::Internal(format!("Failed to encode impersonation token: {}", e))
+        })
+    }
+
     /// Generate token pair
     pub fn generate_token_pair(
         &self,
@@ -135,9 +198,35 @@ impl JwtService {
         roles: Vec<String>,
         scopes: Vec<String>,
     ) -> Result<TokenPair, AppError> {
-        let access_token = self.generate_access_token(user_id, username, roles, scopes)?;
+        self.token_pair(user_id, username, roles, scopes, None)
+    }
+
+    /// Generate token pair bound to a login session
+    ///
+    /// Both tokens carry the session ID, so revoking the session invalidates them.
+    pub fn generate_session_token_pair(
+        &self,
+        user_id: &Uuid,
+        username: &str,
+        roles: Vec<String>,
+        scopes: Vec<String>,
+        session_id: Uuid,
+    ) -> Result<TokenPair, AppError> {
+        self.token_pair(user_id, username, roles, scopes, Some(session_id))
+    }
 
-        let refresh_token = self.generate_refresh_token(user_id, username)?;
+    fn token_pair(
+        &self,
+        user_id: &Uuid,
+        username: &str,
+        roles: Vec<String>,
+        scopes: Vec<String>,
+        session_id: Option<Uuid>,
+    ) -> Result<TokenPair, AppError> {
+        let access_token =
+            self.session_access_token(user_id, username, roles, scopes, session_id)?;
+
+        let refresh_token = self.session_refresh_token(user_id, username, session_id)?;
 
         Ok(TokenPair {
             access_token,
@@ -147,13 +236,17 @@ impl JwtService {
     }
 
     /// Validate and decode token
+    ///
+    /// Tokens signed by a retired key are accepted until its grace period ends.
     pub fn validate_token(&self, token: &str) -> Result<Claims, AppError> {
-        Ok(decode::<Claims>(token, &self.decoding_key, &Validation::new(Algorithm::HS256))
-            .map_err(|e| {
-                tracing::debug!("Token validation failed: {:?}", e);
-                AppError::Unauthorized
-            })?
-            .claims)
+        self.keys
+            .decode::<Claims>(token, Utc::now())
+            .ok_or(AppError::Unauthorized)
+    }
+
+    /// Public keys for other services to verify RS256 tokens
+    pub fn jwks(&self) -> JwkSet {
+        self.keys.jwks(Utc::now())
     }
 
     /// Validate access token specifically
@@ -201,6 +294,7 @@ mod tests {
                 idle_timeout_secs: 600,
                 max_lifetime_secs: 1800,
                 auto_create_if_missing: true,
+                auto_migrate: true,
             },
             logging: crate::config::LoggingConfig {
                 level: "info".to_string(),
@@ -213,6 +307,7 @@ mod tests {
                 rate_limit_rps: 100,
                 trust_proxy: true,
                 allowed_ips: None,
+                ip_allowlist: Default::default(),
                 password_min_length: 8,
                 password_require_uppercase: true,
                 password_require_digit: true,
@@ -221,6 +316,7 @@ mod tests {
                 login_lockout_duration_secs: 1800,
                 runner_api_key: None,
                 runner_webhook_hmac_secret: None,
+                credential_encryption_key: None,
                 runner_webhook_max_skew_secs: 300,
                 runner_webhook_nonce_ttl_secs: 600,
                 login_rate_limit_max_attempts: 10,
@@ -236,6 +332,7 @@ mod tests {
                 command_timeout_secs: 300,
                 host_key_verification: "accept".to_string(),
                 known_hosts_file: None,
+                max_output_bytes: 64 * 1024 * 1024,
             },
             concurrency: crate::config::ConcurrencyConfig {
                 global_limit: 100,
@@ -253,9 +350,35 @@ mod tests {
                 runner_exchange: "ops.runner".to_string(),
                 pool_size: 5,
                 publish_timeout_secs: 10,
+                reconnect_initial_backoff_ms: 500,
+                reconnect_max_backoff_secs: 30,
+                unavailable_threshold_secs: 60,
             },
             runner_docker: crate::config::RunnerDockerConfig::default(),
             metrics: crate::config::MetricsConfig::default(),
+            output: crate::config::OutputConfig::default(),
+            approval: crate::config::ApprovalConfig::default(),
+            stats: crate::config::StatsConfig::default(),
+            archive: crate::config::ArchiveConfig::default(),
+            anomaly: crate::config::AnomalyConfig::default(),
+            blob_store: crate::config::BlobStoreConfig::default(),
+            evidence: crate::config::EvidenceConfig::default(),
+            maintenance: crate::config::MaintenanceConfig::default(),
+            audit: crate::config::AuditConfig::default(),
+            job_budget: crate::config::JobBudgetConfig::default(),
+            network_backoff: crate::config::NetworkBackoffConfig::default(),
+            job_dispatch: crate::config::JobDispatchConfig::default(),
+            advisory: crate::config::AdvisoryConfig::default(),
+            artifact_promotion: crate::config::ArtifactPromotionConfig::default(),
+            load_test: crate::config::LoadTestConfig::default(),
+            i18n: crate::config::I18nConfig::default(),
+            notifications: crate::config::NotificationConfig::default(),
+            host_key_drift: crate::config::HostKeyDriftConfig::default(),
+            query_cache: crate::config::QueryCacheConfig::default(),
+            jwt: crate::config::JwtConfig::default(),
+            two_factor: crate::config::TwoFactorConfig::default(),
+            soft_delete: crate::config::SoftDeleteConfig::default(),
+            payload_limits: crate::config::PayloadLimitsConfig::default(),
         }
     }
 
@@ -310,6 +433,65 @@ mod tests {
         assert!(service.validate_access_token(&refresh_token).is_err());
     }
 
+    #[test]
+    fn test_impersonation_token_is_flagged() {
+        let service = JwtService::from_config(&test_config()).unwrap();
+        let target_id = Uuid::new_v4();
+        let impersonator = ImpersonatorClaims {
+            sub: Uuid::new_v4().to_string(),
+            username: "admin".to_string(),
+            session_id: Uuid::new_v4().to_string(),
+        };
+
+        let token = service
+            .generate_impersonation_token(
+                &target_id,
+                "alice",
+                vec!["viewer".to_string()],
+                vec!["job:read".to_string()],
+                impersonator.clone(),
+                300,
+            )
+            .unwrap();
+
+        let claims = service.validate_access_token(&token).unwrap();
+        assert_eq!(claims.sub, target_id.to_string());
+        assert_eq!(claims.impersonator, Some(impersonator));
+        assert!(claims.sid.is_none());
+        assert!(claims.exp - claims.iat <= 300);
+
+        // 普通令牌不携带模拟标记
+        let token = service
+            .generate_access_token(&target_id, "alice", vec![], vec![])
+            .unwrap();
+        assert!(service
+            .validate_access_token(&token)
+            .unwrap()
+            .impersonator
+            .is_none());
+    }
+
+    #[test]
+    fn test_session_token_pair_carries_session_id() {
+        let service = JwtService::from_config(&test_config()).unwrap();
+        let user_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+
+        let pair = service
+            .generate_session_token_pair(&user_id, "testuser", vec![], vec![], session_id)
+            .unwrap();
+        let access = service.validate_access_token(&pair.access_token).unwrap();
+        let refresh = service.validate_refresh_token(&pair.refresh_token).unwrap();
+        assert_eq!(access.sid, Some(session_id.to_string()));
+        assert_eq!(refresh.sid, Some(session_id.to_string()));
+
+        let pair = service
+            .generate_token_pair(&user_id, "testuser", vec![], vec![])
+            .unwrap();
+        let claims = service.validate_access_token(&pair.access_token).unwrap();
+        assert!(claims.sid.is_none());
+    }
+
     #[test]
     fn test_invalid_token_fails() {
         let service = JwtService::from_config(&test_config()).unwrap();
@@ -1,12 +1,21 @@
 //! JWT 认证中间件
 
-use crate::{auth::jwt::JwtService, error::AppError};
+use crate::{
+    auth::{
+        impersonation::{self, Impersonator},
+        jwt::{Claims, JwtService},
+    },
+    error::AppError,
+    middleware::AppState,
+    services::audit_service::{AuditAction, AuditLogParams},
+};
 use axum::{
     extract::{FromRequestParts, Request, State},
     http::HeaderMap,
     middleware::Next,
     response::Response,
 };
+use serde_json::json;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -17,6 +26,41 @@ pub struct AuthContext {
     pub username: String,
     pub roles: Vec<String>,
     pub scopes: Vec<String>,
+    /// 模拟令牌的签发者（非模拟请求为 None）
+    pub impersonator: Option<Impersonator>,
+    /// 令牌所属登录会话（模拟令牌为 None）
+    pub session_id: Option<Uuid>,
+}
+
+impl AuthContext {
+    /// 从已验证的访问令牌构建认证上下文
+    pub fn from_claims(claims: Claims) -> Result<Self, AppError> {
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Unauthorized)?;
+        let impersonator = claims
+            .impersonator
+            .map(|i| {
+                Ok::<_, AppError>(Impersonator {
+                    user_id: Uuid::parse_str(&i.sub).map_err(|_| AppError::Unauthorized)?,
+                    username: i.username,
+                    session_id: Uuid::parse_str(&i.session_id)
+                        .map_err(|_| AppError::Unauthorized)?,
+                })
+            })
+            .transpose()?;
+        let session_id = claims
+            .sid
+            .map(|sid| Uuid::parse_str(&sid).map_err(|_| AppError::Unauthorized))
+            .transpose()?;
+
+        Ok(Self {
+            user_id,
+            username: claims.username,
+            roles: claims.roles,
+            scopes: claims.scopes,
+            impersonator,
+            session_id,
+        })
+    }
 }
 
 // 实现 FromRequestParts 以便在 handler 中直接提取 AuthContext
@@ -60,13 +104,7 @@ pub async fn jwt_auth_middleware(
     let claims = jwt_service.validate_access_token(&token)?;
 
     // 创建认证上下文
-    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Unauthorized)?;
-    let auth_context = AuthContext {
-        user_id,
-        username: claims.username,
-        roles: claims.roles,
-        scopes: claims.scopes,
-    };
+    let auth_context = AuthContext::from_claims(claims)?;
 
     // 附加到请求扩展
     req.extensions_mut().insert(auth_context);
@@ -82,13 +120,7 @@ pub async fn optional_auth_middleware(
 ) -> Response {
     if let Ok(token) = extract_token(req.headers()) {
         if let Ok(claims) = jwt_service.validate_access_token(&token) {
-            if let Ok(user_id) = Uuid::parse_str(&claims.sub) {
-                let auth_context = AuthContext {
-                    user_id,
-                    username: claims.username,
-                    roles: claims.roles,
-                    scopes: claims.scopes,
-                };
+            if let Ok(auth_context) = AuthContext::from_claims(claims) {
                 req.extensions_mut().insert(auth_context);
             }
         }
@@ -97,6 +129,107 @@ pub async fn optional_auth_middleware(
     next.run(req).await
 }
 
+/// 登录会话校验中间件（需位于 JWT 认证中间件之后）
+///
+/// 拒绝所属会话已撤销或已过期的令牌。未关联会话的令牌无法撤销，一并拒绝
+/// （模拟令牌除外，由模拟请求中间件按模拟会话校验）；
+/// 会话登记之前签发的令牌须用刷新令牌换取绑定会话的新令牌
+pub async fn session_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(auth) = req.extensions().get::<AuthContext>() else {
+        return Ok(next.run(req).await);
+    };
+    if let Some(session_id) = auth.session_id {
+        if !state.auth_service.is_session_active(session_id).await? {
+            tracing::debug!(session_id = %session_id, "Rejected token of revoked session");
+            return Err(AppError::Unauthorized);
+        }
+    } else if auth.impersonator.is_none() {
+        tracing::debug!(user_id = %auth.user_id, "Rejected token without login session");
+        return Err(AppError::Unauthorized);
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// 模拟请求中间件（需位于 JWT 认证中间件之后）
+///
+/// 拒绝已终止或已过期会话的模拟令牌，在模拟者上下文中执行请求，
+/// 并以模拟者为主体额外记录一条请求审计
+pub async fn impersonation_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(auth_context) = req.extensions().get::<AuthContext>().cloned() else {
+        return Ok(next.run(req).await);
+    };
+    let Some(impersonator) = auth_context.impersonator.clone() else {
+        return Ok(next.run(req).await);
+    };
+
+    if !state
+        .auth_service
+        .is_impersonation_active(impersonator.session_id)
+        .await?
+    {
+        tracing::debug!(
+            session_id = %impersonator.session_id,
+            "Rejected token of ended impersonation session"
+        );
+        return Err(AppError::Unauthorized);
+    }
+
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let response = impersonation::scope(impersonator.clone(), next.run(req)).await;
+    let status = response.status();
+
+    let changes = json!({
+        "session_id": impersonator.session_id,
+        "impersonated_user_id": auth_context.user_id,
+        "method": method,
+        "path": path,
+        "status": status.as_u16(),
+    });
+    let summary = format!("{} {} as {}", method, path, auth_context.username);
+    let result = state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: impersonator.user_id,
+            subject_type: "user",
+            subject_name: Some(&impersonator.username),
+            action: AuditAction::UserImpersonatedRequest.as_str(),
+            resource_type: "user",
+            resource_id: Some(auth_context.user_id),
+            resource_name: Some(&auth_context.username),
+            changes: Some(changes),
+            changes_summary: Some(&summary),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: if status.is_client_error() || status.is_server_error() {
+                "failure"
+            } else {
+                "success"
+            },
+            error_message: None,
+        })
+        .await;
+    if let Err(e) = result {
+        tracing::warn!(
+            session_id = %impersonator.session_id,
+            "Failed to audit impersonated request: {}",
+            e
+        );
+    }
+
+    Ok(response)
+}
+
 /// 从扩展中提取 AuthContext 的辅助函数
 pub fn get_auth_context(req: &Request) -> Result<AuthContext, AppError> {
     req.extensions()
@@ -118,6 +251,41 @@ mod tests {
         assert_eq!(token, "test_token_123");
     }
 
+    #[test]
+    fn test_auth_context_from_impersonation_claims() {
+        let impersonator_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+        let claims = Claims {
+            sub: Uuid::new_v4().to_string(),
+            username: "alice".to_string(),
+            token_type: "access".to_string(),
+            roles: vec![],
+            scopes: vec![],
+            iat: 0,
+            exp: 0,
+            jti: Uuid::new_v4().to_string(),
+            impersonator: Some(crate::auth::jwt::ImpersonatorClaims {
+                sub: impersonator_id.to_string(),
+                username: "admin".to_string(),
+                session_id: session_id.to_string(),
+            }),
+            sid: None,
+        };
+
+        let context = AuthContext::from_claims(claims.clone()).unwrap();
+        let impersonator = context.impersonator.unwrap();
+        assert_eq!(impersonator.user_id, impersonator_id);
+        assert_eq!(impersonator.session_id, session_id);
+
+        let mut tampered = claims.clone();
+        tampered.impersonator.as_mut().unwrap().session_id = "not-a-uuid".to_string();
+        assert!(AuthContext::from_claims(tampered).is_err());
+
+        let mut tampered = claims;
+        tampered.sid = Some("not-a-uuid".to_string());
+        assert!(AuthContext::from_claims(tampered).is_err());
+    }
+
     #[test]
     fn test_extract_token_missing() {
         let headers = HeaderMap::new();
@@ -1,13 +1,23 @@
 //! Authentication and authorization module
 
 pub mod api_key;
+pub mod credential_cipher;
+pub mod impersonation;
 pub mod jwt;
 pub mod middleware;
 pub mod password;
+pub mod sessions;
+pub mod signing_keys;
+pub mod totp;
 
 pub use api_key::ApiKeyGenerator;
-pub use jwt::{Claims, JwtService, TokenPair};
+pub use credential_cipher::CredentialCipher;
+pub use impersonation::Impersonator;
+pub use jwt::{Claims, ImpersonatorClaims, JwtService, TokenPair};
 pub use middleware::{
-    extract_token, get_auth_context, jwt_auth_middleware, optional_auth_middleware, AuthContext,
+    extract_token, get_auth_context, impersonation_middleware, jwt_auth_middleware,
+    optional_auth_middleware, session_middleware, AuthContext,
 };
 pub use password::PasswordHasher;
+pub use sessions::SessionRegistry;
+pub use signing_keys::KeyRing;
@@ -152,6 +152,7 @@ mod tests {
                 idle_timeout_secs: 600,
                 max_lifetime_secs: 1800,
                 auto_create_if_missing: true,
+                auto_migrate: true,
             },
             logging: crate::config::LoggingConfig {
                 level: "info".to_string(),
@@ -164,6 +165,7 @@ mod tests {
                 rate_limit_rps: 100,
                 trust_proxy: true,
                 allowed_ips: None,
+                ip_allowlist: Default::default(),
                 password_min_length: 8,
                 password_require_uppercase: true,
                 password_require_digit: true,
@@ -172,6 +174,7 @@ mod tests {
                 login_lockout_duration_secs: 1800,
                 runner_api_key: None,
                 runner_webhook_hmac_secret: None,
+                credential_encryption_key: None,
                 runner_webhook_max_skew_secs: 300,
                 runner_webhook_nonce_ttl_secs: 600,
                 login_rate_limit_max_attempts: 10,
@@ -187,6 +190,7 @@ mod tests {
                 command_timeout_secs: 300,
                 host_key_verification: "accept".to_string(),
                 known_hosts_file: None,
+                max_output_bytes: 64 * 1024 * 1024,
             },
             concurrency: crate::config::ConcurrencyConfig {
                 global_limit: 100,
@@ -204,9 +208,35 @@ mod tests {
                 runner_exchange: "ops.runner".to_string(),
                 pool_size: 5,
                 publish_timeout_secs: 10,
+                reconnect_initial_backoff_ms: 500,
+                reconnect_max_backoff_secs: 30,
+                unavailable_threshold_secs: 60,
             },
             runner_docker: crate::config::RunnerDockerConfig::default(),
             metrics: crate::config::MetricsConfig::default(),
+            output: crate::config::OutputConfig::default(),
+            approval: crate::config::ApprovalConfig::default(),
+            stats: crate::config::StatsConfig::default(),
+            archive: crate::config::ArchiveConfig::default(),
+            anomaly: crate::config::AnomalyConfig::default(),
+            blob_store: crate::config::BlobStoreConfig::default(),
+            evidence: crate::config::EvidenceConfig::default(),
+            maintenance: crate::config::MaintenanceConfig::default(),
+            audit: crate::config::AuditConfig::default(),
+            job_budget: crate::config::JobBudgetConfig::default(),
+            network_backoff: crate::config::NetworkBackoffConfig::default(),
+            job_dispatch: crate::config::JobDispatchConfig::default(),
+            advisory: crate::config::AdvisoryConfig::default(),
+            artifact_promotion: crate::config::ArtifactPromotionConfig::default(),
+            load_test: crate::config::LoadTestConfig::default(),
+            i18n: crate::config::I18nConfig::default(),
+            notifications: crate::config::NotificationConfig::default(),
+            host_key_drift: crate::config::HostKeyDriftConfig::default(),
+            query_cache: crate::config::QueryCacheConfig::default(),
+            jwt: crate::config::JwtConfig::default(),
+            two_factor: crate::config::TwoFactorConfig::default(),
+            soft_delete: crate::config::SoftDeleteConfig::default(),
+            payload_limits: crate::config::PayloadLimitsConfig::default(),
         };
 
         // Valid password
@@ -0,0 +1,58 @@
+//! 登录会话登记
+//!
+//! 每次登录创建一条会话记录，访问令牌与刷新令牌通过 sid 声明关联到会话。
+//! 鉴权时校验会话是否已撤销：校验结果在本实例缓存一段时间，本实例发起的撤销立即清除缓存，
+//! 其他实例上的令牌最迟在缓存过期后失效
+
+use dashmap::DashMap;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::{error::AppError, repository::auth_repo::AuthRepository};
+
+/// 会话撤销原因
+pub const REVOKE_REASON_LOGOUT: &str = "logout";
+pub const REVOKE_REASON_LOGOUT_ALL: &str = "logout_all";
+pub const REVOKE_REASON_USER: &str = "user_revoked";
+pub const REVOKE_REASON_ADMIN: &str = "admin_revoked";
+pub const REVOKE_REASON_USER_DELETED: &str = "user_deleted";
+
+/// 会话有效性缓存
+pub struct SessionRegistry {
+    db: PgPool,
+    entries: DashMap<Uuid, (bool, Instant)>,
+    ttl: Duration,
+}
+
+impl SessionRegistry {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+    pub fn new(db: PgPool, ttl: Duration) -> Self {
+        Self {
+            db,
+            entries: DashMap::new(),
+            ttl,
+        }
+    }
+
+    /// 会话是否未撤销且未过期
+    pub async fn is_active(&self, session_id: Uuid) -> Result<bool, AppError> {
+        if let Some(entry) = self.entries.get(&session_id) {
+            if entry.1.elapsed() < self.ttl {
+                return Ok(entry.0);
+            }
+        }
+
+        let active = AuthRepository::new(self.db.clone())
+            .is_user_session_active(session_id)
+            .await?;
+        self.entries.insert(session_id, (active, Instant::now()));
+        Ok(active)
+    }
+
+    /// 使缓存的会话状态失效（撤销会话后调用）
+    pub fn invalidate(&self, session_id: Uuid) {
+        self.entries.remove(&session_id);
+    }
+}
@@ -0,0 +1,284 @@
+//! JWT 签名密钥环
+//!
+//! HS256 密钥按轮换周期由 jwt_secret 派生（kid 为 `hs-<周期秒数>-<周期序号>`），
+//! 各实例无需共享状态即可得到相同的密钥；未启用轮换时直接使用 jwt_secret（kid 为 `primary`）。
+//! RS256 密钥从私钥文件加载并按生效时间排序，生效最晚的密钥用于签名，公钥通过 JWKS 发布。
+//! 密钥被取代后，其签发的令牌在宽限期内仍被接受；已更换的旧 jwt_secret 在移出配置前一直可用于验证
+
+use chrono::{DateTime, Utc};
+use jsonwebtoken::jwk::{Jwk, JwkSet, PublicKeyUse};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use secrecy::ExposeSecret;
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Mutex;
+
+use crate::config::{AppConfig, JwtAlgorithm};
+use crate::error::AppError;
+use crate::middleware::webhook_hmac::compute_hmac_sha256;
+
+/// 未启用轮换时 HS256 密钥的 kid
+pub const PRIMARY_KID: &str = "primary";
+
+/// 派生 HS256 密钥的上下文
+const DERIVED_KEY_CONTEXT: &str = "ops-jwt-signing-key";
+
+/// 从私钥文件加载的 RS256 密钥
+struct RsaKey {
+    kid: String,
+    activates_at: DateTime<Utc>,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    /// 公钥（JWKS 发布用）
+    jwk: Jwk,
+}
+
+impl RsaKey {
+    fn from_pem(kid: &str, pem: &[u8], activates_at: DateTime<Utc>) -> Result<Self, AppError> {
+        let invalid = |e: jsonwebtoken::errors::Error| {
+            AppError::Config(format!("Invalid RS256 key '{}': {}", kid, e))
+        };
+        let encoding_key = EncodingKey::from_rsa_pem(pem).map_err(invalid)?;
+        let mut jwk = Jwk::from_encoding_key(&encoding_key, Algorithm::RS256).map_err(invalid)?;
+        jwk.common.key_id = Some(kid.to_string());
+        jwk.common.public_key_use = Some(PublicKeyUse::Signature);
+        let decoding_key = DecodingKey::from_jwk(&jwk).map_err(invalid)?;
+        Ok(Self {
+            kid: kid.to_string(),
+            activates_at,
+            encoding_key,
+            decoding_key,
+            jwk,
+        })
+    }
+}
+
+/// JWT 签名密钥环
+pub struct KeyRing {
+    /// 当前 jwt_secret 在前，其后为已更换的旧 jwt_secret
+    secrets: Vec<Vec<u8>>,
+    /// HS256 派生密钥的轮换周期（秒，0 表示不轮换）
+    rotation_interval_secs: i64,
+    grace_period_secs: i64,
+    algorithm: JwtAlgorithm,
+    /// 按生效时间升序
+    rsa_keys: Vec<RsaKey>,
+    /// 最近一次签名使用的 kid（用于记录轮换）
+    last_signing_kid: Mutex<Option<String>>,
+}
+
+impl KeyRing {
+    pub fn from_config(config: &AppConfig) -> Result<Self, AppError> {
+        let secret = config.security.jwt_secret.expose_secret();
+
+        // Ensure secret is at least 32 bytes for HS256
+        if secret.len() < 32 {
+            return Err(AppError::Config("JWT secret too short (min 32 chars)".to_string()));
+        }
+        let mut secrets = vec![secret.as_bytes().to_vec()];
+        secrets.extend(
+            config
+                .jwt
+                .retired_secret_list()
+                .into_iter()
+                .map(|s| s.as_bytes().to_vec()),
+        );
+
+        let mut rsa_keys = config
+            .jwt
+            .rsa_keys
+            .iter()
+            .map(|(kid, key)| {
+                let pem = std::fs::read(&key.private_key_file).map_err(|e| {
+                    AppError::Config(format!(
+                        "Failed to read RS256 key '{}' from {}: {}",
+                        kid, key.private_key_file, e
+                    ))
+                })?;
+                RsaKey::from_pem(kid, &pem, key.activates_at.unwrap_or(DateTime::<Utc>::MIN_UTC))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rsa_keys.sort_by_key(|k| k.activates_at);
+
+        if config.jwt.algorithm == JwtAlgorithm::Rs256
+            && rsa_keys
+                .first()
+                .map_or(true, |k| k.activates_at > Utc::now())
+        {
+            return Err(AppError::Config("No RS256 signing key is active yet".to_string()));
+        }
+
+        Ok(Self {
+            secrets,
+            rotation_interval_secs: config.jwt.rotation_interval_secs as i64,
+            grace_period_secs: config.jwt.grace_period_secs as i64,
+            algorithm: config.jwt.algorithm,
+            rsa_keys,
+            last_signing_kid: Mutex::new(None),
+        })
+    }
+
+    /// 用当前签名密钥签发令牌（header 中带 kid）
+    pub fn encode<T: Serialize>(
+        &self,
+        claims: &T,
+        now: DateTime<Utc>,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let (kid, token) = match self.active_rsa_key(now) {
+            Some(key) if self.algorithm == JwtAlgorithm::Rs256 => {
+                let mut header = Header::new(Algorithm::RS256);
+                header.kid = Some(key.kid.clone());
+                (key.kid.clone(), jsonwebtoken::encode(&header, claims, &key.encoding_key)?)
+            }
+            _ => {
+                let (kid, secret) = self.current_hs_key(now);
+                let mut header = Header::new(Algorithm::HS256);
+                header.kid = Some(kid.clone());
+                let token =
+                    jsonwebtoken::encode(&header, claims, &EncodingKey::from_secret(&secret))?;
+                (kid, token)
+            }
+        };
+        self.record_signing_kid(kid);
+        Ok(token)
+    }
+
+    /// 按 header 中的 kid 选择密钥验证令牌（同时校验过期时间）
+    pub fn decode<T: DeserializeOwned>(&self, token: &str, now: DateTime<Utc>) -> Option<T> {
+        let header = match jsonwebtoken::decode_header(token) {
+            Ok(header) => header,
+            Err(e) => {
+                tracing::debug!("Token header decode failed: {:?}", e);
+                return None;
+            }
+        };
+        let candidates = self.decoding_keys(header.kid.as_deref(), now);
+        if candidates.is_empty() {
+            tracing::debug!(kid = ?header.kid, "No accepted signing key for token");
+            return None;
+        }
+        candidates.into_iter().find_map(|(key, algorithm)| {
+            jsonwebtoken::decode::<T>(token, &key, &Validation::new(algorithm))
+                .map_err(|e| tracing::debug!("Token validation failed: {:?}", e))
+                .ok()
+                .map(|data| data.claims)
+        })
+    }
+
+    /// 发布的 RS256 公钥：尚未生效、正在使用与宽限期内的密钥
+    pub fn jwks(&self, now: DateTime<Utc>) -> JwkSet {
+        JwkSet {
+            keys: (0..self.rsa_keys.len())
+                .filter(|&i| !self.rsa_key_expired(i, now))
+                .map(|i| self.rsa_keys[i].jwk.clone())
+                .collect(),
+        }
+    }
+
+    /// 当前用于签名的 kid
+    pub fn active_kid(&self, now: DateTime<Utc>) -> String {
+        match self.active_rsa_key(now) {
+            Some(key) if self.algorithm == JwtAlgorithm::Rs256 => key.kid.clone(),
+            _ => self.current_hs_key(now).0,
+        }
+    }
+
+    fn active_rsa_key(&self, now: DateTime<Utc>) -> Option<&RsaKey> {
+        self.rsa_keys.iter().rev().find(|k| k.activates_at <= now)
+    }
+
+    /// 被后继密钥取代且已超过宽限期
+    fn rsa_key_expired(&self, index: usize, now: DateTime<Utc>) -> bool {
+        self.rsa_keys.get(index + 1).is_some_and(|next| {
+            next.activates_at <= now
+                && now.timestamp() > next.activates_at.timestamp() + self.grace_period_secs
+        })
+    }
+
+    fn current_hs_key(&self, now: DateTime<Utc>) -> (String, Vec<u8>) {
+        let secret = &self.secrets[0];
+        if self.rotation_interval_secs <= 0 {
+            return (PRIMARY_KID.to_string(), secret.clone());
+        }
+        let interval = self.rotation_interval_secs;
+        let period = now.timestamp().div_euclid(interval);
+        (hs_kid(interval, period), derive_secret(secret, interval, period))
+    }
+
+    fn decoding_keys(
+        &self,
+        kid: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Vec<(DecodingKey, Algorithm)> {
+        let hs_keys = |derive: &dyn Fn(&[u8]) -> Vec<u8>| -> Vec<(DecodingKey, Algorithm)> {
+            self.secrets
+                .iter()
+                .map(|s| (DecodingKey::from_secret(&derive(s.as_slice())), Algorithm::HS256))
+                .collect()
+        };
+        match kid {
+            // 未带 kid 的令牌签发于启用密钥环之前
+            None | Some(PRIMARY_KID) => hs_keys(&|s| s.to_vec()),
+            Some(kid) => match parse_hs_kid(kid) {
+                Some((interval, period)) => {
+                    // 派生密钥在下一周期开始时被取代
+                    let activates_at = period.saturating_mul(interval);
+                    let retires_at = activates_at.saturating_add(interval);
+                    let now = now.timestamp();
+                    if activates_at > now || now > retires_at.saturating_add(self.grace_period_secs)
+                    {
+                        return Vec::new();
+                    }
+                    hs_keys(&|s| derive_secret(s, interval, period))
+                }
+                None => (0..self.rsa_keys.len())
+                    .filter(|&i| {
+                        let key = &self.rsa_keys[i];
+                        key.kid == kid && key.activates_at <= now && !self.rsa_key_expired(i, now)
+                    })
+                    .map(|i| (self.rsa_keys[i].decoding_key.clone(), Algorithm::RS256))
+                    .collect(),
+            },
+        }
+    }
+
+    fn record_signing_kid(&self, kid: String) {
+        let Ok(mut last) = self.last_signing_kid.lock() else {
+            return;
+        };
+        if last.as_deref() != Some(kid.as_str()) {
+            if let Some(previous) = last.as_deref() {
+                tracing::info!(previous_kid = previous, kid = %kid, "JWT signing key rotated");
+            }
+            *last = Some(kid);
+        }
+    }
+}
+
+fn hs_kid(interval: i64, period: i64) -> String {
+    format!("hs-{}-{}", interval, period)
+}
+
+fn parse_hs_kid(kid: &str) -> Option<(i64, i64)> {
+    let (interval, period) = kid.strip_prefix("hs-")?.split_once('-')?;
+    let interval = interval.parse::<i64>().ok().filter(|i| *i > 0)?;
+    Some((interval, period.parse().ok()?))
+}
+
+fn derive_secret(secret: &[u8], interval: i64, period: i64) -> Vec<u8> {
+    let context = format!("{}:{}", DERIVED_KEY_CONTEXT, hs_kid(interval, period));
+    compute_hmac_sha256(secret, context.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hs_kid() {
+        assert_eq!(parse_hs_kid(&hs_kid(86400, 20376)), Some((86400, 20376)));
+        assert_eq!(parse_hs_kid("hs-0-5"), None);
+        assert_eq!(parse_hs_kid("hs-abc"), None);
+        assert_eq!(parse_hs_kid(PRIMARY_KID), None);
+        assert_ne!(derive_secret(b"secret", 3600, 1), derive_secret(b"secret", 3600, 2));
+    }
+}
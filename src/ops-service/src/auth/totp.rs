@@ -0,0 +1,220 @@
+//! TOTP 双因素认证（RFC 6238）
+//!
+//! 使用 HMAC-SHA256、30 秒时间步与 6 位验证码；密钥以 Base32 编码提供给认证器，
+//! otpauth URI 中声明算法，由客户端渲染为二维码。恢复码只保存哈希，每个只能使用一次
+
+use crate::middleware::webhook_hmac::compute_hmac_sha256;
+
+/// 时间步长（秒）
+pub const TOTP_STEP_SECS: i64 = 30;
+
+/// 验证码位数
+pub const TOTP_DIGITS: u32 = 6;
+
+/// 密钥长度（字节）
+const SECRET_BYTES: usize = 20;
+
+/// RFC 4648 Base32 字母表
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// 生成新的 TOTP 密钥（Base32，无填充）
+pub fn generate_secret() -> String {
+    let bytes: [u8; SECRET_BYTES] = rand::random();
+    base32_encode(&bytes)
+}
+
+/// Base32 编码（无填充）
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// Base32 解码（忽略大小写、空格与填充），包含非法字符时返回 None
+pub fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// 指定时间步的验证码
+pub fn code_at(secret: &[u8], step: i64) -> String {
+    let mac = compute_hmac_sha256(secret, &step.to_be_bytes());
+    // 动态截断
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([
+        mac[offset] & 0x7f,
+        mac[offset + 1],
+        mac[offset + 2],
+        mac[offset + 3],
+    ]);
+    format!("{:0width$}", value % 10u32.pow(TOTP_DIGITS), width = TOTP_DIGITS as usize)
+}
+
+/// 时间戳所在的时间步
+pub fn step_at(timestamp: i64) -> i64 {
+    timestamp.div_euclid(TOTP_STEP_SECS)
+}
+
+/// 校验验证码，返回匹配的时间步
+///
+/// 允许前后 `skew_steps` 个时间步的偏差；不接受不晚于 `last_used_step` 的时间步（防重放）
+pub fn verify(
+    secret: &[u8],
+    code: &str,
+    timestamp: i64,
+    skew_steps: u32,
+    last_used_step: Option<i64>,
+) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let current = step_at(timestamp);
+    let skew = skew_steps as i64;
+    (current - skew..=current + skew)
+        .filter(|step| last_used_step.map_or(true, |last| *step > last))
+        .find(|step| constant_time_eq(code_at(secret, *step).as_bytes(), code.as_bytes()))
+}
+
+/// 认证器使用的 otpauth URI
+pub fn provisioning_uri(issuer: &str, account: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA256&digits={}&period={}",
+        uri_encode(issuer),
+        uri_encode(account),
+        secret,
+        uri_encode(issuer),
+        TOTP_DIGITS,
+        TOTP_STEP_SECS
+    )
+}
+
+/// 生成一组恢复码（形如 `abcde-fghij`）
+pub fn generate_recovery_codes(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| {
+            let bytes: [u8; 7] = rand::random();
+            let encoded = base32_encode(&bytes).to_ascii_lowercase();
+            format!("{}-{}", &encoded[..5], &encoded[5..10])
+        })
+        .collect()
+}
+
+/// 规范化恢复码（去掉分隔符与空白、统一小写）后用于哈希比较
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// 输入是否为 TOTP 验证码格式（否则按恢复码处理）
+pub fn looks_like_totp_code(code: &str) -> bool {
+    let code = code.trim();
+    code.len() == TOTP_DIGITS as usize && code.chars().all(|c| c.is_ascii_digit())
+}
+
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 附录 B 的 SHA256 测试密钥
+    const RFC_SECRET: &[u8] = b"12345678901234567890123456789012";
+
+    #[test]
+    fn test_rfc6238_sha256_vectors() {
+        // 附录 B 为 8 位验证码，取后 6 位即为 6 位验证码
+        for (timestamp, expected) in [
+            (59, "46119246"),
+            (1111111109, "68084774"),
+            (1111111111, "67062674"),
+            (1234567890, "91819424"),
+            (2000000000, "90698825"),
+        ] {
+            assert_eq!(code_at(RFC_SECRET, step_at(timestamp)), expected[2..]);
+        }
+    }
+
+    #[test]
+    fn test_base32_roundtrip() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("mzxw6ytboi======").unwrap(), b"foobar");
+        assert!(base32_decode("not base32!").is_none());
+
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(base32_decode(&secret).unwrap().len(), SECRET_BYTES);
+    }
+
+    #[test]
+    fn test_verify_with_skew_and_replay() {
+        let timestamp = 1_760_000_000;
+        let step = step_at(timestamp);
+        let previous = code_at(RFC_SECRET, step - 1);
+
+        assert_eq!(verify(RFC_SECRET, &previous, timestamp, 1, None), Some(step - 1));
+        assert_eq!(verify(RFC_SECRET, &previous, timestamp, 0, None), None);
+        // 已使用过的时间步不再接受
+        assert_eq!(verify(RFC_SECRET, &previous, timestamp, 1, Some(step - 1)), None);
+        assert_eq!(verify(RFC_SECRET, "12345", timestamp, 1, None), None);
+        assert_eq!(verify(RFC_SECRET, "abcdef", timestamp, 1, None), None);
+    }
+
+    #[test]
+    fn test_provisioning_uri_and_recovery_codes() {
+        let uri = provisioning_uri("ops service", "alice@example.com", "MZXW6YTBOI");
+        assert_eq!(
+            uri,
+            "otpauth://totp/ops%20service:alice%40example.com?secret=MZXW6YTBOI\
+             &issuer=ops%20service&algorithm=SHA256&digits=6&period=30"
+        );
+
+        let codes = generate_recovery_codes(10);
+        assert_eq!(codes.len(), 10);
+        assert!(codes
+            .iter()
+            .all(|c| c.len() == 11 && !looks_like_totp_code(c)));
+        assert_eq!(normalize_recovery_code(" ABCDE-fghij "), "abcdefghij");
+    }
+}
@@ -5,8 +5,10 @@ use ops_service::{
     handlers::build_webhook::BuildMessageConsumer,
     handlers::health,
     middleware::{AppState, IpRateLimiter, RateLimitConfig},
+    models::{approval::ApprovalRequest, job::JobStatus},
+    preflight,
     rabbitmq::{RabbitMqConsumer, RabbitMqPublisherPool},
-    realtime::EventBus,
+    realtime::{outbox::OutboxRelay, EventBus},
     routes,
     services::{RunnerScheduler, StorageService},
     telemetry,
@@ -19,6 +21,10 @@ use tokio::sync::RwLock;
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args: Vec<String> = std::env::args().collect();
+    let mut repair_job_stats = false;
+    let mut migrate_command: Option<bool> = None;
+    let mut check_command: Option<bool> = None;
+    let mut seed_command = false;
 
     if args.len() > 1 {
         match args[1].as_str() {
@@ -30,6 +36,26 @@ async fn main() -> anyhow::Result<()> {
                 print_help();
                 return Ok(());
             }
+            "--repair-job-stats" => repair_job_stats = true,
+            "--check" => match args.get(2).map(String::as_str) {
+                None => check_command = Some(false),
+                Some("--json") => check_command = Some(true),
+                Some(other) => {
+                    eprintln!("未知参数: --check {}", other);
+                    print_help();
+                    std::process::exit(1);
+                }
+            },
+            "migrate" => match args.get(2).map(String::as_str) {
+                None => migrate_command = Some(false),
+                Some("--dry-run") => migrate_command = Some(true),
+                Some(other) => {
+                    eprintln!("未知参数: migrate {}", other);
+                    print_help();
+                    std::process::exit(1);
+                }
+            },
+            "seed" => seed_command = true,
             _ => {
                 eprintln!("未知参数: {}", args[1]);
                 print_help();
@@ -46,6 +72,10 @@ async fn main() -> anyhow::Result<()> {
         dotenv::dotenv().ok();
     }
 
+    if let Some(json) = check_command {
+        return run_check_command(json).await;
+    }
+
     health::set_start_time();
 
     let config = AppConfig::from_env().map_err(|e| {
@@ -55,17 +85,46 @@ async fn main() -> anyhow::Result<()> {
 
     telemetry::init_telemetry(&config);
     telemetry::init_metrics();
+    ops_service::output::set_default_ansi_mode(config.output.ansi_mode);
+    ops_service::i18n::set_default_locale(config.i18n.default_locale);
+    ops_service::services::notification_template::set_link_base_url(
+        &config.notifications.link_base_url,
+    );
+    ops_service::cache::configure(&config.query_cache);
 
     tracing::info!(version = env!("CARGO_PKG_VERSION"), "Ops System P0 starting...");
 
     let db_pool = db::create_pool(&config.database).await?;
-    db::run_migrations(&db_pool).await?;
+
+    if let Some(dry_run) = migrate_command {
+        return run_migrate_command(&db_pool, dry_run).await;
+    }
+
+    if config.database.auto_migrate {
+        db::run_migrations(&db_pool).await?;
+    } else {
+        // 多实例部署：迁移由 `ops-service migrate` 单独执行，存在待应用迁移时拒绝启动
+        db::ensure_migrated(&db_pool).await?;
+    }
 
     tracing::info!("Database initialized");
 
-    let concurrency_controller = std::sync::Arc::new(ConcurrencyController::new(
-        ops_service::concurrency::ConcurrencyConfig::default(),
-    ));
+    if repair_job_stats {
+        let repaired = ops_service::services::JobService::repair_job_counts(&db_pool).await?;
+        println!("已修复 {} 个作业的任务计数", repaired);
+        return Ok(());
+    }
+
+    if seed_command {
+        let report = ops_service::seed::run(&db_pool, &config).await?;
+        print!("{}", report.render());
+        return Ok(());
+    }
+
+    let concurrency_controller = std::sync::Arc::new(
+        ConcurrencyController::new(ops_service::concurrency::ConcurrencyConfig::default())
+            .with_db(db_pool.clone()),
+    );
 
     let audit_service =
         std::sync::Arc::new(ops_service::services::AuditService::new(db_pool.clone()));
@@ -77,11 +136,14 @@ async fn main() -> anyhow::Result<()> {
         RateLimitConfig::from_security_config(&config.security),
     ));
 
-    let approval_service = std::sync::Arc::new(ops_service::services::ApprovalService::new(
-        db_pool.clone(),
-        audit_service.clone(),
-        event_bus.clone(),
-    ));
+    let approval_service = std::sync::Arc::new(
+        ops_service::services::ApprovalService::new(
+            db_pool.clone(),
+            audit_service.clone(),
+            event_bus.clone(),
+        )
+        .with_config(config.approval.clone()),
+    );
 
     // 初始化 RabbitMQ 发布器池
     let rabbitmq_publisher =
@@ -99,6 +161,19 @@ async fn main() -> anyhow::Result<()> {
         StorageService::new(ops_service::services::StorageConfig::default())
     }));
 
+    // 初始化内容寻址 blob 存储（与存储服务共用对象存储后端）
+    let blob_store = std::sync::Arc::new(ops_service::services::BlobStore::new(
+        db_pool.clone(),
+        storage_service.clone(),
+        config.blob_store.clone(),
+    ));
+
+    // 初始化统计服务
+    let stats_service = std::sync::Arc::new(ops_service::services::StatsService::new(
+        db_pool.clone(),
+        config.stats.clone(),
+    ));
+
     // 初始化 Webhook Nonce 防重放存储
     let webhook_nonce_store = std::sync::Arc::new(
         ops_service::middleware::webhook_hmac::NonceStore::new(
@@ -106,41 +181,95 @@ async fn main() -> anyhow::Result<()> {
         ),
     );
 
+    // 编译来源 IP 访问策略（全局 / 端点组 / 角色白名单）
+    let ip_access_policy = std::sync::Arc::new(
+        ops_service::middleware::ip_allowlist::IpAccessPolicy::from_config(&config.security)
+            .map_err(|e| anyhow::anyhow!("Invalid IP allowlist configuration: {}", e))?,
+    );
+
+    // 只读维护模式开关（初始状态来自配置）
+    let maintenance = std::sync::Arc::new(
+        ops_service::middleware::maintenance::MaintenanceMode::from_config(&config.maintenance),
+    );
+    if maintenance.is_read_only() {
+        tracing::warn!("Starting in read-only maintenance mode");
+    }
+
+    let job_service = std::sync::Arc::new(
+        ops_service::services::JobService::new(
+            db_pool.clone(),
+            concurrency_controller.clone(),
+            audit_service.clone(),
+            config.ssh.clone(),
+        )
+        .with_event_bus(event_bus.clone())
+        .with_approval_service(approval_service.clone())
+        .with_storage(storage_service.clone())
+        .with_blob_store(blob_store.clone())
+        .with_budget(config.job_budget.clone())
+        .with_network_backoff(config.network_backoff.clone())
+        .with_output_config(config.output.clone())
+        .with_dispatch_queue(&config.job_dispatch),
+    );
+
+    // 初始化合规证据包导出服务
+    let evidence_exporter = std::sync::Arc::new(
+        ops_service::services::EvidenceExporter::new(
+            db_pool.clone(),
+            job_service.clone(),
+            storage_service.clone(),
+            config.evidence.clone(),
+            &config.security.jwt_secret,
+        )
+        .with_event_bus(event_bus.clone()),
+    );
+
+    // 签发与校验共用同一密钥环
+    let jwt_service =
+        std::sync::Arc::new(ops_service::auth::jwt::JwtService::from_config(&config)?);
+
     let app_state = Arc::new(AppState {
         db: db_pool.clone(),
         config: config.clone(),
         auth_service: std::sync::Arc::new(ops_service::services::AuthService::new(
             db_pool.clone(),
-            std::sync::Arc::new(ops_service::auth::jwt::JwtService::from_config(&config)?),
+            jwt_service.clone(),
             std::sync::Arc::new(config.clone()),
         )),
         permission_service: std::sync::Arc::new(ops_service::services::PermissionService::new(
             db_pool.clone(),
         )),
         audit_service: audit_service.clone(),
-        jwt_service: std::sync::Arc::new(ops_service::auth::jwt::JwtService::from_config(&config)?),
-        job_service: std::sync::Arc::new(
-            ops_service::services::JobService::new(
-                db_pool.clone(),
-                concurrency_controller.clone(),
-                audit_service.clone(),
-                config.ssh.clone(),
-            )
-            .with_event_bus(event_bus.clone())
-            .with_approval_service(approval_service.clone()),
-        ),
+        jwt_service,
+        job_service: job_service.clone(),
         approval_service,
         event_bus,
         concurrency_controller,
         rate_limiter,
+        ip_access_policy,
+        maintenance,
         rabbitmq_publisher,
         runner_docker_config_cache,
         runner_scheduler,
         storage_service,
+        blob_store,
+        evidence_exporter,
+        stats_service,
         webhook_nonce_store: Some(webhook_nonce_store),
         runner_config_version: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+        user_locales: Arc::new(ops_service::i18n::UserLocaleCache::new(
+            db_pool.clone(),
+            ops_service::i18n::UserLocaleCache::DEFAULT_TTL,
+        )),
     });
 
+    // 加密升级前以明文保存的 TOTP 密钥
+    app_state
+        .auth_service
+        .two_factor()
+        .encrypt_legacy_secrets()
+        .await?;
+
     let app = routes::create_router(app_state.clone());
 
     // 启动 RabbitMQ 消费者（P2.1：Runner 回传链路闭环）
@@ -149,6 +278,72 @@ async fn main() -> anyhow::Result<()> {
     // 启动审批超时自动过期任务 (P3)
     let expiry_handle = start_approval_expiry_task(app_state.clone());
 
+    // 启动等待审批作业的补偿派发任务
+    start_approval_gate_release_task(app_state.clone());
+
+    // 启动审批提醒任务（按时限进度提醒尚未决策的审批人）
+    start_approval_reminder_task(app_state.clone());
+
+    // 启动主机维护到期检查任务（释放等待维护的任务）
+    start_maintenance_release_task(app_state.clone());
+
+    // 启动作业派发工作池与派发租约续约任务
+    start_job_dispatch_task(app_state.clone());
+    start_job_dispatch_lease_task(app_state.clone());
+
+    // 启动后续作业派发任务（作业链）
+    start_job_chain_task(app_state.clone());
+
+    // 启动 Runner 配置灰度发布评估任务
+    start_runner_config_rollout_task(app_state.clone());
+
+    // 启动事件发件箱中继任务
+    start_outbox_relay_task(app_state.clone());
+
+    // 启动通知模板刷新任务（首次执行即加载自定义模板）
+    start_notification_template_refresh_task(app_state.clone());
+
+    // 启动构建日志保留期清理任务（与产物保留策略一致）
+    if let Some(retention_days) = app_state.storage_service.config().retention_days {
+        start_build_log_retention_task(app_state.clone(), retention_days);
+    }
+
+    // 启动作业归档任务（完成超过保留期的作业移入归档表与对象存储）
+    if config.archive.enabled {
+        start_job_archive_task(app_state.clone());
+    }
+
+    // 启动软删除清理任务（彻底删除超过保留期的主机、作业模板与用户）
+    if config.soft_delete.purge_enabled {
+        start_soft_delete_purge_task(app_state.clone());
+    }
+
+    // 启动 blob 垃圾回收任务（回收超过宽限期的无引用内容）
+    if config.blob_store.gc_enabled {
+        start_blob_gc_task(app_state.clone());
+    }
+
+    // 启动安全异常检测任务
+    if config.anomaly.enabled {
+        start_anomaly_detection_task(app_state.clone());
+    }
+
+    // 启动主机密钥漂移检查任务
+    if config.host_key_drift.enabled {
+        start_host_key_drift_task(app_state.clone());
+    }
+
+    // 启动安全公告导入与匹配任务
+    if config.advisory.enabled {
+        start_advisory_import_task(app_state.clone());
+    }
+
+    // 启动统计预聚合与并发采样任务
+    if config.stats.enabled {
+        start_stats_refresh_task(app_state.clone());
+        start_concurrency_sampling_task(app_state.clone());
+    }
+
     let addr = &config.server.addr;
     let listener = TcpListener::bind(addr).await?;
 
@@ -166,92 +361,31 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// 启动 RabbitMQ 消费者后台任务（含自动重连）
+/// 启动 RabbitMQ 消费者后台任务（断线后按退避自动重连）
 async fn start_rabbitmq_consumer(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
-    let consumer_state = state.clone();
-    tokio::spawn(async move {
-        // 尝试创建消费者（如果 RabbitMQ 未配置，则跳过）
-        let consumer = match RabbitMqConsumer::new(consumer_state.config.rabbitmq.clone()).await {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::warn!("Failed to create RabbitMQ consumer: {}. RabbitMQ message consumption disabled.", e);
-                return;
-            }
-        };
+    let config = state.config.rabbitmq.clone();
+    let monitor = state.rabbitmq_publisher.monitor();
+    let msg_consumer = BuildMessageConsumer::new(state);
 
-        // 设置消费队列
-        if let Err(e) = consumer.setup_consumer_queues().await {
-            tracing::warn!(
-                "Failed to setup consumer queues: {}. RabbitMQ message consumption disabled.",
-                e
-            );
-            return;
-        }
-
-        tracing::info!("RabbitMQ consumer started, listening for build status and log messages");
-
-        let msg_consumer = BuildMessageConsumer::new(consumer_state);
-
-        // 启动状态消息消费者（含自动重连）
-        let status_consumer = consumer.clone();
-        let status_msg_consumer = msg_consumer.clone();
-        let status_handle = tokio::spawn(async move {
-            loop {
-                let consumer = status_msg_consumer.clone();
-                let status_cons = status_consumer.clone();
-                if let Err(e) = status_cons
-                    .consume_status_messages(move |data| {
-                        let c = consumer.clone();
-                        tokio::spawn(async move {
-                            if let Err(e) = c.handle_status_message(data).await {
-                                tracing::error!("Failed to handle status message: {}", e);
-                            }
-                        });
-                    })
-                    .await
-                {
-                    tracing::error!("Status consumer error: {}. Reconnecting in 5s...", e);
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                }
+    let status_consumer = msg_consumer.clone();
+    let status_handler = move |data: Vec<u8>| {
+        let c = status_consumer.clone();
+        tokio::spawn(async move {
+            if let Err(e) = c.handle_status_message(data).await {
+                tracing::error!("Failed to handle status message: {}", e);
             }
         });
-
-        // 启动日志消息消费者（含自动重连）
-        let log_msg_consumer = msg_consumer;
-        let log_cons = consumer;
-        let log_handle = tokio::spawn(async move {
-            loop {
-                let consumer = log_msg_consumer.clone();
-                let cons = log_cons.clone();
-                if let Err(e) = cons
-                    .consume_log_messages(move |data| {
-                        let c = consumer.clone();
-                        tokio::spawn(async move {
-                            if let Err(e) = c.handle_log_message(data).await {
-                                tracing::error!("Failed to handle log message: {}", e);
-                            }
-                        });
-                    })
-                    .await
-                {
-                    tracing::error!("Log consumer error: {}. Reconnecting in 5s...", e);
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                }
+    };
+    let log_handler = move |data: Vec<u8>| {
+        let c = msg_consumer.clone();
+        tokio::spawn(async move {
+            if let Err(e) = c.handle_log_message(data).await {
+                tracing::error!("Failed to handle log message: {}", e);
             }
         });
+    };
 
-        // 等待两个消费者完成（正常情况下不会完成）
-        tokio::select! {
-            _ = status_handle => {
-                tracing::warn!("Status consumer stopped unexpectedly");
-            }
-            _ = log_handle => {
-                tracing::warn!("Log consumer stopped unexpectedly");
-            }
-        }
-
-        tracing::info!("RabbitMQ consumer stopped");
-    })
+    tokio::spawn(RabbitMqConsumer::run(config, monitor, status_handler, log_handler))
 }
 
 async fn shutdown_signal(
@@ -291,15 +425,355 @@ async fn shutdown_signal(
 }
 
 /// 审批超时自动过期后台任务
+///
+/// 将过期的待审批请求标记为超时并通知申请人，按配置取消仍在等待的关联作业
 fn start_approval_expiry_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        let interval_secs = state.config.approval.expiry_interval_secs.max(1);
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
         loop {
             interval.tick().await;
+            // 只读维护模式下跳过本轮，维护结束后自动恢复
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.approval_service.expire_stale_requests().await {
+                Ok(expired) if !expired.is_empty() => {
+                    tracing::info!(count = expired.len(), "Auto-expired approval requests");
+                    // 等待审批的作业随审批超时取消
+                    for request in &expired {
+                        if let Err(e) = state.job_service.resolve_approval_gate(request.id).await {
+                            tracing::warn!(
+                                approval_id = %request.id,
+                                error = %e,
+                                "Failed to resolve job approval gate"
+                            );
+                        }
+                    }
+                    if state.config.approval.cancel_job_on_expiry {
+                        cancel_expired_approval_jobs(&state, &expired).await;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to auto-expire approvals");
+                }
+            }
+        }
+    })
+}
+
+/// 取消审批超时后仍在等待的关联作业
+async fn cancel_expired_approval_jobs(state: &AppState, expired: &[ApprovalRequest]) {
+    for request in expired {
+        let Some(job_id) = request.job_id else {
+            continue;
+        };
+        match state.job_service.get_job(job_id).await {
+            Ok(job) if job.status == JobStatus::Pending => {}
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::warn!(
+                    job_id = %job_id,
+                    error = %e,
+                    "Failed to load job of expired approval"
+                );
+                continue;
+            }
+        }
+        let reason = Some("Approval request expired".to_string());
+        match state
+            .job_service
+            .cancel_job(job_id, request.requested_by, reason)
+            .await
+        {
+            Ok(()) => {
+                tracing::info!(
+                    job_id = %job_id,
+                    approval_id = %request.id,
+                    "Cancelled job of expired approval"
+                );
+            }
+            Err(e) => {
+                tracing::warn!(
+                    job_id = %job_id,
+                    error = %e,
+                    "Failed to cancel job of expired approval"
+                );
+            }
+        }
+    }
+}
+
+/// 等待审批作业的补偿派发后台任务
+///
+/// 处理审批已结束但作业仍在等待审批的情况（如审批后派发失败或服务重启）
+fn start_approval_gate_release_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.job_service.release_approval_gated_jobs().await {
+                Ok(resolved) if resolved > 0 => {
+                    tracing::info!(resolved, "Resolved jobs awaiting approval");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to resolve jobs awaiting approval");
+                }
+            }
+        }
+    })
+}
+
+/// 审批提醒后台任务
+fn start_approval_reminder_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let interval_secs = state.config.approval.reminder_interval_secs.max(1);
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.approval_service.send_due_reminders().await {
+                Ok(sent) if sent > 0 => {
+                    tracing::info!(sent, "Sent approval reminders");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to send approval reminders");
+                }
+            }
+        }
+    })
+}
+
+/// 主机维护到期检查后台任务
+///
+/// 结束到期的维护窗口，并续跑等待维护结束的任务
+fn start_maintenance_release_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.job_service.release_maintenance_tasks().await {
+                Ok(released) if released > 0 => {
+                    tracing::info!(released, "Released tasks waiting for host maintenance");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to release maintenance tasks");
+                }
+            }
+        }
+    })
+}
+
+/// 作业派发后台任务
+///
+/// 有作业入队或工作空闲时立即认领，并定期兜底轮询（其他实例入队的作业）
+fn start_job_dispatch_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let poll_interval_ms = state.config.job_dispatch.poll_interval_ms.max(1);
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_millis(poll_interval_ms));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = state.job_service.dispatch_notified() => {}
+            }
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.job_service.dispatch_queued_jobs().await {
+                Ok(claimed) if claimed > 0 => {
+                    tracing::debug!(claimed, "Claimed queued jobs");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to dispatch queued jobs");
+                }
+            }
+        }
+    })
+}
+
+/// 作业派发租约后台任务
+///
+/// 为本实例执行中的作业续约（只读维护期间同样续约），重新入队租约过期的作业，
+/// 并找回创建后未入队的作业
+fn start_job_dispatch_lease_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(period) = state.job_service.dispatch_renewal_interval() else {
+            return;
+        };
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = state.job_service.renew_dispatch_leases().await {
+                tracing::error!(error = %e, "Failed to renew job dispatch leases");
+            }
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.job_service.recover_stale_dispatches().await {
+                Ok(recovered) if recovered > 0 => {
+                    tracing::warn!(recovered, "Requeued jobs with expired dispatch leases");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to recover job dispatches");
+                }
+            }
+            match state.job_service.recover_undispatched_jobs().await {
+                Ok(recovered) if recovered > 0 => {
+                    tracing::warn!(recovered, "Recovered jobs that were never dispatched");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to recover undispatched jobs");
+                }
+            }
+        }
+    })
+}
+
+/// Runner 配置灰度发布评估后台任务
+///
+/// 金丝雀 Runner 全部应用后进入观察期，按构建失败率自动写入配置或回滚
+fn start_runner_config_rollout_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let rollouts = ops_service::services::RunnerConfigRolloutService::new(state.db.clone());
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match rollouts.evaluate_once().await {
+                Ok(changed) if changed > 0 => {
+                    tracing::info!(changed, "Advanced runner config rollouts");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to evaluate runner config rollouts");
+                }
+            }
+        }
+    })
+}
+
+/// 后续作业派发后台任务
+///
+/// 作业结束且命中后续作业配置时立即派发，并定期兜底扫描（进程重启后补发）
+fn start_job_chain_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = state.job_service.follow_ups_notified() => {}
+            }
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.job_service.launch_follow_up_jobs().await {
+                Ok(processed) if processed > 0 => {
+                    tracing::info!(processed, "Dispatched follow-up jobs");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to dispatch follow-up jobs");
+                }
+            }
+        }
+    })
+}
+
+/// 通知模板刷新后台任务
+///
+/// 定期从数据库加载自定义通知模板，使其他实例的修改在本实例生效
+fn start_notification_template_refresh_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let refresh_secs = state.config.notifications.template_refresh_secs.max(1);
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(refresh_secs));
+        loop {
+            interval.tick().await;
+            if let Err(e) = ops_service::services::notification_template::reload(&state.db).await {
+                tracing::error!(error = %e, "Failed to reload notification templates");
+            }
+        }
+    })
+}
+
+/// 事件发件箱中继后台任务
+///
+/// 收到提交后的通知时立即中继，并定期兜底扫描（进程重启后补发未投递的事件）；
+/// 每小时清理投递超过一天的记录
+fn start_outbox_relay_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let relay = OutboxRelay::new(state.db.clone(), state.event_bus.clone());
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        let mut last_purge = std::time::Instant::now();
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = state.event_bus.outbox_notified() => {}
+            }
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            if let Err(e) = relay.relay_pending().await {
+                tracing::error!(error = %e, "Failed to relay outbox events");
+            }
+
+            if last_purge.elapsed() >= std::time::Duration::from_secs(3600) {
+                last_purge = std::time::Instant::now();
+                match relay
+                    .purge_delivered(std::time::Duration::from_secs(86400))
+                    .await
+                {
+                    Ok(purged) if purged > 0 => {
+                        tracing::info!(purged, "Purged delivered outbox events");
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to purge outbox events");
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// 构建日志保留期清理后台任务
+///
+/// 删除完成时间超过产物保留天数的构建作业的日志块
+fn start_build_log_retention_task(
+    state: Arc<AppState>,
+    retention_days: u32,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
             let result = sqlx::query(
-                "UPDATE approval_requests SET status = 'timeout', completed_at = NOW()
-                 WHERE status = 'pending' AND expires_at < NOW()"
+                "DELETE FROM build_logs bl
+                 USING build_jobs bj
+                 WHERE bl.job_id = bj.id
+                   AND bj.completed_at < NOW() - make_interval(days => $1)",
             )
+            .bind(retention_days as i32)
             .execute(&state.db)
             .await;
 
@@ -307,26 +781,314 @@ fn start_approval_expiry_task(state: Arc<AppState>) -> tokio::task::JoinHandle<(
                 Ok(r) if r.rows_affected() > 0 => {
                     tracing::info!(
                         rows = r.rows_affected(),
-                        "Auto-expired approval requests"
+                        retention_days,
+                        "Purged expired build logs"
                     );
                 }
                 Ok(_) => {}
                 Err(e) => {
-                    tracing::error!(error = %e, "Failed to auto-expire approvals");
+                    tracing::error!(error = %e, "Failed to purge expired build logs");
+                }
+            }
+        }
+    })
+}
+
+/// 作业归档后台任务
+fn start_job_archive_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let config = state.config.archive.clone();
+        let archiver = ops_service::services::JobArchiver::new(
+            state.db.clone(),
+            state.storage_service.clone(),
+            config.clone(),
+        );
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.interval_secs));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match archiver.archive_expired().await {
+                Ok(archived) if archived > 0 => {
+                    tracing::info!(archived, after_days = config.after_days, "Archived jobs");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to archive jobs");
+                }
+            }
+        }
+    })
+}
+
+/// 软删除清理后台任务
+fn start_soft_delete_purge_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let config = state.config.soft_delete.clone();
+        let purger = ops_service::services::SoftDeletePurger::new(state.db.clone(), config.clone());
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.purge_interval_secs));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match purger.purge_expired().await {
+                Ok(purged) if purged > 0 => {
+                    tracing::info!(
+                        purged,
+                        retention_days = config.retention_days,
+                        "Purged soft-deleted resources"
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to purge soft-deleted resources");
+                }
+            }
+        }
+    })
+}
+
+/// blob 垃圾回收后台任务
+fn start_blob_gc_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let interval_secs = state.blob_store.config().gc_interval_secs;
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.blob_store.collect_garbage().await {
+                Ok(report) if report.deleted_count > 0 => {
+                    tracing::info!(
+                        deleted = report.deleted_count,
+                        deleted_bytes = report.deleted_bytes,
+                        "Collected unreferenced blobs"
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to collect unreferenced blobs");
                 }
             }
         }
     })
 }
 
+/// 安全异常检测后台任务
+fn start_anomaly_detection_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let config = state.config.anomaly.clone();
+        let detector =
+            ops_service::services::AnomalyDetector::new(state.db.clone(), config.clone())
+                .with_event_bus(state.event_bus.clone());
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.interval_secs));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match detector.run_once().await {
+                Ok(detected) if detected > 0 => {
+                    tracing::warn!(detected, "Security anomalies detected");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to run anomaly detection");
+                }
+            }
+        }
+    })
+}
+
+/// 主机密钥漂移检查后台任务
+///
+/// 发现的漂移作为安全异常推送，是否推送到安全事件流与异常检测的 notify 配置一致
+fn start_host_key_drift_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let config = state.config.host_key_drift.clone();
+        let anomaly_detector = ops_service::services::AnomalyDetector::new(
+            state.db.clone(),
+            state.config.anomaly.clone(),
+        )
+        .with_event_bus(state.event_bus.clone());
+        let monitor = ops_service::services::HostKeyDriftMonitor::new(
+            state.db.clone(),
+            state.job_service.clone(),
+            anomaly_detector,
+            config.concurrency,
+        );
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.interval_secs.max(60)));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match monitor.run_once().await {
+                Ok(summary) if summary.drifted > 0 => {
+                    tracing::warn!(drifted = summary.drifted, "Host key drift detected");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to run host key drift check");
+                }
+            }
+        }
+    })
+}
+
+/// 安全公告导入与匹配后台任务
+///
+/// 首轮导入镜像目录中的全部文件，之后只导入上一轮开始后修改过的文件；每轮都重算匹配结果
+fn start_advisory_import_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let config = state.config.advisory.clone();
+        let importer =
+            ops_service::services::AdvisoryImporter::new(state.db.clone(), config.clone());
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.interval_secs.max(1)));
+        let mut since = None;
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            let started = std::time::SystemTime::now();
+            match importer.import_feed_dir(since).await {
+                Ok(report) => {
+                    since = Some(started);
+                    if report.imported > 0 {
+                        tracing::info!(
+                            imported = report.imported,
+                            skipped = report.skipped,
+                            matches = report.matches,
+                            "Imported security advisories"
+                        );
+                    }
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to import security advisories");
+                }
+            }
+        }
+    })
+}
+
+/// 统计预聚合后台任务
+///
+/// 启动时回填 backfill_days 天，之后每次重新计算最近 refresh_window_days 天
+fn start_stats_refresh_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let config = state.stats_service.config().clone();
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.refresh_interval_secs));
+        let mut window_days = config.backfill_days;
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            match state.stats_service.refresh(window_days).await {
+                Ok(()) => window_days = config.refresh_window_days,
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to refresh statistics");
+                }
+            }
+        }
+    })
+}
+
+/// 并发使用率采样后台任务
+fn start_concurrency_sampling_task(state: Arc<AppState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let interval_secs = state
+            .stats_service
+            .config()
+            .concurrency_sample_interval_secs;
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            if state.maintenance.is_read_only() {
+                continue;
+            }
+            let stats = state.concurrency_controller.get_stats().await;
+            let failures = state.concurrency_controller.take_acquire_failures();
+            if let Err(e) = state
+                .stats_service
+                .record_concurrency_sample(&stats, &failures)
+                .await
+            {
+                tracing::error!(error = %e, "Failed to record concurrency sample");
+            }
+        }
+    })
+}
+
+/// `migrate` 子命令：列出（--dry-run）或在互斥锁内执行待应用迁移
+async fn run_migrate_command(db_pool: &sqlx::PgPool, dry_run: bool) -> anyhow::Result<()> {
+    let plan = if dry_run {
+        db::migration_plan(db_pool).await?
+    } else {
+        db::run_migrations(db_pool).await?
+    };
+
+    println!("已应用迁移: {}", plan.applied_count);
+    if plan.pending.is_empty() {
+        println!("没有待应用的迁移");
+    } else {
+        println!("{}迁移: {}", if dry_run { "待应用" } else { "本次应用" }, plan.pending.len());
+        for migration in &plan.pending {
+            println!("  {:06} {}", migration.version, migration.description);
+        }
+    }
+    for problem in &plan.problems {
+        println!("问题: {}", problem);
+    }
+
+    plan.verify()?;
+    Ok(())
+}
+
+/// `--check`：部署前检查配置与各依赖，输出报告，存在失败项时以状态码 1 退出
+async fn run_check_command(json: bool) -> anyhow::Result<()> {
+    let report = preflight::run(AppConfig::from_env()).await;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.render());
+    }
+    if !report.passed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 fn print_help() {
     println!("ops-system {}", env!("CARGO_PKG_VERSION"));
     println!();
     println!("用法: ops-system [选项]");
+    println!("      ops-system migrate [--dry-run]");
+    println!("      ops-system --check [--json]");
+    println!("      ops-system seed");
     println!();
     println!("选项:");
     println!("  --version     打印版本信息并退出");
     println!("  --help        打印此帮助信息并退出");
+    println!("  --repair-job-stats  按任务表重新计算历史作业的任务计数后退出");
+    println!("  --check       部署前检查配置与依赖连通性，存在失败项时以非零状态码退出");
+    println!("  --check --json  以 JSON 格式输出检查报告");
+    println!();
+    println!("子命令:");
+    println!("  migrate             在迁移锁内校验并执行待应用的数据库迁移后退出");
+    println!("  migrate --dry-run   仅列出待应用的迁移与校验问题，不做修改");
+    println!("  seed                创建初始管理员、默认角色权限、示例资产组与示例作业模板后退出（可重复执行）");
+    println!("                      管理员密码取自 OPS_SEED_ADMIN_PASSWORD，未设置时随机生成并只打印一次");
     println!();
     println!("环境变量:");
     println!("  所有配置通过环境变量完成");
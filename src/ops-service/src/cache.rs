@@ -0,0 +1,203 @@
+//! 热点查询缓存
+//!
+//! 权限检查与任务执行每次都要查询角色绑定、角色权限和主机记录。这里提供进程内的 TTL 缓存：
+//! - 本实例的写操作显式失效对应条目；其他实例的修改最迟在 TTL 到期后生效
+//! - 加载期间发生失效时丢弃加载结果，避免把失效前读到的旧数据写回缓存
+//! - 命中与未命中计入 `ops_query_cache_requests_total{cache, result}`
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::config::QueryCacheConfig;
+use crate::models::asset::Host;
+use crate::models::role::{Permission, RoleBinding};
+
+/// 是否启用缓存（关闭时直接查询数据库）
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// 单个缓存的最大条目数
+static MAX_ENTRIES: AtomicUsize = AtomicUsize::new(10_000);
+
+/// 用户的角色绑定
+pub static ROLE_BINDINGS: Lazy<TtlCache<Uuid, Arc<Vec<RoleBinding>>>> =
+    Lazy::new(|| TtlCache::new("role_bindings", Duration::from_secs(30)));
+
+/// 角色的权限
+pub static ROLE_PERMISSIONS: Lazy<TtlCache<Uuid, Arc<Vec<Permission>>>> =
+    Lazy::new(|| TtlCache::new("role_permissions", Duration::from_secs(30)));
+
+/// 主机记录
+pub static HOSTS: Lazy<TtlCache<Uuid, Option<Arc<Host>>>> =
+    Lazy::new(|| TtlCache::new("hosts", Duration::from_secs(10)));
+
+/// 按配置设置缓存（启动时调用）
+pub fn configure(config: &QueryCacheConfig) {
+    ENABLED.store(config.enabled, Ordering::Relaxed);
+    MAX_ENTRIES.store(config.max_entries.max(1), Ordering::Relaxed);
+    let permission_ttl = Duration::from_secs(config.permission_ttl_secs);
+    ROLE_BINDINGS.set_ttl(permission_ttl);
+    ROLE_PERMISSIONS.set_ttl(permission_ttl);
+    HOSTS.set_ttl(Duration::from_secs(config.host_ttl_secs));
+}
+
+/// 带过期时间的缓存
+pub struct TtlCache<K, V> {
+    name: &'static str,
+    ttl_ms: AtomicU64,
+    /// 失效计数：加载开始后发生过失效则不写入结果
+    generation: AtomicU64,
+    entries: DashMap<K, (Instant, V)>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> TtlCache<K, V> {
+    pub fn new(name: &'static str, ttl: Duration) -> Self {
+        Self {
+            name,
+            ttl_ms: AtomicU64::new(ttl.as_millis() as u64),
+            generation: AtomicU64::new(0),
+            entries: DashMap::new(),
+        }
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_millis(self.ttl_ms.load(Ordering::Relaxed))
+    }
+
+    fn set_ttl(&self, ttl: Duration) {
+        self.ttl_ms.store(ttl.as_millis() as u64, Ordering::Relaxed);
+        self.invalidate_all();
+    }
+
+    fn enabled(&self) -> bool {
+        ENABLED.load(Ordering::Relaxed) && !self.ttl().is_zero()
+    }
+
+    fn record(&self, result: &'static str) {
+        metrics::counter!("ops_query_cache_requests_total", "cache" => self.name, "result" => result)
+            .increment(1);
+    }
+
+    /// 读取未过期的条目
+    pub fn get(&self, key: &K) -> Option<V> {
+        if !self.enabled() {
+            return None;
+        }
+        let ttl = self.ttl();
+        let value = match self.entries.get(key) {
+            Some(entry) if entry.0.elapsed() < ttl => Some(entry.1.clone()),
+            Some(entry) => {
+                drop(entry);
+                self.entries
+                    .remove_if(key, |_, (cached_at, _)| cached_at.elapsed() >= ttl);
+                None
+            }
+            None => None,
+        };
+        self.record(if value.is_some() { "hit" } else { "miss" });
+        value
+    }
+
+    /// 写入条目；达到上限时先清理过期条目，仍然已满则清空
+    fn insert(&self, key: K, value: V, generation: u64) {
+        if self.generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+        if self.entries.len() >= MAX_ENTRIES.load(Ordering::Relaxed) {
+            let ttl = self.ttl();
+            self.entries
+                .retain(|_, (cached_at, _)| cached_at.elapsed() < ttl);
+            if self.entries.len() >= MAX_ENTRIES.load(Ordering::Relaxed) {
+                self.entries.clear();
+            }
+        }
+        self.entries.insert(key, (Instant::now(), value));
+    }
+
+    /// 读取条目，未命中时加载并缓存（加载失败不缓存）
+    pub async fn get_or_load<E, F, Fut>(&self, key: K, load: F) -> Result<V, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+        let generation = self.generation.load(Ordering::Acquire);
+        let value = load().await?;
+        if self.enabled() {
+            self.insert(key, value.clone(), generation);
+        }
+        Ok(value)
+    }
+
+    /// 失效单个条目
+    pub fn invalidate(&self, key: &K) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.entries.remove(key);
+    }
+
+    /// 失效全部条目（无法确定受影响的键时使用）
+    pub fn invalidate_all(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_or_load_caches_until_invalidated() {
+        let cache = TtlCache::new("test", Duration::from_secs(60));
+        let loaded = cache.get_or_load(1, || async { Ok::<_, ()>(10) }).await;
+        assert_eq!(loaded, Ok(10));
+        // 命中时不再加载
+        let cached = cache.get_or_load(1, || async { Ok::<_, ()>(20) }).await;
+        assert_eq!(cached, Ok(10));
+
+        cache.invalidate(&1);
+        assert_eq!(cache.get(&1), None);
+        let reloaded = cache.get_or_load(1, || async { Ok::<_, ()>(30) }).await;
+        assert_eq!(reloaded, Ok(30));
+
+        // 加载失败不缓存
+        let failed = cache
+            .get_or_load(2, || async { Err::<i32, _>("db down") })
+            .await;
+        assert_eq!(failed, Err("db down"));
+        assert_eq!(cache.get(&2), None);
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_during_load_discards_result() {
+        let cache = TtlCache::new("test", Duration::from_secs(60));
+        let stale = cache
+            .get_or_load(1, || async {
+                cache.invalidate_all();
+                Ok::<_, ()>(10)
+            })
+            .await;
+        assert_eq!(stale, Ok(10));
+        assert_eq!(cache.get(&1), None);
+    }
+
+    #[test]
+    fn test_expired_entries_are_not_returned() {
+        let cache = TtlCache::new("test", Duration::from_millis(1));
+        cache.insert(1, 10, 0);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get(&1), None);
+
+        // TTL 为 0 时不缓存
+        let disabled = TtlCache::new("test", Duration::ZERO);
+        disabled.insert(1, 10, 0);
+        assert_eq!(disabled.get(&1), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! 跨作业公平调度
+//!
+//! 多个作业同时执行时按作业轮询分配执行名额，
+//! 避免先启动的大作业的任务排满全局许可队列，使后启动的作业长时间等待
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// 按作业轮询分配执行名额的调度器
+pub struct FairScheduler {
+    state: Mutex<FairState>,
+}
+
+struct FairState {
+    /// 空闲名额
+    available: usize,
+    /// 各作业等待中的任务（按到达顺序）
+    waiters: HashMap<Uuid, VecDeque<oneshot::Sender<()>>>,
+    /// 有任务等待的作业，按轮询顺序排列
+    rotation: VecDeque<Uuid>,
+}
+
+impl FairState {
+    /// 没有任务在等待时占用一个空闲名额
+    fn take(&mut self) -> bool {
+        if self.available == 0 || !self.rotation.is_empty() {
+            return false;
+        }
+        self.available -= 1;
+        true
+    }
+}
+
+/// 执行名额，释放时交给轮询中的下一个作业
+pub struct FairPermit {
+    scheduler: Arc<FairScheduler>,
+}
+
+impl Drop for FairPermit {
+    fn drop(&mut self) {
+        self.scheduler.release();
+    }
+}
+
+/// 等待中的任务；未拿到名额就被取消（如超时）时，若名额恰好已转交则归还
+struct Waiter {
+    rx: Option<oneshot::Receiver<()>>,
+    scheduler: Arc<FairScheduler>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        if let Some(mut rx) = self.rx.take() {
+            rx.close();
+            if rx.try_recv().is_ok() {
+                self.scheduler.release();
+            }
+        }
+    }
+}
+
+impl FairScheduler {
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(FairState {
+                available: capacity,
+                waiters: HashMap::new(),
+                rotation: VecDeque::new(),
+            }),
+        })
+    }
+
+    /// 非阻塞获取名额；已有任务在等待时不插队
+    pub fn try_acquire(self: &Arc<Self>) -> Option<FairPermit> {
+        self.lock().take().then(|| FairPermit {
+            scheduler: self.clone(),
+        })
+    }
+
+    /// 为作业的任务获取名额，名额紧张时与其他作业轮流分配
+    pub async fn acquire(self: &Arc<Self>, job_id: Uuid) -> FairPermit {
+        loop {
+            // 检查空闲名额与登记等待在同一次加锁中完成，避免错过期间归还的名额
+            let rx = {
+                let mut state = self.lock();
+                if state.take() {
+                    return FairPermit {
+                        scheduler: self.clone(),
+                    };
+                }
+                let (tx, rx) = oneshot::channel();
+                let queue = state.waiters.entry(job_id).or_default();
+                let first = queue.is_empty();
+                queue.push_back(tx);
+                if first {
+                    state.rotation.push_back(job_id);
+                }
+                rx
+            };
+
+            let mut waiter = Waiter {
+                rx: Some(rx),
+                scheduler: self.clone(),
+            };
+            let granted = match waiter.rx.as_mut() {
+                Some(rx) => rx.await.is_ok(),
+                None => false,
+            };
+            waiter.rx = None;
+            if granted {
+                return FairPermit {
+                    scheduler: self.clone(),
+                };
+            }
+        }
+    }
+
+    /// 等待名额的任务数
+    pub fn waiting(&self) -> usize {
+        self.lock().waiters.values().map(VecDeque::len).sum()
+    }
+
+    /// 归还名额：交给轮询中下一个作业最早等待的任务，没有等待任务时放回空闲
+    fn release(&self) {
+        let mut state = self.lock();
+        while let Some(job_id) = state.rotation.pop_front() {
+            let Some(queue) = state.waiters.get_mut(&job_id) else {
+                continue;
+            };
+            let next = queue.pop_front();
+            if queue.is_empty() {
+                state.waiters.remove(&job_id);
+            } else {
+                state.rotation.push_back(job_id);
+            }
+            // 等待方已取消时继续寻找下一个
+            if let Some(tx) = next {
+                if tx.send(()).is_ok() {
+                    return;
+                }
+            }
+        }
+        state.available += 1;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FairState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn wait_for_waiters(scheduler: &FairScheduler, count: usize) {
+        while scheduler.waiting() < count {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interleaves_tasks_across_jobs() {
+        let scheduler = FairScheduler::new(1);
+        let held = scheduler.acquire(Uuid::new_v4()).await;
+
+        let (job_a, job_b) = (Uuid::new_v4(), Uuid::new_v4());
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+        let tasks = [(job_a, "a1"), (job_a, "a2"), (job_a, "a3"), (job_b, "b1")];
+        for (i, (job_id, label)) in tasks.into_iter().enumerate() {
+            let task_scheduler = scheduler.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                let _permit = task_scheduler.acquire(job_id).await;
+                order.lock().unwrap().push(label);
+            }));
+            wait_for_waiters(&scheduler, i + 1).await;
+        }
+
+        // 后到的作业不必等先到作业的全部任务
+        drop(held);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["a1", "b1", "a2", "a3"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_does_not_leak_permit() {
+        let scheduler = FairScheduler::new(1);
+        let held = scheduler.try_acquire().unwrap();
+        assert!(scheduler.try_acquire().is_none());
+
+        let waited =
+            tokio::time::timeout(Duration::from_millis(10), scheduler.acquire(Uuid::new_v4()))
+                .await;
+        assert!(waited.is_err());
+
+        drop(held);
+        assert_eq!(scheduler.waiting(), 0);
+        assert!(scheduler.try_acquire().is_some());
+    }
+}
@@ -2,11 +2,16 @@
 //! P2 阶段：提供全局和分维度的并发控制
 //! 支持：排队/拒绝/等待策略
 
+mod fair;
+
+pub use fair::{FairPermit, FairScheduler};
+
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
 
 /// 并发策略：当达到并发上限时的处理方式
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Default)]
@@ -30,6 +35,8 @@ pub struct ConcurrencyPermit {
     _group_permit: Option<Arc<OwnedSemaphorePermit>>,
     /// 环境许可（可选）
     _env_permit: Option<Arc<OwnedSemaphorePermit>>,
+    /// 作业任务的公平调度名额（可选）
+    _job_slot: Option<Arc<FairPermit>>,
 }
 
 impl ConcurrencyPermit {
@@ -43,8 +50,14 @@ impl ConcurrencyPermit {
             _global_permit: global_permit.map(Arc::new),
             _group_permit: group_permit.map(Arc::new),
             _env_permit: env_permit.map(Arc::new),
+            _job_slot: None,
         }
     }
+
+    fn with_job_slot(mut self, slot: FairPermit) -> Self {
+        self._job_slot = Some(Arc::new(slot));
+        self
+    }
 }
 
 /// 并发控制器
@@ -52,12 +65,38 @@ impl ConcurrencyPermit {
 pub struct ConcurrencyController {
     /// 全局并发限制
     global_semaphore: Arc<Semaphore>,
+    /// 作业任务的跨作业公平调度（名额与全局并发上限一致）
+    job_scheduler: Arc<FairScheduler>,
     /// 分组维度并发限制
     group_semaphores: Arc<Mutex<HashMap<String, GroupSemaphore>>>,
     /// 环境维度并发限制
     environment_semaphores: Arc<Mutex<HashMap<String, EnvironmentSemaphore>>>,
     /// 配置
     config: ConcurrencyConfig,
+    /// 数据库连接（用于读取分组并发元数据，未设置时使用静态配置）
+    db: Option<sqlx::PgPool>,
+    /// 上次采样以来获取许可失败的次数（按作用域）
+    acquire_failures: Arc<std::sync::Mutex<AcquireFailures>>,
+}
+
+/// 获取许可失败（Wait 策略超时、Reject 策略拒绝）的次数，按失败的作用域统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AcquireFailures {
+    #[serde(default)]
+    pub global: u32,
+    /// 按分组 ID
+    #[serde(default)]
+    pub groups: HashMap<String, u32>,
+    /// 按环境
+    #[serde(default)]
+    pub environments: HashMap<String, u32>,
+}
+
+impl AcquireFailures {
+    /// 失败总次数
+    pub fn total(&self) -> u32 {
+        self.global + self.groups.values().sum::<u32>() + self.environments.values().sum::<u32>()
+    }
 }
 
 /// 分组级别的信号量
@@ -65,6 +104,25 @@ pub struct ConcurrencyController {
 struct GroupSemaphore {
     semaphore: Arc<Semaphore>,
     limit: i32,
+    /// 分组元数据加载时间（None 表示已失效，下次获取时重新加载）
+    loaded_at: Option<Instant>,
+}
+
+/// 分组并发元数据（来自 assets_groups）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, sqlx::FromRow)]
+pub struct GroupConcurrencyMetadata {
+    /// 分组自身的并发上限
+    pub concurrent_limit: Option<i32>,
+    /// 是否为生产分组
+    pub is_production: bool,
+}
+
+/// 获取许可失败的作用域
+#[derive(Debug, Clone, Copy)]
+enum AcquireScope<'a> {
+    Global,
+    Group(&'a str),
+    Environment(&'a str),
 }
 
 /// 环境级别的信号量
@@ -91,6 +149,8 @@ pub struct ConcurrencyConfig {
     pub strategy: ConcurrencyStrategy,
     /// 排队策略的最大队列长度（Queue 策略时使用）
     pub queue_max_length: usize,
+    /// 分组元数据缓存时长（秒），到期后重新从数据库读取
+    pub group_metadata_ttl_secs: u64,
 }
 
 impl Default for ConcurrencyConfig {
@@ -103,6 +163,7 @@ impl Default for ConcurrencyConfig {
             acquire_timeout_secs: 300,
             strategy: ConcurrencyStrategy::Wait,
             queue_max_length: 100,
+            group_metadata_ttl_secs: 300,
         }
     }
 }
@@ -171,9 +232,61 @@ impl ConcurrencyController {
 
         Self {
             global_semaphore: Arc::new(Semaphore::new(global_limit)),
+            job_scheduler: FairScheduler::new(global_limit),
             group_semaphores: Arc::new(Mutex::new(HashMap::new())),
             environment_semaphores: Arc::new(Mutex::new(HashMap::new())),
             config,
+            db: None,
+            acquire_failures: Arc::new(std::sync::Mutex::new(AcquireFailures::default())),
+        }
+    }
+
+    /// 从数据库读取分组的并发上限与生产标记
+    pub fn with_db(mut self, db: sqlx::PgPool) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// 使分组的并发元数据缓存失效（分组更新或删除后调用）
+    pub async fn invalidate_group(&self, group_id: &str) {
+        let mut groups = self.group_semaphores.lock().await;
+        if let Some(sem) = groups.get_mut(group_id) {
+            sem.loaded_at = None;
+        }
+    }
+
+    /// 丢弃环境信号量缓存（主机迁出该环境后调用），下次获取时按当前配置重建
+    ///
+    /// 仍有许可在使用时保留，避免新旧信号量并存使该环境超出上限
+    pub async fn invalidate_environment(&self, environment: &str) {
+        let mut envs = self.environment_semaphores.lock().await;
+        let idle = envs
+            .get(environment)
+            .is_some_and(|sem| sem.semaphore.available_permits() >= sem.limit.max(1) as usize);
+        if idle {
+            envs.remove(environment);
+        }
+    }
+
+    /// 取出上次采样以来的获取许可失败次数并清零（由并发采样任务调用）
+    pub fn take_acquire_failures(&self) -> AcquireFailures {
+        self.acquire_failures
+            .lock()
+            .map(|mut failures| std::mem::take(&mut *failures))
+            .unwrap_or_default()
+    }
+
+    /// 记录一次获取许可失败
+    fn record_acquire_failure(&self, scope: AcquireScope<'_>) {
+        let Ok(mut failures) = self.acquire_failures.lock() else {
+            return;
+        };
+        match scope {
+            AcquireScope::Global => failures.global += 1,
+            AcquireScope::Group(id) => *failures.groups.entry(id.to_string()).or_default() += 1,
+            AcquireScope::Environment(env) => {
+                *failures.environments.entry(env.to_string()).or_default() += 1
+            }
         }
     }
 
@@ -200,6 +313,43 @@ impl ConcurrencyController {
         }
     }
 
+    /// 为作业任务获取执行许可
+    ///
+    /// 先按作业轮询取得公平调度名额，再按策略获取全局/分组/环境许可，
+    /// 多个作业并发执行时各作业的任务交替获得许可
+    pub async fn acquire_for_job(
+        &self,
+        job_id: Uuid,
+        group_id: Option<&str>,
+        environment: Option<&str>,
+    ) -> Result<ConcurrencyPermit, ConcurrencyError> {
+        let slot = match self.config.strategy {
+            ConcurrencyStrategy::Reject => self.job_scheduler.try_acquire().ok_or_else(|| {
+                self.record_acquire_failure(AcquireScope::Global);
+                ConcurrencyError::Rejected {
+                    scope_type: "global".to_string(),
+                    scope_value: format!("limit: {}", self.config.global_limit),
+                    strategy: ConcurrencyStrategy::Reject,
+                }
+            })?,
+            ConcurrencyStrategy::Wait | ConcurrencyStrategy::Queue => {
+                let timeout = Duration::from_secs(self.config.acquire_timeout_secs);
+                tokio::time::timeout(timeout, self.job_scheduler.acquire(job_id))
+                    .await
+                    .map_err(|_| {
+                        self.record_acquire_failure(AcquireScope::Global);
+                        ConcurrencyError::AcquireTimeout {
+                            resource: "global".to_string(),
+                        }
+                    })?
+            }
+        };
+        debug!(job_id = %job_id, "Acquired fair scheduling slot");
+
+        let permit = self.acquire(group_id, environment).await?;
+        Ok(permit.with_job_slot(slot))
+    }
+
     /// 非阻塞尝试获取许可（用于 Reject 策略）
     pub async fn try_acquire_nowait(
         &self,
@@ -210,6 +360,7 @@ impl ConcurrencyController {
         let global_permit = match self.global_semaphore.clone().try_acquire_owned() {
             Ok(permit) => Some(permit),
             Err(_) => {
+                self.record_acquire_failure(AcquireScope::Global);
                 return Err(ConcurrencyError::Rejected {
                     scope_type: "global".to_string(),
                     scope_value: format!("limit: {}", self.config.global_limit),
@@ -228,6 +379,7 @@ impl ConcurrencyController {
             match group_sem.semaphore.clone().try_acquire_owned() {
                 Ok(permit) => Some(permit),
                 Err(_) => {
+                    self.record_acquire_failure(AcquireScope::Group(gid));
                     return Err(ConcurrencyError::Rejected {
                         scope_type: "group".to_string(),
                         scope_value: format!("{} (limit: {})", gid, limit),
@@ -247,6 +399,7 @@ impl ConcurrencyController {
             match env_sem.semaphore.clone().try_acquire_owned() {
                 Ok(permit) => Some(permit),
                 Err(_) => {
+                    self.record_acquire_failure(AcquireScope::Environment(env));
                     return Err(ConcurrencyError::Rejected {
                         scope_type: "environment".to_string(),
                         scope_value: format!("{} (limit: {})", env, limit),
@@ -279,8 +432,11 @@ impl ConcurrencyController {
         let global_permit =
             tokio::time::timeout(timeout, self.global_semaphore.clone().acquire_owned())
                 .await
-                .map_err(|_| ConcurrencyError::AcquireTimeout {
-                    resource: "global".to_string(),
+                .map_err(|_| {
+                    self.record_acquire_failure(AcquireScope::Global);
+                    ConcurrencyError::AcquireTimeout {
+                        resource: "global".to_string(),
+                    }
                 })?
                 .map_err(|_| ConcurrencyError::Closed)?;
 
@@ -297,6 +453,7 @@ impl ConcurrencyController {
                     return Err(ConcurrencyError::Closed);
                 }
                 Err(_) => {
+                    self.record_acquire_failure(AcquireScope::Group(gid));
                     return Err(ConcurrencyError::AcquireTimeout {
                         resource: format!("group: {} (limit: {})", gid, limit),
                     });
@@ -317,6 +474,7 @@ impl ConcurrencyController {
                     return Err(ConcurrencyError::Closed);
                 }
                 Err(_) => {
+                    self.record_acquire_failure(AcquireScope::Environment(env));
                     return Err(ConcurrencyError::AcquireTimeout {
                         resource: format!("environment: {} (limit: {})", env, limit),
                     });
@@ -332,25 +490,96 @@ impl ConcurrencyController {
     }
 
     /// 获取或创建分组信号量
+    ///
+    /// 分组上限来自数据库中的分组元数据并按 TTL 缓存；上限变化时就地调整信号量，
+    /// 已持有的许可不受影响
     async fn get_or_create_group_semaphore(&self, group_id: &str) -> GroupSemaphore {
-        let mut groups = self.group_semaphores.lock().await;
+        {
+            let groups = self.group_semaphores.lock().await;
+            if let Some(sem) = groups.get(group_id) {
+                if self.is_group_fresh(sem) {
+                    return sem.clone();
+                }
+            }
+        }
 
-        if let Some(sem) = groups.get(group_id) {
+        // 在锁外查询数据库，避免阻塞其他分组
+        let metadata = self.load_group_metadata(group_id).await;
+        let limit = self.group_limit_for(metadata.as_ref());
+        let loaded_at = Some(Instant::now());
+
+        let mut groups = self.group_semaphores.lock().await;
+        if let Some(sem) = groups.get_mut(group_id) {
+            if sem.limit != limit {
+                info!(
+                    group_id = group_id,
+                    old_limit = sem.limit,
+                    new_limit = limit,
+                    "Group concurrency limit changed"
+                );
+                resize_semaphore(&sem.semaphore, sem.limit.max(1), limit.max(1));
+                sem.limit = limit;
+            }
+            sem.loaded_at = loaded_at;
             return sem.clone();
         }
 
-        let limit = self.config.group_limit.unwrap_or(self.config.global_limit);
-        let semaphore = Arc::new(Semaphore::new(limit.max(1) as usize));
-
         let sem = GroupSemaphore {
-            semaphore: semaphore.clone(),
+            semaphore: Arc::new(Semaphore::new(limit.max(1) as usize)),
             limit,
+            loaded_at,
         };
 
         groups.insert(group_id.to_string(), sem.clone());
         sem
     }
 
+    /// 分组信号量的元数据是否仍然有效
+    fn is_group_fresh(&self, sem: &GroupSemaphore) -> bool {
+        if self.db.is_none() {
+            return true;
+        }
+        sem.loaded_at.is_some_and(|loaded_at| {
+            loaded_at.elapsed() < Duration::from_secs(self.config.group_metadata_ttl_secs)
+        })
+    }
+
+    /// 读取分组并发元数据（未配置数据库、分组不存在或查询失败时返回 None）
+    async fn load_group_metadata(&self, group_id: &str) -> Option<GroupConcurrencyMetadata> {
+        let db = self.db.as_ref()?;
+        let id = Uuid::parse_str(group_id).ok()?;
+
+        match sqlx::query_as::<_, GroupConcurrencyMetadata>(
+            "SELECT concurrent_limit, is_production FROM assets_groups WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(db)
+        .await
+        {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!(error = %e, group_id = group_id, "Failed to load group concurrency metadata");
+                None
+            }
+        }
+    }
+
+    /// 计算分组并发上限：分组自身上限 > 生产分组使用生产限制 > 全局分组限制 > 全局限制
+    pub fn group_limit_for(&self, metadata: Option<&GroupConcurrencyMetadata>) -> i32 {
+        let default_limit = self.config.group_limit.unwrap_or(self.config.global_limit);
+        match metadata {
+            Some(GroupConcurrencyMetadata {
+                concurrent_limit: Some(limit),
+                ..
+            }) if *limit > 0 => *limit,
+            Some(GroupConcurrencyMetadata {
+                is_production: true,
+                ..
+            }) => self.config.production_limit.unwrap_or(default_limit),
+            _ => default_limit,
+        }
+    }
+
     /// 获取或创建环境信号量
     async fn get_or_create_env_semaphore(&self, environment: &str) -> EnvironmentSemaphore {
         let mut envs = self.environment_semaphores.lock().await;
@@ -440,6 +669,7 @@ impl ConcurrencyController {
                 0.0
             },
             strategy: self.config.strategy,
+            tasks_waiting: self.job_scheduler.waiting(),
             group_stats,
             environment_stats: env_stats,
         }
@@ -451,6 +681,27 @@ impl ConcurrencyController {
     }
 }
 
+/// 调整信号量的总许可数
+///
+/// 扩容直接增加许可；缩容时先回收空闲许可，不足部分在后台等待在途任务释放后回收
+fn resize_semaphore(semaphore: &Arc<Semaphore>, old_limit: i32, new_limit: i32) {
+    if new_limit > old_limit {
+        semaphore.add_permits((new_limit - old_limit) as usize);
+    } else if new_limit < old_limit {
+        let excess = (old_limit - new_limit) as usize;
+        let forgotten = semaphore.forget_permits(excess);
+        let remaining = excess - forgotten;
+        if remaining > 0 {
+            let semaphore = semaphore.clone();
+            tokio::spawn(async move {
+                if let Ok(permits) = semaphore.acquire_many_owned(remaining as u32).await {
+                    permits.forget();
+                }
+            });
+        }
+    }
+}
+
 /// 并发统计
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ConcurrencyStats {
@@ -459,6 +710,8 @@ pub struct ConcurrencyStats {
     pub global_available: i32,
     pub global_utilization_percent: f32,
     pub strategy: ConcurrencyStrategy,
+    /// 等待公平调度名额的作业任务数
+    pub tasks_waiting: usize,
     pub group_stats: HashMap<String, ScopeConcurrencyStats>,
     pub environment_stats: HashMap<String, ScopeConcurrencyStats>,
 }
@@ -574,6 +827,54 @@ mod tests {
         // 第三次获取应该失败（达到限制）
         let result = controller.acquire(None, None).await;
         assert!(result.is_err(), "Third acquire should fail due to limit");
+
+        // 失败次数在取出后清零
+        assert_eq!(controller.take_acquire_failures().global, 1);
+        assert_eq!(controller.take_acquire_failures().total(), 0);
+    }
+
+    #[test]
+    fn test_group_limit_for_metadata() {
+        let controller = ConcurrencyController::new(ConcurrencyConfig::default());
+
+        // 无元数据时使用全局分组限制
+        assert_eq!(controller.group_limit_for(None), 10);
+
+        let db_cluster = GroupConcurrencyMetadata {
+            concurrent_limit: Some(2),
+            is_production: true,
+        };
+        assert_eq!(controller.group_limit_for(Some(&db_cluster)), 2);
+
+        let web_fleet = GroupConcurrencyMetadata {
+            concurrent_limit: Some(50),
+            is_production: false,
+        };
+        assert_eq!(controller.group_limit_for(Some(&web_fleet)), 50);
+
+        // 生产分组未声明上限时使用生产限制
+        let production = GroupConcurrencyMetadata {
+            concurrent_limit: None,
+            is_production: true,
+        };
+        assert_eq!(controller.group_limit_for(Some(&production)), 5);
+    }
+
+    #[tokio::test]
+    async fn test_resize_semaphore() {
+        let semaphore = Arc::new(Semaphore::new(4));
+        let held = semaphore.clone().acquire_many_owned(3).await.unwrap();
+
+        // 扩容立即生效
+        resize_semaphore(&semaphore, 4, 6);
+        assert_eq!(semaphore.available_permits(), 3);
+
+        // 缩容先回收空闲许可，在途许可释放后再回收剩余部分
+        resize_semaphore(&semaphore, 6, 2);
+        assert_eq!(semaphore.available_permits(), 0);
+        drop(held);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(semaphore.available_permits(), 2);
     }
 
     #[tokio::test]
@@ -4,8 +4,9 @@
 use crate::config::DatabaseConfig;
 use secrecy::ExposeSecret;
 use sqlx::{
+    migrate::Migrator,
     postgres::{PgConnectOptions, PgPoolOptions},
-    Connection, PgPool,
+    Connection, PgConnection, PgPool,
 };
 use std::str::FromStr;
 use std::time::Duration;
@@ -115,19 +116,232 @@ fn quote_ident(ident: &str) -> String {
     format!("\"{}\"", ident.replace('"', "\"\""))
 }
 
-/// 运行数据库迁移
-pub async fn run_migrations(pool: &PgPool) -> Result<(), DbError> {
-    tracing::info!("Running database migrations...");
+/// 迁移执行互斥锁（PostgreSQL advisory lock key），多实例同时启动时只有一个实例执行迁移
+const MIGRATION_LOCK_KEY: i64 = 0x6f70_735f_6d69_6772;
 
+fn migrator() -> Migrator {
     sqlx::migrate!("../../migrations")
-        .run(pool)
+}
+
+/// 二进制内嵌的迁移
+#[derive(Debug, Clone)]
+pub struct LocalMigration {
+    pub version: i64,
+    pub description: String,
+    pub checksum: Vec<u8>,
+}
+
+/// 数据库中已记录的迁移
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub success: bool,
+    pub checksum: Vec<u8>,
+}
+
+/// 迁移校验问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationProblem {
+    /// 数据库中存在本二进制未知的迁移（二进制版本落后于数据库）
+    UnknownApplied { version: i64 },
+    /// 已应用迁移的内容被修改
+    ChecksumMismatch { version: i64 },
+    /// 上次执行失败遗留的迁移记录
+    Dirty { version: i64 },
+    /// 待应用迁移的版本低于已应用的最高版本（违反只前进原则）
+    OutOfOrder { version: i64, latest_applied: i64 },
+}
+
+impl std::fmt::Display for MigrationProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationProblem::UnknownApplied { version } => {
+                write!(f, "migration {} is applied but unknown to this binary", version)
+            }
+            MigrationProblem::ChecksumMismatch { version } => {
+                write!(f, "migration {} was modified after being applied", version)
+            }
+            MigrationProblem::Dirty { version } => {
+                write!(f, "migration {} previously failed and must be fixed manually", version)
+            }
+            MigrationProblem::OutOfOrder {
+                version,
+                latest_applied,
+            } => write!(
+                f,
+                "pending migration {} is older than applied migration {}",
+                version, latest_applied
+            ),
+        }
+    }
+}
+
+/// 迁移计划：待应用的迁移与校验问题
+#[derive(Debug, Clone, Default)]
+pub struct MigrationPlan {
+    /// 已应用的迁移数量
+    pub applied_count: usize,
+    /// 待应用的迁移（按版本升序）
+    pub pending: Vec<LocalMigration>,
+    pub problems: Vec<MigrationProblem>,
+}
+
+impl MigrationPlan {
+    /// 只前进校验：存在任何问题时拒绝执行
+    pub fn verify(&self) -> Result<(), DbError> {
+        if self.problems.is_empty() {
+            return Ok(());
+        }
+        let details: Vec<String> = self.problems.iter().map(ToString::to_string).collect();
+        Err(DbError::MigrationVerificationFailed(details.join("; ")))
+    }
+}
+
+/// 对比内嵌迁移与数据库记录，生成迁移计划
+pub fn plan_migrations(local: &[LocalMigration], applied: &[AppliedMigration]) -> MigrationPlan {
+    let mut plan = MigrationPlan::default();
+    let latest_applied = applied
+        .iter()
+        .filter(|m| m.success)
+        .map(|m| m.version)
+        .max();
+
+    for record in applied {
+        if !record.success {
+            plan.problems.push(MigrationProblem::Dirty {
+                version: record.version,
+            });
+        } else if !local.iter().any(|m| m.version == record.version) {
+            plan.problems.push(MigrationProblem::UnknownApplied {
+                version: record.version,
+            });
+        }
+    }
+
+    for migration in local {
+        match applied.iter().find(|m| m.version == migration.version) {
+            Some(record) if record.success && record.checksum != migration.checksum => {
+                plan.problems.push(MigrationProblem::ChecksumMismatch {
+                    version: migration.version,
+                });
+            }
+            Some(_) => plan.applied_count += 1,
+            None => {
+                if let Some(latest) = latest_applied.filter(|latest| migration.version < *latest) {
+                    plan.problems.push(MigrationProblem::OutOfOrder {
+                        version: migration.version,
+                        latest_applied: latest,
+                    });
+                }
+                plan.pending.push(migration.clone());
+            }
+        }
+    }
+
+    plan.pending.sort_by_key(|m| m.version);
+    plan
+}
+
+fn local_migrations(migrator: &Migrator) -> Vec<LocalMigration> {
+    migrator
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| LocalMigration {
+            version: m.version,
+            description: m.description.to_string(),
+            checksum: m.checksum.to_vec(),
+        })
+        .collect()
+}
+
+async fn applied_migrations(conn: &mut PgConnection) -> Result<Vec<AppliedMigration>, DbError> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| DbError::MigrationFailed(e.to_string()))?;
+    if !table_exists {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_as::<_, AppliedMigration>(
+        "SELECT version, description, success, checksum FROM _sqlx_migrations ORDER BY version",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| DbError::MigrationFailed(e.to_string()))
+}
+
+/// 生成迁移计划（只读，用于 dry-run 与启动前检查）
+pub async fn migration_plan(pool: &PgPool) -> Result<MigrationPlan, DbError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| DbError::ConnectionFailed(e.to_string()))?;
+    let applied = applied_migrations(&mut conn).await?;
+    Ok(plan_migrations(&local_migrations(&migrator()), &applied))
+}
+
+/// 运行数据库迁移
+///
+/// 在 advisory lock 内重新生成计划并校验只前进约束，
+/// 其他实例正在迁移时阻塞等待，拿到锁后仅执行剩余的迁移。
+pub async fn run_migrations(pool: &PgPool) -> Result<MigrationPlan, DbError> {
+    let mut conn = pool
+        .acquire()
         .await
-        .map_err(|e| {
-            tracing::error!("Migration failed: {}", e);
-            DbError::MigrationFailed(e.to_string())
-        })?;
+        .map_err(|e| DbError::ConnectionFailed(e.to_string()))?;
+
+    tracing::info!("Acquiring migration lock...");
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| DbError::MigrationFailed(e.to_string()))?;
+
+    let result = run_migrations_locked(&mut conn).await;
 
-    tracing::info!("Migrations completed successfully");
+    if let Err(e) = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+    {
+        tracing::warn!(error = %e, "Failed to release migration lock");
+    }
+
+    result
+}
+
+async fn run_migrations_locked(conn: &mut PgConnection) -> Result<MigrationPlan, DbError> {
+    let migrator = migrator();
+    let applied = applied_migrations(conn).await?;
+    let plan = plan_migrations(&local_migrations(&migrator), &applied);
+    plan.verify()?;
+
+    if plan.pending.is_empty() {
+        tracing::info!(applied = plan.applied_count, "Database schema is up to date");
+        return Ok(plan);
+    }
+
+    tracing::info!(pending = plan.pending.len(), "Running database migrations...");
+    migrator.run(&mut *conn).await.map_err(|e| {
+        tracing::error!("Migration failed: {}", e);
+        DbError::MigrationFailed(e.to_string())
+    })?;
+
+    tracing::info!(applied = plan.pending.len(), "Migrations completed successfully");
+    Ok(plan)
+}
+
+/// 启动前检查：存在待应用迁移或校验问题时拒绝启动（不自动执行迁移）
+pub async fn ensure_migrated(pool: &PgPool) -> Result<(), DbError> {
+    let plan = migration_plan(pool).await?;
+    plan.verify()?;
+    if !plan.pending.is_empty() {
+        let versions: Vec<String> = plan.pending.iter().map(|m| m.version.to_string()).collect();
+        return Err(DbError::PendingMigrations(versions.join(", ")));
+    }
     Ok(())
 }
 
@@ -163,6 +377,12 @@ pub enum DbError {
     #[error("Migration failed: {0}")]
     MigrationFailed(String),
 
+    #[error("Migration verification failed: {0}")]
+    MigrationVerificationFailed(String),
+
+    #[error("Pending migrations not applied: {0}; run `ops-service migrate` first")]
+    PendingMigrations(String),
+
     #[error("Health check failed: {0}")]
     HealthCheckFailed(String),
 }
@@ -184,13 +404,64 @@ mod tests {
         let unhealthy = HealthStatus::Unhealthy("Connection refused".to_string());
 
         match healthy {
-            HealthStatus::Healthy => assert!(true),
-            _ => assert!(false),
+            HealthStatus::Healthy => {}
+            _ => panic!("expected Healthy"),
         }
 
         match unhealthy {
             HealthStatus::Unhealthy(msg) => assert_eq!(msg, "Connection refused"),
-            _ => assert!(false),
+            _ => panic!("expected Unhealthy"),
+        }
+    }
+
+    fn local(version: i64, checksum: u8) -> LocalMigration {
+        LocalMigration {
+            version,
+            description: format!("migration {}", version),
+            checksum: vec![checksum],
         }
     }
+
+    fn applied(version: i64, checksum: u8, success: bool) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            description: format!("migration {}", version),
+            success,
+            checksum: vec![checksum],
+        }
+    }
+
+    #[test]
+    fn test_plan_lists_pending_migrations() {
+        let plan =
+            plan_migrations(&[local(1, 1), local(2, 2), local(3, 3)], &[applied(1, 1, true)]);
+        assert_eq!(plan.applied_count, 1);
+        assert_eq!(plan.pending.iter().map(|m| m.version).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(plan.verify().is_ok());
+    }
+
+    #[test]
+    fn test_plan_rejects_non_forward_states() {
+        let plan = plan_migrations(
+            &[local(1, 9), local(2, 2), local(4, 4)],
+            &[
+                applied(1, 1, true),
+                applied(3, 3, true),
+                applied(4, 4, false),
+            ],
+        );
+        assert_eq!(
+            plan.problems,
+            vec![
+                MigrationProblem::UnknownApplied { version: 3 },
+                MigrationProblem::Dirty { version: 4 },
+                MigrationProblem::ChecksumMismatch { version: 1 },
+                MigrationProblem::OutOfOrder {
+                    version: 2,
+                    latest_applied: 3
+                },
+            ]
+        );
+        assert!(matches!(plan.verify(), Err(DbError::MigrationVerificationFailed(_))));
+    }
 }
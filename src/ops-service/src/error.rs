@@ -5,12 +5,166 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Json, Response},
 };
+use common::error::ErrorKind;
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::i18n::Locale;
+
 /// 结果类型别名
 pub type Result<T> = std::result::Result<T, AppError>;
 
+/// 错误码目录
+///
+/// 每个错误码对应一个稳定的机器可读字符串与 HTTP 状态码，
+/// 客户端应基于 `code` 字段（而不是 message 文本）区分错误场景。
+/// 新增错误码时必须同步加入 `ErrorCode::ALL`，以便 /api/errors 端点完整暴露目录。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// 未认证或凭证无效
+    Unauthenticated,
+    /// 认证失败（带具体原因）
+    AuthenticationFailed,
+    /// 无权访问
+    PermissionDenied,
+    /// 需要完成双因素认证（登录验证码、绑定或二次验证）
+    TwoFactorRequired,
+    /// 资源不存在
+    ResourceNotFound,
+    /// 请求格式或参数错误
+    BadRequest,
+    /// 业务校验失败
+    ValidationFailed,
+    /// 请求体超过大小上限
+    PayloadTooLarge,
+    /// 请求字段超出长度或数量限制
+    PayloadLimitExceeded,
+    /// 触发速率或并发限制
+    RateLimited,
+    /// 请求超时
+    Timeout,
+    /// 控制面处于只读维护模式
+    MaintenanceMode,
+    /// SSH 连接失败
+    SshConnectionFailed,
+    /// SSH 认证失败
+    SshAuthenticationFailed,
+    /// SSH 主机密钥验证失败
+    SshHostKeyVerificationFailed,
+    /// SSH 命令执行失败
+    SshExecutionFailed,
+    /// 数据库错误
+    DatabaseError,
+    /// 服务端配置错误
+    ConfigurationError,
+    /// 内部错误
+    InternalError,
+}
+
+impl ErrorCode {
+    /// 完整错误码目录
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::Unauthenticated,
+        ErrorCode::AuthenticationFailed,
+        ErrorCode::PermissionDenied,
+        ErrorCode::TwoFactorRequired,
+        ErrorCode::ResourceNotFound,
+        ErrorCode::BadRequest,
+        ErrorCode::ValidationFailed,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::PayloadLimitExceeded,
+        ErrorCode::RateLimited,
+        ErrorCode::Timeout,
+        ErrorCode::MaintenanceMode,
+        ErrorCode::SshConnectionFailed,
+        ErrorCode::SshAuthenticationFailed,
+        ErrorCode::SshHostKeyVerificationFailed,
+        ErrorCode::SshExecutionFailed,
+        ErrorCode::DatabaseError,
+        ErrorCode::ConfigurationError,
+        ErrorCode::InternalError,
+    ];
+
+    /// 机器可读的错误码字符串
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Unauthenticated => "UNAUTHENTICATED",
+            ErrorCode::AuthenticationFailed => "AUTHENTICATION_FAILED",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::TwoFactorRequired => "TWO_FACTOR_REQUIRED",
+            ErrorCode::ResourceNotFound => "RESOURCE_NOT_FOUND",
+            ErrorCode::BadRequest => "BAD_REQUEST",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::PayloadLimitExceeded => "PAYLOAD_LIMIT_EXCEEDED",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::MaintenanceMode => "MAINTENANCE_MODE",
+            ErrorCode::SshConnectionFailed => "SSH_CONNECTION_FAILED",
+            ErrorCode::SshAuthenticationFailed => "SSH_AUTHENTICATION_FAILED",
+            ErrorCode::SshHostKeyVerificationFailed => "SSH_HOST_KEY_VERIFICATION_FAILED",
+            ErrorCode::SshExecutionFailed => "SSH_EXECUTION_FAILED",
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::ConfigurationError => "CONFIGURATION_ERROR",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+        }
+    }
+
+    /// 共享错误分类（HTTP 状态码与是否可重试由分类派生，与 Runner 保持一致）
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ErrorCode::Unauthenticated | ErrorCode::AuthenticationFailed => {
+                ErrorKind::Unauthenticated
+            }
+            ErrorCode::PermissionDenied | ErrorCode::TwoFactorRequired => {
+                ErrorKind::PermissionDenied
+            }
+            ErrorCode::ResourceNotFound => ErrorKind::NotFound,
+            ErrorCode::BadRequest | ErrorCode::ValidationFailed => ErrorKind::InvalidInput,
+            ErrorCode::PayloadTooLarge => ErrorKind::PayloadTooLarge,
+            ErrorCode::PayloadLimitExceeded => ErrorKind::LimitExceeded,
+            ErrorCode::RateLimited => ErrorKind::RateLimited,
+            ErrorCode::Timeout => ErrorKind::Timeout,
+            ErrorCode::MaintenanceMode => ErrorKind::Unavailable,
+            ErrorCode::SshConnectionFailed => ErrorKind::Network,
+            ErrorCode::SshAuthenticationFailed => ErrorKind::HostAuth,
+            ErrorCode::SshHostKeyVerificationFailed => ErrorKind::HostKey,
+            ErrorCode::SshExecutionFailed => ErrorKind::Execution,
+            ErrorCode::DatabaseError => ErrorKind::Storage,
+            ErrorCode::ConfigurationError => ErrorKind::Configuration,
+            ErrorCode::InternalError => ErrorKind::Internal,
+        }
+    }
+
+    /// 对应的 HTTP 状态码
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.kind().http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// 错误码说明（用于目录文档）
+    pub fn description(&self) -> &'static str {
+        self.description_in(Locale::EnUs)
+    }
+
+    /// 指定语言的错误码说明
+    pub fn description_in(&self, locale: Locale) -> &'static str {
+        let key = format!("error_code.{}", self.as_str());
+        crate::i18n::lookup(locale, &key).unwrap_or("")
+    }
+
+    /// 是否允许客户端重试
+    pub fn retryable(&self) -> bool {
+        self.kind().retryable()
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// 应用错误类型
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -29,6 +183,9 @@ pub enum AppError {
     #[error("Access denied")]
     Forbidden,
 
+    #[error("Two-factor authentication required: {0}")]
+    TwoFactorRequired(String),
+
     #[error("Resource not found: {0}")]
     NotFound(String),
 
@@ -38,12 +195,21 @@ pub enum AppError {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Payload limit exceeded: {0}")]
+    PayloadLimitExceeded(String),
+
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
     #[error("Timeout: {0}")]
     Timeout(String),
 
+    #[error("Maintenance mode: {0}")]
+    Maintenance(String),
+
     #[error("Internal server error: {0}")]
     Internal(String),
 
@@ -53,48 +219,91 @@ pub enum AppError {
     #[error("SSH authentication failed: {0}")]
     SshAuthenticationError(String),
 
+    #[error("SSH host key verification failed: {0}")]
+    SshHostKeyVerificationError(Box<crate::ssh::HostKeyFailure>),
+
     #[error("SSH execution error: {0}")]
     SshExecutionError(String),
 }
 
 impl AppError {
-    /// 获取 HTTP 状态码
-    pub fn status_code(&self) -> StatusCode {
+    /// 获取错误码目录中的错误码
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            AppError::Unauthorized => ErrorCode::Unauthenticated,
+            AppError::Authentication(_) => ErrorCode::AuthenticationFailed,
+            AppError::Forbidden => ErrorCode::PermissionDenied,
+            AppError::TwoFactorRequired(_) => ErrorCode::TwoFactorRequired,
+            AppError::NotFound(_) => ErrorCode::ResourceNotFound,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::Validation(_) => ErrorCode::ValidationFailed,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::PayloadLimitExceeded(_) => ErrorCode::PayloadLimitExceeded,
+            AppError::RateLimitExceeded => ErrorCode::RateLimited,
+            AppError::Timeout(_) => ErrorCode::Timeout,
+            AppError::Maintenance(_) => ErrorCode::MaintenanceMode,
+            AppError::SshConnectionError(_) => ErrorCode::SshConnectionFailed,
+            AppError::SshAuthenticationError(_) => ErrorCode::SshAuthenticationFailed,
+            AppError::SshHostKeyVerificationError(_) => ErrorCode::SshHostKeyVerificationFailed,
+            AppError::SshExecutionError(_) => ErrorCode::SshExecutionFailed,
+            AppError::Database(_) => ErrorCode::DatabaseError,
+            AppError::Config(_) => ErrorCode::ConfigurationError,
+            AppError::Internal(_) => ErrorCode::InternalError,
+        }
+    }
+
+    /// 共享错误分类
+    ///
+    /// 在错误码的分类基础上细化：SSH 连接阶段的超时归为超时
+    pub fn kind(&self) -> ErrorKind {
         match self {
-            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
-            AppError::Authentication(_) => StatusCode::UNAUTHORIZED,
-            AppError::Forbidden => StatusCode::FORBIDDEN,
-            AppError::NotFound(_) => StatusCode::NOT_FOUND,
-            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
-            AppError::Validation(_) => StatusCode::BAD_REQUEST,
-            AppError::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
-            AppError::Timeout(_) => StatusCode::REQUEST_TIMEOUT,
-            AppError::SshConnectionError(_)
-            | AppError::SshAuthenticationError(_)
-            | AppError::SshExecutionError(_)
-            | AppError::Database(_)
-            | AppError::Config(_)
-            | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::SshConnectionError(msg)
+                if msg.contains("超时") || msg.contains("timeout") =>
+            {
+                ErrorKind::Timeout
+            }
+            _ => self.error_code().kind(),
         }
     }
 
+    /// 获取 HTTP 状态码
+    pub fn status_code(&self) -> StatusCode {
+        self.error_code().status_code()
+    }
+
     /// 获取用户友好的错误消息（不包含敏感信息）
     pub fn user_message(&self) -> String {
-        match self {
-            AppError::Unauthorized => "Authentication failed".to_string(),
-            AppError::Authentication(msg) => msg.clone(),
-            AppError::NotFound(msg) => format!("Resource not found: {}", msg),
-            AppError::Forbidden => "Access denied".to_string(),
-            AppError::BadRequest(msg) => msg.clone(),
-            AppError::Validation(msg) => msg.clone(),
-            AppError::RateLimitExceeded => "Rate limit exceeded".to_string(),
-            AppError::Timeout(msg) => format!("Request timeout: {}", msg),
-            AppError::SshConnectionError(_) => "SSH connection failed".to_string(),
-            AppError::SshAuthenticationError(_) => "SSH authentication failed".to_string(),
-            AppError::SshExecutionError(_) => "SSH command execution failed".to_string(),
-            AppError::Database(_) => "Database error occurred".to_string(),
-            AppError::Config(_) => "Configuration error".to_string(),
-            AppError::Internal(msg) => format!("Internal server error: {}", msg),
+        self.message_in(Locale::EnUs)
+    }
+
+    /// 获取指定语言的用户友好错误消息
+    ///
+    /// 客户端错误的详情来自业务代码，不做翻译，只套用对应语言的消息模板
+    pub fn message_in(&self, locale: Locale) -> String {
+        let (key, detail) = match self {
+            AppError::Unauthorized => ("error.unauthorized", None),
+            AppError::Authentication(msg) => ("error.authentication", Some(msg)),
+            AppError::NotFound(msg) => ("error.not_found", Some(msg)),
+            AppError::Forbidden => ("error.forbidden", None),
+            AppError::TwoFactorRequired(msg) => ("error.two_factor_required", Some(msg)),
+            AppError::BadRequest(msg) => ("error.bad_request", Some(msg)),
+            AppError::Validation(msg) => ("error.validation", Some(msg)),
+            AppError::PayloadTooLarge(msg) => ("error.payload_too_large", Some(msg)),
+            AppError::PayloadLimitExceeded(msg) => ("error.payload_limit", Some(msg)),
+            AppError::RateLimitExceeded => ("error.rate_limited", None),
+            AppError::Timeout(msg) => ("error.timeout", Some(msg)),
+            AppError::Maintenance(msg) => ("error.maintenance", Some(msg)),
+            AppError::SshConnectionError(_) => ("error.ssh_connection", None),
+            AppError::SshAuthenticationError(_) => ("error.ssh_authentication", None),
+            AppError::SshHostKeyVerificationError(_) => ("error.ssh_host_key", None),
+            AppError::SshExecutionError(_) => ("error.ssh_execution", None),
+            AppError::Database(_) => ("error.database", None),
+            AppError::Config(_) => ("error.config", None),
+            AppError::Internal(msg) => ("error.internal", Some(msg)),
+        };
+        match detail {
+            Some(detail) => crate::i18n::tf_in(locale, key, &[("detail", detail)]),
+            None => crate::i18n::t_in(locale, key).to_string(),
         }
     }
 
@@ -103,6 +312,26 @@ impl AppError {
         self.status_code().as_u16()
     }
 
+    /// 获取可安全返回给客户端的错误详情
+    /// 仅客户端错误携带详情，服务端错误的内部信息不外泄
+    pub fn detail(&self) -> Option<String> {
+        if !self.error_code().kind().exposes_detail() {
+            return None;
+        }
+        match self {
+            AppError::Authentication(msg)
+            | AppError::NotFound(msg)
+            | AppError::TwoFactorRequired(msg)
+            | AppError::BadRequest(msg)
+            | AppError::Validation(msg)
+            | AppError::PayloadTooLarge(msg)
+            | AppError::PayloadLimitExceeded(msg)
+            | AppError::Timeout(msg)
+            | AppError::Maintenance(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+
     // 便捷方法
     pub fn not_found(msg: &str) -> Self {
         AppError::NotFound(msg.to_string())
@@ -130,18 +359,7 @@ impl AppError {
 
     /// 将错误转换为 SSH 失败原因分类（用于作业任务）
     pub fn to_ssh_failure_reason(&self) -> crate::models::job::FailureReason {
-        match self {
-            AppError::SshAuthenticationError(_) => crate::models::job::FailureReason::AuthFailed,
-            AppError::SshConnectionError(msg)
-                if msg.contains("超时") || msg.contains("timeout") =>
-            {
-                crate::models::job::FailureReason::ConnectionTimeout
-            }
-            AppError::Timeout(_) => crate::models::job::FailureReason::ConnectionTimeout,
-            AppError::SshConnectionError(_) => crate::models::job::FailureReason::NetworkError,
-            AppError::SshExecutionError(_) => crate::models::job::FailureReason::CommandFailed,
-            _ => crate::models::job::FailureReason::Unknown,
-        }
+        self.kind().failure_reason().into()
     }
 }
 
@@ -153,29 +371,81 @@ pub struct ErrorResponse {
 
 #[derive(Serialize)]
 pub struct ErrorDetail {
-    pub code: u16,
+    /// 机器可读错误码（见 ErrorCode 目录）
+    pub code: ErrorCode,
+    /// HTTP 状态码
+    pub status: u16,
+    /// 用户友好的错误消息
     pub message: String,
-    pub request_id: String,
+    /// 错误详情（仅客户端错误）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// 关联 ID（用于日志排查）
+    pub correlation_id: String,
+}
+
+/// 错误码目录条目（/api/errors 端点返回）
+#[derive(Debug, Serialize)]
+pub struct ErrorCatalogEntry {
+    pub code: ErrorCode,
+    pub status: u16,
+    pub description: &'static str,
+    pub retryable: bool,
+}
+
+impl From<ErrorCode> for ErrorCatalogEntry {
+    fn from(code: ErrorCode) -> Self {
+        Self {
+            code,
+            status: code.status_code().as_u16(),
+            description: code.description(),
+            retryable: code.retryable(),
+        }
+    }
+}
+
+/// 获取完整的错误码目录
+pub fn error_catalog() -> Vec<ErrorCatalogEntry> {
+    ErrorCode::ALL
+        .iter()
+        .copied()
+        .map(ErrorCatalogEntry::from)
+        .collect()
+}
+
+/// 获取指定语言的错误码目录
+pub fn error_catalog_in(locale: Locale) -> Vec<ErrorCatalogEntry> {
+    ErrorCode::ALL
+        .iter()
+        .map(|code| ErrorCatalogEntry {
+            description: code.description_in(locale),
+            ..ErrorCatalogEntry::from(*code)
+        })
+        .collect()
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let status = self.status_code();
-        let request_id = uuid::Uuid::new_v4().to_string();
+        let correlation_id = crate::middleware::request_id::current_request_id()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
         let error_response = ErrorResponse {
             error: ErrorDetail {
-                code: self.code(),
-                message: self.user_message(),
-                request_id,
+                code: self.error_code(),
+                status: self.code(),
+                message: self.message_in(crate::i18n::current_locale()),
+                detail: self.detail(),
+                correlation_id,
             },
         };
 
         // 记录错误日志
         tracing::error!(
-            code = self.code(),
+            code = %error_response.error.code,
+            status = self.code(),
             message = %self,
-            request_id = %error_response.error.request_id,
+            correlation_id = %error_response.error.correlation_id,
             "Application error"
         );
 
@@ -197,6 +467,27 @@ impl From<config::ConfigError> for AppError {
     }
 }
 
+/// 从 ConcurrencyError 转换
+impl From<crate::concurrency::ConcurrencyError> for AppError {
+    fn from(e: crate::concurrency::ConcurrencyError) -> Self {
+        match e {
+            crate::concurrency::ConcurrencyError::Rejected { .. }
+            | crate::concurrency::ConcurrencyError::LimitExceeded { .. } => {
+                AppError::RateLimitExceeded
+            }
+            crate::concurrency::ConcurrencyError::QueueFull { .. } => {
+                AppError::Internal("Concurrency queue is full".to_string())
+            }
+            crate::concurrency::ConcurrencyError::AcquireTimeout { .. } => {
+                AppError::Timeout("Acquiring concurrency permit timed out".to_string())
+            }
+            crate::concurrency::ConcurrencyError::Closed => {
+                AppError::Internal("Concurrency controller closed".to_string())
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -208,6 +499,55 @@ mod tests {
         assert_eq!(AppError::NotFound("test".to_string()).code(), 404);
         assert_eq!(AppError::BadRequest("test".to_string()).code(), 400);
         assert_eq!(AppError::RateLimitExceeded.code(), 429);
+        assert_eq!(AppError::PayloadTooLarge("test".to_string()).code(), 413);
+        assert_eq!(AppError::PayloadLimitExceeded("test".to_string()).code(), 422);
+    }
+
+    #[test]
+    fn test_error_code_strings_match_serialization() {
+        for code in ErrorCode::ALL {
+            let json = serde_json::to_string(code).unwrap();
+            assert_eq!(json, format!("\"{}\"", code.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_server_errors_have_no_detail() {
+        assert!(AppError::Internal("secret path".to_string())
+            .detail()
+            .is_none());
+        assert_eq!(
+            AppError::NotFound("Job not found".to_string()).detail(),
+            Some("Job not found".to_string())
+        );
+    }
+
+    #[test]
+    fn test_failure_reason_derived_from_kind() {
+        use crate::models::job::FailureReason;
+
+        let timeout = AppError::SshConnectionError("连接超时".to_string());
+        assert_eq!(timeout.kind(), ErrorKind::Timeout);
+        assert_eq!(timeout.to_ssh_failure_reason(), FailureReason::ConnectionTimeout);
+        assert_eq!(timeout.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let refused = AppError::SshConnectionError("connection refused".to_string());
+        assert_eq!(refused.to_ssh_failure_reason(), FailureReason::NetworkError);
+        assert_eq!(
+            AppError::SshAuthenticationError("denied".to_string()).to_ssh_failure_reason(),
+            FailureReason::AuthFailed
+        );
+        assert_eq!(AppError::validation("bad").to_ssh_failure_reason(), FailureReason::Unknown);
+    }
+
+    #[test]
+    fn test_status_codes_match_shared_taxonomy() {
+        for code in ErrorCode::ALL {
+            assert_eq!(code.status_code().as_u16(), code.kind().http_status());
+        }
+        assert_eq!(ErrorCode::MaintenanceMode.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(ErrorCode::DatabaseError.retryable());
+        assert!(!ErrorCode::ValidationFailed.retryable());
     }
 
     #[test]
@@ -217,25 +557,17 @@ mod tests {
         assert_eq!(message, "Database error occurred");
         assert!(!message.contains("sqlx"));
     }
-}
 
-/// 从 ConcurrencyError 转换
-impl From<crate::concurrency::ConcurrencyError> for AppError {
-    fn from(e: crate::concurrency::ConcurrencyError) -> Self {
-        match e {
-            crate::concurrency::ConcurrencyError::Rejected { .. }
-            | crate::concurrency::ConcurrencyError::LimitExceeded { .. } => {
-                AppError::RateLimitExceeded
-            }
-            crate::concurrency::ConcurrencyError::QueueFull { .. } => {
-                AppError::Internal("Concurrency queue is full".to_string())
-            }
-            crate::concurrency::ConcurrencyError::AcquireTimeout { .. } => {
-                AppError::Timeout("Acquiring concurrency permit timed out".to_string())
-            }
-            crate::concurrency::ConcurrencyError::Closed => {
-                AppError::Internal("Concurrency controller closed".to_string())
-            }
+    #[test]
+    fn test_message_localized_with_detail() {
+        let error = AppError::NotFound("Job not found".to_string());
+        assert_eq!(error.message_in(Locale::ZhCn), "资源不存在：Job not found");
+        assert_eq!(AppError::Forbidden.message_in(Locale::ZhCn), "无权访问");
+        assert_eq!(AppError::validation("bad").message_in(Locale::EnUs), "bad");
+
+        for code in ErrorCode::ALL {
+            assert!(!code.description_in(Locale::ZhCn).is_empty());
+            assert!(!code.description().is_empty());
         }
     }
 }
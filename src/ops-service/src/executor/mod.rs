@@ -0,0 +1,447 @@
+//! 命令执行器抽象
+//! 作业流水线通过 `CommandExecutor` 在目标主机上执行命令/脚本，
+//! 便于替换传输方式（SSH、本地）并在测试中注入确定性的模拟执行器；
+//! Docker 容器与 Kubernetes Pod 目标的适配器见 `target`
+
+pub mod target;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+use crate::error::{AppError, Result};
+use crate::ssh::encoding::decode_output;
+use crate::ssh::executor::ProgressCallback;
+use crate::ssh::{ConnectionPhase, DiagnosticsCollector, ExecutionResult, SSHClient, SshConfig};
+
+/// 执行内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionPayload {
+    /// 单条命令
+    Command(String),
+    /// 脚本（可指定远端脚本路径）
+    Script {
+        content: String,
+        path: Option<String>,
+    },
+    /// 本地暂存的脚本文件（大脚本或二进制内容），分块传输到目标主机后执行
+    ScriptFile {
+        local_path: PathBuf,
+        path: Option<String>,
+    },
+}
+
+/// 执行请求
+#[derive(Clone)]
+pub struct ExecutionRequest {
+    /// 目标主机的连接参数（地址、凭据、超时、主机密钥策略）
+    pub connection: SshConfig,
+    pub payload: ExecutionPayload,
+    /// 增量输出回调
+    pub progress: Option<ProgressCallback>,
+    /// 阶段耗时收集器（任务失败时用于组装诊断信息）
+    pub diagnostics: DiagnosticsCollector,
+}
+
+/// 执行器为执行内容附加的运行环境（用于记录任务执行上下文快照）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutorEnvironment {
+    /// 解释器（为空时由目标主机上执行用户的登录 shell 执行）
+    pub interpreter: Option<String>,
+    /// 执行器传递给进程的环境变量
+    pub env: Vec<(String, String)>,
+}
+
+/// 命令执行器
+///
+/// 命令退出码非零或超时通过 `ExecutionResult` 返回；连接、认证等执行前的失败返回错误
+#[async_trait]
+pub trait CommandExecutor: Send + Sync {
+    /// 执行器名称（用于日志）
+    fn name(&self) -> &'static str;
+
+    /// 执行指定内容时使用的解释器与环境变量（默认不指定解释器、不传递环境变量）
+    fn environment(&self, _payload: &ExecutionPayload) -> ExecutorEnvironment {
+        ExecutorEnvironment::default()
+    }
+
+    /// 在目标主机上执行
+    async fn execute(&self, request: ExecutionRequest) -> Result<ExecutionResult>;
+}
+
+/// SSH 执行器（默认）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SshExecutor;
+
+#[async_trait]
+impl CommandExecutor for SshExecutor {
+    fn name(&self) -> &'static str {
+        "ssh"
+    }
+
+    /// 命令由登录 shell 执行，脚本上传后通过 `sh` 执行；不转发环境变量
+    fn environment(&self, payload: &ExecutionPayload) -> ExecutorEnvironment {
+        ExecutorEnvironment {
+            interpreter: match payload {
+                ExecutionPayload::Command(_) => None,
+                ExecutionPayload::Script { .. } | ExecutionPayload::ScriptFile { .. } => {
+                    Some("sh".to_string())
+                }
+            },
+            env: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, request: ExecutionRequest) -> Result<ExecutionResult> {
+        let client = SSHClient::new(request.connection).with_diagnostics(request.diagnostics);
+        match request.payload {
+            ExecutionPayload::Command(command) => {
+                client
+                    .execute_with_progress(&command, request.progress)
+                    .await
+            }
+            // 脚本执行暂不支持增量输出
+            ExecutionPayload::Script { content, path } => {
+                client.execute_script(&content, path.as_deref()).await
+            }
+            // 文件经 SFTP 分块上传，不经过命令行
+            ExecutionPayload::ScriptFile { local_path, path } => {
+                client
+                    .execute_script_file(&local_path, path.as_deref())
+                    .await
+            }
+        }
+    }
+}
+
+/// 本地执行器：在服务所在机器上通过 `sh` 执行，忽略连接参数
+///
+/// 仅用于开发环境或单机部署，超时使用连接参数中的命令超时
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalExecutor;
+
+#[async_trait]
+impl CommandExecutor for LocalExecutor {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    /// 子进程继承服务进程的环境变量
+    fn environment(&self, _payload: &ExecutionPayload) -> ExecutorEnvironment {
+        ExecutorEnvironment {
+            interpreter: Some("sh -c".to_string()),
+            env: std::env::vars().collect(),
+        }
+    }
+
+    async fn execute(&self, request: ExecutionRequest) -> Result<ExecutionResult> {
+        let mut command = Command::new("sh");
+        match &request.payload {
+            ExecutionPayload::Command(script) => command.arg("-c").arg(script),
+            ExecutionPayload::Script { content, .. } => command.arg("-c").arg(content),
+            // 直接执行本地暂存文件
+            ExecutionPayload::ScriptFile { local_path, .. } => command.arg(local_path),
+        };
+        run_process(command, Stdio::null(), &request).await
+    }
+}
+
+/// 在服务所在机器上运行子进程并收集输出，超时使用连接参数中的命令超时
+pub(crate) async fn run_process(
+    mut command: Command,
+    stdin: Stdio,
+    request: &ExecutionRequest,
+) -> Result<ExecutionResult> {
+    let start_time = Instant::now();
+    request.diagnostics.begin(ConnectionPhase::Exec);
+    let child = command
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| AppError::SshExecutionError(format!("启动本地进程失败: {}", e)))?;
+
+    // 超时后 future 被丢弃，kill_on_drop 会终止子进程
+    let timeout = Duration::from_secs(request.connection.command_timeout_secs);
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => {
+            output.map_err(|e| AppError::SshExecutionError(format!("本地进程执行失败: {}", e)))?
+        }
+        Err(_) => {
+            request.diagnostics.end(false);
+            return Ok(ExecutionResult::timeout(start_time.elapsed().as_secs_f64()));
+        }
+    };
+    request.diagnostics.end(true);
+
+    let decoded = decode_output(request.connection.output_encoding, &output.stdout, &output.stderr);
+    if let Some(progress) = &request.progress {
+        progress(decoded.stdout.clone(), true);
+    }
+
+    let mut result = ExecutionResult::failure(
+        output.status.code().unwrap_or(-1),
+        decoded.stdout,
+        decoded.stderr,
+        start_time.elapsed().as_secs_f64(),
+    );
+    result.output_encoding = Some(decoded.encoding.to_string());
+    Ok(result)
+}
+
+/// 模拟执行器的行为
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockBehavior {
+    /// 退出码 0
+    Succeed { stdout: String },
+    /// 非零退出码
+    Fail { exit_code: i32, stderr: String },
+    /// 命令超时
+    Timeout,
+    /// 连接失败（执行前错误）
+    ConnectionError(String),
+    /// 永不返回（用于取消测试）
+    Hang,
+}
+
+impl MockBehavior {
+    /// 成功并输出指定内容
+    pub fn succeed(stdout: &str) -> Self {
+        MockBehavior::Succeed {
+            stdout: stdout.to_string(),
+        }
+    }
+
+    /// 以指定退出码失败
+    pub fn fail(exit_code: i32, stderr: &str) -> Self {
+        MockBehavior::Fail {
+            exit_code,
+            stderr: stderr.to_string(),
+        }
+    }
+}
+
+/// 确定性的模拟执行器，按目标主机地址返回预设结果，并记录执行过的请求
+pub struct MockExecutor {
+    default_behavior: MockBehavior,
+    host_behaviors: HashMap<String, MockBehavior>,
+    delay: Duration,
+    calls: Mutex<Vec<(String, ExecutionPayload)>>,
+}
+
+impl MockExecutor {
+    /// 创建模拟执行器，未单独配置的主机使用默认行为
+    pub fn new(default_behavior: MockBehavior) -> Self {
+        Self {
+            default_behavior,
+            host_behaviors: HashMap::new(),
+            delay: Duration::ZERO,
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 为指定主机地址设置行为
+    pub fn with_host_behavior(mut self, host: &str, behavior: MockBehavior) -> Self {
+        self.host_behaviors.insert(host.to_string(), behavior);
+        self
+    }
+
+    /// 每次执行前的模拟耗时
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// 已执行的请求（主机地址与执行内容，按调用顺序）
+    pub fn calls(&self) -> Vec<(String, ExecutionPayload)> {
+        self.calls
+            .lock()
+            .map(|calls| calls.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for MockExecutor {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn execute(&self, request: ExecutionRequest) -> Result<ExecutionResult> {
+        let host = request.connection.host.clone();
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push((host.clone(), request.payload.clone()));
+        }
+
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+
+        let duration_secs = self.delay.as_secs_f64();
+        let diagnostics = &request.diagnostics;
+        // 连接失败记为 TCP 阶段失败，其余行为视为已进入执行阶段
+        match self
+            .host_behaviors
+            .get(&host)
+            .unwrap_or(&self.default_behavior)
+        {
+            MockBehavior::Succeed { stdout } => {
+                diagnostics.begin(ConnectionPhase::Exec);
+                diagnostics.end(true);
+                if let Some(progress) = &request.progress {
+                    progress(stdout.clone(), true);
+                }
+                Ok(ExecutionResult::success(stdout.clone(), duration_secs))
+            }
+            MockBehavior::Fail { exit_code, stderr } => {
+                diagnostics.begin(ConnectionPhase::Exec);
+                diagnostics.end(true);
+                Ok(ExecutionResult::failure(
+                    *exit_code,
+                    String::new(),
+                    stderr.clone(),
+                    duration_secs,
+                ))
+            }
+            MockBehavior::Timeout => {
+                diagnostics.begin(ConnectionPhase::Exec);
+                diagnostics.end(false);
+                Ok(ExecutionResult::timeout(duration_secs))
+            }
+            MockBehavior::ConnectionError(message) => {
+                diagnostics.begin(ConnectionPhase::TcpConnect);
+                diagnostics.end(false);
+                Err(AppError::SshConnectionError(message.clone()))
+            }
+            MockBehavior::Hang => {
+                diagnostics.begin(ConnectionPhase::Exec);
+                std::future::pending().await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(host: &str, payload: ExecutionPayload) -> ExecutionRequest {
+        ExecutionRequest {
+            connection: SshConfig::with_password(
+                host.to_string(),
+                "root".to_string(),
+                "secret".to_string(),
+            ),
+            payload,
+            progress: None,
+            diagnostics: DiagnosticsCollector::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_executor_host_behaviors() {
+        let executor = MockExecutor::new(MockBehavior::succeed("ok"))
+            .with_host_behavior("10.0.0.2", MockBehavior::fail(2, "boom"))
+            .with_host_behavior("10.0.0.3", MockBehavior::Timeout);
+
+        let command = ExecutionPayload::Command("uptime".to_string());
+        let ok = executor
+            .execute(request("10.0.0.1", command.clone()))
+            .await
+            .unwrap();
+        assert!(ok.is_success());
+        assert_eq!(ok.stdout, "ok");
+
+        let failed = executor
+            .execute(request("10.0.0.2", command.clone()))
+            .await
+            .unwrap();
+        assert_eq!(failed.exit_code, 2);
+        assert_eq!(failed.stderr, "boom");
+
+        let timed_out = executor
+            .execute(request("10.0.0.3", command.clone()))
+            .await
+            .unwrap();
+        assert!(timed_out.timed_out);
+
+        let hosts: Vec<String> = executor.calls().into_iter().map(|(host, _)| host).collect();
+        assert_eq!(hosts, vec!["10.0.0.1", "10.0.0.2", "10.0.0.3"]);
+    }
+
+    #[tokio::test]
+    async fn test_local_executor_exit_code_and_timeout() {
+        let executor = LocalExecutor;
+
+        let result = executor
+            .execute(request("localhost", ExecutionPayload::Command("echo hi; exit 3".into())))
+            .await
+            .unwrap();
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(result.stdout.trim(), "hi");
+
+        assert_eq!(result.output_encoding.as_deref(), Some("utf-8"));
+
+        // 按 GBK 转换本地进程输出（"中文" 的 GBK 编码）
+        let mut gbk =
+            request("localhost", ExecutionPayload::Command("printf '\\326\\320\\316\\304'".into()));
+        gbk.connection.output_encoding = crate::ssh::OutputEncoding::Auto;
+        let result = executor.execute(gbk).await.unwrap();
+        assert_eq!(result.stdout, "中文");
+        assert_eq!(result.output_encoding.as_deref(), Some("gbk"));
+
+        let mut slow = request("localhost", ExecutionPayload::Command("sleep 5".into()));
+        slow.connection.command_timeout_secs = 1;
+        let result = executor.execute(slow).await.unwrap();
+        assert!(result.timed_out);
+    }
+
+    #[tokio::test]
+    async fn test_local_executor_runs_script_file() {
+        let local_path =
+            std::env::temp_dir().join(format!("ops-script-test-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&local_path, "echo from-file\nexit 4\n").unwrap();
+
+        let result = LocalExecutor
+            .execute(request(
+                "localhost",
+                ExecutionPayload::ScriptFile {
+                    local_path: local_path.clone(),
+                    path: None,
+                },
+            ))
+            .await
+            .unwrap();
+        let _ = std::fs::remove_file(&local_path);
+
+        assert_eq!(result.exit_code, 4);
+        assert_eq!(result.stdout.trim(), "from-file");
+    }
+
+    #[test]
+    fn test_executor_environment() {
+        let script = ExecutionPayload::Script {
+            content: "echo hi".to_string(),
+            path: None,
+        };
+        let command = ExecutionPayload::Command("uptime".to_string());
+
+        assert_eq!(SshExecutor.environment(&command), ExecutorEnvironment::default());
+        assert_eq!(SshExecutor.environment(&script).interpreter.as_deref(), Some("sh"));
+        assert!(SshExecutor.environment(&script).env.is_empty());
+        let file = ExecutionPayload::ScriptFile {
+            local_path: PathBuf::from("/tmp/script"),
+            path: None,
+        };
+        assert_eq!(SshExecutor.environment(&file).interpreter.as_deref(), Some("sh"));
+
+        let local = LocalExecutor.environment(&command);
+        assert_eq!(local.interpreter.as_deref(), Some("sh -c"));
+        assert!(local.env.iter().any(|(name, _)| name == "PATH"));
+    }
+}
@@ -0,0 +1,396 @@
+//! 非 SSH 目标的执行适配器
+//! 主机的 connection_type 为 docker 时经 SSH 连接 Docker 主机并在容器内执行（docker exec）；
+//! 为 kubernetes 时在服务所在机器上通过 kubectl 经 API Server 在 Pod 内执行（kubectl exec）。
+//! 适配器只改变命令送达目标的方式，输出、超时、诊断与审计仍由作业流水线统一处理
+
+use async_trait::async_trait;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::process::Command;
+
+use super::{
+    run_process, CommandExecutor, ExecutionPayload, ExecutionRequest, ExecutorEnvironment,
+};
+use crate::error::{AppError, Result};
+use crate::models::asset::{ConnectionOptions, ConnectionType, Host};
+use crate::services::host_vars::shell_escape;
+use crate::ssh::ExecutionResult;
+
+/// Docker 主机上的 docker 命令
+const DOCKER_BIN: &str = "docker";
+/// 服务所在机器上的 kubectl 命令
+const KUBECTL_BIN: &str = "kubectl";
+
+/// 按主机连接方式选择执行器，SSH 主机使用默认执行器
+///
+/// 连接参数不完整时返回错误（任务失败，不尝试执行）
+pub fn executor_for_host(
+    host: &Host,
+    default: Arc<dyn CommandExecutor>,
+) -> Result<Arc<dyn CommandExecutor>> {
+    let connection_type = host.connection_kind();
+    let options = host
+        .connection_options
+        .as_ref()
+        .map(|options| options.0.clone())
+        .unwrap_or_default();
+    options
+        .validate(connection_type)
+        .map_err(|e| AppError::validation(&e))?;
+
+    Ok(match connection_type {
+        ConnectionType::Ssh => default,
+        ConnectionType::Docker => {
+            Arc::new(DockerExecAdapter::new(default, options.container.unwrap_or_default()))
+        }
+        ConnectionType::Kubernetes => {
+            Arc::new(KubectlExecutor::new(options, host.connection_token.clone()))
+        }
+    })
+}
+
+/// Docker 容器适配器：将执行内容包装为 `docker exec`，交由内层执行器在 Docker 主机上执行
+pub struct DockerExecAdapter {
+    inner: Arc<dyn CommandExecutor>,
+    container: String,
+}
+
+impl DockerExecAdapter {
+    pub fn new(inner: Arc<dyn CommandExecutor>, container: String) -> Self {
+        Self { inner, container }
+    }
+
+    /// 在 Docker 主机上执行的内容
+    ///
+    /// 脚本仍上传到 Docker 主机，由其通过 docker exec 在容器内执行脚本内容；
+    /// 分块传输的脚本文件无法在不读入内存的情况下送入容器，暂不支持
+    fn wrap(&self, payload: ExecutionPayload) -> Result<ExecutionPayload> {
+        match payload {
+            ExecutionPayload::Command(command) => {
+                Ok(ExecutionPayload::Command(self.exec_command(&command)))
+            }
+            ExecutionPayload::Script { content, path } => Ok(ExecutionPayload::Script {
+                content: format!("exec {}\n", self.exec_command(&content)),
+                path,
+            }),
+            ExecutionPayload::ScriptFile { .. } => Err(AppError::SshExecutionError(
+                "Docker 容器目标不支持分块传输的脚本文件".to_string(),
+            )),
+        }
+    }
+
+    fn exec_command(&self, script: &str) -> String {
+        format!(
+            "{} exec {} sh -c {}",
+            DOCKER_BIN,
+            shell_escape(&self.container),
+            shell_escape(script)
+        )
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for DockerExecAdapter {
+    fn name(&self) -> &'static str {
+        "docker"
+    }
+
+    /// 容器内由 `sh -c` 执行，不转发环境变量
+    fn environment(&self, _payload: &ExecutionPayload) -> ExecutorEnvironment {
+        ExecutorEnvironment {
+            interpreter: Some(format!("{} exec {} sh -c", DOCKER_BIN, self.container)),
+            env: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, mut request: ExecutionRequest) -> Result<ExecutionResult> {
+        request.payload = self.wrap(request.payload)?;
+        self.inner.execute(request).await
+    }
+}
+
+/// Kubernetes Pod 执行器：在服务所在机器上运行 `kubectl exec`，忽略 SSH 连接参数
+///
+/// 每次执行生成仅当前用户可读的临时 kubeconfig，执行结束后删除；超时使用连接参数中的命令超时
+pub struct KubectlExecutor {
+    options: ConnectionOptions,
+    token: Option<String>,
+}
+
+impl KubectlExecutor {
+    pub fn new(options: ConnectionOptions, token: Option<String>) -> Self {
+        Self { options, token }
+    }
+
+    /// 生成 kubeconfig（JSON 格式，kubectl 可直接读取）
+    fn kubeconfig(&self, ca_path: Option<&Path>) -> serde_json::Value {
+        let mut cluster = serde_json::json!({ "server": self.options.api_server });
+        if let Some(ca_path) = ca_path {
+            cluster["certificate-authority"] = ca_path.to_string_lossy().into();
+        } else if self.options.insecure_skip_tls_verify {
+            cluster["insecure-skip-tls-verify"] = true.into();
+        }
+        let user = match &self.token {
+            Some(token) => serde_json::json!({ "token": token }),
+            None => serde_json::json!({}),
+        };
+        serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Config",
+            "clusters": [{ "name": "target", "cluster": cluster }],
+            "users": [{ "name": "target", "user": user }],
+            "contexts": [{
+                "name": "target",
+                "context": {
+                    "cluster": "target",
+                    "user": "target",
+                    "namespace": self.options.namespace(),
+                },
+            }],
+            "current-context": "target",
+        })
+    }
+
+    /// kubectl 参数（脚本文件通过标准输入送入 `sh -s`）
+    fn args(&self, kubeconfig: &Path, payload: &ExecutionPayload) -> Vec<String> {
+        let mut args = vec![
+            "--kubeconfig".to_string(),
+            kubeconfig.to_string_lossy().into_owned(),
+            "exec".to_string(),
+        ];
+        if matches!(payload, ExecutionPayload::ScriptFile { .. }) {
+            args.push("-i".to_string());
+        }
+        args.extend([
+            "-n".to_string(),
+            self.options.namespace().to_string(),
+            self.options.pod.clone().unwrap_or_default(),
+        ]);
+        if let Some(container) = &self.options.container {
+            args.extend(["-c".to_string(), container.clone()]);
+        }
+        args.extend(["--".to_string(), "sh".to_string()]);
+        match payload {
+            ExecutionPayload::Command(script)
+            | ExecutionPayload::Script {
+                content: script, ..
+            } => {
+                args.extend(["-c".to_string(), script.clone()]);
+            }
+            ExecutionPayload::ScriptFile { .. } => args.push("-s".to_string()),
+        }
+        args
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for KubectlExecutor {
+    fn name(&self) -> &'static str {
+        "kubectl"
+    }
+
+    /// Pod 内由 `sh` 执行，不转发环境变量
+    fn environment(&self, payload: &ExecutionPayload) -> ExecutorEnvironment {
+        ExecutorEnvironment {
+            interpreter: Some(
+                match payload {
+                    ExecutionPayload::ScriptFile { .. } => "sh -s",
+                    _ => "sh -c",
+                }
+                .to_string(),
+            ),
+            env: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, request: ExecutionRequest) -> Result<ExecutionResult> {
+        let mut files = TempFiles::new();
+        let ca_path = match &self.options.ca_cert {
+            Some(ca_cert) => Some(files.write("ca.crt", ca_cert)?),
+            None => None,
+        };
+        let kubeconfig =
+            files.write("kubeconfig", &self.kubeconfig(ca_path.as_deref()).to_string())?;
+
+        let stdin = match &request.payload {
+            ExecutionPayload::ScriptFile { local_path, .. } => std::fs::File::open(local_path)
+                .map(Stdio::from)
+                .map_err(|e| AppError::SshExecutionError(format!("读取脚本文件失败: {}", e)))?,
+            _ => Stdio::null(),
+        };
+        let mut command = Command::new(KUBECTL_BIN);
+        command.args(self.args(&kubeconfig, &request.payload));
+        // 执行结束（含超时与取消）后 files 被丢弃，临时文件随之删除
+        run_process(command, stdin, &request).await
+    }
+}
+
+/// 单次执行的临时文件，丢弃时删除
+struct TempFiles {
+    prefix: PathBuf,
+    paths: Vec<PathBuf>,
+}
+
+impl TempFiles {
+    fn new() -> Self {
+        Self {
+            prefix: std::env::temp_dir().join(format!("ops-kubectl-{}", uuid::Uuid::new_v4())),
+            paths: Vec::new(),
+        }
+    }
+
+    /// 写入仅当前用户可读写的文件
+    fn write(&mut self, name: &str, content: &str) -> Result<PathBuf> {
+        let path = PathBuf::from(format!("{}-{}", self.prefix.display(), name));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| AppError::SshExecutionError(format!("写入 kubeconfig 失败: {}", e)))?;
+        self.paths.push(path.clone());
+        Ok(path)
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{MockBehavior, MockExecutor};
+    use crate::ssh::{DiagnosticsCollector, SshConfig};
+
+    fn request(payload: ExecutionPayload) -> ExecutionRequest {
+        ExecutionRequest {
+            connection: SshConfig::with_password(
+                "docker-host".to_string(),
+                "root".to_string(),
+                "secret".to_string(),
+            ),
+            payload,
+            progress: None,
+            diagnostics: DiagnosticsCollector::new(),
+        }
+    }
+
+    fn kubernetes_options() -> ConnectionOptions {
+        ConnectionOptions {
+            api_server: Some("https://k8s.example:6443".to_string()),
+            namespace: Some("shop".to_string()),
+            pod: Some("web-0".to_string()),
+            container: Some("app".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_docker_adapter_wraps_payload() {
+        let mock = Arc::new(MockExecutor::new(MockBehavior::succeed("ok")));
+        let adapter = DockerExecAdapter::new(mock.clone(), "web_1".to_string());
+
+        let result = adapter
+            .execute(request(ExecutionPayload::Command("echo 'hi' && uptime".into())))
+            .await
+            .unwrap();
+        assert!(result.is_success());
+        adapter
+            .execute(request(ExecutionPayload::Script {
+                content: "echo hi\n".to_string(),
+                path: Some("/tmp/run.sh".to_string()),
+            }))
+            .await
+            .unwrap();
+
+        let calls = mock.calls();
+        assert_eq!(calls[0].0, "docker-host");
+        assert_eq!(
+            calls[0].1,
+            ExecutionPayload::Command(
+                r#"docker exec web_1 sh -c 'echo '\''hi'\'' && uptime'"#.into()
+            )
+        );
+        assert_eq!(
+            calls[1].1,
+            ExecutionPayload::Script {
+                content: "exec docker exec web_1 sh -c 'echo hi\n'\n".to_string(),
+                path: Some("/tmp/run.sh".to_string()),
+            }
+        );
+
+        let file = ExecutionPayload::ScriptFile {
+            local_path: PathBuf::from("/tmp/script"),
+            path: None,
+        };
+        assert!(adapter.execute(request(file)).await.is_err());
+        assert_eq!(mock.calls().len(), 2);
+    }
+
+    #[test]
+    fn test_kubectl_args_and_kubeconfig() {
+        let executor = KubectlExecutor::new(kubernetes_options(), Some("tok".to_string()));
+        let kubeconfig = Path::new("/tmp/kc");
+
+        let args = executor.args(kubeconfig, &ExecutionPayload::Command("uptime".into()));
+        assert_eq!(
+            args.join(" "),
+            "--kubeconfig /tmp/kc exec -n shop web-0 -c app -- sh -c uptime"
+        );
+        let file = ExecutionPayload::ScriptFile {
+            local_path: PathBuf::from("/tmp/script"),
+            path: None,
+        };
+        let args = executor.args(kubeconfig, &file);
+        assert_eq!(args[3], "-i");
+        assert_eq!(args.last().map(String::as_str), Some("-s"));
+
+        let config = executor.kubeconfig(Some(Path::new("/tmp/ca.crt")));
+        assert_eq!(config["clusters"][0]["cluster"]["server"], "https://k8s.example:6443");
+        assert_eq!(config["clusters"][0]["cluster"]["certificate-authority"], "/tmp/ca.crt");
+        assert_eq!(config["users"][0]["user"]["token"], "tok");
+        assert_eq!(config["contexts"][0]["context"]["namespace"], "shop");
+    }
+
+    #[test]
+    fn test_executor_for_host_selects_by_connection_type() {
+        let mut host: Host = serde_json::from_value(serde_json::json!({
+            "id": uuid::Uuid::new_v4(),
+            "identifier": "web-0",
+            "address": "10.0.0.5",
+            "port": 22,
+            "group_id": uuid::Uuid::new_v4(),
+            "environment": "dev",
+            "tags": [],
+            "status": "active",
+            "created_at": chrono::Utc::now(),
+            "updated_at": chrono::Utc::now(),
+            "version": 1,
+        }))
+        .unwrap();
+        let default: Arc<dyn CommandExecutor> = Arc::new(MockExecutor::new(MockBehavior::Timeout));
+
+        assert_eq!(executor_for_host(&host, default.clone()).unwrap().name(), "mock");
+
+        host.connection_type = "docker".to_string();
+        assert!(executor_for_host(&host, default.clone()).is_err());
+        host.connection_options = Some(sqlx::types::Json(ConnectionOptions {
+            container: Some("web_1".to_string()),
+            ..Default::default()
+        }));
+        assert_eq!(executor_for_host(&host, default.clone()).unwrap().name(), "docker");
+
+        host.connection_type = "kubernetes".to_string();
+        host.connection_options = Some(sqlx::types::Json(kubernetes_options()));
+        assert_eq!(executor_for_host(&host, default).unwrap().name(), "kubectl");
+    }
+}
@@ -0,0 +1,296 @@
+//! 安全公告的 HTTP 处理器
+//! 公告列表、按公告/按主机的受影响报告、公告导入与修复作业
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::AppError,
+    handlers::{asset::check_host_access, job::validate_target_hosts_access},
+    middleware::AppState,
+    models::{advisory::*, job::CreateCommandJobRequest},
+    repository::{AdvisoryRepository, AssetRepository},
+    services::{advisory::remediation_drafts, audit_service::AuditAction, AdvisoryImporter},
+};
+
+/// 用户可访问的环境与分组（None 表示不限制）
+async fn host_scope(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+) -> Result<(Option<Vec<String>>, Option<Vec<String>>), AppError> {
+    let environments = state
+        .permission_service
+        .filter_resources_by_scope(user_id, "environment")
+        .await?;
+    let groups = state
+        .permission_service
+        .filter_resources_by_scope(user_id, "group")
+        .await?;
+
+    let unrestricted = |scope: Vec<String>| (!scope.contains(&"*".to_string())).then_some(scope);
+    Ok((unrestricted(environments), unrestricted(groups)))
+}
+
+/// 查找公告并列出调用方作用域内的受影响主机
+async fn scoped_affected_hosts(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    id: &str,
+) -> Result<(Advisory, Vec<AffectedHost>), AppError> {
+    let repo = AdvisoryRepository::new(state.db.clone());
+    let advisory = repo
+        .find_advisory(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    let (environments, groups) = host_scope(state, user_id).await?;
+    let hosts = repo
+        .list_affected_hosts(&advisory.id)
+        .await?
+        .into_iter()
+        .filter(|h| {
+            let env_ok = environments
+                .as_ref()
+                .map_or(true, |e| e.contains(&h.environment));
+            let group_ok = groups
+                .as_ref()
+                .map_or(true, |g| g.contains(&h.group_id.to_string()));
+            env_ok && group_ok
+        })
+        .collect();
+
+    Ok((advisory, hosts))
+}
+
+/// 列出安全公告（受影响主机数只统计调用方作用域内的主机）
+pub async fn list_advisories(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Query(mut query): Query<AdvisoryListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+    query.limit = query.limit.clamp(1, 500);
+    query.offset = query.offset.max(0);
+
+    let (environments, groups) = host_scope(&state, auth_context.user_id).await?;
+    let repo = AdvisoryRepository::new(state.db.clone());
+    let advisories = repo
+        .list_advisories(&query, environments.as_deref(), groups.as_deref())
+        .await?;
+
+    Ok(Json(json!({
+        "advisories": advisories,
+        "count": advisories.len(),
+        "limit": query.limit,
+        "offset": query.offset,
+    })))
+}
+
+/// 按公告列出受影响主机（id 可以是公告 id 或 CVE 编号）
+pub async fn list_advisory_hosts(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+
+    let (advisory, hosts) = scoped_affected_hosts(&state, auth_context.user_id, &id).await?;
+    Ok(Json(json!({
+        "advisory": advisory,
+        "hosts": hosts,
+        "count": hosts.len(),
+    })))
+}
+
+/// 按主机列出影响该主机的公告
+pub async fn list_host_advisories(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+
+    let host = AssetRepository::new(state.db.clone())
+        .get_host(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+    if !check_host_access(&state, auth_context.user_id, &host).await? {
+        return Err(AppError::not_found("Resource not found"));
+    }
+
+    let advisories = AdvisoryRepository::new(state.db.clone())
+        .list_host_advisories(id)
+        .await?;
+    Ok(Json(json!({
+        "host_id": id,
+        "advisories": advisories,
+        "count": advisories.len(),
+    })))
+}
+
+/// 导入 OSV 记录并立即重算匹配结果（仅管理员）
+pub async fn import_advisories(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(request): Json<ImportAdvisoriesRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth_context.user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin {
+        return Err(AppError::Forbidden);
+    }
+
+    let importer = AdvisoryImporter::new(state.db.clone(), state.config.advisory.clone());
+    let report = importer.import_records(request.records).await?;
+
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::AdvisoryImport,
+            Some("advisory"),
+            None,
+            Some(&format!(
+                "Imported {} advisories ({} unchanged, {} skipped)",
+                report.imported, report.unchanged, report.skipped
+            )),
+            None,
+        )
+        .await?;
+
+    Ok(Json(report))
+}
+
+/// 预览修复作业：按包管理器分组的升级命令与受影响主机
+pub async fn get_remediation_drafts(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+
+    let (advisory, hosts) = scoped_affected_hosts(&state, auth_context.user_id, &id).await?;
+    let drafts = remediation_drafts(&advisory.id, &hosts);
+    Ok(Json(json!({ "drafts": drafts })))
+}
+
+/// 为受影响主机创建修复作业（预填升级命令的命令作业）
+pub async fn create_remediation_job(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<String>,
+    Json(request): Json<CreateRemediationJobRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "execute", None, None)
+        .await?;
+
+    let (advisory, hosts) = scoped_affected_hosts(&state, auth_context.user_id, &id).await?;
+    let mut drafts = remediation_drafts(&advisory.id, &hosts);
+    let draft = match request.package_format.as_deref() {
+        Some(format) => drafts.into_iter().find(|d| d.package_format == format),
+        None if drafts.len() > 1 => {
+            return Err(AppError::validation(
+                "Affected hosts use several package formats, package_format is required",
+            ));
+        }
+        None => drafts.pop(),
+    }
+    .ok_or_else(|| AppError::validation("No affected hosts with an available fix"))?;
+
+    let target_hosts = match request.host_ids {
+        Some(host_ids) => {
+            if let Some(host_id) = host_ids.iter().find(|h| !draft.target_hosts.contains(h)) {
+                return Err(AppError::validation(&format!(
+                    "Host {} is not affected by the advisory",
+                    host_id
+                )));
+            }
+            host_ids
+        }
+        None => draft.target_hosts.clone(),
+    };
+    if target_hosts.is_empty() {
+        return Err(AppError::validation("At least one target host is required"));
+    }
+
+    // 验证用户是否有权限在目标主机上执行作业
+    validate_target_hosts_access(&state, auth_context.user_id, &target_hosts, &[]).await?;
+
+    let host_count = target_hosts.len();
+    let job_request = CreateCommandJobRequest {
+        name: request
+            .name
+            .unwrap_or_else(|| format!("Remediate {}", advisory.id)),
+        description: Some(format!(
+            "Upgrade {} for {}",
+            draft.packages.join(", "),
+            std::iter::once(advisory.id.as_str())
+                .chain(advisory.aliases.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        target_hosts,
+        target_groups: vec![],
+        target_set_id: None,
+        command: draft.command,
+        concurrent_limit: request.concurrent_limit,
+        timeout_secs: request.timeout_secs,
+        retry_times: Some(0),
+        execute_user: request.execute_user,
+        exit_code_rules: None,
+        idempotency_key: request.idempotency_key,
+        singleton_key: None,
+        singleton_policy: Default::default(),
+        tags: vec!["advisory-remediation".to_string()],
+        on_success_job_template: None,
+        on_failure_job_template: None,
+        artifact_id: None,
+        file_manifest: None,
+        drift_check: false,
+        verbose_trace: false,
+    };
+    let job = state
+        .job_service
+        .create_command_job(job_request, auth_context.user_id)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::JobCreate,
+            Some("job"),
+            Some(job.id),
+            Some(&format!("Created remediation job for {} on {} hosts", advisory.id, host_count)),
+            None,
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(job)))
+}
@@ -2,8 +2,8 @@
 //! P3 阶段：审批流相关API处理器
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -13,9 +13,12 @@ use uuid::Uuid;
 use crate::{
     auth::middleware::AuthContext,
     error::{AppError, Result},
-    middleware::AppState,
+    handlers::{audit::view_auditor, job::check_job_access},
+    middleware::{payload_limits::PayloadLimited, AppState},
     models::approval::*,
-    services::audit_service::AuditAction,
+    models::soft_delete::{DeletedResourceQuery, SoftDeleteResource},
+    realtime::ScopeChecker,
+    services::{audit_service::AuditAction, soft_delete, view_audit::ViewTarget},
 };
 
 /// 创建审批请求
@@ -24,6 +27,8 @@ pub async fn create_approval_request(
     auth: AuthContext,
     Json(request): Json<CreateApprovalRequestRequest>,
 ) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
     state
         .permission_service
         .require_permission(auth.user_id, "approval", "read", None, None)
@@ -65,6 +70,55 @@ pub async fn get_approval_request(
     Ok(Json(approval))
 }
 
+/// 获取审批进度
+pub async fn get_approval_progress(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "approval", "read", None, None)
+        .await?;
+
+    let progress = state.approval_service.get_approval_progress(id).await?;
+    Ok(Json(progress))
+}
+
+/// 获取审批请求关联的资源变更
+pub async fn get_approval_change(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "approval", "read", None, None)
+        .await?;
+
+    let change = state
+        .approval_service
+        .get_pending_change(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("No resource change for this approval request"))?;
+    Ok(Json(change))
+}
+
+/// 获取审批时间线
+pub async fn get_approval_timeline(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "approval", "read", None, None)
+        .await?;
+
+    let timeline = state.approval_service.get_approval_timeline(id).await?;
+    Ok(Json(timeline))
+}
+
 /// 查询审批请求列表
 pub async fn list_approval_requests(
     State(state): State<Arc<AppState>>,
@@ -90,16 +144,22 @@ pub async fn approve_request(
     Path(id): Path<Uuid>,
     Json(request): Json<ApproveRequestRequest>,
 ) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
     state
         .permission_service
         .require_permission(auth.user_id, "approval", "approve", None, None)
         .await?;
 
     let is_approve = request.decision == crate::models::approval::ApprovalStatus::Approved;
+    if is_approve {
+        require_high_risk_step_up(&state, &auth, &[id]).await?;
+    }
     state
         .approval_service
         .approve_request(id, auth.user_id, auth.username.clone(), request)
         .await?;
+    resolve_job_gate(&state, id).await;
 
     // 审计日志
     let action = if is_approve {
@@ -120,6 +180,83 @@ pub async fn approve_request(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// 批量批准审批请求
+pub async fn bulk_approve_requests(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(request): Json<BulkApprovalRequest>,
+) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    bulk_decide(state, auth, ApprovalStatus::Approved, request).await
+}
+
+/// 批量拒绝审批请求
+pub async fn bulk_reject_requests(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(request): Json<BulkApprovalRequest>,
+) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    bulk_decide(state, auth, ApprovalStatus::Rejected, request).await
+}
+
+/// 批量审批：逐项返回处理结果，审计日志由服务层合并为一条
+async fn bulk_decide(
+    state: Arc<AppState>,
+    auth: AuthContext,
+    decision: ApprovalStatus,
+    request: BulkApprovalRequest,
+) -> Result<Json<BulkApprovalResponse>> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "approval", "approve", None, None)
+        .await?;
+    if decision == ApprovalStatus::Approved {
+        require_high_risk_step_up(&state, &auth, &request.approval_ids).await?;
+    }
+
+    let response = state
+        .approval_service
+        .bulk_decide(auth.user_id, auth.username.clone(), decision, request)
+        .await?;
+    for result in response.results.iter().filter(|r| r.success) {
+        resolve_job_gate(&state, result.approval_id).await;
+    }
+    Ok(Json(response))
+}
+
+/// 审批结束后派发或取消关联的等待审批作业，失败时由后台任务补偿
+async fn resolve_job_gate(state: &AppState, approval_id: Uuid) {
+    if let Err(e) = state.job_service.resolve_approval_gate(approval_id).await {
+        tracing::warn!(
+            approval_id = %approval_id,
+            error = %e,
+            "Failed to resolve job approval gate"
+        );
+    }
+}
+
+/// 批准高风险请求前要求二次验证（不存在的请求交由审批流程报错）
+async fn require_high_risk_step_up(
+    state: &Arc<AppState>,
+    auth: &AuthContext,
+    approval_ids: &[Uuid],
+) -> Result<()> {
+    for id in approval_ids {
+        let high_risk = state
+            .approval_service
+            .get_approval_request(*id)
+            .await
+            .is_ok_and(|approval| approval.is_high_risk());
+        if high_risk {
+            return state.auth_service.two_factor().require_step_up(auth).await;
+        }
+    }
+    Ok(())
+}
+
 /// 取消审批请求
 pub async fn cancel_approval_request(
     State(state): State<Arc<AppState>>,
@@ -135,6 +272,7 @@ pub async fn cancel_approval_request(
         .approval_service
         .cancel_approval_request(id, auth.user_id)
         .await?;
+    resolve_job_gate(&state, id).await;
 
     // 审计日志
     let _ = state
@@ -199,13 +337,40 @@ pub async fn get_approval_statistics(
 }
 
 /// 订阅审批事件流（SSE）
-pub async fn subscribe_approval_events(State(state): State<Arc<AppState>>) -> Result<Response> {
+/// 订阅时校验 approval.read 权限，推送过程中按缓存周期复核
+pub async fn subscribe_approval_events(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> Result<Response> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "approval", "read", None, None)
+        .await?;
+
+    let checker_state = state.clone();
+    let user_id = auth.user_id;
+    let scope = ScopeChecker::new(ScopeChecker::DEFAULT_TTL, move || {
+        let state = checker_state.clone();
+        Box::pin(async move {
+            state
+                .permission_service
+                .check_permission(user_id, "approval", "read", None, None)
+                .await
+                .unwrap_or(false)
+        })
+    })
+    .with_initial(true);
+
     // 创建SSE流
     let stream = state
         .event_bus
         .subscribe_to_approvals()
+        .with_scope_checker(scope)
         .to_sse_stream()
         .await?;
+    let stream = view_auditor(&state)
+        .track_stream(user_id, "approval", ViewTarget::new("approval", None).verbose(), stream)
+        .await;
 
     // 转换为axum响应
     let body = axum::body::Body::from_stream(stream);
@@ -221,16 +386,65 @@ pub async fn subscribe_approval_events(State(state): State<Arc<AppState>>) -> Re
 }
 
 /// 订阅作业事件流（SSE）
+/// 订阅时校验作业的 group/environment 作用域（反枚举，无权限返回 404），
+/// 推送过程中按缓存周期复核，权限被撤销后立即断开；
+/// 重连时携带 `Last-Event-ID` 则先补发缓冲区中的后续事件
 pub async fn subscribe_job_events(
     State(state): State<Arc<AppState>>,
+    auth: AuthContext,
     Path(job_id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Result<Response> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    let job = state
+        .job_service
+        .get_job(job_id)
+        .await
+        .map_err(|_| AppError::not_found("Job not found"))?;
+
+    if !check_job_access(&state, auth.user_id, &job).await? {
+        return Err(AppError::not_found("Job not found"));
+    }
+
+    let checker_state = state.clone();
+    let user_id = auth.user_id;
+    let scope = ScopeChecker::new(ScopeChecker::DEFAULT_TTL, move || {
+        let state = checker_state.clone();
+        let job = job.clone();
+        Box::pin(async move {
+            let has_read = state
+                .permission_service
+                .check_permission(user_id, "job", "read", None, None)
+                .await
+                .unwrap_or(false);
+            has_read
+                && check_job_access(&state, user_id, &job)
+                    .await
+                    .unwrap_or(false)
+        })
+    })
+    .with_initial(true);
+
+    let last_event_id = headers
+        .get("Last-Event-ID")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
     // 创建SSE流
     let stream = state
         .event_bus
         .subscribe_to_job(job_id)
+        .with_scope_checker(scope)
+        .with_replay_since(last_event_id)
         .to_sse_stream()
         .await?;
+    let stream = view_auditor(&state)
+        .track_stream(user_id, "job", ViewTarget::new("job", Some(job_id)), stream)
+        .await;
 
     // 转换为axum响应
     let body = axum::body::Body::from_stream(stream);
@@ -251,6 +465,8 @@ pub async fn create_job_template(
     auth: AuthContext,
     Json(request): Json<CreateJobTemplateRequest>,
 ) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
     state
         .permission_service
         .require_permission(auth.user_id, "job", "execute", None, None)
@@ -278,6 +494,21 @@ pub async fn get_job_template(
     Ok(Json(template))
 }
 
+/// 查看展开继承与组合后的最终模板
+pub async fn get_resolved_job_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    let resolved = state.job_service.resolve_job_template(id).await?;
+    Ok(Json(resolved))
+}
+
 /// 查询作业模板列表
 pub async fn list_job_templates(
     State(state): State<Arc<AppState>>,
@@ -299,6 +530,8 @@ pub async fn update_job_template(
     Path(id): Path<Uuid>,
     Json(request): Json<UpdateJobTemplateRequest>,
 ) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
     state
         .permission_service
         .require_permission(auth.user_id, "job", "execute", None, None)
@@ -329,20 +562,160 @@ pub async fn delete_job_template(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// 列出已删除（保留期内可恢复）的作业模板
+pub async fn list_deleted_job_templates(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Query(query): Query<DeletedResourceQuery>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    let list = soft_delete::deleted_list(
+        &state.db,
+        &state.config.soft_delete,
+        SoftDeleteResource::JobTemplate,
+        &query,
+    )
+    .await?;
+    Ok(Json(list))
+}
+
+/// 恢复已删除的作业模板
+pub async fn restore_job_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "execute", None, None)
+        .await?;
+
+    let template = state
+        .job_service
+        .restore_job_template(id, auth.user_id)
+        .await?;
+    Ok(Json(template))
+}
+
+/// 查询作业模板使用统计
+pub async fn get_job_template_usage(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    let usage = state.job_service.job_template_usage(id).await?;
+    Ok(Json(usage))
+}
+
+/// 弃用作业模板
+pub async fn deprecate_job_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(request): Json<DeprecateJobTemplateRequest>,
+) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "execute", None, None)
+        .await?;
+
+    let template = state
+        .job_service
+        .deprecate_job_template(id, request, auth.user_id)
+        .await?;
+    Ok(Json(template))
+}
+
+/// 取消作业模板的弃用状态
+pub async fn undeprecate_job_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "execute", None, None)
+        .await?;
+
+    let template = state
+        .job_service
+        .undeprecate_job_template(id, auth.user_id)
+        .await?;
+    Ok(Json(template))
+}
+
+/// 仍在使用弃用模板的团队报告
+pub async fn deprecated_template_usage_report(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Query(query): Query<DeprecatedTemplateUsageQuery>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    let rows = state
+        .job_service
+        .deprecated_template_usage_report(query)
+        .await?;
+    Ok(Json(rows))
+}
+
 /// 执行模板化作业
+///
+/// 模板已弃用时仍然创建作业，并通过 `Warning` 响应头提示替代模板
 pub async fn execute_template_job(
     State(state): State<Arc<AppState>>,
     auth: AuthContext,
     Json(request): Json<ExecuteTemplateJobRequest>,
-) -> Result<impl IntoResponse> {
+) -> Result<Response> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
     state
         .permission_service
         .require_permission(auth.user_id, "job", "execute", None, None)
         .await?;
 
+    let template = state
+        .job_service
+        .get_job_template(request.template_id)
+        .await?;
     let job = state
         .job_service
         .create_job_from_template(request, auth.user_id)
         .await?;
-    Ok((StatusCode::CREATED, Json(job)))
+
+    match deprecation_warning(&template) {
+        Some(warning) => {
+            Ok((StatusCode::CREATED, [(header::WARNING, warning)], Json(job)).into_response())
+        }
+        None => Ok((StatusCode::CREATED, Json(job)).into_response()),
+    }
+}
+
+/// 弃用模板的 `Warning` 响应头（RFC 7234 格式，仅使用 ASCII）
+fn deprecation_warning(template: &JobTemplate) -> Option<HeaderValue> {
+    if !template.is_deprecated() {
+        return None;
+    }
+    let text = match template.replacement_template_id {
+        Some(replacement) => format!(
+            "299 - \"Job template {} is deprecated; use template {} instead\"",
+            template.id, replacement
+        ),
+        None => format!("299 - \"Job template {} is deprecated\"", template.id),
+    };
+    HeaderValue::from_str(&text).ok()
 }
@@ -9,17 +9,26 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
     auth::AuthContext,
     error::{AppError, Result},
     middleware::AppState,
+    models::{
+        approval::{ApprovalStatus, ApprovalTrigger, CreateApprovalRequestRequest},
+        blob::BLOB_OWNER_ARTIFACT,
+        build::{
+            ArtifactPromotion, ArtifactVariant, PromoteArtifactRequest, PROMOTION_PENDING,
+            PROMOTION_PROMOTED, PROMOTION_REJECTED,
+        },
+    },
+    services::{artifact_variants, BlobStore},
 };
 
 use crate::services::audit_service::AuditLogParams;
-use sqlx::Row;
+use sqlx::{PgPool, Row};
 
 /// 产物元数据响应
 #[derive(Debug, Serialize)]
@@ -48,6 +57,12 @@ pub struct ArtifactMetadata {
     /// 版本
     pub version: Option<String>,
 
+    /// 目标 CPU 架构（空表示与平台无关）
+    pub arch: String,
+
+    /// 目标操作系统（空表示与平台无关）
+    pub os: String,
+
     /// 元数据
     pub metadata: serde_json::Value,
 
@@ -76,6 +91,12 @@ pub struct ArtifactListQuery {
     /// 版本
     pub version: Option<String>,
 
+    /// 目标 CPU 架构
+    pub arch: Option<String>,
+
+    /// 目标操作系统
+    pub os: Option<String>,
+
     /// 是否只显示公开产物
     pub public_only: Option<bool>,
 
@@ -84,6 +105,33 @@ pub struct ArtifactListQuery {
     pub per_page: Option<u64>,
 }
 
+/// 产物版本列表查询参数
+#[derive(Debug, Deserialize)]
+pub struct ArtifactVersionQuery {
+    /// 产物类型
+    pub artifact_type: Option<String>,
+
+    /// 版本
+    pub version: Option<String>,
+
+    /// 分页
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// 逻辑版本（同一版本号与产物类型下的全部平台变体）
+#[derive(Debug, Serialize)]
+pub struct ArtifactVersionGroup {
+    pub version: String,
+    pub artifact_type: String,
+
+    /// 最新变体的创建时间
+    pub latest_created_at: chrono::DateTime<chrono::Utc>,
+
+    /// 平台变体（按 arch/os 排序）
+    pub variants: Vec<ArtifactVariant>,
+}
+
 /// 产物列表响应
 #[derive(Debug, Serialize)]
 pub struct ArtifactListResponse {
@@ -122,6 +170,14 @@ pub struct RecordArtifactRequest {
     /// 版本（用于唯一性检查）
     pub version: Option<String>,
 
+    /// 目标 CPU 架构（同一版本的多平台产物按 arch/os 区分）
+    #[serde(default)]
+    pub arch: String,
+
+    /// 目标操作系统
+    #[serde(default)]
+    pub os: String,
+
     /// 元数据
     #[serde(default)]
     pub metadata: serde_json::Value,
@@ -165,14 +221,19 @@ pub async fn record_artifact(
         })?
         .ok_or_else(|| AppError::not_found("Build job not found"))?;
 
-    // 如果指定了版本号，检查是否已存在（防止覆盖上传）
+    let arch = common::normalize_arch(&request.arch);
+    let os = common::normalize_os(&request.os);
+
+    // 如果指定了版本号，检查同一平台的变体是否已存在（防止覆盖上传）
     if let Some(ref version) = request.version {
         if !version.is_empty() {
             let existing = sqlx::query(
-                "SELECT id FROM build_artifacts WHERE version = $1 AND artifact_type = $2",
+                "SELECT id FROM build_artifacts WHERE version = $1 AND artifact_type = $2 AND arch = $3 AND os = $4",
             )
             .bind(version)
             .bind(&request.artifact_type)
+            .bind(&arch)
+            .bind(&os)
             .fetch_optional(&state.db)
             .await
             .map_err(|e| {
@@ -191,8 +252,8 @@ pub async fn record_artifact(
     // 记录产物元数据
     let artifact_id = sqlx::query(
         "INSERT INTO build_artifacts (build_job_id, artifact_name, artifact_type, artifact_path,
-                                     artifact_size, artifact_hash, version, metadata, is_public, uploaded_by)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                                     artifact_size, artifact_hash, version, arch, os, metadata, is_public, uploaded_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
          RETURNING id",
     )
     .bind(request.build_job_id)
@@ -202,6 +263,8 @@ pub async fn record_artifact(
     .bind(request.artifact_size)
     .bind(&request.artifact_hash)
     .bind(&request.version)
+    .bind(&arch)
+    .bind(&os)
     .bind(&request.metadata)
     .bind(request.is_public)
     .bind(auth.user_id)
@@ -213,6 +276,10 @@ pub async fn record_artifact(
     })?
     .get("id");
 
+    if let Err(e) = link_artifact_blob(&state.db, artifact_id, &request.artifact_hash).await {
+        warn!(artifact_id = %artifact_id, error = %e, "Failed to link artifact to blob store");
+    }
+
     // 更新构建作业的产物统计
     sqlx::query(
         "UPDATE build_jobs SET has_artifacts = TRUE, artifact_count = artifact_count + 1 WHERE id = $1",
@@ -239,6 +306,8 @@ pub async fn record_artifact(
             changes: Some(serde_json::json!({
                 "artifact_type": request.artifact_type,
                 "version": request.version,
+                "arch": arch,
+                "os": os,
                 "size": request.artifact_size,
             })),
             changes_summary: None,
@@ -319,6 +388,18 @@ pub async fn list_artifacts(
         param_idx += 1;
     }
 
+    if let Some(ref arch) = query.arch {
+        where_clauses.push(format!("arch = ${}", param_idx));
+        bind_values.push(BindValue::Text(common::normalize_arch(arch)));
+        param_idx += 1;
+    }
+
+    if let Some(ref os) = query.os {
+        where_clauses.push(format!("os = ${}", param_idx));
+        bind_values.push(BindValue::Text(common::normalize_os(os)));
+        param_idx += 1;
+    }
+
     // 非管理员只能看到公开产物或自己上传的产物
     if !is_admin {
         if query.public_only.unwrap_or(false) {
@@ -357,7 +438,7 @@ pub async fn list_artifacts(
 
     let data_sql = format!(
         "SELECT id, build_job_id, artifact_name, artifact_type, artifact_path,
-                artifact_size, artifact_hash, version, metadata, is_public, download_count,
+                artifact_size, artifact_hash, version, arch, os, metadata, is_public, download_count,
                 created_at, uploaded_by
          FROM build_artifacts
          WHERE {}
@@ -391,6 +472,8 @@ pub async fn list_artifacts(
             artifact_size: row.get("artifact_size"),
             artifact_hash: row.get("artifact_hash"),
             version: row.get("version"),
+            arch: row.get("arch"),
+            os: row.get("os"),
             metadata: row.get::<serde_json::Value, _>("metadata"),
             is_public: row.get("is_public"),
             download_count: row.get("download_count"),
@@ -407,6 +490,137 @@ pub async fn list_artifacts(
     }))
 }
 
+/// 按逻辑版本分组查询产物（同一版本号与产物类型的多平台变体归为一组）
+pub async fn list_artifact_versions(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Query(query): Query<ArtifactVersionQuery>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "artifact", "read", None, None)
+        .await?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(50).min(100);
+    let offset = (page - 1) * per_page;
+
+    // 非管理员只能看到公开产物或自己上传的产物
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+    let where_clause = "version IS NOT NULL
+           AND ($1::text IS NULL OR artifact_type = $1)
+           AND ($2::text IS NULL OR version = $2)
+           AND ($3 OR is_public = true OR uploaded_by = $4)";
+
+    let total: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM (SELECT 1 FROM build_artifacts WHERE {} GROUP BY version, artifact_type) g",
+        where_clause
+    ))
+    .bind(&query.artifact_type)
+    .bind(&query.version)
+    .bind(is_admin)
+    .bind(auth.user_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to count artifact versions");
+        AppError::database("Failed to count artifact versions")
+    })?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT version, artifact_type, MAX(created_at) AS latest_created_at,
+               JSON_AGG(JSON_BUILD_OBJECT(
+                   'id', id, 'artifact_name', artifact_name, 'artifact_type', artifact_type,
+                   'artifact_path', artifact_path, 'artifact_size', artifact_size,
+                   'artifact_hash', artifact_hash, 'version', version, 'arch', arch, 'os', os
+               ) ORDER BY arch, os) AS variants
+        FROM build_artifacts
+        WHERE {}
+        GROUP BY version, artifact_type
+        ORDER BY latest_created_at DESC
+        LIMIT $5 OFFSET $6
+        "#,
+        where_clause
+    ))
+    .bind(&query.artifact_type)
+    .bind(&query.version)
+    .bind(is_admin)
+    .bind(auth.user_id)
+    .bind(per_page as i64)
+    .bind(offset as i64)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to list artifact versions");
+        AppError::database("Failed to list artifact versions")
+    })?;
+
+    let versions: Vec<ArtifactVersionGroup> = rows
+        .iter()
+        .map(|row| ArtifactVersionGroup {
+            version: row.get("version"),
+            artifact_type: row.get("artifact_type"),
+            latest_created_at: row.get("latest_created_at"),
+            variants: row
+                .get::<sqlx::types::Json<Vec<ArtifactVariant>>, _>("variants")
+                .0,
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "versions": versions,
+        "total": total,
+        "page": page,
+        "per_page": per_page,
+    })))
+}
+
+/// 查询产物所属逻辑版本的全部平台变体
+pub async fn list_artifact_variants(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "artifact", "read", None, None)
+        .await?;
+
+    let artifact = sqlx::query("SELECT is_public, uploaded_by FROM build_artifacts WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to get artifact");
+            AppError::database("Failed to get artifact")
+        })?
+        .ok_or_else(|| AppError::not_found("Artifact not found"))?;
+
+    let is_public: bool = artifact.get("is_public");
+    let uploaded_by: Uuid = artifact.get("uploaded_by");
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+
+    // 检查权限（反枚举：返回 404 而非 403）
+    if !is_public && !is_admin && uploaded_by != auth.user_id {
+        return Err(AppError::not_found("Artifact not found"));
+    }
+
+    let variants = artifact_variants::list_variants(&state.db, id).await?;
+    Ok(Json(serde_json::json!({
+        "artifact_id": id,
+        "variants": variants,
+    })))
+}
+
 /// 获取产物详情
 pub async fn get_artifact(
     State(state): State<Arc<AppState>>,
@@ -420,7 +634,7 @@ pub async fn get_artifact(
 
     let row = sqlx::query(
         "SELECT id, build_job_id, artifact_name, artifact_type, artifact_path,
-                artifact_size, artifact_hash, version, metadata, is_public, download_count,
+                artifact_size, artifact_hash, version, arch, os, metadata, is_public, download_count,
                 created_at, uploaded_by
          FROM build_artifacts WHERE id = $1",
     )
@@ -455,6 +669,8 @@ pub async fn get_artifact(
         artifact_size: row.get("artifact_size"),
         artifact_hash: row.get("artifact_hash"),
         version: row.get("version"),
+        arch: row.get("arch"),
+        os: row.get("os"),
         metadata: row.get::<serde_json::Value, _>("metadata"),
         is_public,
         download_count: row.get("download_count"),
@@ -701,16 +917,28 @@ pub async fn delete_artifact(
         return Err(AppError::not_found("Artifact not found"));
     }
 
-    // 删除产物（元数据）
+    // 删除产物（元数据），同时释放对 blob 内容的引用
+    let mut tx = state.db.begin().await.map_err(|e| {
+        error!(error = %e, "Failed to begin transaction");
+        AppError::database("Failed to delete artifact")
+    })?;
+
+    BlobStore::release_owner(&mut tx, BLOB_OWNER_ARTIFACT, id).await?;
+
     sqlx::query("DELETE FROM build_artifacts WHERE id = $1")
         .bind(id)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await
         .map_err(|e| {
             error!(error = %e, "Failed to delete artifact");
             AppError::database("Failed to delete artifact")
         })?;
 
+    tx.commit().await.map_err(|e| {
+        error!(error = %e, "Failed to commit transaction");
+        AppError::database("Failed to delete artifact")
+    })?;
+
     // 记录审计日志
     let _ = state
         .audit_service
@@ -737,6 +965,51 @@ pub async fn delete_artifact(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// 产物内容已上传到 blob 存储时（artifact_hash 命中）登记引用并记录 blob_sha256
+///
+/// 返回是否已关联；内容不在 blob 存储中时保持原有的 artifact_path 下载方式
+pub(crate) async fn link_artifact_blob(
+    db: &PgPool,
+    artifact_id: Uuid,
+    artifact_hash: &str,
+) -> Result<bool> {
+    let Some(sha256) = BlobStore::normalize_hash(artifact_hash) else {
+        return Ok(false);
+    };
+
+    let map_err = |e: sqlx::Error| {
+        error!(error = %e, artifact_id = %artifact_id, "Failed to link artifact blob");
+        AppError::database("Failed to link artifact blob")
+    };
+
+    let mut tx = db.begin().await.map_err(map_err)?;
+
+    // FOR SHARE：防止 GC 在登记引用前回收该 blob
+    let exists = sqlx::query_scalar::<_, String>(
+        "SELECT sha256 FROM content_blobs WHERE sha256 = $1 FOR SHARE",
+    )
+    .bind(&sha256)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(map_err)?
+    .is_some();
+    if !exists {
+        return Ok(false);
+    }
+
+    BlobStore::add_ref(&mut tx, &sha256, BLOB_OWNER_ARTIFACT, artifact_id).await?;
+
+    sqlx::query("UPDATE build_artifacts SET blob_sha256 = $2 WHERE id = $1")
+        .bind(artifact_id)
+        .bind(&sha256)
+        .execute(&mut *tx)
+        .await
+        .map_err(map_err)?;
+
+    tx.commit().await.map_err(map_err)?;
+    Ok(true)
+}
+
 /// 下载历史记录
 #[derive(Debug, Serialize)]
 pub struct DownloadHistoryRecord {
@@ -874,7 +1147,8 @@ pub async fn generate_download_url(
 
     // 检查产物是否存在
     let artifact = sqlx::query(
-        "SELECT id, artifact_name, artifact_path, artifact_size, artifact_hash, is_public, uploaded_by
+        "SELECT id, artifact_name, artifact_path, artifact_size, artifact_hash, is_public, uploaded_by,
+                blob_sha256
          FROM build_artifacts WHERE id = $1",
     )
     .bind(id)
@@ -892,6 +1166,7 @@ pub async fn generate_download_url(
     let artifact_hash: String = artifact.get("artifact_hash");
     let is_public: bool = artifact.get("is_public");
     let uploaded_by: Uuid = artifact.get("uploaded_by");
+    let blob_sha256: Option<String> = artifact.get("blob_sha256");
     let is_admin = state
         .permission_service
         .is_admin(auth.user_id)
@@ -903,15 +1178,29 @@ pub async fn generate_download_url(
         return Err(AppError::not_found("Artifact not found"));
     }
 
-    // 使用存储服务生成预签名 URL
-    let download_url = state
-        .storage_service
-        .generate_presigned_url(&artifact_path, id)
-        .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to generate presigned URL");
-            AppError::internal_error("Failed to generate download URL")
-        })?;
+    // 内容已在 blob 存储中时从 blob 下载（本地存储经 blob API，S3 直接签名 blob 对象）
+    let blob = match blob_sha256 {
+        Some(sha256) => state.blob_store.find(sha256.trim()).await?,
+        None => None,
+    };
+    let download_url = match blob {
+        Some(blob)
+            if state.storage_service.storage_type() == crate::services::StorageType::Local =>
+        {
+            Some(format!("/api/v1/blobs/{}", blob.sha256))
+        }
+        blob => {
+            let path = blob.map(|b| b.location).unwrap_or(artifact_path);
+            state
+                .storage_service
+                .generate_presigned_url(&path, id)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to generate presigned URL");
+                    AppError::internal_error("Failed to generate download URL")
+                })?
+        }
+    };
 
     // 获取存储配置的过期时间
     let expires_in_secs = match state.storage_service.storage_type() {
@@ -930,3 +1219,398 @@ pub async fn generate_download_url(
         expires_in_secs,
     }))
 }
+
+// ==================== 环境晋级 ====================
+
+/// 申请将产物晋级到指定环境（创建审批请求，审批通过后调用 complete 完成晋级）
+pub async fn request_artifact_promotion(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PromoteArtifactRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "artifact", "write", None, None)
+        .await?;
+
+    let config = &state.config.artifact_promotion;
+    let to_environment = request.to_environment.trim().to_string();
+    let from_environment = config
+        .previous_environment(&to_environment)
+        .ok_or_else(|| {
+            AppError::validation(&format!(
+                "Environment {} is not in the promotion chain ({})",
+                to_environment, config.environments
+            ))
+        })?;
+
+    let artifact = sqlx::query("SELECT artifact_name, version FROM build_artifacts WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to get artifact");
+            AppError::database("Failed to get artifact")
+        })?
+        .ok_or_else(|| AppError::not_found("Artifact not found"))?;
+    let artifact_name: String = artifact.get("artifact_name");
+    let version: Option<String> = artifact.get("version");
+
+    // 产物须已晋级到前一个环境，且目标环境没有进行中或已完成的晋级
+    let active: Vec<(String, String)> = sqlx::query_as(
+        "SELECT to_environment, status FROM artifact_promotions
+         WHERE artifact_id = $1 AND status IN ('pending', 'promoted')",
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get artifact promotions");
+        AppError::database("Failed to get artifact promotions")
+    })?;
+    if let Some((_, status)) = active.iter().find(|(env, _)| *env == to_environment) {
+        return Err(AppError::validation(&format!(
+            "Artifact promotion to {} is already {}",
+            to_environment, status
+        )));
+    }
+    if let Some(previous) = &from_environment {
+        let promoted = active
+            .iter()
+            .any(|(env, status)| env == previous && status == PROMOTION_PROMOTED);
+        if !promoted {
+            return Err(AppError::validation(&format!(
+                "Artifact must be promoted to {} before {}",
+                previous, to_environment
+            )));
+        }
+    }
+
+    let target = request
+        .target
+        .clone()
+        .filter(|t| !t.trim().is_empty())
+        .or_else(|| config.target_for(&to_environment, &artifact_name, version.as_deref()));
+
+    // 晋级审批
+    let promotion_id = Uuid::new_v4();
+    let label = match &version {
+        Some(version) => format!("{} {}", artifact_name, version),
+        None => artifact_name.clone(),
+    };
+    let trigger = if config.is_restricted(&to_environment) {
+        ApprovalTrigger::ProductionEnvironment
+    } else {
+        ApprovalTrigger::CustomRule
+    };
+    let approval = state
+        .approval_service
+        .create_approval_request(
+            CreateApprovalRequestRequest {
+                job_id: None,
+                request_type: "artifact_promotion".to_string(),
+                title: format!("Promote {} to {}", label, to_environment),
+                description: request.note.clone(),
+                triggers: vec![trigger],
+                required_approvers: config.required_approvers,
+                approval_group_id: None,
+                timeout_mins: config.approval_timeout_mins,
+                metadata: serde_json::json!({
+                    "artifact_id": id,
+                    "promotion_id": promotion_id,
+                    "from_environment": from_environment,
+                    "to_environment": to_environment,
+                    "target": target,
+                }),
+                quorum_rules: None,
+                reminder_percents: None,
+            },
+            auth.user_id,
+        )
+        .await?;
+
+    let promotion = sqlx::query_as::<_, ArtifactPromotion>(
+        r#"
+        INSERT INTO artifact_promotions (
+            id, artifact_id, from_environment, to_environment, target,
+            status, approval_request_id, note, requested_by
+        ) VALUES ($1, $2, $3, $4, $5, 'pending', $6, $7, $8)
+        RETURNING *
+        "#,
+    )
+    .bind(promotion_id)
+    .bind(id)
+    .bind(&from_environment)
+    .bind(&to_environment)
+    .bind(&target)
+    .bind(approval.id)
+    .bind(&request.note)
+    .bind(auth.user_id)
+    .fetch_one(&state.db)
+    .await;
+    let promotion = match promotion {
+        Ok(promotion) => promotion,
+        Err(e) => {
+            // 并发申请同一环境时唯一索引冲突，撤销刚创建的审批请求
+            error!(error = %e, "Failed to record artifact promotion");
+            let _ = state
+                .approval_service
+                .cancel_approval_request(approval.id, auth.user_id)
+                .await;
+            return Err(AppError::database("Failed to record artifact promotion"));
+        }
+    };
+
+    let _ = state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: auth.user_id,
+            subject_type: "user",
+            subject_name: None,
+            action: crate::services::audit_service::AuditAction::ArtifactPromotionRequest.as_str(),
+            resource_type: "artifact",
+            resource_id: Some(id),
+            resource_name: Some(&artifact_name),
+            changes: Some(serde_json::json!({
+                "promotion_id": promotion_id,
+                "approval_request_id": approval.id,
+                "from_environment": from_environment,
+                "to_environment": to_environment,
+                "target": target,
+            })),
+            changes_summary: None,
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        })
+        .await;
+
+    info!(
+        artifact_id = %id,
+        promotion_id = %promotion_id,
+        to_environment = %to_environment,
+        "Artifact promotion requested"
+    );
+
+    Ok((StatusCode::CREATED, Json(promotion)))
+}
+
+/// 按审批结果完成晋级：审批通过时记录晋级，被拒绝、取消或超时时标记为 rejected
+pub async fn complete_artifact_promotion(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path((id, promotion_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "artifact", "write", None, None)
+        .await?;
+
+    let promotion = sqlx::query_as::<_, ArtifactPromotion>(
+        "SELECT * FROM artifact_promotions WHERE id = $1 AND artifact_id = $2",
+    )
+    .bind(promotion_id)
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get artifact promotion");
+        AppError::database("Failed to get artifact promotion")
+    })?
+    .ok_or_else(|| AppError::not_found("Promotion not found"))?;
+
+    if promotion.status != PROMOTION_PENDING {
+        return Err(AppError::validation(&format!(
+            "Artifact promotion is already {}",
+            promotion.status
+        )));
+    }
+    let approval_id = promotion
+        .approval_request_id
+        .ok_or_else(|| AppError::validation("Promotion approval request no longer exists"))?;
+    let approval = state
+        .approval_service
+        .get_approval_request(approval_id)
+        .await?;
+
+    let status = match approval.status {
+        ApprovalStatus::Pending => {
+            return Err(AppError::validation("Artifact promotion is still awaiting approval"));
+        }
+        ApprovalStatus::Approved => PROMOTION_PROMOTED,
+        _ => PROMOTION_REJECTED,
+    };
+    let promotion = sqlx::query_as::<_, ArtifactPromotion>(
+        r#"
+        UPDATE artifact_promotions
+        SET status = $2,
+            promoted_by = CASE WHEN $2 = 'promoted' THEN $3 END,
+            promoted_at = CASE WHEN $2 = 'promoted' THEN NOW() END,
+            updated_at = NOW()
+        WHERE id = $1 AND status = 'pending'
+        RETURNING *
+        "#,
+    )
+    .bind(promotion_id)
+    .bind(status)
+    .bind(auth.user_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to update artifact promotion");
+        AppError::database("Failed to update artifact promotion")
+    })?
+    .ok_or_else(|| AppError::validation("Artifact promotion was completed concurrently"))?;
+
+    if status == PROMOTION_PROMOTED {
+        let _ = state
+            .audit_service
+            .log_action(AuditLogParams {
+                subject_id: auth.user_id,
+                subject_type: "user",
+                subject_name: None,
+                action: crate::services::audit_service::AuditAction::ArtifactPromote.as_str(),
+                resource_type: "artifact",
+                resource_id: Some(id),
+                resource_name: None,
+                changes: Some(serde_json::json!({
+                    "promotion_id": promotion_id,
+                    "approval_request_id": approval_id,
+                    "from_environment": promotion.from_environment,
+                    "to_environment": promotion.to_environment,
+                    "target": promotion.target,
+                })),
+                changes_summary: None,
+                source_ip: None,
+                user_agent: None,
+                trace_id: None,
+                result: "success",
+                error_message: None,
+            })
+            .await;
+    }
+
+    info!(
+        artifact_id = %id,
+        promotion_id = %promotion_id,
+        status = status,
+        "Artifact promotion completed"
+    );
+
+    Ok(Json(promotion))
+}
+
+/// 查询产物的晋级链
+pub async fn list_artifact_promotions(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "artifact", "read", None, None)
+        .await?;
+
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM build_artifacts WHERE id = $1)")
+            .bind(id)
+            .fetch_one(&state.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to get artifact");
+                AppError::database("Failed to get artifact")
+            })?;
+    if !exists {
+        return Err(AppError::not_found("Artifact not found"));
+    }
+
+    let promotions = sqlx::query_as::<_, ArtifactPromotion>(
+        "SELECT * FROM artifact_promotions WHERE artifact_id = $1 ORDER BY requested_at",
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to list artifact promotions");
+        AppError::database("Failed to list artifact promotions")
+    })?;
+    let promoted_environments: Vec<&str> = promotions
+        .iter()
+        .filter(|p| p.status == PROMOTION_PROMOTED)
+        .map(|p| p.to_environment.as_str())
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "artifact_id": id,
+        "promoted_environments": promoted_environments,
+        "promotions": promotions,
+    })))
+}
+
+/// 校验作业部署的产物：目标主机属于受限环境时，产物须已晋级到该环境
+///
+/// 晋级记录按逻辑版本汇总（任一平台变体晋级即视为该版本已晋级），
+/// 并要求每台目标主机都有匹配其平台的变体
+pub(crate) async fn check_artifact_deployment(
+    state: &Arc<AppState>,
+    artifact_id: Option<Uuid>,
+    target_hosts: &[Uuid],
+    target_groups: &[Uuid],
+) -> Result<()> {
+    let Some(artifact_id) = artifact_id else {
+        return Ok(());
+    };
+
+    let promoted: Option<Vec<String>> = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(
+            ARRAY_AGG(p.to_environment) FILTER (WHERE p.status = 'promoted'),
+            '{}'
+        )
+        FROM build_artifacts a
+        LEFT JOIN build_artifacts v
+          ON v.id = a.id
+          OR (a.version IS NOT NULL AND v.version = a.version AND v.artifact_type = a.artifact_type)
+        LEFT JOIN artifact_promotions p ON p.artifact_id = v.id
+        WHERE a.id = $1
+        GROUP BY a.id
+        "#,
+    )
+    .bind(artifact_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get artifact promotions");
+        AppError::database("Failed to get artifact promotions")
+    })?;
+    let promoted = promoted
+        .ok_or_else(|| AppError::validation(&format!("Artifact {} not found", artifact_id)))?;
+
+    let environments: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT environment FROM assets_hosts WHERE id = ANY($1) OR group_id = ANY($2)",
+    )
+    .bind(target_hosts)
+    .bind(target_groups)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get target host environments");
+        AppError::database("Failed to get target host environments")
+    })?;
+
+    let config = &state.config.artifact_promotion;
+    if let Some(environment) = environments
+        .iter()
+        .find(|env| config.is_restricted(env) && !promoted.contains(env))
+    {
+        return Err(AppError::validation(&format!(
+            "Artifact {} has not been promoted to {}",
+            artifact_id, environment
+        )));
+    }
+
+    artifact_variants::check_targets(&state.db, artifact_id, target_hosts, target_groups).await
+}
@@ -1,11 +1,13 @@
 //! 资产管理的 HTTP 处理器
 
 use crate::{
-    auth::middleware::AuthContext, error::AppError, middleware::AppState, models::asset::*,
-    services::audit_service::AuditAction,
+    auth::middleware::AuthContext, cache, error::AppError, middleware::AppState, models::asset::*,
+    services::audit_service::AuditAction, services::connection_test::GroupConnectionTester,
+    services::credential_rotation::CredentialRotator, services::package_inventory,
 };
 use axum::{
     extract::{Path, Query, State},
+    http::StatusCode,
     response::IntoResponse,
     Json,
 };
@@ -13,6 +15,11 @@ use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
 
+use crate::models::approval::{ApprovalTrigger, ResourceChange};
+use crate::models::soft_delete::{DeletedResourceQuery, SoftDeleteResource};
+use crate::services::audit_service::AuditLogParams;
+use crate::services::soft_delete;
+
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
@@ -81,6 +88,8 @@ pub async fn create_group(
         .require_permission(auth_context.user_id, "asset", "write", None, None)
         .await?;
 
+    req.validate().map_err(|e| AppError::validation(&e))?;
+
     let repo = crate::repository::AssetRepository::new(state.db.clone());
     let group = repo.create_group(&req, auth_context.user_id).await?;
 
@@ -98,7 +107,7 @@ pub async fn create_group(
         .await?;
 
     Ok(Json(json!({
-        "message": "资产组创建成功",
+        "message": crate::i18n::t("notice.asset_group.created"),
         "group": group
     })))
 }
@@ -150,12 +159,21 @@ pub async fn update_group(
         .require_permission(auth_context.user_id, "asset", "write", None, None)
         .await?;
 
+    req.validate().map_err(|e| AppError::validation(&e))?;
+
     let repo = crate::repository::AssetRepository::new(state.db.clone());
     let group = repo
         .update_group(id, &req)
         .await?
         .ok_or_else(|| AppError::not_found("Resource not found"))?;
 
+    // 分组并发上限与关键分组标记可能已变更，使相关缓存失效
+    state
+        .concurrency_controller
+        .invalidate_group(&group.id.to_string())
+        .await;
+    state.approval_service.invalidate_critical_group(group.id);
+
     // 审计日志
     state
         .audit_service
@@ -170,7 +188,7 @@ pub async fn update_group(
         .await?;
 
     Ok(Json(json!({
-        "message": "资产组更新成功",
+        "message": crate::i18n::t("notice.asset_group.updated"),
         "group": group
     })))
 }
@@ -196,7 +214,34 @@ pub async fn delete_group(
 
     let group_name = group.name.clone();
 
+    // 生产环境资产组的删除需审批，审批通过后执行
+    if group.is_production {
+        let approval = state
+            .approval_service
+            .request_change(
+                ResourceChange::GroupDelete { group_id: id },
+                format!("Delete asset group {}", group_name),
+                None,
+                vec![ApprovalTrigger::ProductionEnvironment],
+                auth_context.user_id,
+            )
+            .await?;
+
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "message": crate::i18n::t("notice.asset_group.delete_pending_approval"),
+                "approval": approval
+            })),
+        ));
+    }
+
     repo.delete_group(id).await?;
+    state
+        .concurrency_controller
+        .invalidate_group(&id.to_string())
+        .await;
+    state.approval_service.invalidate_critical_group(id);
 
     // 审计日志
     state
@@ -211,9 +256,12 @@ pub async fn delete_group(
         )
         .await?;
 
-    Ok(Json(json!({
-        "message": "资产组删除成功"
-    })))
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "message": crate::i18n::t("notice.asset_group.deleted")
+        })),
+    ))
 }
 
 // ==================== Hosts ====================
@@ -307,6 +355,7 @@ pub async fn create_host(
         .permission_service
         .require_permission(auth_context.user_id, "asset", "write", None, None)
         .await?;
+    req.validate().map_err(|e| AppError::validation(&e))?;
 
     let repo = crate::repository::AssetRepository::new(state.db.clone());
     let host = repo.create_host(&req, auth_context.user_id).await?;
@@ -325,7 +374,7 @@ pub async fn create_host(
         .await?;
 
     Ok(Json(json!({
-        "message": "主机创建成功",
+        "message": crate::i18n::t("notice.host.created"),
         "host": host
     })))
 }
@@ -349,14 +398,28 @@ pub async fn get_host(
         .ok_or_else(|| AppError::not_found("Resource not found"))?;
 
     // 检查作用域权限
+    if !check_host_access(&state, auth_context.user_id, &host).await? {
+        return Err(AppError::not_found("Resource not found"));
+    }
+
+    Ok(Json(host))
+}
+
+/// 检查用户的环境与分组作用域是否覆盖指定主机
+/// 返回 false 时应返回 404 而不是 403（反枚举）
+pub(crate) async fn check_host_access(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    host: &Host,
+) -> Result<bool, AppError> {
     let allowed_environments = state
         .permission_service
-        .filter_resources_by_scope(auth_context.user_id, "environment")
+        .filter_resources_by_scope(user_id, "environment")
         .await?;
 
     let allowed_groups = state
         .permission_service
-        .filter_resources_by_scope(auth_context.user_id, "group")
+        .filter_resources_by_scope(user_id, "group")
         .await?;
 
     // 如果用户没有全局权限，检查环境和分组权限
@@ -366,11 +429,80 @@ pub async fn get_host(
     let group_ok = allowed_groups.contains(&"*".to_string())
         || allowed_groups.contains(&host.group_id.to_string());
 
-    if !env_ok || !group_ok {
+    Ok(env_ok && group_ok)
+}
+
+// ==================== Packages ====================
+
+/// 列出主机已安装的软件包（由清单采集作业写入）
+pub async fn list_host_packages(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(query): Query<HostPackageQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let host = repo
+        .get_host(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+    if !check_host_access(&state, auth_context.user_id, &host).await? {
         return Err(AppError::not_found("Resource not found"));
     }
 
-    Ok(Json(host))
+    let packages = repo.list_host_packages(id, query.search.as_deref()).await?;
+    Ok(Json(json!({
+        "host_id": id,
+        "packages": packages,
+        "count": packages.len(),
+    })))
+}
+
+/// 跨主机查询安装了指定软件包的主机，可按版本范围筛选（如 openssl < 3.0.2）
+pub async fn find_package_hosts(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Query(query): Query<PackageHostQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+    package_inventory::validate_query(&query)?;
+
+    let allowed_environments = state
+        .permission_service
+        .filter_resources_by_scope(auth_context.user_id, "environment")
+        .await?;
+    let allowed_groups = state
+        .permission_service
+        .filter_resources_by_scope(auth_context.user_id, "group")
+        .await?;
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let hosts: Vec<_> = repo
+        .find_package_hosts(query.name.trim(), query.environment.as_deref())
+        .await?
+        .into_iter()
+        .filter(|h| {
+            let env_ok = allowed_environments.contains(&"*".to_string())
+                || allowed_environments.contains(&h.environment);
+            let group_ok = allowed_groups.contains(&"*".to_string())
+                || allowed_groups.contains(&h.group_id.to_string());
+            env_ok && group_ok
+        })
+        .filter(|h| package_inventory::version_matches(&query, &h.version))
+        .collect();
+
+    Ok(Json(json!({
+        "hosts": hosts,
+        "count": hosts.len(),
+    })))
 }
 
 /// 更新主机
@@ -386,29 +518,225 @@ pub async fn update_host(
         .require_permission(auth_context.user_id, "asset", "write", None, None)
         .await?;
 
+    // 变更 SSH 凭据或 Kubernetes 访问令牌需二次验证
+    if req.ssh_password.is_some()
+        || req.ssh_private_key.is_some()
+        || req.ssh_key_passphrase.is_some()
+        || req.connection_token.is_some()
+    {
+        state
+            .auth_service
+            .two_factor()
+            .require_step_up(&auth_context)
+            .await?;
+    }
+
     let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let before = repo
+        .get_host(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+    let new_environment = req
+        .environment
+        .as_deref()
+        .filter(|environment| *environment != before.environment);
+    if let Some(environment) = new_environment {
+        check_environment_change(&state, &auth_context, &repo, &before, &req, environment).await?;
+    }
+
     let host = repo
         .update_host(id, &req, auth_context.user_id)
         .await?
         .ok_or_else(|| AppError::not_found("Resource not found"))?;
 
+    // 审计日志（环境变更记录主机变更前后的差异）
+    if new_environment.is_some() {
+        state
+            .concurrency_controller
+            .invalidate_environment(&before.environment)
+            .await;
+
+        let summary = format!(
+            "Changed environment of host {} from {} to {}",
+            host.identifier, before.environment, host.environment
+        );
+        state
+            .audit_service
+            .log_action(AuditLogParams {
+                subject_id: auth_context.user_id,
+                subject_type: "user",
+                subject_name: None,
+                action: AuditAction::HostEnvironmentChange.as_str(),
+                resource_type: "host",
+                resource_id: Some(host.id),
+                resource_name: Some(&host.identifier),
+                changes: Some(before.audit_diff(&host)),
+                changes_summary: Some(&summary),
+                source_ip: None,
+                user_agent: None,
+                trace_id: None,
+                result: "success",
+                error_message: None,
+            })
+            .await?;
+    } else {
+        state
+            .audit_service
+            .log_action_simple(
+                auth_context.user_id,
+                AuditAction::HostUpdate,
+                Some("host"),
+                Some(host.id),
+                Some(&format!("Updated host: {}", host.identifier)),
+                None,
+            )
+            .await?;
+    }
+
+    Ok(Json(json!({
+        "message": crate::i18n::t("notice.host.updated"),
+        "host": host
+    })))
+}
+
+/// 主机环境变更（如 staging → production）前的检查
+///
+/// 需具备目标环境的 asset:promote 权限，进出生产环境还需二次验证；
+/// 主机须与（变更后的）所属分组处于同一环境，且没有未结束的任务——
+/// 这些任务的并发配额与审批按原环境评估
+async fn check_environment_change(
+    state: &AppState,
+    auth_context: &AuthContext,
+    repo: &crate::repository::AssetRepository,
+    host: &Host,
+    req: &UpdateHostRequest,
+    environment: &str,
+) -> Result<(), AppError> {
+    state
+        .permission_service
+        .require_permission(
+            auth_context.user_id,
+            "asset",
+            "promote",
+            Some("environment"),
+            Some(environment),
+        )
+        .await?;
+
+    let group_id = req.group_id.unwrap_or(host.group_id);
+    let group = repo
+        .get_group(group_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Asset group not found"))?;
+    if group.environment != environment {
+        return Err(AppError::validation(&format!(
+            "Host environment '{}' does not match environment '{}' of group {}",
+            environment, group.environment, group.name
+        )));
+    }
+
+    let is_production = |env: &str| env.eq_ignore_ascii_case("production");
+    if is_production(&host.environment) || is_production(environment) || group.is_production {
+        state
+            .auth_service
+            .two_factor()
+            .require_step_up(auth_context)
+            .await?;
+    }
+
+    if repo.host_has_unfinished_tasks(host.id).await? {
+        return Err(AppError::validation(
+            "Host has unfinished tasks; wait for them to finish or cancel them before changing its environment",
+        ));
+    }
+    Ok(())
+}
+
+/// 更新主机 SSH 凭据
+///
+/// 生产环境主机（主机环境或所属资产组为生产）的凭据变更提交审批，审批通过后生效
+pub async fn update_host_credentials(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateHostCredentialsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    // 检查权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "write", None, None)
+        .await?;
+    state
+        .auth_service
+        .two_factor()
+        .require_step_up(&auth_context)
+        .await?;
+
+    if req.changed_fields().is_empty() {
+        return Err(AppError::BadRequest("未提供需要更新的凭据".to_string()));
+    }
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let host = repo
+        .get_host(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    let is_production = host.environment.eq_ignore_ascii_case("production")
+        || repo
+            .get_group(host.group_id)
+            .await?
+            .is_some_and(|group| group.is_production);
+    if is_production {
+        let approval = state
+            .approval_service
+            .request_change(
+                ResourceChange::HostCredentialUpdate {
+                    host_id: id,
+                    credentials: req,
+                },
+                format!("Update SSH credentials for host {}", host.identifier),
+                None,
+                vec![ApprovalTrigger::ProductionEnvironment],
+                auth_context.user_id,
+            )
+            .await?;
+
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "message": crate::i18n::t("notice.host.credentials_pending_approval"),
+                "approval": approval
+            })),
+        ));
+    }
+
+    let fields = req.changed_fields().join(", ");
+    let host = repo
+        .update_host_credentials(id, &req, auth_context.user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
     // 审计日志
     state
         .audit_service
         .log_action_simple(
             auth_context.user_id,
-            AuditAction::HostUpdate,
+            AuditAction::HostCredentialUpdate,
             Some("host"),
             Some(host.id),
-            Some(&format!("Updated host: {}", host.identifier)),
+            Some(&format!("Updated SSH credentials ({}) for host: {}", fields, host.identifier)),
             None,
         )
         .await?;
 
-    Ok(Json(json!({
-        "message": "主机更新成功",
-        "host": host
-    })))
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "message": crate::i18n::t("notice.host.credentials_updated"),
+            "host": host
+        })),
+    ))
 }
 
 /// 删除主机
@@ -432,7 +760,8 @@ pub async fn delete_host(
 
     let host_info = host.identifier.clone();
 
-    repo.delete_host(id).await?;
+    soft_delete::mark_deleted(&state.db, SoftDeleteResource::Host, id, auth_context.user_id)
+        .await?;
 
     // 审计日志
     state
@@ -448,6 +777,654 @@ pub async fn delete_host(
         .await?;
 
     Ok(Json(json!({
-        "message": "主机删除成功"
+        "message": crate::i18n::t("notice.host.deleted")
+    })))
+}
+
+/// 列出已删除（保留期内可恢复）的主机
+pub async fn list_deleted_hosts(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Query(query): Query<DeletedResourceQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+
+    let list = soft_delete::deleted_list(
+        &state.db,
+        &state.config.soft_delete,
+        SoftDeleteResource::Host,
+        &query,
+    )
+    .await?;
+    Ok(Json(list))
+}
+
+/// 恢复已删除的主机
+pub async fn restore_host(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // 检查权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "write", None, None)
+        .await?;
+
+    if !soft_delete::restore(&state.db, SoftDeleteResource::Host, id).await? {
+        return Err(AppError::not_found("Deleted host not found"));
+    }
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let host = repo
+        .get_host(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::HostRestore,
+            Some("host"),
+            Some(id),
+            Some(&format!("Restored host: {}", host.identifier)),
+            None,
+        )
+        .await?;
+
+    Ok(Json(json!({
+        "message": crate::i18n::t("notice.host.restored"),
+        "host": host
+    })))
+}
+
+/// 设置主机维护
+///
+/// 维护期间目标为该主机的任务挂起为 waiting_maintenance，维护结束后自动下发
+pub async fn set_host_maintenance(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(req): Json<SetHostMaintenanceRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    // 检查权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "write", None, None)
+        .await?;
+
+    req.validate(chrono::Utc::now())
+        .map_err(|e| AppError::validation(&e))?;
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let current = repo
+        .get_host(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    let host = repo
+        .set_host_maintenance(id, &req, auth_context.user_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::validation(&format!(
+                "Host in status '{}' cannot be put into maintenance",
+                current.status
+            ))
+        })?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::HostMaintenanceSet,
+            Some("host"),
+            Some(host.id),
+            Some(&format!(
+                "Set maintenance for host: {} (until: {}, reason: {})",
+                host.identifier,
+                host.maintenance_until
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_else(|| "manual".to_string()),
+                host.maintenance_reason.as_deref().unwrap_or("-")
+            )),
+            None,
+        )
+        .await?;
+
+    state
+        .job_service
+        .publish_host_maintenance_change(&host)
+        .await?;
+
+    Ok(Json(json!({
+        "message": crate::i18n::t("notice.host.maintenance_started"),
+        "host": host
+    })))
+}
+
+/// 结束主机维护，并立即下发等待该主机的任务
+pub async fn clear_host_maintenance(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // 检查权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "write", None, None)
+        .await?;
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    repo.get_host(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    let host = repo
+        .clear_host_maintenance(id, auth_context.user_id)
+        .await?
+        .ok_or_else(|| AppError::validation("Host is not under maintenance"))?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::HostMaintenanceClear,
+            Some("host"),
+            Some(host.id),
+            Some(&format!("Cleared maintenance for host: {}", host.identifier)),
+            None,
+        )
+        .await?;
+
+    state
+        .job_service
+        .publish_host_maintenance_change(&host)
+        .await?;
+    let released_tasks = state.job_service.release_maintenance_tasks().await?;
+
+    Ok(Json(json!({
+        "message": crate::i18n::t("notice.host.maintenance_ended"),
+        "host": host,
+        "released_tasks": released_tasks
     })))
 }
+
+/// 分阶段测试主机连接（DNS、TCP、握手、认证、简单命令），用于排查新主机接入问题
+pub async fn test_host_connection(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // 测试会使用主机凭据登录，需要写权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "write", None, None)
+        .await?;
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let host = repo
+        .get_host(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    if !check_host_access(&state, auth_context.user_id, &host).await? {
+        return Err(AppError::not_found("Resource not found"));
+    }
+
+    let report = state.job_service.test_host_connection(&host).await;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::HostConnectionTest,
+            Some("host"),
+            Some(host.id),
+            Some(&format!(
+                "Tested connection to host: {} ({:?})",
+                host.identifier, report.outcome
+            )),
+            None,
+        )
+        .await?;
+
+    Ok(Json(report))
+}
+
+// ==================== SSH Host Keys ====================
+
+/// 主机密钥管理仅限管理员
+async fn require_admin(state: &AppState, auth_context: &AuthContext) -> Result<(), AppError> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth_context.user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
+
+/// 列出主机密钥验证失败记录（默认仅未处理的）
+pub async fn list_host_key_failures(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Query(filters): Query<HostKeyFailureFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &auth_context).await?;
+
+    let limit = filters.limit.unwrap_or(50).clamp(1, 500);
+    let offset = filters.offset.unwrap_or(0).max(0);
+
+    let failures = sqlx::query_as::<_, SshHostKeyFailureRecord>(
+        r#"
+        SELECT * FROM ssh_host_key_failures
+        WHERE $1 OR resolved_at IS NULL
+        ORDER BY created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(filters.include_resolved)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(json!({
+        "failures": failures,
+        "count": failures.len()
+    })))
+}
+
+/// 核实后重新固定主机密钥
+///
+/// 写入集中管理的 known_hosts；主机已配置主机级 known_hosts 时同步更新，避免旧指纹继续生效。
+/// 同一主机提供相同密钥的未处理失败记录一并标记为已处理；
+/// 漂移检查禁止执行的主机在其漂移记录均已处理后解除禁止。
+pub async fn repin_host_key(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(req): Json<RepinHostKeyRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &auth_context).await?;
+
+    let failure = sqlx::query_as::<_, SshHostKeyFailureRecord>(
+        "SELECT * FROM ssh_host_key_failures WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    if failure.resolved_at.is_some() {
+        return Err(AppError::validation("Host key failure has already been resolved"));
+    }
+    if req.fingerprint.trim() != failure.presented_fingerprint {
+        return Err(AppError::validation(
+            "Fingerprint does not match the key presented by the host",
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    let known_host = sqlx::query_as::<_, SshKnownHost>(
+        r#"
+        INSERT INTO ssh_known_hosts (host, port, key_type, public_key, fingerprint, pinned_by)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (host, port) DO UPDATE SET
+            key_type = EXCLUDED.key_type,
+            public_key = EXCLUDED.public_key,
+            fingerprint = EXCLUDED.fingerprint,
+            pinned_by = EXCLUDED.pinned_by,
+            updated_at = NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(&failure.host)
+    .bind(failure.port)
+    .bind(&failure.key_type)
+    .bind(&failure.public_key)
+    .bind(&failure.presented_fingerprint)
+    .bind(auth_context.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(host_id) = failure.host_id {
+        sqlx::query(
+            r#"
+            UPDATE assets_hosts
+            SET known_hosts = known_hosts || jsonb_build_object($2::text, $3::text),
+                updated_at = NOW(), updated_by = $4
+            WHERE id = $1 AND known_hosts IS NOT NULL
+            "#,
+        )
+        .bind(host_id)
+        .bind(format!("{}:{}", failure.host, failure.port))
+        .bind(&failure.presented_fingerprint)
+        .bind(auth_context.user_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let resolved = sqlx::query(
+        r#"
+        UPDATE ssh_host_key_failures
+        SET resolved_at = NOW(), resolved_by = $4
+        WHERE host = $1 AND port = $2 AND presented_fingerprint = $3 AND resolved_at IS NULL
+        "#,
+    )
+    .bind(&failure.host)
+    .bind(failure.port)
+    .bind(&failure.presented_fingerprint)
+    .bind(auth_context.user_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // 漂移检查的记录均已处理的主机解除禁止执行
+    sqlx::query(
+        r#"
+        UPDATE assets_hosts h
+        SET host_key_blocked_at = NULL
+        WHERE h.address = $1 AND h.port = $2 AND h.host_key_blocked_at IS NOT NULL
+          AND NOT EXISTS (
+              SELECT 1 FROM ssh_host_key_failures f
+              WHERE f.host_id = h.id AND f.source = 'drift_check' AND f.resolved_at IS NULL
+          )
+        "#,
+    )
+    .bind(&failure.host)
+    .bind(failure.port)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    // 按地址解除禁止，可能涉及多台主机
+    cache::HOSTS.invalidate_all();
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::HostKeyRepin,
+            Some("ssh_known_host"),
+            Some(known_host.id),
+            Some(&format!(
+                "Re-pinned host key for {}:{}: {} -> {} {}",
+                failure.host,
+                failure.port,
+                failure.expected_fingerprint.as_deref().unwrap_or("(none)"),
+                failure.key_type,
+                failure.presented_fingerprint
+            )),
+            None,
+        )
+        .await?;
+
+    Ok(Json(json!({
+        "message": crate::i18n::t("notice.host.key_repinned"),
+        "known_host": known_host,
+        "resolved_failures": resolved
+    })))
+}
+
+/// 列出集中管理的 known_hosts
+pub async fn list_known_hosts(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &auth_context).await?;
+
+    let known_hosts =
+        sqlx::query_as::<_, SshKnownHost>("SELECT * FROM ssh_known_hosts ORDER BY host, port")
+            .fetch_all(&state.db)
+            .await?;
+
+    Ok(Json(json!({
+        "known_hosts": known_hosts,
+        "count": known_hosts.len()
+    })))
+}
+
+/// 检查用户是否可以访问资产组（环境与分组作用域）
+async fn check_group_access(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    group: &AssetGroup,
+) -> Result<bool, AppError> {
+    let allowed_environments = state
+        .permission_service
+        .filter_resources_by_scope(user_id, "environment")
+        .await?;
+
+    let allowed_groups = state
+        .permission_service
+        .filter_resources_by_scope(user_id, "group")
+        .await?;
+
+    let env_ok = allowed_environments.contains(&"*".to_string())
+        || allowed_environments.contains(&group.environment);
+
+    let group_ok =
+        allowed_groups.contains(&"*".to_string()) || allowed_groups.contains(&group.id.to_string());
+
+    Ok(env_ok && group_ok)
+}
+
+fn group_connection_tester(state: &Arc<AppState>) -> Arc<GroupConnectionTester> {
+    Arc::new(GroupConnectionTester::new(
+        state.db.clone(),
+        state.job_service.clone(),
+        state.event_bus.clone(),
+    ))
+}
+
+/// 批量测试资产组内全部主机的连接（后台执行，通过通知流推送进度）
+pub async fn test_group_connections(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+    body: Option<Json<StartConnectionTestRunRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    // 测试会使用主机凭据登录，需要写权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "write", None, None)
+        .await?;
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let group = repo
+        .get_group(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    if !check_group_access(&state, auth_context.user_id, &group).await? {
+        return Err(AppError::not_found("Resource not found"));
+    }
+
+    let hosts = repo.list_group_hosts(group.id).await?;
+    if hosts.is_empty() {
+        return Err(AppError::validation("Group has no hosts to test"));
+    }
+
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let host_count = hosts.len();
+    let run = group_connection_tester(&state)
+        .start(group.id, hosts, req.concurrency, auth_context.user_id)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::GroupConnectionTest,
+            Some("group"),
+            Some(group.id),
+            Some(&format!(
+                "Started connection test for group: {} ({} hosts)",
+                group.name, host_count
+            )),
+            None,
+        )
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+/// 列出资产组的批量连接测试记录
+pub async fn list_group_connection_tests(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let group = repo
+        .get_group(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    if !check_group_access(&state, auth_context.user_id, &group).await? {
+        return Err(AppError::not_found("Resource not found"));
+    }
+
+    let runs = group_connection_tester(&state).list(group.id, 50).await?;
+
+    Ok(Json(runs))
+}
+
+/// 获取批量连接测试记录（含各主机报告）
+pub async fn get_connection_test_run(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "asset", "read", None, None)
+        .await?;
+
+    let run = group_connection_tester(&state)
+        .get(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let group = repo
+        .get_group(run.group_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    if !check_group_access(&state, auth_context.user_id, &group).await? {
+        return Err(AppError::not_found("Resource not found"));
+    }
+
+    Ok(Json(run))
+}
+
+// ==================== Credential Rotation ====================
+
+fn credential_rotator(state: &Arc<AppState>) -> Arc<CredentialRotator> {
+    Arc::new(CredentialRotator::new(
+        state.db.clone(),
+        state.job_service.clone(),
+        state.audit_service.clone(),
+    ))
+}
+
+/// 发起主机 SSH 密钥轮换（后台分阶段执行，仅管理员）
+pub async fn start_credential_rotation(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(req): Json<StartCredentialRotationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &auth_context).await?;
+    state
+        .auth_service
+        .two_factor()
+        .require_step_up(&auth_context)
+        .await?;
+
+    // 展开目标主机与资产组，按主机去重
+    let repo = crate::repository::AssetRepository::new(state.db.clone());
+    let mut hosts: Vec<Host> = Vec::new();
+    for host_id in &req.host_ids {
+        let host = repo
+            .get_host(*host_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Resource not found"))?;
+        hosts.push(host);
+    }
+    for group_id in &req.group_ids {
+        hosts.extend(repo.list_group_hosts(*group_id).await?);
+    }
+    let mut seen = std::collections::HashSet::new();
+    hosts.retain(|h| seen.insert(h.id));
+
+    let host_count = hosts.len();
+    let rotation = credential_rotator(&state)
+        .start(hosts, req, auth_context.user_id)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::HostCredentialRotationStart,
+            Some("credential_rotation"),
+            Some(rotation.id),
+            Some(&format!(
+                "Started credential rotation for {} hosts (new key: {})",
+                host_count, rotation.key_fingerprint
+            )),
+            None,
+        )
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(rotation)))
+}
+
+/// 列出凭据轮换记录
+pub async fn list_credential_rotations(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &auth_context).await?;
+
+    let rotations = credential_rotator(&state).list(50).await?;
+
+    Ok(Json(rotations))
+}
+
+/// 获取凭据轮换记录（含各主机结果）
+pub async fn get_credential_rotation(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state, &auth_context).await?;
+
+    let rotation = credential_rotator(&state)
+        .get(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Resource not found"))?;
+
+    Ok(Json(rotation))
+}
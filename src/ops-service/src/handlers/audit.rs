@@ -1,11 +1,14 @@
 //! 审计日志的 HTTP 处理器
 
+use crate::realtime::ScopeChecker;
+use crate::services::view_audit::{ViewAuditor, ViewTarget};
 use crate::{
     auth::middleware::AuthContext, error::AppError, middleware::AppState, models::audit::*,
 };
 use axum::{
-    extract::{Query, State},
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
 use chrono::{DateTime, Utc};
@@ -15,24 +18,44 @@ use std::sync::Arc;
 
 #[derive(Debug, Deserialize)]
 pub struct AuditLogQuery {
-    pub subject_id: Option<uuid::Uuid>,
-    pub resource_type: Option<String>,
-    pub resource_id: Option<uuid::Uuid>,
-    pub action: Option<String>,
-    pub result: Option<String>,
-    pub start_time: Option<DateTime<Utc>>,
-    pub end_time: Option<DateTime<Utc>>,
-    pub trace_id: Option<String>,
+    #[serde(flatten)]
+    pub filters: AuditLogFilters,
+    /// 上一页返回的 next_cursor，提供时忽略 offset
+    pub cursor: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: i64,
     #[serde(default)]
     pub offset: i64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AuditFacetQuery {
+    #[serde(flatten)]
+    pub filters: AuditLogFilters,
+    /// 每个分面返回的最大项数
+    #[serde(default = "default_facet_limit")]
+    pub limit: i64,
+}
+
 fn default_limit() -> i64 {
     50
 }
 
+fn default_facet_limit() -> i64 {
+    20
+}
+
+/// 单页审计日志上限
+const MAX_AUDIT_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct AnomalyQuery {
+    #[serde(flatten)]
+    pub filters: SecurityAnomalyFilters,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LoginEventQuery {
     pub user_id: Option<uuid::Uuid>,
@@ -55,22 +78,32 @@ pub async fn list_audit_logs(
         .require_permission(auth_context.user_id, "audit", "read", None, None)
         .await?;
 
-    let filters = AuditLogFilters {
-        subject_id: query.subject_id,
-        resource_type: query.resource_type,
-        resource_id: query.resource_id,
-        action: query.action,
-        result: query.result,
-        start_time: query.start_time,
-        end_time: query.end_time,
-        trace_id: query.trace_id,
-    };
+    let filters = query.filters;
+    let limit = query.limit.clamp(1, MAX_AUDIT_PAGE_SIZE);
 
-    let logs = state
-        .audit_service
-        .query_logs(&filters, query.limit, query.offset)
-        .await?;
+    let logs = match query.cursor.as_deref() {
+        Some(token) => {
+            let cursor = AuditCursor::decode(token)
+                .ok_or_else(|| AppError::validation("Invalid audit cursor"))?;
+            state
+                .audit_service
+                .query_logs_after(&filters, &cursor, limit)
+                .await?
+        }
+        None => {
+            state
+                .audit_service
+                .query_logs(&filters, limit, query.offset.max(0))
+                .await?
+        }
+    };
     let count = state.audit_service.count_logs(&filters).await?;
+    // 满页时才可能有下一页
+    let next_cursor = if logs.len() as i64 == limit {
+        logs.last().map(|log| AuditCursor::after(log).encode())
+    } else {
+        None
+    };
 
     let _ = state
         .audit_service
@@ -87,10 +120,30 @@ pub async fn list_audit_logs(
     Ok(Json(json!({
         "logs": logs,
         "count": logs.len(),
-        "total": count
+        "total": count,
+        "next_cursor": next_cursor
     })))
 }
 
+/// 查询审计日志分面计数（按操作、资源类型、结果）
+pub async fn audit_facets(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Query(query): Query<AuditFacetQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "audit", "read", None, None)
+        .await?;
+
+    let facets = state
+        .audit_service
+        .facets(&query.filters, query.limit.clamp(1, 100))
+        .await?;
+
+    Ok(Json(json!({ "facets": facets })))
+}
+
 /// 查询登录事件
 pub async fn list_login_events(
     State(state): State<Arc<AppState>>,
@@ -131,3 +184,110 @@ pub async fn list_login_events(
         "count": events.len()
     })))
 }
+
+/// 查询安全异常
+pub async fn list_anomalies(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Query(query): Query<AnomalyQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "audit", "read", None, None)
+        .await?;
+
+    let anomalies = state
+        .audit_service
+        .query_anomalies(&query.filters, query.limit.clamp(1, MAX_AUDIT_PAGE_SIZE))
+        .await?;
+
+    Ok(Json(json!({
+        "anomalies": anomalies,
+        "count": anomalies.len()
+    })))
+}
+
+/// 确认安全异常（需要审计管理权限）
+pub async fn acknowledge_anomaly(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<uuid::Uuid>,
+    body: Option<Json<AcknowledgeAnomalyRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "audit", "admin", None, None)
+        .await?;
+
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    let anomaly = state
+        .audit_service
+        .acknowledge_anomaly(id, auth_context.user_id, request.note.as_deref())
+        .await?;
+
+    let _ = state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            crate::services::audit_service::AuditAction::AnomalyAcknowledge,
+            Some("security_anomaly"),
+            Some(id),
+            Some(&anomaly.summary),
+            None,
+        )
+        .await;
+
+    Ok(Json(anomaly))
+}
+
+/// 订阅安全异常事件流（SSE）
+/// 需启用 OPS_ANOMALY__NOTIFY；订阅时校验 audit.read 权限，推送过程中按缓存周期复核
+pub async fn subscribe_security_events(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<Response, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "audit", "read", None, None)
+        .await?;
+
+    let checker_state = state.clone();
+    let user_id = auth_context.user_id;
+    let scope = ScopeChecker::new(ScopeChecker::DEFAULT_TTL, move || {
+        let state = checker_state.clone();
+        Box::pin(async move {
+            state
+                .permission_service
+                .check_permission(user_id, "audit", "read", None, None)
+                .await
+                .unwrap_or(false)
+        })
+    })
+    .with_initial(true);
+
+    let stream = state
+        .event_bus
+        .subscribe_to_security()
+        .with_scope_checker(scope)
+        .to_sse_stream()
+        .await?;
+    let stream = view_auditor(&state)
+        .track_stream(user_id, "security", ViewTarget::new("audit", None).verbose(), stream)
+        .await;
+
+    let body = axum::body::Body::from_stream(stream);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .header("Connection", "keep-alive")
+        .header("X-Accel-Buffering", "no")
+        .body(body)
+        .map_err(|e| AppError::internal_error(&format!("Failed to create SSE response: {}", e)))
+}
+
+/// 查看类操作审计器（记录级别取自 audit.view_level）
+pub(crate) fn view_auditor(state: &AppState) -> ViewAuditor {
+    ViewAuditor::new(state.audit_service.clone(), state.config.audit.view_level)
+}
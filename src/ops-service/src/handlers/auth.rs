@@ -1,12 +1,21 @@
 //! 认证相关的 HTTP 处理器
 
 use crate::{
-    auth::middleware::AuthContext, error::AppError, middleware::AppState, models::auth::*,
-    services::audit_service::AuditAction,
+    auth::middleware::AuthContext,
+    error::AppError,
+    middleware::AppState,
+    models::auth::*,
+    services::audit_service::{AuditAction, AuditLogParams},
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json,
 };
-use axum::{extract::State, http::HeaderMap, response::IntoResponse, Json};
 use serde_json::json;
 use std::sync::Arc;
+use uuid::Uuid;
 
 /// 登录
 pub async fn login(
@@ -42,6 +51,13 @@ pub async fn login(
     Ok(Json(response))
 }
 
+/// JWKS：发布 RS256 验签公钥，供内部服务校验本服务签发的令牌
+///
+/// 包含尚未生效的密钥以便提前分发；HS256 密钥不对外发布
+pub async fn jwks(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    ([(header::CACHE_CONTROL, "public, max-age=300")], Json(state.jwt_service.jwks()))
+}
+
 /// 刷新令牌
 pub async fn refresh_token(
     State(state): State<Arc<AppState>>,
@@ -79,7 +95,7 @@ pub async fn logout(
         )
         .await?;
 
-    Ok(Json(json!({"message": "已成功登出"})))
+    Ok(Json(json!({"message": crate::i18n::t("notice.auth.logged_out")})))
 }
 
 /// 从所有设备登出
@@ -103,10 +119,195 @@ pub async fn logout_all(
         .await?;
 
     Ok(Json(json!({
-        "message": format!("已从 {} 个设备登出", revoked_count)
+        "message": crate::i18n::tf("notice.auth.logged_out_all", &[("count", &revoked_count)])
     })))
 }
 
+/// 列出当前用户的有效登录会话
+pub async fn list_my_sessions(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    let sessions = state
+        .auth_service
+        .list_sessions(auth_context.user_id, auth_context.session_id)
+        .await?;
+
+    Ok(Json(sessions))
+}
+
+/// 撤销当前用户的某个登录会话（可用于在其他设备上登出）
+pub async fn revoke_my_session(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(session_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = state
+        .auth_service
+        .revoke_session(
+            auth_context.user_id,
+            session_id,
+            auth_context.user_id,
+            crate::auth::sessions::REVOKE_REASON_USER,
+        )
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserSessionRevoke,
+            Some("session"),
+            Some(session.id),
+            Some(&format!("Revoked own session from {}", session.ip_address)),
+            None,
+        )
+        .await?;
+
+    Ok(Json(json!({"message": crate::i18n::t("notice.auth.session_revoked")})))
+}
+
+/// 查询当前用户的双因素认证状态
+pub async fn get_two_factor_status(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    let status = state
+        .auth_service
+        .two_factor()
+        .status(&auth_context)
+        .await?;
+
+    Ok(Json(status))
+}
+
+/// 发起 TOTP 绑定（返回密钥与 otpauth URI，由客户端渲染二维码）
+pub async fn begin_two_factor_enrollment(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    let enrollment = state
+        .auth_service
+        .two_factor()
+        .begin_enrollment(auth_context.user_id, &auth_context.username)
+        .await?;
+
+    Ok(Json(enrollment))
+}
+
+/// 确认 TOTP 绑定（返回恢复码，仅显示一次）
+pub async fn confirm_two_factor_enrollment(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let recovery_codes = state
+        .auth_service
+        .two_factor()
+        .confirm_enrollment(auth_context.user_id, &req.code)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserTwoFactorEnable,
+            Some("user"),
+            Some(auth_context.user_id),
+            Some("Enabled TOTP two-factor authentication"),
+            None,
+        )
+        .await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// 关闭 TOTP（需验证码或恢复码）
+pub async fn disable_two_factor(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .auth_service
+        .two_factor()
+        .disable(&auth_context, &req.code)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserTwoFactorDisable,
+            Some("user"),
+            Some(auth_context.user_id),
+            Some("Disabled TOTP two-factor authentication"),
+            None,
+        )
+        .await?;
+
+    Ok(Json(json!({"message": crate::i18n::t("notice.auth.two_factor_disabled")})))
+}
+
+/// 重新生成恢复码（需验证码或恢复码，旧恢复码作废）
+pub async fn regenerate_recovery_codes(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let recovery_codes = state
+        .auth_service
+        .two_factor()
+        .regenerate_recovery_codes(auth_context.user_id, &req.code)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserTwoFactorRecoveryCodes,
+            Some("user"),
+            Some(auth_context.user_id),
+            Some("Regenerated two-factor recovery codes"),
+            None,
+        )
+        .await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// 二次验证（高风险操作前在当前会话完成验证）
+pub async fn verify_two_factor(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let step_up_until = state
+        .auth_service
+        .two_factor()
+        .step_up(&auth_context, &req.code)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserTwoFactorStepUp,
+            Some("session"),
+            auth_context.session_id,
+            Some(&format!("Step-up verification valid until {}", step_up_until)),
+            None,
+        )
+        .await?;
+
+    Ok(Json(StepUpResponse { step_up_until }))
+}
+
 /// 获取当前用户信息
 pub async fn get_current_user(auth_context: AuthContext) -> Result<impl IntoResponse, AppError> {
     Ok(Json(json!({
@@ -114,12 +315,132 @@ pub async fn get_current_user(auth_context: AuthContext) -> Result<impl IntoResp
         "username": auth_context.username,
         "roles": auth_context.roles,
         "scopes": auth_context.scopes,
+        "impersonator": auth_context.impersonator,
     })))
 }
 
+/// 开始模拟用户（签发短期模拟令牌）
+pub async fn start_impersonation(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    headers: HeaderMap,
+    Json(req): Json<ImpersonateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "user", "impersonate", None, None)
+        .await?;
+
+    let client_ip = get_client_ip_str(&headers, state.config.security.trust_proxy);
+    let user_agent = headers.get("user-agent").and_then(|v| v.to_str().ok());
+
+    let response = state
+        .auth_service
+        .start_impersonation(&auth_context, req, &client_ip, user_agent)
+        .await?;
+
+    // 审计日志
+    let session = &response.session;
+    state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: auth_context.user_id,
+            subject_type: "user",
+            subject_name: Some(&auth_context.username),
+            action: AuditAction::UserImpersonateStart.as_str(),
+            resource_type: "user",
+            resource_id: Some(session.target_user_id),
+            resource_name: Some(&session.target_username),
+            changes: Some(json!({
+                "session_id": session.id,
+                "reason": session.reason,
+                "expires_at": session.expires_at,
+            })),
+            changes_summary: Some(&format!("Started impersonating {}", session.target_username)),
+            source_ip: Some(&client_ip),
+            user_agent,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        })
+        .await?;
+
+    Ok(Json(response))
+}
+
+/// 列出当前有效的模拟会话
+pub async fn list_impersonation_sessions(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "user", "impersonate", None, None)
+        .await?;
+
+    let sessions = state.auth_service.list_active_impersonations().await?;
+
+    Ok(Json(sessions))
+}
+
+/// 终止模拟会话
+///
+/// 持有模拟令牌的一方可以直接结束自己的会话，其他会话需要模拟权限
+pub async fn terminate_impersonation_session(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(session_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // 使用模拟令牌结束当前会话时，以模拟者身份记录
+    let (actor_id, actor_name) = match &auth_context.impersonator {
+        Some(i) if i.session_id == session_id => (i.user_id, i.username.clone()),
+        _ => {
+            state
+                .permission_service
+                .require_permission(auth_context.user_id, "user", "impersonate", None, None)
+                .await?;
+            (auth_context.user_id, auth_context.username.clone())
+        }
+    };
+
+    let session = state
+        .auth_service
+        .terminate_impersonation(session_id, actor_id)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: actor_id,
+            subject_type: "user",
+            subject_name: Some(&actor_name),
+            action: AuditAction::UserImpersonateEnd.as_str(),
+            resource_type: "user",
+            resource_id: Some(session.target_user_id),
+            resource_name: Some(&session.target_username),
+            changes: Some(json!({
+                "session_id": session.id,
+                "impersonator_id": session.impersonator_id,
+            })),
+            changes_summary: Some(&format!(
+                "Terminated impersonation of {} by {}",
+                session.target_username, session.impersonator_name
+            )),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        })
+        .await?;
+
+    Ok(Json(session))
+}
+
 /// 获取客户端 IP 地址字符串（统一版本）
 /// 当 trust_proxy 为 false 时忽略代理头，使用默认值
-fn get_client_ip_str(headers: &HeaderMap, trust_proxy: bool) -> String {
+pub(crate) fn get_client_ip_str(headers: &HeaderMap, trust_proxy: bool) -> String {
     if !trust_proxy {
         return "unknown".to_string();
     }
@@ -0,0 +1,233 @@
+//! 内容寻址 blob API 处理器
+//!
+//! 按 sha256 上传/下载脚本与构建产物内容，查询去重统计；
+//! 大脚本与二进制内容通过 multipart 流式上传
+
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::{AppError, Result},
+    middleware::AppState,
+    models::blob::{ContentBlob, BLOB_OWNER_ARTIFACT, BLOB_OWNER_JOB},
+    services::BlobStore,
+};
+
+/// 客户端声明的内容哈希，上传时校验
+const CONTENT_SHA256_HEADER: &str = "x-content-sha256";
+
+/// 流式上传时承载内容的 multipart 字段名
+const UPLOAD_FIELD: &str = "file";
+
+/// blob 元数据响应
+#[derive(Debug, Serialize)]
+pub struct BlobMetadataResponse {
+    #[serde(flatten)]
+    pub blob: ContentBlob,
+    /// 引用该内容的引用方类型
+    pub owner_types: Vec<String>,
+}
+
+fn parse_hash(sha256: &str) -> Result<String> {
+    BlobStore::normalize_hash(sha256).ok_or_else(|| AppError::validation("Invalid sha256"))
+}
+
+/// 读取权限跟随引用方：任一引用方类型的读权限即可；
+/// 尚无引用的内容只对具备上传权限（artifact:write）的用户可见
+async fn require_blob_read(state: &AppState, auth: &AuthContext, sha256: &str) -> Result<()> {
+    let owner_types = state.blob_store.owner_types(sha256).await?;
+    if owner_types.is_empty() {
+        return state
+            .permission_service
+            .require_permission(auth.user_id, "artifact", "write", None, None)
+            .await;
+    }
+
+    for owner_type in &owner_types {
+        let resource = match owner_type.as_str() {
+            BLOB_OWNER_JOB => "job",
+            BLOB_OWNER_ARTIFACT => "artifact",
+            _ => continue,
+        };
+        if state
+            .permission_service
+            .check_permission(auth.user_id, resource, "read", None, None)
+            .await
+            .unwrap_or(false)
+        {
+            return Ok(());
+        }
+    }
+    Err(AppError::Forbidden)
+}
+
+/// 上传内容（原始字节），已存在时直接返回（去重）
+pub async fn upload_blob(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "artifact", "write", None, None)
+        .await?;
+
+    if let Some(expected) = expected_hash(&headers)? {
+        if expected != BlobStore::hash(&body) {
+            return Err(AppError::validation("Content does not match x-content-sha256 header"));
+        }
+    }
+
+    let result = state.blob_store.put(&body).await?;
+
+    info!(
+        user_id = %auth.user_id,
+        sha256 = %result.sha256,
+        size_bytes = result.size_bytes,
+        deduplicated = result.deduplicated,
+        "Blob uploaded"
+    );
+
+    let status = if result.deduplicated {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(result)))
+}
+
+/// 流式上传内容（multipart 的 file 字段），不整体读入内存，已存在时直接返回（去重）
+///
+/// 用于大脚本与二进制内容：脚本作业通过 `script_sha256` 引用，执行时分块传输到目标主机
+pub async fn upload_blob_stream(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "artifact", "write", None, None)
+        .await?;
+    let expected = expected_hash(&headers)?;
+
+    let field = loop {
+        match multipart
+            .next_field()
+            .await
+            .map_err(|e| AppError::validation(&format!("Invalid multipart body: {}", e)))?
+        {
+            Some(field) if field.name() == Some(UPLOAD_FIELD) => break field,
+            Some(_) => continue,
+            None => return Err(AppError::validation("Missing multipart field: file")),
+        }
+    };
+    let filename = field.file_name().map(str::to_string);
+
+    // 哈希在写入后才能确定；不匹配的内容尚无引用，由 GC 在宽限期后回收
+    let result = state.blob_store.put_stream(field).await?;
+    if expected.is_some_and(|expected| expected != result.sha256) {
+        return Err(AppError::validation("Content does not match x-content-sha256 header"));
+    }
+
+    info!(
+        user_id = %auth.user_id,
+        sha256 = %result.sha256,
+        size_bytes = result.size_bytes,
+        deduplicated = result.deduplicated,
+        filename = ?filename,
+        "Blob uploaded from stream"
+    );
+
+    let status = if result.deduplicated {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(result)))
+}
+
+/// 解析客户端声明的内容哈希
+fn expected_hash(headers: &HeaderMap) -> Result<Option<String>> {
+    headers
+        .get(CONTENT_SHA256_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(BlobStore::normalize_hash)
+                .ok_or_else(|| AppError::validation("Invalid x-content-sha256 header"))
+        })
+        .transpose()
+}
+
+/// 下载内容
+pub async fn download_blob(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(sha256): Path<String>,
+) -> Result<impl IntoResponse> {
+    let sha256 = parse_hash(&sha256)?;
+    require_blob_read(&state, &auth, &sha256).await?;
+
+    let data = state.blob_store.get(&sha256).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::ETAG, format!("\"{}\"", sha256)),
+            (header::CACHE_CONTROL, "private, max-age=31536000, immutable".to_string()),
+        ],
+        data,
+    ))
+}
+
+/// 查询 blob 元数据
+pub async fn get_blob_metadata(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(sha256): Path<String>,
+) -> Result<impl IntoResponse> {
+    let sha256 = parse_hash(&sha256)?;
+    require_blob_read(&state, &auth, &sha256).await?;
+
+    let blob = state
+        .blob_store
+        .find(&sha256)
+        .await?
+        .ok_or_else(|| AppError::not_found("Blob not found"))?;
+    let owner_types = state.blob_store.owner_types(&sha256).await?;
+
+    Ok(Json(BlobMetadataResponse { blob, owner_types }))
+}
+
+/// 去重统计（管理员或 artifact:write）
+pub async fn get_blob_stats(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin {
+        state
+            .permission_service
+            .require_permission(auth.user_id, "artifact", "write", None, None)
+            .await?;
+    }
+
+    let stats = state.blob_store.stats().await?;
+    Ok(Json(stats))
+}
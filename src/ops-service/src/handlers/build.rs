@@ -3,8 +3,8 @@
 //! 提供构建作业的创建、查询、取消、重试等功能
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
     response::{IntoResponse, Json},
 };
 use chrono::Utc;
@@ -58,6 +58,14 @@ pub struct CreateBuildJobRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub publish_target: Option<PublishTargetRequest>,
 
+    /// 仓库凭据（私有仓库，仅随任务消息下发给 Runner，不落库）
+    #[serde(default)]
+    pub git_credentials: Option<GitCredentials>,
+
+    /// 代码检出选项（子模块、LFS、浅克隆）
+    #[serde(default)]
+    pub git_checkout: GitCheckoutOptions,
+
     /// 超时时间（秒）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_secs: Option<i32>,
@@ -65,6 +73,10 @@ pub struct CreateBuildJobRequest {
     /// 标签
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+
+    /// 粘性调度：优先派发到最近一次成功构建同一仓库分支的 Runner（复用缓存）
+    #[serde(default)]
+    pub sticky_runner: bool,
 }
 
 /// 构建步骤请求
@@ -99,10 +111,18 @@ pub struct BuildStepRequest {
     #[serde(default)]
     pub continue_on_failure: bool,
 
+    /// 重试策略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<StepRetryPolicy>,
+
     /// 是否产生产物
     #[serde(default)]
     pub produces_artifact: bool,
 
+    /// 产物的目标平台（交叉编译时指定，缺省为 Runner 自身平台）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_platform: Option<ArtifactPlatform>,
+
     /// Docker 镜像
     #[serde(skip_serializing_if = "Option::is_none")]
     pub docker_image: Option<String>,
@@ -286,6 +306,13 @@ pub async fn create_build_job(
     if request.steps.is_empty() {
         return Err(AppError::validation("Build job must have at least one step"));
     }
+    for step in &request.steps {
+        if let Some(retry) = &step.retry {
+            retry.validate().map_err(|e| {
+                AppError::validation(&format!("Invalid retry policy for step {}: {}", step.id, e))
+            })?;
+        }
+    }
 
     let job_id = Uuid::new_v4();
     let commit = request.commit.filter(|c| !c.is_empty()).unwrap_or_else(|| "".to_string());
@@ -307,7 +334,7 @@ pub async fn create_build_job(
     )
     .bind(parent_job_id)
     .bind(&request.project_name)
-    .bind(&format!("Build: {}", request.project_name))
+    .bind(format!("Build: {}", request.project_name))
     .bind(auth.user_id)
     .bind(serde_json::to_value(&request.tags).unwrap_or(serde_json::json!([])))
     .execute(&state.db)
@@ -389,6 +416,8 @@ pub async fn create_build_job(
             branch: request.branch.clone(),
             commit: commit.clone(),
             triggered_by: auth.user_id,
+            credentials: request.git_credentials.clone(),
+            checkout: request.git_checkout.clone(),
         },
         build: BuildParameters {
             build_type: request.build_type.clone(),
@@ -407,7 +436,12 @@ pub async fn create_build_job(
                 working_dir: s.working_dir.clone(),
                 timeout_secs: s.timeout_secs,
                 continue_on_failure: s.continue_on_failure,
+                retry: s.retry.clone(),
                 produces_artifact: s.produces_artifact,
+                artifact_platform: s
+                    .artifact_platform
+                    .as_ref()
+                    .map(ArtifactPlatform::normalized),
                 docker_image: s.docker_image.clone(),
             })
             .collect(),
@@ -426,7 +460,14 @@ pub async fn create_build_job(
     // 发布到 RabbitMQ
     match state.rabbitmq_publisher.get().await {
         Ok(_) => {
-            if let Err(e) = dispatch_build_task(&state, &build_task, &request.build_type).await {
+            let dispatched = dispatch_build_task(
+                &state,
+                &build_task,
+                &request.build_type,
+                request.sticky_runner,
+            )
+            .await;
+            if let Err(e) = dispatched {
                 error!(error = %e, "Failed to dispatch build task to RabbitMQ");
                 // 不阻塞响应，但记录错误
             } else {
@@ -734,11 +775,31 @@ pub async fn get_build_steps(
         completed_at: Option<chrono::DateTime<chrono::Utc>>,
         exit_code: Option<i32>,
         error: Option<String>,
+        peak_memory_bytes: Option<i64>,
+        cpu_time_ms: Option<i64>,
+        disk_written_bytes: Option<i64>,
+        attempt: i32,
         created_at: chrono::DateTime<chrono::Utc>,
+        #[sqlx(skip)]
+        attempts: Vec<AttemptRow>,
+    }
+
+    // 按重试策略重新执行过的步骤的每次尝试
+    #[derive(sqlx::FromRow, Serialize, Default)]
+    struct AttemptRow {
+        #[serde(skip)]
+        step_id: String,
+        attempt: i32,
+        status: String,
+        started_at: Option<chrono::DateTime<chrono::Utc>>,
+        completed_at: Option<chrono::DateTime<chrono::Utc>>,
+        exit_code: Option<i32>,
+        retrying: bool,
     }
 
-    let steps: Vec<StepRow> = sqlx::query_as(
-        "SELECT step_id, step_name, status::text, started_at, completed_at, exit_code, error, created_at
+    let mut steps: Vec<StepRow> = sqlx::query_as(
+        "SELECT step_id, step_name, status::text, started_at, completed_at, exit_code, error,
+                peak_memory_bytes, cpu_time_ms, disk_written_bytes, attempt, created_at
          FROM build_steps
          WHERE job_id = $1
          ORDER BY created_at",
@@ -751,12 +812,219 @@ pub async fn get_build_steps(
         AppError::database("Failed to get build steps")
     })?;
 
+    let attempts: Vec<AttemptRow> = sqlx::query_as(
+        "SELECT step_id, attempt, status, started_at, completed_at, exit_code, retrying
+         FROM build_step_attempts
+         WHERE job_id = $1
+         ORDER BY step_id, attempt",
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get build step attempts");
+        AppError::database("Failed to get build steps")
+    })?;
+    for attempt in attempts {
+        if let Some(step) = steps.iter_mut().find(|s| s.step_id == attempt.step_id) {
+            step.attempts.push(attempt);
+        }
+    }
+
+    // 资源使用汇总：峰值内存取各步骤最大值，用于评估 Docker 资源限制
+    let resource_summary = serde_json::json!({
+        "max_peak_memory_bytes": steps.iter().filter_map(|s| s.peak_memory_bytes).max(),
+        "total_cpu_time_ms": steps.iter().filter_map(|s| s.cpu_time_ms).sum::<i64>(),
+        "total_disk_written_bytes": steps.iter().filter_map(|s| s.disk_written_bytes).sum::<i64>(),
+    });
+
     Ok(Json(serde_json::json!({
         "job_id": id,
         "steps": steps,
+        "resource_summary": resource_summary,
     })))
 }
 
+/// 构建日志查询参数
+#[derive(Debug, Deserialize)]
+pub struct BuildLogQuery {
+    /// 仅返回指定步骤的日志
+    pub step_id: Option<String>,
+
+    /// 起始位置（按存储顺序跳过的日志块数）
+    #[serde(default)]
+    pub offset: i64,
+
+    /// 返回的最大日志块数（默认 200，最大 1000）
+    pub limit: Option<i64>,
+}
+
+/// 构建日志块
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub struct BuildLogChunk {
+    pub task_id: Uuid,
+    pub step_id: String,
+    pub chunk_index: i64,
+    pub byte_offset: i64,
+    pub level: String,
+    pub content: String,
+    pub content_modified: bool,
+    pub is_final: bool,
+    pub logged_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 日志块排序：步骤按首个日志块的消息序号排列（旧版 Runner 无序号时按到达顺序），
+/// 步骤内按块序号排列
+const BUILD_LOG_ORDER: &str =
+    "ORDER BY MIN(NULLIF(sequence, 0)) OVER (PARTITION BY task_id, step_id), \
+     MIN(id) OVER (PARTITION BY task_id, step_id), task_id, step_id, chunk_index";
+
+/// 校验用户可查看构建日志（无权限时返回 404 防枚举）
+async fn ensure_build_log_access(state: &AppState, auth: &AuthContext, id: Uuid) -> Result<()> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "build", "output_detail", None, None)
+        .await?;
+
+    let job = sqlx::query("SELECT triggered_by FROM build_jobs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to get build job");
+            AppError::database("Failed to get build job")
+        })?
+        .ok_or_else(|| AppError::not_found("Build job not found"))?;
+
+    let triggered_by: Uuid = job.get::<Uuid, _>("triggered_by");
+
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin && triggered_by != auth.user_id {
+        return Err(AppError::not_found("Build job not found")); // 反枚举
+    }
+
+    Ok(())
+}
+
+/// 查询构建日志（分段）
+pub async fn get_build_logs(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(query): Query<BuildLogQuery>,
+) -> Result<impl IntoResponse> {
+    ensure_build_log_access(&state, &auth, id).await?;
+
+    let offset = query.offset.max(0);
+    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
+
+    // 多取一条用于判断是否还有后续日志
+    let mut chunks: Vec<BuildLogChunk> = sqlx::query_as(&format!(
+        "SELECT task_id, step_id, chunk_index, byte_offset, level, content, content_modified,
+                is_final, logged_at
+         FROM build_logs
+         WHERE job_id = $1 AND ($2::text IS NULL OR step_id = $2)
+         {}
+         LIMIT $3 OFFSET $4",
+        BUILD_LOG_ORDER
+    ))
+    .bind(id)
+    .bind(&query.step_id)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get build logs");
+        AppError::database("Failed to get build logs")
+    })?;
+
+    let has_more = chunks.len() as i64 > limit;
+    chunks.truncate(limit as usize);
+    let next_offset = offset + chunks.len() as i64;
+
+    Ok(Json(serde_json::json!({
+        "job_id": id,
+        "chunks": chunks,
+        "offset": offset,
+        "next_offset": next_offset,
+        "has_more": has_more,
+    })))
+}
+
+/// 下载完整构建日志（gzip 压缩）
+pub async fn download_build_logs(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(query): Query<BuildLogQuery>,
+) -> Result<impl IntoResponse> {
+    ensure_build_log_access(&state, &auth, id).await?;
+
+    let chunks: Vec<BuildLogChunk> = sqlx::query_as(&format!(
+        "SELECT task_id, step_id, chunk_index, byte_offset, level, content, content_modified,
+                is_final, logged_at
+         FROM build_logs
+         WHERE job_id = $1 AND ($2::text IS NULL OR step_id = $2)
+         {}",
+        BUILD_LOG_ORDER
+    ))
+    .bind(id)
+    .bind(&query.step_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get build logs");
+        AppError::database("Failed to get build logs")
+    })?;
+
+    let body = gzip_build_log(&render_build_log(&chunks)).map_err(|e| {
+        error!(error = %e, "Failed to compress build logs");
+        AppError::internal_error("Failed to compress build logs")
+    })?;
+
+    let headers = [
+        (header::CONTENT_TYPE, "application/gzip".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"build-{}.log.gz\"", id),
+        ),
+    ];
+
+    Ok((StatusCode::OK, headers, body))
+}
+
+/// 将日志块拼接为纯文本，每个步骤前插入分隔标题
+fn render_build_log(chunks: &[BuildLogChunk]) -> String {
+    let mut text = String::new();
+    let mut current_step: Option<(Uuid, &str)> = None;
+    for chunk in chunks {
+        let step = (chunk.task_id, chunk.step_id.as_str());
+        if current_step != Some(step) {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&format!("==> step: {}\n", chunk.step_id));
+            current_step = Some(step);
+        }
+        text.push_str(&chunk.content);
+    }
+    text
+}
+
+/// gzip 压缩日志文本
+fn gzip_build_log(text: &str) -> std::io::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(text.as_bytes())?;
+    encoder.finish()
+}
+
 /// 取消构建作业
 pub async fn cancel_build_job(
     State(state): State<Arc<AppState>>,
@@ -779,7 +1047,7 @@ pub async fn cancel_build_job(
         })?
         .ok_or_else(|| AppError::not_found("Build job not found"))?;
 
-    let created_by: Uuid = job.get::<Uuid, _>("created_by");
+    let created_by: Uuid = job.get::<Uuid, _>("triggered_by");
     let status: String = job.get::<String, _>("status");
 
     // 检查权限（反枚举：返回 404 而非 403）
@@ -810,6 +1078,24 @@ pub async fn cancel_build_job(
             AppError::database("Failed to cancel build job")
         })?;
 
+    // 通知 Runner 中止正在执行的任务（尽力而为，Runner 未执行该作业时忽略）
+    let cancel_message = BuildCancelMessage {
+        job_id: id,
+        reason: Some("Cancelled by user".to_string()),
+        requested_by: Some(auth.user_id),
+        timestamp: Utc::now(),
+    };
+    match state.rabbitmq_publisher.get().await {
+        Ok(publisher) => {
+            if let Err(e) = publisher.publish_build_cancel(&cancel_message).await {
+                warn!(error = %e, job_id = %id, "Failed to publish build cancel message");
+            }
+        }
+        Err(e) => {
+            warn!(error = %e, job_id = %id, "Failed to get RabbitMQ publisher for cancel");
+        }
+    }
+
     // 记录审计日志
     let _ = state
         .audit_service
@@ -977,26 +1263,54 @@ fn parse_step_type(step_type: &str) -> StepType {
 
 /// 派发构建任务到 RabbitMQ
 ///
-/// 使用 RunnerScheduler 选择合适的 Runner，然后派发任务到 RabbitMQ
+/// 使用 RunnerScheduler 选择合适的 Runner，然后派发任务到 RabbitMQ；
+/// 启用粘性调度时优先选择该仓库分支的亲和 Runner
 async fn dispatch_build_task(
     state: &Arc<AppState>,
     task: &BuildTaskMessage,
     build_type: &str,
+    sticky: bool,
 ) -> Result<()> {
+    // 插件步骤只能派发给安装了对应插件的 Runner
+    let mut required_capabilities: Vec<String> = Vec::new();
+    for name in task
+        .steps
+        .iter()
+        .filter_map(|step| step.step_type.plugin_name())
+    {
+        let capability = format!("{}{}", PLUGIN_STEP_PREFIX, name);
+        if !required_capabilities.contains(&capability) {
+            required_capabilities.push(capability);
+        }
+    }
+
     // 使用 RunnerScheduler 选择合适的 Runner
-    let schedule_result = state
-        .runner_scheduler
-        .schedule_build(build_type, &[])
-        .await
-        .map_err(|e| {
-            error!(error = %e, build_type = %build_type, "Failed to schedule build task");
-            AppError::internal_error(&format!("Failed to schedule build: {}", e))
-        })?;
+    let scheduled = if sticky {
+        state
+            .runner_scheduler
+            .schedule_build_sticky(
+                build_type,
+                &required_capabilities,
+                &task.project.repository_url,
+                &task.project.branch,
+            )
+            .await
+    } else {
+        state
+            .runner_scheduler
+            .schedule_build(build_type, &required_capabilities)
+            .await
+    };
+    let schedule_result = scheduled.map_err(|e| {
+        error!(error = %e, build_type = %build_type, "Failed to schedule build task");
+        AppError::internal_error(&format!("Failed to schedule build: {}", e))
+    })?;
 
     info!(
         runner_id = %schedule_result.runner_id,
         runner_name = %schedule_result.runner_name,
         routing_key = %schedule_result.routing_key,
+        sticky = schedule_result.sticky,
         job_id = %task.job_id,
         "Build task scheduled"
     );
@@ -1037,3 +1351,43 @@ async fn dispatch_build_task(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn chunk(task_id: Uuid, step_id: &str, chunk_index: i64, content: &str) -> BuildLogChunk {
+        BuildLogChunk {
+            task_id,
+            step_id: step_id.to_string(),
+            chunk_index,
+            byte_offset: 0,
+            level: "info".to_string(),
+            content: content.to_string(),
+            content_modified: false,
+            is_final: false,
+            logged_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_render_and_gzip_build_log() {
+        let task_id = Uuid::new_v4();
+        let chunks = vec![
+            chunk(task_id, "install", 0, "npm install\n"),
+            chunk(task_id, "install", 1, "done"),
+            chunk(task_id, "build", 0, "npm run build\n"),
+        ];
+
+        let text = render_build_log(&chunks);
+        assert_eq!(text, "==> step: install\nnpm install\ndone\n==> step: build\nnpm run build\n");
+
+        let compressed = gzip_build_log(&text).unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, text);
+    }
+}
@@ -8,19 +8,25 @@ use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
 use common::messages::{
-    BuildArtifact, BuildLogMessage, BuildStatus, BuildStatusMessage, StepStatus, StepStatusUpdate,
+    BuildArtifact, BuildLogMessage, BuildStatus, BuildStatusMessage, LogLevel, StepStatus,
+    StepStatusUpdate,
 };
-use sqlx::Row;
+use sqlx::{PgConnection, PgPool, Row};
 use uuid::Uuid;
 
 use crate::{
     error::{AppError, Result},
+    handlers::artifact::link_artifact_blob,
     middleware::AppState,
-    realtime::RealtimeEvent,
+    realtime::{outbox, RealtimeEvent},
+    services::RunnerScheduler,
 };
 
 /// 合法状态迁移表
 /// 返回 true 表示允许从当前状态迁移到新状态
+///
+/// completed/failed/cancelled 是终态：重试会创建新的构建作业，
+/// 迟到或重复投递的 Running 消息不得把已结束的作业改回运行中
 fn is_valid_status_transition(current: Option<&str>, new: &str) -> bool {
     match (current, new) {
         // 新建作业可以从任何状态开始
@@ -31,58 +37,14 @@ fn is_valid_status_transition(current: Option<&str>, new: &str) -> bool {
         // running -> completed/failed/cancelled
         (Some("running"), "completed" | "failed" | "cancelled") => true,
         (Some("running"), _) => false,
-        // completed/failed/cancelled 是终态，不允许迁移
-        (Some("completed"), _) => false,
-        (Some("failed"), "running") => true, // 允许重试
-        (Some("failed"), _) => false,
-        (Some("cancelled"), "running") => true, // 允许重试
-        (Some("cancelled"), _) => false,
-        // 未知状态默认拒绝
+        // 终态及未知状态默认拒绝
         _ => false,
     }
 }
 
-/// 接收构建状态更新
-pub async fn build_status_webhook(
-    State(state): State<Arc<AppState>>,
-    Json(payload): Json<BuildStatusMessage>,
-) -> Result<impl IntoResponse> {
-    debug!(
-        task_id = %payload.task_id,
-        job_id = %payload.job_id,
-        runner = %payload.runner_name,
-        status = ?payload.status,
-        "Received build status update"
-    );
-
-    // 检查构建作业是否存在，同时获取当前状态用于迁移校验
-    let job_row = sqlx::query("SELECT id, status::text FROM build_jobs WHERE id = $1")
-        .bind(payload.job_id)
-        .fetch_optional(&state.db)
-        .await
-        .map_err(|e| {
-            error!(error = %e, job_id = %payload.job_id, "Failed to check build job");
-            AppError::database("Failed to check build job")
-        })?;
-
-    let (current_status_opt, job_exists) = match job_row {
-        Some(row) => (
-            row.try_get::<String, _>("status").ok(),
-            true,
-        ),
-        None => (None, false),
-    };
-
-    if !job_exists {
-        warn!(
-            job_id = %payload.job_id,
-            "Build job not found for status update"
-        );
-        return Ok(StatusCode::ACCEPTED);
-    }
-
-    // 更新构建作业状态
-    let status_str = match payload.status {
+/// Runner 上报的构建状态对应的作业状态
+fn job_status_for(status: &BuildStatus) -> &'static str {
+    match status {
         BuildStatus::Received => "pending",
         BuildStatus::Preparing => "pending",
         BuildStatus::Running => "running",
@@ -90,100 +52,215 @@ pub async fn build_status_webhook(
         BuildStatus::Failed => "failed",
         BuildStatus::Timeout => "failed",
         BuildStatus::Cancelled => "cancelled",
+    }
+}
+
+/// 状态消息的处理结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StatusIngestion {
+    /// 构建作业不存在
+    JobNotFound,
+    /// 序号不大于已处理的最大序号（重复投递或乱序到达），作业状态不变
+    Stale { current_status: String },
+    /// 作业状态未变化或迁移不合法
+    Unchanged { current_status: String },
+    /// 作业状态已更新
+    Applied { previous_status: String },
+}
+
+/// 处理一条构建状态消息
+///
+/// 作业行加锁后在同一事务中完成序号登记、状态迁移和发件箱事件，
+/// 同一消息重复投递或乱序到达时作业状态只会按序号前进一次
+async fn ingest_build_status(
+    state: &AppState,
+    payload: &BuildStatusMessage,
+    uploaded_by: Option<Uuid>,
+) -> Result<StatusIngestion> {
+    let status_str = job_status_for(&payload.status);
+
+    let mut tx = state.db.begin().await?;
+
+    // 锁定作业行，串行化同一作业并发处理的状态消息
+    let current_status = sqlx::query_scalar::<_, String>(
+        "SELECT status::text FROM build_jobs WHERE id = $1 FOR UPDATE",
+    )
+    .bind(payload.job_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!(error = %e, job_id = %payload.job_id, "Failed to check build job");
+        AppError::database("Failed to check build job")
+    })?;
+
+    let Some(current_status) = current_status else {
+        return Ok(StatusIngestion::JobNotFound);
     };
 
-    // 状态迁移校验：拒绝非法迁移
-    if !is_valid_status_transition(current_status_opt.as_deref(), status_str) {
+    let ingestion = if !claim_status_sequence(&mut tx, payload).await? {
+        debug!(
+            job_id = %payload.job_id,
+            task_id = %payload.task_id,
+            sequence = payload.sequence,
+            "Duplicate or out-of-order status message, keeping job status"
+        );
+        StatusIngestion::Stale { current_status }
+    } else if current_status == status_str {
+        StatusIngestion::Unchanged { current_status }
+    } else if !is_valid_status_transition(Some(&current_status), status_str) {
         warn!(
             job_id = %payload.job_id,
-            current_status = ?current_status_opt,
+            current_status = %current_status,
             new_status = %status_str,
             "Invalid status transition, ignoring"
         );
-        return Ok(StatusCode::ACCEPTED);
-    }
-
-    // 幂等性保护：如果新旧状态相同且不是终态，跳过更新
-    if current_status_opt.as_deref() == Some(status_str) {
-        debug!(
-            job_id = %payload.job_id,
-            status = %status_str,
-            "Status unchanged, skipping update"
+        StatusIngestion::Unchanged { current_status }
+    } else {
+        let started = matches!(payload.status, BuildStatus::Running | BuildStatus::Preparing);
+        let completed = matches!(
+            payload.status,
+            BuildStatus::Succeeded
+                | BuildStatus::Failed
+                | BuildStatus::Timeout
+                | BuildStatus::Cancelled
         );
-        return Ok(StatusCode::ACCEPTED);
-    }
 
-    // 根据状态更新时间戳
-    let (started_at, completed_at) = match payload.status {
-        BuildStatus::Running | BuildStatus::Preparing => (Some(Utc::now()), None),
-        BuildStatus::Succeeded
-        | BuildStatus::Failed
-        | BuildStatus::Timeout
-        | BuildStatus::Cancelled => {
-            // 获取当前 started_at（如果有）
-            let current: Option<chrono::DateTime<chrono::Utc>> =
-                sqlx::query("SELECT started_at FROM build_jobs WHERE id = $1")
-                    .bind(payload.job_id)
-                    .fetch_optional(&state.db)
-                    .await?
-                    .and_then(|row| row.get("started_at"));
-            (current, Some(Utc::now()))
-        }
-        _ => (None, None),
-    };
+        sqlx::query(
+            "UPDATE build_jobs
+             SET status = CAST($1 AS job_status), runner_name = $2,
+                 started_at = CASE WHEN $3 THEN NOW() ELSE started_at END,
+                 completed_at = CASE WHEN $4 THEN NOW() ELSE completed_at END,
+                 build_summary = COALESCE($6, build_summary),
+                 updated_at = NOW()
+             WHERE id = $5",
+        )
+        .bind(status_str)
+        .bind(&payload.runner_name)
+        .bind(started)
+        .bind(completed)
+        .bind(payload.job_id)
+        .bind(failure_summary(payload))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %payload.job_id, "Failed to update build job status");
+            AppError::database("Failed to update build job")
+        })?;
 
-    let mut query = String::from("UPDATE build_jobs SET status = CAST($1 AS job_status)");
-    let mut param_idx = 2;
+        let event = RealtimeEvent::JobStatusChanged {
+            job_id: payload.job_id,
+            old_status: current_status.clone(),
+            new_status: status_str.to_string(),
+        };
+        outbox::enqueue(&mut *tx, &event).await?;
 
-    if let Some(_start) = started_at {
-        query.push_str(&format!(", started_at = ${}", param_idx));
-        param_idx += 1;
-    }
-    if let Some(_end) = completed_at {
-        query.push_str(&format!(", completed_at = ${}", param_idx));
-        param_idx += 1;
-    }
+        // 成功构建的 Runner 保留了该仓库分支的缓存，记录为后续粘性调度的首选
+        if matches!(payload.status, BuildStatus::Succeeded) {
+            RunnerScheduler::record_affinity(&mut tx, payload.job_id, &payload.runner_name)
+                .await
+                .map_err(|e| {
+                    error!(error = ?e, job_id = %payload.job_id, "Failed to record affinity");
+                    AppError::database("Failed to record project affinity")
+                })?;
+        }
 
-    query.push_str(&format!(", updated_at = NOW() WHERE id = ${}", param_idx));
+        StatusIngestion::Applied {
+            previous_status: current_status,
+        }
+    };
 
-    // 执行更新
-    let mut query_builder = sqlx::query(&query).bind(status_str);
-    if let Some(start) = started_at {
-        query_builder = query_builder.bind(start);
+    tx.commit().await?;
+    if matches!(ingestion, StatusIngestion::Applied { .. }) {
+        state.event_bus.notify_outbox();
     }
-    if let Some(end) = completed_at {
-        query_builder = query_builder.bind(end);
-    }
-    query_builder = query_builder.bind(payload.job_id);
-
-    query_builder.execute(&state.db).await.map_err(|e| {
-        error!(error = %e, job_id = %payload.job_id, "Failed to update build job status");
-        AppError::database("Failed to update build job")
-    })?;
 
-    // 如果有步骤状态更新，处理步骤状态
+    // 步骤状态按步骤独立判断序号：乱序到达的旧消息仍可能携带其他步骤的最新状态
     if let Some(ref step_update) = payload.step_status {
-        let uploaded_by = lookup_runner_id(&state, &payload.runner_name).await;
-        if let Err(e) =
-            update_step_status_with_uploader(&state, &payload, step_update, uploaded_by).await
-        {
+        if let Err(e) = update_step_status(&state.db, payload, step_update, uploaded_by).await {
             error!(error = ?e, "Failed to update step status");
         }
     }
 
-    // 发布实时事件 (SSE)
-    let _ = state.event_bus.publish(RealtimeEvent::JobStatusChanged {
-        job_id: payload.job_id,
-        old_status: current_status_opt.unwrap_or_default(),
-        new_status: status_str.to_string(),
-    });
+    Ok(ingestion)
+}
 
+/// 失败状态附带的错误信息（如 Runner 崩溃后上报的中断任务），写入构建摘要
+fn failure_summary(payload: &BuildStatusMessage) -> Option<String> {
+    let error = payload.error.as_deref()?;
+    Some(match &payload.error_category {
+        Some(category) => format!("[{:?}] {}", category, error),
+        None => error.to_string(),
+    })
+}
+
+/// 登记状态消息序号
+///
+/// 仅当序号大于该任务已处理的最大序号时登记成功并返回 true；
+/// 旧版 Runner 不提供序号（为 0），不做去重
+async fn claim_status_sequence(
+    conn: &mut PgConnection,
+    payload: &BuildStatusMessage,
+) -> Result<bool> {
+    if payload.sequence == 0 {
+        return Ok(true);
+    }
+
+    let claimed = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO build_status_sequences (task_id, job_id, last_sequence)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (task_id) DO UPDATE
+         SET last_sequence = EXCLUDED.last_sequence, updated_at = NOW()
+         WHERE build_status_sequences.last_sequence < EXCLUDED.last_sequence
+         RETURNING task_id",
+    )
+    .bind(payload.task_id)
+    .bind(payload.job_id)
+    .bind(saturating_i64(payload.sequence))
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        error!(error = %e, task_id = %payload.task_id, "Failed to record status sequence");
+        AppError::database("Failed to record status sequence")
+    })?;
+
+    Ok(claimed.is_some())
+}
+
+/// 接收构建状态更新
+pub async fn build_status_webhook(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<BuildStatusMessage>,
+) -> Result<impl IntoResponse> {
     debug!(
+        task_id = %payload.task_id,
         job_id = %payload.job_id,
-        status = %status_str,
-        "Build status updated"
+        runner = %payload.runner_name,
+        status = ?payload.status,
+        "Received build status update"
     );
 
+    let uploaded_by = match payload.step_status {
+        Some(_) => lookup_runner_id(&state, &payload.runner_name).await,
+        None => None,
+    };
+
+    match ingest_build_status(&state, &payload, uploaded_by).await? {
+        StatusIngestion::JobNotFound => {
+            warn!(
+                job_id = %payload.job_id,
+                "Build job not found for status update"
+            );
+        }
+        ingestion => {
+            debug!(
+                job_id = %payload.job_id,
+                status = ?payload.status,
+                ingestion = ?ingestion,
+                "Build status processed"
+            );
+        }
+    }
+
     Ok(StatusCode::ACCEPTED)
 }
 
@@ -207,6 +284,20 @@ pub async fn build_log_webhook(
         return Ok(StatusCode::ACCEPTED);
     }
 
+    let stored = store_log_chunk(&state.db, &payload).await.map_err(|e| {
+        error!(error = %e, job_id = %payload.job_id, "Failed to store build log chunk");
+        AppError::database("Failed to store build log")
+    })?;
+    if !stored {
+        debug!(
+            job_id = %payload.job_id,
+            step_id = %payload.step_id,
+            chunk_index = payload.chunk_index,
+            "Duplicate log chunk, skipping"
+        );
+        return Ok(StatusCode::ACCEPTED);
+    }
+
     // 查找或创建步骤记录，同时获取当前 offset 用于幂等/乱序保护
     let step_row = sqlx::query(
         "SELECT id, COALESCE(log_offset, 0) as log_offset FROM build_steps WHERE job_id = $1 AND step_id = $2",
@@ -298,23 +389,31 @@ pub async fn build_artifact_webhook(
         return Ok(StatusCode::ACCEPTED);
     }
 
-    // 检查产物是否已存在（根据版本号和类型）
-    let existing =
-        sqlx::query("SELECT id FROM build_artifacts WHERE version = $1 AND artifact_type = $2")
-            .bind(&payload.artifact.version)
-            .bind(&payload.artifact.artifact_type)
-            .fetch_optional(&state.db)
-            .await
-            .map_err(|e| {
-                error!(error = %e, "Failed to check existing artifact");
-                AppError::database("Failed to check artifact")
-            })?;
+    let arch = common::normalize_arch(&payload.artifact.arch);
+    let os = common::normalize_os(&payload.artifact.os);
+
+    // 检查产物是否已存在（根据版本号、类型和平台）
+    let existing = sqlx::query(
+        "SELECT id FROM build_artifacts WHERE version = $1 AND artifact_type = $2 AND arch = $3 AND os = $4",
+    )
+    .bind(&payload.artifact.version)
+    .bind(&payload.artifact.artifact_type)
+    .bind(&arch)
+    .bind(&os)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to check existing artifact");
+        AppError::database("Failed to check artifact")
+    })?;
 
     if existing.is_some() {
         // 产物已存在，拒绝覆盖上传
         warn!(
             version = %payload.artifact.version,
             artifact_type = %payload.artifact.artifact_type,
+            arch = %arch,
+            os = %os,
             "Artifact already exists, rejecting overwrite"
         );
         return Err(AppError::validation(
@@ -323,10 +422,11 @@ pub async fn build_artifact_webhook(
     }
 
     // 记录产物元数据
-    sqlx::query(
+    let artifact_id = sqlx::query_scalar::<_, Uuid>(
         "INSERT INTO build_artifacts (build_job_id, artifact_name, artifact_type, artifact_path,
-                                     artifact_size, artifact_hash, version, metadata, uploaded_by)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                                     artifact_size, artifact_hash, version, arch, os, metadata, uploaded_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         RETURNING id",
     )
     .bind(payload.job_id)
     .bind(&payload.artifact.name)
@@ -335,15 +435,21 @@ pub async fn build_artifact_webhook(
     .bind(payload.artifact.size as i64)
     .bind(&payload.artifact.sha256)
     .bind(&payload.artifact.version)
+    .bind(&arch)
+    .bind(&os)
     .bind(serde_json::to_value(payload.metadata).unwrap_or_default())
     .bind(payload.uploaded_by)
-    .execute(&state.db)
+    .fetch_one(&state.db)
     .await
     .map_err(|e| {
         error!(error = %e, "Failed to record artifact");
         AppError::database("Failed to record artifact")
     })?;
 
+    if let Err(e) = link_artifact_blob(&state.db, artifact_id, &payload.artifact.sha256).await {
+        warn!(artifact_id = %artifact_id, error = %e, "Failed to link artifact to blob store");
+    }
+
     // 更新构建作业的产物统计
     sqlx::query(
         "UPDATE build_jobs SET has_artifacts = TRUE, artifact_count = artifact_count + 1 WHERE id = $1",
@@ -376,18 +482,54 @@ async fn lookup_runner_id(state: &AppState, runner_name: &str) -> Option<Uuid> {
         .flatten()
 }
 
-/// 更新步骤状态
-async fn update_step_status(
-    state: &AppState,
-    status_msg: &BuildStatusMessage,
-    step_update: &StepStatusUpdate,
-) -> Result<()> {
-    update_step_status_with_uploader(state, status_msg, step_update, None).await
+/// 将日志块写入 build_logs
+///
+/// 按 (task_id, step_id, chunk_index) 幂等写入，返回 false 表示该块已存在（重复投递）
+async fn store_log_chunk(
+    db: &sqlx::PgPool,
+    payload: &BuildLogMessage,
+) -> std::result::Result<bool, sqlx::Error> {
+    let level = match payload.level {
+        LogLevel::Debug => "debug",
+        LogLevel::Info => "info",
+        LogLevel::Warn => "warn",
+        LogLevel::Error => "error",
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO build_logs
+            (job_id, task_id, step_id, chunk_index, byte_offset, level, content, content_modified,
+             is_final, sequence, logged_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         ON CONFLICT (task_id, step_id, chunk_index) DO NOTHING",
+    )
+    .bind(payload.job_id)
+    .bind(payload.task_id)
+    .bind(&payload.step_id)
+    .bind(saturating_i64(payload.chunk_index))
+    .bind(saturating_i64(payload.offset))
+    .bind(level)
+    .bind(&payload.content)
+    .bind(payload.content_modified)
+    .bind(payload.is_final)
+    .bind(saturating_i64(payload.sequence))
+    .bind(payload.timestamp)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// u64 转换为数据库 BIGINT（溢出时取最大值）
+fn saturating_i64(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
 }
 
 /// 更新步骤状态（带上传者信息）
-async fn update_step_status_with_uploader(
-    state: &AppState,
+///
+/// 序号不大于步骤已应用的序号时忽略该更新（重复投递或乱序到达）
+async fn update_step_status(
+    db: &PgPool,
     status_msg: &BuildStatusMessage,
     step_update: &StepStatusUpdate,
     uploaded_by: Option<Uuid>,
@@ -400,23 +542,43 @@ async fn update_step_status_with_uploader(
         StepStatus::Failed => "failed",
         StepStatus::Timeout => "timeout",
         StepStatus::Skipped => "skipped",
+        StepStatus::Cancelled => "cancelled",
     };
+    let sequence = saturating_i64(status_msg.sequence);
 
-    // 检查步骤是否存在
-    let existing = sqlx::query("SELECT id FROM build_steps WHERE job_id = $1 AND step_id = $2")
-        .bind(status_msg.job_id)
-        .bind(&step_update.step_id)
-        .fetch_optional(&state.db)
-        .await?;
+    // 检查步骤是否存在，同时获取已应用的序号
+    let existing = sqlx::query_scalar::<_, i64>(
+        "SELECT status_sequence FROM build_steps WHERE job_id = $1 AND step_id = $2",
+    )
+    .bind(status_msg.job_id)
+    .bind(&step_update.step_id)
+    .fetch_optional(db)
+    .await?;
+
+    // 资源使用情况（仅步骤结束时上报，未上报时保留原值）
+    let usage = step_update.resource_usage.as_ref();
+    let peak_memory_bytes = usage.map(|u| saturating_i64(u.peak_memory_bytes));
+    let cpu_time_ms = usage.map(|u| saturating_i64(u.cpu_time_ms));
+    let disk_written_bytes = usage.map(|u| saturating_i64(u.disk_written_bytes));
+    // 尝试序号（旧版 Runner 不上报，视为第 1 次）
+    let attempt = i32::try_from(step_update.attempt.max(1)).unwrap_or(i32::MAX);
 
     if existing.is_some() {
-        // 更新现有步骤
-        sqlx::query(
+        // 更新现有步骤（旧版 Runner 不提供序号，不做序号校验）
+        // 新一次尝试开始时清除上一次尝试的结束时间
+        let result = sqlx::query(
             "UPDATE build_steps
              SET status = $1, started_at = COALESCE($2, started_at),
-                 completed_at = COALESCE($3, completed_at),
-                 exit_code = $4, updated_at = NOW()
-             WHERE job_id = $5 AND step_id = $6",
+                 completed_at = CASE WHEN $11 > attempt THEN $3
+                                     ELSE COALESCE($3, completed_at) END,
+                 exit_code = $4,
+                 peak_memory_bytes = COALESCE($7, peak_memory_bytes),
+                 cpu_time_ms = COALESCE($8, cpu_time_ms),
+                 disk_written_bytes = COALESCE($9, disk_written_bytes),
+                 status_sequence = GREATEST(status_sequence, $10),
+                 attempt = GREATEST(attempt, $11),
+                 updated_at = NOW()
+             WHERE job_id = $5 AND step_id = $6 AND ($10 = 0 OR status_sequence < $10)",
         )
         .bind(status_str)
         .bind(step_update.started_at)
@@ -424,13 +586,30 @@ async fn update_step_status_with_uploader(
         .bind(step_update.exit_code)
         .bind(status_msg.job_id)
         .bind(&step_update.step_id)
-        .execute(&state.db)
+        .bind(peak_memory_bytes)
+        .bind(cpu_time_ms)
+        .bind(disk_written_bytes)
+        .bind(sequence)
+        .bind(attempt)
+        .execute(db)
         .await?;
+
+        if result.rows_affected() == 0 {
+            debug!(
+                job_id = %status_msg.job_id,
+                step_id = %step_update.step_id,
+                sequence = status_msg.sequence,
+                "Duplicate or out-of-order step status, skipping"
+            );
+            return Ok(());
+        }
     } else {
         // 创建新步骤记录
         sqlx::query(
-            "INSERT INTO build_steps (job_id, step_id, step_name, status, started_at, completed_at, exit_code, created_at, updated_at)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())",
+            "INSERT INTO build_steps (job_id, step_id, step_name, status, started_at, completed_at, exit_code,
+                                      peak_memory_bytes, cpu_time_ms, disk_written_bytes, status_sequence,
+                                      attempt, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, NOW(), NOW())",
         )
         .bind(status_msg.job_id)
         .bind(&step_update.step_id)
@@ -439,17 +618,54 @@ async fn update_step_status_with_uploader(
         .bind(step_update.started_at)
         .bind(step_update.completed_at)
         .bind(step_update.exit_code)
-        .execute(&state.db)
+        .bind(peak_memory_bytes)
+        .bind(cpu_time_ms)
+        .bind(disk_written_bytes)
+        .bind(sequence)
+        .bind(attempt)
+        .execute(db)
+        .await?;
+    }
+
+    // 记录尝试历史（只记录带尝试序号的新版 Runner 状态）
+    if step_update.attempt > 0 {
+        sqlx::query(
+            "INSERT INTO build_step_attempts (job_id, step_id, attempt, status, started_at,
+                                              completed_at, exit_code, retrying, status_sequence,
+                                              updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW())
+             ON CONFLICT (job_id, step_id, attempt) DO UPDATE SET
+                 status = EXCLUDED.status,
+                 started_at = COALESCE(EXCLUDED.started_at, build_step_attempts.started_at),
+                 completed_at = COALESCE(EXCLUDED.completed_at, build_step_attempts.completed_at),
+                 exit_code = EXCLUDED.exit_code,
+                 retrying = EXCLUDED.retrying,
+                 status_sequence = EXCLUDED.status_sequence,
+                 updated_at = NOW()
+             WHERE EXCLUDED.status_sequence = 0
+                OR build_step_attempts.status_sequence < EXCLUDED.status_sequence",
+        )
+        .bind(status_msg.job_id)
+        .bind(&step_update.step_id)
+        .bind(attempt)
+        .bind(status_str)
+        .bind(step_update.started_at)
+        .bind(step_update.completed_at)
+        .bind(step_update.exit_code)
+        .bind(step_update.retrying)
+        .bind(sequence)
+        .execute(db)
         .await?;
     }
 
     // 如果有产物，记录产物信息
     if let Some(artifact) = &step_update.artifact {
-        sqlx::query(
+        let artifact_id = sqlx::query_scalar::<_, Uuid>(
             "INSERT INTO build_artifacts (build_job_id, artifact_name, artifact_type, artifact_path,
-                                         artifact_size, artifact_hash, version, metadata, uploaded_by)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-             ON CONFLICT (version, artifact_type) DO NOTHING",
+                                         artifact_size, artifact_hash, version, arch, os, metadata, uploaded_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             ON CONFLICT (version, artifact_type, arch, os) WHERE version IS NOT NULL DO NOTHING
+             RETURNING id",
         )
         .bind(status_msg.job_id)
         .bind(&artifact.name)
@@ -458,13 +674,21 @@ async fn update_step_status_with_uploader(
         .bind(artifact.size as i64)
         .bind(&artifact.sha256)
         .bind(&artifact.version)
+        .bind(common::normalize_arch(&artifact.arch))
+        .bind(common::normalize_os(&artifact.os))
         .bind(serde_json::json!({
             "step_id": step_update.step_id,
             "produced_at": Utc::now(),
         }))
         .bind(uploaded_by.unwrap_or_else(Uuid::new_v4))
-        .execute(&state.db)
+        .fetch_optional(db)
         .await?;
+
+        if let Some(artifact_id) = artifact_id {
+            if let Err(e) = link_artifact_blob(db, artifact_id, &artifact.sha256).await {
+                warn!(artifact_id = %artifact_id, error = %e, "Failed to link artifact to blob store");
+            }
+        }
     }
 
     Ok(())
@@ -514,87 +738,17 @@ impl BuildMessageConsumer {
             "Processing build status from RabbitMQ"
         );
 
-        // 获取当前作业状态（用于判断是否需要递减 current_jobs）
-        let current_status: Option<String> =
-            sqlx::query_scalar::<_, String>("SELECT status::text FROM build_jobs WHERE id = $1")
-                .bind(payload.job_id)
-                .fetch_optional(&self.state.db)
-                .await?;
-
-        if current_status.is_none() {
-            warn!(
-                job_id = %payload.job_id,
-                "Build job not found for status update"
-            );
-            return Ok(());
-        }
-
-        // 更新构建作业状态
-        let status_str = match payload.status {
-            BuildStatus::Received => "pending",
-            BuildStatus::Preparing => "pending",
-            BuildStatus::Running => "running",
-            BuildStatus::Succeeded => "completed",
-            BuildStatus::Failed => "failed",
-            BuildStatus::Timeout => "failed",
-            BuildStatus::Cancelled => "cancelled",
-        };
-
-        // 根据状态更新时间戳
-        let (started_at, completed_at) = match payload.status {
-            BuildStatus::Running | BuildStatus::Preparing => (Some(Utc::now()), None),
-            BuildStatus::Succeeded
-            | BuildStatus::Failed
-            | BuildStatus::Timeout
-            | BuildStatus::Cancelled => {
-                let current: Option<chrono::DateTime<chrono::Utc>> =
-                    sqlx::query("SELECT started_at FROM build_jobs WHERE id = $1")
-                        .bind(payload.job_id)
-                        .fetch_optional(&self.state.db)
-                        .await?
-                        .and_then(|row| row.get("started_at"));
-                (current, Some(Utc::now()))
-            }
-            _ => (None, None),
+        let uploaded_by = match payload.step_status {
+            Some(_) => lookup_runner_id(&self.state, &payload.runner_name).await,
+            None => None,
         };
 
-        let mut query = String::from("UPDATE build_jobs SET status = CAST($1 AS job_status)");
-        let mut param_idx = 2;
-
-        if started_at.is_some() {
-            query.push_str(&format!(", started_at = ${}", param_idx));
-            param_idx += 1;
-        }
-        if completed_at.is_some() {
-            query.push_str(&format!(", completed_at = ${}", param_idx));
-            param_idx += 1;
-        }
-
-        query.push_str(&format!(", updated_at = NOW() WHERE id = ${}", param_idx));
-
-        let mut query_builder = sqlx::query(&query).bind(status_str);
-        if let Some(start) = started_at {
-            query_builder = query_builder.bind(start);
-        }
-        if let Some(end) = completed_at {
-            query_builder = query_builder.bind(end);
-        }
-        query_builder = query_builder.bind(payload.job_id);
-
-        query_builder
-            .execute(&self.state.db)
+        let ingestion = ingest_build_status(&self.state, &payload, uploaded_by)
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to update build job: {}", e))?;
-
-        // 处理步骤状态
-        if let Some(ref step_update) = payload.step_status {
-            if let Err(e) = update_step_status(&self.state, &payload, step_update).await {
-                error!(error = ?e, "Failed to update step status");
-            }
-        }
+            .map_err(|e| anyhow::anyhow!("Failed to ingest build status: {}", e))?;
 
         // 递减 Runner current_jobs（仅在任务从运行状态转为最终状态时）
-        // 避免重复递减：只有当前状态是 running/pending 且新状态是最终状态时才递减
+        // 重复投递或过期的消息不递减，避免同一任务多次释放 Runner 容量
         let is_terminal_status = matches!(
             payload.status,
             BuildStatus::Succeeded
@@ -602,7 +756,23 @@ impl BuildMessageConsumer {
                 | BuildStatus::Timeout
                 | BuildStatus::Cancelled
         );
-        let was_running = matches!(current_status.as_deref(), Some("running") | Some("pending"));
+        let was_running = match &ingestion {
+            StatusIngestion::JobNotFound => {
+                warn!(
+                    job_id = %payload.job_id,
+                    "Build job not found for status update"
+                );
+                return Ok(());
+            }
+            StatusIngestion::Stale { .. } => false,
+            StatusIngestion::Applied { previous_status } => {
+                matches!(previous_status.as_str(), "running" | "pending")
+            }
+            // 控制面发起的取消会先将作业置为 cancelled，Runner 回报的 Cancelled 才是任务真正结束
+            StatusIngestion::Unchanged { current_status } => {
+                payload.status == BuildStatus::Cancelled && current_status == "cancelled"
+            }
+        };
 
         if is_terminal_status && was_running {
             if let Err(e) = Self::decrement_runner_jobs(&self.state, &payload.runner_name).await {
@@ -612,7 +782,11 @@ impl BuildMessageConsumer {
             }
         }
 
-        debug!(job_id = %payload.job_id, status = %status_str, "Build status updated from RabbitMQ");
+        debug!(
+            job_id = %payload.job_id,
+            ingestion = ?ingestion,
+            "Build status processed from RabbitMQ"
+        );
         Ok(())
     }
 
@@ -652,6 +826,19 @@ impl BuildMessageConsumer {
             return Ok(());
         }
 
+        let stored = store_log_chunk(&self.state.db, &payload)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to store build log chunk: {}", e))?;
+        if !stored {
+            debug!(
+                job_id = %payload.job_id,
+                step_id = %payload.step_id,
+                chunk_index = payload.chunk_index,
+                "Duplicate log chunk from RabbitMQ, skipping"
+            );
+            return Ok(());
+        }
+
         // 检查步骤是否存在
         let step_exists =
             sqlx::query("SELECT id FROM build_steps WHERE job_id = $1 AND step_id = $2")
@@ -691,3 +878,63 @@ impl BuildMessageConsumer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::messages::ErrorCategory;
+
+    #[test]
+    fn test_terminal_statuses_never_regress() {
+        for terminal in ["completed", "failed", "cancelled"] {
+            for new in ["pending", "running", "completed", "failed", "cancelled"] {
+                assert!(
+                    !is_valid_status_transition(Some(terminal), new),
+                    "{} -> {} should be rejected",
+                    terminal,
+                    new
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_forward_transitions_allowed() {
+        assert!(is_valid_status_transition(Some("pending"), "running"));
+        assert!(is_valid_status_transition(Some("pending"), "cancelled"));
+        assert!(is_valid_status_transition(Some("running"), "completed"));
+        assert!(is_valid_status_transition(Some("running"), "failed"));
+        assert!(!is_valid_status_transition(Some("running"), "pending"));
+    }
+
+    #[test]
+    fn test_job_status_mapping() {
+        assert_eq!(job_status_for(&BuildStatus::Preparing), "pending");
+        assert_eq!(job_status_for(&BuildStatus::Succeeded), "completed");
+        assert_eq!(job_status_for(&BuildStatus::Timeout), "failed");
+    }
+
+    #[test]
+    fn test_failure_summary_includes_category() {
+        let mut payload = BuildStatusMessage {
+            task_id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            runner_name: "runner-1".to_string(),
+            attempt_id: None,
+            status: BuildStatus::Failed,
+            step_status: None,
+            error: None,
+            error_category: None,
+            sequence: 1,
+            timestamp: Utc::now(),
+        };
+        assert_eq!(failure_summary(&payload), None);
+
+        payload.error = Some("Runner terminated unexpectedly".to_string());
+        payload.error_category = Some(ErrorCategory::Infrastructure);
+        assert_eq!(
+            failure_summary(&payload).as_deref(),
+            Some("[Infrastructure] Runner terminated unexpectedly")
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! 环境策略处理器
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::{AppError, Result},
+    middleware::AppState,
+    models::environment_policy::UpsertEnvironmentPolicyRequest,
+    services::{audit_service::AuditAction, EnvironmentPolicyService},
+};
+
+async fn require_admin(state: &Arc<AppState>, auth: &AuthContext) -> Result<()> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
+
+/// 列出环境策略
+pub async fn list_environment_policies(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    let policies = EnvironmentPolicyService::new(state.db.clone())
+        .list()
+        .await?;
+
+    Ok(Json(policies))
+}
+
+/// 获取环境策略
+pub async fn get_environment_policy(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(environment): Path<String>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    let policy = EnvironmentPolicyService::new(state.db.clone())
+        .get(&environment)
+        .await?
+        .ok_or_else(|| AppError::not_found("Environment policy not found"))?;
+
+    Ok(Json(policy))
+}
+
+/// 创建或替换环境策略（仅管理员）
+pub async fn upsert_environment_policy(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(environment): Path<String>,
+    Json(request): Json<UpsertEnvironmentPolicyRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &auth).await?;
+
+    let policy = EnvironmentPolicyService::new(state.db.clone())
+        .upsert(&environment, &request, auth.user_id)
+        .await?;
+
+    let summary = format!(
+        "Updated environment policy '{}': timeout={:?}, concurrency={:?}, approval={}, \
+         {} blackout windows",
+        environment,
+        policy.default_timeout_secs,
+        policy.default_concurrent_limit,
+        policy.require_approval,
+        policy.blackout_windows.len()
+    );
+    state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::EnvironmentPolicyUpdate,
+            Some("environment_policy"),
+            None,
+            Some(&summary),
+            None,
+        )
+        .await?;
+
+    Ok(Json(policy))
+}
+
+/// 删除环境策略（仅管理员）
+pub async fn delete_environment_policy(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(environment): Path<String>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &auth).await?;
+
+    let deleted = EnvironmentPolicyService::new(state.db.clone())
+        .delete(&environment)
+        .await?;
+    if !deleted {
+        return Err(AppError::not_found("Environment policy not found"));
+    }
+
+    let summary = format!("Deleted environment policy '{}'", environment);
+    state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::EnvironmentPolicyDelete,
+            Some("environment_policy"),
+            None,
+            Some(&summary),
+            None,
+        )
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,11 @@
+//! 错误码目录处理器
+//! 提供 /api/errors 端点，供客户端获取机器可读的错误码列表（说明按请求语言输出）
+
+use axum::Json;
+
+use crate::error::{error_catalog_in, ErrorCatalogEntry};
+
+/// 获取错误码目录
+pub async fn list_error_codes() -> Json<Vec<ErrorCatalogEntry>> {
+    Json(error_catalog_in(crate::i18n::current_locale()))
+}
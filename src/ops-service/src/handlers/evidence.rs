@@ -0,0 +1,201 @@
+//! 合规证据包 API 处理器
+//!
+//! 发起作业证据包导出、查询生成进度、签发限时下载链接
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::{AppError, Result},
+    handlers::{audit::view_auditor, job::check_job_access},
+    middleware::AppState,
+    models::evidence::{EvidenceDownloadQuery, EvidenceExport, EvidenceExportStatus},
+    services::{audit_service::AuditAction, view_audit::ViewTarget},
+};
+
+/// 证据包包含审批与审计记录，要求 audit:read 并可访问该作业（反枚举：不可访问时返回 404）
+async fn require_job_evidence_access(
+    state: &Arc<AppState>,
+    auth: &AuthContext,
+    job_id: Uuid,
+) -> Result<()> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "audit", "read", None, None)
+        .await?;
+
+    let job = state
+        .job_service
+        .get_job(job_id)
+        .await
+        .map_err(|_| AppError::not_found("Job not found"))?;
+    if !check_job_access(state, auth.user_id, &job).await? {
+        return Err(AppError::not_found("Job not found"));
+    }
+    Ok(())
+}
+
+/// 导出记录仅对发起人与管理员可见
+async fn load_own_export(
+    state: &Arc<AppState>,
+    auth: &AuthContext,
+    export_id: Uuid,
+) -> Result<EvidenceExport> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "audit", "read", None, None)
+        .await?;
+
+    let export = state
+        .evidence_exporter
+        .get(export_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Evidence export not found"))?;
+    if export.requested_by != auth.user_id
+        && !state
+            .permission_service
+            .is_admin(auth.user_id)
+            .await
+            .unwrap_or(false)
+    {
+        return Err(AppError::not_found("Evidence export not found"));
+    }
+    Ok(export)
+}
+
+/// 发起证据包导出（后台生成，进度通过通知事件流推送）
+pub async fn export_job_evidence(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_job_evidence_access(&state, &auth, job_id).await?;
+
+    let export = state.evidence_exporter.start(job_id, auth.user_id).await?;
+
+    let _ = state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::JobEvidenceExport,
+            Some("job"),
+            Some(job_id),
+            Some("Evidence export requested"),
+            None,
+        )
+        .await;
+
+    info!(job_id = %job_id, export_id = %export.id, "Evidence export requested");
+
+    Ok((StatusCode::ACCEPTED, Json(export)))
+}
+
+/// 作业的证据包导出记录
+pub async fn list_job_evidence_exports(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_job_evidence_access(&state, &auth, job_id).await?;
+
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+    let exports: Vec<EvidenceExport> = state
+        .evidence_exporter
+        .list_for_job(job_id)
+        .await?
+        .into_iter()
+        .filter(|export| is_admin || export.requested_by == auth.user_id)
+        .collect();
+
+    Ok(Json(exports))
+}
+
+/// 查询导出状态与进度
+pub async fn get_evidence_export(
+    State(state): State<Arc<AppState>>,
+    Path(export_id): Path<Uuid>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    let export = load_own_export(&state, &auth, export_id).await?;
+    Ok(Json(export))
+}
+
+/// 签发限时下载链接（仅已完成的导出）
+pub async fn create_evidence_download_link(
+    State(state): State<Arc<AppState>>,
+    Path(export_id): Path<Uuid>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    let export = load_own_export(&state, &auth, export_id).await?;
+    if export.status != EvidenceExportStatus::Completed {
+        return Err(AppError::validation("Evidence export is not completed yet"));
+    }
+
+    Ok(Json(state.evidence_exporter.download_link(export.id)))
+}
+
+/// 通过限时链接下载证据包（链接签名即授权，无需登录，便于转交审计方）
+pub async fn download_evidence(
+    State(state): State<Arc<AppState>>,
+    Path(export_id): Path<Uuid>,
+    Query(query): Query<EvidenceDownloadQuery>,
+) -> Result<impl IntoResponse> {
+    if !state.evidence_exporter.verify_link(
+        export_id,
+        query.expires,
+        &query.signature,
+        chrono::Utc::now(),
+    ) {
+        return Err(AppError::Forbidden);
+    }
+
+    let export = state
+        .evidence_exporter
+        .get(export_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Evidence export not found"))?;
+    let body = state.evidence_exporter.load_archive(&export).await?;
+
+    info!(export_id = %export_id, job_id = %export.job_id, "Evidence archive downloaded");
+
+    // 链接签名即授权，下载记在发起导出的用户名下
+    view_auditor(&state)
+        .record(
+            export.requested_by,
+            AuditAction::JobEvidenceDownload,
+            &ViewTarget::new("job", Some(export.job_id)),
+            "Downloaded evidence archive",
+            serde_json::json!({ "export_id": export.id, "size_bytes": export.size_bytes }),
+        )
+        .await;
+
+    let headers = [
+        (header::CONTENT_TYPE, "application/gzip".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"evidence-{}-{}.json.gz\"", export.job_id, export.id),
+        ),
+        (
+            header::HeaderName::from_static("x-evidence-sha256"),
+            export.sha256.unwrap_or_default(),
+        ),
+        (
+            header::HeaderName::from_static("x-evidence-signature"),
+            export.signature.unwrap_or_default(),
+        ),
+    ];
+
+    Ok((StatusCode::OK, headers, body))
+}
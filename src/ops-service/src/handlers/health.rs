@@ -1,12 +1,16 @@
 //! 健康检查处理器
-//! 提供 /health、/ready 和 /system/concurrency 端点
+//! 提供 /health、/ready（/readyz）和 /system/concurrency 端点
 
 use axum::{extract::State, Json};
 use serde::Serialize;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::{concurrency, db, middleware::AppState};
+use crate::{
+    concurrency, db,
+    middleware::{maintenance::MaintenanceStatus, AppState},
+    rabbitmq::BrokerStatus,
+};
 
 /// 存活探针响应
 #[derive(Serialize)]
@@ -21,6 +25,10 @@ pub struct HealthResponse {
 pub struct ReadinessResponse {
     pub ready: bool,
     pub checks: Vec<HealthCheck>,
+    /// 只读维护模式状态（维护期间仍可处理读请求，因此不影响 ready）
+    pub maintenance: MaintenanceStatus,
+    /// RabbitMQ 连接状态
+    pub rabbitmq: BrokerStatus,
 }
 
 /// 健康检查项
@@ -95,10 +103,16 @@ pub async fn readiness_check(State(state): State<Arc<AppState>>) -> Json<Readine
         },
     });
 
-    // RabbitMQ 连通性检查
-    let rabbitmq_status = match state.rabbitmq_publisher.get().await {
+    // RabbitMQ 连通性检查（短暂断开时为 degraded，不可用超过熔断阈值后才影响 ready）
+    let connected = state.rabbitmq_publisher.get().await;
+    let broker = state.rabbitmq_publisher.monitor().status();
+    let rabbitmq_status = match connected {
         Ok(_) => ("healthy".to_string(), None),
-        Err(e) => ("unhealthy".to_string(), Some(format!("RabbitMQ: {}", e))),
+        Err(e) if broker.circuit_open => (
+            "unhealthy".to_string(),
+            Some(format!("RabbitMQ unavailable for {}s: {}", broker.unavailable_secs, e)),
+        ),
+        Err(e) => ("degraded".to_string(), Some(format!("RabbitMQ: {}", e))),
     };
     checks.push(HealthCheck {
         name: "rabbitmq".to_string(),
@@ -106,11 +120,13 @@ pub async fn readiness_check(State(state): State<Arc<AppState>>) -> Json<Readine
         message: rabbitmq_status.1,
     });
 
-    let all_healthy = checks.iter().all(|c| c.status == "healthy");
+    let ready = checks.iter().all(|c| c.status != "unhealthy");
 
     Json(ReadinessResponse {
-        ready: all_healthy,
+        ready,
         checks,
+        maintenance: state.maintenance.status(),
+        rabbitmq: broker,
     })
 }
 
@@ -0,0 +1,509 @@
+//! 入站 Webhook 处理器
+//!
+//! 管理入站 Webhook，并接收外部系统（监控告警、ITSM）的触发请求
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::{api_key::ApiKeyGenerator, middleware::AuthContext},
+    error::{AppError, Result},
+    handlers::auth::get_client_ip_str,
+    middleware::AppState,
+    models::{
+        approval::ExecuteTemplateJobRequest,
+        inbound_webhook::{
+            CreateInboundWebhookRequest, InboundWebhook, InboundWebhookDelivery,
+            InboundWebhookDeliveryQuery, InboundWebhookTokenResponse,
+            InboundWebhookTriggerResponse, UpdateInboundWebhookRequest,
+        },
+        job::Job,
+    },
+    services::{
+        audit_service::{AuditAction, AuditLogParams},
+        inbound_webhook,
+    },
+};
+
+/// 创建入站 Webhook（明文令牌仅在响应中返回一次）
+pub async fn create_inbound_webhook(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(request): Json<CreateInboundWebhookRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "execute", None, None)
+        .await?;
+
+    if request.name.trim().is_empty() {
+        return Err(AppError::validation("Webhook name is required"));
+    }
+    inbound_webhook::validate_definition(
+        &request.target_hosts,
+        &request.target_groups,
+        &request.fixed_parameters,
+        &request.parameter_rules,
+        request
+            .rate_limit_per_minute
+            .unwrap_or(inbound_webhook::DEFAULT_RATE_LIMIT_PER_MINUTE),
+    )?;
+    let template = state
+        .job_service
+        .get_job_template(request.template_id)
+        .await?;
+    if !template.is_active {
+        return Err(AppError::validation("Job template is not active"));
+    }
+
+    let token = inbound_webhook::generate_token();
+    let webhook =
+        inbound_webhook::create(&state.db, &request, &ApiKeyGenerator::hash(&token), auth.user_id)
+            .await?;
+
+    log_change(
+        &state,
+        &auth,
+        AuditAction::InboundWebhookCreate,
+        &webhook,
+        Some(definition_json(&webhook)),
+        &format!("Created inbound webhook {} for template {}", webhook.name, template.name),
+    )
+    .await;
+
+    Ok((StatusCode::CREATED, Json(InboundWebhookTokenResponse { token, webhook })))
+}
+
+/// 列出入站 Webhook
+pub async fn list_inbound_webhooks(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    Ok(Json(inbound_webhook::list(&state.db).await?))
+}
+
+/// 获取入站 Webhook
+pub async fn get_inbound_webhook(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    Ok(Json(inbound_webhook::get(&state.db, id).await?))
+}
+
+/// 更新入站 Webhook
+pub async fn update_inbound_webhook(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateInboundWebhookRequest>,
+) -> Result<impl IntoResponse> {
+    let existing = get_owned_webhook(&state, &auth, id).await?;
+
+    // 按更新后的完整定义校验
+    let target_hosts = request
+        .target_hosts
+        .as_ref()
+        .unwrap_or(&existing.target_hosts);
+    let target_groups = request
+        .target_groups
+        .as_ref()
+        .unwrap_or(&existing.target_groups);
+    let fixed = request
+        .fixed_parameters
+        .as_ref()
+        .unwrap_or(&existing.fixed_parameters);
+    let rules = request
+        .parameter_rules
+        .as_ref()
+        .unwrap_or(&existing.parameter_rules);
+    let rate_limit = request
+        .rate_limit_per_minute
+        .unwrap_or(existing.rate_limit_per_minute);
+    inbound_webhook::validate_definition(target_hosts, target_groups, fixed, rules, rate_limit)?;
+    let webhook = inbound_webhook::update(&state.db, id, &request).await?;
+
+    log_change(
+        &state,
+        &auth,
+        AuditAction::InboundWebhookUpdate,
+        &webhook,
+        Some(serde_json::json!({
+            "before": definition_json(&existing),
+            "after": definition_json(&webhook),
+        })),
+        &format!("Updated inbound webhook {}", webhook.name),
+    )
+    .await;
+
+    Ok(Json(webhook))
+}
+
+/// 轮换令牌（旧令牌立即失效）
+pub async fn rotate_inbound_webhook_token(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    get_owned_webhook(&state, &auth, id).await?;
+
+    let token = inbound_webhook::generate_token();
+    let webhook =
+        inbound_webhook::rotate_token(&state.db, id, &ApiKeyGenerator::hash(&token)).await?;
+
+    log_change(
+        &state,
+        &auth,
+        AuditAction::InboundWebhookRotateToken,
+        &webhook,
+        None,
+        &format!("Rotated token of inbound webhook {}", webhook.name),
+    )
+    .await;
+
+    Ok(Json(InboundWebhookTokenResponse { token, webhook }))
+}
+
+/// 删除入站 Webhook
+pub async fn delete_inbound_webhook(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let webhook = get_owned_webhook(&state, &auth, id).await?;
+
+    if !inbound_webhook::delete(&state.db, id).await? {
+        return Err(AppError::not_found("Inbound webhook not found"));
+    }
+
+    log_change(
+        &state,
+        &auth,
+        AuditAction::InboundWebhookDelete,
+        &webhook,
+        Some(definition_json(&webhook)),
+        &format!("Deleted inbound webhook {}", webhook.name),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 查询入站 Webhook 的投递记录
+pub async fn list_inbound_webhook_deliveries(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(query): Query<InboundWebhookDeliveryQuery>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    inbound_webhook::get(&state.db, id).await?;
+    Ok(Json(inbound_webhook::list_deliveries(&state.db, id, query.limit).await?))
+}
+
+/// 外部系统触发入站 Webhook
+///
+/// 令牌通过 `Authorization: Bearer` 或 `X-Webhook-Token` 提供；目标范围固定，
+/// 请求体只能经参数白名单提供模板参数。作业以 Webhook 创建者身份提交，
+/// 需要审批的模板与手动执行一样进入审批流程
+pub async fn trigger_inbound_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(payload): Json<Value>,
+) -> Result<impl IntoResponse> {
+    let token =
+        webhook_token(&headers).ok_or_else(|| AppError::authentication("Missing webhook token"))?;
+    let webhook = inbound_webhook::authenticate(&state.db, id, &ApiKeyGenerator::hash(&token))
+        .await?
+        .ok_or_else(|| {
+            warn!(webhook_id = %id, "Inbound webhook authentication failed");
+            AppError::authentication("Invalid webhook token")
+        })?;
+    if !webhook.is_active {
+        return Err(AppError::validation("Inbound webhook is disabled"));
+    }
+
+    let source_ip = get_client_ip_str(&headers, state.config.security.trust_proxy);
+    let user_agent = headers.get("user-agent").and_then(|v| v.to_str().ok());
+    let delivery = inbound_webhook::record_delivery(
+        &state.db,
+        &webhook,
+        &payload,
+        Some(&source_ip),
+        user_agent,
+    )
+    .await?;
+
+    if delivery.status == inbound_webhook::STATUS_RATE_LIMITED {
+        warn!(webhook_id = %webhook.id, "Inbound webhook rate limit exceeded");
+        log_trigger(&state, &webhook, &delivery, None, None, Some("Rate limit exceeded")).await;
+        return Err(AppError::RateLimitExceeded);
+    }
+
+    let parameters = match inbound_webhook::extract_parameters(
+        &payload,
+        &webhook.fixed_parameters,
+        &webhook.parameter_rules,
+    ) {
+        Ok(parameters) => parameters,
+        Err(reason) => {
+            finish(
+                &state,
+                &webhook,
+                &delivery,
+                inbound_webhook::STATUS_REJECTED,
+                None,
+                None,
+                Some(&reason),
+            )
+            .await;
+            return Err(AppError::validation(&reason));
+        }
+    };
+
+    match submit_job(&state, &webhook, &parameters).await {
+        Ok(job) => {
+            info!(
+                webhook_id = %webhook.id,
+                delivery_id = %delivery.id,
+                job_id = %job.id,
+                "Inbound webhook triggered job"
+            );
+            finish(
+                &state,
+                &webhook,
+                &delivery,
+                inbound_webhook::STATUS_TRIGGERED,
+                Some(&parameters),
+                Some(job.id),
+                None,
+            )
+            .await;
+            Ok((
+                StatusCode::ACCEPTED,
+                Json(InboundWebhookTriggerResponse {
+                    delivery_id: delivery.id,
+                    status: inbound_webhook::STATUS_TRIGGERED.to_string(),
+                    job_id: Some(job.id),
+                }),
+            ))
+        }
+        Err(e) => {
+            warn!(webhook_id = %webhook.id, error = %e, "Inbound webhook failed to create job");
+            finish(
+                &state,
+                &webhook,
+                &delivery,
+                inbound_webhook::STATUS_FAILED,
+                Some(&parameters),
+                None,
+                Some(&e.to_string()),
+            )
+            .await;
+            Err(e)
+        }
+    }
+}
+
+/// 以 Webhook 创建者身份基于绑定模板创建作业（创建者须仍具有执行权限）
+async fn submit_job(
+    state: &AppState,
+    webhook: &InboundWebhook,
+    parameters: &Map<String, Value>,
+) -> Result<Job> {
+    state
+        .permission_service
+        .require_permission(webhook.created_by, "job", "execute", None, None)
+        .await?;
+
+    let request = ExecuteTemplateJobRequest {
+        template_id: webhook.template_id,
+        parameters: Value::Object(parameters.clone()),
+        target_hosts: webhook.target_hosts.0.clone(),
+        target_groups: webhook.target_groups.0.clone(),
+        tags: Vec::new(),
+        singleton_key: None,
+        singleton_policy: Default::default(),
+        on_success_job_template: None,
+        on_failure_job_template: None,
+        drift_check: false,
+        verbose_trace: false,
+    };
+    state
+        .job_service
+        .create_job_from_template(request, webhook.created_by)
+        .await
+}
+
+/// 从请求头获取 Webhook 令牌
+fn webhook_token(headers: &HeaderMap) -> Option<String> {
+    if let Some(token) = headers.get("x-webhook-token").and_then(|v| v.to_str().ok()) {
+        return Some(token.trim().to_string());
+    }
+    let value = headers.get("authorization")?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| token.trim().to_string())
+}
+
+/// 获取 Webhook 并确认当前用户可修改（仅创建者或管理员，避免他人以创建者身份触发作业）
+async fn get_owned_webhook(
+    state: &AppState,
+    auth: &AuthContext,
+    id: Uuid,
+) -> Result<InboundWebhook> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "execute", None, None)
+        .await?;
+
+    let webhook = inbound_webhook::get(&state.db, id).await?;
+    if webhook.created_by != auth.user_id
+        && !state.permission_service.is_admin(auth.user_id).await?
+    {
+        return Err(AppError::Forbidden);
+    }
+    Ok(webhook)
+}
+
+/// Webhook 定义的审计快照（不含令牌）
+fn definition_json(webhook: &InboundWebhook) -> Value {
+    serde_json::json!({
+        "template_id": webhook.template_id,
+        "target_hosts": webhook.target_hosts,
+        "target_groups": webhook.target_groups,
+        "fixed_parameters": webhook.fixed_parameters,
+        "parameter_rules": webhook.parameter_rules,
+        "rate_limit_per_minute": webhook.rate_limit_per_minute,
+        "is_active": webhook.is_active,
+    })
+}
+
+/// 记录管理操作审计
+async fn log_change(
+    state: &AppState,
+    auth: &AuthContext,
+    action: AuditAction,
+    webhook: &InboundWebhook,
+    changes: Option<Value>,
+    summary: &str,
+) {
+    let _ = state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: auth.user_id,
+            subject_type: "user",
+            subject_name: Some(&auth.username),
+            action: action.as_str(),
+            resource_type: "inbound_webhook",
+            resource_id: Some(webhook.id),
+            resource_name: Some(&webhook.name),
+            changes,
+            changes_summary: Some(summary),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        })
+        .await;
+}
+
+/// 记录投递结果并写入触发审计
+async fn finish(
+    state: &AppState,
+    webhook: &InboundWebhook,
+    delivery: &InboundWebhookDelivery,
+    status: &str,
+    parameters: Option<&Map<String, Value>>,
+    job_id: Option<Uuid>,
+    error: Option<&str>,
+) {
+    let _ =
+        inbound_webhook::finish_delivery(&state.db, delivery, status, error, parameters, job_id)
+            .await;
+    log_trigger(state, webhook, delivery, parameters, job_id, error).await;
+}
+
+/// 触发审计：记录完整请求体、提取的参数与处理结果
+async fn log_trigger(
+    state: &AppState,
+    webhook: &InboundWebhook,
+    delivery: &InboundWebhookDelivery,
+    parameters: Option<&Map<String, Value>>,
+    job_id: Option<Uuid>,
+    error: Option<&str>,
+) {
+    let _ = state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: webhook.id,
+            subject_type: "webhook",
+            subject_name: Some(&webhook.name),
+            action: AuditAction::InboundWebhookTrigger.as_str(),
+            resource_type: "job_template",
+            resource_id: Some(webhook.template_id),
+            resource_name: None,
+            changes: Some(serde_json::json!({
+                "delivery_id": delivery.id,
+                "payload": delivery.payload,
+                "parameters": parameters,
+                "job_id": job_id,
+                "submitted_as": webhook.created_by,
+            })),
+            changes_summary: Some(&format!("Inbound webhook {} triggered", webhook.name)),
+            source_ip: delivery.source_ip.as_deref(),
+            user_agent: delivery.user_agent.as_deref(),
+            trace_id: None,
+            result: if error.is_some() {
+                "failure"
+            } else {
+                "success"
+            },
+            error_message: error,
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_token_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(webhook_token(&headers).is_none());
+
+        headers.insert("authorization", "Bearer ops_whk_abc".parse().unwrap());
+        assert_eq!(webhook_token(&headers).as_deref(), Some("ops_whk_abc"));
+
+        headers.insert("authorization", "Basic dXNlcjpwYXNz".parse().unwrap());
+        assert!(webhook_token(&headers).is_none());
+
+        headers.insert("x-webhook-token", "ops_whk_def".parse().unwrap());
+        assert_eq!(webhook_token(&headers).as_deref(), Some("ops_whk_def"));
+    }
+}
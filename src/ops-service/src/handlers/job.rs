@@ -3,7 +3,7 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -11,22 +11,35 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
-    auth::middleware::AuthContext, error::Result, middleware::AppState, models::job::*,
-    services::audit_service::AuditAction,
+    auth::middleware::AuthContext,
+    error::Result,
+    handlers::{artifact::check_artifact_deployment, audit::view_auditor},
+    middleware::{payload_limits::PayloadLimited, AppState},
+    models::job::*,
+    services::{audit_service::AuditAction, exit_code_rules, view_audit::ViewTarget},
 };
 
 /// 创建命令作业（带权限检查和作用域验证）
 pub async fn create_command_job(
     State(state): State<Arc<AppState>>,
     auth_context: AuthContext,
-    Json(request): Json<CreateCommandJobRequest>,
+    Json(mut request): Json<CreateCommandJobRequest>,
 ) -> Result<impl IntoResponse> {
+    // 检查请求字段限制
+    request.enforce_limits(&state.config.payload_limits)?;
+
     // 检查执行权限
     state
         .permission_service
         .require_permission(auth_context.user_id, "job", "execute", None, None)
         .await?;
 
+    // 展开作业结果派生的目标集合，随目标主机一起做作用域校验
+    state
+        .job_service
+        .expand_target_set(request.target_set_id.take(), &mut request.target_hosts)
+        .await?;
+
     // 验证用户是否有权限在目标主机/分组上执行作业
     validate_target_hosts_access(
         &state,
@@ -36,6 +49,24 @@ pub async fn create_command_job(
     )
     .await?;
 
+    // 生产环境作业需二次验证
+    require_production_step_up(
+        &state,
+        &auth_context,
+        &request.target_hosts,
+        &request.target_groups,
+    )
+    .await?;
+
+    // 部署产物时校验受限环境的晋级要求
+    check_artifact_deployment(
+        &state,
+        request.artifact_id,
+        &request.target_hosts,
+        &request.target_groups,
+    )
+    .await?;
+
     let host_count = request.target_hosts.len();
     let job = state
         .job_service
@@ -62,14 +93,23 @@ pub async fn create_command_job(
 pub async fn create_script_job(
     State(state): State<Arc<AppState>>,
     auth_context: AuthContext,
-    Json(request): Json<CreateScriptJobRequest>,
+    Json(mut request): Json<CreateScriptJobRequest>,
 ) -> Result<impl IntoResponse> {
+    // 检查请求字段限制
+    request.enforce_limits(&state.config.payload_limits)?;
+
     // 检查执行权限
     state
         .permission_service
         .require_permission(auth_context.user_id, "job", "execute", None, None)
         .await?;
 
+    // 展开作业结果派生的目标集合，随目标主机一起做作用域校验
+    state
+        .job_service
+        .expand_target_set(request.target_set_id.take(), &mut request.target_hosts)
+        .await?;
+
     // 验证用户是否有权限在目标主机/分组上执行作业
     validate_target_hosts_access(
         &state,
@@ -79,6 +119,24 @@ pub async fn create_script_job(
     )
     .await?;
 
+    // 生产环境作业需二次验证
+    require_production_step_up(
+        &state,
+        &auth_context,
+        &request.target_hosts,
+        &request.target_groups,
+    )
+    .await?;
+
+    // 部署产物时校验受限环境的晋级要求
+    check_artifact_deployment(
+        &state,
+        request.artifact_id,
+        &request.target_hosts,
+        &request.target_groups,
+    )
+    .await?;
+
     let host_count = request.target_hosts.len();
     let job = state
         .job_service
@@ -101,6 +159,129 @@ pub async fn create_script_job(
     Ok((StatusCode::CREATED, Json(job)))
 }
 
+/// 创建文件分发作业（带权限检查和作用域验证）
+pub async fn create_file_job(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(mut request): Json<CreateFileJobRequest>,
+) -> Result<impl IntoResponse> {
+    // 检查请求字段限制
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    // 检查执行权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "execute", None, None)
+        .await?;
+
+    // 展开作业结果派生的目标集合，随目标主机一起做作用域校验
+    state
+        .job_service
+        .expand_target_set(request.target_set_id.take(), &mut request.target_hosts)
+        .await?;
+
+    // 验证用户是否有权限在目标主机/分组上执行作业
+    validate_target_hosts_access(
+        &state,
+        auth_context.user_id,
+        &request.target_hosts,
+        &request.target_groups,
+    )
+    .await?;
+
+    // 生产环境作业需二次验证
+    require_production_step_up(
+        &state,
+        &auth_context,
+        &request.target_hosts,
+        &request.target_groups,
+    )
+    .await?;
+
+    let host_count = request.target_hosts.len();
+    let path = request.file.path.clone();
+    let job = state
+        .job_service
+        .create_file_job(request, auth_context.user_id)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::JobCreate,
+            Some("job"),
+            Some(job.id),
+            Some(&format!("Created file job for {} on {} hosts", path, host_count)),
+            None,
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(job)))
+}
+
+/// 创建软件包清单采集作业（带权限检查和作用域验证）
+pub async fn create_inventory_job(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(mut request): Json<CreateInventoryJobRequest>,
+) -> Result<impl IntoResponse> {
+    // 检查请求字段限制
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    // 检查执行权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "execute", None, None)
+        .await?;
+
+    // 展开作业结果派生的目标集合，随目标主机一起做作用域校验
+    state
+        .job_service
+        .expand_target_set(request.target_set_id.take(), &mut request.target_hosts)
+        .await?;
+
+    // 验证用户是否有权限在目标主机/分组上执行作业
+    validate_target_hosts_access(
+        &state,
+        auth_context.user_id,
+        &request.target_hosts,
+        &request.target_groups,
+    )
+    .await?;
+
+    // 生产环境作业需二次验证
+    require_production_step_up(
+        &state,
+        &auth_context,
+        &request.target_hosts,
+        &request.target_groups,
+    )
+    .await?;
+
+    let host_count = request.target_hosts.len();
+    let job = state
+        .job_service
+        .create_inventory_job(request, auth_context.user_id)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::JobCreate,
+            Some("job"),
+            Some(job.id),
+            Some(&format!("Created inventory job on {} hosts", host_count)),
+            None,
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(job)))
+}
+
 /// 查询作业详情（带作用域检查和反枚举）
 pub async fn get_job(
     State(state): State<Arc<AppState>>,
@@ -130,6 +311,80 @@ pub async fn get_job(
     Ok(Json(job))
 }
 
+/// 轮询作业事件（SSE 被代理中断时的降级方案）
+/// 返回回放缓冲区中序号大于 since_seq 的事件，事件格式与 SSE 推送一致
+pub async fn get_job_events(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    Query(query): Query<JobEventsQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    let job = state
+        .job_service
+        .get_job(job_id)
+        .await
+        .map_err(|_| crate::error::AppError::not_found("Job not found"))?;
+    if !check_job_access(&state, auth_context.user_id, &job).await? {
+        return Err(crate::error::AppError::not_found("Job not found"));
+    }
+
+    let delta = state.event_bus.job_events_since(job_id, query.since_seq);
+    view_auditor(&state)
+        .record(
+            auth_context.user_id,
+            AuditAction::JobOutputView,
+            &ViewTarget::new("job", Some(job_id)).verbose(),
+            "Polled job events",
+            serde_json::json!({ "since_seq": query.since_seq }),
+        )
+        .await;
+    Ok(Json(delta.to_json()))
+}
+
+/// 查询作业的详细追踪记录（创建时开启 verbose_trace 的作业）
+/// 按记录 ID 递增返回 after_id 之后的记录，可按任务过滤
+pub async fn get_job_trace(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    Query(query): Query<JobTraceQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    let job = state
+        .job_service
+        .get_job(job_id)
+        .await
+        .map_err(|_| crate::error::AppError::not_found("Job not found"))?;
+    if !check_job_access(&state, auth_context.user_id, &job).await? {
+        return Err(crate::error::AppError::not_found("Job not found"));
+    }
+
+    let entries = state.job_service.get_job_trace(job_id, &query).await?;
+    view_auditor(&state)
+        .record(
+            auth_context.user_id,
+            AuditAction::JobOutputView,
+            &ViewTarget::new("job", Some(job_id)).verbose(),
+            "Viewed job trace",
+            serde_json::json!({ "after_id": query.after_id, "task_id": query.task_id }),
+        )
+        .await;
+    Ok(Json(serde_json::json!({
+        "job_id": job_id,
+        "verbose_trace": job.verbose_trace,
+        "entries": entries,
+    })))
+}
+
 /// 查询作业列表（带作用域过滤）
 pub async fn list_jobs(
     State(state): State<Arc<AppState>>,
@@ -231,37 +486,36 @@ pub async fn get_job_tasks(
         // 用户可以查看任务列表但不能看到详细输出
         // 返回被脱敏的任务列表（只有摘要，无完整输出）
         let tasks = state.job_service.get_job_tasks_summary(job_id).await?;
-        state
-            .audit_service
-            .log_action_simple(
+        view_auditor(&state)
+            .record(
                 auth_context.user_id,
-                crate::services::audit_service::AuditAction::JobOutputView,
-                Some("job"),
-                Some(job_id),
-                Some(&format!(
-                    "Viewed task summary for {} tasks (output detail redacted)",
-                    tasks.len()
-                )),
-                None,
+                AuditAction::JobOutputView,
+                &ViewTarget::new("job", Some(job_id)),
+                &format!("Viewed task summary for {} tasks (output detail redacted)", tasks.len()),
+                serde_json::json!({
+                    "output_detail": false,
+                    "task_ids": tasks.iter().map(|t| t.id).collect::<Vec<_>>(),
+                }),
             )
-            .await?;
+            .await;
         return Ok(Json(crate::models::job::TaskListResponse::Summary(tasks)));
     }
 
     let tasks = state.job_service.get_job_tasks(job_id).await?;
 
     // 记录输出查看审计
-    state
-        .audit_service
-        .log_action_simple(
+    view_auditor(&state)
+        .record(
             auth_context.user_id,
-            crate::services::audit_service::AuditAction::JobOutputView,
-            Some("job"),
-            Some(job_id),
-            Some(&format!("Viewed output detail for {} tasks", tasks.len())),
-            None,
+            AuditAction::JobOutputView,
+            &ViewTarget::new("job", Some(job_id)),
+            &format!("Viewed output detail for {} tasks", tasks.len()),
+            serde_json::json!({
+                "output_detail": true,
+                "task_ids": tasks.iter().map(|t| t.task.id).collect::<Vec<_>>(),
+            }),
         )
-        .await?;
+        .await;
 
     Ok(Json(crate::models::job::TaskListResponse::Full(tasks)))
 }
@@ -273,6 +527,9 @@ pub async fn cancel_job(
     auth_context: AuthContext,
     Json(request): Json<CancelJobRequest>,
 ) -> Result<impl IntoResponse> {
+    // 检查请求字段限制
+    request.enforce_limits(&state.config.payload_limits)?;
+
     // 检查基本的作业执行权限
     state
         .permission_service
@@ -315,13 +572,16 @@ pub async fn cancel_job(
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// 重试作业（带作用域检查和反枚举）
-pub async fn retry_job(
+/// 处理因主机不可达暂停派发的作业：继续派发或中止（带作用域检查和反枚举）
+pub async fn resolve_dispatch_pause(
     State(state): State<Arc<AppState>>,
     Path(job_id): Path<Uuid>,
     auth_context: AuthContext,
-    Json(request): Json<RetryJobRequest>,
+    Json(request): Json<ResolveDispatchPauseRequest>,
 ) -> Result<impl IntoResponse> {
+    // 检查请求字段限制
+    request.enforce_limits(&state.config.payload_limits)?;
+
     // 检查基本的作业执行权限
     state
         .permission_service
@@ -342,37 +602,55 @@ pub async fn retry_job(
         return Err(crate::error::AppError::not_found("Job not found"));
     }
 
-    let job = state
+    let reason = request.reason.clone();
+    state
         .job_service
-        .retry_job(job_id, request, auth_context.user_id)
+        .resolve_dispatch_pause(job_id, request.action, auth_context.user_id, reason.clone())
         .await?;
 
     // 审计日志
+    let (action, summary) = match request.action {
+        DispatchPauseAction::Continue => (
+            AuditAction::JobDispatchResume,
+            "Continued job dispatch after unreachable hosts pause".to_string(),
+        ),
+        DispatchPauseAction::Abort => (
+            AuditAction::JobCancel,
+            format!(
+                "Aborted job after unreachable hosts pause, reason: {}",
+                reason.unwrap_or_default()
+            ),
+        ),
+    };
     state
         .audit_service
         .log_action_simple(
             auth_context.user_id,
-            AuditAction::JobRetry,
+            action,
             Some("job"),
-            Some(job.id),
-            Some(&format!("Retried job, new job ID: {}", job.id)),
+            Some(job_id),
+            Some(&summary),
             None,
         )
         .await?;
 
-    Ok(Json(job))
+    Ok(StatusCode::NO_CONTENT)
 }
 
-/// 获取作业统计（带权限检查和反枚举）
-pub async fn get_job_statistics(
+/// 重试作业（带作用域检查和反枚举）
+pub async fn retry_job(
     State(state): State<Arc<AppState>>,
     Path(job_id): Path<Uuid>,
     auth_context: AuthContext,
+    Json(request): Json<RetryJobRequest>,
 ) -> Result<impl IntoResponse> {
-    // 检查基本的作业读取权限
+    // 检查请求字段限制
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    // 检查基本的作业执行权限
     state
         .permission_service
-        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .require_permission(auth_context.user_id, "job", "execute", None, None)
         .await?;
 
     // 尝试获取作业，如果不存在则返回 404（反枚举）
@@ -383,36 +661,568 @@ pub async fn get_job_statistics(
         }
     };
 
-    // 检查用户是否有权限查看该作业（作用域检查）
-    let can_view = check_job_access(&state, auth_context.user_id, &job).await?;
-    if !can_view {
+    // 检查用户是否有权限操作该作业（作用域检查 + 反枚举）
+    let can_access = check_job_access(&state, auth_context.user_id, &job).await?;
+    if !can_access {
         return Err(crate::error::AppError::not_found("Job not found"));
     }
 
-    let stats = state.job_service.get_job_statistics(job_id).await?;
-    Ok(Json(stats))
-}
+    let job = state
+        .job_service
+        .retry_job(job_id, request, auth_context.user_id)
+        .await?;
 
-// ==================== 权限检查辅助函数 ====================
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::JobRetry,
+            Some("job"),
+            Some(job.id),
+            Some(&format!("Retried job, new job ID: {}", job.id)),
+            None,
+        )
+        .await?;
 
-/// 检查用户是否有权限访问指定作业
-/// 返回 false 时应返回 404 而不是 403（反枚举）
-async fn check_job_access(
-    state: &Arc<AppState>,
-    user_id: Uuid,
-    job: &crate::models::job::Job,
-) -> std::result::Result<bool, crate::error::AppError> {
-    // 管理员可以访问所有作业
-    let is_admin = state
+    Ok(Json(job))
+}
+
+/// 检查批量任务操作的权限：作业执行权限 + 作用域检查（反枚举）
+async fn authorize_task_action(state: &Arc<AppState>, user_id: Uuid, job_id: Uuid) -> Result<()> {
+    state
         .permission_service
-        .is_admin(user_id)
+        .require_permission(user_id, "job", "execute", None, None)
+        .await?;
+
+    let job = state
+        .job_service
+        .get_job(job_id)
         .await
-        .unwrap_or(false);
-    if is_admin {
-        return Ok(true);
+        .map_err(|_| crate::error::AppError::not_found("Job not found"))?;
+    if !check_job_access(state, user_id, &job).await? {
+        return Err(crate::error::AppError::not_found("Job not found"));
     }
+    Ok(())
+}
 
-    // 作业创建者可以访问自己的作业
+/// 取消作业中选中的执行中任务
+pub async fn cancel_tasks(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth_context: AuthContext,
+    Json(request): Json<TaskSelectionRequest>,
+) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    authorize_task_action(&state, auth_context.user_id, job_id).await?;
+    let response = state
+        .job_service
+        .cancel_tasks(job_id, request, auth_context.user_id)
+        .await?;
+    Ok(Json(response))
+}
+
+/// 跳过作业中选中的待执行任务
+pub async fn skip_tasks(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth_context: AuthContext,
+    Json(request): Json<TaskSelectionRequest>,
+) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    authorize_task_action(&state, auth_context.user_id, job_id).await?;
+    let response = state
+        .job_service
+        .skip_tasks(job_id, request, auth_context.user_id)
+        .await?;
+    Ok(Json(response))
+}
+
+/// 仅重新执行作业中选中的任务
+pub async fn rerun_tasks(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth_context: AuthContext,
+    Json(request): Json<TaskSelectionRequest>,
+) -> Result<impl IntoResponse> {
+    request.enforce_limits(&state.config.payload_limits)?;
+
+    authorize_task_action(&state, auth_context.user_id, job_id).await?;
+    let response = state
+        .job_service
+        .rerun_tasks(job_id, request, auth_context.user_id)
+        .await?;
+    Ok(Json(response))
+}
+
+/// 获取作业统计（带权限检查和反枚举）
+pub async fn get_job_statistics(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    // 检查基本的作业读取权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    // 尝试获取作业，如果不存在则返回 404（反枚举）
+    let job = match state.job_service.get_job(job_id).await {
+        Ok(j) => j,
+        Err(_) => {
+            return Err(crate::error::AppError::not_found("Job not found"));
+        }
+    };
+
+    // 检查用户是否有权限查看该作业（作用域检查）
+    let can_view = check_job_access(&state, auth_context.user_id, &job).await?;
+    if !can_view {
+        return Err(crate::error::AppError::not_found("Job not found"));
+    }
+
+    let stats = state.job_service.get_job_statistics(job_id).await?;
+    Ok(Json(stats))
+}
+
+/// 获取作业所在的作业链（根作业及全部后续作业，用于链路可视化）
+pub async fn get_job_chain(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    // 尝试获取作业，如果不存在则返回 404（反枚举）
+    let job = match state.job_service.get_job(job_id).await {
+        Ok(j) => j,
+        Err(_) => {
+            return Err(crate::error::AppError::not_found("Job not found"));
+        }
+    };
+
+    let can_view = check_job_access(&state, auth_context.user_id, &job).await?;
+    if !can_view {
+        return Err(crate::error::AppError::not_found("Job not found"));
+    }
+
+    let chain = state.job_service.get_job_chain(job_id).await?;
+    Ok(Json(chain))
+}
+
+// ==================== 作业结果分组 ====================
+
+/// 查询作业结果派生的主机分组（成功、告警、失败、退出码非 0，可选按输出正则划分）
+pub async fn get_result_host_groups(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    Query(query): Query<ResultHostGroupsQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    // 按输出划分需要读取完整输出
+    if query.output_pattern.is_some() {
+        state
+            .permission_service
+            .require_permission(auth_context.user_id, "job", "output_detail", None, None)
+            .await?;
+    }
+
+    let job = match state.job_service.get_job(job_id).await {
+        Ok(j) => j,
+        Err(_) => {
+            return Err(crate::error::AppError::not_found("Job not found"));
+        }
+    };
+
+    let can_view = check_job_access(&state, auth_context.user_id, &job).await?;
+    if !can_view {
+        return Err(crate::error::AppError::not_found("Job not found"));
+    }
+
+    let groups = state
+        .job_service
+        .get_result_host_groups(job_id, query.output_pattern.as_deref())
+        .await?;
+    Ok(Json(groups))
+}
+
+/// 将作业结果分组保存为目标集合
+pub async fn create_target_set(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth_context: AuthContext,
+    Json(request): Json<CreateJobTargetSetRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "execute", None, None)
+        .await?;
+
+    if request.filter.pattern().is_some() {
+        state
+            .permission_service
+            .require_permission(auth_context.user_id, "job", "output_detail", None, None)
+            .await?;
+    }
+
+    let job = match state.job_service.get_job(job_id).await {
+        Ok(j) => j,
+        Err(_) => {
+            return Err(crate::error::AppError::not_found("Job not found"));
+        }
+    };
+
+    let can_view = check_job_access(&state, auth_context.user_id, &job).await?;
+    if !can_view {
+        return Err(crate::error::AppError::not_found("Job not found"));
+    }
+
+    let target_set = state
+        .job_service
+        .create_target_set(job_id, request, auth_context.user_id)
+        .await?;
+
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::JobTargetSetCreate,
+            Some("job"),
+            Some(job_id),
+            Some(&format!(
+                "Created target set {} with {} hosts",
+                target_set.id,
+                target_set.host_ids.len()
+            )),
+            None,
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(target_set)))
+}
+
+/// 查询目标集合（需能查看源作业）
+pub async fn get_target_set(
+    State(state): State<Arc<AppState>>,
+    Path(target_set_id): Path<Uuid>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    let target_set = state.job_service.get_target_set(target_set_id).await?;
+    let job = match state.job_service.get_job(target_set.source_job_id).await {
+        Ok(j) => j,
+        Err(_) => {
+            return Err(crate::error::AppError::not_found("Target set not found"));
+        }
+    };
+
+    let can_view = check_job_access(&state, auth_context.user_id, &job).await?;
+    if !can_view {
+        return Err(crate::error::AppError::not_found("Target set not found"));
+    }
+
+    Ok(Json(target_set))
+}
+
+// ==================== 基线输出与偏离报告 ====================
+
+/// 创建或替换基线输出
+pub async fn set_output_baseline(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(request): Json<SetOutputBaselineRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "execute", None, None)
+        .await?;
+
+    let baseline = state
+        .job_service
+        .set_output_baseline(request, auth_context.user_id)
+        .await?;
+    Ok(Json(baseline))
+}
+
+/// 查询基线输出（内容即预期输出，需要查看完整输出的权限）
+pub async fn list_output_baselines(
+    State(state): State<Arc<AppState>>,
+    Query(filters): Query<OutputBaselineFilters>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "output_detail", None, None)
+        .await?;
+
+    let baselines = state.job_service.list_output_baselines(filters).await?;
+    Ok(Json(baselines))
+}
+
+/// 删除基线输出
+pub async fn delete_output_baseline(
+    State(state): State<Arc<AppState>>,
+    Path(baseline_id): Path<Uuid>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "execute", None, None)
+        .await?;
+
+    state
+        .job_service
+        .delete_output_baseline(baseline_id, auth_context.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 查询作业的偏离汇总报告（差异行来自输出，需要查看完整输出的权限）
+pub async fn get_drift_report(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "output_detail", None, None)
+        .await?;
+
+    let job = match state.job_service.get_job(job_id).await {
+        Ok(j) => j,
+        Err(_) => {
+            return Err(crate::error::AppError::not_found("Job not found"));
+        }
+    };
+
+    let can_view = check_job_access(&state, auth_context.user_id, &job).await?;
+    if !can_view {
+        return Err(crate::error::AppError::not_found("Job not found"));
+    }
+
+    let report = state.job_service.get_drift_report(job_id).await?;
+    Ok(Json(report))
+}
+
+// ==================== 退出码分类 ====================
+
+/// 查询内置的退出码分类表（模板/作业通过 exit_code_rules.tool 引用）
+pub async fn list_exit_code_tables(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    Ok(Json(exit_code_rules::default_tables()))
+}
+
+// ==================== 标签管理与报表 ====================
+
+/// 查询标签命名空间列表
+pub async fn list_tag_namespaces(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    let namespaces = state.job_service.list_tag_namespaces().await?;
+    Ok(Json(namespaces))
+}
+
+/// 创建标签命名空间（仅管理员）
+pub async fn create_tag_namespace(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(request): Json<CreateJobTagNamespaceRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, auth_context.user_id).await?;
+
+    let namespace = state
+        .job_service
+        .create_tag_namespace(request, auth_context.user_id)
+        .await?;
+    Ok((StatusCode::CREATED, Json(namespace)))
+}
+
+/// 更新标签命名空间（仅管理员）
+pub async fn update_tag_namespace(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    auth_context: AuthContext,
+    Json(request): Json<UpdateJobTagNamespaceRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, auth_context.user_id).await?;
+
+    let namespace = state
+        .job_service
+        .update_tag_namespace(&name, request, auth_context.user_id)
+        .await?;
+    Ok(Json(namespace))
+}
+
+/// 删除标签命名空间（仅管理员）
+pub async fn delete_tag_namespace(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, auth_context.user_id).await?;
+
+    state
+        .job_service
+        .delete_tag_namespace(&name, auth_context.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 查询已登记的标签列表
+pub async fn list_job_tags(
+    State(state): State<Arc<AppState>>,
+    Query(filters): Query<ManagedJobTagFilters>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "job", "read", None, None)
+        .await?;
+
+    let tags = state.job_service.list_managed_tags(filters).await?;
+    Ok(Json(tags))
+}
+
+/// 登记标签（仅管理员）
+pub async fn create_job_tag(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(request): Json<CreateManagedJobTagRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, auth_context.user_id).await?;
+
+    let tag = state
+        .job_service
+        .create_managed_tag(request, auth_context.user_id)
+        .await?;
+    Ok((StatusCode::CREATED, Json(tag)))
+}
+
+/// 更新标签（仅管理员）
+pub async fn update_job_tag(
+    State(state): State<Arc<AppState>>,
+    Path(tag_id): Path<Uuid>,
+    auth_context: AuthContext,
+    Json(request): Json<UpdateManagedJobTagRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, auth_context.user_id).await?;
+
+    let tag = state
+        .job_service
+        .update_managed_tag(tag_id, request, auth_context.user_id)
+        .await?;
+    Ok(Json(tag))
+}
+
+/// 删除标签（仅管理员）
+pub async fn delete_job_tag(
+    State(state): State<Arc<AppState>>,
+    Path(tag_id): Path<Uuid>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, auth_context.user_id).await?;
+
+    state
+        .job_service
+        .delete_managed_tag(tag_id, auth_context.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 按标签聚合的作业报表
+///
+/// 报表覆盖所有作业，要求管理员或 job:read_all 权限
+pub async fn get_job_tag_report(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<JobTagReportQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth_context.user_id)
+        .await
+        .unwrap_or(false);
+    let can_read_all = state
+        .permission_service
+        .check_permission(auth_context.user_id, "job", "read_all", None, None)
+        .await
+        .unwrap_or(false);
+    if !is_admin && !can_read_all {
+        return Err(crate::error::AppError::Forbidden);
+    }
+
+    let format = query.format;
+    let report = state.job_service.job_tag_report(query).await?;
+    view_auditor(&state)
+        .record(
+            auth_context.user_id,
+            AuditAction::JobOutputView,
+            &ViewTarget::new("job_tag_report", None).verbose(),
+            "Viewed job tag report",
+            serde_json::Value::Null,
+        )
+        .await;
+
+    Ok(match format {
+        ReportFormat::Json => Json(report).into_response(),
+        ReportFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, "attachment; filename=\"job-tag-report.csv\""),
+            ],
+            JobTagReportRow::to_csv(&report, crate::i18n::current_locale()),
+        )
+            .into_response(),
+    })
+}
+
+// ==================== 权限检查辅助函数 ====================
+
+/// 检查用户是否有权限访问指定作业
+/// 返回 false 时应返回 404 而不是 403（反枚举）
+pub(crate) async fn check_job_access(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    job: &crate::models::job::Job,
+) -> std::result::Result<bool, crate::error::AppError> {
+    // 管理员可以访问所有作业
+    let is_admin = state
+        .permission_service
+        .is_admin(user_id)
+        .await
+        .unwrap_or(false);
+    if is_admin {
+        return Ok(true);
+    }
+
+    // 作业创建者可以访问自己的作业
     if job.created_by == user_id {
         return Ok(true);
     }
@@ -494,8 +1304,44 @@ async fn check_job_access(
     Ok(false)
 }
 
+/// 目标包含生产环境主机时要求二次验证
+async fn require_production_step_up(
+    state: &Arc<AppState>,
+    auth_context: &AuthContext,
+    target_hosts: &[Uuid],
+    target_groups: &[Uuid],
+) -> Result<()> {
+    let targets_production: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM assets_hosts h
+            LEFT JOIN assets_groups g ON g.id = h.group_id
+            WHERE (h.id = ANY($1) OR h.group_id = ANY($2))
+                AND (LOWER(h.environment) = 'production' OR g.is_production)
+        )
+        "#,
+    )
+    .bind(target_hosts)
+    .bind(target_groups)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Failed to check production targets");
+        crate::error::AppError::database("Failed to check production targets")
+    })?;
+
+    if targets_production {
+        state
+            .auth_service
+            .two_factor()
+            .require_step_up(auth_context)
+            .await?;
+    }
+    Ok(())
+}
+
 /// 验证用户是否有权限在目标主机/分组上执行作业
-async fn validate_target_hosts_access(
+pub(crate) async fn validate_target_hosts_access(
     state: &Arc<AppState>,
     user_id: Uuid,
     target_hosts: &[Uuid],
@@ -591,3 +1437,19 @@ async fn validate_target_hosts_access(
 
     Ok(())
 }
+
+/// 要求管理员权限
+async fn require_admin(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+) -> std::result::Result<(), crate::error::AppError> {
+    let is_admin = state
+        .permission_service
+        .is_admin(user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin {
+        return Err(crate::error::AppError::Forbidden);
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! 平台压测处理器
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::{AppError, Result},
+    middleware::AppState,
+    models::load_test::StartLoadTestRequest,
+    services::{audit_service::AuditAction, LoadTester},
+};
+
+fn load_tester(state: &Arc<AppState>) -> Arc<LoadTester> {
+    Arc::new(LoadTester::new(
+        state.db.clone(),
+        state.audit_service.clone(),
+        state.event_bus.clone(),
+        &state.config,
+    ))
+}
+
+async fn require_admin(state: &Arc<AppState>, auth: &AuthContext) -> Result<()> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
+
+/// 发起压测（后台执行，仅管理员）
+pub async fn start_load_test(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(request): Json<StartLoadTestRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &auth).await?;
+
+    let summary = format!(
+        "Started load test: {} jobs x {} hosts, {} subscribers",
+        request.jobs, request.hosts_per_job, request.subscribers
+    );
+    let run = load_tester(&state).start(request, auth.user_id).await?;
+
+    state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::SystemLoadTestStart,
+            Some("load_test"),
+            Some(run.id),
+            Some(&summary),
+            None,
+        )
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+/// 列出压测记录
+pub async fn list_load_tests(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &auth).await?;
+
+    let runs = load_tester(&state).list(50).await?;
+
+    Ok(Json(runs))
+}
+
+/// 获取压测记录（含测量结果）
+pub async fn get_load_test(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &auth).await?;
+
+    let run = load_tester(&state)
+        .get(id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Load test run not found"))?;
+
+    Ok(Json(run))
+}
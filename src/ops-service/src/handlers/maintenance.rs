@@ -0,0 +1,74 @@
+//! 只读维护模式处理器
+
+use axum::{extract::State, Json};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::{AppError, Result},
+    middleware::{maintenance::MaintenanceStatus, AppState},
+    services::audit_service::AuditAction,
+};
+
+/// 切换维护模式请求
+#[derive(Debug, Deserialize)]
+pub struct SetMaintenanceModeRequest {
+    pub read_only: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// 获取维护模式状态
+pub async fn get_maintenance_status(
+    State(state): State<Arc<AppState>>,
+    _auth: AuthContext,
+) -> Json<MaintenanceStatus> {
+    Json(state.maintenance.status())
+}
+
+/// 切换只读维护模式（仅管理员）
+pub async fn set_maintenance_mode(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(request): Json<SetMaintenanceModeRequest>,
+) -> Result<Json<MaintenanceStatus>> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin && !state.maintenance.is_allowlisted(&auth.username) {
+        return Err(AppError::Forbidden);
+    }
+
+    let status = state
+        .maintenance
+        .set(request.read_only, request.reason, &auth.username);
+    tracing::warn!(
+        read_only = status.read_only,
+        reason = status.reason.as_deref().unwrap_or("-"),
+        changed_by = %auth.username,
+        "Maintenance mode changed"
+    );
+
+    // 维护期间数据库可能不可写，审计失败不影响切换
+    let summary = match (&status.read_only, &status.reason) {
+        (true, Some(reason)) => format!("Enabled read-only mode: {}", reason),
+        (true, None) => "Enabled read-only mode".to_string(),
+        (false, _) => "Disabled read-only mode".to_string(),
+    };
+    let _ = state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::SystemMaintenanceModeChange,
+            Some("system"),
+            None,
+            Some(&summary),
+            None,
+        )
+        .await;
+
+    Ok(Json(status))
+}
@@ -12,6 +12,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::middleware::AppState;
+use crate::rabbitmq::BrokerState;
 
 /// 指标响应
 #[derive(Serialize)]
@@ -57,6 +58,10 @@ pub struct MetricsResponse {
     pub audit_queries_recent_total: i64,
     pub login_events_total: i64,
     pub login_failures_recent_total: i64,
+    pub rabbitmq_connected: bool,
+    pub rabbitmq_circuit_open: bool,
+    pub rabbitmq_unavailable_secs: u64,
+    pub rabbitmq_reconnects_total: u64,
 }
 
 async fn collect_metrics_snapshot(state: &Arc<AppState>) -> MetricsResponse {
@@ -190,6 +195,8 @@ async fn collect_metrics_snapshot(state: &Arc<AppState>) -> MetricsResponse {
     )
     .await;
 
+    let broker = state.rabbitmq_publisher.monitor().status();
+
     MetricsResponse {
         http_requests_total: 0, // 需要从 metrics crate 获取
         http_requests_by_status: HashMap::new(),
@@ -232,6 +239,10 @@ async fn collect_metrics_snapshot(state: &Arc<AppState>) -> MetricsResponse {
         audit_queries_recent_total,
         login_events_total,
         login_failures_recent_total,
+        rabbitmq_connected: broker.state == BrokerState::Connected,
+        rabbitmq_circuit_open: broker.circuit_open,
+        rabbitmq_unavailable_secs: broker.unavailable_secs,
+        rabbitmq_reconnects_total: broker.reconnects_total,
     }
 }
 
@@ -272,6 +283,11 @@ pub async fn metrics_export(State(state): State<Arc<AppState>>) -> Response {
     metrics::gauge!("ops_audit_queries_recent_total").set(snapshot.audit_queries_recent_total as f64);
     metrics::gauge!("ops_login_events_total").set(snapshot.login_events_total as f64);
     metrics::gauge!("ops_login_failures_recent_total").set(snapshot.login_failures_recent_total as f64);
+    metrics::gauge!("ops_rabbitmq_connected").set(u8::from(snapshot.rabbitmq_connected) as f64);
+    metrics::gauge!("ops_rabbitmq_circuit_open")
+        .set(u8::from(snapshot.rabbitmq_circuit_open) as f64);
+    metrics::gauge!("ops_rabbitmq_unavailable_secs").set(snapshot.rabbitmq_unavailable_secs as f64);
+    metrics::gauge!("ops_rabbitmq_reconnects_total").set(snapshot.rabbitmq_reconnects_total as f64);
 
     let body = crate::telemetry::prometheus_handle()
         .map(|handle| handle.render())
@@ -1,16 +1,27 @@
 //! HTTP 处理器模块
 
+pub mod advisory;
 pub mod approval;
 pub mod artifact;
 pub mod asset;
 pub mod audit;
 pub mod auth;
+pub mod blob;
 pub mod build;
 pub mod build_webhook;
+pub mod environment_policy;
+pub mod error_catalog;
+pub mod evidence;
 pub mod health;
+pub mod inbound_webhook;
 pub mod job;
+pub mod load_test;
+pub mod maintenance;
 pub mod metrics;
+pub mod notification_template;
 pub mod role;
 pub mod runner;
 pub mod runner_config;
+pub mod stats;
 pub mod user;
+pub mod watch;
@@ -0,0 +1,179 @@
+//! 通知模板处理器
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::{AppError, Result},
+    middleware::AppState,
+    models::notification_template::{
+        PreviewNotificationTemplateRequest, RollbackNotificationTemplateRequest,
+        UpsertNotificationTemplateRequest,
+    },
+    services::{audit_service::AuditAction, notification_template},
+};
+
+async fn require_admin(state: &Arc<AppState>, auth: &AuthContext) -> Result<()> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth.user_id)
+        .await
+        .unwrap_or(false);
+    if !is_admin {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
+
+/// 修改后刷新本实例的模板缓存（失败时由定期刷新兜底）
+async fn reload_templates(state: &AppState) {
+    if let Err(e) = notification_template::reload(&state.db).await {
+        warn!(error = %e, "Failed to reload notification templates");
+    }
+}
+
+/// 列出通知模板
+pub async fn list_notification_templates(
+    State(state): State<Arc<AppState>>,
+    _auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    Ok(Json(notification_template::list(&state.db).await?))
+}
+
+/// 各事件类型可用的模板变量与渠道
+pub async fn list_notification_template_variables(_auth: AuthContext) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "channels": notification_template::CHANNELS,
+        "events": notification_template::all_variables(),
+    }))
+}
+
+/// 获取通知模板及版本历史
+pub async fn get_notification_template(
+    State(state): State<Arc<AppState>>,
+    _auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let template = notification_template::get(&state.db, id).await?;
+    let versions = notification_template::list_versions(&state.db, id).await?;
+
+    Ok(Json(serde_json::json!({
+        "template": template,
+        "versions": versions,
+    })))
+}
+
+/// 创建或修改通知模板（仅管理员，修改生成新版本）
+pub async fn upsert_notification_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(request): Json<UpsertNotificationTemplateRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &auth).await?;
+
+    let id = notification_template::upsert(&state.db, &request, auth.user_id).await?;
+    let template = notification_template::get(&state.db, id).await?;
+    reload_templates(&state).await;
+
+    let summary = format!(
+        "Saved notification template {}/{}/{} version {}",
+        template.event_type, template.channel, template.locale, template.current_version
+    );
+    state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::NotificationTemplateUpdate,
+            Some("notification_template"),
+            Some(id),
+            Some(&summary),
+            None,
+        )
+        .await?;
+
+    Ok(Json(template))
+}
+
+/// 使用示例事件预览模板
+pub async fn preview_notification_template(
+    _auth: AuthContext,
+    Json(request): Json<PreviewNotificationTemplateRequest>,
+) -> Result<impl IntoResponse> {
+    let preview =
+        notification_template::preview(&request.event_type, &request.body, request.locale)?;
+    Ok(Json(preview))
+}
+
+/// 回滚到历史版本（仅管理员，以该版本内容生成新版本）
+pub async fn rollback_notification_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RollbackNotificationTemplateRequest>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &auth).await?;
+
+    let version =
+        notification_template::rollback(&state.db, id, request.version, auth.user_id).await?;
+    let template = notification_template::get(&state.db, id).await?;
+    reload_templates(&state).await;
+
+    let summary = format!(
+        "Rolled back notification template {}/{}/{} to version {} as version {}",
+        template.event_type, template.channel, template.locale, request.version, version
+    );
+    state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::NotificationTemplateRollback,
+            Some("notification_template"),
+            Some(id),
+            Some(&summary),
+            None,
+        )
+        .await?;
+
+    Ok(Json(template))
+}
+
+/// 删除通知模板（仅管理员，恢复使用内置文案）
+pub async fn delete_notification_template(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    require_admin(&state, &auth).await?;
+
+    let template = notification_template::get(&state.db, id).await?;
+    if !notification_template::delete(&state.db, id).await? {
+        return Err(AppError::not_found("Notification template not found"));
+    }
+    reload_templates(&state).await;
+
+    let summary = format!(
+        "Deleted notification template {}/{}/{}",
+        template.event_type, template.channel, template.locale
+    );
+    state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::NotificationTemplateDelete,
+            Some("notification_template"),
+            Some(id),
+            Some(&summary),
+            None,
+        )
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
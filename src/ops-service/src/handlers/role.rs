@@ -65,7 +65,7 @@ pub async fn create_role(
         .await?;
 
     Ok(Json(json!({
-        "message": "角色创建成功",
+        "message": crate::i18n::t("notice.role.created"),
         "role": role
     })))
 }
@@ -130,7 +130,7 @@ pub async fn update_role(
         .await?;
 
     Ok(Json(json!({
-        "message": "角色更新成功",
+        "message": crate::i18n::t("notice.role.updated"),
         "role": role
     })))
 }
@@ -172,7 +172,7 @@ pub async fn delete_role(
         .await?;
 
     Ok(Json(json!({
-        "message": "角色删除成功"
+        "message": crate::i18n::t("notice.role.deleted")
     })))
 }
 
@@ -270,7 +270,7 @@ pub async fn assign_role(
         .await?;
 
     Ok(Json(json!({
-        "message": "角色分配成功",
+        "message": crate::i18n::t("notice.role.assigned"),
         "binding": binding
     })))
 }
@@ -309,7 +309,7 @@ pub async fn revoke_role(
         .await?;
 
     Ok(Json(json!({
-        "message": "角色撤销成功"
+        "message": crate::i18n::t("notice.role.revoked")
     })))
 }
 
@@ -7,24 +7,56 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
     response::{IntoResponse, Json},
+    Extension,
 };
 use chrono::Utc;
+use rand::distr::{Alphanumeric, SampleString};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use sqlx::Row;
 
+use common::messages::{
+    ImageCacheStats, KnownHostsSync, RunnerCommandAck, RunnerControlMessage, RunningTaskInfo,
+};
+use common::ssh::{known_hosts_digest, render_known_hosts};
+
 use crate::{
-    auth::middleware::AuthContext,
+    auth::{api_key::ApiKeyGenerator, middleware::AuthContext},
     config::RunnerDockerEffectiveConfig,
     error::{AppError, Result},
-    middleware::AppState,
-    services::audit_service::AuditLogParams,
+    middleware::{AppState, AuthenticatedRunner},
+    models::{
+        asset::SshKnownHost,
+        build::{
+            CreateEnrollmentTokenRequest, CreateRunnerCommandRequest, EnrollmentTokenResponse,
+        },
+        runner_config::RunnerDockerConfig as RunnerDockerConfigRow,
+    },
+    repository::runner_repo::RunnerRepository,
+    services::{
+        audit_service::{AuditAction, AuditLogParams},
+        RunnerConfigRolloutService,
+    },
 };
 
+/// 注册令牌默认有效期（秒）
+const DEFAULT_ENROLLMENT_TTL_SECS: u64 = 3600;
+
+/// 注册令牌最长有效期（秒）
+const MAX_ENROLLMENT_TTL_SECS: u64 = 7 * 24 * 3600;
+
+/// 控制指令等待回报的默认超时（秒）
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 120;
+
+/// 控制指令等待回报的最长超时（秒）
+const MAX_COMMAND_TIMEOUT_SECS: u64 = 3600;
+
+/// 控制指令列表返回的最大条数
+const RUNNER_COMMAND_LIST_LIMIT: i64 = 50;
+
 /// Runner 注册请求
 /// 兼容 common::messages::RunnerRegistrationMessage 格式
 #[derive(Debug, Deserialize)]
@@ -99,6 +131,30 @@ pub struct RunnerRegistrationResponse {
     pub server_timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+/// Runner 注册令牌兑换请求
+#[derive(Debug, Deserialize)]
+pub struct RunnerEnrollmentRequest {
+    /// 管理员签发的一次性注册令牌
+    pub enrollment_token: String,
+
+    /// Runner 名称（必须与令牌绑定的名称一致）
+    pub name: String,
+
+    /// 能力标签（必须包含令牌绑定的全部标签）
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+/// Runner 注册令牌兑换响应
+#[derive(Debug, Serialize)]
+pub struct RunnerEnrollmentResponse {
+    /// Runner ID
+    pub runner_id: Uuid,
+
+    /// Runner 专属 API Key（仅返回一次）
+    pub api_key: String,
+}
+
 /// Docker 配置（发送给 Runner）
 #[derive(Debug, Serialize)]
 pub struct RunnerDockerConfiguration {
@@ -163,6 +219,26 @@ pub struct RunnerHeartbeatRequest {
     /// 时间戳（common 库有此字段，可选）
     #[serde(default)]
     pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// 本地 known_hosts 摘要（仅启用 known_hosts 同步的 Runner 上报）
+    #[serde(default)]
+    pub known_hosts_digest: Option<String>,
+
+    /// Docker 镜像拉取与缓存统计（仅支持 Docker 的 Runner 上报）
+    #[serde(default)]
+    pub image_cache: Option<ImageCacheStats>,
+
+    /// 已应用的 Docker 配置版本（来自此前心跳响应的 config_version）
+    #[serde(default)]
+    pub applied_config_version: Option<i64>,
+
+    /// 自上次心跳以来执行完成的控制指令
+    #[serde(default)]
+    pub command_acks: Vec<RunnerCommandAck>,
+
+    /// 正在执行的构建任务（旧版本 Runner 不上报）
+    #[serde(default)]
+    pub running_tasks: Option<Vec<RunningTaskInfo>>,
 }
 
 /// 反序列化状态（兼容枚举格式）
@@ -187,10 +263,14 @@ pub struct RunnerHeartbeatResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub docker: Option<RunnerDockerConfiguration>,
 
-    /// 配置版本号（用于检测配置变更）
+    /// 配置版本号（下发的 Docker 配置版本，Runner 应用后在心跳中回报）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config_version: Option<i64>,
 
+    /// 集中管理的 known_hosts（仅当 Runner 上报的摘要与当前集合不一致时下发）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_hosts: Option<KnownHostsSync>,
+
     /// 服务器时间戳
     pub server_timestamp: chrono::DateTime<chrono::Utc>,
 }
@@ -244,6 +324,9 @@ pub struct RunnerStatusResponse {
     /// 系统信息
     pub system: Option<SystemInfoUpdate>,
 
+    /// 最近一次心跳上报的镜像拉取与缓存统计
+    pub image_cache: Option<serde_json::Value>,
+
     /// 创建时间
     pub created_at: chrono::DateTime<chrono::Utc>,
 
@@ -261,67 +344,206 @@ pub struct RunnerListResponse {
     pub total: i64,
 }
 
+/// Runner 运行中任务清单响应
+#[derive(Debug, Serialize)]
+pub struct RunnerTasksResponse {
+    /// Runner ID
+    pub runner_id: Uuid,
+
+    /// Runner 名称
+    pub runner_name: String,
+
+    /// 是否在线（2 分钟内有心跳）
+    pub online: bool,
+
+    /// 最后心跳时间（心跳上报部分的时效）
+    pub last_heartbeat: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Runner 是否随心跳上报运行中任务（旧版本 Runner 只有状态消息跟踪的数据）
+    pub heartbeat_reporting: bool,
+
+    /// 正在执行的任务
+    pub tasks: Vec<RunnerTaskEntry>,
+
+    /// 总数
+    pub total: usize,
+}
+
+/// Runner 上正在执行的任务
+#[derive(Debug, Serialize)]
+pub struct RunnerTaskEntry {
+    /// 构建作业 ID
+    pub job_id: Uuid,
+
+    /// 任务 ID（仅心跳上报）
+    pub task_id: Option<Uuid>,
+
+    /// 项目名称
+    pub project_name: Option<String>,
+
+    /// 仓库地址
+    pub repository: Option<String>,
+
+    /// 分支
+    pub branch: Option<String>,
+
+    /// 作业状态（pending 表示 Runner 已接收、尚未开始执行）
+    pub status: Option<String>,
+
+    /// Runner 开始执行的时间（心跳上报）
+    pub received_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// 作业开始运行的时间（状态消息）
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// 是否出现在 Runner 最近一次心跳中
+    pub reported_by_runner: bool,
+
+    /// 状态消息显示作业仍在该 Runner 上执行
+    pub tracked: bool,
+
+    /// 步骤进度
+    pub steps: RunnerTaskStepProgress,
+}
+
+/// 运行中任务的步骤进度（来自步骤状态消息）
+#[derive(Debug, Default, Serialize)]
+pub struct RunnerTaskStepProgress {
+    /// 步骤总数
+    pub total: i32,
+
+    /// 已结束的步骤数
+    pub finished: i32,
+
+    /// 正在执行的步骤
+    pub current_step_id: Option<String>,
+
+    /// 正在执行的步骤名称
+    pub current_step_name: Option<String>,
+
+    /// 正在执行的步骤开始时间
+    pub current_step_started_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Runner 运行中任务对应的构建作业
+#[derive(Debug, sqlx::FromRow)]
+struct RunnerTaskJobRow {
+    id: Uuid,
+    project_name: String,
+    repository: String,
+    branch: String,
+    status: String,
+    started_at: Option<chrono::DateTime<chrono::Utc>>,
+    total_steps: i32,
+    tracked: bool,
+}
+
+/// 运行中任务的步骤状态
+#[derive(Debug, sqlx::FromRow)]
+struct RunnerTaskStepRow {
+    job_id: Uuid,
+    step_id: String,
+    step_name: String,
+    status: String,
+    started_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// 合并心跳上报与状态消息跟踪的运行中任务
+///
+/// 心跳上报的任务全部保留；只有状态消息跟踪到的任务也列出（Runner 未上报或尚未发送心跳）
+fn merge_runner_tasks(
+    reported: &[RunningTaskInfo],
+    jobs: Vec<RunnerTaskJobRow>,
+    steps: &[RunnerTaskStepRow],
+) -> Vec<RunnerTaskEntry> {
+    let mut jobs: std::collections::HashMap<Uuid, RunnerTaskJobRow> =
+        jobs.into_iter().map(|job| (job.id, job)).collect();
+
+    let mut job_ids: Vec<Uuid> = reported.iter().map(|task| task.job_id).collect();
+    let mut tracked_only: Vec<&RunnerTaskJobRow> = jobs
+        .values()
+        .filter(|job| job.tracked && !job_ids.contains(&job.id))
+        .collect();
+    tracked_only.sort_by_key(|job| job.started_at);
+    job_ids.extend(tracked_only.iter().map(|job| job.id));
+
+    job_ids
+        .into_iter()
+        .map(|job_id| {
+            let task = reported.iter().find(|task| task.job_id == job_id);
+            let job = jobs.remove(&job_id);
+            let job_steps: Vec<_> = steps.iter().filter(|step| step.job_id == job_id).collect();
+            let current = job_steps.iter().find(|step| step.status == "running");
+            RunnerTaskEntry {
+                job_id,
+                task_id: task.map(|task| task.task_id),
+                received_at: task.map(|task| task.started_at),
+                reported_by_runner: task.is_some(),
+                tracked: job.as_ref().is_some_and(|job| job.tracked),
+                steps: RunnerTaskStepProgress {
+                    total: job.as_ref().map_or(0, |job| job.total_steps),
+                    finished: job_steps
+                        .iter()
+                        .filter(|step| step.status != "pending" && step.status != "running")
+                        .count() as i32,
+                    current_step_id: current.map(|step| step.step_id.clone()),
+                    current_step_name: current.map(|step| step.step_name.clone()),
+                    current_step_started_at: current.and_then(|step| step.started_at),
+                },
+                started_at: job.as_ref().and_then(|job| job.started_at),
+                project_name: job.as_ref().map(|job| job.project_name.clone()),
+                repository: job.as_ref().map(|job| job.repository.clone()),
+                branch: job.as_ref().map(|job| job.branch.clone()),
+                status: job.map(|job| job.status),
+            }
+        })
+        .collect()
+}
+
 // ==================== Helper Functions ====================
 
-/// 从数据库获取 Runner Docker 配置
-/// 如果数据库中没有配置，回退到环境变量配置
+/// 从数据库获取 Runner Docker 配置及其版本
+/// 灰度发布中的金丝雀 Runner 获得候选配置；数据库中没有配置时回退到环境变量配置（无版本）
 async fn get_runner_docker_config(
     state: &Arc<AppState>,
     runner_name: &str,
     capabilities: &[String],
-) -> RunnerDockerEffectiveConfig {
+) -> (RunnerDockerEffectiveConfig, Option<i64>) {
     // 首先尝试从数据库加载配置
-    let db_config = sqlx::query(
-        "SELECT enabled, default_image, default_timeout_secs,
+    let db_config = sqlx::query_as::<_, RunnerDockerConfigRow>(
+        "SELECT id, name, version, enabled, default_image, default_timeout_secs,
                 memory_limit_gb, cpu_shares, pids_limit, images_by_type,
-                per_capability, per_runner
+                per_capability, per_runner, description, created_at, updated_at
          FROM runner_docker_configs
          WHERE name = 'default'",
     )
     .fetch_optional(&state.db)
     .await;
 
-    if let Ok(Some(row)) = db_config {
-        use crate::config::{RunnerDockerConfig as Config, RunnerDockerOverride};
-
-        let enabled: bool = row.get("enabled");
-        let default_image: String = row.get("default_image");
-        let default_timeout_secs: i32 = row.get("default_timeout_secs");
-        let memory_limit_gb: Option<i64> = row.get("memory_limit_gb");
-        let cpu_shares: Option<i64> = row.get("cpu_shares");
-        let pids_limit: Option<i64> = row.get("pids_limit");
-
-        let images_by_type_json: sqlx::types::Json<serde_json::Value> = row.get("images_by_type");
-        let per_capability_json: sqlx::types::Json<serde_json::Value> = row.get("per_capability");
-        let per_runner_json: sqlx::types::Json<serde_json::Value> = row.get("per_runner");
-
-        let images_by_type: HashMap<String, String> =
-            serde_json::from_value(images_by_type_json.0).unwrap_or_default();
-        let per_capability: HashMap<String, RunnerDockerOverride> =
-            serde_json::from_value(per_capability_json.0).unwrap_or_default();
-        let per_runner: HashMap<String, RunnerDockerOverride> =
-            serde_json::from_value(per_runner_json.0).unwrap_or_default();
-
-        let db_cfg = Config {
-            enabled,
-            default_image,
-            images_by_type,
-            memory_limit_gb,
-            cpu_shares,
-            pids_limit,
-            default_timeout_secs: default_timeout_secs as u64,
-            per_runner,
-            per_capability,
-        };
-
-        return db_cfg.get_config_for_runner(runner_name, capabilities);
+    if let Ok(Some(config)) = db_config {
+        let rollout = RunnerConfigRolloutService::new(state.db.clone())
+            .active_for_runner(config.id, runner_name)
+            .await
+            .unwrap_or(None);
+        // 候选配置的版本即发布的目标版本
+        let config = rollout.and_then(|r| r.candidate()).unwrap_or(config);
+
+        return (
+            config
+                .to_docker_config()
+                .get_config_for_runner(runner_name, capabilities),
+            Some(config.version),
+        );
     }
 
     // 回退到环境变量配置
-    state
-        .config
-        .runner_docker
-        .get_config_for_runner(runner_name, capabilities)
+    (
+        state
+            .config
+            .runner_docker
+            .get_config_for_runner(runner_name, capabilities),
+        None,
+    )
 }
 
 // ==================== Runner API ====================
@@ -329,8 +551,11 @@ async fn get_runner_docker_config(
 /// Runner 注册
 pub async fn register_runner(
     State(state): State<Arc<AppState>>,
+    runner: Option<Extension<AuthenticatedRunner>>,
     Json(request): Json<RunnerRegistrationRequest>,
 ) -> Result<impl IntoResponse> {
+    ensure_runner_identity(runner.as_deref(), &request.name)?;
+
     // 获取合并后的出站白名单
     let outbound_allowlist = request.get_outbound_allowlist();
 
@@ -451,7 +676,7 @@ pub async fn register_runner(
 
     // 构建 Docker 配置（动态配置，考虑 Runner 名称和能力标签）
     let docker_config = if request.docker_supported {
-        let effective =
+        let (effective, _) =
             get_runner_docker_config(&state, &request.name, &request.capabilities).await;
 
         Some(RunnerDockerConfiguration {
@@ -478,11 +703,113 @@ pub async fn register_runner(
     Ok((StatusCode::OK, Json(response)))
 }
 
+/// 使用专属凭据的 Runner 只能以自己的名称注册和上报心跳
+fn ensure_runner_identity(runner: Option<&AuthenticatedRunner>, name: &str) -> Result<()> {
+    match runner {
+        Some(runner) if runner.name != name => {
+            tracing::warn!(
+                runner_name = %runner.name,
+                requested_name = %name,
+                "Runner credential used for a different runner name"
+            );
+            Err(AppError::Forbidden)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runner 能力标签是否包含令牌绑定的全部标签
+fn labels_satisfied(expected: &[String], capabilities: &[String]) -> bool {
+    expected.iter().all(|label| capabilities.contains(label))
+}
+
+/// 使用一次性注册令牌换取 Runner 专属凭据
+///
+/// 令牌本身即为凭证，不需要共享的 Runner API Key
+pub async fn enroll_runner(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RunnerEnrollmentRequest>,
+) -> Result<impl IntoResponse> {
+    let api_key = ApiKeyGenerator::generate();
+    let repo = RunnerRepository::new(state.db.clone());
+    let (token, runner_id) = repo
+        .enroll_runner(
+            &ApiKeyGenerator::hash(&request.enrollment_token),
+            &request.capabilities,
+            &ApiKeyGenerator::hash(&api_key),
+            |token| {
+                if token.runner_name != request.name {
+                    return Err(AppError::authentication(
+                        "Enrollment token was issued for a different runner",
+                    ));
+                }
+                if !labels_satisfied(&token.labels, &request.capabilities) {
+                    return Err(AppError::validation(
+                        "Runner capabilities do not include the labels bound to the token",
+                    ));
+                }
+                Ok(())
+            },
+        )
+        .await?;
+
+    let _ = state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: runner_id,
+            subject_type: "runner",
+            subject_name: Some(&request.name),
+            action: AuditAction::RunnerEnroll.as_str(),
+            resource_type: "runner",
+            resource_id: Some(runner_id),
+            resource_name: Some(&request.name),
+            changes: Some(serde_json::json!({
+                "enrollment_token_id": token.id,
+                "capabilities": request.capabilities,
+            })),
+            changes_summary: Some(&format!("Runner enrolled: {}", request.name)),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        })
+        .await;
+
+    info!(runner_id = %runner_id, name = %request.name, "Runner enrolled with one-time token");
+
+    Ok((StatusCode::OK, Json(RunnerEnrollmentResponse { runner_id, api_key })))
+}
+
+/// 生成 known_hosts 同步数据，Runner 摘要已是最新时返回 None
+async fn known_hosts_sync_for(
+    state: &AppState,
+    runner_digest: &str,
+) -> Result<Option<KnownHostsSync>> {
+    let known_hosts = sqlx::query_as::<_, SshKnownHost>("SELECT * FROM ssh_known_hosts")
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to load known_hosts for runner sync");
+            AppError::database("Failed to load known_hosts")
+        })?;
+
+    let entries: Vec<_> = known_hosts.iter().map(SshKnownHost::to_entry).collect();
+    let digest = known_hosts_digest(&render_known_hosts(&entries));
+    if digest == runner_digest {
+        return Ok(None);
+    }
+    Ok(Some(KnownHostsSync { digest, entries }))
+}
+
 /// Runner 心跳
 pub async fn runner_heartbeat(
     State(state): State<Arc<AppState>>,
+    runner: Option<Extension<AuthenticatedRunner>>,
     Json(request): Json<RunnerHeartbeatRequest>,
 ) -> Result<impl IntoResponse> {
+    ensure_runner_identity(runner.as_deref(), &request.name)?;
+
     // 查找 Runner
     let runner =
         sqlx::query("SELECT id, capabilities, docker_supported FROM runners WHERE name = $1")
@@ -510,15 +837,31 @@ pub async fn runner_heartbeat(
         _ => "active",
     };
 
-    // 更新心跳和状态
+    // 更新心跳和状态（未上报镜像缓存统计或配置版本时保留原值）
+    let image_cache = request
+        .image_cache
+        .as_ref()
+        .and_then(|stats| serde_json::to_value(stats).ok());
+    let running_tasks = request
+        .running_tasks
+        .as_ref()
+        .and_then(|tasks| serde_json::to_value(tasks).ok());
     sqlx::query(
         "UPDATE runners
-         SET status = $1, current_jobs = $2, last_heartbeat = NOW(), updated_at = NOW()
+         SET status = $1, current_jobs = $2, last_heartbeat = NOW(), updated_at = NOW(),
+             image_cache = COALESCE($4, image_cache), running_tasks = $6,
+             applied_config_at = CASE
+                 WHEN $5::bigint IS DISTINCT FROM applied_config_version AND $5 IS NOT NULL
+                 THEN NOW() ELSE applied_config_at END,
+             applied_config_version = COALESCE($5, applied_config_version)
          WHERE id = $3",
     )
     .bind(status)
     .bind(request.current_jobs as i32)
     .bind(runner_id)
+    .bind(image_cache)
+    .bind(request.applied_config_version)
+    .bind(running_tasks)
     .execute(&state.db)
     .await
     .map_err(|e| {
@@ -526,9 +869,45 @@ pub async fn runner_heartbeat(
         AppError::database("Failed to update heartbeat")
     })?;
 
+    // 记录控制指令执行结果
+    if !request.command_acks.is_empty() {
+        let repo = RunnerRepository::new(state.db.clone());
+        match repo
+            .acknowledge_commands(runner_id, &request.command_acks)
+            .await
+        {
+            Ok(updated) => debug!(
+                runner_id = %runner_id,
+                received = request.command_acks.len(),
+                updated,
+                "Runner command acknowledgments recorded"
+            ),
+            Err(e) => error!(error = %e, "Failed to record runner command acknowledgments"),
+        }
+    }
+
+    // 镜像拉取与缓存指标（按 Runner 区分）
+    if let Some(stats) = &request.image_cache {
+        let runner = request.name.clone();
+        metrics::gauge!("ops_runner_image_pulls_total", "runner" => runner.clone())
+            .set(stats.pulls as f64);
+        metrics::gauge!("ops_runner_image_pull_failures_total", "runner" => runner.clone())
+            .set(stats.pull_failures as f64);
+        metrics::gauge!("ops_runner_image_pull_avg_ms", "runner" => runner.clone())
+            .set(stats.avg_pull_time_ms().unwrap_or(0.0));
+        metrics::gauge!("ops_runner_image_pull_max_ms", "runner" => runner.clone())
+            .set(stats.max_pull_time_ms as f64);
+        if let Some(hit_rate) = stats.hit_rate() {
+            metrics::gauge!("ops_runner_image_cache_hit_ratio", "runner" => runner).set(hit_rate);
+        }
+    }
+
     // 构建 Docker 配置（动态配置）
+    let mut served_version = None;
     let docker_config = if docker_supported {
-        let effective = get_runner_docker_config(&state, &request.name, &capabilities).await;
+        let (effective, version) =
+            get_runner_docker_config(&state, &request.name, &capabilities).await;
+        served_version = version;
 
         Some(RunnerDockerConfiguration {
             enabled: effective.enabled,
@@ -543,9 +922,19 @@ pub async fn runner_heartbeat(
         None
     };
 
+    let known_hosts = match &request.known_hosts_digest {
+        Some(digest) => known_hosts_sync_for(&state, digest).await?,
+        None => None,
+    };
+
     let response = RunnerHeartbeatResponse {
         docker: docker_config,
-        config_version: Some(state.runner_config_version.load(std::sync::atomic::Ordering::Relaxed) as i64),
+        config_version: served_version.or(Some(
+            state
+                .runner_config_version
+                .load(std::sync::atomic::Ordering::Relaxed) as i64,
+        )),
+        known_hosts,
         server_timestamp: Utc::now(),
     };
 
@@ -567,7 +956,7 @@ pub async fn get_runner_status(
 
     let row = sqlx::query(
         "SELECT id, name, capabilities, docker_supported, max_concurrent_jobs,
-                current_jobs, status, last_heartbeat, created_at, updated_at
+                current_jobs, status, last_heartbeat, image_cache, created_at, updated_at
          FROM runners WHERE id = $1",
     )
     .bind(id)
@@ -592,11 +981,99 @@ pub async fn get_runner_status(
         status: row.get("status"),
         last_heartbeat: row.get("last_heartbeat"),
         system: None, // 系统信息需要额外查询
+        image_cache: row.get("image_cache"),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
     }))
 }
 
+/// 获取 Runner 正在执行的任务
+///
+/// 合并 Runner 最近一次心跳上报的任务与状态消息跟踪到的未结束作业，
+/// 供下线 Runner 前确认会中断哪些任务
+pub async fn list_runner_tasks(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "read", None, None)
+        .await?;
+
+    let row = sqlx::query(
+        "SELECT name, last_heartbeat, running_tasks,
+                COALESCE(last_heartbeat > NOW() - INTERVAL '2 minutes', false) AS online
+         FROM runners WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get runner");
+        AppError::database("Failed to get runner")
+    })?
+    .ok_or_else(|| AppError::not_found("Runner not found"))?;
+
+    let runner_name: String = row.get("name");
+    let running_tasks: Option<serde_json::Value> = row.get("running_tasks");
+    let heartbeat_reporting = running_tasks.is_some();
+    let reported: Vec<RunningTaskInfo> = running_tasks
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    let reported_ids: Vec<Uuid> = reported.iter().map(|task| task.job_id).collect();
+
+    let jobs: Vec<RunnerTaskJobRow> = sqlx::query_as(
+        "SELECT bj.id, j.name AS project_name, bj.repository, bj.branch, bj.status::text,
+                bj.started_at,
+                CASE WHEN jsonb_typeof(bj.build_parameters->'steps') = 'array'
+                     THEN jsonb_array_length(bj.build_parameters->'steps')
+                     ELSE 0 END AS total_steps,
+                COALESCE(bj.runner_name = $1 AND bj.status::text IN ('pending', 'running'), false)
+                    AS tracked
+         FROM build_jobs bj
+         JOIN jobs j ON j.id = bj.job_id
+         WHERE (bj.runner_name = $1 AND bj.status::text IN ('pending', 'running'))
+            OR bj.id = ANY($2)",
+    )
+    .bind(&runner_name)
+    .bind(&reported_ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get runner build jobs");
+        AppError::database("Failed to get runner tasks")
+    })?;
+
+    let job_ids: Vec<Uuid> = jobs.iter().map(|job| job.id).collect();
+    let steps: Vec<RunnerTaskStepRow> = sqlx::query_as(
+        "SELECT job_id, step_id, COALESCE(step_name, step_id) AS step_name, status::text,
+                started_at
+         FROM build_steps
+         WHERE job_id = ANY($1)
+         ORDER BY created_at",
+    )
+    .bind(&job_ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get runner build steps");
+        AppError::database("Failed to get runner tasks")
+    })?;
+
+    let tasks = merge_runner_tasks(&reported, jobs, &steps);
+
+    Ok(Json(RunnerTasksResponse {
+        runner_id: id,
+        runner_name,
+        online: row.get("online"),
+        last_heartbeat: row.get("last_heartbeat"),
+        heartbeat_reporting,
+        total: tasks.len(),
+        tasks,
+    }))
+}
+
 /// 获取 Runner 列表
 pub async fn list_runners(
     State(state): State<Arc<AppState>>,
@@ -609,7 +1086,7 @@ pub async fn list_runners(
 
     let rows = sqlx::query(
         "SELECT id, name, capabilities, docker_supported, max_concurrent_jobs,
-                current_jobs, status, last_heartbeat, created_at, updated_at
+                current_jobs, status, last_heartbeat, image_cache, created_at, updated_at
          FROM runners
          ORDER BY created_at DESC",
     )
@@ -636,6 +1113,7 @@ pub async fn list_runners(
             status: row.get("status"),
             last_heartbeat: row.get("last_heartbeat"),
             system: None,
+            image_cache: row.get("image_cache"),
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
         });
@@ -789,9 +1267,388 @@ pub async fn delete_runner(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// 向 Runner 下发控制指令
+///
+/// 指令经 Runner 控制队列投递，执行结果随 Runner 心跳回报；超时未回报的指令标记为 timed_out
+pub async fn create_runner_command(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CreateRunnerCommandRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "write", None, None)
+        .await?;
+
+    let timeout_secs = request.timeout_secs.unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS);
+    if timeout_secs == 0 || timeout_secs > MAX_COMMAND_TIMEOUT_SECS {
+        return Err(AppError::validation(&format!(
+            "Command timeout must be between 1 and {} seconds",
+            MAX_COMMAND_TIMEOUT_SECS
+        )));
+    }
+
+    let runner = sqlx::query(
+        "SELECT name, COALESCE(last_heartbeat > NOW() - INTERVAL '2 minutes', false) AS online
+         FROM runners WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to check runner");
+        AppError::database("Failed to check runner")
+    })?
+    .ok_or_else(|| AppError::not_found("Runner not found"))?;
+
+    let runner_name: String = runner.get("name");
+    let online: bool = runner.get("online");
+    // 控制队列随 Runner 连接删除，离线 Runner 收不到指令
+    if !online {
+        return Err(AppError::validation("Runner is offline and cannot receive commands"));
+    }
+
+    let action = request.action.as_str();
+    let repo = RunnerRepository::new(state.db.clone());
+    let command = repo
+        .create_command(
+            id,
+            action,
+            auth.user_id,
+            Utc::now() + chrono::Duration::seconds(timeout_secs as i64),
+        )
+        .await?;
+
+    let message = RunnerControlMessage {
+        command_id: command.id,
+        action: request.action,
+        requested_by: Some(auth.user_id),
+        timestamp: Utc::now(),
+    };
+    let published = match state.rabbitmq_publisher.get().await {
+        Ok(publisher) => publisher
+            .publish_runner_control(&runner_name, &message)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let published_ok = published.is_ok();
+    let command = match published {
+        Ok(()) => command,
+        Err(e) => {
+            warn!(error = %e, runner_id = %id, "Failed to publish runner control command");
+            repo.fail_command(command.id, "Failed to publish command to runner control queue")
+                .await?
+        }
+    };
+
+    let _ = state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: auth.user_id,
+            subject_type: "user",
+            subject_name: Some(&auth.username),
+            action: AuditAction::RunnerCommand.as_str(),
+            resource_type: "runner",
+            resource_id: Some(id),
+            resource_name: Some(&runner_name),
+            changes: Some(serde_json::json!({
+                "command_id": command.id,
+                "action": action,
+                "timeout_secs": timeout_secs,
+            })),
+            changes_summary: Some(&format!("Sent {} command to runner: {}", action, runner_name)),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: if published_ok { "success" } else { "failure" },
+            error_message: command.result_message.as_deref(),
+        })
+        .await;
+
+    info!(
+        runner_id = %id,
+        runner_name = %runner_name,
+        command_id = %command.id,
+        action = %action,
+        "Runner control command sent"
+    );
+
+    Ok((StatusCode::ACCEPTED, Json(command)))
+}
+
+/// 列出 Runner 最近的控制指令
+pub async fn list_runner_commands(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "read", None, None)
+        .await?;
+
+    // 先标记超时的指令，保证返回的状态是最新的
+    let repo = RunnerRepository::new(state.db.clone());
+    repo.expire_commands().await?;
+    let commands = repo.list_commands(id, RUNNER_COMMAND_LIST_LIMIT).await?;
+
+    Ok(Json(commands))
+}
+
+/// 获取 Runner 控制指令的执行状态
+pub async fn get_runner_command(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path((id, command_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "read", None, None)
+        .await?;
+
+    let repo = RunnerRepository::new(state.db.clone());
+    repo.expire_commands().await?;
+    let command = repo
+        .get_command(id, command_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Runner command not found"))?;
+
+    Ok(Json(command))
+}
+
+/// 签发 Runner 注册令牌
+pub async fn create_enrollment_token(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(request): Json<CreateEnrollmentTokenRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "write", None, None)
+        .await?;
+
+    let runner_name = request.runner_name.trim();
+    if runner_name.is_empty() {
+        return Err(AppError::validation("Runner name is required"));
+    }
+    let ttl_secs = request.ttl_secs.unwrap_or(DEFAULT_ENROLLMENT_TTL_SECS);
+    if ttl_secs == 0 || ttl_secs > MAX_ENROLLMENT_TTL_SECS {
+        return Err(AppError::validation(&format!(
+            "Enrollment token TTL must be between 1 and {} seconds",
+            MAX_ENROLLMENT_TTL_SECS
+        )));
+    }
+
+    let token = format!("ops_enr_{}", Alphanumeric.sample_string(&mut rand::rng(), 32));
+    let repo = RunnerRepository::new(state.db.clone());
+    let enrollment = repo
+        .create_enrollment_token(
+            &ApiKeyGenerator::hash(&token),
+            runner_name,
+            &request.labels,
+            auth.user_id,
+            Utc::now() + chrono::Duration::seconds(ttl_secs as i64),
+        )
+        .await?;
+
+    let _ = state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: auth.user_id,
+            subject_type: "user",
+            subject_name: Some(&auth.username),
+            action: AuditAction::RunnerEnrollmentTokenCreate.as_str(),
+            resource_type: "runner_enrollment_token",
+            resource_id: Some(enrollment.id),
+            resource_name: Some(runner_name),
+            changes: Some(serde_json::json!({
+                "labels": request.labels,
+                "expires_at": enrollment.expires_at,
+            })),
+            changes_summary: Some(&format!("Created enrollment token for runner: {}", runner_name)),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        })
+        .await;
+
+    Ok((StatusCode::CREATED, Json(EnrollmentTokenResponse { token, enrollment })))
+}
+
+/// 列出待使用的 Runner 注册令牌
+pub async fn list_enrollment_tokens(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "read", None, None)
+        .await?;
+
+    let repo = RunnerRepository::new(state.db.clone());
+    let tokens = repo.list_pending_enrollment_tokens().await?;
+
+    Ok(Json(tokens))
+}
+
+/// 撤销未使用的 Runner 注册令牌
+pub async fn revoke_enrollment_token(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "write", None, None)
+        .await?;
+
+    let repo = RunnerRepository::new(state.db.clone());
+    if !repo.revoke_enrollment_token(id).await? {
+        return Err(AppError::not_found("Pending enrollment token not found"));
+    }
+
+    let _ = state
+        .audit_service
+        .log_action(AuditLogParams {
+            subject_id: auth.user_id,
+            subject_type: "user",
+            subject_name: Some(&auth.username),
+            action: AuditAction::RunnerEnrollmentTokenRevoke.as_str(),
+            resource_type: "runner_enrollment_token",
+            resource_id: Some(id),
+            resource_name: None,
+            changes: None,
+            changes_summary: Some("Revoked runner enrollment token"),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        })
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// 更新 Runner 状态请求
 #[derive(Debug, Deserialize)]
 pub struct UpdateRunnerStatusRequest {
     /// 新状态（active, maintenance, disabled）
     pub status: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_satisfied() {
+        let caps = vec!["docker".to_string(), "rust".to_string()];
+        assert!(labels_satisfied(&[], &caps));
+        assert!(labels_satisfied(&["rust".to_string()], &caps));
+        assert!(!labels_satisfied(&["rust".to_string(), "java".to_string()], &caps));
+    }
+
+    #[test]
+    fn test_ensure_runner_identity() {
+        let runner = AuthenticatedRunner {
+            runner_id: Uuid::new_v4(),
+            name: "runner-a".to_string(),
+        };
+        assert!(ensure_runner_identity(Some(&runner), "runner-a").is_ok());
+        assert!(ensure_runner_identity(Some(&runner), "runner-b").is_err());
+        // 共享 API Key 不绑定 Runner 名称
+        assert!(ensure_runner_identity(None, "runner-b").is_ok());
+    }
+
+    #[test]
+    fn test_merge_runner_tasks() {
+        let now = Utc::now();
+        let reported_job = Uuid::new_v4();
+        let tracked_job = Uuid::new_v4();
+        let finished_job = Uuid::new_v4();
+        let job = |id: Uuid, status: &str, tracked: bool| RunnerTaskJobRow {
+            id,
+            project_name: "api".to_string(),
+            repository: "https://example.com/api.git".to_string(),
+            branch: "main".to_string(),
+            status: status.to_string(),
+            started_at: Some(now),
+            total_steps: 3,
+            tracked,
+        };
+        let step = |job_id: Uuid, step_id: &str, status: &str| RunnerTaskStepRow {
+            job_id,
+            step_id: step_id.to_string(),
+            step_name: step_id.to_string(),
+            status: status.to_string(),
+            started_at: Some(now),
+        };
+
+        let reported = vec![RunningTaskInfo {
+            job_id: reported_job,
+            task_id: Uuid::new_v4(),
+            started_at: now,
+        }];
+        let jobs = vec![
+            job(reported_job, "running", true),
+            job(tracked_job, "pending", true),
+            // 未被 Runner 上报且已结束的作业不列出
+            job(finished_job, "completed", false),
+        ];
+        let steps = vec![
+            step(reported_job, "install", "succeeded"),
+            step(reported_job, "build", "running"),
+        ];
+
+        let tasks = merge_runner_tasks(&reported, jobs, &steps);
+        assert_eq!(tasks.len(), 2);
+
+        let first = &tasks[0];
+        assert_eq!(first.job_id, reported_job);
+        assert!(first.reported_by_runner && first.tracked);
+        assert_eq!(first.steps.total, 3);
+        assert_eq!(first.steps.finished, 1);
+        assert_eq!(first.steps.current_step_id.as_deref(), Some("build"));
+
+        let second = &tasks[1];
+        assert_eq!(second.job_id, tracked_job);
+        assert!(!second.reported_by_runner && second.tracked);
+        assert!(second.task_id.is_none());
+        assert_eq!(second.status.as_deref(), Some("pending"));
+    }
+
+    #[test]
+    fn test_heartbeat_command_acks_optional() {
+        let body = serde_json::json!({
+            "name": "runner-a",
+            "status": "active",
+            "current_jobs": 0,
+            "system": {
+                "cpu_usage_percent": 1.0,
+                "memory_usage_percent": 1.0,
+                "disk_usage_percent": 1.0,
+                "available_memory_mb": 1024,
+                "available_disk_gb": 10.0
+            }
+        });
+        let request: RunnerHeartbeatRequest = serde_json::from_value(body.clone()).unwrap();
+        assert!(request.command_acks.is_empty());
+
+        let command_id = Uuid::new_v4();
+        let mut body = body;
+        body["command_acks"] = serde_json::json!([{
+            "command_id": command_id,
+            "success": true,
+            "message": "Build consumption paused",
+            "timestamp": Utc::now()
+        }]);
+        let request: RunnerHeartbeatRequest = serde_json::from_value(body).unwrap();
+        assert_eq!(request.command_acks.len(), 1);
+        assert_eq!(request.command_acks[0].command_id, command_id);
+    }
+}
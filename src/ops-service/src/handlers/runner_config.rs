@@ -18,10 +18,15 @@ use crate::{
     error::{AppError, Result},
     middleware::AppState,
     models::runner_config::{
-        RunnerConfigHistory, RunnerConfigHistoryResponse, RunnerConfigOverride, RunnerDockerConfig,
-        RunnerDockerConfigListResponse, RunnerDockerConfigRequest, RunnerDockerConfigResponse,
+        RunnerConfigHistory, RunnerConfigHistoryResponse, RunnerConfigOverride,
+        RunnerConfigRollout, RunnerDockerConfig, RunnerDockerConfigListResponse,
+        RunnerDockerConfigRequest, RunnerDockerConfigResponse,
+    },
+    services::{
+        audit_service::AuditAction,
+        runner_config_rollout::{select_canaries, NewRunnerConfigRollout},
+        RunnerConfigRolloutService,
     },
-    services::audit_service::AuditAction,
 };
 
 // ==================== Request/Response ====================
@@ -64,6 +69,72 @@ pub struct UpdateRunnerDockerConfigRequest {
     pub change_reason: Option<String>,
 }
 
+impl UpdateRunnerDockerConfigRequest {
+    /// 将变更应用到配置（生成灰度发布的候选配置），返回是否有变更
+    fn apply_to(&self, config: &mut RunnerDockerConfig) -> std::result::Result<bool, String> {
+        let mut changed = false;
+
+        if let Some(enabled) = self.enabled {
+            config.enabled = enabled;
+            changed = true;
+        }
+        if let Some(ref image) = self.default_image {
+            config.default_image = image.clone();
+            changed = true;
+        }
+        if let Some(timeout) = self.default_timeout_secs {
+            if !(60..=86400).contains(&timeout) {
+                return Err("Timeout must be between 60 and 86400 seconds".to_string());
+            }
+            config.default_timeout_secs = timeout;
+            changed = true;
+        }
+        if let Some(memory) = self.memory_limit_gb {
+            if !(1..=128).contains(&memory) {
+                return Err("Memory limit must be between 1 and 128 GB".to_string());
+            }
+            config.memory_limit_gb = Some(memory);
+            changed = true;
+        }
+        if let Some(cpu) = self.cpu_shares {
+            if !(128..=4096).contains(&cpu) {
+                return Err("CPU shares must be between 128 and 4096".to_string());
+            }
+            config.cpu_shares = Some(cpu);
+            changed = true;
+        }
+        if let Some(pids) = self.pids_limit {
+            if !(64..=65536).contains(&pids) {
+                return Err("PIDs limit must be between 64 and 65536".to_string());
+            }
+            config.pids_limit = Some(pids);
+            changed = true;
+        }
+        if let Some(ref images) = self.images_by_type {
+            config.images_by_type =
+                sqlx::types::Json(serde_json::to_value(images).unwrap_or(serde_json::json!({})));
+            changed = true;
+        }
+        if let Some(ref capability) = self.per_capability {
+            config.per_capability = sqlx::types::Json(
+                serde_json::to_value(capability).unwrap_or(serde_json::json!({})),
+            );
+            changed = true;
+        }
+        if let Some(ref runner) = self.per_runner {
+            config.per_runner =
+                sqlx::types::Json(serde_json::to_value(runner).unwrap_or(serde_json::json!({})));
+            changed = true;
+        }
+        if let Some(ref desc) = self.description {
+            config.description = Some(desc.clone());
+            changed = true;
+        }
+
+        Ok(changed)
+    }
+}
+
 /// 设置活跃配置请求
 #[derive(Debug, Deserialize)]
 pub struct SetActiveConfigRequest {
@@ -71,6 +142,80 @@ pub struct SetActiveConfigRequest {
     pub config_id: Uuid,
 }
 
+/// 创建灰度发布请求（配置变更字段与更新请求相同）
+#[derive(Debug, Deserialize)]
+pub struct CreateRunnerConfigRolloutRequest {
+    /// 配置变更
+    #[serde(flatten)]
+    pub changes: UpdateRunnerDockerConfigRequest,
+
+    /// 金丝雀比例（1-100，不指定时选择全部匹配标签的 Runner）
+    pub percent: Option<i32>,
+
+    /// 金丝雀 Runner 需具备的全部能力标签
+    #[serde(default)]
+    pub labels: Vec<String>,
+
+    /// 观察期（秒）
+    #[serde(default = "default_bake_secs")]
+    pub bake_secs: i64,
+
+    /// 允许的最大构建失败率（0-1），超过时自动回滚
+    #[serde(default = "default_max_error_rate")]
+    pub max_error_rate: f64,
+
+    /// 判定失败率所需的最少完成构建数
+    #[serde(default)]
+    pub min_jobs: i64,
+
+    /// 观察通过后是否自动写入配置
+    #[serde(default = "default_auto_promote")]
+    pub auto_promote: bool,
+}
+
+fn default_bake_secs() -> i64 {
+    600
+}
+
+fn default_max_error_rate() -> f64 {
+    0.1
+}
+
+fn default_auto_promote() -> bool {
+    true
+}
+
+impl CreateRunnerConfigRolloutRequest {
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.percent.is_none() && self.labels.is_empty() {
+            return Err("Either percent or labels is required to select canary runners".to_string());
+        }
+        if let Some(percent) = self.percent {
+            if !(1..=100).contains(&percent) {
+                return Err("Percent must be between 1 and 100".to_string());
+            }
+        }
+        if !(0..=7 * 86400).contains(&self.bake_secs) {
+            return Err("Bake period must be between 0 and 604800 seconds".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.max_error_rate) {
+            return Err("Max error rate must be between 0 and 1".to_string());
+        }
+        if self.min_jobs < 0 {
+            return Err("Min jobs must not be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 回滚灰度发布请求
+#[derive(Debug, Deserialize)]
+pub struct RollbackRunnerConfigRolloutRequest {
+    /// 回滚原因
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
 // ==================== Handler Functions ====================
 
 /// 获取所有 Runner Docker 配置
@@ -84,7 +229,7 @@ pub async fn list_runner_configs(
         .await?;
 
     let rows = sqlx::query_as::<_, RunnerDockerConfig>(
-        "SELECT id, name, enabled, default_image, default_timeout_secs,
+        "SELECT id, name, version, enabled, default_image, default_timeout_secs,
                 memory_limit_gb, cpu_shares, pids_limit, images_by_type,
                 per_capability, per_runner, description, created_at, updated_at
          FROM runner_docker_configs
@@ -117,7 +262,7 @@ pub async fn get_runner_config(
         .await?;
 
     let config = sqlx::query_as::<_, RunnerDockerConfig>(
-        "SELECT id, name, enabled, default_image, default_timeout_secs,
+        "SELECT id, name, version, enabled, default_image, default_timeout_secs,
                 memory_limit_gb, cpu_shares, pids_limit, images_by_type,
                 per_capability, per_runner, description, created_at, updated_at
          FROM runner_docker_configs
@@ -205,7 +350,7 @@ pub async fn create_runner_config(
 
     // 获取并返回创建的配置
     let config = sqlx::query_as::<_, RunnerDockerConfig>(
-        "SELECT id, name, enabled, default_image, default_timeout_secs,
+        "SELECT id, name, version, enabled, default_image, default_timeout_secs,
                 memory_limit_gb, cpu_shares, pids_limit, images_by_type,
                 per_capability, per_runner, description, created_at, updated_at
          FROM runner_docker_configs
@@ -251,7 +396,7 @@ pub async fn update_runner_config(
 
     // 获取当前配置
     let current = sqlx::query_as::<_, RunnerDockerConfig>(
-        "SELECT id, name, enabled, default_image, default_timeout_secs,
+        "SELECT id, name, version, enabled, default_image, default_timeout_secs,
                 memory_limit_gb, cpu_shares, pids_limit, images_by_type,
                 per_capability, per_runner, description, created_at, updated_at
          FROM runner_docker_configs
@@ -319,6 +464,17 @@ pub async fn update_runner_config(
         return Ok(Json::<RunnerDockerConfigResponse>(current.into()));
     }
 
+    // 灰度发布进行中时只能通过发布流程变更配置
+    if RunnerConfigRolloutService::new(state.db.clone())
+        .has_active(id)
+        .await?
+    {
+        return Err(AppError::validation(
+            "Runner config has an active rollout, promote or roll it back first",
+        ));
+    }
+
+    updates.push("version = version + 1".to_string());
     updates.push("updated_at = NOW()".to_string());
 
     let query_str =
@@ -381,7 +537,7 @@ pub async fn update_runner_config(
     // 记录历史
     let old_config_json = serde_json::to_value(&current).unwrap_or(serde_json::json!({}));
     let new_config = sqlx::query_as::<_, RunnerDockerConfig>(
-        "SELECT id, name, enabled, default_image, default_timeout_secs,
+        "SELECT id, name, version, enabled, default_image, default_timeout_secs,
                 memory_limit_gb, cpu_shares, pids_limit, images_by_type,
                 per_capability, per_runner, description, created_at, updated_at
          FROM runner_docker_configs
@@ -398,13 +554,14 @@ pub async fn update_runner_config(
 
     let _ = sqlx::query(
         "INSERT INTO runner_config_history
-         (config_id, old_config, new_config, change_reason, created_at)
-         VALUES ($1, $2, $3, $4, NOW())",
+         (config_id, old_config, new_config, change_reason, version, created_at)
+         VALUES ($1, $2, $3, $4, $5, NOW())",
     )
     .bind(id)
     .bind(sqlx::types::Json(old_config_json))
     .bind(sqlx::types::Json(new_config_json))
     .bind(&request.change_reason)
+    .bind(new_config.version)
     .execute(&state.db)
     .await
     .map_err(|e| {
@@ -519,7 +676,8 @@ pub async fn get_config_history(
         .ok_or_else(|| AppError::not_found("Runner config not found"))?;
 
     let history = sqlx::query_as::<_, RunnerConfigHistory>(
-        "SELECT id, config_id, old_config, new_config, change_reason, changed_by, created_at
+        "SELECT id, config_id, old_config, new_config, change_reason, changed_by, version,
+                created_at
          FROM runner_config_history
          WHERE config_id = $1
          ORDER BY created_at DESC
@@ -588,6 +746,250 @@ pub async fn get_active_runner_config(
     }
 }
 
+// ==================== Canary Rollout ====================
+
+/// 获取属于指定配置的灰度发布
+async fn find_rollout(
+    service: &RunnerConfigRolloutService,
+    config_id: Uuid,
+    rollout_id: Uuid,
+) -> Result<RunnerConfigRollout> {
+    service
+        .get(rollout_id)
+        .await?
+        .filter(|r| r.config_id == config_id)
+        .ok_or_else(|| AppError::not_found("Rollout not found"))
+}
+
+/// 创建灰度发布：候选配置先下发给选中的金丝雀 Runner
+pub async fn create_runner_config_rollout(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CreateRunnerConfigRolloutRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "write", None, None)
+        .await?;
+
+    request.validate().map_err(|e| AppError::validation(&e))?;
+
+    let current = sqlx::query_as::<_, RunnerDockerConfig>(
+        "SELECT id, name, version, enabled, default_image, default_timeout_secs,
+                memory_limit_gb, cpu_shares, pids_limit, images_by_type,
+                per_capability, per_runner, description, created_at, updated_at
+         FROM runner_docker_configs
+         WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, config_id = %id, "Failed to get current config");
+        AppError::database("Failed to get current config")
+    })?
+    .ok_or_else(|| AppError::not_found("Runner config not found"))?;
+
+    // 心跳只下发 default 配置
+    if current.name != "default" {
+        return Err(AppError::validation("Only the default runner config can be rolled out"));
+    }
+
+    let mut candidate = current.clone();
+    let changed = request
+        .changes
+        .apply_to(&mut candidate)
+        .map_err(|e| AppError::validation(&e))?;
+    if !changed {
+        return Err(AppError::validation("Rollout must change at least one field"));
+    }
+    candidate.version = current.version + 1;
+
+    // 从在线且支持 Docker 的 Runner 中选择金丝雀
+    let rows = sqlx::query(
+        "SELECT name, capabilities FROM runners
+         WHERE docker_supported AND status = 'active'
+           AND last_heartbeat > NOW() - INTERVAL '2 minutes'",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to list runners for rollout");
+        AppError::database("Failed to list runners for rollout")
+    })?;
+    let runners: Vec<(String, Vec<String>)> = rows
+        .iter()
+        .map(|row| {
+            let capabilities: serde_json::Value = row.get("capabilities");
+            (row.get("name"), serde_json::from_value(capabilities).unwrap_or_default())
+        })
+        .collect();
+
+    let canary_runners =
+        select_canaries(&runners, &request.labels, request.percent, &Uuid::new_v4().to_string());
+    if canary_runners.is_empty() {
+        return Err(AppError::validation("No online runners match the rollout selector"));
+    }
+
+    let service = RunnerConfigRolloutService::new(state.db.clone());
+    let rollout = service
+        .create(NewRunnerConfigRollout {
+            config_id: id,
+            base_version: current.version,
+            candidate,
+            change_reason: request.changes.change_reason.clone(),
+            percent: request.percent,
+            labels: request.labels.clone(),
+            canary_runners,
+            bake_secs: request.bake_secs,
+            max_error_rate: request.max_error_rate,
+            min_jobs: request.min_jobs,
+            auto_promote: request.auto_promote,
+            created_by: auth.user_id,
+        })
+        .await?;
+
+    info!(
+        rollout_id = %rollout.id,
+        config_id = %id,
+        version = rollout.target_version,
+        canaries = rollout.canary_runners.0.len(),
+        "Runner config rollout started"
+    );
+
+    // 审计日志
+    let _ = state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::RunnerConfigRolloutStart,
+            Some("runner_config"),
+            Some(id),
+            Some(&format!(
+                "Started rollout of runner config {} v{} to {} canary runners, reason: {}",
+                current.name,
+                rollout.target_version,
+                rollout.canary_runners.0.len(),
+                request.changes.change_reason.as_deref().unwrap_or("N/A")
+            )),
+            None,
+        )
+        .await;
+
+    let canaries = service.canary_status(&rollout).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "rollout": rollout, "canaries": canaries })),
+    ))
+}
+
+/// 列出配置的灰度发布
+pub async fn list_runner_config_rollouts(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "read", None, None)
+        .await?;
+
+    let rollouts = RunnerConfigRolloutService::new(state.db.clone())
+        .list(id)
+        .await?;
+    let total = rollouts.len();
+
+    Ok(Json(serde_json::json!({ "rollouts": rollouts, "total": total })))
+}
+
+/// 获取灰度发布及金丝雀 Runner 的应用状态
+pub async fn get_runner_config_rollout(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path((id, rollout_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "read", None, None)
+        .await?;
+
+    let service = RunnerConfigRolloutService::new(state.db.clone());
+    let rollout = find_rollout(&service, id, rollout_id).await?;
+    let canaries = service.canary_status(&rollout).await?;
+
+    Ok(Json(serde_json::json!({ "rollout": rollout, "canaries": canaries })))
+}
+
+/// 手动写入候选配置（跳过剩余观察期）
+pub async fn promote_runner_config_rollout(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path((id, rollout_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "write", None, None)
+        .await?;
+
+    let service = RunnerConfigRolloutService::new(state.db.clone());
+    find_rollout(&service, id, rollout_id).await?;
+    let rollout = service.promote(rollout_id, Some(auth.user_id)).await?;
+
+    // 审计日志
+    let _ = state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::RunnerConfigRolloutPromote,
+            Some("runner_config"),
+            Some(id),
+            Some(&format!("Promoted runner config rollout to v{}", rollout.target_version)),
+            None,
+        )
+        .await;
+
+    Ok(Json(rollout))
+}
+
+/// 手动回滚灰度发布
+pub async fn rollback_runner_config_rollout(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path((id, rollout_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<RollbackRunnerConfigRolloutRequest>,
+) -> Result<impl IntoResponse> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "runner", "write", None, None)
+        .await?;
+
+    let service = RunnerConfigRolloutService::new(state.db.clone());
+    find_rollout(&service, id, rollout_id).await?;
+    let reason = request
+        .reason
+        .unwrap_or_else(|| "Rolled back manually".to_string());
+    let rollout = service.roll_back(rollout_id, &reason).await?;
+
+    // 审计日志
+    let _ = state
+        .audit_service
+        .log_action_simple(
+            auth.user_id,
+            AuditAction::RunnerConfigRolloutRollback,
+            Some("runner_config"),
+            Some(id),
+            Some(&format!(
+                "Rolled back runner config rollout v{}, reason: {}",
+                rollout.target_version, reason
+            )),
+            None,
+        )
+        .await;
+
+    Ok(Json(rollout))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -595,7 +997,7 @@ mod tests {
 
     #[test]
     fn test_default_enabled() {
-        assert_eq!(default_enabled(), true);
+        assert!(default_enabled());
     }
 
     #[test]
@@ -617,4 +1019,51 @@ mod tests {
         assert_eq!(request.enabled, Some(true));
         assert_eq!(request.change_reason, Some("Test update".to_string()));
     }
+
+    #[test]
+    fn test_rollout_request_builds_candidate() {
+        let request: CreateRunnerConfigRolloutRequest = serde_json::from_value(serde_json::json!({
+            "default_image": "ubuntu:24.04",
+            "memory_limit_gb": 8,
+            "change_reason": "Upgrade base image",
+            "percent": 10,
+        }))
+        .unwrap();
+        assert!(request.validate().is_ok());
+        assert_eq!(request.bake_secs, 600);
+        assert!(request.auto_promote);
+
+        let mut config = RunnerDockerConfig {
+            id: Uuid::new_v4(),
+            name: "default".to_string(),
+            version: 4,
+            enabled: true,
+            default_image: "ubuntu:22.04".to_string(),
+            default_timeout_secs: 1800,
+            memory_limit_gb: Some(4),
+            cpu_shares: Some(1024),
+            pids_limit: Some(1024),
+            images_by_type: sqlx::types::Json(serde_json::json!({})),
+            per_capability: sqlx::types::Json(serde_json::json!({})),
+            per_runner: sqlx::types::Json(serde_json::json!({})),
+            description: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert_eq!(request.changes.apply_to(&mut config), Ok(true));
+        assert_eq!(config.default_image, "ubuntu:24.04");
+        assert_eq!(config.memory_limit_gb, Some(8));
+        assert_eq!(config.cpu_shares, Some(1024));
+
+        // 未指定比例和标签时无法选择金丝雀
+        let unscoped: CreateRunnerConfigRolloutRequest =
+            serde_json::from_value(serde_json::json!({ "cpu_shares": 2048 })).unwrap();
+        assert!(unscoped.validate().is_err());
+
+        // 超出范围的变更被拒绝
+        let invalid: CreateRunnerConfigRolloutRequest =
+            serde_json::from_value(serde_json::json!({ "pids_limit": 10, "labels": ["gpu"] }))
+                .unwrap();
+        assert!(invalid.changes.apply_to(&mut config).is_err());
+    }
 }
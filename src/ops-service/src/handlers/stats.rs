@@ -0,0 +1,135 @@
+//! Statistics API handlers
+//! 运维看板统计接口：读取后台任务预聚合的数据
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::{AppError, Result},
+    middleware::AppState,
+    models::{
+        handover::HandoverQuery,
+        stats::{ConcurrencyHistoryQuery, ConcurrencyTimelineQuery, StatsQuery},
+    },
+    services::handover,
+};
+
+/// 统计覆盖所有作业与主机，要求管理员或 job:read_all 权限
+async fn require_stats_access(state: &AppState, auth_context: &AuthContext) -> Result<()> {
+    let is_admin = state
+        .permission_service
+        .is_admin(auth_context.user_id)
+        .await
+        .unwrap_or(false);
+    let can_read_all = state
+        .permission_service
+        .check_permission(auth_context.user_id, "job", "read_all", None, None)
+        .await
+        .unwrap_or(false);
+    if !is_admin && !can_read_all {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
+
+/// 每日作业数量（按类型与状态）
+pub async fn get_jobs_daily(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StatsQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_stats_access(&state, &auth_context).await?;
+    let stats = state.stats_service.jobs_daily(&query).await?;
+    Ok(Json(stats))
+}
+
+/// 失败作业的平均恢复时间（MTTR）
+pub async fn get_job_recovery(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StatsQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_stats_access(&state, &auth_context).await?;
+    let stats = state.stats_service.job_recovery(&query).await?;
+    Ok(Json(stats))
+}
+
+/// 任务失败最多的主机
+pub async fn get_top_failing_hosts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StatsQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_stats_access(&state, &auth_context).await?;
+    let stats = state.stats_service.top_failing_hosts(&query).await?;
+    Ok(Json(stats))
+}
+
+/// 最繁忙的 Runner
+pub async fn get_busiest_runners(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StatsQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_stats_access(&state, &auth_context).await?;
+    let stats = state.stats_service.busiest_runners(&query).await?;
+    Ok(Json(stats))
+}
+
+/// 审批耗时分位数
+pub async fn get_approval_turnaround(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StatsQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_stats_access(&state, &auth_context).await?;
+    let stats = state.stats_service.approval_turnaround(&query).await?;
+    Ok(Json(stats))
+}
+
+/// 并发使用率历史
+pub async fn get_concurrency_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ConcurrencyHistoryQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_stats_access(&state, &auth_context).await?;
+    let samples = state.stats_service.concurrency_history(&query).await?;
+    Ok(Json(samples))
+}
+
+/// 并发时间线（按分组/环境，用于容量规划）
+pub async fn get_concurrency_timeline(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ConcurrencyTimelineQuery>,
+    auth_context: AuthContext,
+) -> Result<impl IntoResponse> {
+    require_stats_access(&state, &auth_context).await?;
+    let timeline = state.stats_service.concurrency_timeline(&query).await?;
+    Ok(Json(timeline))
+}
+
+/// 交接班报告：时间窗口内的作业、待处理审批、离线 Runner、未恢复的失败主机与关键审计
+pub async fn get_handover_report(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HandoverQuery>,
+    auth_context: AuthContext,
+) -> Result<Response> {
+    require_stats_access(&state, &auth_context).await?;
+    let (from, to) = query
+        .window(chrono::Utc::now())
+        .map_err(|e| AppError::validation(&e))?;
+    let report = handover::generate(&state.db, from, to).await?;
+
+    if query.wants_markdown() {
+        return Ok(([(header::CONTENT_TYPE, "text/markdown; charset=utf-8")], report.markdown)
+            .into_response());
+    }
+    Ok(Json(report).into_response())
+}
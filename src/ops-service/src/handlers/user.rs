@@ -1,11 +1,11 @@
 //! 用户管理的 HTTP 处理器
 
 use crate::{
-    auth::middleware::AuthContext, auth::password::PasswordHasher, error::AppError,
+    auth::middleware::AuthContext, auth::password::PasswordHasher, error::AppError, i18n::Locale,
     middleware::AppState, models::user::*, services::audit_service::AuditAction,
 };
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::IntoResponse,
     Json,
 };
@@ -13,6 +13,9 @@ use serde_json::json;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::models::soft_delete::{DeletedResourceQuery, SoftDeleteResource};
+use crate::services::soft_delete;
+
 /// 列出用户
 pub async fn list_users(
     State(state): State<Arc<AppState>>,
@@ -73,7 +76,7 @@ pub async fn create_user(
         .await?;
 
     Ok(Json(json!({
-        "message": "用户创建成功",
+        "message": crate::i18n::t("notice.user.created"),
         "user": UserResponse::from(user)
     })))
 }
@@ -132,7 +135,7 @@ pub async fn update_user(
         .await?;
 
     Ok(Json(json!({
-        "message": "用户更新成功",
+        "message": crate::i18n::t("notice.user.updated"),
         "user": UserResponse::from(user)
     })))
 }
@@ -163,7 +166,17 @@ pub async fn delete_user(
 
     let username = user.username.clone();
 
-    repo.delete(id).await?;
+    soft_delete::mark_deleted(&state.db, SoftDeleteResource::User, id, auth_context.user_id)
+        .await?;
+    // 已删除的用户不能再登录，已签发的令牌随会话撤销失效
+    state
+        .auth_service
+        .revoke_all_sessions(
+            id,
+            auth_context.user_id,
+            crate::auth::sessions::REVOKE_REASON_USER_DELETED,
+        )
+        .await?;
 
     // 审计日志
     state
@@ -179,16 +192,186 @@ pub async fn delete_user(
         .await?;
 
     Ok(Json(json!({
-        "message": "用户删除成功"
+        "message": crate::i18n::t("notice.user.deleted")
     })))
 }
 
+/// 列出已删除（保留期内可恢复）的用户
+pub async fn list_deleted_users(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Query(query): Query<DeletedResourceQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "user", "read", None, None)
+        .await?;
+
+    let list = soft_delete::deleted_list(
+        &state.db,
+        &state.config.soft_delete,
+        SoftDeleteResource::User,
+        &query,
+    )
+    .await?;
+    Ok(Json(list))
+}
+
+/// 恢复已删除的用户
+pub async fn restore_user(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // 检查权限
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "user", "write", None, None)
+        .await?;
+
+    if !soft_delete::restore(&state.db, SoftDeleteResource::User, id).await? {
+        return Err(AppError::not_found("Deleted user not found"));
+    }
+
+    let repo = crate::repository::UserRepository::new(state.db.clone());
+    let user = repo
+        .find_by_id(&id)
+        .await?
+        .ok_or_else(|| AppError::not_found("User not found"))?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserRestore,
+            Some("user"),
+            Some(id),
+            Some(&format!("Restored user: {}", user.username)),
+            None,
+        )
+        .await?;
+
+    Ok(Json(json!({
+        "message": crate::i18n::t("notice.user.restored"),
+        "user": UserResponse::from(user)
+    })))
+}
+
+/// 列出指定用户的有效登录会话
+pub async fn list_user_sessions(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "user", "read", None, None)
+        .await?;
+
+    let sessions = state
+        .auth_service
+        .list_sessions(id, auth_context.session_id)
+        .await?;
+
+    Ok(Json(sessions))
+}
+
+/// 撤销指定用户的全部登录会话（强制其在所有设备上重新登录）
+pub async fn revoke_user_sessions(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "user", "write", None, None)
+        .await?;
+
+    let repo = crate::repository::UserRepository::new(state.db.clone());
+    let user = repo
+        .find_by_id(&id)
+        .await?
+        .ok_or_else(|| AppError::not_found("User not found"))?;
+
+    let revoked_count = state
+        .auth_service
+        .revoke_all_sessions(id, auth_context.user_id, crate::auth::sessions::REVOKE_REASON_ADMIN)
+        .await?;
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserSessionRevoke,
+            Some("user"),
+            Some(id),
+            Some(&format!("Revoked all {} sessions of user: {}", revoked_count, user.username)),
+            None,
+        )
+        .await?;
+
+    Ok(Json(json!({
+        "message": crate::i18n::tf("notice.user.sessions_revoked", &[("count", &revoked_count)])
+    })))
+}
+
+/// 重置用户的双因素认证（用户丢失认证器与恢复码时由管理员操作）
+pub async fn reset_user_two_factor(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .permission_service
+        .require_permission(auth_context.user_id, "user", "write", None, None)
+        .await?;
+    state
+        .auth_service
+        .two_factor()
+        .require_step_up(&auth_context)
+        .await?;
+
+    let repo = crate::repository::UserRepository::new(state.db.clone());
+    let user = repo
+        .find_by_id(&id)
+        .await?
+        .ok_or_else(|| AppError::not_found("User not found"))?;
+
+    if !state.auth_service.two_factor().reset(id).await? {
+        return Err(AppError::BadRequest("用户未绑定双因素认证".to_string()));
+    }
+
+    // 审计日志
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserTwoFactorReset,
+            Some("user"),
+            Some(id),
+            Some(&format!("Reset two-factor authentication of user: {}", user.username)),
+            None,
+        )
+        .await?;
+
+    Ok(Json(json!({"message": crate::i18n::t("notice.user.two_factor_reset")})))
+}
+
 /// 修改密码
 pub async fn change_password(
     State(state): State<Arc<AppState>>,
     auth_context: AuthContext,
     Json(req): Json<ChangePasswordRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    // 凭证变更需二次验证
+    state
+        .auth_service
+        .two_factor()
+        .require_step_up(&auth_context)
+        .await?;
+
     let repo = crate::repository::UserRepository::new(state.db.clone());
     let user = repo
         .find_by_id(&auth_context.user_id)
@@ -222,6 +405,50 @@ pub async fn change_password(
         .await?;
 
     Ok(Json(json!({
-        "message": "密码修改成功"
+        "message": crate::i18n::t("notice.user.password_changed")
+    })))
+}
+
+/// 更新当前用户的语言偏好
+pub async fn update_my_locale(
+    State(state): State<Arc<AppState>>,
+    auth_context: AuthContext,
+    Json(req): Json<UpdateLocaleRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let locale = match req.locale.as_deref() {
+        Some(tag) => Some(Locale::parse(tag).ok_or_else(|| {
+            AppError::validation(&format!("Unsupported locale '{}' (use en-US or zh-CN)", tag))
+        })?),
+        None => None,
+    };
+
+    let repo = crate::repository::UserRepository::new(state.db.clone());
+    if !repo
+        .update_locale(auth_context.user_id, locale.map(|l| l.tag()))
+        .await?
+    {
+        return Err(AppError::not_found("User not found"));
+    }
+    state.user_locales.invalidate(auth_context.user_id);
+
+    let summary =
+        format!("User set language preference to {}", locale.map_or("default", |l| l.tag()));
+    state
+        .audit_service
+        .log_action_simple(
+            auth_context.user_id,
+            AuditAction::UserUpdate,
+            Some("user"),
+            Some(auth_context.user_id),
+            Some(&summary),
+            None,
+        )
+        .await?;
+
+    // 提示按新设置的语言输出
+    let locale = locale.unwrap_or_else(crate::i18n::current_locale);
+    Ok(Json(json!({
+        "locale": locale,
+        "message": crate::i18n::t_in(locale, "notice.user.locale_updated")
     })))
 }
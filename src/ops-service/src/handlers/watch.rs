@@ -0,0 +1,141 @@
+//! Watch handlers
+//! 关注作业、主机或模板，并通过 SSE 接收定向通知
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auth::middleware::AuthContext,
+    error::{AppError, Result},
+    handlers::{asset::check_host_access, audit::view_auditor, job::check_job_access},
+    middleware::AppState,
+    models::watch::*,
+    realtime::ScopeChecker,
+    services::view_audit::ViewTarget,
+};
+
+/// 关注作业、主机或模板
+/// 关注时校验对象的查看权限（无权限返回 404，反枚举）；重复关注返回已有记录
+pub async fn create_watch(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Json(request): Json<CreateWatchRequest>,
+) -> Result<impl IntoResponse> {
+    match request.target_type {
+        WatchTargetType::Job => {
+            state
+                .permission_service
+                .require_permission(auth.user_id, "job", "read", None, None)
+                .await?;
+            let job = state
+                .job_service
+                .get_job(request.target_id)
+                .await
+                .map_err(|_| AppError::not_found("Job not found"))?;
+            if !check_job_access(&state, auth.user_id, &job).await? {
+                return Err(AppError::not_found("Job not found"));
+            }
+        }
+        WatchTargetType::Host => {
+            state
+                .permission_service
+                .require_permission(auth.user_id, "asset", "read", None, None)
+                .await?;
+            let host = crate::repository::AssetRepository::new(state.db.clone())
+                .get_host(request.target_id)
+                .await?
+                .ok_or_else(|| AppError::not_found("Resource not found"))?;
+            if !check_host_access(&state, auth.user_id, &host).await? {
+                return Err(AppError::not_found("Resource not found"));
+            }
+        }
+        WatchTargetType::Template => {
+            state
+                .permission_service
+                .require_permission(auth.user_id, "job", "read", None, None)
+                .await?;
+        }
+    }
+
+    let watch = state
+        .job_service
+        .create_watch(auth.user_id, request)
+        .await?;
+    Ok((StatusCode::CREATED, Json(watch)))
+}
+
+/// 列出当前用户的关注
+pub async fn list_watches(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Query(query): Query<WatchListQuery>,
+) -> Result<impl IntoResponse> {
+    let watches = state
+        .job_service
+        .list_watches(auth.user_id, query.target_type)
+        .await?;
+    Ok(Json(watches))
+}
+
+/// 取消关注
+pub async fn delete_watch(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    state.job_service.delete_watch(auth.user_id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 订阅关注通知流（SSE）
+/// 只推送发给当前用户的通知；推送过程中按缓存周期复核作业查看权限
+pub async fn subscribe_notifications(
+    State(state): State<Arc<AppState>>,
+    auth: AuthContext,
+) -> Result<Response> {
+    state
+        .permission_service
+        .require_permission(auth.user_id, "job", "read", None, None)
+        .await?;
+
+    let checker_state = state.clone();
+    let user_id = auth.user_id;
+    let scope = ScopeChecker::new(ScopeChecker::DEFAULT_TTL, move || {
+        let state = checker_state.clone();
+        Box::pin(async move {
+            state
+                .permission_service
+                .check_permission(user_id, "job", "read", None, None)
+                .await
+                .unwrap_or(false)
+        })
+    })
+    .with_initial(true);
+
+    let stream = state
+        .event_bus
+        .subscribe_to_notifications(auth.user_id)
+        .with_scope_checker(scope)
+        .to_sse_stream()
+        .await?;
+    let stream = view_auditor(&state)
+        .track_stream(user_id, "notification", ViewTarget::new("watch", None).verbose(), stream)
+        .await;
+
+    let body = axum::body::Body::from_stream(stream);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .header("Connection", "keep-alive")
+        .header("X-Accel-Buffering", "no")
+        .body(body)
+        .map_err(|e| AppError::internal_error(&format!("Failed to create SSE response: {}", e)))
+}
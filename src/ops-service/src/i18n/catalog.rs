@@ -0,0 +1,305 @@
+//! 消息目录
+//!
+//! 每种语言一张静态表，键按用途分组：error.* 错误消息、error_code.* 错误码说明、
+//! notice.* 操作结果提示、notification.* 推送通知、report.* 报表表头。
+//! 新增消息时两张表需同时补充（由单元测试校验），`{name}` 为占位符
+
+use super::Locale;
+
+const EN_US: &[(&str, &str)] = &[
+    // 错误消息
+    ("error.unauthorized", "Authentication failed"),
+    ("error.authentication", "{detail}"),
+    ("error.forbidden", "Access denied"),
+    ("error.two_factor_required", "{detail}"),
+    ("error.not_found", "Resource not found: {detail}"),
+    ("error.bad_request", "{detail}"),
+    ("error.validation", "{detail}"),
+    ("error.payload_too_large", "Request body too large: {detail}"),
+    ("error.payload_limit", "{detail}"),
+    ("error.rate_limited", "Rate limit exceeded"),
+    ("error.timeout", "Request timeout: {detail}"),
+    ("error.maintenance", "{detail}"),
+    ("error.ssh_connection", "SSH connection failed"),
+    ("error.ssh_authentication", "SSH authentication failed"),
+    ("error.ssh_host_key", "SSH host key verification failed"),
+    ("error.ssh_execution", "SSH command execution failed"),
+    ("error.database", "Database error occurred"),
+    ("error.config", "Configuration error"),
+    ("error.internal", "Internal server error: {detail}"),
+    ("error.user.not_found", "User does not exist"),
+    (
+        "error.two_factor.already_enabled",
+        "Two-factor authentication is already enabled",
+    ),
+    ("error.two_factor.not_enrolling", "Two-factor enrollment has not been started"),
+    ("error.two_factor.not_enabled", "Two-factor authentication is not enabled"),
+    ("error.two_factor.invalid_code", "Invalid verification code"),
+    (
+        "error.two_factor.required_by_role",
+        "Your role requires two-factor authentication",
+    ),
+    (
+        "error.two_factor.session_required",
+        "The token is not bound to a login session; please log in again",
+    ),
+    (
+        "error.impersonation.nested",
+        "Cannot start an impersonation from an impersonated session",
+    ),
+    ("error.impersonation.self", "Cannot impersonate yourself"),
+    ("error.impersonation.reason_required", "An impersonation reason is required"),
+    (
+        "error.impersonation.duration_range",
+        "Impersonation duration must be between 1 and {max} seconds",
+    ),
+    ("error.impersonation.ended", "The impersonation session has already ended"),
+    ("error.impersonation.not_found", "Impersonation session does not exist"),
+    // 错误码说明
+    ("error_code.UNAUTHENTICATED", "Missing or invalid credentials"),
+    ("error_code.AUTHENTICATION_FAILED", "Authentication was rejected; see detail"),
+    ("error_code.PERMISSION_DENIED", "The caller lacks permission for this operation"),
+    (
+        "error_code.TWO_FACTOR_REQUIRED",
+        "A TOTP code, 2FA enrollment or recent step-up verification is required",
+    ),
+    (
+        "error_code.RESOURCE_NOT_FOUND",
+        "The requested resource does not exist or is not visible",
+    ),
+    ("error_code.BAD_REQUEST", "The request is malformed"),
+    (
+        "error_code.VALIDATION_FAILED",
+        "The request failed business validation; see detail",
+    ),
+    (
+        "error_code.PAYLOAD_TOO_LARGE",
+        "The request body exceeds the configured size limit",
+    ),
+    (
+        "error_code.PAYLOAD_LIMIT_EXCEEDED",
+        "A request field exceeds its length or count limit; see detail",
+    ),
+    ("error_code.RATE_LIMITED", "Rate or concurrency limit exceeded; retry later"),
+    ("error_code.TIMEOUT", "The operation timed out"),
+    (
+        "error_code.MAINTENANCE_MODE",
+        "The control plane is in read-only maintenance mode; retry after maintenance",
+    ),
+    (
+        "error_code.SSH_CONNECTION_FAILED",
+        "Could not connect to the target host over SSH",
+    ),
+    (
+        "error_code.SSH_AUTHENTICATION_FAILED",
+        "SSH authentication against the target host failed",
+    ),
+    (
+        "error_code.SSH_HOST_KEY_VERIFICATION_FAILED",
+        "The target host presented an SSH host key that does not match the pinned key",
+    ),
+    ("error_code.SSH_EXECUTION_FAILED", "The remote command could not be executed"),
+    ("error_code.DATABASE_ERROR", "A database error occurred"),
+    ("error_code.CONFIGURATION_ERROR", "The server is misconfigured"),
+    ("error_code.INTERNAL_ERROR", "An unexpected internal error occurred"),
+    // 操作结果提示
+    ("notice.asset_group.created", "Asset group created"),
+    ("notice.asset_group.updated", "Asset group updated"),
+    ("notice.asset_group.deleted", "Asset group deleted"),
+    (
+        "notice.asset_group.delete_pending_approval",
+        "Asset group deletion submitted for approval",
+    ),
+    ("notice.host.created", "Host created"),
+    ("notice.host.updated", "Host updated"),
+    ("notice.host.credentials_updated", "Host credentials updated"),
+    (
+        "notice.host.credentials_pending_approval",
+        "Host credential change submitted for approval",
+    ),
+    ("notice.host.deleted", "Host deleted"),
+    ("notice.host.restored", "Host restored"),
+    ("notice.host.maintenance_started", "Host entered maintenance"),
+    ("notice.host.maintenance_ended", "Host maintenance ended"),
+    ("notice.host.key_repinned", "Host key re-pinned"),
+    ("notice.auth.logged_out", "Logged out"),
+    ("notice.auth.logged_out_all", "Logged out from {count} devices"),
+    ("notice.auth.session_revoked", "Session revoked"),
+    ("notice.auth.two_factor_disabled", "Two-factor authentication disabled"),
+    ("notice.role.created", "Role created"),
+    ("notice.role.updated", "Role updated"),
+    ("notice.role.deleted", "Role deleted"),
+    ("notice.role.assigned", "Role assigned"),
+    ("notice.role.revoked", "Role revoked"),
+    ("notice.user.created", "User created"),
+    ("notice.user.updated", "User updated"),
+    ("notice.user.deleted", "User deleted"),
+    ("notice.user.restored", "User restored"),
+    ("notice.user.password_changed", "Password changed"),
+    ("notice.user.locale_updated", "Language preference updated"),
+    ("notice.user.sessions_revoked", "Revoked {count} sessions"),
+    ("notice.user.two_factor_reset", "Two-factor authentication reset"),
+    // 推送通知
+    (
+        "notification.watch_status_changed",
+        "{target_type} {target_id} changed from {old_status} to {new_status}",
+    ),
+    ("notification.watch_status", "{target_type} {target_id} is now {new_status}"),
+    (
+        "notification.approval_reminder",
+        "Approval \"{title}\" has used {percent}% of its time limit and expires at {expires_at}",
+    ),
+    (
+        "notification.approval_expired",
+        "Approval \"{title}\" expired at {expires_at} without a decision",
+    ),
+    // 报表表头
+    ("report.job_tag.tag", "Tag"),
+    ("report.job_tag.period_start", "Period start"),
+    ("report.job_tag.total_jobs", "Total jobs"),
+    ("report.job_tag.succeeded_jobs", "Succeeded"),
+    ("report.job_tag.failed_jobs", "Failed"),
+    ("report.job_tag.partially_succeeded_jobs", "Partially succeeded"),
+    ("report.job_tag.cancelled_jobs", "Cancelled"),
+    ("report.job_tag.success_rate", "Success rate"),
+    ("report.job_tag.avg_duration_secs", "Avg duration (s)"),
+    ("report.job_tag.max_duration_secs", "Max duration (s)"),
+];
+
+const ZH_CN: &[(&str, &str)] = &[
+    // 错误消息
+    ("error.unauthorized", "认证失败"),
+    ("error.authentication", "认证失败：{detail}"),
+    ("error.forbidden", "无权访问"),
+    ("error.two_factor_required", "需要双因素认证：{detail}"),
+    ("error.not_found", "资源不存在：{detail}"),
+    ("error.bad_request", "请求无效：{detail}"),
+    ("error.validation", "校验失败：{detail}"),
+    ("error.payload_too_large", "请求体过大：{detail}"),
+    ("error.payload_limit", "超出请求限制：{detail}"),
+    ("error.rate_limited", "请求过于频繁，请稍后重试"),
+    ("error.timeout", "请求超时：{detail}"),
+    ("error.maintenance", "系统维护中：{detail}"),
+    ("error.ssh_connection", "SSH 连接失败"),
+    ("error.ssh_authentication", "SSH 认证失败"),
+    ("error.ssh_host_key", "SSH 主机密钥验证失败"),
+    ("error.ssh_execution", "SSH 命令执行失败"),
+    ("error.database", "数据库错误"),
+    ("error.config", "配置错误"),
+    ("error.internal", "服务器内部错误：{detail}"),
+    ("error.user.not_found", "用户不存在"),
+    ("error.two_factor.already_enabled", "已启用双因素认证"),
+    ("error.two_factor.not_enrolling", "未发起双因素认证绑定"),
+    ("error.two_factor.not_enabled", "未启用双因素认证"),
+    ("error.two_factor.invalid_code", "验证码无效"),
+    ("error.two_factor.required_by_role", "当前角色要求启用双因素认证"),
+    ("error.two_factor.session_required", "当前令牌未关联登录会话，请重新登录"),
+    ("error.impersonation.nested", "模拟会话中不能再次发起模拟"),
+    ("error.impersonation.self", "不能模拟自己"),
+    ("error.impersonation.reason_required", "必须填写模拟原因"),
+    ("error.impersonation.duration_range", "模拟有效期必须在 1 到 {max} 秒之间"),
+    ("error.impersonation.ended", "模拟会话已结束"),
+    ("error.impersonation.not_found", "模拟会话不存在"),
+    // 错误码说明
+    ("error_code.UNAUTHENTICATED", "缺少凭证或凭证无效"),
+    ("error_code.AUTHENTICATION_FAILED", "认证被拒绝，详见 detail"),
+    ("error_code.PERMISSION_DENIED", "调用方无权执行该操作"),
+    (
+        "error_code.TWO_FACTOR_REQUIRED",
+        "需要提供 TOTP 验证码、绑定双因素认证或完成二次验证",
+    ),
+    ("error_code.RESOURCE_NOT_FOUND", "请求的资源不存在或不可见"),
+    ("error_code.BAD_REQUEST", "请求格式错误"),
+    ("error_code.VALIDATION_FAILED", "请求未通过业务校验，详见 detail"),
+    ("error_code.PAYLOAD_TOO_LARGE", "请求体超过配置的大小上限"),
+    ("error_code.PAYLOAD_LIMIT_EXCEEDED", "请求字段超出长度或数量限制，详见 detail"),
+    ("error_code.RATE_LIMITED", "超出速率或并发限制，请稍后重试"),
+    ("error_code.TIMEOUT", "操作超时"),
+    ("error_code.MAINTENANCE_MODE", "控制面处于只读维护模式，请在维护结束后重试"),
+    ("error_code.SSH_CONNECTION_FAILED", "无法通过 SSH 连接目标主机"),
+    ("error_code.SSH_AUTHENTICATION_FAILED", "目标主机的 SSH 认证失败"),
+    (
+        "error_code.SSH_HOST_KEY_VERIFICATION_FAILED",
+        "目标主机提供的 SSH 主机密钥与已固定的密钥不一致",
+    ),
+    ("error_code.SSH_EXECUTION_FAILED", "远程命令无法执行"),
+    ("error_code.DATABASE_ERROR", "发生数据库错误"),
+    ("error_code.CONFIGURATION_ERROR", "服务端配置错误"),
+    ("error_code.INTERNAL_ERROR", "发生未预期的内部错误"),
+    // 操作结果提示
+    ("notice.asset_group.created", "资产组创建成功"),
+    ("notice.asset_group.updated", "资产组更新成功"),
+    ("notice.asset_group.deleted", "资产组删除成功"),
+    ("notice.asset_group.delete_pending_approval", "资产组删除已提交审批"),
+    ("notice.host.created", "主机创建成功"),
+    ("notice.host.updated", "主机更新成功"),
+    ("notice.host.credentials_updated", "主机凭据更新成功"),
+    ("notice.host.credentials_pending_approval", "主机凭据变更已提交审批"),
+    ("notice.host.deleted", "主机删除成功"),
+    ("notice.host.restored", "主机恢复成功"),
+    ("notice.host.maintenance_started", "主机已进入维护"),
+    ("notice.host.maintenance_ended", "主机维护已结束"),
+    ("notice.host.key_repinned", "主机密钥已重新固定"),
+    ("notice.auth.logged_out", "已成功登出"),
+    ("notice.auth.logged_out_all", "已从 {count} 个设备登出"),
+    ("notice.auth.session_revoked", "会话已撤销"),
+    ("notice.auth.two_factor_disabled", "双因素认证已关闭"),
+    ("notice.role.created", "角色创建成功"),
+    ("notice.role.updated", "角色更新成功"),
+    ("notice.role.deleted", "角色删除成功"),
+    ("notice.role.assigned", "角色分配成功"),
+    ("notice.role.revoked", "角色撤销成功"),
+    ("notice.user.created", "用户创建成功"),
+    ("notice.user.updated", "用户更新成功"),
+    ("notice.user.deleted", "用户删除成功"),
+    ("notice.user.restored", "用户恢复成功"),
+    ("notice.user.password_changed", "密码修改成功"),
+    ("notice.user.locale_updated", "语言偏好已更新"),
+    ("notice.user.sessions_revoked", "已撤销 {count} 个会话"),
+    ("notice.user.two_factor_reset", "双因素认证已重置"),
+    // 推送通知
+    (
+        "notification.watch_status_changed",
+        "{target_type} {target_id} 状态由 {old_status} 变为 {new_status}",
+    ),
+    ("notification.watch_status", "{target_type} {target_id} 当前状态为 {new_status}"),
+    (
+        "notification.approval_reminder",
+        "审批“{title}”已用去 {percent}% 的时限，将于 {expires_at} 过期",
+    ),
+    (
+        "notification.approval_expired",
+        "审批“{title}”已于 {expires_at} 超时，未完成审批",
+    ),
+    // 报表表头
+    ("report.job_tag.tag", "标签"),
+    ("report.job_tag.period_start", "统计周期"),
+    ("report.job_tag.total_jobs", "作业总数"),
+    ("report.job_tag.succeeded_jobs", "成功"),
+    ("report.job_tag.failed_jobs", "失败"),
+    ("report.job_tag.partially_succeeded_jobs", "部分成功"),
+    ("report.job_tag.cancelled_jobs", "已取消"),
+    ("report.job_tag.success_rate", "成功率"),
+    ("report.job_tag.avg_duration_secs", "平均耗时（秒）"),
+    ("report.job_tag.max_duration_secs", "最长耗时（秒）"),
+];
+
+fn table(locale: Locale) -> &'static [(&'static str, &'static str)] {
+    match locale {
+        Locale::EnUs => EN_US,
+        Locale::ZhCn => ZH_CN,
+    }
+}
+
+/// 在指定语言的目录中查找消息（不回退）
+pub(super) fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    table(locale)
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, message)| *message)
+}
+
+#[cfg(test)]
+pub(super) fn keys(locale: Locale) -> impl Iterator<Item = &'static str> {
+    table(locale).iter().map(|(key, _)| *key)
+}
@@ -0,0 +1,254 @@
+//! 国际化（i18n）
+//!
+//! API 错误消息、操作结果提示、通知文案与报表表头按请求语言输出。
+//! 请求语言依次取：用户偏好 > Accept-Language > 配置的默认语言，
+//! 由中间件通过 task-local 在整个请求处理过程中可见。
+//! 消息按键查找，依次回退：请求语言 > 默认语言 > en-US > 消息键本身
+
+mod catalog;
+
+use dashmap::DashMap;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::fmt::Display;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// 支持的语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en-US")]
+    EnUs,
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+}
+
+impl Locale {
+    pub const ALL: &'static [Locale] = &[Locale::EnUs, Locale::ZhCn];
+
+    /// BCP 47 语言标签
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::ZhCn => "zh-CN",
+        }
+    }
+
+    /// 解析语言标签，只按主语言匹配（zh-TW、zh-Hans 均使用 zh-CN 目录）
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::EnUs),
+            "zh" => Some(Locale::ZhCn),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+/// 默认语言（启动时由配置设置）
+static DEFAULT_LOCALE: OnceCell<Locale> = OnceCell::new();
+
+/// 设置默认语言（未设置时为 en-US）
+pub fn set_default_locale(locale: Locale) {
+    let _ = DEFAULT_LOCALE.set(locale);
+}
+
+/// 获取默认语言
+pub fn default_locale() -> Locale {
+    DEFAULT_LOCALE.get().copied().unwrap_or_default()
+}
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+/// 获取当前任务上下文中的请求语言，不在请求上下文中时返回默认语言
+pub fn current_locale() -> Locale {
+    LOCALE
+        .try_with(|locale| *locale)
+        .unwrap_or_else(|_| default_locale())
+}
+
+/// 在指定语言的上下文中执行 future
+pub async fn scope<F>(locale: Locale, fut: F) -> F::Output
+where
+    F: Future,
+{
+    LOCALE.scope(locale, fut).await
+}
+
+/// 按 Accept-Language 选择语言（按权重从高到低取第一个支持的语言，q=0 表示不接受）
+pub fn negotiate(accept_language: &str) -> Option<Locale> {
+    let mut candidates: Vec<(f32, &str)> = accept_language
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && quality > 0.0).then_some((quality, tag))
+        })
+        .collect();
+    // 稳定排序，同权重时保持客户端给出的顺序
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    candidates
+        .into_iter()
+        .find_map(|(_, tag)| Locale::parse(tag))
+}
+
+/// 按回退链查找消息，各语言目录均不存在时返回 None
+pub fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    [locale, default_locale(), Locale::EnUs]
+        .into_iter()
+        .find_map(|l| catalog::lookup(l, key))
+}
+
+/// 查找指定语言的消息，不存在时返回消息键本身
+pub fn t_in(locale: Locale, key: &'static str) -> &'static str {
+    lookup(locale, key).unwrap_or(key)
+}
+
+/// 查找当前请求语言的消息
+pub fn t(key: &'static str) -> &'static str {
+    t_in(current_locale(), key)
+}
+
+/// 查找消息并替换 `{name}` 占位符
+pub fn tf_in(locale: Locale, key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    format(t_in(locale, key), args)
+}
+
+/// 查找当前请求语言的消息并替换占位符
+pub fn tf(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    tf_in(current_locale(), key, args)
+}
+
+/// 替换模板中的 `{name}` 占位符，未提供的占位符原样保留
+pub fn format(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut output = template.to_string();
+    for (name, value) in args {
+        output = output.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    output
+}
+
+/// 用户语言偏好缓存
+///
+/// 每个请求都需要用户偏好，缓存避免逐请求查询；修改偏好时主动失效，
+/// 其他实例上的缓存在 TTL 到期后刷新
+pub struct UserLocaleCache {
+    db: Pool<Postgres>,
+    entries: DashMap<Uuid, (Option<Locale>, Instant)>,
+    ttl: Duration,
+}
+
+impl UserLocaleCache {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+    pub fn new(db: Pool<Postgres>, ttl: Duration) -> Self {
+        Self {
+            db,
+            entries: DashMap::new(),
+            ttl,
+        }
+    }
+
+    /// 获取用户的语言偏好（未设置或查询失败时为空）
+    pub async fn get(&self, user_id: Uuid) -> Option<Locale> {
+        if let Some(entry) = self.entries.get(&user_id) {
+            if entry.1.elapsed() < self.ttl {
+                return entry.0;
+            }
+        }
+
+        let locale =
+            match sqlx::query_scalar::<_, Option<String>>("SELECT locale FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.db)
+                .await
+            {
+                Ok(tag) => tag.flatten().as_deref().and_then(Locale::parse),
+                Err(e) => {
+                    tracing::warn!(user_id = %user_id, error = %e, "Failed to load user locale");
+                    return None;
+                }
+            };
+        self.entries.insert(user_id, (locale, Instant::now()));
+        locale
+    }
+
+    /// 使缓存的用户偏好失效
+    pub fn invalidate(&self, user_id: Uuid) {
+        self.entries.remove(&user_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale_tags() {
+        assert_eq!(Locale::parse("zh-CN"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("zh_TW"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("ZH"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("en-GB"), Some(Locale::EnUs));
+        assert_eq!(Locale::parse("fr-FR"), None);
+        assert_eq!(Locale::parse(""), None);
+        for locale in Locale::ALL {
+            assert_eq!(Locale::parse(locale.tag()), Some(*locale));
+        }
+    }
+
+    #[test]
+    fn test_negotiate_accept_language() {
+        assert_eq!(negotiate("zh-CN,zh;q=0.9,en;q=0.8"), Some(Locale::ZhCn));
+        assert_eq!(negotiate("fr-FR, en;q=0.5, zh;q=0.7"), Some(Locale::ZhCn));
+        assert_eq!(negotiate("en-US;q=0.8, zh-CN;q=0"), Some(Locale::EnUs));
+        assert_eq!(negotiate("de, fr"), None);
+        assert_eq!(negotiate("*"), None);
+        assert_eq!(negotiate(""), None);
+    }
+
+    #[test]
+    fn test_lookup_falls_back_to_english_and_key() {
+        assert_eq!(t_in(Locale::ZhCn, "error.forbidden"), "无权访问");
+        assert_eq!(t_in(Locale::EnUs, "error.forbidden"), "Access denied");
+        assert_eq!(t_in(Locale::ZhCn, "no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn test_format_placeholders() {
+        let text = tf_in(Locale::EnUs, "error.not_found", &[("detail", &"Job not found")]);
+        assert_eq!(text, "Resource not found: Job not found");
+        assert_eq!(format("{a} {b}", &[("a", &1)]), "1 {b}");
+    }
+
+    #[test]
+    fn test_catalogs_have_same_keys() {
+        for locale in Locale::ALL {
+            for key in catalog::keys(Locale::EnUs) {
+                assert!(catalog::lookup(*locale, key).is_some(), "{} is missing {}", locale, key);
+            }
+            assert_eq!(catalog::keys(*locale).count(), catalog::keys(Locale::EnUs).count());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scope_sets_current_locale() {
+        assert_eq!(current_locale(), default_locale());
+        let locale = scope(Locale::ZhCn, async { current_locale() }).await;
+        assert_eq!(locale, Locale::ZhCn);
+    }
+}
@@ -2,19 +2,24 @@
 //! 提供共享类型和工具
 
 pub mod auth;
+pub mod cache;
 pub mod concurrency;
 pub mod config;
 pub mod db;
 pub mod error;
+pub mod executor;
 pub mod handlers;
+pub mod i18n;
 pub mod middleware;
 pub mod models;
 pub mod output;
 pub mod permissions;
+pub mod preflight;
 pub mod rabbitmq;
 pub mod realtime;
 pub mod repository;
 pub mod routes;
+pub mod seed;
 pub mod services;
 pub mod ssh;
 pub mod telemetry;
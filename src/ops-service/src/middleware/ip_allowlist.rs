@@ -0,0 +1,707 @@
+//! 来源 IP 访问控制
+//!
+//! 在全局 allowed_ips 之外，支持：
+//! - 按端点组限制来源网段（如作业创建仅允许办公网，Runner 回调仅允许机房网段）
+//! - 按角色限制来源网段（如 admin 角色仅允许办公网登录后操作）
+//! - 受信代理感知的客户端 IP 解析（仅信任来自已知代理的转发头）
+//!
+//! 所有放行/拒绝决策以 target = "ip_access" 记录日志，拒绝同时计入 ip_access_denied_total 指标。
+
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::auth::middleware::AuthContext;
+use crate::config::{IpAllowlistConfig, SecurityConfig};
+use crate::error::AppError;
+
+/// 单个 IP 网段（单个 IP 视为 /32 或 /128）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// 判断 IP 是否属于该网段（IPv4 映射的 IPv6 地址按 IPv4 处理）
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = prefix_mask_v4(self.prefix_len);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = prefix_mask_v6(self.prefix_len);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_mask_v4(prefix_len: u8) -> u32 {
+    u32::MAX
+        .checked_shl(32 - u32::from(prefix_len))
+        .unwrap_or(0)
+}
+
+fn prefix_mask_v6(prefix_len: u8) -> u128 {
+    u128::MAX
+        .checked_shl(128 - u32::from(prefix_len))
+        .unwrap_or(0)
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+
+        let network = addr
+            .parse::<IpAddr>()
+            .map_err(|_| format!("invalid IP address in '{}'", s))?
+            .to_canonical();
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(p) => p
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| format!("invalid prefix length in '{}'", s))?,
+            None => max_len,
+        };
+
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// 网段列表，命中任一网段即视为匹配
+#[derive(Debug, Clone, Default)]
+pub struct CidrList(Vec<IpCidr>);
+
+impl CidrList {
+    /// 解析逗号或空白分隔的 IP/CIDR 列表（环境变量配置使用该格式）
+    pub fn parse(list: &str) -> Result<Self, String> {
+        Self::from_entries(
+            list.split([',', ' ', '\t', '\n'])
+                .filter(|s| !s.trim().is_empty()),
+        )
+    }
+
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        entries
+            .into_iter()
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// ==================== 端点组 ====================
+
+/// 端点组：按方法 + 路径模式归类的一组端点
+///
+/// 路径模式中 `{...}` 匹配单个路径段，末尾 `*` 匹配任意剩余路径；
+/// method 为 None 时匹配所有方法
+pub struct EndpointGroup {
+    pub name: &'static str,
+    routes: &'static [(Option<Method>, &'static str)],
+}
+
+/// 内置端点组，请求归属于首个匹配的组
+pub const ENDPOINT_GROUPS: &[EndpointGroup] = &[
+    EndpointGroup {
+        name: "runner",
+        routes: &[
+            (None, "/api/v1/runners/register"),
+            (None, "/api/v1/runners/heartbeat"),
+            (None, "/api/v1/runners/enroll"),
+            (None, "/api/v1/webhooks/*"),
+        ],
+    },
+    EndpointGroup {
+        name: "job_create",
+        routes: &[
+            (Some(Method::POST), "/api/v1/jobs"),
+            (Some(Method::POST), "/api/v1/jobs/command"),
+            (Some(Method::POST), "/api/v1/jobs/script"),
+            (Some(Method::POST), "/api/v1/jobs/{id}/retry"),
+            (Some(Method::POST), "/api/v1/job-templates/execute"),
+            (Some(Method::POST), "/api/v1/builds"),
+            (Some(Method::POST), "/api/v1/builds/{id}/retry"),
+        ],
+    },
+    EndpointGroup {
+        name: "admin",
+        routes: &[
+            (Some(Method::POST), "/api/v1/users"),
+            (Some(Method::PUT), "/api/v1/users/{id}"),
+            (Some(Method::DELETE), "/api/v1/users/{id}"),
+            (None, "/api/v1/users/{id}/roles"),
+            (None, "/api/v1/roles*"),
+            (None, "/api/v1/role-bindings*"),
+            (None, "/api/v1/permissions"),
+            (None, "/api/v1/auth/impersonate"),
+            (None, "/api/v1/auth/impersonations*"),
+            (None, "/api/v1/runners/enrollment-tokens*"),
+        ],
+    },
+    EndpointGroup {
+        name: "audit",
+        routes: &[(None, "/api/v1/audit/*")],
+    },
+    EndpointGroup {
+        name: "metrics",
+        routes: &[
+            (Some(Method::GET), "/metrics"),
+            (Some(Method::GET), "/metrics.json"),
+        ],
+    },
+];
+
+/// 查找请求所属的端点组
+pub fn endpoint_group(method: &Method, path: &str) -> Option<&'static str> {
+    ENDPOINT_GROUPS
+        .iter()
+        .find(|group| {
+            group.routes.iter().any(|(m, pattern)| {
+                m.as_ref().map_or(true, |m| m == method) && path_matches(pattern, path)
+            })
+        })
+        .map(|group| group.name)
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    if let Some(prefix) = pattern.strip_suffix('*') {
+        return path.starts_with(prefix);
+    }
+
+    let mut pattern_segments = pattern.split('/');
+    let mut path_segments = path.trim_end_matches('/').split('/');
+    loop {
+        match (pattern_segments.next(), path_segments.next()) {
+            (None, None) => return true,
+            (Some(p), Some(s)) if p.starts_with('{') && p.ends_with('}') && !s.is_empty() => {}
+            (Some(p), Some(s)) if p == s => {}
+            _ => return false,
+        }
+    }
+}
+
+// ==================== 访问策略 ====================
+
+/// 单次访问检查的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessDecision {
+    /// 没有适用的白名单规则
+    Unrestricted,
+    /// 命中规则并放行
+    Allowed { rule: String },
+    /// 命中规则但来源不在白名单内
+    Denied { rule: String },
+}
+
+/// 编译后的来源访问策略（启动时由 SecurityConfig 构建一次）
+#[derive(Debug, Clone, Default)]
+pub struct IpAccessPolicy {
+    global: Option<CidrList>,
+    groups: HashMap<&'static str, CidrList>,
+    roles: HashMap<String, CidrList>,
+    trusted_proxies: Option<CidrList>,
+    log_allowed: bool,
+}
+
+impl IpAccessPolicy {
+    /// 从安全配置构建策略，任何无法解析的网段或未知端点组都会返回错误
+    pub fn from_config(config: &SecurityConfig) -> Result<Self, String> {
+        Self::compile(config.allowed_ips.as_deref(), &config.ip_allowlist)
+    }
+
+    fn compile(
+        allowed_ips: Option<&[String]>,
+        allowlist: &IpAllowlistConfig,
+    ) -> Result<Self, String> {
+        let global = allowed_ips
+            .map(|ips| CidrList::from_entries(ips.iter().map(String::as_str)))
+            .transpose()
+            .map_err(|e| format!("security.allowed_ips: {}", e))?;
+
+        let mut groups = HashMap::new();
+        for (name, list) in &allowlist.groups {
+            let group = ENDPOINT_GROUPS
+                .iter()
+                .find(|g| g.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| {
+                    let known: Vec<_> = ENDPOINT_GROUPS.iter().map(|g| g.name).collect();
+                    format!(
+                        "security.ip_allowlist.groups: unknown endpoint group '{}' (known: {})",
+                        name,
+                        known.join(", ")
+                    )
+                })?;
+            let cidrs = CidrList::parse(list)
+                .map_err(|e| format!("security.ip_allowlist.groups.{}: {}", name, e))?;
+            groups.insert(group.name, cidrs);
+        }
+
+        let mut roles = HashMap::new();
+        for (role, list) in &allowlist.roles {
+            let cidrs = CidrList::parse(list)
+                .map_err(|e| format!("security.ip_allowlist.roles.{}: {}", role, e))?;
+            roles.insert(role.to_lowercase(), cidrs);
+        }
+
+        let trusted_proxies = allowlist
+            .trusted_proxies
+            .as_deref()
+            .map(CidrList::parse)
+            .transpose()
+            .map_err(|e| format!("security.ip_allowlist.trusted_proxies: {}", e))?
+            .filter(|list| !list.is_empty());
+
+        Ok(Self {
+            global,
+            groups,
+            roles,
+            trusted_proxies,
+            log_allowed: allowlist.log_allowed,
+        })
+    }
+
+    /// 是否配置了角色白名单（未配置时角色中间件直接放行）
+    pub fn has_role_rules(&self) -> bool {
+        !self.roles.is_empty()
+    }
+
+    /// 解析客户端 IP
+    ///
+    /// 配置了 trusted_proxies 时，仅当对端地址属于受信代理才解析转发头，
+    /// 并从 X-Forwarded-For 右侧开始跳过受信代理，取第一个非代理地址，
+    /// 避免客户端自行伪造 X-Forwarded-For 绕过白名单。
+    /// 未配置 trusted_proxies 时沿用 get_client_ip_with_addr 的行为
+    pub fn resolve_client_ip(&self, req: &Request, trust_proxy: bool) -> IpAddr {
+        let Some(trusted) = self.trusted_proxies.as_ref().filter(|_| trust_proxy) else {
+            return super::get_client_ip_with_addr(req, trust_proxy)
+                .unwrap_or(IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+        };
+
+        let peer = req
+            .extensions()
+            .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+            .map(|info| info.0.ip().to_canonical());
+        let Some(peer) = peer else {
+            return super::get_client_ip_with_addr(req, false)
+                .unwrap_or(IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+        };
+        if !trusted.contains(peer) {
+            return peer;
+        }
+
+        let headers = req.headers();
+        if let Some(forwarded) = headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
+            let chain: Vec<IpAddr> = forwarded
+                .split(',')
+                .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+                .map(|ip| ip.to_canonical())
+                .collect();
+            if let Some(client) = chain.iter().rev().find(|ip| !trusted.contains(**ip)) {
+                return *client;
+            }
+            if let Some(first) = chain.first() {
+                return *first;
+            }
+        }
+
+        headers
+            .get("x-real-ip")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+            .unwrap_or(peer)
+    }
+
+    /// 检查全局白名单与端点组白名单
+    pub fn check_endpoint(&self, ip: IpAddr, method: &Method, path: &str) -> AccessDecision {
+        let mut decision = AccessDecision::Unrestricted;
+
+        if let Some(global) = &self.global {
+            if !global.contains(ip) {
+                return AccessDecision::Denied {
+                    rule: "global".to_string(),
+                };
+            }
+            decision = AccessDecision::Allowed {
+                rule: "global".to_string(),
+            };
+        }
+
+        if let Some(group) = endpoint_group(method, path) {
+            if let Some(cidrs) = self.groups.get(group) {
+                let rule = format!("group:{}", group);
+                if !cidrs.contains(ip) {
+                    return AccessDecision::Denied { rule };
+                }
+                decision = AccessDecision::Allowed { rule };
+            }
+        }
+
+        decision
+    }
+
+    /// 检查角色白名单
+    ///
+    /// 用户持有的每个受限角色都必须允许该来源：无法区分请求由哪个角色授权，
+    /// 因此以最严格的角色为准。未受限的角色不影响结果
+    pub fn check_roles(&self, ip: IpAddr, roles: &[String]) -> AccessDecision {
+        let mut decision = AccessDecision::Unrestricted;
+
+        for role in roles {
+            if let Some(cidrs) = self.roles.get(&role.to_lowercase()) {
+                let rule = format!("role:{}", role);
+                if !cidrs.contains(ip) {
+                    return AccessDecision::Denied { rule };
+                }
+                decision = AccessDecision::Allowed { rule };
+            }
+        }
+
+        decision
+    }
+
+    /// 记录访问决策
+    fn log_decision(&self, decision: &AccessDecision, ip: IpAddr, method: &Method, path: &str) {
+        match decision {
+            AccessDecision::Unrestricted => {}
+            AccessDecision::Allowed { rule } if self.log_allowed => {
+                tracing::info!(
+                    target: "ip_access",
+                    decision = "allow",
+                    rule = %rule,
+                    client_ip = %ip,
+                    method = %method,
+                    path = %path,
+                    "Request allowed by IP allowlist"
+                );
+            }
+            AccessDecision::Allowed { rule } => {
+                tracing::debug!(
+                    target: "ip_access",
+                    decision = "allow",
+                    rule = %rule,
+                    client_ip = %ip,
+                    method = %method,
+                    path = %path,
+                    "Request allowed by IP allowlist"
+                );
+            }
+            AccessDecision::Denied { rule } => {
+                tracing::warn!(
+                    target: "ip_access",
+                    decision = "deny",
+                    rule = %rule,
+                    client_ip = %ip,
+                    method = %method,
+                    path = %path,
+                    "Request denied by IP allowlist"
+                );
+                metrics::counter!("ip_access_denied_total", "rule" => rule.clone()).increment(1);
+            }
+        }
+    }
+}
+
+/// 优先使用 rate_limit_middleware 已解析的 IP，保证同一请求内各中间件结论一致
+fn request_client_ip(state: &AppState, req: &Request) -> IpAddr {
+    req.extensions()
+        .get::<IpAddr>()
+        .copied()
+        .unwrap_or_else(|| {
+            state
+                .ip_access_policy
+                .resolve_client_ip(req, state.config.security.trust_proxy)
+        })
+}
+
+/// IP 白名单中间件（全局 + 端点组）
+pub async fn ip_whitelist_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let client_ip = request_client_ip(&state, &req);
+    let policy = &state.ip_access_policy;
+    let decision = policy.check_endpoint(client_ip, req.method(), req.uri().path());
+    policy.log_decision(&decision, client_ip, req.method(), req.uri().path());
+
+    if let AccessDecision::Denied { .. } = decision {
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// 角色 IP 白名单中间件（需位于 JWT 认证中间件之后）
+pub async fn role_ip_allowlist_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let policy = &state.ip_access_policy;
+    if !policy.has_role_rules() {
+        return Ok(next.run(req).await);
+    }
+    let Some(auth_context) = req.extensions().get::<AuthContext>() else {
+        return Ok(next.run(req).await);
+    };
+
+    let client_ip = request_client_ip(&state, &req);
+    let decision = policy.check_roles(client_ip, &auth_context.roles);
+    policy.log_decision(&decision, client_ip, req.method(), req.uri().path());
+
+    if let AccessDecision::Denied { .. } = decision {
+        tracing::warn!(
+            target: "ip_access",
+            user_id = %auth_context.user_id,
+            username = %auth_context.username,
+            "Role restricted from client network"
+        );
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn policy(
+        allowed_ips: Option<Vec<&str>>,
+        groups: &[(&str, &str)],
+        roles: &[(&str, &str)],
+    ) -> IpAccessPolicy {
+        let allowed_ips: Option<Vec<String>> =
+            allowed_ips.map(|ips| ips.into_iter().map(String::from).collect());
+        let allowlist = IpAllowlistConfig {
+            groups: groups
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            roles: roles
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        IpAccessPolicy::compile(allowed_ips.as_deref(), &allowlist).unwrap()
+    }
+
+    #[test]
+    fn test_cidr_parse_and_contains() {
+        let cidr: IpCidr = "10.20.0.0/16".parse().unwrap();
+        assert!(cidr.contains(ip("10.20.3.4")));
+        assert!(!cidr.contains(ip("10.21.0.1")));
+        assert!(cidr.contains(ip("::ffff:10.20.0.9")));
+
+        let single: IpCidr = "192.168.1.5".parse().unwrap();
+        assert!(single.contains(ip("192.168.1.5")));
+        assert!(!single.contains(ip("192.168.1.6")));
+
+        let any: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(ip("8.8.8.8")));
+        assert!(!any.contains(ip("2001:db8::1")));
+
+        let v6: IpCidr = "2001:db8::/32".parse().unwrap();
+        assert!(v6.contains(ip("2001:db8:1::1")));
+        assert!(!v6.contains(ip("2001:db9::1")));
+    }
+
+    #[test]
+    fn test_cidr_parse_rejects_invalid() {
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("2001:db8::/129".parse::<IpCidr>().is_err());
+        assert!("office".parse::<IpCidr>().is_err());
+        assert!("10.0.0.0/abc".parse::<IpCidr>().is_err());
+        assert!(CidrList::parse("10.0.0.0/8, bogus").is_err());
+    }
+
+    #[test]
+    fn test_cidr_list_parse_separators() {
+        let list = CidrList::parse("10.0.0.0/8, 192.168.0.0/16 172.16.0.1").unwrap();
+        assert!(list.contains(ip("10.1.1.1")));
+        assert!(list.contains(ip("192.168.200.1")));
+        assert!(list.contains(ip("172.16.0.1")));
+        assert!(!list.contains(ip("172.16.0.2")));
+        assert!(CidrList::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compile_rejects_unknown_group() {
+        let allowlist = IpAllowlistConfig {
+            groups: [("deploy".to_string(), "10.0.0.0/8".to_string())].into(),
+            ..Default::default()
+        };
+        let err = IpAccessPolicy::compile(None, &allowlist).unwrap_err();
+        assert!(err.contains("unknown endpoint group 'deploy'"));
+    }
+
+    #[test]
+    fn test_endpoint_group_matching() {
+        assert_eq!(endpoint_group(&Method::POST, "/api/v1/jobs"), Some("job_create"));
+        assert_eq!(endpoint_group(&Method::POST, "/api/v1/jobs/script"), Some("job_create"));
+        assert_eq!(endpoint_group(&Method::POST, "/api/v1/jobs/abc/retry"), Some("job_create"));
+        assert_eq!(endpoint_group(&Method::GET, "/api/v1/jobs"), None);
+        assert_eq!(endpoint_group(&Method::POST, "/api/v1/jobs/abc/cancel"), None);
+        assert_eq!(endpoint_group(&Method::POST, "/api/v1/runners/heartbeat"), Some("runner"));
+        assert_eq!(endpoint_group(&Method::POST, "/api/v1/webhooks/build/status"), Some("runner"));
+        assert_eq!(endpoint_group(&Method::PUT, "/api/v1/users/abc"), Some("admin"));
+        assert_eq!(endpoint_group(&Method::PUT, "/api/v1/users/me/password"), None);
+        assert_eq!(endpoint_group(&Method::GET, "/api/v1/roles/abc/permissions"), Some("admin"));
+        assert_eq!(endpoint_group(&Method::GET, "/api/v1/audit/logs"), Some("audit"));
+        assert_eq!(endpoint_group(&Method::GET, "/health"), None);
+    }
+
+    #[test]
+    fn test_check_endpoint_group_rules() {
+        let policy =
+            policy(None, &[("job_create", "10.1.0.0/16"), ("runner", "172.20.0.0/14")], &[]);
+
+        assert_eq!(
+            policy.check_endpoint(ip("10.1.2.3"), &Method::POST, "/api/v1/jobs"),
+            AccessDecision::Allowed {
+                rule: "group:job_create".to_string()
+            }
+        );
+        assert_eq!(
+            policy.check_endpoint(ip("8.8.8.8"), &Method::POST, "/api/v1/jobs"),
+            AccessDecision::Denied {
+                rule: "group:job_create".to_string()
+            }
+        );
+        assert_eq!(
+            policy.check_endpoint(ip("10.1.2.3"), &Method::POST, "/api/v1/runners/heartbeat"),
+            AccessDecision::Denied {
+                rule: "group:runner".to_string()
+            }
+        );
+        // 未配置白名单的端点组不受限
+        assert_eq!(
+            policy.check_endpoint(ip("8.8.8.8"), &Method::GET, "/api/v1/audit/logs"),
+            AccessDecision::Unrestricted
+        );
+    }
+
+    #[test]
+    fn test_check_endpoint_global_applies_first() {
+        let policy = policy(Some(vec!["10.0.0.0/8"]), &[("job_create", "10.1.0.0/16")], &[]);
+
+        assert_eq!(
+            policy.check_endpoint(ip("192.168.1.1"), &Method::GET, "/api/v1/hosts"),
+            AccessDecision::Denied {
+                rule: "global".to_string()
+            }
+        );
+        assert_eq!(
+            policy.check_endpoint(ip("10.9.0.1"), &Method::GET, "/api/v1/hosts"),
+            AccessDecision::Allowed {
+                rule: "global".to_string()
+            }
+        );
+        assert_eq!(
+            policy.check_endpoint(ip("10.9.0.1"), &Method::POST, "/api/v1/jobs"),
+            AccessDecision::Denied {
+                rule: "group:job_create".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_check_roles_requires_every_restricted_role() {
+        let policy = policy(None, &[], &[("admin", "10.1.0.0/16"), ("Operator", "10.0.0.0/8")]);
+        let roles = vec![
+            "admin".to_string(),
+            "operator".to_string(),
+            "viewer".to_string(),
+        ];
+
+        assert_eq!(
+            policy.check_roles(ip("10.1.0.5"), &roles),
+            AccessDecision::Allowed {
+                rule: "role:operator".to_string()
+            }
+        );
+        assert_eq!(
+            policy.check_roles(ip("10.2.0.5"), &roles),
+            AccessDecision::Denied {
+                rule: "role:admin".to_string()
+            }
+        );
+        assert_eq!(
+            policy.check_roles(ip("8.8.8.8"), &["viewer".to_string()]),
+            AccessDecision::Unrestricted
+        );
+    }
+
+    #[test]
+    fn test_resolve_client_ip_with_trusted_proxies() {
+        let policy = IpAccessPolicy {
+            trusted_proxies: Some(CidrList::parse("10.0.0.0/24").unwrap()),
+            ..Default::default()
+        };
+        let request = |peer: &str, xff: Option<&str>| {
+            let mut builder = Request::builder().uri("/api/v1/jobs");
+            if let Some(xff) = xff {
+                builder = builder.header("x-forwarded-for", xff);
+            }
+            let mut req = builder.body(axum::body::Body::empty()).unwrap();
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(std::net::SocketAddr::new(ip(peer), 443)));
+            req
+        };
+
+        // 来自受信代理：跳过链路右侧的代理地址，伪造的最左侧地址被忽略
+        let req = request("10.0.0.2", Some("1.2.3.4, 203.0.113.7, 10.0.0.3"));
+        assert_eq!(policy.resolve_client_ip(&req, true), ip("203.0.113.7"));
+
+        // 非受信对端直接连接：忽略转发头
+        let req = request("198.51.100.9", Some("10.1.0.1"));
+        assert_eq!(policy.resolve_client_ip(&req, true), ip("198.51.100.9"));
+
+        // trust_proxy 关闭时始终使用对端地址
+        let req = request("10.0.0.2", Some("203.0.113.7"));
+        assert_eq!(policy.resolve_client_ip(&req, false), ip("10.0.0.2"));
+    }
+}
@@ -0,0 +1,57 @@
+//! 请求语言
+//!
+//! `locale_middleware` 按 Accept-Language 确定请求语言；鉴权之后由
+//! `user_locale_middleware` 以用户保存的语言偏好覆盖。响应未设置 Content-Language 时补充该头
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+use super::AppState;
+use crate::auth::middleware::AuthContext;
+use crate::i18n::{self, Locale};
+
+/// 按 Accept-Language 选择请求语言的中间件（放在请求追踪之内）
+pub async fn locale_middleware(req: Request, next: Next) -> Response {
+    let locale = req
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(i18n::negotiate)
+        .unwrap_or_else(i18n::default_locale);
+    run_with_locale(locale, req, next).await
+}
+
+/// 以用户语言偏好覆盖请求语言的中间件（放在 JWT 鉴权之内）
+pub async fn user_locale_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let user_id = req
+        .extensions()
+        .get::<AuthContext>()
+        .map(|auth| auth.user_id);
+    let preferred = match user_id {
+        Some(user_id) => state.user_locales.get(user_id).await,
+        None => None,
+    };
+    match preferred {
+        Some(locale) => run_with_locale(locale, req, next).await,
+        None => next.run(req).await,
+    }
+}
+
+async fn run_with_locale(locale: Locale, req: Request, next: Next) -> Response {
+    let mut response = i18n::scope(locale, next.run(req)).await;
+    // 内层已按用户偏好设置时保留内层的值
+    response
+        .headers_mut()
+        .entry(header::CONTENT_LANGUAGE)
+        .or_insert(HeaderValue::from_static(locale.tag()));
+    response
+}
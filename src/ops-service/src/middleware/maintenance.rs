@@ -0,0 +1,200 @@
+//! 只读维护模式
+//!
+//! 开启后所有写请求（非 GET/HEAD/OPTIONS）返回 503，白名单管理员除外；
+//! 后台调度任务在每轮开始前检查开关并跳过本轮，用于数据库迁移等维护窗口。
+//! 登录、刷新令牌与维护模式切换接口始终放行，保证管理员能够结束维护。
+
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    auth::middleware::AuthContext, config::MaintenanceConfig, error::AppError, middleware::AppState,
+};
+
+/// 维护模式切换接口
+pub const MAINTENANCE_PATH: &str = "/api/v1/system/maintenance";
+
+/// 只读模式下始终放行的写接口
+const EXEMPT_PATHS: &[&str] = &[
+    "/api/v1/auth/login",
+    "/api/v1/auth/refresh",
+    MAINTENANCE_PATH,
+];
+
+/// 维护模式状态
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct MaintenanceStatus {
+    pub read_only: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// 最近一次切换时间（启动时由配置开启则为启动时间）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+    /// 最近一次切换的操作人（由配置开启时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_by: Option<String>,
+}
+
+/// 全局只读开关
+pub struct MaintenanceMode {
+    status: RwLock<MaintenanceStatus>,
+    admin_allowlist: Vec<String>,
+}
+
+impl MaintenanceMode {
+    pub fn from_config(config: &MaintenanceConfig) -> Self {
+        let admin_allowlist = config
+            .admin_allowlist
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let status = MaintenanceStatus {
+            read_only: config.read_only,
+            reason: config.reason.clone().filter(|_| config.read_only),
+            since: config.read_only.then(Utc::now),
+            changed_by: None,
+        };
+        Self {
+            status: RwLock::new(status),
+            admin_allowlist,
+        }
+    }
+
+    /// 是否处于只读模式
+    pub fn is_read_only(&self) -> bool {
+        self.status.read().map(|s| s.read_only).unwrap_or(false)
+    }
+
+    /// 当前状态
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.read().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// 切换只读模式，返回切换后的状态
+    pub fn set(
+        &self,
+        read_only: bool,
+        reason: Option<String>,
+        changed_by: &str,
+    ) -> MaintenanceStatus {
+        let status = MaintenanceStatus {
+            read_only,
+            reason: reason.filter(|_| read_only),
+            since: Some(Utc::now()),
+            changed_by: Some(changed_by.to_string()),
+        };
+        if let Ok(mut current) = self.status.write() {
+            *current = status.clone();
+        }
+        status
+    }
+
+    /// 用户是否在维护白名单中
+    pub fn is_allowlisted(&self, username: &str) -> bool {
+        self.admin_allowlist.iter().any(|name| name == username)
+    }
+
+    /// 判断请求在当前模式下是否放行
+    pub fn allows(&self, method: &Method, path: &str, username: Option<&str>) -> bool {
+        if !self.is_read_only()
+            || matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+            || EXEMPT_PATHS.contains(&path)
+        {
+            return true;
+        }
+        username.is_some_and(|username| self.is_allowlisted(username))
+    }
+
+    /// 被拒绝请求的提示信息
+    pub fn rejection_message(&self) -> String {
+        match self.status().reason {
+            Some(reason) => format!("Control plane is in read-only maintenance mode: {}", reason),
+            None => "Control plane is in read-only maintenance mode".to_string(),
+        }
+    }
+}
+
+/// 只读维护模式中间件
+///
+/// 需放在 JWT 鉴权之内（鉴权先执行），以便识别白名单管理员
+pub async fn maintenance_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let maintenance = &state.maintenance;
+    let username = req
+        .extensions()
+        .get::<AuthContext>()
+        .map(|auth| auth.username.as_str());
+    if maintenance.allows(req.method(), req.uri().path(), username) {
+        return Ok(next.run(req).await);
+    }
+
+    tracing::warn!(
+        method = %req.method(),
+        path = %req.uri().path(),
+        username = username.unwrap_or("-"),
+        "Rejected write request in maintenance mode"
+    );
+    Err(AppError::Maintenance(maintenance.rejection_message()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(read_only: bool, allowlist: Option<&str>) -> MaintenanceMode {
+        MaintenanceMode::from_config(&MaintenanceConfig {
+            read_only,
+            reason: Some("database migration".to_string()),
+            admin_allowlist: allowlist.map(String::from),
+        })
+    }
+
+    #[test]
+    fn test_disabled_allows_everything() {
+        let mode = mode(false, None);
+        assert!(mode.allows(&Method::POST, "/api/v1/jobs", None));
+        assert_eq!(mode.status().reason, None);
+    }
+
+    #[test]
+    fn test_read_only_blocks_writes_except_allowlist() {
+        let mode = mode(true, Some("root, ops-admin"));
+        assert!(mode.allows(&Method::GET, "/api/v1/jobs", Some("alice")));
+        assert!(!mode.allows(&Method::POST, "/api/v1/jobs", Some("alice")));
+        assert!(!mode.allows(&Method::DELETE, "/api/v1/hosts/1", None));
+        assert!(mode.allows(&Method::POST, "/api/v1/jobs", Some("ops-admin")));
+        assert!(mode.allows(&Method::POST, "/api/v1/auth/login", None));
+        assert!(mode.allows(&Method::PUT, MAINTENANCE_PATH, Some("alice")));
+        assert_eq!(
+            mode.rejection_message(),
+            "Control plane is in read-only maintenance mode: database migration"
+        );
+    }
+
+    #[test]
+    fn test_set_records_operator() {
+        let mode = mode(false, None);
+        let status = mode.set(true, Some("upgrade".to_string()), "root");
+        assert!(mode.is_read_only());
+        assert_eq!(status.changed_by.as_deref(), Some("root"));
+        assert_eq!(mode.status(), status);
+
+        // 关闭时清除原因
+        let status = mode.set(false, Some("ignored".to_string()), "root");
+        assert!(!mode.is_read_only());
+        assert_eq!(status.reason, None);
+    }
+}
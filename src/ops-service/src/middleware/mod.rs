@@ -1,8 +1,17 @@
 //! HTTP 中间件
-//! 请求追踪、速率限制、IP 白名单、webhook HMAC 鉴权
+//! 请求追踪、请求语言、速率限制、IP 白名单、webhook HMAC 鉴权、只读维护模式、请求体限制
 
+pub mod ip_allowlist;
+pub mod locale;
+pub mod maintenance;
+pub mod payload_limits;
+pub mod request_id;
 pub mod webhook_hmac;
 
+pub use ip_allowlist::{ip_whitelist_middleware, role_ip_allowlist_middleware};
+pub use locale::{locale_middleware, user_locale_middleware};
+pub use payload_limits::payload_limit_middleware;
+
 use axum::{
     extract::{Request, State},
     http::HeaderMap,
@@ -56,6 +65,10 @@ pub struct AppState {
     pub concurrency_controller: Arc<crate::concurrency::ConcurrencyController>,
     /// IP 限流器
     pub rate_limiter: Arc<IpRateLimiter>,
+    /// 来源 IP 访问策略（全局 / 端点组 / 角色白名单）
+    pub ip_access_policy: Arc<ip_allowlist::IpAccessPolicy>,
+    /// 只读维护模式开关
+    pub maintenance: Arc<maintenance::MaintenanceMode>,
     /// RabbitMQ 发布器池 (P2.1)
     pub rabbitmq_publisher: Arc<crate::rabbitmq::RabbitMqPublisherPool>,
     /// Runner Docker 配置缓存 (运行时可重新加载)
@@ -64,17 +77,27 @@ pub struct AppState {
     pub runner_scheduler: Arc<crate::services::RunnerScheduler>,
     /// 存储服务 (P2.1)
     pub storage_service: Arc<crate::services::StorageService>,
+    /// 内容寻址 blob 存储（脚本与产物去重）
+    pub blob_store: Arc<crate::services::BlobStore>,
+    /// 合规证据包导出服务
+    pub evidence_exporter: Arc<crate::services::EvidenceExporter>,
+    /// 统计服务（预聚合看板数据）
+    pub stats_service: Arc<crate::services::StatsService>,
     /// Webhook Nonce 防重放存储
     pub webhook_nonce_store: Option<Arc<webhook_hmac::NonceStore>>,
     /// Runner 配置版本号（单调递增，供 Runner 检测配置变更）
     pub runner_config_version: Arc<AtomicU64>,
+    /// 用户语言偏好缓存
+    pub user_locales: Arc<crate::i18n::UserLocaleCache>,
 }
 
 /// 请求追踪中间件
 /// 为每个请求生成 trace_id 和 request_id，并记录指标
+///
+/// 客户端传入合法的 X-Request-Id 时沿用该值，否则生成新的 request_id
 pub async fn request_tracking_middleware(mut req: Request, next: Next) -> Response {
     let trace_id = extract_or_generate_trace_id(req.headers());
-    let request_id = Uuid::new_v4().to_string();
+    let request_id = extract_or_generate_request_id(req.headers());
 
     let method = req.method().to_string();
     let _method_static = method.as_str();
@@ -109,7 +132,7 @@ pub async fn request_tracking_middleware(mut req: Request, next: Next) -> Respon
     async move {
         let start = Instant::now();
 
-        let response = next.run(req).await;
+        let response = request_id::scope(request_id.clone(), next.run(req)).await;
 
         let elapsed = start.elapsed();
 
@@ -153,7 +176,7 @@ pub async fn request_tracking_middleware(mut req: Request, next: Next) -> Respon
             response.headers_mut().insert("x-trace-id", v);
         }
         if let Ok(v) = request_id.parse() {
-            response.headers_mut().insert("x-request-id", v);
+            response.headers_mut().insert(request_id::REQUEST_ID_HEADER, v);
         }
 
         response
@@ -171,6 +194,15 @@ fn extract_or_generate_trace_id(headers: &HeaderMap) -> String {
         .unwrap_or_else(|| Uuid::new_v4().to_string())
 }
 
+/// 从请求头中提取或生成 request_id
+fn extract_or_generate_request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(request_id::REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(request_id::sanitize)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
 /// 速率限制中间件（基于 Governor 的真实实现）
 /// 使用 IP 地址作为限流键
 pub async fn rate_limit_middleware(
@@ -178,8 +210,10 @@ pub async fn rate_limit_middleware(
     mut req: Request,
     next: Next,
 ) -> Result<Response, crate::error::AppError> {
-    // 获取客户端 IP
-    let client_ip = get_client_ip_with_addr(&req, state.config.security.trust_proxy)?;
+    // 获取客户端 IP（受信代理感知）
+    let client_ip = state
+        .ip_access_policy
+        .resolve_client_ip(&req, state.config.security.trust_proxy);
 
     // 从扩展中获取限流器
     let rate_limiter = state.rate_limiter.clone();
@@ -216,30 +250,6 @@ pub async fn rate_limit_middleware(
     Ok(next.run(req).await)
 }
 
-/// IP 白名单中间件
-pub async fn ip_whitelist_middleware(
-    State(state): State<Arc<AppState>>,
-    req: Request,
-    next: Next,
-) -> Result<Response, crate::error::AppError> {
-    if let Some(allowed_ips) = &state.config.security.allowed_ips {
-        let client_ip = get_client_ip_with_addr(&req, state.config.security.trust_proxy)?;
-        let client_ip_str = client_ip.to_string();
-
-        if !allowed_ips.contains(&client_ip_str) {
-            tracing::warn!(
-                client_ip = %client_ip_str,
-                "IP not in whitelist"
-            );
-            return Err(crate::error::AppError::Forbidden);
-        }
-
-        tracing::debug!(client_ip = %client_ip_str, "IP allowed by whitelist");
-    }
-
-    Ok(next.run(req).await)
-}
-
 /// 获取客户端 IP 地址（返回字符串）
 #[allow(dead_code)]
 pub fn get_client_ip(req: &Request, trust_proxy: bool) -> Result<String, crate::error::AppError> {
@@ -495,36 +505,98 @@ impl axum::extract::FromRequestParts<()> for ClientIp {
         parts: &mut axum::http::request::Parts,
         _state: &(),
     ) -> Result<Self, Self::Rejection> {
-        // 尝试从扩展中获取（由限流中间件设置）
+        // 优先使用限流中间件按 trust_proxy / trusted_proxies 解析出的 IP
         if let Some(ip) = parts.extensions.get::<IpAddr>() {
             return Ok(ClientIp(*ip));
         }
 
-        // 从头部解析
-        let headers = &parts.headers;
+        // 未经过限流中间件时只使用对端地址，不解析可被客户端伪造的代理头
+        if let Some(connect_info) = parts
+            .extensions
+            .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        {
+            return Ok(ClientIp(connect_info.0.ip()));
+        }
 
-        // 尝试各种代理头
-        if let Some(forwarded) = headers.get("x-forwarded-for") {
-            if let Ok(s) = forwarded.to_str() {
-                if let Some(first) = s.split(',').next() {
-                    if let Ok(ip) = first.trim().parse::<IpAddr>() {
-                        return Ok(ClientIp(ip));
-                    }
-                }
+        // 默认返回本地回环
+        Ok(ClientIp(IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1))))
+    }
+}
+
+// ==================== Runner API Key 鉴权 ====================
+
+/// 通过专属凭据认证的 Runner（附加到请求扩展）
+///
+/// 使用共享 API Key 认证的请求不附加该扩展
+#[derive(Debug, Clone)]
+pub struct AuthenticatedRunner {
+    pub runner_id: Uuid,
+    pub name: String,
+}
+
+/// Runner API Key 鉴权中间件
+/// 验证 Runner 注册和心跳请求中的 API Key
+///
+/// 接受配置中的共享 API Key，或通过注册令牌换取的 Runner 专属凭据
+pub async fn runner_auth_middleware(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, crate::error::AppError> {
+    // 从请求头获取 API Key（支持多种格式）
+    let headers = req.headers();
+    let provided_key = headers
+        .get("x-runner-api-key")
+        .or_else(|| headers.get("authorization"))
+        .and_then(|v| v.to_str().ok())
+        .map(|key| {
+            // 处理 Bearer token 格式
+            if key.to_lowercase().starts_with("bearer ") {
+                key[7..].to_string()
+            } else {
+                key.to_string()
             }
+        });
+
+    // Runner 专属凭据
+    if let Some(key) = provided_key.as_deref().filter(|k| k.starts_with("ops_ak_")) {
+        let repo = crate::repository::runner_repo::RunnerRepository::new(state.db.clone());
+        let key_hash = crate::auth::api_key::ApiKeyGenerator::hash(key);
+        if let Some((runner_id, name)) = repo.find_by_api_key_hash(&key_hash).await? {
+            tracing::debug!(runner_name = %name, "Runner credential validated successfully");
+            req.extensions_mut()
+                .insert(AuthenticatedRunner { runner_id, name });
+            return Ok(next.run(req).await);
         }
+    }
 
-        if let Some(real_ip) = headers.get("x-real-ip") {
-            if let Ok(s) = real_ip.to_str() {
-                if let Ok(ip) = s.parse::<IpAddr>() {
-                    return Ok(ClientIp(ip));
-                }
-            }
+    // 如果配置中未设置 runner_api_key，则跳过鉴权（用于开发/测试环境）
+    let expected_key = match &state.config.security.runner_api_key {
+        Some(key) => key.expose_secret(),
+        None => {
+            tracing::debug!("Runner API key not configured, skipping auth");
+            return Ok(next.run(req).await);
         }
+    };
 
-        // 默认返回本地回环
-        Ok(ClientIp(IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1))))
+    let Some(provided_key) = provided_key else {
+        return Err(crate::error::AppError::authentication("Missing Runner API Key"));
+    };
+
+    // 验证 API Key
+    if provided_key != expected_key {
+        tracing::warn!(
+            runner_name = req
+                .headers()
+                .get("x-runner-name")
+                .and_then(|v| v.to_str().ok()),
+            "Invalid Runner API key"
+        );
+        return Err(crate::error::AppError::authentication("Invalid Runner API Key"));
     }
+
+    tracing::debug!("Runner API key validated successfully");
+    Ok(next.run(req).await)
 }
 
 #[cfg(test)]
@@ -545,6 +617,19 @@ mod tests {
         assert_ne!(trace_id, "test-trace-123");
     }
 
+    #[test]
+    fn test_extract_or_generate_request_id() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "req-abc".parse().unwrap());
+        assert_eq!(extract_or_generate_request_id(&headers), "req-abc");
+
+        // 非法值被丢弃并重新生成
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "bad value".parse().unwrap());
+        let request_id = extract_or_generate_request_id(&headers);
+        assert!(Uuid::parse_str(&request_id).is_ok());
+    }
+
     #[test]
     fn test_rate_limit_config_default() {
         let config = RateLimitConfig::default();
@@ -573,52 +658,3 @@ mod tests {
         assert!(!limiter.check_rate_limit(&ip).await.unwrap());
     }
 }
-
-// ==================== Runner API Key 鉴权 ====================
-
-/// Runner API Key 鉴权中间件
-/// 验证 Runner 注册和心跳请求中的 API Key
-pub async fn runner_auth_middleware(
-    State(state): State<Arc<AppState>>,
-    req: Request,
-    next: Next,
-) -> Result<Response, crate::error::AppError> {
-    // 如果配置中未设置 runner_api_key，则跳过鉴权（用于开发/测试环境）
-    let expected_key = match &state.config.security.runner_api_key {
-        Some(key) => key.expose_secret(),
-        None => {
-            tracing::debug!("Runner API key not configured, skipping auth");
-            return Ok(next.run(req).await);
-        }
-    };
-
-    // 从请求头获取 API Key（支持多种格式）
-    let headers = req.headers();
-    let provided_key = headers
-        .get("x-runner-api-key")
-        .or_else(|| headers.get("authorization"))
-        .and_then(|v| v.to_str().ok());
-
-    let provided_key = if let Some(key) = provided_key {
-        // 处理 Bearer token 格式
-        if key.to_lowercase().starts_with("bearer ") {
-            &key[7..]
-        } else {
-            key
-        }
-    } else {
-        return Err(crate::error::AppError::authentication("Missing Runner API Key"));
-    };
-
-    // 验证 API Key
-    if provided_key != expected_key {
-        tracing::warn!(
-            runner_name = headers.get("x-runner-name").and_then(|v| v.to_str().ok()),
-            "Invalid Runner API key"
-        );
-        return Err(crate::error::AppError::authentication("Invalid Runner API Key"));
-    }
-
-    tracing::debug!("Runner API key validated successfully");
-    Ok(next.run(req).await)
-}
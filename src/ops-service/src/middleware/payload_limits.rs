@@ -0,0 +1,384 @@
+//! 请求体大小与字段限制
+//!
+//! 请求体上限由 `DefaultBodyLimit` 施加，超限时 axum 返回纯文本 413，
+//! 由 `payload_limit_middleware` 改写为统一错误响应；
+//! 作业、模板与审批请求在 handler 入口按 `PayloadLimitsConfig`
+//! 检查脚本大小、目标数、标签数与字段长度
+
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use uuid::Uuid;
+
+use crate::config::PayloadLimitsConfig;
+use crate::error::{AppError, Result};
+use crate::models::approval::{
+    ApproveRequestRequest, BulkApprovalRequest, CreateApprovalRequestRequest,
+    CreateJobTemplateRequest, DeprecateJobTemplateRequest, ExecuteTemplateJobRequest,
+    UpdateJobTemplateRequest,
+};
+use crate::models::job::{
+    CancelJobRequest, CreateCommandJobRequest, CreateFileJobRequest, CreateInventoryJobRequest,
+    CreateScriptJobRequest, FileManifest, ResolveDispatchPauseRequest, RetryJobRequest,
+    TaskSelectionRequest,
+};
+
+/// 将请求体超限的纯文本响应改写为统一错误响应
+pub async fn payload_limit_middleware(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE {
+        return response;
+    }
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if is_json {
+        return response;
+    }
+    AppError::PayloadTooLarge("Request body exceeds the size limit".to_string()).into_response()
+}
+
+/// 有字段限制的请求体
+pub trait PayloadLimited {
+    /// 检查字段限制，返回第一个超限字段的说明
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String>;
+
+    /// 检查字段限制（超限时返回 422）
+    fn enforce_limits(&self, limits: &PayloadLimitsConfig) -> Result<()> {
+        self.check_limits(limits)
+            .map_err(AppError::PayloadLimitExceeded)
+    }
+}
+
+/// 文本字段长度
+fn text(limits: &PayloadLimitsConfig, field: &str, value: &str) -> std::result::Result<(), String> {
+    if value.len() > limits.max_field_bytes {
+        return Err(format!("{} exceeds {} bytes", field, limits.max_field_bytes));
+    }
+    Ok(())
+}
+
+fn opt_text(
+    limits: &PayloadLimitsConfig,
+    field: &str,
+    value: Option<&String>,
+) -> std::result::Result<(), String> {
+    value.map_or(Ok(()), |v| text(limits, field, v))
+}
+
+/// 命令、脚本与模板内容长度
+fn script(
+    limits: &PayloadLimitsConfig,
+    field: &str,
+    value: Option<&String>,
+) -> std::result::Result<(), String> {
+    match value {
+        Some(v) if v.len() > limits.max_script_bytes => {
+            Err(format!("{} exceeds {} bytes", field, limits.max_script_bytes))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 目标数（目标主机 + 目标分组）
+fn targets(
+    limits: &PayloadLimitsConfig,
+    hosts: &[Uuid],
+    groups: &[Uuid],
+) -> std::result::Result<(), String> {
+    if hosts.len() + groups.len() > limits.max_targets {
+        return Err(format!(
+            "target_hosts and target_groups exceed {} entries",
+            limits.max_targets
+        ));
+    }
+    Ok(())
+}
+
+/// 标签数与单个标签长度
+fn tags(limits: &PayloadLimitsConfig, tags: &[String]) -> std::result::Result<(), String> {
+    if tags.len() > limits.max_tags {
+        return Err(format!("tags exceed {} entries", limits.max_tags));
+    }
+    tags.iter().try_for_each(|tag| text(limits, "tag", tag))
+}
+
+/// 其余列表字段的条目数
+fn items(limits: &PayloadLimitsConfig, field: &str, len: usize) -> std::result::Result<(), String> {
+    if len > limits.max_list_items {
+        return Err(format!("{} exceed {} entries", field, limits.max_list_items));
+    }
+    Ok(())
+}
+
+fn file_manifest(
+    limits: &PayloadLimitsConfig,
+    manifest: Option<&FileManifest>,
+) -> std::result::Result<(), String> {
+    let Some(manifest) = manifest else {
+        return Ok(());
+    };
+    items(limits, "file_manifest.paths", manifest.paths.len())?;
+    items(limits, "file_manifest.watch_roots", manifest.watch_roots.len())?;
+    manifest
+        .paths
+        .iter()
+        .chain(&manifest.watch_roots)
+        .try_for_each(|path| text(limits, "file_manifest path", path))
+}
+
+impl PayloadLimited for CreateCommandJobRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        text(limits, "name", &self.name)?;
+        opt_text(limits, "description", self.description.as_ref())?;
+        script(limits, "command", Some(&self.command))?;
+        targets(limits, &self.target_hosts, &self.target_groups)?;
+        tags(limits, &self.tags)?;
+        opt_text(limits, "execute_user", self.execute_user.as_ref())?;
+        opt_text(limits, "idempotency_key", self.idempotency_key.as_ref())?;
+        opt_text(limits, "singleton_key", self.singleton_key.as_ref())?;
+        file_manifest(limits, self.file_manifest.as_ref())
+    }
+}
+
+impl PayloadLimited for CreateScriptJobRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        text(limits, "name", &self.name)?;
+        opt_text(limits, "description", self.description.as_ref())?;
+        script(limits, "script", Some(&self.script))?;
+        opt_text(limits, "script_path", self.script_path.as_ref())?;
+        targets(limits, &self.target_hosts, &self.target_groups)?;
+        tags(limits, &self.tags)?;
+        opt_text(limits, "execute_user", self.execute_user.as_ref())?;
+        opt_text(limits, "idempotency_key", self.idempotency_key.as_ref())?;
+        opt_text(limits, "singleton_key", self.singleton_key.as_ref())?;
+        file_manifest(limits, self.file_manifest.as_ref())
+    }
+}
+
+impl PayloadLimited for CreateFileJobRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        text(limits, "name", &self.name)?;
+        opt_text(limits, "description", self.description.as_ref())?;
+        text(limits, "path", &self.file.path)?;
+        script(limits, "validate_command", self.file.validate_command.as_ref())?;
+        targets(limits, &self.target_hosts, &self.target_groups)?;
+        tags(limits, &self.tags)?;
+        opt_text(limits, "execute_user", self.execute_user.as_ref())?;
+        opt_text(limits, "idempotency_key", self.idempotency_key.as_ref())?;
+        opt_text(limits, "singleton_key", self.singleton_key.as_ref())
+    }
+}
+
+impl PayloadLimited for CreateInventoryJobRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        opt_text(limits, "name", self.name.as_ref())?;
+        opt_text(limits, "description", self.description.as_ref())?;
+        targets(limits, &self.target_hosts, &self.target_groups)?;
+        tags(limits, &self.tags)?;
+        opt_text(limits, "execute_user", self.execute_user.as_ref())?;
+        opt_text(limits, "idempotency_key", self.idempotency_key.as_ref())
+    }
+}
+
+impl PayloadLimited for CancelJobRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        opt_text(limits, "reason", self.reason.as_ref())
+    }
+}
+
+impl PayloadLimited for ResolveDispatchPauseRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        opt_text(limits, "reason", self.reason.as_ref())
+    }
+}
+
+impl PayloadLimited for RetryJobRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        items(limits, "task_ids", self.task_ids.as_ref().map_or(0, Vec::len))
+    }
+}
+
+impl PayloadLimited for TaskSelectionRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        items(limits, "task_ids", self.task_ids.len())?;
+        items(limits, "host_ids", self.host_ids.len())
+    }
+}
+
+impl PayloadLimited for CreateJobTemplateRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        text(limits, "name", &self.name)?;
+        opt_text(limits, "description", self.description.as_ref())?;
+        script(limits, "template_content", Some(&self.template_content))?;
+        script(limits, "prepend_content", self.prepend_content.as_ref())?;
+        script(limits, "append_content", self.append_content.as_ref())?;
+        items(limits, "applicable_environments", self.applicable_environments.len())?;
+        items(limits, "applicable_groups", self.applicable_groups.len())?;
+        items(limits, "includes", self.includes.len())
+    }
+}
+
+impl PayloadLimited for UpdateJobTemplateRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        opt_text(limits, "name", self.name.as_ref())?;
+        opt_text(limits, "description", self.description.as_ref())?;
+        script(limits, "template_content", self.template_content.as_ref())?;
+        script(limits, "prepend_content", self.prepend_content.as_ref())?;
+        script(limits, "append_content", self.append_content.as_ref())?;
+        let environments = self.applicable_environments.as_ref().map_or(0, Vec::len);
+        items(limits, "applicable_environments", environments)?;
+        let groups = self.applicable_groups.as_ref().map_or(0, Vec::len);
+        items(limits, "applicable_groups", groups)?;
+        items(limits, "includes", self.includes.as_ref().map_or(0, Vec::len))
+    }
+}
+
+impl PayloadLimited for DeprecateJobTemplateRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        opt_text(limits, "reason", self.reason.as_ref())
+    }
+}
+
+impl PayloadLimited for ExecuteTemplateJobRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        targets(limits, &self.target_hosts, &self.target_groups)?;
+        tags(limits, &self.tags)?;
+        opt_text(limits, "singleton_key", self.singleton_key.as_ref())
+    }
+}
+
+impl PayloadLimited for CreateApprovalRequestRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        text(limits, "request_type", &self.request_type)?;
+        text(limits, "title", &self.title)?;
+        opt_text(limits, "description", self.description.as_ref())?;
+        items(limits, "triggers", self.triggers.len())?;
+        items(limits, "quorum_rules", self.quorum_rules.as_ref().map_or(0, Vec::len))?;
+        items(limits, "reminder_percents", self.reminder_percents.as_ref().map_or(0, Vec::len))
+    }
+}
+
+impl PayloadLimited for ApproveRequestRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        opt_text(limits, "comment", self.comment.as_ref())
+    }
+}
+
+impl PayloadLimited for BulkApprovalRequest {
+    fn check_limits(&self, limits: &PayloadLimitsConfig) -> std::result::Result<(), String> {
+        items(limits, "approval_ids", self.approval_ids.len())?;
+        opt_text(limits, "comment", self.comment.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::job::SingletonPolicy;
+
+    fn limits() -> PayloadLimitsConfig {
+        PayloadLimitsConfig {
+            max_body_bytes: 1024,
+            max_script_bytes: 16,
+            max_targets: 2,
+            max_tags: 2,
+            max_field_bytes: 8,
+            max_list_items: 2,
+        }
+    }
+
+    fn command_job() -> CreateCommandJobRequest {
+        CreateCommandJobRequest {
+            name: "deploy".to_string(),
+            description: None,
+            target_hosts: vec![Uuid::new_v4()],
+            target_groups: vec![],
+            target_set_id: None,
+            command: "uptime".to_string(),
+            concurrent_limit: None,
+            timeout_secs: None,
+            retry_times: None,
+            execute_user: None,
+            exit_code_rules: None,
+            file_manifest: None,
+            drift_check: false,
+            verbose_trace: false,
+            idempotency_key: None,
+            singleton_key: None,
+            singleton_policy: SingletonPolicy::Reject,
+            tags: vec!["web".to_string()],
+            on_success_job_template: None,
+            on_failure_job_template: None,
+            artifact_id: None,
+        }
+    }
+
+    #[test]
+    fn test_command_job_limits() {
+        let limits = limits();
+        assert!(command_job().check_limits(&limits).is_ok());
+
+        let mut job = command_job();
+        job.command = "x".repeat(17);
+        assert_eq!(job.check_limits(&limits).unwrap_err(), "command exceeds 16 bytes");
+
+        let mut job = command_job();
+        job.target_groups = vec![Uuid::new_v4(), Uuid::new_v4()];
+        assert!(job
+            .check_limits(&limits)
+            .unwrap_err()
+            .starts_with("target_hosts"));
+
+        let mut job = command_job();
+        job.tags = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(job.check_limits(&limits).unwrap_err(), "tags exceed 2 entries");
+
+        let mut job = command_job();
+        job.tags = vec!["too-long-tag".to_string()];
+        assert_eq!(job.check_limits(&limits).unwrap_err(), "tag exceeds 8 bytes");
+    }
+
+    #[test]
+    fn test_enforce_limits_returns_422() {
+        let mut job = command_job();
+        job.name = "a-very-long-name".to_string();
+        let error = job.enforce_limits(&limits()).unwrap_err();
+        assert_eq!(error.code(), 422);
+        assert_eq!(error.detail().as_deref(), Some("name exceeds 8 bytes"));
+    }
+
+    #[test]
+    fn test_bulk_approval_limits() {
+        let request = BulkApprovalRequest {
+            approval_ids: vec![Uuid::new_v4(); 3],
+            comment: None,
+        };
+        assert_eq!(request.check_limits(&limits()).unwrap_err(), "approval_ids exceed 2 entries");
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_rejection_is_json() {
+        use axum::{body::Body, extract::DefaultBodyLimit, routing::post, Json, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/", post(|Json(body): Json<serde_json::Value>| async move { Json(body) }))
+            .layer(DefaultBodyLimit::max(16))
+            .layer(axum::middleware::from_fn(payload_limit_middleware));
+
+        let request = axum::http::Request::post("/")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(format!("\"{}\"", "x".repeat(64))))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let content_type = response.headers().get(header::CONTENT_TYPE).unwrap();
+        assert_eq!(content_type, "application/json");
+    }
+}
@@ -0,0 +1,87 @@
+//! 请求关联 ID（X-Request-Id）
+//!
+//! 请求追踪中间件为每个请求确定一个 request_id，并通过 task-local 在整个请求处理
+//! 过程中可见，供错误响应、审计日志、事件总线和 RabbitMQ 消息头读取。
+//! 由请求派生的后台任务需通过 [`spawn`] 启动，才能继承同一个 request_id。
+
+use std::future::Future;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+/// 请求关联 ID 的 HTTP 头
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 客户端传入的 request_id 最大长度
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// 获取当前任务上下文中的 request_id
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// 在指定 request_id 的上下文中执行 future
+pub async fn scope<F>(request_id: String, fut: F) -> F::Output
+where
+    F: Future,
+{
+    REQUEST_ID.scope(request_id, fut).await
+}
+
+/// 启动继承当前 request_id 和 tracing span 的后台任务
+///
+/// 不在请求上下文中调用时等价于 `tokio::spawn`
+pub fn spawn<F>(fut: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let fut = fut.in_current_span();
+    match current_request_id() {
+        Some(id) => tokio::spawn(REQUEST_ID.scope(id, fut)),
+        None => tokio::spawn(fut),
+    }
+}
+
+/// 校验客户端传入的 request_id
+///
+/// 只接受长度受限的可见 ASCII 字符，避免日志注入和响应头污染
+pub fn sanitize(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty()
+        || value.len() > MAX_REQUEST_ID_LEN
+        || !value.bytes().all(|b| b.is_ascii_graphic())
+    {
+        return None;
+    }
+    Some(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_request_id() {
+        assert_eq!(sanitize(" abc-123 "), Some("abc-123".to_string()));
+        assert_eq!(sanitize(""), None);
+        assert_eq!(sanitize("has space"), None);
+        assert_eq!(sanitize("line\nbreak"), None);
+        assert_eq!(sanitize(&"a".repeat(MAX_REQUEST_ID_LEN + 1)), None);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_propagates_to_spawned_task() {
+        assert_eq!(current_request_id(), None);
+
+        let inherited = scope("req-1".to_string(), async {
+            spawn(async { current_request_id() }).await.unwrap()
+        })
+        .await;
+
+        assert_eq!(inherited, Some("req-1".to_string()));
+    }
+}
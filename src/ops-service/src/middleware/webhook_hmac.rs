@@ -90,7 +90,7 @@ pub fn compute_hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
     };
 
     // Step 2: 用 0 填充到 block size
-    let mut key_padded = vec![0u8; BLOCK_SIZE];
+    let mut key_padded = [0u8; BLOCK_SIZE];
     key_padded[..key_material.len()].copy_from_slice(&key_material);
 
     // Step 3: 创建 inner key (key ⊕ 0x36) 和 outer key (key ⊕ 0x5c)
@@ -104,15 +104,15 @@ pub fn compute_hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
     // Step 4: inner hash = SHA256(key⊕ipad || message)
     let inner_hash = {
         let mut hasher = Sha256::new();
-        hasher.update(&inner_key);
+        hasher.update(inner_key);
         hasher.update(message);
         hasher.finalize()
     };
 
     // Step 5: final hash = SHA256(key⊕opad || inner_hash)
     let mut hasher = Sha256::new();
-    hasher.update(&outer_key);
-    hasher.update(&inner_hash);
+    hasher.update(outer_key);
+    hasher.update(inner_hash);
     hasher.finalize().to_vec()
 }
 
@@ -179,7 +179,7 @@ pub async fn build_webhook_hmac_middleware(
     })?;
 
     let now = chrono::Utc::now().timestamp();
-    let skew = (now - timestamp).abs() as u64;
+    let skew = (now - timestamp).unsigned_abs();
     if skew > max_skew {
         tracing::warn!(
             runner_id = %runner_id,
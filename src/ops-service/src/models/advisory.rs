@@ -0,0 +1,230 @@
+//! Security advisory domain models
+//! 安全公告：从 OSV 数据导入，与主机软件包清单匹配得到受影响主机
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::asset::PackageFormat;
+
+/// OSV record (https://ossf.github.io/osv-schema/), only the fields used for matching
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvRecord {
+    pub id: String,
+    pub modified: DateTime<Utc>,
+    #[serde(default)]
+    pub published: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub withdrawn: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub details: Option<String>,
+    #[serde(default)]
+    pub severity: Vec<OsvSeverity>,
+    #[serde(default)]
+    pub affected: Vec<OsvAffected>,
+    #[serde(default)]
+    pub database_specific: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvSeverity {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub score: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvAffected {
+    pub package: Option<OsvPackage>,
+    #[serde(default)]
+    pub ranges: Vec<OsvRange>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvPackage {
+    pub ecosystem: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvRange {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub events: Vec<OsvEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvEvent {
+    pub introduced: Option<String>,
+    pub fixed: Option<String>,
+    pub last_affected: Option<String>,
+}
+
+/// Advisory converted from an OSV record, ready to be stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewAdvisory {
+    pub id: String,
+    pub aliases: Vec<String>,
+    pub summary: Option<String>,
+    pub severity: Option<String>,
+    pub source: String,
+    pub published: Option<DateTime<Utc>>,
+    pub modified: DateTime<Utc>,
+    pub packages: Vec<AdvisoryPackage>,
+}
+
+/// Affected version range of one package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdvisoryPackage {
+    pub package_format: PackageFormat,
+    pub package_name: String,
+    pub ecosystem_release: String, // 发行版版本（如 Debian:12 中的 12），未指定时为空
+    pub introduced: Option<String>, // 为空表示自最早版本起受影响
+    pub fixed_version: Option<String>,
+    pub last_affected: Option<String>, // 与 fixed_version 均为空表示尚无修复版本
+}
+
+/// Stored advisory
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Advisory {
+    pub id: String,
+    pub aliases: Vec<String>,
+    pub summary: Option<String>,
+    pub severity: Option<String>,
+    pub source: String,
+    pub published: Option<DateTime<Utc>>,
+    pub modified: DateTime<Utc>,
+    pub imported_at: DateTime<Utc>,
+}
+
+/// Advisory list entry with the number of affected hosts visible to the caller
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AdvisorySummary {
+    pub id: String,
+    pub aliases: Vec<String>,
+    pub summary: Option<String>,
+    pub severity: Option<String>,
+    pub source: String,
+    pub published: Option<DateTime<Utc>>,
+    pub modified: DateTime<Utc>,
+    pub affected_hosts: i64,
+}
+
+/// Advisory list query
+#[derive(Debug, Deserialize)]
+pub struct AdvisoryListQuery {
+    pub search: Option<String>, // 按公告 id、CVE 编号或摘要模糊匹配
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub affected_only: bool, // 只返回影响至少一台主机的公告
+    #[serde(default = "default_advisory_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn default_advisory_limit() -> i64 {
+    50
+}
+
+/// Host package affected by an advisory, candidate produced by joining ranges with inventory
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AdvisoryMatchCandidate {
+    pub advisory_id: String,
+    pub host_id: Uuid,
+    pub os_version: Option<String>,
+    pub package_format: String,
+    pub package_name: String,
+    pub installed_version: String,
+    pub ecosystem_release: String,
+    pub introduced: Option<String>,
+    pub fixed_version: Option<String>,
+    pub last_affected: Option<String>,
+}
+
+/// Matched installed package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdvisoryMatch {
+    pub advisory_id: String,
+    pub host_id: Uuid,
+    pub package_format: String,
+    pub package_name: String,
+    pub installed_version: String,
+    pub fixed_version: Option<String>,
+}
+
+/// Affected host in the per-advisory report
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AffectedHost {
+    pub host_id: Uuid,
+    pub identifier: String,
+    pub address: String,
+    pub environment: String,
+    pub group_id: Uuid,
+    pub package_format: String,
+    pub package_name: String,
+    pub installed_version: String,
+    pub fixed_version: Option<String>,
+    pub matched_at: DateTime<Utc>,
+}
+
+/// Advisory affecting a host in the per-host report
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct HostAdvisory {
+    pub advisory_id: String,
+    pub aliases: Vec<String>,
+    pub summary: Option<String>,
+    pub severity: Option<String>,
+    pub package_format: String,
+    pub package_name: String,
+    pub installed_version: String,
+    pub fixed_version: Option<String>,
+    pub matched_at: DateTime<Utc>,
+}
+
+/// Import request: OSV records posted directly
+#[derive(Debug, Deserialize)]
+pub struct ImportAdvisoriesRequest {
+    pub records: Vec<serde_json::Value>,
+}
+
+/// Import result
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AdvisoryImportReport {
+    pub imported: usize,  // 新增或更新的公告数
+    pub unchanged: usize, // modified 未变化而跳过的公告数
+    pub skipped: usize,   // 无法解析、已撤回或不涉及支持的发行版的记录数
+    pub matches: usize,   // 重算后的匹配数
+}
+
+/// Remediation job request; the command is generated from the affected packages
+#[derive(Debug, Deserialize)]
+pub struct CreateRemediationJobRequest {
+    /// 受影响主机涉及多种包管理器时必须指定
+    #[serde(default)]
+    pub package_format: Option<String>,
+    /// 只修复其中部分主机（须为受影响主机）
+    #[serde(default)]
+    pub host_ids: Option<Vec<Uuid>>,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub concurrent_limit: Option<i32>,
+    pub timeout_secs: Option<i32>,
+    pub execute_user: Option<String>,
+    pub idempotency_key: Option<String>,
+}
+
+/// Pre-filled remediation job for the affected hosts of one package format
+#[derive(Debug, Clone, Serialize)]
+pub struct RemediationDraft {
+    pub advisory_id: String,
+    pub package_format: String,
+    pub packages: Vec<String>,
+    pub command: String,
+    pub target_hosts: Vec<Uuid>,
+}
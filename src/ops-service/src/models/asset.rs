@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::types::Json;
 use uuid::Uuid;
 
+use crate::ssh::OutputEncoding;
+
 /// Asset group
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct AssetGroup {
@@ -13,6 +15,11 @@ pub struct AssetGroup {
     pub description: Option<String>,
     pub environment: String,
     pub parent_id: Option<Uuid>,
+    // 分组并发上限（为空时回退到全局分组/生产环境限制）
+    pub concurrent_limit: Option<i32>,
+    pub is_production: bool,
+    // 关键分组（目标主机属于该分组的作业需要审批）
+    pub is_critical: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: Option<Uuid>,
@@ -25,6 +32,22 @@ pub struct CreateGroupRequest {
     pub description: Option<String>,
     pub environment: String,
     pub parent_id: Option<Uuid>,
+    #[serde(default)]
+    pub concurrent_limit: Option<i32>,
+    #[serde(default)]
+    pub is_production: bool,
+    #[serde(default)]
+    pub is_critical: bool,
+}
+
+impl CreateGroupRequest {
+    /// 校验并发上限
+    pub fn validate(&self) -> Result<(), String> {
+        match self.concurrent_limit {
+            Some(limit) if limit <= 0 => Err("concurrent_limit must be positive".into()),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Update group request
@@ -34,6 +57,20 @@ pub struct UpdateGroupRequest {
     pub description: Option<String>,
     pub environment: Option<String>,
     pub parent_id: Option<Uuid>,
+    /// 并发上限；0 表示清除，回退到全局配置
+    pub concurrent_limit: Option<i32>,
+    pub is_production: Option<bool>,
+    pub is_critical: Option<bool>,
+}
+
+impl UpdateGroupRequest {
+    /// 校验并发上限
+    pub fn validate(&self) -> Result<(), String> {
+        match self.concurrent_limit {
+            Some(limit) if limit < 0 => Err("concurrent_limit must not be negative".into()),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Host asset
@@ -52,6 +89,10 @@ pub struct Host {
     pub notes: Option<String>,
     pub os_type: Option<String>,
     pub os_version: Option<String>,
+    // CPU 架构（清单采集作业收集，部署时据此选择产物变体）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub arch: Option<String>,
     // SSH 认证凭据（主机级，优先于全局默认值）
     pub ssh_username: Option<String>,
     pub ssh_password: Option<String>,       // 加密存储
@@ -63,6 +104,28 @@ pub struct Host {
     // SSH known_hosts（新增，JSON 格式存储）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub known_hosts: Option<Json<std::collections::HashMap<String, String>>>,
+    // 远端输出编码（"utf-8", "gbk", "auto"，为空时按 UTF-8 处理）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub output_encoding: Option<String>,
+    // 连接方式（"ssh", "docker", "kubernetes"），非 SSH 目标的参数见 connection_options
+    #[serde(default)]
+    #[sqlx(default)]
+    pub connection_type: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub connection_options: Option<Json<ConnectionOptions>>,
+    // Kubernetes API Server 访问令牌（不返回给客户端）
+    #[serde(default, skip_serializing)]
+    #[sqlx(default)]
+    pub connection_token: Option<String>,
+    // 维护窗口（status 为 maintenance 时生效，结束时间为空表示需手动结束）
+    pub maintenance_until: Option<DateTime<Utc>>,
+    pub maintenance_reason: Option<String>,
+    pub maintenance_started_at: Option<DateTime<Utc>>,
+    pub maintenance_set_by: Option<Uuid>,
+    /// 主机密钥漂移检查发现密钥变化的时间（非空时禁止执行，重新固定后清除）
+    pub host_key_blocked_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: Option<Uuid>,
@@ -70,6 +133,173 @@ pub struct Host {
     pub version: i32,
 }
 
+impl Host {
+    /// 主机当前是否处于维护中（维护结束时间已过视为已结束）
+    pub fn is_under_maintenance(&self, now: DateTime<Utc>) -> bool {
+        self.status == "maintenance" && self.maintenance_until.map_or(true, |until| until > now)
+    }
+
+    /// 主机的连接方式（未设置或无法识别时按 SSH 处理）
+    pub fn connection_kind(&self) -> ConnectionType {
+        self.connection_type.parse().unwrap_or_default()
+    }
+
+    /// 与更新后的主机对比，返回变更字段的前后值（审计用）
+    ///
+    /// 凭据只记录是否变更；更新时间、更新人与版本号不计入
+    pub fn audit_diff(&self, after: &Host) -> serde_json::Value {
+        const IGNORED: &[&str] = &["updated_at", "updated_by", "version"];
+        const SECRETS: &[&str] = &["ssh_password", "ssh_private_key", "ssh_key_passphrase"];
+
+        let fields = |host: &Host| match serde_json::to_value(host) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        let (before, after_fields) = (fields(self), fields(after));
+        let keys: std::collections::BTreeSet<&String> =
+            before.keys().chain(after_fields.keys()).collect();
+
+        let mut diff = serde_json::Map::new();
+        for key in keys {
+            if IGNORED.contains(&key.as_str()) {
+                continue;
+            }
+            let old = before.get(key).unwrap_or(&serde_json::Value::Null);
+            let new = after_fields.get(key).unwrap_or(&serde_json::Value::Null);
+            if old == new {
+                continue;
+            }
+            let entry = if SECRETS.contains(&key.as_str()) {
+                serde_json::json!({ "changed": true })
+            } else {
+                serde_json::json!({ "before": old, "after": new })
+            };
+            diff.insert(key.clone(), entry);
+        }
+        // 访问令牌不参与序列化
+        if self.connection_token != after.connection_token {
+            diff.insert("connection_token".to_string(), serde_json::json!({ "changed": true }));
+        }
+        serde_json::Value::Object(diff)
+    }
+}
+
+/// 主机连接方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionType {
+    /// 通过 SSH 连接主机（默认）
+    #[default]
+    Ssh,
+    /// 经 SSH 连接 address 所指的 Docker 主机，在容器内执行（docker exec）
+    Docker,
+    /// 通过 API Server 在 Pod 内执行（kubectl exec）
+    Kubernetes,
+}
+
+impl ConnectionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionType::Ssh => "ssh",
+            ConnectionType::Docker => "docker",
+            ConnectionType::Kubernetes => "kubernetes",
+        }
+    }
+}
+
+impl std::str::FromStr for ConnectionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ssh" => Ok(ConnectionType::Ssh),
+            "docker" => Ok(ConnectionType::Docker),
+            "kubernetes" => Ok(ConnectionType::Kubernetes),
+            other => Err(format!("Unknown connection type: {}", other)),
+        }
+    }
+}
+
+/// 非 SSH 目标的连接参数
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionOptions {
+    /// 容器名称或 ID（docker 必填；kubernetes 为 Pod 内的容器，为空时使用默认容器）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// Kubernetes API Server 地址（如 https://10.0.0.1:6443）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_server: Option<String>,
+    /// Pod 所在命名空间（为空时为 default）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pod: Option<String>,
+    /// API Server 的 CA 证书（PEM）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+    #[serde(default)]
+    pub insecure_skip_tls_verify: bool,
+}
+
+impl ConnectionOptions {
+    /// 按连接方式校验必填参数与名称格式（名称会拼接到 docker/kubectl 命令行中）
+    pub fn validate(&self, connection_type: ConnectionType) -> Result<(), String> {
+        match connection_type {
+            ConnectionType::Ssh => Ok(()),
+            ConnectionType::Docker => {
+                let container = self
+                    .container
+                    .as_deref()
+                    .ok_or("Docker hosts require connection_options.container")?;
+                validate_target_name("container", container)
+            }
+            ConnectionType::Kubernetes => {
+                let api_server = self
+                    .api_server
+                    .as_deref()
+                    .ok_or("Kubernetes hosts require connection_options.api_server")?;
+                if !api_server.starts_with("https://") && !api_server.starts_with("http://") {
+                    return Err("api_server must be an http(s) URL".into());
+                }
+                let pod = self
+                    .pod
+                    .as_deref()
+                    .ok_or("Kubernetes hosts require connection_options.pod")?;
+                validate_target_name("pod", pod)?;
+                if let Some(namespace) = &self.namespace {
+                    validate_target_name("namespace", namespace)?;
+                }
+                if let Some(container) = &self.container {
+                    validate_target_name("container", container)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Pod 所在命名空间
+    pub fn namespace(&self) -> &str {
+        self.namespace.as_deref().unwrap_or("default")
+    }
+}
+
+/// 容器、Pod、命名空间名称：字母或数字开头，仅含字母、数字、'.'、'_'、'-'
+fn validate_target_name(field: &str, name: &str) -> Result<(), String> {
+    let valid = name.len() <= 253
+        && name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid {} name: {}", field, name))
+    }
+}
+
 /// Create host request
 #[derive(Debug, Deserialize)]
 pub struct CreateHostRequest {
@@ -98,6 +328,25 @@ pub struct CreateHostRequest {
     pub host_key_verification: Option<String>,
     // SSH known_hosts（可选，JSON 格式）
     pub known_hosts: Option<std::collections::HashMap<String, String>>,
+    // 远端输出编码（可选）
+    #[serde(default)]
+    pub output_encoding: Option<OutputEncoding>,
+    // 连接方式与非 SSH 目标参数（可选，默认 SSH）
+    #[serde(default)]
+    pub connection_type: ConnectionType,
+    #[serde(default)]
+    pub connection_options: Option<ConnectionOptions>,
+    pub connection_token: Option<String>,
+}
+
+impl CreateHostRequest {
+    /// 校验连接参数
+    pub fn validate(&self) -> Result<(), String> {
+        self.connection_options
+            .clone()
+            .unwrap_or_default()
+            .validate(self.connection_type)
+    }
 }
 
 fn default_port() -> i32 {
@@ -130,9 +379,67 @@ pub struct UpdateHostRequest {
     pub host_key_verification: Option<String>,
     // SSH known_hosts（可选，JSON 格式）
     pub known_hosts: Option<std::collections::HashMap<String, String>>,
+    // 远端输出编码（可选）
+    #[serde(default)]
+    pub output_encoding: Option<OutputEncoding>,
+    // 连接方式与非 SSH 目标参数（可选，与当前配置合并后校验）
+    #[serde(default)]
+    pub connection_type: Option<ConnectionType>,
+    #[serde(default)]
+    pub connection_options: Option<ConnectionOptions>,
+    pub connection_token: Option<String>,
     pub version: i32, // For optimistic locking
 }
 
+/// Update host SSH credentials request
+///
+/// 未提供的字段保持不变；生产环境主机的凭据变更需审批后生效
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateHostCredentialsRequest {
+    #[serde(default)]
+    pub ssh_username: Option<String>,
+    #[serde(default)]
+    pub ssh_password: Option<String>,
+    #[serde(default)]
+    pub ssh_private_key: Option<String>,
+    #[serde(default)]
+    pub ssh_key_passphrase: Option<String>,
+}
+
+impl UpdateHostCredentialsRequest {
+    /// 变更的字段名（不含取值，用于审批说明与审计）
+    pub fn changed_fields(&self) -> Vec<&'static str> {
+        [
+            ("ssh_username", self.ssh_username.is_some()),
+            ("ssh_password", self.ssh_password.is_some()),
+            ("ssh_private_key", self.ssh_private_key.is_some()),
+            ("ssh_key_passphrase", self.ssh_key_passphrase.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(field, _)| field)
+        .collect()
+    }
+}
+
+/// Set host maintenance request
+#[derive(Debug, Deserialize)]
+pub struct SetHostMaintenanceRequest {
+    /// 维护结束时间；为空时需手动结束维护
+    pub until: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+impl SetHostMaintenanceRequest {
+    /// 校验维护结束时间
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        match self.until {
+            Some(until) if until <= now => Err("Maintenance end time must be in the future".into()),
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Host list filters
 #[derive(Debug, Deserialize)]
 pub struct HostListFilters {
@@ -151,3 +458,469 @@ pub struct HostResponse {
     pub group_name: String,
     pub owner_name: Option<String>,
 }
+
+/// Centrally pinned SSH host key
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SshKnownHost {
+    pub id: Uuid,
+    pub host: String,
+    pub port: i32,
+    pub key_type: String,
+    pub public_key: String,
+    pub fingerprint: String, // OpenSSH 格式：SHA256:<base64>
+    pub pinned_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SshKnownHost {
+    /// 转换为下发给 Runner 的 known_hosts 记录
+    pub fn to_entry(&self) -> common::ssh::KnownHostEntry {
+        common::ssh::KnownHostEntry {
+            host: self.host.clone(),
+            port: self.port as u16,
+            key_type: self.key_type.clone(),
+            public_key: self.public_key.clone(),
+        }
+    }
+}
+
+/// SSH host key verification failure
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SshHostKeyFailureRecord {
+    pub id: Uuid,
+    pub host_id: Option<Uuid>,
+    pub task_id: Option<Uuid>,
+    pub host: String,
+    pub port: i32,
+    pub kind: String,   // "mismatch" 或 "unknown"
+    pub source: String, // "task" 或 "drift_check"
+    pub expected_fingerprint: Option<String>,
+    pub presented_fingerprint: String,
+    pub key_type: String,
+    pub public_key: String,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<Uuid>,
+}
+
+/// Host key failure list filters
+#[derive(Debug, Deserialize)]
+pub struct HostKeyFailureFilters {
+    #[serde(default)]
+    pub include_resolved: bool,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Re-pin host key request
+#[derive(Debug, Deserialize)]
+pub struct RepinHostKeyRequest {
+    /// 运维人员带外核实后的指纹，必须与主机本次提供的指纹一致
+    pub fingerprint: String,
+}
+
+/// Result of a single connection test stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionStageStatus {
+    Success,
+    Failed,
+    /// 前序阶段失败，未执行
+    Skipped,
+}
+
+/// Connection test stage (DNS, TCP, handshake, auth, exec)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionTestStage {
+    pub stage: crate::ssh::ConnectionPhase,
+    pub status: ConnectionStageStatus,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Overall connection test outcome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionTestOutcome {
+    /// 所有阶段成功
+    Reachable,
+    /// DNS、TCP 或握手失败
+    Unreachable,
+    AuthFailed,
+    /// 主机密钥与固定的指纹不一致或未知
+    HostKeyMismatch,
+    /// 已登录但测试命令失败或超时
+    CommandFailed,
+}
+
+impl ConnectionTestOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionTestOutcome::Reachable => "reachable",
+            ConnectionTestOutcome::Unreachable => "unreachable",
+            ConnectionTestOutcome::AuthFailed => "auth_failed",
+            ConnectionTestOutcome::HostKeyMismatch => "host_key_mismatch",
+            ConnectionTestOutcome::CommandFailed => "command_failed",
+        }
+    }
+}
+
+/// Staged connection test report for a host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionTestReport {
+    pub host_id: Uuid,
+    pub host_identifier: String,
+    pub address: String,
+    pub port: i32,
+    pub success: bool,
+    pub outcome: ConnectionTestOutcome,
+    pub stages: Vec<ConnectionTestStage>,
+    /// 执行器返回的错误（未归属到具体阶段时也在此给出）
+    pub error: Option<String>,
+    pub total_ms: u64,
+    pub tested_at: DateTime<Utc>,
+}
+
+/// Batch connection test run status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "connection_test_run_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionTestRunStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl ConnectionTestRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionTestRunStatus::Running => "running",
+            ConnectionTestRunStatus::Completed => "completed",
+            ConnectionTestRunStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Batch connection test summary (host counts per outcome)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionTestSummary {
+    pub total: usize,
+    pub reachable: usize,
+    pub unreachable: usize,
+    pub auth_failed: usize,
+    pub host_key_mismatch: usize,
+    pub command_failed: usize,
+}
+
+impl ConnectionTestSummary {
+    pub fn from_reports(reports: &[ConnectionTestReport]) -> Self {
+        let mut summary = Self {
+            total: reports.len(),
+            ..Self::default()
+        };
+        for report in reports {
+            match report.outcome {
+                ConnectionTestOutcome::Reachable => summary.reachable += 1,
+                ConnectionTestOutcome::Unreachable => summary.unreachable += 1,
+                ConnectionTestOutcome::AuthFailed => summary.auth_failed += 1,
+                ConnectionTestOutcome::HostKeyMismatch => summary.host_key_mismatch += 1,
+                ConnectionTestOutcome::CommandFailed => summary.command_failed += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// Batch connection test run for a group
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ConnectionTestRun {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub requested_by: Uuid,
+    pub status: ConnectionTestRunStatus,
+    pub concurrency: i32,
+    pub total_hosts: i32,
+    pub completed_hosts: i32,
+    pub summary: Option<Json<ConnectionTestSummary>>,
+    /// 各主机的分阶段测试报告（完成后写入；列表接口不返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<Json<Vec<ConnectionTestReport>>>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Start batch connection test request
+#[derive(Debug, Default, Deserialize)]
+pub struct StartConnectionTestRunRequest {
+    /// 同时测试的主机数（默认 10，最大 50）
+    pub concurrency: Option<u32>,
+}
+
+/// Credential rotation run status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "credential_rotation_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialRotationStatus {
+    Running,
+    /// 全部主机已切换到新密钥
+    Completed,
+    /// 部分主机切换失败（已回滚或保持原凭据）
+    PartiallyCompleted,
+    Failed,
+}
+
+/// Credential rotation phase
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "credential_rotation_phase", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialRotationPhase {
+    /// 通过作业向主机追加新公钥（使用当前凭据登录）
+    Push,
+    /// 使用新私钥登录验证
+    Verify,
+    /// 替换主机存储的凭据
+    Swap,
+    /// 通过作业从主机删除旧公钥（使用新凭据登录）
+    Retire,
+    Done,
+}
+
+/// Per-host credential rotation outcome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostRotationOutcome {
+    /// 已切换到新密钥并删除旧公钥
+    Rotated,
+    /// 已切换到新密钥，旧公钥仍保留在主机上
+    RotatedOldKeyRetained,
+    /// 推送新公钥失败，凭据未变更
+    PushFailed,
+    /// 验证或替换失败，已删除新公钥，凭据未变更
+    RolledBack,
+    /// 回滚失败，主机上可能残留新公钥，凭据未变更
+    RollbackFailed,
+}
+
+impl HostRotationOutcome {
+    /// 主机是否已切换到新凭据
+    pub fn is_rotated(&self) -> bool {
+        matches!(self, HostRotationOutcome::Rotated | HostRotationOutcome::RotatedOldKeyRetained)
+    }
+}
+
+/// Per-host credential rotation report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostRotationReport {
+    pub host_id: Uuid,
+    pub host_identifier: String,
+    pub outcome: HostRotationOutcome,
+    /// 出错的阶段（成功时为空）
+    pub failed_phase: Option<CredentialRotationPhase>,
+    /// 轮换前主机级私钥的公钥指纹（使用密码或全局默认凭据时为空）
+    pub old_key_fingerprint: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Host credential rotation run
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CredentialRotation {
+    pub id: Uuid,
+    pub requested_by: Uuid,
+    pub status: CredentialRotationStatus,
+    pub phase: CredentialRotationPhase,
+    /// generated 或 provided
+    pub key_source: String,
+    /// 新公钥（authorized_keys 行格式）
+    pub public_key: String,
+    pub key_fingerprint: String,
+    pub retire_old_key: bool,
+    pub total_hosts: i32,
+    pub push_job_id: Option<Uuid>,
+    pub retire_job_ids: Vec<Uuid>,
+    pub rollback_job_id: Option<Uuid>,
+    /// 各主机的轮换结果（完成后写入；列表接口不返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<Json<Vec<HostRotationReport>>>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Start credential rotation request
+///
+/// 不派生 Debug，避免私钥写入日志
+#[derive(Deserialize)]
+pub struct StartCredentialRotationRequest {
+    #[serde(default)]
+    pub host_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub group_ids: Vec<Uuid>,
+    /// 使用提供的私钥（OpenSSH 或 PEM 格式），为空时生成 Ed25519 密钥
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub key_passphrase: Option<String>,
+    /// 切换后从主机删除旧的主机级公钥（默认 true）
+    #[serde(default = "default_retire_old_key")]
+    pub retire_old_key: bool,
+}
+
+fn default_retire_old_key() -> bool {
+    true
+}
+
+/// Package manager an inventory was collected from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageFormat {
+    Dpkg,
+    Rpm,
+    Apk,
+    Pacman,
+}
+
+impl PackageFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dpkg => "dpkg",
+            Self::Rpm => "rpm",
+            Self::Apk => "apk",
+            Self::Pacman => "pacman",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "dpkg" => Some(Self::Dpkg),
+            "rpm" => Some(Self::Rpm),
+            "apk" => Some(Self::Apk),
+            "pacman" => Some(Self::Pacman),
+            _ => None,
+        }
+    }
+}
+
+/// Installed package parsed from an inventory job's output
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    pub arch: String, // 包管理器未提供架构时为空
+}
+
+/// Package inventory collected from one host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageInventory {
+    pub format: PackageFormat,
+    pub packages: Vec<InstalledPackage>,
+    pub host_arch: Option<String>, // 主机 CPU 架构（uname -m，已规范化）
+}
+
+/// Stored host package
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct HostPackage {
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub package_format: String,
+    pub job_id: Option<Uuid>, // 采集该清单的作业
+    pub collected_at: DateTime<Utc>,
+}
+
+/// Host package list query
+#[derive(Debug, Deserialize)]
+pub struct HostPackageQuery {
+    pub search: Option<String>, // 按包名模糊匹配
+}
+
+/// Fleet-wide package query, e.g. hosts with openssl < 3.0.2
+#[derive(Debug, Deserialize)]
+pub struct PackageHostQuery {
+    pub name: String,
+    pub environment: Option<String>,
+    pub version_lt: Option<String>,
+    pub version_lte: Option<String>,
+    pub version_gt: Option<String>,
+    pub version_gte: Option<String>,
+}
+
+/// Host with a matching installed package
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PackageHost {
+    pub host_id: Uuid,
+    pub identifier: String,
+    pub address: String,
+    pub environment: String,
+    pub group_id: Uuid,
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub collected_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host() -> Host {
+        Host {
+            id: Uuid::new_v4(),
+            identifier: "web-01".to_string(),
+            display_name: None,
+            address: "10.0.0.1".to_string(),
+            port: 22,
+            group_id: Uuid::new_v4(),
+            environment: "staging".to_string(),
+            tags: Json(vec![]),
+            owner_id: None,
+            status: "active".to_string(),
+            notes: None,
+            os_type: None,
+            os_version: None,
+            arch: None,
+            ssh_username: None,
+            ssh_password: None,
+            ssh_private_key: None,
+            ssh_key_passphrase: None,
+            host_key_verification: None,
+            known_hosts: None,
+            output_encoding: None,
+            connection_type: "ssh".to_string(),
+            connection_options: None,
+            connection_token: None,
+            maintenance_until: None,
+            maintenance_reason: None,
+            maintenance_started_at: None,
+            maintenance_set_by: None,
+            host_key_blocked_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: None,
+            updated_by: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn test_audit_diff_reports_changed_fields_only() {
+        let before = host();
+        let mut after = before.clone();
+        after.environment = "production".to_string();
+        after.tags = Json(vec!["db".to_string()]);
+        after.ssh_password = Some("encrypted".to_string());
+        after.updated_by = Some(Uuid::new_v4());
+        after.version += 1;
+
+        let diff = before.audit_diff(&after);
+        assert_eq!(diff["environment"]["before"], "staging");
+        assert_eq!(diff["environment"]["after"], "production");
+        assert_eq!(diff["tags"]["after"], serde_json::json!(["db"]));
+        // 凭据不记录内容
+        assert_eq!(diff["ssh_password"], serde_json::json!({ "changed": true }));
+        assert_eq!(diff.as_object().unwrap().len(), 3);
+
+        assert_eq!(before.audit_diff(&before), serde_json::json!({}));
+    }
+}
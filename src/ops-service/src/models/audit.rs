@@ -27,16 +27,70 @@ pub struct AuditLog {
 }
 
 /// Audit log filters
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct AuditLogFilters {
     pub subject_id: Option<Uuid>,
     pub resource_type: Option<String>,
     pub resource_id: Option<Uuid>,
     pub action: Option<String>,
+    /// Action category, e.g. `job` matches `job.create`, `job.cancel`
+    pub action_prefix: Option<String>,
     pub result: Option<String>,
+    pub source_ip: Option<String>,
     pub start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
     pub trace_id: Option<String>,
+    /// Free-text match against subject name, resource name and change summary
+    pub search: Option<String>,
+}
+
+/// Keyset pagination position in the (occurred_at DESC, id DESC) audit ordering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditCursor {
+    pub occurred_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl AuditCursor {
+    /// Cursor pointing just past the given entry
+    pub fn after(log: &AuditLog) -> Self {
+        Self {
+            occurred_at: log.occurred_at,
+            id: log.id,
+        }
+    }
+
+    /// Opaque URL-safe token handed to clients
+    pub fn encode(&self) -> String {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+        URL_SAFE_NO_PAD.encode(format!("{}|{}", self.occurred_at.timestamp_micros(), self.id))
+    }
+
+    /// Parse a token produced by [`AuditCursor::encode`]
+    pub fn decode(token: &str) -> Option<Self> {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+        let raw = String::from_utf8(URL_SAFE_NO_PAD.decode(token).ok()?).ok()?;
+        let (micros, id) = raw.split_once('|')?;
+        Some(Self {
+            occurred_at: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+/// One facet bucket: a distinct value and the number of matching entries
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditFacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Facet counts over the entries matching a filter set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditFacets {
+    pub action: Vec<AuditFacetCount>,
+    pub resource_type: Vec<AuditFacetCount>,
+    pub result: Vec<AuditFacetCount>,
 }
 
 /// Login event
@@ -55,6 +109,45 @@ pub struct LoginEvent {
     pub occurred_at: DateTime<Utc>,
 }
 
+/// Anomaly flagged by the background analyzer
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SecurityAnomaly {
+    pub id: Uuid,
+    /// new_login_ip, failed_auth_spike, job_creation_spike, off_hours_production
+    pub kind: String,
+    /// low, medium, high
+    pub severity: String,
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub source_ip: Option<String>,
+    pub summary: String,
+    pub details: serde_json::Value,
+    #[serde(skip_serializing)]
+    pub dedup_key: String,
+    pub detected_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub acknowledged_by: Option<Uuid>,
+    pub acknowledge_note: Option<String>,
+}
+
+/// Security anomaly filters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SecurityAnomalyFilters {
+    pub kind: Option<String>,
+    pub severity: Option<String>,
+    pub user_id: Option<Uuid>,
+    /// `false` lists only open anomalies, `true` only acknowledged ones
+    pub acknowledged: Option<bool>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+/// Acknowledge anomaly request
+#[derive(Debug, Default, Deserialize)]
+pub struct AcknowledgeAnomalyRequest {
+    pub note: Option<String>,
+}
+
 /// Refresh token record
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct RefreshToken {
@@ -68,4 +161,72 @@ pub struct RefreshToken {
     pub revoked_at: Option<DateTime<Utc>>,
     pub replaced_by: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    /// 所属登录会话
+    pub session_id: Option<Uuid>,
+}
+
+/// Login session record
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UserSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub device_id: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: String,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub last_seen_ip: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_by: Option<Uuid>,
+    pub revoke_reason: Option<String>,
+    /// 二次验证的有效期（高风险操作前需在有效期内）
+    pub step_up_until: Option<DateTime<Utc>>,
+}
+
+/// Login session as listed to its owner
+#[derive(Debug, Serialize)]
+pub struct UserSessionResponse {
+    #[serde(flatten)]
+    pub session: UserSession,
+    /// 是否为发起本次请求的会话
+    pub current: bool,
+}
+
+/// Impersonation session record
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ImpersonationSession {
+    pub id: Uuid,
+    pub impersonator_id: Uuid,
+    pub impersonator_name: String,
+    pub target_user_id: Uuid,
+    pub target_username: String,
+    pub reason: String,
+    pub source_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub terminated_at: Option<DateTime<Utc>>,
+    pub terminated_by: Option<Uuid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_cursor_roundtrip() {
+        let cursor = AuditCursor {
+            occurred_at: DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap(),
+            id: Uuid::new_v4(),
+        };
+        let token = cursor.encode();
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(AuditCursor::decode(&token), Some(cursor));
+
+        assert_eq!(AuditCursor::decode("not a cursor"), None);
+        assert_eq!(AuditCursor::decode(""), None);
+    }
 }
@@ -1,31 +1,90 @@
 //! Authentication-related models
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-/// Login request
+pub use common::api::{LoginRequest, LoginResponse, RefreshTokenRequest};
+
+/// Logout request
+#[derive(Debug, Deserialize)]
+pub struct LogoutRequest {
+    pub refresh_token: String,
+}
+
+/// Impersonation request
 #[derive(Debug, Deserialize)]
-pub struct LoginRequest {
-    pub username: String,
-    pub password: String,
+pub struct ImpersonateRequest {
+    /// 被模拟的用户
+    pub user_id: uuid::Uuid,
+    /// 模拟原因（必填，写入审计日志）
+    pub reason: String,
+    /// 令牌有效期（秒），缺省使用默认值
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
 }
 
-/// Login response
+/// Impersonation response
+///
+/// 模拟令牌不附带刷新令牌，过期或会话终止后需重新申请
 #[derive(Debug, Serialize)]
-pub struct LoginResponse {
+pub struct ImpersonationResponse {
     pub access_token: String,
-    pub refresh_token: String,
     pub expires_in: u64,
-    pub user: super::user::UserWithRoles,
+    pub session: super::audit::ImpersonationSession,
 }
 
-/// Token refresh request
-#[derive(Debug, Deserialize)]
-pub struct RefreshTokenRequest {
-    pub refresh_token: String,
+/// TOTP secret record
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserTotp {
+    pub user_id: Uuid,
+    /// 加密后的 Base32 密钥（见 auth::credential_cipher）
+    pub secret: String,
+    /// 为空表示已发起绑定但尚未确认
+    pub enabled_at: Option<DateTime<Utc>>,
+    /// 最近一次通过校验的时间步（防重放）
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
-/// Logout request
+/// Two-factor verification request（TOTP 验证码或恢复码）
 #[derive(Debug, Deserialize)]
-pub struct LogoutRequest {
-    pub refresh_token: String,
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+/// Two-factor status of the current user
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    /// 已发起绑定但尚未确认
+    pub pending: bool,
+    /// 所属角色是否要求启用双因素认证
+    pub required: bool,
+    pub recovery_codes_remaining: i64,
+    /// 当前会话二次验证的有效期
+    pub step_up_until: Option<DateTime<Utc>>,
+}
+
+/// TOTP enrollment response
+///
+/// 二维码由客户端根据 otpauth_uri 渲染，密钥也可手动输入认证器
+#[derive(Debug, Serialize)]
+pub struct TwoFactorEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// Recovery codes response（仅在生成时返回一次明文）
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Step-up verification response
+#[derive(Debug, Serialize)]
+pub struct StepUpResponse {
+    pub step_up_until: DateTime<Utc>,
 }
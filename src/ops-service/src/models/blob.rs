@@ -0,0 +1,138 @@
+//! 内容寻址 blob 存储模型
+//! 脚本与构建产物按 sha256 去重保存，引用计数归零后由 GC 回收
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// 脚本作业的脚本内容
+pub const BLOB_OWNER_JOB: &str = "job";
+/// 构建产物内容
+pub const BLOB_OWNER_ARTIFACT: &str = "artifact";
+
+/// blob 元数据
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ContentBlob {
+    pub sha256: String,
+    pub size_bytes: i64,
+    #[serde(skip_serializing)]
+    pub location: String,
+    pub ref_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub touched_at: DateTime<Utc>,
+}
+
+/// 写入结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobPutResult {
+    pub sha256: String,
+    pub size_bytes: i64,
+    /// 内容已存在，未重复写入对象存储
+    pub deduplicated: bool,
+}
+
+/// 按引用方类型统计
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BlobOwnerStats {
+    pub owner_type: String,
+    pub references: i64,
+    pub logical_bytes: i64,
+}
+
+/// 去重统计
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobStats {
+    /// 存储中的 blob 数量与字节数（每份内容只计一次）
+    pub blob_count: i64,
+    pub stored_bytes: i64,
+    /// 引用数量与逻辑字节数（不去重时需要保存的总量）
+    pub reference_count: i64,
+    pub logical_bytes: i64,
+    /// 去重节省的字节数
+    pub saved_bytes: i64,
+    /// 逻辑字节数 / 被引用内容的实际字节数
+    pub dedup_ratio: f64,
+    /// 等待 GC 回收的无引用 blob
+    pub unreferenced_count: i64,
+    pub unreferenced_bytes: i64,
+    pub by_owner_type: Vec<BlobOwnerStats>,
+}
+
+impl BlobStats {
+    /// 由聚合结果计算派生指标
+    pub fn from_totals(
+        blob_count: i64,
+        stored_bytes: i64,
+        unreferenced_count: i64,
+        unreferenced_bytes: i64,
+        by_owner_type: Vec<BlobOwnerStats>,
+    ) -> Self {
+        let reference_count = by_owner_type.iter().map(|o| o.references).sum();
+        let logical_bytes: i64 = by_owner_type.iter().map(|o| o.logical_bytes).sum();
+        let referenced_bytes = stored_bytes - unreferenced_bytes;
+        let dedup_ratio = if referenced_bytes > 0 {
+            logical_bytes as f64 / referenced_bytes as f64
+        } else {
+            1.0
+        };
+
+        Self {
+            blob_count,
+            stored_bytes,
+            reference_count,
+            logical_bytes,
+            saved_bytes: (logical_bytes - referenced_bytes).max(0),
+            dedup_ratio,
+            unreferenced_count,
+            unreferenced_bytes,
+            by_owner_type,
+        }
+    }
+}
+
+/// 单轮 GC 结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BlobGcReport {
+    pub deleted_count: u64,
+    pub deleted_bytes: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_stats_from_totals() {
+        // 3 个 blob 共 3000 字节，其中 1 个（500 字节）无引用；
+        // 被引用内容 2500 字节，逻辑上共 7500 字节
+        let stats = BlobStats::from_totals(
+            3,
+            3000,
+            1,
+            500,
+            vec![
+                BlobOwnerStats {
+                    owner_type: BLOB_OWNER_JOB.to_string(),
+                    references: 4,
+                    logical_bytes: 4000,
+                },
+                BlobOwnerStats {
+                    owner_type: BLOB_OWNER_ARTIFACT.to_string(),
+                    references: 2,
+                    logical_bytes: 3500,
+                },
+            ],
+        );
+
+        assert_eq!(stats.reference_count, 6);
+        assert_eq!(stats.logical_bytes, 7500);
+        assert_eq!(stats.saved_bytes, 5000);
+        assert!((stats.dedup_ratio - 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_blob_stats_empty_store() {
+        let stats = BlobStats::from_totals(0, 0, 0, 0, vec![]);
+        assert_eq!(stats.saved_bytes, 0);
+        assert_eq!(stats.dedup_ratio, 1.0);
+    }
+}
@@ -111,7 +111,10 @@ pub enum StepStatus {
     Running,
     Succeeded,
     Failed,
+    /// 超时（可按重试策略重试）
+    Timeout,
     Skipped,
+    Cancelled,
 }
 
 /// 构建步骤
@@ -130,6 +133,11 @@ pub struct BuildStep {
     pub completed_at: Option<DateTime<Utc>>,
     pub duration_secs: Option<i64>,
 
+    // 资源使用
+    pub peak_memory_bytes: Option<i64>,  // 峰值内存（字节）
+    pub cpu_time_ms: Option<i64>,        // CPU 时间（毫秒）
+    pub disk_written_bytes: Option<i64>, // 磁盘写入量（字节）
+
     // 输出
     pub output_summary: Option<String>, // 输出摘要
     pub output_detail: Option<String>,  // 完整输出
@@ -152,6 +160,8 @@ pub struct BuildArtifact {
     pub artifact_size: i64,      // 大小（字节）
     pub artifact_hash: String,   // Hash（SHA256）
     pub version: Option<String>, // 版本号
+    pub arch: String,            // 目标架构（为空表示与平台无关）
+    pub os: String,              // 目标操作系统（为空表示与平台无关）
 
     // 产物元数据
     pub metadata: Json<serde_json::Value>, // 额外元数据
@@ -170,6 +180,20 @@ pub struct BuildArtifact {
     pub uploaded_by: Uuid, // 上传者
 }
 
+/// 产物变体（同一逻辑版本下某个目标平台的产物）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ArtifactVariant {
+    pub id: Uuid,
+    pub artifact_name: String,
+    pub artifact_type: String,
+    pub artifact_path: String,
+    pub artifact_size: i64,
+    pub artifact_hash: String,
+    pub version: Option<String>,
+    pub arch: String,
+    pub os: String,
+}
+
 /// Runner 配置
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Runner {
@@ -210,6 +234,66 @@ pub struct CreateRunnerRequest {
     pub allowed_ips: Option<Vec<String>>,
 }
 
+/// Runner 注册令牌（一次性，不含令牌哈希）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RunnerEnrollmentToken {
+    pub id: Uuid,
+    pub runner_name: String,       // 允许注册的 Runner 名称
+    pub labels: Json<Vec<String>>, // Runner 必须具备的能力标签
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub used_by_runner_id: Option<Uuid>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// 创建 Runner 注册令牌请求
+#[derive(Debug, Deserialize)]
+pub struct CreateEnrollmentTokenRequest {
+    pub runner_name: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// 有效期（秒），缺省使用默认值
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+/// 创建 Runner 注册令牌响应（明文令牌仅返回一次）
+#[derive(Debug, Serialize)]
+pub struct EnrollmentTokenResponse {
+    pub token: String,
+    #[serde(flatten)]
+    pub enrollment: RunnerEnrollmentToken,
+}
+
+/// Runner 控制指令（结果随 Runner 心跳回报）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RunnerCommand {
+    pub id: Uuid,
+    pub runner_id: Uuid,
+    /// pause / resume / cleanup_workspaces / reregister / refresh_config
+    pub action: String,
+    /// pending / succeeded / failed / timed_out
+    pub status: String,
+    /// Runner 回报的执行结果或超时说明
+    pub result_message: Option<String>,
+    pub requested_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// 超过此时间仍未回报视为超时
+    pub expires_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+}
+
+/// 下发 Runner 控制指令请求
+#[derive(Debug, Deserialize)]
+pub struct CreateRunnerCommandRequest {
+    pub action: common::RunnerControlAction,
+    /// 等待 Runner 回报的超时时间（秒），缺省使用默认值
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
 /// 构建作业查询过滤器
 #[derive(Debug, Deserialize)]
 pub struct BuildJobListFilters {
@@ -245,6 +329,43 @@ pub struct ArtifactDownload {
     pub user_agent: Option<String>,
 }
 
+/// 产物晋级状态
+pub const PROMOTION_PENDING: &str = "pending";
+pub const PROMOTION_PROMOTED: &str = "promoted";
+pub const PROMOTION_REJECTED: &str = "rejected";
+
+/// 产物晋级记录（晋级链中的一环）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ArtifactPromotion {
+    pub id: Uuid,
+    pub artifact_id: Uuid,
+    pub from_environment: Option<String>, // 来源环境（链首环境为空）
+    pub to_environment: String,           // 目标环境
+    pub target: Option<String>,           // 晋级到的镜像仓库或存储位置
+    pub status: String,                   // pending/promoted/rejected
+    pub approval_request_id: Option<Uuid>, // 晋级审批请求
+    pub note: Option<String>,
+
+    // 审计字段
+    pub requested_by: Uuid,
+    pub requested_at: DateTime<Utc>,
+    pub promoted_by: Option<Uuid>, // 审批通过后完成晋级的用户
+    pub promoted_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 申请产物晋级请求
+#[derive(Debug, Deserialize)]
+pub struct PromoteArtifactRequest {
+    /// 目标环境（须在环境链中，且产物已晋级到前一个环境）
+    pub to_environment: String,
+    /// 晋级目标位置，为空时按配置的环境前缀生成
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
 /// 构建作业摘要
 #[derive(Debug, Serialize)]
 pub struct BuildJobSummary {
@@ -0,0 +1,297 @@
+//! Environment policy domain models
+//! 按环境配置的作业默认值与限制：默认超时、默认并发、强制审批、允许的作业类型与禁止变更窗口
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::job::JobType;
+
+/// 禁止变更窗口（按周重复）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlackoutWindow {
+    /// 生效的星期（1 = 周一 … 7 = 周日，为空表示每天），跨午夜的窗口按开始时间所在日判断
+    #[serde(default)]
+    pub weekdays: Vec<u32>,
+    /// 本地开始时间（HH:MM:SS）
+    pub start: NaiveTime,
+    /// 本地结束时间，早于开始时间时表示跨越午夜
+    pub end: NaiveTime,
+    /// 本地时间相对 UTC 的偏移（小时）
+    #[serde(default)]
+    pub utc_offset_hours: i32,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl BlackoutWindow {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.weekdays.iter().any(|d| !(1..=7).contains(d)) {
+            return Err("weekdays must be between 1 (Monday) and 7 (Sunday)".into());
+        }
+        if !(-12..=14).contains(&self.utc_offset_hours) {
+            return Err("utc_offset_hours must be between -12 and 14".into());
+        }
+        if self.start == self.end {
+            return Err("Blackout window start and end must differ".into());
+        }
+        Ok(())
+    }
+
+    /// 时间点是否处于窗口内
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let offset = FixedOffset::east_opt(self.utc_offset_hours * 3600)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).expect("zero offset is valid"));
+        let local = at.with_timezone(&offset);
+        let time = local.time();
+        let weekday = local.weekday().number_from_monday();
+        let applies = |day: u32| self.weekdays.is_empty() || self.weekdays.contains(&day);
+
+        if self.start < self.end {
+            applies(weekday) && time >= self.start && time < self.end
+        } else {
+            // 跨午夜：开始日 start 之后，或次日 end 之前
+            let previous = if weekday == 1 { 7 } else { weekday - 1 };
+            (applies(weekday) && time >= self.start) || (applies(previous) && time < self.end)
+        }
+    }
+}
+
+/// 环境策略
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EnvironmentPolicy {
+    pub environment: String,
+    /// 作业未指定超时时使用
+    pub default_timeout_secs: Option<i32>,
+    /// 作业未指定并发时使用
+    pub default_concurrent_limit: Option<i32>,
+    /// 目标包含该环境的作业必须审批
+    pub require_approval: bool,
+    /// 允许的作业类型（为空表示不限制）
+    pub allowed_job_types: Option<Json<Vec<JobType>>>,
+    pub blackout_windows: Json<Vec<BlackoutWindow>>,
+    pub updated_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 创建或更新环境策略请求
+#[derive(Debug, Deserialize)]
+pub struct UpsertEnvironmentPolicyRequest {
+    #[serde(default)]
+    pub default_timeout_secs: Option<i32>,
+    #[serde(default)]
+    pub default_concurrent_limit: Option<i32>,
+    #[serde(default)]
+    pub require_approval: bool,
+    #[serde(default)]
+    pub allowed_job_types: Option<Vec<JobType>>,
+    #[serde(default)]
+    pub blackout_windows: Vec<BlackoutWindow>,
+}
+
+impl UpsertEnvironmentPolicyRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.default_timeout_secs.is_some_and(|t| t <= 0) {
+            return Err("default_timeout_secs must be positive".into());
+        }
+        if self.default_concurrent_limit.is_some_and(|c| c <= 0) {
+            return Err("default_concurrent_limit must be positive".into());
+        }
+        if self
+            .allowed_job_types
+            .as_ref()
+            .is_some_and(|t| t.is_empty())
+        {
+            return Err("allowed_job_types must not be empty (omit it to allow all)".into());
+        }
+        self.blackout_windows
+            .iter()
+            .try_for_each(BlackoutWindow::validate)
+    }
+}
+
+/// 作业目标涉及的全部环境策略合并后的结果（取最保守值）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EffectivePolicy {
+    /// 各策略默认超时的最小值
+    pub default_timeout_secs: Option<i32>,
+    /// 各策略默认并发的最小值
+    pub default_concurrent_limit: Option<i32>,
+    pub require_approval: bool,
+    /// 各策略允许类型的交集（None 表示不限制）
+    pub allowed_job_types: Option<Vec<JobType>>,
+    /// 各策略的禁止变更窗口及所属环境
+    pub blackout_windows: Vec<(String, BlackoutWindow)>,
+}
+
+impl EffectivePolicy {
+    pub fn merge(policies: &[EnvironmentPolicy]) -> Self {
+        let mut effective = Self::default();
+        for policy in policies {
+            effective.default_timeout_secs =
+                min_some(effective.default_timeout_secs, policy.default_timeout_secs);
+            effective.default_concurrent_limit =
+                min_some(effective.default_concurrent_limit, policy.default_concurrent_limit);
+            effective.require_approval |= policy.require_approval;
+            if let Some(Json(allowed)) = &policy.allowed_job_types {
+                effective.allowed_job_types = Some(match effective.allowed_job_types.take() {
+                    Some(current) => current
+                        .into_iter()
+                        .filter(|t| allowed.contains(t))
+                        .collect(),
+                    None => allowed.clone(),
+                });
+            }
+            effective.blackout_windows.extend(
+                policy
+                    .blackout_windows
+                    .iter()
+                    .map(|w| (policy.environment.clone(), w.clone())),
+            );
+        }
+        effective
+    }
+
+    /// 校验作业类型是否允许、当前是否处于禁止变更窗口
+    pub fn check(&self, job_type: &JobType, now: DateTime<Utc>) -> Result<(), AppError> {
+        if self
+            .allowed_job_types
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(job_type))
+        {
+            return Err(AppError::validation(&format!(
+                "Job type {:?} is not allowed in the target environments",
+                job_type
+            )));
+        }
+        if let Some((environment, window)) =
+            self.blackout_windows.iter().find(|(_, w)| w.contains(now))
+        {
+            return Err(AppError::BadRequest(format!(
+                "Environment '{}' is in a blackout window{}",
+                environment,
+                window
+                    .reason
+                    .as_deref()
+                    .map(|r| format!(": {}", r))
+                    .unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn min_some(a: Option<i32>, b: Option<i32>) -> Option<i32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn time(value: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(value, "%H:%M").unwrap()
+    }
+
+    fn policy(environment: &str) -> EnvironmentPolicy {
+        EnvironmentPolicy {
+            environment: environment.to_string(),
+            default_timeout_secs: None,
+            default_concurrent_limit: None,
+            require_approval: false,
+            allowed_job_types: None,
+            blackout_windows: Json(vec![]),
+            updated_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_blackout_window_overnight() {
+        // 周五 22:00 至次日 06:00（UTC+8）
+        let window = BlackoutWindow {
+            weekdays: vec![5],
+            start: time("22:00"),
+            end: time("06:00"),
+            utc_offset_hours: 8,
+            reason: Some("weekend freeze".to_string()),
+        };
+        assert!(window.validate().is_ok());
+
+        // 2026-10-16 为周五
+        let friday_night = Utc.with_ymd_and_hms(2026, 10, 16, 15, 0, 0).unwrap();
+        let saturday_early = Utc.with_ymd_and_hms(2026, 10, 16, 21, 0, 0).unwrap();
+        let saturday_morning = Utc.with_ymd_and_hms(2026, 10, 16, 23, 0, 0).unwrap();
+        let thursday_night = Utc.with_ymd_and_hms(2026, 10, 15, 15, 0, 0).unwrap();
+        assert!(window.contains(friday_night));
+        assert!(window.contains(saturday_early));
+        assert!(!window.contains(saturday_morning));
+        assert!(!window.contains(thursday_night));
+    }
+
+    #[test]
+    fn test_merge_takes_most_conservative() {
+        let mut prod = policy("production");
+        prod.default_timeout_secs = Some(600);
+        prod.default_concurrent_limit = Some(5);
+        prod.require_approval = true;
+        prod.allowed_job_types = Some(Json(vec![JobType::Command, JobType::Script]));
+
+        let mut staging = policy("staging");
+        staging.default_timeout_secs = Some(1800);
+        staging.allowed_job_types = Some(Json(vec![JobType::Script, JobType::File]));
+
+        let effective = EffectivePolicy::merge(&[prod, staging]);
+        assert_eq!(effective.default_timeout_secs, Some(600));
+        assert_eq!(effective.default_concurrent_limit, Some(5));
+        assert!(effective.require_approval);
+        assert_eq!(effective.allowed_job_types, Some(vec![JobType::Script]));
+
+        let now = Utc::now();
+        assert!(effective.check(&JobType::Script, now).is_ok());
+        assert!(matches!(effective.check(&JobType::Command, now), Err(AppError::Validation(_))));
+        assert_eq!(EffectivePolicy::merge(&[]), EffectivePolicy::default());
+    }
+
+    #[test]
+    fn test_check_rejects_active_blackout() {
+        let mut prod = policy("production");
+        prod.blackout_windows = Json(vec![BlackoutWindow {
+            weekdays: vec![],
+            start: time("00:00"),
+            end: time("23:59"),
+            utc_offset_hours: 0,
+            reason: Some("release freeze".to_string()),
+        }]);
+        let effective = EffectivePolicy::merge(&[prod]);
+
+        let at = Utc.with_ymd_and_hms(2026, 10, 15, 12, 0, 0).unwrap();
+        let err = effective.check(&JobType::Command, at).unwrap_err();
+        assert!(err.to_string().contains("release freeze"));
+    }
+
+    #[test]
+    fn test_upsert_request_validation() {
+        let request: UpsertEnvironmentPolicyRequest = serde_json::from_value(serde_json::json!({
+            "default_timeout_secs": 0
+        }))
+        .unwrap();
+        assert!(request.validate().is_err());
+
+        let request: UpsertEnvironmentPolicyRequest = serde_json::from_value(serde_json::json!({
+            "require_approval": true,
+            "allowed_job_types": ["Command"],
+            "blackout_windows": [{"weekdays": [8], "start": "22:00:00", "end": "06:00:00"}]
+        }))
+        .unwrap();
+        assert!(request.validate().is_err());
+    }
+}
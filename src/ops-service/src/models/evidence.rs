@@ -0,0 +1,139 @@
+//! Evidence export domain models
+//! 作业合规证据包：作业详情、任务日志、审批与审计记录打包签名，供审计方核验
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::approval::{ApprovalRecord, ApprovalRequest};
+use super::audit::AuditLog;
+use super::job::{Job, TaskResponse};
+
+/// 证据包格式标识（写入归档，便于审计方识别版本）
+pub const EVIDENCE_FORMAT: &str = "ops-service.evidence.v1";
+
+/// 签名算法
+pub const EVIDENCE_SIGNATURE_ALGORITHM: &str = "HMAC-SHA256";
+
+/// 导出状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "evidence_export_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceExportStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl EvidenceExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvidenceExportStatus::Pending => "pending",
+            EvidenceExportStatus::Running => "running",
+            EvidenceExportStatus::Completed => "completed",
+            EvidenceExportStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::fmt::Display for EvidenceExportStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 导出记录
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EvidenceExport {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub requested_by: Uuid,
+    pub status: EvidenceExportStatus,
+    /// 进度（0-100）
+    pub progress: i32,
+    /// 当前阶段（job/tasks/approvals/audit_logs/signing）
+    pub stage: Option<String>,
+    #[serde(skip_serializing)]
+    pub location: Option<String>,
+    pub size_bytes: Option<i64>,
+    /// 归档文件（gzip）的 sha256
+    pub sha256: Option<String>,
+    pub signature: Option<String>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// 限时下载链接
+#[derive(Debug, Clone, Serialize)]
+pub struct EvidenceDownloadLink {
+    pub export_id: Uuid,
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// 下载链接参数
+#[derive(Debug, Deserialize)]
+pub struct EvidenceDownloadQuery {
+    /// 过期时间（Unix 秒）
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// 审批请求及其审批记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceApproval {
+    pub request: ApprovalRequest,
+    pub records: Vec<ApprovalRecord>,
+}
+
+/// 证据包清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceManifest {
+    pub export_id: Uuid,
+    pub job_id: Uuid,
+    pub generated_by: Uuid,
+    pub generated_at: DateTime<Utc>,
+    pub task_count: usize,
+    pub approval_count: usize,
+    pub audit_log_count: usize,
+}
+
+/// 证据内容
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EvidenceContent {
+    pub job: Job,
+    /// 任务及完整输出日志
+    pub tasks: Vec<TaskResponse>,
+    pub approvals: Vec<EvidenceApproval>,
+    /// 与作业及其审批相关的审计记录（按时间排序）
+    pub audit_logs: Vec<AuditLog>,
+}
+
+/// 被签名的部分
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EvidencePayload {
+    pub manifest: EvidenceManifest,
+    pub content: EvidenceContent,
+}
+
+/// 签名信息
+///
+/// `value` 为 HMAC-SHA256(key, payload)，payload 按紧凑 JSON 序列化；
+/// `key_id` 为签名密钥 sha256 的前 16 位，用于区分密钥轮换
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceSignature {
+    pub algorithm: String,
+    pub key_id: String,
+    pub value: String,
+}
+
+/// 证据包归档（gzip 压缩的 JSON 文档）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceArchive {
+    pub format: String,
+    pub payload: serde_json::Value,
+    pub signature: EvidenceSignature,
+}
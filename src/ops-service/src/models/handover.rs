@@ -0,0 +1,169 @@
+//! 交接班报告模型
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::job::JobStatus;
+
+/// 交接班报告查询参数
+#[derive(Debug, Deserialize)]
+pub struct HandoverQuery {
+    /// 窗口起始时间（默认结束时间前 12 小时，即一个班次）
+    pub from: Option<DateTime<Utc>>,
+    /// 窗口结束时间（默认当前时间）
+    pub to: Option<DateTime<Utc>>,
+    /// 返回格式：json（默认，含渲染后的 markdown）或 markdown
+    pub format: Option<String>,
+}
+
+impl HandoverQuery {
+    /// 默认窗口（小时）
+    pub const DEFAULT_WINDOW_HOURS: i64 = 12;
+    /// 查询窗口的最大跨度（7 天）
+    pub const MAX_WINDOW_HOURS: i64 = 168;
+
+    /// 查询窗口（起始、结束）
+    pub fn window(&self, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+        let to = self.to.unwrap_or(now);
+        let from = self
+            .from
+            .unwrap_or(to - Duration::hours(Self::DEFAULT_WINDOW_HOURS));
+        if from >= to {
+            return Err("from must be earlier than to".into());
+        }
+        if to - from > Duration::hours(Self::MAX_WINDOW_HOURS) {
+            return Err(format!(
+                "Handover window must not exceed {} hours",
+                Self::MAX_WINDOW_HOURS
+            ));
+        }
+        Ok((from, to))
+    }
+
+    /// 是否仅返回 markdown 文本
+    pub fn wants_markdown(&self) -> bool {
+        self.format
+            .as_deref()
+            .is_some_and(|format| format.eq_ignore_ascii_case("markdown"))
+    }
+}
+
+/// 窗口内按状态统计的作业数量
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HandoverJobStatusCount {
+    pub status: JobStatus,
+    pub job_count: i64,
+}
+
+/// 窗口内失败或部分成功的作业
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HandoverFailedJob {
+    pub id: Uuid,
+    pub name: String,
+    pub status: JobStatus,
+    pub failed_tasks: i32,
+    pub total_tasks: i32,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// 待处理的审批
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HandoverPendingApproval {
+    pub id: Uuid,
+    pub job_id: Option<Uuid>,
+    pub title: String,
+    pub requested_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub current_approvals: i32,
+    pub required_approvers: i32,
+}
+
+/// 离线的 Runner（启用状态但心跳超时）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HandoverOfflineRunner {
+    pub id: Uuid,
+    pub name: String,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+}
+
+/// 未恢复的失败主机：窗口内失败，且此后没有再成功执行过任务
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HandoverFailedHost {
+    pub host_id: Uuid,
+    pub identifier: String,
+    pub address: String,
+    pub job_id: Uuid,
+    pub job_name: String,
+    pub failure_message: Option<String>,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// 值得关注的审计记录（失败的操作或高影响操作）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HandoverAudit {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub subject_name: Option<String>,
+    pub action: String,
+    pub resource_type: String,
+    pub resource_name: Option<String>,
+    pub result: String,
+    pub changes_summary: Option<String>,
+}
+
+/// 窗口内的作业概况
+#[derive(Debug, Serialize)]
+pub struct HandoverJobSummary {
+    pub total: i64,
+    pub by_status: Vec<HandoverJobStatusCount>,
+    pub failed: Vec<HandoverFailedJob>,
+}
+
+/// 交接班报告
+#[derive(Debug, Serialize)]
+pub struct HandoverReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    pub jobs: HandoverJobSummary,
+    pub pending_approvals: Vec<HandoverPendingApproval>,
+    pub offline_runners: Vec<HandoverOfflineRunner>,
+    pub failed_hosts: Vec<HandoverFailedHost>,
+    pub notable_audits: Vec<HandoverAudit>,
+    /// 渲染后的 markdown，可直接粘贴到交接记录
+    pub markdown: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handover_query_window() {
+        let now = Utc::now();
+        let query = HandoverQuery {
+            from: None,
+            to: None,
+            format: None,
+        };
+        assert_eq!(query.window(now), Ok((now - Duration::hours(12), now)));
+        assert!(!query.wants_markdown());
+
+        let query = HandoverQuery {
+            from: Some(now),
+            to: Some(now - Duration::hours(1)),
+            format: Some("Markdown".to_string()),
+        };
+        assert!(query.window(now).is_err());
+        assert!(query.wants_markdown());
+
+        let query = HandoverQuery {
+            from: Some(now - Duration::days(8)),
+            to: None,
+            format: Some("json".to_string()),
+        };
+        assert!(query.window(now).is_err());
+        assert!(!query.wants_markdown());
+    }
+}
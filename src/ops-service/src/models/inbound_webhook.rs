@@ -0,0 +1,125 @@
+//! 入站 Webhook 模型
+//! 外部系统通过令牌触发绑定的作业模板
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// 入站 Webhook（不含令牌哈希）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InboundWebhook {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub template_id: Uuid,
+    pub target_hosts: Json<Vec<Uuid>>,
+    pub target_groups: Json<Vec<Uuid>>,
+    pub fixed_parameters: Json<serde_json::Map<String, serde_json::Value>>,
+    pub parameter_rules: Json<BTreeMap<String, WebhookParameterRule>>,
+    pub rate_limit_per_minute: i32,
+    pub is_active: bool,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_triggered_at: Option<DateTime<Utc>>,
+}
+
+/// 参数白名单规则
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhookParameterRule {
+    /// 值在请求体中的位置（JSON Pointer），缺省为 `/parameters/<参数名>`
+    #[serde(default)]
+    pub pointer: Option<String>,
+    /// 值必须完整匹配的正则，缺省只允许安全字符集
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// 最大长度（字符），缺省使用默认值
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// 非空时值必须为其中之一
+    #[serde(default)]
+    pub allowed_values: Vec<String>,
+    /// 请求体缺少该值时拒绝触发
+    #[serde(default)]
+    pub required: bool,
+    /// 请求体缺少该值时使用的默认值
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// 投递记录
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InboundWebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub received_at: DateTime<Utc>,
+    pub source_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub payload: Json<serde_json::Value>,
+    /// received / triggered / rejected / rate_limited / failed
+    pub status: String,
+    pub error: Option<String>,
+    pub parameters: Option<Json<serde_json::Value>>,
+    pub job_id: Option<Uuid>,
+}
+
+/// 创建入站 Webhook 请求
+#[derive(Debug, Deserialize)]
+pub struct CreateInboundWebhookRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub template_id: Uuid,
+    #[serde(default)]
+    pub target_hosts: Vec<Uuid>,
+    #[serde(default)]
+    pub target_groups: Vec<Uuid>,
+    #[serde(default)]
+    pub fixed_parameters: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub parameter_rules: BTreeMap<String, WebhookParameterRule>,
+    /// 每分钟最多触发次数，缺省使用默认值
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<i32>,
+}
+
+/// 更新入站 Webhook 请求（绑定的模板不可修改）
+#[derive(Debug, Deserialize)]
+pub struct UpdateInboundWebhookRequest {
+    pub description: Option<String>,
+    pub target_hosts: Option<Vec<Uuid>>,
+    pub target_groups: Option<Vec<Uuid>>,
+    pub fixed_parameters: Option<serde_json::Map<String, serde_json::Value>>,
+    pub parameter_rules: Option<BTreeMap<String, WebhookParameterRule>>,
+    pub rate_limit_per_minute: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// 创建或轮换令牌响应（明文令牌仅返回一次）
+#[derive(Debug, Serialize)]
+pub struct InboundWebhookTokenResponse {
+    pub token: String,
+    #[serde(flatten)]
+    pub webhook: InboundWebhook,
+}
+
+/// 投递记录查询参数
+#[derive(Debug, Deserialize)]
+pub struct InboundWebhookDeliveryQuery {
+    #[serde(default = "default_delivery_limit")]
+    pub limit: i64,
+}
+
+fn default_delivery_limit() -> i64 {
+    50
+}
+
+/// 触发结果
+#[derive(Debug, Serialize)]
+pub struct InboundWebhookTriggerResponse {
+    pub delivery_id: Uuid,
+    pub status: String,
+    pub job_id: Option<Uuid>,
+}
@@ -6,78 +6,54 @@ use serde::{Deserialize, Serialize};
 use sqlx::types::Json;
 use uuid::Uuid;
 
-/// 作业类型
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
-#[sqlx(type_name = "job_type", rename_all = "snake_case")]
-pub enum JobType {
-    /// 命令作业
-    Command,
-    /// 脚本作业
-    Script,
-    /// 构建作业
-    Build,
-}
+pub use common::api::{JobStatus, JobType, TaskStatus};
 
-/// 作业状态
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
-#[sqlx(type_name = "job_status", rename_all = "snake_case")]
-pub enum JobStatus {
-    /// 待执行
-    Pending,
-    /// 执行中
-    Running,
-    /// 已完成
-    Completed,
-    /// 已失败
+/// 退出码分类结果
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitCodeOutcome {
+    Succeeded,
+    Warning,
     Failed,
-    /// 已取消
-    Cancelled,
-    /// 部分成功（部分任务成功，部分失败）
-    PartiallySucceeded,
 }
 
-impl std::fmt::Display for JobStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            JobStatus::Pending => write!(f, "pending"),
-            JobStatus::Running => write!(f, "running"),
-            JobStatus::Completed => write!(f, "completed"),
-            JobStatus::Failed => write!(f, "failed"),
-            JobStatus::Cancelled => write!(f, "cancelled"),
-            JobStatus::PartiallySucceeded => write!(f, "partially_succeeded"),
-        }
+/// 退出码分类规则：命中 codes 或闭区间 range 的退出码归为 outcome
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExitCodeRule {
+    #[serde(default)]
+    pub codes: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<(i32, i32)>,
+    pub outcome: ExitCodeOutcome,
+}
+
+impl ExitCodeRule {
+    pub fn matches(&self, exit_code: i32) -> bool {
+        self.codes.contains(&exit_code)
+            || self
+                .range
+                .is_some_and(|(from, to)| (from..=to).contains(&exit_code))
     }
 }
 
-/// 任务状态（单个主机执行状态）
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
-#[sqlx(type_name = "task_status", rename_all = "snake_case")]
-pub enum TaskStatus {
-    /// 待执行
-    Pending,
-    /// 执行中
-    Running,
-    /// 成功
-    Succeeded,
-    /// 失败
-    Failed,
-    /// 超时
-    Timeout,
-    /// 已取消
-    Cancelled,
+/// 退出码分类规则集
+///
+/// 依次匹配自定义规则与内置工具分类表，均未命中时退出码 0 为成功、其余为失败
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExitCodeRules {
+    /// 内置分类表（如 rsync、grep、diff），见 /api/v1/exit-code-tables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<ExitCodeRule>,
 }
 
-impl std::fmt::Display for TaskStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            TaskStatus::Pending => write!(f, "pending"),
-            TaskStatus::Running => write!(f, "running"),
-            TaskStatus::Succeeded => write!(f, "succeeded"),
-            TaskStatus::Failed => write!(f, "failed"),
-            TaskStatus::Timeout => write!(f, "timeout"),
-            TaskStatus::Cancelled => write!(f, "cancelled"),
-        }
-    }
+/// 内置退出码分类表
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitCodeTable {
+    pub tool: &'static str,
+    pub description: &'static str,
+    pub rules: Vec<ExitCodeRule>,
 }
 
 /// 失败原因分类
@@ -96,10 +72,33 @@ pub enum FailureReason {
     CommandTimeout,
     /// 命令执行失败（非零退出码）
     CommandFailed,
+    /// 主机密钥验证失败（密钥不匹配或未知主机）
+    HostKeyMismatch,
+    /// 作业超出执行预算被停止
+    BudgetExceeded,
+    /// 命令输出超过上限被终止
+    OutputLimitExceeded,
+    /// 执行中认领作业的实例租约过期，结果无法确认
+    DispatchLeaseExpired,
     /// 未知错误
     Unknown,
 }
 
+impl From<common::FailureReason> for FailureReason {
+    fn from(reason: common::FailureReason) -> Self {
+        match reason {
+            common::FailureReason::NetworkError => FailureReason::NetworkError,
+            common::FailureReason::AuthFailed => FailureReason::AuthFailed,
+            common::FailureReason::ConnectionTimeout => FailureReason::ConnectionTimeout,
+            common::FailureReason::HandshakeTimeout => FailureReason::HandshakeTimeout,
+            common::FailureReason::CommandTimeout => FailureReason::CommandTimeout,
+            common::FailureReason::CommandFailed => FailureReason::CommandFailed,
+            common::FailureReason::HostKeyMismatch => FailureReason::HostKeyMismatch,
+            common::FailureReason::Unknown => FailureReason::Unknown,
+        }
+    }
+}
+
 /// 作业 - 顶层概念，代表批量执行任务
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Job {
@@ -114,25 +113,36 @@ pub struct Job {
     pub target_groups: Json<Vec<Uuid>>, // 目标分组ID列表
 
     // 执行参数
-    pub command: Option<String>,     // 命令作业的命令
-    pub script: Option<String>,      // 脚本作业的脚本内容
-    pub script_path: Option<String>, // 脚本路径（如适用）
+    pub command: Option<String>,                       // 命令作业的命令
+    pub script: Option<String>,                        // 脚本作业的脚本内容
+    pub script_path: Option<String>,                   // 脚本路径（如适用）
+    pub script_sha256: Option<String>, // 按哈希保存在 blob 存储中的脚本（此时 script 为空）
+    pub script_streamed: bool,         // 脚本经 SFTP 以文件传输到目标主机（不回填 script）
+    pub file_spec: Option<Json<FileDistributionSpec>>, // 文件分发作业的文件内容与写入选项
+    pub file_manifest: Option<Json<FileManifest>>, // 声明读写的文件（执行后报告未声明修改）
+    pub drift_check: bool,             // 执行后与基线输出比对（偏离检测作业）
+    pub verbose_trace: bool,           // 记录详细执行追踪（调试用）
 
     // 执行配置
-    pub concurrent_limit: Option<i32>, // 并发上限
-    pub timeout_secs: Option<i32>,     // 超时时间（秒）
-    pub retry_times: Option<i32>,      // 重试次数
-    pub execute_user: Option<String>,  // 执行用户
+    pub concurrent_limit: Option<i32>,                // 并发上限
+    pub timeout_secs: Option<i32>,                    // 超时时间（秒）
+    pub retry_times: Option<i32>,                     // 重试次数
+    pub execute_user: Option<String>,                 // 执行用户
+    pub exit_code_rules: Option<Json<ExitCodeRules>>, // 退出码分类规则（为空时仅 0 为成功）
 
     // 幂等性控制
     pub idempotency_key: Option<String>, // 幂等键
+    pub singleton_key: Option<String>,   // 互斥键（同一键同时只执行一个作业）
+    pub singleton_waiting: bool,         // 是否在同键作业之后排队等待
 
     // 结果统计
     pub total_tasks: i32,
     pub succeeded_tasks: i32,
+    pub warning_tasks: i32, // 退出码归为告警的任务（计入作业成功）
     pub failed_tasks: i32,
     pub timeout_tasks: i32,
     pub cancelled_tasks: i32,
+    pub skipped_tasks: i32, // 跳过未执行的任务（不参与作业状态判定）
 
     // 审计字段
     pub created_by: Uuid,
@@ -143,6 +153,296 @@ pub struct Job {
 
     // 元数据
     pub tags: Json<Vec<String>>,
+    pub template_id: Option<Uuid>, // 来源模板（模板作业）
+    pub artifact_id: Option<Uuid>, // 部署的构建产物
+
+    // 作业链
+    pub on_success_job_template: Option<Json<FollowUpJobTemplate>>, // 成功后启动的后续作业
+    pub on_failure_job_template: Option<Json<FollowUpJobTemplate>>, // 失败后启动的后续作业
+    pub parent_job_id: Option<Uuid>,                                // 触发本作业的父作业
+    pub chain_trigger: Option<String>, // 由父作业的 success/failure 触发
+    pub chain_depth: i32,              // 在作业链中的深度（根作业为 0）
+    pub chain_status: Option<String>,  // 后续作业派发状态：pending/launched/skipped/failed
+    pub chain_error: Option<String>,   // 未能启动后续作业的原因
+
+    // 执行预算
+    pub budget_exceeded: Option<Json<JobBudgetBreach>>, // 超出的执行预算（作业因此失败）
+
+    // 主机不可达退避
+    pub dispatch_paused_at: Option<DateTime<Utc>>, // 暂停派发的时间（等待确认继续或中止）
+    pub network_backoff: Option<Json<NetworkBackoffTrip>>, // 最近一次暂停派发的原因
+}
+
+/// 作业执行预算项
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobBudgetLimit {
+    /// 保存的输出总字节数
+    OutputBytes,
+    /// 推送的增量输出事件数
+    Events,
+    /// 任务数
+    Tasks,
+}
+
+impl JobBudgetLimit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobBudgetLimit::OutputBytes => "output_bytes",
+            JobBudgetLimit::Events => "events",
+            JobBudgetLimit::Tasks => "tasks",
+        }
+    }
+}
+
+/// 作业超出执行预算的记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JobBudgetBreach {
+    pub limit: JobBudgetLimit,
+    /// 预算上限
+    pub max: u64,
+    /// 超出时的实际值
+    pub observed: u64,
+    pub breached_at: DateTime<Utc>,
+}
+
+/// 作业因主机不可达暂停派发的记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NetworkBackoffTrip {
+    /// 窗口内不可达（网络错误、连接超时）的任务数
+    pub unreachable_tasks: u32,
+    /// 窗口内结束的任务数
+    pub sample_size: u32,
+    pub failure_rate: f64,
+    pub paused_at: DateTime<Utc>,
+}
+
+/// 作业链最大深度（根作业为 0），超过时不再启动后续作业
+pub const MAX_CHAIN_DEPTH: i32 = 5;
+
+/// 后续作业触发条件
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainTrigger {
+    /// 父作业全部成功（completed）
+    Success,
+    /// 父作业失败或部分成功（failed/partially_succeeded）；取消的作业不触发
+    Failure,
+}
+
+impl ChainTrigger {
+    /// 作业终态对应的触发条件
+    pub fn for_status(status: &JobStatus) -> Option<Self> {
+        match status {
+            JobStatus::Completed => Some(ChainTrigger::Success),
+            JobStatus::Failed | JobStatus::PartiallySucceeded => Some(ChainTrigger::Failure),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChainTrigger::Success => "success",
+            ChainTrigger::Failure => "failure",
+        }
+    }
+}
+
+/// 从父作业结果映射到后续作业模板参数的取值
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainParameterSource {
+    JobId,
+    JobName,
+    Status,
+    TotalTasks,
+    SucceededTasks,
+    FailedTasks,
+    /// 成功任务的主机标识，逗号分隔
+    SucceededHosts,
+    /// 失败/超时任务的主机标识，逗号分隔
+    FailedHosts,
+}
+
+/// 后续作业的目标主机
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowUpTarget {
+    /// 沿用父作业的目标主机
+    #[default]
+    ParentTargets,
+    /// 父作业中成功的主机
+    SucceededHosts,
+    /// 父作业中失败/超时的主机
+    FailedHosts,
+}
+
+/// 后续作业配置：父作业结束后按模板创建作业
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FollowUpJobTemplate {
+    pub template_id: Uuid,
+    /// 固定参数
+    #[serde(default)]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+    /// 参数名 -> 父作业结果（覆盖同名固定参数）
+    #[serde(default)]
+    pub parameter_mapping: std::collections::BTreeMap<String, ChainParameterSource>,
+    #[serde(default)]
+    pub target: FollowUpTarget,
+    /// 后续作业自身的后续作业
+    #[serde(default)]
+    pub on_success_job_template: Option<Box<FollowUpJobTemplate>>,
+    #[serde(default)]
+    pub on_failure_job_template: Option<Box<FollowUpJobTemplate>>,
+}
+
+impl FollowUpJobTemplate {
+    /// 嵌套层数（自身为 1）
+    pub fn depth(&self) -> i32 {
+        let nested = self
+            .on_success_job_template
+            .iter()
+            .chain(self.on_failure_job_template.iter())
+            .map(|f| f.depth())
+            .max()
+            .unwrap_or(0);
+        nested + 1
+    }
+
+    /// 指定触发条件下的后续作业
+    pub fn follow_up(&self, trigger: ChainTrigger) -> Option<&FollowUpJobTemplate> {
+        match trigger {
+            ChainTrigger::Success => self.on_success_job_template.as_deref(),
+            ChainTrigger::Failure => self.on_failure_job_template.as_deref(),
+        }
+    }
+}
+
+/// 作业链中的节点（用于链路可视化）
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct JobChainNode {
+    pub job_id: Uuid,
+    pub parent_job_id: Option<Uuid>,
+    pub name: String,
+    pub status: JobStatus,
+    pub template_id: Option<Uuid>,
+    pub chain_trigger: Option<String>,
+    pub chain_depth: i32,
+    pub chain_status: Option<String>,
+    pub chain_error: Option<String>,
+    /// 已配置的后续作业模板
+    pub on_success_template_id: Option<Uuid>,
+    pub on_failure_template_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// 作业链（根作业及全部后续作业，按深度与创建时间排序）
+#[derive(Debug, Clone, Serialize)]
+pub struct JobChain {
+    pub root_job_id: Uuid,
+    pub nodes: Vec<JobChainNode>,
+}
+
+/// 按作业结果筛选主机的条件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResultHostFilter {
+    /// 任务成功的主机
+    Succeeded,
+    /// 退出码被归为告警的主机
+    Warning,
+    /// 任务失败或超时的主机
+    Failed,
+    /// 退出码非 0 的主机（未取得退出码的任务不计入）
+    NonZeroExit,
+    /// 输出匹配正则的主机
+    OutputMatches { pattern: String },
+    /// 输出不匹配正则的主机
+    OutputNotMatches { pattern: String },
+    /// 输出偏离基线的主机
+    Drifted,
+}
+
+impl ResultHostFilter {
+    /// 输出正则（仅输出类条件）
+    pub fn pattern(&self) -> Option<&str> {
+        match self {
+            Self::OutputMatches { pattern } | Self::OutputNotMatches { pattern } => Some(pattern),
+            _ => None,
+        }
+    }
+
+    /// 判断任务是否满足条件，输出类条件使用预先编译的正则
+    pub fn matches(&self, task: &Task, regex: Option<&regex::Regex>) -> bool {
+        let output_matched = || {
+            let output = task
+                .output_detail
+                .as_deref()
+                .or(task.output_summary.as_deref())
+                .unwrap_or_default();
+            regex.is_some_and(|re| re.is_match(output))
+        };
+        match self {
+            Self::Succeeded => task.status == TaskStatus::Succeeded,
+            Self::Warning => task.status == TaskStatus::Warning,
+            Self::Failed => matches!(task.status, TaskStatus::Failed | TaskStatus::Timeout),
+            Self::NonZeroExit => task.exit_code.is_some_and(|code| code != 0),
+            Self::OutputMatches { .. } => output_matched(),
+            Self::OutputNotMatches { .. } => !output_matched(),
+            Self::Drifted => task
+                .output_drift
+                .as_ref()
+                .is_some_and(|drift| drift.state == DriftState::Drifted),
+        }
+    }
+}
+
+/// 作业结果派生的主机分组
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultHostGroup {
+    pub filter: ResultHostFilter,
+    pub host_ids: Vec<Uuid>,
+    pub count: usize,
+}
+
+/// 查询作业结果分组的参数
+#[derive(Debug, Deserialize)]
+pub struct ResultHostGroupsQuery {
+    /// 额外按输出正则划分匹配/不匹配两组
+    pub output_pattern: Option<String>,
+}
+
+/// 从作业结果创建的目标集合，可作为后续作业的 target_set_id
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct JobTargetSet {
+    pub id: Uuid,
+    pub source_job_id: Uuid,
+    pub name: Option<String>,
+    pub filter: Json<ResultHostFilter>,
+    pub host_ids: Json<Vec<Uuid>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 创建目标集合请求
+#[derive(Debug, Deserialize)]
+pub struct CreateJobTargetSetRequest {
+    pub name: Option<String>,
+    pub filter: ResultHostFilter,
+}
+
+/// 互斥键冲突策略：已有同键作业处于 pending/running 时如何处理新作业
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SingletonPolicy {
+    /// 拒绝新作业
+    #[default]
+    Reject,
+    /// 新作业排队，前序作业结束后执行
+    Queue,
+    /// 取消进行中的作业，执行新作业
+    Replace,
 }
 
 /// 创建命令作业请求
@@ -152,14 +452,40 @@ pub struct CreateCommandJobRequest {
     pub description: Option<String>,
     pub target_hosts: Vec<Uuid>,
     pub target_groups: Vec<Uuid>,
+    /// 作业结果派生的目标集合，创建时展开为目标主机
+    #[serde(default)]
+    pub target_set_id: Option<Uuid>,
     pub command: String,
     pub concurrent_limit: Option<i32>,
     pub timeout_secs: Option<i32>,
     pub retry_times: Option<i32>,
     pub execute_user: Option<String>,
+    /// 退出码分类规则（模板作业未指定时继承模板的规则）
+    #[serde(default)]
+    pub exit_code_rules: Option<ExitCodeRules>,
+    /// 声明将读写的文件，执行后报告声明路径的变化与监视目录中的未声明修改
+    #[serde(default)]
+    pub file_manifest: Option<FileManifest>,
+    /// 执行后将每台主机的输出与基线比对，不一致的任务标记为偏离
+    #[serde(default)]
+    pub drift_check: bool,
+    /// 记录详细执行追踪（SSH 协商、阶段耗时、并发等待、事件发布），目标含生产环境时仅管理员可开启
+    #[serde(default)]
+    pub verbose_trace: bool,
     pub idempotency_key: Option<String>,
     #[serde(default)]
+    pub singleton_key: Option<String>,
+    #[serde(default)]
+    pub singleton_policy: SingletonPolicy,
+    #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub on_success_job_template: Option<FollowUpJobTemplate>,
+    #[serde(default)]
+    pub on_failure_job_template: Option<FollowUpJobTemplate>,
+    /// 部署的构建产物（目标主机属于受限环境时须已晋级到该环境）
+    #[serde(default)]
+    pub artifact_id: Option<Uuid>,
 }
 
 /// 创建脚本作业请求
@@ -169,15 +495,267 @@ pub struct CreateScriptJobRequest {
     pub description: Option<String>,
     pub target_hosts: Vec<Uuid>,
     pub target_groups: Vec<Uuid>,
+    /// 作业结果派生的目标集合，创建时展开为目标主机
+    #[serde(default)]
+    pub target_set_id: Option<Uuid>,
+    /// 脚本内容（与 script_sha256 二选一）
+    #[serde(default)]
     pub script: String,
+    /// 已通过 /api/v1/blobs/upload 上传的脚本或二进制内容，执行时以文件传输到目标主机，不解析主机变量
+    #[serde(default)]
+    pub script_sha256: Option<String>,
     pub script_path: Option<String>,
     pub concurrent_limit: Option<i32>,
     pub timeout_secs: Option<i32>,
     pub retry_times: Option<i32>,
     pub execute_user: Option<String>,
+    /// 退出码分类规则（模板作业未指定时继承模板的规则）
+    #[serde(default)]
+    pub exit_code_rules: Option<ExitCodeRules>,
+    /// 声明将读写的文件，执行后报告声明路径的变化与监视目录中的未声明修改
+    #[serde(default)]
+    pub file_manifest: Option<FileManifest>,
+    /// 执行后将每台主机的输出与基线比对，不一致的任务标记为偏离
+    #[serde(default)]
+    pub drift_check: bool,
+    /// 记录详细执行追踪（SSH 协商、阶段耗时、并发等待、事件发布），目标含生产环境时仅管理员可开启
+    #[serde(default)]
+    pub verbose_trace: bool,
     pub idempotency_key: Option<String>,
     #[serde(default)]
+    pub singleton_key: Option<String>,
+    #[serde(default)]
+    pub singleton_policy: SingletonPolicy,
+    #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub on_success_job_template: Option<FollowUpJobTemplate>,
+    #[serde(default)]
+    pub on_failure_job_template: Option<FollowUpJobTemplate>,
+    /// 部署的构建产物（目标主机属于受限环境时须已晋级到该环境）
+    #[serde(default)]
+    pub artifact_id: Option<Uuid>,
+}
+
+/// 文件分发选项
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileDistributionSpec {
+    /// 目标文件的绝对路径
+    pub path: String,
+    pub content: String,
+    /// 八进制权限，如 "0644"（新文件默认 0644，已有文件默认保持原权限）
+    #[serde(default)]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    /// 内容变化时将原文件备份为 `<path>.ops-backup.<时间戳>`
+    #[serde(default = "default_file_backup")]
+    pub backup: bool,
+    /// 写入后执行的校验命令，失败时恢复原文件
+    #[serde(default)]
+    pub validate_command: Option<String>,
+}
+
+fn default_file_backup() -> bool {
+    true
+}
+
+/// 作业文件清单（变更控制证据）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileManifest {
+    /// 作业将读写的文件或目录（绝对路径，目录包含其下所有文件）
+    pub paths: Vec<String>,
+    /// 检查未声明修改的目录（为空时使用默认目录）
+    #[serde(default)]
+    pub watch_roots: Vec<String>,
+}
+
+/// 创建文件分发作业请求
+#[derive(Debug, Deserialize, validator::Validate)]
+pub struct CreateFileJobRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub target_hosts: Vec<Uuid>,
+    pub target_groups: Vec<Uuid>,
+    /// 作业结果派生的目标集合，创建时展开为目标主机
+    #[serde(default)]
+    pub target_set_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub file: FileDistributionSpec,
+    pub concurrent_limit: Option<i32>,
+    pub timeout_secs: Option<i32>,
+    pub retry_times: Option<i32>,
+    pub execute_user: Option<String>,
+    pub idempotency_key: Option<String>,
+    #[serde(default)]
+    pub singleton_key: Option<String>,
+    #[serde(default)]
+    pub singleton_policy: SingletonPolicy,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub on_success_job_template: Option<FollowUpJobTemplate>,
+    #[serde(default)]
+    pub on_failure_job_template: Option<FollowUpJobTemplate>,
+}
+
+/// 创建软件包清单采集作业请求
+#[derive(Debug, Deserialize, validator::Validate)]
+pub struct CreateInventoryJobRequest {
+    /// 为空时使用默认名称
+    #[serde(default)]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub target_hosts: Vec<Uuid>,
+    pub target_groups: Vec<Uuid>,
+    /// 作业结果派生的目标集合，创建时展开为目标主机
+    #[serde(default)]
+    pub target_set_id: Option<Uuid>,
+    pub concurrent_limit: Option<i32>,
+    pub timeout_secs: Option<i32>,
+    pub execute_user: Option<String>,
+    pub idempotency_key: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// 文件写入后校验结果
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileValidationStatus {
+    Passed,
+    Failed,
+    /// 未配置校验命令或未执行到校验
+    Skipped,
+}
+
+/// 单台主机的文件分发结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileDistributionReport {
+    /// 文件内容是否变化（新建文件视为变化）
+    pub changed: bool,
+    /// 目标文件写入前是否已存在
+    pub existed: bool,
+    pub backup_path: Option<String>,
+    /// 原文件与新内容的 unified diff（已脱敏，过长时截断）
+    pub diff: Option<String>,
+    pub validation: FileValidationStatus,
+    /// 校验失败后是否已恢复原文件
+    pub rolled_back: bool,
+}
+
+/// 声明路径中的文件变化
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// 声明路径中发生变化的文件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeclaredFileChange {
+    pub path: String,
+    pub change: FileChangeKind,
+}
+
+/// 单台主机的文件清单报告
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileManifestReport {
+    pub declared_changes: Vec<DeclaredFileChange>,
+    /// 监视目录中被修改但未被声明路径覆盖的文件
+    pub undeclared_modifications: Vec<String>,
+    /// 文件数超出上限，结果不完整
+    pub truncated: bool,
+    /// 是否取得了执行后的报告（为 false 时其余字段无意义）
+    pub complete: bool,
+    /// 报告完整、未截断且没有未声明修改
+    pub compliant: bool,
+}
+
+/// 基线输出（golden output）
+///
+/// 可按模板、按主机或按模板+主机配置；比对时依次使用模板+主机、模板、主机级基线
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OutputBaseline {
+    pub id: Uuid,
+    pub template_id: Option<Uuid>,
+    pub host_id: Option<Uuid>,
+    pub expected_output: String,
+    pub description: Option<String>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 创建或替换基线输出请求（同一模板/主机组合只保留一条）
+#[derive(Debug, Deserialize)]
+pub struct SetOutputBaselineRequest {
+    #[serde(default)]
+    pub template_id: Option<Uuid>,
+    #[serde(default)]
+    pub host_id: Option<Uuid>,
+    pub expected_output: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// 查询基线输出的参数
+#[derive(Debug, Default, Deserialize)]
+pub struct OutputBaselineFilters {
+    pub template_id: Option<Uuid>,
+    pub host_id: Option<Uuid>,
+}
+
+/// 与基线比对的结果
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftState {
+    /// 输出与基线一致
+    InSync,
+    /// 输出偏离基线
+    Drifted,
+    /// 未找到适用的基线
+    NoBaseline,
+}
+
+/// 单台主机的基线比对结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutputDrift {
+    pub state: DriftState,
+    pub baseline_id: Option<Uuid>,
+    /// 差异行（"-" 为基线独有，"+" 为实际输出独有，已脱敏）
+    #[serde(default)]
+    pub diff: Vec<String>,
+    /// 差异行超出上限被截断
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// 作业的偏离汇总报告
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub job_id: Uuid,
+    pub in_sync: usize,
+    pub drifted: usize,
+    pub no_baseline: usize,
+    /// 未完成比对的任务（执行失败、超时、取消或尚未执行）
+    pub not_checked: usize,
+    pub hosts: Vec<HostDriftEntry>,
+}
+
+/// 偏离报告中的单台主机
+#[derive(Debug, Clone, Serialize)]
+pub struct HostDriftEntry {
+    pub task_id: Uuid,
+    pub host_id: Uuid,
+    pub identifier: String,
+    pub task_status: TaskStatus,
+    /// 未完成比对时为空
+    pub drift: Option<OutputDrift>,
 }
 
 /// 任务 - 作业的执行单元，对应单个主机
@@ -201,16 +779,230 @@ pub struct Task {
     // 输出存档
     pub output_summary: Option<String>, // 输出摘要（用于列表展示，限制长度）
     pub output_detail: Option<String>,  // 完整输出（用于详细查询）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub output_normalized: bool, // 输出在规范化时是否被修改（ANSI 序列、控制字符）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub output_encoding: Option<String>, // 输出的原始编码（已转换为 UTF-8 存储）
 
     // 重试信息
     pub retry_count: i32,
     pub max_retries: i32,
 
+    // 执行上下文快照（任务开始执行时记录，已脱敏）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub execution_context: Option<Json<ExecutionContextSnapshot>>,
+
+    // 失败诊断（连接阶段、各阶段耗时、stderr 末尾若干行）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub diagnostics: Option<Json<crate::ssh::TaskDiagnostics>>,
+
+    // 文件分发结果（仅文件分发作业：是否变化、备份、diff、校验与回滚）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub file_result: Option<Json<FileDistributionReport>>,
+
+    // 文件清单报告（仅声明了文件清单的作业：声明路径的变化与未声明修改）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub file_manifest_report: Option<Json<FileManifestReport>>,
+
+    // 基线比对结果（仅偏离检测作业）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub output_drift: Option<Json<OutputDrift>>,
+
+    // 执行日志（各阶段到达的时间，用于定位停滞任务停在哪一步）
+    #[serde(default)]
+    #[sqlx(default)]
+    pub journal: Json<Vec<TaskJournalEntry>>,
+
     // 审计字段
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// 任务执行日志的阶段（按发生顺序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskJournalPhase {
+    /// 任务创建
+    Created,
+    /// 获得并发许可
+    PermitAcquired,
+    /// 与主机建立连接（SSH 握手完成）
+    Connected,
+    /// 认证通过
+    Authenticated,
+    /// 开始执行
+    ExecStarted,
+    /// 执行结束，输出收集完毕
+    OutputComplete,
+    /// 任务结果已写入数据库
+    StatusPersisted,
+    /// 任务事件已发布
+    EventsPublished,
+}
+
+impl TaskJournalPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::PermitAcquired => "permit_acquired",
+            Self::Connected => "connected",
+            Self::Authenticated => "authenticated",
+            Self::ExecStarted => "exec_started",
+            Self::OutputComplete => "output_complete",
+            Self::StatusPersisted => "status_persisted",
+            Self::EventsPublished => "events_published",
+        }
+    }
+}
+
+/// 任务执行日志条目
+///
+/// 存储为 `[阶段, 毫秒时间戳]`，接口中展开为对象。阶段保留为字符串，
+/// 滚动升级期间新版本写入的未知阶段不影响旧版本读取任务
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "StoredJournalEntry")]
+pub struct TaskJournalEntry {
+    pub phase: String,
+    pub at: DateTime<Utc>,
+}
+
+impl TaskJournalEntry {
+    /// 存储格式
+    pub fn compact(phase: TaskJournalPhase, at: DateTime<Utc>) -> serde_json::Value {
+        serde_json::json!([phase.as_str(), at.timestamp_millis()])
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredJournalEntry {
+    Compact(String, i64),
+    Expanded { phase: String, at: DateTime<Utc> },
+}
+
+impl From<StoredJournalEntry> for TaskJournalEntry {
+    fn from(entry: StoredJournalEntry) -> Self {
+        match entry {
+            StoredJournalEntry::Compact(phase, millis) => Self {
+                phase,
+                at: DateTime::from_timestamp_millis(millis).unwrap_or_default(),
+            },
+            StoredJournalEntry::Expanded { phase, at } => Self { phase, at },
+        }
+    }
+}
+
+/// 值可以原样记录的环境变量（其他变量只保留名称，值替换为掩码）
+pub const ENV_VALUE_ALLOWLIST: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "LANG", "LC_ALL", "LC_CTYPE", "TZ", "TERM", "PWD",
+];
+
+/// 环境变量值的掩码
+pub const MASKED_ENV_VALUE: &str = "***";
+
+/// 任务执行上下文快照
+///
+/// 记录任务实际的执行方式，便于事后追溯“到底执行了什么”；只记录认证方式类型，从不包含凭据
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExecutionContextSnapshot {
+    /// 执行器（ssh/local/mock）
+    pub executor: String,
+    pub host: String,
+    pub port: u16,
+    /// 实际执行用户
+    pub user: String,
+    /// 执行用户来源（job/host/default）
+    pub user_source: String,
+    /// 认证方式（password/private_key）
+    pub auth_method: String,
+    /// 凭据来源（host/default）
+    pub credential_source: String,
+    pub ssh_options: SshOptionsSnapshot,
+    /// 解释器（为空时由目标主机上执行用户的登录 shell 执行）
+    pub interpreter: Option<String>,
+    pub script_path: Option<String>,
+    /// 执行器传递的环境变量（不在白名单内的值已掩码）
+    pub env: std::collections::BTreeMap<String, String>,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// 任务执行时的 SSH 选项
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SshOptionsSnapshot {
+    pub connect_timeout_secs: u64,
+    pub handshake_timeout_secs: u64,
+    pub command_timeout_secs: u64,
+    pub host_key_verification: crate::ssh::HostKeyVerification,
+    /// known_hosts 来源（host/central/file），为空表示未使用
+    pub known_hosts_source: Option<String>,
+    /// 配置的输出编码
+    #[serde(default)]
+    pub output_encoding: crate::ssh::OutputEncoding,
+}
+
+impl ExecutionContextSnapshot {
+    /// 脱敏环境变量：白名单内的变量保留值，其余只保留名称
+    pub fn mask_env(
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> std::collections::BTreeMap<String, String> {
+        vars.into_iter()
+            .map(|(name, value)| {
+                if ENV_VALUE_ALLOWLIST.contains(&name.as_str()) {
+                    (name, value)
+                } else {
+                    (name, MASKED_ENV_VALUE.to_string())
+                }
+            })
+            .collect()
+    }
+}
+
+/// 作业事件增量查询参数
+#[derive(Debug, Deserialize)]
+pub struct JobEventsQuery {
+    /// 上次收到的事件序号（SSE 的 `id` 或上次轮询返回的 `last_seq`），首次轮询为 0
+    #[serde(default)]
+    pub since_seq: u64,
+}
+
+/// 作业详细追踪记录
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct JobTraceEntry {
+    pub id: i64,
+    pub job_id: Uuid,
+    pub task_id: Option<Uuid>,
+    /// ssh / phase / concurrency / event
+    pub category: String,
+    pub message: String,
+    pub duration_ms: Option<i64>,
+    pub details: Option<Json<serde_json::Value>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 作业详细追踪查询参数
+#[derive(Debug, Deserialize)]
+pub struct JobTraceQuery {
+    /// 上次返回的最大记录 ID，首次查询为 0
+    #[serde(default)]
+    pub after_id: i64,
+    /// 只返回指定任务的记录
+    #[serde(default)]
+    pub task_id: Option<Uuid>,
+    #[serde(default = "default_trace_limit")]
+    pub limit: i64,
+}
+
+fn default_trace_limit() -> i64 {
+    500
+}
+
 /// 作业查询过滤器
 #[derive(Debug, Deserialize, validator::Validate)]
 pub struct JobListFilters {
@@ -221,6 +1013,9 @@ pub struct JobListFilters {
     pub search: Option<String>, // 搜索name/description
     pub date_from: Option<DateTime<Utc>>,
     pub date_to: Option<DateTime<Utc>>,
+    /// 是否包含已归档的作业
+    #[serde(default)]
+    pub include_archived: bool,
 }
 
 /// 作业列表响应（包含摘要信息）
@@ -273,6 +1068,8 @@ pub struct TaskSummary {
     pub host_identifier: String,
     pub host_address: String,
     pub host_display_name: Option<String>,
+    /// 执行日志
+    pub journal: Vec<TaskJournalEntry>,
 }
 
 /// 任务列表响应（可以是完整响应或摘要）
@@ -292,6 +1089,24 @@ pub struct CancelJobRequest {
     pub reason: Option<String>,
 }
 
+/// 暂停派发后的处理
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatchPauseAction {
+    /// 继续派发剩余任务
+    Continue,
+    /// 中止作业（取消剩余任务）
+    Abort,
+}
+
+/// 处理暂停派发的作业请求
+#[derive(Debug, Deserialize, validator::Validate)]
+pub struct ResolveDispatchPauseRequest {
+    pub action: DispatchPauseAction,
+    /// 中止原因
+    pub reason: Option<String>,
+}
+
 /// 重试作业请求
 #[derive(Debug, Deserialize, validator::Validate)]
 pub struct RetryJobRequest {
@@ -302,18 +1117,80 @@ pub struct RetryJobRequest {
     pub task_ids: Option<Vec<Uuid>>,
 }
 
+/// 批量任务操作
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskBulkAction {
+    /// 取消执行中的任务
+    Cancel,
+    /// 跳过待执行的任务
+    Skip,
+    /// 重新执行已结束的任务
+    Rerun,
+}
+
+impl TaskBulkAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskBulkAction::Cancel => "cancel",
+            TaskBulkAction::Skip => "skip",
+            TaskBulkAction::Rerun => "rerun",
+        }
+    }
+}
+
+/// 批量任务操作请求（按任务或目标主机选择，两者取并集）
+#[derive(Debug, Deserialize, validator::Validate)]
+pub struct TaskSelectionRequest {
+    #[serde(default)]
+    pub task_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub host_ids: Vec<Uuid>,
+}
+
+/// 批量任务操作单项结果
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskBulkItemResult {
+    /// 选择的任务（按主机选择且作业中没有该主机时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_id: Option<Uuid>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_status: Option<TaskStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_status: Option<TaskStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 批量任务操作结果（附带重新计算后的作业统计）
+#[derive(Debug, Serialize)]
+pub struct TaskBulkResponse {
+    pub job_id: Uuid,
+    pub action: TaskBulkAction,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<TaskBulkItemResult>,
+    pub statistics: JobStatistics,
+}
+
 /// 作业执行统计
 #[derive(Debug, Serialize)]
 pub struct JobStatistics {
     pub job_id: Uuid,
     pub total_tasks: i32,
     pub succeeded_tasks: i32,
+    pub warning_tasks: i32, // 退出码归为告警
     pub failed_tasks: i32,
     pub timeout_tasks: i32,
     pub cancelled_tasks: i32,
+    pub skipped_tasks: i32,
     pub pending_tasks: i32,
     pub running_tasks: i32,
-    pub success_rate: f64,              // 成功率
+    pub waiting_maintenance_tasks: i32, // 等待主机维护结束
+    pub success_rate: f64,              // 成功率（不计跳过的任务）
     pub avg_duration_secs: Option<f64>, // 平均执行时长
     // 失败原因分类统计（P2）
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -335,10 +1212,244 @@ pub struct FailureReasonStats {
     pub command_timeout: i32,
     /// 命令执行失败数量
     pub command_failed: i32,
+    /// 主机密钥验证失败数量
+    pub host_key_mismatch: i32,
+    /// 超出执行预算数量
+    pub budget_exceeded: i32,
+    /// 输出超过上限数量
+    pub output_limit_exceeded: i32,
+    /// 派发租约过期中断数量
+    pub dispatch_lease_expired: i32,
     /// 未知错误数量
     pub unknown: i32,
 }
 
+/// 作业标签命名空间（如 team、change）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct JobTagNamespace {
+    pub name: String,
+    pub description: Option<String>,
+    /// 取值正则；为空时只允许已登记的标签值
+    pub value_pattern: Option<String>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 已登记的作业标签（namespace:value）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ManagedJobTag {
+    pub id: Uuid,
+    pub namespace: String,
+    pub value: String,
+    pub description: Option<String>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ManagedJobTag {
+    /// 作业上使用的完整标签
+    pub fn tag(&self) -> String {
+        format!("{}:{}", self.namespace, self.value)
+    }
+}
+
+/// 创建标签命名空间请求
+#[derive(Debug, Deserialize, validator::Validate)]
+pub struct CreateJobTagNamespaceRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub value_pattern: Option<String>,
+}
+
+/// 更新标签命名空间请求（整体替换描述与取值正则）
+#[derive(Debug, Deserialize, validator::Validate)]
+pub struct UpdateJobTagNamespaceRequest {
+    pub description: Option<String>,
+    pub value_pattern: Option<String>,
+}
+
+/// 创建标签请求
+#[derive(Debug, Deserialize, validator::Validate)]
+pub struct CreateManagedJobTagRequest {
+    pub namespace: String,
+    pub value: String,
+    pub description: Option<String>,
+}
+
+/// 更新标签请求
+#[derive(Debug, Deserialize, validator::Validate)]
+pub struct UpdateManagedJobTagRequest {
+    pub description: Option<String>,
+}
+
+/// 标签查询过滤器
+#[derive(Debug, Deserialize)]
+pub struct ManagedJobTagFilters {
+    pub namespace: Option<String>,
+}
+
+/// 报表时间粒度
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportInterval {
+    Day,
+    Week,
+    #[default]
+    Month,
+}
+
+impl ReportInterval {
+    /// 对应 PostgreSQL date_trunc 的字段名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportInterval::Day => "day",
+            ReportInterval::Week => "week",
+            ReportInterval::Month => "month",
+        }
+    }
+}
+
+/// 按标签聚合的作业报表查询
+#[derive(Debug, Deserialize)]
+pub struct JobTagReportQuery {
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub interval: ReportInterval,
+    /// 只统计该命名空间下的标签
+    pub namespace: Option<String>,
+    /// 只统计该标签
+    pub tag: Option<String>,
+    /// 输出格式（json / csv，CSV 表头按请求语言输出）
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// 报表输出格式
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// 按标签与时间段聚合的作业统计
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct JobTagReportRow {
+    pub tag: String,
+    pub period_start: DateTime<Utc>,
+    pub total_jobs: i64,
+    pub succeeded_jobs: i64,
+    pub failed_jobs: i64,
+    pub partially_succeeded_jobs: i64,
+    pub cancelled_jobs: i64,
+    pub success_rate: f64,              // 成功率（已结束作业中完全成功的比例）
+    pub avg_duration_secs: Option<f64>, // 平均执行时长
+    pub max_duration_secs: Option<f64>, // 最长执行时长
+}
+
+impl JobTagReportRow {
+    /// CSV 列对应的表头消息键（与 `csv_fields` 顺序一致）
+    const CSV_HEADERS: [&'static str; 10] = [
+        "report.job_tag.tag",
+        "report.job_tag.period_start",
+        "report.job_tag.total_jobs",
+        "report.job_tag.succeeded_jobs",
+        "report.job_tag.failed_jobs",
+        "report.job_tag.partially_succeeded_jobs",
+        "report.job_tag.cancelled_jobs",
+        "report.job_tag.success_rate",
+        "report.job_tag.avg_duration_secs",
+        "report.job_tag.max_duration_secs",
+    ];
+
+    fn csv_fields(&self) -> [String; 10] {
+        let optional = |v: Option<f64>| v.map(|v| format!("{:.2}", v)).unwrap_or_default();
+        [
+            self.tag.clone(),
+            self.period_start.to_rfc3339(),
+            self.total_jobs.to_string(),
+            self.succeeded_jobs.to_string(),
+            self.failed_jobs.to_string(),
+            self.partially_succeeded_jobs.to_string(),
+            self.cancelled_jobs.to_string(),
+            format!("{:.4}", self.success_rate),
+            optional(self.avg_duration_secs),
+            optional(self.max_duration_secs),
+        ]
+    }
+
+    /// 生成 CSV 报表，表头使用指定语言
+    pub fn to_csv(rows: &[JobTagReportRow], locale: crate::i18n::Locale) -> String {
+        let header = Self::CSV_HEADERS.map(|key| crate::i18n::t_in(locale, key).to_string());
+        std::iter::once(header)
+            .chain(rows.iter().map(Self::csv_fields))
+            .map(|fields| {
+                let fields: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+                fields.join(",") + "\r\n"
+            })
+            .collect()
+    }
+}
+
+/// 含逗号、引号或换行的字段加引号，字段内引号加倍
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 解析作业标签
+///
+/// 带命名空间的标签返回 `(namespace, value)`；不含冒号的自由标签返回 None
+pub fn parse_job_tag(tag: &str) -> Result<Option<(&str, &str)>, String> {
+    let Some((namespace, value)) = tag.split_once(':') else {
+        if tag.trim().is_empty() {
+            return Err("Tag must not be empty".to_string());
+        }
+        return Ok(None);
+    };
+    validate_tag_namespace_name(namespace)?;
+    validate_tag_value(value)?;
+    Ok(Some((namespace, value)))
+}
+
+/// 校验命名空间名称：小写字母开头，仅含小写字母、数字、`-`、`_`，最长 64
+pub fn validate_tag_namespace_name(name: &str) -> Result<(), String> {
+    let valid = name.len() <= 64
+        && name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid tag namespace: {}", name))
+    }
+}
+
+/// 校验标签值：字母或数字开头，仅含字母、数字、`.`、`-`、`_`，最长 128
+pub fn validate_tag_value(value: &str) -> Result<(), String> {
+    let valid = value.len() <= 128
+        && value
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid tag value: {}", value))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -358,22 +1469,45 @@ mod tests {
             command: Some("echo 'hello'".to_string()),
             script: None,
             script_path: None,
+            script_sha256: None,
+            script_streamed: false,
+            file_spec: None,
+            file_manifest: None,
+            drift_check: false,
+            verbose_trace: false,
             concurrent_limit: Some(5),
             timeout_secs: Some(300),
             retry_times: Some(2),
             execute_user: Some("root".to_string()),
+            exit_code_rules: None,
             idempotency_key: Some("test-key-123".to_string()),
+            singleton_key: None,
+            singleton_waiting: false,
             total_tasks: 1,
             succeeded_tasks: 0,
+            warning_tasks: 0,
             failed_tasks: 0,
             timeout_tasks: 0,
             cancelled_tasks: 0,
+            skipped_tasks: 0,
             created_by: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
             started_at: None,
             completed_at: None,
             tags: Json(vec!["test".to_string(), "batch".to_string()]),
+            template_id: None,
+            artifact_id: None,
+            on_success_job_template: None,
+            on_failure_job_template: None,
+            parent_job_id: None,
+            chain_trigger: None,
+            chain_depth: 0,
+            chain_status: None,
+            chain_error: None,
+            budget_exceeded: None,
+            dispatch_paused_at: None,
+            network_backoff: None,
         }
     }
 
@@ -383,6 +1517,8 @@ mod tests {
             (JobType::Command, "Command"),
             (JobType::Script, "Script"),
             (JobType::Build, "Build"),
+            (JobType::File, "File"),
+            (JobType::Inventory, "Inventory"),
         ];
 
         for (job_type, expected) in types {
@@ -394,6 +1530,8 @@ mod tests {
                 (JobType::Command, JobType::Command) => {}
                 (JobType::Script, JobType::Script) => {}
                 (JobType::Build, JobType::Build) => {}
+                (JobType::File, JobType::File) => {}
+                (JobType::Inventory, JobType::Inventory) => {}
                 _ => panic!("Job type mismatch"),
             }
         }
@@ -408,6 +1546,7 @@ mod tests {
             (JobStatus::Failed, "Failed"),
             (JobStatus::Cancelled, "Cancelled"),
             (JobStatus::PartiallySucceeded, "PartiallySucceeded"),
+            (JobStatus::AwaitingApproval, "AwaitingApproval"),
         ];
 
         for (status, expected) in statuses {
@@ -422,6 +1561,7 @@ mod tests {
                 (JobStatus::Failed, JobStatus::Failed) => {}
                 (JobStatus::Cancelled, JobStatus::Cancelled) => {}
                 (JobStatus::PartiallySucceeded, JobStatus::PartiallySucceeded) => {}
+                (JobStatus::AwaitingApproval, JobStatus::AwaitingApproval) => {}
                 _ => panic!("Job status mismatch"),
             }
         }
@@ -433,9 +1573,11 @@ mod tests {
             (TaskStatus::Pending, "Pending"),
             (TaskStatus::Running, "Running"),
             (TaskStatus::Succeeded, "Succeeded"),
+            (TaskStatus::Warning, "Warning"),
             (TaskStatus::Failed, "Failed"),
             (TaskStatus::Timeout, "Timeout"),
             (TaskStatus::Cancelled, "Cancelled"),
+            (TaskStatus::Skipped, "Skipped"),
         ];
 
         for (status, expected) in statuses {
@@ -447,9 +1589,11 @@ mod tests {
                 (TaskStatus::Pending, TaskStatus::Pending) => {}
                 (TaskStatus::Running, TaskStatus::Running) => {}
                 (TaskStatus::Succeeded, TaskStatus::Succeeded) => {}
+                (TaskStatus::Warning, TaskStatus::Warning) => {}
                 (TaskStatus::Failed, TaskStatus::Failed) => {}
                 (TaskStatus::Timeout, TaskStatus::Timeout) => {}
                 (TaskStatus::Cancelled, TaskStatus::Cancelled) => {}
+                (TaskStatus::Skipped, TaskStatus::Skipped) => {}
                 _ => panic!("Task status mismatch"),
             }
         }
@@ -464,6 +1608,10 @@ mod tests {
             (FailureReason::HandshakeTimeout, "HandshakeTimeout"),
             (FailureReason::CommandTimeout, "CommandTimeout"),
             (FailureReason::CommandFailed, "CommandFailed"),
+            (FailureReason::HostKeyMismatch, "HostKeyMismatch"),
+            (FailureReason::BudgetExceeded, "BudgetExceeded"),
+            (FailureReason::OutputLimitExceeded, "OutputLimitExceeded"),
+            (FailureReason::DispatchLeaseExpired, "DispatchLeaseExpired"),
             (FailureReason::Unknown, "Unknown"),
         ];
 
@@ -479,6 +1627,10 @@ mod tests {
                 (FailureReason::HandshakeTimeout, FailureReason::HandshakeTimeout) => {}
                 (FailureReason::CommandTimeout, FailureReason::CommandTimeout) => {}
                 (FailureReason::CommandFailed, FailureReason::CommandFailed) => {}
+                (FailureReason::HostKeyMismatch, FailureReason::HostKeyMismatch) => {}
+                (FailureReason::BudgetExceeded, FailureReason::BudgetExceeded) => {}
+                (FailureReason::OutputLimitExceeded, FailureReason::OutputLimitExceeded) => {}
+                (FailureReason::DispatchLeaseExpired, FailureReason::DispatchLeaseExpired) => {}
                 (FailureReason::Unknown, FailureReason::Unknown) => {}
                 _ => panic!("Failure reason mismatch"),
             }
@@ -536,13 +1688,23 @@ mod tests {
             description: Some("Deploy to production".to_string()),
             target_hosts: vec![Uuid::new_v4()],
             target_groups: vec![],
+            target_set_id: None,
             command: "kubectl apply -f deployment.yaml".to_string(),
             concurrent_limit: Some(10),
             timeout_secs: Some(600),
             retry_times: Some(1),
             execute_user: Some("ubuntu".to_string()),
+            exit_code_rules: None,
             idempotency_key: Some("deploy-prod-001".to_string()),
+            singleton_key: Some("nightly-vacuum".to_string()),
+            singleton_policy: SingletonPolicy::Queue,
             tags: vec!["deploy".to_string(), "production".to_string()],
+            on_success_job_template: None,
+            on_failure_job_template: None,
+            artifact_id: None,
+            file_manifest: None,
+            drift_check: false,
+            verbose_trace: false,
         };
 
         assert_eq!(request.name, "Deploy Application");
@@ -551,6 +1713,19 @@ mod tests {
         assert_eq!(request.tags.len(), 2);
     }
 
+    #[test]
+    fn test_singleton_policy_defaults_to_reject() {
+        let json = r#"{"name": "n", "description": null, "target_hosts": [], "target_groups": [],
+            "command": "uptime", "concurrent_limit": null, "timeout_secs": null,
+            "retry_times": null, "execute_user": null, "idempotency_key": null}"#;
+        let request: CreateCommandJobRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.singleton_key, None);
+        assert_eq!(request.singleton_policy, SingletonPolicy::Reject);
+
+        let policy: SingletonPolicy = serde_json::from_str("\"queue\"").unwrap();
+        assert_eq!(policy, SingletonPolicy::Queue);
+    }
+
     #[test]
     fn test_create_script_job_request() {
         let script = r#"#!/bin/bash
@@ -565,14 +1740,25 @@ echo "Deployment complete"
             description: None,
             target_hosts: vec![],
             target_groups: vec![Uuid::new_v4()],
+            target_set_id: None,
             script,
+            script_sha256: None,
             script_path: Some("/deploy/deploy.sh".to_string()),
             concurrent_limit: None,
             timeout_secs: Some(900),
             retry_times: None,
             execute_user: None,
+            exit_code_rules: None,
             idempotency_key: None,
+            singleton_key: None,
+            singleton_policy: SingletonPolicy::default(),
             tags: vec![],
+            on_success_job_template: None,
+            on_failure_job_template: None,
+            artifact_id: None,
+            file_manifest: None,
+            drift_check: false,
+            verbose_trace: false,
         };
 
         assert_eq!(request.name, "Script Deploy");
@@ -595,8 +1781,16 @@ echo "Deployment complete"
             duration_secs: None,
             output_summary: None,
             output_detail: None,
+            output_normalized: false,
+            output_encoding: None,
             retry_count: 0,
             max_retries: 3,
+            execution_context: None,
+            diagnostics: None,
+            file_result: None,
+            file_manifest_report: None,
+            output_drift: None,
+            journal: Json::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -621,8 +1815,16 @@ echo "Deployment complete"
             duration_secs: Some(45),
             output_summary: Some("Command succeeded".to_string()),
             output_detail: Some("Full output here...".to_string()),
+            output_normalized: false,
+            output_encoding: None,
             retry_count: 0,
             max_retries: 3,
+            execution_context: None,
+            diagnostics: None,
+            file_result: None,
+            file_manifest_report: None,
+            output_drift: None,
+            journal: Json::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -646,11 +1848,14 @@ echo "Deployment complete"
             job_id: Uuid::new_v4(),
             total_tasks: 10,
             succeeded_tasks: 8,
+            warning_tasks: 0,
             failed_tasks: 1,
             timeout_tasks: 1,
             cancelled_tasks: 0,
+            skipped_tasks: 0,
             pending_tasks: 0,
             running_tasks: 0,
+            waiting_maintenance_tasks: 0,
             success_rate: 0.8,
             avg_duration_secs: Some(120.0),
             failure_reason_stats: Some(FailureReasonStats::default()),
@@ -686,6 +1891,10 @@ echo "Deployment complete"
             handshake_timeout: 0,
             command_timeout: 3,
             command_failed: 4,
+            host_key_mismatch: 0,
+            budget_exceeded: 0,
+            output_limit_exceeded: 0,
+            dispatch_lease_expired: 0,
             unknown: 1,
         };
 
@@ -708,6 +1917,7 @@ echo "Deployment complete"
             search: Some("deploy".to_string()),
             date_from: Some(Utc::now()),
             date_to: Some(Utc::now()),
+            include_archived: false,
         };
 
         assert_eq!(filters.job_type, Some(JobType::Command));
@@ -790,8 +2000,16 @@ echo "Deployment complete"
             duration_secs: Some(10),
             output_summary: Some("Error: command failed".to_string()),
             output_detail: Some("Full error output...".to_string()),
+            output_normalized: false,
+            output_encoding: None,
             retry_count: 1,
             max_retries: 3,
+            execution_context: None,
+            diagnostics: None,
+            file_result: None,
+            file_manifest_report: None,
+            output_drift: None,
+            journal: Json::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -818,8 +2036,16 @@ echo "Deployment complete"
             duration_secs: Some(300),
             output_summary: Some("Timeout".to_string()),
             output_detail: None,
+            output_normalized: false,
+            output_encoding: None,
             retry_count: 0,
             max_retries: 2,
+            execution_context: None,
+            diagnostics: None,
+            file_result: None,
+            file_manifest_report: None,
+            output_drift: None,
+            journal: Json::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -838,6 +2064,7 @@ echo "Deployment complete"
             JobStatus::Failed,
             JobStatus::Cancelled,
             JobStatus::PartiallySucceeded,
+            JobStatus::AwaitingApproval,
         ];
 
         for status in statuses {
@@ -857,4 +2084,172 @@ echo "Deployment complete"
 
         assert_eq!(deserialized.0.len(), 3);
     }
+
+    #[test]
+    fn test_parse_job_tag() {
+        assert_eq!(parse_job_tag("team:payments"), Ok(Some(("team", "payments"))));
+        assert_eq!(parse_job_tag("change:CHG-1234"), Ok(Some(("change", "CHG-1234"))));
+        assert_eq!(parse_job_tag("urgent"), Ok(None));
+
+        assert!(parse_job_tag("").is_err());
+        assert!(parse_job_tag("Team:payments").is_err());
+        assert!(parse_job_tag("team:").is_err());
+        assert!(parse_job_tag(":payments").is_err());
+        assert!(parse_job_tag("team:pay ments").is_err());
+        assert!(parse_job_tag("team:a:b").is_err());
+        assert!(validate_tag_value(&"x".repeat(129)).is_err());
+    }
+
+    #[test]
+    fn test_job_tag_report_query_defaults() {
+        let query: JobTagReportQuery = serde_json::from_str(r#"{"namespace": "team"}"#).unwrap();
+        assert_eq!(query.interval, ReportInterval::Month);
+        assert_eq!(query.interval.as_str(), "month");
+        assert_eq!(query.namespace.as_deref(), Some("team"));
+
+        let query: JobTagReportQuery = serde_json::from_str(r#"{"interval": "week"}"#).unwrap();
+        assert_eq!(query.interval.as_str(), "week");
+        assert_eq!(query.format, ReportFormat::Json);
+    }
+
+    #[test]
+    fn test_job_tag_report_csv_localized_headers() {
+        let row = JobTagReportRow {
+            tag: "team:a,b".to_string(),
+            period_start: "2026-10-01T00:00:00Z".parse().unwrap(),
+            total_jobs: 4,
+            succeeded_jobs: 3,
+            failed_jobs: 1,
+            partially_succeeded_jobs: 0,
+            cancelled_jobs: 0,
+            success_rate: 0.75,
+            avg_duration_secs: Some(12.5),
+            max_duration_secs: None,
+        };
+
+        let csv = JobTagReportRow::to_csv(&[row], crate::i18n::Locale::ZhCn);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("标签,统计周期,作业总数,"));
+        assert_eq!(lines[1], "\"team:a,b\",2026-10-01T00:00:00+00:00,4,3,1,0,0,0.7500,12.50,");
+
+        let csv = JobTagReportRow::to_csv(&[], crate::i18n::Locale::EnUs);
+        assert!(csv.starts_with("Tag,Period start,Total jobs,"));
+    }
+
+    #[test]
+    fn test_mask_env_keeps_only_allowlisted_values() {
+        let env = ExecutionContextSnapshot::mask_env(vec![
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("DATABASE_URL".to_string(), "postgres://u:secret@db".to_string()),
+        ]);
+
+        assert_eq!(env["PATH"], "/usr/bin");
+        assert_eq!(env["DATABASE_URL"], MASKED_ENV_VALUE);
+        assert!(!serde_json::to_string(&env).unwrap().contains("secret"));
+    }
+
+    #[test]
+    fn test_follow_up_job_template_defaults_and_depth() {
+        let template_id = Uuid::new_v4();
+        let follow_up: FollowUpJobTemplate = serde_json::from_value(serde_json::json!({
+            "template_id": template_id,
+            "parameter_mapping": {"hosts": "failed_hosts"},
+            "on_success_job_template": {"template_id": Uuid::new_v4()}
+        }))
+        .unwrap();
+
+        assert_eq!(follow_up.target, FollowUpTarget::ParentTargets);
+        assert!(follow_up.parameters.is_empty());
+        assert_eq!(follow_up.parameter_mapping["hosts"], ChainParameterSource::FailedHosts);
+        assert_eq!(follow_up.depth(), 2);
+        assert!(follow_up.follow_up(ChainTrigger::Success).is_some());
+        assert!(follow_up.follow_up(ChainTrigger::Failure).is_none());
+    }
+
+    #[test]
+    fn test_chain_trigger_for_status() {
+        assert_eq!(ChainTrigger::for_status(&JobStatus::Completed), Some(ChainTrigger::Success));
+        assert_eq!(
+            ChainTrigger::for_status(&JobStatus::PartiallySucceeded),
+            Some(ChainTrigger::Failure)
+        );
+        assert_eq!(ChainTrigger::for_status(&JobStatus::Failed), Some(ChainTrigger::Failure));
+        assert_eq!(ChainTrigger::for_status(&JobStatus::Cancelled), None);
+    }
+
+    #[test]
+    fn test_result_host_filter_matches() {
+        let task = |status: TaskStatus, exit_code: Option<i32>, output: &str| Task {
+            id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            host_id: Uuid::new_v4(),
+            status,
+            failure_reason: None,
+            failure_message: None,
+            exit_code,
+            started_at: None,
+            completed_at: None,
+            duration_secs: None,
+            output_summary: Some(output.to_string()),
+            output_detail: None,
+            output_normalized: false,
+            output_encoding: None,
+            retry_count: 0,
+            max_retries: 0,
+            execution_context: None,
+            diagnostics: None,
+            file_result: None,
+            file_manifest_report: None,
+            output_drift: None,
+            journal: Json::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let ok = task(TaskStatus::Succeeded, Some(0), "disk usage 91%");
+        let failed = task(TaskStatus::Failed, Some(2), "permission denied");
+        let timed_out = task(TaskStatus::Timeout, None, "");
+        let warned = task(TaskStatus::Warning, Some(24), "file vanished");
+
+        assert!(ResultHostFilter::Succeeded.matches(&ok, None));
+        assert!(ResultHostFilter::Failed.matches(&failed, None));
+        assert!(ResultHostFilter::Failed.matches(&timed_out, None));
+        assert!(ResultHostFilter::Warning.matches(&warned, None));
+        assert!(!ResultHostFilter::Succeeded.matches(&warned, None));
+        assert!(ResultHostFilter::NonZeroExit.matches(&failed, None));
+        assert!(!ResultHostFilter::NonZeroExit.matches(&timed_out, None));
+
+        let filter: ResultHostFilter = serde_json::from_value(
+            serde_json::json!({"kind": "output_matches", "pattern": "9\\d%"}),
+        )
+        .unwrap();
+        let regex = regex::Regex::new(filter.pattern().unwrap()).unwrap();
+        assert!(filter.matches(&ok, Some(&regex)));
+        assert!(!filter.matches(&failed, Some(&regex)));
+        assert!(ResultHostFilter::OutputNotMatches {
+            pattern: "9\\d%".to_string()
+        }
+        .matches(&failed, Some(&regex)));
+    }
+
+    #[test]
+    fn test_task_journal_entry_compact_and_expanded() {
+        let at = DateTime::from_timestamp_millis(1_700_000_000_123).unwrap();
+        let stored = TaskJournalEntry::compact(TaskJournalPhase::PermitAcquired, at);
+        assert_eq!(stored, serde_json::json!(["permit_acquired", 1_700_000_000_123i64]));
+
+        let entry: TaskJournalEntry = serde_json::from_value(stored).unwrap();
+        assert_eq!(entry.phase, "permit_acquired");
+        assert_eq!(entry.at, at);
+
+        // 接口返回展开的对象，反序列化后不变
+        let expanded = serde_json::to_value(&entry).unwrap();
+        assert_eq!(expanded["phase"], "permit_acquired");
+        let reparsed: TaskJournalEntry = serde_json::from_value(expanded).unwrap();
+        assert_eq!(reparsed, entry);
+
+        // 未知阶段原样保留
+        let unknown: TaskJournalEntry =
+            serde_json::from_value(serde_json::json!(["future_phase", 0])).unwrap();
+        assert_eq!(unknown.phase, "future_phase");
+    }
 }
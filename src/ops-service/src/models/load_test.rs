@@ -0,0 +1,193 @@
+//! Load test domain models
+//! 平台自身的压测：用模拟执行器运行合成作业，衡量调度吞吐、数据库写放大、事件总线扇出与 SSE 延迟
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+/// 压测状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "load_test_run_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LoadTestRunStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl LoadTestRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoadTestRunStatus::Running => "running",
+            LoadTestRunStatus::Completed => "completed",
+            LoadTestRunStatus::Failed => "failed",
+        }
+    }
+}
+
+/// 发起压测请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartLoadTestRequest {
+    /// 合成作业数
+    pub jobs: u32,
+    /// 每个作业的目标主机数（合成主机在作业间共用）
+    pub hosts_per_job: u32,
+    /// 模拟执行耗时（毫秒）
+    #[serde(default)]
+    pub task_duration_ms: u64,
+    /// 每个任务的模拟输出字节数
+    #[serde(default)]
+    pub output_bytes: usize,
+    /// 模拟的事件流订阅者数
+    #[serde(default = "default_load_test_subscribers")]
+    pub subscribers: u32,
+    /// 相邻作业的创建间隔（毫秒，0 表示连续创建）
+    #[serde(default)]
+    pub launch_interval_ms: u64,
+    /// 结束后删除合成作业与主机
+    #[serde(default = "default_load_test_cleanup")]
+    pub cleanup: bool,
+}
+
+fn default_load_test_subscribers() -> u32 {
+    10
+}
+
+fn default_load_test_cleanup() -> bool {
+    true
+}
+
+/// 压测记录
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LoadTestRun {
+    pub id: Uuid,
+    pub requested_by: Uuid,
+    pub status: LoadTestRunStatus,
+    pub parameters: Json<StartLoadTestRequest>,
+    /// 测量结果（完成后写入；列表接口不返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<Json<LoadTestReport>>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// 延迟分布（毫秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub samples: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencySummary {
+    /// 按最近秩法计算分位数
+    pub fn from_samples(mut samples: Vec<f64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_by(|a, b| a.total_cmp(b));
+        let percentile = |p: f64| {
+            let rank = (p * samples.len() as f64).ceil() as usize;
+            samples[rank.clamp(1, samples.len()) - 1]
+        };
+        Self {
+            samples: samples.len() as u64,
+            p50_ms: percentile(0.50),
+            p95_ms: percentile(0.95),
+            p99_ms: percentile(0.99),
+            max_ms: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// 调度吞吐
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchedulerReport {
+    /// 从创建第一个作业到全部作业结束的耗时（秒）
+    pub duration_secs: f64,
+    pub jobs_per_sec: f64,
+    pub tasks_per_sec: f64,
+    /// 创建作业接口耗时
+    pub job_create: LatencySummary,
+    /// 作业创建到任务开始执行的等待时间
+    pub task_start_delay: LatencySummary,
+    /// 超时后被取消的作业数
+    pub unfinished_jobs: u64,
+    pub failed_tasks: u64,
+}
+
+/// 数据库写入量（压测期间 pg_stat_database 与 WAL 位置的差值，包含同期其他流量）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseReport {
+    pub transactions: i64,
+    pub rows_inserted: i64,
+    pub rows_updated: i64,
+    pub rows_deleted: i64,
+    /// 备库或无权限时为空
+    pub wal_bytes: Option<i64>,
+    pub transactions_per_task: f64,
+    pub rows_written_per_task: f64,
+    pub wal_bytes_per_task: Option<f64>,
+}
+
+/// 事件总线扇出
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventBusReport {
+    pub subscribers: u32,
+    /// 合成作业写入发件箱的状态事件数
+    pub events_published: u64,
+    /// 全部订阅者收到的合成作业状态事件数
+    pub events_delivered: u64,
+    /// 全部订阅者收到的任务输出事件数（直接发布到事件总线，不经发件箱）
+    pub output_events_delivered: u64,
+    /// 订阅者处理不及被广播通道丢弃的事件数（含同期其他事件）
+    pub events_lagged: u64,
+    /// 状态事件送达数 / (发布数 × 订阅者数)
+    pub delivery_ratio: f64,
+    /// 序列化为 SSE 数据的总字节数
+    pub sse_bytes: u64,
+}
+
+/// 压测结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadTestReport {
+    pub jobs: u32,
+    pub tasks: u64,
+    pub scheduler: SchedulerReport,
+    pub database: DatabaseReport,
+    pub event_bus: EventBusReport,
+    /// 任务执行结束到订阅者收到任务终态事件的延迟（经结果写入、发件箱中继与广播）
+    pub sse_latency: LatencySummary,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_summary_percentiles() {
+        let summary = LatencySummary::from_samples((1..=100).rev().map(f64::from).collect());
+        assert_eq!(summary.samples, 100);
+        assert_eq!(summary.p50_ms, 50.0);
+        assert_eq!(summary.p95_ms, 95.0);
+        assert_eq!(summary.p99_ms, 99.0);
+        assert_eq!(summary.max_ms, 100.0);
+
+        let single = LatencySummary::from_samples(vec![7.5]);
+        assert_eq!((single.p50_ms, single.p99_ms), (7.5, 7.5));
+        assert_eq!(LatencySummary::from_samples(Vec::new()), LatencySummary::default());
+    }
+
+    #[test]
+    fn test_start_request_defaults() {
+        let request: StartLoadTestRequest =
+            serde_json::from_value(serde_json::json!({"jobs": 20, "hosts_per_job": 3000})).unwrap();
+        assert_eq!(request.subscribers, 10);
+        assert_eq!(request.task_duration_ms, 0);
+        assert!(request.cleanup);
+    }
+}
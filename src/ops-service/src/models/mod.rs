@@ -3,12 +3,23 @@
 //! P2 阶段添加作业系统与构建系统模型
 //! P3 阶段添加审批流与实时能力模型
 
+pub mod advisory;
 pub mod approval;
 pub mod asset;
 pub mod audit;
 pub mod auth;
+pub mod blob;
 pub mod build;
+pub mod environment_policy;
+pub mod evidence;
+pub mod handover;
+pub mod inbound_webhook;
 pub mod job;
+pub mod load_test;
+pub mod notification_template;
 pub mod role;
 pub mod runner_config;
+pub mod soft_delete;
+pub mod stats;
 pub mod user;
+pub mod watch;
@@ -0,0 +1,77 @@
+//! 通知模板模型
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::i18n::Locale;
+
+/// 通知模板（当前版本的内容随列表返回）
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct NotificationTemplate {
+    pub id: Uuid,
+    pub event_type: String,
+    pub channel: String,
+    pub locale: String,
+    pub current_version: i32,
+    pub body: String,
+    pub updated_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 通知模板版本
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct NotificationTemplateVersion {
+    pub id: Uuid,
+    pub template_id: Uuid,
+    pub version: i32,
+    pub body: String,
+    pub comment: Option<String>,
+    pub rolled_back_from: Option<i32>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 创建或修改通知模板请求（按事件类型 + 渠道 + 语言定位，修改生成新版本）
+#[derive(Debug, Deserialize)]
+pub struct UpsertNotificationTemplateRequest {
+    pub event_type: String,
+    pub channel: String,
+    pub locale: Locale,
+    pub body: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// 回滚到指定版本请求
+#[derive(Debug, Deserialize)]
+pub struct RollbackNotificationTemplateRequest {
+    pub version: i32,
+}
+
+/// 预览请求：使用示例事件渲染模板内容
+#[derive(Debug, Deserialize)]
+pub struct PreviewNotificationTemplateRequest {
+    pub event_type: String,
+    pub body: String,
+    #[serde(default)]
+    pub locale: Locale,
+}
+
+/// 预览结果
+#[derive(Debug, Serialize)]
+pub struct NotificationTemplatePreview {
+    pub rendered: String,
+    /// 示例事件的变量取值
+    pub variables: serde_json::Map<String, serde_json::Value>,
+    /// 同一示例事件的内置文案，便于对比
+    pub builtin: Option<String>,
+}
+
+/// 事件类型可用的模板变量
+#[derive(Debug, Serialize)]
+pub struct NotificationEventVariables {
+    pub event_type: &'static str,
+    pub variables: &'static [&'static str],
+}
@@ -11,6 +11,7 @@ use uuid::Uuid;
 pub struct RunnerDockerConfig {
     pub id: Uuid,
     pub name: String,
+    pub version: i64,
 
     // 基础配置
     pub enabled: bool,
@@ -108,6 +109,7 @@ pub struct RunnerConfigOverride {
 pub struct RunnerDockerConfigResponse {
     pub id: Uuid,
     pub name: String,
+    pub version: i64,
     pub enabled: bool,
     pub default_image: String,
     pub default_timeout_secs: i64,
@@ -136,6 +138,7 @@ impl From<RunnerDockerConfig> for RunnerDockerConfigResponse {
         Self {
             id: config.id,
             name: config.name,
+            version: config.version,
             enabled: config.enabled,
             default_image: config.default_image,
             default_timeout_secs: config.default_timeout_secs,
@@ -168,6 +171,7 @@ pub struct RunnerConfigHistory {
     pub new_config: Json<serde_json::Value>,
     pub change_reason: Option<String>,
     pub changed_by: Option<Uuid>,
+    pub version: Option<i64>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -180,6 +184,7 @@ pub struct RunnerConfigHistoryResponse {
     pub new_config: serde_json::Value,
     pub change_reason: Option<String>,
     pub changed_by: Option<Uuid>,
+    pub version: Option<i64>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -192,6 +197,7 @@ impl From<RunnerConfigHistory> for RunnerConfigHistoryResponse {
             new_config: history.new_config.0,
             change_reason: history.change_reason,
             changed_by: history.changed_by,
+            version: history.version,
             created_at: history.created_at,
         }
     }
@@ -209,6 +215,84 @@ pub struct ActiveRunnerConfig {
     pub default_timeout_secs: i64,
 }
 
+impl RunnerDockerConfig {
+    /// 转换为下发给 Runner 的配置（覆盖项解析失败时忽略）
+    pub fn to_docker_config(&self) -> crate::config::RunnerDockerConfig {
+        crate::config::RunnerDockerConfig {
+            enabled: self.enabled,
+            default_image: self.default_image.clone(),
+            images_by_type: serde_json::from_value(self.images_by_type.0.clone())
+                .unwrap_or_default(),
+            memory_limit_gb: self.memory_limit_gb,
+            cpu_shares: self.cpu_shares,
+            pids_limit: self.pids_limit,
+            default_timeout_secs: self.default_timeout_secs.max(0) as u64,
+            per_runner: serde_json::from_value(self.per_runner.0.clone()).unwrap_or_default(),
+            per_capability: serde_json::from_value(self.per_capability.0.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// 灰度发布状态
+pub const ROLLOUT_APPLYING: &str = "applying";
+pub const ROLLOUT_BAKING: &str = "baking";
+pub const ROLLOUT_COMPLETED: &str = "completed";
+pub const ROLLOUT_ROLLED_BACK: &str = "rolled_back";
+
+/// Runner 配置灰度发布
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RunnerConfigRollout {
+    pub id: Uuid,
+    pub config_id: Uuid,
+    pub base_version: i64,
+    pub target_version: i64,
+    /// 候选配置（RunnerDockerConfig 快照）
+    pub candidate_config: Json<serde_json::Value>,
+    pub change_reason: Option<String>,
+    pub percent: Option<i32>,
+    pub labels: Json<Vec<String>>,
+    /// 创建时选出的金丝雀 Runner 名称
+    pub canary_runners: Json<Vec<String>>,
+    pub bake_secs: i64,
+    pub max_error_rate: f64,
+    pub min_jobs: i64,
+    pub auto_promote: bool,
+    pub status: String,
+    pub status_reason: Option<String>,
+    pub baking_started_at: Option<DateTime<Utc>>,
+    pub observed_jobs: i64,
+    pub observed_failures: i64,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl RunnerConfigRollout {
+    /// 是否仍在进行中（金丝雀 Runner 使用候选配置）
+    pub fn is_active(&self) -> bool {
+        self.status == ROLLOUT_APPLYING || self.status == ROLLOUT_BAKING
+    }
+
+    /// 候选配置
+    pub fn candidate(&self) -> Option<RunnerDockerConfig> {
+        serde_json::from_value(self.candidate_config.0.clone()).ok()
+    }
+}
+
+/// 金丝雀 Runner 的配置应用状态
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CanaryRunnerStatus {
+    pub name: String,
+    pub status: Option<String>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub applied_config_version: Option<i64>,
+    pub applied_config_at: Option<DateTime<Utc>>,
+    /// 最近 2 分钟内有心跳
+    pub online: bool,
+}
+
 // 默认值函数（导出供测试使用）
 pub fn default_enabled() -> bool {
     true
@@ -323,6 +407,7 @@ mod tests {
         let config = RunnerDockerConfig {
             id: Uuid::new_v4(),
             name: "test-config".to_string(),
+            version: 3,
             enabled: true,
             default_image: "ubuntu:22.04".to_string(),
             default_timeout_secs: 1800,
@@ -345,9 +430,42 @@ mod tests {
         assert_eq!(response.images_by_type.get("node").unwrap(), "node:20-alpine");
     }
 
+    #[test]
+    fn test_candidate_snapshot_to_docker_config() {
+        let config = RunnerDockerConfig {
+            id: Uuid::new_v4(),
+            name: "default".to_string(),
+            version: 2,
+            enabled: true,
+            default_image: "ubuntu:24.04".to_string(),
+            default_timeout_secs: 900,
+            memory_limit_gb: Some(8),
+            cpu_shares: None,
+            pids_limit: Some(2048),
+            images_by_type: Json(serde_json::json!({"node": "node:20-alpine"})),
+            per_capability: Json(serde_json::json!({"gpu": {"memory_limit_gb": 32}})),
+            per_runner: Json(serde_json::json!({})),
+            description: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        // 候选配置以 JSON 快照保存，取回后应与原配置一致
+        let snapshot: RunnerDockerConfig =
+            serde_json::from_value(serde_json::to_value(&config).unwrap()).unwrap();
+        assert_eq!(snapshot.version, 2);
+
+        let docker = snapshot.to_docker_config();
+        assert_eq!(docker.default_timeout_secs, 900);
+        assert_eq!(docker.images_by_type.get("node").unwrap(), "node:20-alpine");
+        let effective = docker.get_config_for_runner("runner-1", &["gpu".to_string()]);
+        assert_eq!(effective.default_image, "ubuntu:24.04");
+        assert_eq!(effective.memory_limit_gb, Some(32));
+    }
+
     #[test]
     fn test_default_values() {
-        assert_eq!(default_enabled(), true);
+        assert!(default_enabled());
         assert_eq!(default_image(), "ubuntu:22.04");
         assert_eq!(default_timeout(), 1800);
     }
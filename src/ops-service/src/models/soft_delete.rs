@@ -0,0 +1,95 @@
+//! 软删除相关模型
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 支持软删除与恢复的资源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoftDeleteResource {
+    Host,
+    JobTemplate,
+    User,
+}
+
+impl SoftDeleteResource {
+    pub const ALL: [SoftDeleteResource; 3] = [
+        SoftDeleteResource::Host,
+        SoftDeleteResource::JobTemplate,
+        SoftDeleteResource::User,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SoftDeleteResource::Host => "host",
+            SoftDeleteResource::JobTemplate => "job_template",
+            SoftDeleteResource::User => "user",
+        }
+    }
+
+    /// 资源所在的表
+    pub fn table(&self) -> &'static str {
+        match self {
+            SoftDeleteResource::Host => "assets_hosts",
+            SoftDeleteResource::JobTemplate => "job_templates",
+            SoftDeleteResource::User => "users",
+        }
+    }
+
+    /// 展示名称所在的列
+    pub fn name_column(&self) -> &'static str {
+        match self {
+            SoftDeleteResource::Host => "identifier",
+            SoftDeleteResource::JobTemplate => "name",
+            SoftDeleteResource::User => "username",
+        }
+    }
+}
+
+/// 已软删除的资源
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DeletedResource {
+    pub id: Uuid,
+    pub name: String,
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: Option<Uuid>,
+}
+
+/// 已删除资源列表查询
+#[derive(Debug, Deserialize)]
+pub struct DeletedResourceQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+/// 已删除资源列表
+#[derive(Debug, Serialize)]
+pub struct DeletedResourceList {
+    pub resource_type: SoftDeleteResource,
+    /// 保留天数，删除超过该天数的资源将被彻底清除
+    pub retention_days: u32,
+    pub items: Vec<DeletedResource>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_soft_delete_resource_serde() {
+        for resource in SoftDeleteResource::ALL {
+            let value = serde_json::to_value(resource).unwrap();
+            assert_eq!(value, resource.as_str());
+            assert_eq!(serde_json::from_value::<SoftDeleteResource>(value).unwrap(), resource);
+        }
+        assert_eq!(SoftDeleteResource::JobTemplate.table(), "job_templates");
+        assert!(serde_json::from_value::<SoftDeleteResource>("asset_group".into()).is_err());
+    }
+}
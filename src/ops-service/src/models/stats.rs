@@ -0,0 +1,406 @@
+//! Statistics models
+//! 运维看板统计：基于后台任务预聚合的 stats_* 表
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::concurrency::AcquireFailures;
+
+use super::job::{JobStatus, JobType};
+
+/// 统计查询的最大天数
+pub const MAX_STATS_DAYS: u32 = 365;
+
+/// 统计查询参数
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// 统计最近的天数（默认 30，最大 365）
+    pub days: Option<u32>,
+    /// 排行类接口返回的条数（默认 10，最大 100）
+    pub limit: Option<i64>,
+}
+
+impl StatsQuery {
+    /// 统计窗口的起始日期（UTC，含当天）
+    pub fn since(&self, today: NaiveDate) -> NaiveDate {
+        let days = self.days.unwrap_or(30).clamp(1, MAX_STATS_DAYS);
+        today - Duration::days(days as i64 - 1)
+    }
+
+    /// 排行条数
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(10).clamp(1, 100)
+    }
+}
+
+/// 并发历史查询参数
+#[derive(Debug, Deserialize)]
+pub struct ConcurrencyHistoryQuery {
+    /// 查询最近的小时数（默认 24，最大 720）
+    pub hours: Option<u32>,
+}
+
+impl ConcurrencyHistoryQuery {
+    /// 查询窗口的起始时间
+    pub fn since(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::hours(self.hours.unwrap_or(24).clamp(1, 720) as i64)
+    }
+}
+
+/// 并发时间线查询参数
+#[derive(Debug, Deserialize)]
+pub struct ConcurrencyTimelineQuery {
+    /// 窗口起始时间（默认结束时间前 24 小时）
+    pub from: Option<DateTime<Utc>>,
+    /// 窗口结束时间（默认当前时间）
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ConcurrencyTimelineQuery {
+    /// 查询窗口的最大跨度（与并发历史一致，30 天）
+    pub const MAX_WINDOW_HOURS: i64 = 720;
+
+    /// 查询窗口（起始、结束）
+    pub fn window(&self, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+        let to = self.to.unwrap_or(now);
+        let from = self.from.unwrap_or(to - Duration::hours(24));
+        if from >= to {
+            return Err("from must be earlier than to".into());
+        }
+        if to - from > Duration::hours(Self::MAX_WINDOW_HOURS) {
+            return Err(format!(
+                "Timeline window must not exceed {} hours",
+                Self::MAX_WINDOW_HOURS
+            ));
+        }
+        Ok((from, to))
+    }
+}
+
+/// 每日作业数量
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct JobDailyStat {
+    pub day: NaiveDate,
+    pub job_type: JobType,
+    pub status: JobStatus,
+    pub job_count: i64,
+    pub avg_duration_secs: Option<f64>,
+}
+
+/// 每日失败作业恢复时间（MTTR）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct JobRecoveryStat {
+    pub day: NaiveDate,
+    pub job_type: JobType,
+    pub failed_jobs: i64,
+    pub recovered_jobs: i64,
+    pub avg_recovery_secs: Option<f64>, // 失败到同名同类型作业下次成功的平均时长
+}
+
+/// 失败最多的主机
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HostFailureStat {
+    pub host_id: Uuid,
+    pub identifier: String,
+    pub address: String,
+    pub total_tasks: i64,
+    pub failed_tasks: i64,
+    pub warning_tasks: i64, // 退出码归为告警（不计入失败率）
+    pub failure_rate: f64,
+}
+
+/// 最繁忙的 Runner
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RunnerUsageStat {
+    pub runner_name: String,
+    pub build_count: i64,
+    pub succeeded_count: i64,
+    pub failed_count: i64,
+    pub busy_secs: f64,
+}
+
+/// 每日审批耗时分位数
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ApprovalTurnaroundStat {
+    pub day: NaiveDate,
+    pub completed_requests: i64,
+    pub approved_requests: i64,
+    pub rejected_requests: i64,
+    pub avg_mins: Option<f64>,
+    pub p50_mins: Option<f64>,
+    pub p90_mins: Option<f64>,
+    pub p99_mins: Option<f64>,
+}
+
+/// 并发使用率采样
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ConcurrencySample {
+    pub sampled_at: DateTime<Utc>,
+    pub global_limit: i32,
+    pub global_used: i32,
+    pub utilization_percent: f32,
+    pub scopes: sqlx::types::Json<serde_json::Value>, // 分组/环境维度的使用率
+    /// 距上次采样获取许可失败的次数
+    pub acquire_failures: i32,
+}
+
+/// 采样中单个作用域的使用情况
+#[derive(Debug, Deserialize)]
+struct ScopeUsage {
+    limit: i32,
+    used: i32,
+}
+
+/// 采样中分组/环境维度的数据（见 `StatsService::record_concurrency_sample`）
+#[derive(Debug, Default, Deserialize)]
+struct SampleScopes {
+    #[serde(default)]
+    groups: HashMap<String, ScopeUsage>,
+    #[serde(default)]
+    environments: HashMap<String, ScopeUsage>,
+    #[serde(default)]
+    acquire_failures: AcquireFailures,
+}
+
+/// 并发时间线上的一个采样点
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ConcurrencyTimelinePoint {
+    pub sampled_at: DateTime<Utc>,
+    pub limit: i32,
+    /// 采样时正在运行（持有许可）的任务数
+    pub used: i32,
+    /// 是否达到上限
+    pub saturated: bool,
+    /// 距上次采样获取许可失败的次数
+    pub acquire_failures: u32,
+}
+
+/// 单个作用域（全局、分组或环境）的并发时间线
+#[derive(Debug, Serialize)]
+pub struct ConcurrencyScopeTimeline {
+    /// 作用域标识：global、分组 ID 或环境名
+    pub scope: String,
+    /// 分组名称（仅分组作用域）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub peak_used: i32,
+    /// 达到上限的采样数
+    pub saturated_samples: usize,
+    pub acquire_failures: u64,
+    pub points: Vec<ConcurrencyTimelinePoint>,
+}
+
+impl ConcurrencyScopeTimeline {
+    fn new(scope: String, name: Option<String>) -> Self {
+        Self {
+            scope,
+            name,
+            peak_used: 0,
+            saturated_samples: 0,
+            acquire_failures: 0,
+            points: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, sampled_at: DateTime<Utc>, limit: i32, used: i32, failures: u32) {
+        // 缩容期间在途任务可能超过新上限，已用数不小于 0
+        let used = used.max(0);
+        let saturated = limit > 0 && used >= limit;
+        self.peak_used = self.peak_used.max(used);
+        self.saturated_samples += saturated as usize;
+        self.acquire_failures += failures as u64;
+        self.points.push(ConcurrencyTimelinePoint {
+            sampled_at,
+            limit,
+            used,
+            saturated,
+            acquire_failures: failures,
+        });
+    }
+}
+
+/// 并发时间线：按采样时间排列的各作用域运行中任务数，用于容量规划
+#[derive(Debug, Serialize)]
+pub struct ConcurrencyTimeline {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub sample_count: usize,
+    pub global: ConcurrencyScopeTimeline,
+    pub groups: Vec<ConcurrencyScopeTimeline>,
+    pub environments: Vec<ConcurrencyScopeTimeline>,
+}
+
+impl ConcurrencyTimeline {
+    /// 由按时间排序的采样构建时间线；分组与环境按达到上限的采样数、峰值降序排列
+    pub fn from_samples(
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        samples: &[ConcurrencySample],
+        group_names: &HashMap<String, String>,
+    ) -> Self {
+        let mut global = ConcurrencyScopeTimeline::new("global".to_string(), None);
+        let mut groups: BTreeMap<String, ConcurrencyScopeTimeline> = BTreeMap::new();
+        let mut environments: BTreeMap<String, ConcurrencyScopeTimeline> = BTreeMap::new();
+
+        for sample in samples {
+            let scopes: SampleScopes =
+                serde_json::from_value(sample.scopes.0.clone()).unwrap_or_default();
+            let failures = &scopes.acquire_failures;
+            global.push(
+                sample.sampled_at,
+                sample.global_limit,
+                sample.global_used,
+                failures.global,
+            );
+            for (id, usage) in &scopes.groups {
+                groups
+                    .entry(id.clone())
+                    .or_insert_with(|| {
+                        ConcurrencyScopeTimeline::new(id.clone(), group_names.get(id).cloned())
+                    })
+                    .push(
+                        sample.sampled_at,
+                        usage.limit,
+                        usage.used,
+                        failures.groups.get(id).copied().unwrap_or(0),
+                    );
+            }
+            for (env, usage) in &scopes.environments {
+                environments
+                    .entry(env.clone())
+                    .or_insert_with(|| ConcurrencyScopeTimeline::new(env.clone(), None))
+                    .push(
+                        sample.sampled_at,
+                        usage.limit,
+                        usage.used,
+                        failures.environments.get(env).copied().unwrap_or(0),
+                    );
+            }
+        }
+
+        let sort = |scopes: BTreeMap<String, ConcurrencyScopeTimeline>| {
+            let mut scopes: Vec<_> = scopes.into_values().collect();
+            scopes.sort_by(|a, b| {
+                (b.saturated_samples, b.peak_used).cmp(&(a.saturated_samples, a.peak_used))
+            });
+            scopes
+        };
+        Self {
+            from,
+            to,
+            sample_count: samples.len(),
+            global,
+            groups: sort(groups),
+            environments: sort(environments),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_query_window() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 31).unwrap();
+        let query = StatsQuery {
+            days: None,
+            limit: None,
+        };
+        assert_eq!(query.since(today), NaiveDate::from_ymd_opt(2026, 3, 2).unwrap());
+        assert_eq!(query.limit(), 10);
+
+        let query = StatsQuery {
+            days: Some(0),
+            limit: Some(1000),
+        };
+        assert_eq!(query.since(today), today);
+        assert_eq!(query.limit(), 100);
+
+        let query = StatsQuery {
+            days: Some(10_000),
+            limit: Some(0),
+        };
+        assert_eq!(query.since(today), today - Duration::days(364));
+        assert_eq!(query.limit(), 1);
+    }
+
+    #[test]
+    fn test_concurrency_history_window() {
+        let now = Utc::now();
+        let query = ConcurrencyHistoryQuery { hours: None };
+        assert_eq!(query.since(now), now - Duration::hours(24));
+
+        let query = ConcurrencyHistoryQuery {
+            hours: Some(100_000),
+        };
+        assert_eq!(query.since(now), now - Duration::hours(720));
+    }
+
+    #[test]
+    fn test_concurrency_timeline_window() {
+        let now = Utc::now();
+        let query = ConcurrencyTimelineQuery {
+            from: None,
+            to: None,
+        };
+        assert_eq!(query.window(now), Ok((now - Duration::hours(24), now)));
+
+        let query = ConcurrencyTimelineQuery {
+            from: Some(now),
+            to: Some(now - Duration::hours(1)),
+        };
+        assert!(query.window(now).is_err());
+
+        let query = ConcurrencyTimelineQuery {
+            from: Some(now - Duration::days(31)),
+            to: None,
+        };
+        assert!(query.window(now).is_err());
+    }
+
+    #[test]
+    fn test_concurrency_timeline_from_samples() {
+        let start = Utc::now();
+        let sample = |minutes: i64, used: i32, group_used: i32, timeouts: u32| ConcurrencySample {
+            sampled_at: start + Duration::minutes(minutes),
+            global_limit: 10,
+            global_used: used,
+            utilization_percent: used as f32 * 10.0,
+            scopes: sqlx::types::Json(serde_json::json!({
+                "groups": {
+                    "g1": { "limit": 2, "used": group_used, "available": 2 - group_used },
+                    "g2": { "limit": 5, "used": 1, "available": 4 },
+                },
+                "environments": { "production": { "limit": 5, "used": used } },
+                "acquire_failures": { "groups": { "g1": timeouts } },
+            })),
+            acquire_failures: timeouts as i32,
+        };
+        let samples = vec![sample(0, 3, 1, 0), sample(1, 6, 2, 4), sample(2, 4, 2, 1)];
+        let names = HashMap::from([("g1".to_string(), "db-cluster".to_string())]);
+
+        let timeline =
+            ConcurrencyTimeline::from_samples(start, start + Duration::hours(1), &samples, &names);
+        assert_eq!(timeline.sample_count, 3);
+        assert_eq!(timeline.global.peak_used, 6);
+        assert_eq!(timeline.global.saturated_samples, 0);
+
+        // 达到上限次数最多的分组排在前面
+        let g1 = &timeline.groups[0];
+        assert_eq!(g1.scope, "g1");
+        assert_eq!(g1.name.as_deref(), Some("db-cluster"));
+        assert_eq!(g1.saturated_samples, 2);
+        assert_eq!(g1.acquire_failures, 5);
+        assert_eq!(g1.points.len(), 3);
+        assert!(g1.points[1].saturated);
+        assert_eq!(g1.points[1].acquire_failures, 4);
+        assert_eq!(timeline.groups[1].name, None);
+
+        assert_eq!(timeline.environments[0].scope, "production");
+        assert_eq!(timeline.environments[0].peak_used, 6);
+        assert_eq!(timeline.environments[0].saturated_samples, 1);
+    }
+}
@@ -4,6 +4,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+pub use common::api::{UserResponse, UserWithRoles};
+
 /// User account
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
@@ -25,6 +27,8 @@ pub struct User {
     // Metadata
     pub full_name: Option<String>,
     pub department: Option<String>,
+    /// 语言偏好（en-US / zh-CN，为空时按 Accept-Language 与默认语言）
+    pub locale: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: Option<Uuid>,
@@ -88,17 +92,11 @@ pub struct ChangePasswordRequest {
     pub new_password: String,
 }
 
-/// User response (without sensitive data)
-#[derive(Debug, Serialize)]
-pub struct UserResponse {
-    pub id: Uuid,
-    pub username: String,
-    pub email: Option<String>,
-    pub status: String,
-    pub full_name: Option<String>,
-    pub department: Option<String>,
-    pub must_change_password: bool,
-    pub created_at: DateTime<Utc>,
+/// Update language preference request
+#[derive(Debug, Deserialize)]
+pub struct UpdateLocaleRequest {
+    /// 语言标签，为空表示清除偏好
+    pub locale: Option<String>,
 }
 
 impl From<User> for UserResponse {
@@ -111,16 +109,8 @@ impl From<User> for UserResponse {
             full_name: user.full_name,
             department: user.department,
             must_change_password: user.must_change_password,
+            locale: user.locale,
             created_at: user.created_at,
         }
     }
 }
-
-/// User with roles
-#[derive(Debug, Serialize)]
-pub struct UserWithRoles {
-    #[serde(flatten)]
-    pub user: UserResponse,
-    pub roles: Vec<String>,
-    pub scopes: Vec<String>,
-}
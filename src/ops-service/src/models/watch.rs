@@ -0,0 +1,76 @@
+//! Watch domain models
+//! 用户关注作业、主机或模板，关注对象状态变更时推送定向通知
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 关注对象类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "watch_target_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WatchTargetType {
+    /// 作业
+    Job,
+    /// 主机（关注针对该主机的作业及主机维护状态）
+    Host,
+    /// 作业模板（关注由该模板创建的作业）
+    Template,
+}
+
+impl WatchTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchTargetType::Job => "job",
+            WatchTargetType::Host => "host",
+            WatchTargetType::Template => "template",
+        }
+    }
+}
+
+impl std::fmt::Display for WatchTargetType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 关注记录
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Watch {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub target_type: WatchTargetType,
+    pub target_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 创建关注请求
+#[derive(Debug, Deserialize)]
+pub struct CreateWatchRequest {
+    pub target_type: WatchTargetType,
+    pub target_id: Uuid,
+}
+
+/// 关注查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct WatchListQuery {
+    /// 按对象类型过滤
+    pub target_type: Option<WatchTargetType>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_target_type_serde() {
+        let request: CreateWatchRequest = serde_json::from_value(serde_json::json!({
+            "target_type": "template",
+            "target_id": Uuid::nil(),
+        }))
+        .unwrap();
+        assert_eq!(request.target_type, WatchTargetType::Template);
+        assert_eq!(request.target_type.to_string(), "template");
+        assert!(serde_json::from_value::<WatchTargetType>(serde_json::json!("group")).is_err());
+    }
+}
@@ -1,7 +1,8 @@
 //! 输出存档与脱敏模块
 //! P2 阶段：提供输出脱敏和存档功能
 
-use once_cell::sync::Lazy;
+use common::terminal::{normalize_output_with, AnsiMode};
+use once_cell::sync::{Lazy, OnceCell};
 use regex::Regex;
 use std::sync::Arc;
 
@@ -134,6 +135,30 @@ pub fn default_sanitizer() -> Arc<OutputSanitizer> {
     Arc::clone(&DEFAULT_SANITIZER)
 }
 
+/// 全局默认的 ANSI 序列处理方式（启动时由配置设置）
+static DEFAULT_ANSI_MODE: OnceCell<AnsiMode> = OnceCell::new();
+
+/// 设置默认的 ANSI 序列处理方式（仅首次调用生效）
+pub fn set_default_ansi_mode(mode: AnsiMode) {
+    let _ = DEFAULT_ANSI_MODE.set(mode);
+}
+
+/// 获取默认的 ANSI 序列处理方式
+pub fn default_ansi_mode() -> AnsiMode {
+    DEFAULT_ANSI_MODE.get().copied().unwrap_or_default()
+}
+
+/// 输出处理结果
+#[derive(Debug, Clone)]
+pub struct ProcessedOutput {
+    /// 摘要
+    pub summary: String,
+    /// 明细
+    pub detail: String,
+    /// 规范化时是否修改了内容（ANSI 序列、控制字符）
+    pub normalized: bool,
+}
+
 /// 输出存档管理器
 pub struct OutputArchive {
     /// 摘要最大长度
@@ -144,6 +169,8 @@ pub struct OutputArchive {
     enable_sanitization: bool,
     /// 脱敏器
     sanitizer: Arc<OutputSanitizer>,
+    /// ANSI 序列处理方式
+    ansi_mode: AnsiMode,
 }
 
 impl OutputArchive {
@@ -158,6 +185,7 @@ impl OutputArchive {
             max_detail_length,
             enable_sanitization,
             sanitizer: default_sanitizer(),
+            ansi_mode: default_ansi_mode(),
         }
     }
 
@@ -168,16 +196,39 @@ impl OutputArchive {
             max_detail_length: 100_000, // 100KB
             enable_sanitization: true,
             sanitizer: default_sanitizer(),
+            ansi_mode: default_ansi_mode(),
         }
     }
 
-    /// 处理输出（脱敏、截断）
+    /// 设置 ANSI 序列处理方式
+    pub fn with_ansi_mode(mut self, ansi_mode: AnsiMode) -> Self {
+        self.ansi_mode = ansi_mode;
+        self
+    }
+
+    /// 规范化并脱敏输出，返回处理后的内容和是否经过规范化修改
+    ///
+    /// 脱敏在剥离转义序列之后进行，避免转义序列打断脱敏规则的匹配
+    fn normalize_and_sanitize(&self, output: &str) -> (String, bool) {
+        let normalized = normalize_output_with(output, self.ansi_mode, |text| {
+            if self.enable_sanitization {
+                self.sanitizer.sanitize(text)
+            } else {
+                text.to_string()
+            }
+        });
+        (normalized.content, normalized.modified)
+    }
+
+    /// 处理输出（规范化、脱敏、截断）
     pub fn process_output(&self, output: &str) -> (String, String) {
-        let processed = if self.enable_sanitization {
-            self.sanitizer.sanitize(output)
-        } else {
-            output.to_string()
-        };
+        let processed = self.process(output);
+        (processed.summary, processed.detail)
+    }
+
+    /// 处理输出（规范化、脱敏、截断），并记录是否经过规范化修改
+    pub fn process(&self, output: &str) -> ProcessedOutput {
+        let (processed, normalized) = self.normalize_and_sanitize(output);
 
         // 生成摘要
         let summary = if processed.len() > self.max_summary_length {
@@ -198,16 +249,16 @@ impl OutputArchive {
             processed
         };
 
-        (summary, detail)
+        ProcessedOutput {
+            summary,
+            detail,
+            normalized,
+        }
     }
 
     /// 仅生成摘要
     pub fn create_summary(&self, output: &str) -> String {
-        let processed = if self.enable_sanitization {
-            self.sanitizer.sanitize(output)
-        } else {
-            output.to_string()
-        };
+        let (processed, _) = self.normalize_and_sanitize(output);
 
         if processed.len() > self.max_summary_length {
             format!("{}...", &processed[..self.max_summary_length])
@@ -277,6 +328,19 @@ mod tests {
         assert!(detail.contains("truncated"));
     }
 
+    #[test]
+    fn test_output_archive_normalizes_ansi() {
+        let archive = OutputArchive::default_config().with_ansi_mode(AnsiMode::Strip);
+        let output = "\x1b[32mpassword=\x1b[0msecret\x1b[K\r\ndone\x07";
+        let processed = archive.process(output);
+
+        assert_eq!(processed.detail, "password=***\ndone");
+        assert!(processed.normalized);
+
+        let plain = archive.process("plain output");
+        assert!(!plain.normalized);
+    }
+
     #[test]
     fn test_contains_sensitive() {
         let sanitizer = OutputSanitizer::new_default();
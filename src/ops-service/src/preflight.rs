@@ -0,0 +1,329 @@
+//! 部署前检查（`ops-service --check`）
+//!
+//! 加载配置后逐项验证数据库、迁移状态、RabbitMQ、对象存储的连通性与 JWT 密钥强度，
+//! 输出结构化报告，存在失败项时以非零状态码退出，供部署流水线在切换流量前调用
+
+use config::ConfigError;
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::config::{AppConfig, JwtAlgorithm, DEFAULT_JWT_SECRET};
+use crate::db;
+use crate::rabbitmq::RabbitMqPublisher;
+use crate::services::StorageService;
+
+/// 单项检查的超时时间
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// JWT 密钥的最小长度
+const MIN_JWT_SECRET_LEN: usize = 32;
+
+/// JWT 密钥中至少包含的不同字符数
+const MIN_JWT_SECRET_DISTINCT_CHARS: usize = 10;
+
+/// 依赖配置的检查项（配置加载失败时跳过）
+const DEPENDENT_CHECKS: [&str; 5] = [
+    "jwt_secret",
+    "database",
+    "migrations",
+    "rabbitmq",
+    "storage",
+];
+
+/// 检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// 通过
+    Pass,
+    /// 通过但需关注（不影响退出码）
+    Warn,
+    /// 失败
+    Fail,
+    /// 前置检查失败，未执行
+    Skipped,
+}
+
+impl CheckStatus {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Pass => "PASS",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+            Self::Skipped => "SKIP",
+        }
+    }
+}
+
+/// 单项检查
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+/// 检查报告
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightReport {
+    pub version: &'static str,
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+impl PreflightReport {
+    fn new(checks: Vec<CheckResult>) -> Self {
+        let passed = checks.iter().all(|c| c.status != CheckStatus::Fail);
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            passed,
+            checks,
+        }
+    }
+
+    /// 文本格式的报告（每项一行）
+    pub fn render(&self) -> String {
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        let mut out = format!("ops-system {} preflight check\n", self.version);
+        for check in &self.checks {
+            out.push_str(&format!(
+                "  [{}] {:<width$}  {} ({} ms)\n",
+                check.status.label(),
+                check.name,
+                check.message,
+                check.duration_ms,
+            ));
+        }
+        out.push_str(if self.passed {
+            "Result: passed\n"
+        } else {
+            "Result: failed\n"
+        });
+        out
+    }
+}
+
+/// 执行全部检查
+///
+/// 配置加载失败时其余检查均跳过；数据库不可用时跳过迁移状态检查
+pub async fn run(config: Result<AppConfig, ConfigError>) -> PreflightReport {
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            let mut checks = vec![result("config", CheckStatus::Fail, e.to_string(), 0)];
+            for name in DEPENDENT_CHECKS {
+                checks.push(skipped(name, "Configuration not loaded"));
+            }
+            return PreflightReport::new(checks);
+        }
+    };
+
+    let mut checks = vec![result(
+        "config",
+        CheckStatus::Pass,
+        "Configuration loaded and valid",
+        0,
+    )];
+    checks.push(check_jwt(&config));
+
+    // 检查模式不自动建库，避免对目标环境产生副作用
+    let mut database = config.database.clone();
+    database.auto_create_if_missing = false;
+    let (check, pool) = timed("database", async {
+        let pool = db::create_pool(&database)
+            .await
+            .map_err(|e| e.to_string())?;
+        match db::health_check(&pool).await {
+            db::HealthStatus::Healthy => Ok((CheckStatus::Pass, "Connected".to_string(), pool)),
+            db::HealthStatus::Unhealthy(msg) => Err(msg),
+        }
+    })
+    .await;
+    checks.push(check);
+
+    match pool {
+        Some(pool) => {
+            let auto_migrate = config.database.auto_migrate;
+            let (check, _) = timed("migrations", async {
+                let plan = db::migration_plan(&pool).await.map_err(|e| e.to_string())?;
+                plan.verify().map_err(|e| e.to_string())?;
+                if plan.pending.is_empty() {
+                    let message = format!("{} applied, none pending", plan.applied_count);
+                    return Ok((CheckStatus::Pass, message, ()));
+                }
+                let versions: Vec<String> =
+                    plan.pending.iter().map(|m| m.version.to_string()).collect();
+                if auto_migrate {
+                    let message =
+                        format!("Pending {} will be applied on startup", versions.join(", "));
+                    Ok((CheckStatus::Warn, message, ()))
+                } else {
+                    Err(format!(
+                        "Pending {} and auto_migrate is disabled; run `ops-service migrate` first",
+                        versions.join(", ")
+                    ))
+                }
+            })
+            .await;
+            checks.push(check);
+            pool.close().await;
+        }
+        None => checks.push(skipped("migrations", "Database unavailable")),
+    }
+
+    let (check, _) = timed("rabbitmq", async {
+        let publisher = RabbitMqPublisher::new(config.rabbitmq.clone())
+            .await
+            .map_err(|e| format!("{:#}", e))?;
+        if publisher.health_check().await {
+            Ok((CheckStatus::Pass, "Connected".to_string(), ()))
+        } else {
+            // 交换机在服务首次启动时声明
+            let message = "Connected, build exchange not declared yet".to_string();
+            Ok((CheckStatus::Warn, message, ()))
+        }
+    })
+    .await;
+    checks.push(check);
+
+    let (check, _) = timed("storage", async {
+        let storage = StorageService::from_env().map_err(|e| format!("{:#}", e))?;
+        storage.probe().await.map_err(|e| format!("{:#}", e))?;
+        let message = format!("{:?} storage writable", storage.storage_type());
+        Ok((CheckStatus::Pass, message, ()))
+    })
+    .await;
+    checks.push(check);
+
+    PreflightReport::new(checks)
+}
+
+/// 检查 JWT 签名密钥：HS256 检查密钥强度，RS256 检查私钥能否加载
+fn check_jwt(config: &AppConfig) -> CheckResult {
+    let started = Instant::now();
+    let problem = assess_jwt_secret(config.security.jwt_secret.expose_secret()).or_else(|| {
+        crate::auth::jwt::JwtService::from_config(config)
+            .err()
+            .map(|e| format!("Failed to load signing keys: {}", e))
+    });
+    let (status, message) = match problem {
+        Some(problem) => (CheckStatus::Fail, problem),
+        None if config.jwt.algorithm == JwtAlgorithm::Rs256 => (
+            CheckStatus::Pass,
+            format!("{} RSA signing keys loaded", config.jwt.rsa_keys.len()),
+        ),
+        None => (CheckStatus::Pass, "Secret strength acceptable".to_string()),
+    };
+    result("jwt_secret", status, message, elapsed_ms(started))
+}
+
+/// 评估 JWT 密钥强度，返回发现的问题
+pub fn assess_jwt_secret(secret: &str) -> Option<String> {
+    if secret == DEFAULT_JWT_SECRET {
+        return Some("JWT secret is the built-in default".to_string());
+    }
+    let len = secret.chars().count();
+    if len < MIN_JWT_SECRET_LEN {
+        return Some(format!(
+            "JWT secret is {} characters, at least {} required",
+            len, MIN_JWT_SECRET_LEN
+        ));
+    }
+    let mut distinct: Vec<char> = secret.chars().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() < MIN_JWT_SECRET_DISTINCT_CHARS {
+        return Some(format!(
+            "JWT secret has only {} distinct characters, at least {} required",
+            distinct.len(),
+            MIN_JWT_SECRET_DISTINCT_CHARS
+        ));
+    }
+    None
+}
+
+/// 带超时执行一项检查，成功时返回检查附带的值
+async fn timed<T, F>(name: &'static str, check: F) -> (CheckResult, Option<T>)
+where
+    F: Future<Output = Result<(CheckStatus, String, T), String>>,
+{
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(CHECK_TIMEOUT, check).await;
+    let duration_ms = elapsed_ms(started);
+    match outcome {
+        Ok(Ok((status, message, value))) => {
+            (result(name, status, message, duration_ms), Some(value))
+        }
+        Ok(Err(message)) => (result(name, CheckStatus::Fail, message, duration_ms), None),
+        Err(_) => {
+            let message = format!("Timed out after {}s", CHECK_TIMEOUT.as_secs());
+            (result(name, CheckStatus::Fail, message, duration_ms), None)
+        }
+    }
+}
+
+fn result(
+    name: &'static str,
+    status: CheckStatus,
+    message: impl Into<String>,
+    duration_ms: u64,
+) -> CheckResult {
+    CheckResult {
+        name,
+        status,
+        message: message.into(),
+        duration_ms,
+    }
+}
+
+fn skipped(name: &'static str, reason: &str) -> CheckResult {
+    result(name, CheckStatus::Skipped, reason, 0)
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_jwt_secret() {
+        assert!(assess_jwt_secret(DEFAULT_JWT_SECRET).is_some());
+        assert!(assess_jwt_secret("short-secret").is_some());
+        assert!(assess_jwt_secret(&"ab".repeat(32)).is_some());
+        assert!(assess_jwt_secret("kQ7v$2pLx9!mR4wZ8nT1yB6cF3hJ5sD0").is_none());
+    }
+
+    #[test]
+    fn test_report_fails_only_on_failed_checks() {
+        let report = PreflightReport::new(vec![
+            result("database", CheckStatus::Pass, "Connected", 3),
+            result("migrations", CheckStatus::Warn, "Pending", 1),
+            skipped("storage", "Configuration not loaded"),
+        ]);
+        assert!(report.passed);
+        assert!(report.render().ends_with("Result: passed\n"));
+
+        let report = PreflightReport::new(vec![
+            result("database", CheckStatus::Fail, "Connection refused", 3),
+            skipped("migrations", "Database unavailable"),
+        ]);
+        assert!(!report.passed);
+        assert!(report.render().contains("[FAIL] database"));
+    }
+
+    #[tokio::test]
+    async fn test_config_error_skips_remaining_checks() {
+        let report = run(Err(ConfigError::Message("bad config".to_string()))).await;
+        assert!(!report.passed);
+        assert_eq!(report.checks[0].status, CheckStatus::Fail);
+        assert!(report.checks[1..]
+            .iter()
+            .all(|c| c.status == CheckStatus::Skipped));
+    }
+}
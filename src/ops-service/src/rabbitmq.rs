@@ -1,23 +1,175 @@
 //! RabbitMQ 发布器
 //!
-//! 负责将构建任务派发到 RabbitMQ，供 Runner 消费执行
+//! 负责将构建任务派发到 RabbitMQ，供 Runner 消费执行。
+//! 发布器与消费者断线后按指数退避自动重连并重新声明交换机与队列，
+//! 连接状态由 `BrokerMonitor` 统一记录，Broker 持续不可用超过阈值后熔断，就绪探针返回未就绪
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use futures::pin_mut;
+use lapin::types::{AMQPValue, FieldTable, ShortString};
 use lapin::{options::*, BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind};
-use lapin::types::ShortString;
 use secrecy::ExposeSecret;
 use serde::Serialize;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
 use crate::config::RabbitMqConfig;
+use common::{BuildCancelMessage, MessageHeaders, RoutingKeys, RunnerControlMessage};
 
 fn short_string(value: impl Into<String>) -> ShortString {
     value.into().into()
 }
 
+/// 将当前上下文中的 request_id 写入消息属性
+///
+/// 同时设置 AMQP correlation_id 和 x-request-id 消息头，Runner 据此关联日志
+fn with_request_id(properties: BasicProperties) -> BasicProperties {
+    let Some(request_id) = crate::middleware::request_id::current_request_id() else {
+        return properties;
+    };
+
+    let mut headers = FieldTable::default();
+    headers.insert(
+        short_string(MessageHeaders::REQUEST_ID),
+        AMQPValue::LongString(request_id.clone().into()),
+    );
+    properties
+        .with_correlation_id(short_string(request_id))
+        .with_headers(headers)
+}
+
+/// Broker 连接状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokerState {
+    /// 启动后尚未连接成功
+    Connecting,
+    /// 已连接
+    Connected,
+    /// 连接中断，正在重连
+    Disconnected,
+}
+
+/// Broker 连接状态快照
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokerStatus {
+    pub state: BrokerState,
+    /// 不可用时长超过阈值，熔断打开
+    pub circuit_open: bool,
+    /// 当前不可用的持续时长（已连接时为 0）
+    pub unavailable_secs: u64,
+    /// 断线后重连成功的次数
+    pub reconnects_total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+struct MonitorState {
+    state: BrokerState,
+    since: Instant,
+    last_error: Option<String>,
+}
+
+/// Broker 连接状态监控与熔断（发布器与消费者共享）
+pub struct BrokerMonitor {
+    unavailable_threshold: Duration,
+    inner: Mutex<MonitorState>,
+    reconnects: AtomicU64,
+}
+
+impl BrokerMonitor {
+    pub fn new(unavailable_threshold: Duration) -> Self {
+        Self {
+            unavailable_threshold,
+            inner: Mutex::new(MonitorState {
+                state: BrokerState::Connecting,
+                since: Instant::now(),
+                last_error: None,
+            }),
+            reconnects: AtomicU64::new(0),
+        }
+    }
+
+    /// 记录连接成功
+    pub fn record_connected(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.state == BrokerState::Connected {
+            return;
+        }
+        if inner.state == BrokerState::Disconnected {
+            self.reconnects.fetch_add(1, Ordering::Relaxed);
+            info!(
+                unavailable_secs = inner.since.elapsed().as_secs(),
+                "RabbitMQ connection restored"
+            );
+        }
+        inner.state = BrokerState::Connected;
+        inner.since = Instant::now();
+        inner.last_error = None;
+    }
+
+    /// 记录连接失败或中断（不可用时长从首次失败起计算）
+    pub fn record_failure(&self, error: impl Into<String>) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.state == BrokerState::Connected {
+            warn!("RabbitMQ connection lost");
+            inner.state = BrokerState::Disconnected;
+            inner.since = Instant::now();
+        }
+        inner.last_error = Some(error.into());
+    }
+
+    pub fn status(&self) -> BrokerStatus {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let unavailable = match inner.state {
+            BrokerState::Connected => Duration::ZERO,
+            BrokerState::Connecting | BrokerState::Disconnected => inner.since.elapsed(),
+        };
+        BrokerStatus {
+            state: inner.state,
+            circuit_open: inner.state != BrokerState::Connected
+                && unavailable >= self.unavailable_threshold,
+            unavailable_secs: unavailable.as_secs(),
+            reconnects_total: self.reconnects.load(Ordering::Relaxed),
+            last_error: inner.last_error.clone(),
+        }
+    }
+}
+
+/// 重连退避：从初始值起每次失败翻倍，不超过上限，并向下随机抖动最多 20%
+pub struct ReconnectBackoff {
+    initial: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl ReconnectBackoff {
+    pub fn new(config: &RabbitMqConfig) -> Self {
+        Self {
+            initial: Duration::from_millis(config.reconnect_initial_backoff_ms),
+            max: Duration::from_secs(config.reconnect_max_backoff_secs),
+            attempt: 0,
+        }
+    }
+
+    /// 下一次重连前的等待时间
+    pub fn next_delay(&mut self) -> Duration {
+        let base = self
+            .initial
+            .saturating_mul(2u32.saturating_pow(self.attempt.min(16)))
+            .min(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+        base.mul_f64(1.0 - rand::random::<f64>() * 0.2)
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
 /// RabbitMQ 发布器
 #[derive(Clone)]
 pub struct RabbitMqPublisher {
@@ -134,9 +286,11 @@ impl RabbitMqPublisher {
                 short_string(routing_key.clone()),
                 BasicPublishOptions::default(),
                 payload,
-                BasicProperties::default()
-                    .with_delivery_mode(2) // 持久化
-                    .with_content_type("application/json".into()),
+                with_request_id(
+                    BasicProperties::default()
+                        .with_delivery_mode(2) // 持久化
+                        .with_content_type("application/json".into()),
+                ),
             )
             .await?
             .await?;
@@ -150,6 +304,63 @@ impl RabbitMqPublisher {
         Ok(())
     }
 
+    /// 发布构建取消消息
+    ///
+    /// 路由键：build.cancel.<job_id>，所有 Runner 的控制队列均会收到，
+    /// 由正在执行该作业的 Runner 负责中止
+    pub async fn publish_build_cancel(&self, message: &BuildCancelMessage) -> Result<()> {
+        let routing_key = format!("{}.{}", RoutingKeys::BUILD_CANCEL, message.job_id);
+        let payload = serde_json::to_vec(message).context("Failed to serialize cancel message")?;
+
+        self.channel
+            .basic_publish(
+                short_string(self.config.build_exchange.clone()),
+                short_string(routing_key.clone()),
+                BasicPublishOptions::default(),
+                &payload,
+                with_request_id(
+                    BasicProperties::default()
+                        .with_delivery_mode(1) // 非持久化：Runner 重启后任务本身已中断
+                        .with_content_type("application/json".into()),
+                ),
+            )
+            .await?;
+
+        debug!("Build cancel published: {}", routing_key);
+        Ok(())
+    }
+
+    /// 发布 Runner 控制指令
+    ///
+    /// 路由键：runner.control.<runner_name>，只投递到该 Runner 的控制队列；
+    /// Runner 离线时控制队列不存在，消息被丢弃，指令随后超时
+    pub async fn publish_runner_control(
+        &self,
+        runner_name: &str,
+        message: &RunnerControlMessage,
+    ) -> Result<()> {
+        let routing_key = format!("{}.{}", RoutingKeys::RUNNER_CONTROL, runner_name);
+        let payload =
+            serde_json::to_vec(message).context("Failed to serialize runner control message")?;
+
+        self.channel
+            .basic_publish(
+                short_string(self.config.build_exchange.clone()),
+                short_string(routing_key.clone()),
+                BasicPublishOptions::default(),
+                &payload,
+                with_request_id(
+                    BasicProperties::default()
+                        .with_delivery_mode(1) // 非持久化：指令只对当前在线的 Runner 有效
+                        .with_content_type("application/json".into()),
+                ),
+            )
+            .await?;
+
+        debug!("Runner control published: {}", routing_key);
+        Ok(())
+    }
+
     /// 发布到 Runner 交换机（用于注册/心跳响应等）
     pub async fn publish_to_runner(
         &self,
@@ -164,9 +375,11 @@ impl RabbitMqPublisher {
                 short_string(routing_key),
                 BasicPublishOptions::default(),
                 &data,
-                BasicProperties::default()
-                    .with_delivery_mode(1) // 非持久化
-                    .with_content_type("application/json".into()),
+                with_request_id(
+                    BasicProperties::default()
+                        .with_delivery_mode(1) // 非持久化
+                        .with_content_type("application/json".into()),
+                ),
             )
             .await?;
 
@@ -197,21 +410,42 @@ impl RabbitMqPublisher {
     }
 }
 
+/// 发布器重连状态（退避期内的请求直接失败，避免每个请求都尝试连接）
+struct ReconnectState {
+    backoff: ReconnectBackoff,
+    retry_at: Option<Instant>,
+}
+
 /// RabbitMQ 发布器池
 pub struct RabbitMqPublisherPool {
     publisher: Arc<RwLock<Option<RabbitMqPublisher>>>,
     config: RabbitMqConfig,
+    monitor: Arc<BrokerMonitor>,
+    reconnect: Mutex<ReconnectState>,
 }
 
 impl RabbitMqPublisherPool {
     /// 创建新的发布器池
     pub fn new(config: RabbitMqConfig) -> Self {
+        let monitor =
+            Arc::new(BrokerMonitor::new(Duration::from_secs(config.unavailable_threshold_secs)));
+        let reconnect = Mutex::new(ReconnectState {
+            backoff: ReconnectBackoff::new(&config),
+            retry_at: None,
+        });
         Self {
             publisher: Arc::new(RwLock::new(None)),
             config,
+            monitor,
+            reconnect,
         }
     }
 
+    /// 连接状态监控（消费者共享同一实例）
+    pub fn monitor(&self) -> Arc<BrokerMonitor> {
+        self.monitor.clone()
+    }
+
     /// 获取或初始化发布器
     pub async fn get(&self) -> Result<RabbitMqPublisher> {
         // 检查是否有可用的发布器
@@ -220,17 +454,59 @@ impl RabbitMqPublisherPool {
             if let Some(publisher) = reader.as_ref() {
                 // 检查健康状态
                 if publisher.health_check().await {
+                    self.monitor.record_connected();
                     return Ok(publisher.clone());
                 }
             }
         }
 
-        // 需要重新初始化
+        // 需要重新初始化（等待写锁期间可能已由其他请求重连成功；退避期内直接失败）
         let mut writer = self.publisher.write().await;
-        let new_publisher = RabbitMqPublisher::new(self.config.clone()).await?;
-        new_publisher.setup_infrastructure().await?;
-        *writer = Some(new_publisher.clone());
-        Ok(new_publisher)
+        if let Some(publisher) = writer.as_ref() {
+            if publisher.health_check().await {
+                return Ok(publisher.clone());
+            }
+        }
+        let retry_at = self.reconnect_state().retry_at;
+        if let Some(wait) = retry_at.and_then(|at| at.checked_duration_since(Instant::now())) {
+            return Err(anyhow!(
+                "RabbitMQ unavailable, next reconnect attempt in {}ms",
+                wait.as_millis()
+            ));
+        }
+
+        *writer = None;
+        let connected = async {
+            let publisher = RabbitMqPublisher::new(self.config.clone()).await?;
+            publisher.setup_infrastructure().await?;
+            Ok::<_, anyhow::Error>(publisher)
+        }
+        .await;
+        let mut reconnect = self.reconnect_state();
+        match connected {
+            Ok(publisher) => {
+                reconnect.backoff.reset();
+                reconnect.retry_at = None;
+                self.monitor.record_connected();
+                *writer = Some(publisher.clone());
+                Ok(publisher)
+            }
+            Err(e) => {
+                let delay = reconnect.backoff.next_delay();
+                reconnect.retry_at = Some(Instant::now() + delay);
+                self.monitor.record_failure(format!("{:#}", e));
+                warn!(
+                    error = %e,
+                    retry_in_ms = delay.as_millis() as u64,
+                    "RabbitMQ publisher reconnect failed"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn reconnect_state(&self) -> std::sync::MutexGuard<'_, ReconnectState> {
+        self.reconnect.lock().unwrap_or_else(|e| e.into_inner())
     }
 
     /// 健康检查
@@ -249,7 +525,6 @@ impl RabbitMqPublisherPool {
 #[derive(Clone)]
 pub struct RabbitMqConsumer {
     config: Arc<RabbitMqConfig>,
-    #[allow(dead_code)]
     connection: Arc<Connection>,
     channel: Arc<Channel>,
 }
@@ -404,12 +679,13 @@ impl RabbitMqConsumer {
                     }
                 }
                 Err(e) => {
-                    tracing::error!("Consumer error: {}", e);
+                    // 通道或连接已失效，由调用方重建连接
+                    return Err(e).context("Status consumer failed");
                 }
             }
         }
 
-        Ok(())
+        Err(anyhow!("Status consumer stream closed"))
     }
 
     /// 启动日志消息消费者
@@ -453,12 +729,78 @@ impl RabbitMqConsumer {
                     }
                 }
                 Err(e) => {
-                    tracing::error!("Log consumer error: {}", e);
+                    return Err(e).context("Log consumer failed");
                 }
             }
         }
 
-        Ok(())
+        Err(anyhow!("Log consumer stream closed"))
+    }
+
+    /// 关闭连接
+    pub async fn close(&self) {
+        if let Err(e) = self.connection.close(200, "Reconnecting".into()).await {
+            debug!("Failed to close RabbitMQ consumer connection: {}", e);
+        }
+    }
+
+    /// 持续消费状态与日志消息，直到任务被取消
+    ///
+    /// 连接失败或任一消费流中断时关闭连接，按退避重连并重新声明交换机与队列后
+    /// 以相同的消费者标签恢复消费；未确认的消息由 Broker 重新投递
+    pub async fn run<S, L>(
+        config: RabbitMqConfig,
+        monitor: Arc<BrokerMonitor>,
+        status_handler: S,
+        log_handler: L,
+    ) where
+        S: FnMut(Vec<u8>) + Clone + Send + 'static,
+        L: FnMut(Vec<u8>) + Clone + Send + 'static,
+    {
+        let mut backoff = ReconnectBackoff::new(&config);
+        loop {
+            let connected = async {
+                let consumer = Self::new(config.clone()).await?;
+                consumer.setup_consumer_queues().await?;
+                Ok::<_, anyhow::Error>(consumer)
+            }
+            .await;
+            let consumer = match connected {
+                Ok(consumer) => consumer,
+                Err(e) => {
+                    monitor.record_failure(format!("{:#}", e));
+                    let delay = backoff.next_delay();
+                    warn!(
+                        error = %e,
+                        retry_in_ms = delay.as_millis() as u64,
+                        "RabbitMQ consumer connect failed"
+                    );
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+            };
+
+            monitor.record_connected();
+            backoff.reset();
+            info!("RabbitMQ consumer started, listening for build status and log messages");
+
+            let error = tokio::select! {
+                result = consumer.consume_status_messages(status_handler.clone()) => result,
+                result = consumer.consume_log_messages(log_handler.clone()) => result,
+            }
+            .err()
+            .unwrap_or_else(|| anyhow!("Consumer stopped"));
+            monitor.record_failure(format!("{:#}", error));
+            consumer.close().await;
+
+            let delay = backoff.next_delay();
+            warn!(
+                error = %error,
+                retry_in_ms = delay.as_millis() as u64,
+                "RabbitMQ consumer disconnected, reconnecting"
+            );
+            tokio::time::sleep(delay).await;
+        }
     }
 }
 
@@ -475,6 +817,9 @@ mod tests {
             runner_exchange: "test.ops.runner".to_string(),
             pool_size: 1,
             publish_timeout_secs: 5,
+            reconnect_initial_backoff_ms: 500,
+            reconnect_max_backoff_secs: 30,
+            unavailable_threshold_secs: 60,
         }
     }
 
@@ -493,4 +838,54 @@ mod tests {
         let routing_key = format!("build.{}.{}", "node", "runner-1");
         assert_eq!(routing_key, "build.node.runner-1");
     }
+
+    #[test]
+    fn test_reconnect_backoff_doubles_up_to_max() {
+        let mut config = create_test_config();
+        config.reconnect_initial_backoff_ms = 1000;
+        config.reconnect_max_backoff_secs = 4;
+        let mut backoff = ReconnectBackoff::new(&config);
+
+        for max_ms in [1000, 2000, 4000, 4000, 4000] {
+            let delay = backoff.next_delay().as_millis();
+            assert!(delay <= max_ms && delay >= max_ms * 8 / 10, "{}", delay);
+        }
+        backoff.reset();
+        assert!(backoff.next_delay() <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_broker_monitor_circuit_and_reconnects() {
+        let monitor = BrokerMonitor::new(Duration::ZERO);
+        assert_eq!(monitor.status().state, BrokerState::Connecting);
+        assert!(monitor.status().circuit_open);
+
+        monitor.record_connected();
+        let status = monitor.status();
+        assert_eq!(status.state, BrokerState::Connected);
+        assert!(!status.circuit_open);
+        assert_eq!(status.reconnects_total, 0);
+
+        monitor.record_failure("connection reset");
+        let status = monitor.status();
+        assert_eq!(status.state, BrokerState::Disconnected);
+        assert!(status.circuit_open);
+        assert_eq!(status.last_error.as_deref(), Some("connection reset"));
+
+        monitor.record_connected();
+        let status = monitor.status();
+        assert!(!status.circuit_open);
+        assert_eq!(status.reconnects_total, 1);
+        assert!(status.last_error.is_none());
+    }
+
+    #[test]
+    fn test_broker_monitor_tolerates_short_outage() {
+        let monitor = BrokerMonitor::new(Duration::from_secs(60));
+        monitor.record_connected();
+        monitor.record_failure("connection reset");
+        let status = monitor.status();
+        assert_eq!(status.state, BrokerState::Disconnected);
+        assert!(!status.circuit_open);
+    }
 }
@@ -1,15 +1,25 @@
 //! Real-time event streaming
 //! P3 阶段：实时事件推送（SSE）
 
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::{broadcast, RwLock};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Notify, RwLock};
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
+use crate::i18n::{self, Locale};
+use crate::services::notification_template;
+
+pub mod outbox;
 
 /// 实时事件类型
-#[derive(Debug, Clone)]
+///
+/// 序列化格式与 SSE 数据一致（`{"type": ..., "data": {...}}`），用于写入事件发件箱
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum RealtimeEvent {
     /// 作业状态变更
     JobStatusChanged {
@@ -31,6 +41,25 @@ pub enum RealtimeEvent {
         output: String,
         is_complete: bool,
     },
+    /// 任务增量输出超过阈值，切换为仅摘要模式（之后只在任务结束时推送输出摘要）
+    TaskOutputSummarized {
+        task_id: Uuid,
+        job_id: Uuid,
+        /// 切换时的输出字节数
+        output_bytes: u64,
+        threshold_bytes: u64,
+    },
+    /// 作业因主机不可达暂停派发（告警，等待确认继续或中止）
+    JobDispatchPaused {
+        job_id: Uuid,
+        /// 窗口内不可达的任务数
+        unreachable_tasks: u32,
+        /// 窗口内结束的任务数
+        sample_size: u32,
+        failure_rate: f64,
+    },
+    /// 确认继续后恢复派发
+    JobDispatchResumed { job_id: Uuid, resumed_by: Uuid },
     /// 审批状态变更
     ApprovalStatusChanged {
         approval_id: Uuid,
@@ -44,6 +73,79 @@ pub enum RealtimeEvent {
         title: String,
         requested_by: Uuid,
     },
+    /// 主机维护状态变更
+    HostMaintenanceChanged {
+        host_id: Uuid,
+        /// 有任务等待该主机的作业
+        job_ids: Vec<Uuid>,
+        in_maintenance: bool,
+        maintenance_until: Option<chrono::DateTime<chrono::Utc>>,
+        reason: Option<String>,
+    },
+    /// 关注对象状态变更通知（仅推送给关注该对象的用户）
+    WatchNotification {
+        /// 接收通知的用户
+        user_id: Uuid,
+        /// 关注对象类型（job/host/template）
+        target_type: String,
+        target_id: Uuid,
+        /// 触发通知的作业（主机维护状态变更时为空）
+        job_id: Option<Uuid>,
+        old_status: Option<String>,
+        new_status: String,
+        /// 触发通知的作业名称
+        #[serde(default)]
+        job_name: Option<String>,
+        /// 作业已运行时长（秒，作业尚未开始时为空）
+        #[serde(default)]
+        duration_secs: Option<i64>,
+    },
+    /// 检测到安全异常（仅推送给有审计查看权限的安全事件流订阅者）
+    SecurityAnomalyDetected {
+        anomaly_id: Uuid,
+        kind: String,
+        severity: String,
+        user_id: Option<Uuid>,
+        summary: String,
+    },
+    /// 证据包导出进度（仅推送给发起导出的用户）
+    EvidenceExportProgress {
+        export_id: Uuid,
+        job_id: Uuid,
+        user_id: Uuid,
+        status: String,
+        stage: Option<String>,
+        progress: i32,
+    },
+    /// 资产组批量连接测试进度（仅推送给发起测试的用户；每完成一台主机推送一次，结束时不带主机）
+    ConnectionTestProgress {
+        run_id: Uuid,
+        group_id: Uuid,
+        user_id: Uuid,
+        status: String,
+        completed: i32,
+        total: i32,
+        host_id: Option<Uuid>,
+        outcome: Option<String>,
+    },
+    /// 审批提醒（按审批时限的进度提醒尚未决策的审批人，同时推送到审批事件流）
+    ApprovalReminder {
+        approval_id: Uuid,
+        job_id: Option<Uuid>,
+        title: String,
+        /// 已用时限的百分比
+        percent: i32,
+        expires_at: chrono::DateTime<chrono::Utc>,
+        approver_ids: Vec<Uuid>,
+    },
+    /// 审批请求超时未决（通知申请人，同时推送到审批事件流）
+    ApprovalExpired {
+        approval_id: Uuid,
+        job_id: Option<Uuid>,
+        title: String,
+        requested_by: Uuid,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    },
     /// 心跳信号（保持连接活跃）
     Heartbeat,
 }
@@ -51,6 +153,11 @@ pub enum RealtimeEvent {
 impl RealtimeEvent {
     /// 转换为SSE格式的数据
     pub fn to_sse_data(&self) -> String {
+        self.to_json().to_string()
+    }
+
+    /// 转换为 JSON 数据
+    fn to_json(&self) -> serde_json::Value {
         match self {
             RealtimeEvent::JobStatusChanged {
                 job_id,
@@ -63,8 +170,7 @@ impl RealtimeEvent {
                     "old_status": old_status,
                     "new_status": new_status,
                 }
-            })
-            .to_string(),
+            }),
             RealtimeEvent::TaskStatusChanged {
                 task_id,
                 job_id,
@@ -78,8 +184,7 @@ impl RealtimeEvent {
                     "old_status": old_status,
                     "new_status": new_status,
                 }
-            })
-            .to_string(),
+            }),
             RealtimeEvent::TaskOutputUpdate {
                 task_id,
                 job_id,
@@ -93,8 +198,42 @@ impl RealtimeEvent {
                     "output": output,
                     "is_complete": is_complete,
                 }
-            })
-            .to_string(),
+            }),
+            RealtimeEvent::TaskOutputSummarized {
+                task_id,
+                job_id,
+                output_bytes,
+                threshold_bytes,
+            } => serde_json::json!({
+                "type": "task_output_summarized",
+                "data": {
+                    "task_id": task_id,
+                    "job_id": job_id,
+                    "output_bytes": output_bytes,
+                    "threshold_bytes": threshold_bytes,
+                }
+            }),
+            RealtimeEvent::JobDispatchPaused {
+                job_id,
+                unreachable_tasks,
+                sample_size,
+                failure_rate,
+            } => serde_json::json!({
+                "type": "job_dispatch_paused",
+                "data": {
+                    "job_id": job_id,
+                    "unreachable_tasks": unreachable_tasks,
+                    "sample_size": sample_size,
+                    "failure_rate": failure_rate,
+                }
+            }),
+            RealtimeEvent::JobDispatchResumed { job_id, resumed_by } => serde_json::json!({
+                "type": "job_dispatch_resumed",
+                "data": {
+                    "job_id": job_id,
+                    "resumed_by": resumed_by,
+                }
+            }),
             RealtimeEvent::ApprovalStatusChanged {
                 approval_id,
                 old_status,
@@ -106,8 +245,7 @@ impl RealtimeEvent {
                     "old_status": old_status,
                     "new_status": new_status,
                 }
-            })
-            .to_string(),
+            }),
             RealtimeEvent::NewApprovalRequest {
                 approval_id,
                 job_id,
@@ -121,15 +259,233 @@ impl RealtimeEvent {
                     "title": title,
                     "requested_by": requested_by,
                 }
-            })
-            .to_string(),
+            }),
+            RealtimeEvent::HostMaintenanceChanged {
+                host_id,
+                job_ids,
+                in_maintenance,
+                maintenance_until,
+                reason,
+            } => serde_json::json!({
+                "type": "host_maintenance_changed",
+                "data": {
+                    "host_id": host_id,
+                    "job_ids": job_ids,
+                    "in_maintenance": in_maintenance,
+                    "maintenance_until": maintenance_until,
+                    "reason": reason,
+                }
+            }),
+            RealtimeEvent::WatchNotification {
+                user_id,
+                target_type,
+                target_id,
+                job_id,
+                old_status,
+                new_status,
+                job_name,
+                duration_secs,
+            } => serde_json::json!({
+                "type": "watch_notification",
+                "data": {
+                    "user_id": user_id,
+                    "target_type": target_type,
+                    "target_id": target_id,
+                    "job_id": job_id,
+                    "old_status": old_status,
+                    "new_status": new_status,
+                    "job_name": job_name,
+                    "duration_secs": duration_secs,
+                }
+            }),
+            RealtimeEvent::SecurityAnomalyDetected {
+                anomaly_id,
+                kind,
+                severity,
+                user_id,
+                summary,
+            } => serde_json::json!({
+                "type": "security_anomaly_detected",
+                "data": {
+                    "anomaly_id": anomaly_id,
+                    "kind": kind,
+                    "severity": severity,
+                    "user_id": user_id,
+                    "summary": summary,
+                }
+            }),
+            RealtimeEvent::EvidenceExportProgress {
+                export_id,
+                job_id,
+                user_id,
+                status,
+                stage,
+                progress,
+            } => serde_json::json!({
+                "type": "evidence_export_progress",
+                "data": {
+                    "export_id": export_id,
+                    "job_id": job_id,
+                    "user_id": user_id,
+                    "status": status,
+                    "stage": stage,
+                    "progress": progress,
+                }
+            }),
+            RealtimeEvent::ConnectionTestProgress {
+                run_id,
+                group_id,
+                user_id,
+                status,
+                completed,
+                total,
+                host_id,
+                outcome,
+            } => serde_json::json!({
+                "type": "connection_test_progress",
+                "data": {
+                    "run_id": run_id,
+                    "group_id": group_id,
+                    "user_id": user_id,
+                    "status": status,
+                    "completed": completed,
+                    "total": total,
+                    "host_id": host_id,
+                    "outcome": outcome,
+                }
+            }),
+            RealtimeEvent::ApprovalReminder {
+                approval_id,
+                job_id,
+                title,
+                percent,
+                expires_at,
+                approver_ids,
+            } => serde_json::json!({
+                "type": "approval_reminder",
+                "data": {
+                    "approval_id": approval_id,
+                    "job_id": job_id,
+                    "title": title,
+                    "percent": percent,
+                    "expires_at": expires_at,
+                    "approver_ids": approver_ids,
+                }
+            }),
+            RealtimeEvent::ApprovalExpired {
+                approval_id,
+                job_id,
+                title,
+                requested_by,
+                expires_at,
+            } => serde_json::json!({
+                "type": "approval_expired",
+                "data": {
+                    "approval_id": approval_id,
+                    "job_id": job_id,
+                    "title": title,
+                    "requested_by": requested_by,
+                    "expires_at": expires_at,
+                }
+            }),
             RealtimeEvent::Heartbeat => serde_json::json!({
                 "type": "heartbeat",
                 "data": {
                     "timestamp": chrono::Utc::now().to_rfc3339()
                 }
-            })
-            .to_string(),
+            }),
+        }
+    }
+
+    /// 判断事件是否与指定作业相关（不含心跳）
+    pub fn concerns_job(&self, job_id: Uuid) -> bool {
+        match self {
+            RealtimeEvent::JobStatusChanged { job_id: id, .. }
+            | RealtimeEvent::TaskStatusChanged { job_id: id, .. }
+            | RealtimeEvent::TaskOutputUpdate { job_id: id, .. }
+            | RealtimeEvent::TaskOutputSummarized { job_id: id, .. }
+            | RealtimeEvent::JobDispatchPaused { job_id: id, .. }
+            | RealtimeEvent::JobDispatchResumed { job_id: id, .. } => *id == job_id,
+            RealtimeEvent::HostMaintenanceChanged { job_ids, .. } => job_ids.contains(&job_id),
+            _ => false,
+        }
+    }
+
+    /// 发给用户的通知文案（仅关注通知、审批提醒与审批超时），按订阅者的语言生成
+    ///
+    /// 优先使用该语言的自定义通知模板，未配置时使用内置文案
+    pub fn notification_message(&self, locale: Locale) -> Option<String> {
+        notification_template::render_custom(self, notification_template::CHANNEL_IN_APP, locale)
+            .or_else(|| self.builtin_notification_message(locale))
+    }
+
+    /// 内置通知文案（i18n 目录）
+    pub fn builtin_notification_message(&self, locale: Locale) -> Option<String> {
+        match self {
+            RealtimeEvent::WatchNotification {
+                target_type,
+                target_id,
+                old_status,
+                new_status,
+                ..
+            } => Some(match old_status {
+                Some(old_status) => i18n::tf_in(
+                    locale,
+                    "notification.watch_status_changed",
+                    &[
+                        ("target_type", target_type),
+                        ("target_id", target_id),
+                        ("old_status", old_status),
+                        ("new_status", new_status),
+                    ],
+                ),
+                None => i18n::tf_in(
+                    locale,
+                    "notification.watch_status",
+                    &[
+                        ("target_type", target_type),
+                        ("target_id", target_id),
+                        ("new_status", new_status),
+                    ],
+                ),
+            }),
+            RealtimeEvent::ApprovalReminder {
+                title,
+                percent,
+                expires_at,
+                ..
+            } => Some(i18n::tf_in(
+                locale,
+                "notification.approval_reminder",
+                &[
+                    ("title", title),
+                    ("percent", percent),
+                    ("expires_at", &expires_at.format("%Y-%m-%d %H:%M UTC")),
+                ],
+            )),
+            RealtimeEvent::ApprovalExpired {
+                title, expires_at, ..
+            } => Some(i18n::tf_in(
+                locale,
+                "notification.approval_expired",
+                &[
+                    ("title", title),
+                    ("expires_at", &expires_at.format("%Y-%m-%d %H:%M UTC")),
+                ],
+            )),
+            _ => None,
+        }
+    }
+
+    /// 判断事件是否为发给指定用户的通知（关注通知、证据包导出与批量连接测试进度、审批提醒与超时）
+    pub fn notifies_user(&self, user_id: Uuid) -> bool {
+        match self {
+            RealtimeEvent::WatchNotification { user_id: id, .. }
+            | RealtimeEvent::EvidenceExportProgress { user_id: id, .. }
+            | RealtimeEvent::ConnectionTestProgress { user_id: id, .. } => *id == user_id,
+            RealtimeEvent::ApprovalReminder { approver_ids, .. } => approver_ids.contains(&user_id),
+            RealtimeEvent::ApprovalExpired { requested_by, .. } => *requested_by == user_id,
+            _ => false,
         }
     }
 
@@ -139,65 +495,337 @@ impl RealtimeEvent {
             RealtimeEvent::JobStatusChanged { .. } => "job_status_changed",
             RealtimeEvent::TaskStatusChanged { .. } => "task_status_changed",
             RealtimeEvent::TaskOutputUpdate { .. } => "task_output_update",
+            RealtimeEvent::TaskOutputSummarized { .. } => "task_output_summarized",
+            RealtimeEvent::JobDispatchPaused { .. } => "job_dispatch_paused",
+            RealtimeEvent::JobDispatchResumed { .. } => "job_dispatch_resumed",
             RealtimeEvent::ApprovalStatusChanged { .. } => "approval_status_changed",
             RealtimeEvent::NewApprovalRequest { .. } => "new_approval_request",
+            RealtimeEvent::HostMaintenanceChanged { .. } => "host_maintenance_changed",
+            RealtimeEvent::WatchNotification { .. } => "watch_notification",
+            RealtimeEvent::SecurityAnomalyDetected { .. } => "security_anomaly_detected",
+            RealtimeEvent::EvidenceExportProgress { .. } => "evidence_export_progress",
+            RealtimeEvent::ConnectionTestProgress { .. } => "connection_test_progress",
+            RealtimeEvent::ApprovalReminder { .. } => "approval_reminder",
+            RealtimeEvent::ApprovalExpired { .. } => "approval_expired",
             RealtimeEvent::Heartbeat => "heartbeat",
         }
     }
 }
 
+/// 事件信封
+///
+/// 在事件总线上传递的事件，附带发布时所在请求的关联 ID
+#[derive(Debug, Clone)]
+pub struct EventEnvelope {
+    pub event: RealtimeEvent,
+    /// 关联 ID（发布时的 request_id，后台任务发布时可能为空）
+    pub correlation_id: Option<String>,
+    /// 事件序号（由事件总线发布时分配，单调递增；心跳为 0）
+    pub seq: u64,
+}
+
+impl EventEnvelope {
+    /// 创建待发布的信封（序号由事件总线分配）
+    pub fn new(event: RealtimeEvent, correlation_id: Option<String>) -> Self {
+        Self {
+            event,
+            correlation_id,
+            seq: 0,
+        }
+    }
+
+    /// 转换为 JSON 数据（在事件数据中附带 seq 与 correlation_id）
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = self.event.to_json();
+        if let Some(obj) = value.as_object_mut() {
+            if self.seq > 0 {
+                obj.insert("seq".to_string(), serde_json::json!(self.seq));
+            }
+            if let Some(id) = &self.correlation_id {
+                obj.insert("correlation_id".to_string(), serde_json::json!(id));
+            }
+        }
+        value
+    }
+
+    /// 转换为SSE格式的数据
+    pub fn to_sse_data(&self) -> String {
+        self.to_json().to_string()
+    }
+
+    /// 转换为完整的 SSE 消息（带序号时输出 `id:` 行，客户端可据此断点续传或改用轮询）
+    ///
+    /// 通知类事件在 data 中附带按订阅者语言生成的 message
+    fn to_sse_message(&self, locale: Locale) -> String {
+        let id_line = if self.seq > 0 {
+            format!("id: {}\n", self.seq)
+        } else {
+            String::new()
+        };
+        let mut value = self.to_json();
+        if let Some(message) = self.event.notification_message(locale) {
+            if let Some(data) = value.get_mut("data").and_then(|d| d.as_object_mut()) {
+                data.insert("message".to_string(), serde_json::json!(message));
+            }
+        }
+        format!("{}event: {}\ndata: {}\n\n", id_line, self.event.event_type(), value)
+    }
+}
+
+/// 最近事件的回放缓冲区，SSE 断线续传与轮询增量接口共用
+struct EventHistory {
+    capacity: usize,
+    next_seq: u64,
+    events: VecDeque<EventEnvelope>,
+}
+
+impl EventHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_seq: 1,
+            events: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// 分配序号并写入缓冲区，超出容量时丢弃最旧的事件
+    fn record(&mut self, envelope: &mut EventEnvelope) {
+        envelope.seq = self.next_seq;
+        self.next_seq += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(envelope.clone());
+    }
+
+    /// 查询序号大于 `since_seq` 且满足过滤条件的事件
+    fn since(&self, since_seq: u64, filter: impl Fn(&RealtimeEvent) -> bool) -> EventDelta {
+        let last_seq = self.next_seq - 1;
+        let oldest_seq = self.events.front().map_or(self.next_seq, |e| e.seq);
+        // 所需事件已被淘汰，或序号来自重启前的进程，客户端需要重新加载完整状态
+        let truncated = since_seq > last_seq || since_seq + 1 < oldest_seq;
+        let since_seq = if since_seq > last_seq { 0 } else { since_seq };
+
+        let events = self
+            .events
+            .iter()
+            .filter(|e| e.seq > since_seq && filter(&e.event))
+            .cloned()
+            .collect();
+        EventDelta {
+            events,
+            last_seq,
+            truncated,
+        }
+    }
+}
+
+/// 增量事件查询结果
+#[derive(Debug, Clone)]
+pub struct EventDelta {
+    /// 序号大于查询起点的事件（按序号升序）
+    pub events: Vec<EventEnvelope>,
+    /// 当前最新的事件序号，作为下次查询的起点
+    pub last_seq: u64,
+    /// 查询起点之后的部分事件已不在缓冲区中
+    pub truncated: bool,
+}
+
+impl EventDelta {
+    /// 转换为 JSON 响应（事件格式与 SSE 数据一致）
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "events": self.events.iter().map(EventEnvelope::to_json).collect::<Vec<_>>(),
+            "last_seq": self.last_seq,
+            "truncated": self.truncated,
+        })
+    }
+}
+
 /// 事件总线
 #[derive(Clone)]
 pub struct EventBus {
     /// 广播发送器（用于向所有订阅者发送事件）
-    sender: broadcast::Sender<RealtimeEvent>,
+    sender: broadcast::Sender<EventEnvelope>,
+    /// 发件箱有新事件的通知（唤醒中继任务）
+    outbox_signal: Arc<Notify>,
+    /// 最近事件回放缓冲区（容量与广播通道一致）
+    history: Arc<Mutex<EventHistory>>,
 }
 
 impl EventBus {
     /// 创建新的事件总线
     pub fn new(capacity: usize) -> Self {
         let (sender, _) = broadcast::channel(capacity);
-        Self { sender }
+        Self {
+            sender,
+            outbox_signal: Arc::new(Notify::new()),
+            history: Arc::new(Mutex::new(EventHistory::new(capacity))),
+        }
     }
 
     /// 发布事件
+    ///
+    /// 自动附带当前上下文中的 request_id 作为关联 ID
     pub fn publish(&self, event: RealtimeEvent) -> Result<()> {
+        self.publish_envelope(EventEnvelope::new(
+            event,
+            crate::middleware::request_id::current_request_id(),
+        ))
+    }
+
+    /// 发布已附带关联 ID 的事件（发件箱中继使用写入时记录的关联 ID）
+    ///
+    /// 除心跳外的事件分配序号并写入回放缓冲区；没有订阅者时同样写入，供轮询读取
+    pub fn publish_envelope(&self, mut envelope: EventEnvelope) -> Result<()> {
+        let mut history = lock_history(&self.history);
+        if !matches!(envelope.event, RealtimeEvent::Heartbeat) {
+            history.record(&mut envelope);
+        }
+        // 持锁发送，保证订阅者收到的顺序与序号一致
         self.sender
-            .send(event)
+            .send(envelope)
             .map_err(|e| AppError::internal_error(&format!("Failed to publish event: {}", e)))?;
         Ok(())
     }
 
+    /// 查询序号大于 `since_seq` 的指定作业事件（轮询增量接口）
+    pub fn job_events_since(&self, job_id: Uuid, since_seq: u64) -> EventDelta {
+        lock_history(&self.history).since(since_seq, |event| event.concerns_job(job_id))
+    }
+
+    /// 通知中继任务发件箱有新事件（在事务提交后调用）
+    pub fn notify_outbox(&self) {
+        self.outbox_signal.notify_one();
+    }
+
+    /// 等待发件箱新事件通知
+    pub async fn outbox_notified(&self) {
+        self.outbox_signal.notified().await;
+    }
+
     /// 订阅事件
-    pub fn subscribe(&self) -> broadcast::Receiver<RealtimeEvent> {
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
         self.sender.subscribe()
     }
 
     /// 订阅特定作业的事件
     pub fn subscribe_to_job(&self, job_id: Uuid) -> JobEventStream {
-        JobEventStream::new(self.subscribe(), job_id)
+        JobEventStream::new(self.subscribe(), self.history.clone(), job_id)
     }
 
     /// 订阅所有审批事件
     pub fn subscribe_to_approvals(&self) -> ApprovalEventStream {
         ApprovalEventStream::new(self.subscribe())
     }
+
+    /// 订阅发给指定用户的关注通知
+    pub fn subscribe_to_notifications(&self, user_id: Uuid) -> NotificationEventStream {
+        NotificationEventStream::new(self.subscribe(), user_id)
+    }
+
+    /// 订阅安全异常事件
+    pub fn subscribe_to_security(&self) -> SecurityEventStream {
+        SecurityEventStream::new(self.subscribe())
+    }
+}
+
+/// 获取回放缓冲区锁（持锁时不会 panic，忽略中毒）
+fn lock_history(history: &Mutex<EventHistory>) -> std::sync::MutexGuard<'_, EventHistory> {
+    history.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 订阅者作用域校验器
+///
+/// 订阅建立时已做过一次完整的权限校验；事件推送过程中通过该校验器复核，
+/// 校验结果按 TTL 缓存，避免每条事件都访问数据库。
+/// 一旦复核失败（例如角色绑定被撤销），事件流立即终止。
+pub struct ScopeChecker {
+    check: Arc<dyn Fn() -> BoxFuture<'static, bool> + Send + Sync>,
+    ttl: Duration,
+    cached: Option<(Instant, bool)>,
+}
+
+impl ScopeChecker {
+    /// 默认缓存时长
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+    /// 创建校验器
+    pub fn new<F>(ttl: Duration, check: F) -> Self
+    where
+        F: Fn() -> BoxFuture<'static, bool> + Send + Sync + 'static,
+    {
+        Self {
+            check: Arc::new(check),
+            ttl,
+            cached: None,
+        }
+    }
+
+    /// 创建带初始校验结果的校验器（订阅时刚完成校验，无需立即复核）
+    pub fn with_initial(mut self, allowed: bool) -> Self {
+        self.cached = Some((Instant::now(), allowed));
+        self
+    }
+
+    /// 判断订阅者当前是否仍有权接收事件
+    pub async fn is_allowed(&mut self) -> bool {
+        if let Some((checked_at, allowed)) = self.cached {
+            if checked_at.elapsed() < self.ttl {
+                return allowed;
+            }
+        }
+
+        let allowed = (self.check)().await;
+        self.cached = Some((Instant::now(), allowed));
+        allowed
+    }
 }
 
 /// 作业事件流（过滤特定作业的事件）
 pub struct JobEventStream {
-    receiver: broadcast::Receiver<RealtimeEvent>,
+    receiver: broadcast::Receiver<EventEnvelope>,
+    history: Arc<Mutex<EventHistory>>,
     job_id: Uuid,
+    scope: Option<ScopeChecker>,
+    replay_since: Option<u64>,
 }
 
 impl JobEventStream {
-    fn new(receiver: broadcast::Receiver<RealtimeEvent>, job_id: Uuid) -> Self {
-        Self { receiver, job_id }
+    fn new(
+        receiver: broadcast::Receiver<EventEnvelope>,
+        history: Arc<Mutex<EventHistory>>,
+        job_id: Uuid,
+    ) -> Self {
+        Self {
+            receiver,
+            history,
+            job_id,
+            scope: None,
+            replay_since: None,
+        }
+    }
+
+    /// 设置订阅者作用域校验器
+    pub fn with_scope_checker(mut self, scope: ScopeChecker) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// 订阅开始时先补发序号大于 `since_seq` 的缓冲事件（SSE `Last-Event-ID` 续传）
+    pub fn with_replay_since(mut self, since_seq: Option<u64>) -> Self {
+        self.replay_since = since_seq;
+        self
     }
 
     /// 转换为SSE流
     pub async fn to_sse_stream(mut self) -> Result<impl futures::Stream<Item = Result<String>>> {
         let (tx, rx) = tokio::sync::mpsc::channel(100);
+        // 在请求上下文中调用，推送任务沿用订阅请求的语言
+        let locale = i18n::current_locale();
 
         // 心跳定时器
         let heartbeat_tx = tx.clone();
@@ -217,22 +845,39 @@ impl JobEventStream {
 
         // 事件转发任务
         tokio::spawn(async move {
-            while let Ok(event) = self.receiver.recv().await {
+            // 已订阅广播后再读取缓冲区，补发与实时事件之间不会遗漏；按序号去重
+            let replay = self.replay_since.map(|since| {
+                let job_id = self.job_id;
+                lock_history(&self.history).since(since, |event| event.concerns_job(job_id))
+            });
+            let mut last_seq = 0;
+            for envelope in replay.into_iter().flat_map(|delta| delta.events) {
+                if !scope_allows(&mut self.scope).await {
+                    return;
+                }
+                last_seq = envelope.seq;
+                if tx.send(Ok(envelope.to_sse_message(locale))).await.is_err() {
+                    return;
+                }
+            }
+
+            while let Ok(envelope) = self.receiver.recv().await {
+                let event = &envelope.event;
                 // 过滤与当前作业相关的事件
-                let should_send = match &event {
-                    RealtimeEvent::JobStatusChanged { job_id, .. } => job_id == &self.job_id,
-                    RealtimeEvent::TaskStatusChanged { job_id, .. } => job_id == &self.job_id,
-                    RealtimeEvent::TaskOutputUpdate { job_id, .. } => job_id == &self.job_id,
-                    RealtimeEvent::Heartbeat => true,
-                    _ => false,
-                };
-
-                if should_send {
-                    let sse_data =
-                        format!("event: {}\ndata: {}\n\n", event.event_type(), event.to_sse_data());
-                    if tx.send(Ok(sse_data)).await.is_err() {
-                        break;
-                    }
+                let should_send = (event.concerns_job(self.job_id)
+                    || matches!(event, RealtimeEvent::Heartbeat))
+                    && (envelope.seq == 0 || envelope.seq > last_seq);
+
+                if should_send && !scope_allows(&mut self.scope).await {
+                    tracing::info!(
+                        job_id = %self.job_id,
+                        "Subscriber lost access, closing job event stream"
+                    );
+                    break;
+                }
+
+                if should_send && tx.send(Ok(envelope.to_sse_message(locale))).await.is_err() {
+                    break;
                 }
             }
         });
@@ -243,19 +888,39 @@ impl JobEventStream {
     }
 }
 
+/// 校验订阅者作用域（未设置校验器时默认放行）
+async fn scope_allows(scope: &mut Option<ScopeChecker>) -> bool {
+    match scope {
+        Some(checker) => checker.is_allowed().await,
+        None => true,
+    }
+}
+
 /// 审批事件流
 pub struct ApprovalEventStream {
-    receiver: broadcast::Receiver<RealtimeEvent>,
+    receiver: broadcast::Receiver<EventEnvelope>,
+    scope: Option<ScopeChecker>,
 }
 
 impl ApprovalEventStream {
-    fn new(receiver: broadcast::Receiver<RealtimeEvent>) -> Self {
-        Self { receiver }
+    fn new(receiver: broadcast::Receiver<EventEnvelope>) -> Self {
+        Self {
+            receiver,
+            scope: None,
+        }
+    }
+
+    /// 设置订阅者作用域校验器
+    pub fn with_scope_checker(mut self, scope: ScopeChecker) -> Self {
+        self.scope = Some(scope);
+        self
     }
 
     /// 转换为SSE流
     pub async fn to_sse_stream(mut self) -> Result<impl futures::Stream<Item = Result<String>>> {
         let (tx, rx) = tokio::sync::mpsc::channel(100);
+        // 在请求上下文中调用，推送任务沿用订阅请求的语言
+        let locale = i18n::current_locale();
 
         // 心跳定时器
         let heartbeat_tx = tx.clone();
@@ -275,21 +940,160 @@ impl ApprovalEventStream {
 
         // 事件转发任务
         tokio::spawn(async move {
-            while let Ok(event) = self.receiver.recv().await {
+            while let Ok(envelope) = self.receiver.recv().await {
+                let event = &envelope.event;
                 // 过滤审批相关的事件
                 let should_send = matches!(
                     event,
                     RealtimeEvent::ApprovalStatusChanged { .. }
                         | RealtimeEvent::NewApprovalRequest { .. }
+                        | RealtimeEvent::ApprovalReminder { .. }
+                        | RealtimeEvent::ApprovalExpired { .. }
                         | RealtimeEvent::Heartbeat
                 );
 
-                if should_send {
-                    let sse_data =
-                        format!("event: {}\ndata: {}\n\n", event.event_type(), event.to_sse_data());
-                    if tx.send(Ok(sse_data)).await.is_err() {
-                        break;
-                    }
+                if should_send && !scope_allows(&mut self.scope).await {
+                    tracing::info!("Subscriber lost access, closing approval event stream");
+                    break;
+                }
+
+                if should_send && tx.send(Ok(envelope.to_sse_message(locale))).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+        Ok(stream)
+    }
+}
+
+/// 关注通知事件流（仅包含发给订阅用户的通知）
+pub struct NotificationEventStream {
+    receiver: broadcast::Receiver<EventEnvelope>,
+    user_id: Uuid,
+    scope: Option<ScopeChecker>,
+}
+
+impl NotificationEventStream {
+    fn new(receiver: broadcast::Receiver<EventEnvelope>, user_id: Uuid) -> Self {
+        Self {
+            receiver,
+            user_id,
+            scope: None,
+        }
+    }
+
+    /// 设置订阅者作用域校验器
+    pub fn with_scope_checker(mut self, scope: ScopeChecker) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// 转换为SSE流
+    pub async fn to_sse_stream(mut self) -> Result<impl futures::Stream<Item = Result<String>>> {
+        let (tx, rx) = tokio::sync::mpsc::channel(100);
+        // 在请求上下文中调用，推送任务沿用订阅请求的语言
+        let locale = i18n::current_locale();
+
+        // 心跳定时器
+        let heartbeat_tx = tx.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                if heartbeat_tx
+                    .send(Ok(RealtimeEvent::Heartbeat.to_sse_data()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        // 事件转发任务
+        tokio::spawn(async move {
+            while let Ok(envelope) = self.receiver.recv().await {
+                let should_send = envelope.event.notifies_user(self.user_id)
+                    || matches!(envelope.event, RealtimeEvent::Heartbeat);
+
+                if should_send && !scope_allows(&mut self.scope).await {
+                    tracing::info!(
+                        user_id = %self.user_id,
+                        "Subscriber lost access, closing notification stream"
+                    );
+                    break;
+                }
+
+                if should_send && tx.send(Ok(envelope.to_sse_message(locale))).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+        Ok(stream)
+    }
+}
+
+/// 安全事件流（仅包含安全异常事件）
+pub struct SecurityEventStream {
+    receiver: broadcast::Receiver<EventEnvelope>,
+    scope: Option<ScopeChecker>,
+}
+
+impl SecurityEventStream {
+    fn new(receiver: broadcast::Receiver<EventEnvelope>) -> Self {
+        Self {
+            receiver,
+            scope: None,
+        }
+    }
+
+    /// 设置订阅者作用域校验器
+    pub fn with_scope_checker(mut self, scope: ScopeChecker) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// 转换为SSE流
+    pub async fn to_sse_stream(mut self) -> Result<impl futures::Stream<Item = Result<String>>> {
+        let (tx, rx) = tokio::sync::mpsc::channel(100);
+        // 在请求上下文中调用，推送任务沿用订阅请求的语言
+        let locale = i18n::current_locale();
+
+        // 心跳定时器
+        let heartbeat_tx = tx.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                if heartbeat_tx
+                    .send(Ok(RealtimeEvent::Heartbeat.to_sse_data()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        // 事件转发任务
+        tokio::spawn(async move {
+            while let Ok(envelope) = self.receiver.recv().await {
+                let should_send = matches!(
+                    envelope.event,
+                    RealtimeEvent::SecurityAnomalyDetected { .. } | RealtimeEvent::Heartbeat
+                );
+
+                if should_send && !scope_allows(&mut self.scope).await {
+                    tracing::info!("Subscriber lost access, closing security event stream");
+                    break;
+                }
+
+                if should_send && tx.send(Ok(envelope.to_sse_message(locale))).await.is_err() {
+                    break;
                 }
             }
         });
@@ -418,6 +1222,329 @@ mod tests {
         assert!(masked.contains("username=admin"));
     }
 
+    #[tokio::test]
+    async fn test_scope_checker_caches_result() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let calls_clone = calls.clone();
+        let mut checker = ScopeChecker::new(Duration::from_secs(60), move || {
+            calls_clone.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Box::pin(async { true })
+        });
+
+        assert!(checker.is_allowed().await);
+        assert!(checker.is_allowed().await);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scope_checker_rechecks_after_ttl() {
+        let mut checker =
+            ScopeChecker::new(Duration::ZERO, || Box::pin(async { false })).with_initial(true);
+
+        // TTL 为 0，初始缓存立即失效，复核结果为拒绝
+        assert!(!checker.is_allowed().await);
+    }
+
+    #[tokio::test]
+    async fn test_publish_attaches_correlation_id() {
+        let bus = EventBus::new(16);
+        let mut rx = bus.subscribe();
+
+        crate::middleware::request_id::scope("req-42".to_string(), async {
+            bus.publish(RealtimeEvent::Heartbeat).unwrap();
+        })
+        .await;
+
+        let envelope = rx.recv().await.unwrap();
+        assert_eq!(envelope.correlation_id.as_deref(), Some("req-42"));
+        assert!(envelope
+            .to_sse_data()
+            .contains("\"correlation_id\":\"req-42\""));
+    }
+
+    #[test]
+    fn test_host_maintenance_event_json() {
+        let host_id = Uuid::new_v4();
+        let job_id = Uuid::new_v4();
+        let event = RealtimeEvent::HostMaintenanceChanged {
+            host_id,
+            job_ids: vec![job_id],
+            in_maintenance: false,
+            maintenance_until: None,
+            reason: None,
+        };
+
+        assert_eq!(event.event_type(), "host_maintenance_changed");
+        let value = event.to_json();
+        assert_eq!(value["type"], "host_maintenance_changed");
+        assert_eq!(value["data"]["host_id"], serde_json::json!(host_id));
+        assert_eq!(value["data"]["job_ids"], serde_json::json!([job_id]));
+        assert_eq!(value["data"]["in_maintenance"], false);
+    }
+
+    #[test]
+    fn test_task_output_summarized_concerns_job() {
+        let job_id = Uuid::new_v4();
+        let event = RealtimeEvent::TaskOutputSummarized {
+            task_id: Uuid::new_v4(),
+            job_id,
+            output_bytes: 2 * 1024 * 1024,
+            threshold_bytes: 1024 * 1024,
+        };
+
+        assert_eq!(event.event_type(), "task_output_summarized");
+        assert!(event.concerns_job(job_id));
+        assert!(!event.concerns_job(Uuid::new_v4()));
+        assert_eq!(serde_json::to_value(&event).unwrap(), event.to_json());
+    }
+
+    #[test]
+    fn test_job_dispatch_paused_concerns_job() {
+        let job_id = Uuid::new_v4();
+        let event = RealtimeEvent::JobDispatchPaused {
+            job_id,
+            unreachable_tasks: 8,
+            sample_size: 10,
+            failure_rate: 0.8,
+        };
+
+        assert_eq!(event.event_type(), "job_dispatch_paused");
+        assert!(event.concerns_job(job_id));
+        assert!(!event.concerns_job(Uuid::new_v4()));
+        assert_eq!(serde_json::to_value(&event).unwrap(), event.to_json());
+    }
+
+    #[test]
+    fn test_watch_notification_targets_single_user() {
+        let user_id = Uuid::new_v4();
+        let job_id = Uuid::new_v4();
+        let event = RealtimeEvent::WatchNotification {
+            user_id,
+            target_type: "template".to_string(),
+            target_id: Uuid::new_v4(),
+            job_id: Some(job_id),
+            old_status: Some("running".to_string()),
+            new_status: "failed".to_string(),
+            job_name: Some("nightly-backup".to_string()),
+            duration_secs: Some(95),
+        };
+
+        assert_eq!(event.event_type(), "watch_notification");
+        assert!(event.notifies_user(user_id));
+        assert!(!event.notifies_user(Uuid::new_v4()));
+        // 定向通知不进入作业事件流
+        assert!(!event.concerns_job(job_id));
+        assert_eq!(serde_json::to_value(&event).unwrap(), event.to_json());
+    }
+
+    #[test]
+    fn test_notification_message_is_localized() {
+        let target_id = Uuid::new_v4();
+        let event = RealtimeEvent::WatchNotification {
+            user_id: Uuid::new_v4(),
+            target_type: "host".to_string(),
+            target_id,
+            job_id: None,
+            old_status: Some("online".to_string()),
+            new_status: "maintenance".to_string(),
+            job_name: None,
+            duration_secs: None,
+        };
+        assert_eq!(
+            event.notification_message(Locale::ZhCn).unwrap(),
+            format!("host {} 状态由 online 变为 maintenance", target_id)
+        );
+
+        let envelope = EventEnvelope::new(event, None);
+        let message = envelope.to_sse_message(Locale::EnUs);
+        assert!(message.contains(&format!(
+            "\"message\":\"host {} changed from online to maintenance\"",
+            target_id
+        )));
+        assert!(RealtimeEvent::Heartbeat
+            .notification_message(Locale::EnUs)
+            .is_none());
+    }
+
+    #[test]
+    fn test_security_anomaly_event_json() {
+        let event = RealtimeEvent::SecurityAnomalyDetected {
+            anomaly_id: Uuid::new_v4(),
+            kind: "failed_auth_spike".to_string(),
+            severity: "high".to_string(),
+            user_id: None,
+            summary: "25 failed logins from 10.0.0.9".to_string(),
+        };
+
+        assert_eq!(event.event_type(), "security_anomaly_detected");
+        assert!(!event.notifies_user(Uuid::new_v4()));
+        assert_eq!(serde_json::to_value(&event).unwrap(), event.to_json());
+    }
+
+    #[test]
+    fn test_evidence_export_progress_targets_requester() {
+        let user_id = Uuid::new_v4();
+        let job_id = Uuid::new_v4();
+        let event = RealtimeEvent::EvidenceExportProgress {
+            export_id: Uuid::new_v4(),
+            job_id,
+            user_id,
+            status: "running".to_string(),
+            stage: Some("tasks".to_string()),
+            progress: 40,
+        };
+
+        assert_eq!(event.event_type(), "evidence_export_progress");
+        assert!(event.notifies_user(user_id));
+        assert!(!event.notifies_user(Uuid::new_v4()));
+        assert!(!event.concerns_job(job_id));
+        assert_eq!(serde_json::to_value(&event).unwrap(), event.to_json());
+    }
+
+    #[test]
+    fn test_connection_test_progress_targets_requester() {
+        let user_id = Uuid::new_v4();
+        let event = RealtimeEvent::ConnectionTestProgress {
+            run_id: Uuid::new_v4(),
+            group_id: Uuid::new_v4(),
+            user_id,
+            status: "running".to_string(),
+            completed: 3,
+            total: 10,
+            host_id: Some(Uuid::new_v4()),
+            outcome: Some("auth_failed".to_string()),
+        };
+
+        assert_eq!(event.event_type(), "connection_test_progress");
+        assert!(event.notifies_user(user_id));
+        assert!(!event.notifies_user(Uuid::new_v4()));
+        assert_eq!(serde_json::to_value(&event).unwrap(), event.to_json());
+    }
+
+    #[test]
+    fn test_approval_reminder_notifies_outstanding_approvers() {
+        let approver = Uuid::new_v4();
+        let event = RealtimeEvent::ApprovalReminder {
+            approval_id: Uuid::new_v4(),
+            job_id: None,
+            title: "Restart nginx on prod".to_string(),
+            percent: 80,
+            expires_at: chrono::Utc::now(),
+            approver_ids: vec![approver],
+        };
+
+        assert_eq!(event.event_type(), "approval_reminder");
+        assert!(event.notifies_user(approver));
+        assert!(!event.notifies_user(Uuid::new_v4()));
+        assert_eq!(serde_json::to_value(&event).unwrap(), event.to_json());
+    }
+
+    #[test]
+    fn test_approval_expired_notifies_requester() {
+        let requester = Uuid::new_v4();
+        let event = RealtimeEvent::ApprovalExpired {
+            approval_id: Uuid::new_v4(),
+            job_id: Some(Uuid::new_v4()),
+            title: "Restart nginx on prod".to_string(),
+            requested_by: requester,
+            expires_at: chrono::Utc::now(),
+        };
+
+        assert_eq!(event.event_type(), "approval_expired");
+        assert!(event.notifies_user(requester));
+        assert!(!event.notifies_user(Uuid::new_v4()));
+        assert!(event
+            .notification_message(Locale::EnUs)
+            .unwrap()
+            .starts_with("Approval \"Restart nginx on prod\" expired at"));
+        assert_eq!(serde_json::to_value(&event).unwrap(), event.to_json());
+    }
+
+    #[test]
+    fn test_event_serialization_matches_sse_format() {
+        let event = RealtimeEvent::JobStatusChanged {
+            job_id: Uuid::new_v4(),
+            old_status: "running".to_string(),
+            new_status: "completed".to_string(),
+        };
+
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value, event.to_json());
+
+        let decoded: RealtimeEvent = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.to_json(), event.to_json());
+    }
+
+    fn job_event(job_id: Uuid, new_status: &str) -> RealtimeEvent {
+        RealtimeEvent::JobStatusChanged {
+            job_id,
+            old_status: "pending".to_string(),
+            new_status: new_status.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_job_events_since_uses_replay_buffer() {
+        let bus = EventBus::new(3);
+        let job_id = Uuid::new_v4();
+        // 没有订阅者时发布失败，但事件仍写入缓冲区
+        let _ = bus.publish(job_event(job_id, "running"));
+        let _ = bus.publish(job_event(Uuid::new_v4(), "running"));
+        let _ = bus.publish(job_event(job_id, "completed"));
+
+        let delta = bus.job_events_since(job_id, 0);
+        assert_eq!(delta.events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(delta.last_seq, 3);
+        assert!(!delta.truncated);
+        assert!(bus.job_events_since(job_id, 3).events.is_empty());
+
+        let value = delta.to_json();
+        assert_eq!(value["events"][0]["seq"], 1);
+        assert_eq!(value["events"][0]["type"], "job_status_changed");
+        assert_eq!(value["events"][1]["data"]["new_status"], "completed");
+
+        // 超出容量后最旧的事件被淘汰，起点早于缓冲区时标记为截断
+        let _ = bus.publish(job_event(job_id, "failed"));
+        let delta = bus.job_events_since(job_id, 0);
+        assert!(delta.truncated);
+        assert_eq!(delta.events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![3, 4]);
+        assert!(!bus.job_events_since(job_id, 1).truncated);
+
+        // 序号大于当前最新序号（服务重启）时返回全部缓冲事件
+        let delta = bus.job_events_since(job_id, 100);
+        assert!(delta.truncated);
+        assert_eq!(delta.events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_job_stream_replays_buffered_events_with_ids() {
+        use futures::StreamExt;
+
+        let bus = EventBus::new(16);
+        let job_id = Uuid::new_v4();
+        let _ = bus.publish(job_event(job_id, "running"));
+        let _ = bus.publish(job_event(job_id, "completed"));
+
+        let mut stream = bus
+            .subscribe_to_job(job_id)
+            .with_replay_since(Some(1))
+            .to_sse_stream()
+            .await
+            .unwrap();
+        bus.publish(job_event(job_id, "cancelled")).unwrap();
+
+        let mut frames = Vec::new();
+        while frames.len() < 2 {
+            let frame = stream.next().await.unwrap().unwrap();
+            if frame.starts_with("id: ") {
+                frames.push(frame);
+            }
+        }
+        assert!(frames[0].starts_with("id: 2\nevent: job_status_changed\n"));
+        assert!(frames[0].contains("\"seq\":2"));
+        assert!(frames[1].starts_with("id: 3\n"));
+    }
+
     #[test]
     fn test_mask_email() {
         let output = "Email: test@example.com";
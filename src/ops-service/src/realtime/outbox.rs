@@ -0,0 +1,157 @@
+//! 事务性事件发件箱
+//! 状态变更与对应事件在同一事务中写入 `event_outbox`，由中继任务按写入顺序发布到事件总线，
+//! 避免提交后发布失败或进程崩溃导致事件丢失
+
+use sqlx::{PgExecutor, PgPool};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, warn};
+
+use super::{EventBus, EventEnvelope, RealtimeEvent};
+use crate::error::{AppError, Result};
+
+/// 单批中继的最大事件数
+const RELAY_BATCH_SIZE: i64 = 200;
+
+/// 中继失败次数上限，超过后不再重试（保留记录供排查）
+const MAX_RELAY_ATTEMPTS: i32 = 5;
+
+/// 写入发件箱
+///
+/// 传入状态变更所在的事务；提交后调用 `EventBus::notify_outbox` 唤醒中继
+pub async fn enqueue<'e, E>(executor: E, event: &RealtimeEvent) -> Result<()>
+where
+    E: PgExecutor<'e>,
+{
+    let payload = serde_json::to_value(event)
+        .map_err(|e| AppError::internal_error(&format!("Failed to serialize event: {}", e)))?;
+
+    sqlx::query(
+        "INSERT INTO event_outbox (event_type, payload, correlation_id) VALUES ($1, $2, $3)",
+    )
+    .bind(event.event_type())
+    .bind(payload)
+    .bind(crate::middleware::request_id::current_request_id())
+    .execute(executor)
+    .await
+    .map_err(|e| {
+        error!(error = %e, event_type = event.event_type(), "Failed to write event to outbox");
+        AppError::database("Failed to write event")
+    })?;
+
+    Ok(())
+}
+
+/// 发件箱中继：将未投递的事件发布到事件总线并标记为已投递
+pub struct OutboxRelay {
+    db: PgPool,
+    event_bus: Arc<EventBus>,
+}
+
+impl OutboxRelay {
+    pub fn new(db: PgPool, event_bus: Arc<EventBus>) -> Self {
+        Self { db, event_bus }
+    }
+
+    /// 中继一批未投递的事件，返回已投递数量
+    ///
+    /// 使用 `FOR UPDATE SKIP LOCKED`，多实例同时运行时同一事件只会被一个实例处理
+    pub async fn relay_batch(&self) -> Result<usize> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        let rows = sqlx::query_as::<_, (i64, serde_json::Value, Option<String>)>(
+            r#"
+            SELECT id, payload, correlation_id
+            FROM event_outbox
+            WHERE delivered_at IS NULL AND attempts < $1
+            ORDER BY id
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(MAX_RELAY_ATTEMPTS)
+        .bind(RELAY_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch pending outbox events");
+            AppError::database("Failed to fetch events")
+        })?;
+
+        let mut delivered = Vec::with_capacity(rows.len());
+        for (id, payload, correlation_id) in rows {
+            match serde_json::from_value::<RealtimeEvent>(payload) {
+                Ok(event) => {
+                    // 没有订阅者时发送失败，事件无人接收，同样视为已投递
+                    let _ = self
+                        .event_bus
+                        .publish_envelope(EventEnvelope::new(event, correlation_id));
+                    delivered.push(id);
+                }
+                Err(e) => {
+                    warn!(outbox_id = id, error = %e, "Failed to decode outbox event");
+                    sqlx::query(
+                        "UPDATE event_outbox SET attempts = attempts + 1, last_error = $2 WHERE id = $1",
+                    )
+                    .bind(id)
+                    .bind(e.to_string())
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| {
+                        error!(error = %e, "Failed to record outbox failure");
+                        AppError::database("Failed to update events")
+                    })?;
+                }
+            }
+        }
+
+        if !delivered.is_empty() {
+            sqlx::query("UPDATE event_outbox SET delivered_at = NOW() WHERE id = ANY($1)")
+                .bind(&delivered)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to mark outbox events delivered");
+                    AppError::database("Failed to update events")
+                })?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+
+        Ok(delivered.len())
+    }
+
+    /// 中继所有未投递的事件
+    pub async fn relay_pending(&self) -> Result<usize> {
+        let mut total = 0;
+        loop {
+            let delivered = self.relay_batch().await?;
+            total += delivered;
+            if (delivered as i64) < RELAY_BATCH_SIZE {
+                return Ok(total);
+            }
+        }
+    }
+
+    /// 删除投递时间早于保留期的事件
+    pub async fn purge_delivered(&self, retention: Duration) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM event_outbox WHERE delivered_at < NOW() - make_interval(secs => $1)",
+        )
+        .bind(retention.as_secs_f64())
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to purge delivered outbox events");
+            AppError::database("Failed to purge events")
+        })?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,295 @@
+//! Advisory repository (安全公告与匹配结果数据访问)
+
+use crate::{error::AppError, models::advisory::*};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct AdvisoryRepository {
+    db: PgPool,
+}
+
+impl AdvisoryRepository {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    // ==================== Advisories ====================
+
+    /// 写入公告及其版本范围，已存在且 modified 未变化时跳过并返回 false
+    pub async fn upsert_advisory(&self, advisory: &NewAdvisory) -> Result<bool, AppError> {
+        let mut tx = self.db.begin().await?;
+        let existing: Option<DateTime<Utc>> =
+            sqlx::query_scalar("SELECT modified FROM advisories WHERE id = $1 FOR UPDATE")
+                .bind(&advisory.id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if existing.is_some_and(|modified| modified >= advisory.modified) {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO advisories (id, aliases, summary, severity, source, published, modified)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id) DO UPDATE SET
+                aliases = EXCLUDED.aliases,
+                summary = EXCLUDED.summary,
+                severity = EXCLUDED.severity,
+                source = EXCLUDED.source,
+                published = EXCLUDED.published,
+                modified = EXCLUDED.modified,
+                imported_at = NOW()
+            "#,
+        )
+        .bind(&advisory.id)
+        .bind(&advisory.aliases)
+        .bind(&advisory.summary)
+        .bind(&advisory.severity)
+        .bind(&advisory.source)
+        .bind(advisory.published)
+        .bind(advisory.modified)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM advisory_packages WHERE advisory_id = $1")
+            .bind(&advisory.id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut formats = Vec::new();
+        let mut names = Vec::new();
+        let mut releases = Vec::new();
+        let mut introduced = Vec::new();
+        let mut fixed = Vec::new();
+        let mut last_affected = Vec::new();
+        for package in &advisory.packages {
+            formats.push(package.package_format.as_str());
+            names.push(package.package_name.as_str());
+            releases.push(package.ecosystem_release.as_str());
+            introduced.push(package.introduced.as_deref());
+            fixed.push(package.fixed_version.as_deref());
+            last_affected.push(package.last_affected.as_deref());
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO advisory_packages (
+                advisory_id, package_format, package_name, ecosystem_release, introduced,
+                fixed_version, last_affected
+            )
+            SELECT $1, p.format, p.name, p.release, p.introduced, p.fixed, p.last_affected
+            FROM UNNEST($2::text[], $3::text[], $4::text[], $5::text[], $6::text[], $7::text[])
+                AS p(format, name, release, introduced, fixed, last_affected)
+            "#,
+        )
+        .bind(&advisory.id)
+        .bind(&formats)
+        .bind(&names)
+        .bind(&releases)
+        .bind(&introduced)
+        .bind(&fixed)
+        .bind(&last_affected)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// 删除公告（OSV 记录被撤回时），匹配结果随之级联删除
+    pub async fn delete_advisory(&self, id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM advisories WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 按公告 id 或 CVE 编号查找公告
+    pub async fn find_advisory(&self, id_or_alias: &str) -> Result<Option<Advisory>, AppError> {
+        let advisory = sqlx::query_as::<_, Advisory>(
+            r#"
+            SELECT id, aliases, summary, severity, source, published, modified, imported_at
+            FROM advisories
+            WHERE id = $1 OR aliases @> ARRAY[$1]::text[]
+            ORDER BY (id = $1) DESC, modified DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(id_or_alias)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(advisory)
+    }
+
+    /// 列出公告，受影响主机数只统计调用方作用域内的主机（None 表示不限制）
+    pub async fn list_advisories(
+        &self,
+        query: &AdvisoryListQuery,
+        environments: Option<&[String]>,
+        groups: Option<&[String]>,
+    ) -> Result<Vec<AdvisorySummary>, AppError> {
+        let advisories = sqlx::query_as::<_, AdvisorySummary>(
+            r#"
+            SELECT a.id, a.aliases, a.summary, a.severity, a.source, a.published, a.modified,
+                COALESCE(c.affected_hosts, 0) AS affected_hosts
+            FROM advisories a
+            LEFT JOIN LATERAL (
+                SELECT COUNT(DISTINCT m.host_id) AS affected_hosts
+                FROM advisory_matches m
+                JOIN assets_hosts h ON h.id = m.host_id
+                WHERE m.advisory_id = a.id
+                    AND ($1::text[] IS NULL OR h.environment = ANY($1))
+                    AND ($2::text[] IS NULL OR h.group_id::text = ANY($2))
+            ) c ON TRUE
+            WHERE ($3::text IS NULL
+                    OR a.id ILIKE $3
+                    OR a.summary ILIKE $3
+                    OR EXISTS (SELECT 1 FROM UNNEST(a.aliases) alias WHERE alias ILIKE $3))
+                AND ($4::text IS NULL OR a.severity = $4)
+                AND (NOT $5 OR COALESCE(c.affected_hosts, 0) > 0)
+            ORDER BY COALESCE(c.affected_hosts, 0) DESC, a.modified DESC
+            LIMIT $6 OFFSET $7
+            "#,
+        )
+        .bind(environments)
+        .bind(groups)
+        .bind(query.search.as_ref().map(|s| format!("%{}%", s)))
+        .bind(query.severity.as_ref().map(|s| s.to_lowercase()))
+        .bind(query.affected_only)
+        .bind(query.limit)
+        .bind(query.offset)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(advisories)
+    }
+
+    // ==================== Matches ====================
+
+    /// 按包管理器与包名关联公告版本范围与主机已安装的软件包（版本条件由调用方按版本规则判断）
+    pub async fn list_match_candidates(&self) -> Result<Vec<AdvisoryMatchCandidate>, AppError> {
+        let candidates = sqlx::query_as::<_, AdvisoryMatchCandidate>(
+            r#"
+            SELECT ap.advisory_id, p.host_id, h.os_version, p.package_format,
+                p.name AS package_name, p.version AS installed_version, ap.ecosystem_release,
+                ap.introduced, ap.fixed_version, ap.last_affected
+            FROM advisory_packages ap
+            JOIN host_packages p
+                ON p.package_format = ap.package_format AND p.name = ap.package_name
+            JOIN assets_hosts h ON h.id = p.host_id
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(candidates)
+    }
+
+    /// 以新的匹配结果替换现有结果，仍然存在的匹配保留首次发现时间
+    pub async fn replace_matches(&self, matches: &[AdvisoryMatch]) -> Result<(), AppError> {
+        let mut advisory_ids = Vec::new();
+        let mut host_ids = Vec::new();
+        let mut formats = Vec::new();
+        let mut names = Vec::new();
+        let mut versions = Vec::new();
+        let mut fixed = Vec::new();
+        for m in matches {
+            advisory_ids.push(m.advisory_id.as_str());
+            host_ids.push(m.host_id);
+            formats.push(m.package_format.as_str());
+            names.push(m.package_name.as_str());
+            versions.push(m.installed_version.as_str());
+            fixed.push(m.fixed_version.as_deref());
+        }
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query(
+            r#"
+            DELETE FROM advisory_matches m
+            WHERE NOT EXISTS (
+                SELECT 1
+                FROM UNNEST($1::text[], $2::uuid[], $3::text[], $4::text[])
+                    AS n(advisory_id, host_id, name, version)
+                WHERE n.advisory_id = m.advisory_id AND n.host_id = m.host_id
+                    AND n.name = m.package_name AND n.version = m.installed_version
+            )
+            "#,
+        )
+        .bind(&advisory_ids)
+        .bind(&host_ids)
+        .bind(&names)
+        .bind(&versions)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO advisory_matches (
+                advisory_id, host_id, package_format, package_name, installed_version,
+                fixed_version
+            )
+            SELECT n.advisory_id, n.host_id, n.format, n.name, n.version, n.fixed
+            FROM UNNEST($1::text[], $2::uuid[], $3::text[], $4::text[], $5::text[], $6::text[])
+                AS n(advisory_id, host_id, format, name, version, fixed)
+            ON CONFLICT (advisory_id, host_id, package_name, installed_version) DO UPDATE SET
+                package_format = EXCLUDED.package_format,
+                fixed_version = EXCLUDED.fixed_version
+            "#,
+        )
+        .bind(&advisory_ids)
+        .bind(&host_ids)
+        .bind(&formats)
+        .bind(&names)
+        .bind(&versions)
+        .bind(&fixed)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// 列出受公告影响的主机
+    pub async fn list_affected_hosts(
+        &self,
+        advisory_id: &str,
+    ) -> Result<Vec<AffectedHost>, AppError> {
+        let hosts = sqlx::query_as::<_, AffectedHost>(
+            r#"
+            SELECT m.host_id, h.identifier, h.address, h.environment, h.group_id,
+                m.package_format, m.package_name, m.installed_version, m.fixed_version,
+                m.matched_at
+            FROM advisory_matches m
+            JOIN assets_hosts h ON h.id = m.host_id
+            WHERE m.advisory_id = $1
+            ORDER BY h.identifier, m.package_name, m.installed_version
+            "#,
+        )
+        .bind(advisory_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(hosts)
+    }
+
+    /// 列出影响指定主机的公告
+    pub async fn list_host_advisories(&self, host_id: Uuid) -> Result<Vec<HostAdvisory>, AppError> {
+        let advisories = sqlx::query_as::<_, HostAdvisory>(
+            r#"
+            SELECT m.advisory_id, a.aliases, a.summary, a.severity, m.package_format,
+                m.package_name, m.installed_version, m.fixed_version, m.matched_at
+            FROM advisory_matches m
+            JOIN advisories a ON a.id = m.advisory_id
+            WHERE m.host_id = $1
+            ORDER BY a.modified DESC, m.package_name
+            "#,
+        )
+        .bind(host_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(advisories)
+    }
+}
@@ -1,7 +1,8 @@
 //! Asset repository (资产数据访问)
 
-use crate::{error::AppError, models::asset::*};
+use crate::{cache, error::AppError, models::asset::*};
 use sqlx::{PgPool, Row};
+use std::sync::Arc;
 use uuid::Uuid;
 
 pub struct AssetRepository {
@@ -23,8 +24,11 @@ impl AssetRepository {
     ) -> Result<AssetGroup, AppError> {
         let group = sqlx::query_as::<_, AssetGroup>(
             r#"
-            INSERT INTO assets_groups (name, description, environment, parent_id, created_by)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO assets_groups (
+                name, description, environment, parent_id, concurrent_limit, is_production,
+                is_critical, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING *
             "#,
         )
@@ -32,6 +36,9 @@ impl AssetRepository {
         .bind(&req.description)
         .bind(&req.environment)
         .bind(req.parent_id)
+        .bind(req.concurrent_limit)
+        .bind(req.is_production)
+        .bind(req.is_critical)
         .bind(created_by)
         .fetch_one(&self.db)
         .await?;
@@ -86,6 +93,12 @@ impl AssetRepository {
                 description = COALESCE($3, description),
                 environment = COALESCE($4, environment),
                 parent_id = COALESCE($5, parent_id),
+                concurrent_limit = CASE
+                    WHEN $6::int = 0 THEN NULL
+                    ELSE COALESCE($6, concurrent_limit)
+                END,
+                is_production = COALESCE($7, is_production),
+                is_critical = COALESCE($8, is_critical),
                 updated_at = NOW()
             WHERE id = $1
             RETURNING *
@@ -96,6 +109,9 @@ impl AssetRepository {
         .bind(&req.description)
         .bind(&req.environment)
         .bind(req.parent_id)
+        .bind(req.concurrent_limit)
+        .bind(req.is_production)
+        .bind(req.is_critical)
         .fetch_optional(&self.db)
         .await?;
 
@@ -109,6 +125,9 @@ impl AssetRepository {
             .execute(&self.db)
             .await?;
 
+        // 组内主机的 group_id 被置空
+        cache::HOSTS.invalidate_all();
+
         Ok(result.rows_affected() > 0)
     }
 
@@ -124,9 +143,10 @@ impl AssetRepository {
             r#"
             INSERT INTO assets_hosts (
                 identifier, display_name, address, port, group_id, environment,
-                tags, owner_id, status, notes, os_type, os_version, created_by
+                tags, owner_id, status, notes, os_type, os_version, created_by, output_encoding,
+                connection_type, connection_options, connection_token
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             RETURNING *
             "#
         )
@@ -143,6 +163,10 @@ impl AssetRepository {
         .bind(&req.os_type)
         .bind(&req.os_version)
         .bind(created_by)
+        .bind(req.output_encoding.map(|e| e.as_str()))
+        .bind(req.connection_type.as_str())
+        .bind(req.connection_options.clone().map(sqlx::types::Json))
+        .bind(&req.connection_token)
         .fetch_one(&self.db)
         .await?;
 
@@ -151,20 +175,57 @@ impl AssetRepository {
 
     /// 获取主机
     pub async fn get_host(&self, id: Uuid) -> Result<Option<Host>, AppError> {
-        let host = sqlx::query_as::<_, Host>("SELECT * FROM assets_hosts WHERE id = $1")
-            .bind(id)
-            .fetch_optional(&self.db)
-            .await?;
+        let host = sqlx::query_as::<_, Host>(
+            "SELECT * FROM assets_hosts WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await?;
 
         Ok(host)
     }
 
+    /// 按 ID 获取主机（含已删除的主机，经由缓存），用于任务执行等热点路径
+    pub async fn get_host_cached(&self, id: Uuid) -> Result<Option<Arc<Host>>, AppError> {
+        cache::HOSTS
+            .get_or_load(id, || async {
+                let host = sqlx::query_as::<_, Host>("SELECT * FROM assets_hosts WHERE id = $1")
+                    .bind(id)
+                    .fetch_optional(&self.db)
+                    .await?;
+                Ok(host.map(Arc::new))
+            })
+            .await
+    }
+
+    /// 主机是否有未结束作业中尚未完成的任务（含等待审批、等待维护的作业）
+    pub async fn host_has_unfinished_tasks(&self, id: Uuid) -> Result<bool, AppError> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM tasks t
+                JOIN jobs j ON j.id = t.job_id
+                WHERE t.host_id = $1
+                  AND t.status IN ('pending', 'running', 'waiting_maintenance')
+                  AND j.status IN ('pending', 'running', 'awaiting_approval')
+            )
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(exists)
+    }
+
     /// 根据标识符获取主机
     pub async fn get_host_by_identifier(&self, identifier: &str) -> Result<Option<Host>, AppError> {
-        let host = sqlx::query_as::<_, Host>("SELECT * FROM assets_hosts WHERE identifier = $1")
-            .bind(identifier)
-            .fetch_optional(&self.db)
-            .await?;
+        let host = sqlx::query_as::<_, Host>(
+            "SELECT * FROM assets_hosts WHERE identifier = $1 AND deleted_at IS NULL",
+        )
+        .bind(identifier)
+        .fetch_optional(&self.db)
+        .await?;
 
         Ok(host)
     }
@@ -176,7 +237,7 @@ impl AssetRepository {
         limit: i64,
         offset: i64,
     ) -> Result<Vec<Host>, AppError> {
-        let mut query = String::from("SELECT * FROM assets_hosts WHERE 1=1");
+        let mut query = String::from("SELECT * FROM assets_hosts WHERE deleted_at IS NULL");
         let mut index = 0;
 
         if filters.group_id.is_some() {
@@ -237,6 +298,18 @@ impl AssetRepository {
         Ok(hosts)
     }
 
+    /// 列出资产组内的全部主机
+    pub async fn list_group_hosts(&self, group_id: Uuid) -> Result<Vec<Host>, AppError> {
+        let hosts = sqlx::query_as::<_, Host>(
+            "SELECT * FROM assets_hosts WHERE group_id = $1 AND deleted_at IS NULL ORDER BY identifier",
+        )
+        .bind(group_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(hosts)
+    }
+
     /// 更新主机
     pub async fn update_host(
         &self,
@@ -254,6 +327,20 @@ impl AssetRepository {
             return Err(AppError::BadRequest("资源已被其他用户修改".to_string()));
         }
 
+        // 连接方式与参数与当前配置合并后校验
+        let connection_type = req
+            .connection_type
+            .unwrap_or_else(|| current.connection_kind());
+        let connection_options = req
+            .connection_options
+            .clone()
+            .or_else(|| current.connection_options.as_ref().map(|o| o.0.clone()));
+        connection_options
+            .clone()
+            .unwrap_or_default()
+            .validate(connection_type)
+            .map_err(|e| AppError::validation(&e))?;
+
         // 转换tags为Json类型
         let tags_json = req.tags.as_ref().map(|t| sqlx::types::Json(t.clone()));
 
@@ -272,9 +359,13 @@ impl AssetRepository {
                 notes = COALESCE($10, notes),
                 os_type = COALESCE($11, os_type),
                 os_version = COALESCE($12, os_version),
+                output_encoding = COALESCE($14, output_encoding),
+                connection_type = $15,
+                connection_options = $16,
+                connection_token = COALESCE($17, connection_token),
                 updated_by = $13,
                 updated_at = NOW()
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             RETURNING *
             "#,
         )
@@ -291,25 +382,125 @@ impl AssetRepository {
         .bind(&req.os_type)
         .bind(&req.os_version)
         .bind(updated_by)
+        .bind(req.output_encoding.map(|e| e.as_str()))
+        .bind(connection_type.as_str())
+        .bind(connection_options.map(sqlx::types::Json))
+        .bind(&req.connection_token)
         .fetch_optional(&self.db)
         .await?;
 
+        cache::HOSTS.invalidate(&id);
+
         Ok(host)
     }
 
-    /// 删除主机
-    pub async fn delete_host(&self, id: Uuid) -> Result<bool, AppError> {
-        let result = sqlx::query("DELETE FROM assets_hosts WHERE id = $1")
-            .bind(id)
-            .execute(&self.db)
-            .await?;
+    /// 更新主机 SSH 凭据（未提供的字段保持不变）
+    pub async fn update_host_credentials(
+        &self,
+        id: Uuid,
+        req: &UpdateHostCredentialsRequest,
+        updated_by: Uuid,
+    ) -> Result<Option<Host>, AppError> {
+        let host = sqlx::query_as::<_, Host>(
+            r#"
+            UPDATE assets_hosts
+            SET
+                ssh_username = COALESCE($2, ssh_username),
+                ssh_password = COALESCE($3, ssh_password),
+                ssh_private_key = COALESCE($4, ssh_private_key),
+                ssh_key_passphrase = COALESCE($5, ssh_key_passphrase),
+                updated_by = $6,
+                updated_at = NOW(),
+                version = version + 1
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(&req.ssh_username)
+        .bind(&req.ssh_password)
+        .bind(&req.ssh_private_key)
+        .bind(&req.ssh_key_passphrase)
+        .bind(updated_by)
+        .fetch_optional(&self.db)
+        .await?;
 
-        Ok(result.rows_affected() > 0)
+        cache::HOSTS.invalidate(&id);
+
+        Ok(host)
+    }
+
+    /// 设置主机维护（仅 active 或已在维护中的主机；重复设置时保留开始时间）
+    pub async fn set_host_maintenance(
+        &self,
+        id: Uuid,
+        req: &SetHostMaintenanceRequest,
+        set_by: Uuid,
+    ) -> Result<Option<Host>, AppError> {
+        let host = sqlx::query_as::<_, Host>(
+            r#"
+            UPDATE assets_hosts
+            SET
+                status = 'maintenance',
+                maintenance_until = $2,
+                maintenance_reason = $3,
+                maintenance_started_at = CASE
+                    WHEN status = 'maintenance' THEN COALESCE(maintenance_started_at, NOW())
+                    ELSE NOW()
+                END,
+                maintenance_set_by = $4,
+                updated_by = $4,
+                updated_at = NOW()
+            WHERE id = $1 AND status IN ('active', 'maintenance')
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(req.until)
+        .bind(&req.reason)
+        .bind(set_by)
+        .fetch_optional(&self.db)
+        .await?;
+
+        cache::HOSTS.invalidate(&id);
+
+        Ok(host)
+    }
+
+    /// 结束主机维护，恢复为 active
+    pub async fn clear_host_maintenance(
+        &self,
+        id: Uuid,
+        updated_by: Uuid,
+    ) -> Result<Option<Host>, AppError> {
+        let host = sqlx::query_as::<_, Host>(
+            r#"
+            UPDATE assets_hosts
+            SET
+                status = 'active',
+                maintenance_until = NULL,
+                maintenance_reason = NULL,
+                maintenance_started_at = NULL,
+                maintenance_set_by = NULL,
+                updated_by = $2,
+                updated_at = NOW()
+            WHERE id = $1 AND status = 'maintenance'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(updated_by)
+        .fetch_optional(&self.db)
+        .await?;
+
+        cache::HOSTS.invalidate(&id);
+
+        Ok(host)
     }
 
     /// 统计主机数量
     pub async fn count_hosts(&self, filters: &HostListFilters) -> Result<i64, AppError> {
-        let mut query = String::from("SELECT COUNT(*) FROM assets_hosts WHERE 1=1");
+        let mut query = String::from("SELECT COUNT(*) FROM assets_hosts WHERE deleted_at IS NULL");
         let mut index = 0;
 
         if filters.group_id.is_some() {
@@ -362,4 +553,101 @@ impl AssetRepository {
         let count: i64 = query_builder.fetch_one(&self.db).await?.get(0);
         Ok(count)
     }
+
+    // ==================== Packages ====================
+
+    /// 整体替换主机的软件包清单
+    pub async fn replace_host_packages(
+        &self,
+        host_id: Uuid,
+        job_id: Uuid,
+        inventory: &PackageInventory,
+    ) -> Result<(), AppError> {
+        let (mut names, mut versions, mut arches) = (Vec::new(), Vec::new(), Vec::new());
+        for package in &inventory.packages {
+            names.push(package.name.as_str());
+            versions.push(package.version.as_str());
+            arches.push(package.arch.as_str());
+        }
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query("DELETE FROM host_packages WHERE host_id = $1")
+            .bind(host_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO host_packages (host_id, name, version, arch, package_format, job_id)
+            SELECT $1, p.name, p.version, p.arch, $2, $3
+            FROM UNNEST($4::text[], $5::text[], $6::text[]) AS p(name, version, arch)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(host_id)
+        .bind(inventory.format.as_str())
+        .bind(job_id)
+        .bind(&names)
+        .bind(&versions)
+        .bind(&arches)
+        .execute(&mut *tx)
+        .await?;
+        // 采集到的主机架构写入主机信息（未采集到时保留原值）
+        if let Some(arch) = &inventory.host_arch {
+            sqlx::query("UPDATE assets_hosts SET arch = $2 WHERE id = $1")
+                .bind(host_id)
+                .bind(arch)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        cache::HOSTS.invalidate(&host_id);
+
+        Ok(())
+    }
+
+    /// 列出主机已安装的软件包
+    pub async fn list_host_packages(
+        &self,
+        host_id: Uuid,
+        search: Option<&str>,
+    ) -> Result<Vec<HostPackage>, AppError> {
+        let packages = sqlx::query_as::<_, HostPackage>(
+            r#"
+            SELECT name, version, arch, package_format, job_id, collected_at
+            FROM host_packages
+            WHERE host_id = $1 AND ($2::text IS NULL OR name ILIKE $2)
+            ORDER BY name, version, arch
+            "#,
+        )
+        .bind(host_id)
+        .bind(search.map(|s| format!("%{}%", s)))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(packages)
+    }
+
+    /// 查询安装了指定软件包的主机（版本条件由调用方按版本规则筛选）
+    pub async fn find_package_hosts(
+        &self,
+        name: &str,
+        environment: Option<&str>,
+    ) -> Result<Vec<PackageHost>, AppError> {
+        let hosts = sqlx::query_as::<_, PackageHost>(
+            r#"
+            SELECT p.host_id, h.identifier, h.address, h.environment, h.group_id,
+                p.name, p.version, p.arch, p.collected_at
+            FROM host_packages p
+            JOIN assets_hosts h ON h.id = p.host_id
+            WHERE p.name = $1 AND ($2::text IS NULL OR h.environment = $2)
+            ORDER BY h.identifier, p.version, p.arch
+            "#,
+        )
+        .bind(name)
+        .bind(environment)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(hosts)
+    }
 }
@@ -1,7 +1,7 @@
 //! Audit repository (审计数据访问)
 
 use crate::{error::AppError, models::audit::*};
-use sqlx::{PgPool, Row};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
 use uuid::Uuid;
 
 pub struct AuditRepository {
@@ -50,158 +50,112 @@ impl AuditRepository {
         Ok(())
     }
 
-    /// 查询审计日志
+    /// 查询审计日志（偏移分页）
     pub async fn query_audit_logs(
         &self,
         filters: &AuditLogFilters,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<AuditLog>, AppError> {
-        let mut query = String::from("SELECT * FROM audit_logs WHERE 1=1");
-        let mut index = 0;
-
-        if filters.subject_id.is_some() {
-            index += 1;
-            query.push_str(&format!(" AND subject_id = ${}", index));
-        }
-        if let Some(_resource_type) = &filters.resource_type {
-            index += 1;
-            query.push_str(&format!(" AND resource_type = ${}", index));
-        }
-        if filters.resource_id.is_some() {
-            index += 1;
-            query.push_str(&format!(" AND resource_id = ${}", index));
-        }
-        if let Some(_action) = &filters.action {
-            index += 1;
-            query.push_str(&format!(" AND action = ${}", index));
-        }
-        if let Some(_result) = &filters.result {
-            index += 1;
-            query.push_str(&format!(" AND result = ${}", index));
-        }
-        if filters.start_time.is_some() {
-            index += 1;
-            query.push_str(&format!(" AND occurred_at >= ${}", index));
-        }
-        if filters.end_time.is_some() {
-            index += 1;
-            query.push_str(&format!(" AND occurred_at <= ${}", index));
-        }
-        if let Some(_trace_id) = &filters.trace_id {
-            index += 1;
-            query.push_str(&format!(" AND trace_id = ${}", index));
-        }
-
-        query.push_str(&format!(
-            " ORDER BY occurred_at DESC LIMIT ${} OFFSET ${}",
-            index + 1,
-            index + 2
-        ));
+        let mut query = QueryBuilder::new("SELECT * FROM audit_logs WHERE 1=1");
+        push_audit_filters(&mut query, filters);
+        query
+            .push(" ORDER BY occurred_at DESC, id DESC LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
 
-        let mut query_builder = sqlx::query_as::<_, AuditLog>(&query);
+        let logs = query
+            .build_query_as::<AuditLog>()
+            .fetch_all(&self.db)
+            .await?;
+        Ok(logs)
+    }
 
-        if let Some(subject_id) = filters.subject_id {
-            query_builder = query_builder.bind(subject_id);
-        }
-        if let Some(resource_type) = &filters.resource_type {
-            query_builder = query_builder.bind(resource_type);
-        }
-        if let Some(resource_id) = filters.resource_id {
-            query_builder = query_builder.bind(resource_id);
-        }
-        if let Some(action) = &filters.action {
-            query_builder = query_builder.bind(action);
-        }
-        if let Some(result) = &filters.result {
-            query_builder = query_builder.bind(result);
-        }
-        if let Some(start_time) = filters.start_time {
-            query_builder = query_builder.bind(start_time);
-        }
-        if let Some(end_time) = filters.end_time {
-            query_builder = query_builder.bind(end_time);
-        }
-        if let Some(trace_id) = &filters.trace_id {
-            query_builder = query_builder.bind(trace_id);
-        }
+    /// 查询审计日志（键集分页，返回游标之后的条目）
+    ///
+    /// 与偏移分页使用相同排序，深翻页时仍能走 (occurred_at, id) 索引
+    pub async fn query_audit_logs_after(
+        &self,
+        filters: &AuditLogFilters,
+        cursor: &AuditCursor,
+        limit: i64,
+    ) -> Result<Vec<AuditLog>, AppError> {
+        let mut query = QueryBuilder::new("SELECT * FROM audit_logs WHERE 1=1");
+        push_audit_filters(&mut query, filters);
+        query
+            .push(" AND (occurred_at, id) < (")
+            .push_bind(cursor.occurred_at)
+            .push(", ")
+            .push_bind(cursor.id)
+            .push(") ORDER BY occurred_at DESC, id DESC LIMIT ")
+            .push_bind(limit);
 
-        let logs = query_builder
-            .bind(limit)
-            .bind(offset)
+        let logs = query
+            .build_query_as::<AuditLog>()
             .fetch_all(&self.db)
             .await?;
-
         Ok(logs)
     }
 
     /// 统计审计日志数量
     pub async fn count_audit_logs(&self, filters: &AuditLogFilters) -> Result<i64, AppError> {
-        let mut query = String::from("SELECT COUNT(*) FROM audit_logs WHERE 1=1");
-        let mut index = 0;
+        let mut query = QueryBuilder::new("SELECT COUNT(*) FROM audit_logs WHERE 1=1");
+        push_audit_filters(&mut query, filters);
 
-        if filters.subject_id.is_some() {
-            index += 1;
-            query.push_str(&format!(" AND subject_id = ${}", index));
-        }
-        if let Some(_resource_type) = &filters.resource_type {
-            index += 1;
-            query.push_str(&format!(" AND resource_type = ${}", index));
-        }
-        if filters.resource_id.is_some() {
-            index += 1;
-            query.push_str(&format!(" AND resource_id = ${}", index));
-        }
-        if let Some(_action) = &filters.action {
-            index += 1;
-            query.push_str(&format!(" AND action = ${}", index));
-        }
-        if let Some(_result) = &filters.result {
-            index += 1;
-            query.push_str(&format!(" AND result = ${}", index));
-        }
-        if filters.start_time.is_some() {
-            index += 1;
-            query.push_str(&format!(" AND occurred_at >= ${}", index));
-        }
-        if filters.end_time.is_some() {
-            index += 1;
-            query.push_str(&format!(" AND occurred_at <= ${}", index));
-        }
-        if let Some(_trace_id) = &filters.trace_id {
-            index += 1;
-            query.push_str(&format!(" AND trace_id = ${}", index));
-        }
+        let count: i64 = query.build().fetch_one(&self.db).await?.get(0);
+        Ok(count)
+    }
 
-        let mut query_builder = sqlx::query(&query);
+    /// 统计审计日志分面（按操作、资源类型、结果分组计数，每个分面取前 limit 项）
+    ///
+    /// 每个分面忽略自身维度的过滤条件，便于界面展示该维度的其他可选值
+    pub async fn audit_facets(
+        &self,
+        filters: &AuditLogFilters,
+        limit: i64,
+    ) -> Result<AuditFacets, AppError> {
+        let action_filters = AuditLogFilters {
+            action: None,
+            ..filters.clone()
+        };
+        let resource_type_filters = AuditLogFilters {
+            resource_type: None,
+            ..filters.clone()
+        };
+        let result_filters = AuditLogFilters {
+            result: None,
+            ..filters.clone()
+        };
 
-        if let Some(subject_id) = filters.subject_id {
-            query_builder = query_builder.bind(subject_id);
-        }
-        if let Some(resource_type) = &filters.resource_type {
-            query_builder = query_builder.bind(resource_type);
-        }
-        if let Some(resource_id) = filters.resource_id {
-            query_builder = query_builder.bind(resource_id);
-        }
-        if let Some(action) = &filters.action {
-            query_builder = query_builder.bind(action);
-        }
-        if let Some(result) = &filters.result {
-            query_builder = query_builder.bind(result);
-        }
-        if let Some(start_time) = filters.start_time {
-            query_builder = query_builder.bind(start_time);
-        }
-        if let Some(end_time) = filters.end_time {
-            query_builder = query_builder.bind(end_time);
-        }
-        if let Some(trace_id) = &filters.trace_id {
-            query_builder = query_builder.bind(trace_id);
-        }
+        Ok(AuditFacets {
+            action: self.facet_counts("action", &action_filters, limit).await?,
+            resource_type: self
+                .facet_counts("resource_type", &resource_type_filters, limit)
+                .await?,
+            result: self.facet_counts("result", &result_filters, limit).await?,
+        })
+    }
 
-        let count: i64 = query_builder.fetch_one(&self.db).await?.get(0);
-        Ok(count)
+    async fn facet_counts(
+        &self,
+        column: &'static str,
+        filters: &AuditLogFilters,
+        limit: i64,
+    ) -> Result<Vec<AuditFacetCount>, AppError> {
+        let mut query = QueryBuilder::new(format!(
+            "SELECT {column} AS value, COUNT(*) AS count FROM audit_logs WHERE 1=1"
+        ));
+        push_audit_filters(&mut query, filters);
+        query
+            .push(format!(" GROUP BY {column} ORDER BY count DESC, value LIMIT "))
+            .push_bind(limit);
+
+        let counts = query
+            .build_query_as::<AuditFacetCount>()
+            .fetch_all(&self.db)
+            .await?;
+        Ok(counts)
     }
 
     // ==================== Login Events ====================
@@ -256,4 +210,170 @@ impl AuditRepository {
 
         Ok(events)
     }
+
+    // ==================== Security Anomalies ====================
+
+    /// 查询安全异常（按发现时间倒序）
+    pub async fn query_anomalies(
+        &self,
+        filters: &SecurityAnomalyFilters,
+        limit: i64,
+    ) -> Result<Vec<SecurityAnomaly>, AppError> {
+        let mut query = QueryBuilder::new("SELECT * FROM security_anomalies WHERE 1=1");
+        if let Some(kind) = &filters.kind {
+            query.push(" AND kind = ").push_bind(kind.clone());
+        }
+        if let Some(severity) = &filters.severity {
+            query.push(" AND severity = ").push_bind(severity.clone());
+        }
+        if let Some(user_id) = filters.user_id {
+            query.push(" AND user_id = ").push_bind(user_id);
+        }
+        match filters.acknowledged {
+            Some(true) => {
+                query.push(" AND acknowledged_at IS NOT NULL");
+            }
+            Some(false) => {
+                query.push(" AND acknowledged_at IS NULL");
+            }
+            None => {}
+        }
+        if let Some(start_time) = filters.start_time {
+            query.push(" AND detected_at >= ").push_bind(start_time);
+        }
+        if let Some(end_time) = filters.end_time {
+            query.push(" AND detected_at <= ").push_bind(end_time);
+        }
+        query
+            .push(" ORDER BY detected_at DESC LIMIT ")
+            .push_bind(limit);
+
+        let anomalies = query
+            .build_query_as::<SecurityAnomaly>()
+            .fetch_all(&self.db)
+            .await?;
+        Ok(anomalies)
+    }
+
+    /// 确认安全异常，已确认或不存在时返回 None
+    pub async fn acknowledge_anomaly(
+        &self,
+        id: Uuid,
+        acknowledged_by: Uuid,
+        note: Option<&str>,
+    ) -> Result<Option<SecurityAnomaly>, AppError> {
+        let anomaly = sqlx::query_as::<_, SecurityAnomaly>(
+            r#"
+            UPDATE security_anomalies
+            SET acknowledged_at = NOW(), acknowledged_by = $2, acknowledge_note = $3
+            WHERE id = $1 AND acknowledged_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(acknowledged_by)
+        .bind(note)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(anomaly)
+    }
+}
+
+/// 审计全文检索的匹配表达式，须与 idx_audit_logs_search 的索引表达式保持一致
+const AUDIT_SEARCH_EXPR: &str = "(COALESCE(subject_name, '') || ' ' || COALESCE(resource_name, '') || ' ' || COALESCE(changes_summary, ''))";
+
+/// 追加审计日志过滤条件（查询、计数与分面共用）
+fn push_audit_filters(query: &mut QueryBuilder<'_, Postgres>, filters: &AuditLogFilters) {
+    if let Some(subject_id) = filters.subject_id {
+        query.push(" AND subject_id = ").push_bind(subject_id);
+    }
+    if let Some(resource_type) = &filters.resource_type {
+        query
+            .push(" AND resource_type = ")
+            .push_bind(resource_type.clone());
+    }
+    if let Some(resource_id) = filters.resource_id {
+        query.push(" AND resource_id = ").push_bind(resource_id);
+    }
+    if let Some(action) = &filters.action {
+        query.push(" AND action = ").push_bind(action.clone());
+    }
+    if let Some(prefix) = &filters.action_prefix {
+        query
+            .push(" AND action LIKE ")
+            .push_bind(format!("{}.%", escape_like(prefix.trim_end_matches('.'))));
+    }
+    if let Some(result) = &filters.result {
+        query.push(" AND result = ").push_bind(result.clone());
+    }
+    if let Some(source_ip) = &filters.source_ip {
+        query.push(" AND source_ip = ").push_bind(source_ip.clone());
+    }
+    if let Some(start_time) = filters.start_time {
+        query.push(" AND occurred_at >= ").push_bind(start_time);
+    }
+    if let Some(end_time) = filters.end_time {
+        query.push(" AND occurred_at <= ").push_bind(end_time);
+    }
+    if let Some(trace_id) = &filters.trace_id {
+        query.push(" AND trace_id = ").push_bind(trace_id.clone());
+    }
+    if let Some(search) = filters
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        query
+            .push(format!(" AND {AUDIT_SEARCH_EXPR} ILIKE "))
+            .push_bind(format!("%{}%", escape_like(search)));
+    }
+}
+
+/// 转义 LIKE 模式中的通配符
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_audit_filters() {
+        let filters = AuditLogFilters {
+            action_prefix: Some("job.".to_string()),
+            source_ip: Some("10.0.0.1".to_string()),
+            search: Some("deploy".to_string()),
+            ..Default::default()
+        };
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM audit_logs WHERE 1=1");
+        push_audit_filters(&mut query, &filters);
+        assert_eq!(
+            query.sql(),
+            format!(
+                "SELECT * FROM audit_logs WHERE 1=1 AND action LIKE $1 AND source_ip = $2 AND {} ILIKE $3",
+                AUDIT_SEARCH_EXPR
+            )
+        );
+
+        // 空白搜索词不产生条件
+        let filters = AuditLogFilters {
+            search: Some("  ".to_string()),
+            ..Default::default()
+        };
+        let mut query = QueryBuilder::<Postgres>::new("SELECT 1 WHERE 1=1");
+        push_audit_filters(&mut query, &filters);
+        assert_eq!(query.sql(), "SELECT 1 WHERE 1=1");
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("100%_done\\"), "100\\%\\_done\\\\");
+        assert_eq!(escape_like("plain"), "plain");
+    }
 }
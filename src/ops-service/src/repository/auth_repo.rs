@@ -1,6 +1,9 @@
 //! Authentication repository (认证数据访问)
 
-use crate::{error::AppError, models::audit::*};
+use crate::{
+    error::AppError,
+    models::{audit::*, auth::UserTotp},
+};
 use sha2::{Digest, Sha256};
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
@@ -21,9 +24,10 @@ impl AuthRepository {
         sqlx::query(
             r#"
             INSERT INTO refresh_tokens (
-                id, token_hash, user_id, device_id, user_agent, ip_address, expires_at, created_at
+                id, token_hash, user_id, device_id, user_agent, ip_address, expires_at, created_at,
+                session_id
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             "#,
         )
         .bind(token.id)
@@ -34,6 +38,7 @@ impl AuthRepository {
         .bind(&token.ip_address)
         .bind(token.expires_at)
         .bind(token.created_at)
+        .bind(token.session_id)
         .execute(&self.db)
         .await?;
 
@@ -104,6 +109,499 @@ impl AuthRepository {
         Ok(result.rows_affected())
     }
 
+    // ==================== Login Sessions ====================
+
+    /// 创建登录会话
+    pub async fn create_user_session(
+        &self,
+        session: &UserSession,
+    ) -> Result<UserSession, AppError> {
+        let session = sqlx::query_as::<_, UserSession>(
+            r#"
+            INSERT INTO user_sessions (
+                id, user_id, device_id, user_agent, ip_address, created_at, last_seen_at,
+                last_seen_ip, expires_at, step_up_until
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING *
+            "#,
+        )
+        .bind(session.id)
+        .bind(session.user_id)
+        .bind(&session.device_id)
+        .bind(&session.user_agent)
+        .bind(&session.ip_address)
+        .bind(session.created_at)
+        .bind(session.last_seen_at)
+        .bind(&session.last_seen_ip)
+        .bind(session.expires_at)
+        .bind(session.step_up_until)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// 查找登录会话
+    pub async fn find_user_session(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Option<UserSession>, AppError> {
+        let session = sqlx::query_as::<_, UserSession>("SELECT * FROM user_sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_optional(&self.db)
+            .await?;
+
+        Ok(session)
+    }
+
+    /// 列出用户未撤销且未过期的登录会话（最近活跃在前）
+    pub async fn list_active_user_sessions(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<UserSession>, AppError> {
+        let sessions = sqlx::query_as::<_, UserSession>(
+            r#"
+            SELECT * FROM user_sessions
+            WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            ORDER BY last_seen_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// 刷新令牌时记录会话活跃时间并顺延有效期
+    pub async fn touch_user_session(
+        &self,
+        session_id: Uuid,
+        ip_address: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET last_seen_at = NOW(), last_seen_ip = $2, expires_at = $3
+            WHERE id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(session_id)
+        .bind(ip_address)
+        .bind(expires_at)
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 撤销登录会话及其刷新令牌，会话已撤销或不属于该用户时返回 None
+    pub async fn revoke_user_session(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        revoked_by: Uuid,
+        reason: &str,
+    ) -> Result<Option<UserSession>, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        let session = sqlx::query_as::<_, UserSession>(
+            r#"
+            UPDATE user_sessions
+            SET revoked_at = NOW(), revoked_by = $3, revoke_reason = $4
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(revoked_by)
+        .bind(reason)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if session.is_some() {
+            sqlx::query(
+                r#"
+                UPDATE refresh_tokens SET revoked_at = NOW()
+                WHERE session_id = $1 AND revoked_at IS NULL
+                "#,
+            )
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(session)
+    }
+
+    /// 撤销用户的全部登录会话，返回被撤销的会话 ID
+    pub async fn revoke_all_user_sessions(
+        &self,
+        user_id: Uuid,
+        revoked_by: Uuid,
+        reason: &str,
+    ) -> Result<Vec<Uuid>, AppError> {
+        let session_ids: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            UPDATE user_sessions
+            SET revoked_at = NOW(), revoked_by = $2, revoke_reason = $3
+            WHERE user_id = $1 AND revoked_at IS NULL
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(revoked_by)
+        .bind(reason)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(session_ids)
+    }
+
+    /// 登录会话是否仍然有效
+    pub async fn is_user_session_active(&self, session_id: Uuid) -> Result<bool, AppError> {
+        let active: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM user_sessions
+                WHERE id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            )
+            "#,
+        )
+        .bind(session_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(active)
+    }
+
+    /// 记录会话的二次验证有效期
+    pub async fn set_user_session_step_up(
+        &self,
+        session_id: Uuid,
+        user_id: Uuid,
+        step_up_until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_sessions SET step_up_until = $3
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(step_up_until)
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ==================== Two-Factor ====================
+
+    /// 查找用户的 TOTP 密钥
+    pub async fn find_user_totp(&self, user_id: Uuid) -> Result<Option<UserTotp>, AppError> {
+        let totp = sqlx::query_as::<_, UserTotp>("SELECT * FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.db)
+            .await?;
+
+        Ok(totp)
+    }
+
+    /// 列出全部 TOTP 密钥
+    pub async fn list_user_totp(&self) -> Result<Vec<UserTotp>, AppError> {
+        let totps = sqlx::query_as::<_, UserTotp>("SELECT * FROM user_totp")
+            .fetch_all(&self.db)
+            .await?;
+
+        Ok(totps)
+    }
+
+    /// 替换 TOTP 密钥的存储值（仍为旧值时才替换），返回是否替换
+    pub async fn replace_totp_secret(
+        &self,
+        user_id: Uuid,
+        current: &str,
+        secret: &str,
+    ) -> Result<bool, AppError> {
+        let result =
+            sqlx::query("UPDATE user_totp SET secret = $3 WHERE user_id = $1 AND secret = $2")
+                .bind(user_id)
+                .bind(current)
+                .bind(secret)
+                .execute(&self.db)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 保存待确认的 TOTP 密钥（覆盖未确认的旧密钥），已启用时返回 false
+    pub async fn upsert_pending_totp(&self, user_id: Uuid, secret: &str) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO user_totp (user_id, secret)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = NOW(),
+                updated_at = NOW()
+            WHERE user_totp.enabled_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(secret)
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 确认绑定：启用 TOTP 并替换恢复码，已启用或未发起绑定时返回 None
+    pub async fn enable_user_totp(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<Option<UserTotp>, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        let totp = sqlx::query_as::<_, UserTotp>(
+            r#"
+            UPDATE user_totp
+            SET enabled_at = NOW(), last_used_step = $2, updated_at = NOW()
+            WHERE user_id = $1 AND enabled_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if totp.is_some() {
+            sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                r#"
+                INSERT INTO user_recovery_codes (user_id, code_hash)
+                SELECT $1, UNNEST($2::VARCHAR[])
+                "#,
+            )
+            .bind(user_id)
+            .bind(recovery_code_hashes)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(totp)
+    }
+
+    /// 记录通过校验的时间步；该时间步不晚于上次使用的时间步时返回 false（防重放）
+    pub async fn record_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_totp SET last_used_step = $2, updated_at = NOW()
+            WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 删除用户的 TOTP 密钥与恢复码，返回是否存在密钥
+    pub async fn delete_user_totp(&self, user_id: Uuid) -> Result<bool, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        let result = sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 替换用户的恢复码（旧恢复码全部作废）
+    pub async fn replace_recovery_codes(
+        &self,
+        user_id: Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), AppError> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO user_recovery_codes (user_id, code_hash)
+            SELECT $1, UNNEST($2::VARCHAR[])
+            "#,
+        )
+        .bind(user_id)
+        .bind(recovery_code_hashes)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 使用恢复码（每个只能使用一次），没有匹配的可用恢复码时返回 false
+    pub async fn consume_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &str,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_recovery_codes SET used_at = NOW()
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 统计未使用的恢复码
+    pub async fn count_unused_recovery_codes(&self, user_id: Uuid) -> Result<i64, AppError> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(count)
+    }
+
+    // ==================== Impersonation Sessions ====================
+
+    /// 创建模拟会话
+    pub async fn create_impersonation_session(
+        &self,
+        session: &ImpersonationSession,
+    ) -> Result<ImpersonationSession, AppError> {
+        let session = sqlx::query_as::<_, ImpersonationSession>(
+            r#"
+            INSERT INTO impersonation_sessions (
+                id, impersonator_id, impersonator_name, target_user_id, target_username,
+                reason, source_ip, user_agent, created_at, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING *
+            "#,
+        )
+        .bind(session.id)
+        .bind(session.impersonator_id)
+        .bind(&session.impersonator_name)
+        .bind(session.target_user_id)
+        .bind(&session.target_username)
+        .bind(&session.reason)
+        .bind(&session.source_ip)
+        .bind(&session.user_agent)
+        .bind(session.created_at)
+        .bind(session.expires_at)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// 查找模拟会话
+    pub async fn find_impersonation_session(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Option<ImpersonationSession>, AppError> {
+        let session = sqlx::query_as::<_, ImpersonationSession>(
+            "SELECT * FROM impersonation_sessions WHERE id = $1",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// 列出未终止且未过期的模拟会话
+    pub async fn list_active_impersonation_sessions(
+        &self,
+    ) -> Result<Vec<ImpersonationSession>, AppError> {
+        let sessions = sqlx::query_as::<_, ImpersonationSession>(
+            r#"
+            SELECT * FROM impersonation_sessions
+            WHERE terminated_at IS NULL AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// 终止模拟会话，会话已终止或已过期时返回 None
+    pub async fn terminate_impersonation_session(
+        &self,
+        session_id: Uuid,
+        terminated_by: Uuid,
+    ) -> Result<Option<ImpersonationSession>, AppError> {
+        let session = sqlx::query_as::<_, ImpersonationSession>(
+            r#"
+            UPDATE impersonation_sessions
+            SET terminated_at = NOW(), terminated_by = $2
+            WHERE id = $1 AND terminated_at IS NULL AND expires_at > NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(session_id)
+        .bind(terminated_by)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// 模拟会话是否仍然有效
+    pub async fn is_impersonation_session_active(
+        &self,
+        session_id: Uuid,
+    ) -> Result<bool, AppError> {
+        let active: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM impersonation_sessions
+                WHERE id = $1 AND terminated_at IS NULL AND expires_at > NOW()
+            )
+            "#,
+        )
+        .bind(session_id)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(active)
+    }
+
     // ==================== Login Events ====================
 
     /// 记录登录事件
@@ -1,13 +1,17 @@
 //! Database repository layer
 
+pub mod advisory_repo;
 pub mod asset_repo;
 pub mod audit_repo;
 pub mod auth_repo;
 pub mod role_repo;
+pub mod runner_repo;
 pub mod user_repo;
 
+pub use advisory_repo::*;
 pub use asset_repo::*;
 pub use audit_repo::*;
 pub use auth_repo::*;
 pub use role_repo::*;
+pub use runner_repo::*;
 pub use user_repo::*;
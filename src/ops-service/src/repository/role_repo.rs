@@ -1,6 +1,6 @@
 //! Role repository (角色数据访问)
 
-use crate::{error::AppError, models::role::*};
+use crate::{cache, error::AppError, models::role::*};
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 
@@ -102,6 +102,10 @@ impl RoleRepository {
             .execute(&self.db)
             .await?;
 
+        // 角色绑定随角色级联删除，无法确定受影响的用户
+        cache::ROLE_PERMISSIONS.invalidate(&id);
+        cache::ROLE_BINDINGS.invalidate_all();
+
         Ok(result.rows_affected() > 0)
     }
 
@@ -174,6 +178,8 @@ impl RoleRepository {
         .execute(&self.db)
         .await?;
 
+        cache::ROLE_PERMISSIONS.invalidate(&role_id);
+
         Ok(())
     }
 
@@ -190,6 +196,8 @@ impl RoleRepository {
                 .execute(&self.db)
                 .await?;
 
+        cache::ROLE_PERMISSIONS.invalidate(&role_id);
+
         Ok(result.rows_affected() > 0)
     }
 
@@ -246,17 +254,25 @@ impl RoleRepository {
         .fetch_one(&self.db)
         .await?;
 
+        cache::ROLE_BINDINGS.invalidate(&user_id);
+
         Ok(binding)
     }
 
     /// 撤销用户的角色
     pub async fn revoke_role_from_user(&self, binding_id: Uuid) -> Result<bool, AppError> {
-        let result = sqlx::query("DELETE FROM role_bindings WHERE id = $1")
-            .bind(binding_id)
-            .execute(&self.db)
-            .await?;
+        let user_id = sqlx::query_scalar::<_, Uuid>(
+            "DELETE FROM role_bindings WHERE id = $1 RETURNING user_id",
+        )
+        .bind(binding_id)
+        .fetch_optional(&self.db)
+        .await?;
 
-        Ok(result.rows_affected() > 0)
+        if let Some(user_id) = user_id {
+            cache::ROLE_BINDINGS.invalidate(&user_id);
+        }
+
+        Ok(user_id.is_some())
     }
 
     /// 撤销用户的所有角色绑定
@@ -266,6 +282,8 @@ impl RoleRepository {
             .execute(&self.db)
             .await?;
 
+        cache::ROLE_BINDINGS.invalidate(&user_id);
+
         Ok(result.rows_affected())
     }
 }
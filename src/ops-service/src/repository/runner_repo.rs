@@ -0,0 +1,293 @@
+//! Runner repository (Runner 凭据与注册令牌数据访问)
+
+use crate::{
+    error::AppError,
+    models::build::{RunnerCommand, RunnerEnrollmentToken},
+};
+use chrono::{DateTime, Utc};
+use common::RunnerCommandAck;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct RunnerRepository {
+    db: PgPool,
+}
+
+impl RunnerRepository {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    // ==================== Enrollment Tokens ====================
+
+    /// 创建注册令牌
+    pub async fn create_enrollment_token(
+        &self,
+        token_hash: &str,
+        runner_name: &str,
+        labels: &[String],
+        created_by: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<RunnerEnrollmentToken, AppError> {
+        let token = sqlx::query_as::<_, RunnerEnrollmentToken>(
+            r#"
+            INSERT INTO runner_enrollment_tokens (
+                token_hash, runner_name, labels, created_by, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(token_hash)
+        .bind(runner_name)
+        .bind(sqlx::types::Json(labels))
+        .bind(created_by)
+        .bind(expires_at)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// 列出未使用、未撤销且未过期的注册令牌
+    pub async fn list_pending_enrollment_tokens(
+        &self,
+    ) -> Result<Vec<RunnerEnrollmentToken>, AppError> {
+        let tokens = sqlx::query_as::<_, RunnerEnrollmentToken>(
+            r#"
+            SELECT * FROM runner_enrollment_tokens
+            WHERE used_at IS NULL AND revoked_at IS NULL AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    /// 撤销未使用的注册令牌
+    pub async fn revoke_enrollment_token(&self, token_id: Uuid) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            "UPDATE runner_enrollment_tokens SET revoked_at = NOW() WHERE id = $1 AND used_at IS NULL AND revoked_at IS NULL",
+        )
+        .bind(token_id)
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 使用注册令牌登记 Runner 并设置其专属凭据
+    ///
+    /// 令牌在同一事务中加锁并标记为已使用，保证只能成功使用一次；
+    /// `validate` 返回错误时事务回滚，令牌保持可用。同名 Runner 已存在时轮换其凭据
+    pub async fn enroll_runner<F>(
+        &self,
+        token_hash: &str,
+        capabilities: &[String],
+        api_key_hash: &str,
+        validate: F,
+    ) -> Result<(RunnerEnrollmentToken, Uuid), AppError>
+    where
+        F: FnOnce(&RunnerEnrollmentToken) -> Result<(), AppError>,
+    {
+        let mut tx = self.db.begin().await?;
+
+        let token = sqlx::query_as::<_, RunnerEnrollmentToken>(
+            r#"
+            SELECT * FROM runner_enrollment_tokens
+            WHERE token_hash = $1 AND used_at IS NULL AND revoked_at IS NULL AND expires_at > NOW()
+            FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::authentication("Invalid or expired enrollment token"))?;
+
+        validate(&token)?;
+
+        let runner_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO runners (name, capabilities, api_key_hash)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name) DO UPDATE
+            SET api_key_hash = EXCLUDED.api_key_hash, updated_at = NOW()
+            RETURNING id
+            "#,
+        )
+        .bind(&token.runner_name)
+        .bind(sqlx::types::Json(capabilities))
+        .bind(api_key_hash)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let token = sqlx::query_as::<_, RunnerEnrollmentToken>(
+            r#"
+            UPDATE runner_enrollment_tokens
+            SET used_at = NOW(), used_by_runner_id = $2
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(token.id)
+        .bind(runner_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok((token, runner_id))
+    }
+
+    // ==================== Runner Commands ====================
+
+    /// 登记已下发的控制指令
+    pub async fn create_command(
+        &self,
+        runner_id: Uuid,
+        action: &str,
+        requested_by: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<RunnerCommand, AppError> {
+        let command = sqlx::query_as::<_, RunnerCommand>(
+            r#"
+            INSERT INTO runner_commands (runner_id, action, requested_by, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(runner_id)
+        .bind(action)
+        .bind(requested_by)
+        .bind(expires_at)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(command)
+    }
+
+    /// 指令未能下发时直接标记失败
+    pub async fn fail_command(
+        &self,
+        command_id: Uuid,
+        message: &str,
+    ) -> Result<RunnerCommand, AppError> {
+        let command = sqlx::query_as::<_, RunnerCommand>(
+            r#"
+            UPDATE runner_commands SET status = 'failed', result_message = $2
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(command_id)
+        .bind(message)
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(command)
+    }
+
+    /// 将超时未回报的指令标记为 timed_out
+    pub async fn expire_commands(&self) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE runner_commands
+            SET status = 'timed_out',
+                result_message = 'Runner did not acknowledge the command before timeout'
+            WHERE status = 'pending' AND expires_at <= NOW()
+            "#,
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 记录 Runner 回报的执行结果，返回更新的指令数
+    ///
+    /// 只更新本 Runner 仍在等待回报的指令，超时后到达的结果被忽略
+    pub async fn acknowledge_commands(
+        &self,
+        runner_id: Uuid,
+        acks: &[RunnerCommandAck],
+    ) -> Result<u64, AppError> {
+        let mut updated = 0;
+        for ack in acks {
+            let result = sqlx::query(
+                r#"
+                UPDATE runner_commands
+                SET status = $3, result_message = $4, acknowledged_at = $5
+                WHERE id = $1 AND runner_id = $2 AND status = 'pending' AND expires_at > NOW()
+                "#,
+            )
+            .bind(ack.command_id)
+            .bind(runner_id)
+            .bind(if ack.success { "succeeded" } else { "failed" })
+            .bind(&ack.message)
+            .bind(ack.timestamp)
+            .execute(&self.db)
+            .await?;
+            updated += result.rows_affected();
+        }
+
+        Ok(updated)
+    }
+
+    /// 列出 Runner 最近的控制指令
+    pub async fn list_commands(
+        &self,
+        runner_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<RunnerCommand>, AppError> {
+        let commands = sqlx::query_as::<_, RunnerCommand>(
+            r#"
+            SELECT * FROM runner_commands
+            WHERE runner_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(runner_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(commands)
+    }
+
+    /// 获取 Runner 的单条控制指令
+    pub async fn get_command(
+        &self,
+        runner_id: Uuid,
+        command_id: Uuid,
+    ) -> Result<Option<RunnerCommand>, AppError> {
+        let command = sqlx::query_as::<_, RunnerCommand>(
+            "SELECT * FROM runner_commands WHERE id = $1 AND runner_id = $2",
+        )
+        .bind(command_id)
+        .bind(runner_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(command)
+    }
+
+    // ==================== Runner Credentials ====================
+
+    /// 根据专属凭据哈希查找 Runner，返回 (id, name)
+    pub async fn find_by_api_key_hash(
+        &self,
+        api_key_hash: &str,
+    ) -> Result<Option<(Uuid, String)>, AppError> {
+        let runner = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, name FROM runners WHERE api_key_hash = $1",
+        )
+        .bind(api_key_hash)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(runner)
+    }
+}
@@ -15,20 +15,23 @@ impl UserRepository {
 
     /// 根据用户名查找用户
     pub async fn find_by_username(&self, username: &str) -> Result<Option<User>, AppError> {
-        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
-            .bind(username)
-            .fetch_optional(&self.db)
-            .await?;
+        let user = sqlx::query_as::<_, User>(
+            "SELECT * FROM users WHERE username = $1 AND deleted_at IS NULL",
+        )
+        .bind(username)
+        .fetch_optional(&self.db)
+        .await?;
 
         Ok(user)
     }
 
     /// 根据 ID 查找用户
     pub async fn find_by_id(&self, id: &Uuid) -> Result<Option<User>, AppError> {
-        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
-            .bind(id)
-            .fetch_optional(&self.db)
-            .await?;
+        let user =
+            sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 AND deleted_at IS NULL")
+                .bind(id)
+                .fetch_optional(&self.db)
+                .await?;
 
         Ok(user)
     }
@@ -74,7 +77,7 @@ impl UserRepository {
                 department = COALESCE($4, department),
                 status = COALESCE($5, status),
                 updated_at = NOW()
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             RETURNING *
             "#,
         )
@@ -118,10 +121,11 @@ impl UserRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    /// 删除用户
-    pub async fn delete(&self, id: Uuid) -> Result<bool, AppError> {
-        let result = sqlx::query("DELETE FROM users WHERE id = $1")
+    /// 更新语言偏好
+    pub async fn update_locale(&self, id: Uuid, locale: Option<&str>) -> Result<bool, AppError> {
+        let result = sqlx::query("UPDATE users SET locale = $2, updated_at = NOW() WHERE id = $1")
             .bind(id)
+            .bind(locale)
             .execute(&self.db)
             .await?;
 
@@ -217,7 +221,7 @@ impl UserRepository {
     /// 列出所有用户
     pub async fn list(&self, limit: i64, offset: i64) -> Result<Vec<User>, AppError> {
         let users = sqlx::query_as::<_, User>(
-            "SELECT * FROM users ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+            "SELECT * FROM users WHERE deleted_at IS NULL ORDER BY created_at DESC LIMIT $1 OFFSET $2",
         )
         .bind(limit)
         .bind(offset)
@@ -229,7 +233,7 @@ impl UserRepository {
 
     /// 统计用户数量
     pub async fn count(&self) -> Result<i64, AppError> {
-        let count: i64 = sqlx::query("SELECT COUNT(*) FROM users")
+        let count: i64 = sqlx::query("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL")
             .fetch_one(&self.db)
             .await?
             .get(0);
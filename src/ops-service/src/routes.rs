@@ -20,34 +20,31 @@ use crate::middleware::runner_auth_middleware;
 /// 创建应用路由
 /// state 由 main 统一装配，包含所有服务实例
 pub fn create_router(state: Arc<AppState>) -> Router {
-    // 公开端点（健康检查）
+    // 公开端点（健康检查、错误码目录、JWKS）
     let public_routes = Router::new()
         .route("/health", get(handlers::health::health_check))
         .route("/ready", get(handlers::health::readiness_check))
-        .route("/api/v1/system/concurrency", get(handlers::health::get_concurrency_status));
+        .route("/readyz", get(handlers::health::readiness_check))
+        .route("/api/v1/system/concurrency", get(handlers::health::get_concurrency_status))
+        .route("/api/errors", get(handlers::error_catalog::list_error_codes))
+        .route("/.well-known/jwks.json", get(handlers::auth::jwks))
+        // 证据包限时下载（链接签名即授权）
+        .route(
+            "/api/v1/evidence-exports/{id}/download",
+            get(handlers::evidence::download_evidence)
+        );
 
     // Runner Webhook 路由（使用 Runner API Key 鉴权）
     let runner_routes = Router::new()
-        .route(
-            "/api/v1/runners/register",
-            post(handlers::runner::register_runner)
-        )
-        .route(
-            "/api/v1/webhooks/runner/register",
-            post(handlers::runner::register_runner)
-        )
-        .route(
-            "/api/v1/runners/heartbeat",
-            post(handlers::runner::runner_heartbeat)
-        )
-        .route(
-            "/api/v1/webhooks/runner/heartbeat",
-            post(handlers::runner::runner_heartbeat)
-        )
+        .route("/api/v1/runners/register", post(handlers::runner::register_runner))
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
-            runner_auth_middleware,
-        ));
+            crate::middleware::maintenance::maintenance_middleware,
+        ))
+        .route("/api/v1/webhooks/runner/register", post(handlers::runner::register_runner))
+        .route("/api/v1/runners/heartbeat", post(handlers::runner::runner_heartbeat))
+        .route("/api/v1/webhooks/runner/heartbeat", post(handlers::runner::runner_heartbeat))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), runner_auth_middleware));
 
     // 构建 Webhook 路由（使用 HMAC 签名鉴权）
     let webhook_routes = Router::new()
@@ -55,14 +52,15 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             "/api/v1/webhooks/build/status",
             post(handlers::build_webhook::build_status_webhook)
         )
-        .route(
-            "/api/v1/webhooks/build/log",
-            post(handlers::build_webhook::build_log_webhook)
-        )
+        .route("/api/v1/webhooks/build/log", post(handlers::build_webhook::build_log_webhook))
         .route(
             "/api/v1/webhooks/build/artifact",
             post(handlers::build_webhook::build_artifact_webhook)
         )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::maintenance::maintenance_middleware,
+        ))
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
             crate::middleware::webhook_hmac::build_webhook_hmac_middleware,
@@ -71,14 +69,50 @@ pub fn create_router(state: Arc<AppState>) -> Router {
     // 认证路由（无需认证，但应用速率限制）
     let auth_routes = Router::new()
         .route("/api/v1/auth/login", post(handlers::auth::login))
-        .route("/api/v1/auth/refresh", post(handlers::auth::refresh_token));
+        .route("/api/v1/auth/refresh", post(handlers::auth::refresh_token))
+        // Runner 注册令牌兑换（令牌本身即凭证）
+        .route("/api/v1/runners/enroll", post(handlers::runner::enroll_runner))
+        // 入站 Webhook 触发（Webhook 专属令牌鉴权）
+        .route(
+            "/api/v1/webhooks/inbound/{id}",
+            post(handlers::inbound_webhook::trigger_inbound_webhook)
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::maintenance::maintenance_middleware,
+        ));
 
     // 需要认证的路由
     let authenticated_routes = Router::new()
         // 当前用户信息
         .route("/api/v1/auth/me", get(handlers::auth::get_current_user))
+        // 用户模拟
+        .route("/api/v1/auth/impersonate", post(handlers::auth::start_impersonation))
+        .route(
+            "/api/v1/auth/impersonations",
+            get(handlers::auth::list_impersonation_sessions)
+        )
+        .route(
+            "/api/v1/auth/impersonations/{id}/terminate",
+            post(handlers::auth::terminate_impersonation_session)
+        )
         .route("/api/v1/auth/logout", post(handlers::auth::logout))
         .route("/api/v1/auth/logout-all", post(handlers::auth::logout_all))
+        .route("/api/v1/auth/sessions", get(handlers::auth::list_my_sessions))
+        .route("/api/v1/auth/sessions/{id}", delete(handlers::auth::revoke_my_session))
+        // 双因素认证（TOTP）
+        .route("/api/v1/auth/2fa", get(handlers::auth::get_two_factor_status))
+        .route("/api/v1/auth/2fa/enroll", post(handlers::auth::begin_two_factor_enrollment))
+        .route(
+            "/api/v1/auth/2fa/enroll/confirm",
+            post(handlers::auth::confirm_two_factor_enrollment)
+        )
+        .route("/api/v1/auth/2fa/disable", post(handlers::auth::disable_two_factor))
+        .route(
+            "/api/v1/auth/2fa/recovery-codes",
+            post(handlers::auth::regenerate_recovery_codes)
+        )
+        .route("/api/v1/auth/2fa/verify", post(handlers::auth::verify_two_factor))
 
         // 用户管理（需要权限）
         .route(
@@ -92,7 +126,25 @@ pub fn create_router(state: Arc<AppState>) -> Router {
                 .put(handlers::user::update_user)
                 .delete(handlers::user::delete_user)
         )
+        .route(
+            "/api/v1/users/{id}/sessions",
+            get(handlers::user::list_user_sessions)
+                .delete(handlers::user::revoke_user_sessions)
+        )
+        .route("/api/v1/users/{id}/two-factor", delete(handlers::user::reset_user_two_factor))
+        .route("/api/v1/users/deleted", get(handlers::user::list_deleted_users))
+        .route("/api/v1/users/{id}/restore", post(handlers::user::restore_user))
         .route("/api/v1/users/me/password", put(handlers::user::change_password))
+        .route("/api/v1/users/me/locale", put(handlers::user::update_my_locale))
+        .route(
+            "/api/v1/users/me/watches",
+            get(handlers::watch::list_watches)
+                .post(handlers::watch::create_watch)
+        )
+        .route(
+            "/api/v1/users/me/watches/{id}",
+            delete(handlers::watch::delete_watch)
+        )
 
         // 资产组
         .route(
@@ -106,6 +158,27 @@ pub fn create_router(state: Arc<AppState>) -> Router {
                 .put(handlers::asset::update_group)
                 .delete(handlers::asset::delete_group)
         )
+        .route(
+            "/api/v1/groups/{id}/test-connections",
+            post(handlers::asset::test_group_connections)
+        )
+        .route(
+            "/api/v1/groups/{id}/connection-tests",
+            get(handlers::asset::list_group_connection_tests)
+        )
+        .route(
+            "/api/v1/connection-tests/{id}",
+            get(handlers::asset::get_connection_test_run)
+        )
+        .route(
+            "/api/v1/credential-rotations",
+            post(handlers::asset::start_credential_rotation)
+                .get(handlers::asset::list_credential_rotations)
+        )
+        .route(
+            "/api/v1/credential-rotations/{id}",
+            get(handlers::asset::get_credential_rotation)
+        )
 
         // 主机
         .route(
@@ -119,6 +192,74 @@ pub fn create_router(state: Arc<AppState>) -> Router {
                 .put(handlers::asset::update_host)
                 .delete(handlers::asset::delete_host)
         )
+        .route(
+            "/api/v1/hosts/deleted",
+            get(handlers::asset::list_deleted_hosts)
+        )
+        .route(
+            "/api/v1/hosts/{id}/restore",
+            post(handlers::asset::restore_host)
+        )
+        .route(
+            "/api/v1/hosts/{id}/credentials",
+            put(handlers::asset::update_host_credentials)
+        )
+        .route(
+            "/api/v1/hosts/{id}/maintenance",
+            post(handlers::asset::set_host_maintenance)
+                .delete(handlers::asset::clear_host_maintenance)
+        )
+        .route(
+            "/api/v1/hosts/{id}/test-connection",
+            post(handlers::asset::test_host_connection)
+        )
+        .route(
+            "/api/v1/hosts/{id}/packages",
+            get(handlers::asset::list_host_packages)
+        )
+        .route(
+            "/api/v1/hosts/{id}/advisories",
+            get(handlers::advisory::list_host_advisories)
+        )
+
+        // 软件包清单（跨主机按版本查询）
+        .route(
+            "/api/v1/packages/hosts",
+            get(handlers::asset::find_package_hosts)
+        )
+
+        // 安全公告（OSV 导入与受影响主机匹配）
+        .route(
+            "/api/v1/advisories",
+            get(handlers::advisory::list_advisories)
+        )
+        .route(
+            "/api/v1/advisories/import",
+            post(handlers::advisory::import_advisories)
+        )
+        .route(
+            "/api/v1/advisories/{id}/hosts",
+            get(handlers::advisory::list_advisory_hosts)
+        )
+        .route(
+            "/api/v1/advisories/{id}/remediation",
+            get(handlers::advisory::get_remediation_drafts)
+                .post(handlers::advisory::create_remediation_job)
+        )
+
+        // SSH 主机密钥（仅管理员）
+        .route(
+            "/api/v1/ssh/host-key-failures",
+            get(handlers::asset::list_host_key_failures)
+        )
+        .route(
+            "/api/v1/ssh/host-key-failures/{id}/repin",
+            post(handlers::asset::repin_host_key)
+        )
+        .route(
+            "/api/v1/ssh/known-hosts",
+            get(handlers::asset::list_known_hosts)
+        )
 
         // 作业管理
         .route(
@@ -133,6 +274,14 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             "/api/v1/jobs/script",
             post(handlers::job::create_script_job)
         )
+        .route(
+            "/api/v1/jobs/file",
+            post(handlers::job::create_file_job)
+        )
+        .route(
+            "/api/v1/jobs/inventory",
+            post(handlers::job::create_inventory_job)
+        )
         .route(
             "/api/v1/jobs/{id}",
             get(handlers::job::get_job)
@@ -149,14 +298,138 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             "/api/v1/jobs/{id}/retry",
             post(handlers::job::retry_job)
         )
+        .route(
+            "/api/v1/jobs/{id}/dispatch/resolve",
+            post(handlers::job::resolve_dispatch_pause)
+        )
+        .route(
+            "/api/v1/jobs/{id}/tasks/cancel",
+            post(handlers::job::cancel_tasks)
+        )
+        .route(
+            "/api/v1/jobs/{id}/tasks/skip",
+            post(handlers::job::skip_tasks)
+        )
+        .route(
+            "/api/v1/jobs/{id}/tasks/rerun",
+            post(handlers::job::rerun_tasks)
+        )
         .route(
             "/api/v1/jobs/{id}/statistics",
             get(handlers::job::get_job_statistics)
         )
+        .route(
+            "/api/v1/jobs/{id}/events",
+            get(handlers::job::get_job_events)
+        )
+        .route(
+            "/api/v1/jobs/{id}/trace",
+            get(handlers::job::get_job_trace)
+        )
+        .route(
+            "/api/v1/jobs/{id}/chain",
+            get(handlers::job::get_job_chain)
+        )
+        .route(
+            "/api/v1/jobs/{id}/result-groups",
+            get(handlers::job::get_result_host_groups)
+        )
+        .route(
+            "/api/v1/jobs/{id}/target-sets",
+            post(handlers::job::create_target_set)
+        )
+        .route(
+            "/api/v1/target-sets/{id}",
+            get(handlers::job::get_target_set)
+        )
+        .route(
+            "/api/v1/jobs/{id}/drift-report",
+            get(handlers::job::get_drift_report)
+        )
+        .route(
+            "/api/v1/output-baselines",
+            get(handlers::job::list_output_baselines)
+                .post(handlers::job::set_output_baseline)
+        )
+        .route(
+            "/api/v1/output-baselines/{id}",
+            delete(handlers::job::delete_output_baseline)
+        )
+        .route(
+            "/api/v1/exit-code-tables",
+            get(handlers::job::list_exit_code_tables)
+        )
+
+        // 合规证据包导出
+        .route(
+            "/api/v1/jobs/{id}/export-evidence",
+            post(handlers::evidence::export_job_evidence)
+        )
+        .route(
+            "/api/v1/jobs/{id}/evidence-exports",
+            get(handlers::evidence::list_job_evidence_exports)
+        )
+        .route(
+            "/api/v1/evidence-exports/{id}",
+            get(handlers::evidence::get_evidence_export)
+        )
+        .route(
+            "/api/v1/evidence-exports/{id}/download-link",
+            post(handlers::evidence::create_evidence_download_link)
+        )
+
+        // 作业标签与报表
+        .route(
+            "/api/v1/job-tags",
+            get(handlers::job::list_job_tags)
+                .post(handlers::job::create_job_tag)
+        )
+        .route(
+            "/api/v1/job-tags/{id}",
+            put(handlers::job::update_job_tag)
+                .delete(handlers::job::delete_job_tag)
+        )
+        .route(
+            "/api/v1/job-tag-namespaces",
+            get(handlers::job::list_tag_namespaces)
+                .post(handlers::job::create_tag_namespace)
+        )
+        .route(
+            "/api/v1/job-tag-namespaces/{name}",
+            put(handlers::job::update_tag_namespace)
+                .delete(handlers::job::delete_tag_namespace)
+        )
+        .route(
+            "/api/v1/reports/jobs/by-tag",
+            get(handlers::job::get_job_tag_report)
+        )
+
+        // 运维看板统计（预聚合）
+        .route("/api/v1/stats/jobs/daily", get(handlers::stats::get_jobs_daily))
+        .route("/api/v1/stats/jobs/mttr", get(handlers::stats::get_job_recovery))
+        .route("/api/v1/stats/hosts/top-failing", get(handlers::stats::get_top_failing_hosts))
+        .route("/api/v1/stats/runners/busiest", get(handlers::stats::get_busiest_runners))
+        .route(
+            "/api/v1/stats/approvals/turnaround",
+            get(handlers::stats::get_approval_turnaround)
+        )
+        .route("/api/v1/stats/concurrency", get(handlers::stats::get_concurrency_history))
+        .route("/api/v1/concurrency/timeline", get(handlers::stats::get_concurrency_timeline))
+        .route("/api/v1/reports/handover", get(handlers::stats::get_handover_report))
 
         // 审计日志（需要审计权限）
         .route("/api/v1/audit/logs", get(handlers::audit::list_audit_logs))
+        .route("/api/v1/audit/logs/facets", get(handlers::audit::audit_facets))
         .route("/api/v1/audit/login-events", get(handlers::audit::list_login_events))
+        .route("/api/v1/audit/anomalies", get(handlers::audit::list_anomalies))
+        .route(
+            "/api/v1/audit/anomalies/stream",
+            get(handlers::audit::subscribe_security_events)
+        )
+        .route(
+            "/api/v1/audit/anomalies/{id}/acknowledge",
+            post(handlers::audit::acknowledge_anomaly)
+        )
 
         // 角色管理（P1）
         .route(
@@ -201,10 +474,30 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             "/api/v1/approvals/{id}",
             get(handlers::approval::get_approval_request)
         )
+        .route(
+            "/api/v1/approvals/{id}/progress",
+            get(handlers::approval::get_approval_progress)
+        )
+        .route(
+            "/api/v1/approvals/{id}/change",
+            get(handlers::approval::get_approval_change)
+        )
+        .route(
+            "/api/v1/approvals/{id}/timeline",
+            get(handlers::approval::get_approval_timeline)
+        )
         .route(
             "/api/v1/approvals/{id}/approve",
             post(handlers::approval::approve_request)
         )
+        .route(
+            "/api/v1/approvals/bulk/approve",
+            post(handlers::approval::bulk_approve_requests)
+        )
+        .route(
+            "/api/v1/approvals/bulk/reject",
+            post(handlers::approval::bulk_reject_requests)
+        )
         .route(
             "/api/v1/approvals/{id}/cancel",
             post(handlers::approval::cancel_approval_request)
@@ -230,11 +523,93 @@ pub fn create_router(state: Arc<AppState>) -> Router {
                 .put(handlers::approval::update_job_template)
                 .delete(handlers::approval::delete_job_template)
         )
+        .route(
+            "/api/v1/job-templates/deleted",
+            get(handlers::approval::list_deleted_job_templates)
+        )
+        .route(
+            "/api/v1/job-templates/{id}/restore",
+            post(handlers::approval::restore_job_template)
+        )
+        .route(
+            "/api/v1/job-templates/deprecated-usage",
+            get(handlers::approval::deprecated_template_usage_report)
+        )
+        .route(
+            "/api/v1/job-templates/{id}/usage",
+            get(handlers::approval::get_job_template_usage)
+        )
+        .route(
+            "/api/v1/job-templates/{id}/deprecate",
+            post(handlers::approval::deprecate_job_template)
+                .delete(handlers::approval::undeprecate_job_template)
+        )
+        .route(
+            "/api/v1/job-templates/{id}/resolved",
+            get(handlers::approval::get_resolved_job_template)
+        )
         .route(
             "/api/v1/job-templates/execute",
             post(handlers::approval::execute_template_job)
         )
 
+        // 入站 Webhook（外部系统触发作业模板）
+        .route(
+            "/api/v1/inbound-webhooks",
+            get(handlers::inbound_webhook::list_inbound_webhooks)
+                .post(handlers::inbound_webhook::create_inbound_webhook)
+        )
+        .route(
+            "/api/v1/inbound-webhooks/{id}",
+            get(handlers::inbound_webhook::get_inbound_webhook)
+                .put(handlers::inbound_webhook::update_inbound_webhook)
+                .delete(handlers::inbound_webhook::delete_inbound_webhook)
+        )
+        .route(
+            "/api/v1/inbound-webhooks/{id}/rotate-token",
+            post(handlers::inbound_webhook::rotate_inbound_webhook_token)
+        )
+        .route(
+            "/api/v1/inbound-webhooks/{id}/deliveries",
+            get(handlers::inbound_webhook::list_inbound_webhook_deliveries)
+        )
+
+        // 通知模板
+        .route(
+            "/api/v1/notification-templates",
+            get(handlers::notification_template::list_notification_templates)
+                .put(handlers::notification_template::upsert_notification_template)
+        )
+        .route(
+            "/api/v1/notification-templates/variables",
+            get(handlers::notification_template::list_notification_template_variables)
+        )
+        .route(
+            "/api/v1/notification-templates/preview",
+            post(handlers::notification_template::preview_notification_template)
+        )
+        .route(
+            "/api/v1/notification-templates/{id}",
+            get(handlers::notification_template::get_notification_template)
+                .delete(handlers::notification_template::delete_notification_template)
+        )
+        .route(
+            "/api/v1/notification-templates/{id}/rollback",
+            post(handlers::notification_template::rollback_notification_template)
+        )
+
+        // 环境策略
+        .route(
+            "/api/v1/environment-policies",
+            get(handlers::environment_policy::list_environment_policies)
+        )
+        .route(
+            "/api/v1/environment-policies/{environment}",
+            get(handlers::environment_policy::get_environment_policy)
+                .put(handlers::environment_policy::upsert_environment_policy)
+                .delete(handlers::environment_policy::delete_environment_policy)
+        )
+
         // 实时事件流 (P3 - SSE)
         .route(
             "/api/v1/stream/approvals",
@@ -244,6 +619,10 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             "/api/v1/stream/jobs/{id}",
             get(handlers::approval::subscribe_job_events)
         )
+        .route(
+            "/api/v1/stream/notifications",
+            get(handlers::watch::subscribe_notifications)
+        )
 
         // 构建作业 (P2.1)
         .route(
@@ -267,18 +646,48 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             "/api/v1/builds/{id}/steps",
             get(handlers::build::get_build_steps)
         )
+        .route(
+            "/api/v1/builds/{id}/logs",
+            get(handlers::build::get_build_logs)
+        )
+        .route(
+            "/api/v1/builds/{id}/logs/download",
+            get(handlers::build::download_build_logs)
+        )
 
         // Runner 管理 (P2.1)
         .route(
             "/api/v1/runners",
             get(handlers::runner::list_runners)
         )
+        .route(
+            "/api/v1/runners/enrollment-tokens",
+            get(handlers::runner::list_enrollment_tokens)
+                .post(handlers::runner::create_enrollment_token)
+        )
+        .route(
+            "/api/v1/runners/enrollment-tokens/{id}",
+            delete(handlers::runner::revoke_enrollment_token)
+        )
         .route(
             "/api/v1/runners/{id}",
             get(handlers::runner::get_runner_status)
                 .put(handlers::runner::update_runner_status)
                 .delete(handlers::runner::delete_runner)
         )
+        .route(
+            "/api/v1/runners/{id}/tasks",
+            get(handlers::runner::list_runner_tasks)
+        )
+        .route(
+            "/api/v1/runners/{id}/commands",
+            get(handlers::runner::list_runner_commands)
+                .post(handlers::runner::create_runner_command)
+        )
+        .route(
+            "/api/v1/runners/{id}/commands/{command_id}",
+            get(handlers::runner::get_runner_command)
+        )
 
         // Runner Docker 配置管理 (Web UI)
         .route(
@@ -296,6 +705,24 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             "/api/v1/runner-docker-configs/{id}/history",
             get(handlers::runner_config::get_config_history)
         )
+        // Runner 配置灰度发布
+        .route(
+            "/api/v1/runner-docker-configs/{id}/rollouts",
+            get(handlers::runner_config::list_runner_config_rollouts)
+                .post(handlers::runner_config::create_runner_config_rollout)
+        )
+        .route(
+            "/api/v1/runner-docker-configs/{id}/rollouts/{rollout_id}",
+            get(handlers::runner_config::get_runner_config_rollout)
+        )
+        .route(
+            "/api/v1/runner-docker-configs/{id}/rollouts/{rollout_id}/promote",
+            post(handlers::runner_config::promote_runner_config_rollout)
+        )
+        .route(
+            "/api/v1/runner-docker-configs/{id}/rollouts/{rollout_id}/rollback",
+            post(handlers::runner_config::rollback_runner_config_rollout)
+        )
 
         // 构建产物 (P2.1)
         .route(
@@ -303,12 +730,20 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             post(handlers::artifact::record_artifact)
                 .get(handlers::artifact::list_artifacts)
         )
+        .route(
+            "/api/v1/artifact-versions",
+            get(handlers::artifact::list_artifact_versions)
+        )
         .route(
             "/api/v1/artifacts/{id}",
             get(handlers::artifact::get_artifact)
                 .put(handlers::artifact::update_artifact)
                 .delete(handlers::artifact::delete_artifact)
         )
+        .route(
+            "/api/v1/artifacts/{id}/variants",
+            get(handlers::artifact::list_artifact_variants)
+        )
         .route(
             "/api/v1/artifacts/{id}/download",
             post(handlers::artifact::record_download)
@@ -321,6 +756,67 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             "/api/v1/artifacts/{id}/downloads",
             get(handlers::artifact::get_download_history)
         )
+        .route(
+            "/api/v1/artifacts/{id}/promotions",
+            post(handlers::artifact::request_artifact_promotion)
+                .get(handlers::artifact::list_artifact_promotions)
+        )
+        .route(
+            "/api/v1/artifacts/{id}/promotions/{promotion_id}/complete",
+            post(handlers::artifact::complete_artifact_promotion)
+        )
+
+        // 内容寻址 blob 存储
+        .route(
+            "/api/v1/blobs",
+            put(handlers::blob::upload_blob).layer(axum::extract::DefaultBodyLimit::max(
+                state.config.blob_store.max_upload_bytes,
+            ))
+        )
+        .route(
+            "/api/v1/blobs/upload",
+            post(handlers::blob::upload_blob_stream).layer(axum::extract::DefaultBodyLimit::max(
+                state.config.blob_store.max_stream_upload_bytes as usize,
+            ))
+        )
+        .route("/api/v1/blobs/stats", get(handlers::blob::get_blob_stats))
+        .route("/api/v1/blobs/{sha256}", get(handlers::blob::download_blob))
+        .route("/api/v1/blobs/{sha256}/meta", get(handlers::blob::get_blob_metadata))
+
+        // 平台压测（仅管理员）
+        .route(
+            "/api/v1/load-tests",
+            post(handlers::load_test::start_load_test)
+                .get(handlers::load_test::list_load_tests)
+        )
+        .route("/api/v1/load-tests/{id}", get(handlers::load_test::get_load_test))
+
+        // 只读维护模式
+        .route(
+            crate::middleware::maintenance::MAINTENANCE_PATH,
+            get(handlers::maintenance::get_maintenance_status)
+                .put(handlers::maintenance::set_maintenance_mode)
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::maintenance::maintenance_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::auth::middleware::impersonation_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::role_ip_allowlist_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::user_locale_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::auth::middleware::session_middleware,
+        ))
         .layer(axum::middleware::from_fn_with_state(
             state.jwt_service.clone(),
             crate::auth::middleware::jwt_auth_middleware,
@@ -353,6 +849,9 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .merge(auth_routes)
         .merge(authenticated_routes)
         .merge(metrics_routes)
+        // 全局请求体上限（blob 上传路由使用各自的上限）
+        .layer(axum::extract::DefaultBodyLimit::max(state.config.payload_limits.max_body_bytes))
+        .layer(axum::middleware::from_fn(crate::middleware::payload_limit_middleware))
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
             crate::middleware::ip_whitelist_middleware,
@@ -361,6 +860,7 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             state.clone(),
             crate::middleware::rate_limit_middleware,
         ))
+        .layer(axum::middleware::from_fn(crate::middleware::locale_middleware))
         .layer(axum::middleware::from_fn(crate::middleware::request_tracking_middleware))
         .with_state(state)
 }
@@ -0,0 +1,488 @@
+//! 初始数据（`ops-service seed`）
+//!
+//! 新部署执行一次即可获得可用的系统，无需手写 SQL：
+//! - 默认权限与角色（admin/operator/viewer/auditor），admin 角色拥有全部权限
+//! - 初始管理员：密码取自 `OPS_SEED_ADMIN_PASSWORD`，未设置时随机生成并只打印这一次；
+//!   迁移创建的管理员仍使用内置默认密码时同样替换
+//! - 示例资产组与示例作业模板
+//!
+//! 每一项都只在缺失时创建，可重复执行
+
+use rand::distr::{Alphanumeric, SampleString};
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres, Transaction};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::password::PasswordHasher;
+use crate::config::AppConfig;
+use crate::error::{AppError, Result};
+
+/// 管理员密码的环境变量
+pub const ADMIN_PASSWORD_ENV: &str = "OPS_SEED_ADMIN_PASSWORD";
+
+/// 初始管理员用户名
+pub const ADMIN_USERNAME: &str = "admin";
+
+/// 迁移创建管理员时使用的内置默认密码，仍未修改时由 seed 替换
+const MIGRATION_DEFAULT_ADMIN_PASSWORD: &str = "Admin123!";
+
+/// 生成密码的分段数与每段长度
+const GENERATED_PASSWORD_SEGMENTS: usize = 4;
+const GENERATED_PASSWORD_SEGMENT_LEN: usize = 6;
+
+/// 默认权限（与初始迁移一致）
+const DEFAULT_PERMISSIONS: &[(&str, &str, &str)] = &[
+    ("asset", "read", "View assets and groups"),
+    ("asset", "write", "Create, update, delete assets"),
+    ("job", "read", "View jobs and tasks"),
+    ("job", "execute", "Execute jobs on targets"),
+    ("job", "approve", "Approve jobs (for production)"),
+    ("job", "output_detail", "View detailed job execution output"),
+    ("job", "read_all", "View all jobs across all scopes (global read)"),
+    ("approval", "read", "View approval requests and groups"),
+    ("approval", "approve", "Approve or reject approval requests"),
+    ("build", "read", "View build jobs and build status"),
+    ("build", "execute", "Create, cancel, retry build jobs"),
+    ("build", "output_detail", "View detailed build step output"),
+    ("runner", "read", "View runner status and runner list"),
+    ("runner", "write", "Manage runner status and runner records"),
+    ("artifact", "read", "View artifacts and download metadata"),
+    ("artifact", "write", "Manage artifact metadata and retention"),
+    ("artifact", "download", "Download artifacts and generate download URLs"),
+    ("audit", "read", "View audit logs"),
+    ("audit", "admin", "Access system-level audit"),
+    ("user", "read", "View user information"),
+    ("user", "write", "Manage users and roles"),
+    ("role", "read", "View roles and permissions"),
+    ("role", "write", "Manage roles and permissions"),
+    ("role_binding", "write", "Manage user role bindings"),
+    ("system", "admin", "System administration"),
+];
+
+/// 角色定义：(名称, 描述, 是否系统角色, 权限)
+type RoleSpec = (&'static str, &'static str, bool, &'static [(&'static str, &'static str)]);
+
+/// 默认角色及其权限（admin 在授权时单独处理为全部权限）
+const DEFAULT_ROLES: &[RoleSpec] = &[
+    ("admin", "Full system access", true, &[]),
+    (
+        "operator",
+        "Can execute jobs and view assets",
+        false,
+        &[
+            ("asset", "read"),
+            ("job", "read"),
+            ("job", "execute"),
+            ("job", "output_detail"),
+            ("approval", "read"),
+            ("build", "read"),
+            ("build", "execute"),
+            ("runner", "read"),
+            ("artifact", "read"),
+            ("artifact", "download"),
+        ],
+    ),
+    (
+        "viewer",
+        "Read-only access to assets and jobs",
+        false,
+        &[
+            ("asset", "read"),
+            ("job", "read"),
+            ("approval", "read"),
+            ("build", "read"),
+            ("runner", "read"),
+            ("artifact", "read"),
+        ],
+    ),
+    (
+        "auditor",
+        "Read-only access to audit logs",
+        false,
+        &[("audit", "read"), ("audit", "admin")],
+    ),
+];
+
+/// 示例资产组
+const EXAMPLE_GROUP_NAME: &str = "example";
+const EXAMPLE_GROUP_ENVIRONMENT: &str = "dev";
+
+/// 示例作业模板
+const EXAMPLE_TEMPLATE_NAME: &str = "example-disk-usage";
+const EXAMPLE_TEMPLATE_CONTENT: &str = "df -h {{path}}";
+
+/// 管理员的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminOutcome {
+    /// 新建
+    Created,
+    /// 替换了迁移内置的默认密码
+    DefaultPasswordReplaced,
+    /// 已存在且密码已修改，未做改动
+    Unchanged,
+}
+
+/// 执行结果
+#[derive(Debug, Serialize)]
+pub struct SeedReport {
+    pub permissions_created: u64,
+    pub roles_created: u64,
+    pub admin: AdminOutcome,
+    /// 随机生成的管理员密码，仅在本次生成时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_password: Option<String>,
+    pub asset_group_created: bool,
+    pub job_template_created: bool,
+}
+
+impl SeedReport {
+    /// 渲染为文本
+    pub fn render(&self) -> String {
+        let created = |created: bool| if created { "已创建" } else { "已存在" };
+        let mut out = String::new();
+        out.push_str(&format!("权限: 新建 {}\n", self.permissions_created));
+        out.push_str(&format!("角色: 新建 {}\n", self.roles_created));
+        out.push_str(&format!(
+            "管理员 {}: {}\n",
+            ADMIN_USERNAME,
+            match self.admin {
+                AdminOutcome::Created => "已创建",
+                AdminOutcome::DefaultPasswordReplaced => "已替换默认密码",
+                AdminOutcome::Unchanged => "已存在",
+            }
+        ));
+        if let Some(password) = &self.generated_password {
+            out.push_str(&format!(
+                "管理员初始密码（仅显示这一次，首次登录后须修改）: {}\n",
+                password
+            ));
+        }
+        out.push_str(&format!(
+            "示例资产组 {}: {}\n",
+            EXAMPLE_GROUP_NAME,
+            created(self.asset_group_created)
+        ));
+        out.push_str(&format!(
+            "示例作业模板 {}: {}\n",
+            EXAMPLE_TEMPLATE_NAME,
+            created(self.job_template_created)
+        ));
+        out
+    }
+}
+
+/// 随机生成满足默认密码策略的密码（分段，含大小写字母、数字和分隔符）
+pub fn generate_password() -> String {
+    loop {
+        let password = (0..GENERATED_PASSWORD_SEGMENTS)
+            .map(|_| Alphanumeric.sample_string(&mut rand::rng(), GENERATED_PASSWORD_SEGMENT_LEN))
+            .collect::<Vec<_>>()
+            .join("-");
+        if password.chars().any(|c| c.is_ascii_uppercase())
+            && password.chars().any(|c| c.is_ascii_lowercase())
+            && password.chars().any(|c| c.is_ascii_digit())
+        {
+            return password;
+        }
+    }
+}
+
+/// 管理员密码：环境变量优先（须满足密码策略），否则随机生成
+fn admin_password(provided: Option<String>, config: &AppConfig) -> Result<(String, bool)> {
+    match provided.filter(|password| !password.is_empty()) {
+        Some(password) => {
+            PasswordHasher::validate_password_policy(&password, config).map_err(|e| {
+                AppError::validation(&format!("{} rejected: {}", ADMIN_PASSWORD_ENV, e))
+            })?;
+            Ok((password, false))
+        }
+        None => Ok((generate_password(), true)),
+    }
+}
+
+fn db_error(e: sqlx::Error, message: &str) -> AppError {
+    error!(error = %e, "{}", message);
+    AppError::database(message)
+}
+
+/// 执行初始化（单个事务内完成）
+pub async fn run(db: &Pool<Postgres>, config: &AppConfig) -> Result<SeedReport> {
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| db_error(e, "Failed to begin transaction"))?;
+
+    let (permissions_created, roles_created) = seed_roles(&mut tx).await?;
+    let (admin_id, admin, generated_password) = seed_admin(&mut tx, config).await?;
+    let (group_id, asset_group_created) = seed_asset_group(&mut tx, admin_id).await?;
+    let job_template_created = seed_job_template(&mut tx, admin_id, group_id).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| db_error(e, "Failed to commit transaction"))?;
+
+    let report = SeedReport {
+        permissions_created,
+        roles_created,
+        admin,
+        generated_password,
+        asset_group_created,
+        job_template_created,
+    };
+    info!(
+        permissions_created = report.permissions_created,
+        roles_created = report.roles_created,
+        admin = ?report.admin,
+        asset_group_created = report.asset_group_created,
+        job_template_created = report.job_template_created,
+        "Seed finished"
+    );
+    Ok(report)
+}
+
+/// 默认权限与角色；返回新建的权限数与角色数
+async fn seed_roles(tx: &mut Transaction<'_, Postgres>) -> Result<(u64, u64)> {
+    let mut permissions_created = 0;
+    for (resource, action, description) in DEFAULT_PERMISSIONS {
+        permissions_created += sqlx::query(
+            "INSERT INTO permissions (resource, action, description) VALUES ($1, $2, $3)
+             ON CONFLICT (resource, action) DO NOTHING",
+        )
+        .bind(resource)
+        .bind(action)
+        .bind(description)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| db_error(e, "Failed to seed permissions"))?
+        .rows_affected();
+    }
+
+    let mut roles_created = 0;
+    for (name, description, is_system, permissions) in DEFAULT_ROLES {
+        roles_created += sqlx::query(
+            "INSERT INTO roles (name, description, is_system) VALUES ($1, $2, $3)
+             ON CONFLICT (name) DO NOTHING",
+        )
+        .bind(name)
+        .bind(description)
+        .bind(is_system)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| db_error(e, "Failed to seed roles"))?
+        .rows_affected();
+
+        for (resource, action) in permissions.iter() {
+            sqlx::query(
+                "INSERT INTO role_permissions (role_id, permission_id)
+                 SELECT r.id, p.id FROM roles r, permissions p
+                 WHERE r.name = $1 AND p.resource = $2 AND p.action = $3
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(name)
+            .bind(resource)
+            .bind(action)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| db_error(e, "Failed to seed role permissions"))?;
+        }
+    }
+
+    // admin 拥有全部权限（包括后续迁移新增的）
+    sqlx::query(
+        "INSERT INTO role_permissions (role_id, permission_id)
+         SELECT r.id, p.id FROM roles r, permissions p WHERE r.name = 'admin'
+         ON CONFLICT DO NOTHING",
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| db_error(e, "Failed to seed role permissions"))?;
+
+    Ok((permissions_created, roles_created))
+}
+
+/// 初始管理员；返回用户 ID、处理结果与本次生成的密码
+async fn seed_admin(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &AppConfig,
+) -> Result<(Uuid, AdminOutcome, Option<String>)> {
+    let existing = sqlx::query_as::<_, (Uuid, String, bool)>(
+        "SELECT id, password_hash, deleted_at IS NOT NULL FROM users WHERE username = $1",
+    )
+    .bind(ADMIN_USERNAME)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| db_error(e, "Failed to load admin user"))?;
+
+    let hasher = PasswordHasher::new();
+    let (admin_id, outcome) = match existing {
+        Some((_, _, true)) => {
+            return Err(AppError::validation(&format!(
+                "User {} is deleted; restore it before running seed",
+                ADMIN_USERNAME
+            )));
+        }
+        Some((id, hash, false))
+            if hasher
+                .verify(MIGRATION_DEFAULT_ADMIN_PASSWORD, &hash)
+                .is_err() =>
+        {
+            (id, AdminOutcome::Unchanged)
+        }
+        Some((id, _, false)) => (id, AdminOutcome::DefaultPasswordReplaced),
+        None => (Uuid::nil(), AdminOutcome::Created),
+    };
+
+    let mut generated_password = None;
+    let admin_id = match outcome {
+        AdminOutcome::Unchanged => admin_id,
+        _ => {
+            let (password, generated) =
+                admin_password(std::env::var(ADMIN_PASSWORD_ENV).ok(), config)?;
+            let hash = hasher.hash(&password)?;
+            // 生成的密码会出现在终端输出中，要求首次登录后修改
+            let id = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO users (
+                    username, email, password_hash, full_name, status, must_change_password
+                ) VALUES ($1, $2, $3, 'System Administrator', 'enabled', $4)
+                ON CONFLICT (username) DO UPDATE SET
+                    password_hash = EXCLUDED.password_hash,
+                    password_changed_at = NOW(),
+                    must_change_password = EXCLUDED.must_change_password
+                RETURNING id
+                "#,
+            )
+            .bind(ADMIN_USERNAME)
+            .bind(format!("{}@ops-system.local", ADMIN_USERNAME))
+            .bind(&hash)
+            .bind(generated)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(|e| db_error(e, "Failed to seed admin user"))?;
+            if generated {
+                generated_password = Some(password);
+            }
+            id
+        }
+    };
+
+    sqlx::query(
+        "INSERT INTO role_bindings (user_id, role_id, scope_type, created_by)
+         SELECT $1, r.id, 'global', $1 FROM roles r
+         WHERE r.name = 'admin'
+           AND NOT EXISTS (
+               SELECT 1 FROM role_bindings b
+               WHERE b.user_id = $1 AND b.role_id = r.id AND b.scope_type = 'global'
+           )",
+    )
+    .bind(admin_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| db_error(e, "Failed to bind admin role"))?;
+
+    Ok((admin_id, outcome, generated_password))
+}
+
+/// 示例资产组；返回资产组 ID 与是否新建
+async fn seed_asset_group(
+    tx: &mut Transaction<'_, Postgres>,
+    admin_id: Uuid,
+) -> Result<(Uuid, bool)> {
+    let created = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO assets_groups (name, description, environment, created_by)
+         VALUES ($1, 'Example asset group created by seed', $2, $3)
+         ON CONFLICT (name, environment) DO NOTHING
+         RETURNING id",
+    )
+    .bind(EXAMPLE_GROUP_NAME)
+    .bind(EXAMPLE_GROUP_ENVIRONMENT)
+    .bind(admin_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| db_error(e, "Failed to seed asset group"))?;
+    if let Some(id) = created {
+        return Ok((id, true));
+    }
+
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM assets_groups WHERE name = $1 AND environment = $2",
+    )
+    .bind(EXAMPLE_GROUP_NAME)
+    .bind(EXAMPLE_GROUP_ENVIRONMENT)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| db_error(e, "Failed to load asset group"))?;
+    Ok((id, false))
+}
+
+/// 示例作业模板（低风险只读命令，限定在示例资产组）；返回是否新建
+async fn seed_job_template(
+    tx: &mut Transaction<'_, Postgres>,
+    admin_id: Uuid,
+    group_id: Uuid,
+) -> Result<bool> {
+    let parameters_schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "path": {"type": "string", "default": "/", "description": "Mount point to inspect"}
+        }
+    });
+    let created = sqlx::query(
+        r#"
+        INSERT INTO job_templates (
+            name, description, template_type, template_content, parameters_schema,
+            default_timeout_secs, risk_level, requires_approval,
+            applicable_environments, applicable_groups, created_by
+        ) VALUES ($1, $2, 'command', $3, $4, 60, 'low', false, $5, $6, $7)
+        ON CONFLICT (name) DO NOTHING
+        "#,
+    )
+    .bind(EXAMPLE_TEMPLATE_NAME)
+    .bind("Example template created by seed: show disk usage")
+    .bind(EXAMPLE_TEMPLATE_CONTENT)
+    .bind(Json(parameters_schema))
+    .bind(Json(vec![EXAMPLE_GROUP_ENVIRONMENT]))
+    .bind(Json(vec![group_id]))
+    .bind(admin_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| db_error(e, "Failed to seed job template"))?
+    .rows_affected();
+
+    Ok(created > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_password() {
+        for _ in 0..20 {
+            let password = generate_password();
+            assert_eq!(
+                password.len(),
+                GENERATED_PASSWORD_SEGMENTS * (GENERATED_PASSWORD_SEGMENT_LEN + 1) - 1
+            );
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert_eq!(password.matches('-').count(), GENERATED_PASSWORD_SEGMENTS - 1);
+        }
+        assert_ne!(generate_password(), generate_password());
+    }
+
+    #[test]
+    fn test_default_roles_reference_default_permissions() {
+        for (_, _, _, permissions) in DEFAULT_ROLES {
+            for permission in permissions.iter() {
+                assert!(DEFAULT_PERMISSIONS
+                    .iter()
+                    .any(|(resource, action, _)| (*resource, *action) == *permission));
+            }
+        }
+    }
+}
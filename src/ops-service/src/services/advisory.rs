@@ -0,0 +1,552 @@
+//! 安全公告匹配
+//!
+//! 从 OSV 格式的公告数据（配置的本地镜像目录或 API 提交）导入受影响的软件包版本范围，
+//! 与清单采集作业写入的 host_packages 按包管理器、包名与版本比较得到受影响主机。
+//! - 发行版生态映射到包管理器：Debian/Ubuntu → dpkg，Alpine → apk，
+//!   Red Hat/AlmaLinux/Rocky/SUSE → rpm；其他生态（语言包仓库等）的条目忽略
+//! - 同一软件包在不同发行版版本上的修复版本不同，主机 os_version 已知时只使用对应发行版版本的范围
+//! - OSV 的发行版数据按源码包命名，只能匹配到与源码包同名的二进制包
+//! - 不支持 OVAL（XML）格式，需先转换为 OSV
+//!
+//! 匹配结果由后台任务定期重算，也可在导入后立即重算；修复作业按包管理器生成升级命令。
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::time::SystemTime;
+
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::{
+    config::AdvisoryConfig,
+    error::{AppError, Result},
+    models::advisory::*,
+    models::asset::PackageFormat,
+    repository::AdvisoryRepository,
+    services::package_inventory::compare_versions,
+};
+
+/// 单次 API 导入允许的最大记录数
+pub const MAX_IMPORT_RECORDS: usize = 1000;
+
+/// 单个公告文件的最大字节数
+const MAX_FEED_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// 将 OSV 生态映射为包管理器、来源与发行版版本，如 "Debian:12" → (dpkg, "Debian", "12")
+pub fn map_ecosystem(ecosystem: &str) -> Option<(PackageFormat, &str, String)> {
+    let mut parts = ecosystem.split(':');
+    let family = parts.next()?.trim();
+    let format = match family {
+        "Debian" | "Ubuntu" => PackageFormat::Dpkg,
+        "Alpine" => PackageFormat::Apk,
+        "Red Hat" | "AlmaLinux" | "Rocky Linux" | "SUSE" | "openSUSE" => PackageFormat::Rpm,
+        _ => return None,
+    };
+    // 取第一个以数字开头的段（跳过 Ubuntu:Pro、Red Hat:enterprise_linux 等前缀）
+    let release = parts
+        .map(|p| p.trim().trim_start_matches('v'))
+        .find(|p| p.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or_default()
+        .to_string();
+    Some((format, family, release))
+}
+
+/// 将 OSV 记录转换为待写入的公告，没有可匹配的发行版软件包时返回 None
+pub fn convert_record(record: &OsvRecord) -> Option<NewAdvisory> {
+    let mut source = None;
+    let mut packages = Vec::new();
+    for affected in &record.affected {
+        let Some(package) = &affected.package else {
+            continue;
+        };
+        let Some((format, family, release)) = map_ecosystem(&package.ecosystem) else {
+            continue;
+        };
+        source.get_or_insert(family);
+        for range in affected.ranges.iter().filter(|r| r.kind == "ECOSYSTEM") {
+            let mut introduced: Option<String> = None;
+            let mut open = false;
+            for event in &range.events {
+                if let Some(version) = &event.introduced {
+                    // "0" 表示自最早版本起受影响
+                    introduced = Some(version.clone()).filter(|v| v != "0");
+                    open = true;
+                }
+                let (fixed, last_affected) = (event.fixed.clone(), event.last_affected.clone());
+                if open && (fixed.is_some() || last_affected.is_some()) {
+                    packages.push(AdvisoryPackage {
+                        package_format: format,
+                        package_name: package.name.clone(),
+                        ecosystem_release: release.clone(),
+                        introduced: introduced.take(),
+                        fixed_version: fixed,
+                        last_affected,
+                    });
+                    open = false;
+                }
+            }
+            // 只有 introduced 没有结束事件：尚无修复版本
+            if open {
+                packages.push(AdvisoryPackage {
+                    package_format: format,
+                    package_name: package.name.clone(),
+                    ecosystem_release: release.clone(),
+                    introduced,
+                    fixed_version: None,
+                    last_affected: None,
+                });
+            }
+        }
+    }
+    if packages.is_empty() {
+        return None;
+    }
+    packages.dedup();
+
+    Some(NewAdvisory {
+        id: record.id.clone(),
+        aliases: record.aliases.clone(),
+        summary: record.summary.clone().or_else(|| {
+            record
+                .details
+                .as_deref()
+                .map(|d| d.lines().next().unwrap_or_default().to_string())
+        }),
+        severity: severity(record),
+        source: source?.to_string(),
+        published: record.published,
+        modified: record.modified,
+        packages,
+    })
+}
+
+/// 严重级别：优先取 database_specific.severity，其次取非 CVSS 向量的定性评级（如 Ubuntu 的优先级）
+fn severity(record: &OsvRecord) -> Option<String> {
+    let specific = record
+        .database_specific
+        .as_ref()
+        .and_then(|d| d.get("severity"))
+        .and_then(|s| s.as_str());
+    specific
+        .or_else(|| {
+            record
+                .severity
+                .iter()
+                .find(|s| !s.kind.starts_with("CVSS"))
+                .map(|s| s.score.as_str())
+        })
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+}
+
+/// 判断主机的 os_version 是否属于范围对应的发行版版本（任一方未知时视为属于）
+fn release_matches(ecosystem_release: &str, os_version: Option<&str>) -> bool {
+    let os_version = os_version
+        .unwrap_or_default()
+        .trim()
+        .trim_start_matches('v');
+    if ecosystem_release.is_empty() || !os_version.starts_with(|c: char| c.is_ascii_digit()) {
+        return true;
+    }
+    os_version == ecosystem_release || os_version.starts_with(&format!("{}.", ecosystem_release))
+}
+
+/// 判断已安装版本是否落在受影响范围内
+fn version_affected(candidate: &AdvisoryMatchCandidate) -> bool {
+    let version = candidate.installed_version.as_str();
+    let introduced_ok = candidate
+        .introduced
+        .as_deref()
+        .map_or(true, |i| compare_versions(version, i).is_ge());
+    let fixed_ok = candidate
+        .fixed_version
+        .as_deref()
+        .map_or(true, |f| compare_versions(version, f).is_lt());
+    let last_ok = candidate
+        .last_affected
+        .as_deref()
+        .map_or(true, |l| compare_versions(version, l).is_le());
+    introduced_ok && fixed_ok && last_ok
+}
+
+/// 由候选记录计算匹配结果
+///
+/// 同一已安装软件包命中多个范围时（主机发行版版本未知），取最低的修复版本
+pub fn compute_matches(candidates: Vec<AdvisoryMatchCandidate>) -> Vec<AdvisoryMatch> {
+    let mut matches: HashMap<(String, uuid::Uuid, String, String), AdvisoryMatch> = HashMap::new();
+    for candidate in candidates {
+        if !release_matches(&candidate.ecosystem_release, candidate.os_version.as_deref())
+            || !version_affected(&candidate)
+        {
+            continue;
+        }
+        let key = (
+            candidate.advisory_id.clone(),
+            candidate.host_id,
+            candidate.package_name.clone(),
+            candidate.installed_version.clone(),
+        );
+        let fixed = candidate.fixed_version;
+        match matches.get_mut(&key) {
+            Some(existing) => {
+                let lower = match (&existing.fixed_version, &fixed) {
+                    (None, Some(_)) => true,
+                    (Some(current), Some(new)) => compare_versions(new, current).is_lt(),
+                    _ => false,
+                };
+                if lower {
+                    existing.fixed_version = fixed;
+                }
+            }
+            None => {
+                matches.insert(
+                    key,
+                    AdvisoryMatch {
+                        advisory_id: candidate.advisory_id,
+                        host_id: candidate.host_id,
+                        package_format: candidate.package_format,
+                        package_name: candidate.package_name,
+                        installed_version: candidate.installed_version,
+                        fixed_version: fixed,
+                    },
+                );
+            }
+        }
+    }
+    matches.into_values().collect()
+}
+
+/// 包名只允许包管理器使用的字符，避免拼接到命令中产生注入
+fn is_safe_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_' | ':' | '@'))
+}
+
+/// 生成升级指定软件包的命令
+pub fn remediation_command(format: PackageFormat, packages: &[String]) -> Result<String> {
+    if packages.is_empty() {
+        return Err(AppError::validation("No packages to upgrade"));
+    }
+    if let Some(name) = packages.iter().find(|p| !is_safe_package_name(p)) {
+        return Err(AppError::validation(&format!("Unsupported package name: {}", name)));
+    }
+    let list = packages.join(" ");
+    let command = match format {
+        PackageFormat::Dpkg => format!(
+            "apt-get update && DEBIAN_FRONTEND=noninteractive apt-get install --only-upgrade -y {}",
+            list
+        ),
+        PackageFormat::Rpm => format!(
+            "if command -v dnf >/dev/null 2>&1; then dnf upgrade -y {0}; \
+             else yum update -y {0}; fi",
+            list
+        ),
+        PackageFormat::Apk => format!("apk update && apk upgrade {}", list),
+        // Arch 不支持部分升级
+        PackageFormat::Pacman => "pacman -Syu --noconfirm".to_string(),
+    };
+    Ok(command)
+}
+
+/// 按包管理器分组生成修复作业草稿，只包含已有修复版本的软件包
+pub fn remediation_drafts(advisory_id: &str, hosts: &[AffectedHost]) -> Vec<RemediationDraft> {
+    let mut grouped: HashMap<&str, (BTreeSet<String>, BTreeSet<uuid::Uuid>)> = HashMap::new();
+    for host in hosts.iter().filter(|h| h.fixed_version.is_some()) {
+        let entry = grouped.entry(host.package_format.as_str()).or_default();
+        entry.0.insert(host.package_name.clone());
+        entry.1.insert(host.host_id);
+    }
+
+    let mut drafts: Vec<RemediationDraft> = grouped
+        .into_iter()
+        .filter_map(|(format, (packages, target_hosts))| {
+            let packages: Vec<String> = packages.into_iter().collect();
+            let command = PackageFormat::parse(format)
+                .and_then(|f| remediation_command(f, &packages).ok())?;
+            Some(RemediationDraft {
+                advisory_id: advisory_id.to_string(),
+                package_format: format.to_string(),
+                packages,
+                command,
+                target_hosts: target_hosts.into_iter().collect(),
+            })
+        })
+        .collect();
+    drafts.sort_by(|a, b| a.package_format.cmp(&b.package_format));
+    drafts
+}
+
+/// 公告导入器
+pub struct AdvisoryImporter {
+    repo: AdvisoryRepository,
+    config: AdvisoryConfig,
+}
+
+impl AdvisoryImporter {
+    pub fn new(db: PgPool, config: AdvisoryConfig) -> Self {
+        Self {
+            repo: AdvisoryRepository::new(db),
+            config,
+        }
+    }
+
+    /// 导入 API 提交的 OSV 记录并重算匹配结果
+    pub async fn import_records(
+        &self,
+        records: Vec<serde_json::Value>,
+    ) -> Result<AdvisoryImportReport> {
+        if records.len() > MAX_IMPORT_RECORDS {
+            return Err(AppError::validation(&format!(
+                "At most {} records can be imported at once",
+                MAX_IMPORT_RECORDS
+            )));
+        }
+        let mut report = AdvisoryImportReport::default();
+        for value in records {
+            match serde_json::from_value::<OsvRecord>(value) {
+                Ok(record) => self.store(&record, &mut report).await?,
+                Err(_) => report.skipped += 1,
+            }
+        }
+        report.matches = self.rematch().await?;
+        Ok(report)
+    }
+
+    /// 导入镜像目录中修改时间晚于 `since` 的 OSV 文件（每个文件为单条记录或记录数组）并重算匹配结果
+    ///
+    /// 未配置镜像目录时只重算匹配结果
+    pub async fn import_feed_dir(&self, since: Option<SystemTime>) -> Result<AdvisoryImportReport> {
+        let mut report = AdvisoryImportReport::default();
+        if let Some(dir) = self.config.feed_dir.as_deref() {
+            self.import_dir(Path::new(dir), since, &mut report).await?;
+        }
+        report.matches = self.rematch().await?;
+        Ok(report)
+    }
+
+    async fn import_dir(
+        &self,
+        dir: &Path,
+        since: Option<SystemTime>,
+        report: &mut AdvisoryImportReport,
+    ) -> Result<()> {
+        let mut entries = tokio::fs::read_dir(dir).await.map_err(|e| {
+            AppError::internal_error(&format!("Failed to read advisory feed directory: {}", e))
+        })?;
+        while let Some(entry) = entries.next_entry().await.map_err(|e| {
+            AppError::internal_error(&format!("Failed to read advisory feed directory: {}", e))
+        })? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            let modified = metadata.modified().ok();
+            if since.is_some_and(|since| modified.is_some_and(|m| m <= since)) {
+                continue;
+            }
+            if metadata.len() > MAX_FEED_FILE_BYTES {
+                warn!(path = %path.display(), "Advisory file too large, skipped");
+                report.skipped += 1;
+                continue;
+            }
+            let records = match tokio::fs::read(&path).await {
+                Ok(bytes) => parse_feed_file(&bytes),
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Failed to read advisory file");
+                    None
+                }
+            };
+            let Some(records) = records else {
+                report.skipped += 1;
+                continue;
+            };
+            for record in records {
+                self.store(&record, report).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn store(&self, record: &OsvRecord, report: &mut AdvisoryImportReport) -> Result<()> {
+        // 已撤回的公告删除，避免继续报告受影响主机
+        if record.withdrawn.is_some() {
+            self.repo.delete_advisory(&record.id).await?;
+            report.skipped += 1;
+            return Ok(());
+        }
+        match convert_record(record) {
+            Some(advisory) => {
+                if self.repo.upsert_advisory(&advisory).await? {
+                    report.imported += 1;
+                } else {
+                    report.unchanged += 1;
+                }
+            }
+            None => report.skipped += 1,
+        }
+        Ok(())
+    }
+
+    /// 按当前的公告与软件包清单重算匹配结果，返回匹配数
+    pub async fn rematch(&self) -> Result<usize> {
+        let matches = compute_matches(self.repo.list_match_candidates().await?);
+        self.repo.replace_matches(&matches).await?;
+        Ok(matches.len())
+    }
+}
+
+/// 解析公告文件：单条记录或记录数组，无法解析时返回 None
+fn parse_feed_file(bytes: &[u8]) -> Option<Vec<OsvRecord>> {
+    let value: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+    match value {
+        serde_json::Value::Array(items) => Some(
+            items
+                .into_iter()
+                .filter_map(|v| serde_json::from_value(v).ok())
+                .collect(),
+        ),
+        value => serde_json::from_value(value).ok().map(|r| vec![r]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn record(json: serde_json::Value) -> OsvRecord {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn candidate(
+        host_id: Uuid,
+        os_version: Option<&str>,
+        installed: &str,
+        release: &str,
+        fixed: Option<&str>,
+    ) -> AdvisoryMatchCandidate {
+        AdvisoryMatchCandidate {
+            advisory_id: "DSA-1".to_string(),
+            host_id,
+            os_version: os_version.map(str::to_string),
+            package_format: "dpkg".to_string(),
+            package_name: "openssl".to_string(),
+            installed_version: installed.to_string(),
+            ecosystem_release: release.to_string(),
+            introduced: None,
+            fixed_version: fixed.map(str::to_string),
+            last_affected: None,
+        }
+    }
+
+    #[test]
+    fn test_map_ecosystem() {
+        assert_eq!(
+            map_ecosystem("Debian:12"),
+            Some((PackageFormat::Dpkg, "Debian", "12".to_string()))
+        );
+        assert_eq!(
+            map_ecosystem("Ubuntu:Pro:18.04:LTS"),
+            Some((PackageFormat::Dpkg, "Ubuntu", "18.04".to_string()))
+        );
+        assert_eq!(
+            map_ecosystem("Alpine:v3.18"),
+            Some((PackageFormat::Apk, "Alpine", "3.18".to_string()))
+        );
+        assert_eq!(
+            map_ecosystem("Rocky Linux:9"),
+            Some((PackageFormat::Rpm, "Rocky Linux", "9".to_string()))
+        );
+        assert_eq!(map_ecosystem("PyPI"), None);
+    }
+
+    #[test]
+    fn test_convert_record() {
+        let osv = record(serde_json::json!({
+            "id": "DSA-5532-1",
+            "modified": "2023-10-25T00:00:00Z",
+            "aliases": ["CVE-2023-5363"],
+            "details": "openssl - security update\nmore text",
+            "affected": [
+                {
+                    "package": {"ecosystem": "Debian:12", "name": "openssl"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [
+                        {"introduced": "0"}, {"fixed": "3.0.11-1~deb12u2"}
+                    ]}]
+                },
+                {
+                    "package": {"ecosystem": "PyPI", "name": "cryptography"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [{"introduced": "0"}]}]
+                },
+                {
+                    "package": {"ecosystem": "Debian:11", "name": "openssl"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [{"introduced": "1.1.1"}]}]
+                }
+            ],
+            "database_specific": {"severity": "HIGH"}
+        }));
+        let advisory = convert_record(&osv).unwrap();
+        assert_eq!(advisory.source, "Debian");
+        assert_eq!(advisory.summary.as_deref(), Some("openssl - security update"));
+        assert_eq!(advisory.severity.as_deref(), Some("high"));
+        assert_eq!(advisory.aliases, vec!["CVE-2023-5363".to_string()]);
+        assert_eq!(advisory.packages.len(), 2);
+        assert_eq!(advisory.packages[0].introduced, None);
+        assert_eq!(advisory.packages[0].fixed_version.as_deref(), Some("3.0.11-1~deb12u2"));
+        assert_eq!(advisory.packages[1].ecosystem_release, "11");
+        assert_eq!(advisory.packages[1].introduced.as_deref(), Some("1.1.1"));
+        assert_eq!(advisory.packages[1].fixed_version, None);
+
+        let npm_only = record(serde_json::json!({
+            "id": "GHSA-xxxx",
+            "modified": "2023-10-25T00:00:00Z",
+            "affected": [{"package": {"ecosystem": "npm", "name": "left-pad"}, "ranges": []}]
+        }));
+        assert!(convert_record(&npm_only).is_none());
+    }
+
+    #[test]
+    fn test_compute_matches_uses_host_release() {
+        let (bookworm, bullseye, unknown) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let deb12 = Some("3.0.11-1~deb12u2");
+        let deb11 = Some("1.1.1w-0+deb11u1");
+        let mut matches = compute_matches(vec![
+            // Debian 12 主机：只按 12 的范围判断
+            candidate(bookworm, Some("12.4"), "3.0.9-1", "12", deb12),
+            candidate(bookworm, Some("12.4"), "3.0.9-1", "11", deb11),
+            // Debian 11 主机已升级到修复版本，不应被 12 的范围误报
+            candidate(bullseye, Some("11"), "1.1.1w-0+deb11u1", "11", deb11),
+            candidate(bullseye, Some("11"), "1.1.1w-0+deb11u1", "12", deb12),
+            // 发行版版本未知：命中多个范围时取最低的修复版本
+            candidate(unknown, None, "1.1.1n-0+deb11u5", "11", deb11),
+            candidate(unknown, None, "1.1.1n-0+deb11u5", "12", deb12),
+        ]);
+        matches.sort_by_key(|m| m.host_id != bookworm);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].host_id, bookworm);
+        assert_eq!(matches[0].fixed_version.as_deref(), deb12);
+        assert_eq!(matches[1].host_id, unknown);
+        assert_eq!(matches[1].fixed_version.as_deref(), deb11);
+    }
+
+    #[test]
+    fn test_remediation_command() {
+        let packages = vec!["openssl".to_string(), "libssl3".to_string()];
+        assert_eq!(
+            remediation_command(PackageFormat::Apk, &packages).unwrap(),
+            "apk update && apk upgrade openssl libssl3"
+        );
+        assert!(remediation_command(PackageFormat::Dpkg, &packages)
+            .unwrap()
+            .ends_with("--only-upgrade -y openssl libssl3"));
+        assert!(remediation_command(PackageFormat::Rpm, &["bash; rm -rf /".to_string()]).is_err());
+        assert!(remediation_command(PackageFormat::Rpm, &["-y".to_string()]).is_err());
+        assert!(remediation_command(PackageFormat::Rpm, &[]).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+//! 安全异常检测
+//!
+//! 后台定期扫描登录事件与作业记录，发现以下异常并写入 security_anomalies：
+//! - 新 IP 登录：用户在回看期内首次从某 IP 成功登录（无 GeoIP 数据，以网段是否已知近似地域变化）
+//! - 失败认证激增：窗口内同一来源 IP 或同一用户名的失败登录次数超过阈值
+//! - 作业创建量异常：用户最近一小时创建的作业数远超其基线小时均值
+//! - 非工作时间的生产执行：工作时间外创建了目标包含 prod 环境主机的作业
+//!
+//! 主机密钥漂移由主机密钥漂移检查发现后通过 `report_host_key_drift` 写入。
+//! 每条异常带有检测器生成的去重键，重复扫描同一窗口不会重复记录；
+//! 启用通知时新异常与安全事件在同一事务内写入事件发件箱
+
+use chrono::{DateTime, Datelike, FixedOffset, Timelike, Utc, Weekday};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::config::AnomalyConfig;
+use crate::error::{AppError, Result};
+use crate::models::asset::Host;
+use crate::realtime::{outbox, EventBus, RealtimeEvent};
+use crate::ssh::HostKeyFailure;
+
+/// 异常类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnomalyKind {
+    NewLoginIp,
+    FailedAuthSpike,
+    JobCreationSpike,
+    OffHoursProduction,
+    HostKeyDrift,
+}
+
+impl AnomalyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::NewLoginIp => "new_login_ip",
+            AnomalyKind::FailedAuthSpike => "failed_auth_spike",
+            AnomalyKind::JobCreationSpike => "job_creation_spike",
+            AnomalyKind::OffHoursProduction => "off_hours_production",
+            AnomalyKind::HostKeyDrift => "host_key_drift",
+        }
+    }
+}
+
+/// 待写入的异常
+#[derive(Debug, Clone)]
+struct AnomalyCandidate {
+    kind: AnomalyKind,
+    severity: &'static str,
+    user_id: Option<Uuid>,
+    username: Option<String>,
+    source_ip: Option<String>,
+    summary: String,
+    details: serde_json::Value,
+    dedup_key: String,
+}
+
+/// 安全异常检测器
+pub struct AnomalyDetector {
+    db: Pool<Postgres>,
+    event_bus: Option<Arc<EventBus>>,
+    config: AnomalyConfig,
+}
+
+impl AnomalyDetector {
+    pub fn new(db: Pool<Postgres>, config: AnomalyConfig) -> Self {
+        Self {
+            db,
+            event_bus: None,
+            config,
+        }
+    }
+
+    /// 设置事件总线（config.notify 为 true 时推送新异常）
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    /// 执行一轮检测，返回新记录的异常数量
+    ///
+    /// 扫描最近两个检测间隔内的活动，相邻两轮的扫描范围有重叠，由去重键保证不重复记录；
+    /// 单个检测器失败只记录错误，不影响其他检测器
+    pub async fn run_once(&self) -> Result<usize> {
+        let now = Utc::now();
+        let since = now - chrono::Duration::seconds((self.config.interval_secs * 2) as i64);
+
+        let mut candidates = Vec::new();
+        let detections = [
+            (AnomalyKind::NewLoginIp, self.detect_new_login_ips(since).await),
+            (AnomalyKind::FailedAuthSpike, self.detect_failed_auth_spikes(now).await),
+            (AnomalyKind::JobCreationSpike, self.detect_job_creation_spikes(now).await),
+            (AnomalyKind::OffHoursProduction, self.detect_off_hours_production(since).await),
+        ];
+        for (kind, detected) in detections {
+            match detected {
+                Ok(found) => candidates.extend(found),
+                Err(e) => error!(kind = kind.as_str(), error = %e, "Anomaly detector failed"),
+            }
+        }
+
+        let mut recorded = 0;
+        for candidate in candidates {
+            if self.record(&candidate).await? {
+                info!(
+                    kind = candidate.kind.as_str(),
+                    severity = candidate.severity,
+                    summary = %candidate.summary,
+                    "Security anomaly detected"
+                );
+                recorded += 1;
+            }
+        }
+        Ok(recorded)
+    }
+
+    /// 新 IP 登录：回看期内有成功登录记录、但从未从该 IP 成功登录过的用户
+    ///
+    /// 用户首次登录（回看期内无任何历史）不视为异常
+    async fn detect_new_login_ips(&self, since: DateTime<Utc>) -> Result<Vec<AnomalyCandidate>> {
+        let rows = sqlx::query_as::<_, (Uuid, String, String, DateTime<Utc>, Vec<String>)>(
+            r#"
+            SELECT e.user_id, e.username, e.source_ip, MIN(e.occurred_at),
+                   ARRAY(
+                       SELECT DISTINCT k.source_ip FROM login_events k
+                       WHERE k.user_id = e.user_id
+                         AND k.event_type = 'login_success'
+                         AND k.occurred_at < $1
+                         AND k.occurred_at >= NOW() - make_interval(days => $2)
+                   )
+            FROM login_events e
+            WHERE e.event_type = 'login_success'
+              AND e.user_id IS NOT NULL
+              AND e.occurred_at >= $1
+              AND NOT EXISTS (
+                  SELECT 1 FROM login_events p
+                  WHERE p.user_id = e.user_id
+                    AND p.source_ip = e.source_ip
+                    AND p.event_type = 'login_success'
+                    AND p.occurred_at < $1
+                    AND p.occurred_at >= NOW() - make_interval(days => $2)
+              )
+            GROUP BY e.user_id, e.username, e.source_ip
+            "#,
+        )
+        .bind(since)
+        .bind(self.config.new_ip_lookback_days as i32)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to scan login events for new IPs");
+            AppError::database("Failed to scan login events")
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .filter(|(_, _, _, _, known_ips)| !known_ips.is_empty())
+            .map(|(user_id, username, ip, first_seen, known_ips)| {
+                let new_network = !known_ips.iter().any(|known| same_network(known, &ip));
+                AnomalyCandidate {
+                    kind: AnomalyKind::NewLoginIp,
+                    severity: if new_network { "medium" } else { "low" },
+                    user_id: Some(user_id),
+                    summary: format!("{} logged in from new IP {}", username, ip),
+                    details: json!({
+                        "first_seen": first_seen,
+                        "new_network": new_network,
+                        "known_ip_count": known_ips.len(),
+                    }),
+                    dedup_key: format!("{}:{}:{}", AnomalyKind::NewLoginIp.as_str(), user_id, ip),
+                    username: Some(username),
+                    source_ip: Some(ip),
+                }
+            })
+            .collect())
+    }
+
+    /// 失败认证激增：分别按来源 IP（暴力破解）与用户名（分布式撞库）统计窗口内失败次数
+    async fn detect_failed_auth_spikes(&self, now: DateTime<Utc>) -> Result<Vec<AnomalyCandidate>> {
+        let window_secs = self.config.failed_auth_window_secs.max(1);
+        let window_start = now - chrono::Duration::seconds(window_secs as i64);
+        // 去重键按窗口分桶，持续的攻击每个窗口记录一次
+        let bucket = now.timestamp() / window_secs as i64;
+        let threshold = self.config.failed_auth_threshold as i64;
+
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, "Failed to scan login failures");
+            AppError::database("Failed to scan login events")
+        };
+        let by_ip = sqlx::query_as::<_, (String, i64, Vec<String>)>(
+            r#"
+            SELECT source_ip, COUNT(*), ARRAY_AGG(DISTINCT username)
+            FROM login_events
+            WHERE event_type = 'login_failure' AND occurred_at >= $1
+            GROUP BY source_ip
+            HAVING COUNT(*) >= $2
+            "#,
+        )
+        .bind(window_start)
+        .bind(threshold)
+        .fetch_all(&self.db)
+        .await
+        .map_err(map_err)?;
+        let by_user = sqlx::query_as::<_, (String, Option<Uuid>, i64, Vec<String>)>(
+            r#"
+            SELECT username, MAX(user_id::text)::uuid, COUNT(*), ARRAY_AGG(DISTINCT source_ip)
+            FROM login_events
+            WHERE event_type = 'login_failure' AND occurred_at >= $1
+            GROUP BY username
+            HAVING COUNT(*) >= $2
+            "#,
+        )
+        .bind(window_start)
+        .bind(threshold)
+        .fetch_all(&self.db)
+        .await
+        .map_err(map_err)?;
+
+        let kind = AnomalyKind::FailedAuthSpike;
+        let severity = |count: i64| {
+            if count >= threshold * 3 {
+                "high"
+            } else {
+                "medium"
+            }
+        };
+        let mut candidates: Vec<AnomalyCandidate> = by_ip
+            .into_iter()
+            .map(|(ip, count, usernames)| AnomalyCandidate {
+                kind,
+                severity: severity(count),
+                user_id: None,
+                username: None,
+                summary: format!("{} failed logins from {} in {}s", count, ip, window_secs),
+                details: json!({
+                    "failures": count,
+                    "window_secs": window_secs,
+                    "usernames": usernames,
+                }),
+                dedup_key: format!("{}:ip:{}:{}", kind.as_str(), ip, bucket),
+                source_ip: Some(ip),
+            })
+            .collect();
+        candidates.extend(by_user.into_iter().map(|(username, user_id, count, ips)| {
+            AnomalyCandidate {
+                kind,
+                severity: severity(count),
+                user_id,
+                summary: format!("{} failed logins for {} in {}s", count, username, window_secs),
+                details: json!({
+                    "failures": count,
+                    "window_secs": window_secs,
+                    "source_ips": ips,
+                }),
+                dedup_key: format!("{}:user:{}:{}", kind.as_str(), username, bucket),
+                username: Some(username),
+                source_ip: None,
+            }
+        }));
+        Ok(candidates)
+    }
+
+    /// 作业创建量异常：最近一小时的创建数与用户基线期内的小时均值比较
+    async fn detect_job_creation_spikes(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<AnomalyCandidate>> {
+        let hour_start = now - chrono::Duration::hours(1);
+        let baseline_days = self.config.job_creation_baseline_days.max(1);
+        let rows = sqlx::query_as::<_, (Uuid, String, i64, i64)>(
+            r#"
+            SELECT j.created_by, u.username,
+                   COUNT(*) FILTER (WHERE j.created_at >= $1),
+                   COUNT(*) FILTER (WHERE j.created_at < $1)
+            FROM jobs j
+            JOIN users u ON u.id = j.created_by
+            WHERE j.created_at >= $1 - make_interval(days => $2)
+            GROUP BY j.created_by, u.username
+            HAVING COUNT(*) FILTER (WHERE j.created_at >= $1) >= $3
+            "#,
+        )
+        .bind(hour_start)
+        .bind(baseline_days as i32)
+        .bind(self.config.job_creation_min_count as i64)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to scan job creation volume");
+            AppError::database("Failed to scan jobs")
+        })?;
+
+        let kind = AnomalyKind::JobCreationSpike;
+        let bucket = now.timestamp() / 3600;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(user_id, username, recent, baseline_total)| {
+                let baseline_hourly = baseline_total as f64 / (baseline_days as f64 * 24.0);
+                if !is_volume_spike(recent, baseline_hourly, &self.config) {
+                    return None;
+                }
+                Some(AnomalyCandidate {
+                    kind,
+                    severity: "medium",
+                    user_id: Some(user_id),
+                    summary: format!(
+                        "{} created {} jobs in the last hour (baseline {:.1}/h)",
+                        username, recent, baseline_hourly
+                    ),
+                    details: json!({
+                        "jobs_last_hour": recent,
+                        "baseline_hourly": baseline_hourly,
+                        "baseline_days": baseline_days,
+                    }),
+                    dedup_key: format!("{}:{}:{}", kind.as_str(), user_id, bucket),
+                    username: Some(username),
+                    source_ip: None,
+                })
+            })
+            .collect())
+    }
+
+    /// 非工作时间的生产执行：目标主机包含 prod 环境的作业按创建时间判断
+    async fn detect_off_hours_production(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<AnomalyCandidate>> {
+        let rows = sqlx::query_as::<_, (Uuid, String, Uuid, String, DateTime<Utc>)>(
+            r#"
+            SELECT j.id, j.name, j.created_by, u.username, j.created_at
+            FROM jobs j
+            JOIN users u ON u.id = j.created_by
+            WHERE j.created_at >= $1
+              AND EXISTS (
+                  SELECT 1 FROM assets_hosts h
+                  WHERE h.environment = 'prod'
+                    AND h.id::text IN (SELECT jsonb_array_elements_text(j.target_hosts))
+              )
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to scan production jobs");
+            AppError::database("Failed to scan jobs")
+        })?;
+
+        let kind = AnomalyKind::OffHoursProduction;
+        Ok(rows
+            .into_iter()
+            .filter(|(_, _, _, _, created_at)| !is_business_hours(*created_at, &self.config))
+            .map(|(job_id, name, user_id, username, created_at)| AnomalyCandidate {
+                kind,
+                severity: "high",
+                user_id: Some(user_id),
+                summary: format!(
+                    "{} started production job '{}' outside business hours",
+                    username, name
+                ),
+                details: json!({
+                    "job_id": job_id,
+                    "job_name": name,
+                    "created_at": created_at,
+                }),
+                dedup_key: format!("{}:{}", kind.as_str(), job_id),
+                username: Some(username),
+                source_ip: None,
+            })
+            .collect())
+    }
+
+    /// 记录主机密钥漂移，同一主机提供的同一新密钥只记录一次
+    pub async fn report_host_key_drift(
+        &self,
+        host: &Host,
+        failure: &HostKeyFailure,
+    ) -> Result<bool> {
+        let kind = AnomalyKind::HostKeyDrift;
+        let candidate = AnomalyCandidate {
+            kind,
+            severity: "high",
+            user_id: None,
+            username: None,
+            source_ip: None,
+            summary: format!(
+                "Host key of {} ({}:{}) changed: pinned {}, presented {} {}",
+                host.identifier,
+                failure.host,
+                failure.port,
+                failure.expected_fingerprint.as_deref().unwrap_or("(none)"),
+                failure.key_type,
+                failure.presented_fingerprint
+            ),
+            details: json!({
+                "host_id": host.id,
+                "identifier": host.identifier,
+                "address": failure.host,
+                "port": failure.port,
+                "expected_fingerprint": failure.expected_fingerprint,
+                "presented_fingerprint": failure.presented_fingerprint,
+                "key_type": failure.key_type,
+            }),
+            dedup_key: format!("{}:{}:{}", kind.as_str(), host.id, failure.presented_fingerprint),
+        };
+        self.record(&candidate).await
+    }
+
+    /// 写入异常记录，去重键已存在时返回 false
+    async fn record(&self, candidate: &AnomalyCandidate) -> Result<bool> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        let id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO security_anomalies (
+                kind, severity, user_id, username, source_ip, summary, details, dedup_key
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (dedup_key) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(candidate.kind.as_str())
+        .bind(candidate.severity)
+        .bind(candidate.user_id)
+        .bind(&candidate.username)
+        .bind(&candidate.source_ip)
+        .bind(&candidate.summary)
+        .bind(&candidate.details)
+        .bind(&candidate.dedup_key)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, dedup_key = %candidate.dedup_key, "Failed to record anomaly");
+            AppError::database("Failed to record anomaly")
+        })?;
+        let Some(id) = id else {
+            return Ok(false);
+        };
+
+        let notify = self.config.notify && self.event_bus.is_some();
+        if notify {
+            let event = RealtimeEvent::SecurityAnomalyDetected {
+                anomaly_id: id,
+                kind: candidate.kind.as_str().to_string(),
+                severity: candidate.severity.to_string(),
+                user_id: candidate.user_id,
+                summary: candidate.summary.clone(),
+            };
+            outbox::enqueue(&mut *tx, &event).await?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        if let (true, Some(event_bus)) = (notify, &self.event_bus) {
+            event_bus.notify_outbox();
+        }
+        Ok(true)
+    }
+}
+
+/// 判断时间点是否处于配置的工作时间内
+pub fn is_business_hours(at: DateTime<Utc>, config: &AnomalyConfig) -> bool {
+    let offset = FixedOffset::east_opt(config.business_utc_offset_hours * 3600)
+        .unwrap_or_else(|| FixedOffset::east_opt(0).expect("zero offset is valid"));
+    let local = at.with_timezone(&offset);
+    if config.business_weekdays_only && matches!(local.weekday(), Weekday::Sat | Weekday::Sun) {
+        return false;
+    }
+    (config.business_hours_start..config.business_hours_end).contains(&local.hour())
+}
+
+/// 判断最近一小时的作业创建数是否构成异常
+///
+/// 需同时达到最低阈值并超过基线小时均值的配置倍数；无基线的新用户只按最低阈值判断
+fn is_volume_spike(recent: i64, baseline_hourly: f64, config: &AnomalyConfig) -> bool {
+    recent >= config.job_creation_min_count as i64
+        && recent as f64 > baseline_hourly * config.job_creation_multiplier
+}
+
+/// 判断两个 IP 是否属于同一网段（IPv4 /24、IPv6 /48），用于近似判断登录地域是否变化
+fn same_network(a: &str, b: &str) -> bool {
+    match (a.parse::<IpAddr>(), b.parse::<IpAddr>()) {
+        (Ok(IpAddr::V4(a)), Ok(IpAddr::V4(b))) => a.octets()[..3] == b.octets()[..3],
+        (Ok(IpAddr::V6(a)), Ok(IpAddr::V6(b))) => a.segments()[..3] == b.segments()[..3],
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_business_hours_with_offset() {
+        let config = AnomalyConfig::default();
+        // 2026-10-14 是周三；UTC 02:00 即 UTC+8 的 10:00
+        let weekday_morning = Utc.with_ymd_and_hms(2026, 10, 14, 2, 0, 0).unwrap();
+        assert!(is_business_hours(weekday_morning, &config));
+        // UTC 12:00 即 UTC+8 的 20:00，超过 19 点
+        let weekday_evening = Utc.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap();
+        assert!(!is_business_hours(weekday_evening, &config));
+        // UTC+8 的周六 10:00
+        let saturday = Utc.with_ymd_and_hms(2026, 10, 17, 2, 0, 0).unwrap();
+        assert!(!is_business_hours(saturday, &config));
+
+        let config = AnomalyConfig {
+            business_weekdays_only: false,
+            ..AnomalyConfig::default()
+        };
+        assert!(is_business_hours(saturday, &config));
+    }
+
+    #[test]
+    fn test_volume_spike_thresholds() {
+        let config = AnomalyConfig::default();
+        // 低于最低阈值
+        assert!(!is_volume_spike(29, 0.0, &config));
+        // 无基线，达到最低阈值
+        assert!(is_volume_spike(30, 0.0, &config));
+        // 基线本身较高，未超过倍数
+        assert!(!is_volume_spike(40, 10.0, &config));
+        assert!(is_volume_spike(51, 10.0, &config));
+    }
+
+    #[test]
+    fn test_same_network() {
+        assert!(same_network("10.1.2.3", "10.1.2.200"));
+        assert!(!same_network("10.1.2.3", "10.1.3.3"));
+        assert!(same_network("2001:db8:1::1", "2001:db8:1:ff::2"));
+        assert!(!same_network("10.1.2.3", "2001:db8:1::1"));
+        assert!(same_network("unknown", "unknown"));
+    }
+}
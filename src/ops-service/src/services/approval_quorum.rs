@@ -0,0 +1,259 @@
+//! 审批法定人数规则
+//!
+//! 审批请求可配置多条规则（如“服务负责人组 1 人 且 SRE 组 1 人”、“指定 3 人中的 2 人”），
+//! 所有规则同时满足才批准。每个批准人只计入一条规则：同时属于两个组的审批人不能一人满足两条规则，
+//! 评估时按二分匹配为批准人分配规则，使满足的名额尽可能多。
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::approval::{QuorumRule, QuorumRuleProgress},
+};
+
+/// 单个请求允许的最大规则数
+pub const MAX_QUORUM_RULES: usize = 16;
+
+/// 规则评估所需的审批组成员
+pub type GroupMembers = HashMap<Uuid, Vec<Uuid>>;
+
+/// 校验规则结构（不检查审批组是否存在）
+pub fn validate_rules(rules: &[QuorumRule]) -> Result<()> {
+    if rules.is_empty() {
+        return Err(AppError::validation("Quorum rules must not be empty"));
+    }
+    if rules.len() > MAX_QUORUM_RULES {
+        return Err(AppError::validation(&format!(
+            "At most {} quorum rules are allowed",
+            MAX_QUORUM_RULES
+        )));
+    }
+    for rule in rules {
+        if rule.min_approvals < 1 {
+            return Err(AppError::validation("Quorum rule min_approvals must be at least 1"));
+        }
+        if rule.group_id.is_none() && rule.user_ids.is_empty() {
+            return Err(AppError::validation(
+                "Quorum rule requires an approval group or named approvers",
+            ));
+        }
+        if rule.group_id.is_none() && (rule.user_ids.len() as i32) < rule.min_approvals {
+            return Err(AppError::validation(
+                "Quorum rule requires more approvals than named approvers",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 各规则人数之和，即批准所需的最少审批人数
+pub fn total_required(rules: &[QuorumRule]) -> i32 {
+    rules.iter().map(|rule| rule.min_approvals).sum()
+}
+
+/// 规则的合格审批人（审批组成员与指定人员的并集）
+pub fn eligible_approvers(rule: &QuorumRule, groups: &GroupMembers) -> Vec<Uuid> {
+    let mut approvers = rule.user_ids.clone();
+    if let Some(members) = rule.group_id.and_then(|id| groups.get(&id)) {
+        for member in members {
+            if !approvers.contains(member) {
+                approvers.push(*member);
+            }
+        }
+    }
+    approvers
+}
+
+/// 审批人是否至少符合一条规则
+pub fn is_eligible(rules: &[QuorumRule], groups: &GroupMembers, approver: Uuid) -> bool {
+    rules
+        .iter()
+        .any(|rule| eligible_approvers(rule, groups).contains(&approver))
+}
+
+/// 按批准顺序评估各规则进度
+pub fn evaluate(
+    rules: &[QuorumRule],
+    groups: &GroupMembers,
+    approvals: &[Uuid],
+) -> Vec<QuorumRuleProgress> {
+    let eligible: Vec<Vec<Uuid>> = rules
+        .iter()
+        .map(|rule| eligible_approvers(rule, groups))
+        .collect();
+
+    // 每条规则展开为 min_approvals 个名额，批准人与名额做最大匹配
+    let slots: Vec<usize> = rules
+        .iter()
+        .enumerate()
+        .flat_map(|(index, rule)| std::iter::repeat(index).take(rule.min_approvals.max(0) as usize))
+        .collect();
+    let mut owners: Vec<Option<usize>> = vec![None; slots.len()];
+    let mut approvers: Vec<Uuid> = Vec::new();
+    for approver in approvals {
+        if !approvers.contains(approver) {
+            approvers.push(*approver);
+        }
+    }
+    for candidate in 0..approvers.len() {
+        let mut visited = vec![false; slots.len()];
+        assign(candidate, &approvers, &slots, &eligible, &mut owners, &mut visited);
+    }
+
+    rules
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| {
+            let approved_by: Vec<Uuid> = slots
+                .iter()
+                .zip(&owners)
+                .filter(|(rule_index, _)| **rule_index == index)
+                .filter_map(|(_, owner)| owner.map(|owner| approvers[owner]))
+                .collect();
+            let remaining = (rule.min_approvals - approved_by.len() as i32).max(0);
+            QuorumRuleProgress {
+                name: rule.name.clone(),
+                min_approvals: rule.min_approvals,
+                approved_by,
+                remaining,
+                satisfied: remaining == 0,
+                eligible_approvers: eligible[index].clone(),
+            }
+        })
+        .collect()
+}
+
+/// 为批准人寻找增广路径（优先使用空闲名额，已分配的批准人尽量保持原规则）
+fn assign(
+    candidate: usize,
+    approvers: &[Uuid],
+    slots: &[usize],
+    eligible: &[Vec<Uuid>],
+    owners: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    let fits = |slot: usize| eligible[slots[slot]].contains(&approvers[candidate]);
+    if let Some(slot) = (0..slots.len()).find(|&slot| owners[slot].is_none() && fits(slot)) {
+        owners[slot] = Some(candidate);
+        return true;
+    }
+    for slot in 0..slots.len() {
+        if visited[slot] || !fits(slot) {
+            continue;
+        }
+        visited[slot] = true;
+        if let Some(owner) = owners[slot] {
+            if assign(owner, approvers, slots, eligible, owners, visited) {
+                owners[slot] = Some(candidate);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// 所有规则是否均已满足
+pub fn is_satisfied(progress: &[QuorumRuleProgress]) -> bool {
+    progress.iter().all(|rule| rule.satisfied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group_rule(name: &str, group_id: Uuid, min_approvals: i32) -> QuorumRule {
+        QuorumRule {
+            name: Some(name.to_string()),
+            min_approvals,
+            group_id: Some(group_id),
+            user_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn test_one_from_each_group() {
+        let (owners, sre) = (Uuid::new_v4(), Uuid::new_v4());
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let groups = GroupMembers::from([(owners, vec![alice, bob]), (sre, vec![carol])]);
+        let rules = vec![
+            group_rule("service-owner", owners, 1),
+            group_rule("sre", sre, 1),
+        ];
+
+        let progress = evaluate(&rules, &groups, &[alice, bob]);
+        assert!(progress[0].satisfied);
+        assert_eq!(progress[0].approved_by, vec![alice]);
+        assert_eq!(progress[1].remaining, 1);
+        assert!(!is_satisfied(&progress));
+
+        let progress = evaluate(&rules, &groups, &[alice, bob, carol]);
+        assert!(is_satisfied(&progress));
+        assert_eq!(progress[1].approved_by, vec![carol]);
+    }
+
+    #[test]
+    fn test_approver_counts_toward_one_rule_only() {
+        let (owners, sre) = (Uuid::new_v4(), Uuid::new_v4());
+        let (both, sre_only) = (Uuid::new_v4(), Uuid::new_v4());
+        let groups = GroupMembers::from([(owners, vec![both]), (sre, vec![both, sre_only])]);
+        let rules = vec![
+            group_rule("sre", sre, 1),
+            group_rule("service-owner", owners, 1),
+        ];
+
+        let progress = evaluate(&rules, &groups, &[both]);
+        assert_eq!(progress[0].approved_by, vec![both]);
+        assert!(!is_satisfied(&progress));
+
+        // 先批准的人被重新分配到唯一能满足的规则
+        let progress = evaluate(&rules, &groups, &[both, sre_only]);
+        assert!(is_satisfied(&progress));
+        assert_eq!(progress[0].approved_by, vec![sre_only]);
+        assert_eq!(progress[1].approved_by, vec![both]);
+    }
+
+    #[test]
+    fn test_named_two_of_three() {
+        let users: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let rules = vec![QuorumRule {
+            name: None,
+            min_approvals: 2,
+            group_id: None,
+            user_ids: users.clone(),
+        }];
+        let groups = GroupMembers::new();
+
+        assert!(!is_eligible(&rules, &groups, Uuid::new_v4()));
+        assert!(is_eligible(&rules, &groups, users[2]));
+
+        let progress = evaluate(&rules, &groups, &[users[2], users[2]]);
+        assert_eq!(progress[0].remaining, 1);
+        let progress = evaluate(&rules, &groups, &[users[2], Uuid::new_v4(), users[0]]);
+        assert!(is_satisfied(&progress));
+        assert_eq!(progress[0].approved_by, vec![users[2], users[0]]);
+        assert_eq!(total_required(&rules), 2);
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        assert!(validate_rules(&[]).is_err());
+        let no_target = QuorumRule {
+            name: None,
+            min_approvals: 1,
+            group_id: None,
+            user_ids: vec![],
+        };
+        assert!(validate_rules(&[no_target]).is_err());
+        let too_many = QuorumRule {
+            name: None,
+            min_approvals: 3,
+            group_id: None,
+            user_ids: vec![Uuid::new_v4(), Uuid::new_v4()],
+        };
+        assert!(validate_rules(&[too_many]).is_err());
+        assert!(validate_rules(&[group_rule("sre", Uuid::new_v4(), 0)]).is_err());
+        assert!(validate_rules(&[group_rule("sre", Uuid::new_v4(), 2)]).is_ok());
+    }
+}
@@ -0,0 +1,90 @@
+//! 审批提醒调度
+//!
+//! 设置了超时的审批请求按时限进度（如 50%、80%）提醒尚未决策的审批人，避免请求无声超时。
+//! 提醒进度取自审批策略（`approval.reminder_percents`），创建请求时可单独覆盖；
+//! 每个进度点只提醒一次，后台任务错过多个进度点时只补发最高的一个。
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{AppError, Result};
+
+/// 单个请求允许的最大提醒次数
+pub const MAX_REMINDERS: usize = 5;
+
+/// 校验并规范化提醒进度（升序、去重）
+pub fn validate_percents(percents: &[i32]) -> Result<Vec<i32>> {
+    if percents.len() > MAX_REMINDERS {
+        return Err(AppError::validation(&format!(
+            "At most {} reminders are allowed",
+            MAX_REMINDERS
+        )));
+    }
+    if percents.iter().any(|p| !(1..=99).contains(p)) {
+        return Err(AppError::validation("Reminder percents must be between 1 and 99"));
+    }
+    let mut percents = percents.to_vec();
+    percents.sort_unstable();
+    percents.dedup();
+    Ok(percents)
+}
+
+/// 解析配置中的提醒进度（逗号分隔），忽略无效项
+pub fn parse_percents(value: &str) -> Vec<i32> {
+    let mut percents: Vec<i32> = value
+        .split(',')
+        .filter_map(|p| p.trim().parse().ok())
+        .filter(|p| (1..=99).contains(p))
+        .collect();
+    percents.sort_unstable();
+    percents.dedup();
+    percents.truncate(MAX_REMINDERS);
+    percents
+}
+
+/// 当前应发送的提醒进度：已到期且高于已发送的最高进度中取最大者
+pub fn due_percent(
+    percents: &[i32],
+    requested_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    last_sent: Option<i32>,
+) -> Option<i32> {
+    let window = expires_at - requested_at;
+    percents
+        .iter()
+        .copied()
+        .filter(|p| last_sent.map_or(true, |last| *p > last))
+        .filter(|p| requested_at + window * *p / 100 <= now)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_validate_and_parse_percents() {
+        assert_eq!(validate_percents(&[80, 50, 80]).unwrap(), vec![50, 80]);
+        assert!(validate_percents(&[0]).is_err());
+        assert!(validate_percents(&[100]).is_err());
+        assert!(validate_percents(&[10, 20, 30, 40, 50, 60]).is_err());
+
+        assert_eq!(parse_percents("80, 50,abc,150"), vec![50, 80]);
+        assert!(parse_percents("").is_empty());
+    }
+
+    #[test]
+    fn test_due_percent() {
+        let requested_at = Utc::now();
+        let expires_at = requested_at + Duration::minutes(100);
+        let at = |mins| requested_at + Duration::minutes(mins);
+
+        assert_eq!(due_percent(&[50, 80], requested_at, expires_at, at(40), None), None);
+        assert_eq!(due_percent(&[50, 80], requested_at, expires_at, at(50), None), Some(50));
+        assert_eq!(due_percent(&[50, 80], requested_at, expires_at, at(60), Some(50)), None);
+        // 错过多个进度点时只发最高的一个
+        assert_eq!(due_percent(&[50, 80], requested_at, expires_at, at(90), None), Some(80));
+        assert_eq!(due_percent(&[50, 80], requested_at, expires_at, at(90), Some(80)), None);
+    }
+}
@@ -2,23 +2,180 @@
 //! P3 阶段：审批流服务
 
 use chrono::{Duration, Utc};
-use sqlx::{Pool, Postgres};
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use sqlx::{PgExecutor, Pool, Postgres};
 use std::sync::Arc;
-use tracing::{error, info, instrument};
+use std::time::Instant;
+use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 
+use crate::config::ApprovalConfig;
 use crate::error::{AppError, Result};
 use crate::models::approval::*;
 use crate::models::asset::Host;
+use crate::models::audit::AuditLog;
 use crate::models::job::Job;
-use crate::realtime::{EventBus, RealtimeEvent};
-use crate::services::audit_service::{AuditAction, AuditService};
+use crate::realtime::{outbox, EventBus, RealtimeEvent};
+use crate::services::approval_quorum::{self, GroupMembers};
+use crate::services::approval_reminder;
+use crate::services::audit_service::{AuditAction, AuditLogParams, AuditService};
+use crate::services::environment_policy;
+use crate::services::pending_change;
+
+/// 自动审批记录的审批人名称
+pub const AUTO_APPROVER_NAME: &str = "auto-approval";
+
+/// 单次批量审批的最大请求数
+pub const MAX_BULK_APPROVALS: usize = 100;
+
+/// 每轮超时检查最多处理的审批请求数
+const EXPIRY_BATCH_SIZE: i64 = 500;
+
+/// 关键分组标记的缓存时间，其他实例修改标记后在此时间内生效
+const CRITICAL_GROUP_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 时间线中已由审批请求与审批记录表达的审计动作
+const TIMELINE_COVERED_ACTIONS: &[&str] = &[
+    "approval.create",
+    "approval.approve",
+    "approval.reject",
+    "approval.cancel",
+    "approval.auto_approve",
+];
+
+/// 作业风险评估结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobRiskAssessment {
+    pub is_production: bool,
+    pub exceeds_threshold: bool,
+    pub is_high_risk: bool,
+    pub is_critical: bool,
+    /// 目标环境策略要求强制审批
+    pub environment_policy: bool,
+    /// 模板风险等级（low/medium/high/critical），非模板作业为空
+    pub template_risk_level: Option<String>,
+}
+
+impl JobRiskAssessment {
+    /// 是否需要审批
+    pub fn requires_approval(&self) -> bool {
+        self.is_production
+            || self.exceeds_threshold
+            || self.is_high_risk
+            || self.is_critical
+            || self.environment_policy
+    }
+
+    /// 风险评分：各触发条件与模板风险等级的加权和
+    pub fn score(&self) -> u32 {
+        let mut score = 0;
+        if self.is_production {
+            score += 20;
+        }
+        if self.exceeds_threshold {
+            score += 10;
+        }
+        if self.is_critical {
+            score += 20;
+        }
+        if self.environment_policy {
+            score += 20;
+        }
+        if self.is_high_risk {
+            score += 50;
+        }
+        score
+            + match self
+                .template_risk_level
+                .as_deref()
+                .map(str::to_lowercase)
+                .as_deref()
+            {
+                Some("low") => 0,
+                Some("medium") => 10,
+                Some("high") => 30,
+                // 未知等级按最高风险处理
+                Some(_) => 50,
+                None => 0,
+            }
+    }
+
+    /// 对应的审批触发条件
+    pub fn triggers(&self) -> Vec<ApprovalTrigger> {
+        let mut triggers = Vec::new();
+        if self.is_production {
+            triggers.push(ApprovalTrigger::ProductionEnvironment);
+        }
+        if self.is_critical {
+            triggers.push(ApprovalTrigger::CriticalGroup);
+        }
+        if self.is_high_risk {
+            triggers.push(ApprovalTrigger::HighRiskCommand);
+        }
+        if self.exceeds_threshold {
+            triggers.push(ApprovalTrigger::TargetCountThreshold);
+        }
+        if self.environment_policy {
+            triggers.push(ApprovalTrigger::EnvironmentPolicy);
+        }
+        triggers
+    }
+}
+
+/// 计算模板作业的审批指纹
+///
+/// 相同模板、参数（键顺序无关）与目标主机集合得到相同指纹
+pub fn approval_fingerprint(
+    template_id: Uuid,
+    parameters: &serde_json::Value,
+    target_hosts: &[Uuid],
+) -> String {
+    let mut hosts = target_hosts.to_vec();
+    hosts.sort();
+    hosts.dedup();
+
+    let mut hasher = Sha256::new();
+    hasher.update(template_id.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(canonical_json(parameters).as_bytes());
+    hasher.update(b"\n");
+    for host in hosts {
+        hasher.update(host.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// 按键排序序列化 JSON，保证指纹与参数顺序无关
+fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(k, v)| {
+                    format!("{}:{}", serde_json::Value::from(k.as_str()), canonical_json(v))
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        serde_json::Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
 
 /// 审批服务
 pub struct ApprovalService {
     db: Pool<Postgres>,
     audit_service: Arc<AuditService>,
     event_bus: Arc<EventBus>,
+    config: ApprovalConfig,
+    /// 分组是否为关键分组（风险评估逐主机查询，缓存避免重复查询）
+    critical_groups: DashMap<Uuid, (bool, Instant)>,
 }
 
 impl ApprovalService {
@@ -32,23 +189,86 @@ impl ApprovalService {
             db,
             audit_service,
             event_bus,
+            config: ApprovalConfig::default(),
+            critical_groups: DashMap::new(),
         }
     }
 
+    /// 设置审批策略配置
+    pub fn with_config(mut self, config: ApprovalConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     /// 创建审批请求
     #[instrument(skip(self, request))]
     pub async fn create_approval_request(
         &self,
         request: CreateApprovalRequestRequest,
         requested_by: Uuid,
+    ) -> Result<ApprovalRequest> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+        let approval_request = self
+            .insert_approval_request(&mut tx, &request, requested_by)
+            .await?;
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        self.approval_request_created(&approval_request).await?;
+        Ok(approval_request)
+    }
+
+    /// 在调用方的事务中创建审批请求
+    ///
+    /// 新审批请求事件与请求在同一事务中写入发件箱；事务提交后须调用
+    /// `approval_request_created` 投递事件并记录审计
+    pub async fn insert_approval_request(
+        &self,
+        conn: &mut sqlx::PgConnection,
+        request: &CreateApprovalRequestRequest,
+        requested_by: Uuid,
     ) -> Result<ApprovalRequest> {
         info!(title = %request.title, "Creating approval request");
 
+        // 法定人数规则：校验结构与审批组，所需人数取各规则之和
+        let mut required_approvers = request.required_approvers;
+        if let Some(rules) = &request.quorum_rules {
+            approval_quorum::validate_rules(rules)?;
+            let groups = load_group_members(&self.db, rules).await?;
+            for rule in rules {
+                if let Some(group_id) = rule.group_id.filter(|id| !groups.contains_key(id)) {
+                    return Err(AppError::validation(&format!(
+                        "Approval group {} not found or inactive",
+                        group_id
+                    )));
+                }
+                let eligible = approval_quorum::eligible_approvers(rule, &groups);
+                if (eligible.len() as i32) < rule.min_approvals {
+                    return Err(AppError::validation(
+                        "Quorum rule requires more approvals than eligible approvers",
+                    ));
+                }
+            }
+            required_approvers = approval_quorum::total_required(rules);
+        }
+
         // 计算过期时间
         let expires_at = request
             .timeout_mins
             .map(|timeout_mins| Utc::now() + Duration::minutes(timeout_mins as i64));
 
+        // 提醒进度：请求未指定时取审批策略，仅对设置了超时的请求生效
+        let reminder_percents = match &request.reminder_percents {
+            Some(percents) => approval_reminder::validate_percents(percents)?,
+            None => approval_reminder::parse_percents(&self.config.reminder_percents),
+        };
+        let reminder_percents = (expires_at.is_some() && !reminder_percents.is_empty())
+            .then_some(sqlx::types::Json(reminder_percents));
+
         // 创建审批请求
         let approval_id = Uuid::new_v4();
         let approval_request = sqlx::query_as::<_, ApprovalRequest>(
@@ -57,12 +277,12 @@ impl ApprovalService {
                 id, job_id, request_type, title, description,
                 triggers, required_approvers, approval_group_id,
                 status, current_approvals, requested_by, requested_at,
-                timeout_mins, expires_at, metadata
+                timeout_mins, expires_at, metadata, quorum_rules, reminder_percents
             ) VALUES (
                 $1, $2, $3, $4, $5,
                 $6, $7, $8,
                 'pending', 0, $9, NOW(),
-                $10, $11, $12
+                $10, $11, $12, $13, $14
             ) RETURNING *
             "#,
         )
@@ -71,44 +291,53 @@ impl ApprovalService {
         .bind(&request.request_type)
         .bind(&request.title)
         .bind(&request.description)
-        .bind(&request.triggers)
-        .bind(request.required_approvers)
+        .bind(sqlx::types::Json(&request.triggers))
+        .bind(required_approvers)
         .bind(request.approval_group_id)
         .bind(requested_by)
         .bind(request.timeout_mins)
         .bind(expires_at)
         .bind(&request.metadata)
-        .fetch_one(&self.db)
+        .bind(request.quorum_rules.as_ref().map(sqlx::types::Json))
+        .bind(reminder_percents)
+        .fetch_one(&mut *conn)
         .await
         .map_err(|e| {
             error!(error = %e, "Failed to create approval request");
             AppError::database("Failed to create approval request")
         })?;
 
+        if let Some(job_id) = request.job_id {
+            let event = RealtimeEvent::NewApprovalRequest {
+                approval_id,
+                job_id: Some(job_id),
+                title: request.title.clone(),
+                requested_by,
+            };
+            outbox::enqueue(&mut *conn, &event).await?;
+        }
+
+        Ok(approval_request)
+    }
+
+    /// 审批请求所在事务提交后投递事件并记录审计
+    pub async fn approval_request_created(&self, approval_request: &ApprovalRequest) -> Result<()> {
+        self.event_bus.notify_outbox();
+
         // 记录审计
         self.audit_service
             .log_action_simple(
-                requested_by,
+                approval_request.requested_by,
                 AuditAction::ApprovalCreate,
                 Some("approval"),
-                Some(approval_id),
-                Some(&format!("Approval request: {}", request.title)),
+                Some(approval_request.id),
+                Some(&format!("Approval request: {}", approval_request.title)),
                 None,
             )
             .await?;
 
-        // 发布新审批请求事件
-        if let Some(job_id) = request.job_id {
-            self.event_bus.publish(RealtimeEvent::NewApprovalRequest {
-                approval_id,
-                job_id: Some(job_id),
-                title: request.title.clone(),
-                requested_by,
-            })?;
-        }
-
-        info!(approval_id = %approval_id, "Approval request created successfully");
-        Ok(approval_request)
+        info!(approval_id = %approval_request.id, "Approval request created successfully");
+        Ok(())
     }
 
     /// 获取审批请求详情
@@ -195,6 +424,173 @@ impl ApprovalService {
             "Processing approval"
         );
 
+        let new_status = self
+            .record_decision(
+                approval_id,
+                approver_id,
+                &approver_name,
+                &request.decision,
+                request.comment.as_deref(),
+            )
+            .await?;
+
+        // 记录审计
+        let audit_action = match request.decision {
+            ApprovalStatus::Approved => AuditAction::ApprovalApprove,
+            ApprovalStatus::Rejected => AuditAction::ApprovalReject,
+            _ => AuditAction::ApprovalCreate, // fallback
+        };
+
+        self.audit_service
+            .log_action_simple(
+                approver_id,
+                audit_action,
+                Some("approval"),
+                Some(approval_id),
+                Some(&format!("Decision: {:?}", request.decision)),
+                None,
+            )
+            .await?;
+
+        info!(
+            approval_id = %approval_id,
+            new_status = ?new_status,
+            "Approval processed successfully"
+        );
+
+        Ok(())
+    }
+
+    /// 批量审批：以同一决策和意见逐个处理待审批请求
+    ///
+    /// 单项失败不影响其他请求，结果逐项返回；整批只记录一条审计日志，列出所有受影响的请求
+    #[instrument(skip(self, request))]
+    pub async fn bulk_decide(
+        &self,
+        approver_id: Uuid,
+        approver_name: String,
+        decision: ApprovalStatus,
+        request: BulkApprovalRequest,
+    ) -> Result<BulkApprovalResponse> {
+        if !matches!(decision, ApprovalStatus::Approved | ApprovalStatus::Rejected) {
+            return Err(AppError::validation("Decision must be approved or rejected"));
+        }
+        let mut approval_ids = request.approval_ids;
+        let mut seen = std::collections::HashSet::new();
+        approval_ids.retain(|id| seen.insert(*id));
+        if approval_ids.is_empty() {
+            return Err(AppError::validation("No approval requests selected"));
+        }
+        if approval_ids.len() > MAX_BULK_APPROVALS {
+            return Err(AppError::validation(&format!(
+                "At most {} approval requests can be processed at once",
+                MAX_BULK_APPROVALS
+            )));
+        }
+
+        info!(
+            approver_id = %approver_id,
+            decision = ?decision,
+            count = approval_ids.len(),
+            "Processing bulk approval"
+        );
+
+        let mut results = Vec::with_capacity(approval_ids.len());
+        for approval_id in approval_ids {
+            let result = self
+                .record_decision(
+                    approval_id,
+                    approver_id,
+                    &approver_name,
+                    &decision,
+                    request.comment.as_deref(),
+                )
+                .await;
+            results.push(match result {
+                Ok(new_status) => BulkApprovalItemResult {
+                    approval_id,
+                    success: true,
+                    new_status: Some(new_status),
+                    error: None,
+                },
+                Err(e) => {
+                    warn!(approval_id = %approval_id, error = %e, "Bulk approval item failed");
+                    BulkApprovalItemResult {
+                        approval_id,
+                        success: false,
+                        new_status: None,
+                        error: Some(e.to_string()),
+                    }
+                }
+            });
+        }
+
+        let response = BulkApprovalResponse::from_results(results);
+        let affected: Vec<Uuid> = response
+            .results
+            .iter()
+            .filter(|r| r.success)
+            .map(|r| r.approval_id)
+            .collect();
+        let failed: Vec<Uuid> = response
+            .results
+            .iter()
+            .filter(|r| !r.success)
+            .map(|r| r.approval_id)
+            .collect();
+
+        let audit_action = if matches!(decision, ApprovalStatus::Approved) {
+            AuditAction::ApprovalApprove
+        } else {
+            AuditAction::ApprovalReject
+        };
+        let summary = format!(
+            "Bulk decision {:?}: {} succeeded, {} failed",
+            decision, response.succeeded, response.failed
+        );
+        let error_message =
+            (response.succeeded == 0).then(|| "All approval requests failed".to_string());
+        self.audit_service
+            .log_action(AuditLogParams {
+                subject_id: approver_id,
+                subject_type: "user",
+                subject_name: Some(&approver_name),
+                action: audit_action.as_str(),
+                resource_type: "approval",
+                resource_id: None,
+                resource_name: None,
+                changes: Some(serde_json::json!({
+                    "bulk": true,
+                    "decision": decision,
+                    "comment": request.comment,
+                    "approval_ids": affected,
+                    "failed_ids": failed,
+                })),
+                changes_summary: Some(&summary),
+                source_ip: None,
+                user_agent: None,
+                trace_id: None,
+                result: if error_message.is_some() {
+                    "failure"
+                } else {
+                    "success"
+                },
+                error_message: error_message.as_deref(),
+            })
+            .await?;
+
+        Ok(response)
+    }
+
+    /// 记录一条审批决策并更新请求状态，返回更新后的状态（不记录审计）
+    async fn record_decision(
+        &self,
+        approval_id: Uuid,
+        approver_id: Uuid,
+        approver_name: &str,
+        decision: &ApprovalStatus,
+        comment: Option<&str>,
+    ) -> Result<ApprovalStatus> {
         let mut tx = self.db.begin().await.map_err(|e| {
             error!(error = %e, "Failed to begin transaction");
             AppError::database("Failed to begin transaction")
@@ -238,6 +634,8 @@ impl ApprovalService {
                     error!(error = %e, "Failed to commit transaction");
                     AppError::database("Failed to commit transaction")
                 })?;
+                self.resolve_pending_change(approval_id, &ApprovalStatus::Timeout, None)
+                    .await;
 
                 return Err(AppError::validation("Approval request has expired"));
             }
@@ -260,6 +658,20 @@ impl ApprovalService {
             return Err(AppError::validation("Already approved or rejected this request"));
         }
 
+        // 配置了法定人数规则时，只有符合某条规则的审批人可以决策
+        let quorum = match &approval_req.quorum_rules {
+            Some(rules) => {
+                let groups = load_group_members(&mut *tx, rules).await?;
+                if !approval_quorum::is_eligible(rules, &groups, approver_id) {
+                    return Err(AppError::validation(
+                        "Approver is not eligible under the quorum rules",
+                    ));
+                }
+                Some((rules, groups))
+            }
+            None => None,
+        };
+
         // 创建审批记录
         sqlx::query(
             r#"
@@ -275,9 +687,9 @@ impl ApprovalService {
         .bind(Uuid::new_v4())
         .bind(approval_id)
         .bind(approver_id)
-        .bind(&approver_name)
-        .bind(request.decision.clone())
-        .bind(&request.comment)
+        .bind(approver_name)
+        .bind(decision.clone())
+        .bind(comment)
         .execute(&mut *tx)
         .await
         .map_err(|e| {
@@ -286,16 +698,24 @@ impl ApprovalService {
         })?;
 
         // 更新审批请求数量
-        let current_approvals = if matches!(request.decision, ApprovalStatus::Approved) {
+        let current_approvals = if matches!(decision, ApprovalStatus::Approved) {
             approval_req.current_approvals + 1
         } else {
             approval_req.current_approvals
         };
 
         // 判断是否完成审批
-        let new_status = if matches!(request.decision, ApprovalStatus::Rejected) {
+        let quorum_met = match &quorum {
+            Some((rules, groups)) if matches!(decision, ApprovalStatus::Approved) => {
+                let approvals = load_approvers(&mut *tx, approval_id).await?;
+                approval_quorum::is_satisfied(&approval_quorum::evaluate(rules, groups, &approvals))
+            }
+            Some(_) => false,
+            None => current_approvals >= approval_req.required_approvers,
+        };
+        let new_status = if matches!(decision, ApprovalStatus::Rejected) {
             ApprovalStatus::Rejected
-        } else if current_approvals >= approval_req.required_approvers {
+        } else if quorum_met {
             ApprovalStatus::Approved
         } else {
             ApprovalStatus::Pending
@@ -322,45 +742,487 @@ impl ApprovalService {
             AppError::database("Failed to update approval request")
         })?;
 
+        // 审批状态变更事件
+        let event = RealtimeEvent::ApprovalStatusChanged {
+            approval_id,
+            old_status: format!("{:?}", approval_req.status),
+            new_status: format!("{:?}", new_status),
+        };
+        outbox::enqueue(&mut *tx, &event).await?;
+
         tx.commit().await.map_err(|e| {
             error!(error = %e, "Failed to commit transaction");
             AppError::database("Failed to commit transaction")
         })?;
+        self.event_bus.notify_outbox();
 
-        // 记录审计
-        let audit_action = match request.decision {
-            ApprovalStatus::Approved => AuditAction::ApprovalApprove,
-            ApprovalStatus::Rejected => AuditAction::ApprovalReject,
-            _ => AuditAction::ApprovalCreate, // fallback
-        };
+        if !matches!(new_status, ApprovalStatus::Pending) {
+            self.resolve_pending_change(approval_id, &new_status, Some(approver_id))
+                .await;
+        }
 
-        self.audit_service
-            .log_action_simple(
-                approver_id,
-                audit_action,
-                Some("approval"),
-                Some(approval_id),
-                Some(&format!("Decision: {:?}", request.decision)),
-                None,
+        Ok(new_status)
+    }
+
+    /// 以审批请求包装资源变更：保存变更内容，审批通过后应用
+    #[instrument(skip(self, change, description), fields(kind = change.kind()))]
+    pub async fn request_change(
+        &self,
+        change: ResourceChange,
+        title: String,
+        description: Option<String>,
+        triggers: Vec<ApprovalTrigger>,
+        requested_by: Uuid,
+    ) -> Result<ApprovalRequest> {
+        if pending_change::has_pending(&self.db, &change).await? {
+            return Err(AppError::validation("Resource already has a change awaiting approval"));
+        }
+
+        let approval = self
+            .create_approval_request(
+                CreateApprovalRequestRequest {
+                    job_id: None,
+                    request_type: pending_change::REQUEST_TYPE.to_string(),
+                    title,
+                    description,
+                    triggers,
+                    required_approvers: self.config.change_required_approvers,
+                    approval_group_id: None,
+                    timeout_mins: Some(self.config.change_timeout_mins),
+                    metadata: serde_json::json!({
+                        "resource_type": change.resource_type(),
+                        "resource_id": change.resource_id(),
+                        "change_kind": change.kind(),
+                        "summary": change.summary(),
+                    }),
+                    quorum_rules: None,
+                    reminder_percents: None,
+                },
+                requested_by,
             )
             .await?;
 
-        // 发布审批状态变更事件
-        self.event_bus
-            .publish(RealtimeEvent::ApprovalStatusChanged {
-                approval_id,
-                old_status: format!("{:?}", approval_req.status),
-                new_status: format!("{:?}", new_status),
-            })?;
+        // 保存失败（如并发提交同一资源的变更）时取消刚创建的审批请求
+        if let Err(e) = pending_change::insert(&self.db, approval.id, &change, requested_by).await {
+            if let Err(cancel_err) = self
+                .cancel_approval_request(approval.id, requested_by)
+                .await
+            {
+                warn!(
+                    approval_id = %approval.id,
+                    error = %cancel_err,
+                    "Failed to cancel approval request for unsaved change"
+                );
+            }
+            return Err(e);
+        }
+
+        info!(approval_id = %approval.id, "Resource change awaiting approval");
+        Ok(approval)
+    }
+
+    /// 审批请求关联的资源变更
+    #[instrument(skip(self))]
+    pub async fn get_pending_change(&self, approval_id: Uuid) -> Result<Option<PendingChange>> {
+        pending_change::find_by_approval(&self.db, approval_id).await
+    }
+
+    /// 审批请求结束后处理关联的资源变更：批准则应用，否则丢弃
+    ///
+    /// 审批结果已提交，变更处理失败只记录在变更上，不影响审批结果
+    async fn resolve_pending_change(
+        &self,
+        approval_id: Uuid,
+        status: &ApprovalStatus,
+        resolved_by: Option<Uuid>,
+    ) {
+        let change = match pending_change::find_by_approval(&self.db, approval_id).await {
+            Ok(Some(change)) if change.status == pending_change::CHANGE_PENDING => change,
+            Ok(_) => return,
+            Err(e) => {
+                error!(approval_id = %approval_id, error = %e, "Failed to load pending change");
+                return;
+            }
+        };
+
+        let (outcome, error_message) = match (status, resolved_by, &change.payload) {
+            (ApprovalStatus::Approved, Some(applied_by), Some(payload)) => {
+                match pending_change::apply(&self.db, &payload.0, applied_by).await {
+                    Ok(()) => (pending_change::CHANGE_APPLIED, None),
+                    Err(e) => (pending_change::CHANGE_FAILED, Some(e.to_string())),
+                }
+            }
+            (ApprovalStatus::Approved, _, _) => {
+                (pending_change::CHANGE_FAILED, Some("Change payload is missing".to_string()))
+            }
+            _ => (pending_change::CHANGE_DISCARDED, None),
+        };
+
+        if let Err(e) = pending_change::mark_resolved(
+            &self.db,
+            change.id,
+            outcome,
+            error_message.as_deref(),
+            resolved_by,
+        )
+        .await
+        {
+            error!(change_id = %change.id, error = %e, "Failed to record change outcome");
+        }
 
         info!(
             approval_id = %approval_id,
-            new_status = ?new_status,
-            current_approvals = current_approvals,
-            "Approval processed successfully"
+            change_id = %change.id,
+            outcome,
+            "Pending change resolved"
         );
 
-        Ok(())
+        // 丢弃的变更已由审批请求的审计记录体现
+        if outcome == pending_change::CHANGE_DISCARDED {
+            return;
+        }
+        let Some(payload) = change.payload else {
+            return;
+        };
+        let action = match payload.0 {
+            ResourceChange::HostCredentialUpdate { .. } => AuditAction::HostCredentialUpdate,
+            ResourceChange::GroupDelete { .. } => AuditAction::AssetGroupDelete,
+        };
+        if let Err(e) = self
+            .audit_service
+            .log_action_simple(
+                resolved_by.unwrap_or(change.requested_by),
+                action,
+                Some(&change.resource_type),
+                Some(change.resource_id),
+                Some(&format!("{} (approval {})", change.summary, approval_id)),
+                error_message.as_deref(),
+            )
+            .await
+        {
+            warn!(change_id = %change.id, error = %e, "Failed to audit applied change");
+        }
+    }
+
+    /// 获取审批进度（法定人数规则逐条报告已计入的批准人与剩余人数）
+    #[instrument(skip(self))]
+    pub async fn get_approval_progress(&self, approval_id: Uuid) -> Result<ApprovalProgress> {
+        let request = self.get_approval_request(approval_id).await?;
+        let quorum = match &request.quorum_rules {
+            Some(rules) => {
+                let groups = load_group_members(&self.db, rules).await?;
+                let approvals = load_approvers(&self.db, approval_id).await?;
+                Some(approval_quorum::evaluate(rules, &groups, &approvals))
+            }
+            None => None,
+        };
+        let satisfied = match &quorum {
+            Some(progress) => approval_quorum::is_satisfied(progress),
+            None => request.current_approvals >= request.required_approvers,
+        };
+
+        Ok(ApprovalProgress {
+            approval_id,
+            status: request.status,
+            required_approvers: request.required_approvers,
+            current_approvals: request.current_approvals,
+            satisfied,
+            quorum,
+        })
+    }
+
+    /// 发送到期的审批提醒，返回发送数量
+    ///
+    /// 由后台任务定期调用；提醒记录与事件在同一事务中写入，唯一约束保证同一进度点只提醒一次
+    #[instrument(skip(self))]
+    pub async fn send_due_reminders(&self) -> Result<usize> {
+        let requests = sqlx::query_as::<_, ApprovalRequest>(
+            r#"
+            SELECT * FROM approval_requests
+            WHERE status = 'pending' AND reminder_percents IS NOT NULL AND expires_at > NOW()
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch approvals pending reminders");
+            AppError::database("Failed to fetch approval requests")
+        })?;
+        if requests.is_empty() {
+            return Ok(0);
+        }
+
+        let ids: Vec<Uuid> = requests.iter().map(|r| r.id).collect();
+        let last_sent: std::collections::HashMap<Uuid, i32> = sqlx::query_as::<_, (Uuid, i32)>(
+            r#"
+            SELECT approval_request_id, MAX(percent) FROM approval_reminders
+            WHERE approval_request_id = ANY($1)
+            GROUP BY approval_request_id
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch sent approval reminders");
+            AppError::database("Failed to fetch approval reminders")
+        })?
+        .into_iter()
+        .collect();
+
+        let now = Utc::now();
+        let mut sent = 0;
+        for request in requests {
+            let (Some(percents), Some(expires_at)) =
+                (&request.reminder_percents, request.expires_at)
+            else {
+                continue;
+            };
+            let Some(percent) = approval_reminder::due_percent(
+                percents,
+                request.requested_at,
+                expires_at,
+                now,
+                last_sent.get(&request.id).copied(),
+            ) else {
+                continue;
+            };
+
+            let approver_ids = self.outstanding_approvers(&request).await?;
+            let mut tx = self.db.begin().await.map_err(|e| {
+                error!(error = %e, "Failed to begin transaction");
+                AppError::database("Failed to begin transaction")
+            })?;
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO approval_reminders (approval_request_id, percent, recipients)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (approval_request_id, percent) DO NOTHING
+                "#,
+            )
+            .bind(request.id)
+            .bind(percent)
+            .bind(sqlx::types::Json(&approver_ids))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!(error = %e, approval_id = %request.id, "Failed to record approval reminder");
+                AppError::database("Failed to record approval reminder")
+            })?
+            .rows_affected();
+            if inserted == 0 {
+                continue;
+            }
+
+            let event = RealtimeEvent::ApprovalReminder {
+                approval_id: request.id,
+                job_id: request.job_id,
+                title: request.title.clone(),
+                percent,
+                expires_at,
+                approver_ids,
+            };
+            outbox::enqueue(&mut *tx, &event).await?;
+            tx.commit().await.map_err(|e| {
+                error!(error = %e, "Failed to commit transaction");
+                AppError::database("Failed to commit transaction")
+            })?;
+            sent += 1;
+        }
+
+        if sent > 0 {
+            self.event_bus.notify_outbox();
+        }
+        Ok(sent)
+    }
+
+    /// 将已过期的待审批请求标记为超时，返回本轮过期的请求
+    ///
+    /// 由后台任务定期调用；状态变更事件与发给申请人的超时通知在同一事务中写入发件箱，
+    /// 已被其他实例或审批操作锁定的请求留到下一轮处理
+    #[instrument(skip(self))]
+    pub async fn expire_stale_requests(&self) -> Result<Vec<ApprovalRequest>> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+        let expired = sqlx::query_as::<_, ApprovalRequest>(
+            r#"
+            UPDATE approval_requests
+            SET status = 'timeout', completed_at = NOW(), updated_at = NOW()
+            WHERE id IN (
+                SELECT id FROM approval_requests
+                WHERE status = 'pending' AND expires_at < NOW()
+                ORDER BY expires_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(EXPIRY_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to expire approval requests");
+            AppError::database("Failed to expire approval requests")
+        })?;
+        if expired.is_empty() {
+            return Ok(expired);
+        }
+
+        for request in &expired {
+            let status_changed = RealtimeEvent::ApprovalStatusChanged {
+                approval_id: request.id,
+                old_status: format!("{:?}", ApprovalStatus::Pending),
+                new_status: format!("{:?}", ApprovalStatus::Timeout),
+            };
+            outbox::enqueue(&mut *tx, &status_changed).await?;
+            let Some(expires_at) = request.expires_at else {
+                continue;
+            };
+            let notice = RealtimeEvent::ApprovalExpired {
+                approval_id: request.id,
+                job_id: request.job_id,
+                title: request.title.clone(),
+                requested_by: request.requested_by,
+                expires_at,
+            };
+            outbox::enqueue(&mut *tx, &notice).await?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        self.event_bus.notify_outbox();
+
+        for request in &expired {
+            self.resolve_pending_change(request.id, &ApprovalStatus::Timeout, None)
+                .await;
+        }
+        Ok(expired)
+    }
+
+    /// 获取审批请求已发送的提醒
+    #[instrument(skip(self))]
+    pub async fn list_reminders(&self, approval_id: Uuid) -> Result<Vec<ApprovalReminder>> {
+        sqlx::query_as::<_, ApprovalReminder>(
+            "SELECT * FROM approval_reminders WHERE approval_request_id = $1 ORDER BY sent_at",
+        )
+        .bind(approval_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, approval_id = %approval_id, "Failed to fetch approval reminders");
+            AppError::database("Failed to fetch approval reminders")
+        })
+    }
+
+    /// 获取审批时间线：创建、提醒、各审批人决策、取消/超时、关联作业开始执行及其他审计记录
+    #[instrument(skip(self))]
+    pub async fn get_approval_timeline(&self, approval_id: Uuid) -> Result<ApprovalTimeline> {
+        let request = self.get_approval_request(approval_id).await?;
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, approval_id = %approval_id, "Failed to load approval timeline");
+            AppError::database("Failed to load approval timeline")
+        };
+
+        let requester_name =
+            sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = $1")
+                .bind(request.requested_by)
+                .fetch_optional(&self.db)
+                .await
+                .map_err(map_err)?;
+        let records = sqlx::query_as::<_, ApprovalRecord>(
+            "SELECT * FROM approval_records WHERE approval_request_id = $1 ORDER BY approved_at",
+        )
+        .bind(approval_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(map_err)?;
+        let reminders = self.list_reminders(approval_id).await?;
+        let audit_logs = sqlx::query_as::<_, AuditLog>(
+            r#"
+            SELECT * FROM audit_logs
+            WHERE resource_type = 'approval' AND resource_id = $1
+            ORDER BY occurred_at
+            "#,
+        )
+        .bind(approval_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(map_err)?;
+        // 关联作业可能已归档
+        let job_started_at = match request.job_id {
+            Some(job_id) => sqlx::query_scalar::<_, Option<chrono::DateTime<Utc>>>(
+                r#"
+                SELECT started_at FROM jobs WHERE id = $1
+                UNION ALL
+                SELECT started_at FROM jobs_archive WHERE id = $1
+                "#,
+            )
+            .bind(job_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(map_err)?
+            .flatten(),
+            None => None,
+        };
+
+        let events = build_timeline(
+            &request,
+            requester_name,
+            &records,
+            &reminders,
+            &audit_logs,
+            job_started_at,
+        );
+        Ok(ApprovalTimeline {
+            approval_id,
+            status: request.status,
+            events,
+        })
+    }
+
+    /// 尚未决策的合格审批人：法定人数规则的合格审批人或审批组成员，排除已决策者
+    async fn outstanding_approvers(&self, request: &ApprovalRequest) -> Result<Vec<Uuid>> {
+        let mut eligible: Vec<Uuid> = Vec::new();
+        if let Some(rules) = &request.quorum_rules {
+            let groups = load_group_members(&self.db, rules).await?;
+            for rule in rules.iter() {
+                for approver in approval_quorum::eligible_approvers(rule, &groups) {
+                    if !eligible.contains(&approver) {
+                        eligible.push(approver);
+                    }
+                }
+            }
+        } else if let Some(group_id) = request.approval_group_id {
+            let members = sqlx::query_scalar::<_, sqlx::types::Json<Vec<Uuid>>>(
+                "SELECT member_ids FROM approval_groups WHERE id = $1 AND is_active = true",
+            )
+            .bind(group_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to fetch approval group members");
+                AppError::database("Failed to fetch approval group members")
+            })?;
+            eligible = members.map(|m| m.0).unwrap_or_default();
+        }
+
+        let decided = sqlx::query_scalar::<_, Uuid>(
+            "SELECT approver_id FROM approval_records WHERE approval_request_id = $1",
+        )
+        .bind(request.id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch approval records");
+            AppError::database("Failed to fetch approval records")
+        })?;
+        eligible.retain(|id| !decided.contains(id));
+        Ok(eligible)
     }
 
     /// 取消审批请求
@@ -387,6 +1249,8 @@ impl ApprovalService {
         if updated.rows_affected() == 0 {
             return Err(AppError::validation("Approval request cannot be cancelled"));
         }
+        self.resolve_pending_change(approval_id, &ApprovalStatus::Cancelled, Some(cancelled_by))
+            .await;
 
         // 记录审计
         self.audit_service
@@ -462,6 +1326,20 @@ impl ApprovalService {
         job: &Job,
         target_hosts: &[Host],
     ) -> Result<bool> {
+        Ok(self
+            .assess_job_risk(job, target_hosts, None)
+            .await
+            .requires_approval())
+    }
+
+    /// 评估作业风险
+    #[instrument(skip(self))]
+    pub async fn assess_job_risk(
+        &self,
+        job: &Job,
+        target_hosts: &[Host],
+        template_risk_level: Option<&str>,
+    ) -> JobRiskAssessment {
         // 检查是否为生产环境
         let is_production = target_hosts
             .iter()
@@ -484,21 +1362,243 @@ impl ApprovalService {
             }
         }
 
-        let requires_approval =
-            is_production || exceeds_threshold || is_high_risk || is_critical;
+        // 检查目标环境策略是否要求强制审批，策略读取失败时按需要审批处理
+        let environment_policy =
+            match environment_policy::effective_policy(&self.db, target_hosts).await {
+                Ok(policy) => policy.require_approval,
+                Err(e) => {
+                    warn!(job_id = %job.id, error = %e, "Failed to load environment policies");
+                    true
+                }
+            };
 
-        if requires_approval {
+        let assessment = JobRiskAssessment {
+            is_production,
+            exceeds_threshold,
+            is_high_risk,
+            is_critical,
+            environment_policy,
+            template_risk_level: template_risk_level.map(str::to_string),
+        };
+
+        if assessment.requires_approval() {
             info!(
                 job_id = %job.id,
                 is_production,
                 exceeds_threshold,
                 is_high_risk,
                 is_critical,
+                environment_policy,
+                risk_score = assessment.score(),
                 "Job requires approval"
             );
         }
 
-        Ok(requires_approval)
+        assessment
+    }
+
+    /// 尝试自动审批低风险的重复作业
+    ///
+    /// 策略启用、风险评分低于上限，且回溯窗口内存在相同指纹的人工批准作业时，
+    /// 创建一条引用原始审批的自动审批记录并返回新审批请求 ID
+    #[instrument(skip(self, job, assessment))]
+    pub async fn try_auto_approve(
+        &self,
+        job: &Job,
+        fingerprint: &str,
+        assessment: &JobRiskAssessment,
+    ) -> Result<Option<Uuid>> {
+        if !self.config.auto_approval_enabled {
+            return Ok(None);
+        }
+
+        let risk_score = assessment.score();
+        if risk_score >= self.config.auto_approval_max_risk_score {
+            info!(
+                job_id = %job.id,
+                risk_score,
+                max_risk_score = self.config.auto_approval_max_risk_score,
+                "Risk score too high for auto-approval"
+            );
+            return Ok(None);
+        }
+
+        // 查找窗口内相同指纹、由人工批准的审批请求（自动审批不能作为来源）
+        let source = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT ar.id, r.approver_id
+            FROM approval_requests ar
+            JOIN jobs j ON j.id = ar.job_id
+            JOIN LATERAL (
+                SELECT approver_id FROM approval_records
+                WHERE approval_request_id = ar.id
+                  AND decision = 'approved' AND is_automatic = FALSE
+                ORDER BY approved_at DESC
+                LIMIT 1
+            ) r ON TRUE
+            WHERE j.approval_fingerprint = $1
+              AND j.id <> $2
+              AND ar.status = 'approved'
+              AND ar.completed_at >= NOW() - make_interval(days => $3::int)
+            ORDER BY ar.completed_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(fingerprint)
+        .bind(job.id)
+        .bind(self.config.auto_approval_lookback_days)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to look up previous approval");
+            AppError::database("Failed to look up previous approval")
+        })?;
+
+        let Some((source_approval_id, source_approver_id)) = source else {
+            return Ok(None);
+        };
+
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        let approval_id = Uuid::new_v4();
+        let metadata = serde_json::json!({
+            "auto_approved": true,
+            "source_approval_id": source_approval_id,
+            "approval_fingerprint": fingerprint,
+            "risk_score": risk_score,
+            "template_risk_level": assessment.template_risk_level,
+        });
+        sqlx::query(
+            r#"
+            INSERT INTO approval_requests (
+                id, job_id, request_type, title, description,
+                triggers, required_approvers,
+                status, current_approvals, requested_by, requested_at,
+                completed_at, metadata
+            ) VALUES (
+                $1, $2, 'job_execution', $3, $4,
+                $5, 1,
+                'approved', 1, $6, NOW(),
+                NOW(), $7
+            )
+            "#,
+        )
+        .bind(approval_id)
+        .bind(job.id)
+        .bind(format!("Auto-approved: {}", job.name))
+        .bind(format!("Repeat of job approved in request {}", source_approval_id))
+        .bind(sqlx::types::Json(assessment.triggers()))
+        .bind(job.created_by)
+        .bind(sqlx::types::Json(&metadata))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to create auto-approval request");
+            AppError::database("Failed to create approval request")
+        })?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO approval_records (
+                id, approval_request_id, approver_id, approver_name,
+                decision, comment, is_automatic, source_approval_id, approved_at
+            ) VALUES (
+                $1, $2, $3, $4,
+                'approved', $5, TRUE, $6, NOW()
+            )
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(approval_id)
+        .bind(source_approver_id)
+        .bind(AUTO_APPROVER_NAME)
+        .bind(format!(
+            "Auto-approved from approval {} (risk score {})",
+            source_approval_id, risk_score
+        ))
+        .bind(source_approval_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to create auto-approval record");
+            AppError::database("Failed to create approval record")
+        })?;
+
+        let event = RealtimeEvent::ApprovalStatusChanged {
+            approval_id,
+            old_status: format!("{:?}", ApprovalStatus::Pending),
+            new_status: format!("{:?}", ApprovalStatus::Approved),
+        };
+        outbox::enqueue(&mut *tx, &event).await?;
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        self.event_bus.notify_outbox();
+
+        // 自动审批使用独立的审计动作，便于与人工审批区分
+        self.audit_service
+            .log_action_simple(
+                job.created_by,
+                AuditAction::ApprovalAutoApprove,
+                Some("approval"),
+                Some(approval_id),
+                Some(&format!(
+                    "Job {} auto-approved from approval {} (risk score {})",
+                    job.id, source_approval_id, risk_score
+                )),
+                None,
+            )
+            .await?;
+
+        info!(
+            job_id = %job.id,
+            approval_id = %approval_id,
+            source_approval_id = %source_approval_id,
+            risk_score,
+            "Job auto-approved"
+        );
+
+        Ok(Some(approval_id))
+    }
+
+    /// 在作业转为等待审批的事务中创建关联的审批请求
+    ///
+    /// 事务提交后须调用 `approval_request_created`
+    #[instrument(skip(self, conn, job, assessment), fields(job_id = %job.id))]
+    pub async fn request_job_approval(
+        &self,
+        conn: &mut sqlx::PgConnection,
+        job: &Job,
+        assessment: &JobRiskAssessment,
+    ) -> Result<ApprovalRequest> {
+        self.insert_approval_request(
+            conn,
+            &CreateApprovalRequestRequest {
+                job_id: Some(job.id),
+                request_type: "job_execution".to_string(),
+                title: format!("Job execution: {}", job.name),
+                description: job.description.clone(),
+                triggers: assessment.triggers(),
+                required_approvers: self.config.job_required_approvers,
+                approval_group_id: None,
+                timeout_mins: Some(self.config.job_timeout_mins),
+                metadata: serde_json::json!({
+                    "job_type": job.job_type,
+                    "target_count": job.total_tasks,
+                    "risk_score": assessment.score(),
+                    "template_risk_level": assessment.template_risk_level,
+                }),
+                quorum_rules: None,
+                reminder_percents: None,
+            },
+            job.created_by,
+        )
+        .await
     }
 
     /// 判断是否为高风险命令
@@ -522,23 +1622,37 @@ impl ApprovalService {
             .any(|pattern| command_lower.contains(&pattern.to_lowercase()))
     }
 
-    /// 检查分组是否为关键分组
+    /// 检查分组是否为关键分组（资产组的 is_critical 标记）
     ///
-    /// 关键分组的作业需要审批
-    /// 检查条件：
-    /// 1. 分组标记为 is_critical = true
-    /// 2. 分组名称包含 "critical" 或 "prod"
-    async fn is_critical_group(&self, group_id: uuid::Uuid) -> bool {
-        sqlx::query_scalar::<_, bool>(
-            "SELECT EXISTS(
-                SELECT 1 FROM assets_groups
-                WHERE id = $1 AND (is_critical = true OR name ILIKE '%prod%' OR name ILIKE '%critical%')
-            )"
+    /// 关键分组的作业需要审批；查询失败时按关键分组处理
+    async fn is_critical_group(&self, group_id: Uuid) -> bool {
+        if let Some(entry) = self.critical_groups.get(&group_id) {
+            if entry.1.elapsed() < CRITICAL_GROUP_CACHE_TTL {
+                return entry.0;
+            }
+        }
+
+        let is_critical = match sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM assets_groups WHERE id = $1 AND is_critical)",
         )
         .bind(group_id)
         .fetch_one(&self.db)
         .await
-        .unwrap_or(false)
+        {
+            Ok(is_critical) => is_critical,
+            Err(e) => {
+                warn!(group_id = %group_id, error = %e, "Failed to check critical group");
+                return true;
+            }
+        };
+        self.critical_groups
+            .insert(group_id, (is_critical, Instant::now()));
+        is_critical
+    }
+
+    /// 使缓存的关键分组标记失效（修改或删除资产组后调用）
+    pub fn invalidate_critical_group(&self, group_id: Uuid) {
+        self.critical_groups.remove(&group_id);
     }
 
     /// 获取审批统计
@@ -642,6 +1756,186 @@ impl ApprovalService {
     }
 }
 
+/// 读取规则引用的启用中审批组成员
+async fn load_group_members<'e, E>(executor: E, rules: &[QuorumRule]) -> Result<GroupMembers>
+where
+    E: PgExecutor<'e>,
+{
+    let group_ids: Vec<Uuid> = rules.iter().filter_map(|rule| rule.group_id).collect();
+    if group_ids.is_empty() {
+        return Ok(GroupMembers::new());
+    }
+
+    let rows = sqlx::query_as::<_, (Uuid, sqlx::types::Json<Vec<Uuid>>)>(
+        "SELECT id, member_ids FROM approval_groups WHERE id = ANY($1) AND is_active = true",
+    )
+    .bind(&group_ids)
+    .fetch_all(executor)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to fetch approval group members");
+        AppError::database("Failed to fetch approval group members")
+    })?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, members)| (id, members.0))
+        .collect())
+}
+
+/// 合并各来源的事件并按时间排序（同一时刻保持来源顺序）
+fn build_timeline(
+    request: &ApprovalRequest,
+    requester_name: Option<String>,
+    records: &[ApprovalRecord],
+    reminders: &[ApprovalReminder],
+    audit_logs: &[AuditLog],
+    job_started_at: Option<chrono::DateTime<Utc>>,
+) -> Vec<ApprovalTimelineEvent> {
+    let mut events = vec![ApprovalTimelineEvent {
+        at: request.requested_at,
+        kind: ApprovalTimelineEventKind::Created,
+        actor_id: Some(request.requested_by),
+        actor_name: requester_name,
+        summary: format!("Approval requested: {}", request.title),
+        comment: request.description.clone(),
+        details: serde_json::json!({
+            "required_approvers": request.required_approvers,
+            "timeout_mins": request.timeout_mins,
+            "expires_at": request.expires_at,
+        }),
+    }];
+
+    for reminder in reminders {
+        events.push(ApprovalTimelineEvent {
+            at: reminder.sent_at,
+            kind: ApprovalTimelineEventKind::ReminderSent,
+            actor_id: None,
+            actor_name: None,
+            summary: format!(
+                "Reminder sent to {} outstanding approver(s) at {}% of the approval window",
+                reminder.recipients.len(),
+                reminder.percent
+            ),
+            comment: None,
+            details: serde_json::json!({
+                "percent": reminder.percent,
+                "recipients": reminder.recipients,
+            }),
+        });
+    }
+
+    for record in records {
+        let (kind, verb) = match record.decision {
+            ApprovalStatus::Rejected => (ApprovalTimelineEventKind::Rejected, "Rejected"),
+            _ => (ApprovalTimelineEventKind::Approved, "Approved"),
+        };
+        let summary = if record.is_automatic {
+            format!("{} automatically", verb)
+        } else {
+            format!("{} by {}", verb, record.approver_name)
+        };
+        events.push(ApprovalTimelineEvent {
+            at: record.approved_at,
+            kind,
+            actor_id: Some(record.approver_id),
+            actor_name: Some(record.approver_name.clone()),
+            summary,
+            comment: record.comment.clone(),
+            details: serde_json::json!({
+                "is_automatic": record.is_automatic,
+                "source_approval_id": record.source_approval_id,
+            }),
+        });
+    }
+
+    let completed_at = request.completed_at.or(request.expires_at);
+    match (&request.status, completed_at) {
+        (ApprovalStatus::Cancelled, Some(at)) => {
+            let cancelled_by = audit_logs
+                .iter()
+                .find(|log| log.action == AuditAction::ApprovalCancel.as_str());
+            events.push(ApprovalTimelineEvent {
+                at,
+                kind: ApprovalTimelineEventKind::Cancelled,
+                actor_id: cancelled_by.map(|log| log.subject_id),
+                actor_name: cancelled_by.and_then(|log| log.subject_name.clone()),
+                summary: "Approval request cancelled".to_string(),
+                comment: None,
+                details: serde_json::json!({}),
+            });
+        }
+        (ApprovalStatus::Timeout, Some(at)) => {
+            events.push(ApprovalTimelineEvent {
+                at,
+                kind: ApprovalTimelineEventKind::Expired,
+                actor_id: None,
+                actor_name: None,
+                summary: "Approval request expired without a decision".to_string(),
+                comment: None,
+                details: serde_json::json!({
+                    "current_approvals": request.current_approvals,
+                    "required_approvers": request.required_approvers,
+                }),
+            });
+        }
+        _ => {}
+    }
+
+    for log in audit_logs
+        .iter()
+        .filter(|log| !TIMELINE_COVERED_ACTIONS.contains(&log.action.as_str()))
+    {
+        events.push(ApprovalTimelineEvent {
+            at: log.occurred_at,
+            kind: ApprovalTimelineEventKind::Audit,
+            actor_id: Some(log.subject_id),
+            actor_name: log.subject_name.clone(),
+            summary: log
+                .changes_summary
+                .clone()
+                .unwrap_or_else(|| log.action.clone()),
+            comment: None,
+            details: serde_json::json!({
+                "action": log.action,
+                "result": log.result,
+                "changes": log.changes,
+            }),
+        });
+    }
+
+    if let (Some(job_id), Some(at)) = (request.job_id, job_started_at) {
+        events.push(ApprovalTimelineEvent {
+            at,
+            kind: ApprovalTimelineEventKind::JobStarted,
+            actor_id: None,
+            actor_name: None,
+            summary: "Linked job started executing".to_string(),
+            comment: None,
+            details: serde_json::json!({ "job_id": job_id }),
+        });
+    }
+
+    events.sort_by_key(|event| event.at);
+    events
+}
+
+/// 按批准时间顺序读取已批准的审批人
+async fn load_approvers<'e, E>(executor: E, approval_id: Uuid) -> Result<Vec<Uuid>>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query_scalar::<_, Uuid>(
+        "SELECT approver_id FROM approval_records WHERE approval_request_id = $1 AND decision = 'approved' ORDER BY approved_at, created_at",
+    )
+    .bind(approval_id)
+    .fetch_all(executor)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to fetch approval records");
+        AppError::database("Failed to fetch approval records")
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -682,6 +1976,7 @@ mod tests {
             (ApprovalTrigger::CriticalGroup, "CriticalGroup"),
             (ApprovalTrigger::HighRiskCommand, "HighRiskCommand"),
             (ApprovalTrigger::TargetCountThreshold, "TargetCountThreshold"),
+            (ApprovalTrigger::EnvironmentPolicy, "EnvironmentPolicy"),
             (ApprovalTrigger::CustomRule, "CustomRule"),
         ];
 
@@ -755,6 +2050,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_job_risk_assessment_score() {
+        let low = JobRiskAssessment {
+            is_production: true,
+            template_risk_level: Some("low".to_string()),
+            ..Default::default()
+        };
+        assert!(low.requires_approval());
+        assert_eq!(low.score(), 20);
+        assert!(matches!(low.triggers()[..], [ApprovalTrigger::ProductionEnvironment]));
+
+        let risky = JobRiskAssessment {
+            is_production: true,
+            is_high_risk: true,
+            template_risk_level: Some("HIGH".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(risky.score(), 100);
+
+        let unknown_level = JobRiskAssessment {
+            template_risk_level: Some("unknown".to_string()),
+            ..Default::default()
+        };
+        assert!(!unknown_level.requires_approval());
+        assert_eq!(unknown_level.score(), 50);
+
+        let policy = JobRiskAssessment {
+            environment_policy: true,
+            ..Default::default()
+        };
+        assert!(policy.requires_approval());
+        assert_eq!(policy.score(), 20);
+        assert!(matches!(policy.triggers()[..], [ApprovalTrigger::EnvironmentPolicy]));
+    }
+
+    #[test]
+    fn test_approval_fingerprint_is_order_independent() {
+        let template_id = Uuid::new_v4();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let params = serde_json::json!({"service": "nginx", "opts": {"x": 1, "y": [1, 2]}});
+        let reordered: serde_json::Value =
+            serde_json::from_str(r#"{"opts": {"y": [1, 2], "x": 1}, "service": "nginx"}"#).unwrap();
+
+        let fp = approval_fingerprint(template_id, &params, &[a, b]);
+        assert_eq!(fp.len(), 64);
+        assert_eq!(fp, approval_fingerprint(template_id, &reordered, &[b, a]));
+
+        let other_params = serde_json::json!({"service": "redis", "opts": {"x": 1, "y": [1, 2]}});
+        assert_ne!(fp, approval_fingerprint(template_id, &other_params, &[a, b]));
+        assert_ne!(fp, approval_fingerprint(template_id, &params, &[a]));
+        assert_ne!(fp, approval_fingerprint(Uuid::new_v4(), &params, &[a, b]));
+    }
+
     #[test]
     fn test_create_approval_request_request() {
         let request = CreateApprovalRequestRequest {
@@ -770,6 +2118,8 @@ mod tests {
             approval_group_id: Some(Uuid::new_v4()),
             timeout_mins: Some(60),
             metadata: serde_json::json!({"environment": "production", "risk_level": "high"}),
+            quorum_rules: None,
+            reminder_percents: None,
         };
 
         assert_eq!(request.request_type, "job_execution");
@@ -831,6 +2181,11 @@ mod tests {
             requires_approval: true,
             applicable_environments: vec!["production".to_string(), "staging".to_string()],
             applicable_groups: vec![Uuid::new_v4()],
+            extends_template_id: None,
+            prepend_content: None,
+            append_content: None,
+            includes: vec![],
+            exit_code_rules: None,
         };
 
         assert_eq!(request.name, "Deploy Application");
@@ -853,6 +2208,12 @@ mod tests {
             requires_approval: Some(false),
             applicable_environments: None,
             applicable_groups: None,
+            extends_template_id: None,
+            clear_extends: false,
+            prepend_content: None,
+            append_content: None,
+            includes: None,
+            exit_code_rules: None,
         };
 
         assert_eq!(request.name, Some("Updated Name".to_string()));
@@ -868,6 +2229,12 @@ mod tests {
             target_hosts: vec![Uuid::new_v4(), Uuid::new_v4()],
             target_groups: vec![],
             tags: vec!["deploy".to_string()],
+            on_success_job_template: None,
+            on_failure_job_template: None,
+            singleton_key: None,
+            singleton_policy: Default::default(),
+            drift_check: false,
+            verbose_trace: false,
         };
 
         assert_eq!(request.target_hosts.len(), 2);
@@ -932,6 +2299,8 @@ mod tests {
             updated_at: Utc::now(),
             completed_at: None,
             metadata: Json(serde_json::json!({})),
+            quorum_rules: None,
+            reminder_percents: Some(Json(vec![50, 80])),
         };
 
         assert_eq!(request.status, ApprovalStatus::Pending);
@@ -948,6 +2317,8 @@ mod tests {
             approver_name: "John Doe".to_string(),
             decision: ApprovalStatus::Approved,
             comment: Some("Approved".to_string()),
+            is_automatic: false,
+            source_approval_id: None,
             approved_at: Utc::now(),
             created_at: Utc::now(),
         };
@@ -957,6 +2328,101 @@ mod tests {
         assert!(record.comment.is_some());
     }
 
+    #[test]
+    fn test_build_timeline_orders_sources() {
+        let requested_at = Utc::now() - chrono::Duration::minutes(60);
+        let at = |mins| requested_at + chrono::Duration::minutes(mins);
+        let approval_id = Uuid::new_v4();
+        let approver_id = Uuid::new_v4();
+        let request = ApprovalRequest {
+            id: approval_id,
+            job_id: Some(Uuid::new_v4()),
+            request_type: "job_execution".to_string(),
+            title: "Restart database".to_string(),
+            description: None,
+            triggers: Json(vec![ApprovalTrigger::HighRiskCommand]),
+            required_approvers: 2,
+            approval_group_id: None,
+            status: ApprovalStatus::Timeout,
+            current_approvals: 1,
+            requested_by: Uuid::new_v4(),
+            requested_at,
+            timeout_mins: Some(40),
+            expires_at: Some(at(40)),
+            created_at: requested_at,
+            updated_at: at(40),
+            completed_at: Some(at(41)),
+            metadata: Json(serde_json::json!({})),
+            quorum_rules: None,
+            reminder_percents: Some(Json(vec![50])),
+        };
+        let records = vec![ApprovalRecord {
+            id: Uuid::new_v4(),
+            approval_request_id: approval_id,
+            approver_id,
+            approver_name: "alice".to_string(),
+            decision: ApprovalStatus::Approved,
+            comment: Some("Looks safe".to_string()),
+            is_automatic: false,
+            source_approval_id: None,
+            approved_at: at(25),
+            created_at: at(25),
+        }];
+        let reminders = vec![ApprovalReminder {
+            id: Uuid::new_v4(),
+            approval_request_id: approval_id,
+            percent: 50,
+            recipients: Json(vec![Uuid::new_v4()]),
+            sent_at: at(20),
+        }];
+        let audit = |action: &str, mins| AuditLog {
+            id: Uuid::new_v4(),
+            subject_id: approver_id,
+            subject_type: "user".to_string(),
+            subject_name: Some("alice".to_string()),
+            action: action.to_string(),
+            resource_type: "approval".to_string(),
+            resource_id: Some(approval_id),
+            resource_name: None,
+            changes: None,
+            changes_summary: Some("Escalated to on-call".to_string()),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            request_id: None,
+            result: "success".to_string(),
+            error_message: None,
+            occurred_at: at(mins),
+        };
+        let audit_logs = vec![
+            audit("approval.approve", 25),
+            audit("approval.escalate", 30),
+        ];
+
+        let events = build_timeline(
+            &request,
+            Some("bob".to_string()),
+            &records,
+            &reminders,
+            &audit_logs,
+            None,
+        );
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ApprovalTimelineEventKind::Created,
+                ApprovalTimelineEventKind::ReminderSent,
+                ApprovalTimelineEventKind::Approved,
+                ApprovalTimelineEventKind::Audit,
+                ApprovalTimelineEventKind::Expired,
+            ]
+        );
+        assert_eq!(events[0].actor_name.as_deref(), Some("bob"));
+        assert_eq!(events[2].comment.as_deref(), Some("Looks safe"));
+        assert_eq!(events[3].summary, "Escalated to on-call");
+    }
+
     #[test]
     fn test_approval_group_model() {
         let group = ApprovalGroup {
@@ -998,6 +2464,15 @@ mod tests {
             created_by: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            extends_template_id: None,
+            prepend_content: None,
+            append_content: None,
+            includes: Json(vec![]),
+            exit_code_rules: None,
+            deprecated_at: None,
+            deprecated_by: None,
+            deprecation_reason: None,
+            replacement_template_id: None,
         };
 
         assert_eq!(template.name, "Standard Deploy");
@@ -0,0 +1,284 @@
+//! 多平台产物变体
+//!
+//! 同一版本号与产物类型（version + artifact_type）下按目标平台（arch/os）区分的多个产物
+//! 视为同一逻辑版本的变体。部署作业引用其中任一变体，执行时按目标主机的架构（清单采集作业收集）
+//! 与操作系统（os_type）为每台主机选择变体，命令与脚本中的 `{{artifact.path}}` 等引用
+//! 解析为所选变体。
+//! 选择规则：
+//! - 架构匹配的变体优先，其次为与平台无关（arch 为空）的变体；同等条件下操作系统一致的优先
+//! - 变体未指定操作系统或主机未记录操作系统时视为匹配
+//! - 主机架构未知时，仅当候选变体只涉及一种架构时才选择
+
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::{asset::Host, build::ArtifactVariant},
+    services::host_vars,
+};
+
+const ARTIFACT_PREFIX: &str = "artifact.";
+
+/// 可引用的产物属性
+pub const ARTIFACT_ATTRIBUTES: &[&str] = &["id", "name", "version", "path", "sha256", "arch", "os"];
+
+/// 校验失败时最多列出的主机数
+const MAX_REPORTED_HOSTS: usize = 10;
+
+/// 查询产物所属逻辑版本的全部变体（产物不存在时返回空）
+pub async fn list_variants(db: &PgPool, artifact_id: Uuid) -> Result<Vec<ArtifactVariant>> {
+    sqlx::query_as::<_, ArtifactVariant>(
+        r#"
+        SELECT v.id, v.artifact_name, v.artifact_type, v.artifact_path, v.artifact_size,
+               v.artifact_hash, v.version, v.arch, v.os
+        FROM build_artifacts a
+        JOIN build_artifacts v
+          ON v.id = a.id
+          OR (a.version IS NOT NULL AND v.version = a.version AND v.artifact_type = a.artifact_type)
+        WHERE a.id = $1
+        ORDER BY v.arch, v.os
+        "#,
+    )
+    .bind(artifact_id)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, artifact_id = %artifact_id, "Failed to list artifact variants");
+        AppError::database("Failed to list artifact variants")
+    })
+}
+
+/// 按主机的架构与操作系统选择变体，无可用变体时返回原因
+pub fn select_variant<'a>(
+    variants: &'a [ArtifactVariant],
+    arch: Option<&str>,
+    os: Option<&str>,
+) -> std::result::Result<&'a ArtifactVariant, String> {
+    let arch = arch.map(common::normalize_arch).filter(|a| !a.is_empty());
+    let os = os.map(common::normalize_os).filter(|o| !o.is_empty());
+
+    let candidates: Vec<&ArtifactVariant> = variants
+        .iter()
+        .filter(|v| v.os.is_empty() || os.as_ref().map_or(true, |os| &v.os == os))
+        .filter(|v| v.arch.is_empty() || arch.as_ref().map_or(true, |arch| &v.arch == arch))
+        .collect();
+
+    if arch.is_none() {
+        let mut arches: Vec<&str> = candidates
+            .iter()
+            .map(|v| v.arch.as_str())
+            .filter(|a| !a.is_empty())
+            .collect();
+        arches.sort_unstable();
+        arches.dedup();
+        if arches.len() > 1 {
+            return Err(format!(
+                "host architecture is unknown and variants exist for {}; run an inventory job to collect it",
+                arches.join(", ")
+            ));
+        }
+    }
+
+    // 架构一致优先于平台无关，其次操作系统一致优先
+    candidates
+        .into_iter()
+        .max_by_key(|v| (!v.arch.is_empty(), !v.os.is_empty()))
+        .ok_or_else(|| {
+            format!(
+                "no variant for {}/{}",
+                os.as_deref().unwrap_or("any"),
+                arch.as_deref().unwrap_or("any")
+            )
+        })
+}
+
+/// 为执行任务的主机选择变体
+pub async fn select_for_host(
+    db: &PgPool,
+    artifact_id: Uuid,
+    host: &Host,
+) -> Result<ArtifactVariant> {
+    let variants = list_variants(db, artifact_id).await?;
+    if variants.is_empty() {
+        return Err(AppError::validation(&format!("Artifact {} not found", artifact_id)));
+    }
+    select_variant(&variants, host.arch.as_deref(), host.os_type.as_deref())
+        .cloned()
+        .map_err(|reason| {
+            AppError::validation(&format!(
+                "No artifact variant for host {}: {}",
+                host.identifier, reason
+            ))
+        })
+}
+
+/// 创建部署作业时校验每台目标主机都有可用的变体
+pub async fn check_targets(
+    db: &PgPool,
+    artifact_id: Uuid,
+    target_hosts: &[Uuid],
+    target_groups: &[Uuid],
+) -> Result<()> {
+    let variants = list_variants(db, artifact_id).await?;
+    let hosts: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT identifier, arch, os_type FROM assets_hosts
+        WHERE (id = ANY($1) OR group_id = ANY($2)) AND deleted_at IS NULL
+        ORDER BY identifier
+        "#,
+    )
+    .bind(target_hosts)
+    .bind(target_groups)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to get target host platforms");
+        AppError::database("Failed to get target host platforms")
+    })?;
+
+    let unmatched: Vec<String> = hosts
+        .iter()
+        .filter_map(|(identifier, arch, os)| {
+            select_variant(&variants, arch.as_deref(), os.as_deref())
+                .err()
+                .map(|reason| format!("{} ({})", identifier, reason))
+        })
+        .collect();
+    if unmatched.is_empty() {
+        return Ok(());
+    }
+    let more = unmatched.len().saturating_sub(MAX_REPORTED_HOSTS);
+    let mut listed = unmatched
+        .into_iter()
+        .take(MAX_REPORTED_HOSTS)
+        .collect::<Vec<_>>()
+        .join("; ");
+    if more > 0 {
+        listed.push_str(&format!("; and {} more", more));
+    }
+    Err(AppError::validation(&format!(
+        "Artifact {} has no variant for target hosts: {}",
+        artifact_id, listed
+    )))
+}
+
+/// 校验文本中的产物变量引用（作业未关联产物时不允许引用）
+pub fn validate(text: &str, has_artifact: bool) -> Result<()> {
+    for reference in host_vars::references(text, ARTIFACT_PREFIX) {
+        if !ARTIFACT_ATTRIBUTES.contains(&reference.name) {
+            return Err(AppError::validation(&format!(
+                "Unknown artifact variable: {{{{artifact.{}}}}}",
+                reference.name
+            )));
+        }
+        if !has_artifact {
+            return Err(AppError::validation(&format!(
+                "Artifact variable {{{{artifact.{}}}}} requires artifact_id",
+                reference.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 使用所选变体替换文本中的产物变量引用
+pub fn interpolate(text: &str, variant: Option<&ArtifactVariant>) -> Result<String> {
+    let refs = host_vars::references(text, ARTIFACT_PREFIX);
+    if refs.is_empty() {
+        return Ok(text.to_string());
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for reference in refs {
+        let value = variant.and_then(|v| match reference.name {
+            "id" => Some(v.id.to_string()),
+            "name" => Some(v.artifact_name.clone()),
+            "version" => v.version.clone(),
+            "path" => Some(v.artifact_path.clone()),
+            "sha256" => Some(v.artifact_hash.clone()),
+            "arch" => Some(v.arch.clone()),
+            "os" => Some(v.os.clone()),
+            _ => None,
+        });
+        let value = value.ok_or_else(|| {
+            AppError::validation(&format!(
+                "Artifact variable {{{{artifact.{}}}}} is not available",
+                reference.name
+            ))
+        })?;
+        result.push_str(&text[last..reference.start]);
+        result.push_str(&host_vars::shell_escape(&value));
+        last = reference.end;
+    }
+    result.push_str(&text[last..]);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(arch: &str, os: &str) -> ArtifactVariant {
+        ArtifactVariant {
+            id: Uuid::new_v4(),
+            artifact_name: "app".to_string(),
+            artifact_type: "binary".to_string(),
+            artifact_path: format!("s3://artifacts/app/1.0.0/{}-{}/app", os, arch),
+            artifact_size: 1024,
+            artifact_hash: "abc123".to_string(),
+            version: Some("1.0.0".to_string()),
+            arch: arch.to_string(),
+            os: os.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_selects_variant_by_host_platform() {
+        let variants = vec![variant("aarch64", "linux"), variant("x86_64", "linux")];
+        let selected = select_variant(&variants, Some("arm64"), Some("Ubuntu")).unwrap();
+        assert_eq!(selected.arch, "aarch64");
+        let selected = select_variant(&variants, Some("amd64"), None).unwrap();
+        assert_eq!(selected.arch, "x86_64");
+        assert!(select_variant(&variants, Some("x86_64"), Some("windows")).is_err());
+        assert!(select_variant(&variants, Some("riscv64"), Some("linux")).is_err());
+    }
+
+    #[test]
+    fn test_unknown_host_arch_requires_single_arch() {
+        let variants = vec![variant("aarch64", "linux"), variant("x86_64", "linux")];
+        let err = select_variant(&variants, None, Some("linux")).unwrap_err();
+        assert!(err.contains("aarch64, x86_64"));
+
+        let variants = vec![variant("x86_64", "linux")];
+        assert!(select_variant(&variants, None, None).is_ok());
+    }
+
+    #[test]
+    fn test_prefers_exact_arch_over_platform_independent() {
+        let variants = vec![variant("", ""), variant("x86_64", "linux")];
+        let selected = select_variant(&variants, Some("x86_64"), None).unwrap();
+        assert_eq!(selected.arch, "x86_64");
+        let selected = select_variant(&variants, Some("aarch64"), None).unwrap();
+        assert_eq!(selected.arch, "");
+    }
+
+    #[test]
+    fn test_interpolate_and_validate() {
+        let selected = variant("aarch64", "linux");
+        let command =
+            "curl -o /tmp/app {{artifact.path}} && echo {{ artifact.sha256 }} {{host.address}}";
+        assert_eq!(
+            interpolate(command, Some(&selected)).unwrap(),
+            "curl -o /tmp/app s3://artifacts/app/1.0.0/linux-aarch64/app && echo abc123 {{host.address}}"
+        );
+        assert!(interpolate("{{artifact.path}}", None).is_err());
+
+        assert!(validate("echo {{artifact.arch}}", true).is_ok());
+        assert!(validate("echo {{artifact.arch}}", false).is_err());
+        assert!(validate("echo {{artifact.url}}", true).is_err());
+        assert!(validate("echo {{host.arch}}", false).is_ok());
+    }
+}
@@ -11,9 +11,19 @@ pub enum AuditAction {
     UserCreate,
     UserUpdate,
     UserDelete,
+    UserRestore,
     UserLogin,
     UserLogout,
     UserPasswordChange,
+    UserSessionRevoke,
+    UserTwoFactorEnable,
+    UserTwoFactorDisable,
+    UserTwoFactorReset,
+    UserTwoFactorRecoveryCodes,
+    UserTwoFactorStepUp,
+    UserImpersonateStart,
+    UserImpersonateEnd,
+    UserImpersonatedRequest,
 
     // 资产相关
     AssetGroupCreate,
@@ -22,13 +32,49 @@ pub enum AuditAction {
     HostCreate,
     HostUpdate,
     HostDelete,
+    HostRestore,
+    HostKeyRepin,
+    HostMaintenanceSet,
+    HostMaintenanceClear,
+    HostConnectionTest,
+    GroupConnectionTest,
+    HostCredentialRotationStart,
+    HostCredentialRotate,
+    HostCredentialUpdate,
+    HostEnvironmentChange,
+    AdvisoryImport,
 
     // 作业相关
     JobCreate,
     JobCancel,
     JobRetry,
+    JobTaskCancel,
+    JobTaskSkip,
+    JobTaskRerun,
     JobExecute,
     JobOutputView,
+    JobEvidenceExport,
+    JobEvidenceDownload,
+    JobTargetSetCreate,
+    JobBudgetExceeded,
+    JobDispatchPaused,
+    JobDispatchResume,
+    JobTagCreate,
+    JobTagUpdate,
+    JobTagDelete,
+    JobOutputBaselineSet,
+    JobOutputBaselineDelete,
+    JobTemplateDelete,
+    JobTemplateRestore,
+    JobTemplateDeprecate,
+    JobTemplateUndeprecate,
+    InboundWebhookCreate,
+    InboundWebhookUpdate,
+    InboundWebhookDelete,
+    InboundWebhookRotateToken,
+    InboundWebhookTrigger,
+    EnvironmentPolicyUpdate,
+    EnvironmentPolicyDelete,
 
     // 构建相关
     BuildCreate,
@@ -39,6 +85,8 @@ pub enum AuditAction {
     ArtifactUpdate,
     ArtifactDelete,
     ArtifactDownload,
+    ArtifactPromotionRequest,
+    ArtifactPromote,
 
     // 权限相关
     RoleCreate,
@@ -52,6 +100,7 @@ pub enum AuditAction {
     ApprovalApprove,
     ApprovalReject,
     ApprovalCancel,
+    ApprovalAutoApprove,
     ApprovalGroupCreate,
     ApprovalGroupUpdate,
     ApprovalGroupDelete,
@@ -60,15 +109,32 @@ pub enum AuditAction {
     RunnerConfigCreate,
     RunnerConfigUpdate,
     RunnerConfigDelete,
+    RunnerConfigRolloutStart,
+    RunnerConfigRolloutPromote,
+    RunnerConfigRolloutRollback,
 
     // Runner 相关
     RunnerRegister,
     RunnerReregister,
     RunnerUpdate,
     RunnerDelete,
+    RunnerEnroll,
+    RunnerEnrollmentTokenCreate,
+    RunnerEnrollmentTokenRevoke,
+    RunnerCommand,
 
     // 审计查询
     AuditQuery,
+    StreamSubscribe,
+    StreamClose,
+    AnomalyAcknowledge,
+
+    // 系统管理
+    SystemMaintenanceModeChange,
+    SystemLoadTestStart,
+    NotificationTemplateUpdate,
+    NotificationTemplateRollback,
+    NotificationTemplateDelete,
 }
 
 impl AuditAction {
@@ -77,9 +143,19 @@ impl AuditAction {
             AuditAction::UserCreate => "user.create",
             AuditAction::UserUpdate => "user.update",
             AuditAction::UserDelete => "user.delete",
+            AuditAction::UserRestore => "user.restore",
             AuditAction::UserLogin => "user.login",
             AuditAction::UserLogout => "user.logout",
             AuditAction::UserPasswordChange => "user.password_change",
+            AuditAction::UserSessionRevoke => "user.session_revoke",
+            AuditAction::UserTwoFactorEnable => "user.two_factor_enable",
+            AuditAction::UserTwoFactorDisable => "user.two_factor_disable",
+            AuditAction::UserTwoFactorReset => "user.two_factor_reset",
+            AuditAction::UserTwoFactorRecoveryCodes => "user.two_factor_recovery_codes",
+            AuditAction::UserTwoFactorStepUp => "user.two_factor_step_up",
+            AuditAction::UserImpersonateStart => "user.impersonate_start",
+            AuditAction::UserImpersonateEnd => "user.impersonate_end",
+            AuditAction::UserImpersonatedRequest => "user.impersonated_request",
 
             AuditAction::AssetGroupCreate => "asset.group.create",
             AuditAction::AssetGroupUpdate => "asset.group.update",
@@ -87,12 +163,48 @@ impl AuditAction {
             AuditAction::HostCreate => "asset.host.create",
             AuditAction::HostUpdate => "asset.host.update",
             AuditAction::HostDelete => "asset.host.delete",
+            AuditAction::HostRestore => "asset.host.restore",
+            AuditAction::HostKeyRepin => "asset.host.key_repin",
+            AuditAction::HostMaintenanceSet => "asset.host.maintenance_set",
+            AuditAction::HostMaintenanceClear => "asset.host.maintenance_clear",
+            AuditAction::HostConnectionTest => "asset.host.connection_test",
+            AuditAction::GroupConnectionTest => "asset.group.connection_test",
+            AuditAction::HostCredentialRotationStart => "asset.host.credential_rotation_start",
+            AuditAction::HostCredentialRotate => "asset.host.credential_rotate",
+            AuditAction::HostCredentialUpdate => "asset.host.credential_update",
+            AuditAction::HostEnvironmentChange => "asset.host.environment_change",
+            AuditAction::AdvisoryImport => "asset.advisory.import",
 
             AuditAction::JobCreate => "job.create",
             AuditAction::JobCancel => "job.cancel",
             AuditAction::JobRetry => "job.retry",
+            AuditAction::JobTaskCancel => "job.task_cancel",
+            AuditAction::JobTaskSkip => "job.task_skip",
+            AuditAction::JobTaskRerun => "job.task_rerun",
             AuditAction::JobExecute => "job.execute",
             AuditAction::JobOutputView => "job.output_view",
+            AuditAction::JobEvidenceExport => "job.evidence_export",
+            AuditAction::JobEvidenceDownload => "job.evidence_download",
+            AuditAction::JobTargetSetCreate => "job.target_set.create",
+            AuditAction::JobBudgetExceeded => "job.budget_exceeded",
+            AuditAction::JobDispatchPaused => "job.dispatch_paused",
+            AuditAction::JobDispatchResume => "job.dispatch_resume",
+            AuditAction::JobTagCreate => "job_tag.create",
+            AuditAction::JobTagUpdate => "job_tag.update",
+            AuditAction::JobTagDelete => "job_tag.delete",
+            AuditAction::JobOutputBaselineSet => "job_baseline.set",
+            AuditAction::JobOutputBaselineDelete => "job_baseline.delete",
+            AuditAction::JobTemplateDelete => "job_template.delete",
+            AuditAction::JobTemplateRestore => "job_template.restore",
+            AuditAction::JobTemplateDeprecate => "job_template.deprecate",
+            AuditAction::JobTemplateUndeprecate => "job_template.undeprecate",
+            AuditAction::InboundWebhookCreate => "inbound_webhook.create",
+            AuditAction::InboundWebhookUpdate => "inbound_webhook.update",
+            AuditAction::InboundWebhookDelete => "inbound_webhook.delete",
+            AuditAction::InboundWebhookRotateToken => "inbound_webhook.rotate_token",
+            AuditAction::InboundWebhookTrigger => "inbound_webhook.trigger",
+            AuditAction::EnvironmentPolicyUpdate => "environment_policy.update",
+            AuditAction::EnvironmentPolicyDelete => "environment_policy.delete",
 
             AuditAction::BuildCreate => "build.create",
             AuditAction::BuildExecute => "build.execute",
@@ -102,6 +214,8 @@ impl AuditAction {
             AuditAction::ArtifactUpdate => "artifact.update",
             AuditAction::ArtifactDelete => "artifact.delete",
             AuditAction::ArtifactDownload => "artifact.download",
+            AuditAction::ArtifactPromotionRequest => "artifact.promotion.request",
+            AuditAction::ArtifactPromote => "artifact.promote",
 
             AuditAction::RoleCreate => "role.create",
             AuditAction::RoleUpdate => "role.update",
@@ -113,6 +227,7 @@ impl AuditAction {
             AuditAction::ApprovalApprove => "approval.approve",
             AuditAction::ApprovalReject => "approval.reject",
             AuditAction::ApprovalCancel => "approval.cancel",
+            AuditAction::ApprovalAutoApprove => "approval.auto_approve",
             AuditAction::ApprovalGroupCreate => "approval_group.create",
             AuditAction::ApprovalGroupUpdate => "approval_group.update",
             AuditAction::ApprovalGroupDelete => "approval_group.delete",
@@ -120,13 +235,29 @@ impl AuditAction {
             AuditAction::RunnerConfigCreate => "runner_config.create",
             AuditAction::RunnerConfigUpdate => "runner_config.update",
             AuditAction::RunnerConfigDelete => "runner_config.delete",
+            AuditAction::RunnerConfigRolloutStart => "runner_config.rollout.start",
+            AuditAction::RunnerConfigRolloutPromote => "runner_config.rollout.promote",
+            AuditAction::RunnerConfigRolloutRollback => "runner_config.rollout.rollback",
 
             AuditAction::RunnerRegister => "runner.register",
             AuditAction::RunnerReregister => "runner.re_register",
             AuditAction::RunnerUpdate => "runner.update",
             AuditAction::RunnerDelete => "runner.delete",
+            AuditAction::RunnerEnroll => "runner.enroll",
+            AuditAction::RunnerEnrollmentTokenCreate => "runner.enrollment_token_create",
+            AuditAction::RunnerEnrollmentTokenRevoke => "runner.enrollment_token_revoke",
+            AuditAction::RunnerCommand => "runner.command",
 
             AuditAction::AuditQuery => "audit.query",
+            AuditAction::StreamSubscribe => "audit.stream_subscribe",
+            AuditAction::StreamClose => "audit.stream_close",
+            AuditAction::AnomalyAcknowledge => "audit.anomaly_acknowledge",
+
+            AuditAction::SystemMaintenanceModeChange => "system.maintenance_mode_change",
+            AuditAction::SystemLoadTestStart => "system.load_test_start",
+            AuditAction::NotificationTemplateUpdate => "notification_template.update",
+            AuditAction::NotificationTemplateRollback => "notification_template.rollback",
+            AuditAction::NotificationTemplateDelete => "notification_template.delete",
         }
     }
 }
@@ -160,7 +291,16 @@ impl AuditService {
     }
 
     /// 记录审计日志条目
+    ///
+    /// 模拟请求中记录的条目会在 changes 中附带模拟者信息
     pub async fn log_action(&self, params: AuditLogParams<'_>) -> Result<(), AppError> {
+        let changes = match crate::auth::impersonation::current_impersonator() {
+            Some(impersonator) => {
+                Some(crate::auth::impersonation::annotate_changes(params.changes, &impersonator))
+            }
+            None => params.changes,
+        };
+
         let log = AuditLog {
             id: Uuid::new_v4(),
             subject_id: params.subject_id,
@@ -170,12 +310,12 @@ impl AuditService {
             resource_type: params.resource_type.to_string(),
             resource_id: params.resource_id,
             resource_name: params.resource_name.map(|s| s.to_string()),
-            changes: params.changes,
+            changes,
             changes_summary: params.changes_summary.map(|s| s.to_string()),
             source_ip: params.source_ip.map(|s| s.to_string()),
             user_agent: params.user_agent.map(|s| s.to_string()),
             trace_id: params.trace_id.map(|s| s.to_string()),
-            request_id: None, // 可以从请求上下文中提取
+            request_id: crate::middleware::request_id::current_request_id(),
             result: params.result.to_string(),
             error_message: params.error_message.map(|s| s.to_string()),
             occurred_at: chrono::Utc::now(),
@@ -232,12 +372,56 @@ impl AuditService {
         repo.query_audit_logs(filters, limit, offset).await
     }
 
+    /// 查询游标之后的审计日志（键集分页）
+    pub async fn query_logs_after(
+        &self,
+        filters: &AuditLogFilters,
+        cursor: &AuditCursor,
+        limit: i64,
+    ) -> Result<Vec<AuditLog>, AppError> {
+        let repo = AuditRepository::new(self.db.clone());
+        repo.query_audit_logs_after(filters, cursor, limit).await
+    }
+
+    /// 查询审计日志分面计数
+    pub async fn facets(
+        &self,
+        filters: &AuditLogFilters,
+        limit: i64,
+    ) -> Result<AuditFacets, AppError> {
+        let repo = AuditRepository::new(self.db.clone());
+        repo.audit_facets(filters, limit).await
+    }
+
     /// 查询审计日志数量
     pub async fn count_logs(&self, filters: &AuditLogFilters) -> Result<i64, AppError> {
         let repo = AuditRepository::new(self.db.clone());
         repo.count_audit_logs(filters).await
     }
 
+    /// 查询安全异常
+    pub async fn query_anomalies(
+        &self,
+        filters: &SecurityAnomalyFilters,
+        limit: i64,
+    ) -> Result<Vec<SecurityAnomaly>, AppError> {
+        let repo = AuditRepository::new(self.db.clone());
+        repo.query_anomalies(filters, limit).await
+    }
+
+    /// 确认安全异常
+    pub async fn acknowledge_anomaly(
+        &self,
+        id: Uuid,
+        acknowledged_by: Uuid,
+        note: Option<&str>,
+    ) -> Result<SecurityAnomaly, AppError> {
+        let repo = AuditRepository::new(self.db.clone());
+        repo.acknowledge_anomaly(id, acknowledged_by, note)
+            .await?
+            .ok_or_else(|| AppError::not_found("Anomaly not found or already acknowledged"))
+    }
+
     /// 查询登录事件
     pub async fn query_login_events(
         &self,
@@ -1,12 +1,18 @@
 //! 认证服务：登录、登出、令牌刷新
 
 use crate::{
-    auth::jwt::{JwtService, TokenPair},
+    auth::credential_cipher::CredentialCipher,
+    auth::impersonation::{self, DEFAULT_IMPERSONATION_SECS, MAX_IMPERSONATION_SECS},
+    auth::jwt::{ImpersonatorClaims, JwtService, TokenPair},
+    auth::middleware::AuthContext,
     auth::password::PasswordHasher,
+    auth::sessions::{self, SessionRegistry},
     config::AppConfig,
     error::AppError,
+    i18n,
     models::{audit::*, auth::*, user::*},
     repository::{auth_repo::AuthRepository, user_repo::UserRepository},
+    services::two_factor::TwoFactorService,
 };
 use chrono::Timelike;
 use sqlx::PgPool;
@@ -17,11 +23,19 @@ pub struct AuthService {
     db: PgPool,
     jwt_service: Arc<JwtService>,
     config: Arc<AppConfig>,
+    sessions: SessionRegistry,
+    two_factor: TwoFactorService,
 }
 
 impl AuthService {
     pub fn new(db: PgPool, jwt_service: Arc<JwtService>, config: Arc<AppConfig>) -> Self {
         Self {
+            sessions: SessionRegistry::new(db.clone(), SessionRegistry::DEFAULT_TTL),
+            two_factor: TwoFactorService::new(
+                db.clone(),
+                config.two_factor.clone(),
+                CredentialCipher::from_config(&config.security),
+            ),
             db,
             jwt_service,
             config,
@@ -116,7 +130,12 @@ impl AuthService {
             return Err(AppError::Unauthorized);
         }
 
-        // 密码正确，重置失败次数
+        // 已启用双因素认证时校验 TOTP 验证码或恢复码
+        let two_factor_verified = self
+            .verify_login_second_factor(&user, req.totp_code.as_deref(), client_ip, user_agent)
+            .await?;
+
+        // 认证通过，重置失败次数
         if user.failed_login_attempts > 0 {
             let _ = user_repo.reset_failed_attempts(user.id).await;
         }
@@ -124,12 +143,37 @@ impl AuthService {
         // 获取用户角色和权限范围
         let (roles, scopes) = self.get_user_roles_and_scopes(user.id).await?;
 
+        // 登记登录会话
+        let now = chrono::Utc::now();
+        let expires_at =
+            now + chrono::Duration::seconds(self.config.security.refresh_token_exp_secs as i64);
+        let device_id = self.generate_device_fingerprint(user_agent);
+        let session = auth_repo
+            .create_user_session(&UserSession {
+                id: Uuid::new_v4(),
+                user_id: user.id,
+                device_id: device_id.clone(),
+                user_agent: user_agent.map(|s| s.to_string()),
+                ip_address: client_ip.to_string(),
+                created_at: now,
+                last_seen_at: now,
+                last_seen_ip: Some(client_ip.to_string()),
+                expires_at,
+                revoked_at: None,
+                revoked_by: None,
+                revoke_reason: None,
+                // 通过双因素认证登录的会话无需立即二次验证
+                step_up_until: two_factor_verified.then(|| self.two_factor.step_up_deadline(now)),
+            })
+            .await?;
+
         // 生成令牌
-        let token_pair = self.jwt_service.generate_token_pair(
+        let token_pair = self.jwt_service.generate_session_token_pair(
             &user.id,
             &user.username,
             roles.clone(),
             scopes.clone(),
+            session.id,
         )?;
 
         // 存储刷新令牌
@@ -138,14 +182,14 @@ impl AuthService {
             id: Uuid::new_v4(),
             token_hash,
             user_id: user.id,
-            device_id: self.generate_device_fingerprint(user_agent),
+            device_id,
             user_agent: user_agent.map(|s| s.to_string()),
             ip_address: client_ip.to_string(),
-            expires_at: chrono::Utc::now()
-                + chrono::Duration::seconds(self.config.security.refresh_token_exp_secs as i64),
+            expires_at,
             revoked_at: None,
             replaced_by: None,
-            created_at: chrono::Utc::now(),
+            created_at: now,
+            session_id: Some(session.id),
         };
 
         auth_repo.store_refresh_token(&refresh_token).await?;
@@ -203,6 +247,13 @@ impl AuthService {
             return Err(AppError::Unauthorized);
         }
 
+        // 所属会话已撤销时拒绝刷新
+        if let Some(session_id) = refresh_token_record.session_id {
+            if !self.sessions.is_active(session_id).await? {
+                return Err(AppError::Unauthorized);
+            }
+        }
+
         // 获取用户
         let user_repo = UserRepository::new(self.db.clone());
         let user: User = user_repo
@@ -216,10 +267,26 @@ impl AuthService {
         // 获取用户角色和权限范围
         let (roles, scopes) = self.get_user_roles_and_scopes(user.id).await?;
 
+        let expires_at = chrono::Utc::now()
+            + chrono::Duration::seconds(self.config.security.refresh_token_exp_secs as i64);
+
+        // 沿用原会话；会话登记之前签发的刷新令牌补登会话，此后即可按会话撤销
+        let session_id = match refresh_token_record.session_id {
+            Some(session_id) => session_id,
+            None => {
+                self.adopt_legacy_refresh_token(&refresh_token_record, client_ip, expires_at)
+                    .await?
+            }
+        };
+
         // 生成新的令牌对
-        let new_token_pair =
-            self.jwt_service
-                .generate_token_pair(&user.id, &user.username, roles, scopes)?;
+        let new_token_pair = self.jwt_service.generate_session_token_pair(
+            &user.id,
+            &user.username,
+            roles,
+            scopes,
+            session_id,
+        )?;
 
         // 撤销旧的刷新令牌
         let _ = auth_repo
@@ -235,19 +302,58 @@ impl AuthService {
             device_id: None, // 刷新时不重新生成设备指纹
             user_agent: None,
             ip_address: client_ip.to_string(),
-            expires_at: chrono::Utc::now()
-                + chrono::Duration::seconds(self.config.security.refresh_token_exp_secs as i64),
+            expires_at,
             revoked_at: None,
             replaced_by: Some(refresh_token_record.id),
             created_at: chrono::Utc::now(),
+            session_id: Some(session_id),
         };
 
         auth_repo.store_refresh_token(&new_refresh_token).await?;
 
+        // 记录会话活跃时间，会话有效期随刷新令牌顺延
+        auth_repo
+            .touch_user_session(session_id, client_ip, expires_at)
+            .await?;
+
         Ok(new_token_pair)
     }
 
-    /// 登出（撤销刷新令牌）
+    /// 为会话登记之前签发的刷新令牌补登登录会话
+    async fn adopt_legacy_refresh_token(
+        &self,
+        token: &RefreshToken,
+        client_ip: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Uuid, AppError> {
+        let now = chrono::Utc::now();
+        let session = AuthRepository::new(self.db.clone())
+            .create_user_session(&UserSession {
+                id: Uuid::new_v4(),
+                user_id: token.user_id,
+                device_id: token.device_id.clone(),
+                user_agent: token.user_agent.clone(),
+                ip_address: token.ip_address.clone(),
+                created_at: token.created_at,
+                last_seen_at: now,
+                last_seen_ip: Some(client_ip.to_string()),
+                expires_at,
+                revoked_at: None,
+                revoked_by: None,
+                revoke_reason: None,
+                step_up_until: None,
+            })
+            .await?;
+
+        tracing::info!(
+            user_id = %token.user_id,
+            session_id = %session.id,
+            "Registered login session for legacy refresh token"
+        );
+        Ok(session.id)
+    }
+
+    /// 登出（撤销刷新令牌及其所属会话）
     pub async fn logout(&self, refresh_token: &str, user_id: Uuid) -> Result<(), AppError> {
         let auth_repo = AuthRepository::new(self.db.clone());
         let token_hash = AuthRepository::hash_token(refresh_token);
@@ -256,13 +362,259 @@ impl AuthService {
             .revoke_refresh_token_by_hash(&token_hash, user_id)
             .await?;
 
+        let session_id = auth_repo
+            .find_refresh_token_by_hash(&token_hash)
+            .await?
+            .filter(|token| token.user_id == user_id)
+            .and_then(|token| token.session_id);
+        if let Some(session_id) = session_id {
+            auth_repo
+                .revoke_user_session(user_id, session_id, user_id, sessions::REVOKE_REASON_LOGOUT)
+                .await?;
+            self.sessions.invalidate(session_id);
+        }
+
         Ok(())
     }
 
     /// 从所有设备登出
     pub async fn logout_all(&self, user_id: Uuid) -> Result<u64, AppError> {
         let auth_repo = AuthRepository::new(self.db.clone());
-        auth_repo.revoke_all_refresh_tokens(user_id).await
+        let revoked = auth_repo.revoke_all_refresh_tokens(user_id).await?;
+        self.revoke_all_sessions(user_id, user_id, sessions::REVOKE_REASON_LOGOUT_ALL)
+            .await?;
+
+        Ok(revoked)
+    }
+
+    /// 列出用户的有效登录会话，`current` 为发起请求的会话
+    pub async fn list_sessions(
+        &self,
+        user_id: Uuid,
+        current: Option<Uuid>,
+    ) -> Result<Vec<UserSessionResponse>, AppError> {
+        let auth_repo = AuthRepository::new(self.db.clone());
+        let sessions = auth_repo.list_active_user_sessions(user_id).await?;
+
+        Ok(sessions
+            .into_iter()
+            .map(|session| UserSessionResponse {
+                current: Some(session.id) == current,
+                session,
+            })
+            .collect())
+    }
+
+    /// 撤销用户的单个登录会话，会话签发的令牌随即失效
+    pub async fn revoke_session(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        revoked_by: Uuid,
+        reason: &str,
+    ) -> Result<UserSession, AppError> {
+        let auth_repo = AuthRepository::new(self.db.clone());
+        let revoked = auth_repo
+            .revoke_user_session(user_id, session_id, revoked_by, reason)
+            .await?;
+        self.sessions.invalidate(session_id);
+        if let Some(session) = revoked {
+            return Ok(session);
+        }
+
+        match auth_repo.find_user_session(session_id).await? {
+            Some(session) if session.user_id == user_id => {
+                Err(AppError::BadRequest("会话已撤销".to_string()))
+            }
+            _ => Err(AppError::NotFound("会话不存在".to_string())),
+        }
+    }
+
+    /// 撤销用户的全部登录会话及刷新令牌，返回被撤销的会话数
+    pub async fn revoke_all_sessions(
+        &self,
+        user_id: Uuid,
+        revoked_by: Uuid,
+        reason: &str,
+    ) -> Result<usize, AppError> {
+        let auth_repo = AuthRepository::new(self.db.clone());
+        let session_ids = auth_repo
+            .revoke_all_user_sessions(user_id, revoked_by, reason)
+            .await?;
+        auth_repo.revoke_all_refresh_tokens(user_id).await?;
+        for session_id in &session_ids {
+            self.sessions.invalidate(*session_id);
+        }
+
+        Ok(session_ids.len())
+    }
+
+    /// 双因素认证服务
+    pub fn two_factor(&self) -> &TwoFactorService {
+        &self.two_factor
+    }
+
+    /// 登录时校验第二因素，返回是否经过校验（未启用双因素认证时为 false）
+    async fn verify_login_second_factor(
+        &self,
+        user: &User,
+        code: Option<&str>,
+        client_ip: &str,
+        user_agent: Option<&str>,
+    ) -> Result<bool, AppError> {
+        if !self.two_factor.is_enabled(user.id).await? {
+            return Ok(false);
+        }
+        let Some(code) = code.map(str::trim).filter(|c| !c.is_empty()) else {
+            return Err(AppError::TwoFactorRequired("TOTP code required".to_string()));
+        };
+        if self.two_factor.verify_code(user.id, code).await? {
+            return Ok(true);
+        }
+
+        // 验证码错误与密码错误一样计入失败次数
+        let user_repo = UserRepository::new(self.db.clone());
+        let _ = user_repo.increment_failed_attempts(user.id).await;
+        self.record_login_event(
+            Some(user.id),
+            &user.username,
+            "login_failure",
+            Some("invalid_totp"),
+            client_ip,
+            user_agent,
+        )
+        .await;
+        Err(AppError::Unauthorized)
+    }
+
+    /// 登录会话是否仍然有效（带缓存）
+    pub async fn is_session_active(&self, session_id: Uuid) -> Result<bool, AppError> {
+        self.sessions.is_active(session_id).await
+    }
+
+    /// 开始模拟用户
+    ///
+    /// 模拟令牌携带目标用户的角色和权限范围，不能嵌套模拟，也不能模拟自己；
+    /// 管理员及持有模拟权限的用户不可被模拟
+    pub async fn start_impersonation(
+        &self,
+        actor: &AuthContext,
+        req: ImpersonateRequest,
+        client_ip: &str,
+        user_agent: Option<&str>,
+    ) -> Result<ImpersonationResponse, AppError> {
+        if actor.impersonator.is_some() {
+            return Err(AppError::BadRequest(i18n::t("error.impersonation.nested").to_string()));
+        }
+        if req.user_id == actor.user_id {
+            return Err(AppError::BadRequest(i18n::t("error.impersonation.self").to_string()));
+        }
+        let reason = req.reason.trim();
+        if reason.is_empty() {
+            return Err(AppError::BadRequest(
+                i18n::t("error.impersonation.reason_required").to_string(),
+            ));
+        }
+        let duration_secs = req.duration_secs.unwrap_or(DEFAULT_IMPERSONATION_SECS);
+        if duration_secs == 0 || duration_secs > MAX_IMPERSONATION_SECS {
+            return Err(AppError::BadRequest(i18n::tf(
+                "error.impersonation.duration_range",
+                &[("max", &MAX_IMPERSONATION_SECS)],
+            )));
+        }
+
+        let user_repo = UserRepository::new(self.db.clone());
+        let target: User = user_repo
+            .find_by_id(&req.user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(i18n::t("error.user.not_found").to_string()))?;
+        self.check_account_status(&target)?;
+
+        let (roles, scopes) = self.get_user_roles_and_scopes(target.id).await?;
+        if impersonation::is_protected_target(&roles, &scopes) {
+            tracing::warn!(
+                impersonator = %actor.username,
+                target = %target.username,
+                "Rejected impersonation of a protected user"
+            );
+            return Err(AppError::Forbidden);
+        }
+
+        let now = chrono::Utc::now();
+        let session = ImpersonationSession {
+            id: Uuid::new_v4(),
+            impersonator_id: actor.user_id,
+            impersonator_name: actor.username.clone(),
+            target_user_id: target.id,
+            target_username: target.username.clone(),
+            reason: reason.to_string(),
+            source_ip: Some(client_ip.to_string()),
+            user_agent: user_agent.map(|s| s.to_string()),
+            created_at: now,
+            expires_at: now + chrono::Duration::seconds(duration_secs as i64),
+            terminated_at: None,
+            terminated_by: None,
+        };
+        let auth_repo = AuthRepository::new(self.db.clone());
+        let session = auth_repo.create_impersonation_session(&session).await?;
+
+        let access_token = self.jwt_service.generate_impersonation_token(
+            &target.id,
+            &target.username,
+            roles,
+            scopes,
+            ImpersonatorClaims {
+                sub: actor.user_id.to_string(),
+                username: actor.username.clone(),
+                session_id: session.id.to_string(),
+            },
+            duration_secs,
+        )?;
+
+        tracing::warn!(
+            impersonator = %actor.username,
+            target = %target.username,
+            session_id = %session.id,
+            "Impersonation session started"
+        );
+
+        Ok(ImpersonationResponse {
+            access_token,
+            expires_in: duration_secs,
+            session,
+        })
+    }
+
+    /// 列出当前有效的模拟会话
+    pub async fn list_active_impersonations(&self) -> Result<Vec<ImpersonationSession>, AppError> {
+        let auth_repo = AuthRepository::new(self.db.clone());
+        auth_repo.list_active_impersonation_sessions().await
+    }
+
+    /// 终止模拟会话，会话签发的令牌随即失效
+    pub async fn terminate_impersonation(
+        &self,
+        session_id: Uuid,
+        terminated_by: Uuid,
+    ) -> Result<ImpersonationSession, AppError> {
+        let auth_repo = AuthRepository::new(self.db.clone());
+        if let Some(session) = auth_repo
+            .terminate_impersonation_session(session_id, terminated_by)
+            .await?
+        {
+            return Ok(session);
+        }
+
+        match auth_repo.find_impersonation_session(session_id).await? {
+            Some(_) => Err(AppError::BadRequest(i18n::t("error.impersonation.ended").to_string())),
+            None => Err(AppError::NotFound(i18n::t("error.impersonation.not_found").to_string())),
+        }
+    }
+
+    /// 模拟会话是否仍然有效
+    pub async fn is_impersonation_active(&self, session_id: Uuid) -> Result<bool, AppError> {
+        let auth_repo = AuthRepository::new(self.db.clone());
+        auth_repo.is_impersonation_session_active(session_id).await
     }
 
     /// 检查账户状态
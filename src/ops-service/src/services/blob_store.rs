@@ -0,0 +1,567 @@
+//! 内容寻址 blob 存储
+//!
+//! 内容以 sha256 为键写入对象存储（见 `StorageService::blob_key`），相同内容只保存一份。
+//! 脚本作业、构建产物等引用方通过 content_blob_refs 登记引用，
+//! 引用计数归零且超过宽限期的 blob 由后台 GC 删除。
+//! 大脚本与二进制内容经本地暂存文件流式上传/下载，内存占用与内容大小无关
+
+use crate::{
+    config::BlobStoreConfig,
+    error::{AppError, Result},
+    models::blob::{BlobGcReport, BlobOwnerStats, BlobPutResult, BlobStats, ContentBlob},
+    services::StorageService,
+};
+use futures::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// 单轮 GC 最多回收的 blob 数
+const GC_BATCH_SIZE: i64 = 500;
+
+/// 流式读写本地暂存文件的分块大小
+const STAGING_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 内容寻址 blob 存储
+pub struct BlobStore {
+    db: Pool<Postgres>,
+    storage: Arc<StorageService>,
+    config: BlobStoreConfig,
+}
+
+impl BlobStore {
+    pub fn new(db: Pool<Postgres>, storage: Arc<StorageService>, config: BlobStoreConfig) -> Self {
+        Self {
+            db,
+            storage,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &BlobStoreConfig {
+        &self.config
+    }
+
+    /// 计算内容的 sha256（小写十六进制）
+    pub fn hash(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    /// 校验并规范化外部传入的哈希（64 位十六进制，统一为小写）
+    pub fn normalize_hash(sha256: &str) -> Option<String> {
+        let sha256 = sha256.trim();
+        (sha256.len() == 64 && sha256.bytes().all(|b| b.is_ascii_hexdigit()))
+            .then(|| sha256.to_ascii_lowercase())
+    }
+
+    /// 写入内容，已存在相同内容时只刷新 touched_at
+    ///
+    /// 新写入的 blob 引用计数为 0，需在宽限期内通过 `add_ref` 登记引用，否则会被 GC 回收
+    pub async fn put(&self, data: &[u8]) -> Result<BlobPutResult> {
+        if data.len() > self.config.max_upload_bytes {
+            return Err(AppError::validation(&format!(
+                "Blob exceeds the maximum size of {} bytes",
+                self.config.max_upload_bytes
+            )));
+        }
+
+        let sha256 = Self::hash(data);
+        let size_bytes = data.len() as i64;
+        if self.touch_existing(&sha256).await? {
+            debug!(sha256 = %sha256, size_bytes, "Blob already stored");
+            return Ok(BlobPutResult {
+                sha256,
+                size_bytes,
+                deduplicated: true,
+            });
+        }
+
+        // 对象键由哈希决定，并发写入相同内容时覆盖写是安全的
+        let location = self
+            .storage
+            .put_object(&StorageService::blob_key(&sha256), data)
+            .await
+            .map_err(|e| {
+                error!(error = %e, sha256 = %sha256, "Failed to upload blob");
+                AppError::internal_error("Failed to store blob")
+            })?;
+
+        let inserted = self.record(&sha256, size_bytes, &location).await?;
+        debug!(sha256 = %sha256, size_bytes, "Blob stored");
+        Ok(BlobPutResult {
+            sha256,
+            size_bytes,
+            deduplicated: !inserted,
+        })
+    }
+
+    /// 流式写入内容：分块写入本地暂存文件并计算哈希，再从暂存文件流式上传到对象存储
+    ///
+    /// 大小上限为 `max_stream_upload_bytes`，暂存文件在返回前删除
+    pub async fn put_stream<S, B, E>(&self, stream: S) -> Result<BlobPutResult>
+    where
+        S: Stream<Item = std::result::Result<B, E>>,
+        B: AsRef<[u8]>,
+        E: std::fmt::Display,
+    {
+        let staging = StagedFile(self.staging_path("upload"));
+        self.put_staged(stream, staging.path()).await
+    }
+
+    async fn put_staged<S, B, E>(&self, stream: S, staging: &Path) -> Result<BlobPutResult>
+    where
+        S: Stream<Item = std::result::Result<B, E>>,
+        B: AsRef<[u8]>,
+        E: std::fmt::Display,
+    {
+        let io_err = |e: std::io::Error| {
+            error!(error = %e, path = %staging.display(), "Failed to write staging file");
+            AppError::internal_error("Failed to stage upload")
+        };
+
+        if let Some(parent) = staging.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(io_err)?;
+        }
+        let mut file = tokio::fs::File::create(staging).await.map_err(io_err)?;
+        let mut hasher = Sha256::new();
+        let mut size_bytes: u64 = 0;
+
+        futures::pin_mut!(stream);
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk
+                .map_err(|e| AppError::validation(&format!("Failed to read upload: {}", e)))?;
+            let chunk = chunk.as_ref();
+            size_bytes += chunk.len() as u64;
+            if size_bytes > self.config.max_stream_upload_bytes {
+                return Err(AppError::validation(&format!(
+                    "Blob exceeds the maximum size of {} bytes",
+                    self.config.max_stream_upload_bytes
+                )));
+            }
+            hasher.update(chunk);
+            file.write_all(chunk).await.map_err(io_err)?;
+        }
+        file.flush().await.map_err(io_err)?;
+        drop(file);
+
+        let sha256 = hex::encode(hasher.finalize());
+        let size_bytes = size_bytes as i64;
+        if self.touch_existing(&sha256).await? {
+            debug!(sha256 = %sha256, size_bytes, "Blob already stored");
+            return Ok(BlobPutResult {
+                sha256,
+                size_bytes,
+                deduplicated: true,
+            });
+        }
+
+        let location = self
+            .storage
+            .put_object_file(&StorageService::blob_key(&sha256), staging)
+            .await
+            .map_err(|e| {
+                error!(error = %e, sha256 = %sha256, "Failed to upload blob");
+                AppError::internal_error("Failed to store blob")
+            })?;
+
+        let inserted = self.record(&sha256, size_bytes, &location).await?;
+        debug!(sha256 = %sha256, size_bytes, "Blob stored from stream");
+        Ok(BlobPutResult {
+            sha256,
+            size_bytes,
+            deduplicated: !inserted,
+        })
+    }
+
+    /// 刷新已有 blob，返回是否存在；与 GC 并发时行锁保证二者只有一方生效
+    async fn touch_existing(&self, sha256: &str) -> Result<bool> {
+        let existing = sqlx::query_scalar::<_, String>(
+            "UPDATE content_blobs SET touched_at = NOW() WHERE sha256 = $1 RETURNING sha256",
+        )
+        .bind(sha256)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, sha256 = %sha256, "Failed to look up blob");
+            AppError::database("Failed to look up blob")
+        })?;
+        Ok(existing.is_some())
+    }
+
+    /// 登记已写入对象存储的 blob，返回是否插入了新记录
+    async fn record(&self, sha256: &str, size_bytes: i64, location: &str) -> Result<bool> {
+        // xmax = 0 表示本次插入了新行（并发写入相同内容时另一方走 DO UPDATE）
+        sqlx::query_scalar::<_, bool>(
+            "INSERT INTO content_blobs (sha256, size_bytes, location)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (sha256) DO UPDATE SET touched_at = NOW()
+             RETURNING (xmax = 0)",
+        )
+        .bind(sha256)
+        .bind(size_bytes)
+        .bind(location)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, sha256 = %sha256, "Failed to record blob");
+            AppError::database("Failed to record blob")
+        })
+    }
+
+    /// 本地暂存文件路径（`staging_dir` 未配置时位于系统临时目录）
+    fn staging_path(&self, label: &str) -> PathBuf {
+        let dir = self
+            .config
+            .staging_dir
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+        dir.join(format!("ops-{}-{}", label, Uuid::new_v4().simple()))
+    }
+
+    /// 将内容下载到本地暂存文件（不整体读入内存），下载后分块重新计算哈希校验
+    pub async fn stage(&self, sha256: &str) -> Result<StagedFile> {
+        let blob = self
+            .find(sha256)
+            .await?
+            .ok_or_else(|| AppError::not_found("Blob not found"))?;
+        let staged = StagedFile(self.staging_path("blob"));
+        let dest = staged.path();
+
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                error!(error = %e, path = %dest.display(), "Failed to create staging directory");
+                AppError::internal_error("Failed to stage blob")
+            })?;
+        }
+        self.storage
+            .get_object_to_file(&blob.location, dest)
+            .await
+            .map_err(|e| {
+                error!(error = %e, sha256 = %sha256, "Failed to download blob");
+                AppError::internal_error("Failed to read blob")
+            })?;
+
+        let actual = Self::hash_file(dest).await.map_err(|e| {
+            error!(error = %e, path = %dest.display(), "Failed to read staged blob");
+            AppError::internal_error("Failed to read blob")
+        })?;
+        if actual != blob.sha256 {
+            error!(sha256 = %sha256, location = %blob.location, "Stored blob content does not match its hash");
+            return Err(AppError::internal_error("Stored blob is corrupted"));
+        }
+        Ok(staged)
+    }
+
+    /// 分块计算文件的 sha256
+    async fn hash_file(path: &Path) -> std::io::Result<String> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; STAGING_CHUNK_SIZE];
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// 查询 blob 元数据
+    pub async fn find(&self, sha256: &str) -> Result<Option<ContentBlob>> {
+        sqlx::query_as::<_, ContentBlob>("SELECT * FROM content_blobs WHERE sha256 = $1")
+            .bind(sha256)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, sha256 = %sha256, "Failed to fetch blob");
+                AppError::database("Failed to fetch blob")
+            })
+    }
+
+    /// 按哈希读取内容，读取后校验哈希防止返回损坏的对象
+    pub async fn get(&self, sha256: &str) -> Result<Vec<u8>> {
+        let blob = self
+            .find(sha256)
+            .await?
+            .ok_or_else(|| AppError::not_found("Blob not found"))?;
+
+        let data = self.storage.get_object(&blob.location).await.map_err(|e| {
+            error!(error = %e, sha256 = %sha256, "Failed to read blob");
+            AppError::internal_error("Failed to read blob")
+        })?;
+        if Self::hash(&data) != blob.sha256 {
+            error!(sha256 = %sha256, location = %blob.location, "Stored blob content does not match its hash");
+            return Err(AppError::internal_error("Stored blob is corrupted"));
+        }
+        Ok(data)
+    }
+
+    /// 读取 UTF-8 文本内容（脚本）
+    pub async fn get_text(&self, sha256: &str) -> Result<String> {
+        String::from_utf8(self.get(sha256).await?).map_err(|_| {
+            error!(sha256 = %sha256, "Blob is not valid UTF-8 text");
+            AppError::internal_error("Blob is not valid UTF-8 text")
+        })
+    }
+
+    /// 登记引用（需与引用方记录在同一事务内），重复登记不会重复计数
+    pub async fn add_ref(
+        conn: &mut sqlx::PgConnection,
+        sha256: &str,
+        owner_type: &str,
+        owner_id: Uuid,
+    ) -> Result<bool> {
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, sha256 = %sha256, owner_type, owner_id = %owner_id, "Failed to add blob reference");
+            AppError::database("Failed to add blob reference")
+        };
+
+        let inserted = sqlx::query(
+            "INSERT INTO content_blob_refs (sha256, owner_type, owner_id)
+             VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+        )
+        .bind(sha256)
+        .bind(owner_type)
+        .bind(owner_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(map_err)?
+        .rows_affected()
+            > 0;
+
+        if inserted {
+            sqlx::query(
+                "UPDATE content_blobs SET ref_count = ref_count + 1, touched_at = NOW() WHERE sha256 = $1",
+            )
+            .bind(sha256)
+            .execute(&mut *conn)
+            .await
+            .map_err(map_err)?;
+        }
+        Ok(inserted)
+    }
+
+    /// 释放引用方持有的所有引用，返回释放数量
+    pub async fn release_owner(
+        conn: &mut sqlx::PgConnection,
+        owner_type: &str,
+        owner_id: Uuid,
+    ) -> Result<u64> {
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, owner_type, owner_id = %owner_id, "Failed to release blob references");
+            AppError::database("Failed to release blob references")
+        };
+
+        let released = sqlx::query_scalar::<_, String>(
+            "DELETE FROM content_blob_refs WHERE owner_type = $1 AND owner_id = $2 RETURNING sha256",
+        )
+        .bind(owner_type)
+        .bind(owner_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(map_err)?;
+
+        if !released.is_empty() {
+            sqlx::query(
+                "UPDATE content_blobs b SET ref_count = b.ref_count - r.cnt, touched_at = NOW()
+                 FROM (SELECT sha256, COUNT(*)::INT AS cnt FROM UNNEST($1::TEXT[]) AS sha256 GROUP BY sha256) r
+                 WHERE b.sha256 = r.sha256",
+            )
+            .bind(&released)
+            .execute(&mut *conn)
+            .await
+            .map_err(map_err)?;
+        }
+        Ok(released.len() as u64)
+    }
+
+    /// 引用该 blob 的引用方类型（用于按引用方权限控制读取）
+    pub async fn owner_types(&self, sha256: &str) -> Result<Vec<String>> {
+        sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT owner_type FROM content_blob_refs WHERE sha256 = $1 ORDER BY owner_type",
+        )
+        .bind(sha256)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, sha256 = %sha256, "Failed to fetch blob owners");
+            AppError::database("Failed to fetch blob owners")
+        })
+    }
+
+    /// 去重统计
+    pub async fn stats(&self) -> Result<BlobStats> {
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, "Failed to compute blob statistics");
+            AppError::database("Failed to compute blob statistics")
+        };
+
+        let (blob_count, stored_bytes, unreferenced_count, unreferenced_bytes) =
+            sqlx::query_as::<_, (i64, i64, i64, i64)>(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(size_bytes), 0)::BIGINT,
+                        COUNT(*) FILTER (WHERE ref_count = 0),
+                        COALESCE(SUM(size_bytes) FILTER (WHERE ref_count = 0), 0)::BIGINT
+                 FROM content_blobs",
+            )
+            .fetch_one(&self.db)
+            .await
+            .map_err(map_err)?;
+
+        let by_owner_type = sqlx::query_as::<_, BlobOwnerStats>(
+            r#"SELECT r.owner_type,
+                      COUNT(*) AS "references",
+                      COALESCE(SUM(b.size_bytes), 0)::BIGINT AS logical_bytes
+               FROM content_blob_refs r
+               JOIN content_blobs b ON b.sha256 = r.sha256
+               GROUP BY r.owner_type
+               ORDER BY r.owner_type"#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(map_err)?;
+
+        Ok(BlobStats::from_totals(
+            blob_count,
+            stored_bytes,
+            unreferenced_count,
+            unreferenced_bytes,
+            by_owner_type,
+        ))
+    }
+
+    /// 回收无引用且超过宽限期的 blob
+    ///
+    /// 每个 blob 在独立事务内先删除记录再删除对象，对象删除失败时回滚记录，下轮重试
+    pub async fn collect_garbage(&self) -> Result<BlobGcReport> {
+        let grace_secs = self.config.gc_grace_secs as f64;
+        let candidates = sqlx::query_scalar::<_, String>(
+            "SELECT sha256 FROM content_blobs
+             WHERE ref_count = 0 AND touched_at < NOW() - make_interval(secs => $1)
+             ORDER BY touched_at LIMIT $2",
+        )
+        .bind(grace_secs)
+        .bind(GC_BATCH_SIZE)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to find unreferenced blobs");
+            AppError::database("Failed to find unreferenced blobs")
+        })?;
+
+        let mut report = BlobGcReport::default();
+        for sha256 in candidates {
+            match self.collect_one(&sha256, grace_secs).await {
+                Ok(Some(size_bytes)) => {
+                    report.deleted_count += 1;
+                    report.deleted_bytes += size_bytes;
+                }
+                Ok(None) => {}
+                Err(e) => warn!(error = %e, sha256 = %sha256, "Failed to collect blob"),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// 回收单个 blob，复查条件不满足（期间被重新引用或上传）时返回 None
+    async fn collect_one(&self, sha256: &str, grace_secs: f64) -> Result<Option<i64>> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        let deleted = sqlx::query_as::<_, (String, i64)>(
+            "DELETE FROM content_blobs
+             WHERE sha256 = $1 AND ref_count = 0 AND touched_at < NOW() - make_interval(secs => $2)
+             RETURNING location, size_bytes",
+        )
+        .bind(sha256)
+        .bind(grace_secs)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, sha256 = %sha256, "Failed to delete blob record");
+            AppError::database("Failed to delete blob")
+        })?;
+        let Some((location, size_bytes)) = deleted else {
+            return Ok(None);
+        };
+
+        self.storage.delete_object(&location).await.map_err(|e| {
+            error!(error = %e, sha256 = %sha256, location = %location, "Failed to delete blob object");
+            AppError::internal_error("Failed to delete blob object")
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        Ok(Some(size_bytes))
+    }
+}
+
+/// 本地暂存文件，释放时删除
+#[derive(Debug)]
+pub struct StagedFile(PathBuf);
+
+impl StagedFile {
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!(error = %e, path = %self.0.display(), "Failed to remove staging file");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_is_lowercase_sha256() {
+        assert_eq!(
+            BlobStore::hash(b"hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_normalize_hash() {
+        let hash = "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824";
+        assert_eq!(BlobStore::normalize_hash(hash), Some(hash.to_ascii_lowercase()));
+        assert_eq!(BlobStore::normalize_hash("abc"), None);
+        assert_eq!(BlobStore::normalize_hash(&"g".repeat(64)), None);
+        assert_eq!(BlobStore::normalize_hash(&format!("../{}", &hash[3..])), None);
+    }
+
+    #[tokio::test]
+    async fn test_hash_file_and_staged_file_cleanup() {
+        let path = std::env::temp_dir().join(format!("ops-blob-test-{}", Uuid::new_v4()));
+        let data = vec![7u8; STAGING_CHUNK_SIZE * 2 + 3];
+        std::fs::write(&path, &data).unwrap();
+        assert_eq!(BlobStore::hash_file(&path).await.unwrap(), BlobStore::hash(&data));
+
+        drop(StagedFile(path.clone()));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_blob_key_layout() {
+        let hash = BlobStore::hash(b"hello");
+        assert_eq!(StorageService::blob_key(&hash), format!("blobs/sha256/2c/{}", hash));
+    }
+}
@@ -0,0 +1,432 @@
+//! 主机连接测试
+//!
+//! 依次执行 DNS 解析、TCP 连接、SSH 握手、认证与一条简单命令，按阶段返回成功与否、耗时与错误，
+//! 便于排查新主机接入问题而无需创建作业。阶段耗时复用任务失败诊断的收集器。
+//! 资产组可批量测试，结果汇总后存档
+
+use chrono::Utc;
+use futures::StreamExt;
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::asset::{
+    ConnectionStageStatus, ConnectionTestOutcome, ConnectionTestReport, ConnectionTestRun,
+    ConnectionTestRunStatus, ConnectionTestStage, ConnectionTestSummary, Host,
+};
+use crate::realtime::{EventBus, RealtimeEvent};
+use crate::services::JobService;
+use crate::ssh::diagnostics::PhaseTiming;
+use crate::ssh::{ConnectionPhase, ExecutionResult};
+
+/// 测试阶段执行的命令
+pub const CONNECTION_TEST_COMMAND: &str = "echo ok";
+
+/// 测试命令超时（秒）
+pub const CONNECTION_TEST_COMMAND_TIMEOUT_SECS: u64 = 15;
+
+/// 按发生顺序排列的测试阶段
+const STAGES: [ConnectionPhase; 5] = [
+    ConnectionPhase::Dns,
+    ConnectionPhase::TcpConnect,
+    ConnectionPhase::Handshake,
+    ConnectionPhase::Auth,
+    ConnectionPhase::Exec,
+];
+
+/// 根据阶段耗时与执行结果生成测试报告
+///
+/// 没有耗时记录的阶段标记为跳过（前序阶段失败，或执行器不区分该阶段）
+pub fn build_report(
+    host: &Host,
+    timings: &[PhaseTiming],
+    result: &Result<ExecutionResult>,
+    total_ms: u64,
+) -> ConnectionTestReport {
+    let error = match result {
+        Ok(r) if r.timed_out => Some("Test command timed out".to_string()),
+        Ok(r) if r.exit_code != 0 => Some(format!("Test command exited with {}", r.exit_code)),
+        Ok(_) => None,
+        Err(e) => Some(e.to_string()),
+    };
+    let command_failed = matches!(result, Ok(r) if r.timed_out || r.exit_code != 0);
+
+    let stages: Vec<ConnectionTestStage> = STAGES
+        .iter()
+        .map(|phase| match timings.iter().find(|t| t.phase == *phase) {
+            Some(timing) => {
+                let failed =
+                    !timing.succeeded || (*phase == ConnectionPhase::Exec && command_failed);
+                ConnectionTestStage {
+                    stage: *phase,
+                    status: if failed {
+                        ConnectionStageStatus::Failed
+                    } else {
+                        ConnectionStageStatus::Success
+                    },
+                    latency_ms: Some(timing.duration_ms),
+                    error: if failed { error.clone() } else { None },
+                }
+            }
+            None => ConnectionTestStage {
+                stage: *phase,
+                status: ConnectionStageStatus::Skipped,
+                latency_ms: None,
+                error: None,
+            },
+        })
+        .collect();
+
+    let failed_stage = stages
+        .iter()
+        .find(|s| s.status == ConnectionStageStatus::Failed)
+        .map(|s| s.stage);
+    let outcome = match result {
+        Ok(_) if command_failed => ConnectionTestOutcome::CommandFailed,
+        Ok(_) => ConnectionTestOutcome::Reachable,
+        Err(AppError::SshAuthenticationError(_)) => ConnectionTestOutcome::AuthFailed,
+        Err(AppError::SshHostKeyVerificationError(_)) => ConnectionTestOutcome::HostKeyMismatch,
+        Err(_) if failed_stage == Some(ConnectionPhase::Exec) => {
+            ConnectionTestOutcome::CommandFailed
+        }
+        Err(_) => ConnectionTestOutcome::Unreachable,
+    };
+
+    ConnectionTestReport {
+        host_id: host.id,
+        host_identifier: host.identifier.clone(),
+        address: host.address.clone(),
+        port: host.port,
+        success: outcome == ConnectionTestOutcome::Reachable,
+        outcome,
+        stages,
+        error,
+        total_ms,
+        tested_at: Utc::now(),
+    }
+}
+
+/// 批量测试默认并发数
+pub const DEFAULT_GROUP_TEST_CONCURRENCY: u32 = 10;
+
+/// 批量测试最大并发数
+pub const MAX_GROUP_TEST_CONCURRENCY: u32 = 50;
+
+/// 资产组批量连接测试
+///
+/// 以有限并发对组内全部主机执行分阶段测试，每完成一台推送进度事件，
+/// 结束后将各主机报告与汇总（可达、认证失败、密钥不一致等）写入 connection_test_runs 供后续查阅
+pub struct GroupConnectionTester {
+    db: Pool<Postgres>,
+    job_service: Arc<JobService>,
+    event_bus: Arc<EventBus>,
+}
+
+impl GroupConnectionTester {
+    pub fn new(db: Pool<Postgres>, job_service: Arc<JobService>, event_bus: Arc<EventBus>) -> Self {
+        Self {
+            db,
+            job_service,
+            event_bus,
+        }
+    }
+
+    /// 创建测试记录并在后台执行
+    pub async fn start(
+        self: &Arc<Self>,
+        group_id: Uuid,
+        hosts: Vec<Host>,
+        concurrency: Option<u32>,
+        requested_by: Uuid,
+    ) -> Result<ConnectionTestRun> {
+        let concurrency = concurrency
+            .unwrap_or(DEFAULT_GROUP_TEST_CONCURRENCY)
+            .clamp(1, MAX_GROUP_TEST_CONCURRENCY);
+
+        let run = sqlx::query_as::<_, ConnectionTestRun>(
+            "INSERT INTO connection_test_runs (group_id, requested_by, concurrency, total_hosts)
+             VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(group_id)
+        .bind(requested_by)
+        .bind(concurrency as i32)
+        .bind(hosts.len() as i32)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, group_id = %group_id, "Failed to create connection test run");
+            AppError::database("Failed to create connection test run")
+        })?;
+
+        let tester = self.clone();
+        let run_for_task = run.clone();
+        tokio::spawn(async move {
+            tester.execute(run_for_task, hosts).await;
+        });
+
+        Ok(run)
+    }
+
+    async fn execute(&self, run: ConnectionTestRun, hosts: Vec<Host>) {
+        let total = hosts.len() as i32;
+        let mut reports: Vec<ConnectionTestReport> = Vec::with_capacity(hosts.len());
+
+        let mut stream = futures::stream::iter(hosts)
+            .map(|host| async move { self.job_service.test_host_connection(&host).await })
+            .buffer_unordered(run.concurrency.max(1) as usize);
+
+        while let Some(report) = stream.next().await {
+            let completed = reports.len() as i32 + 1;
+            if let Err(e) =
+                sqlx::query("UPDATE connection_test_runs SET completed_hosts = $2 WHERE id = $1")
+                    .bind(run.id)
+                    .bind(completed)
+                    .execute(&self.db)
+                    .await
+            {
+                warn!(error = %e, run_id = %run.id, "Failed to update connection test progress");
+            }
+            self.publish_progress(&run, ConnectionTestRunStatus::Running, completed, Some(&report));
+            reports.push(report);
+        }
+
+        let summary = ConnectionTestSummary::from_reports(&reports);
+        let status = match sqlx::query(
+            "UPDATE connection_test_runs
+             SET status = 'completed', completed_hosts = $2, summary = $3, results = $4, completed_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(run.id)
+        .bind(total)
+        .bind(Json(&summary))
+        .bind(Json(&reports))
+        .execute(&self.db)
+        .await
+        {
+            Ok(_) => ConnectionTestRunStatus::Completed,
+            Err(e) => {
+                error!(error = %e, run_id = %run.id, "Failed to store connection test results");
+                let _ = sqlx::query(
+                    "UPDATE connection_test_runs
+                     SET status = 'failed', error_message = $2, completed_at = NOW()
+                     WHERE id = $1",
+                )
+                .bind(run.id)
+                .bind("Failed to store connection test results")
+                .execute(&self.db)
+                .await;
+                ConnectionTestRunStatus::Failed
+            }
+        };
+
+        info!(
+            run_id = %run.id,
+            group_id = %run.group_id,
+            total = summary.total,
+            reachable = summary.reachable,
+            auth_failed = summary.auth_failed,
+            host_key_mismatch = summary.host_key_mismatch,
+            "Group connection test finished"
+        );
+        self.publish_progress(&run, status, total, None);
+    }
+
+    /// 进度事件只用于界面反馈，直接发布（不经发件箱），丢失时以查询接口为准
+    fn publish_progress(
+        &self,
+        run: &ConnectionTestRun,
+        status: ConnectionTestRunStatus,
+        completed: i32,
+        report: Option<&ConnectionTestReport>,
+    ) {
+        let event = RealtimeEvent::ConnectionTestProgress {
+            run_id: run.id,
+            group_id: run.group_id,
+            user_id: run.requested_by,
+            status: status.as_str().to_string(),
+            completed,
+            total: run.total_hosts,
+            host_id: report.map(|r| r.host_id),
+            outcome: report.map(|r| r.outcome.as_str().to_string()),
+        };
+        if let Err(e) = self.event_bus.publish(event) {
+            warn!(error = %e, run_id = %run.id, "Failed to publish connection test progress");
+        }
+    }
+
+    /// 列出资产组的测试记录（不含各主机明细）
+    pub async fn list(&self, group_id: Uuid, limit: i64) -> Result<Vec<ConnectionTestRun>> {
+        sqlx::query_as::<_, ConnectionTestRun>(
+            "SELECT id, group_id, requested_by, status, concurrency, total_hosts, completed_hosts,
+                    summary, NULL::jsonb AS results, error_message, created_at, completed_at
+             FROM connection_test_runs WHERE group_id = $1
+             ORDER BY created_at DESC LIMIT $2",
+        )
+        .bind(group_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, group_id = %group_id, "Failed to list connection test runs");
+            AppError::database("Failed to list connection test runs")
+        })
+    }
+
+    pub async fn get(&self, run_id: Uuid) -> Result<Option<ConnectionTestRun>> {
+        sqlx::query_as::<_, ConnectionTestRun>("SELECT * FROM connection_test_runs WHERE id = $1")
+            .bind(run_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, run_id = %run_id, "Failed to load connection test run");
+                AppError::database("Failed to load connection test run")
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh::{HostKeyFailure, HostKeyFailureKind};
+
+    fn host() -> Host {
+        Host {
+            id: uuid::Uuid::new_v4(),
+            identifier: "web-01".to_string(),
+            display_name: None,
+            address: "10.0.0.1".to_string(),
+            port: 22,
+            group_id: uuid::Uuid::new_v4(),
+            environment: "dev".to_string(),
+            tags: sqlx::types::Json(vec![]),
+            owner_id: None,
+            status: "active".to_string(),
+            notes: None,
+            os_type: None,
+            os_version: None,
+            arch: None,
+            ssh_username: None,
+            ssh_password: None,
+            ssh_private_key: None,
+            ssh_key_passphrase: None,
+            host_key_verification: None,
+            known_hosts: None,
+            output_encoding: None,
+            connection_type: "ssh".to_string(),
+            connection_options: None,
+            connection_token: None,
+            maintenance_until: None,
+            maintenance_reason: None,
+            maintenance_started_at: None,
+            maintenance_set_by: None,
+            host_key_blocked_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: None,
+            updated_by: None,
+            version: 1,
+        }
+    }
+
+    fn timing(phase: ConnectionPhase, succeeded: bool) -> PhaseTiming {
+        PhaseTiming {
+            phase,
+            duration_ms: 5,
+            succeeded,
+        }
+    }
+
+    #[test]
+    fn test_all_stages_succeed() {
+        let timings: Vec<_> = STAGES.iter().map(|p| timing(*p, true)).collect();
+        let result = Ok(ExecutionResult::success("ok".to_string(), 0.1));
+
+        let report = build_report(&host(), &timings, &result, 42);
+        assert!(report.success);
+        assert_eq!(report.outcome, ConnectionTestOutcome::Reachable);
+        assert!(report
+            .stages
+            .iter()
+            .all(|s| s.status == ConnectionStageStatus::Success));
+        assert_eq!(report.error, None);
+    }
+
+    #[test]
+    fn test_auth_failure_skips_exec() {
+        let timings = vec![
+            timing(ConnectionPhase::Dns, true),
+            timing(ConnectionPhase::TcpConnect, true),
+            timing(ConnectionPhase::Handshake, true),
+            timing(ConnectionPhase::Auth, false),
+        ];
+        let result = Err(AppError::SshAuthenticationError("SSH认证失败".to_string()));
+
+        let report = build_report(&host(), &timings, &result, 42);
+        assert!(!report.success);
+        assert_eq!(report.outcome, ConnectionTestOutcome::AuthFailed);
+        assert_eq!(report.stages[3].status, ConnectionStageStatus::Failed);
+        assert!(report.stages[3].error.is_some());
+        assert_eq!(report.stages[4].status, ConnectionStageStatus::Skipped);
+    }
+
+    #[test]
+    fn test_non_zero_exit_fails_exec_stage() {
+        let timings: Vec<_> = STAGES.iter().map(|p| timing(*p, true)).collect();
+        let result = Ok(ExecutionResult::failure(127, String::new(), "not found".to_string(), 0.1));
+
+        let report = build_report(&host(), &timings, &result, 42);
+        assert_eq!(report.outcome, ConnectionTestOutcome::CommandFailed);
+        assert_eq!(report.stages[4].status, ConnectionStageStatus::Failed);
+        assert_eq!(report.stages[4].error.as_deref(), Some("Test command exited with 127"));
+    }
+
+    #[test]
+    fn test_tcp_failure_is_unreachable() {
+        let timings = vec![
+            timing(ConnectionPhase::Dns, true),
+            timing(ConnectionPhase::TcpConnect, false),
+        ];
+        let result = Err(AppError::SshConnectionError("TCP连接失败".to_string()));
+
+        let report = build_report(&host(), &timings, &result, 42);
+        assert_eq!(report.outcome, ConnectionTestOutcome::Unreachable);
+        assert_eq!(report.stages[2].status, ConnectionStageStatus::Skipped);
+    }
+
+    #[test]
+    fn test_summary_counts_outcomes() {
+        let all_ok: Vec<_> = STAGES.iter().map(|p| timing(*p, true)).collect();
+        let ok =
+            build_report(&host(), &all_ok, &Ok(ExecutionResult::success("ok".to_string(), 0.1)), 1);
+        let auth = build_report(
+            &host(),
+            &[timing(ConnectionPhase::Auth, false)],
+            &Err(AppError::SshAuthenticationError("denied".to_string())),
+            1,
+        );
+        let key = build_report(
+            &host(),
+            &[timing(ConnectionPhase::Handshake, false)],
+            &Err(AppError::SshHostKeyVerificationError(Box::new(HostKeyFailure {
+                host: "10.0.0.1".to_string(),
+                port: 22,
+                kind: HostKeyFailureKind::Mismatch,
+                expected_fingerprint: Some("SHA256:old".to_string()),
+                presented_fingerprint: "SHA256:new".to_string(),
+                key_type: "ssh-ed25519".to_string(),
+                public_key: "AAAA".to_string(),
+            }))),
+            1,
+        );
+
+        let summary = ConnectionTestSummary::from_reports(&[ok.clone(), ok, auth, key]);
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.reachable, 2);
+        assert_eq!(summary.auth_failed, 1);
+        assert_eq!(summary.host_key_mismatch, 1);
+        assert_eq!(summary.unreachable, 0);
+    }
+}
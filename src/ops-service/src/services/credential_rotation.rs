@@ -0,0 +1,737 @@
+//! 主机凭据轮换
+//!
+//! 分阶段完成 SSH 密钥轮换，每个阶段的进度写入 credential_rotations：
+//! 1. 推送：以作业（使用主机当前凭据登录）向登录用户的 authorized_keys 追加新公钥
+//! 2. 验证：使用新私钥登录并执行测试命令
+//! 3. 替换：将主机存储的私钥替换为新私钥
+//! 4. 退役：以作业（此时已使用新凭据登录）删除旧的主机级公钥
+//!
+//! 验证或替换失败的主机自动回滚（以作业删除新公钥），凭据保持不变。
+//! 使用密码或全局默认私钥的主机不删除旧凭据（全局私钥为多台主机共用）
+
+use futures::StreamExt;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::ssh_key::{LineEnding, PrivateKey};
+use russh::keys::{decode_secret_key, PublicKeyBase64};
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::cache;
+use crate::error::{AppError, Result};
+use crate::models::asset::{
+    CredentialRotation, CredentialRotationPhase, CredentialRotationStatus, Host,
+    HostRotationOutcome, HostRotationReport, StartCredentialRotationRequest,
+};
+use crate::models::job::{CreateCommandJobRequest, SingletonPolicy, TaskStatus};
+use crate::services::audit_service::AuditAction;
+use crate::services::{AuditService, JobService};
+use crate::ssh::{host_key, SshAuth};
+
+/// 单次轮换的主机数上限
+pub const MAX_ROTATION_HOSTS: usize = 500;
+
+/// 并发验证新密钥的主机数
+const VERIFY_CONCURRENCY: usize = 10;
+
+/// 推送、退役、回滚作业的单任务超时（秒）
+const KEY_JOB_TIMEOUT_SECS: i32 = 60;
+
+/// 等待作业结束的最长时间（作业可能需要审批或等待主机维护结束）
+const JOB_WAIT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// 作业结束检查间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 轮换使用的新密钥
+pub struct RotationKey {
+    /// 私钥（写入主机凭据）
+    private_key: String,
+    passphrase: Option<String>,
+    /// authorized_keys 行
+    pub public_key: String,
+    /// 公钥数据（base64）
+    pub key_base64: String,
+    pub fingerprint: String,
+    /// generated 或 provided
+    pub source: &'static str,
+}
+
+impl RotationKey {
+    /// 解析提供的私钥，未提供时生成 Ed25519 密钥；公钥行以 comment 标注来源
+    pub fn prepare(
+        private_key: Option<&str>,
+        passphrase: Option<&str>,
+        comment: &str,
+    ) -> Result<Self> {
+        let (key, private_key, source) = match private_key {
+            Some(pem) => {
+                let key = decode_secret_key(pem, passphrase)
+                    .map_err(|_| AppError::validation("Invalid private key or passphrase"))?;
+                (key, pem.to_string(), "provided")
+            }
+            None => {
+                let seed: [u8; 32] = rand::random();
+                let key = PrivateKey::from(Ed25519Keypair::from_seed(&seed));
+                let pem = key.to_openssh(LineEnding::LF).map_err(|e| {
+                    error!(error = %e, "Failed to encode generated SSH key");
+                    AppError::internal_error("Failed to generate SSH key")
+                })?;
+                (key, pem.to_string(), "generated")
+            }
+        };
+
+        let key_base64 = key.public_key().public_key_base64();
+        let fingerprint = host_key::fingerprint(&key_base64)
+            .ok_or_else(|| AppError::internal_error("Failed to fingerprint SSH key"))?;
+        Ok(Self {
+            public_key: format!("{} {} {}", key.algorithm().as_str(), key_base64, comment),
+            key_base64,
+            fingerprint,
+            source,
+            private_key,
+            passphrase: passphrase.map(str::to_string),
+        })
+    }
+
+    fn auth(&self) -> SshAuth {
+        SshAuth::Key {
+            private_key: self.private_key.clone(),
+            passphrase: self.passphrase.clone(),
+        }
+    }
+}
+
+/// 主机级私钥对应的公钥数据（base64）；使用密码、全局默认凭据或私钥无法解析时为空
+fn host_key_base64(host: &Host) -> Option<String> {
+    let private_key = host.ssh_private_key.as_deref()?;
+    match decode_secret_key(private_key, host.ssh_key_passphrase.as_deref()) {
+        Ok(key) => Some(key.public_key().public_key_base64()),
+        Err(e) => {
+            warn!(error = %e, host = %host.identifier, "Failed to decode host private key");
+            None
+        }
+    }
+}
+
+/// 向登录用户的 authorized_keys 追加公钥（已存在时不重复追加）
+pub fn push_key_command(public_key: &str) -> String {
+    format!(
+        "umask 077; mkdir -p ~/.ssh && touch ~/.ssh/authorized_keys && \
+         (grep -qxF '{key}' ~/.ssh/authorized_keys || echo '{key}' >> ~/.ssh/authorized_keys)",
+        key = public_key
+    )
+}
+
+/// 从登录用户的 authorized_keys 删除包含指定公钥数据的行（保留文件权限，grep 出错时不改写）
+pub fn remove_key_command(key_base64: &str) -> String {
+    format!(
+        "f=~/.ssh/authorized_keys; [ -f \"$f\" ] || exit 0; umask 077; \
+         grep -vF '{key}' \"$f\" > \"$f.ops-rotate\"; \
+         [ $? -le 1 ] && cat \"$f.ops-rotate\" > \"$f\"; s=$?; rm -f \"$f.ops-rotate\"; exit $s",
+        key = key_base64
+    )
+}
+
+/// 轮换中修改 authorized_keys 的作业
+#[derive(Debug, Clone, Copy)]
+enum KeyJob {
+    /// 追加新公钥
+    Push,
+    /// 删除旧公钥
+    Retire,
+    /// 删除新公钥
+    Rollback,
+}
+
+impl KeyJob {
+    fn name(&self) -> &'static str {
+        match self {
+            KeyJob::Push => "Credential rotation: push new key",
+            KeyJob::Retire => "Credential rotation: retire old key",
+            KeyJob::Rollback => "Credential rotation: roll back new key",
+        }
+    }
+
+    /// 记录作业 ID 的语句
+    fn record_sql(&self) -> &'static str {
+        match self {
+            KeyJob::Push => "UPDATE credential_rotations SET push_job_id = $2 WHERE id = $1",
+            KeyJob::Retire => {
+                "UPDATE credential_rotations SET retire_job_ids = array_append(retire_job_ids, $2)
+                 WHERE id = $1"
+            }
+            KeyJob::Rollback => {
+                "UPDATE credential_rotations SET rollback_job_id = $2 WHERE id = $1"
+            }
+        }
+    }
+}
+
+/// 记录主机在某阶段失败
+fn mark_failed(
+    reports: &mut HashMap<Uuid, HostRotationReport>,
+    host_id: Uuid,
+    outcome: HostRotationOutcome,
+    phase: CredentialRotationPhase,
+    error: String,
+) {
+    if let Some(report) = reports.get_mut(&host_id) {
+        report.outcome = outcome;
+        report.failed_phase = Some(phase);
+        report.error = Some(error);
+    }
+}
+
+/// 根据各主机结果确定轮换状态
+pub fn rotation_status(reports: &[HostRotationReport]) -> CredentialRotationStatus {
+    let rotated = reports.iter().filter(|r| r.outcome.is_rotated()).count();
+    if rotated == reports.len() {
+        CredentialRotationStatus::Completed
+    } else if rotated == 0 {
+        CredentialRotationStatus::Failed
+    } else {
+        CredentialRotationStatus::PartiallyCompleted
+    }
+}
+
+/// 主机凭据轮换
+pub struct CredentialRotator {
+    db: Pool<Postgres>,
+    job_service: Arc<JobService>,
+    audit_service: Arc<AuditService>,
+}
+
+impl CredentialRotator {
+    pub fn new(
+        db: Pool<Postgres>,
+        job_service: Arc<JobService>,
+        audit_service: Arc<AuditService>,
+    ) -> Self {
+        Self {
+            db,
+            job_service,
+            audit_service,
+        }
+    }
+
+    /// 准备新密钥、创建轮换记录并在后台执行
+    pub async fn start(
+        self: &Arc<Self>,
+        hosts: Vec<Host>,
+        request: StartCredentialRotationRequest,
+        requested_by: Uuid,
+    ) -> Result<CredentialRotation> {
+        if hosts.is_empty() {
+            return Err(AppError::validation("No target hosts for credential rotation"));
+        }
+        if hosts.len() > MAX_ROTATION_HOSTS {
+            return Err(AppError::validation(&format!(
+                "Credential rotation is limited to {} hosts",
+                MAX_ROTATION_HOSTS
+            )));
+        }
+
+        let rotation_id = Uuid::new_v4();
+        let key = RotationKey::prepare(
+            request.private_key.as_deref(),
+            request.key_passphrase.as_deref(),
+            &format!("ops-rotation-{}", rotation_id.simple()),
+        )?;
+
+        let rotation = sqlx::query_as::<_, CredentialRotation>(
+            "INSERT INTO credential_rotations
+                 (id, requested_by, key_source, public_key, key_fingerprint, retire_old_key,
+                  total_hosts)
+             VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *",
+        )
+        .bind(rotation_id)
+        .bind(requested_by)
+        .bind(key.source)
+        .bind(&key.public_key)
+        .bind(&key.fingerprint)
+        .bind(request.retire_old_key)
+        .bind(hosts.len() as i32)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to create credential rotation");
+            AppError::database("Failed to create credential rotation")
+        })?;
+
+        let rotator = self.clone();
+        let rotation_for_task = rotation.clone();
+        tokio::spawn(async move {
+            rotator.execute(rotation_for_task, hosts, key).await;
+        });
+
+        Ok(rotation)
+    }
+
+    async fn execute(&self, rotation: CredentialRotation, hosts: Vec<Host>, key: RotationKey) {
+        match self.run(&rotation, hosts, &key).await {
+            Ok(reports) => {
+                let status = rotation_status(&reports);
+                let rotated = reports.iter().filter(|r| r.outcome.is_rotated()).count();
+                info!(
+                    rotation_id = %rotation.id,
+                    status = ?status,
+                    rotated,
+                    total = reports.len(),
+                    "Credential rotation finished"
+                );
+                if let Err(e) = sqlx::query(
+                    "UPDATE credential_rotations
+                     SET status = $2, phase = 'done', results = $3, completed_at = NOW()
+                     WHERE id = $1",
+                )
+                .bind(rotation.id)
+                .bind(status)
+                .bind(Json(&reports))
+                .execute(&self.db)
+                .await
+                {
+                    error!(
+                        error = %e,
+                        rotation_id = %rotation.id,
+                        "Failed to store rotation results"
+                    );
+                }
+            }
+            Err(e) => {
+                error!(error = %e, rotation_id = %rotation.id, "Credential rotation failed");
+                if let Err(e) = sqlx::query(
+                    "UPDATE credential_rotations
+                     SET status = 'failed', error_message = $2, completed_at = NOW()
+                     WHERE id = $1",
+                )
+                .bind(rotation.id)
+                .bind(e.to_string())
+                .execute(&self.db)
+                .await
+                {
+                    error!(
+                        error = %e,
+                        rotation_id = %rotation.id,
+                        "Failed to store rotation error"
+                    );
+                }
+            }
+        }
+    }
+
+    async fn run(
+        &self,
+        rotation: &CredentialRotation,
+        hosts: Vec<Host>,
+        key: &RotationKey,
+    ) -> Result<Vec<HostRotationReport>> {
+        let mut reports: HashMap<Uuid, HostRotationReport> = hosts
+            .iter()
+            .map(|host| {
+                let report = HostRotationReport {
+                    host_id: host.id,
+                    host_identifier: host.identifier.clone(),
+                    outcome: HostRotationOutcome::Rotated,
+                    failed_phase: None,
+                    old_key_fingerprint: host_key_base64(host)
+                        .and_then(|k| host_key::fingerprint(&k)),
+                    error: None,
+                };
+                (host.id, report)
+            })
+            .collect();
+
+        // 推送新公钥（使用当前凭据登录）
+        let host_ids: Vec<Uuid> = hosts.iter().map(|h| h.id).collect();
+        let push_failures = self
+            .run_key_job(rotation, KeyJob::Push, &host_ids, push_key_command(&key.public_key))
+            .await?;
+        let mut pushed = Vec::new();
+        for host in &hosts {
+            match push_failures.get(&host.id) {
+                Some(error) => mark_failed(
+                    &mut reports,
+                    host.id,
+                    HostRotationOutcome::PushFailed,
+                    CredentialRotationPhase::Push,
+                    error.clone(),
+                ),
+                None => pushed.push(host.clone()),
+            }
+        }
+
+        // 使用新私钥登录验证
+        self.set_phase(rotation.id, CredentialRotationPhase::Verify)
+            .await;
+        // 每个验证持有自己的主机与凭据，不借用循环外的数据
+        let verified: Vec<(Host, Result<()>)> = futures::stream::iter(pushed)
+            .map(|host| {
+                let job_service = self.job_service.clone();
+                let auth = key.auth();
+                async move {
+                    let result = job_service.verify_host_auth(&host, auth).await;
+                    (host, result)
+                }
+            })
+            .buffer_unordered(VERIFY_CONCURRENCY)
+            .collect()
+            .await;
+
+        // 替换存储的凭据
+        self.set_phase(rotation.id, CredentialRotationPhase::Swap)
+            .await;
+        let mut rollback = Vec::new();
+        let mut swapped = Vec::new();
+        for (host, result) in verified {
+            if let Err(e) = result {
+                warn!(error = %e, host = %host.identifier, "New key verification failed");
+                rollback.push((host.id, CredentialRotationPhase::Verify, e.to_string()));
+                continue;
+            }
+            match self.swap_credential(rotation, &host, key).await {
+                Ok(()) => swapped.push(host),
+                Err(e) => rollback.push((host.id, CredentialRotationPhase::Swap, e.to_string())),
+            }
+        }
+
+        // 回滚验证或替换失败的主机（凭据未变更，仍使用旧凭据登录）
+        if !rollback.is_empty() {
+            let rollback_hosts: Vec<Uuid> = rollback.iter().map(|(id, _, _)| *id).collect();
+            let failures = self
+                .run_key_job(
+                    rotation,
+                    KeyJob::Rollback,
+                    &rollback_hosts,
+                    remove_key_command(&key.key_base64),
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    rollback_hosts
+                        .iter()
+                        .map(|id| (*id, e.to_string()))
+                        .collect()
+                });
+            for (host_id, phase, error) in rollback {
+                let outcome = match failures.get(&host_id) {
+                    Some(rollback_error) => {
+                        warn!(
+                            host_id = %host_id,
+                            error = %rollback_error,
+                            "Failed to roll back new key"
+                        );
+                        HostRotationOutcome::RollbackFailed
+                    }
+                    None => HostRotationOutcome::RolledBack,
+                };
+                mark_failed(&mut reports, host_id, outcome, phase, error);
+            }
+        }
+
+        // 删除旧的主机级公钥（已切换到新凭据登录）；同一旧公钥的主机共用一个作业
+        self.set_phase(rotation.id, CredentialRotationPhase::Retire)
+            .await;
+        let mut retire_groups: BTreeMap<String, Vec<Uuid>> = BTreeMap::new();
+        for host in swapped {
+            let old_key = host_key_base64(&host).filter(|k| *k != key.key_base64);
+            match old_key {
+                Some(old_key) if rotation.retire_old_key => {
+                    retire_groups.entry(old_key).or_default().push(host.id);
+                }
+                _ => {
+                    if let Some(report) = reports.get_mut(&host.id) {
+                        report.outcome = HostRotationOutcome::RotatedOldKeyRetained;
+                    }
+                }
+            }
+        }
+        for (old_key, host_ids) in retire_groups {
+            let failures = self
+                .run_key_job(rotation, KeyJob::Retire, &host_ids, remove_key_command(&old_key))
+                .await
+                .unwrap_or_else(|e| host_ids.iter().map(|id| (*id, e.to_string())).collect());
+            for (host_id, error) in failures {
+                mark_failed(
+                    &mut reports,
+                    host_id,
+                    HostRotationOutcome::RotatedOldKeyRetained,
+                    CredentialRotationPhase::Retire,
+                    error,
+                );
+            }
+        }
+
+        Ok(hosts
+            .iter()
+            .filter_map(|host| reports.remove(&host.id))
+            .collect())
+    }
+
+    /// 以命令作业在主机上修改 authorized_keys 并等待结束
+    ///
+    /// 返回未成功主机的错误；作业超时未结束时取消
+    async fn run_key_job(
+        &self,
+        rotation: &CredentialRotation,
+        kind: KeyJob,
+        host_ids: &[Uuid],
+        command: String,
+    ) -> Result<HashMap<Uuid, String>> {
+        let job = self
+            .job_service
+            .create_command_job(
+                CreateCommandJobRequest {
+                    name: kind.name().to_string(),
+                    description: Some(format!("Credential rotation {}", rotation.id)),
+                    target_hosts: host_ids.to_vec(),
+                    target_groups: Vec::new(),
+                    target_set_id: None,
+                    command,
+                    concurrent_limit: None,
+                    timeout_secs: Some(KEY_JOB_TIMEOUT_SECS),
+                    retry_times: Some(0),
+                    execute_user: None,
+                    exit_code_rules: None,
+                    file_manifest: None,
+                    drift_check: false,
+                    verbose_trace: false,
+                    idempotency_key: None,
+                    singleton_key: None,
+                    singleton_policy: SingletonPolicy::default(),
+                    tags: Vec::new(),
+                    on_success_job_template: None,
+                    on_failure_job_template: None,
+                    artifact_id: None,
+                },
+                rotation.requested_by,
+            )
+            .await?;
+        self.record_job(rotation.id, kind, job.id).await;
+
+        let deadline = Instant::now() + JOB_WAIT_TIMEOUT;
+        loop {
+            let finished = sqlx::query_scalar::<_, bool>(
+                "SELECT status NOT IN ('pending', 'running', 'awaiting_approval') FROM jobs WHERE id = $1",
+            )
+            .bind(job.id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job.id, "Failed to check credential rotation job");
+                AppError::database("Failed to check credential rotation job")
+            })?;
+            if finished {
+                break;
+            }
+            if Instant::now() >= deadline {
+                warn!(
+                    job_id = %job.id,
+                    rotation_id = %rotation.id,
+                    "Credential rotation job timed out"
+                );
+                if let Err(e) = self
+                    .job_service
+                    .cancel_job(
+                        job.id,
+                        rotation.requested_by,
+                        Some("Credential rotation timed out".to_string()),
+                    )
+                    .await
+                {
+                    warn!(error = %e, job_id = %job.id, "Failed to cancel credential rotation job");
+                }
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        let tasks = self.job_service.get_job_tasks(job.id).await?;
+        let mut failures: HashMap<Uuid, String> = host_ids
+            .iter()
+            .map(|id| (*id, "Host was not targeted by the job".to_string()))
+            .collect();
+        for response in tasks {
+            let task = response.task;
+            if matches!(task.status, TaskStatus::Succeeded | TaskStatus::Warning) {
+                failures.remove(&task.host_id);
+            } else {
+                let error = task
+                    .failure_message
+                    .unwrap_or_else(|| format!("Task finished with status {}", task.status));
+                failures.insert(task.host_id, error);
+            }
+        }
+        Ok(failures)
+    }
+
+    /// 替换主机存储的私钥（保留登录用户与密码）
+    async fn swap_credential(
+        &self,
+        rotation: &CredentialRotation,
+        host: &Host,
+        key: &RotationKey,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE assets_hosts
+             SET ssh_private_key = $2, ssh_key_passphrase = $3, updated_by = $4,
+                 updated_at = NOW(), version = version + 1
+             WHERE id = $1",
+        )
+        .bind(host.id)
+        .bind(&key.private_key)
+        .bind(&key.passphrase)
+        .bind(rotation.requested_by)
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, host = %host.identifier, "Failed to swap host credential");
+            AppError::database("Failed to swap host credential")
+        })?;
+        cache::HOSTS.invalidate(&host.id);
+
+        if let Err(e) = self
+            .audit_service
+            .log_action_simple(
+                rotation.requested_by,
+                AuditAction::HostCredentialRotate,
+                Some("host"),
+                Some(host.id),
+                Some(&format!(
+                    "Rotated SSH key for host {} to {} (rotation {})",
+                    host.identifier, key.fingerprint, rotation.id
+                )),
+                None,
+            )
+            .await
+        {
+            warn!(error = %e, host = %host.identifier, "Failed to audit credential swap");
+        }
+        Ok(())
+    }
+
+    async fn set_phase(&self, rotation_id: Uuid, phase: CredentialRotationPhase) {
+        if let Err(e) = sqlx::query("UPDATE credential_rotations SET phase = $2 WHERE id = $1")
+            .bind(rotation_id)
+            .bind(phase)
+            .execute(&self.db)
+            .await
+        {
+            warn!(error = %e, rotation_id = %rotation_id, "Failed to update rotation phase");
+        }
+    }
+
+    async fn record_job(&self, rotation_id: Uuid, kind: KeyJob, job_id: Uuid) {
+        if let Err(e) = sqlx::query(kind.record_sql())
+            .bind(rotation_id)
+            .bind(job_id)
+            .execute(&self.db)
+            .await
+        {
+            warn!(error = %e, rotation_id = %rotation_id, "Failed to record rotation job");
+        }
+    }
+
+    /// 列出轮换记录（不含各主机结果）
+    pub async fn list(&self, limit: i64) -> Result<Vec<CredentialRotation>> {
+        sqlx::query_as::<_, CredentialRotation>(
+            "SELECT id, requested_by, status, phase, key_source, public_key, key_fingerprint,
+                    retire_old_key, total_hosts, push_job_id, retire_job_ids, rollback_job_id,
+                    NULL::jsonb AS results, error_message, created_at, completed_at
+             FROM credential_rotations ORDER BY created_at DESC LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to list credential rotations");
+            AppError::database("Failed to list credential rotations")
+        })
+    }
+
+    pub async fn get(&self, rotation_id: Uuid) -> Result<Option<CredentialRotation>> {
+        sqlx::query_as::<_, CredentialRotation>("SELECT * FROM credential_rotations WHERE id = $1")
+            .bind(rotation_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(
+                    error = %e,
+                    rotation_id = %rotation_id,
+                    "Failed to load credential rotation"
+                );
+                AppError::database("Failed to load credential rotation")
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(outcome: HostRotationOutcome) -> HostRotationReport {
+        HostRotationReport {
+            host_id: Uuid::new_v4(),
+            host_identifier: "web-01".to_string(),
+            outcome,
+            failed_phase: None,
+            old_key_fingerprint: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_generated_key_round_trips() {
+        let key = RotationKey::prepare(None, None, "ops-rotation-test").unwrap();
+        assert_eq!(key.source, "generated");
+        assert!(key.public_key.starts_with("ssh-ed25519 "));
+        assert!(key.public_key.ends_with(" ops-rotation-test"));
+        assert!(key.fingerprint.starts_with("SHA256:"));
+
+        // 生成的私钥可被执行器解析，且再次准备得到相同公钥
+        let provided = RotationKey::prepare(Some(&key.private_key), None, "c").unwrap();
+        assert_eq!(provided.source, "provided");
+        assert_eq!(provided.key_base64, key.key_base64);
+        assert_eq!(provided.fingerprint, key.fingerprint);
+    }
+
+    #[test]
+    fn test_invalid_provided_key_rejected() {
+        assert!(RotationKey::prepare(Some("not a key"), None, "c").is_err());
+    }
+
+    #[test]
+    fn test_key_commands() {
+        let push = push_key_command("ssh-ed25519 AAAAC3 ops-rotation-1");
+        assert!(push.contains("grep -qxF 'ssh-ed25519 AAAAC3 ops-rotation-1'"));
+        assert!(push.contains("echo 'ssh-ed25519 AAAAC3 ops-rotation-1' >>"));
+
+        let remove = remove_key_command("AAAAC3");
+        assert!(remove.contains("grep -vF 'AAAAC3'"));
+        assert!(remove.ends_with("exit $s"));
+    }
+
+    #[test]
+    fn test_rotation_status() {
+        let all = [
+            report(HostRotationOutcome::Rotated),
+            report(HostRotationOutcome::RotatedOldKeyRetained),
+        ];
+        assert_eq!(rotation_status(&all), CredentialRotationStatus::Completed);
+
+        let partial = [
+            report(HostRotationOutcome::Rotated),
+            report(HostRotationOutcome::RolledBack),
+        ];
+        assert_eq!(rotation_status(&partial), CredentialRotationStatus::PartiallyCompleted);
+
+        let none = [
+            report(HostRotationOutcome::PushFailed),
+            report(HostRotationOutcome::RollbackFailed),
+        ];
+        assert_eq!(rotation_status(&none), CredentialRotationStatus::Failed);
+    }
+}
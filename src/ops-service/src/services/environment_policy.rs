@@ -0,0 +1,129 @@
+//! 环境策略
+//!
+//! 按环境配置作业的默认超时、默认并发、强制审批、允许的作业类型与禁止变更窗口。
+//! 创建作业时合并目标主机所在环境的策略：补全未指定的超时与并发，校验作业类型与窗口；
+//! 审批评估将强制审批作为触发条件
+
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres};
+use std::collections::BTreeSet;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::asset::Host;
+use crate::models::environment_policy::{
+    EffectivePolicy, EnvironmentPolicy, UpsertEnvironmentPolicyRequest,
+};
+
+/// 合并目标主机所在环境的策略
+pub async fn effective_policy(db: &Pool<Postgres>, hosts: &[Host]) -> Result<EffectivePolicy> {
+    let environments: Vec<&str> = hosts
+        .iter()
+        .map(|h| h.environment.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if environments.is_empty() {
+        return Ok(EffectivePolicy::default());
+    }
+
+    let policies = sqlx::query_as::<_, EnvironmentPolicy>(
+        "SELECT * FROM environment_policies WHERE environment = ANY($1)",
+    )
+    .bind(&environments)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to load environment policies");
+        AppError::database("Failed to load environment policies")
+    })?;
+    Ok(EffectivePolicy::merge(&policies))
+}
+
+/// 环境策略管理
+pub struct EnvironmentPolicyService {
+    db: Pool<Postgres>,
+}
+
+impl EnvironmentPolicyService {
+    pub fn new(db: Pool<Postgres>) -> Self {
+        Self { db }
+    }
+
+    pub async fn list(&self) -> Result<Vec<EnvironmentPolicy>> {
+        sqlx::query_as::<_, EnvironmentPolicy>(
+            "SELECT * FROM environment_policies ORDER BY environment",
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to list environment policies");
+            AppError::database("Failed to list environment policies")
+        })
+    }
+
+    pub async fn get(&self, environment: &str) -> Result<Option<EnvironmentPolicy>> {
+        sqlx::query_as::<_, EnvironmentPolicy>(
+            "SELECT * FROM environment_policies WHERE environment = $1",
+        )
+        .bind(environment)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, environment, "Failed to load environment policy");
+            AppError::database("Failed to load environment policy")
+        })
+    }
+
+    /// 创建或整体替换环境策略
+    pub async fn upsert(
+        &self,
+        environment: &str,
+        request: &UpsertEnvironmentPolicyRequest,
+        updated_by: Uuid,
+    ) -> Result<EnvironmentPolicy> {
+        request.validate().map_err(|e| AppError::validation(&e))?;
+
+        sqlx::query_as::<_, EnvironmentPolicy>(
+            "INSERT INTO environment_policies
+                 (environment, default_timeout_secs, default_concurrent_limit, require_approval,
+                  allowed_job_types, blackout_windows, updated_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (environment) DO UPDATE SET
+                 default_timeout_secs = EXCLUDED.default_timeout_secs,
+                 default_concurrent_limit = EXCLUDED.default_concurrent_limit,
+                 require_approval = EXCLUDED.require_approval,
+                 allowed_job_types = EXCLUDED.allowed_job_types,
+                 blackout_windows = EXCLUDED.blackout_windows,
+                 updated_by = EXCLUDED.updated_by,
+                 updated_at = NOW()
+             RETURNING *",
+        )
+        .bind(environment)
+        .bind(request.default_timeout_secs)
+        .bind(request.default_concurrent_limit)
+        .bind(request.require_approval)
+        .bind(request.allowed_job_types.as_ref().map(Json))
+        .bind(Json(&request.blackout_windows))
+        .bind(updated_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, environment, "Failed to save environment policy");
+            AppError::database("Failed to save environment policy")
+        })
+    }
+
+    pub async fn delete(&self, environment: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM environment_policies WHERE environment = $1")
+            .bind(environment)
+            .execute(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, environment, "Failed to delete environment policy");
+                AppError::database("Failed to delete environment policy")
+            })?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,549 @@
+//! 合规证据包导出
+//!
+//! 将作业详情、任务完整日志、审批链路与相关审计记录打包为单个签名归档（gzip 压缩的 JSON），
+//! 后台异步生成并推送进度事件，生成后写入对象存储，通过限时签名链接下载。
+//!
+//! 签名为 HMAC-SHA256(key, payload 的紧凑 JSON)，同时嵌入归档与记录在导出表中，
+//! 审计方可用 `verify_archive` 或等价实现独立核验
+
+use crate::{
+    config::EvidenceConfig,
+    error::{AppError, Result},
+    middleware::webhook_hmac::{compute_hmac_sha256, compute_hmac_signature_hex},
+    models::approval::{ApprovalRecord, ApprovalRequest},
+    models::audit::AuditLog,
+    models::evidence::*,
+    realtime::{EventBus, RealtimeEvent},
+    services::{JobService, StorageService},
+};
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use std::io::{Read, Write};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// 未配置签名密钥时，由 JWT 密钥派生证据包签名密钥（用途隔离）
+const DERIVED_KEY_CONTEXT: &[u8] = b"ops-service/evidence-export";
+
+/// 证据包导出服务
+pub struct EvidenceExporter {
+    db: Pool<Postgres>,
+    job_service: Arc<JobService>,
+    storage: Arc<StorageService>,
+    event_bus: Option<Arc<EventBus>>,
+    config: EvidenceConfig,
+    signing_key: Vec<u8>,
+}
+
+impl EvidenceExporter {
+    pub fn new(
+        db: Pool<Postgres>,
+        job_service: Arc<JobService>,
+        storage: Arc<StorageService>,
+        config: EvidenceConfig,
+        jwt_secret: &SecretString,
+    ) -> Self {
+        let signing_key = match &config.signing_key {
+            Some(key) => key.expose_secret().as_bytes().to_vec(),
+            None => compute_hmac_sha256(jwt_secret.expose_secret().as_bytes(), DERIVED_KEY_CONTEXT),
+        };
+        Self {
+            db,
+            job_service,
+            storage,
+            event_bus: None,
+            config,
+            signing_key,
+        }
+    }
+
+    /// 设置事件总线（推送导出进度）
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    /// 创建导出记录并在后台生成证据包
+    pub async fn start(
+        self: &Arc<Self>,
+        job_id: Uuid,
+        requested_by: Uuid,
+    ) -> Result<EvidenceExport> {
+        let export = sqlx::query_as::<_, EvidenceExport>(
+            "INSERT INTO evidence_exports (job_id, requested_by) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(job_id)
+        .bind(requested_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to create evidence export");
+            AppError::database("Failed to create evidence export")
+        })?;
+
+        let exporter = self.clone();
+        let export_id = export.id;
+        tokio::spawn(async move {
+            exporter.generate(export_id).await;
+        });
+
+        Ok(export)
+    }
+
+    /// 生成证据包，失败时记录错误并推送失败事件
+    pub async fn generate(&self, export_id: Uuid) {
+        let result = match self.get(export_id).await {
+            Ok(Some(export)) => self.build_and_store(&export).await.map_err(|e| (export, e)),
+            Ok(None) => return,
+            Err(e) => {
+                error!(error = %e, export_id = %export_id, "Failed to load evidence export");
+                return;
+            }
+        };
+
+        if let Err((export, e)) = result {
+            error!(error = %e, export_id = %export_id, "Failed to generate evidence export");
+            let failed = sqlx::query(
+                "UPDATE evidence_exports
+                 SET status = 'failed', error_message = $2, updated_at = NOW(), completed_at = NOW()
+                 WHERE id = $1",
+            )
+            .bind(export_id)
+            .bind(e.to_string())
+            .execute(&self.db)
+            .await;
+            if let Err(e) = failed {
+                error!(error = %e, export_id = %export_id, "Failed to mark evidence export as failed");
+            }
+            self.publish_progress(
+                &export,
+                EvidenceExportStatus::Failed,
+                export.stage.clone(),
+                export.progress,
+            );
+        }
+    }
+
+    async fn build_and_store(&self, export: &EvidenceExport) -> Result<()> {
+        self.set_progress(export, "job", 5).await?;
+        let job = self.job_service.get_job(export.job_id).await?;
+
+        self.set_progress(export, "tasks", 20).await?;
+        let tasks = self.job_service.get_job_tasks(export.job_id).await?;
+
+        self.set_progress(export, "approvals", 50).await?;
+        let approvals = self.load_approvals(export.job_id).await?;
+
+        self.set_progress(export, "audit_logs", 65).await?;
+        let approval_ids: Vec<Uuid> = approvals.iter().map(|a| a.request.id).collect();
+        let audit_logs = self.load_audit_logs(export.job_id, &approval_ids).await?;
+
+        self.set_progress(export, "signing", 85).await?;
+        let payload = EvidencePayload {
+            manifest: EvidenceManifest {
+                export_id: export.id,
+                job_id: export.job_id,
+                generated_by: export.requested_by,
+                generated_at: Utc::now(),
+                task_count: tasks.len(),
+                approval_count: approvals.len(),
+                audit_log_count: audit_logs.len(),
+            },
+            content: EvidenceContent {
+                job,
+                tasks,
+                approvals,
+                audit_logs,
+            },
+        };
+        let (archive, signature) = encode_archive(&self.signing_key, &payload)?;
+        let sha256 = hex::encode(Sha256::digest(&archive));
+
+        let key = format!("evidence/{}/{}.json.gz", export.job_id, export.id);
+        let location = self.storage.put_object(&key, &archive).await.map_err(|e| {
+            error!(error = %e, export_id = %export.id, "Failed to store evidence archive");
+            AppError::internal_error("Failed to store evidence archive")
+        })?;
+
+        sqlx::query(
+            "UPDATE evidence_exports
+             SET status = 'completed', progress = 100, stage = NULL, location = $2, size_bytes = $3,
+                 sha256 = $4, signature = $5, updated_at = NOW(), completed_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(export.id)
+        .bind(&location)
+        .bind(archive.len() as i64)
+        .bind(&sha256)
+        .bind(&signature.value)
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, export_id = %export.id, "Failed to complete evidence export");
+            AppError::database("Failed to complete evidence export")
+        })?;
+
+        info!(
+            export_id = %export.id,
+            job_id = %export.job_id,
+            size_bytes = archive.len(),
+            "Evidence archive generated"
+        );
+        self.publish_progress(export, EvidenceExportStatus::Completed, None, 100);
+        Ok(())
+    }
+
+    async fn set_progress(
+        &self,
+        export: &EvidenceExport,
+        stage: &str,
+        progress: i32,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE evidence_exports SET status = 'running', stage = $2, progress = $3, updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(export.id)
+        .bind(stage)
+        .bind(progress)
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, export_id = %export.id, "Failed to update evidence export progress");
+            AppError::database("Failed to update evidence export progress")
+        })?;
+
+        self.publish_progress(
+            export,
+            EvidenceExportStatus::Running,
+            Some(stage.to_string()),
+            progress,
+        );
+        Ok(())
+    }
+
+    /// 进度事件只用于界面反馈，直接发布（不经发件箱），丢失时以状态接口为准
+    fn publish_progress(
+        &self,
+        export: &EvidenceExport,
+        status: EvidenceExportStatus,
+        stage: Option<String>,
+        progress: i32,
+    ) {
+        if let Some(event_bus) = &self.event_bus {
+            let event = RealtimeEvent::EvidenceExportProgress {
+                export_id: export.id,
+                job_id: export.job_id,
+                user_id: export.requested_by,
+                status: status.to_string(),
+                stage,
+                progress,
+            };
+            if let Err(e) = event_bus.publish(event) {
+                warn!(error = %e, export_id = %export.id, "Failed to publish evidence export progress");
+            }
+        }
+    }
+
+    async fn load_approvals(&self, job_id: Uuid) -> Result<Vec<EvidenceApproval>> {
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, job_id = %job_id, "Failed to load approvals for evidence");
+            AppError::database("Failed to load approvals")
+        };
+
+        let requests = sqlx::query_as::<_, ApprovalRequest>(
+            "SELECT * FROM approval_requests WHERE job_id = $1 ORDER BY requested_at",
+        )
+        .bind(job_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(map_err)?;
+
+        let mut approvals = Vec::with_capacity(requests.len());
+        for request in requests {
+            let records = sqlx::query_as::<_, ApprovalRecord>(
+                "SELECT * FROM approval_records WHERE approval_request_id = $1 ORDER BY approved_at",
+            )
+            .bind(request.id)
+            .fetch_all(&self.db)
+            .await
+            .map_err(map_err)?;
+            approvals.push(EvidenceApproval { request, records });
+        }
+        Ok(approvals)
+    }
+
+    async fn load_audit_logs(&self, job_id: Uuid, approval_ids: &[Uuid]) -> Result<Vec<AuditLog>> {
+        sqlx::query_as::<_, AuditLog>(
+            "SELECT * FROM audit_logs
+             WHERE (resource_type = 'job' AND resource_id = $1)
+                OR (resource_type = 'approval' AND resource_id = ANY($2))
+             ORDER BY occurred_at, id",
+        )
+        .bind(job_id)
+        .bind(approval_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to load audit logs for evidence");
+            AppError::database("Failed to load audit logs")
+        })
+    }
+
+    pub async fn get(&self, export_id: Uuid) -> Result<Option<EvidenceExport>> {
+        sqlx::query_as::<_, EvidenceExport>("SELECT * FROM evidence_exports WHERE id = $1")
+            .bind(export_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, export_id = %export_id, "Failed to get evidence export");
+                AppError::database("Failed to get evidence export")
+            })
+    }
+
+    /// 作业的导出记录（最新在前）
+    pub async fn list_for_job(&self, job_id: Uuid) -> Result<Vec<EvidenceExport>> {
+        sqlx::query_as::<_, EvidenceExport>(
+            "SELECT * FROM evidence_exports WHERE job_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(job_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to list evidence exports");
+            AppError::database("Failed to list evidence exports")
+        })
+    }
+
+    /// 生成限时下载链接
+    pub fn download_link(&self, export_id: Uuid) -> EvidenceDownloadLink {
+        let expires_at = Utc::now() + chrono::Duration::seconds(self.config.link_ttl_secs as i64);
+        let expires = expires_at.timestamp();
+        let signature = sign_link(&self.signing_key, export_id, expires);
+        EvidenceDownloadLink {
+            export_id,
+            url: format!(
+                "/api/v1/evidence-exports/{}/download?expires={}&signature={}",
+                export_id, expires, signature
+            ),
+            expires_at,
+        }
+    }
+
+    /// 校验下载链接签名与有效期
+    pub fn verify_link(
+        &self,
+        export_id: Uuid,
+        expires: i64,
+        signature: &str,
+        now: DateTime<Utc>,
+    ) -> bool {
+        expires >= now.timestamp() && sign_link(&self.signing_key, export_id, expires) == signature
+    }
+
+    /// 读取已生成的归档
+    pub async fn load_archive(&self, export: &EvidenceExport) -> Result<Vec<u8>> {
+        let location = match (export.status, &export.location) {
+            (EvidenceExportStatus::Completed, Some(location)) => location,
+            _ => return Err(AppError::not_found("Evidence archive not ready")),
+        };
+        self.storage.get_object(location).await.map_err(|e| {
+            error!(error = %e, export_id = %export.id, "Failed to read evidence archive");
+            AppError::internal_error("Failed to read evidence archive")
+        })
+    }
+
+    /// 校验归档签名（使用本实例的签名密钥）
+    pub fn verify(&self, archive: &[u8]) -> Result<bool> {
+        verify_archive(&self.signing_key, archive)
+    }
+}
+
+fn sign_link(key: &[u8], export_id: Uuid, expires: i64) -> String {
+    compute_hmac_signature_hex(
+        key,
+        format!("evidence-download:{}:{}", export_id, expires).as_bytes(),
+    )
+}
+
+/// 签名密钥标识（密钥 sha256 的前 16 位）
+fn key_id(key: &[u8]) -> String {
+    hex::encode(Sha256::digest(key))[..16].to_string()
+}
+
+/// 编码并签名证据包，返回 gzip 压缩的归档与签名
+pub fn encode_archive(
+    key: &[u8],
+    payload: &EvidencePayload,
+) -> Result<(Vec<u8>, EvidenceSignature)> {
+    let payload = serde_json::to_value(payload)
+        .map_err(|_| AppError::internal_error("Failed to serialize evidence payload"))?;
+    let signed = serde_json::to_vec(&payload)
+        .map_err(|_| AppError::internal_error("Failed to serialize evidence payload"))?;
+    let signature = EvidenceSignature {
+        algorithm: EVIDENCE_SIGNATURE_ALGORITHM.to_string(),
+        key_id: key_id(key),
+        value: compute_hmac_signature_hex(key, &signed),
+    };
+
+    let archive = EvidenceArchive {
+        format: EVIDENCE_FORMAT.to_string(),
+        payload,
+        signature: signature.clone(),
+    };
+    let json = serde_json::to_vec(&archive)
+        .map_err(|_| AppError::internal_error("Failed to serialize evidence archive"))?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let bytes = encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish())
+        .map_err(|_| AppError::internal_error("Failed to compress evidence archive"))?;
+    Ok((bytes, signature))
+}
+
+/// 解码归档并校验签名
+pub fn verify_archive(key: &[u8], archive: &[u8]) -> Result<bool> {
+    let mut json = Vec::new();
+    GzDecoder::new(archive)
+        .read_to_end(&mut json)
+        .map_err(|_| AppError::validation("Failed to decompress evidence archive"))?;
+    let archive: EvidenceArchive = serde_json::from_slice(&json)
+        .map_err(|_| AppError::validation("Failed to parse evidence archive"))?;
+    if archive.format != EVIDENCE_FORMAT
+        || archive.signature.algorithm != EVIDENCE_SIGNATURE_ALGORITHM
+    {
+        return Ok(false);
+    }
+    let signed = serde_json::to_vec(&archive.payload)
+        .map_err(|_| AppError::internal_error("Failed to serialize evidence payload"))?;
+    Ok(compute_hmac_signature_hex(key, &signed) == archive.signature.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::job::{Job, JobStatus, JobType};
+    use sqlx::types::Json;
+
+    fn sample_payload() -> EvidencePayload {
+        let job_id = Uuid::new_v4();
+        let job = Job {
+            id: job_id,
+            job_type: JobType::Command,
+            name: "deploy-web".to_string(),
+            description: None,
+            status: JobStatus::Completed,
+            target_hosts: Json(vec![Uuid::new_v4()]),
+            target_groups: Json(vec![]),
+            command: Some("uptime".to_string()),
+            script: None,
+            script_path: None,
+            script_sha256: None,
+            script_streamed: false,
+            file_spec: None,
+            file_manifest: None,
+            drift_check: false,
+            verbose_trace: false,
+            concurrent_limit: None,
+            timeout_secs: None,
+            retry_times: None,
+            execute_user: None,
+            exit_code_rules: None,
+            idempotency_key: None,
+            singleton_key: None,
+            singleton_waiting: false,
+            total_tasks: 1,
+            succeeded_tasks: 1,
+            warning_tasks: 0,
+            failed_tasks: 0,
+            timeout_tasks: 0,
+            cancelled_tasks: 0,
+            skipped_tasks: 0,
+            created_by: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            tags: Json(vec!["prod".to_string()]),
+            template_id: None,
+            artifact_id: None,
+            on_success_job_template: None,
+            on_failure_job_template: None,
+            parent_job_id: None,
+            chain_trigger: None,
+            chain_depth: 0,
+            chain_status: None,
+            chain_error: None,
+            budget_exceeded: None,
+            dispatch_paused_at: None,
+            network_backoff: None,
+        };
+
+        EvidencePayload {
+            manifest: EvidenceManifest {
+                export_id: Uuid::new_v4(),
+                job_id,
+                generated_by: job.created_by,
+                generated_at: Utc::now(),
+                task_count: 0,
+                approval_count: 0,
+                audit_log_count: 0,
+            },
+            content: EvidenceContent {
+                job,
+                tasks: vec![],
+                approvals: vec![],
+                audit_logs: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_archive_signature_roundtrip() {
+        let key = b"evidence-signing-key";
+        let (archive, signature) = encode_archive(key, &sample_payload()).unwrap();
+
+        assert_eq!(signature.algorithm, EVIDENCE_SIGNATURE_ALGORITHM);
+        assert_eq!(signature.key_id.len(), 16);
+        assert!(verify_archive(key, &archive).unwrap());
+        // 其他密钥无法通过校验
+        assert!(!verify_archive(b"other-key", &archive).unwrap());
+    }
+
+    #[test]
+    fn test_tampered_archive_fails_verification() {
+        let key = b"evidence-signing-key";
+        let (archive, _) = encode_archive(key, &sample_payload()).unwrap();
+
+        let mut json = Vec::new();
+        GzDecoder::new(archive.as_slice())
+            .read_to_end(&mut json)
+            .unwrap();
+        let mut value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        value["payload"]["content"]["job"]["command"] = serde_json::json!("rm -rf /tmp/x");
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&serde_json::to_vec(&value).unwrap())
+            .unwrap();
+        let tampered = encoder.finish().unwrap();
+
+        assert!(!verify_archive(key, &tampered).unwrap());
+    }
+
+    #[test]
+    fn test_download_link_signature() {
+        let key = b"evidence-signing-key";
+        let export_id = Uuid::new_v4();
+        let expires = Utc::now().timestamp() + 60;
+        let signature = sign_link(key, export_id, expires);
+
+        assert_eq!(signature, sign_link(key, export_id, expires));
+        assert_ne!(signature, sign_link(key, Uuid::new_v4(), expires));
+        assert_ne!(signature, sign_link(key, export_id, expires + 1));
+    }
+}
@@ -0,0 +1,165 @@
+//! 退出码分类
+//!
+//! 部分工具用非零退出码表示告警（如 rsync 24 表示源文件在传输中消失），不应判为失败。
+//! 模板/作业可配置分类规则，将退出码映射为成功、告警或失败；也可引用内置的常见工具分类表。
+//! 分类顺序：自定义规则 → 工具分类表 → 默认（0 为成功，其余为失败）。
+
+use crate::{
+    error::{AppError, Result},
+    models::job::{ExitCodeOutcome, ExitCodeRule, ExitCodeRules, ExitCodeTable},
+};
+
+/// 单个规则集允许的最大规则数
+pub const MAX_RULES: usize = 32;
+
+/// 内置工具分类表
+pub fn default_tables() -> Vec<ExitCodeTable> {
+    let warning = |codes: &[i32]| ExitCodeRule {
+        codes: codes.to_vec(),
+        range: None,
+        outcome: ExitCodeOutcome::Warning,
+    };
+    vec![
+        ExitCodeTable {
+            tool: "rsync",
+            description: "24: 源文件在传输中消失；25: 达到 --max-delete 上限",
+            rules: vec![warning(&[24, 25])],
+        },
+        ExitCodeTable {
+            tool: "grep",
+            description: "1: 未匹配到内容",
+            rules: vec![warning(&[1])],
+        },
+        ExitCodeTable {
+            tool: "diff",
+            description: "1: 文件存在差异",
+            rules: vec![warning(&[1])],
+        },
+        ExitCodeTable {
+            tool: "cmp",
+            description: "1: 文件存在差异",
+            rules: vec![warning(&[1])],
+        },
+        ExitCodeTable {
+            tool: "systemctl",
+            description: "3: 单元未运行（is-active/status）",
+            rules: vec![warning(&[3])],
+        },
+    ]
+}
+
+/// 按工具名查找内置分类表
+pub fn default_table(tool: &str) -> Option<ExitCodeTable> {
+    default_tables().into_iter().find(|t| t.tool == tool)
+}
+
+/// 校验分类规则
+pub fn validate(rules: &ExitCodeRules) -> Result<()> {
+    if let Some(tool) = &rules.tool {
+        if default_table(tool).is_none() {
+            return Err(AppError::validation(&format!("Unknown exit code table: {}", tool)));
+        }
+    }
+    if rules.rules.len() > MAX_RULES {
+        return Err(AppError::validation(&format!(
+            "At most {} exit code rules are allowed",
+            MAX_RULES
+        )));
+    }
+    for rule in &rules.rules {
+        if rule.codes.is_empty() && rule.range.is_none() {
+            return Err(AppError::validation("Exit code rule must specify codes or range"));
+        }
+        if rule.range.is_some_and(|(from, to)| from > to) {
+            return Err(AppError::validation("Exit code range start must not exceed end"));
+        }
+    }
+    Ok(())
+}
+
+/// 对退出码分类
+pub fn classify(rules: Option<&ExitCodeRules>, exit_code: i32) -> ExitCodeOutcome {
+    if let Some(rules) = rules {
+        if let Some(rule) = rules.rules.iter().find(|r| r.matches(exit_code)) {
+            return rule.outcome;
+        }
+        if let Some(table) = rules.tool.as_deref().and_then(default_table) {
+            if let Some(rule) = table.rules.iter().find(|r| r.matches(exit_code)) {
+                return rule.outcome;
+            }
+        }
+    }
+    if exit_code == 0 {
+        ExitCodeOutcome::Succeeded
+    } else {
+        ExitCodeOutcome::Failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(codes: &[i32], range: Option<(i32, i32)>, outcome: ExitCodeOutcome) -> ExitCodeRule {
+        ExitCodeRule {
+            codes: codes.to_vec(),
+            range,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_classify_default_and_tool_table() {
+        assert_eq!(classify(None, 0), ExitCodeOutcome::Succeeded);
+        assert_eq!(classify(None, 24), ExitCodeOutcome::Failed);
+
+        let rsync = ExitCodeRules {
+            tool: Some("rsync".to_string()),
+            rules: vec![],
+        };
+        assert_eq!(classify(Some(&rsync), 0), ExitCodeOutcome::Succeeded);
+        assert_eq!(classify(Some(&rsync), 24), ExitCodeOutcome::Warning);
+        assert_eq!(classify(Some(&rsync), 23), ExitCodeOutcome::Failed);
+    }
+
+    #[test]
+    fn test_custom_rules_take_precedence() {
+        let rules = ExitCodeRules {
+            tool: Some("grep".to_string()),
+            rules: vec![
+                rule(&[1], None, ExitCodeOutcome::Succeeded),
+                rule(&[], Some((100, 110)), ExitCodeOutcome::Warning),
+                rule(&[0], None, ExitCodeOutcome::Failed),
+            ],
+        };
+        assert_eq!(classify(Some(&rules), 1), ExitCodeOutcome::Succeeded);
+        assert_eq!(classify(Some(&rules), 105), ExitCodeOutcome::Warning);
+        assert_eq!(classify(Some(&rules), 0), ExitCodeOutcome::Failed);
+        assert_eq!(classify(Some(&rules), 2), ExitCodeOutcome::Failed);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&ExitCodeRules::default()).is_ok());
+        assert!(validate(&ExitCodeRules {
+            tool: Some("unknown".to_string()),
+            rules: vec![],
+        })
+        .is_err());
+        assert!(validate(&ExitCodeRules {
+            tool: None,
+            rules: vec![rule(&[], None, ExitCodeOutcome::Warning)],
+        })
+        .is_err());
+        assert!(validate(&ExitCodeRules {
+            tool: None,
+            rules: vec![rule(&[], Some((5, 1)), ExitCodeOutcome::Warning)],
+        })
+        .is_err());
+        assert!(validate(&ExitCodeRules {
+            tool: None,
+            rules: vec![rule(&[1], None, ExitCodeOutcome::Warning); MAX_RULES + 1],
+        })
+        .is_err());
+    }
+}
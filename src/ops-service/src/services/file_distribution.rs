@@ -0,0 +1,309 @@
+//! 文件分发作业
+//!
+//! 将文件内容（配置片段、cron 条目、证书等）写入目标主机上的指定路径。
+//! 分发通过生成的 POSIX shell 脚本完成，复用脚本作业的执行链路：
+//! - 内容以 base64 嵌入脚本，先写入同目录临时文件，再原子替换目标文件
+//! - 已有文件默认保持原权限与属主，可通过 mode/owner/group 覆盖
+//! - 内容变化时按配置备份原文件，并输出原文件与新内容的 diff
+//! - 配置了校验命令时写入后执行，失败则恢复原文件（新建的文件则删除）并以非零退出码结束
+//!
+//! 脚本以标记行报告结果，执行后由 `parse_report` 解析为每台主机的分发结果
+
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::{
+    error::{AppError, Result},
+    models::job::{FileDistributionReport, FileDistributionSpec, FileValidationStatus},
+    services::host_vars::shell_escape,
+};
+
+/// 文件内容上限（字节）
+pub const MAX_FILE_CONTENT_BYTES: usize = 1024 * 1024;
+
+/// diff 保留的最大行数
+pub const MAX_DIFF_LINES: usize = 500;
+
+const DIFF_BEGIN: &str = "OPS_FILE_DIFF_BEGIN";
+const DIFF_END: &str = "OPS_FILE_DIFF_END";
+const RESULT_PREFIX: &str = "OPS_FILE_RESULT";
+const VALIDATION_PREFIX: &str = "OPS_FILE_VALIDATION";
+const ROLLBACK_MARKER: &str = "OPS_FILE_ROLLBACK";
+
+/// 校验分发选项
+pub fn validate_spec(spec: &FileDistributionSpec) -> Result<()> {
+    if !spec.path.starts_with('/') || spec.path.ends_with('/') {
+        return Err(AppError::validation("File path must be an absolute file path"));
+    }
+    if spec.path.chars().any(|c| c.is_control()) || spec.path.split('/').any(|p| p == "..") {
+        return Err(AppError::validation("File path contains invalid characters"));
+    }
+    if spec.content.len() > MAX_FILE_CONTENT_BYTES {
+        return Err(AppError::validation(&format!(
+            "File content exceeds {} bytes",
+            MAX_FILE_CONTENT_BYTES
+        )));
+    }
+    if let Some(mode) = &spec.mode {
+        let valid = (3..=4).contains(&mode.len()) && mode.chars().all(|c| ('0'..='7').contains(&c));
+        if !valid {
+            return Err(AppError::validation("File mode must be octal, e.g. 0644"));
+        }
+    }
+    for name in spec.owner.iter().chain(spec.group.iter()) {
+        let valid = !name.is_empty()
+            && name.len() <= 32
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+        if !valid {
+            return Err(AppError::validation(&format!("Invalid file owner or group: {}", name)));
+        }
+    }
+    if spec
+        .validate_command
+        .as_deref()
+        .is_some_and(|c| c.trim().is_empty())
+    {
+        return Err(AppError::validation("Validation command must not be empty"));
+    }
+    Ok(())
+}
+
+/// 生成分发脚本
+pub fn build_script(spec: &FileDistributionSpec) -> String {
+    let encoded = general_purpose::STANDARD.encode(spec.content.as_bytes());
+    let mut encoded_lines = String::new();
+    for chunk in encoded.as_bytes().chunks(76) {
+        encoded_lines.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        encoded_lines.push('\n');
+    }
+
+    let mut script = format!(
+        r#"#!/bin/sh
+set -eu
+target={target}
+dir=$(dirname "$target")
+tmp=$(mktemp "$dir/.ops-file.XXXXXX")
+prev=""
+trap 'rm -f "$tmp" ${{prev:+"$prev"}}' EXIT
+existed=0
+if [ -e "$target" ]; then
+  existed=1
+  prev=$(mktemp "$dir/.ops-prev.XXXXXX")
+  cp -p "$target" "$prev"
+  cp -p "$target" "$tmp"
+else
+  chmod 0644 "$tmp"
+fi
+base64 -d > "$tmp" <<'OPS_FILE_CONTENT'
+{content}OPS_FILE_CONTENT
+changed=1
+if [ "$existed" = 1 ] && cmp -s "$target" "$tmp"; then
+  changed=0
+fi
+echo {diff_begin}
+if [ "$existed" = 1 ]; then
+  diff -u "$target" "$tmp" | head -n {max_diff} || true
+else
+  diff -u /dev/null "$tmp" | head -n {max_diff} || true
+fi
+echo {diff_end}
+backup=""
+"#,
+        target = shell_escape(&spec.path),
+        content = encoded_lines,
+        diff_begin = DIFF_BEGIN,
+        diff_end = DIFF_END,
+        max_diff = MAX_DIFF_LINES,
+    );
+
+    if spec.backup {
+        script.push_str(
+            r#"if [ "$existed" = 1 ] && [ "$changed" = 1 ]; then
+  backup="$target.ops-backup.$(date +%Y%m%d%H%M%S)"
+  cp -p "$target" "$backup"
+fi
+"#,
+        );
+    }
+    if let Some(mode) = &spec.mode {
+        script.push_str(&format!("chmod {} \"$tmp\"\n", mode));
+    }
+    match (&spec.owner, &spec.group) {
+        (Some(owner), Some(group)) => {
+            script.push_str(&format!("chown {}:{} \"$tmp\"\n", owner, group))
+        }
+        (Some(owner), None) => script.push_str(&format!("chown {} \"$tmp\"\n", owner)),
+        (None, Some(group)) => script.push_str(&format!("chgrp {} \"$tmp\"\n", group)),
+        (None, None) => {}
+    }
+    script.push_str(&format!(
+        "mv -f \"$tmp\" \"$target\"\necho \"{} changed=$changed existed=$existed backup=$backup\"\n",
+        RESULT_PREFIX
+    ));
+
+    match &spec.validate_command {
+        Some(command) => script.push_str(&format!(
+            r#"if sh -c {command}; then
+  echo "{validation} passed"
+else
+  status=$?
+  echo "{validation} failed"
+  if [ "$existed" = 1 ]; then
+    mv -f "$prev" "$target"
+    prev=""
+  else
+    rm -f "$target"
+  fi
+  echo "{rollback}"
+  exit "$status"
+fi
+"#,
+            command = shell_escape(command),
+            validation = VALIDATION_PREFIX,
+            rollback = ROLLBACK_MARKER,
+        )),
+        None => script.push_str(&format!("echo \"{} skipped\"\n", VALIDATION_PREFIX)),
+    }
+
+    script
+}
+
+/// 解析分发脚本的输出
+///
+/// 脚本中途失败（如权限不足）时缺少结果行，按未变化处理并标记未校验
+pub fn parse_report(stdout: &str) -> FileDistributionReport {
+    let mut report = FileDistributionReport {
+        changed: false,
+        existed: false,
+        backup_path: None,
+        diff: None,
+        validation: FileValidationStatus::Skipped,
+        rolled_back: false,
+    };
+
+    let mut diff: Option<Vec<&str>> = None;
+    for line in stdout.lines() {
+        if let Some(lines) = diff.as_mut() {
+            if line == DIFF_END {
+                let text = lines.join("\n");
+                report.diff = (!text.is_empty()).then_some(text);
+                diff = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        if line == DIFF_BEGIN {
+            diff = Some(Vec::new());
+        } else if let Some(fields) = line.strip_prefix(RESULT_PREFIX) {
+            for field in fields.split_whitespace() {
+                match field.split_once('=') {
+                    Some(("changed", value)) => report.changed = value == "1",
+                    Some(("existed", value)) => report.existed = value == "1",
+                    Some(("backup", value)) if !value.is_empty() => {
+                        report.backup_path = Some(value.to_string())
+                    }
+                    _ => {}
+                }
+            }
+        } else if let Some(status) = line.strip_prefix(VALIDATION_PREFIX) {
+            report.validation = match status.trim() {
+                "passed" => FileValidationStatus::Passed,
+                "failed" => FileValidationStatus::Failed,
+                _ => FileValidationStatus::Skipped,
+            };
+        } else if line == ROLLBACK_MARKER {
+            report.rolled_back = true;
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> FileDistributionSpec {
+        FileDistributionSpec {
+            path: "/etc/cron.d/backup".to_string(),
+            content: "0 2 * * * root /usr/local/bin/backup\n".to_string(),
+            mode: Some("0644".to_string()),
+            owner: Some("root".to_string()),
+            group: None,
+            backup: true,
+            validate_command: Some("test -s /etc/cron.d/backup".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_validate_spec() {
+        assert!(validate_spec(&spec()).is_ok());
+
+        let mut relative = spec();
+        relative.path = "etc/app.conf".to_string();
+        assert!(validate_spec(&relative).is_err());
+
+        let mut traversal = spec();
+        traversal.path = "/etc/../root/.ssh/authorized_keys".to_string();
+        assert!(validate_spec(&traversal).is_err());
+
+        let mut mode = spec();
+        mode.mode = Some("0999".to_string());
+        assert!(validate_spec(&mode).is_err());
+
+        let mut owner = spec();
+        owner.owner = Some("root; reboot".to_string());
+        assert!(validate_spec(&owner).is_err());
+    }
+
+    #[test]
+    fn test_build_script_embeds_content_and_options() {
+        let script = build_script(&spec());
+        let encoded = general_purpose::STANDARD.encode(spec().content);
+
+        assert!(script.contains(&encoded));
+        assert!(script.contains("target=/etc/cron.d/backup\n"));
+        assert!(script.contains("chmod 0644 \"$tmp\""));
+        assert!(script.contains("chown root \"$tmp\""));
+        assert!(script.contains("backup=\"$target.ops-backup."));
+        assert!(script.contains("if sh -c 'test -s /etc/cron.d/backup'; then"));
+
+        let mut no_backup = spec();
+        no_backup.backup = false;
+        no_backup.validate_command = None;
+        let script = build_script(&no_backup);
+        assert!(!script.contains("ops-backup"));
+        assert!(script.contains("OPS_FILE_VALIDATION skipped"));
+    }
+
+    #[test]
+    fn test_parse_report() {
+        let stdout = "OPS_FILE_DIFF_BEGIN\n--- a\n+++ b\n-old\n+new\nOPS_FILE_DIFF_END\n\
+                      OPS_FILE_RESULT changed=1 existed=1 backup=/etc/app.conf.ops-backup.20240101000000\n\
+                      OPS_FILE_VALIDATION failed\nOPS_FILE_ROLLBACK\n";
+        let report = parse_report(stdout);
+
+        assert!(report.changed);
+        assert!(report.existed);
+        assert_eq!(report.backup_path.as_deref(), Some("/etc/app.conf.ops-backup.20240101000000"));
+        assert_eq!(report.diff.as_deref(), Some("--- a\n+++ b\n-old\n+new"));
+        assert_eq!(report.validation, FileValidationStatus::Failed);
+        assert!(report.rolled_back);
+    }
+
+    #[test]
+    fn test_parse_report_unchanged_without_backup() {
+        let stdout = "OPS_FILE_DIFF_BEGIN\nOPS_FILE_DIFF_END\n\
+                      OPS_FILE_RESULT changed=0 existed=1 backup=\nOPS_FILE_VALIDATION skipped\n";
+        let report = parse_report(stdout);
+
+        assert!(!report.changed);
+        assert_eq!(report.backup_path, None);
+        assert_eq!(report.diff, None);
+        assert_eq!(report.validation, FileValidationStatus::Skipped);
+        assert!(!report.rolled_back);
+    }
+}
@@ -0,0 +1,330 @@
+//! 作业文件清单（变更控制证据）
+//!
+//! 命令/脚本作业可声明将读写的文件或目录，执行时命令被包裹在生成的 POSIX shell 片段中：
+//! - 执行前对声明路径逐文件计算 sha256 快照，并创建时间戳标记文件
+//! - 执行后再次快照，对比得出声明路径中新建、修改、删除的文件
+//! - 在监视目录中查找晚于标记文件修改的文件，未被声明路径覆盖的记为未声明修改
+//!
+//! 包裹片段以标记行输出报告，执行后由 `split_report` 从输出中剥离并解析。
+//! 未声明的删除无法通过修改时间发现，只对声明路径给出删除记录
+
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{AppError, Result},
+    models::job::{DeclaredFileChange, FileChangeKind, FileManifest, FileManifestReport},
+    services::host_vars::shell_escape,
+};
+
+/// 单个作业可声明的路径数上限
+pub const MAX_MANIFEST_PATHS: usize = 100;
+
+/// 单次快照/扫描保留的文件数上限（超出时报告标记为截断）
+pub const MAX_TRACKED_FILES: usize = 2000;
+
+/// 未指定监视目录时检查的目录
+pub const DEFAULT_WATCH_ROOTS: &[&str] = &["/etc", "/opt", "/srv", "/usr/local", "/var/www"];
+
+const REPORT_BEGIN: &str = "OPS_MANIFEST_BEGIN";
+const REPORT_END: &str = "OPS_MANIFEST_END";
+
+/// 校验文件清单
+pub fn validate_manifest(manifest: &FileManifest) -> Result<()> {
+    if manifest.paths.is_empty() {
+        return Err(AppError::validation("File manifest must declare at least one path"));
+    }
+    if manifest.paths.len() > MAX_MANIFEST_PATHS || manifest.watch_roots.len() > MAX_MANIFEST_PATHS
+    {
+        return Err(AppError::validation(&format!(
+            "File manifest supports at most {} paths",
+            MAX_MANIFEST_PATHS
+        )));
+    }
+    for path in manifest.paths.iter().chain(&manifest.watch_roots) {
+        if !path.starts_with('/') || path.trim_end_matches('/').is_empty() {
+            return Err(AppError::validation(&format!(
+                "Manifest path must be an absolute path below /: {}",
+                path
+            )));
+        }
+        if path.chars().any(|c| c.is_control()) || path.split('/').any(|p| p == "..") {
+            return Err(AppError::validation(&format!(
+                "Manifest path contains invalid characters: {}",
+                path
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 用快照与修改扫描包裹作业命令，保留命令的退出码
+///
+/// 命令在子 shell 中执行，其中的 `exit` 不会跳过执行后的快照
+pub fn wrap(command: &str, manifest: &FileManifest) -> String {
+    let paths = quote_paths(manifest.paths.iter().map(String::as_str));
+    let roots = if manifest.watch_roots.is_empty() {
+        quote_paths(DEFAULT_WATCH_ROOTS.iter().copied())
+    } else {
+        quote_paths(manifest.watch_roots.iter().map(String::as_str))
+    };
+    let limit = MAX_TRACKED_FILES + 1;
+
+    format!(
+        r#"ops_manifest_snapshot() {{
+  for ops_manifest_path in {paths}; do
+    if [ -d "$ops_manifest_path" ]; then
+      find "$ops_manifest_path" -xdev -type f -exec sha256sum {{}} + 2>/dev/null
+    elif [ -e "$ops_manifest_path" ]; then
+      sha256sum "$ops_manifest_path" 2>/dev/null
+    fi
+  done | head -n {limit}
+}}
+ops_manifest_dir=$(mktemp -d 2>/dev/null) || ops_manifest_dir=""
+if [ -n "$ops_manifest_dir" ]; then
+  ops_manifest_snapshot > "$ops_manifest_dir/before"
+  touch "$ops_manifest_dir/marker"
+fi
+(
+{command}
+)
+ops_manifest_status=$?
+if [ -n "$ops_manifest_dir" ]; then
+  ops_manifest_snapshot > "$ops_manifest_dir/after"
+  echo {begin}
+  sed 's/^/B /' "$ops_manifest_dir/before"
+  sed 's/^/A /' "$ops_manifest_dir/after"
+  find {roots} -xdev -type f -newer "$ops_manifest_dir/marker" 2>/dev/null \
+    | head -n {limit} | sed 's/^/M /'
+  echo {end}
+  rm -rf "$ops_manifest_dir"
+fi
+exit "$ops_manifest_status"
+"#,
+        paths = paths,
+        roots = roots,
+        limit = limit,
+        command = command,
+        begin = REPORT_BEGIN,
+        end = REPORT_END,
+    )
+}
+
+fn quote_paths<'a>(paths: impl Iterator<Item = &'a str>) -> String {
+    paths
+        .map(|p| shell_escape(p.trim_end_matches('/')))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 从执行输出中剥离清单报告，返回去除报告后的输出与解析结果
+///
+/// 缺少报告（临时目录创建失败、输出被截断）时报告标记为不完整
+pub fn split_report(stdout: &str, manifest: &FileManifest) -> (String, FileManifestReport) {
+    let mut output = Vec::new();
+    let mut section: Option<Vec<&str>> = None;
+    let mut report_lines = None;
+    for line in stdout.lines() {
+        if let Some(lines) = section.as_mut() {
+            if line == REPORT_END {
+                report_lines = section.take();
+            } else {
+                lines.push(line);
+            }
+        } else if line == REPORT_BEGIN {
+            section = Some(Vec::new());
+        } else {
+            output.push(line);
+        }
+    }
+
+    let mut output = output.join("\n");
+    if stdout.ends_with('\n') && !output.is_empty() {
+        output.push('\n');
+    }
+    let report = match report_lines {
+        Some(lines) => parse_report(&lines, manifest),
+        None => FileManifestReport {
+            declared_changes: Vec::new(),
+            undeclared_modifications: Vec::new(),
+            truncated: false,
+            complete: false,
+            compliant: false,
+        },
+    };
+    (output, report)
+}
+
+fn parse_report(lines: &[&str], manifest: &FileManifest) -> FileManifestReport {
+    let mut before = BTreeMap::new();
+    let mut after = BTreeMap::new();
+    let mut modified = Vec::new();
+    for line in lines {
+        let Some((kind, rest)) = line.split_once(' ') else {
+            continue;
+        };
+        match kind {
+            "B" | "A" => {
+                // sha256sum 输出格式：<hash>  <path>
+                if let Some((hash, path)) = rest.split_once("  ") {
+                    let snapshot = if kind == "B" { &mut before } else { &mut after };
+                    snapshot.insert(path.to_string(), hash.to_string());
+                }
+            }
+            "M" => modified.push(rest.to_string()),
+            _ => {}
+        }
+    }
+
+    let truncated = before.len() > MAX_TRACKED_FILES
+        || after.len() > MAX_TRACKED_FILES
+        || modified.len() > MAX_TRACKED_FILES;
+
+    let mut declared_changes = Vec::new();
+    for (path, hash) in &after {
+        let change = match before.get(path) {
+            None => FileChangeKind::Created,
+            Some(previous) if previous != hash => FileChangeKind::Modified,
+            Some(_) => continue,
+        };
+        declared_changes.push(DeclaredFileChange {
+            path: path.clone(),
+            change,
+        });
+    }
+    for path in before.keys().filter(|path| !after.contains_key(*path)) {
+        declared_changes.push(DeclaredFileChange {
+            path: path.clone(),
+            change: FileChangeKind::Deleted,
+        });
+    }
+    declared_changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut undeclared_modifications: Vec<String> = modified
+        .into_iter()
+        .take(MAX_TRACKED_FILES)
+        .filter(|path| !is_declared(path, manifest))
+        .collect();
+    undeclared_modifications.sort();
+    undeclared_modifications.dedup();
+
+    // 截断时无法证明没有未声明修改，按不合规处理
+    let compliant = !truncated && undeclared_modifications.is_empty();
+    FileManifestReport {
+        declared_changes,
+        undeclared_modifications,
+        truncated,
+        complete: true,
+        compliant,
+    }
+}
+
+/// 路径是否被清单中的某个声明路径（文件本身或其所在目录）覆盖
+fn is_declared(path: &str, manifest: &FileManifest) -> bool {
+    manifest.paths.iter().any(|declared| {
+        let declared = declared.trim_end_matches('/');
+        path == declared
+            || path
+                .strip_prefix(declared)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> FileManifest {
+        FileManifest {
+            paths: vec!["/etc/nginx/".to_string(), "/etc/hosts".to_string()],
+            watch_roots: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_manifest() {
+        assert!(validate_manifest(&manifest()).is_ok());
+
+        let empty = FileManifest {
+            paths: Vec::new(),
+            watch_roots: Vec::new(),
+        };
+        assert!(validate_manifest(&empty).is_err());
+
+        let mut relative = manifest();
+        relative.paths.push("etc/app.conf".to_string());
+        assert!(validate_manifest(&relative).is_err());
+
+        let mut root = manifest();
+        root.watch_roots.push("/".to_string());
+        assert!(validate_manifest(&root).is_err());
+
+        let mut traversal = manifest();
+        traversal.paths.push("/etc/../root/.ssh".to_string());
+        assert!(validate_manifest(&traversal).is_err());
+    }
+
+    #[test]
+    fn test_wrap_preserves_command_and_exit_status() {
+        let script = wrap("systemctl reload nginx; exit 3", &manifest());
+
+        assert!(script.contains("for ops_manifest_path in /etc/nginx /etc/hosts; do"));
+        assert!(script.contains("(\nsystemctl reload nginx; exit 3\n)\nops_manifest_status=$?"));
+        assert!(script.contains("find /etc /opt /srv /usr/local /var/www -xdev"));
+        assert!(script.ends_with("exit \"$ops_manifest_status\"\n"));
+
+        let mut custom = manifest();
+        custom.watch_roots = vec!["/srv/app data".to_string()];
+        assert!(wrap("true", &custom).contains("find '/srv/app data' -xdev"));
+    }
+
+    #[test]
+    fn test_split_report() {
+        let stdout = "reloading\n\
+                      OPS_MANIFEST_BEGIN\n\
+                      B aaa  /etc/nginx/nginx.conf\n\
+                      B bbb  /etc/nginx/old.conf\n\
+                      B ccc  /etc/hosts\n\
+                      A abc  /etc/nginx/nginx.conf\n\
+                      A ddd  /etc/nginx/new.conf\n\
+                      A ccc  /etc/hosts\n\
+                      M /etc/nginx/nginx.conf\n\
+                      M /etc/nginx/new.conf\n\
+                      M /etc/cron.d/cleanup\n\
+                      OPS_MANIFEST_END\n";
+        let (output, report) = split_report(stdout, &manifest());
+
+        assert_eq!(output, "reloading\n");
+        assert!(report.complete);
+        assert!(!report.truncated);
+        let changes: Vec<_> = report
+            .declared_changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.change))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("/etc/nginx/new.conf", FileChangeKind::Created),
+                ("/etc/nginx/nginx.conf", FileChangeKind::Modified),
+                ("/etc/nginx/old.conf", FileChangeKind::Deleted),
+            ]
+        );
+        assert_eq!(report.undeclared_modifications, vec!["/etc/cron.d/cleanup".to_string()]);
+        assert!(!report.compliant);
+    }
+
+    #[test]
+    fn test_split_report_missing_or_compliant() {
+        let (output, report) = split_report("done\n", &manifest());
+        assert_eq!(output, "done\n");
+        assert!(!report.complete);
+        assert!(!report.compliant);
+
+        let stdout = "OPS_MANIFEST_BEGIN\nM /etc/nginx/conf.d/site.conf\nOPS_MANIFEST_END\n";
+        let (output, report) = split_report(stdout, &manifest());
+        assert_eq!(output, "");
+        assert!(report.compliant);
+        // 与声明目录同前缀的其他目录不视为已声明
+        assert!(!is_declared("/etc/nginx-extra/site.conf", &manifest()));
+    }
+}
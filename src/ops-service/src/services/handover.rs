@@ -0,0 +1,410 @@
+//! 交接班报告
+//!
+//! 汇总时间窗口内的作业执行情况、待处理审批、离线 Runner、未恢复的失败主机与值得关注的审计记录，
+//! 同时渲染为 markdown，交接时无需逐个页面翻查
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::fmt::Write;
+use tracing::error;
+
+use crate::error::{AppError, Result};
+use crate::models::handover::*;
+use crate::services::audit_service::AuditAction;
+
+/// 各列表的最大条数
+const MAX_ITEMS: i64 = 50;
+
+/// 心跳超过该时长视为离线（与 Runner 健康检查一致）
+const RUNNER_OFFLINE_MINS: i32 = 2;
+
+/// 高影响的审计操作（失败的操作无论类型都会列出）
+const NOTABLE_AUDIT_ACTIONS: &[AuditAction] = &[
+    AuditAction::UserDelete,
+    AuditAction::UserTwoFactorDisable,
+    AuditAction::UserTwoFactorReset,
+    AuditAction::UserImpersonateStart,
+    AuditAction::HostDelete,
+    AuditAction::HostKeyRepin,
+    AuditAction::HostMaintenanceSet,
+    AuditAction::HostMaintenanceClear,
+    AuditAction::HostCredentialRotate,
+    AuditAction::HostEnvironmentChange,
+    AuditAction::JobCancel,
+    AuditAction::JobBudgetExceeded,
+    AuditAction::EnvironmentPolicyUpdate,
+    AuditAction::EnvironmentPolicyDelete,
+    AuditAction::ArtifactPromote,
+    AuditAction::RoleCreate,
+    AuditAction::RoleUpdate,
+    AuditAction::RoleDelete,
+    AuditAction::RoleBindingCreate,
+    AuditAction::RoleBindingDelete,
+    AuditAction::ApprovalAutoApprove,
+    AuditAction::RunnerConfigRolloutStart,
+    AuditAction::RunnerConfigRolloutRollback,
+    AuditAction::RunnerDelete,
+    AuditAction::RunnerCommand,
+    AuditAction::AnomalyAcknowledge,
+    AuditAction::SystemMaintenanceModeChange,
+    AuditAction::SystemLoadTestStart,
+];
+
+fn db_error(e: sqlx::Error, what: &str) -> AppError {
+    error!(error = %e, "Failed to fetch {} for handover report", what);
+    AppError::database("Failed to generate handover report")
+}
+
+/// 生成时间窗口 [from, to) 的交接班报告
+pub async fn generate(
+    db: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<HandoverReport> {
+    let by_status = sqlx::query_as::<_, HandoverJobStatusCount>(
+        r#"
+        SELECT status, COUNT(*) AS job_count
+        FROM jobs
+        WHERE created_at >= $1 AND created_at < $2
+        GROUP BY status
+        ORDER BY job_count DESC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error(e, "job counts"))?;
+
+    let failed = sqlx::query_as::<_, HandoverFailedJob>(
+        r#"
+        SELECT id, name, status, failed_tasks + timeout_tasks AS failed_tasks, total_tasks,
+               completed_at
+        FROM jobs
+        WHERE created_at >= $1 AND created_at < $2
+          AND status IN ('failed', 'partially_succeeded')
+        ORDER BY completed_at DESC NULLS LAST
+        LIMIT $3
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(MAX_ITEMS)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error(e, "failed jobs"))?;
+
+    // 交接时仍未处理的审批，不限申请时间
+    let pending_approvals = sqlx::query_as::<_, HandoverPendingApproval>(
+        r#"
+        SELECT id, job_id, title, requested_at, expires_at, current_approvals, required_approvers
+        FROM approval_requests
+        WHERE status = 'pending' AND requested_at < $1
+        ORDER BY expires_at NULLS LAST, requested_at
+        LIMIT $2
+        "#,
+    )
+    .bind(to)
+    .bind(MAX_ITEMS)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error(e, "pending approvals"))?;
+
+    let offline_runners = sqlx::query_as::<_, HandoverOfflineRunner>(
+        r#"
+        SELECT id, name, last_heartbeat
+        FROM runners
+        WHERE status = 'active'
+          AND (last_heartbeat IS NULL
+               OR last_heartbeat < NOW() - make_interval(mins => $1))
+        ORDER BY last_heartbeat NULLS FIRST, name
+        LIMIT $2
+        "#,
+    )
+    .bind(RUNNER_OFFLINE_MINS)
+    .bind(MAX_ITEMS)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error(e, "offline runners"))?;
+
+    // 窗口内出现过失败的主机中，最近一次结束的任务仍为失败的主机
+    let failed_hosts = sqlx::query_as::<_, HandoverFailedHost>(
+        r#"
+        SELECT host_id, identifier, address, job_id, job_name, failure_message, failed_at
+        FROM (
+            SELECT DISTINCT ON (t.host_id)
+                t.host_id, h.identifier, h.address, t.job_id, j.name AS job_name,
+                t.failure_message, t.completed_at AS failed_at, t.status
+            FROM tasks t
+            JOIN assets_hosts h ON h.id = t.host_id AND h.deleted_at IS NULL
+            JOIN jobs j ON j.id = t.job_id
+            WHERE t.completed_at IS NOT NULL
+              AND t.status IN ('succeeded', 'warning', 'failed', 'timeout')
+              AND t.host_id IN (
+                  SELECT host_id FROM tasks
+                  WHERE status IN ('failed', 'timeout')
+                    AND completed_at >= $1 AND completed_at < $2
+              )
+            ORDER BY t.host_id, t.completed_at DESC
+        ) latest
+        WHERE status IN ('failed', 'timeout')
+        ORDER BY failed_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(MAX_ITEMS)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error(e, "failed hosts"))?;
+
+    // 登录失败由异常检测单独处理，不计入
+    let notable_actions: Vec<&str> = NOTABLE_AUDIT_ACTIONS.iter().map(|a| a.as_str()).collect();
+    let notable_audits = sqlx::query_as::<_, HandoverAudit>(
+        r#"
+        SELECT id, occurred_at, subject_name, action, resource_type, resource_name, result,
+               changes_summary
+        FROM audit_logs
+        WHERE occurred_at >= $1 AND occurred_at < $2
+          AND (action = ANY($3) OR (result <> 'success' AND action <> $4))
+        ORDER BY occurred_at DESC
+        LIMIT $5
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(&notable_actions)
+    .bind(AuditAction::UserLogin.as_str())
+    .bind(MAX_ITEMS)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error(e, "notable audits"))?;
+
+    let mut report = HandoverReport {
+        from,
+        to,
+        generated_at: Utc::now(),
+        jobs: HandoverJobSummary {
+            total: by_status.iter().map(|s| s.job_count).sum(),
+            by_status,
+            failed,
+        },
+        pending_approvals,
+        offline_runners,
+        failed_hosts,
+        notable_audits,
+        markdown: String::new(),
+    };
+    report.markdown = render_markdown(&report);
+    Ok(report)
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+/// 表格单元格内容：去掉换行并转义竖线
+fn cell(value: &str) -> String {
+    value
+        .replace(['\r', '\n'], " ")
+        .replace('|', "\\|")
+        .trim()
+        .to_string()
+}
+
+/// 渲染为 markdown
+pub fn render_markdown(report: &HandoverReport) -> String {
+    let mut md = String::new();
+    let _ = writeln!(
+        md,
+        "# Shift handover: {} - {}\n",
+        format_time(report.from),
+        format_time(report.to)
+    );
+
+    let _ = writeln!(md, "## Jobs ({})\n", report.jobs.total);
+    if report.jobs.by_status.is_empty() {
+        md.push_str("No jobs were executed.\n\n");
+    } else {
+        for status in &report.jobs.by_status {
+            let _ = writeln!(md, "- {}: {}", status.status, status.job_count);
+        }
+        md.push('\n');
+    }
+    if !report.jobs.failed.is_empty() {
+        md.push_str("| Job | Status | Failed tasks | Completed |\n|---|---|---|---|\n");
+        for job in &report.jobs.failed {
+            let _ = writeln!(
+                md,
+                "| {} (`{}`) | {} | {}/{} | {} |",
+                cell(&job.name),
+                job.id,
+                job.status,
+                job.failed_tasks,
+                job.total_tasks,
+                job.completed_at.map(format_time).unwrap_or_default()
+            );
+        }
+        md.push('\n');
+    }
+
+    let _ = writeln!(md, "## Pending approvals ({})\n", report.pending_approvals.len());
+    if report.pending_approvals.is_empty() {
+        md.push_str("None.\n\n");
+    } else {
+        md.push_str("| Title | Approvals | Requested | Expires |\n|---|---|---|---|\n");
+        for approval in &report.pending_approvals {
+            let _ = writeln!(
+                md,
+                "| {} | {}/{} | {} | {} |",
+                cell(&approval.title),
+                approval.current_approvals,
+                approval.required_approvers,
+                format_time(approval.requested_at),
+                approval.expires_at.map(format_time).unwrap_or_default()
+            );
+        }
+        md.push('\n');
+    }
+
+    let _ = writeln!(md, "## Offline runners ({})\n", report.offline_runners.len());
+    if report.offline_runners.is_empty() {
+        md.push_str("None.\n\n");
+    } else {
+        for runner in &report.offline_runners {
+            let last_seen = runner
+                .last_heartbeat
+                .map_or_else(|| "never".to_string(), format_time);
+            let _ = writeln!(md, "- {} (last heartbeat: {})", cell(&runner.name), last_seen);
+        }
+        md.push('\n');
+    }
+
+    let _ = writeln!(md, "## Unresolved failed hosts ({})\n", report.failed_hosts.len());
+    if report.failed_hosts.is_empty() {
+        md.push_str("None.\n\n");
+    } else {
+        md.push_str("| Host | Job | Failed at | Reason |\n|---|---|---|---|\n");
+        for host in &report.failed_hosts {
+            let _ = writeln!(
+                md,
+                "| {} ({}) | {} | {} | {} |",
+                cell(&host.identifier),
+                cell(&host.address),
+                cell(&host.job_name),
+                format_time(host.failed_at),
+                cell(host.failure_message.as_deref().unwrap_or(""))
+            );
+        }
+        md.push('\n');
+    }
+
+    let _ = writeln!(md, "## Notable audit events ({})\n", report.notable_audits.len());
+    if report.notable_audits.is_empty() {
+        md.push_str("None.\n");
+    } else {
+        md.push_str("| Time | User | Action | Resource | Result |\n|---|---|---|---|---|\n");
+        for audit in &report.notable_audits {
+            let resource = match &audit.resource_name {
+                Some(name) => format!("{} {}", audit.resource_type, name),
+                None => audit.resource_type.clone(),
+            };
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {} |",
+                format_time(audit.occurred_at),
+                cell(audit.subject_name.as_deref().unwrap_or("system")),
+                cell(&audit.action),
+                cell(&resource),
+                cell(&audit.result)
+            );
+        }
+    }
+
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::job::JobStatus;
+    use chrono::{Duration, TimeZone};
+    use uuid::Uuid;
+
+    fn empty_report() -> HandoverReport {
+        let to = Utc.with_ymd_and_hms(2026, 10, 15, 8, 0, 0).unwrap();
+        HandoverReport {
+            from: to - Duration::hours(12),
+            to,
+            generated_at: to,
+            jobs: HandoverJobSummary {
+                total: 0,
+                by_status: Vec::new(),
+                failed: Vec::new(),
+            },
+            pending_approvals: Vec::new(),
+            offline_runners: Vec::new(),
+            failed_hosts: Vec::new(),
+            notable_audits: Vec::new(),
+            markdown: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_empty() {
+        let md = render_markdown(&empty_report());
+        assert!(md.starts_with("# Shift handover: 2026-10-14 20:00 UTC - 2026-10-15 08:00 UTC"));
+        assert!(md.contains("## Jobs (0)\n\nNo jobs were executed."));
+        assert!(md.contains("## Offline runners (0)\n\nNone."));
+    }
+
+    #[test]
+    fn test_render_markdown_sections() {
+        let mut report = empty_report();
+        report.jobs.total = 3;
+        report.jobs.by_status = vec![
+            HandoverJobStatusCount {
+                status: JobStatus::Completed,
+                job_count: 2,
+            },
+            HandoverJobStatusCount {
+                status: JobStatus::Failed,
+                job_count: 1,
+            },
+        ];
+        report.jobs.failed = vec![HandoverFailedJob {
+            id: Uuid::nil(),
+            name: "deploy | web".to_string(),
+            status: JobStatus::Failed,
+            failed_tasks: 2,
+            total_tasks: 5,
+            completed_at: None,
+        }];
+        report.offline_runners = vec![HandoverOfflineRunner {
+            id: Uuid::nil(),
+            name: "runner-1".to_string(),
+            last_heartbeat: None,
+        }];
+        report.failed_hosts = vec![HandoverFailedHost {
+            host_id: Uuid::nil(),
+            identifier: "web-01".to_string(),
+            address: "10.0.0.1".to_string(),
+            job_id: Uuid::nil(),
+            job_name: "deploy".to_string(),
+            failure_message: Some("exit 1\nstderr".to_string()),
+            failed_at: report.to,
+        }];
+
+        let md = render_markdown(&report);
+        assert!(md.contains("## Jobs (3)"));
+        assert!(md.contains("- completed: 2\n- failed: 1"));
+        assert!(md.contains(
+            "| deploy \\| web (`00000000-0000-0000-0000-000000000000`) | failed | 2/5 |"
+        ));
+        assert!(md.contains("- runner-1 (last heartbeat: never)"));
+        assert!(
+            md.contains("| web-01 (10.0.0.1) | deploy | 2026-10-15 08:00 UTC | exit 1 stderr |")
+        );
+    }
+}
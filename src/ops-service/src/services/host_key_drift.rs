@@ -0,0 +1,307 @@
+//! 主机密钥漂移检查
+//!
+//! 后台定期与全部 SSH 主机完成握手（不认证、不执行命令），将服务器提供的主机密钥与连接时使用的固定指纹比对，
+//! 及时发现中间人攻击或未预期的主机重建。发现不一致时：
+//! - 记录来源为 drift_check 的主机密钥失败记录（与任务执行时的失败记录共用重新固定流程）
+//! - 将主机标记为禁止执行，任务不再连接主机而直接以 host_key_mismatch 失败
+//! - 作为高危安全异常推送到安全事件流
+//!
+//! 管理员核实后重新固定密钥即解除禁止。未固定指纹的主机无从比对，只计数不处理
+
+use futures::StreamExt;
+use sqlx::{Pool, Postgres};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::cache;
+use crate::error::{AppError, Result};
+use crate::models::asset::{Host, SshHostKeyFailureRecord};
+use crate::services::{AnomalyDetector, JobService};
+use crate::ssh::host_key::fingerprint_matches;
+use crate::ssh::{HostKeyFailure, HostKeyFailureKind, PresentedHostKey};
+
+/// 失败记录来源：定期漂移检查
+pub const SOURCE_DRIFT_CHECK: &str = "drift_check";
+
+/// 单台主机的检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriftOutcome {
+    /// 与固定的指纹一致
+    Matched,
+    /// 没有固定的指纹
+    Unpinned,
+    /// 与固定的指纹不一致
+    Drifted(Box<HostKeyFailure>),
+}
+
+/// 一轮检查的汇总
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DriftCheckSummary {
+    pub checked: usize,
+    pub matched: usize,
+    pub unpinned: usize,
+    pub unreachable: usize,
+    pub drifted: usize,
+}
+
+/// 比对服务器提供的密钥与固定的指纹
+pub fn compare(
+    host: &str,
+    port: u16,
+    pinned: Option<&str>,
+    presented: &PresentedHostKey,
+) -> DriftOutcome {
+    match pinned {
+        None => DriftOutcome::Unpinned,
+        Some(pinned) if fingerprint_matches(pinned, &presented.public_key) => DriftOutcome::Matched,
+        Some(pinned) => DriftOutcome::Drifted(Box::new(HostKeyFailure {
+            host: host.to_string(),
+            port,
+            kind: HostKeyFailureKind::Mismatch,
+            expected_fingerprint: Some(pinned.to_string()),
+            presented_fingerprint: presented.fingerprint.clone(),
+            key_type: presented.key_type.clone(),
+            public_key: presented.public_key.clone(),
+        })),
+    }
+}
+
+/// 主机密钥漂移检查
+pub struct HostKeyDriftMonitor {
+    db: Pool<Postgres>,
+    job_service: Arc<JobService>,
+    anomaly_detector: AnomalyDetector,
+    concurrency: usize,
+}
+
+impl HostKeyDriftMonitor {
+    pub fn new(
+        db: Pool<Postgres>,
+        job_service: Arc<JobService>,
+        anomaly_detector: AnomalyDetector,
+        concurrency: usize,
+    ) -> Self {
+        Self {
+            db,
+            job_service,
+            anomaly_detector,
+            concurrency: concurrency.max(1),
+        }
+    }
+
+    /// 执行一轮检查
+    ///
+    /// 单台主机无法连接只计数；记录漂移失败时返回错误，由下一轮重试
+    pub async fn run_once(&self) -> Result<DriftCheckSummary> {
+        // docker 主机通过 SSH 连接主机地址，同样检查
+        let hosts = sqlx::query_as::<_, Host>(
+            "SELECT * FROM assets_hosts
+             WHERE deleted_at IS NULL
+               AND status IN ('active', 'maintenance')
+               AND connection_type IN ('ssh', 'docker')
+             ORDER BY identifier",
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to list hosts for host key drift check");
+            AppError::database("Failed to list hosts")
+        })?;
+
+        let mut summary = DriftCheckSummary::default();
+        let mut stream = futures::stream::iter(hosts)
+            .map(|host| async move {
+                let probe = self.job_service.probe_host_key(&host).await;
+                (host, probe)
+            })
+            .buffer_unordered(self.concurrency);
+
+        while let Some((host, probe)) = stream.next().await {
+            summary.checked += 1;
+            let (presented, pinned) = match probe {
+                Ok(probe) => probe,
+                Err(e) => {
+                    warn!(
+                        host = %host.identifier,
+                        error = %e,
+                        "Host key drift check could not reach host"
+                    );
+                    summary.unreachable += 1;
+                    continue;
+                }
+            };
+            match compare(&host.address, host.port as u16, pinned.as_deref(), &presented) {
+                DriftOutcome::Matched => summary.matched += 1,
+                DriftOutcome::Unpinned => summary.unpinned += 1,
+                DriftOutcome::Drifted(failure) => {
+                    summary.drifted += 1;
+                    self.record_drift(&host, &failure).await?;
+                }
+            }
+        }
+
+        info!(
+            checked = summary.checked,
+            matched = summary.matched,
+            unpinned = summary.unpinned,
+            unreachable = summary.unreachable,
+            drifted = summary.drifted,
+            "Host key drift check finished"
+        );
+        Ok(summary)
+    }
+
+    /// 记录漂移并禁止主机执行；同一主机提供的同一新密钥只记录一次
+    async fn record_drift(&self, host: &Host, failure: &HostKeyFailure) -> Result<()> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        let recorded = sqlx::query(
+            r#"
+            INSERT INTO ssh_host_key_failures (
+                host_id, host, port, kind, source,
+                expected_fingerprint, presented_fingerprint, key_type, public_key
+            )
+            SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9
+            WHERE NOT EXISTS (
+                SELECT 1 FROM ssh_host_key_failures
+                WHERE host_id = $1 AND source = $5
+                  AND presented_fingerprint = $7 AND resolved_at IS NULL
+            )
+            "#,
+        )
+        .bind(host.id)
+        .bind(&failure.host)
+        .bind(failure.port as i32)
+        .bind(failure.kind.as_str())
+        .bind(SOURCE_DRIFT_CHECK)
+        .bind(&failure.expected_fingerprint)
+        .bind(&failure.presented_fingerprint)
+        .bind(&failure.key_type)
+        .bind(&failure.public_key)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, host = %host.identifier, "Failed to record host key drift");
+            AppError::database("Failed to record host key drift")
+        })?
+        .rows_affected();
+
+        sqlx::query(
+            "UPDATE assets_hosts SET host_key_blocked_at = NOW()
+             WHERE id = $1 AND host_key_blocked_at IS NULL",
+        )
+        .bind(host.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, host = %host.identifier, "Failed to block host");
+            AppError::database("Failed to record host key drift")
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        cache::HOSTS.invalidate(&host.id);
+
+        if recorded > 0 {
+            error!(
+                host = %host.identifier,
+                expected = ?failure.expected_fingerprint,
+                actual = %failure.presented_fingerprint,
+                "Host key drift detected, host blocked until re-pinned"
+            );
+        }
+        // 通知失败不影响禁止执行，下一轮检查时按去重键补发
+        if let Err(e) = self
+            .anomaly_detector
+            .report_host_key_drift(host, failure)
+            .await
+        {
+            error!(error = %e, host = %host.identifier, "Failed to report host key drift");
+        }
+        Ok(())
+    }
+}
+
+/// 被禁止执行的主机的任务错误：使用最近一次未处理的漂移记录，便于直接重新固定
+pub async fn blocked_error(db: &Pool<Postgres>, host: &Host) -> AppError {
+    let record = sqlx::query_as::<_, SshHostKeyFailureRecord>(
+        "SELECT * FROM ssh_host_key_failures
+         WHERE host_id = $1 AND source = $2 AND resolved_at IS NULL
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(host.id)
+    .bind(SOURCE_DRIFT_CHECK)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, host = %host.identifier, "Failed to load host key drift record");
+    })
+    .ok()
+    .flatten();
+
+    let failure = match record {
+        Some(record) => HostKeyFailure {
+            host: record.host,
+            port: record.port as u16,
+            kind: HostKeyFailureKind::Mismatch,
+            expected_fingerprint: record.expected_fingerprint,
+            presented_fingerprint: record.presented_fingerprint,
+            key_type: record.key_type,
+            public_key: record.public_key,
+        },
+        None => HostKeyFailure {
+            host: host.address.clone(),
+            port: host.port as u16,
+            kind: HostKeyFailureKind::Mismatch,
+            expected_fingerprint: None,
+            presented_fingerprint: String::new(),
+            key_type: String::new(),
+            public_key: String::new(),
+        },
+    };
+    AppError::SshHostKeyVerificationError(Box::new(failure))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh::host_key::fingerprint;
+
+    const KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+    const OTHER_KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIHJ5DOtSm1xJ7uMnrDzyPXTTxV0Dt/eYcDAFBHTv9USf";
+
+    fn presented(key: &str) -> PresentedHostKey {
+        PresentedHostKey {
+            key_type: "ssh-ed25519".to_string(),
+            public_key: key.to_string(),
+            fingerprint: fingerprint(key).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_compare_matched_and_unpinned() {
+        let pinned = fingerprint(KEY).unwrap();
+        assert_eq!(compare("10.0.0.5", 22, Some(&pinned), &presented(KEY)), DriftOutcome::Matched);
+        assert_eq!(compare("10.0.0.5", 22, None, &presented(KEY)), DriftOutcome::Unpinned);
+    }
+
+    #[test]
+    fn test_compare_reports_drift() {
+        let pinned = fingerprint(KEY).unwrap();
+        let DriftOutcome::Drifted(failure) =
+            compare("10.0.0.5", 2222, Some(&pinned), &presented(OTHER_KEY))
+        else {
+            panic!("expected drift");
+        };
+        assert_eq!(failure.kind, HostKeyFailureKind::Mismatch);
+        assert_eq!(failure.port, 2222);
+        assert_eq!(failure.expected_fingerprint.as_deref(), Some(pinned.as_str()));
+        assert_eq!(failure.presented_fingerprint, fingerprint(OTHER_KEY).unwrap());
+        assert_eq!(failure.public_key, OTHER_KEY);
+    }
+}
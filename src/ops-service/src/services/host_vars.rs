@@ -0,0 +1,225 @@
+//! 执行时主机变量插值
+//!
+//! 命令与脚本中可引用目标主机属性，如 `{{host.address}}`、`{{host.environment}}`、
+//! `{{host.tags.role}}`（取 `role:<value>` 形式标签的值），在每个任务执行前按主机解析。
+//! 规则：
+//! - 仅处理以 `host.` 开头的占位符（允许两侧空白），其余 `{{...}}` 原样保留
+//! - 未知属性、主机未设置的可选属性、不存在的标签键均报错，不会静默替换为空串
+//! - 值仅含安全字符时原样插入，否则按 POSIX shell 单引号转义后插入
+
+use crate::{
+    error::{AppError, Result},
+    models::asset::Host,
+};
+
+const HOST_PREFIX: &str = "host.";
+const TAGS_PREFIX: &str = "tags.";
+
+/// 可引用的主机属性（不含 `tags.<key>`）
+pub const HOST_ATTRIBUTES: &[&str] = &[
+    "id",
+    "identifier",
+    "display_name",
+    "address",
+    "port",
+    "environment",
+    "group_id",
+    "os_type",
+    "os_version",
+    "arch",
+];
+
+/// 占位符在文本中的位置与引用名（去掉前缀）
+pub(crate) struct Reference<'a> {
+    pub start: usize,
+    pub end: usize,
+    pub name: &'a str,
+}
+
+/// 查找所有以 prefix（如 `host.`）开头的变量引用
+pub(crate) fn references<'a>(text: &'a str, prefix: &str) -> Vec<Reference<'a>> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = text[offset..].find("{{") {
+        let start = offset + open;
+        let Some(close) = text[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + close + 2;
+        let inner = text[start + 2..end - 2].trim();
+        match inner.strip_prefix(prefix) {
+            Some(name) => {
+                found.push(Reference { start, end, name });
+                offset = end;
+            }
+            None => offset = start + 2,
+        }
+    }
+    found
+}
+
+/// 校验引用名是否为已知属性（创建作业时调用，标签键需到执行时才能确认）
+fn check_name(name: &str) -> Result<()> {
+    let known = match name.strip_prefix(TAGS_PREFIX) {
+        Some(key) => !key.is_empty(),
+        None => HOST_ATTRIBUTES.contains(&name),
+    };
+    if known {
+        Ok(())
+    } else {
+        Err(AppError::validation(&format!("Unknown host variable: {{{{host.{}}}}}", name)))
+    }
+}
+
+/// 校验文本中的主机变量引用
+pub fn validate(text: &str) -> Result<()> {
+    references(text, HOST_PREFIX)
+        .iter()
+        .try_for_each(|r| check_name(r.name))
+}
+
+fn lookup(host: &Host, name: &str) -> Result<String> {
+    check_name(name)?;
+
+    let missing = || {
+        AppError::validation(&format!(
+            "Host variable {{{{host.{}}}}} is not set for host {}",
+            name, host.identifier
+        ))
+    };
+
+    if let Some(key) = name.strip_prefix(TAGS_PREFIX) {
+        return host
+            .tags
+            .iter()
+            .find_map(|tag| match tag.split_once(':') {
+                Some((k, v)) if k == key => Some(v.to_string()),
+                _ => None,
+            })
+            .ok_or_else(missing);
+    }
+
+    let value = match name {
+        "id" => Some(host.id.to_string()),
+        "identifier" => Some(host.identifier.clone()),
+        "display_name" => host.display_name.clone(),
+        "address" => Some(host.address.clone()),
+        "port" => Some(host.port.to_string()),
+        "environment" => Some(host.environment.clone()),
+        "group_id" => Some(host.group_id.to_string()),
+        "os_type" => host.os_type.clone(),
+        "os_version" => host.os_version.clone(),
+        "arch" => host.arch.clone(),
+        _ => None,
+    };
+    value.ok_or_else(missing)
+}
+
+/// shell 转义：仅含安全字符时原样返回，否则使用单引号包裹
+pub fn shell_escape(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@%+=,".contains(c));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// 使用主机属性替换文本中的主机变量引用
+pub fn interpolate(text: &str, host: &Host) -> Result<String> {
+    let refs = references(text, HOST_PREFIX);
+    if refs.is_empty() {
+        return Ok(text.to_string());
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for reference in refs {
+        result.push_str(&text[last..reference.start]);
+        result.push_str(&shell_escape(&lookup(host, reference.name)?));
+        last = reference.end;
+    }
+    result.push_str(&text[last..]);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::types::Json;
+
+    fn host() -> Host {
+        Host {
+            id: uuid::Uuid::new_v4(),
+            identifier: "web-01".to_string(),
+            display_name: None,
+            address: "10.0.0.1".to_string(),
+            port: 22,
+            group_id: uuid::Uuid::new_v4(),
+            environment: "prod".to_string(),
+            tags: Json(vec!["role:web".to_string(), "owner:team a".to_string()]),
+            owner_id: None,
+            status: "active".to_string(),
+            notes: None,
+            os_type: None,
+            os_version: None,
+            arch: None,
+            ssh_username: None,
+            ssh_password: None,
+            ssh_private_key: None,
+            ssh_key_passphrase: None,
+            host_key_verification: None,
+            known_hosts: None,
+            output_encoding: None,
+            connection_type: "ssh".to_string(),
+            connection_options: None,
+            connection_token: None,
+            maintenance_until: None,
+            maintenance_reason: None,
+            maintenance_started_at: None,
+            maintenance_set_by: None,
+            host_key_blocked_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: None,
+            updated_by: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn test_interpolates_attributes_and_tags() {
+        let command = "cp /etc/app/{{host.environment}}/{{ host.tags.role }}.conf /tmp && ping {{host.address}}";
+        assert_eq!(
+            interpolate(command, &host()).unwrap(),
+            "cp /etc/app/prod/web.conf /tmp && ping 10.0.0.1"
+        );
+    }
+
+    #[test]
+    fn test_unsafe_values_are_quoted() {
+        assert_eq!(interpolate("echo {{host.tags.owner}}", &host()).unwrap(), "echo 'team a'");
+        assert_eq!(shell_escape("a'b"), r"'a'\''b'");
+        assert_eq!(shell_escape("$(reboot)"), "'$(reboot)'");
+        assert_eq!(shell_escape(""), "''");
+    }
+
+    #[test]
+    fn test_unknown_references_are_errors() {
+        assert!(interpolate("echo {{host.hostname}}", &host()).is_err());
+        assert!(interpolate("echo {{host.tags.zone}}", &host()).is_err());
+        assert!(interpolate("echo {{host.os_type}}", &host()).is_err());
+        assert!(validate("echo {{host.hostname}}").is_err());
+        assert!(validate("echo {{host.tags.zone}} {{host.port}}").is_ok());
+    }
+
+    #[test]
+    fn test_other_placeholders_are_kept() {
+        let text = "echo {{name}} {{host.identifier}} {{";
+        assert_eq!(interpolate(text, &host()).unwrap(), "echo {{name}} web-01 {{");
+    }
+}
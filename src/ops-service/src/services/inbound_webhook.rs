@@ -0,0 +1,550 @@
+//! 入站 Webhook
+//!
+//! 外部系统（监控告警、ITSM）凭 Webhook 专属令牌触发绑定的作业模板。
+//! 目标范围由 Webhook 固定，请求体只能通过参数白名单提供模板参数：
+//! 每个参数从请求体的指定位置提取，必须为标量且通过长度、取值与正则校验，
+//! 未列入白名单的字段一律忽略。每次经过认证的请求都记录完整请求体与处理结果，
+//! 按 Webhook 限制每分钟触发次数。
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use tracing::error;
+use uuid::Uuid;
+
+use rand::distr::{Alphanumeric, SampleString};
+
+use crate::error::{AppError, Result};
+use crate::models::inbound_webhook::{
+    CreateInboundWebhookRequest, InboundWebhook, InboundWebhookDelivery,
+    UpdateInboundWebhookRequest, WebhookParameterRule,
+};
+
+/// 令牌前缀
+pub const TOKEN_PREFIX: &str = "ops_whk_";
+
+/// 投递状态
+pub const STATUS_RECEIVED: &str = "received";
+pub const STATUS_TRIGGERED: &str = "triggered";
+pub const STATUS_REJECTED: &str = "rejected";
+pub const STATUS_RATE_LIMITED: &str = "rate_limited";
+pub const STATUS_FAILED: &str = "failed";
+
+/// 默认每分钟触发上限
+pub const DEFAULT_RATE_LIMIT_PER_MINUTE: i32 = 10;
+
+/// 每分钟触发上限的最大值
+const MAX_RATE_LIMIT_PER_MINUTE: i32 = 600;
+
+/// 参数值默认最大长度
+const DEFAULT_MAX_VALUE_LENGTH: usize = 256;
+
+/// 投递记录单次查询上限
+const MAX_DELIVERY_QUERY_LIMIT: i64 = 500;
+
+/// 参数名最大长度
+const MAX_PARAMETER_NAME_LENGTH: usize = 64;
+
+/// 参数名：与模板占位符一致的标识符
+fn is_valid_parameter_name(name: &str) -> bool {
+    name.len() <= MAX_PARAMETER_NAME_LENGTH
+        && name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 未配置正则时允许的字符集（不含 shell 元字符与空白）
+fn is_safe_value(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "._:/@=,+-".contains(c))
+}
+
+/// 生成新的明文令牌
+pub fn generate_token() -> String {
+    format!("{}{}", TOKEN_PREFIX, Alphanumeric.sample_string(&mut rand::rng(), 40))
+}
+
+/// 校验 Webhook 定义：固定目标非空、限流范围、参数白名单规则可用且不与固定参数重名
+pub fn validate_definition(
+    target_hosts: &[Uuid],
+    target_groups: &[Uuid],
+    fixed_parameters: &Map<String, Value>,
+    parameter_rules: &BTreeMap<String, WebhookParameterRule>,
+    rate_limit_per_minute: i32,
+) -> Result<()> {
+    if target_hosts.is_empty() && target_groups.is_empty() {
+        return Err(AppError::validation("Webhook requires target_hosts or target_groups"));
+    }
+    if !(1..=MAX_RATE_LIMIT_PER_MINUTE).contains(&rate_limit_per_minute) {
+        return Err(AppError::validation(&format!(
+            "rate_limit_per_minute must be between 1 and {}",
+            MAX_RATE_LIMIT_PER_MINUTE
+        )));
+    }
+    for (name, value) in fixed_parameters {
+        if !is_valid_parameter_name(name) {
+            return Err(AppError::validation(&format!("Invalid parameter name: {}", name)));
+        }
+        if value.is_array() || value.is_object() {
+            return Err(AppError::validation(&format!(
+                "Fixed parameter {} must be a scalar value",
+                name
+            )));
+        }
+    }
+    for (name, rule) in parameter_rules {
+        if !is_valid_parameter_name(name) {
+            return Err(AppError::validation(&format!("Invalid parameter name: {}", name)));
+        }
+        if fixed_parameters.contains_key(name) {
+            return Err(AppError::validation(&format!(
+                "Parameter {} is both fixed and allowlisted",
+                name
+            )));
+        }
+        if let Some(pointer) = &rule.pointer {
+            if !pointer.is_empty() && !pointer.starts_with('/') {
+                return Err(AppError::validation(&format!(
+                    "Pointer for parameter {} must start with '/'",
+                    name
+                )));
+            }
+        }
+        if let Some(pattern) = &rule.pattern {
+            anchored(pattern).map_err(|e| {
+                AppError::validation(&format!("Invalid pattern for parameter {}: {}", name, e))
+            })?;
+        }
+        if rule.max_length == Some(0) {
+            return Err(AppError::validation(&format!(
+                "max_length for parameter {} must be positive",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 正则需匹配完整值
+fn anchored(pattern: &str) -> std::result::Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+/// 按白名单从请求体提取模板参数，固定参数优先；返回拒绝原因
+pub fn extract_parameters(
+    payload: &Value,
+    fixed_parameters: &Map<String, Value>,
+    parameter_rules: &BTreeMap<String, WebhookParameterRule>,
+) -> std::result::Result<Map<String, Value>, String> {
+    let mut parameters = Map::new();
+    for (name, rule) in parameter_rules {
+        let pointer = rule
+            .pointer
+            .clone()
+            .unwrap_or_else(|| format!("/parameters/{}", name));
+        let value = match payload.pointer(&pointer) {
+            None | Some(Value::Null) => match (&rule.default, rule.required) {
+                (Some(default), _) => default.clone(),
+                (None, true) => return Err(format!("Missing required parameter: {}", name)),
+                (None, false) => continue,
+            },
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Number(n)) => n.to_string(),
+            Some(Value::Bool(b)) => b.to_string(),
+            Some(_) => return Err(format!("Parameter {} must be a scalar value", name)),
+        };
+
+        let max_length = rule.max_length.unwrap_or(DEFAULT_MAX_VALUE_LENGTH);
+        if value.chars().count() > max_length {
+            return Err(format!("Parameter {} exceeds {} characters", name, max_length));
+        }
+        if !rule.allowed_values.is_empty() && !rule.allowed_values.contains(&value) {
+            return Err(format!("Parameter {} has a value that is not allowed", name));
+        }
+        let valid = match &rule.pattern {
+            Some(pattern) => anchored(pattern)
+                .map(|re| re.is_match(&value))
+                .unwrap_or(false),
+            None => is_safe_value(&value),
+        };
+        if !valid {
+            return Err(format!("Parameter {} does not match the allowed pattern", name));
+        }
+        parameters.insert(name.clone(), Value::String(value));
+    }
+    for (name, value) in fixed_parameters {
+        parameters.insert(name.clone(), value.clone());
+    }
+    Ok(parameters)
+}
+
+/// 创建 Webhook
+pub async fn create(
+    db: &PgPool,
+    request: &CreateInboundWebhookRequest,
+    token_hash: &str,
+    created_by: Uuid,
+) -> Result<InboundWebhook> {
+    sqlx::query_as::<_, InboundWebhook>(
+        r#"
+        INSERT INTO inbound_webhooks
+            (name, description, token_hash, template_id, target_hosts, target_groups,
+             fixed_parameters, parameter_rules, rate_limit_per_minute, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING *
+        "#,
+    )
+    .bind(request.name.trim())
+    .bind(&request.description)
+    .bind(token_hash)
+    .bind(request.template_id)
+    .bind(Json(&request.target_hosts))
+    .bind(Json(&request.target_groups))
+    .bind(Json(&request.fixed_parameters))
+    .bind(Json(&request.parameter_rules))
+    .bind(
+        request
+            .rate_limit_per_minute
+            .unwrap_or(DEFAULT_RATE_LIMIT_PER_MINUTE),
+    )
+    .bind(created_by)
+    .fetch_one(db)
+    .await
+    .map_err(|e| match e.as_database_error() {
+        Some(d) if d.is_unique_violation() => AppError::validation("Webhook name already exists"),
+        _ => {
+            error!(error = %e, "Failed to create inbound webhook");
+            AppError::database("Failed to create inbound webhook")
+        }
+    })
+}
+
+/// 查询 Webhook
+pub async fn get(db: &PgPool, id: Uuid) -> Result<InboundWebhook> {
+    sqlx::query_as::<_, InboundWebhook>("SELECT * FROM inbound_webhooks WHERE id = $1")
+        .bind(id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, webhook_id = %id, "Failed to get inbound webhook");
+            AppError::database("Failed to get inbound webhook")
+        })?
+        .ok_or_else(|| AppError::not_found("Inbound webhook not found"))
+}
+
+/// 列出全部 Webhook
+pub async fn list(db: &PgPool) -> Result<Vec<InboundWebhook>> {
+    sqlx::query_as::<_, InboundWebhook>("SELECT * FROM inbound_webhooks ORDER BY name")
+        .fetch_all(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to list inbound webhooks");
+            AppError::database("Failed to list inbound webhooks")
+        })
+}
+
+/// 更新 Webhook（未提供的字段保持不变）
+pub async fn update(
+    db: &PgPool,
+    id: Uuid,
+    request: &UpdateInboundWebhookRequest,
+) -> Result<InboundWebhook> {
+    sqlx::query_as::<_, InboundWebhook>(
+        r#"
+        UPDATE inbound_webhooks SET
+            description = COALESCE($2, description),
+            target_hosts = COALESCE($3, target_hosts),
+            target_groups = COALESCE($4, target_groups),
+            fixed_parameters = COALESCE($5, fixed_parameters),
+            parameter_rules = COALESCE($6, parameter_rules),
+            rate_limit_per_minute = COALESCE($7, rate_limit_per_minute),
+            is_active = COALESCE($8, is_active),
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(&request.description)
+    .bind(request.target_hosts.as_ref().map(Json))
+    .bind(request.target_groups.as_ref().map(Json))
+    .bind(request.fixed_parameters.as_ref().map(Json))
+    .bind(request.parameter_rules.as_ref().map(Json))
+    .bind(request.rate_limit_per_minute)
+    .bind(request.is_active)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, webhook_id = %id, "Failed to update inbound webhook");
+        AppError::database("Failed to update inbound webhook")
+    })?
+    .ok_or_else(|| AppError::not_found("Inbound webhook not found"))
+}
+
+/// 替换令牌哈希（旧令牌立即失效）
+pub async fn rotate_token(db: &PgPool, id: Uuid, token_hash: &str) -> Result<InboundWebhook> {
+    sqlx::query_as::<_, InboundWebhook>(
+        "UPDATE inbound_webhooks SET token_hash = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .bind(token_hash)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, webhook_id = %id, "Failed to rotate inbound webhook token");
+        AppError::database("Failed to rotate inbound webhook token")
+    })?
+    .ok_or_else(|| AppError::not_found("Inbound webhook not found"))
+}
+
+/// 删除 Webhook（投递记录一并删除）
+pub async fn delete(db: &PgPool, id: Uuid) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM inbound_webhooks WHERE id = $1")
+        .bind(id)
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, webhook_id = %id, "Failed to delete inbound webhook");
+            AppError::database("Failed to delete inbound webhook")
+        })?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// 按 ID 与令牌哈希查找 Webhook，不匹配时返回 None
+pub async fn authenticate(
+    db: &PgPool,
+    id: Uuid,
+    token_hash: &str,
+) -> Result<Option<InboundWebhook>> {
+    sqlx::query_as::<_, InboundWebhook>(
+        "SELECT * FROM inbound_webhooks WHERE id = $1 AND token_hash = $2",
+    )
+    .bind(id)
+    .bind(token_hash)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, webhook_id = %id, "Failed to authenticate inbound webhook");
+        AppError::database("Failed to authenticate inbound webhook")
+    })
+}
+
+/// 记录一次投递并执行限流
+///
+/// Webhook 行加锁后统计最近一分钟内未被限流的投递数，超过上限时记录为 rate_limited，
+/// 并发请求按行锁串行计数，不会同时越过上限
+pub async fn record_delivery(
+    db: &PgPool,
+    webhook: &InboundWebhook,
+    payload: &Value,
+    source_ip: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<InboundWebhookDelivery> {
+    let db_err = |e: sqlx::Error| {
+        error!(error = %e, webhook_id = %webhook.id, "Failed to record inbound webhook delivery");
+        AppError::database("Failed to record inbound webhook delivery")
+    };
+
+    let mut tx = db.begin().await.map_err(db_err)?;
+    sqlx::query("SELECT id FROM inbound_webhooks WHERE id = $1 FOR UPDATE")
+        .bind(webhook.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+    let recent: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM inbound_webhook_deliveries
+        WHERE webhook_id = $1 AND status <> $2 AND received_at > NOW() - INTERVAL '1 minute'
+        "#,
+    )
+    .bind(webhook.id)
+    .bind(STATUS_RATE_LIMITED)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    let status = if recent >= i64::from(webhook.rate_limit_per_minute) {
+        STATUS_RATE_LIMITED
+    } else {
+        STATUS_RECEIVED
+    };
+    let delivery = sqlx::query_as::<_, InboundWebhookDelivery>(
+        r#"
+        INSERT INTO inbound_webhook_deliveries (webhook_id, source_ip, user_agent, payload, status)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(webhook.id)
+    .bind(source_ip)
+    .bind(user_agent)
+    .bind(Json(payload))
+    .bind(status)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+    tx.commit().await.map_err(db_err)?;
+    Ok(delivery)
+}
+
+/// 记录投递的处理结果；触发成功时更新 Webhook 的最近触发时间
+pub async fn finish_delivery(
+    db: &PgPool,
+    delivery: &InboundWebhookDelivery,
+    status: &str,
+    error_message: Option<&str>,
+    parameters: Option<&Map<String, Value>>,
+    job_id: Option<Uuid>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        WITH delivery AS (
+            UPDATE inbound_webhook_deliveries
+            SET status = $2, error = $3, parameters = $4, job_id = $5
+            WHERE id = $1
+            RETURNING webhook_id
+        )
+        UPDATE inbound_webhooks SET last_triggered_at = NOW()
+        WHERE $2 = 'triggered' AND id = (SELECT webhook_id FROM delivery)
+        "#,
+    )
+    .bind(delivery.id)
+    .bind(status)
+    .bind(error_message)
+    .bind(parameters.map(Json))
+    .bind(job_id)
+    .execute(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, delivery_id = %delivery.id, "Failed to update inbound webhook delivery");
+        AppError::database("Failed to update inbound webhook delivery")
+    })?;
+    Ok(())
+}
+
+/// 查询 Webhook 的最近投递记录
+pub async fn list_deliveries(
+    db: &PgPool,
+    webhook_id: Uuid,
+    limit: i64,
+) -> Result<Vec<InboundWebhookDelivery>> {
+    sqlx::query_as::<_, InboundWebhookDelivery>(
+        r#"
+        SELECT * FROM inbound_webhook_deliveries
+        WHERE webhook_id = $1
+        ORDER BY received_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(webhook_id)
+    .bind(limit.clamp(1, MAX_DELIVERY_QUERY_LIMIT))
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, webhook_id = %webhook_id, "Failed to list inbound webhook deliveries");
+        AppError::database("Failed to list inbound webhook deliveries")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(pointer: Option<&str>) -> WebhookParameterRule {
+        WebhookParameterRule {
+            pointer: pointer.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_extracts_only_allowlisted_parameters() {
+        let mut rules = BTreeMap::new();
+        rules.insert("service".to_string(), rule(Some("/alert/labels/service")));
+        rules.insert("instance".to_string(), rule(None));
+        let mut fixed = Map::new();
+        fixed.insert("action".to_string(), json!("restart"));
+
+        let payload = json!({
+            "alert": {"labels": {"service": "nginx", "severity": "critical"}},
+            "parameters": {"instance": "web-01:9100", "action": "rm -rf /"},
+        });
+        let parameters = extract_parameters(&payload, &fixed, &rules).unwrap();
+        assert_eq!(parameters.len(), 3);
+        assert_eq!(parameters["service"], "nginx");
+        assert_eq!(parameters["instance"], "web-01:9100");
+        assert_eq!(parameters["action"], "restart");
+    }
+
+    #[test]
+    fn test_rejects_unsafe_or_invalid_values() {
+        let mut rules = BTreeMap::new();
+        rules.insert("service".to_string(), rule(None));
+
+        let payload = json!({"parameters": {"service": "nginx; reboot"}});
+        assert!(extract_parameters(&payload, &Map::new(), &rules).is_err());
+        let payload = json!({"parameters": {"service": ["nginx"]}});
+        assert!(extract_parameters(&payload, &Map::new(), &rules).is_err());
+
+        rules.get_mut("service").unwrap().allowed_values =
+            vec!["nginx".to_string(), "redis".to_string()];
+        let payload = json!({"parameters": {"service": "mysql"}});
+        assert!(extract_parameters(&payload, &Map::new(), &rules).is_err());
+
+        let service = rules.get_mut("service").unwrap();
+        service.allowed_values.clear();
+        service.pattern = Some("[a-z]+".to_string());
+        service.max_length = Some(5);
+        let payload = json!({"parameters": {"service": "nginx1"}});
+        assert!(extract_parameters(&payload, &Map::new(), &rules).is_err());
+        let payload = json!({"parameters": {"service": "redis"}});
+        assert!(extract_parameters(&payload, &Map::new(), &rules).is_ok());
+    }
+
+    #[test]
+    fn test_required_and_default_parameters() {
+        let mut rules = BTreeMap::new();
+        rules.insert(
+            "service".to_string(),
+            WebhookParameterRule {
+                required: true,
+                ..Default::default()
+            },
+        );
+        assert!(extract_parameters(&json!({}), &Map::new(), &rules).is_err());
+
+        rules.get_mut("service").unwrap().default = Some("nginx".to_string());
+        let parameters = extract_parameters(&json!({}), &Map::new(), &rules).unwrap();
+        assert_eq!(parameters["service"], "nginx");
+
+        rules.insert("optional".to_string(), rule(None));
+        let parameters = extract_parameters(&json!({}), &Map::new(), &rules).unwrap();
+        assert!(!parameters.contains_key("optional"));
+    }
+
+    #[test]
+    fn test_validate_definition() {
+        let hosts = vec![Uuid::new_v4()];
+        let mut rules = BTreeMap::new();
+        rules.insert("service".to_string(), rule(Some("/labels/service")));
+        assert!(validate_definition(&hosts, &[], &Map::new(), &rules, 10).is_ok());
+        assert!(validate_definition(&[], &[], &Map::new(), &rules, 10).is_err());
+        assert!(validate_definition(&hosts, &[], &Map::new(), &rules, 0).is_err());
+
+        let mut fixed = Map::new();
+        fixed.insert("service".to_string(), json!("nginx"));
+        assert!(validate_definition(&hosts, &[], &fixed, &rules, 10).is_err());
+
+        rules.insert("bad-name".to_string(), rule(None));
+        assert!(validate_definition(&hosts, &[], &Map::new(), &rules, 10).is_err());
+        rules.remove("bad-name");
+        rules.get_mut("service").unwrap().pattern = Some("(".to_string());
+        assert!(validate_definition(&hosts, &[], &Map::new(), &rules, 10).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+//! 作业归档（冷存储分层）
+//!
+//! 完成超过保留期的命令/脚本作业从 jobs/tasks 移入 jobs_archive/tasks_archive，
+//! 任务完整输出打包压缩后写入对象存储，保持热表规模可控。
+//! 详情查询在热表中找不到作业时透明回查归档（需要从对象存储读取输出，延迟略高）
+
+use crate::{
+    config::ArchiveConfig,
+    error::{AppError, Result},
+    models::job::{Job, Task},
+    services::StorageService,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// 可归档作业的条件（$1 为归档天数）
+///
+/// 构建作业仍被 build_jobs 引用，关联审批请求的作业需保留审批链路，二者都留在热表
+const ARCHIVABLE_JOB_CONDITION: &str =
+    "j.status IN ('completed', 'failed', 'cancelled', 'partially_succeeded')
+     AND j.job_type IN ('command', 'script', 'file')
+     AND j.completed_at < NOW() - make_interval(days => $1)
+     AND NOT EXISTS (SELECT 1 FROM approval_requests ar WHERE ar.job_id = j.id)";
+
+/// 对象存储中的任务输出包
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedOutputs {
+    job_id: Uuid,
+    outputs: BTreeMap<Uuid, String>,
+}
+
+/// 作业归档器
+pub struct JobArchiver {
+    db: Pool<Postgres>,
+    storage: Arc<StorageService>,
+    config: ArchiveConfig,
+}
+
+impl JobArchiver {
+    pub fn new(db: Pool<Postgres>, storage: Arc<StorageService>, config: ArchiveConfig) -> Self {
+        Self {
+            db,
+            storage,
+            config,
+        }
+    }
+
+    /// 归档一批到期的作业，返回归档数量
+    ///
+    /// 单个作业归档失败只记录错误，不影响同批其他作业
+    pub async fn archive_expired(&self) -> Result<usize> {
+        let job_ids = sqlx::query_scalar::<_, Uuid>(&format!(
+            "SELECT j.id FROM jobs j WHERE {} ORDER BY j.completed_at LIMIT $2",
+            ARCHIVABLE_JOB_CONDITION
+        ))
+        .bind(self.config.after_days as i32)
+        .bind(self.config.batch_size as i64)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to find archivable jobs");
+            AppError::database("Failed to find archivable jobs")
+        })?;
+
+        let mut archived = 0;
+        for job_id in job_ids {
+            match self.archive_job(job_id).await {
+                Ok(true) => archived += 1,
+                Ok(false) => {}
+                Err(e) => error!(job_id = %job_id, error = %e, "Failed to archive job"),
+            }
+        }
+        Ok(archived)
+    }
+
+    /// 归档单个作业，作业不满足归档条件时返回 false
+    ///
+    /// 先上传输出包再在事务内搬移数据库记录；事务未提交时删除已上传的输出包
+    pub async fn archive_job(&self, job_id: Uuid) -> Result<bool> {
+        let outputs = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, output_detail FROM tasks WHERE job_id = $1 AND output_detail IS NOT NULL",
+        )
+        .bind(job_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to fetch task outputs");
+            AppError::database("Failed to fetch task outputs")
+        })?;
+
+        let location = if outputs.is_empty() {
+            None
+        } else {
+            let bundle = encode_outputs(job_id, outputs.into_iter().collect())?;
+            let location = self
+                .storage
+                .put_object(&output_key(job_id), &bundle)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, job_id = %job_id, "Failed to upload archived outputs");
+                    AppError::internal_error("Failed to upload archived outputs")
+                })?;
+            Some(location)
+        };
+
+        let result = self.move_job(job_id, location.as_deref()).await;
+        if !matches!(result, Ok(true)) {
+            if let Some(location) = &location {
+                if let Err(e) = self.storage.delete_object(location).await {
+                    warn!(error = %e, location = %location, "Failed to remove orphaned outputs");
+                }
+            }
+        }
+        if let Ok(true) = result {
+            info!(job_id = %job_id, "Job archived");
+        }
+        result
+    }
+
+    /// 在事务内把作业与任务移入归档表
+    async fn move_job(&self, job_id: Uuid, location: Option<&str>) -> Result<bool> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        // 锁定作业并复查条件（上传期间作业可能已被重试或删除）
+        let locked = sqlx::query_scalar::<_, Uuid>(&format!(
+            "SELECT j.id FROM jobs j WHERE j.id = $2 AND {} FOR UPDATE",
+            ARCHIVABLE_JOB_CONDITION
+        ))
+        .bind(self.config.after_days as i32)
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to lock job for archiving");
+            AppError::database("Failed to lock job")
+        })?;
+        if locked.is_none() {
+            return Ok(false);
+        }
+
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, job_id = %job_id, "Failed to move job into archive");
+            AppError::database("Failed to archive job")
+        };
+        sqlx::query("INSERT INTO jobs_archive SELECT * FROM jobs WHERE id = $1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        // 按列名搬移（归档表在 tasks 列之后还有 output_location/archived_at，之后新增的列顺序不一致）；
+        // 完整输出已在输出包中，不保留在归档表
+        sqlx::query(
+            "INSERT INTO tasks_archive
+             SELECT (jsonb_populate_record(
+                 NULL::tasks_archive,
+                 to_jsonb(t) || jsonb_build_object(
+                     'output_location', CASE WHEN t.output_detail IS NOT NULL THEN $2::text END,
+                     'archived_at', NOW()
+                 )
+             )).*
+             FROM tasks t WHERE t.job_id = $1",
+        )
+        .bind(job_id)
+        .bind(location)
+        .execute(&mut *tx)
+        .await
+        .map_err(map_err)?;
+        sqlx::query("UPDATE tasks_archive SET output_detail = NULL WHERE job_id = $1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        // 任务随作业级联删除
+        sqlx::query("DELETE FROM jobs WHERE id = $1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        Ok(true)
+    }
+
+    /// 查询已归档的作业
+    pub async fn find_job(db: &Pool<Postgres>, job_id: Uuid) -> Result<Option<Job>> {
+        sqlx::query_as::<_, Job>("SELECT * FROM jobs_archive WHERE id = $1")
+            .bind(job_id)
+            .fetch_optional(db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job_id, "Failed to fetch archived job");
+                AppError::database("Failed to fetch job")
+            })
+    }
+
+    /// 查询已归档作业的任务
+    ///
+    /// 提供存储服务时从对象存储回填完整输出；输出包读取失败时只记录警告，任务仍保留输出摘要
+    pub async fn load_tasks(
+        db: &Pool<Postgres>,
+        storage: Option<&StorageService>,
+        job_id: Uuid,
+    ) -> Result<Vec<Task>> {
+        let mut tasks = sqlx::query_as::<_, Task>(
+            "SELECT * FROM tasks_archive WHERE job_id = $1 ORDER BY created_at",
+        )
+        .bind(job_id)
+        .fetch_all(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to fetch archived tasks");
+            AppError::database("Failed to fetch tasks")
+        })?;
+
+        let Some(storage) = storage else {
+            return Ok(tasks);
+        };
+
+        let locations = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT output_location FROM tasks_archive
+             WHERE job_id = $1 AND output_location IS NOT NULL",
+        )
+        .bind(job_id)
+        .fetch_all(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to fetch archived output locations");
+            AppError::database("Failed to fetch tasks")
+        })?;
+
+        for location in locations {
+            let outputs = match storage.get_object(&location).await {
+                Ok(bundle) => decode_outputs(&bundle),
+                Err(e) => Err(AppError::internal_error(&e.to_string())),
+            };
+            match outputs {
+                Ok(mut outputs) => {
+                    for task in tasks.iter_mut() {
+                        if let Some(output) = outputs.remove(&task.id) {
+                            task.output_detail = Some(output);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %e, location = %location, "Failed to load archived outputs");
+                }
+            }
+        }
+        Ok(tasks)
+    }
+}
+
+/// 输出包在对象存储中的键（按归档月份分目录，便于配置生命周期策略）
+fn output_key(job_id: Uuid) -> String {
+    format!("job-archives/{}/{}.json.gz", chrono::Utc::now().format("%Y/%m"), job_id)
+}
+
+/// 把任务输出编码为 gzip 压缩的 JSON
+fn encode_outputs(job_id: Uuid, outputs: BTreeMap<Uuid, String>) -> Result<Vec<u8>> {
+    let bundle = ArchivedOutputs { job_id, outputs };
+    let json = serde_json::to_vec(&bundle)
+        .map_err(|_| AppError::internal_error("Failed to serialize archived outputs"))?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish())
+        .map_err(|_| AppError::internal_error("Failed to compress archived outputs"))
+}
+
+/// 解码输出包，返回任务 ID 到完整输出的映射
+fn decode_outputs(bundle: &[u8]) -> Result<BTreeMap<Uuid, String>> {
+    let mut json = Vec::new();
+    GzDecoder::new(bundle)
+        .read_to_end(&mut json)
+        .map_err(|_| AppError::internal_error("Failed to decompress archived outputs"))?;
+    let bundle: ArchivedOutputs = serde_json::from_slice(&json)
+        .map_err(|_| AppError::internal_error("Failed to parse archived outputs"))?;
+    Ok(bundle.outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outputs_roundtrip() {
+        let job_id = Uuid::new_v4();
+        let task_id = Uuid::new_v4();
+        let outputs = BTreeMap::from([(task_id, "line 1\n中文输出\n".repeat(100))]);
+
+        let bundle = encode_outputs(job_id, outputs.clone()).unwrap();
+        assert!(bundle.len() < outputs[&task_id].len());
+        assert_eq!(decode_outputs(&bundle).unwrap(), outputs);
+        assert!(decode_outputs(b"not gzip").is_err());
+    }
+
+    #[test]
+    fn test_output_key_is_per_job() {
+        let job_id = Uuid::new_v4();
+        let key = output_key(job_id);
+        assert!(key.starts_with("job-archives/"));
+        assert!(key.ends_with(&format!("/{}.json.gz", job_id)));
+    }
+}
@@ -0,0 +1,144 @@
+//! 作业执行预算
+//!
+//! 防止失控作业占满存储与事件通道：单个作业保存的输出总字节数、推送的增量输出事件数与任务数设有上限。
+//! 执行期间各任务共享同一个计量器，首次超出任一上限时记录超出项并唤醒作业的预算监视，
+//! 由作业停止剩余任务并以 budget_exceeded 失败结束。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use chrono::Utc;
+use tokio::sync::Notify;
+
+use crate::{
+    config::JobBudgetConfig,
+    models::job::{JobBudgetBreach, JobBudgetLimit},
+};
+
+/// 单个作业的执行预算计量器
+pub struct JobBudget {
+    config: JobBudgetConfig,
+    output_bytes: AtomicU64,
+    events: AtomicU64,
+    breach: OnceLock<JobBudgetBreach>,
+    tripped: Notify,
+}
+
+impl JobBudget {
+    pub fn new(config: JobBudgetConfig) -> Self {
+        Self {
+            config,
+            output_bytes: AtomicU64::new(0),
+            events: AtomicU64::new(0),
+            breach: OnceLock::new(),
+            tripped: Notify::new(),
+        }
+    }
+
+    /// 检查任务数，超出时返回 false
+    pub fn check_tasks(&self, count: u64) -> bool {
+        let max = self.config.max_tasks;
+        if max == 0 || count <= max {
+            return true;
+        }
+        self.trip(JobBudgetLimit::Tasks, max, count);
+        false
+    }
+
+    /// 计入保存的输出字节数，超出时返回 false
+    pub fn record_output(&self, bytes: u64) -> bool {
+        self.consume(
+            JobBudgetLimit::OutputBytes,
+            &self.output_bytes,
+            bytes,
+            self.config.max_output_bytes,
+        )
+    }
+
+    /// 计入一个增量输出事件，超出时返回 false（调用方不再推送）
+    pub fn record_event(&self) -> bool {
+        self.consume(JobBudgetLimit::Events, &self.events, 1, self.config.max_events)
+    }
+
+    /// 首次超出的预算项
+    pub fn breach(&self) -> Option<&JobBudgetBreach> {
+        self.breach.get()
+    }
+
+    /// 等待预算被超出
+    pub async fn tripped(&self) {
+        if self.breach.get().is_none() {
+            self.tripped.notified().await;
+        }
+    }
+
+    fn consume(&self, limit: JobBudgetLimit, counter: &AtomicU64, amount: u64, max: u64) -> bool {
+        let observed = counter.fetch_add(amount, Ordering::Relaxed) + amount;
+        if max == 0 || observed <= max {
+            return true;
+        }
+        self.trip(limit, max, observed);
+        false
+    }
+
+    fn trip(&self, limit: JobBudgetLimit, max: u64, observed: u64) {
+        let breach = JobBudgetBreach {
+            limit,
+            max,
+            observed,
+            breached_at: Utc::now(),
+        };
+        // 只记录首次超出，notify_one 保留许可，监视尚未开始等待时也不会错过
+        if self.breach.set(breach).is_ok() {
+            self.tripped.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(max_output_bytes: u64, max_events: u64, max_tasks: u64) -> JobBudget {
+        JobBudget::new(JobBudgetConfig {
+            max_output_bytes,
+            max_events,
+            max_tasks,
+        })
+    }
+
+    #[test]
+    fn test_first_breach_is_recorded() {
+        let budget = budget(100, 2, 10);
+        assert!(budget.check_tasks(10));
+        assert!(budget.record_output(60));
+        assert!(budget.record_event());
+        assert!(budget.record_event());
+        assert!(budget.breach().is_none());
+
+        assert!(!budget.record_output(50));
+        assert!(!budget.record_event());
+        let breach = budget.breach().unwrap();
+        assert_eq!(breach.limit, JobBudgetLimit::OutputBytes);
+        assert_eq!((breach.max, breach.observed), (100, 110));
+    }
+
+    #[test]
+    fn test_zero_means_unlimited() {
+        let budget = budget(0, 0, 0);
+        assert!(budget.check_tasks(1_000_000));
+        assert!(budget.record_output(u32::MAX as u64));
+        assert!(budget.record_event());
+        assert!(budget.breach().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tripped_resolves_after_breach() {
+        let budget = budget(0, 0, 5);
+        assert!(!budget.check_tasks(6));
+        tokio::time::timeout(std::time::Duration::from_secs(1), budget.tripped())
+            .await
+            .expect("budget monitor should wake up");
+        assert_eq!(budget.breach().unwrap().limit, JobBudgetLimit::Tasks);
+    }
+}
@@ -0,0 +1,183 @@
+//! 作业派发队列
+//!
+//! 待执行的作业写入 job_dispatch_queue，由各实例的工作池以 SKIP LOCKED 认领；
+//! 认领后定期续约，执行结束删除记录。租约过期（实例崩溃）的记录重新入队，
+//! 中断时执行中的任务标记为失败，其余任务由重新认领的实例续跑。
+//! 创建事务提交后、入队前崩溃遗漏的作业由补偿任务找回
+
+use sqlx::{PgExecutor, PgPool};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+
+/// 已认领的派发记录
+#[derive(Debug, Clone, Copy, sqlx::FromRow)]
+pub(crate) struct ClaimedDispatch {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub attempts: i32,
+}
+
+/// 作业入队（已有待认领的记录时合并）
+pub(crate) async fn enqueue<'e, E>(executor: E, job_id: Uuid) -> Result<()>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query("INSERT INTO job_dispatch_queue (job_id) VALUES ($1) ON CONFLICT DO NOTHING")
+        .bind(job_id)
+        .execute(executor)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to enqueue job dispatch");
+            AppError::database("Failed to enqueue job")
+        })?;
+    Ok(())
+}
+
+/// 认领最早入队的一条记录；同一作业已被认领（执行中）时跳过，待其结束后再认领
+pub(crate) async fn claim(db: &PgPool, worker_id: &str) -> Result<Option<ClaimedDispatch>> {
+    sqlx::query_as::<_, ClaimedDispatch>(
+        r#"
+        UPDATE job_dispatch_queue
+        SET claimed_by = $1, claimed_at = NOW(), heartbeat_at = NOW(), attempts = attempts + 1
+        WHERE id = (
+            SELECT q.id FROM job_dispatch_queue q
+            WHERE q.claimed_at IS NULL
+              AND NOT EXISTS (
+                  SELECT 1 FROM job_dispatch_queue c
+                  WHERE c.job_id = q.job_id AND c.claimed_at IS NOT NULL
+              )
+            ORDER BY q.enqueued_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, job_id, attempts
+        "#,
+    )
+    .bind(worker_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to claim job dispatch");
+        AppError::database("Failed to claim job")
+    })
+}
+
+/// 执行结束后删除认领记录
+pub(crate) async fn complete(db: &PgPool, id: Uuid) -> Result<()> {
+    sqlx::query("DELETE FROM job_dispatch_queue WHERE id = $1")
+        .bind(id)
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, dispatch_id = %id, "Failed to complete job dispatch");
+            AppError::database("Failed to complete job dispatch")
+        })?;
+    Ok(())
+}
+
+/// 为执行中的记录续约，返回续约的记录数
+pub(crate) async fn heartbeat(db: &PgPool, ids: &[Uuid]) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE job_dispatch_queue SET heartbeat_at = NOW() WHERE id = ANY($1) AND claimed_at IS NOT NULL",
+    )
+    .bind(ids)
+    .execute(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to renew job dispatch leases");
+        AppError::database("Failed to renew job dispatch leases")
+    })?;
+    Ok(result.rows_affected())
+}
+
+/// 租约过期的记录重新入队，返回重新入队的作业
+///
+/// 认领的实例已不在执行，作业中仍为 running 的任务无法确认结果，标记为失败
+pub(crate) async fn recover_stale(db: &PgPool, lease_secs: u64) -> Result<Vec<Uuid>> {
+    let mut tx = db.begin().await.map_err(|e| {
+        error!(error = %e, "Failed to begin transaction");
+        AppError::database("Failed to begin transaction")
+    })?;
+
+    let stale = sqlx::query_as::<_, (Uuid, i32)>(
+        r#"
+        DELETE FROM job_dispatch_queue
+        WHERE id IN (
+            SELECT id FROM job_dispatch_queue
+            WHERE claimed_at IS NOT NULL
+              AND heartbeat_at < NOW() - make_interval(secs => $1)
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING job_id, attempts
+        "#,
+    )
+    .bind(lease_secs as f64)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to fetch stale job dispatches");
+        AppError::database("Failed to recover job dispatches")
+    })?;
+    if stale.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (job_ids, attempts): (Vec<Uuid>, Vec<i32>) = stale.into_iter().unzip();
+    sqlx::query(
+        "UPDATE tasks SET status = 'failed', failure_reason = 'dispatch_lease_expired', failure_message = 'Task interrupted: dispatcher lease expired', completed_at = NOW() WHERE job_id = ANY($1) AND status = 'running'",
+    )
+    .bind(&job_ids)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to fail interrupted tasks");
+        AppError::database("Failed to update tasks")
+    })?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO job_dispatch_queue (job_id, attempts)
+        SELECT * FROM UNNEST($1::uuid[], $2::int[])
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(&job_ids)
+    .bind(&attempts)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to requeue job dispatches");
+        AppError::database("Failed to recover job dispatches")
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        error!(error = %e, "Failed to commit transaction");
+        AppError::database("Failed to commit transaction")
+    })?;
+    Ok(job_ids)
+}
+
+/// 创建后未入队的待执行作业，超过宽限时间（秒）才视为遗漏
+///
+/// 排队等待互斥键的作业由前序作业结束后调度，不在此列
+pub(crate) async fn find_orphaned(db: &PgPool, grace_secs: u64) -> Result<Vec<Uuid>> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT j.id FROM jobs j
+        WHERE j.status = 'pending' AND NOT j.singleton_waiting
+          AND j.created_at < NOW() - make_interval(secs => $1)
+          AND NOT EXISTS (SELECT 1 FROM job_dispatch_queue q WHERE q.job_id = j.id)
+        ORDER BY j.created_at
+        LIMIT 100
+        "#,
+    )
+    .bind(grace_secs as f64)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to fetch undispatched jobs");
+        AppError::database("Failed to fetch jobs")
+    })
+}
@@ -1,23 +1,204 @@
 //! 作业服务层
 //! P2 阶段：提供作业的创建、查询、取消、重试等功能
 
+use dashmap::DashMap;
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::types::Json;
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
+use tokio::sync::{watch, Notify, OwnedSemaphorePermit, Semaphore};
 use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 
+use crate::cache;
 use crate::concurrency::ConcurrencyController;
-use crate::config::SshConfig as AppSshConfig;
+use crate::config::{
+    JobBudgetConfig, JobDispatchConfig, NetworkBackoffConfig, OutputConfig,
+    SshConfig as AppSshConfig,
+};
 use crate::error::{AppError, Result};
-use crate::models::asset::Host;
+use crate::executor::{target, CommandExecutor, ExecutionPayload, ExecutionRequest, SshExecutor};
+use crate::middleware::request_id;
+use crate::models::approval::{ApprovalRequest, ApprovalStatus};
+use crate::models::asset::{ConnectionTestReport, Host};
+use crate::models::blob::BLOB_OWNER_JOB;
 use crate::models::job::*;
+use crate::models::soft_delete::SoftDeleteResource;
+use crate::models::watch::{CreateWatchRequest, Watch, WatchTargetType};
 use crate::output::OutputArchive;
-use crate::realtime::EventBus;
-use crate::services::audit_service::{AuditAction, AuditService};
-use crate::services::ApprovalService;
-use crate::ssh::{HostKeyVerification, SSHClient, SshAuth, SshConfig};
+use crate::realtime::{outbox, EventBus, RealtimeEvent};
+use crate::services::approval_service::{approval_fingerprint, JobRiskAssessment};
+use crate::services::artifact_variants;
+use crate::services::audit_service::{AuditAction, AuditLogParams, AuditService};
+use crate::services::blob_store::StagedFile;
+use crate::services::connection_test;
+use crate::services::environment_policy;
+use crate::services::exit_code_rules;
+use crate::services::file_distribution;
+use crate::services::file_manifest;
+use crate::services::host_key_drift;
+use crate::services::host_vars;
+use crate::services::job_budget::JobBudget;
+use crate::services::job_dispatch;
+use crate::services::job_trace::{self, JobTracer, PublishCounter};
+use crate::services::network_backoff::{self, NetworkBackoff};
+use crate::services::output_drift;
+use crate::services::output_shaper::{OutputShaper, ShapedOutput};
+use crate::services::package_inventory;
+use crate::services::soft_delete;
+use crate::services::task_journal::TaskJournal;
+use crate::services::template_resolver::TemplateGraph;
+use crate::services::{ApprovalService, BlobStore, JobArchiver, StorageService};
+use crate::ssh::{
+    DiagnosticsCollector, ExecutionResult, HostKeyVerification, OutputEncoding, PresentedHostKey,
+    SSHClient, SshAuth, SshConfig,
+};
 use secrecy::ExposeSecret;
 
+/// 单次批量任务操作最多选择的任务与主机数
+const MAX_BULK_TASKS: usize = 1000;
+
+/// 主机处于维护中的判定条件（assets_hosts，维护结束时间已过视为已结束）
+const HOST_UNDER_MAINTENANCE: &str =
+    "status = 'maintenance' AND (maintenance_until IS NULL OR maintenance_until > NOW())";
+
+/// 按任务表重新计算单个作业的计数，同时返回是否仍有未结束的任务
+const REFRESH_JOB_COUNTS_SQL: &str = r#"
+    UPDATE jobs j
+    SET succeeded_tasks = c.succeeded,
+        warning_tasks = c.warning,
+        failed_tasks = c.failed,
+        timeout_tasks = c.timeout,
+        cancelled_tasks = c.cancelled,
+        skipped_tasks = c.skipped
+    FROM (
+        SELECT
+            COUNT(*) FILTER (WHERE status = 'succeeded')::int AS succeeded,
+            COUNT(*) FILTER (WHERE status = 'warning')::int AS warning,
+            COUNT(*) FILTER (WHERE status = 'failed')::int AS failed,
+            COUNT(*) FILTER (WHERE status = 'timeout')::int AS timeout,
+            COUNT(*) FILTER (WHERE status = 'cancelled')::int AS cancelled,
+            COUNT(*) FILTER (WHERE status = 'skipped')::int AS skipped,
+            COUNT(*) FILTER (WHERE status IN ('pending', 'running', 'waiting_maintenance')) > 0
+                AS has_unfinished
+        FROM tasks
+        WHERE job_id = $1
+    ) c
+    WHERE j.id = $1
+    RETURNING j.succeeded_tasks AS succeeded, j.warning_tasks AS warning,
+        j.failed_tasks AS failed, j.timeout_tasks AS timeout, j.cancelled_tasks AS cancelled,
+        j.skipped_tasks AS skipped, c.has_unfinished
+"#;
+
+/// 作业的任务计数（由任务表汇总）
+#[derive(Debug, sqlx::FromRow)]
+struct JobTaskCounts {
+    succeeded: i32,
+    warning: i32,
+    failed: i32,
+    timeout: i32,
+    cancelled: i32,
+    skipped: i32,
+    has_unfinished: bool,
+}
+
+/// 模板作业的审批上下文
+struct TemplateApprovalContext {
+    template_id: Uuid,
+    risk_level: String,
+    parameters: serde_json::Value,
+}
+
+/// 后续作业在作业链中的位置
+struct ChainLink {
+    parent_job_id: Uuid,
+    trigger: ChainTrigger,
+    depth: i32,
+}
+
+/// 后续作业派发结果
+enum FollowUpOutcome {
+    /// 已创建（后续作业 ID）
+    Launched(Uuid),
+    /// 不启动（原因）
+    Skipped(String),
+}
+
+/// 互斥键准入结果
+enum SingletonAdmission {
+    /// 无同键作业进行中（或未设置互斥键），立即执行
+    Run,
+    /// 排队等待同键作业结束
+    Queue,
+    /// 取消进行中的同键作业后执行
+    Replace(Vec<Uuid>),
+}
+
+impl SingletonAdmission {
+    fn is_queued(&self) -> bool {
+        matches!(self, SingletonAdmission::Queue)
+    }
+}
+
+/// 运行中作业（或任务）的取消信号
+type CancellationRegistry = DashMap<Uuid, Arc<watch::Sender<bool>>>;
+
+/// 本实例的作业派发工作池
+struct DispatchPool {
+    /// 认领派发记录时登记的实例标识
+    worker_id: String,
+    /// 空闲工作数
+    workers: Arc<Semaphore>,
+    /// 执行中的派发记录（记录 ID -> 作业 ID），定期续约
+    active: DashMap<Uuid, Uuid>,
+    lease_secs: u64,
+}
+
+/// 执行中的派发记录，执行结束（含 panic）时停止续约并归还工作
+struct ActiveDispatch {
+    pool: Arc<DispatchPool>,
+    id: Uuid,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for ActiveDispatch {
+    fn drop(&mut self) {
+        self.pool.active.remove(&self.id);
+    }
+}
+
+/// 后台执行作业所需的共享依赖
+#[derive(Clone)]
+struct JobExecutionContext {
+    db: Pool<Postgres>,
+    concurrency_controller: Arc<ConcurrencyController>,
+    ssh_config: AppSshConfig,
+    event_bus: Arc<EventBus>,
+    executor: Arc<dyn CommandExecutor>,
+    cancellations: Arc<CancellationRegistry>,
+    task_cancellations: Arc<CancellationRegistry>,
+    blob_store: Option<Arc<BlobStore>>,
+    chain_signal: Arc<Notify>,
+    dispatch_pool: Option<Arc<DispatchPool>>,
+    dispatch_signal: Arc<Notify>,
+    audit_service: Arc<AuditService>,
+    budget: JobBudgetConfig,
+    network_backoff: NetworkBackoffConfig,
+    output: OutputConfig,
+}
+
+/// 主机连接参数及各项来源（来源用于执行上下文快照）
+pub(crate) struct ResolvedConnection {
+    pub config: SshConfig,
+    /// 执行用户来源（job/host/default）
+    pub user_source: &'static str,
+    /// 凭据来源（host/default）
+    pub credential_source: &'static str,
+    /// known_hosts 来源（host/central/file）
+    pub known_hosts_source: Option<&'static str>,
+}
+
 /// 作业服务
 pub struct JobService {
     db: Pool<Postgres>,
@@ -26,6 +207,24 @@ pub struct JobService {
     ssh_config: AppSshConfig,
     event_bus: Arc<EventBus>,
     approval_service: Option<Arc<ApprovalService>>,
+    executor: Arc<dyn CommandExecutor>,
+    cancellations: Arc<CancellationRegistry>,
+    /// 执行中任务的取消信号（批量取消任务时使用）
+    task_cancellations: Arc<CancellationRegistry>,
+    storage: Option<Arc<StorageService>>,
+    blob_store: Option<Arc<BlobStore>>,
+    /// 有作业结束且待启动后续作业时通知派发任务
+    chain_signal: Arc<Notify>,
+    /// 作业派发工作池（未启用派发队列时在本进程内直接执行）
+    dispatch_pool: Option<Arc<DispatchPool>>,
+    /// 有作业入队或工作空闲时通知派发任务
+    dispatch_signal: Arc<Notify>,
+    /// 单个作业的执行预算
+    budget: JobBudgetConfig,
+    /// 主机不可达退避
+    network_backoff: NetworkBackoffConfig,
+    /// 增量输出推送整形
+    output: OutputConfig,
 }
 
 impl JobService {
@@ -43,9 +242,26 @@ impl JobService {
             ssh_config,
             event_bus: Arc::new(EventBus::new(1000)),
             approval_service: None,
+            executor: Arc::new(SshExecutor),
+            cancellations: Arc::new(DashMap::new()),
+            task_cancellations: Arc::new(DashMap::new()),
+            storage: None,
+            blob_store: None,
+            chain_signal: Arc::new(Notify::new()),
+            dispatch_pool: None,
+            dispatch_signal: Arc::new(Notify::new()),
+            budget: JobBudgetConfig::default(),
+            network_backoff: NetworkBackoffConfig::default(),
+            output: OutputConfig::default(),
         }
     }
 
+    /// 设置命令执行器（默认 SSH，测试时可注入模拟执行器）
+    pub fn with_executor(mut self, executor: Arc<dyn CommandExecutor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
     /// 设置事件总线（用于外部注入）
     pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
         self.event_bus = event_bus;
@@ -58,12 +274,67 @@ impl JobService {
         self
     }
 
+    /// 设置存储服务（用于读取归档作业的任务输出）
+    pub fn with_storage(mut self, storage: Arc<StorageService>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// 设置 blob 存储（脚本内容按哈希去重保存）
+    pub fn with_blob_store(mut self, blob_store: Arc<BlobStore>) -> Self {
+        self.blob_store = Some(blob_store);
+        self
+    }
+
+    /// 设置作业执行预算
+    pub fn with_budget(mut self, budget: JobBudgetConfig) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// 设置主机不可达退避
+    pub fn with_network_backoff(mut self, network_backoff: NetworkBackoffConfig) -> Self {
+        self.network_backoff = network_backoff;
+        self
+    }
+
+    /// 设置增量输出推送整形
+    pub fn with_output_config(mut self, output: OutputConfig) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// 启用持久化派发队列：作业写入派发队列，由 `dispatch_queued_jobs` 按工作池容量认领执行
+    ///
+    /// 未启用时（测试、压测）作业在本进程内直接执行
+    pub fn with_dispatch_queue(mut self, config: &JobDispatchConfig) -> Self {
+        self.dispatch_pool = Some(Arc::new(DispatchPool {
+            worker_id: format!("ops-service-{}", Uuid::new_v4()),
+            workers: Arc::new(Semaphore::new(config.workers)),
+            active: DashMap::new(),
+            lease_secs: config.lease_secs,
+        }));
+        self
+    }
+
     /// 创建命令作业
     #[instrument(skip(self, request))]
     pub async fn create_command_job(
         &self,
         request: CreateCommandJobRequest,
         created_by: Uuid,
+    ) -> Result<Job> {
+        self.create_command_job_with_context(request, created_by, None, None)
+            .await
+    }
+
+    /// 创建命令作业（模板作业附带审批上下文，用于自动审批；后续作业附带其在作业链中的位置）
+    async fn create_command_job_with_context(
+        &self,
+        request: CreateCommandJobRequest,
+        created_by: Uuid,
+        template: Option<TemplateApprovalContext>,
+        chain: Option<ChainLink>,
     ) -> Result<Job> {
         info!(name = %request.name, "Creating command job");
 
@@ -78,6 +349,24 @@ impl JobService {
             }
         }
 
+        self.validate_job_tags(&request.tags).await?;
+        host_vars::validate(&request.command)?;
+        artifact_variants::validate(&request.command, request.artifact_id.is_some())?;
+        if let Some(rules) = &request.exit_code_rules {
+            exit_code_rules::validate(rules)?;
+        }
+        if let Some(manifest) = &request.file_manifest {
+            file_manifest::validate_manifest(manifest)?;
+        }
+        self.validate_follow_ups(
+            [
+                &request.on_success_job_template,
+                &request.on_failure_job_template,
+            ],
+            chain.as_ref().map_or(0, |c| c.depth),
+        )
+        .await?;
+
         // 验证目标主机
         let target_hosts = self
             .resolve_target_hosts(&request.target_hosts, &request.target_groups)
@@ -85,6 +374,16 @@ impl JobService {
         if target_hosts.is_empty() {
             return Err(AppError::validation("No valid target hosts found"));
         }
+        // 合并目标环境策略：校验作业类型与禁止变更窗口，补全默认超时与并发
+        let policy = environment_policy::effective_policy(&self.db, &target_hosts).await?;
+        policy.check(&JobType::Command, chrono::Utc::now())?;
+        let verbose_trace =
+            job_trace::resolve_enabled(&self.db, request.verbose_trace, &target_hosts, created_by)
+                .await?;
+        let target_host_ids: Vec<Uuid> = target_hosts.iter().map(|h| h.id).collect();
+        let fingerprint = template
+            .as_ref()
+            .map(|t| approval_fingerprint(t.template_id, &t.parameters, &target_host_ids));
 
         // 开始事务
         let mut tx = self.db.begin().await.map_err(|e| {
@@ -92,22 +391,36 @@ impl JobService {
             AppError::database("Failed to begin transaction")
         })?;
 
+        // 同一互斥键的作业进行中时按策略拒绝、排队或替换
+        let admission = Self::admit_singleton(
+            &mut tx,
+            request.singleton_key.as_deref(),
+            request.singleton_policy,
+        )
+        .await?;
+
         // 创建作业记录
         let job_id = Uuid::new_v4();
-        let job = sqlx::query_as::<_, Job>(
+        let mut job = sqlx::query_as::<_, Job>(
             r#"
             INSERT INTO jobs (
                 id, job_type, name, description, status,
                 target_hosts, target_groups,
                 command, concurrent_limit, timeout_secs, retry_times, execute_user,
-                idempotency_key,
-                total_tasks, created_by, tags
+                idempotency_key, singleton_key, singleton_waiting,
+                total_tasks, created_by, tags, approval_fingerprint, template_id,
+                on_success_job_template, on_failure_job_template,
+                parent_job_id, chain_trigger, chain_depth, exit_code_rules, artifact_id,
+                file_manifest, drift_check, verbose_trace
             ) VALUES (
                 $1, $2, $3, $4, 'pending',
                 $5, $6,
                 $7, $8, $9, $10, $11,
-                $12,
-                $13, $14, $15
+                $12, $17, $18,
+                $13, $14, $15, $16, $19,
+                $20, $21,
+                $22, $23, $24, $25, $26,
+                $27, $28, $29
             ) RETURNING *
             "#,
         )
@@ -115,17 +428,31 @@ impl JobService {
         .bind(JobType::Command)
         .bind(&request.name)
         .bind(&request.description)
-        .bind(target_hosts.iter().map(|h| h.id).collect::<Vec<_>>())
-        .bind(&request.target_groups)
+        .bind(Json(&target_host_ids))
+        .bind(Json(&request.target_groups))
         .bind(&request.command)
-        .bind(request.concurrent_limit)
-        .bind(request.timeout_secs)
+        .bind(request.concurrent_limit.or(policy.default_concurrent_limit))
+        .bind(request.timeout_secs.or(policy.default_timeout_secs))
         .bind(request.retry_times.unwrap_or(0))
         .bind(&request.execute_user)
         .bind(&request.idempotency_key)
         .bind(target_hosts.len() as i32)
         .bind(created_by)
-        .bind(&request.tags)
+        .bind(Json(&request.tags))
+        .bind(&fingerprint)
+        .bind(&request.singleton_key)
+        .bind(admission.is_queued())
+        .bind(template.as_ref().map(|t| t.template_id))
+        .bind(request.on_success_job_template.as_ref().map(Json))
+        .bind(request.on_failure_job_template.as_ref().map(Json))
+        .bind(chain.as_ref().map(|c| c.parent_job_id))
+        .bind(chain.as_ref().map(|c| c.trigger.as_str()))
+        .bind(chain.as_ref().map_or(0, |c| c.depth))
+        .bind(request.exit_code_rules.as_ref().map(Json))
+        .bind(request.artifact_id)
+        .bind(request.file_manifest.as_ref().map(Json))
+        .bind(request.drift_check)
+        .bind(verbose_trace)
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| {
@@ -174,42 +501,34 @@ impl JobService {
 
         info!(job_id = %job_id, "Command job created successfully");
 
-        // 审批检查：如果需要审批，将作业状态设为 awaiting_approval
+        // 审批检查：需要审批且未能自动审批时，作业等待审批通过后再派发
         if let Some(ref approval_svc) = self.approval_service {
-            if approval_svc.check_job_requires_approval(&job, &target_hosts).await? {
-                info!(job_id = %job_id, "Job requires approval, setting status to awaiting_approval");
-                sqlx::query("UPDATE jobs SET status = 'awaiting_approval' WHERE id = $1")
-                    .bind(job_id)
-                    .execute(&self.db)
-                    .await
-                    .map_err(|e| {
-                        error!(error = %e, "Failed to update job status");
-                        AppError::database("Failed to update job status")
-                    })?;
-                return Ok(job);
+            let risk_level = template.as_ref().map(|t| t.risk_level.as_str());
+            let assessment = approval_svc
+                .assess_job_risk(&job, &target_hosts, risk_level)
+                .await;
+            if assessment.requires_approval() {
+                let auto_approval = match &fingerprint {
+                    Some(fingerprint) => approval_svc
+                        .try_auto_approve(&job, fingerprint, &assessment)
+                        .await
+                        .unwrap_or_else(|e| {
+                            warn!(error = %e, job_id = %job_id, "Auto-approval failed");
+                            None
+                        }),
+                    None => None,
+                };
+                if auto_approval.is_none() {
+                    self.hold_for_approval(approval_svc, &mut job, &assessment)
+                        .await?;
+                    return Ok(job);
+                }
             }
         }
 
-        // 异步启动作业执行
-        let db_clone = self.db.clone();
-        let concurrency_clone = self.concurrency_controller.clone();
-        let audit_clone = self.audit_service.clone();
-        let ssh_config_clone = self.ssh_config.clone();
-        let event_bus_clone = self.event_bus.clone();
-        tokio::spawn(async move {
-            if let Err(e) = Self::execute_job(
-                job_id,
-                db_clone,
-                concurrency_clone,
-                audit_clone,
-                ssh_config_clone,
-                event_bus_clone,
-            )
-            .await
-            {
-                error!(error = %e, job_id = %job_id, "Failed to execute job");
-            }
-        });
+        // 异步启动作业执行（排队中的作业由前序作业结束后调度）
+        self.start_admitted_job(job_id, admission, created_by)
+            .await?;
 
         Ok(job)
     }
@@ -230,10 +549,40 @@ impl JobService {
                     job_id = %existing.id,
                     "Found existing job with same idempotency key"
                 );
+                let mut existing = existing;
+                Self::load_script(self.blob_store.as_deref(), &mut existing).await?;
                 return Ok(existing);
             }
         }
 
+        self.validate_job_tags(&request.tags).await?;
+        // 引用已上传的内容时以文件传输到目标主机，不解析主机变量
+        let uploaded = self.resolve_uploaded_script(&request).await?;
+        if uploaded.is_none() {
+            host_vars::validate(&request.script)?;
+            artifact_variants::validate(&request.script, request.artifact_id.is_some())?;
+        }
+        if let Some(rules) = &request.exit_code_rules {
+            exit_code_rules::validate(rules)?;
+        }
+        // 以文件传输的内容不经 shell 包裹，无法记录文件修改
+        if let Some(manifest) = &request.file_manifest {
+            if uploaded.is_some() {
+                return Err(AppError::validation(
+                    "File manifests are not supported for uploaded scripts",
+                ));
+            }
+            file_manifest::validate_manifest(manifest)?;
+        }
+        self.validate_follow_ups(
+            [
+                &request.on_success_job_template,
+                &request.on_failure_job_template,
+            ],
+            0,
+        )
+        .await?;
+
         // 验证目标主机
         let target_hosts = self
             .resolve_target_hosts(&request.target_hosts, &request.target_groups)
@@ -241,6 +590,19 @@ impl JobService {
         if target_hosts.is_empty() {
             return Err(AppError::validation("No valid target hosts found"));
         }
+        // 合并目标环境策略：校验作业类型与禁止变更窗口，补全默认超时与并发
+        let policy = environment_policy::effective_policy(&self.db, &target_hosts).await?;
+        policy.check(&JobType::Script, chrono::Utc::now())?;
+        let verbose_trace =
+            job_trace::resolve_enabled(&self.db, request.verbose_trace, &target_hosts, created_by)
+                .await?;
+
+        // 配置了 blob 存储时脚本内容按哈希保存，作业记录只保留哈希
+        let script_sha256 = match (&uploaded, &self.blob_store) {
+            (Some(sha256), _) => Some(sha256.clone()),
+            (None, Some(store)) => Some(store.put(request.script.as_bytes()).await?.sha256),
+            (None, None) => None,
+        };
 
         // 开始事务
         let mut tx = self.db.begin().await.map_err(|e| {
@@ -248,22 +610,34 @@ impl JobService {
             AppError::database("Failed to begin transaction")
         })?;
 
+        // 同一互斥键的作业进行中时按策略拒绝、排队或替换
+        let admission = Self::admit_singleton(
+            &mut tx,
+            request.singleton_key.as_deref(),
+            request.singleton_policy,
+        )
+        .await?;
+
         // 创建作业记录
         let job_id = Uuid::new_v4();
-        let job = sqlx::query_as::<_, Job>(
+        let mut job = sqlx::query_as::<_, Job>(
             r#"
             INSERT INTO jobs (
                 id, job_type, name, description, status,
                 target_hosts, target_groups,
                 script, script_path, concurrent_limit, timeout_secs, retry_times, execute_user,
-                idempotency_key,
-                total_tasks, created_by, tags
+                idempotency_key, singleton_key, singleton_waiting,
+                total_tasks, created_by, tags, script_sha256, script_streamed,
+                on_success_job_template, on_failure_job_template, exit_code_rules, artifact_id,
+                file_manifest, drift_check, verbose_trace
             ) VALUES (
                 $1, $2, $3, $4, 'pending',
                 $5, $6,
                 $7, $8, $9, $10, $11, $12,
-                $13,
-                $14, $15, $16
+                $13, $17, $18,
+                $14, $15, $16, $19, $22,
+                $20, $21, $23, $24,
+                $25, $26, $27
             ) RETURNING *
             "#,
         )
@@ -271,18 +645,29 @@ impl JobService {
         .bind(JobType::Script)
         .bind(&request.name)
         .bind(&request.description)
-        .bind(target_hosts.iter().map(|h| h.id).collect::<Vec<_>>())
-        .bind(&request.target_groups)
-        .bind(&request.script)
+        .bind(Json(target_hosts.iter().map(|h| h.id).collect::<Vec<_>>()))
+        .bind(Json(&request.target_groups))
+        .bind(script_sha256.is_none().then_some(&request.script))
         .bind(&request.script_path)
-        .bind(request.concurrent_limit)
-        .bind(request.timeout_secs)
+        .bind(request.concurrent_limit.or(policy.default_concurrent_limit))
+        .bind(request.timeout_secs.or(policy.default_timeout_secs))
         .bind(request.retry_times.unwrap_or(0))
         .bind(&request.execute_user)
         .bind(&request.idempotency_key)
         .bind(target_hosts.len() as i32)
         .bind(created_by)
-        .bind(&request.tags)
+        .bind(Json(&request.tags))
+        .bind(&request.singleton_key)
+        .bind(admission.is_queued())
+        .bind(&script_sha256)
+        .bind(request.on_success_job_template.as_ref().map(Json))
+        .bind(request.on_failure_job_template.as_ref().map(Json))
+        .bind(uploaded.is_some())
+        .bind(request.exit_code_rules.as_ref().map(Json))
+        .bind(request.artifact_id)
+        .bind(request.file_manifest.as_ref().map(Json))
+        .bind(request.drift_check)
+        .bind(verbose_trace)
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| {
@@ -290,6 +675,13 @@ impl JobService {
             AppError::database("Failed to create job")
         })?;
 
+        if let Some(sha256) = &script_sha256 {
+            BlobStore::add_ref(&mut tx, sha256, BLOB_OWNER_JOB, job_id).await?;
+            if uploaded.is_none() {
+                job.script = Some(request.script.clone());
+            }
+        }
+
         // 创建任务记录
         for host in &target_hosts {
             sqlx::query(
@@ -331,133 +723,499 @@ impl JobService {
 
         info!(job_id = %job_id, "Script job created successfully");
 
-        // 审批检查：如果需要审批，将作业状态设为 awaiting_approval
+        // 审批检查：需要审批时，作业等待审批通过后再派发
         if let Some(ref approval_svc) = self.approval_service {
-            if approval_svc.check_job_requires_approval(&job, &target_hosts).await? {
-                info!(job_id = %job_id, "Script job requires approval, setting status to awaiting_approval");
-                sqlx::query("UPDATE jobs SET status = 'awaiting_approval' WHERE id = $1")
-                    .bind(job_id)
-                    .execute(&self.db)
-                    .await
-                    .map_err(|e| {
-                        error!(error = %e, "Failed to update job status");
-                        AppError::database("Failed to update job status")
-                    })?;
+            let assessment = approval_svc
+                .assess_job_risk(&job, &target_hosts, None)
+                .await;
+            if assessment.requires_approval() {
+                self.hold_for_approval(approval_svc, &mut job, &assessment)
+                    .await?;
                 return Ok(job);
             }
         }
 
-        // 异步启动作业执行
-        let db_clone = self.db.clone();
-        let concurrency_clone = self.concurrency_controller.clone();
-        let audit_clone = self.audit_service.clone();
-        let ssh_config_clone = self.ssh_config.clone();
-        let event_bus_clone = self.event_bus.clone();
-        tokio::spawn(async move {
-            if let Err(e) = Self::execute_job(
-                job_id,
-                db_clone,
-                concurrency_clone,
-                audit_clone,
-                ssh_config_clone,
-                event_bus_clone,
-            )
-            .await
-            {
-                error!(error = %e, job_id = %job_id, "Failed to execute job");
-            }
-        });
+        // 异步启动作业执行（排队中的作业由前序作业结束后调度）
+        self.start_admitted_job(job_id, admission, created_by)
+            .await?;
 
         Ok(job)
     }
 
-    /// 查询作业详情
-    #[instrument(skip(self))]
-    pub async fn get_job(&self, job_id: Uuid) -> Result<Job> {
-        sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
-            .bind(job_id)
-            .fetch_one(&self.db)
-            .await
-            .map_err(|e| {
-                if let sqlx::Error::RowNotFound = e {
-                    AppError::not_found("Job not found")
-                } else {
-                    error!(error = %e, job_id = %job_id, "Failed to fetch job");
-                    AppError::database("Failed to fetch job")
-                }
-            })
-    }
-
-    /// 查询作业列表
-    #[instrument(skip(self))]
-    pub async fn list_jobs(&self, filters: JobListFilters) -> Result<Vec<Job>> {
-        let mut query = String::from("SELECT * FROM jobs WHERE 1=1");
-        let mut count = 0;
+    /// 创建文件分发作业
+    #[instrument(skip(self, request))]
+    pub async fn create_file_job(
+        &self,
+        request: CreateFileJobRequest,
+        created_by: Uuid,
+    ) -> Result<Job> {
+        info!(name = %request.name, path = %request.file.path, "Creating file job");
 
-        if filters.job_type.is_some() {
-            count += 1;
-            query.push_str(&format!(" AND job_type = ${}", count));
-        }
-        if filters.status.is_some() {
-            count += 1;
-            query.push_str(&format!(" AND status = ${}", count));
-        }
-        if filters.created_by.is_some() {
-            count += 1;
-            query.push_str(&format!(" AND created_by = ${}", count));
-        }
-        if filters.search.is_some() {
-            count += 1;
-            query
-                .push_str(&format!(" AND (name ILIKE ${} OR description ILIKE ${})", count, count));
+        // 检查幂等键
+        if let Some(key) = &request.idempotency_key {
+            if let Some(existing) = self.get_by_idempotency_key(key).await? {
+                info!(
+                    job_id = %existing.id,
+                    "Found existing job with same idempotency key"
+                );
+                return Ok(existing);
+            }
         }
-        if filters.date_from.is_some() {
-            count += 1;
-            query.push_str(&format!(" AND created_at >= ${}", count));
+
+        self.validate_job_tags(&request.tags).await?;
+        file_distribution::validate_spec(&request.file)?;
+        if let Some(command) = &request.file.validate_command {
+            host_vars::validate(command)?;
+            artifact_variants::validate(command, false)?;
         }
-        if filters.date_to.is_some() {
-            count += 1;
-            query.push_str(&format!(" AND created_at <= ${}", count));
+        self.validate_follow_ups(
+            [
+                &request.on_success_job_template,
+                &request.on_failure_job_template,
+            ],
+            0,
+        )
+        .await?;
+
+        // 验证目标主机
+        let target_hosts = self
+            .resolve_target_hosts(&request.target_hosts, &request.target_groups)
+            .await?;
+        if target_hosts.is_empty() {
+            return Err(AppError::validation("No valid target hosts found"));
         }
+        // 合并目标环境策略：校验作业类型与禁止变更窗口，补全默认超时与并发
+        let policy = environment_policy::effective_policy(&self.db, &target_hosts).await?;
+        policy.check(&JobType::File, chrono::Utc::now())?;
 
-        query.push_str(" ORDER BY created_at DESC LIMIT 100");
+        // 开始事务
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
 
-        // Prepare search pattern outside of bind to avoid lifetime issues
-        let search_pattern = filters.search.as_ref().map(|s| format!("%{}%", s));
+        // 同一互斥键的作业进行中时按策略拒绝、排队或替换
+        let admission = Self::admit_singleton(
+            &mut tx,
+            request.singleton_key.as_deref(),
+            request.singleton_policy,
+        )
+        .await?;
 
-        let mut q = sqlx::query_as::<_, Job>(&query);
+        // 创建作业记录
+        let job_id = Uuid::new_v4();
+        let mut job = sqlx::query_as::<_, Job>(
+            r#"
+            INSERT INTO jobs (
+                id, job_type, name, description, status,
+                target_hosts, target_groups,
+                file_spec, concurrent_limit, timeout_secs, retry_times, execute_user,
+                idempotency_key, singleton_key, singleton_waiting,
+                total_tasks, created_by, tags,
+                on_success_job_template, on_failure_job_template
+            ) VALUES (
+                $1, $2, $3, $4, 'pending',
+                $5, $6,
+                $7, $8, $9, $10, $11,
+                $12, $16, $17,
+                $13, $14, $15,
+                $18, $19
+            ) RETURNING *
+            "#,
+        )
+        .bind(job_id)
+        .bind(JobType::File)
+        .bind(&request.name)
+        .bind(&request.description)
+        .bind(Json(target_hosts.iter().map(|h| h.id).collect::<Vec<_>>()))
+        .bind(Json(&request.target_groups))
+        .bind(Json(&request.file))
+        .bind(request.concurrent_limit.or(policy.default_concurrent_limit))
+        .bind(request.timeout_secs.or(policy.default_timeout_secs))
+        .bind(request.retry_times.unwrap_or(0))
+        .bind(&request.execute_user)
+        .bind(&request.idempotency_key)
+        .bind(target_hosts.len() as i32)
+        .bind(created_by)
+        .bind(Json(&request.tags))
+        .bind(&request.singleton_key)
+        .bind(admission.is_queued())
+        .bind(request.on_success_job_template.as_ref().map(Json))
+        .bind(request.on_failure_job_template.as_ref().map(Json))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to insert file job");
+            AppError::database("Failed to create job")
+        })?;
 
-        if let Some(job_type) = filters.job_type {
-            q = q.bind(job_type);
-        }
-        if let Some(status) = filters.status {
-            q = q.bind(status);
-        }
-        if let Some(created_by) = filters.created_by {
-            q = q.bind(created_by);
-        }
-        if let Some(ref pattern) = search_pattern {
-            q = q.bind(pattern).bind(pattern);
-        }
-        if let Some(date_from) = filters.date_from {
-            q = q.bind(date_from);
-        }
-        if let Some(date_to) = filters.date_to {
-            q = q.bind(date_to);
+        // 创建任务记录
+        for host in &target_hosts {
+            sqlx::query(
+                r#"
+                INSERT INTO tasks (
+                    id, job_id, host_id, status, max_retries
+                ) VALUES ($1, $2, $3, 'pending', $4)
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(job_id)
+            .bind(host.id)
+            .bind(request.retry_times.unwrap_or(0))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!(error = %e, host_id = %host.id, "Failed to insert task");
+                AppError::database("Failed to create task")
+            })?;
         }
 
-        q.fetch_all(&self.db).await.map_err(|e| {
-            error!(error = %e, "Failed to fetch jobs");
-            AppError::database("Failed to fetch jobs")
-        })
-    }
+        // 提交事务
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
 
-    /// 查询作业列表（带作用域过滤）
-    /// 根据 user_id、allowed_groups、allowed_environments 过滤作业
-    #[instrument(skip(self, allowed_groups, allowed_environments))]
-    pub async fn list_jobs_with_scope(
-        &self,
-        filters: JobListFilters,
+        // 记录审计
+        self.audit_service
+            .log_action_simple(
+                created_by,
+                AuditAction::JobCreate,
+                Some("job"),
+                Some(job_id),
+                Some("File job created"),
+                None,
+            )
+            .await?;
+
+        info!(job_id = %job_id, "File job created successfully");
+
+        // 审批检查：需要审批时，作业等待审批通过后再派发
+        if let Some(ref approval_svc) = self.approval_service {
+            let assessment = approval_svc
+                .assess_job_risk(&job, &target_hosts, None)
+                .await;
+            if assessment.requires_approval() {
+                self.hold_for_approval(approval_svc, &mut job, &assessment)
+                    .await?;
+                return Ok(job);
+            }
+        }
+
+        // 异步启动作业执行（排队中的作业由前序作业结束后调度）
+        self.start_admitted_job(job_id, admission, created_by)
+            .await?;
+
+        Ok(job)
+    }
+
+    /// 创建软件包清单采集作业（执行内置采集脚本，成功后写入主机软件包清单）
+    #[instrument(skip(self, request))]
+    pub async fn create_inventory_job(
+        &self,
+        request: CreateInventoryJobRequest,
+        created_by: Uuid,
+    ) -> Result<Job> {
+        // 检查幂等键
+        if let Some(key) = &request.idempotency_key {
+            if let Some(existing) = self.get_by_idempotency_key(key).await? {
+                info!(
+                    job_id = %existing.id,
+                    "Found existing job with same idempotency key"
+                );
+                return Ok(existing);
+            }
+        }
+
+        self.validate_job_tags(&request.tags).await?;
+
+        // 验证目标主机
+        let target_hosts = self
+            .resolve_target_hosts(&request.target_hosts, &request.target_groups)
+            .await?;
+        if target_hosts.is_empty() {
+            return Err(AppError::validation("No valid target hosts found"));
+        }
+        // 合并目标环境策略：校验作业类型与禁止变更窗口，补全默认超时与并发
+        let policy = environment_policy::effective_policy(&self.db, &target_hosts).await?;
+        policy.check(&JobType::Inventory, chrono::Utc::now())?;
+
+        // 开始事务
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        // 创建作业记录
+        let job_id = Uuid::new_v4();
+        let name = request
+            .name
+            .clone()
+            .unwrap_or_else(|| "Package inventory".to_string());
+        let mut job = sqlx::query_as::<_, Job>(
+            r#"
+            INSERT INTO jobs (
+                id, job_type, name, description, status,
+                target_hosts, target_groups,
+                concurrent_limit, timeout_secs, retry_times, execute_user,
+                idempotency_key, total_tasks, created_by, tags
+            ) VALUES (
+                $1, $2, $3, $4, 'pending',
+                $5, $6,
+                $7, $8, 0, $9,
+                $10, $11, $12, $13
+            ) RETURNING *
+            "#,
+        )
+        .bind(job_id)
+        .bind(JobType::Inventory)
+        .bind(&name)
+        .bind(&request.description)
+        .bind(Json(target_hosts.iter().map(|h| h.id).collect::<Vec<_>>()))
+        .bind(Json(&request.target_groups))
+        .bind(request.concurrent_limit.or(policy.default_concurrent_limit))
+        .bind(request.timeout_secs.or(policy.default_timeout_secs))
+        .bind(&request.execute_user)
+        .bind(&request.idempotency_key)
+        .bind(target_hosts.len() as i32)
+        .bind(created_by)
+        .bind(Json(&request.tags))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to insert inventory job");
+            AppError::database("Failed to create job")
+        })?;
+
+        // 创建任务记录
+        for host in &target_hosts {
+            sqlx::query(
+                r#"
+                INSERT INTO tasks (
+                    id, job_id, host_id, status, max_retries
+                ) VALUES ($1, $2, $3, 'pending', 0)
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(job_id)
+            .bind(host.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!(error = %e, host_id = %host.id, "Failed to insert task");
+                AppError::database("Failed to create task")
+            })?;
+        }
+
+        // 提交事务
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+
+        // 记录审计
+        self.audit_service
+            .log_action_simple(
+                created_by,
+                AuditAction::JobCreate,
+                Some("job"),
+                Some(job_id),
+                Some("Inventory job created"),
+                None,
+            )
+            .await?;
+
+        info!(job_id = %job_id, "Inventory job created successfully");
+
+        // 审批检查：需要审批时，作业等待审批通过后再派发
+        if let Some(ref approval_svc) = self.approval_service {
+            let assessment = approval_svc
+                .assess_job_risk(&job, &target_hosts, None)
+                .await;
+            if assessment.requires_approval() {
+                self.hold_for_approval(approval_svc, &mut job, &assessment)
+                    .await?;
+                return Ok(job);
+            }
+        }
+
+        // 异步启动作业执行
+        self.dispatch_job(job_id).await?;
+
+        Ok(job)
+    }
+
+    /// 查询作业的详细追踪记录
+    #[instrument(skip(self))]
+    pub async fn get_job_trace(
+        &self,
+        job_id: Uuid,
+        query: &JobTraceQuery,
+    ) -> Result<Vec<JobTraceEntry>> {
+        job_trace::list(&self.db, job_id, query).await
+    }
+
+    /// 查询作业详情（热表中不存在时回查归档）
+    #[instrument(skip(self))]
+    pub async fn get_job(&self, job_id: Uuid) -> Result<Job> {
+        let mut job = match self.fetch_job(job_id).await? {
+            Some(job) => job,
+            None => JobArchiver::find_job(&self.db, job_id)
+                .await?
+                .ok_or_else(|| AppError::not_found("Job not found"))?,
+        };
+        Self::load_script(self.blob_store.as_deref(), &mut job).await?;
+        Ok(job)
+    }
+
+    /// 将流式传输的脚本暂存到本地（无待执行任务或非流式脚本时返回 None）
+    async fn stage_script(
+        blob_store: Option<&BlobStore>,
+        job: &Job,
+        has_tasks: bool,
+    ) -> Option<StagedFile> {
+        if !job.script_streamed || !has_tasks {
+            return None;
+        }
+        let (Some(store), Some(sha256)) = (blob_store, job.script_sha256.as_deref()) else {
+            error!(job_id = %job.id, "Streamed script requires a configured blob store");
+            return None;
+        };
+        match store.stage(sha256.trim()).await {
+            Ok(staged) => Some(staged),
+            Err(e) => {
+                error!(error = %e, job_id = %job.id, "Failed to stage script content");
+                None
+            }
+        }
+    }
+
+    /// 校验脚本作业引用的已上传内容，返回规范化的哈希（未引用时返回 None）
+    async fn resolve_uploaded_script(
+        &self,
+        request: &CreateScriptJobRequest,
+    ) -> Result<Option<String>> {
+        let Some(sha256) = &request.script_sha256 else {
+            return Ok(None);
+        };
+        if !request.script.is_empty() {
+            return Err(AppError::validation("Provide either script or script_sha256, not both"));
+        }
+        let sha256 = BlobStore::normalize_hash(sha256)
+            .ok_or_else(|| AppError::validation("Invalid script_sha256"))?;
+        let store = self
+            .blob_store
+            .as_deref()
+            .ok_or_else(|| AppError::validation("Blob store is not configured"))?;
+        if store.find(&sha256).await?.is_none() {
+            return Err(AppError::not_found("Uploaded script not found"));
+        }
+        Ok(Some(sha256))
+    }
+
+    /// 从 blob 存储加载按哈希保存的脚本内容（列表接口只返回 script_sha256）
+    async fn load_script(blob_store: Option<&BlobStore>, job: &mut Job) -> Result<()> {
+        // 流式传输的脚本可能是大文件或二进制内容，不整体读入内存
+        if job.script.is_none() && !job.script_streamed {
+            if let Some(sha256) = &job.script_sha256 {
+                let store = blob_store.ok_or_else(|| {
+                    error!(job_id = %job.id, "Script stored by hash but blob store is not configured");
+                    AppError::internal_error("Blob store is not configured")
+                })?;
+                job.script = Some(store.get_text(sha256.trim()).await?);
+            }
+        }
+        Ok(())
+    }
+
+    /// 查询热表中的作业
+    async fn fetch_job(&self, job_id: Uuid) -> Result<Option<Job>> {
+        sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
+            .bind(job_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job_id, "Failed to fetch job");
+                AppError::database("Failed to fetch job")
+            })
+    }
+
+    /// 查询作业列表
+    #[instrument(skip(self))]
+    pub async fn list_jobs(&self, filters: JobListFilters) -> Result<Vec<Job>> {
+        // 归档表与热表列结构一致，可直接合并查询
+        let source = if filters.include_archived {
+            "(SELECT * FROM jobs UNION ALL SELECT * FROM jobs_archive) jobs"
+        } else {
+            "jobs"
+        };
+        let mut query = format!("SELECT * FROM {} WHERE 1=1", source);
+        let mut count = 0;
+
+        if filters.job_type.is_some() {
+            count += 1;
+            query.push_str(&format!(" AND job_type = ${}", count));
+        }
+        if filters.status.is_some() {
+            count += 1;
+            query.push_str(&format!(" AND status = ${}", count));
+        }
+        if filters.created_by.is_some() {
+            count += 1;
+            query.push_str(&format!(" AND created_by = ${}", count));
+        }
+        if filters.search.is_some() {
+            count += 1;
+            query
+                .push_str(&format!(" AND (name ILIKE ${} OR description ILIKE ${})", count, count));
+        }
+        if filters.date_from.is_some() {
+            count += 1;
+            query.push_str(&format!(" AND created_at >= ${}", count));
+        }
+        if filters.date_to.is_some() {
+            count += 1;
+            query.push_str(&format!(" AND created_at <= ${}", count));
+        }
+
+        query.push_str(" ORDER BY created_at DESC LIMIT 100");
+
+        // Prepare search pattern outside of bind to avoid lifetime issues
+        let search_pattern = filters.search.as_ref().map(|s| format!("%{}%", s));
+
+        let mut q = sqlx::query_as::<_, Job>(&query);
+
+        if let Some(job_type) = filters.job_type {
+            q = q.bind(job_type);
+        }
+        if let Some(status) = filters.status {
+            q = q.bind(status);
+        }
+        if let Some(created_by) = filters.created_by {
+            q = q.bind(created_by);
+        }
+        if let Some(ref pattern) = search_pattern {
+            q = q.bind(pattern).bind(pattern);
+        }
+        if let Some(date_from) = filters.date_from {
+            q = q.bind(date_from);
+        }
+        if let Some(date_to) = filters.date_to {
+            q = q.bind(date_to);
+        }
+
+        q.fetch_all(&self.db).await.map_err(|e| {
+            error!(error = %e, "Failed to fetch jobs");
+            AppError::database("Failed to fetch jobs")
+        })
+    }
+
+    /// 查询作业列表（带作用域过滤）
+    /// 根据 user_id、allowed_groups、allowed_environments 过滤作业
+    #[instrument(skip(self, allowed_groups, allowed_environments))]
+    pub async fn list_jobs_with_scope(
+        &self,
+        filters: JobListFilters,
         user_id: Uuid,
         has_global_access: bool,
         allowed_groups: Vec<String>,
@@ -511,22 +1269,21 @@ impl JobService {
         }
 
         // 查询目标主机的 group 和 environment
-        let hosts =
-            sqlx::query_as::<_, Host>("SELECT group_id, environment FROM hosts WHERE id = ANY($1)")
-                .bind(&target_host_ids)
-                .fetch_all(&self.db)
-                .await
-                .map_err(|e| {
-                    error!(error = %e, "Failed to fetch hosts for scope check");
-                    AppError::database("Failed to fetch hosts")
-                })?;
+        let hosts = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT group_id, environment FROM assets_hosts WHERE id = ANY($1)",
+        )
+        .bind(&target_host_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch hosts for scope check");
+            AppError::database("Failed to fetch hosts")
+        })?;
 
         // 检查是否有至少一个主机在用户允许的作用域内
-        for host in hosts {
-            let group_match =
-                has_global_groups || allowed_groups.contains(&host.group_id.to_string());
-            let env_match =
-                has_global_environments || allowed_environments.contains(&host.environment);
+        for (group_id, environment) in hosts {
+            let group_match = has_global_groups || allowed_groups.contains(&group_id.to_string());
+            let env_match = has_global_environments || allowed_environments.contains(&environment);
 
             if group_match && env_match {
                 return Ok(true);
@@ -540,36 +1297,19 @@ impl JobService {
     #[instrument(skip(self))]
     /// 获取作业的任务列表
     pub async fn get_job_tasks(&self, job_id: Uuid) -> Result<Vec<TaskResponse>> {
-        // 验证作业存在
-        self.get_job(job_id).await?;
-
-        let tasks =
-            sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE job_id = $1 ORDER BY created_at")
-                .bind(job_id)
-                .fetch_all(&self.db)
-                .await
-                .map_err(|e| {
-                    error!(error = %e, job_id = %job_id, "Failed to fetch tasks");
-                    AppError::database("Failed to fetch tasks")
-                })?;
+        let tasks = self.load_job_tasks(job_id, true).await?;
 
         // 为每个任务获取主机信息
         let mut responses = Vec::new();
         for task in tasks {
-            let host = sqlx::query_as::<_, Host>("SELECT * FROM hosts WHERE id = $1")
-                .bind(task.host_id)
-                .fetch_one(&self.db)
-                .await
-                .map_err(|e| {
-                    error!(error = %e, host_id = %task.host_id, "Failed to fetch host");
-                    AppError::database("Failed to fetch host")
-                })?;
+            let (host_identifier, host_address, host_display_name) =
+                self.task_host_labels(task.host_id).await?;
 
             responses.push(TaskResponse {
                 task,
-                host_identifier: host.identifier,
-                host_address: host.address,
-                host_display_name: host.display_name,
+                host_identifier,
+                host_address,
+                host_display_name,
             });
         }
 
@@ -583,30 +1323,13 @@ impl JobService {
         &self,
         job_id: Uuid,
     ) -> Result<Vec<crate::models::job::TaskSummary>> {
-        // 验证作业存在
-        self.get_job(job_id).await?;
-
-        let tasks =
-            sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE job_id = $1 ORDER BY created_at")
-                .bind(job_id)
-                .fetch_all(&self.db)
-                .await
-                .map_err(|e| {
-                    error!(error = %e, job_id = %job_id, "Failed to fetch tasks");
-                    AppError::database("Failed to fetch tasks")
-                })?;
+        let tasks = self.load_job_tasks(job_id, false).await?;
 
         // 为每个任务获取主机信息并构建摘要
         let mut summaries = Vec::new();
         for task in tasks {
-            let host = sqlx::query_as::<_, Host>("SELECT * FROM hosts WHERE id = $1")
-                .bind(task.host_id)
-                .fetch_one(&self.db)
-                .await
-                .map_err(|e| {
-                    error!(error = %e, host_id = %task.host_id, "Failed to fetch host");
-                    AppError::database("Failed to fetch host")
-                })?;
+            let (host_identifier, host_address, host_display_name) =
+                self.task_host_labels(task.host_id).await?;
 
             summaries.push(crate::models::job::TaskSummary {
                 id: task.id,
@@ -620,15 +1343,61 @@ impl JobService {
                 duration_secs: task.duration_secs,
                 output_summary: task.output_summary,
                 output_detail_truncated: true, // 指示完整输出被截断
-                host_identifier: host.identifier,
-                host_address: host.address,
-                host_display_name: host.display_name,
+                host_identifier,
+                host_address,
+                host_display_name,
+                journal: task.journal.0,
             });
         }
 
         Ok(summaries)
     }
 
+    /// 加载作业的任务，作业已归档时回查归档表
+    ///
+    /// with_outputs 为 true 时从对象存储回填归档任务的完整输出
+    async fn load_job_tasks(&self, job_id: Uuid, with_outputs: bool) -> Result<Vec<Task>> {
+        if self.fetch_job(job_id).await?.is_none() {
+            if JobArchiver::find_job(&self.db, job_id).await?.is_none() {
+                return Err(AppError::not_found("Job not found"));
+            }
+            let storage = if with_outputs {
+                self.storage.as_deref()
+            } else {
+                None
+            };
+            return JobArchiver::load_tasks(&self.db, storage, job_id).await;
+        }
+
+        sqlx::query_as::<_, Task>("SELECT * FROM tasks WHERE job_id = $1 ORDER BY created_at")
+            .bind(job_id)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job_id, "Failed to fetch tasks");
+                AppError::database("Failed to fetch tasks")
+            })
+    }
+
+    /// 查询任务主机的标识、地址与显示名
+    ///
+    /// 归档任务的主机可能已被删除，此时以主机 ID 作为标识
+    async fn task_host_labels(&self, host_id: Uuid) -> Result<(String, String, Option<String>)> {
+        let host = crate::repository::AssetRepository::new(self.db.clone())
+            .get_host_cached(host_id)
+            .await
+            .map_err(|e| {
+                error!(error = %e, host_id = %host_id, "Failed to fetch host");
+                AppError::database("Failed to fetch host")
+            })?;
+        Ok(match host {
+            Some(host) => {
+                (host.identifier.clone(), host.address.clone(), host.display_name.clone())
+            }
+            None => (host_id.to_string(), String::new(), None),
+        })
+    }
+
     /// 取消作业
     #[instrument(skip(self))]
     pub async fn cancel_job(
@@ -644,9 +1413,22 @@ impl JobService {
             AppError::database("Failed to begin transaction")
         })?;
 
+        // 锁定作业并读取取消前的状态
+        let previous_status = sqlx::query_scalar::<_, JobStatus>(
+            "SELECT status FROM jobs WHERE id = $1 AND status IN ('pending', 'running', 'awaiting_approval') FOR UPDATE",
+        )
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch job status");
+            AppError::database("Failed to cancel job")
+        })?
+        .ok_or_else(|| AppError::validation("Job cannot be cancelled"))?;
+
         // 更新作业状态
-        let updated = sqlx::query(
-            "UPDATE jobs SET status = 'cancelled', completed_at = NOW() WHERE id = $1 AND status IN ('pending', 'running')"
+        sqlx::query(
+            "UPDATE jobs SET status = 'cancelled', completed_at = NOW(), dispatch_paused_at = NULL WHERE id = $1",
         )
         .bind(job_id)
         .execute(&mut *tx)
@@ -656,26 +1438,59 @@ impl JobService {
             AppError::database("Failed to cancel job")
         })?;
 
-        if updated.rows_affected() == 0 {
-            return Err(AppError::validation("Job cannot be cancelled"));
-        }
-
-        // 取消所有pending/running的任务
-        sqlx::query(
-            "UPDATE tasks SET status = 'cancelled', completed_at = NOW() WHERE job_id = $1 AND status IN ('pending', 'running')"
+        // 取消所有pending/running/等待维护的任务
+        let cancelled_tasks = sqlx::query_as::<_, (Uuid, TaskStatus)>(
+            r#"
+            UPDATE tasks t
+            SET status = 'cancelled', completed_at = NOW()
+            FROM tasks old
+            WHERE old.id = t.id AND t.job_id = $1
+              AND t.status IN ('pending', 'running', 'waiting_maintenance')
+            RETURNING t.id, old.status
+            "#,
         )
         .bind(job_id)
-        .execute(&mut *tx)
+        .fetch_all(&mut *tx)
         .await
         .map_err(|e| {
             error!(error = %e, "Failed to cancel tasks");
             AppError::database("Failed to cancel tasks")
         })?;
+        Self::refresh_job_counts(&mut tx, job_id).await?;
+
+        // 状态变更事件与取消在同一事务中写入发件箱
+        Self::enqueue_with_watchers(
+            &mut tx,
+            &RealtimeEvent::JobStatusChanged {
+                job_id,
+                old_status: previous_status.to_string(),
+                new_status: JobStatus::Cancelled.to_string(),
+            },
+        )
+        .await?;
+        for (task_id, old_status) in &cancelled_tasks {
+            outbox::enqueue(
+                &mut *tx,
+                &RealtimeEvent::TaskStatusChanged {
+                    task_id: *task_id,
+                    job_id,
+                    old_status: old_status.to_string(),
+                    new_status: TaskStatus::Cancelled.to_string(),
+                },
+            )
+            .await?;
+        }
 
         tx.commit().await.map_err(|e| {
             error!(error = %e, "Failed to commit transaction");
             AppError::database("Failed to commit transaction")
         })?;
+        self.event_bus.notify_outbox();
+
+        // 中断执行中的任务
+        if let Some(cancel_tx) = self.cancellations.get(&job_id) {
+            let _ = cancel_tx.send(true);
+        }
 
         // 记录审计
         self.audit_service
@@ -693,6 +1508,58 @@ impl JobService {
         Ok(())
     }
 
+    /// 处理因主机不可达暂停派发的作业：继续派发剩余任务，或中止作业（取消剩余任务）
+    #[instrument(skip(self))]
+    pub async fn resolve_dispatch_pause(
+        &self,
+        job_id: Uuid,
+        action: DispatchPauseAction,
+        requested_by: Uuid,
+        reason: Option<String>,
+    ) -> Result<()> {
+        match action {
+            DispatchPauseAction::Continue => {
+                // 执行作业的实例轮询到暂停已清除后恢复派发
+                let resumed = Self::update_with_events(
+                    &self.db,
+                    &self.event_bus,
+                    sqlx::query(
+                        "UPDATE jobs SET dispatch_paused_at = NULL WHERE id = $1 AND status = 'running' AND dispatch_paused_at IS NOT NULL",
+                    )
+                    .bind(job_id),
+                    vec![RealtimeEvent::JobDispatchResumed {
+                        job_id,
+                        resumed_by: requested_by,
+                    }],
+                    "Failed to resume job dispatch",
+                )
+                .await?;
+                if resumed == 0 {
+                    return Err(AppError::validation("Job dispatch is not paused"));
+                }
+                info!(job_id = %job_id, "Job dispatch resumed");
+                Ok(())
+            }
+            DispatchPauseAction::Abort => {
+                let paused = sqlx::query_scalar::<_, bool>(
+                    "SELECT dispatch_paused_at IS NOT NULL FROM jobs WHERE id = $1 AND status = 'running'",
+                )
+                .bind(job_id)
+                .fetch_optional(&self.db)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to fetch job");
+                    AppError::database("Failed to fetch job")
+                })?
+                .unwrap_or(false);
+                if !paused {
+                    return Err(AppError::validation("Job dispatch is not paused"));
+                }
+                self.cancel_job(job_id, requested_by, reason).await
+            }
+        }
+    }
+
     /// 重试作业
     #[instrument(skip(self))]
     pub async fn retry_job(
@@ -703,7 +1570,13 @@ impl JobService {
     ) -> Result<Job> {
         info!(job_id = %job_id, "Retrying job");
 
-        let job = self.get_job(job_id).await?;
+        let job = match self.fetch_job(job_id).await? {
+            Some(job) => job,
+            None => {
+                self.get_job(job_id).await?;
+                return Err(AppError::validation("Archived jobs cannot be retried"));
+            }
+        };
 
         // 只允许失败、部分成功或已取消的作业重试
         if !matches!(
@@ -761,73 +1634,398 @@ impl JobService {
             return Err(AppError::validation("No tasks to retry"));
         }
 
-        // 重置任务状态
-        for task in &tasks_to_retry {
-            sqlx::query(
-                "UPDATE tasks SET status = 'pending', failure_reason = NULL, failure_message = NULL, started_at = NULL, completed_at = NULL WHERE id = $1"
+        let task_ids: Vec<Uuid> = tasks_to_retry.iter().map(|t| t.id).collect();
+        Self::reset_tasks_for_rerun(&mut tx, &job, &task_ids).await?;
+        self.enqueue_dispatch(&mut tx, job_id).await?;
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+
+        // 记录审计
+        self.audit_service
+            .log_action_simple(
+                requested_by,
+                AuditAction::JobRetry,
+                Some("job"),
+                Some(job_id),
+                Some("Command failed"),
+                None,
             )
-            .bind(task.id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| {
-                error!(error = %e, task_id = %task.id, "Failed to reset task");
-                AppError::database("Failed to reset task")
-            })?;
+            .await?;
+
+        info!(job_id = %job_id, tasks_count = tasks_to_retry.len(), "Job retry scheduled");
+
+        // 异步启动作业执行
+        self.start_dispatch(job_id);
+
+        self.get_job(job_id).await
+    }
+
+    /// 将已结束作业的指定任务重置为待执行，并将作业重置为 pending
+    ///
+    /// 同一互斥键已有作业进行中时不允许重新执行；调用方在同一事务中入队并在提交后启动执行
+    async fn reset_tasks_for_rerun(
+        tx: &mut sqlx::PgConnection,
+        job: &Job,
+        task_ids: &[Uuid],
+    ) -> Result<()> {
+        if let Some(key) = &job.singleton_key {
+            Self::lock_singleton_key(&mut *tx, key).await?;
+            if let Some(active) = Self::active_singleton_jobs(&mut *tx, key).await?.first() {
+                return Err(AppError::validation(&format!(
+                    "Job {} with singleton key '{}' is already pending or running",
+                    active, key
+                )));
+            }
         }
 
+        // 重置任务状态
+        sqlx::query(
+            "UPDATE tasks SET status = 'pending', failure_reason = NULL, failure_message = NULL, diagnostics = NULL, file_result = NULL, file_manifest_report = NULL, output_drift = NULL, journal = DEFAULT, started_at = NULL, completed_at = NULL WHERE job_id = $1 AND id = ANY($2)"
+        )
+        .bind(job.id)
+        .bind(task_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job.id, "Failed to reset tasks");
+            AppError::database("Failed to reset task")
+        })?;
+
         // 重置作业状态
         sqlx::query(
-            "UPDATE jobs SET status = 'pending', started_at = NULL, completed_at = NULL WHERE id = $1"
+            "UPDATE jobs SET status = 'pending', singleton_waiting = FALSE, started_at = NULL, completed_at = NULL, budget_exceeded = NULL, dispatch_paused_at = NULL, network_backoff = NULL WHERE id = $1"
         )
-        .bind(job_id)
+        .bind(job.id)
         .execute(&mut *tx)
         .await
         .map_err(|e| {
             error!(error = %e, "Failed to reset job");
             AppError::database("Failed to reset job")
         })?;
+        Self::refresh_job_counts(&mut *tx, job.id).await?;
+        Ok(())
+    }
 
-        tx.commit().await.map_err(|e| {
-            error!(error = %e, "Failed to commit transaction");
-            AppError::database("Failed to commit transaction")
+    // ==================== 批量任务操作 ====================
+
+    /// 解析批量任务操作选择的任务
+    ///
+    /// 返回作业中被选中的任务，以及不属于作业的任务或主机对应的失败项
+    async fn select_job_tasks(
+        &self,
+        job_id: Uuid,
+        selection: &TaskSelectionRequest,
+    ) -> Result<(Vec<Task>, Vec<TaskBulkItemResult>)> {
+        let mut task_ids = selection.task_ids.clone();
+        let mut host_ids = selection.host_ids.clone();
+        task_ids.sort();
+        task_ids.dedup();
+        host_ids.sort();
+        host_ids.dedup();
+        if task_ids.is_empty() && host_ids.is_empty() {
+            return Err(AppError::validation("No tasks selected"));
+        }
+        if task_ids.len() + host_ids.len() > MAX_BULK_TASKS {
+            return Err(AppError::validation(&format!(
+                "At most {} tasks can be processed at once",
+                MAX_BULK_TASKS
+            )));
+        }
+
+        let tasks = sqlx::query_as::<_, Task>(
+            "SELECT * FROM tasks WHERE job_id = $1 AND (id = ANY($2) OR host_id = ANY($3)) ORDER BY created_at",
+        )
+        .bind(job_id)
+        .bind(&task_ids)
+        .bind(&host_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to fetch selected tasks");
+            AppError::database("Failed to fetch tasks")
         })?;
 
-        // 记录审计
+        let missing_tasks = task_ids
+            .into_iter()
+            .filter(|id| !tasks.iter().any(|t| t.id == *id))
+            .map(|id| TaskBulkItemResult {
+                task_id: Some(id),
+                host_id: None,
+                success: false,
+                previous_status: None,
+                new_status: None,
+                error: Some("Task not found in job".to_string()),
+            });
+        let missing_hosts = host_ids
+            .into_iter()
+            .filter(|id| !tasks.iter().any(|t| t.host_id == *id))
+            .map(|id| TaskBulkItemResult {
+                task_id: None,
+                host_id: Some(id),
+                success: false,
+                previous_status: None,
+                new_status: None,
+                error: Some("Host is not a target of this job".to_string()),
+            });
+        let missing = missing_tasks.chain(missing_hosts).collect();
+        Ok((tasks, missing))
+    }
+
+    /// 汇总批量任务操作结果，附带重新计算后的作业统计并记录审计
+    async fn finish_bulk_task_action(
+        &self,
+        job_id: Uuid,
+        action: TaskBulkAction,
+        results: Vec<TaskBulkItemResult>,
+        requested_by: Uuid,
+    ) -> Result<TaskBulkResponse> {
+        let succeeded = results.iter().filter(|r| r.success).count();
+        let failed = results.len() - succeeded;
+
+        let audit_action = match action {
+            TaskBulkAction::Cancel => AuditAction::JobTaskCancel,
+            TaskBulkAction::Skip => AuditAction::JobTaskSkip,
+            TaskBulkAction::Rerun => AuditAction::JobTaskRerun,
+        };
         self.audit_service
             .log_action_simple(
                 requested_by,
-                AuditAction::JobRetry,
+                audit_action,
                 Some("job"),
                 Some(job_id),
-                Some("Command failed"),
+                Some(&format!(
+                    "Bulk task {}: {} succeeded, {} failed",
+                    action.as_str(),
+                    succeeded,
+                    failed
+                )),
                 None,
             )
             .await?;
 
-        info!(job_id = %job_id, tasks_count = tasks_to_retry.len(), "Job retry scheduled");
+        info!(job_id = %job_id, action = action.as_str(), succeeded, failed, "Bulk task action");
+        Ok(TaskBulkResponse {
+            job_id,
+            action,
+            succeeded,
+            failed,
+            results,
+            statistics: self.get_job_statistics(job_id).await?,
+        })
+    }
 
-        // 异步启动作业执行
-        let db_clone = self.db.clone();
-        let concurrency_clone = self.concurrency_controller.clone();
-        let audit_clone = self.audit_service.clone();
-        let ssh_config_clone = self.ssh_config.clone();
-        let event_bus_clone = self.event_bus.clone();
-        tokio::spawn(async move {
-            if let Err(e) = Self::execute_job(
+    /// 将选中的任务从 `from` 状态更新为 `to`，同一事务中刷新作业计数并写入状态变更事件
+    ///
+    /// 返回被更新的任务及其原状态，以及是否仍有未结束的任务
+    async fn transition_selected_tasks(
+        &self,
+        job_id: Uuid,
+        tasks: &[Task],
+        from: &[&str],
+        to: TaskStatus,
+    ) -> Result<(Vec<(Uuid, TaskStatus)>, bool)> {
+        let task_ids: Vec<Uuid> = tasks.iter().map(|t| t.id).collect();
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+        Self::lock_job(&mut tx, job_id).await?;
+
+        let updated = sqlx::query_as::<_, (Uuid, TaskStatus)>(
+            r#"
+            UPDATE tasks t
+            SET status = $4, completed_at = NOW()
+            FROM tasks old
+            WHERE old.id = t.id AND t.job_id = $1 AND t.id = ANY($2)
+              AND t.status::text = ANY($3)
+            RETURNING t.id, old.status
+            "#,
+        )
+        .bind(job_id)
+        .bind(&task_ids)
+        .bind(from)
+        .bind(&to)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to update selected tasks");
+            AppError::database("Failed to update tasks")
+        })?;
+        let counts = Self::refresh_job_counts(&mut tx, job_id).await?;
+
+        for (task_id, old_status) in &updated {
+            outbox::enqueue(
+                &mut *tx,
+                &RealtimeEvent::TaskStatusChanged {
+                    task_id: *task_id,
+                    job_id,
+                    old_status: old_status.to_string(),
+                    new_status: to.to_string(),
+                },
+            )
+            .await?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        self.event_bus.notify_outbox();
+        Ok((updated, counts.has_unfinished))
+    }
+
+    /// 按状态更新结果生成逐项结果
+    fn transition_results(
+        tasks: &[Task],
+        updated: &[(Uuid, TaskStatus)],
+        to: TaskStatus,
+        rejected: impl Fn(&Task) -> String,
+    ) -> Vec<TaskBulkItemResult> {
+        tasks
+            .iter()
+            .map(|task| match updated.iter().find(|(id, _)| *id == task.id) {
+                Some((_, old_status)) => TaskBulkItemResult {
+                    task_id: Some(task.id),
+                    host_id: Some(task.host_id),
+                    success: true,
+                    previous_status: Some(old_status.clone()),
+                    new_status: Some(to.clone()),
+                    error: None,
+                },
+                None => TaskBulkItemResult {
+                    task_id: Some(task.id),
+                    host_id: Some(task.host_id),
+                    success: false,
+                    previous_status: Some(task.status.clone()),
+                    new_status: None,
+                    error: Some(rejected(task)),
+                },
+            })
+            .collect()
+    }
+
+    /// 取消选中的执行中任务，其余任务继续执行
+    #[instrument(skip(self, selection))]
+    pub async fn cancel_tasks(
+        &self,
+        job_id: Uuid,
+        selection: TaskSelectionRequest,
+        requested_by: Uuid,
+    ) -> Result<TaskBulkResponse> {
+        let (tasks, mut results) = self.select_job_tasks(job_id, &selection).await?;
+        let (updated, _) = self
+            .transition_selected_tasks(job_id, &tasks, &["running"], TaskStatus::Cancelled)
+            .await?;
+
+        // 中断执行中的任务
+        for (task_id, _) in &updated {
+            if let Some(cancel_tx) = self.task_cancellations.get(task_id) {
+                let _ = cancel_tx.send(true);
+            }
+        }
+
+        results.extend(Self::transition_results(&tasks, &updated, TaskStatus::Cancelled, |task| {
+            format!("Only running tasks can be cancelled (task is {})", task.status)
+        }));
+        self.finish_bulk_task_action(job_id, TaskBulkAction::Cancel, results, requested_by)
+            .await
+    }
+
+    /// 跳过选中的待执行（含等待维护）任务，被跳过的任务不会执行
+    ///
+    /// 运行中的作业跳过后已没有未结束的任务、且本实例没有在执行该作业时，调度一次执行以完成收尾
+    #[instrument(skip(self, selection))]
+    pub async fn skip_tasks(
+        &self,
+        job_id: Uuid,
+        selection: TaskSelectionRequest,
+        requested_by: Uuid,
+    ) -> Result<TaskBulkResponse> {
+        let (tasks, mut results) = self.select_job_tasks(job_id, &selection).await?;
+        let (updated, has_unfinished) = self
+            .transition_selected_tasks(
                 job_id,
-                db_clone,
-                concurrency_clone,
-                audit_clone,
-                ssh_config_clone,
-                event_bus_clone,
+                &tasks,
+                &["pending", "waiting_maintenance"],
+                TaskStatus::Skipped,
             )
+            .await?;
+
+        if !updated.is_empty() && !has_unfinished && !self.cancellations.contains_key(&job_id) {
+            let status =
+                sqlx::query_scalar::<_, JobStatus>("SELECT status FROM jobs WHERE id = $1")
+                    .bind(job_id)
+                    .fetch_one(&self.db)
+                    .await
+                    .map_err(|e| {
+                        error!(error = %e, "Failed to fetch job status");
+                        AppError::database("Failed to fetch job")
+                    })?;
+            if status == JobStatus::Running {
+                self.dispatch_job(job_id).await?;
+            }
+        }
+
+        results.extend(Self::transition_results(&tasks, &updated, TaskStatus::Skipped, |task| {
+            format!("Only pending tasks can be skipped (task is {})", task.status)
+        }));
+        self.finish_bulk_task_action(job_id, TaskBulkAction::Skip, results, requested_by)
             .await
-            {
-                error!(error = %e, job_id = %job_id, "Failed to execute job");
+    }
+
+    /// 仅重新执行选中的任务（作业须已结束），其余任务保持原结果
+    #[instrument(skip(self, selection))]
+    pub async fn rerun_tasks(
+        &self,
+        job_id: Uuid,
+        selection: TaskSelectionRequest,
+        requested_by: Uuid,
+    ) -> Result<TaskBulkResponse> {
+        let job = match self.fetch_job(job_id).await? {
+            Some(job) => job,
+            None => {
+                self.get_job(job_id).await?;
+                return Err(AppError::validation("Archived jobs cannot be re-run"));
             }
-        });
+        };
+        if matches!(
+            job.status,
+            JobStatus::Pending | JobStatus::Running | JobStatus::AwaitingApproval
+        ) {
+            return Err(AppError::validation(
+                "Tasks can only be re-run after the job has finished",
+            ));
+        }
 
-        self.get_job(job_id).await
+        let (tasks, mut results) = self.select_job_tasks(job_id, &selection).await?;
+        if !tasks.is_empty() {
+            let task_ids: Vec<Uuid> = tasks.iter().map(|t| t.id).collect();
+            let mut tx = self.db.begin().await.map_err(|e| {
+                error!(error = %e, "Failed to begin transaction");
+                AppError::database("Failed to begin transaction")
+            })?;
+            Self::reset_tasks_for_rerun(&mut tx, &job, &task_ids).await?;
+            self.enqueue_dispatch(&mut tx, job_id).await?;
+            tx.commit().await.map_err(|e| {
+                error!(error = %e, "Failed to commit transaction");
+                AppError::database("Failed to commit transaction")
+            })?;
+            self.start_dispatch(job_id);
+        }
+
+        results.extend(tasks.iter().map(|task| TaskBulkItemResult {
+            task_id: Some(task.id),
+            host_id: Some(task.host_id),
+            success: true,
+            previous_status: Some(task.status.clone()),
+            new_status: Some(TaskStatus::Pending),
+            error: None,
+        }));
+        self.finish_bulk_task_action(job_id, TaskBulkAction::Rerun, results, requested_by)
+            .await
     }
 
     /// 获取作业统计（包含失败原因分类）
@@ -857,8 +2055,21 @@ impl JobService {
             AppError::database("Failed to count tasks")
         })? as i32;
 
-        let success_rate = if job.total_tasks > 0 {
-            job.succeeded_tasks as f64 / job.total_tasks as f64
+        let waiting_maintenance_tasks = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM tasks WHERE job_id = $1 AND status = 'waiting_maintenance'",
+        )
+        .bind(job_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to count waiting maintenance tasks");
+            AppError::database("Failed to count tasks")
+        })? as i32;
+
+        // 告警任务计入成功，跳过的任务不计入
+        let executed_tasks = job.total_tasks - job.skipped_tasks;
+        let success_rate = if executed_tasks > 0 {
+            (job.succeeded_tasks + job.warning_tasks) as f64 / executed_tasks as f64
         } else {
             0.0
         };
@@ -882,11 +2093,14 @@ impl JobService {
             job_id,
             total_tasks: job.total_tasks,
             succeeded_tasks: job.succeeded_tasks,
+            warning_tasks: job.warning_tasks,
             failed_tasks: job.failed_tasks,
             timeout_tasks: job.timeout_tasks,
             cancelled_tasks: job.cancelled_tasks,
+            skipped_tasks: job.skipped_tasks,
             pending_tasks,
             running_tasks,
+            waiting_maintenance_tasks,
             success_rate,
             avg_duration_secs: avg_duration,
             failure_reason_stats: Some(failure_reason_stats),
@@ -923,6 +2137,10 @@ impl JobService {
                 Some(FailureReason::HandshakeTimeout) => stats.handshake_timeout = count,
                 Some(FailureReason::CommandTimeout) => stats.command_timeout = count,
                 Some(FailureReason::CommandFailed) => stats.command_failed = count,
+                Some(FailureReason::HostKeyMismatch) => stats.host_key_mismatch = count,
+                Some(FailureReason::BudgetExceeded) => stats.budget_exceeded = count,
+                Some(FailureReason::OutputLimitExceeded) => stats.output_limit_exceeded = count,
+                Some(FailureReason::DispatchLeaseExpired) => stats.dispatch_lease_expired = count,
                 Some(FailureReason::Unknown) | None => stats.unknown += count,
             }
         }
@@ -932,36 +2150,430 @@ impl JobService {
 
     // ==================== 私有方法 ====================
 
-    /// 通过幂等键查找作业
-    async fn get_by_idempotency_key(&self, key: &str) -> Result<Option<Job>> {
-        sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE idempotency_key = $1")
+    /// 锁定互斥键（事务级咨询锁），串行化同键作业的创建与排队调度
+    async fn lock_singleton_key(conn: &mut sqlx::PgConnection, key: &str) -> Result<()> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
             .bind(key)
-            .fetch_optional(&self.db)
+            .execute(&mut *conn)
             .await
             .map_err(|e| {
-                error!(error = %e, "Failed to fetch job by idempotency key");
-                AppError::database("Failed to fetch job")
-            })
+                error!(error = %e, singleton_key = key, "Failed to lock singleton key");
+                AppError::database("Failed to lock singleton key")
+            })?;
+        Ok(())
     }
 
-    /// 解析目标主机（直接指定的 + 分组中的）
-    async fn resolve_target_hosts(
-        &self,
-        host_ids: &[Uuid],
-        group_ids: &[Uuid],
-    ) -> Result<Vec<Host>> {
-        let mut hosts = Vec::new();
+    /// 查询互斥键下 pending/running 的作业（含排队中的），按创建时间排序
+    async fn active_singleton_jobs(conn: &mut sqlx::PgConnection, key: &str) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM jobs WHERE singleton_key = $1 AND status IN ('pending', 'running') ORDER BY created_at",
+        )
+        .bind(key)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, singleton_key = key, "Failed to fetch active singleton jobs");
+            AppError::database("Failed to fetch jobs")
+        })
+    }
 
-        // 获取直接指定的主机
-        if !host_ids.is_empty() {
-            let direct_hosts = sqlx::query_as::<_, Host>(
-                "SELECT * FROM hosts WHERE id = ANY($1) AND status = 'active'",
-            )
-            .bind(host_ids)
-            .fetch_all(&self.db)
-            .await
-            .map_err(|e| {
-                error!(error = %e, "Failed to fetch hosts by ids");
+    /// 在创建作业的事务中按互斥键策略决定新作业的调度方式
+    ///
+    /// 锁持有到事务结束，并发创建同键作业时只有一个能看到"无进行中作业"
+    async fn admit_singleton(
+        conn: &mut sqlx::PgConnection,
+        key: Option<&str>,
+        policy: SingletonPolicy,
+    ) -> Result<SingletonAdmission> {
+        let Some(key) = key else {
+            return Ok(SingletonAdmission::Run);
+        };
+        Self::lock_singleton_key(conn, key).await?;
+
+        let active = Self::active_singleton_jobs(conn, key).await?;
+        if active.is_empty() {
+            return Ok(SingletonAdmission::Run);
+        }
+
+        match policy {
+            SingletonPolicy::Reject => Err(AppError::validation(&format!(
+                "Job {} with singleton key '{}' is already pending or running",
+                active[0], key
+            ))),
+            SingletonPolicy::Queue => Ok(SingletonAdmission::Queue),
+            SingletonPolicy::Replace => Ok(SingletonAdmission::Replace(active)),
+        }
+    }
+
+    /// 按互斥键准入结果调度新建的作业
+    async fn start_admitted_job(
+        &self,
+        job_id: Uuid,
+        admission: SingletonAdmission,
+        created_by: Uuid,
+    ) -> Result<()> {
+        match admission {
+            SingletonAdmission::Run => self.dispatch_job(job_id).await?,
+            SingletonAdmission::Queue => {
+                info!(job_id = %job_id, "Job queued behind active job with the same singleton key");
+            }
+            SingletonAdmission::Replace(replaced) => {
+                for replaced_id in replaced {
+                    let reason = format!("Replaced by job {}", job_id);
+                    // 被替换的作业可能已自行结束
+                    if let Err(e) = self.cancel_job(replaced_id, created_by, Some(reason)).await {
+                        warn!(error = %e, job_id = %replaced_id, "Failed to cancel replaced job");
+                    }
+                }
+                info!(job_id = %job_id, "Replaced active jobs with the same singleton key");
+                self.dispatch_job(job_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 需要审批的作业转为 awaiting_approval 并创建关联的审批请求
+    ///
+    /// 状态变更与审批请求在同一事务中写入；创建失败时取消作业，避免作业无限期等待
+    async fn hold_for_approval(
+        &self,
+        approval_svc: &ApprovalService,
+        job: &mut Job,
+        assessment: &JobRiskAssessment,
+    ) -> Result<()> {
+        match self.await_approval(approval_svc, job, assessment).await {
+            Ok(approval) => {
+                job.status = JobStatus::AwaitingApproval;
+                info!(job_id = %job.id, approval_id = %approval.id, "Job awaiting approval");
+                approval_svc.approval_request_created(&approval).await
+            }
+            Err(e) => {
+                let reason = Some("Failed to create approval request".to_string());
+                if let Err(cancel_err) = self.cancel_job(job.id, job.created_by, reason).await {
+                    warn!(
+                        job_id = %job.id,
+                        error = %cancel_err,
+                        "Failed to cancel job without approval request"
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// 在一个事务中将作业转为 awaiting_approval 并创建审批请求
+    async fn await_approval(
+        &self,
+        approval_svc: &ApprovalService,
+        job: &Job,
+        assessment: &JobRiskAssessment,
+    ) -> Result<ApprovalRequest> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+        sqlx::query("UPDATE jobs SET status = 'awaiting_approval' WHERE id = $1")
+            .bind(job.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to update job status");
+                AppError::database("Failed to update job status")
+            })?;
+        Self::enqueue_with_watchers(
+            &mut tx,
+            &RealtimeEvent::JobStatusChanged {
+                job_id: job.id,
+                old_status: job.status.to_string(),
+                new_status: JobStatus::AwaitingApproval.to_string(),
+            },
+        )
+        .await?;
+        let approval = approval_svc
+            .request_job_approval(&mut tx, job, assessment)
+            .await?;
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        Ok(approval)
+    }
+
+    /// 审批请求结束后处理关联的等待审批作业：批准则派发，否则取消
+    ///
+    /// 返回是否处理了作业；审批仍在进行或作业已不在等待审批时不做处理
+    #[instrument(skip(self))]
+    pub async fn resolve_approval_gate(&self, approval_id: Uuid) -> Result<bool> {
+        let gate = sqlx::query_as::<_, (Uuid, ApprovalStatus, Uuid)>(
+            r#"
+            SELECT j.id, ar.status, ar.requested_by
+            FROM approval_requests ar
+            JOIN jobs j ON j.id = ar.job_id
+            WHERE ar.id = $1 AND ar.status <> 'pending' AND j.status = 'awaiting_approval'
+            "#,
+        )
+        .bind(approval_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, approval_id = %approval_id, "Failed to fetch approval gate");
+            AppError::database("Failed to fetch job")
+        })?;
+        let Some((job_id, status, requested_by)) = gate else {
+            return Ok(false);
+        };
+        self.resolve_gated_job(job_id, approval_id, &status, requested_by)
+            .await
+    }
+
+    /// 处理审批已结束但仍在等待审批的作业（补偿批量审批、超时与多实例下遗漏的派发）
+    pub async fn release_approval_gated_jobs(&self) -> Result<usize> {
+        // 同一作业存在多条审批请求时以最新一条为准
+        let gates = sqlx::query_as::<_, (Uuid, Uuid, ApprovalStatus, Uuid)>(
+            r#"
+            SELECT job_id, approval_id, status, requested_by FROM (
+                SELECT DISTINCT ON (j.id)
+                    j.id AS job_id, ar.id AS approval_id, ar.status, ar.requested_by
+                FROM jobs j
+                JOIN approval_requests ar ON ar.job_id = j.id
+                WHERE j.status = 'awaiting_approval'
+                ORDER BY j.id, ar.requested_at DESC
+            ) latest
+            WHERE status <> 'pending'
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch jobs awaiting approval");
+            AppError::database("Failed to fetch jobs")
+        })?;
+
+        let mut resolved = 0;
+        for (job_id, approval_id, status, requested_by) in gates {
+            match self
+                .resolve_gated_job(job_id, approval_id, &status, requested_by)
+                .await
+            {
+                Ok(true) => resolved += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    warn!(job_id = %job_id, error = %e, "Failed to resolve job approval gate");
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// 按审批结果派发或取消等待审批的作业
+    ///
+    /// 派发时重新检查互斥键：同键作业仍在进行时转为排队，由前序作业结束后调度
+    async fn resolve_gated_job(
+        &self,
+        job_id: Uuid,
+        approval_id: Uuid,
+        status: &ApprovalStatus,
+        requested_by: Uuid,
+    ) -> Result<bool> {
+        if *status != ApprovalStatus::Approved {
+            let reason = format!("Approval request {} ended as {:?}", approval_id, status);
+            return match self.cancel_job(job_id, requested_by, Some(reason)).await {
+                Ok(()) => Ok(true),
+                // 作业已被取消或派发
+                Err(AppError::Validation(_)) => Ok(false),
+                Err(e) => Err(e),
+            };
+        }
+
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+        let singleton_key = sqlx::query_scalar::<_, Option<String>>(
+            r#"
+            SELECT singleton_key FROM jobs
+            WHERE id = $1 AND status = 'awaiting_approval'
+            FOR UPDATE
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch job status");
+            AppError::database("Failed to fetch job")
+        })?;
+        let Some(singleton_key) = singleton_key else {
+            return Ok(false);
+        };
+
+        let queued = match &singleton_key {
+            Some(key) => {
+                Self::lock_singleton_key(&mut tx, key).await?;
+                !Self::active_singleton_jobs(&mut tx, key).await?.is_empty()
+            }
+            None => false,
+        };
+        sqlx::query("UPDATE jobs SET status = 'pending', singleton_waiting = $2 WHERE id = $1")
+            .bind(job_id)
+            .bind(queued)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to update job status");
+                AppError::database("Failed to update job status")
+            })?;
+        Self::enqueue_with_watchers(
+            &mut tx,
+            &RealtimeEvent::JobStatusChanged {
+                job_id,
+                old_status: JobStatus::AwaitingApproval.to_string(),
+                new_status: JobStatus::Pending.to_string(),
+            },
+        )
+        .await?;
+        // 与状态变更在同一事务中入队，避免提交后派发丢失
+        if !queued {
+            self.enqueue_dispatch(&mut tx, job_id).await?;
+        }
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        self.event_bus.notify_outbox();
+
+        if queued {
+            info!(
+                job_id = %job_id,
+                approval_id = %approval_id,
+                "Approved job queued behind active job with the same singleton key"
+            );
+        } else {
+            info!(job_id = %job_id, approval_id = %approval_id, "Dispatching approved job");
+            self.start_dispatch(job_id);
+        }
+        Ok(true)
+    }
+
+    /// 作业结束后调度同一互斥键下最早排队的作业
+    async fn start_next_singleton(job_id: Uuid, ctx: &JobExecutionContext) -> Result<()> {
+        let mut tx = ctx.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        // 作业仍在执行（等待维护续跑）时不调度
+        let key = sqlx::query_scalar::<_, String>(
+            "SELECT singleton_key FROM jobs WHERE id = $1 AND singleton_key IS NOT NULL AND status NOT IN ('pending', 'running')",
+        )
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch job singleton key");
+            AppError::database("Failed to fetch job")
+        })?;
+        let Some(key) = key else {
+            return Ok(());
+        };
+        Self::lock_singleton_key(&mut tx, &key).await?;
+
+        let next = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE jobs SET singleton_waiting = FALSE
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE singleton_key = $1 AND singleton_waiting AND status = 'pending'
+                ORDER BY created_at
+                LIMIT 1
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM jobs
+                WHERE singleton_key = $1 AND NOT singleton_waiting
+                  AND status IN ('pending', 'running')
+            )
+            RETURNING id
+            "#,
+        )
+        .bind(&key)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, singleton_key = %key, "Failed to dequeue singleton job");
+            AppError::database("Failed to update job")
+        })?;
+
+        // 启用派发队列时与出队在同一事务中入队，避免出队后派发丢失
+        if let (Some(next), Some(_)) = (next, &ctx.dispatch_pool) {
+            job_dispatch::enqueue(&mut *tx, next).await?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+
+        if let Some(next) = next {
+            info!(job_id = %next, previous_job_id = %job_id, "Starting queued singleton job");
+            if ctx.dispatch_pool.is_some() {
+                ctx.dispatch_signal.notify_one();
+            } else {
+                Self::spawn_with_context(next, ctx.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// 通过幂等键查找作业
+    async fn get_by_idempotency_key(&self, key: &str) -> Result<Option<Job>> {
+        sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE idempotency_key = $1")
+            .bind(key)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to fetch job by idempotency key");
+                AppError::database("Failed to fetch job")
+            })
+    }
+
+    /// 解析目标主机（直接指定的 + 分组中的）
+    ///
+    /// 维护中的主机同样作为目标，其任务在执行时挂起，待维护结束后再下发；
+    /// 直接指定已删除的主机时拒绝创建作业
+    async fn resolve_target_hosts(
+        &self,
+        host_ids: &[Uuid],
+        group_ids: &[Uuid],
+    ) -> Result<Vec<Host>> {
+        let mut hosts = Vec::new();
+
+        // 获取直接指定的主机
+        if !host_ids.is_empty() {
+            let deleted: Vec<String> = sqlx::query_scalar(
+                "SELECT identifier FROM assets_hosts WHERE id = ANY($1) AND deleted_at IS NOT NULL ORDER BY identifier",
+            )
+            .bind(host_ids)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to check deleted hosts");
+                AppError::database("Failed to fetch hosts")
+            })?;
+            if !deleted.is_empty() {
+                return Err(AppError::validation(&format!(
+                    "Target hosts have been deleted: {}",
+                    deleted.join(", ")
+                )));
+            }
+
+            let direct_hosts = sqlx::query_as::<_, Host>(
+                "SELECT * FROM assets_hosts WHERE id = ANY($1) AND status IN ('active', 'maintenance') AND deleted_at IS NULL",
+            )
+            .bind(host_ids)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to fetch hosts by ids");
                 AppError::database("Failed to fetch hosts")
             })?;
             hosts.extend(direct_hosts);
@@ -970,7 +2582,7 @@ impl JobService {
         // 获取分组中的主机
         if !group_ids.is_empty() {
             let group_hosts = sqlx::query_as::<_, Host>(
-                "SELECT DISTINCT h.* FROM hosts h JOIN asset_groups ag ON h.group_id = ag.id WHERE ag.id = ANY($1) AND h.status = 'active'"
+                "SELECT DISTINCT h.* FROM assets_hosts h JOIN assets_groups ag ON h.group_id = ag.id WHERE ag.id = ANY($1) AND h.status IN ('active', 'maintenance') AND h.deleted_at IS NULL"
             )
             .bind(group_ids)
             .fetch_all(&self.db)
@@ -990,55 +2602,156 @@ impl JobService {
     }
 
     /// 执行作业（异步）
-    async fn execute_job(
+    ///
+    /// 目标主机处于维护中的任务会被挂起为 waiting_maintenance，作业保持 running，
+    /// 维护结束后由 `release_maintenance_tasks` 重新调度本方法继续执行
+    async fn execute_job(job_id: Uuid, ctx: JobExecutionContext) -> Result<()> {
+        // 登记取消信号，`cancel_job` 通过它中断执行中的任务
+        let (cancel_tx, _) = watch::channel(false);
+        let cancel_tx = Arc::new(cancel_tx);
+        ctx.cancellations.insert(job_id, cancel_tx.clone());
+
+        let result = Self::run_job(job_id, &ctx, &cancel_tx).await;
+
+        // 仅移除本轮登记的信号（续跑可能已登记新的）
+        ctx.cancellations
+            .remove_if(&job_id, |_, sender| Arc::ptr_eq(sender, &cancel_tx));
+
+        if let Err(e) = Self::start_next_singleton(job_id, &ctx).await {
+            error!(error = %e, job_id = %job_id, "Failed to start queued singleton job");
+        }
+        result
+    }
+
+    async fn run_job(
         job_id: Uuid,
-        db: Pool<Postgres>,
-        concurrency_controller: Arc<ConcurrencyController>,
-        audit_service: Arc<AuditService>,
-        ssh_config: AppSshConfig,
-        event_bus: Arc<EventBus>,
+        ctx: &JobExecutionContext,
+        cancel_tx: &Arc<watch::Sender<bool>>,
     ) -> Result<()> {
-        info!(job_id = %job_id, "Starting job execution");
+        info!(job_id = %job_id, executor = ctx.executor.name(), "Starting job execution");
+        let db = &ctx.db;
 
-        // 更新作业状态为running
-        sqlx::query("UPDATE jobs SET status = 'running', started_at = NOW() WHERE id = $1")
-            .bind(job_id)
-            .execute(&db)
-            .await
-            .map_err(|e| {
-                error!(error = %e, "Failed to update job status to running");
-                AppError::database("Failed to update job status")
-            })?;
+        let previous_status =
+            sqlx::query_scalar::<_, JobStatus>("SELECT status FROM jobs WHERE id = $1")
+                .bind(job_id)
+                .fetch_one(db)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to fetch job status");
+                    AppError::database("Failed to fetch job")
+                })?;
+        // 作业已在运行说明是维护结束后的续跑，累计已有的任务计数
+        let resumed = match previous_status {
+            JobStatus::Running => true,
+            JobStatus::Pending => false,
+            // 调度前已被取消
+            _ => {
+                info!(job_id = %job_id, status = %previous_status, "Job is no longer runnable");
+                return Ok(());
+            }
+        };
 
-        // 发布作业状态变更事件：pending -> running
-        let _ = event_bus.publish(crate::realtime::RealtimeEvent::JobStatusChanged {
-            job_id,
-            old_status: "pending".to_string(),
-            new_status: "running".to_string(),
-        });
+        if !resumed {
+            // 更新作业状态为running，并发布作业状态变更事件：pending -> running
+            let started = Self::update_with_events(
+                db,
+                &ctx.event_bus,
+                sqlx::query(
+                    "UPDATE jobs SET status = 'running', started_at = NOW() WHERE id = $1 AND status = 'pending'"
+                )
+                .bind(job_id),
+                vec![RealtimeEvent::JobStatusChanged {
+                    job_id,
+                    old_status: "pending".to_string(),
+                    new_status: "running".to_string(),
+                }],
+                "Failed to update job status",
+            )
+            .await?;
+            if started == 0 {
+                info!(job_id = %job_id, "Job was cancelled before it started");
+                return Ok(());
+            }
+        }
 
         // 获取作业信息
-        let job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
+        let mut job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
             .bind(job_id)
-            .fetch_one(&db)
+            .fetch_one(db)
             .await
             .map_err(|e| {
                 error!(error = %e, "Failed to fetch job");
                 AppError::database("Failed to fetch job")
             })?;
+        // 脚本加载失败时各任务按缺少脚本失败，作业照常收尾
+        if let Err(e) = Self::load_script(ctx.blob_store.as_deref(), &mut job).await {
+            error!(error = %e, job_id = %job_id, "Failed to load script content");
+        }
 
         // 获取所有待执行的任务
         let tasks = sqlx::query_as::<_, Task>(
             "SELECT * FROM tasks WHERE job_id = $1 AND status = 'pending' ORDER BY created_at",
         )
         .bind(job_id)
-        .fetch_all(&db)
+        .fetch_all(db)
         .await
         .map_err(|e| {
             error!(error = %e, "Failed to fetch tasks");
             AppError::database("Failed to fetch tasks")
         })?;
 
+        // 挂起目标主机处于维护中的任务
+        let tasks = Self::hold_maintenance_tasks(db, &ctx.event_bus, job_id, tasks).await?;
+
+        // 执行预算：计入已保存的输出（续跑、重试），任务数超出时不再执行任务
+        let budget = Arc::new(JobBudget::new(ctx.budget.clone()));
+        budget.record_output(Self::stored_output_bytes(db, job_id).await?);
+        let tasks = if budget.check_tasks(job.total_tasks as u64) {
+            tasks
+        } else {
+            Vec::new()
+        };
+        // 流式传输的脚本在本地暂存一份供各任务分块传输，作业执行结束后删除；
+        // 暂存失败时各任务按缺少脚本失败
+        let staged_script = Self::stage_script(ctx.blob_store.as_deref(), &job, !tasks.is_empty())
+            .await
+            .map(Arc::new);
+
+        // 超出预算时停止剩余任务
+        let budget_monitor = {
+            let budget = budget.clone();
+            let ctx = ctx.clone();
+            let cancel_tx = cancel_tx.clone();
+            let created_by = job.created_by;
+            request_id::spawn(async move {
+                budget.tripped().await;
+                if let Some(breach) = budget.breach() {
+                    Self::stop_over_budget(&ctx, job_id, created_by, breach, &cancel_tx).await;
+                }
+            })
+        };
+
+        // 不可达任务比例过高时暂停派发，等待确认继续或中止
+        let backoff = Arc::new(NetworkBackoff::new(ctx.network_backoff.clone()));
+        let backoff_monitor = {
+            let backoff = backoff.clone();
+            let ctx = ctx.clone();
+            let cancel_rx = cancel_tx.subscribe();
+            let created_by = job.created_by;
+            request_id::spawn(async move {
+                while let Some(trip) = backoff.tripped().await {
+                    let resumed =
+                        Self::pause_dispatch(&ctx, job_id, created_by, &trip, cancel_rx.clone())
+                            .await;
+                    // 中止（作业已取消）时放行等待的任务，由任务自行按已取消结束
+                    backoff.resume();
+                    if !resumed {
+                        break;
+                    }
+                }
+            })
+        };
+
         // 并发执行任务
         let semaphore = if let Some(limit) = job.concurrent_limit {
             Arc::new(tokio::sync::Semaphore::new(limit as usize))
@@ -1049,147 +2762,1003 @@ impl JobService {
         let mut task_handles = Vec::new();
 
         for task in tasks {
-            let db_clone = db.clone();
-            let concurrency_clone = concurrency_controller.clone();
-            let audit_clone = audit_service.clone();
+            let task_id = task.id;
+            let ctx_clone = ctx.clone();
             let semaphore_clone = semaphore.clone();
             let job_clone = job.clone();
-            let ssh_config_clone = ssh_config.clone();
-            let event_bus_clone = event_bus.clone();
+            let cancel_rx = cancel_tx.subscribe();
+            let budget_clone = budget.clone();
+            let backoff_clone = backoff.clone();
+            let staged_clone = staged_script.clone();
 
-            let handle = tokio::spawn(async move {
+            let handle = request_id::spawn(async move {
                 let _permit = semaphore_clone.acquire().await.unwrap_or_else(|_| {
                     tracing::error!("Semaphore closed unexpectedly");
                     std::process::abort();
                 });
-                Self::execute_task(
+                backoff_clone.resumed().await;
+                let result = Self::execute_task(
                     task,
                     job_clone,
-                    db_clone,
-                    concurrency_clone,
-                    audit_clone,
-                    ssh_config_clone,
-                    event_bus_clone,
+                    ctx_clone,
+                    cancel_rx,
+                    budget_clone,
+                    staged_clone,
                 )
-                .await
+                .await;
+                // 释放并发许可前计入，暂停后下一个任务不再开始执行
+                match &result {
+                    Ok(TaskStatus::Cancelled) => {}
+                    Ok(_) => {
+                        backoff_clone.record(false);
+                    }
+                    Err(e) => {
+                        backoff_clone
+                            .record(network_backoff::is_unreachable(&e.to_ssh_failure_reason()));
+                    }
+                }
+                result
             });
 
-            task_handles.push(handle);
+            task_handles.push((task_id, handle));
         }
 
-        // 等待所有任务完成
-        let mut succeeded = 0;
-        let mut failed = 0;
-        let timeout = 0;
-
-        for handle in task_handles {
-            match handle.await {
-                Ok(Ok(())) => succeeded += 1,
-                Ok(Err(_)) => failed += 1,
-                Err(_) => failed += 1,
+        // 等待所有任务完成；任务结果与作业计数已在各自的状态更新事务中写入数据库
+        let mut aborted = Vec::new();
+        for (task_id, handle) in task_handles {
+            if let Ok(Err(_)) | Err(_) = handle.await {
+                aborted.push(task_id);
             }
         }
 
-        // 更新作业状态
-        let (status, succeeded_tasks, failed_tasks, timeout_tasks) =
-            Self::calculate_job_status(succeeded, failed, timeout, job.total_tasks);
+        // 任务均已结束，不会再超出预算；超出时等待监视完成收尾
+        let budget_breach = budget.breach().cloned();
+        if budget_breach.is_some() {
+            let _ = budget_monitor.await;
+        } else {
+            budget_monitor.abort();
+        }
+        // 最后几个任务触发的暂停已没有待派发的任务，不再等待确认
+        backoff_monitor.abort();
+        if backoff.is_paused() {
+            Self::clear_dispatch_pause(db, job_id).await;
+        }
 
-        sqlx::query(
-            "UPDATE jobs SET status = $1, succeeded_tasks = $2, failed_tasks = $3, timeout_tasks = $4, completed_at = NOW() WHERE id = $5"
-        )
-        .bind(&status)
-        .bind(succeeded_tasks)
-        .bind(failed_tasks)
-        .bind(timeout_tasks)
-        .bind(job_id)
-        .execute(&db)
-        .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to update job final status");
-            AppError::database("Failed to update job status")
+        // 执行中途出错或异常退出的任务可能未写入最终状态，统一标记为失败
+        if !aborted.is_empty() {
+            Self::update_task_with_events(
+                db,
+                &ctx.event_bus,
+                job_id,
+                sqlx::query(
+                    "UPDATE tasks SET status = 'failed', failure_message = 'Task execution aborted', completed_at = NOW() WHERE id = ANY($1) AND status IN ('pending', 'running')"
+                )
+                .bind(&aborted),
+                vec![],
+                "Failed to update task",
+            )
+            .await?;
+        }
+
+        // 仍有未结束的任务（等待维护或续跑中）时作业保持 running
+        let mut tx = db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+        Self::lock_job(&mut tx, job_id).await?;
+        let counts = Self::refresh_job_counts(&mut tx, job_id).await?;
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
         })?;
 
-        // 发布作业状态变更事件：running -> final status
-        let _ = event_bus.publish(crate::realtime::RealtimeEvent::JobStatusChanged {
-            job_id,
-            old_status: "running".to_string(),
-            new_status: status.to_string(),
-        });
+        if counts.has_unfinished {
+            info!(
+                job_id = %job_id,
+                succeeded = counts.succeeded,
+                warning = counts.warning,
+                failed = counts.failed,
+                "Job has tasks waiting for host maintenance, keeping it running"
+            );
+            return Ok(());
+        }
+
+        // 更新作业状态（已取消的作业保持 cancelled，超出预算的作业失败；告警任务计入成功，
+        // 跳过的任务不参与判定）
+        let (status, succeeded_tasks, failed_tasks, _) = Self::calculate_job_status(
+            counts.succeeded + counts.warning,
+            counts.failed,
+            counts.timeout,
+            job.total_tasks - counts.skipped,
+        );
+        let status = if budget_breach.is_some() {
+            JobStatus::Failed
+        } else {
+            status
+        };
+
+        // 命中后续作业配置时标记为待派发，与终态在同一语句中写入
+        let trigger = ChainTrigger::for_status(&status);
+        let has_follow_up = match trigger {
+            Some(ChainTrigger::Success) => job.on_success_job_template.is_some(),
+            Some(ChainTrigger::Failure) => job.on_failure_job_template.is_some(),
+            None => false,
+        };
+
+        // 发布作业状态变更事件：running -> final status
+        let finished = Self::update_with_events(
+            db,
+            &ctx.event_bus,
+            sqlx::query(
+                "UPDATE jobs SET status = $1, completed_at = NOW(), chain_status = CASE WHEN $3 THEN 'pending' END, chain_error = NULL WHERE id = $2 AND status = 'running'"
+            )
+            .bind(&status)
+            .bind(job_id)
+            .bind(has_follow_up),
+            vec![RealtimeEvent::JobStatusChanged {
+                job_id,
+                old_status: "running".to_string(),
+                new_status: status.to_string(),
+            }],
+            "Failed to update job status",
+        )
+        .await?;
+
+        if finished == 0 {
+            info!(job_id = %job_id, "Job was cancelled during execution");
+            return Ok(());
+        }
 
         info!(
             job_id = %job_id,
             status = ?status,
             succeeded = succeeded_tasks,
+            warning = counts.warning,
             failed = failed_tasks,
+            timeout = counts.timeout,
+            cancelled = counts.cancelled,
             "Job execution completed"
         );
+        if has_follow_up {
+            ctx.chain_signal.notify_one();
+        }
 
         Ok(())
     }
 
-    /// 执行单个任务
+    /// 将目标主机处于维护中的任务挂起为 waiting_maintenance，返回可立即执行的任务
+    async fn hold_maintenance_tasks(
+        db: &Pool<Postgres>,
+        event_bus: &EventBus,
+        job_id: Uuid,
+        tasks: Vec<Task>,
+    ) -> Result<Vec<Task>> {
+        if tasks.is_empty() {
+            return Ok(tasks);
+        }
+
+        let host_ids: Vec<Uuid> = tasks.iter().map(|t| t.host_id).collect();
+        let maintenance_hosts = sqlx::query_scalar::<_, Uuid>(&format!(
+            "SELECT id FROM assets_hosts WHERE id = ANY($1) AND {}",
+            HOST_UNDER_MAINTENANCE
+        ))
+        .bind(&host_ids)
+        .fetch_all(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to check host maintenance");
+            AppError::database("Failed to fetch hosts")
+        })?;
+
+        if maintenance_hosts.is_empty() {
+            return Ok(tasks);
+        }
+
+        let (held, runnable): (Vec<Task>, Vec<Task>) = tasks
+            .into_iter()
+            .partition(|t| maintenance_hosts.contains(&t.host_id));
+        let held_ids: Vec<Uuid> = held.iter().map(|t| t.id).collect();
+
+        let events = held
+            .iter()
+            .map(|task| RealtimeEvent::TaskStatusChanged {
+                task_id: task.id,
+                job_id,
+                old_status: "pending".to_string(),
+                new_status: "waiting_maintenance".to_string(),
+            })
+            .collect();
+        Self::update_with_events(
+            db,
+            event_bus,
+            sqlx::query(
+                "UPDATE tasks SET status = 'waiting_maintenance' WHERE id = ANY($1) AND status = 'pending'",
+            )
+            .bind(&held_ids),
+            events,
+            "Failed to update task status",
+        )
+        .await?;
+
+        info!(
+            job_id = %job_id,
+            held = held.len(),
+            "Tasks held until host maintenance ends"
+        );
+
+        Ok(runnable)
+    }
+
+    /// 作业已保存的任务输出字节数（不含待重试的任务）
+    async fn stored_output_bytes(db: &Pool<Postgres>, job_id: Uuid) -> Result<u64> {
+        let bytes = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COALESCE(SUM(
+                COALESCE(octet_length(output_summary), 0) + COALESCE(octet_length(output_detail), 0)
+            ), 0)::bigint
+            FROM tasks
+            WHERE job_id = $1 AND status <> 'pending'
+            "#,
+        )
+        .bind(job_id)
+        .fetch_one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to sum stored task output");
+            AppError::database("Failed to fetch tasks")
+        })?;
+        Ok(bytes.max(0) as u64)
+    }
+
+    /// 超出执行预算：未结束的任务以 budget_exceeded 失败，中断执行中的任务，记录超出项与审计
+    async fn stop_over_budget(
+        ctx: &JobExecutionContext,
+        job_id: Uuid,
+        created_by: Uuid,
+        breach: &JobBudgetBreach,
+        cancel_tx: &watch::Sender<bool>,
+    ) {
+        warn!(
+            job_id = %job_id,
+            limit = breach.limit.as_str(),
+            max = breach.max,
+            observed = breach.observed,
+            "Job exceeded its execution budget, stopping remaining tasks"
+        );
+        let message = format!(
+            "Job execution budget exceeded: {} {} > {}",
+            breach.limit.as_str(),
+            breach.observed,
+            breach.max
+        );
+
+        if let Err(e) = Self::update_task_with_events(
+            &ctx.db,
+            &ctx.event_bus,
+            job_id,
+            sqlx::query(
+                "UPDATE tasks SET status = 'failed', failure_reason = 'budget_exceeded', failure_message = $2, completed_at = NOW() WHERE job_id = $1 AND status IN ('pending', 'running', 'waiting_maintenance')"
+            )
+            .bind(job_id)
+            .bind(&message),
+            vec![],
+            "Failed to update task",
+        )
+        .await
+        {
+            error!(error = %e, job_id = %job_id, "Failed to stop tasks over budget");
+        }
+        // 任务状态已更新，执行中的任务丢弃执行 future 即中断
+        let _ = cancel_tx.send(true);
+
+        if let Err(e) = sqlx::query("UPDATE jobs SET budget_exceeded = $2 WHERE id = $1")
+            .bind(job_id)
+            .bind(Json(breach))
+            .execute(&ctx.db)
+            .await
+        {
+            error!(error = %e, job_id = %job_id, "Failed to record budget breach");
+        }
+
+        let params = AuditLogParams {
+            subject_id: created_by,
+            subject_type: "system",
+            subject_name: None,
+            action: AuditAction::JobBudgetExceeded.as_str(),
+            resource_type: "job",
+            resource_id: Some(job_id),
+            resource_name: None,
+            changes: Some(serde_json::json!({
+                "limit": breach.limit,
+                "max": breach.max,
+                "observed": breach.observed,
+            })),
+            changes_summary: Some(&message),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "failure",
+            error_message: None,
+        };
+        if let Err(e) = ctx.audit_service.log_action(params).await {
+            warn!(error = %e, job_id = %job_id, "Failed to audit budget breach");
+        }
+    }
+
+    /// 不可达任务比例过高：记录暂停、推送告警事件与审计，等待确认继续或中止
+    ///
+    /// 确认继续时返回 true；作业已中止、取消或结束时返回 false
+    async fn pause_dispatch(
+        ctx: &JobExecutionContext,
+        job_id: Uuid,
+        created_by: Uuid,
+        trip: &NetworkBackoffTrip,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> bool {
+        warn!(
+            job_id = %job_id,
+            unreachable = trip.unreachable_tasks,
+            sample_size = trip.sample_size,
+            "Too many tasks failed as unreachable, pausing job dispatch"
+        );
+        let paused = Self::update_with_events(
+            &ctx.db,
+            &ctx.event_bus,
+            sqlx::query(
+                "UPDATE jobs SET dispatch_paused_at = $2, network_backoff = $3 WHERE id = $1 AND status = 'running'",
+            )
+            .bind(job_id)
+            .bind(trip.paused_at)
+            .bind(Json(trip)),
+            vec![RealtimeEvent::JobDispatchPaused {
+                job_id,
+                unreachable_tasks: trip.unreachable_tasks,
+                sample_size: trip.sample_size,
+                failure_rate: trip.failure_rate,
+            }],
+            "Failed to pause job dispatch",
+        )
+        .await;
+        match paused {
+            Ok(0) => return false,
+            Ok(_) => {}
+            // 无法记录暂停时无人能确认，继续派发
+            Err(e) => {
+                error!(error = %e, job_id = %job_id, "Failed to pause job dispatch, continuing");
+                return true;
+            }
+        }
+
+        let summary = format!(
+            "Job dispatch paused: {} of the last {} tasks failed as unreachable",
+            trip.unreachable_tasks, trip.sample_size
+        );
+        let params = AuditLogParams {
+            subject_id: created_by,
+            subject_type: "system",
+            subject_name: None,
+            action: AuditAction::JobDispatchPaused.as_str(),
+            resource_type: "job",
+            resource_id: Some(job_id),
+            resource_name: None,
+            changes: Some(serde_json::json!(trip)),
+            changes_summary: Some(&summary),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        };
+        if let Err(e) = ctx.audit_service.log_action(params).await {
+            warn!(error = %e, job_id = %job_id, "Failed to audit job dispatch pause");
+        }
+
+        // 确认可能由其他实例处理，轮询作业记录；本实例取消作业时立即结束等待
+        let interval =
+            std::time::Duration::from_secs(ctx.network_backoff.poll_interval_secs.max(1));
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = Self::wait_cancelled(cancel_rx.clone()) => return false,
+            }
+            let state = sqlx::query_as::<_, (JobStatus, bool)>(
+                "SELECT status, dispatch_paused_at IS NOT NULL FROM jobs WHERE id = $1",
+            )
+            .bind(job_id)
+            .fetch_one(&ctx.db)
+            .await;
+            match state {
+                Ok((JobStatus::Running, true)) => {}
+                Ok((JobStatus::Running, false)) => {
+                    info!(job_id = %job_id, "Continuing job dispatch after confirmation");
+                    return true;
+                }
+                Ok(_) => return false,
+                Err(e) => warn!(error = %e, job_id = %job_id, "Failed to check job dispatch pause"),
+            }
+        }
+    }
+
+    /// 清除暂停派发（已没有待派发的任务）
+    async fn clear_dispatch_pause(db: &Pool<Postgres>, job_id: Uuid) {
+        if let Err(e) = sqlx::query("UPDATE jobs SET dispatch_paused_at = NULL WHERE id = $1")
+            .bind(job_id)
+            .execute(db)
+            .await
+        {
+            warn!(error = %e, job_id = %job_id, "Failed to clear job dispatch pause");
+        }
+    }
+
+    /// 执行单个任务，返回任务的最终状态
     async fn execute_task(
         task: Task,
         job: Job,
-        db: Pool<Postgres>,
-        concurrency_controller: Arc<ConcurrencyController>,
-        _audit_service: Arc<AuditService>,
-        ssh_config: AppSshConfig,
-        event_bus: Arc<EventBus>,
-    ) -> Result<()> {
+        ctx: JobExecutionContext,
+        cancel_rx: watch::Receiver<bool>,
+        budget: Arc<JobBudget>,
+        staged_script: Option<Arc<StagedFile>>,
+    ) -> Result<TaskStatus> {
         info!(
             task_id = %task.id,
             job_id = %job.id,
             host_id = %task.host_id,
             "Executing task"
         );
+        let db = &ctx.db;
+        let ssh_config = &ctx.ssh_config;
+        let event_bus = &ctx.event_bus;
 
-        // 更新任务状态为running
-        sqlx::query("UPDATE tasks SET status = 'running', started_at = NOW() WHERE id = $1")
-            .bind(task.id)
-            .execute(&db)
-            .await
-            .map_err(|e| {
-                error!(error = %e, "Failed to update task status");
-                AppError::database("Failed to update task status")
-            })?;
-
-        // 发布任务状态变更事件：pending -> running
-        let _ = event_bus.publish(crate::realtime::RealtimeEvent::TaskStatusChanged {
-            task_id: task.id,
-            job_id: job.id,
-            old_status: "pending".to_string(),
-            new_status: "running".to_string(),
-        });
+        // 更新任务状态为running（任务可能已随作业取消），发布事件：pending -> running
+        let started = Self::update_with_events(
+            db,
+            event_bus,
+            sqlx::query(
+                "UPDATE tasks SET status = 'running', started_at = NOW() WHERE id = $1 AND status = 'pending'",
+            )
+            .bind(task.id),
+            vec![RealtimeEvent::TaskStatusChanged {
+                task_id: task.id,
+                job_id: job.id,
+                old_status: "pending".to_string(),
+                new_status: "running".to_string(),
+            }],
+            "Failed to update task status",
+        )
+        .await?;
+        if started == 0 {
+            info!(task_id = %task.id, "Task was cancelled before it started");
+            return Ok(TaskStatus::Cancelled);
+        }
+        // 执行日志记录各阶段到达时间，任务卡住时据此定位停在哪一步
+        let journal = TaskJournal::start(db.clone(), task.id);
 
-        // 获取主机信息
-        let host = sqlx::query_as::<_, Host>("SELECT * FROM hosts WHERE id = $1")
-            .bind(task.host_id)
-            .fetch_one(&db)
+        // 获取主机信息（经由缓存，主机的写操作会失效缓存）
+        let host = crate::repository::AssetRepository::new(db.clone())
+            .get_host_cached(task.host_id)
             .await
             .map_err(|e| {
                 error!(error = %e, "Failed to fetch host");
                 AppError::database("Failed to fetch host")
-            })?;
+            })?
+            .ok_or_else(|| AppError::not_found("Host not found"))?;
+
+        // 开启详细追踪的作业记录并发等待、连接协商、阶段耗时与事件发布结果
+        let tracer = job
+            .verbose_trace
+            .then(|| JobTracer::new(db.clone(), job.id));
+
+        // 获取并发许可（多个作业并发时按作业轮询分配）
+        let permit_wait = std::time::Instant::now();
+        let permit = ctx
+            .concurrency_controller
+            .acquire_for_job(job.id, Some(&host.group_id.to_string()), Some(&host.environment))
+            .await;
+        if let Some(tracer) = &tracer {
+            let message = match &permit {
+                Ok(_) => "Acquired concurrency permit".to_string(),
+                Err(e) => format!("Failed to acquire concurrency permit: {}", e),
+            };
+            tracer
+                .record(
+                    Some(task.id),
+                    job_trace::CATEGORY_CONCURRENCY,
+                    &message,
+                    Some(permit_wait.elapsed().as_millis() as u64),
+                    Some(serde_json::json!({
+                        "group_id": host.group_id,
+                        "environment": host.environment,
+                    })),
+                )
+                .await;
+        }
+        if permit.is_ok() {
+            journal.record(TaskJournalPhase::PermitAcquired);
+        }
+        let _permit = permit.map_err(|e| {
+            error!(error = %e, "Failed to acquire concurrency permit");
+            e
+        })?;
 
-        // 获取并发许可
-        let _permit = concurrency_controller
-            .acquire(Some(&host.group_id.to_string()), Some(&host.environment))
-            .await
-            .map_err(|e| {
-                error!(error = %e, "Failed to acquire concurrency permit");
-                e
-            })?;
+        // 组装连接参数并交给执行器
+        let ResolvedConnection {
+            config: ssh_exec_config,
+            user_source,
+            credential_source,
+            known_hosts_source,
+        } = Self::resolve_connection(
+            db,
+            ssh_config,
+            &host,
+            job.execute_user.as_deref(),
+            job.timeout_secs
+                .unwrap_or(ssh_config.command_timeout_secs as i32) as u64,
+        )
+        .await;
+
+        // 创建进度回调用于增量输出推送
+        let job_id_for_callback = job.id;
+        let task_id_for_callback = task.id;
+        let event_bus_for_callback = event_bus.clone();
+        let budget_for_callback = budget.clone();
+        let shaper = OutputShaper::new(&ctx.output);
+        let output_events = Arc::new(PublishCounter::default());
+        let output_events_for_callback = output_events.clone();
 
-        // 创建SSH客户端并执行命令
-        // 优先使用主机级凭据，否则回退到全局默认配置
+        let progress_callback = std::sync::Arc::new(move |output: String, is_complete: bool| {
+            // 合并、限速，超过阈值后只推送一次提示
+            let Some(shaped) = shaper.offer(output, is_complete) else {
+                return;
+            };
+
+            // 超出事件预算后不再推送
+            if !budget_for_callback.record_event() {
+                return;
+            }
 
-        // 确定用户名：主机级 > 作业指定 > 全局默认
-        let username = job
-            .execute_user
-            .clone()
-            .or_else(|| host.ssh_username.clone())
-            .unwrap_or_else(|| ssh_config.default_username.clone());
+            let event = match shaped {
+                ShapedOutput::Output {
+                    output,
+                    is_complete,
+                } => RealtimeEvent::TaskOutputUpdate {
+                    task_id: task_id_for_callback,
+                    job_id: job_id_for_callback,
+                    // 脱敏输出
+                    output: crate::realtime::DataMasker::mask_output(&output),
+                    is_complete,
+                },
+                ShapedOutput::Summarized {
+                    output_bytes,
+                    threshold_bytes,
+                } => {
+                    warn!(
+                        task_id = %task_id_for_callback,
+                        output_bytes,
+                        "Task output exceeded threshold, streaming summary only"
+                    );
+                    RealtimeEvent::TaskOutputSummarized {
+                        task_id: task_id_for_callback,
+                        job_id: job_id_for_callback,
+                        output_bytes,
+                        threshold_bytes,
+                    }
+                }
+            };
+
+            // 发布增量输出更新事件
+            let delivered = event_bus_for_callback.publish(event).is_ok();
+            output_events_for_callback.record(delivered);
+        });
+
+        // 部署作业按主机的架构与操作系统选择产物变体，命令与脚本中的产物变量解析为所选变体
+        let variant = match job.artifact_id {
+            Some(artifact_id) => artifact_variants::select_for_host(db, artifact_id, &host)
+                .await
+                .map(Some),
+            None => Ok(None),
+        };
+        let interpolate = |text: &str| {
+            let text = host_vars::interpolate(text, &host)?;
+            artifact_variants::interpolate(&text, variant.as_ref().ok().and_then(Option::as_ref))
+        };
+
+        // 根据作业类型执行不同的命令（按主机解析主机变量，未知引用使任务失败）
+        let payload = match job.job_type {
+            JobType::Command => job
+                .command
+                .as_deref()
+                .ok_or_else(|| AppError::validation("Command job must have a command"))
+                .and_then(interpolate)
+                .map(|command| match &job.file_manifest {
+                    Some(manifest) => file_manifest::wrap(&command, manifest),
+                    None => command,
+                })
+                .map(ExecutionPayload::Command),
+            // 已上传的脚本/二进制内容以文件传输，不解析主机变量
+            JobType::Script if job.script_streamed => staged_script
+                .as_deref()
+                .ok_or_else(|| AppError::validation("Script job must have a script"))
+                .map(|staged| ExecutionPayload::ScriptFile {
+                    local_path: staged.path().to_path_buf(),
+                    path: job.script_path.clone(),
+                }),
+            JobType::Script => job
+                .script
+                .as_deref()
+                .ok_or_else(|| AppError::validation("Script job must have a script"))
+                .and_then(interpolate)
+                .map(|content| ExecutionPayload::Script {
+                    content: match &job.file_manifest {
+                        Some(manifest) => file_manifest::wrap(&content, manifest),
+                        None => content,
+                    },
+                    path: job.script_path.clone(),
+                }),
+            // 文件分发以生成的脚本执行，校验命令按主机解析主机变量
+            JobType::File => job
+                .file_spec
+                .as_ref()
+                .ok_or_else(|| AppError::validation("File job must have a file spec"))
+                .and_then(|spec| {
+                    let mut spec = spec.0.clone();
+                    spec.validate_command = spec
+                        .validate_command
+                        .as_deref()
+                        .map(interpolate)
+                        .transpose()?;
+                    Ok(file_distribution::build_script(&spec))
+                })
+                .map(|content| ExecutionPayload::Script {
+                    content,
+                    path: None,
+                }),
+            JobType::Inventory => Ok(ExecutionPayload::Script {
+                content: package_inventory::COLLECT_SCRIPT.to_string(),
+                path: None,
+            }),
+            // 构建作业暂不支持远程执行
+            JobType::Build => {
+                Err(AppError::validation("Build jobs are not supported for SSH execution"))
+            }
+        };
+
+        // 没有可用的产物变体时任务失败
+        let payload = variant.and(payload);
+
+        // 主机密钥漂移检查发现密钥变化的主机，在重新固定前不连接主机
+        let payload = match host.host_key_blocked_at {
+            Some(_) => payload.and(Err(host_key_drift::blocked_error(db, &host).await)),
+            None => payload,
+        };
+
+        // 按主机连接方式选择执行器（SSH 主机、Docker 容器或 Kubernetes Pod）
+        let executor = target::executor_for_host(&host, ctx.executor.clone());
+        let prepared = payload.and_then(|payload| executor.map(|executor| (executor, payload)));
+        let diagnostics = if tracer.is_some() {
+            DiagnosticsCollector::verbose()
+        } else {
+            DiagnosticsCollector::new()
+        }
+        .with_listener(journal.phase_listener());
+        let result = match prepared {
+            Ok((executor, payload)) => {
+                let snapshot = Self::execution_snapshot(
+                    executor.as_ref(),
+                    &ssh_exec_config,
+                    &payload,
+                    user_source,
+                    credential_source,
+                    known_hosts_source,
+                );
+                Self::record_execution_context(db, task.id, &snapshot).await;
+
+                let request = ExecutionRequest {
+                    connection: ssh_exec_config,
+                    payload,
+                    progress: Some(progress_callback),
+                    diagnostics: diagnostics.clone(),
+                };
+                // 登记任务级取消信号，`cancel_tasks` 通过它中断单个任务
+                let (task_cancel_tx, task_cancel_rx) = watch::channel(false);
+                ctx.task_cancellations
+                    .insert(task.id, Arc::new(task_cancel_tx));
+                let outcome = tokio::select! {
+                    result = executor.execute(request) => Some(result),
+                    _ = Self::wait_cancelled(cancel_rx) => None,
+                    _ = Self::wait_cancelled(task_cancel_rx) => None,
+                };
+                ctx.task_cancellations.remove(&task.id);
+                match outcome {
+                    Some(result) => result,
+                    None => {
+                        // 任务状态已由 cancel_job / cancel_tasks 更新，丢弃执行 future 即中断执行
+                        info!(task_id = %task.id, "Task execution interrupted by cancellation");
+                        return Ok(TaskStatus::Cancelled);
+                    }
+                }
+            }
+            Err(e) => Err(e),
+        };
+        if let Some(tracer) = &tracer {
+            Self::record_execution_trace(tracer, task.id, &diagnostics, &output_events).await;
+        }
+
+        match result {
+            Ok(mut exec_result) => {
+                journal.record(TaskJournalPhase::OutputComplete);
+                let (status, failure_reason, failure_message) =
+                    Self::classify_result(&exec_result, job.exit_code_rules.as_deref());
+
+                // 声明了文件清单的作业从输出中剥离清单报告
+                let file_manifest_report = job.file_manifest.as_ref().map(|manifest| {
+                    let (stdout, report) =
+                        file_manifest::split_report(&exec_result.stdout, manifest);
+                    exec_result.stdout = stdout;
+                    Json(report)
+                });
+
+                // 偏离检测作业将成功任务的输出与基线比对，基线读取失败时不记录比对结果
+                let output_drift = if job.drift_check
+                    && matches!(status, TaskStatus::Succeeded | TaskStatus::Warning)
+                {
+                    match output_drift::find_baseline(db, job.template_id, task.host_id).await {
+                        Ok(baseline) => Some(Json(output_drift::compare(
+                            baseline.as_ref(),
+                            &exec_result.stdout,
+                        ))),
+                        Err(e) => {
+                            warn!(task_id = %task.id, error = %e, "Skipping output drift check");
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                // 使用脱敏模块处理输出
+                let output_archive = OutputArchive::default_config();
+
+                // 合并 stdout 和 stderr
+                let full_output = if exec_result.stderr.is_empty() {
+                    exec_result.stdout.clone()
+                } else if exec_result.stdout.is_empty() {
+                    exec_result.stderr.clone()
+                } else {
+                    format!("{}\n{}", exec_result.stdout, exec_result.stderr)
+                };
+
+                // 规范化、脱敏并生成摘要和明细
+                let processed = output_archive.process(&full_output);
+                let output_summary = processed.summary;
+
+                // 失败时记录诊断信息（stderr 已脱敏）
+                let failed = !matches!(status, TaskStatus::Succeeded | TaskStatus::Warning);
+                let task_diagnostics = failed.then(|| {
+                    let stderr = crate::output::default_sanitizer().sanitize(&exec_result.stderr);
+                    Json(diagnostics.build(failure_message.map(str::to_string), &stderr))
+                });
+
+                // 文件分发作业解析每台主机的分发结果（diff 已脱敏）
+                let file_result = (job.job_type == JobType::File).then(|| {
+                    let mut report = file_distribution::parse_report(&exec_result.stdout);
+                    report.diff = report
+                        .diff
+                        .map(|diff| crate::output::default_sanitizer().sanitize(&diff));
+                    Json(report)
+                });
+
+                // 发布任务状态变更事件：running -> final status
+                let updated = Self::update_task_with_events(
+                    db,
+                    event_bus,
+                    job.id,
+                    sqlx::query(
+                        "UPDATE tasks SET status = $1, exit_code = $2, output_summary = $3, output_detail = $4, output_normalized = $5, failure_reason = $6, failure_message = $7, completed_at = NOW(), duration_secs = $8, output_encoding = $10, diagnostics = $11, file_result = $12, file_manifest_report = $13, output_drift = $14 WHERE id = $9 AND status = 'running'"
+                    )
+                    .bind(&status)
+                    .bind(exec_result.exit_code)
+                    .bind(&output_summary)
+                    .bind(&processed.detail)
+                    .bind(processed.normalized)
+                    .bind(&failure_reason)
+                    .bind(failure_message)
+                    .bind(exec_result.duration_secs as i64)
+                    .bind(task.id)
+                    .bind(&exec_result.output_encoding)
+                    .bind(task_diagnostics)
+                    .bind(file_result)
+                    .bind(file_manifest_report)
+                    .bind(output_drift),
+                    vec![RealtimeEvent::TaskStatusChanged {
+                        task_id: task.id,
+                        job_id: job.id,
+                        old_status: "running".to_string(),
+                        new_status: status.to_string(),
+                    }],
+                    "Failed to update task",
+                )
+                .await?;
+                if let Some(tracer) = &tracer {
+                    Self::record_status_event_trace(tracer, task.id, &status, updated).await;
+                }
+                if updated == 0 {
+                    return Ok(TaskStatus::Cancelled);
+                }
+                journal.record(TaskJournalPhase::StatusPersisted);
+                // 输出保存后再计入预算，超出时由作业的预算监视停止剩余任务
+                budget.record_output((output_summary.len() + processed.detail.len()) as u64);
+
+                // 清单采集作业将解析结果写入主机软件包清单
+                if job.job_type == JobType::Inventory && status == TaskStatus::Succeeded {
+                    Self::store_package_inventory(db, task.host_id, job.id, &exec_result.stdout)
+                        .await;
+                }
+
+                // 发布任务输出更新事件（流式输出不经过发件箱）
+                let published =
+                    event_bus.publish(crate::realtime::RealtimeEvent::TaskOutputUpdate {
+                        task_id: task.id,
+                        job_id: job.id,
+                        output: output_summary.clone(),
+                        is_complete: true,
+                    });
+                if let Some(tracer) = &tracer {
+                    let details = match &published {
+                        Ok(()) => serde_json::json!({ "delivered": true }),
+                        Err(e) => serde_json::json!({ "delivered": false, "error": e.to_string() }),
+                    };
+                    tracer
+                        .record(
+                            Some(task.id),
+                            job_trace::CATEGORY_EVENT,
+                            "Published final task output",
+                            None,
+                            Some(details),
+                        )
+                        .await;
+                }
+                journal.record(TaskJournalPhase::EventsPublished);
+
+                Ok(status)
+            }
+            Err(e) => {
+                error!(error = %e, executor = ctx.executor.name(), "Failed to execute command");
+                // 被禁止执行的主机已有漂移记录，不重复记录
+                if let (AppError::SshHostKeyVerificationError(failure), None) =
+                    (&e, host.host_key_blocked_at)
+                {
+                    JobService::record_host_key_failure(db, host.id, task.id, failure).await;
+                }
+                // 根据错误类型分类失败原因
+                let failure_reason = e.to_ssh_failure_reason();
+                let task_diagnostics = diagnostics.build(Some(e.to_string()), "");
+                // 发布任务状态变更事件：running -> failed
+                let updated = Self::update_task_with_events(
+                    db,
+                    event_bus,
+                    job.id,
+                    sqlx::query(
+                        "UPDATE tasks SET status = 'failed', failure_reason = $1, failure_message = $2, completed_at = NOW(), diagnostics = $4 WHERE id = $3 AND status = 'running'"
+                    )
+                    .bind(&failure_reason)
+                    .bind(e.to_string())
+                    .bind(task.id)
+                    .bind(Json(task_diagnostics)),
+                    vec![RealtimeEvent::TaskStatusChanged {
+                        task_id: task.id,
+                        job_id: job.id,
+                        old_status: "running".to_string(),
+                        new_status: "failed".to_string(),
+                    }],
+                    "Failed to update task",
+                )
+                .await?;
+                if let Some(tracer) = &tracer {
+                    Self::record_status_event_trace(tracer, task.id, &TaskStatus::Failed, updated)
+                        .await;
+                }
+                if updated == 0 {
+                    return Ok(TaskStatus::Cancelled);
+                }
+                // 状态变更事件与状态在同一事务中写入发件箱
+                journal.record(TaskJournalPhase::StatusPersisted);
+                journal.record(TaskJournalPhase::EventsPublished);
+
+                Err(e)
+            }
+        }
+    }
+
+    /// 写入任务执行的详细追踪：连接协商日志、各阶段耗时与增量输出事件的发布结果
+    async fn record_execution_trace(
+        tracer: &JobTracer,
+        task_id: Uuid,
+        diagnostics: &DiagnosticsCollector,
+        output_events: &PublishCounter,
+    ) {
+        for note in diagnostics.notes() {
+            let details = note
+                .phase
+                .map(|phase| serde_json::json!({ "phase": phase }));
+            tracer
+                .record(Some(task_id), job_trace::CATEGORY_SSH, &note.message, None, details)
+                .await;
+        }
+        for timing in diagnostics.timings() {
+            let message = if timing.succeeded {
+                "Connection phase completed"
+            } else {
+                "Connection phase failed"
+            };
+            let details = serde_json::json!({
+                "phase": timing.phase,
+                "succeeded": timing.succeeded,
+            });
+            tracer
+                .record(
+                    Some(task_id),
+                    job_trace::CATEGORY_PHASE,
+                    message,
+                    Some(timing.duration_ms),
+                    Some(details),
+                )
+                .await;
+        }
+        tracer
+            .record(
+                Some(task_id),
+                job_trace::CATEGORY_EVENT,
+                "Published incremental output events",
+                None,
+                Some(output_events.to_json()),
+            )
+            .await;
+    }
+
+    /// 写入任务最终状态事件的入队结果（未更新任务时事件未入队）
+    async fn record_status_event_trace(
+        tracer: &JobTracer,
+        task_id: Uuid,
+        status: &TaskStatus,
+        updated: u64,
+    ) {
+        let message = if updated > 0 {
+            format!("Enqueued task status event running -> {}", status)
+        } else {
+            "Task status event not enqueued: task is no longer running".to_string()
+        };
+        tracer
+            .record(
+                Some(task_id),
+                job_trace::CATEGORY_EVENT,
+                &message,
+                None,
+                Some(serde_json::json!({ "status": status.to_string(), "enqueued": updated > 0 })),
+            )
+            .await;
+    }
+
+    /// 组装主机的连接参数
+    ///
+    /// 优先使用主机级凭据，否则回退到全局默认配置；作业执行与连接测试共用
+    pub(crate) async fn resolve_connection(
+        db: &Pool<Postgres>,
+        ssh_config: &AppSshConfig,
+        host: &Host,
+        execute_user: Option<&str>,
+        command_timeout_secs: u64,
+    ) -> ResolvedConnection {
+        // 确定用户名：作业指定 > 主机级 > 全局默认
+        let (username, user_source) = match (execute_user, &host.ssh_username) {
+            (Some(user), _) => (user.to_string(), "job"),
+            (None, Some(user)) => (user.clone(), "host"),
+            (None, None) => (ssh_config.default_username.clone(), "default"),
+        };
+        let credential_source = if host.ssh_private_key.is_some() || host.ssh_password.is_some() {
+            "host"
+        } else {
+            "default"
+        };
 
         // 确定认证方式：优先使用主机级私钥，其次主机级密码，再然后全局私钥，最后全局密码
         let auth = if let Some(host_private_key) = &host.ssh_private_key {
@@ -1239,175 +3808,723 @@ impl JobService {
         };
 
         // 获取 known_hosts 配置
-        // 优先级：主机级 known_hosts > 全局 known_hosts 文件 > None
-        let known_hosts = if let Some(host_known_hosts) = &host.known_hosts {
+        // 优先级：主机级 known_hosts > 集中管理的 known_hosts > 全局 known_hosts 文件 > None
+        let (known_hosts, known_hosts_source) = if let Some(host_known_hosts) = &host.known_hosts {
             // 主机级配置（JSON 格式）
-            Some(host_known_hosts.0.clone())
-        } else if let Some(ref file_path) = ssh_config.known_hosts_file {
+            (Some(host_known_hosts.0.clone()), Some("host"))
+        } else if let Some(central) =
+            JobService::load_central_known_hosts(db, &host.address, host.port).await
+        {
+            (Some(central), Some("central"))
+        } else if let Some(file_path) = &ssh_config.known_hosts_file {
             // 从文件读取 known_hosts
-            JobService::load_known_hosts_file(file_path).await
+            let known_hosts = JobService::load_known_hosts_file(file_path).await;
+            let source = known_hosts.as_ref().map(|_| "file");
+            (known_hosts, source)
         } else {
-            None
+            (None, None)
         };
 
-        let ssh_exec_config = SshConfig {
-            host: host.address.clone(),
-            port: host.port as u16,
-            username,
-            auth,
-            connect_timeout_secs: ssh_config.connect_timeout_secs,
+        // 解析主机的输出编码，未配置或无效时按 UTF-8 处理
+        let output_encoding = host
+            .output_encoding
+            .as_deref()
+            .map(|value| {
+                value.parse::<OutputEncoding>().unwrap_or_else(|_| {
+                    warn!(
+                        host = %host.identifier,
+                        encoding = %value,
+                        "Invalid output_encoding value, using utf-8"
+                    );
+                    OutputEncoding::default()
+                })
+            })
+            .unwrap_or_default();
+
+        let config = SshConfig {
+            host: host.address.clone(),
+            port: host.port as u16,
+            username,
+            auth,
+            connect_timeout_secs: ssh_config.connect_timeout_secs,
             handshake_timeout_secs: ssh_config.handshake_timeout_secs,
-            command_timeout_secs: job
-                .timeout_secs
-                .unwrap_or(ssh_config.command_timeout_secs as i32)
-                as u64,
+            command_timeout_secs,
             host_key_verification,
             known_hosts,
+            output_encoding,
+            max_output_bytes: ssh_config.max_output_bytes,
         };
 
-        let client = SSHClient::new(ssh_exec_config);
+        ResolvedConnection {
+            config,
+            user_source,
+            credential_source,
+            known_hosts_source,
+        }
+    }
 
-        // 创建进度回调用于增量输出推送
-        let job_id_for_callback = job.id;
-        let task_id_for_callback = task.id;
-        let event_bus_for_callback = event_bus.clone();
+    /// 生成任务执行上下文快照（只记录认证方式类型，不含凭据）
+    fn execution_snapshot(
+        executor: &dyn CommandExecutor,
+        connection: &SshConfig,
+        payload: &ExecutionPayload,
+        user_source: &str,
+        credential_source: &str,
+        known_hosts_source: Option<&str>,
+    ) -> ExecutionContextSnapshot {
+        let environment = executor.environment(payload);
+        ExecutionContextSnapshot {
+            executor: executor.name().to_string(),
+            host: connection.host.clone(),
+            port: connection.port,
+            user: connection.username.clone(),
+            user_source: user_source.to_string(),
+            auth_method: match connection.auth {
+                SshAuth::Password { .. } => "password",
+                SshAuth::Key { .. } => "private_key",
+            }
+            .to_string(),
+            credential_source: credential_source.to_string(),
+            ssh_options: SshOptionsSnapshot {
+                connect_timeout_secs: connection.connect_timeout_secs,
+                handshake_timeout_secs: connection.handshake_timeout_secs,
+                command_timeout_secs: connection.command_timeout_secs,
+                host_key_verification: connection.host_key_verification.clone(),
+                known_hosts_source: known_hosts_source.map(str::to_string),
+                output_encoding: connection.output_encoding,
+            },
+            interpreter: environment.interpreter,
+            script_path: match payload {
+                ExecutionPayload::Command(_) => None,
+                ExecutionPayload::Script { path, .. }
+                | ExecutionPayload::ScriptFile { path, .. } => path.clone(),
+            },
+            env: ExecutionContextSnapshot::mask_env(environment.env),
+            captured_at: chrono::Utc::now(),
+        }
+    }
 
-        let progress_callback = std::sync::Arc::new(move |output: String, is_complete: bool| {
-            // 脱敏输出
-            let masked_output = crate::realtime::DataMasker::mask_output(&output);
+    /// 记录任务执行上下文快照（失败不影响任务执行）
+    async fn record_execution_context(
+        db: &Pool<Postgres>,
+        task_id: Uuid,
+        snapshot: &ExecutionContextSnapshot,
+    ) {
+        if let Err(e) = sqlx::query("UPDATE tasks SET execution_context = $1 WHERE id = $2")
+            .bind(Json(snapshot))
+            .bind(task_id)
+            .execute(db)
+            .await
+        {
+            warn!(error = %e, task_id = %task_id, "Failed to record task execution context");
+        }
+    }
 
-            // 发布增量输出更新事件
-            let _ =
-                event_bus_for_callback.publish(crate::realtime::RealtimeEvent::TaskOutputUpdate {
-                    task_id: task_id_for_callback,
-                    job_id: job_id_for_callback,
-                    output: masked_output,
-                    is_complete,
-                });
-        });
+    /// 解析清单采集输出并替换主机的软件包清单（失败只记录日志，不影响任务结果）
+    async fn store_package_inventory(
+        db: &Pool<Postgres>,
+        host_id: Uuid,
+        job_id: Uuid,
+        output: &str,
+    ) {
+        let Some(inventory) = package_inventory::parse_inventory(output) else {
+            warn!(host_id = %host_id, job_id = %job_id, "Inventory output has no package list");
+            return;
+        };
+        let count = inventory.packages.len();
+        let repo = crate::repository::AssetRepository::new(db.clone());
+        match repo
+            .replace_host_packages(host_id, job_id, &inventory)
+            .await
+        {
+            Ok(()) => info!(host_id = %host_id, packages = count, "Host package inventory stored"),
+            Err(e) => {
+                warn!(error = %e, host_id = %host_id, "Failed to store host package inventory")
+            }
+        }
+    }
 
-        // 根据作业类型执行不同的命令
-        let result = match job.job_type {
-            JobType::Command => {
-                let command = job
-                    .command
-                    .as_ref()
-                    .ok_or_else(|| AppError::validation("Command job must have a command"))?;
-                client
-                    .execute_with_progress(command, Some(progress_callback))
-                    .await
+    /// 根据执行结果与作业的退出码分类规则确定任务状态、失败原因与失败说明
+    fn classify_result(
+        result: &ExecutionResult,
+        rules: Option<&ExitCodeRules>,
+    ) -> (TaskStatus, Option<FailureReason>, Option<&'static str>) {
+        if result.timed_out {
+            return (
+                TaskStatus::Timeout,
+                Some(FailureReason::CommandTimeout),
+                Some("Command timed out"),
+            );
+        }
+        if result.output_limit_exceeded {
+            return (
+                TaskStatus::Failed,
+                Some(FailureReason::OutputLimitExceeded),
+                Some("Command output exceeded size limit, command terminated"),
+            );
+        }
+        match exit_code_rules::classify(rules, result.exit_code) {
+            ExitCodeOutcome::Succeeded => (TaskStatus::Succeeded, None, None),
+            ExitCodeOutcome::Warning => (TaskStatus::Warning, None, None),
+            ExitCodeOutcome::Failed => {
+                (TaskStatus::Failed, Some(FailureReason::CommandFailed), Some("Command failed"))
             }
-            JobType::Script => {
-                let script = job
-                    .script
-                    .as_ref()
-                    .ok_or_else(|| AppError::validation("Script job must have a script"))?;
-                // 对于脚本作业，暂时使用普通的 execute_script（不带进度）
-                // TODO: 后续可以为 execute_script 也添加进度回调支持
-                client
-                    .execute_script(script, job.script_path.as_deref())
-                    .await
+        }
+    }
+
+    /// 等待作业取消信号；信号发送端已释放时视为不会取消
+    async fn wait_cancelled(mut cancel_rx: watch::Receiver<bool>) {
+        loop {
+            if *cancel_rx.borrow_and_update() {
+                return;
             }
-            JobType::Build => {
-                // 构建作业暂不支持 SSH 执行
-                Err(AppError::validation("Build jobs are not supported for SSH execution"))
+            if cancel_rx.changed().await.is_err() {
+                std::future::pending::<()>().await;
             }
-        };
+        }
+    }
 
-        match result {
-            Ok(exec_result) => {
-                let (status, failure_reason, failure_message) = if exec_result.timed_out {
-                    (
-                        TaskStatus::Timeout,
-                        Some(FailureReason::CommandTimeout),
-                        Some("Command timed out"),
-                    )
-                } else if exec_result.exit_code == 0 {
-                    (TaskStatus::Succeeded, None, None)
-                } else {
-                    (TaskStatus::Failed, Some(FailureReason::CommandFailed), Some("Command failed"))
-                };
+    /// 在同一事务中执行状态变更并写入事件发件箱，返回影响行数
+    ///
+    /// 未更新任何行（状态已被并发修改）时不写入事件
+    async fn update_with_events(
+        db: &Pool<Postgres>,
+        event_bus: &EventBus,
+        query: Query<'_, Postgres, PgArguments>,
+        events: Vec<RealtimeEvent>,
+        failure: &'static str,
+    ) -> Result<u64> {
+        Self::update_in_transaction(db, event_bus, None, query, events, failure).await
+    }
 
-                // 使用脱敏模块处理输出
-                let output_archive = OutputArchive::default_config();
+    /// 更新任务结果，并在同一事务中按任务表重新计算所属作业的计数
+    async fn update_task_with_events(
+        db: &Pool<Postgres>,
+        event_bus: &EventBus,
+        job_id: Uuid,
+        query: Query<'_, Postgres, PgArguments>,
+        events: Vec<RealtimeEvent>,
+        failure: &'static str,
+    ) -> Result<u64> {
+        Self::update_in_transaction(db, event_bus, Some(job_id), query, events, failure).await
+    }
 
-                // 合并 stdout 和 stderr
-                let full_output = if exec_result.stderr.is_empty() {
-                    exec_result.stdout.clone()
-                } else if exec_result.stdout.is_empty() {
-                    exec_result.stderr.clone()
+    async fn update_in_transaction(
+        db: &Pool<Postgres>,
+        event_bus: &EventBus,
+        counted_job: Option<Uuid>,
+        query: Query<'_, Postgres, PgArguments>,
+        events: Vec<RealtimeEvent>,
+        failure: &'static str,
+    ) -> Result<u64> {
+        let mut tx = db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        // 与 cancel_job 一致先锁作业行再改任务，避免死锁
+        if let Some(job_id) = counted_job {
+            Self::lock_job(&mut tx, job_id).await?;
+        }
+
+        let updated = query
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "{}", failure);
+                AppError::database(failure)
+            })?
+            .rows_affected();
+
+        if updated > 0 {
+            if let Some(job_id) = counted_job {
+                Self::refresh_job_counts(&mut tx, job_id).await?;
+            }
+            for event in &events {
+                Self::enqueue_with_watchers(&mut tx, event).await?;
+            }
+        }
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+
+        if updated > 0 {
+            event_bus.notify_outbox();
+        }
+        Ok(updated)
+    }
+
+    /// 锁定作业行，串行化同一作业的计数更新
+    async fn lock_job(conn: &mut sqlx::PgConnection, job_id: Uuid) -> Result<()> {
+        sqlx::query("SELECT id FROM jobs WHERE id = $1 FOR UPDATE")
+            .bind(job_id)
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job_id, "Failed to lock job");
+                AppError::database("Failed to lock job")
+            })?;
+        Ok(())
+    }
+
+    /// 按任务表重新计算作业的任务计数
+    ///
+    /// 调用方须已在同一事务中锁定作业行（`lock_job`），
+    /// 这样并发完成的任务在锁释放后看到彼此已提交的状态，计数不会丢失
+    async fn refresh_job_counts(
+        conn: &mut sqlx::PgConnection,
+        job_id: Uuid,
+    ) -> Result<JobTaskCounts> {
+        sqlx::query_as::<_, JobTaskCounts>(REFRESH_JOB_COUNTS_SQL)
+            .bind(job_id)
+            .fetch_one(conn)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job_id, "Failed to refresh job task counts");
+                AppError::database("Failed to update job task counts")
+            })
+    }
+
+    /// 按任务表修复历史作业的计数（服务重启等原因导致计数与任务状态不一致时使用），
+    /// 返回被修正的作业数
+    pub async fn repair_job_counts(db: &Pool<Postgres>) -> Result<u64> {
+        let repaired = sqlx::query(
+            r#"
+            UPDATE jobs j
+            SET succeeded_tasks = c.succeeded,
+                warning_tasks = c.warning,
+                failed_tasks = c.failed,
+                timeout_tasks = c.timeout,
+                cancelled_tasks = c.cancelled,
+                skipped_tasks = c.skipped
+            FROM (
+                SELECT job_id,
+                    COUNT(*) FILTER (WHERE status = 'succeeded')::int AS succeeded,
+                    COUNT(*) FILTER (WHERE status = 'warning')::int AS warning,
+                    COUNT(*) FILTER (WHERE status = 'failed')::int AS failed,
+                    COUNT(*) FILTER (WHERE status = 'timeout')::int AS timeout,
+                    COUNT(*) FILTER (WHERE status = 'cancelled')::int AS cancelled,
+                    COUNT(*) FILTER (WHERE status = 'skipped')::int AS skipped
+                FROM tasks
+                GROUP BY job_id
+            ) c
+            WHERE j.id = c.job_id
+              AND (j.succeeded_tasks, j.warning_tasks, j.failed_tasks, j.timeout_tasks,
+                  j.cancelled_tasks, j.skipped_tasks)
+                  IS DISTINCT FROM
+                  (c.succeeded, c.warning, c.failed, c.timeout, c.cancelled, c.skipped)
+            "#,
+        )
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to repair job task counts");
+            AppError::database("Failed to repair job task counts")
+        })?
+        .rows_affected();
+
+        info!(repaired = repaired, "Job task counts repaired");
+        Ok(repaired)
+    }
+
+    /// 写入事件发件箱，并在同一事务中为关注者写入定向通知
+    async fn enqueue_with_watchers(
+        conn: &mut sqlx::PgConnection,
+        event: &RealtimeEvent,
+    ) -> Result<()> {
+        outbox::enqueue(&mut *conn, event).await?;
+        for notification in Self::watch_notifications(conn, event).await? {
+            outbox::enqueue(&mut *conn, &notification).await?;
+        }
+        Ok(())
+    }
+
+    /// 生成事件对应的关注者通知
+    ///
+    /// 作业状态变更通知关注该作业、其目标主机或来源模板的用户，每个用户只通知一次；
+    /// 主机维护状态变更通知关注该主机的用户
+    async fn watch_notifications(
+        conn: &mut sqlx::PgConnection,
+        event: &RealtimeEvent,
+    ) -> Result<Vec<RealtimeEvent>> {
+        let (watchers, job_id, old_status, new_status) = match event {
+            RealtimeEvent::JobStatusChanged {
+                job_id,
+                old_status,
+                new_status,
+            } => {
+                // 同一用户命中多个关注对象时按枚举顺序取最具体的一个（作业 > 主机 > 模板）
+                let watchers = sqlx::query_as::<_, (Uuid, WatchTargetType, Uuid)>(
+                    r#"
+                    SELECT DISTINCT ON (w.user_id) w.user_id, w.target_type, w.target_id
+                    FROM watches w
+                    JOIN jobs j ON j.id = $1
+                    WHERE (w.target_type = 'job' AND w.target_id = j.id)
+                       OR (w.target_type = 'host' AND j.target_hosts ? w.target_id::text)
+                       OR (w.target_type = 'template' AND w.target_id = j.template_id)
+                    ORDER BY w.user_id, w.target_type
+                    "#,
+                )
+                .bind(job_id)
+                .fetch_all(&mut *conn)
+                .await;
+                (watchers, Some(*job_id), Some(old_status.clone()), new_status.clone())
+            }
+            RealtimeEvent::HostMaintenanceChanged {
+                host_id,
+                in_maintenance,
+                ..
+            } => {
+                let watchers = sqlx::query_as::<_, (Uuid, WatchTargetType, Uuid)>(
+                    "SELECT user_id, target_type, target_id FROM watches WHERE target_type = 'host' AND target_id = $1",
+                )
+                .bind(host_id)
+                .fetch_all(&mut *conn)
+                .await;
+                let new_status = if *in_maintenance {
+                    "maintenance"
                 } else {
-                    format!("{}\n{}", exec_result.stdout, exec_result.stderr)
+                    "active"
                 };
+                (watchers, None, None, new_status.to_string())
+            }
+            _ => return Ok(Vec::new()),
+        };
 
-                // 脱敏并生成摘要和明细
-                let (output_summary, output_detail) = output_archive.process_output(&full_output);
+        let watchers = watchers.map_err(|e| {
+            error!(error = %e, "Failed to fetch watchers");
+            AppError::database("Failed to fetch watchers")
+        })?;
+        if watchers.is_empty() {
+            return Ok(Vec::new());
+        }
 
-                sqlx::query(
-                    "UPDATE tasks SET status = $1, exit_code = $2, output_summary = $3, output_detail = $4, failure_reason = $5, failure_message = $6, completed_at = NOW(), duration_secs = $7 WHERE id = $8"
-                )
-                .bind(&status)
-                .bind(exec_result.exit_code)
-                .bind(&output_summary)
-                .bind(&output_detail)
-                .bind(&failure_reason)
-                .bind(failure_message)
-                .bind(exec_result.duration_secs as i64)
-                .bind(task.id)
-                .execute(&db)
-                .await
-                .map_err(|e| {
-                    error!(error = %e, "Failed to update task result");
-                    AppError::database("Failed to update task")
-                })?;
+        // 通知模板可引用作业名称与运行时长
+        let (job_name, duration_secs) = match job_id {
+            Some(job_id) => sqlx::query_as::<_, (String, Option<i64>)>(
+                "SELECT name, EXTRACT(EPOCH FROM (COALESCE(completed_at, NOW()) - started_at))::BIGINT FROM jobs WHERE id = $1",
+            )
+            .bind(job_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job_id, "Failed to fetch job for watch notifications");
+                AppError::database("Failed to fetch job")
+            })?
+            .map_or((None, None), |(name, duration)| (Some(name), duration)),
+            None => (None, None),
+        };
+        Ok(watchers
+            .into_iter()
+            .map(|(user_id, target_type, target_id)| RealtimeEvent::WatchNotification {
+                user_id,
+                target_type: target_type.to_string(),
+                target_id,
+                job_id,
+                old_status: old_status.clone(),
+                new_status: new_status.clone(),
+                job_name: job_name.clone(),
+                duration_secs,
+            })
+            .collect())
+    }
 
-                // 发布任务状态变更事件：running -> final status
-                let status_str = status.to_string();
-                let _ = event_bus.publish(crate::realtime::RealtimeEvent::TaskStatusChanged {
-                    task_id: task.id,
-                    job_id: job.id,
-                    old_status: "running".to_string(),
-                    new_status: status_str.clone(),
-                });
+    /// 对主机执行分阶段连接测试（DNS、TCP、握手、认证、简单命令），不创建作业
+    pub async fn test_host_connection(&self, host: &Host) -> ConnectionTestReport {
+        let connection = Self::resolve_connection(
+            &self.db,
+            &self.ssh_config,
+            host,
+            None,
+            connection_test::CONNECTION_TEST_COMMAND_TIMEOUT_SECS,
+        )
+        .await;
+
+        let diagnostics = DiagnosticsCollector::new();
+        let started = std::time::Instant::now();
+        // 与作业执行相同，按主机连接方式在容器或 Pod 内执行测试命令
+        let result = match target::executor_for_host(host, self.executor.clone()) {
+            Ok(executor) => {
+                executor
+                    .execute(ExecutionRequest {
+                        connection: connection.config,
+                        payload: ExecutionPayload::Command(
+                            connection_test::CONNECTION_TEST_COMMAND.to_string(),
+                        ),
+                        progress: None,
+                        diagnostics: diagnostics.clone(),
+                    })
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        let total_ms = started.elapsed().as_millis() as u64;
 
-                // 发布任务输出更新事件
-                let _ = event_bus.publish(crate::realtime::RealtimeEvent::TaskOutputUpdate {
-                    task_id: task.id,
-                    job_id: job.id,
-                    output: output_summary.clone(),
-                    is_complete: true,
-                });
+        info!(
+            host = %host.identifier,
+            executor = self.executor.name(),
+            success = result.as_ref().is_ok_and(|r| r.is_success()),
+            total_ms,
+            "Host connection test finished"
+        );
+        connection_test::build_report(host, &diagnostics.timings(), &result, total_ms)
+    }
 
-                Ok(())
+    /// 使用指定认证方式（而非主机已存储的凭据）登录主机并执行测试命令，用于凭据轮换验证
+    pub async fn verify_host_auth(&self, host: &Host, auth: SshAuth) -> Result<()> {
+        let mut connection = Self::resolve_connection(
+            &self.db,
+            &self.ssh_config,
+            host,
+            None,
+            connection_test::CONNECTION_TEST_COMMAND_TIMEOUT_SECS,
+        )
+        .await;
+        connection.config.auth = auth;
+
+        let result = self
+            .executor
+            .execute(ExecutionRequest {
+                connection: connection.config,
+                payload: ExecutionPayload::Command(
+                    connection_test::CONNECTION_TEST_COMMAND.to_string(),
+                ),
+                progress: None,
+                diagnostics: DiagnosticsCollector::new(),
+            })
+            .await?;
+        if result.timed_out {
+            return Err(AppError::SshExecutionError("Test command timed out".to_string()));
+        }
+        if result.exit_code != 0 {
+            return Err(AppError::SshExecutionError(format!(
+                "Test command exited with {}",
+                result.exit_code
+            )));
+        }
+        Ok(())
+    }
+
+    /// 仅与主机完成 SSH 握手，返回服务器提供的主机密钥与连接使用的固定指纹（未固定时为空）
+    pub async fn probe_host_key(&self, host: &Host) -> Result<(PresentedHostKey, Option<String>)> {
+        let connection = Self::resolve_connection(
+            &self.db,
+            &self.ssh_config,
+            host,
+            None,
+            connection_test::CONNECTION_TEST_COMMAND_TIMEOUT_SECS,
+        )
+        .await;
+        let config = connection.config;
+        let pinned = config
+            .known_hosts
+            .as_ref()
+            .and_then(|hosts| hosts.get(&format!("{}:{}", config.host, config.port)))
+            .cloned();
+
+        let presented = SSHClient::new(config).probe_host_key().await?;
+        Ok((presented, pinned))
+    }
+
+    /// 后台执行作业所需的依赖快照
+    fn execution_context(&self) -> JobExecutionContext {
+        JobExecutionContext {
+            db: self.db.clone(),
+            concurrency_controller: self.concurrency_controller.clone(),
+            ssh_config: self.ssh_config.clone(),
+            event_bus: self.event_bus.clone(),
+            executor: self.executor.clone(),
+            cancellations: self.cancellations.clone(),
+            task_cancellations: self.task_cancellations.clone(),
+            blob_store: self.blob_store.clone(),
+            chain_signal: self.chain_signal.clone(),
+            dispatch_pool: self.dispatch_pool.clone(),
+            dispatch_signal: self.dispatch_signal.clone(),
+            audit_service: self.audit_service.clone(),
+            budget: self.budget.clone(),
+            network_backoff: self.network_backoff.clone(),
+            output: self.output.clone(),
+        }
+    }
+
+    /// 调度作业执行：写入派发队列，未启用派发队列时直接在后台执行
+    async fn dispatch_job(&self, job_id: Uuid) -> Result<()> {
+        if self.dispatch_pool.is_some() {
+            job_dispatch::enqueue(&self.db, job_id).await?;
+        }
+        self.start_dispatch(job_id);
+        Ok(())
+    }
+
+    /// 在作业转为待执行的事务中写入派发队列（未启用派发队列时不写入），提交后调用 `start_dispatch`
+    async fn enqueue_dispatch(&self, conn: &mut sqlx::PgConnection, job_id: Uuid) -> Result<()> {
+        if self.dispatch_pool.is_some() {
+            job_dispatch::enqueue(conn, job_id).await?;
+        }
+        Ok(())
+    }
+
+    /// 启动已入队的作业：通知工作池认领，未启用派发队列时直接在后台执行
+    fn start_dispatch(&self, job_id: Uuid) {
+        if self.dispatch_pool.is_some() {
+            self.dispatch_signal.notify_one();
+        } else {
+            Self::spawn_with_context(job_id, self.execution_context());
+        }
+    }
+
+    /// 等待有作业入队或工作空闲的通知
+    pub async fn dispatch_notified(&self) {
+        self.dispatch_signal.notified().await;
+    }
+
+    /// 按空闲工作数认领派发队列中的作业并在后台执行，返回认领的作业数
+    pub async fn dispatch_queued_jobs(&self) -> Result<usize> {
+        let Some(pool) = &self.dispatch_pool else {
+            return Ok(0);
+        };
+
+        let mut claimed = 0;
+        while let Ok(permit) = pool.workers.clone().try_acquire_owned() {
+            let Some(dispatch) = job_dispatch::claim(&self.db, &pool.worker_id).await? else {
+                break;
+            };
+            claimed += 1;
+            if dispatch.attempts > 1 {
+                warn!(
+                    job_id = %dispatch.job_id,
+                    attempts = dispatch.attempts,
+                    "Resuming job whose previous dispatch lease expired"
+                );
             }
-            Err(e) => {
-                error!(error = %e, "Failed to execute command");
-                // 根据错误类型分类失败原因
-                let failure_reason = e.to_ssh_failure_reason();
-                sqlx::query(
-                    "UPDATE tasks SET status = 'failed', failure_reason = $1, failure_message = $2, completed_at = NOW() WHERE id = $3"
+
+            pool.active.insert(dispatch.id, dispatch.job_id);
+            let active = ActiveDispatch {
+                pool: pool.clone(),
+                id: dispatch.id,
+                _permit: permit,
+            };
+            let ctx = self.execution_context();
+            request_id::spawn(async move {
+                let job_id = dispatch.job_id;
+                if let Err(e) = Self::execute_job(job_id, ctx.clone()).await {
+                    error!(error = %e, job_id = %job_id, "Failed to execute job");
+                }
+                // 删除失败时记录不再续约，租约过期后重新入队，届时作业已结束不会重复执行
+                let _ = job_dispatch::complete(&ctx.db, dispatch.id).await;
+                drop(active);
+                ctx.dispatch_signal.notify_one();
+            });
+        }
+        Ok(claimed)
+    }
+
+    /// 派发记录的续约间隔
+    pub fn dispatch_renewal_interval(&self) -> Option<std::time::Duration> {
+        self.dispatch_pool
+            .as_ref()
+            .map(|pool| std::time::Duration::from_secs((pool.lease_secs / 3).max(1)))
+    }
+
+    /// 为本实例执行中的作业续约
+    pub async fn renew_dispatch_leases(&self) -> Result<u64> {
+        let Some(pool) = &self.dispatch_pool else {
+            return Ok(0);
+        };
+        let ids: Vec<Uuid> = pool.active.iter().map(|entry| *entry.key()).collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        job_dispatch::heartbeat(&self.db, &ids).await
+    }
+
+    /// 将租约过期（认领的实例已崩溃）的作业重新入队，返回重新入队的作业数
+    pub async fn recover_stale_dispatches(&self) -> Result<usize> {
+        let Some(pool) = &self.dispatch_pool else {
+            return Ok(0);
+        };
+        let recovered = job_dispatch::recover_stale(&self.db, pool.lease_secs).await?;
+        for job_id in &recovered {
+            warn!(job_id = %job_id, "Requeued job after its dispatch lease expired");
+        }
+        if !recovered.is_empty() {
+            self.dispatch_signal.notify_one();
+        }
+        Ok(recovered.len())
+    }
+
+    /// 找回创建事务提交后、入队前实例崩溃而未派发的作业，返回找回的作业数
+    ///
+    /// 遗漏的作业可能尚未经过审批检查：重新评估风险，需要审批的转为等待审批（不自动审批），
+    /// 其余入队派发
+    pub async fn recover_undispatched_jobs(&self) -> Result<usize> {
+        let Some(pool) = &self.dispatch_pool else {
+            return Ok(0);
+        };
+        let mut recovered = 0;
+        for job_id in job_dispatch::find_orphaned(&self.db, pool.lease_secs).await? {
+            match self.readmit_job(job_id).await {
+                Ok(()) => {
+                    warn!(job_id = %job_id, "Recovered job that was never dispatched");
+                    recovered += 1;
+                }
+                Err(e) => {
+                    error!(error = %e, job_id = %job_id, "Failed to recover undispatched job");
+                }
+            }
+        }
+        Ok(recovered)
+    }
+
+    /// 对未派发的作业重新进行审批检查后派发
+    async fn readmit_job(&self, job_id: Uuid) -> Result<()> {
+        let Some(mut job) = self.fetch_job(job_id).await? else {
+            return Ok(());
+        };
+        if let Some(ref approval_svc) = self.approval_service {
+            let target_hosts = sqlx::query_as::<_, Host>(
+                "SELECT h.* FROM assets_hosts h JOIN tasks t ON t.host_id = h.id WHERE t.job_id = $1",
+            )
+            .bind(job_id)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job_id, "Failed to fetch job hosts");
+                AppError::database("Failed to fetch hosts")
+            })?;
+            let risk_level = match job.template_id {
+                Some(template_id) => sqlx::query_scalar::<_, String>(
+                    "SELECT risk_level FROM job_templates WHERE id = $1",
                 )
-                .bind(&failure_reason)
-                .bind(e.to_string())
-                .bind(task.id)
-                .execute(&db)
+                .bind(template_id)
+                .fetch_optional(&self.db)
                 .await
                 .map_err(|e| {
-                    error!(error = %e, "Failed to update task failure");
-                    AppError::database("Failed to update task")
-                })?;
-
-                // 发布任务状态变更事件：running -> failed
-                let _ = event_bus.publish(crate::realtime::RealtimeEvent::TaskStatusChanged {
-                    task_id: task.id,
-                    job_id: job.id,
-                    old_status: "running".to_string(),
-                    new_status: "failed".to_string(),
-                });
-
-                Err(e)
+                    error!(error = %e, template_id = %template_id, "Failed to fetch job template");
+                    AppError::database("Failed to fetch job template")
+                })?,
+                None => None,
+            };
+            let assessment = approval_svc
+                .assess_job_risk(&job, &target_hosts, risk_level.as_deref())
+                .await;
+            if assessment.requires_approval() {
+                return self
+                    .hold_for_approval(approval_svc, &mut job, &assessment)
+                    .await;
             }
         }
+        self.dispatch_job(job_id).await
+    }
+
+    fn spawn_with_context(job_id: Uuid, ctx: JobExecutionContext) {
+        request_id::spawn(async move {
+            if let Err(e) = Self::execute_job(job_id, ctx).await {
+                error!(error = %e, job_id = %job_id, "Failed to execute job");
+            }
+        });
     }
 
     /// 计算作业最终状态
@@ -1426,33 +4543,556 @@ impl JobService {
         }
     }
 
-    // ==================== 模板管理 ====================
+    // ==================== 主机维护 ====================
 
-    /// 创建作业模板
-    #[instrument(skip(self, request))]
-    pub async fn create_job_template(
-        &self,
-        request: crate::models::approval::CreateJobTemplateRequest,
-        created_by: Uuid,
-    ) -> Result<crate::models::approval::JobTemplate> {
-        info!(name = %request.name, "Creating job template");
+    /// 通知主机维护状态变更（附带有任务等待该主机的作业）
+    pub async fn publish_host_maintenance_change(&self, host: &Host) -> Result<()> {
+        let mut conn = self.db.acquire().await.map_err(|e| {
+            error!(error = %e, "Failed to acquire connection");
+            AppError::database("Failed to acquire connection")
+        })?;
+        Self::enqueue_host_maintenance_change(&mut conn, host).await?;
+        self.event_bus.notify_outbox();
+        Ok(())
+    }
 
-        let template = sqlx::query_as::<_, crate::models::approval::JobTemplate>(
-            r#"
-            INSERT INTO job_templates (
-                id, name, description, template_type,
-                template_content, parameters_schema,
-                default_timeout_secs, default_retry_times, default_concurrent_limit,
-                risk_level, requires_approval,
-                applicable_environments, applicable_groups,
-                is_active, created_by
+    /// 将主机维护状态变更事件写入发件箱
+    async fn enqueue_host_maintenance_change(
+        conn: &mut sqlx::PgConnection,
+        host: &Host,
+    ) -> Result<()> {
+        let job_ids = sqlx::query_scalar::<_, Uuid>(
+            "SELECT DISTINCT job_id FROM tasks WHERE host_id = $1 AND status IN ('pending', 'waiting_maintenance')",
+        )
+        .bind(host.id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch jobs affected by host maintenance");
+            AppError::database("Failed to fetch tasks")
+        })?;
+
+        let event = RealtimeEvent::HostMaintenanceChanged {
+            host_id: host.id,
+            job_ids,
+            in_maintenance: host.is_under_maintenance(chrono::Utc::now()),
+            maintenance_until: host.maintenance_until,
+            reason: host.maintenance_reason.clone(),
+        };
+        Self::enqueue_with_watchers(conn, &event).await
+    }
+
+    /// 释放等待维护的任务
+    ///
+    /// 先结束已到期的维护窗口，再将主机已不在维护中的任务改回 pending 并续跑所属作业。
+    /// 作业仍有 pending/running 任务时（本轮执行尚未结束）留到下次处理，避免并发续跑
+    pub async fn release_maintenance_tasks(&self) -> Result<usize> {
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        let expired = sqlx::query_as::<_, Host>(
+            r#"
+            UPDATE assets_hosts
+            SET status = 'active',
+                maintenance_until = NULL,
+                maintenance_reason = NULL,
+                maintenance_started_at = NULL,
+                maintenance_set_by = NULL,
+                updated_at = NOW()
+            WHERE status = 'maintenance' AND maintenance_until <= NOW()
+            RETURNING *
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to expire host maintenance");
+            AppError::database("Failed to update hosts")
+        })?;
+
+        for host in &expired {
+            info!(host_id = %host.id, host = %host.identifier, "Host maintenance window ended");
+            Self::enqueue_host_maintenance_change(&mut tx, host).await?;
+        }
+
+        let released = sqlx::query_as::<_, (Uuid, Uuid)>(&format!(
+            r#"
+            UPDATE tasks t
+            SET status = 'pending'
+            WHERE t.status = 'waiting_maintenance'
+              AND t.host_id NOT IN (SELECT id FROM assets_hosts WHERE {})
+              AND NOT EXISTS (
+                  SELECT 1 FROM tasks o
+                  WHERE o.job_id = t.job_id AND o.status IN ('pending', 'running')
+              )
+            RETURNING t.id, t.job_id
+            "#,
+            HOST_UNDER_MAINTENANCE
+        ))
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to release tasks waiting for maintenance");
+            AppError::database("Failed to update task status")
+        })?;
+
+        let mut job_ids = Vec::new();
+        for (task_id, job_id) in &released {
+            let event = RealtimeEvent::TaskStatusChanged {
+                task_id: *task_id,
+                job_id: *job_id,
+                old_status: "waiting_maintenance".to_string(),
+                new_status: "pending".to_string(),
+            };
+            outbox::enqueue(&mut *tx, &event).await?;
+            if !job_ids.contains(job_id) {
+                job_ids.push(*job_id);
+            }
+        }
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+        for host in &expired {
+            cache::HOSTS.invalidate(&host.id);
+        }
+        if !expired.is_empty() || !released.is_empty() {
+            self.event_bus.notify_outbox();
+        }
+
+        for job_id in job_ids {
+            info!(job_id = %job_id, "Resuming job after host maintenance");
+            self.dispatch_job(job_id).await?;
+        }
+
+        Ok(released.len())
+    }
+
+    // ==================== 标签管理 ====================
+
+    /// 校验作业标签
+    ///
+    /// 带命名空间的标签必须使用已登记的命名空间：命名空间配置了取值正则时按正则校验，
+    /// 否则取值必须已登记；不含冒号的自由标签保持兼容
+    pub async fn validate_job_tags(&self, tags: &[String]) -> Result<()> {
+        for tag in tags {
+            let Some((namespace, value)) =
+                parse_job_tag(tag).map_err(|e| AppError::validation(&e))?
+            else {
+                continue;
+            };
+
+            let namespace_row = sqlx::query_as::<_, JobTagNamespace>(
+                "SELECT * FROM job_tag_namespaces WHERE name = $1",
+            )
+            .bind(namespace)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to fetch tag namespace");
+                AppError::database("Failed to fetch tag namespace")
+            })?
+            .ok_or_else(|| {
+                AppError::validation(&format!("Unknown tag namespace: {}", namespace))
+            })?;
+
+            let allowed = match namespace_row.value_pattern.as_deref() {
+                Some(pattern) => regex::Regex::new(pattern)
+                    .map(|re| re.is_match(value))
+                    .unwrap_or(false),
+                None => sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS(SELECT 1 FROM job_tags WHERE namespace = $1 AND value = $2)",
+                )
+                .bind(namespace)
+                .bind(value)
+                .fetch_one(&self.db)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to check job tag");
+                    AppError::database("Failed to check job tag")
+                })?,
+            };
+
+            if !allowed {
+                return Err(AppError::validation(&format!("Tag is not allowed: {}", tag)));
+            }
+        }
+        Ok(())
+    }
+
+    /// 查询标签命名空间列表
+    pub async fn list_tag_namespaces(&self) -> Result<Vec<JobTagNamespace>> {
+        sqlx::query_as::<_, JobTagNamespace>("SELECT * FROM job_tag_namespaces ORDER BY name")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to fetch tag namespaces");
+                AppError::database("Failed to fetch tag namespaces")
+            })
+    }
+
+    /// 创建标签命名空间
+    #[instrument(skip(self, request))]
+    pub async fn create_tag_namespace(
+        &self,
+        request: CreateJobTagNamespaceRequest,
+        created_by: Uuid,
+    ) -> Result<JobTagNamespace> {
+        validate_tag_namespace_name(&request.name).map_err(|e| AppError::validation(&e))?;
+        validate_value_pattern(request.value_pattern.as_deref())?;
+
+        let namespace = sqlx::query_as::<_, JobTagNamespace>(
+            r#"
+            INSERT INTO job_tag_namespaces (name, description, value_pattern, created_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(&request.name)
+        .bind(&request.description)
+        .bind(&request.value_pattern)
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            if e.as_database_error()
+                .is_some_and(|d| d.is_unique_violation())
+            {
+                AppError::validation("Tag namespace already exists")
+            } else {
+                error!(error = %e, "Failed to create tag namespace");
+                AppError::database("Failed to create tag namespace")
+            }
+        })?;
+
+        self.audit_service
+            .log_action_simple(
+                created_by,
+                AuditAction::JobTagCreate,
+                Some("job_tag_namespace"),
+                None,
+                Some(&format!("Tag namespace: {}", namespace.name)),
+                None,
+            )
+            .await?;
+
+        Ok(namespace)
+    }
+
+    /// 更新标签命名空间
+    #[instrument(skip(self, request))]
+    pub async fn update_tag_namespace(
+        &self,
+        name: &str,
+        request: UpdateJobTagNamespaceRequest,
+        updated_by: Uuid,
+    ) -> Result<JobTagNamespace> {
+        validate_value_pattern(request.value_pattern.as_deref())?;
+
+        let namespace = sqlx::query_as::<_, JobTagNamespace>(
+            r#"
+            UPDATE job_tag_namespaces
+            SET description = $2, value_pattern = $3, updated_at = NOW()
+            WHERE name = $1
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(&request.description)
+        .bind(&request.value_pattern)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to update tag namespace");
+            AppError::database("Failed to update tag namespace")
+        })?
+        .ok_or_else(|| AppError::not_found("Tag namespace not found"))?;
+
+        self.audit_service
+            .log_action_simple(
+                updated_by,
+                AuditAction::JobTagUpdate,
+                Some("job_tag_namespace"),
+                None,
+                Some(&format!("Tag namespace: {}", name)),
+                None,
+            )
+            .await?;
+
+        Ok(namespace)
+    }
+
+    /// 删除标签命名空间（同时删除其下登记的标签，已有作业上的标签不受影响）
+    #[instrument(skip(self))]
+    pub async fn delete_tag_namespace(&self, name: &str, deleted_by: Uuid) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM job_tag_namespaces WHERE name = $1")
+            .bind(name)
+            .execute(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to delete tag namespace");
+                AppError::database("Failed to delete tag namespace")
+            })?;
+
+        if deleted.rows_affected() == 0 {
+            return Err(AppError::not_found("Tag namespace not found"));
+        }
+
+        self.audit_service
+            .log_action_simple(
+                deleted_by,
+                AuditAction::JobTagDelete,
+                Some("job_tag_namespace"),
+                None,
+                Some(&format!("Tag namespace: {}", name)),
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 查询已登记的标签列表
+    pub async fn list_managed_tags(
+        &self,
+        filters: ManagedJobTagFilters,
+    ) -> Result<Vec<ManagedJobTag>> {
+        sqlx::query_as::<_, ManagedJobTag>(
+            r#"
+            SELECT * FROM job_tags
+            WHERE ($1::text IS NULL OR namespace = $1)
+            ORDER BY namespace, value
+            "#,
+        )
+        .bind(&filters.namespace)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch job tags");
+            AppError::database("Failed to fetch job tags")
+        })
+    }
+
+    /// 登记标签
+    #[instrument(skip(self, request))]
+    pub async fn create_managed_tag(
+        &self,
+        request: CreateManagedJobTagRequest,
+        created_by: Uuid,
+    ) -> Result<ManagedJobTag> {
+        validate_tag_namespace_name(&request.namespace).map_err(|e| AppError::validation(&e))?;
+        validate_tag_value(&request.value).map_err(|e| AppError::validation(&e))?;
+
+        let tag = sqlx::query_as::<_, ManagedJobTag>(
+            r#"
+            INSERT INTO job_tags (namespace, value, description, created_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(&request.namespace)
+        .bind(&request.value)
+        .bind(&request.description)
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(d) if d.is_unique_violation() => AppError::validation("Job tag already exists"),
+            Some(d) if d.is_foreign_key_violation() => {
+                AppError::validation(&format!("Unknown tag namespace: {}", request.namespace))
+            }
+            _ => {
+                error!(error = %e, "Failed to create job tag");
+                AppError::database("Failed to create job tag")
+            }
+        })?;
+
+        self.audit_service
+            .log_action_simple(
+                created_by,
+                AuditAction::JobTagCreate,
+                Some("job_tag"),
+                Some(tag.id),
+                Some(&format!("Job tag: {}", tag.tag())),
+                None,
+            )
+            .await?;
+
+        Ok(tag)
+    }
+
+    /// 更新标签描述
+    #[instrument(skip(self, request))]
+    pub async fn update_managed_tag(
+        &self,
+        tag_id: Uuid,
+        request: UpdateManagedJobTagRequest,
+        updated_by: Uuid,
+    ) -> Result<ManagedJobTag> {
+        let tag = sqlx::query_as::<_, ManagedJobTag>(
+            "UPDATE job_tags SET description = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+        )
+        .bind(tag_id)
+        .bind(&request.description)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to update job tag");
+            AppError::database("Failed to update job tag")
+        })?
+        .ok_or_else(|| AppError::not_found("Job tag not found"))?;
+
+        self.audit_service
+            .log_action_simple(
+                updated_by,
+                AuditAction::JobTagUpdate,
+                Some("job_tag"),
+                Some(tag.id),
+                Some(&format!("Job tag: {}", tag.tag())),
+                None,
+            )
+            .await?;
+
+        Ok(tag)
+    }
+
+    /// 删除已登记的标签（已有作业上的标签不受影响）
+    #[instrument(skip(self))]
+    pub async fn delete_managed_tag(&self, tag_id: Uuid, deleted_by: Uuid) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM job_tags WHERE id = $1")
+            .bind(tag_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to delete job tag");
+                AppError::database("Failed to delete job tag")
+            })?;
+
+        if deleted.rows_affected() == 0 {
+            return Err(AppError::not_found("Job tag not found"));
+        }
+
+        self.audit_service
+            .log_action_simple(
+                deleted_by,
+                AuditAction::JobTagDelete,
+                Some("job_tag"),
+                Some(tag_id),
+                Some("Deleted job tag"),
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 按标签与时间段聚合作业数量、成功率与执行时长
+    ///
+    /// 默认统计最近 90 天，时间段按 `interval` 截断
+    #[instrument(skip(self))]
+    pub async fn job_tag_report(&self, query: JobTagReportQuery) -> Result<Vec<JobTagReportRow>> {
+        let date_to = query.date_to.unwrap_or_else(chrono::Utc::now);
+        let date_from = query
+            .date_from
+            .unwrap_or_else(|| date_to - chrono::Duration::days(90));
+        if date_from >= date_to {
+            return Err(AppError::validation("date_from must be earlier than date_to"));
+        }
+
+        sqlx::query_as::<_, JobTagReportRow>(
+            r#"
+            SELECT
+                t.tag,
+                date_trunc($1, j.created_at) AS period_start,
+                COUNT(*) AS total_jobs,
+                COUNT(*) FILTER (WHERE j.status = 'completed') AS succeeded_jobs,
+                COUNT(*) FILTER (WHERE j.status = 'failed') AS failed_jobs,
+                COUNT(*) FILTER (WHERE j.status = 'partially_succeeded')
+                    AS partially_succeeded_jobs,
+                COUNT(*) FILTER (WHERE j.status = 'cancelled') AS cancelled_jobs,
+                COALESCE(
+                    COUNT(*) FILTER (WHERE j.status = 'completed')::float8
+                        / NULLIF(COUNT(*) FILTER (
+                            WHERE j.status IN ('completed', 'failed', 'partially_succeeded')
+                        ), 0),
+                    0
+                ) AS success_rate,
+                AVG(EXTRACT(EPOCH FROM (j.completed_at - j.started_at)))::float8
+                    AS avg_duration_secs,
+                MAX(EXTRACT(EPOCH FROM (j.completed_at - j.started_at)))::float8
+                    AS max_duration_secs
+            FROM jobs j
+            CROSS JOIN LATERAL jsonb_array_elements_text(j.tags) AS t(tag)
+            WHERE j.created_at >= $2
+              AND j.created_at < $3
+              AND ($4::text IS NULL OR t.tag LIKE $4 || ':%')
+              AND ($5::text IS NULL OR t.tag = $5)
+            GROUP BY t.tag, period_start
+            ORDER BY period_start, t.tag
+            "#,
+        )
+        .bind(query.interval.as_str())
+        .bind(date_from)
+        .bind(date_to)
+        .bind(&query.namespace)
+        .bind(&query.tag)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to build job tag report");
+            AppError::database("Failed to build job tag report")
+        })
+    }
+
+    // ==================== 模板管理 ====================
+
+    /// 创建作业模板
+    #[instrument(skip(self, request))]
+    pub async fn create_job_template(
+        &self,
+        request: crate::models::approval::CreateJobTemplateRequest,
+        created_by: Uuid,
+    ) -> Result<crate::models::approval::JobTemplate> {
+        info!(name = %request.name, "Creating job template");
+
+        if request.extends_template_id.is_none() && request.includes.is_empty() {
+            if request.template_content.trim().is_empty() {
+                return Err(AppError::validation("Template content is required"));
+            }
+        } else {
+            self.load_template_graph().await?.check_references(
+                None,
+                request.extends_template_id,
+                &request.includes,
+            )?;
+        }
+        if let Some(rules) = &request.exit_code_rules {
+            exit_code_rules::validate(rules)?;
+        }
+
+        let template = sqlx::query_as::<_, crate::models::approval::JobTemplate>(
+            r#"
+            INSERT INTO job_templates (
+                id, name, description, template_type,
+                template_content, parameters_schema,
+                default_timeout_secs, default_retry_times, default_concurrent_limit,
+                risk_level, requires_approval,
+                applicable_environments, applicable_groups,
+                is_active, created_by,
+                extends_template_id, prepend_content, append_content, includes,
+                exit_code_rules
             ) VALUES (
                 $1, $2, $3, $4,
                 $5, $6,
                 $7, $8, $9,
                 $10, $11,
                 $12, $13,
-                true, $14
+                true, $14,
+                $15, $16, $17, $18,
+                $19
             ) RETURNING *
             "#,
         )
@@ -1470,6 +5110,11 @@ impl JobService {
         .bind(&request.applicable_environments)
         .bind(&request.applicable_groups)
         .bind(created_by)
+        .bind(request.extends_template_id)
+        .bind(&request.prepend_content)
+        .bind(&request.append_content)
+        .bind(Json(&request.includes))
+        .bind(request.exit_code_rules.as_ref().map(Json))
         .fetch_one(&self.db)
         .await
         .map_err(|e| {
@@ -1515,6 +5160,31 @@ impl JobService {
         })
     }
 
+    /// 启用中模板的继承与组合关系
+    async fn load_template_graph(&self) -> Result<TemplateGraph> {
+        let templates = sqlx::query_as::<_, crate::models::approval::JobTemplate>(
+            "SELECT * FROM job_templates WHERE is_active = true",
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch job templates");
+            AppError::database("Failed to fetch job templates")
+        })?;
+        Ok(TemplateGraph::new(templates))
+    }
+
+    /// 展开模板的继承与组合，得到最终生效的模板
+    #[instrument(skip(self))]
+    pub async fn resolve_job_template(
+        &self,
+        template_id: Uuid,
+    ) -> Result<crate::models::approval::ResolvedJobTemplate> {
+        // 模板本身不存在时返回 404，引用的模板缺失时返回校验错误
+        self.get_job_template(template_id).await?;
+        self.load_template_graph().await?.resolve(template_id)
+    }
+
     /// 查询作业模板列表
     #[instrument(skip(self))]
     pub async fn list_job_templates(&self) -> Result<Vec<crate::models::approval::JobTemplate>> {
@@ -1539,7 +5209,29 @@ impl JobService {
     ) -> Result<crate::models::approval::JobTemplate> {
         info!(template_id = %template_id, "Updating job template");
 
-        // 构建动态更新查询
+        if let Some(rules) = &request.exit_code_rules {
+            exit_code_rules::validate(rules)?;
+        }
+
+        if request.extends_template_id.is_some()
+            || request.clear_extends
+            || request.includes.is_some()
+        {
+            let current = self.get_job_template(template_id).await?;
+            let extends = if request.clear_extends {
+                None
+            } else {
+                request.extends_template_id.or(current.extends_template_id)
+            };
+            let includes = request.includes.clone().unwrap_or(current.includes.0);
+            self.load_template_graph().await?.check_references(
+                Some(template_id),
+                extends,
+                &includes,
+            )?;
+        }
+
+        // 构建动态更新查询
         let mut updates = Vec::new();
         let mut count = 0;
 
@@ -1579,184 +5271,1256 @@ impl JobService {
             count += 1;
             updates.push(format!("requires_approval = ${}", count));
         }
-        if request.applicable_environments.is_some() {
-            count += 1;
-            updates.push(format!("applicable_environments = ${}", count));
+        if request.applicable_environments.is_some() {
+            count += 1;
+            updates.push(format!("applicable_environments = ${}", count));
+        }
+        if request.applicable_groups.is_some() {
+            count += 1;
+            updates.push(format!("applicable_groups = ${}", count));
+        }
+        if request.clear_extends {
+            updates.push("extends_template_id = NULL".to_string());
+        } else if request.extends_template_id.is_some() {
+            count += 1;
+            updates.push(format!("extends_template_id = ${}", count));
+        }
+        if request.prepend_content.is_some() {
+            count += 1;
+            updates.push(format!("prepend_content = ${}", count));
+        }
+        if request.append_content.is_some() {
+            count += 1;
+            updates.push(format!("append_content = ${}", count));
+        }
+        if request.includes.is_some() {
+            count += 1;
+            updates.push(format!("includes = ${}", count));
+        }
+        if request.exit_code_rules.is_some() {
+            count += 1;
+            updates.push(format!("exit_code_rules = ${}", count));
+        }
+
+        updates.push("updated_at = NOW()".to_string());
+
+        let query = format!(
+            "UPDATE job_templates SET {} WHERE id = ${} AND is_active = true RETURNING *",
+            updates.join(", "),
+            count + 1
+        );
+
+        let mut q = sqlx::query_as::<_, crate::models::approval::JobTemplate>(&query);
+
+        if let Some(name) = request.name {
+            q = q.bind(name);
+        }
+        if let Some(description) = request.description {
+            q = q.bind(description);
+        }
+        if let Some(template_content) = request.template_content {
+            q = q.bind(template_content);
+        }
+        if let Some(parameters_schema) = request.parameters_schema {
+            q = q.bind(parameters_schema);
+        }
+        if let Some(default_timeout_secs) = request.default_timeout_secs {
+            q = q.bind(default_timeout_secs);
+        }
+        if let Some(default_retry_times) = request.default_retry_times {
+            q = q.bind(default_retry_times);
+        }
+        if let Some(default_concurrent_limit) = request.default_concurrent_limit {
+            q = q.bind(default_concurrent_limit);
+        }
+        if let Some(risk_level) = request.risk_level {
+            q = q.bind(risk_level);
+        }
+        if let Some(requires_approval) = request.requires_approval {
+            q = q.bind(requires_approval);
+        }
+        if let Some(applicable_environments) = request.applicable_environments {
+            q = q.bind(applicable_environments);
+        }
+        if let Some(applicable_groups) = request.applicable_groups {
+            q = q.bind(applicable_groups);
+        }
+        if let Some(extends_template_id) = request
+            .extends_template_id
+            .filter(|_| !request.clear_extends)
+        {
+            q = q.bind(extends_template_id);
+        }
+        if let Some(prepend_content) = request.prepend_content {
+            q = q.bind(prepend_content);
+        }
+        if let Some(append_content) = request.append_content {
+            q = q.bind(append_content);
+        }
+        if let Some(includes) = request.includes {
+            q = q.bind(Json(includes));
+        }
+        if let Some(rules) = request.exit_code_rules {
+            q = q.bind(Json(rules));
+        }
+
+        q = q.bind(template_id);
+
+        let template = q.fetch_one(&self.db).await.map_err(|e| {
+            if let sqlx::Error::RowNotFound = e {
+                AppError::not_found("Job template not found")
+            } else {
+                error!(error = %e, "Failed to update job template");
+                AppError::database("Failed to update job template")
+            }
+        })?;
+
+        // 记录审计
+        self.audit_service
+            .log_action_simple(
+                updated_by,
+                crate::services::audit_service::AuditAction::JobCreate,
+                Some("job_templates"),
+                Some(template_id),
+                Some("Updated job template"),
+                None,
+            )
+            .await?;
+
+        info!(template_id = %template_id, "Job template updated successfully");
+        Ok(template)
+    }
+
+    /// 删除作业模板（软删除）
+    #[instrument(skip(self))]
+    pub async fn delete_job_template(&self, template_id: Uuid, deleted_by: Uuid) -> Result<()> {
+        info!(template_id = %template_id, "Deleting job template");
+
+        // 仍被其他模板继承或组合的模板不能删除
+        let dependents: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT name FROM job_templates
+            WHERE is_active = true AND id <> $1
+              AND (extends_template_id = $1 OR includes @> jsonb_build_array($1::text))
+            ORDER BY name
+            "#,
+        )
+        .bind(template_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to check template dependents");
+            AppError::database("Failed to check template dependents")
+        })?;
+        if !dependents.is_empty() {
+            return Err(AppError::validation(&format!(
+                "Job template is referenced by: {}",
+                dependents.join(", ")
+            )));
+        }
+
+        if !soft_delete::mark_deleted(
+            &self.db,
+            SoftDeleteResource::JobTemplate,
+            template_id,
+            deleted_by,
+        )
+        .await?
+        {
+            return Err(AppError::not_found("Job template not found"));
+        }
+
+        // 记录审计
+        self.audit_service
+            .log_action_simple(
+                deleted_by,
+                AuditAction::JobTemplateDelete,
+                Some("job_templates"),
+                Some(template_id),
+                Some("Deleted job template"),
+                None,
+            )
+            .await?;
+
+        info!(template_id = %template_id, "Job template deleted successfully");
+        Ok(())
+    }
+
+    /// 恢复已删除的作业模板
+    ///
+    /// 模板继承或组合的模板须仍然有效，否则恢复后无法展开
+    #[instrument(skip(self))]
+    pub async fn restore_job_template(
+        &self,
+        template_id: Uuid,
+        restored_by: Uuid,
+    ) -> Result<crate::models::approval::JobTemplate> {
+        let deleted =
+            soft_delete::find_deleted(&self.db, SoftDeleteResource::JobTemplate, template_id)
+                .await?
+                .ok_or_else(|| AppError::not_found("Deleted job template not found"))?;
+
+        let missing: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT ref.id::text FROM job_templates t
+            CROSS JOIN LATERAL (
+                SELECT t.extends_template_id AS id WHERE t.extends_template_id IS NOT NULL
+                UNION
+                SELECT value::uuid FROM jsonb_array_elements_text(t.includes)
+            ) ref
+            WHERE t.id = $1
+              AND NOT EXISTS (
+                SELECT 1 FROM job_templates d WHERE d.id = ref.id AND d.is_active = true
+              )
+            "#,
+        )
+        .bind(template_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to check template references");
+            AppError::database("Failed to check template references")
+        })?;
+        if !missing.is_empty() {
+            return Err(AppError::validation(&format!(
+                "Job template references deleted templates: {}",
+                missing.join(", ")
+            )));
+        }
+
+        if !soft_delete::restore(&self.db, SoftDeleteResource::JobTemplate, template_id).await? {
+            return Err(AppError::not_found("Deleted job template not found"));
+        }
+
+        self.audit_service
+            .log_action_simple(
+                restored_by,
+                AuditAction::JobTemplateRestore,
+                Some("job_templates"),
+                Some(template_id),
+                Some(&format!("Restored job template: {}", deleted.name)),
+                None,
+            )
+            .await?;
+
+        info!(template_id = %template_id, "Job template restored successfully");
+        self.get_job_template(template_id).await
+    }
+
+    /// 作业模板使用统计：执行次数、成功率、最近使用时间与使用者
+    #[instrument(skip(self))]
+    pub async fn job_template_usage(
+        &self,
+        template_id: Uuid,
+    ) -> Result<crate::models::approval::JobTemplateUsage> {
+        self.get_job_template(template_id).await?;
+
+        let (total_runs, succeeded_runs, failed_runs, partially_succeeded_runs, success_rate) =
+            sqlx::query_as::<_, (i64, i64, i64, i64, f64)>(
+                r#"
+                SELECT
+                    COUNT(*),
+                    COUNT(*) FILTER (WHERE status = 'completed'),
+                    COUNT(*) FILTER (WHERE status = 'failed'),
+                    COUNT(*) FILTER (WHERE status = 'partially_succeeded'),
+                    COALESCE(
+                        COUNT(*) FILTER (WHERE status = 'completed')::float8
+                            / NULLIF(COUNT(*) FILTER (
+                                WHERE status IN ('completed', 'failed', 'partially_succeeded')
+                            ), 0),
+                        0
+                    )
+                FROM jobs
+                WHERE template_id = $1
+                "#,
+            )
+            .bind(template_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to count template usage");
+                AppError::database("Failed to fetch template usage")
+            })?;
+
+        let users = sqlx::query_as::<_, crate::models::approval::JobTemplateUserUsage>(
+            r#"
+            SELECT j.created_by AS user_id, u.username,
+                   COUNT(*) AS runs, MAX(j.created_at) AS last_used_at
+            FROM jobs j
+            LEFT JOIN users u ON u.id = j.created_by
+            WHERE j.template_id = $1
+            GROUP BY j.created_by, u.username
+            ORDER BY last_used_at DESC
+            "#,
+        )
+        .bind(template_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch template users");
+            AppError::database("Failed to fetch template usage")
+        })?;
+
+        let last = users.first();
+        Ok(crate::models::approval::JobTemplateUsage {
+            template_id,
+            total_runs,
+            succeeded_runs,
+            failed_runs,
+            partially_succeeded_runs,
+            success_rate,
+            last_used_at: last.map(|u| u.last_used_at),
+            last_used_by: last.map(|u| u.user_id),
+            last_used_by_username: last.and_then(|u| u.username.clone()),
+            users,
+        })
+    }
+
+    /// 弃用作业模板
+    ///
+    /// 替代模板须有效、未弃用且不是模板本身；重复弃用时更新原因与替代模板
+    #[instrument(skip(self, request))]
+    pub async fn deprecate_job_template(
+        &self,
+        template_id: Uuid,
+        request: crate::models::approval::DeprecateJobTemplateRequest,
+        deprecated_by: Uuid,
+    ) -> Result<crate::models::approval::JobTemplate> {
+        let template = self.get_job_template(template_id).await?;
+
+        if let Some(replacement_id) = request.replacement_template_id {
+            if replacement_id == template_id {
+                return Err(AppError::validation("A template cannot be its own replacement"));
+            }
+            let replacement = self
+                .get_job_template(replacement_id)
+                .await
+                .map_err(|e| match e {
+                    AppError::NotFound(_) => AppError::validation("Replacement template not found"),
+                    other => other,
+                })?;
+            if replacement.is_deprecated() {
+                return Err(AppError::validation(&format!(
+                    "Replacement template is deprecated: {}",
+                    replacement.name
+                )));
+            }
+        }
+        let reason = request
+            .reason
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty());
+
+        let updated = sqlx::query_as::<_, crate::models::approval::JobTemplate>(
+            r#"
+            UPDATE job_templates
+            SET deprecated_at = COALESCE(deprecated_at, NOW()), deprecated_by = $2,
+                deprecation_reason = $3, replacement_template_id = $4, updated_at = NOW()
+            WHERE id = $1 AND is_active = true
+            RETURNING *
+            "#,
+        )
+        .bind(template_id)
+        .bind(deprecated_by)
+        .bind(&reason)
+        .bind(request.replacement_template_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to deprecate job template");
+            AppError::database("Failed to deprecate job template")
+        })?
+        .ok_or_else(|| AppError::not_found("Job template not found"))?;
+
+        self.audit_service
+            .log_action_simple(
+                deprecated_by,
+                AuditAction::JobTemplateDeprecate,
+                Some("job_templates"),
+                Some(template_id),
+                Some(&format!("Deprecated job template: {}", template.name)),
+                None,
+            )
+            .await?;
+
+        info!(template_id = %template_id, "Job template deprecated");
+        Ok(updated)
+    }
+
+    /// 取消作业模板的弃用状态
+    #[instrument(skip(self))]
+    pub async fn undeprecate_job_template(
+        &self,
+        template_id: Uuid,
+        undeprecated_by: Uuid,
+    ) -> Result<crate::models::approval::JobTemplate> {
+        let updated = sqlx::query_as::<_, crate::models::approval::JobTemplate>(
+            r#"
+            UPDATE job_templates
+            SET deprecated_at = NULL, deprecated_by = NULL, deprecation_reason = NULL,
+                replacement_template_id = NULL, updated_at = NOW()
+            WHERE id = $1 AND is_active = true AND deprecated_at IS NOT NULL
+            RETURNING *
+            "#,
+        )
+        .bind(template_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to undeprecate job template");
+            AppError::database("Failed to undeprecate job template")
+        })?
+        .ok_or_else(|| AppError::not_found("Deprecated job template not found"))?;
+
+        self.audit_service
+            .log_action_simple(
+                undeprecated_by,
+                AuditAction::JobTemplateUndeprecate,
+                Some("job_templates"),
+                Some(template_id),
+                Some(&format!("Undeprecated job template: {}", updated.name)),
+                None,
+            )
+            .await?;
+
+        info!(template_id = %template_id, "Job template undeprecated");
+        Ok(updated)
+    }
+
+    /// 仍在使用弃用模板的团队（统计最近 `days` 天内基于弃用模板创建的作业）
+    #[instrument(skip(self))]
+    pub async fn deprecated_template_usage_report(
+        &self,
+        query: crate::models::approval::DeprecatedTemplateUsageQuery,
+    ) -> Result<Vec<crate::models::approval::DeprecatedTemplateUsageRow>> {
+        let days = query.days.unwrap_or(30);
+        if !(1..=365).contains(&days) {
+            return Err(AppError::validation("days must be between 1 and 365"));
+        }
+
+        sqlx::query_as::<_, crate::models::approval::DeprecatedTemplateUsageRow>(
+            r#"
+            SELECT
+                t.id AS template_id,
+                t.name AS template_name,
+                t.replacement_template_id,
+                COALESCE(
+                    (SELECT substring(tag FROM 6) FROM jsonb_array_elements_text(j.tags) AS tag
+                     WHERE tag LIKE 'team:%' ORDER BY tag LIMIT 1),
+                    NULLIF(u.department, ''),
+                    'unassigned'
+                ) AS team,
+                COUNT(*) AS runs,
+                COUNT(DISTINCT j.created_by) AS users,
+                MAX(j.created_at) AS last_used_at
+            FROM jobs j
+            JOIN job_templates t ON t.id = j.template_id
+            LEFT JOIN users u ON u.id = j.created_by
+            WHERE t.deprecated_at IS NOT NULL
+              AND t.is_active = true
+              AND j.created_at >= NOW() - make_interval(days => $1)
+            GROUP BY t.id, t.name, t.replacement_template_id, team
+            ORDER BY t.name, runs DESC, team
+            "#,
+        )
+        .bind(days)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to build deprecated template usage report");
+            AppError::database("Failed to build deprecated template usage report")
+        })
+    }
+
+    /// 基于模板创建作业
+    #[instrument(skip(self, request))]
+    pub async fn create_job_from_template(
+        &self,
+        request: crate::models::approval::ExecuteTemplateJobRequest,
+        created_by: Uuid,
+    ) -> Result<Job> {
+        self.create_template_job(request, created_by, None).await
+    }
+
+    /// 基于模板创建作业（后续作业附带其在作业链中的位置，并以父作业与触发条件作为幂等键）
+    async fn create_template_job(
+        &self,
+        request: crate::models::approval::ExecuteTemplateJobRequest,
+        created_by: Uuid,
+        chain: Option<ChainLink>,
+    ) -> Result<Job> {
+        info!(template_id = %request.template_id, "Creating job from template");
+
+        // 获取模板
+        let template = self.get_job_template(request.template_id).await?;
+
+        if !template.is_active {
+            return Err(AppError::validation("Job template is not active"));
+        }
+        if template.is_deprecated() {
+            warn!(
+                template_id = %template.id,
+                replacement_template_id = ?template.replacement_template_id,
+                "Creating job from deprecated template"
+            );
+        }
+
+        // 展开继承与组合后替换模板参数
+        let resolved = self.load_template_graph().await?.resolve(template.id)?;
+        let command =
+            self.substitute_template_params(&resolved.template_content, &request.parameters)?;
+
+        // 构建作业请求
+        let job_request = CreateCommandJobRequest {
+            name: format!("{} (from template)", template.name),
+            description: template.description,
+            command,
+            target_hosts: request.target_hosts,
+            target_groups: request.target_groups,
+            target_set_id: None,
+            timeout_secs: resolved.default_timeout_secs,
+            retry_times: resolved.default_retry_times,
+            concurrent_limit: resolved.default_concurrent_limit,
+            execute_user: None,
+            exit_code_rules: resolved.exit_code_rules,
+            idempotency_key: chain
+                .as_ref()
+                .map(|c| format!("chain:{}:{}", c.parent_job_id, c.trigger.as_str())),
+            singleton_key: request.singleton_key,
+            singleton_policy: request.singleton_policy,
+            tags: request.tags,
+            on_success_job_template: request.on_success_job_template,
+            on_failure_job_template: request.on_failure_job_template,
+            artifact_id: None,
+            file_manifest: None,
+            drift_check: request.drift_check,
+            verbose_trace: request.verbose_trace,
+        };
+
+        let context = TemplateApprovalContext {
+            template_id: template.id,
+            risk_level: resolved.risk_level,
+            parameters: request.parameters,
+        };
+
+        // 创建作业
+        self.create_command_job_with_context(job_request, created_by, Some(context), chain)
+            .await
+    }
+
+    /// 替换模板中的参数
+    fn substitute_template_params(
+        &self,
+        template: &str,
+        params: &serde_json::Value,
+    ) -> Result<String> {
+        let mut result = template.to_string();
+
+        if let Some(obj) = params.as_object() {
+            for (key, value) in obj {
+                let placeholder = format!("{{{{{}}}}}", key);
+                let replacement = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Number(n) => n.to_string(),
+                    serde_json::Value::Bool(b) => b.to_string(),
+                    serde_json::Value::Null => String::new(),
+                    _ => value.to_string(),
+                };
+                result = result.replace(&placeholder, &replacement);
+            }
+        }
+
+        Ok(result)
+    }
+
+    // ==================== 作业链 ====================
+
+    /// 校验后续作业配置：嵌套深度不超过上限，引用的模板存在且启用
+    async fn validate_follow_ups(
+        &self,
+        follow_ups: [&Option<FollowUpJobTemplate>; 2],
+        depth: i32,
+    ) -> Result<()> {
+        let mut pending: Vec<(&FollowUpJobTemplate, i32)> = follow_ups
+            .iter()
+            .filter_map(|f| f.as_ref())
+            .map(|f| (f, depth))
+            .collect();
+        while let Some((follow_up, parent_depth)) = pending.pop() {
+            if parent_depth + follow_up.depth() > MAX_CHAIN_DEPTH {
+                return Err(AppError::validation(&format!(
+                    "Job chain exceeds maximum depth of {}",
+                    MAX_CHAIN_DEPTH
+                )));
+            }
+            self.get_job_template(follow_up.template_id)
+                .await
+                .map_err(|e| match e {
+                    AppError::NotFound(_) => AppError::validation(&format!(
+                        "Follow-up job template {} not found or inactive",
+                        follow_up.template_id
+                    )),
+                    e => e,
+                })?;
+            for trigger in [ChainTrigger::Success, ChainTrigger::Failure] {
+                if let Some(nested) = follow_up.follow_up(trigger) {
+                    pending.push((nested, parent_depth + 1));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 等待有作业结束且待启动后续作业的通知
+    pub async fn follow_ups_notified(&self) {
+        self.chain_signal.notified().await;
+    }
+
+    /// 为已结束且命中后续作业配置的作业启动后续作业，返回处理的作业数
+    ///
+    /// 后续作业以父作业与触发条件作为幂等键，重复派发（多实例、中途重启）不会重复创建
+    pub async fn launch_follow_up_jobs(&self) -> Result<usize> {
+        let parents = sqlx::query_as::<_, Job>(
+            "SELECT * FROM jobs WHERE chain_status = 'pending' ORDER BY completed_at LIMIT 50",
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch jobs with pending follow-ups");
+            AppError::database("Failed to fetch jobs")
+        })?;
+
+        for parent in &parents {
+            let (chain_status, chain_error) = match self.launch_follow_up(parent).await {
+                Ok(FollowUpOutcome::Launched(child_id)) => {
+                    info!(job_id = %parent.id, follow_up_job_id = %child_id, "Follow-up job launched");
+                    ("launched", None)
+                }
+                Ok(FollowUpOutcome::Skipped(reason)) => {
+                    warn!(job_id = %parent.id, reason = %reason, "Follow-up job skipped");
+                    ("skipped", Some(reason))
+                }
+                Err(e) => {
+                    error!(error = %e, job_id = %parent.id, "Failed to launch follow-up job");
+                    ("failed", Some(e.to_string()))
+                }
+            };
+            sqlx::query(
+                "UPDATE jobs SET chain_status = $2, chain_error = $3 WHERE id = $1 AND chain_status = 'pending'",
+            )
+            .bind(parent.id)
+            .bind(chain_status)
+            .bind(chain_error)
+            .execute(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %parent.id, "Failed to update follow-up status");
+                AppError::database("Failed to update job")
+            })?;
+        }
+
+        Ok(parents.len())
+    }
+
+    async fn launch_follow_up(&self, parent: &Job) -> Result<FollowUpOutcome> {
+        let Some(trigger) = ChainTrigger::for_status(&parent.status) else {
+            return Ok(FollowUpOutcome::Skipped(format!("Job finished as {}", parent.status)));
+        };
+        let follow_up = match trigger {
+            ChainTrigger::Success => &parent.on_success_job_template,
+            ChainTrigger::Failure => &parent.on_failure_job_template,
+        };
+        let Some(Json(follow_up)) = follow_up else {
+            return Ok(FollowUpOutcome::Skipped(format!(
+                "No follow-up job configured on {}",
+                trigger.as_str()
+            )));
+        };
+
+        // 循环保护：限制链深度，且同一模板在链上只执行一次
+        let depth = parent.chain_depth + 1;
+        if depth > MAX_CHAIN_DEPTH {
+            return Ok(FollowUpOutcome::Skipped(format!(
+                "Job chain exceeds maximum depth of {}",
+                MAX_CHAIN_DEPTH
+            )));
+        }
+        let chain_templates = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            WITH RECURSIVE chain AS (
+                SELECT id, parent_job_id, template_id, 0 AS level FROM jobs WHERE id = $1
+                UNION ALL
+                SELECT j.id, j.parent_job_id, j.template_id, c.level + 1
+                FROM jobs j JOIN chain c ON j.id = c.parent_job_id
+                WHERE c.level < $2
+            )
+            SELECT template_id FROM chain WHERE template_id IS NOT NULL
+            "#,
+        )
+        .bind(parent.id)
+        .bind(MAX_CHAIN_DEPTH)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %parent.id, "Failed to fetch job chain");
+            AppError::database("Failed to fetch job chain")
+        })?;
+        if chain_templates.contains(&follow_up.template_id) {
+            return Ok(FollowUpOutcome::Skipped(format!(
+                "Template {} already ran in this job chain",
+                follow_up.template_id
+            )));
+        }
+
+        let target_hosts = match follow_up.target {
+            FollowUpTarget::ParentTargets => parent.target_hosts.0.clone(),
+            FollowUpTarget::SucceededHosts => {
+                self.chain_task_hosts(parent.id, &["succeeded", "warning"])
+                    .await?
+            }
+            FollowUpTarget::FailedHosts => {
+                self.chain_task_hosts(parent.id, &["failed", "timeout"])
+                    .await?
+            }
+        };
+        if target_hosts.is_empty() {
+            return Ok(FollowUpOutcome::Skipped("No target hosts for follow-up job".to_string()));
+        }
+
+        let mut parameters = follow_up.parameters.clone();
+        for (name, source) in &follow_up.parameter_mapping {
+            parameters.insert(name.clone(), self.chain_parameter_value(parent, *source).await?);
+        }
+
+        let request = crate::models::approval::ExecuteTemplateJobRequest {
+            template_id: follow_up.template_id,
+            parameters: serde_json::Value::Object(parameters),
+            target_hosts,
+            target_groups: vec![],
+            tags: vec![],
+            singleton_key: None,
+            singleton_policy: SingletonPolicy::default(),
+            on_success_job_template: follow_up.on_success_job_template.as_deref().cloned(),
+            on_failure_job_template: follow_up.on_failure_job_template.as_deref().cloned(),
+            drift_check: false,
+            verbose_trace: false,
+        };
+        let chain = ChainLink {
+            parent_job_id: parent.id,
+            trigger,
+            depth,
+        };
+        let child = self
+            .create_template_job(request, parent.created_by, Some(chain))
+            .await?;
+        Ok(FollowUpOutcome::Launched(child.id))
+    }
+
+    /// 父作业中处于指定状态的任务的主机
+    async fn chain_task_hosts(&self, job_id: Uuid, statuses: &[&str]) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT host_id FROM tasks WHERE job_id = $1 AND status::text = ANY($2) ORDER BY host_id",
+        )
+        .bind(job_id)
+        .bind(statuses)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to fetch task hosts");
+            AppError::database("Failed to fetch tasks")
+        })
+    }
+
+    /// 从父作业结果中取出映射到后续作业的参数值
+    async fn chain_parameter_value(
+        &self,
+        parent: &Job,
+        source: ChainParameterSource,
+    ) -> Result<serde_json::Value> {
+        let statuses = match source {
+            ChainParameterSource::JobId => return Ok(parent.id.to_string().into()),
+            ChainParameterSource::JobName => return Ok(parent.name.clone().into()),
+            ChainParameterSource::Status => return Ok(parent.status.to_string().into()),
+            ChainParameterSource::TotalTasks => return Ok(parent.total_tasks.into()),
+            ChainParameterSource::SucceededTasks => return Ok(parent.succeeded_tasks.into()),
+            ChainParameterSource::FailedTasks => {
+                return Ok((parent.failed_tasks + parent.timeout_tasks).into())
+            }
+            ChainParameterSource::SucceededHosts => vec!["succeeded", "warning"],
+            ChainParameterSource::FailedHosts => vec!["failed", "timeout"],
+        };
+        let identifiers = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT h.identifier FROM tasks t
+            JOIN assets_hosts h ON h.id = t.host_id
+            WHERE t.job_id = $1 AND t.status::text = ANY($2)
+            ORDER BY h.identifier
+            "#,
+        )
+        .bind(parent.id)
+        .bind(&statuses)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %parent.id, "Failed to fetch task hosts");
+            AppError::database("Failed to fetch tasks")
+        })?;
+        Ok(identifiers.join(",").into())
+    }
+
+    /// 获取作业所在的作业链（含已归档的作业）
+    pub async fn get_job_chain(&self, job_id: Uuid) -> Result<JobChain> {
+        let job = self.get_job(job_id).await?;
+
+        // 向上找到根作业（父作业可能已被清理，此时以能找到的最上层作业为根）
+        let mut root = job;
+        for _ in 0..MAX_CHAIN_DEPTH {
+            let Some(parent_id) = root.parent_job_id else {
+                break;
+            };
+            match self.get_job(parent_id).await {
+                Ok(parent) => root = parent,
+                Err(AppError::NotFound(_)) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let nodes = sqlx::query_as::<_, JobChainNode>(
+            r#"
+            WITH RECURSIVE all_jobs AS (
+                SELECT * FROM jobs
+                UNION ALL
+                SELECT * FROM jobs_archive
+            ),
+            chain AS (
+                SELECT * FROM all_jobs WHERE id = $1
+                UNION ALL
+                SELECT a.* FROM all_jobs a JOIN chain c ON a.parent_job_id = c.id
+                WHERE a.chain_depth <= $2
+            )
+            SELECT
+                id AS job_id, parent_job_id, name, status, template_id,
+                chain_trigger, chain_depth, chain_status, chain_error,
+                (on_success_job_template->>'template_id')::uuid AS on_success_template_id,
+                (on_failure_job_template->>'template_id')::uuid AS on_failure_template_id,
+                created_at, completed_at
+            FROM chain
+            ORDER BY chain_depth, created_at
+            "#,
+        )
+        .bind(root.id)
+        .bind(MAX_CHAIN_DEPTH)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to fetch job chain");
+            AppError::database("Failed to fetch job chain")
+        })?;
+
+        Ok(JobChain {
+            root_job_id: root.id,
+            nodes,
+        })
+    }
+
+    // ==================== 作业结果分组 ====================
+
+    /// 按条件筛选作业结果中的主机（作业需已结束）
+    async fn result_hosts(
+        &self,
+        job_id: Uuid,
+        filters: &[ResultHostFilter],
+    ) -> Result<Vec<Vec<Uuid>>> {
+        let job = self.get_job(job_id).await?;
+        if matches!(
+            job.status,
+            JobStatus::Pending | JobStatus::Running | JobStatus::AwaitingApproval
+        ) {
+            return Err(AppError::validation("Job has not finished yet"));
+        }
+
+        let with_outputs = filters.iter().any(|f| f.pattern().is_some());
+        let tasks = self.load_job_tasks(job_id, with_outputs).await?;
+
+        filters
+            .iter()
+            .map(|filter| {
+                let regex = filter
+                    .pattern()
+                    .map(regex::Regex::new)
+                    .transpose()
+                    .map_err(|e| AppError::validation(&format!("Invalid output pattern: {}", e)))?;
+                let mut host_ids: Vec<Uuid> = tasks
+                    .iter()
+                    .filter(|task| filter.matches(task, regex.as_ref()))
+                    .map(|task| task.host_id)
+                    .collect();
+                host_ids.sort();
+                host_ids.dedup();
+                Ok(host_ids)
+            })
+            .collect()
+    }
+
+    /// 作业结果派生的主机分组：成功、告警、失败、退出码非 0，指定输出正则时另含匹配/不匹配两组
+    pub async fn get_result_host_groups(
+        &self,
+        job_id: Uuid,
+        output_pattern: Option<&str>,
+    ) -> Result<Vec<ResultHostGroup>> {
+        let mut filters = vec![
+            ResultHostFilter::Succeeded,
+            ResultHostFilter::Warning,
+            ResultHostFilter::Failed,
+            ResultHostFilter::NonZeroExit,
+        ];
+        if let Some(pattern) = output_pattern {
+            filters.push(ResultHostFilter::OutputMatches {
+                pattern: pattern.to_string(),
+            });
+            filters.push(ResultHostFilter::OutputNotMatches {
+                pattern: pattern.to_string(),
+            });
         }
-        if request.applicable_groups.is_some() {
-            count += 1;
-            updates.push(format!("applicable_groups = ${}", count));
+
+        let groups = self.result_hosts(job_id, &filters).await?;
+        Ok(filters
+            .into_iter()
+            .zip(groups)
+            .map(|(filter, host_ids)| ResultHostGroup {
+                filter,
+                count: host_ids.len(),
+                host_ids,
+            })
+            .collect())
+    }
+
+    /// 将作业结果分组保存为目标集合，供后续作业通过 target_set_id 引用
+    #[instrument(skip(self, request))]
+    pub async fn create_target_set(
+        &self,
+        job_id: Uuid,
+        request: CreateJobTargetSetRequest,
+        created_by: Uuid,
+    ) -> Result<JobTargetSet> {
+        let host_ids = self
+            .result_hosts(job_id, std::slice::from_ref(&request.filter))
+            .await?
+            .pop()
+            .unwrap_or_default();
+        if host_ids.is_empty() {
+            return Err(AppError::validation("No hosts match the filter"));
         }
 
-        updates.push("updated_at = NOW()".to_string());
+        let target_set = sqlx::query_as::<_, JobTargetSet>(
+            r#"
+            INSERT INTO job_target_sets (source_job_id, name, filter, host_ids, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(job_id)
+        .bind(&request.name)
+        .bind(Json(&request.filter))
+        .bind(Json(&host_ids))
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, job_id = %job_id, "Failed to insert target set");
+            AppError::database("Failed to create target set")
+        })?;
 
-        let query = format!(
-            "UPDATE job_templates SET {} WHERE id = ${} AND is_active = true RETURNING *",
-            updates.join(", "),
-            count + 1
+        info!(
+            target_set_id = %target_set.id,
+            job_id = %job_id,
+            hosts = host_ids.len(),
+            "Target set created"
         );
+        Ok(target_set)
+    }
 
-        let mut q = sqlx::query_as::<_, crate::models::approval::JobTemplate>(&query);
+    /// 查询目标集合
+    pub async fn get_target_set(&self, target_set_id: Uuid) -> Result<JobTargetSet> {
+        sqlx::query_as::<_, JobTargetSet>("SELECT * FROM job_target_sets WHERE id = $1")
+            .bind(target_set_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to fetch target set");
+                AppError::database("Failed to fetch target set")
+            })?
+            .ok_or_else(|| AppError::not_found("Target set not found"))
+    }
 
-        if let Some(name) = request.name {
-            q = q.bind(name);
-        }
-        if let Some(description) = request.description {
-            q = q.bind(description);
-        }
-        if let Some(template_content) = request.template_content {
-            q = q.bind(template_content);
-        }
-        if let Some(parameters_schema) = request.parameters_schema {
-            q = q.bind(parameters_schema);
-        }
-        if let Some(default_timeout_secs) = request.default_timeout_secs {
-            q = q.bind(default_timeout_secs);
-        }
-        if let Some(default_retry_times) = request.default_retry_times {
-            q = q.bind(default_retry_times);
-        }
-        if let Some(default_concurrent_limit) = request.default_concurrent_limit {
-            q = q.bind(default_concurrent_limit);
-        }
-        if let Some(risk_level) = request.risk_level {
-            q = q.bind(risk_level);
-        }
-        if let Some(requires_approval) = request.requires_approval {
-            q = q.bind(requires_approval);
-        }
-        if let Some(applicable_environments) = request.applicable_environments {
-            q = q.bind(applicable_environments);
-        }
-        if let Some(applicable_groups) = request.applicable_groups {
-            q = q.bind(applicable_groups);
+    /// 将目标集合展开到目标主机列表（在作用域校验之前调用）
+    pub async fn expand_target_set(
+        &self,
+        target_set_id: Option<Uuid>,
+        target_hosts: &mut Vec<Uuid>,
+    ) -> Result<()> {
+        if let Some(target_set_id) = target_set_id {
+            let target_set = self.get_target_set(target_set_id).await?;
+            target_hosts.extend(target_set.host_ids.0);
+            target_hosts.sort();
+            target_hosts.dedup();
         }
+        Ok(())
+    }
 
-        q = q.bind(template_id);
+    // ==================== 基线输出 ====================
 
-        let template = q.fetch_one(&self.db).await.map_err(|e| {
-            if let sqlx::Error::RowNotFound = e {
-                AppError::not_found("Job template not found")
-            } else {
-                error!(error = %e, "Failed to update job template");
-                AppError::database("Failed to update job template")
+    /// 创建或替换基线输出（同一模板/主机组合只保留一条）
+    #[instrument(skip(self, request))]
+    pub async fn set_output_baseline(
+        &self,
+        request: SetOutputBaselineRequest,
+        created_by: Uuid,
+    ) -> Result<OutputBaseline> {
+        output_drift::validate_baseline(&request)?;
+
+        let baseline = sqlx::query_as::<_, OutputBaseline>(
+            r#"
+            INSERT INTO output_baselines
+                (template_id, host_id, expected_output, description, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (template_id, host_id) DO UPDATE SET
+                expected_output = EXCLUDED.expected_output,
+                description = EXCLUDED.description,
+                created_by = EXCLUDED.created_by,
+                updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(request.template_id)
+        .bind(request.host_id)
+        .bind(&request.expected_output)
+        .bind(&request.description)
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(d) if d.is_foreign_key_violation() => {
+                AppError::validation("Unknown job template or host")
+            }
+            _ => {
+                error!(error = %e, "Failed to save output baseline");
+                AppError::database("Failed to save output baseline")
             }
         })?;
 
-        // 记录审计
         self.audit_service
             .log_action_simple(
-                updated_by,
-                crate::services::audit_service::AuditAction::JobCreate,
-                Some("job_templates"),
-                Some(template_id),
-                Some("Updated job template"),
+                created_by,
+                AuditAction::JobOutputBaselineSet,
+                Some("output_baseline"),
+                Some(baseline.id),
+                Some(&format!(
+                    "Set output baseline (template {:?}, host {:?}, {} bytes)",
+                    baseline.template_id,
+                    baseline.host_id,
+                    baseline.expected_output.len()
+                )),
                 None,
             )
             .await?;
 
-        info!(template_id = %template_id, "Job template updated successfully");
-        Ok(template)
+        Ok(baseline)
     }
 
-    /// 删除作业模板（软删除）
-    #[instrument(skip(self))]
-    pub async fn delete_job_template(&self, template_id: Uuid, deleted_by: Uuid) -> Result<()> {
-        info!(template_id = %template_id, "Deleting job template");
-
-        let updated = sqlx::query(
-            "UPDATE job_templates SET is_active = false, updated_at = NOW() WHERE id = $1",
+    /// 查询基线输出
+    pub async fn list_output_baselines(
+        &self,
+        filters: OutputBaselineFilters,
+    ) -> Result<Vec<OutputBaseline>> {
+        sqlx::query_as::<_, OutputBaseline>(
+            r#"
+            SELECT * FROM output_baselines
+            WHERE ($1::uuid IS NULL OR template_id = $1)
+              AND ($2::uuid IS NULL OR host_id = $2)
+            ORDER BY created_at DESC
+            "#,
         )
-        .bind(template_id)
-        .execute(&self.db)
+        .bind(filters.template_id)
+        .bind(filters.host_id)
+        .fetch_all(&self.db)
         .await
         .map_err(|e| {
-            error!(error = %e, "Failed to delete job template");
-            AppError::database("Failed to delete job template")
-        })?;
+            error!(error = %e, "Failed to list output baselines");
+            AppError::database("Failed to list output baselines")
+        })
+    }
 
-        if updated.rows_affected() == 0 {
-            return Err(AppError::not_found("Job template not found"));
+    /// 删除基线输出
+    #[instrument(skip(self))]
+    pub async fn delete_output_baseline(&self, baseline_id: Uuid, deleted_by: Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM output_baselines WHERE id = $1")
+            .bind(baseline_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to delete output baseline");
+                AppError::database("Failed to delete output baseline")
+            })?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Output baseline not found"));
         }
 
-        // 记录审计
         self.audit_service
             .log_action_simple(
                 deleted_by,
-                crate::services::audit_service::AuditAction::JobCancel,
-                Some("job_templates"),
-                Some(template_id),
-                Some("Deleted job template"),
+                AuditAction::JobOutputBaselineDelete,
+                Some("output_baseline"),
+                Some(baseline_id),
+                Some("Deleted output baseline"),
                 None,
             )
             .await?;
-
-        info!(template_id = %template_id, "Job template deleted successfully");
         Ok(())
     }
 
-    /// 基于模板创建作业
-    #[instrument(skip(self, request))]
-    pub async fn create_job_from_template(
-        &self,
-        request: crate::models::approval::ExecuteTemplateJobRequest,
-        created_by: Uuid,
-    ) -> Result<Job> {
-        info!(template_id = %request.template_id, "Creating job from template");
+    /// 作业的偏离汇总报告
+    pub async fn get_drift_report(&self, job_id: Uuid) -> Result<DriftReport> {
+        let job = self.get_job(job_id).await?;
+        if !job.drift_check {
+            return Err(AppError::validation("Job was not created with drift_check enabled"));
+        }
+        let tasks = self.load_job_tasks(job_id, false).await?;
 
-        // 获取模板
-        let template = self.get_job_template(request.template_id).await?;
+        // 主机可能已被删除，此时以主机 ID 作为标识
+        let host_ids: Vec<Uuid> = tasks.iter().map(|t| t.host_id).collect();
+        let identifiers: std::collections::HashMap<Uuid, String> =
+            sqlx::query_as::<_, (Uuid, String)>(
+                "SELECT id, identifier FROM assets_hosts WHERE id = ANY($1)",
+            )
+            .bind(&host_ids)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, job_id = %job_id, "Failed to fetch hosts");
+                AppError::database("Failed to fetch hosts")
+            })?
+            .into_iter()
+            .collect();
 
-        if !template.is_active {
-            return Err(AppError::validation("Job template is not active"));
+        let mut report = DriftReport {
+            job_id,
+            in_sync: 0,
+            drifted: 0,
+            no_baseline: 0,
+            not_checked: 0,
+            hosts: Vec::with_capacity(tasks.len()),
+        };
+        for task in tasks {
+            let drift = task.output_drift.map(|d| d.0);
+            match drift.as_ref().map(|d| d.state) {
+                Some(DriftState::InSync) => report.in_sync += 1,
+                Some(DriftState::Drifted) => report.drifted += 1,
+                Some(DriftState::NoBaseline) => report.no_baseline += 1,
+                None => report.not_checked += 1,
+            }
+            report.hosts.push(HostDriftEntry {
+                task_id: task.id,
+                host_id: task.host_id,
+                identifier: identifiers
+                    .get(&task.host_id)
+                    .cloned()
+                    .unwrap_or_else(|| task.host_id.to_string()),
+                task_status: task.status,
+                drift,
+            });
         }
+        // 偏离的主机排在前面
+        report
+            .hosts
+            .sort_by_key(|h| h.drift.as_ref().map(|d| d.state) != Some(DriftState::Drifted));
+        Ok(report)
+    }
 
-        // 替换模板参数
-        let command =
-            self.substitute_template_params(&template.template_content, &request.parameters)?;
+    // ==================== 关注 ====================
 
-        // 构建作业请求
-        let job_request = CreateCommandJobRequest {
-            name: format!("{} (from template)", template.name),
-            description: template.description,
-            command,
-            target_hosts: request.target_hosts,
-            target_groups: request.target_groups,
-            timeout_secs: template.default_timeout_secs,
-            retry_times: template.default_retry_times,
-            concurrent_limit: template.default_concurrent_limit,
-            execute_user: None,
-            idempotency_key: None,
-            tags: request.tags,
+    /// 关注作业、主机或模板（重复关注返回已有记录）
+    #[instrument(skip(self))]
+    pub async fn create_watch(&self, user_id: Uuid, request: CreateWatchRequest) -> Result<Watch> {
+        let table = match request.target_type {
+            WatchTargetType::Job => "jobs",
+            WatchTargetType::Host => "assets_hosts",
+            WatchTargetType::Template => "job_templates",
         };
+        let exists = sqlx::query_scalar::<_, bool>(&format!(
+            "SELECT EXISTS(SELECT 1 FROM {} WHERE id = $1)",
+            table
+        ))
+        .bind(request.target_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to check watch target");
+            AppError::database("Failed to check watch target")
+        })?;
+        if !exists {
+            return Err(AppError::not_found(&format!(
+                "{} {} not found",
+                request.target_type, request.target_id
+            )));
+        }
 
-        // 创建作业
-        self.create_command_job(job_request, created_by).await
+        sqlx::query_as::<_, Watch>(
+            r#"
+            INSERT INTO watches (user_id, target_type, target_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, target_type, target_id)
+            DO UPDATE SET target_id = EXCLUDED.target_id
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(request.target_type)
+        .bind(request.target_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to create watch");
+            AppError::database("Failed to create watch")
+        })
     }
 
-    /// 替换模板中的参数
-    fn substitute_template_params(
+    /// 列出用户的关注（可按对象类型过滤）
+    pub async fn list_watches(
         &self,
-        template: &str,
-        params: &serde_json::Value,
-    ) -> Result<String> {
-        let mut result = template.to_string();
-
-        if let Some(obj) = params.as_object() {
-            for (key, value) in obj {
-                let placeholder = format!("{{{{{}}}}}", key);
-                let replacement = match value {
-                    serde_json::Value::String(s) => s.clone(),
-                    serde_json::Value::Number(n) => n.to_string(),
-                    serde_json::Value::Bool(b) => b.to_string(),
-                    serde_json::Value::Null => String::new(),
-                    _ => value.to_string(),
-                };
-                result = result.replace(&placeholder, &replacement);
-            }
-        }
+        user_id: Uuid,
+        target_type: Option<WatchTargetType>,
+    ) -> Result<Vec<Watch>> {
+        sqlx::query_as::<_, Watch>(
+            r#"
+            SELECT * FROM watches
+            WHERE user_id = $1 AND ($2::watch_target_type IS NULL OR target_type = $2)
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .bind(target_type)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to list watches");
+            AppError::database("Failed to list watches")
+        })
+    }
 
-        Ok(result)
+    /// 取消关注（只能删除自己的关注）
+    #[instrument(skip(self))]
+    pub async fn delete_watch(&self, user_id: Uuid, watch_id: Uuid) -> Result<Watch> {
+        sqlx::query_as::<_, Watch>("DELETE FROM watches WHERE id = $1 AND user_id = $2 RETURNING *")
+            .bind(watch_id)
+            .bind(user_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to delete watch");
+                AppError::database("Failed to delete watch")
+            })?
+            .ok_or_else(|| AppError::not_found("Watch not found"))
     }
 
     // ==================== 实时事件发布 ====================
@@ -1825,13 +6589,67 @@ impl JobService {
 
     // ==================== SSH 辅助方法 ====================
 
+    /// 从集中管理的 known_hosts 中加载指定主机的固定密钥
+    async fn load_central_known_hosts(
+        db: &sqlx::PgPool,
+        address: &str,
+        port: i32,
+    ) -> Option<std::collections::HashMap<String, String>> {
+        let fingerprint = sqlx::query_scalar::<_, String>(
+            "SELECT fingerprint FROM ssh_known_hosts WHERE host = $1 AND port = $2",
+        )
+        .bind(address)
+        .bind(port)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, host = %address, "Failed to load central known_hosts");
+        })
+        .ok()??;
+
+        Some(std::collections::HashMap::from([(
+            format!("{}:{}", address, port),
+            fingerprint,
+        )]))
+    }
+
+    /// 记录主机密钥验证失败，供管理员核实后重新固定
+    async fn record_host_key_failure(
+        db: &sqlx::PgPool,
+        host_id: Uuid,
+        task_id: Uuid,
+        failure: &crate::ssh::HostKeyFailure,
+    ) {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO ssh_host_key_failures (
+                host_id, task_id, host, port, kind,
+                expected_fingerprint, presented_fingerprint, key_type, public_key
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(host_id)
+        .bind(task_id)
+        .bind(&failure.host)
+        .bind(failure.port as i32)
+        .bind(failure.kind.as_str())
+        .bind(&failure.expected_fingerprint)
+        .bind(&failure.presented_fingerprint)
+        .bind(&failure.key_type)
+        .bind(&failure.public_key)
+        .execute(db)
+        .await;
+
+        if let Err(e) = result {
+            error!(error = %e, host = %failure.host, "Failed to record host key failure");
+        }
+    }
+
     /// 从文件加载 known_hosts
-    /// 解析 SSH known_hosts 文件格式，返回 HashMap<host_key, fingerprint>
+    /// 解析 SSH known_hosts 文件格式，返回 HashMap<host:port, fingerprint>
     async fn load_known_hosts_file(
         file_path: &str,
     ) -> Option<std::collections::HashMap<String, String>> {
-        use std::collections::HashMap;
-
         // 读取文件内容
         let content = match tokio::fs::read_to_string(file_path).await {
             Ok(content) => content,
@@ -1841,30 +6659,14 @@ impl JobService {
             }
         };
 
-        let mut known_hosts = HashMap::new();
-
-        // 解析 known_hosts 文件
-        for line in content.lines() {
-            let line = line.trim();
-
-            // 跳过空行和注释
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-
-            // known_hosts 格式: host patterns, key type, public key
-            // 简化解析：提取 host 和公钥哈希
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                let host_pattern = parts[0];
-                let public_key_base64 = parts[2];
-
-                // 计算 SHA256 指纹
-                if let Ok(hash) = calculate_ssh_fingerprint(public_key_base64) {
-                    known_hosts.insert(host_pattern.to_string(), hash);
-                }
-            }
-        }
+        let known_hosts: std::collections::HashMap<String, String> = content
+            .lines()
+            .flat_map(common::ssh::KnownHostEntry::parse_line)
+            .filter_map(|entry| {
+                crate::ssh::host_key::fingerprint(&entry.public_key)
+                    .map(|fingerprint| (entry.host_key(), fingerprint))
+            })
+            .collect();
 
         if known_hosts.is_empty() {
             warn!(file_path = %file_path, "No valid entries found in known_hosts file");
@@ -1890,20 +6692,11 @@ impl JobService {
     }
 }
 
-/// 计算 SSH 公钥的 SHA256 指纹
-fn calculate_ssh_fingerprint(public_key_base64: &str) -> std::result::Result<String, String> {
-    use base64::Engine;
-    use sha2::Digest;
-
-    // 解码 base64 公钥
-    let key_bytes = base64::engine::general_purpose::STANDARD
-        .decode(public_key_base64)
-        .map_err(|e| format!("Failed to decode public key: {}", e))?;
-
-    // 计算 SHA256 哈希
-    let mut hasher = sha2::Sha256::new();
-    hasher.update(&key_bytes);
-    let hash = hasher.finalize();
-
-    Ok(hex::encode(hash))
+/// 校验命名空间的取值正则
+fn validate_value_pattern(pattern: Option<&str>) -> Result<()> {
+    if let Some(pattern) = pattern {
+        regex::Regex::new(pattern)
+            .map_err(|e| AppError::validation(&format!("Invalid value pattern: {}", e)))?;
+    }
+    Ok(())
 }
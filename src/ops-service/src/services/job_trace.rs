@@ -0,0 +1,158 @@
+//! 作业详细追踪（verbose trace）
+//!
+//! 开启详细追踪的作业在执行过程中额外记录 SSH 协商日志、各阶段耗时、并发许可等待时间
+//! 与事件发布结果，写入 job_trace_entries，可通过 API 查询。
+//! 目标主机包含生产环境时创建作业自动关闭，管理员创建的作业除外
+
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::asset::Host;
+use crate::models::job::{JobTraceEntry, JobTraceQuery};
+use crate::services::PermissionService;
+
+/// 追踪记录类别
+pub const CATEGORY_SSH: &str = "ssh";
+pub const CATEGORY_PHASE: &str = "phase";
+pub const CATEGORY_CONCURRENCY: &str = "concurrency";
+pub const CATEGORY_EVENT: &str = "event";
+
+/// 单次查询最多返回的记录数
+const MAX_QUERY_LIMIT: i64 = 2000;
+
+/// 作业的追踪记录器（仅开启详细追踪的作业创建）
+#[derive(Clone)]
+pub(crate) struct JobTracer {
+    db: PgPool,
+    job_id: Uuid,
+}
+
+impl JobTracer {
+    pub fn new(db: PgPool, job_id: Uuid) -> Self {
+        Self { db, job_id }
+    }
+
+    /// 写入一条追踪记录；写入失败只记录日志，不影响作业执行
+    pub async fn record(
+        &self,
+        task_id: Option<Uuid>,
+        category: &str,
+        message: &str,
+        duration_ms: Option<u64>,
+        details: Option<Value>,
+    ) {
+        let result = sqlx::query(
+            "INSERT INTO job_trace_entries (job_id, task_id, category, message, duration_ms, details) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(self.job_id)
+        .bind(task_id)
+        .bind(category)
+        .bind(message)
+        .bind(duration_ms.map(|ms| i64::try_from(ms).unwrap_or(i64::MAX)))
+        .bind(details.map(Json))
+        .execute(&self.db)
+        .await;
+        if let Err(e) = result {
+            warn!(error = %e, job_id = %self.job_id, "Failed to record job trace entry");
+        }
+    }
+}
+
+/// 事件发布结果计数（增量输出事件逐条发布，汇总后写入追踪）
+#[derive(Debug, Default)]
+pub(crate) struct PublishCounter {
+    delivered: AtomicU64,
+    undelivered: AtomicU64,
+}
+
+impl PublishCounter {
+    pub fn record(&self, delivered: bool) {
+        let counter = if delivered {
+            &self.delivered
+        } else {
+            &self.undelivered
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "delivered": self.delivered.load(Ordering::Relaxed),
+            "undelivered": self.undelivered.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// 创建作业时确定是否开启详细追踪
+///
+/// 目标主机属于生产环境（主机环境为 production 或所属分组为生产分组）时，
+/// 仅管理员创建的作业保留详细追踪
+pub(crate) async fn resolve_enabled(
+    db: &PgPool,
+    requested: bool,
+    target_hosts: &[Host],
+    created_by: Uuid,
+) -> Result<bool> {
+    if !requested {
+        return Ok(false);
+    }
+
+    let mut group_ids: Vec<Uuid> = target_hosts.iter().map(|h| h.group_id).collect();
+    group_ids.sort();
+    group_ids.dedup();
+    let production = target_hosts
+        .iter()
+        .any(|h| h.environment.eq_ignore_ascii_case("production"))
+        || sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM assets_groups WHERE id = ANY($1) AND is_production)",
+        )
+        .bind(&group_ids)
+        .fetch_one(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to check production groups");
+            AppError::database("Failed to fetch groups")
+        })?;
+    if !production {
+        return Ok(true);
+    }
+
+    let permission_service = PermissionService::new(db.clone());
+    if permission_service.is_admin(created_by).await? {
+        info!(user_id = %created_by, "Verbose trace enabled for production targets by admin");
+        return Ok(true);
+    }
+    info!(user_id = %created_by, "Verbose trace disabled for production targets");
+    Ok(false)
+}
+
+/// 查询作业的追踪记录（按记录 ID 递增）
+pub(crate) async fn list(
+    db: &PgPool,
+    job_id: Uuid,
+    query: &JobTraceQuery,
+) -> Result<Vec<JobTraceEntry>> {
+    sqlx::query_as::<_, JobTraceEntry>(
+        r#"
+        SELECT * FROM job_trace_entries
+        WHERE job_id = $1 AND id > $2 AND ($3::uuid IS NULL OR task_id = $3)
+        ORDER BY id
+        LIMIT $4
+        "#,
+    )
+    .bind(job_id)
+    .bind(query.after_id)
+    .bind(query.task_id)
+    .bind(query.limit.clamp(1, MAX_QUERY_LIMIT))
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, job_id = %job_id, "Failed to fetch job trace");
+        AppError::database("Failed to fetch job trace")
+    })
+}
@@ -0,0 +1,807 @@
+//! 平台压测
+//!
+//! 在本实例上以模拟执行器运行合成作业，走完整的作业流水线（任务写库、发件箱、事件总线），
+//! 用于容量评估：
+//! - 调度吞吐：每秒完成的作业与任务数、创建作业耗时、作业创建到任务开始执行的等待时间
+//! - 数据库写放大：压测前后 pg_stat_database 计数与 WAL 位置的差值，按任务平均
+//! - 事件总线扇出：多个模拟订阅者接收合成作业事件并序列化为 SSE 数据，统计送达与丢弃数
+//! - SSE 延迟：任务执行结束到订阅者收到任务终态事件的时间
+//!
+//! 合成主机地址为 `*.invalid`，不会发起真实连接。
+//! 数据库统计包含同期其他流量且有秒级刷新延迟，结果为近似值
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::cache;
+use crate::concurrency::{ConcurrencyConfig, ConcurrencyController};
+use crate::config::{AppConfig, JobBudgetConfig, LoadTestConfig, OutputConfig, SshConfig};
+use crate::error::{AppError, Result};
+use crate::executor::{
+    CommandExecutor, ExecutionPayload, ExecutionRequest, MockBehavior, MockExecutor,
+};
+use crate::models::job::{CreateCommandJobRequest, SingletonPolicy};
+use crate::models::load_test::*;
+use crate::realtime::{EventBus, EventEnvelope, RealtimeEvent};
+use crate::services::{AuditService, JobService};
+use crate::ssh::ExecutionResult;
+
+/// 单个任务模拟执行耗时上限（毫秒）
+pub const MAX_TASK_DURATION_MS: u64 = 60_000;
+
+/// 单个任务模拟输出上限（字节）
+pub const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// 作业结束检查间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 等待发件箱事件中继完成的最长时间
+const OUTBOX_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 读取数据库统计前的等待时间（各连接的统计计数有秒级刷新延迟）
+const STATS_SETTLE: Duration = Duration::from_secs(1);
+
+/// 校验压测参数（未启用压测时拒绝）
+pub fn validate_request(config: &LoadTestConfig, request: &StartLoadTestRequest) -> Result<()> {
+    if !config.enabled {
+        return Err(AppError::BadRequest("Load testing is disabled on this instance".to_string()));
+    }
+    if request.jobs == 0 || request.hosts_per_job == 0 {
+        return Err(AppError::validation("jobs and hosts_per_job must be at least 1"));
+    }
+    let total_tasks = u64::from(request.jobs) * u64::from(request.hosts_per_job);
+    if total_tasks > config.max_total_tasks {
+        return Err(AppError::validation(&format!(
+            "Load test would create {} tasks, limit is {}",
+            total_tasks, config.max_total_tasks
+        )));
+    }
+    if request.subscribers > config.max_subscribers {
+        return Err(AppError::validation(&format!(
+            "subscribers must not exceed {}",
+            config.max_subscribers
+        )));
+    }
+    if request.task_duration_ms > MAX_TASK_DURATION_MS || request.output_bytes > MAX_OUTPUT_BYTES {
+        return Err(AppError::validation(&format!(
+            "task_duration_ms must not exceed {} and output_bytes must not exceed {}",
+            MAX_TASK_DURATION_MS, MAX_OUTPUT_BYTES
+        )));
+    }
+    Ok(())
+}
+
+/// 记录每次执行结束时间的模拟执行器
+///
+/// 合成作业的命令互不相同，按（命令, 主机地址）区分任务
+struct TimedExecutor {
+    inner: MockExecutor,
+    completed: DashMap<(String, String), Instant>,
+}
+
+#[async_trait]
+impl CommandExecutor for TimedExecutor {
+    fn name(&self) -> &'static str {
+        "load_test"
+    }
+
+    async fn execute(&self, request: ExecutionRequest) -> Result<ExecutionResult> {
+        let command = match &request.payload {
+            ExecutionPayload::Command(command) => command.clone(),
+            _ => String::new(),
+        };
+        let host = request.connection.host.clone();
+        let result = self.inner.execute(request).await;
+        self.completed.insert((command, host), Instant::now());
+        result
+    }
+}
+
+/// 单个模拟订阅者的接收统计
+#[derive(Debug, Default)]
+struct SubscriberStats {
+    /// 按作业统计的状态事件数（经发件箱写入的事件）
+    status_events: HashMap<Uuid, u64>,
+    /// 按作业统计的输出事件数（直接发布到事件总线）
+    output_events: HashMap<Uuid, u64>,
+    /// 按作业统计的 SSE 数据字节数
+    sse_bytes: HashMap<Uuid, u64>,
+    lagged: u64,
+    /// 任务终态事件的接收时间（仅探测订阅者记录）
+    terminal: HashMap<Uuid, Instant>,
+}
+
+impl SubscriberStats {
+    fn record(&mut self, envelope: &EventEnvelope, probe: bool) {
+        let (job_id, counter) = match &envelope.event {
+            RealtimeEvent::JobStatusChanged { job_id, .. } => (*job_id, &mut self.status_events),
+            RealtimeEvent::TaskStatusChanged {
+                task_id,
+                job_id,
+                new_status,
+                ..
+            } => {
+                if probe
+                    && !matches!(new_status.as_str(), "pending" | "running" | "waiting_maintenance")
+                {
+                    self.terminal.insert(*task_id, Instant::now());
+                }
+                (*job_id, &mut self.status_events)
+            }
+            RealtimeEvent::TaskOutputUpdate { job_id, .. }
+            | RealtimeEvent::TaskOutputSummarized { job_id, .. } => {
+                (*job_id, &mut self.output_events)
+            }
+            _ => return,
+        };
+        *counter.entry(job_id).or_default() += 1;
+        // 与 SSE 连接一致序列化每个事件，计入扇出的序列化开销
+        *self.sse_bytes.entry(job_id).or_default() += envelope.to_sse_data().len() as u64;
+    }
+
+    fn sum(counts: &HashMap<Uuid, u64>, job_ids: &[Uuid]) -> u64 {
+        job_ids.iter().filter_map(|id| counts.get(id)).sum()
+    }
+}
+
+/// 模拟订阅者：持续接收事件直到收到停止信号，停止前取完已到达的事件
+async fn subscribe(
+    mut rx: broadcast::Receiver<EventEnvelope>,
+    mut stop: watch::Receiver<bool>,
+    probe: bool,
+) -> SubscriberStats {
+    let mut stats = SubscriberStats::default();
+    loop {
+        let received = tokio::select! {
+            received = rx.recv() => received,
+            _ = stop.changed() => break,
+        };
+        match received {
+            Ok(envelope) => stats.record(&envelope, probe),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => stats.lagged += skipped,
+            Err(broadcast::error::RecvError::Closed) => return stats,
+        }
+    }
+    loop {
+        match rx.try_recv() {
+            Ok(envelope) => stats.record(&envelope, probe),
+            Err(broadcast::error::TryRecvError::Lagged(skipped)) => stats.lagged += skipped,
+            Err(_) => break,
+        }
+    }
+    stats
+}
+
+/// 数据库统计快照
+#[derive(Debug, Clone, Copy, sqlx::FromRow)]
+struct DbSnapshot {
+    transactions: i64,
+    inserted: i64,
+    updated: i64,
+    deleted: i64,
+    #[sqlx(skip)]
+    wal_position: Option<i64>,
+}
+
+/// 合成任务的执行记录
+#[derive(Debug, sqlx::FromRow)]
+struct SyntheticTask {
+    id: Uuid,
+    address: String,
+    command: Option<String>,
+    status: String,
+    /// 作业创建到任务开始执行的等待时间（毫秒）
+    start_delay_ms: Option<f64>,
+}
+
+/// 平台压测服务
+pub struct LoadTester {
+    db: Pool<Postgres>,
+    audit_service: Arc<AuditService>,
+    event_bus: Arc<EventBus>,
+    ssh_config: SshConfig,
+    budget: JobBudgetConfig,
+    output: OutputConfig,
+    config: LoadTestConfig,
+}
+
+impl LoadTester {
+    pub fn new(
+        db: Pool<Postgres>,
+        audit_service: Arc<AuditService>,
+        event_bus: Arc<EventBus>,
+        config: &AppConfig,
+    ) -> Self {
+        Self {
+            db,
+            audit_service,
+            event_bus,
+            ssh_config: config.ssh.clone(),
+            budget: config.job_budget.clone(),
+            output: config.output.clone(),
+            config: config.load_test.clone(),
+        }
+    }
+
+    /// 创建压测记录并在后台执行
+    pub async fn start(
+        self: &Arc<Self>,
+        request: StartLoadTestRequest,
+        requested_by: Uuid,
+    ) -> Result<LoadTestRun> {
+        validate_request(&self.config, &request)?;
+
+        let run = sqlx::query_as::<_, LoadTestRun>(
+            "INSERT INTO load_test_runs (requested_by, parameters) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(requested_by)
+        .bind(Json(&request))
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to create load test run");
+            AppError::database("Failed to create load test run")
+        })?;
+
+        let tester = self.clone();
+        let run_for_task = run.clone();
+        tokio::spawn(async move {
+            tester.execute(run_for_task).await;
+        });
+
+        Ok(run)
+    }
+
+    async fn execute(&self, run: LoadTestRun) {
+        let result = self.run(&run).await;
+        let stored = match &result {
+            Ok(report) => {
+                info!(
+                    run_id = %run.id,
+                    tasks = report.tasks,
+                    duration_secs = report.scheduler.duration_secs,
+                    tasks_per_sec = report.scheduler.tasks_per_sec,
+                    sse_p99_ms = report.sse_latency.p99_ms,
+                    "Load test finished"
+                );
+                sqlx::query(
+                    "UPDATE load_test_runs
+                     SET status = 'completed', report = $2, completed_at = NOW()
+                     WHERE id = $1",
+                )
+                .bind(run.id)
+                .bind(Json(report))
+                .execute(&self.db)
+                .await
+            }
+            Err(e) => {
+                error!(error = %e, run_id = %run.id, "Load test failed");
+                sqlx::query(
+                    "UPDATE load_test_runs
+                     SET status = 'failed', error_message = $2, completed_at = NOW()
+                     WHERE id = $1",
+                )
+                .bind(run.id)
+                .bind(e.to_string())
+                .execute(&self.db)
+                .await
+            }
+        };
+        if let Err(e) = stored {
+            error!(error = %e, run_id = %run.id, "Failed to store load test result");
+        }
+    }
+
+    async fn run(&self, run: &LoadTestRun) -> Result<LoadTestReport> {
+        let params = &run.parameters.0;
+        let (group_id, host_ids) = self.create_hosts(run, params.hosts_per_job).await?;
+
+        let mut job_ids = Vec::with_capacity(params.jobs as usize);
+        let result = self.measure(run, &host_ids, &mut job_ids).await;
+
+        if params.cleanup {
+            self.cleanup(group_id, &job_ids).await;
+        }
+        result
+    }
+
+    /// 在独立分组中批量创建合成主机
+    async fn create_hosts(&self, run: &LoadTestRun, count: u32) -> Result<(Uuid, Vec<Uuid>)> {
+        let prefix = format!("loadtest-{}", run.id.simple());
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, run_id = %run.id, "Failed to create synthetic hosts");
+            AppError::database("Failed to create synthetic hosts")
+        };
+
+        let group_id = sqlx::query_scalar::<_, Uuid>(
+            "INSERT INTO assets_groups (name, description, environment, created_by)
+             VALUES ($1, 'Synthetic hosts for a load test', 'dev', $2) RETURNING id",
+        )
+        .bind(&prefix)
+        .bind(run.requested_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(map_err)?;
+
+        let host_ids = sqlx::query_scalar::<_, Uuid>(
+            "INSERT INTO assets_hosts
+                 (identifier, address, group_id, environment, notes, created_by)
+             SELECT $1 || '-' || i, $1 || '-' || i || '.invalid', $2, 'dev',
+                    'Synthetic host for a load test', $3
+             FROM generate_series(1, $4) AS i
+             RETURNING id",
+        )
+        .bind(&prefix)
+        .bind(group_id)
+        .bind(run.requested_by)
+        .bind(count as i32)
+        .fetch_all(&self.db)
+        .await
+        .map_err(map_err)?;
+
+        Ok((group_id, host_ids))
+    }
+
+    async fn measure(
+        &self,
+        run: &LoadTestRun,
+        host_ids: &[Uuid],
+        job_ids: &mut Vec<Uuid>,
+    ) -> Result<LoadTestReport> {
+        let params = &run.parameters.0;
+        let executor = Arc::new(TimedExecutor {
+            inner: MockExecutor::new(MockBehavior::succeed(&"x".repeat(params.output_bytes)))
+                .with_delay(Duration::from_millis(params.task_duration_ms)),
+            completed: DashMap::new(),
+        });
+        // 独立的并发控制器（与服务启动时的配置一致），不占用真实作业的并发配额
+        let job_service = JobService::new(
+            self.db.clone(),
+            Arc::new(ConcurrencyController::new(ConcurrencyConfig::default())),
+            self.audit_service.clone(),
+            self.ssh_config.clone(),
+        )
+        .with_event_bus(self.event_bus.clone())
+        .with_executor(executor.clone())
+        .with_budget(self.budget.clone())
+        .with_output_config(self.output.clone());
+
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let subscribers: Vec<_> = (0..params.subscribers)
+            .map(|index| {
+                tokio::spawn(subscribe(self.event_bus.subscribe(), stop_rx.clone(), index == 0))
+            })
+            .collect();
+
+        let before = self.db_snapshot().await?;
+        let started = Instant::now();
+
+        let mut create_ms = Vec::with_capacity(params.jobs as usize);
+        for index in 0..params.jobs {
+            let begin = Instant::now();
+            let created = job_service
+                .create_command_job(synthetic_job(run.id, index, host_ids), run.requested_by)
+                .await;
+            match created {
+                Ok(job) => job_ids.push(job.id),
+                Err(e) => {
+                    let _ = stop_tx.send(true);
+                    self.cancel_jobs(&job_service, job_ids, run.requested_by)
+                        .await;
+                    return Err(e);
+                }
+            }
+            create_ms.push(begin.elapsed().as_secs_f64() * 1000.0);
+            if params.launch_interval_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(params.launch_interval_ms)).await;
+            }
+        }
+
+        let job_ids: &[Uuid] = job_ids;
+
+        // 等待全部作业结束，超时后取消剩余作业
+        let deadline = started + Duration::from_secs(self.config.timeout_secs);
+        let unfinished = loop {
+            let unfinished = self.unfinished_jobs(job_ids).await?;
+            if unfinished.is_empty() || Instant::now() >= deadline {
+                break unfinished;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        };
+        let duration = started.elapsed();
+        if !unfinished.is_empty() {
+            warn!(run_id = %run.id, unfinished = unfinished.len(), "Load test timed out");
+            self.cancel_jobs(&job_service, &unfinished, run.requested_by)
+                .await;
+        }
+
+        self.wait_outbox_drained(job_ids).await;
+        let _ = stop_tx.send(true);
+        let mut stats = Vec::with_capacity(subscribers.len());
+        for handle in subscribers {
+            if let Ok(subscriber) = handle.await {
+                stats.push(subscriber);
+            }
+        }
+
+        tokio::time::sleep(STATS_SETTLE).await;
+        let after = self.db_snapshot().await?;
+        let tasks = self.synthetic_tasks(job_ids).await?;
+        let events_published = self.outbox_events(job_ids).await?;
+
+        let executed = executor.completed.len() as u64;
+        let per_task = |value: i64| value as f64 / executed.max(1) as f64;
+        let secs = duration.as_secs_f64().max(f64::EPSILON);
+
+        let finished_jobs = job_ids.len().saturating_sub(unfinished.len());
+        let scheduler = SchedulerReport {
+            duration_secs: duration.as_secs_f64(),
+            jobs_per_sec: finished_jobs as f64 / secs,
+            tasks_per_sec: executed as f64 / secs,
+            job_create: LatencySummary::from_samples(create_ms),
+            task_start_delay: LatencySummary::from_samples(
+                tasks.iter().filter_map(|t| t.start_delay_ms).collect(),
+            ),
+            unfinished_jobs: unfinished.len() as u64,
+            failed_tasks: tasks
+                .iter()
+                .filter(|t| matches!(t.status.as_str(), "failed" | "timeout"))
+                .count() as u64,
+        };
+
+        let rows_written = (after.inserted - before.inserted)
+            + (after.updated - before.updated)
+            + (after.deleted - before.deleted);
+        let wal_bytes = after
+            .wal_position
+            .zip(before.wal_position)
+            .map(|(after, before)| after - before);
+        let database = DatabaseReport {
+            transactions: after.transactions - before.transactions,
+            rows_inserted: after.inserted - before.inserted,
+            rows_updated: after.updated - before.updated,
+            rows_deleted: after.deleted - before.deleted,
+            wal_bytes,
+            transactions_per_task: per_task(after.transactions - before.transactions),
+            rows_written_per_task: per_task(rows_written),
+            wal_bytes_per_task: wal_bytes.map(per_task),
+        };
+
+        let events_delivered: u64 = stats
+            .iter()
+            .map(|s| SubscriberStats::sum(&s.status_events, job_ids))
+            .sum();
+        let expected = events_published * u64::from(params.subscribers);
+        let event_bus = EventBusReport {
+            subscribers: params.subscribers,
+            events_published,
+            events_delivered,
+            output_events_delivered: stats
+                .iter()
+                .map(|s| SubscriberStats::sum(&s.output_events, job_ids))
+                .sum(),
+            events_lagged: stats.iter().map(|s| s.lagged).sum(),
+            delivery_ratio: if expected == 0 {
+                0.0
+            } else {
+                events_delivered as f64 / expected as f64
+            },
+            sse_bytes: stats
+                .iter()
+                .map(|s| SubscriberStats::sum(&s.sse_bytes, job_ids))
+                .sum(),
+        };
+
+        // 任务执行结束时间（执行器记录）与探测订阅者收到终态事件的时间之差
+        let sse_latency = match stats.first() {
+            Some(probe) => LatencySummary::from_samples(
+                tasks
+                    .iter()
+                    .filter_map(|task| {
+                        let received = probe.terminal.get(&task.id)?;
+                        let key = (task.command.clone().unwrap_or_default(), task.address.clone());
+                        let done = executor.completed.get(&key)?;
+                        Some(received.saturating_duration_since(*done).as_secs_f64() * 1000.0)
+                    })
+                    .collect(),
+            ),
+            None => LatencySummary::default(),
+        };
+
+        Ok(LoadTestReport {
+            jobs: params.jobs,
+            tasks: tasks.len() as u64,
+            scheduler,
+            database,
+            event_bus,
+            sse_latency,
+        })
+    }
+
+    async fn cancel_jobs(&self, job_service: &JobService, job_ids: &[Uuid], requested_by: Uuid) {
+        for job_id in job_ids {
+            if let Err(e) = job_service
+                .cancel_job(*job_id, requested_by, Some("Load test stopped".to_string()))
+                .await
+            {
+                warn!(error = %e, job_id = %job_id, "Failed to cancel load test job");
+            }
+        }
+    }
+
+    async fn unfinished_jobs(&self, job_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM jobs WHERE id = ANY($1) AND status IN ('pending', 'running')",
+        )
+        .bind(job_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to check load test jobs");
+            AppError::database("Failed to check load test jobs")
+        })
+    }
+
+    /// 等待合成作业的发件箱事件全部中继到事件总线
+    async fn wait_outbox_drained(&self, job_ids: &[Uuid]) {
+        let job_ids = job_id_strings(job_ids);
+        let deadline = Instant::now() + OUTBOX_DRAIN_TIMEOUT;
+        while Instant::now() < deadline {
+            let pending = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM event_outbox
+                 WHERE delivered_at IS NULL AND payload->'data'->>'job_id' = ANY($1)",
+            )
+            .bind(&job_ids)
+            .fetch_one(&self.db)
+            .await;
+            match pending {
+                Ok(0) => return,
+                Ok(_) => tokio::time::sleep(POLL_INTERVAL).await,
+                Err(e) => {
+                    warn!(error = %e, "Failed to check pending outbox events");
+                    return;
+                }
+            }
+        }
+        warn!("Outbox events of load test jobs were not fully relayed in time");
+    }
+
+    async fn outbox_events(&self, job_ids: &[Uuid]) -> Result<u64> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM event_outbox WHERE payload->'data'->>'job_id' = ANY($1)",
+        )
+        .bind(job_id_strings(job_ids))
+        .fetch_one(&self.db)
+        .await
+        .map(|count| count as u64)
+        .map_err(|e| {
+            error!(error = %e, "Failed to count load test events");
+            AppError::database("Failed to count load test events")
+        })
+    }
+
+    async fn synthetic_tasks(&self, job_ids: &[Uuid]) -> Result<Vec<SyntheticTask>> {
+        sqlx::query_as::<_, SyntheticTask>(
+            "SELECT t.id, h.address, j.command, t.status::text AS status,
+                    (EXTRACT(EPOCH FROM t.started_at - j.created_at) * 1000)::float8
+                        AS start_delay_ms
+             FROM tasks t
+             JOIN jobs j ON j.id = t.job_id
+             JOIN assets_hosts h ON h.id = t.host_id
+             WHERE t.job_id = ANY($1)",
+        )
+        .bind(job_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to load load test tasks");
+            AppError::database("Failed to load load test tasks")
+        })
+    }
+
+    /// 读取当前数据库的事务与行写入计数及 WAL 位置（备库或无权限时 WAL 位置为空）
+    async fn db_snapshot(&self) -> Result<DbSnapshot> {
+        let mut snapshot = sqlx::query_as::<_, DbSnapshot>(
+            "SELECT (xact_commit + xact_rollback)::bigint AS transactions,
+                    tup_inserted::bigint AS inserted, tup_updated::bigint AS updated,
+                    tup_deleted::bigint AS deleted
+             FROM pg_stat_database WHERE datname = current_database()",
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to read database statistics");
+            AppError::database("Failed to read database statistics")
+        })?;
+        snapshot.wal_position = sqlx::query_scalar::<_, i64>(
+            "SELECT pg_wal_lsn_diff(pg_current_wal_lsn(), '0/0')::bigint",
+        )
+        .fetch_one(&self.db)
+        .await
+        .ok();
+        Ok(snapshot)
+    }
+
+    /// 删除合成作业（任务随作业级联删除）与合成主机
+    async fn cleanup(&self, group_id: Uuid, job_ids: &[Uuid]) {
+        let steps = [
+            ("DELETE FROM jobs WHERE id = ANY($1)", None),
+            ("DELETE FROM assets_hosts WHERE group_id = $1", Some(group_id)),
+            ("DELETE FROM assets_groups WHERE id = $1", Some(group_id)),
+        ];
+        for (sql, group) in steps {
+            let query = match group {
+                Some(group_id) => sqlx::query(sql).bind(group_id),
+                None => sqlx::query(sql).bind(job_ids),
+            };
+            if let Err(e) = query.execute(&self.db).await {
+                warn!(error = %e, group_id = %group_id, "Failed to clean up load test data");
+                return;
+            }
+        }
+        cache::HOSTS.invalidate_all();
+    }
+
+    /// 列出压测记录（不含测量结果）
+    pub async fn list(&self, limit: i64) -> Result<Vec<LoadTestRun>> {
+        sqlx::query_as::<_, LoadTestRun>(
+            "SELECT id, requested_by, status, parameters, NULL::jsonb AS report, error_message,
+                    created_at, completed_at
+             FROM load_test_runs ORDER BY created_at DESC LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to list load test runs");
+            AppError::database("Failed to list load test runs")
+        })
+    }
+
+    pub async fn get(&self, run_id: Uuid) -> Result<Option<LoadTestRun>> {
+        sqlx::query_as::<_, LoadTestRun>("SELECT * FROM load_test_runs WHERE id = $1")
+            .bind(run_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, run_id = %run_id, "Failed to load load test run");
+                AppError::database("Failed to load load test run")
+            })
+    }
+}
+
+/// 合成作业：命令带有压测与作业序号，使各作业的执行内容互不相同
+fn synthetic_job(run_id: Uuid, index: u32, host_ids: &[Uuid]) -> CreateCommandJobRequest {
+    CreateCommandJobRequest {
+        name: format!("load-test-{}-{}", run_id.simple(), index),
+        description: Some("Synthetic job for a load test".to_string()),
+        target_hosts: host_ids.to_vec(),
+        target_groups: vec![],
+        target_set_id: None,
+        command: format!("true # load-test {} {}", run_id, index),
+        concurrent_limit: None,
+        timeout_secs: None,
+        retry_times: None,
+        execute_user: None,
+        exit_code_rules: None,
+        file_manifest: None,
+        drift_check: false,
+        verbose_trace: false,
+        idempotency_key: None,
+        singleton_key: None,
+        singleton_policy: SingletonPolicy::default(),
+        tags: vec![],
+        on_success_job_template: None,
+        on_failure_job_template: None,
+        artifact_id: None,
+    }
+}
+
+fn job_id_strings(job_ids: &[Uuid]) -> Vec<String> {
+    job_ids.iter().map(Uuid::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> StartLoadTestRequest {
+        StartLoadTestRequest {
+            jobs: 10,
+            hosts_per_job: 3000,
+            task_duration_ms: 50,
+            output_bytes: 256,
+            subscribers: 10,
+            launch_interval_ms: 0,
+            cleanup: true,
+        }
+    }
+
+    #[test]
+    fn test_subscriber_stats_per_job() {
+        let job_id = Uuid::new_v4();
+        let task_id = Uuid::new_v4();
+        let status = |new_status: &str| {
+            EventEnvelope::new(
+                RealtimeEvent::TaskStatusChanged {
+                    task_id,
+                    job_id,
+                    old_status: "running".to_string(),
+                    new_status: new_status.to_string(),
+                },
+                None,
+            )
+        };
+        let output = EventEnvelope::new(
+            RealtimeEvent::TaskOutputUpdate {
+                task_id,
+                job_id,
+                output: "ok".to_string(),
+                is_complete: true,
+            },
+            None,
+        );
+
+        let mut probe = SubscriberStats::default();
+        probe.record(&status("running"), true);
+        assert!(probe.terminal.is_empty());
+        probe.record(&output, true);
+        probe.record(&status("succeeded"), true);
+        probe.record(&EventEnvelope::new(RealtimeEvent::Heartbeat, None), true);
+
+        assert!(probe.terminal.contains_key(&task_id));
+        assert_eq!(SubscriberStats::sum(&probe.status_events, &[job_id]), 2);
+        assert_eq!(SubscriberStats::sum(&probe.output_events, &[job_id]), 1);
+        assert_eq!(SubscriberStats::sum(&probe.status_events, &[Uuid::new_v4()]), 0);
+        assert!(SubscriberStats::sum(&probe.sse_bytes, &[job_id]) > 0);
+
+        // 非探测订阅者不记录接收时间
+        let mut other = SubscriberStats::default();
+        other.record(&status("failed"), false);
+        assert!(other.terminal.is_empty());
+    }
+
+    #[test]
+    fn test_synthetic_jobs_have_distinct_commands() {
+        let run_id = Uuid::new_v4();
+        let hosts = vec![Uuid::new_v4(), Uuid::new_v4()];
+        let first = synthetic_job(run_id, 0, &hosts);
+        let second = synthetic_job(run_id, 1, &hosts);
+
+        assert_ne!(first.command, second.command);
+        assert!(first.command.starts_with("true # load-test"));
+        assert_eq!(first.target_hosts, hosts);
+    }
+
+    #[test]
+    fn test_validate_request() {
+        let mut config = LoadTestConfig {
+            enabled: true,
+            ..LoadTestConfig::default()
+        };
+        assert!(validate_request(&config, &request()).is_ok());
+
+        let mut too_many = request();
+        too_many.jobs = 40;
+        assert!(validate_request(&config, &too_many).is_err());
+
+        let mut empty = request();
+        empty.hosts_per_job = 0;
+        assert!(validate_request(&config, &empty).is_err());
+
+        let mut slow = request();
+        slow.task_duration_ms = MAX_TASK_DURATION_MS + 1;
+        assert!(validate_request(&config, &slow).is_err());
+
+        config.enabled = false;
+        assert!(matches!(validate_request(&config, &request()), Err(AppError::BadRequest(_))));
+    }
+}
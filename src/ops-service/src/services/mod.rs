@@ -1,17 +1,64 @@
 //! Business logic services layer
 
+pub mod advisory;
+pub mod anomaly_detector;
+pub mod approval_quorum;
+pub mod approval_reminder;
 pub mod approval_service;
+pub mod artifact_variants;
 pub mod audit_service;
 pub mod auth_service;
+pub mod blob_store;
+pub mod connection_test;
+pub mod credential_rotation;
+pub mod environment_policy;
+pub mod evidence_export;
+pub mod exit_code_rules;
+pub mod file_distribution;
+pub mod file_manifest;
+pub mod handover;
+pub mod host_key_drift;
+pub mod host_vars;
+pub mod inbound_webhook;
+pub mod job_archive;
+pub mod job_budget;
+pub mod job_dispatch;
 pub mod job_service;
+pub mod job_trace;
+pub mod load_test;
+pub mod network_backoff;
+pub mod notification_template;
+pub mod output_drift;
+pub mod output_shaper;
+pub mod package_inventory;
+pub mod pending_change;
 pub mod permission_service;
+pub mod runner_config_rollout;
 pub mod runner_service;
+pub mod soft_delete;
+pub mod stats_service;
 pub mod storage_service;
+pub mod task_journal;
+pub mod template_resolver;
+pub mod two_factor;
+pub mod view_audit;
 
+pub use advisory::AdvisoryImporter;
+pub use anomaly_detector::AnomalyDetector;
 pub use approval_service::ApprovalService;
 pub use audit_service::AuditService;
 pub use auth_service::AuthService;
+pub use blob_store::BlobStore;
+pub use environment_policy::EnvironmentPolicyService;
+pub use evidence_export::EvidenceExporter;
+pub use host_key_drift::HostKeyDriftMonitor;
+pub use job_archive::JobArchiver;
 pub use job_service::JobService;
+pub use load_test::LoadTester;
 pub use permission_service::PermissionService;
-pub use runner_service::{RunnerInfo, RunnerScheduler, RunnerSummary};
+pub use runner_config_rollout::RunnerConfigRolloutService;
+pub use runner_service::{ProjectAffinity, RunnerInfo, RunnerScheduler, RunnerSummary};
+pub use soft_delete::SoftDeletePurger;
+pub use stats_service::StatsService;
 pub use storage_service::{StorageConfig, StorageService, StorageType};
+pub use two_factor::TwoFactorService;
@@ -0,0 +1,192 @@
+//! 主机不可达退避
+//!
+//! 整个网段不可达时，作业的任务会逐台等到连接失败。执行期间各任务共享同一个计量器，
+//! 按最近结束的任务组成滑动窗口，不可达（网络错误、连接超时）的比例达到阈值时暂停派发：
+//! 已获得并发许可的任务在开始执行前等待，由作业记录暂停、推送告警事件并等待确认继续或中止。
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::Utc;
+use tokio::sync::watch;
+
+use crate::{
+    config::NetworkBackoffConfig,
+    models::job::{FailureReason, NetworkBackoffTrip},
+};
+
+/// 失败原因是否说明主机不可达
+pub fn is_unreachable(reason: &FailureReason) -> bool {
+    matches!(reason, FailureReason::NetworkError | FailureReason::ConnectionTimeout)
+}
+
+/// 单个作业的不可达退避计量器
+pub struct NetworkBackoff {
+    config: NetworkBackoffConfig,
+    /// 最近结束的任务是否不可达
+    outcomes: Mutex<VecDeque<bool>>,
+    /// 暂停中时为触发暂停的记录
+    paused: watch::Sender<Option<NetworkBackoffTrip>>,
+}
+
+impl NetworkBackoff {
+    pub fn new(config: NetworkBackoffConfig) -> Self {
+        Self {
+            config,
+            outcomes: Mutex::new(VecDeque::new()),
+            paused: watch::Sender::new(None),
+        }
+    }
+
+    /// 计入一个结束的任务，达到阈值时暂停派发并返回 true
+    ///
+    /// 暂停期间结束的任务（暂停前已开始执行）不计入
+    pub fn record(&self, unreachable: bool) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        let Ok(mut outcomes) = self.outcomes.lock() else {
+            return false;
+        };
+        if self.is_paused() {
+            return false;
+        }
+        outcomes.push_back(unreachable);
+        while outcomes.len() > self.config.window.max(1) {
+            outcomes.pop_front();
+        }
+
+        let sample_size = outcomes.len();
+        let unreachable_tasks = outcomes.iter().filter(|unreachable| **unreachable).count();
+        let failure_rate = unreachable_tasks as f64 / sample_size as f64;
+        if sample_size < self.config.min_samples.max(1) || failure_rate < self.config.failure_rate {
+            return false;
+        }
+        outcomes.clear();
+        self.paused.send_replace(Some(NetworkBackoffTrip {
+            unreachable_tasks: unreachable_tasks as u32,
+            sample_size: sample_size as u32,
+            failure_rate,
+            paused_at: Utc::now(),
+        }));
+        true
+    }
+
+    /// 是否暂停派发
+    pub fn is_paused(&self) -> bool {
+        self.paused.borrow().is_some()
+    }
+
+    /// 等待暂停派发，返回触发暂停的记录
+    pub async fn tripped(&self) -> Option<NetworkBackoffTrip> {
+        let mut receiver = self.paused.subscribe();
+        let trip = receiver.wait_for(Option::is_some).await.ok()?;
+        trip.clone()
+    }
+
+    /// 等待恢复派发（未暂停时立即返回）
+    pub async fn resumed(&self) {
+        let mut receiver = self.paused.subscribe();
+        let _ = receiver.wait_for(Option::is_none).await;
+    }
+
+    /// 恢复派发，窗口重新开始统计
+    pub fn resume(&self) {
+        if let Ok(mut outcomes) = self.outcomes.lock() {
+            outcomes.clear();
+        }
+        self.paused.send_replace(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn backoff(window: usize, min_samples: usize, failure_rate: f64) -> NetworkBackoff {
+        NetworkBackoff::new(NetworkBackoffConfig {
+            enabled: true,
+            window,
+            min_samples,
+            failure_rate,
+            poll_interval_secs: 1,
+        })
+    }
+
+    #[test]
+    fn test_trips_when_rate_reached_within_window() {
+        let backoff = backoff(4, 3, 0.75);
+        assert!(!backoff.record(true));
+        assert!(!backoff.record(true));
+        // 样本不足时不判定；第三个样本中 2/3 未达到阈值
+        assert!(!backoff.record(false));
+        assert!(!backoff.is_paused());
+        // 窗口内 3/4 不可达
+        assert!(backoff.record(true));
+        assert!(backoff.is_paused());
+
+        // 暂停期间结束的任务不计入
+        assert!(!backoff.record(true));
+        backoff.resume();
+        assert!(!backoff.is_paused());
+        assert!(!backoff.record(true));
+    }
+
+    #[test]
+    fn test_window_drops_old_outcomes() {
+        let backoff = backoff(3, 3, 0.6);
+        assert!(!backoff.record(true));
+        assert!(!backoff.record(false));
+        assert!(!backoff.record(false));
+        assert!(!backoff.record(true));
+        // 最早的不可达已移出窗口：[false, true, true]
+        assert!(backoff.record(true));
+    }
+
+    #[test]
+    fn test_disabled_never_trips() {
+        let backoff = NetworkBackoff::new(NetworkBackoffConfig {
+            enabled: false,
+            ..NetworkBackoffConfig::default()
+        });
+        for _ in 0..100 {
+            assert!(!backoff.record(true));
+        }
+        assert!(!backoff.is_paused());
+    }
+
+    #[tokio::test]
+    async fn test_waiters_released_on_resume() {
+        let backoff = std::sync::Arc::new(backoff(2, 2, 0.5));
+        // 未暂停时立即返回
+        tokio::time::timeout(Duration::from_secs(1), backoff.resumed())
+            .await
+            .expect("not paused");
+
+        backoff.record(true);
+        backoff.record(true);
+        let trip = backoff.tripped().await.unwrap();
+        assert_eq!((trip.unreachable_tasks, trip.sample_size), (2, 2));
+
+        let waiter = {
+            let backoff = backoff.clone();
+            tokio::spawn(async move { backoff.resumed().await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        backoff.resume();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter should be released")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_unreachable_reasons() {
+        assert!(is_unreachable(&FailureReason::NetworkError));
+        assert!(is_unreachable(&FailureReason::ConnectionTimeout));
+        assert!(!is_unreachable(&FailureReason::AuthFailed));
+        assert!(!is_unreachable(&FailureReason::CommandFailed));
+    }
+}
@@ -0,0 +1,742 @@
+//! 通知模板
+//!
+//! 通知文案可按事件类型、投递渠道与语言自定义，覆盖 i18n 目录中的内置文案。
+//! 模板使用 handlebars 风格语法：`{{变量}}` 插入变量，
+//! `{{#if 变量}}…{{else}}…{{/if}}` 按变量是否为空选择内容。
+//! 每次修改生成新版本，回滚以历史版本的内容生成新版本。
+//! 生效的模板缓存在进程内：修改后立即刷新本实例缓存，其他实例由后台任务定期刷新
+
+use chrono::Utc;
+use once_cell::sync::{Lazy, OnceCell};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::i18n::Locale;
+use crate::models::notification_template::{
+    NotificationEventVariables, NotificationTemplate, NotificationTemplatePreview,
+    NotificationTemplateVersion, UpsertNotificationTemplateRequest,
+};
+use crate::realtime::RealtimeEvent;
+
+/// 站内通知渠道（SSE 通知流）
+pub const CHANNEL_IN_APP: &str = "in_app";
+
+/// 支持的投递渠道
+pub const CHANNELS: &[&str] = &[CHANNEL_IN_APP];
+
+/// 各事件类型可用的模板变量
+pub const EVENT_VARIABLES: &[(&str, &[&str])] = &[
+    (
+        "watch_notification",
+        &[
+            "target_type",
+            "target_id",
+            "job_id",
+            "job_name",
+            "old_status",
+            "new_status",
+            "duration",
+            "link",
+        ],
+    ),
+    (
+        "approval_reminder",
+        &[
+            "approval_id",
+            "job_id",
+            "title",
+            "percent",
+            "expires_at",
+            "link",
+        ],
+    ),
+    ("approval_expired", &["approval_id", "job_id", "title", "expires_at", "link"]),
+];
+
+/// 模板内容最大长度（字符）
+const MAX_BODY_LENGTH: usize = 2000;
+
+/// 条件块最大嵌套层数
+const MAX_NESTING_DEPTH: usize = 8;
+
+/// 模板缓存键：(事件类型, 渠道, 语言)
+type TemplateKey = (String, String, Locale);
+
+/// 生效模板缓存：模板缓存键 -> 已解析的模板
+static ACTIVE_TEMPLATES: Lazy<RwLock<HashMap<TemplateKey, Arc<Template>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 通知链接前缀（启动时由配置设置）
+static LINK_BASE_URL: OnceCell<String> = OnceCell::new();
+
+/// 设置通知链接前缀（未设置时链接为相对路径）
+pub fn set_link_base_url(base_url: &str) {
+    let _ = LINK_BASE_URL.set(base_url.trim_end_matches('/').to_string());
+}
+
+fn link(path: &str) -> String {
+    format!("{}{}", LINK_BASE_URL.get().map_or("", String::as_str), path)
+}
+
+/// 事件类型可用的变量
+pub fn variables_for(event_type: &str) -> Option<&'static [&'static str]> {
+    EVENT_VARIABLES
+        .iter()
+        .find(|(name, _)| *name == event_type)
+        .map(|(_, variables)| *variables)
+}
+
+/// 全部事件类型及其变量
+pub fn all_variables() -> Vec<NotificationEventVariables> {
+    EVENT_VARIABLES
+        .iter()
+        .map(|(event_type, variables)| NotificationEventVariables {
+            event_type,
+            variables,
+        })
+        .collect()
+}
+
+/// 模板语法节点
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Variable(String),
+    If {
+        name: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// 解析中的条件块
+struct Block {
+    name: String,
+    then: Vec<Node>,
+    otherwise: Option<Vec<Node>>,
+}
+
+/// 已解析的模板
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// 解析模板，语法错误时返回原因
+    pub fn parse(body: &str) -> std::result::Result<Self, String> {
+        let mut root = Vec::new();
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut rest = body;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                current(&mut root, &mut blocks).push(Node::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| "Unclosed '{{' in template".to_string())?;
+            let tag = rest[start + 2..start + end].trim();
+            rest = &rest[start + end + 2..];
+
+            if let Some(name) = tag.strip_prefix("#if ") {
+                if blocks.len() >= MAX_NESTING_DEPTH {
+                    return Err("Conditional blocks are nested too deeply".to_string());
+                }
+                blocks.push(Block {
+                    name: variable_name(name.trim())?,
+                    then: Vec::new(),
+                    otherwise: None,
+                });
+            } else if tag == "else" {
+                match blocks.last_mut() {
+                    Some(block) if block.otherwise.is_none() => block.otherwise = Some(Vec::new()),
+                    Some(_) => return Err("Duplicate {{else}} in block".to_string()),
+                    None => return Err("{{else}} outside of {{#if}}".to_string()),
+                }
+            } else if tag == "/if" {
+                let block = blocks
+                    .pop()
+                    .ok_or_else(|| "{{/if}} without matching {{#if}}".to_string())?;
+                current(&mut root, &mut blocks).push(Node::If {
+                    name: block.name,
+                    then: block.then,
+                    otherwise: block.otherwise.unwrap_or_default(),
+                });
+            } else {
+                let name = variable_name(tag)?;
+                current(&mut root, &mut blocks).push(Node::Variable(name));
+            }
+        }
+        if let Some(block) = blocks.last() {
+            return Err(format!("Unclosed {{{{#if {}}}}}", block.name));
+        }
+        if !rest.is_empty() {
+            current(&mut root, &mut blocks).push(Node::Text(rest.to_string()));
+        }
+        Ok(Self { nodes: root })
+    }
+
+    /// 模板引用的变量（去重）
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        collect_variables(&self.nodes, &mut names);
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// 渲染模板，未提供的变量按空值处理
+    pub fn render(&self, variables: &[(&str, String)]) -> String {
+        let mut output = String::new();
+        render_nodes(&self.nodes, variables, &mut output);
+        output
+    }
+}
+
+/// 当前接收节点的位置（最内层条件块的当前分支）
+fn current<'a>(root: &'a mut Vec<Node>, blocks: &'a mut [Block]) -> &'a mut Vec<Node> {
+    match blocks.last_mut() {
+        Some(Block {
+            otherwise: Some(otherwise),
+            ..
+        }) => otherwise,
+        Some(block) => &mut block.then,
+        None => root,
+    }
+}
+
+fn variable_name(name: &str) -> std::result::Result<String, String> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!("Invalid variable name: '{}'", name))
+    }
+}
+
+fn collect_variables<'a>(nodes: &'a [Node], names: &mut Vec<&'a str>) {
+    for node in nodes {
+        match node {
+            Node::Text(_) => {}
+            Node::Variable(name) => names.push(name),
+            Node::If {
+                name,
+                then,
+                otherwise,
+            } => {
+                names.push(name);
+                collect_variables(then, names);
+                collect_variables(otherwise, names);
+            }
+        }
+    }
+}
+
+fn render_nodes(nodes: &[Node], variables: &[(&str, String)], output: &mut String) {
+    let value = |name: &str| {
+        variables
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map_or("", |(_, v)| v.as_str())
+    };
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable(name) => output.push_str(value(name)),
+            Node::If {
+                name,
+                then,
+                otherwise,
+            } => {
+                let branch = if value(name).is_empty() {
+                    otherwise
+                } else {
+                    then
+                };
+                render_nodes(branch, variables, output);
+            }
+        }
+    }
+}
+
+/// 校验模板：事件类型与渠道受支持、语法正确、只引用该事件类型的变量
+pub fn validate(event_type: &str, channel: &str, body: &str) -> Result<Template> {
+    let allowed = variables_for(event_type).ok_or_else(|| {
+        AppError::validation(&format!("Unsupported notification event type: {}", event_type))
+    })?;
+    if !CHANNELS.contains(&channel) {
+        return Err(AppError::validation(&format!(
+            "Unsupported notification channel: {}",
+            channel
+        )));
+    }
+    if body.trim().is_empty() {
+        return Err(AppError::validation("Template body is required"));
+    }
+    if body.chars().count() > MAX_BODY_LENGTH {
+        return Err(AppError::validation(&format!(
+            "Template body exceeds {} characters",
+            MAX_BODY_LENGTH
+        )));
+    }
+    let template = Template::parse(body)
+        .map_err(|e| AppError::validation(&format!("Invalid template: {}", e)))?;
+    if let Some(unknown) = template
+        .variables()
+        .into_iter()
+        .find(|v| !allowed.contains(v))
+    {
+        return Err(AppError::validation(&format!(
+            "Unknown variable '{}' for {}; available: {}",
+            unknown,
+            event_type,
+            allowed.join(", ")
+        )));
+    }
+    Ok(template)
+}
+
+/// 事件的类型名与模板变量（非通知类事件返回 None）
+pub fn event_variables(
+    event: &RealtimeEvent,
+) -> Option<(&'static str, Vec<(&'static str, String)>)> {
+    let optional = |id: &Option<Uuid>| id.map(|id| id.to_string()).unwrap_or_default();
+    match event {
+        RealtimeEvent::WatchNotification {
+            target_type,
+            target_id,
+            job_id,
+            job_name,
+            old_status,
+            new_status,
+            duration_secs,
+            ..
+        } => Some((
+            "watch_notification",
+            vec![
+                ("target_type", target_type.clone()),
+                ("target_id", target_id.to_string()),
+                ("job_id", optional(job_id)),
+                ("job_name", job_name.clone().unwrap_or_default()),
+                ("old_status", old_status.clone().unwrap_or_default()),
+                ("new_status", new_status.clone()),
+                ("duration", duration_secs.map(format_duration).unwrap_or_default()),
+                (
+                    "link",
+                    job_id.map_or_else(String::new, |id| link(&format!("/api/v1/jobs/{}", id))),
+                ),
+            ],
+        )),
+        RealtimeEvent::ApprovalReminder {
+            approval_id,
+            job_id,
+            title,
+            percent,
+            expires_at,
+            ..
+        } => Some((
+            "approval_reminder",
+            vec![
+                ("approval_id", approval_id.to_string()),
+                ("job_id", optional(job_id)),
+                ("title", title.clone()),
+                ("percent", percent.to_string()),
+                ("expires_at", expires_at.format("%Y-%m-%d %H:%M UTC").to_string()),
+                ("link", link(&format!("/api/v1/approvals/{}", approval_id))),
+            ],
+        )),
+        RealtimeEvent::ApprovalExpired {
+            approval_id,
+            job_id,
+            title,
+            expires_at,
+            ..
+        } => Some((
+            "approval_expired",
+            vec![
+                ("approval_id", approval_id.to_string()),
+                ("job_id", optional(job_id)),
+                ("title", title.clone()),
+                ("expires_at", expires_at.format("%Y-%m-%d %H:%M UTC").to_string()),
+                ("link", link(&format!("/api/v1/approvals/{}", approval_id))),
+            ],
+        )),
+        _ => None,
+    }
+}
+
+/// 时长的可读格式（如 `1h 2m 5s`）
+pub fn format_duration(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (hours, minutes, seconds) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m {}s", minutes, seconds),
+        _ => format!("{}h {}m {}s", hours, minutes, seconds),
+    }
+}
+
+/// 使用自定义模板渲染通知文案，未配置模板时返回 None（使用内置文案）
+pub fn render_custom(event: &RealtimeEvent, channel: &str, locale: Locale) -> Option<String> {
+    let (event_type, variables) = event_variables(event)?;
+    let template = ACTIVE_TEMPLATES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&(event_type.to_string(), channel.to_string(), locale))
+        .cloned()?;
+    Some(template.render(&variables))
+}
+
+/// 示例事件（用于预览）
+pub fn sample_event(event_type: &str) -> Option<RealtimeEvent> {
+    let sample_job_id = Uuid::from_u128(0x6f1c_2a7e_9b3d_4c58_a1e2_0d4b_7c9e_3f21);
+    let job_id = Some(sample_job_id);
+    let approval_id = Uuid::from_u128(0x2b8d_41f0_6c3a_4e97_b5d2_8a1f_0e6c_9d34);
+    let expires_at = Utc::now() + chrono::Duration::hours(1);
+    let title = "Restart nginx on production".to_string();
+    match event_type {
+        "watch_notification" => Some(RealtimeEvent::WatchNotification {
+            user_id: Uuid::nil(),
+            target_type: "job".to_string(),
+            target_id: sample_job_id,
+            job_id,
+            job_name: Some("nightly-backup".to_string()),
+            old_status: Some("running".to_string()),
+            new_status: "failed".to_string(),
+            duration_secs: Some(95),
+        }),
+        "approval_reminder" => Some(RealtimeEvent::ApprovalReminder {
+            approval_id,
+            job_id,
+            title,
+            percent: 75,
+            expires_at,
+            approver_ids: Vec::new(),
+        }),
+        "approval_expired" => Some(RealtimeEvent::ApprovalExpired {
+            approval_id,
+            job_id,
+            title,
+            requested_by: Uuid::nil(),
+            expires_at,
+        }),
+        _ => None,
+    }
+}
+
+/// 使用示例事件渲染模板
+pub fn preview(
+    event_type: &str,
+    body: &str,
+    locale: Locale,
+) -> Result<NotificationTemplatePreview> {
+    let template = validate(event_type, CHANNEL_IN_APP, body)?;
+    let event = sample_event(event_type).ok_or_else(|| {
+        AppError::validation(&format!("Unsupported notification event type: {}", event_type))
+    })?;
+    let (_, variables) = event_variables(&event).unwrap_or_default();
+    Ok(NotificationTemplatePreview {
+        rendered: template.render(&variables),
+        variables: variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::String(value.clone())))
+            .collect::<Map<_, _>>(),
+        builtin: event.builtin_notification_message(locale),
+    })
+}
+
+/// 列出通知模板（附当前版本内容）
+pub async fn list(db: &PgPool) -> Result<Vec<NotificationTemplate>> {
+    sqlx::query_as::<_, NotificationTemplate>(
+        r#"
+        SELECT t.id, t.event_type, t.channel, t.locale, t.current_version, v.body,
+               t.updated_by, t.created_at, t.updated_at
+        FROM notification_templates t
+        JOIN notification_template_versions v
+          ON v.template_id = t.id AND v.version = t.current_version
+        ORDER BY t.event_type, t.channel, t.locale
+        "#,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to list notification templates");
+        AppError::database("Failed to list notification templates")
+    })
+}
+
+/// 查询通知模板
+pub async fn get(db: &PgPool, id: Uuid) -> Result<NotificationTemplate> {
+    sqlx::query_as::<_, NotificationTemplate>(
+        r#"
+        SELECT t.id, t.event_type, t.channel, t.locale, t.current_version, v.body,
+               t.updated_by, t.created_at, t.updated_at
+        FROM notification_templates t
+        JOIN notification_template_versions v
+          ON v.template_id = t.id AND v.version = t.current_version
+        WHERE t.id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, template_id = %id, "Failed to get notification template");
+        AppError::database("Failed to get notification template")
+    })?
+    .ok_or_else(|| AppError::not_found("Notification template not found"))
+}
+
+/// 查询模板的版本历史（新版本在前）
+pub async fn list_versions(db: &PgPool, id: Uuid) -> Result<Vec<NotificationTemplateVersion>> {
+    sqlx::query_as::<_, NotificationTemplateVersion>(
+        "SELECT * FROM notification_template_versions WHERE template_id = $1 ORDER BY version DESC",
+    )
+    .bind(id)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, template_id = %id, "Failed to list notification template versions");
+        AppError::database("Failed to list notification template versions")
+    })
+}
+
+/// 创建模板或为已有模板生成新版本，返回模板 ID
+pub async fn upsert(
+    db: &PgPool,
+    request: &UpsertNotificationTemplateRequest,
+    updated_by: Uuid,
+) -> Result<Uuid> {
+    validate(&request.event_type, &request.channel, &request.body)?;
+
+    let db_err = |e: sqlx::Error| {
+        error!(error = %e, "Failed to save notification template");
+        AppError::database("Failed to save notification template")
+    };
+    let mut tx = db.begin().await.map_err(db_err)?;
+    let (id, version): (Uuid, i32) = sqlx::query_as(
+        r#"
+        INSERT INTO notification_templates (event_type, channel, locale, updated_by)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (event_type, channel, locale) DO UPDATE SET
+            current_version = notification_templates.current_version + 1,
+            updated_by = EXCLUDED.updated_by,
+            updated_at = NOW()
+        RETURNING id, current_version
+        "#,
+    )
+    .bind(&request.event_type)
+    .bind(&request.channel)
+    .bind(request.locale.tag())
+    .bind(updated_by)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+    insert_version(
+        &mut tx,
+        id,
+        version,
+        &request.body,
+        request.comment.as_deref(),
+        None,
+        updated_by,
+    )
+    .await
+    .map_err(db_err)?;
+    tx.commit().await.map_err(db_err)?;
+    Ok(id)
+}
+
+/// 以历史版本的内容生成新版本
+pub async fn rollback(db: &PgPool, id: Uuid, version: i32, updated_by: Uuid) -> Result<i32> {
+    let db_err = |e: sqlx::Error| {
+        error!(error = %e, template_id = %id, "Failed to roll back notification template");
+        AppError::database("Failed to roll back notification template")
+    };
+    let mut tx = db.begin().await.map_err(db_err)?;
+    let current: i32 = sqlx::query_scalar(
+        "SELECT current_version FROM notification_templates WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or_else(|| AppError::not_found("Notification template not found"))?;
+    if version == current {
+        return Err(AppError::validation(&format!("Version {} is already current", version)));
+    }
+    let body: String = sqlx::query_scalar(
+        "SELECT body FROM notification_template_versions WHERE template_id = $1 AND version = $2",
+    )
+    .bind(id)
+    .bind(version)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or_else(|| AppError::not_found("Notification template version not found"))?;
+
+    let new_version = current + 1;
+    insert_version(&mut tx, id, new_version, &body, None, Some(version), updated_by)
+        .await
+        .map_err(db_err)?;
+    sqlx::query(
+        "UPDATE notification_templates SET current_version = $2, updated_by = $3, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(id)
+    .bind(new_version)
+    .bind(updated_by)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+    tx.commit().await.map_err(db_err)?;
+    Ok(new_version)
+}
+
+async fn insert_version(
+    tx: &mut sqlx::PgConnection,
+    template_id: Uuid,
+    version: i32,
+    body: &str,
+    comment: Option<&str>,
+    rolled_back_from: Option<i32>,
+    created_by: Uuid,
+) -> std::result::Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO notification_template_versions
+            (template_id, version, body, comment, rolled_back_from, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(template_id)
+    .bind(version)
+    .bind(body)
+    .bind(comment)
+    .bind(rolled_back_from)
+    .bind(created_by)
+    .execute(tx)
+    .await?;
+    Ok(())
+}
+
+/// 删除模板（恢复使用内置文案）
+pub async fn delete(db: &PgPool, id: Uuid) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM notification_templates WHERE id = $1")
+        .bind(id)
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, template_id = %id, "Failed to delete notification template");
+            AppError::database("Failed to delete notification template")
+        })?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// 从数据库重新加载生效模板到进程内缓存，返回模板数
+///
+/// 无法解析的模板（例如升级后变量被移除）跳过并记录警告，对应通知回退到内置文案
+pub async fn reload(db: &PgPool) -> Result<usize> {
+    let templates = list(db).await?;
+    let mut active = HashMap::with_capacity(templates.len());
+    for template in templates {
+        let Some(locale) = Locale::parse(&template.locale) else {
+            warn!(template_id = %template.id, locale = %template.locale, "Skipping notification template with unsupported locale");
+            continue;
+        };
+        match validate(&template.event_type, &template.channel, &template.body) {
+            Ok(parsed) => {
+                active.insert((template.event_type, template.channel, locale), Arc::new(parsed));
+            }
+            Err(e) => {
+                warn!(template_id = %template.id, error = %e, "Skipping invalid notification template");
+            }
+        }
+    }
+    let count = active.len();
+    *ACTIVE_TEMPLATES.write().unwrap_or_else(|e| e.into_inner()) = active;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_render_variables() {
+        let template =
+            Template::parse("Job {{ job_name }} is {{new_status}} ({{duration}})").unwrap();
+        assert_eq!(template.variables(), vec!["duration", "job_name", "new_status"]);
+        let rendered = template.render(&[
+            ("job_name", "backup".to_string()),
+            ("new_status", "failed".to_string()),
+        ]);
+        assert_eq!(rendered, "Job backup is failed ()");
+    }
+
+    #[test]
+    fn test_conditional_blocks() {
+        let template = Template::parse(
+            "{{#if old_status}}{{old_status}} -> {{new_status}}{{else}}now {{new_status}}{{/if}}",
+        )
+        .unwrap();
+        let vars = [
+            ("old_status", "running".to_string()),
+            ("new_status", "failed".to_string()),
+        ];
+        assert_eq!(template.render(&vars), "running -> failed");
+        let vars = [
+            ("old_status", String::new()),
+            ("new_status", "failed".to_string()),
+        ];
+        assert_eq!(template.render(&vars), "now failed");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{{job_name").is_err());
+        assert!(Template::parse("{{#if job_name}}x").is_err());
+        assert!(Template::parse("x{{/if}}").is_err());
+        assert!(Template::parse("{{else}}").is_err());
+        assert!(Template::parse("{{#if a}}1{{else}}2{{else}}3{{/if}}").is_err());
+        assert!(Template::parse("{{job name}}").is_err());
+        assert!(Template::parse("plain text").is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_variables() {
+        assert!(validate("watch_notification", CHANNEL_IN_APP, "{{job_name}} {{link}}").is_ok());
+        assert!(validate("approval_expired", CHANNEL_IN_APP, "{{job_name}}").is_err());
+        assert!(validate("job_status_changed", CHANNEL_IN_APP, "{{job_id}}").is_err());
+        assert!(validate("watch_notification", "sms", "{{job_name}}").is_err());
+        assert!(validate("watch_notification", CHANNEL_IN_APP, "  ").is_err());
+    }
+
+    #[test]
+    fn test_preview_with_sample_event() {
+        for (event_type, _) in EVENT_VARIABLES {
+            assert!(sample_event(event_type).is_some());
+        }
+        let preview = preview(
+            "watch_notification",
+            "{{job_name}} {{new_status}} after {{duration}}",
+            Locale::EnUs,
+        )
+        .unwrap();
+        assert_eq!(preview.rendered, "nightly-backup failed after 1m 35s");
+        assert!(preview.builtin.is_some());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(95), "1m 35s");
+        assert_eq!(format_duration(3725), "1h 2m 5s");
+        assert_eq!(format_duration(-3), "0s");
+    }
+}
@@ -0,0 +1,228 @@
+//! 基线输出比对（偏离检测）
+//!
+//! 偏离检测作业执行成功后，按模板+主机、模板、主机的顺序查找适用的基线，
+//! 将 stdout 与基线逐行比对（忽略行尾空白、换行符差异与末尾空行），不一致时记录差异行。
+//! 差异先去掉相同的首尾行，剩余部分行数较少时以最长公共子序列对齐，否则整体记为删除/新增
+
+use sqlx::{Pool, Postgres};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::job::{DriftState, OutputBaseline, OutputDrift, SetOutputBaselineRequest},
+};
+
+/// 基线输出的大小上限
+pub const MAX_BASELINE_BYTES: usize = 1024 * 1024;
+
+/// 单个任务保留的差异行数上限
+pub const MAX_DIFF_LINES: usize = 200;
+
+/// 以最长公共子序列对齐的最大规模（两侧差异行数之积）
+const MAX_ALIGN_CELLS: usize = 1_000_000;
+
+/// 校验基线输出请求
+pub fn validate_baseline(request: &SetOutputBaselineRequest) -> Result<()> {
+    if request.template_id.is_none() && request.host_id.is_none() {
+        return Err(AppError::validation("Baseline requires a template_id or host_id"));
+    }
+    if request.expected_output.len() > MAX_BASELINE_BYTES {
+        return Err(AppError::validation(&format!(
+            "Baseline output exceeds {} bytes",
+            MAX_BASELINE_BYTES
+        )));
+    }
+    Ok(())
+}
+
+/// 查找任务适用的基线：模板+主机 > 模板 > 主机
+pub async fn find_baseline(
+    db: &Pool<Postgres>,
+    template_id: Option<Uuid>,
+    host_id: Uuid,
+) -> Result<Option<OutputBaseline>> {
+    sqlx::query_as::<_, OutputBaseline>(
+        r#"
+        SELECT * FROM output_baselines
+        WHERE (template_id = $1 AND (host_id = $2 OR host_id IS NULL))
+           OR (template_id IS NULL AND host_id = $2)
+        ORDER BY template_id IS NULL, host_id IS NULL
+        LIMIT 1
+        "#,
+    )
+    .bind(template_id)
+    .bind(host_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, host_id = %host_id, "Failed to fetch output baseline");
+        AppError::database("Failed to fetch output baseline")
+    })
+}
+
+/// 将实际输出与基线比对，差异行已脱敏
+pub fn compare(baseline: Option<&OutputBaseline>, actual: &str) -> OutputDrift {
+    let Some(baseline) = baseline else {
+        return OutputDrift {
+            state: DriftState::NoBaseline,
+            baseline_id: None,
+            diff: Vec::new(),
+            truncated: false,
+        };
+    };
+
+    let mut diff = diff_lines(&normalize(&baseline.expected_output), &normalize(actual));
+    let truncated = diff.len() > MAX_DIFF_LINES;
+    diff.truncate(MAX_DIFF_LINES);
+    let sanitizer = crate::output::default_sanitizer();
+    OutputDrift {
+        state: if diff.is_empty() {
+            DriftState::InSync
+        } else {
+            DriftState::Drifted
+        },
+        baseline_id: Some(baseline.id),
+        diff: diff.iter().map(|line| sanitizer.sanitize(line)).collect(),
+        truncated,
+    }
+}
+
+/// 按行拆分并去除行尾空白与末尾空行
+fn normalize(output: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// 逐行差异："-" 为基线独有的行，"+" 为实际输出独有的行
+fn diff_lines(expected: &[&str], actual: &[&str]) -> Vec<String> {
+    let prefix = expected
+        .iter()
+        .zip(actual)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (expected, actual) = (&expected[prefix..], &actual[prefix..]);
+    let suffix = expected
+        .iter()
+        .rev()
+        .zip(actual.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let expected = &expected[..expected.len() - suffix];
+    let actual = &actual[..actual.len() - suffix];
+
+    let removed = |line: &&str| format!("-{}", line);
+    let added = |line: &&str| format!("+{}", line);
+    if expected.len().saturating_mul(actual.len()) > MAX_ALIGN_CELLS {
+        return expected
+            .iter()
+            .map(removed)
+            .chain(actual.iter().map(added))
+            .collect();
+    }
+
+    // lcs[i][j]：expected[i..] 与 actual[j..] 的最长公共子序列长度
+    let (n, m) = (expected.len(), actual.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(removed(&expected[i]));
+            i += 1;
+        } else {
+            diff.push(added(&actual[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn baseline(expected: &str) -> OutputBaseline {
+        OutputBaseline {
+            id: Uuid::new_v4(),
+            template_id: None,
+            host_id: Some(Uuid::new_v4()),
+            expected_output: expected.to_string(),
+            description: None,
+            created_by: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_compare_ignores_whitespace_differences() {
+        let baseline = baseline("PermitRootLogin no\nPasswordAuthentication no\n");
+        let drift =
+            compare(Some(&baseline), "PermitRootLogin no  \r\nPasswordAuthentication no\r\n\r\n");
+        assert_eq!(drift.state, DriftState::InSync);
+        assert_eq!(drift.baseline_id, Some(baseline.id));
+        assert!(drift.diff.is_empty());
+
+        let missing = compare(None, "anything");
+        assert_eq!(missing.state, DriftState::NoBaseline);
+        assert_eq!(missing.baseline_id, None);
+    }
+
+    #[test]
+    fn test_compare_reports_changed_lines() {
+        let baseline = baseline("a\nb\nc\nd\n");
+        let drift = compare(Some(&baseline), "a\nx\nc\nd\ne\n");
+        assert_eq!(drift.state, DriftState::Drifted);
+        assert_eq!(drift.diff, vec!["-b", "+x", "+e"]);
+        assert!(!drift.truncated);
+    }
+
+    #[test]
+    fn test_diff_is_truncated() {
+        let expected = (0..MAX_DIFF_LINES)
+            .map(|i| format!("line {}\n", i))
+            .collect::<String>();
+        let drift = compare(Some(&baseline(&expected)), "");
+        assert_eq!(drift.diff.len(), MAX_DIFF_LINES);
+        assert!(!drift.truncated);
+
+        let drift = compare(Some(&baseline(&expected)), "other\n");
+        assert_eq!(drift.diff.len(), MAX_DIFF_LINES);
+        assert!(drift.truncated);
+    }
+
+    #[test]
+    fn test_validate_baseline_requires_scope() {
+        let request = SetOutputBaselineRequest {
+            template_id: None,
+            host_id: None,
+            expected_output: "ok".to_string(),
+            description: None,
+        };
+        assert!(validate_baseline(&request).is_err());
+
+        let request = SetOutputBaselineRequest {
+            host_id: Some(Uuid::new_v4()),
+            ..request
+        };
+        assert!(validate_baseline(&request).is_ok());
+    }
+}
@@ -0,0 +1,176 @@
+//! 任务增量输出的推送整形
+//!
+//! 持续大量输出的命令（如 `yes`）会以高频事件占满事件总线与 SSE 客户端。每个任务独立整形：
+//! - 合并窗口内的多次输出：执行器每次推送截至当前的完整输出，合并即保留最新一份，
+//!   被合并的内容随下一次推送或任务完成时一并送达
+//! - 令牌桶限制每秒事件数，突发上限为每秒事件数
+//! - 输出量超过阈值后切换为仅摘要模式：推送一次提示事件，之后不再推送增量输出，
+//!   任务结束时仍推送保存后的输出摘要
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::OutputConfig;
+
+/// 整形后需要推送的事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapedOutput {
+    /// 推送输出
+    Output { output: String, is_complete: bool },
+    /// 切换为仅摘要模式的提示
+    Summarized {
+        output_bytes: u64,
+        threshold_bytes: u64,
+    },
+}
+
+struct ShaperState {
+    last_emit: Option<Instant>,
+    tokens: f64,
+    last_refill: Instant,
+    summarized: bool,
+}
+
+/// 单个任务的增量输出整形器
+pub struct OutputShaper {
+    window: Duration,
+    max_events_per_sec: u32,
+    summary_threshold_bytes: u64,
+    state: Mutex<ShaperState>,
+}
+
+impl OutputShaper {
+    pub fn new(config: &OutputConfig) -> Self {
+        Self {
+            window: Duration::from_millis(config.coalesce_window_ms),
+            max_events_per_sec: config.max_events_per_sec,
+            summary_threshold_bytes: config.summary_threshold_bytes,
+            state: Mutex::new(ShaperState {
+                last_emit: None,
+                tokens: config.max_events_per_sec as f64,
+                last_refill: Instant::now(),
+                summarized: false,
+            }),
+        }
+    }
+
+    /// 提交一次输出，返回需要推送的事件（None 表示本次合并到之后的推送或已切换为摘要模式）
+    pub fn offer(&self, output: String, is_complete: bool) -> Option<ShapedOutput> {
+        self.offer_at(Instant::now(), output, is_complete)
+    }
+
+    fn offer_at(&self, now: Instant, output: String, is_complete: bool) -> Option<ShapedOutput> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.summarized {
+            return None;
+        }
+
+        let output_bytes = output.len() as u64;
+        if self.summary_threshold_bytes > 0 && output_bytes > self.summary_threshold_bytes {
+            state.summarized = true;
+            return Some(ShapedOutput::Summarized {
+                output_bytes,
+                threshold_bytes: self.summary_threshold_bytes,
+            });
+        }
+
+        // 完成时的输出总是推送，不占用速率
+        if is_complete {
+            return Some(ShapedOutput::Output {
+                output,
+                is_complete,
+            });
+        }
+
+        if state
+            .last_emit
+            .is_some_and(|last| now.duration_since(last) < self.window)
+        {
+            return None;
+        }
+
+        if self.max_events_per_sec > 0 {
+            let rate = self.max_events_per_sec as f64;
+            let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * rate).min(rate);
+            state.last_refill = now;
+            if state.tokens < 1.0 {
+                return None;
+            }
+            state.tokens -= 1.0;
+        }
+
+        state.last_emit = Some(now);
+        Some(ShapedOutput::Output {
+            output,
+            is_complete,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shaper(window_ms: u64, max_events_per_sec: u32, threshold: u64) -> OutputShaper {
+        OutputShaper::new(&OutputConfig {
+            coalesce_window_ms: window_ms,
+            max_events_per_sec,
+            summary_threshold_bytes: threshold,
+            ..Default::default()
+        })
+    }
+
+    fn emitted(result: Option<ShapedOutput>) -> bool {
+        matches!(result, Some(ShapedOutput::Output { .. }))
+    }
+
+    #[test]
+    fn test_coalesces_within_window() {
+        let shaper = shaper(200, 0, 0);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        assert!(emitted(shaper.offer_at(at(0), "a".into(), false)));
+        assert!(!emitted(shaper.offer_at(at(50), "ab".into(), false)));
+        assert!(!emitted(shaper.offer_at(at(150), "abc".into(), false)));
+        assert_eq!(
+            shaper.offer_at(at(250), "abcd".into(), false),
+            Some(ShapedOutput::Output {
+                output: "abcd".into(),
+                is_complete: false
+            })
+        );
+        // 完成时的输出不受窗口限制
+        assert!(emitted(shaper.offer_at(at(260), "abcde".into(), true)));
+    }
+
+    #[test]
+    fn test_caps_events_per_second() {
+        let shaper = shaper(0, 2, 0);
+        let start = Instant::now();
+        let sent = (0..10)
+            .filter(|i| {
+                let now = start + Duration::from_millis(i * 100);
+                emitted(shaper.offer_at(now, "x".into(), false))
+            })
+            .count();
+        // 初始突发 2 个，之后每 500ms 补充 1 个
+        assert_eq!(sent, 3);
+    }
+
+    #[test]
+    fn test_switches_to_summary_once() {
+        let shaper = shaper(0, 0, 10);
+        let start = Instant::now();
+        assert!(emitted(shaper.offer_at(start, "0123456789".into(), false)));
+        assert_eq!(
+            shaper.offer_at(start, "0123456789ab".into(), false),
+            Some(ShapedOutput::Summarized {
+                output_bytes: 12,
+                threshold_bytes: 10
+            })
+        );
+        assert_eq!(shaper.offer_at(start, "0123456789abc".into(), false), None);
+        assert_eq!(shaper.offer_at(start, "0123456789abcd".into(), true), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! 软件包清单采集
+//!
+//! 内置的清单采集作业在目标主机上按可用的包管理器（dpkg、rpm、apk、pacman）列出已安装的软件包，
+//! 输出首行以标记行声明包管理器，执行成功后由 `parse_inventory` 解析并整体替换该主机的软件包清单，
+//! 同时收集主机 CPU 架构（部署多平台产物时据此选择变体）。
+//! 版本比较采用 dpkg 的规则（epoch、数字段按数值比较、`~` 排在最前），用于跨主机的版本筛选。
+
+use std::cmp::Ordering;
+
+use crate::{
+    error::{AppError, Result},
+    models::asset::{InstalledPackage, PackageFormat, PackageHostQuery, PackageInventory},
+};
+
+/// 单台主机保存的最大软件包数
+pub const MAX_PACKAGES: usize = 20_000;
+
+/// 包名与版本的最大长度（与 host_packages 列宽一致）
+const MAX_FIELD_LEN: usize = 255;
+
+/// 主机架构的最大长度（与 assets_hosts.arch 列宽一致）
+const MAX_ARCH_LEN: usize = 32;
+
+const FORMAT_MARKER: &str = "OPS_PKG_FORMAT ";
+const ARCH_MARKER: &str = "OPS_HOST_ARCH ";
+
+/// 采集脚本：按包管理器输出制表符分隔的清单，均不可用时以退出码 3 失败
+pub const COLLECT_SCRIPT: &str = r#"#!/bin/sh
+echo "OPS_HOST_ARCH $(uname -m 2>/dev/null)"
+if command -v dpkg-query >/dev/null 2>&1; then
+  echo "OPS_PKG_FORMAT dpkg"
+  dpkg-query -W -f='${db:Status-Abbrev}\t${Package}\t${Version}\t${Architecture}\n'
+elif command -v rpm >/dev/null 2>&1; then
+  echo "OPS_PKG_FORMAT rpm"
+  rpm -qa --qf '%{NAME}\t%|EPOCH?{%{EPOCH}:}:{}|%{VERSION}-%{RELEASE}\t%{ARCH}\n'
+elif command -v apk >/dev/null 2>&1; then
+  echo "OPS_PKG_FORMAT apk"
+  apk list --installed 2>/dev/null
+elif command -v pacman >/dev/null 2>&1; then
+  echo "OPS_PKG_FORMAT pacman"
+  pacman -Q
+else
+  echo "No supported package manager found" >&2
+  exit 3
+fi
+"#;
+
+/// 解析采集输出，缺少包管理器标记行时返回 None
+pub fn parse_inventory(output: &str) -> Option<PackageInventory> {
+    let mut lines = output.lines();
+    let mut host_arch = None;
+    let format = lines
+        .by_ref()
+        .find_map(|line| {
+            if let Some(arch) = line.strip_prefix(ARCH_MARKER) {
+                host_arch = Some(common::normalize_arch(arch));
+            }
+            line.strip_prefix(FORMAT_MARKER)
+        })
+        .and_then(|format| PackageFormat::parse(format.trim()))?;
+
+    let mut packages: Vec<InstalledPackage> =
+        lines.filter_map(|line| parse_line(format, line)).collect();
+    packages.sort();
+    packages.dedup();
+    packages.truncate(MAX_PACKAGES);
+    Some(PackageInventory {
+        format,
+        packages,
+        host_arch: host_arch.filter(|arch| !arch.is_empty() && arch.len() <= MAX_ARCH_LEN),
+    })
+}
+
+fn parse_line(format: PackageFormat, line: &str) -> Option<InstalledPackage> {
+    match format {
+        // 只保留已安装（ii）的软件包，排除已删除但保留配置的记录
+        PackageFormat::Dpkg => {
+            let mut fields = line.split('\t');
+            if !fields.next()?.starts_with("ii") {
+                return None;
+            }
+            package(fields.next()?, fields.next()?, fields.next().unwrap_or_default())
+        }
+        PackageFormat::Rpm => {
+            let mut fields = line.split('\t');
+            package(fields.next()?, fields.next()?, fields.next().unwrap_or_default())
+        }
+        // musl-1.2.4-r2 x86_64 {musl} (MIT) [installed]
+        PackageFormat::Apk => {
+            if !line.trim_end().ends_with("[installed]") {
+                return None;
+            }
+            let mut fields = line.split_whitespace();
+            let full = fields.next()?;
+            let arch = fields.next().unwrap_or_default();
+            let mut parts = full.rsplitn(3, '-');
+            let release = parts.next()?;
+            let version = parts.next()?;
+            let name = parts.next()?;
+            package(name, &format!("{}-{}", version, release), arch)
+        }
+        PackageFormat::Pacman => {
+            let mut fields = line.split_whitespace();
+            package(fields.next()?, fields.next()?, "")
+        }
+    }
+}
+
+fn package(name: &str, version: &str, arch: &str) -> Option<InstalledPackage> {
+    let (name, version, arch) = (name.trim(), version.trim(), arch.trim());
+    if name.is_empty() || version.is_empty() {
+        return None;
+    }
+    if name.len() > MAX_FIELD_LEN || version.len() > MAX_FIELD_LEN || arch.len() > 64 {
+        return None;
+    }
+    // rpm 的 gpg-pubkey 等伪软件包没有架构
+    let arch = if arch == "(none)" { "" } else { arch };
+    Some(InstalledPackage {
+        name: name.to_string(),
+        version: version.to_string(),
+        arch: arch.to_string(),
+    })
+}
+
+/// 校验跨主机查询条件
+pub fn validate_query(query: &PackageHostQuery) -> Result<()> {
+    if query.name.trim().is_empty() {
+        return Err(AppError::validation("Package name is required"));
+    }
+    let bounds = [
+        &query.version_lt,
+        &query.version_lte,
+        &query.version_gt,
+        &query.version_gte,
+    ];
+    if bounds
+        .iter()
+        .any(|b| b.as_deref().is_some_and(|v| v.trim().is_empty()))
+    {
+        return Err(AppError::validation("Version bounds must not be empty"));
+    }
+    Ok(())
+}
+
+/// 判断版本是否满足查询的全部版本条件
+pub fn version_matches(query: &PackageHostQuery, version: &str) -> bool {
+    let check = |bound: &Option<String>, accept: &[Ordering]| {
+        bound
+            .as_deref()
+            .map_or(true, |b| accept.contains(&compare_versions(version, b)))
+    };
+    check(&query.version_lt, &[Ordering::Less])
+        && check(&query.version_lte, &[Ordering::Less, Ordering::Equal])
+        && check(&query.version_gt, &[Ordering::Greater])
+        && check(&query.version_gte, &[Ordering::Greater, Ordering::Equal])
+}
+
+/// 按 dpkg 规则比较版本号
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (epoch_a, rest_a) = split_epoch(a);
+    let (epoch_b, rest_b) = split_epoch(b);
+    epoch_a
+        .cmp(&epoch_b)
+        .then_with(|| compare_fragment(rest_a.as_bytes(), rest_b.as_bytes()))
+}
+
+fn split_epoch(version: &str) -> (u64, &str) {
+    match version.split_once(':') {
+        Some((epoch, rest)) if !epoch.is_empty() && epoch.bytes().all(|c| c.is_ascii_digit()) => {
+            (epoch.parse().unwrap_or(0), rest)
+        }
+        _ => (0, version),
+    }
+}
+
+/// 非数字字符的排序权重：`~` 最小，结尾与数字其次，字母按字符序，其余符号排在字母之后
+fn char_order(c: Option<u8>) -> i32 {
+    match c {
+        Some(b'~') => -1,
+        None => 0,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => c as i32,
+        Some(c) => c as i32 + 256,
+    }
+}
+
+fn compare_fragment(a: &[u8], b: &[u8]) -> Ordering {
+    let is_digit = |s: &[u8], i: usize| s.get(i).is_some_and(u8::is_ascii_digit);
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        // 非数字部分逐字符比较
+        while (i < a.len() && !is_digit(a, i)) || (j < b.len() && !is_digit(b, j)) {
+            let (x, y) = (char_order(a.get(i).copied()), char_order(b.get(j).copied()));
+            if x != y {
+                return x.cmp(&y);
+            }
+            i += 1;
+            j += 1;
+        }
+        // 数字部分按数值比较（忽略前导零）
+        while a.get(i) == Some(&b'0') {
+            i += 1;
+        }
+        while b.get(j) == Some(&b'0') {
+            j += 1;
+        }
+        let mut first_diff = Ordering::Equal;
+        while is_digit(a, i) && is_digit(b, j) {
+            if first_diff == Ordering::Equal {
+                first_diff = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+        if is_digit(a, i) {
+            return Ordering::Greater;
+        }
+        if is_digit(b, j) {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(inventory: &PackageInventory) -> Vec<(&str, &str, &str)> {
+        inventory
+            .packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str(), p.arch.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_dpkg_and_rpm() {
+        let dpkg = "OPS_HOST_ARCH aarch64\n\
+            OPS_PKG_FORMAT dpkg\n\
+            ii \topenssl\t3.0.2-0ubuntu1.10\tamd64\n\
+            rc \told-lib\t1.0\tamd64\n\
+            ii \tlibc6\t2.35-0ubuntu3\tamd64\n";
+        let inventory = parse_inventory(dpkg).unwrap();
+        assert_eq!(inventory.format, PackageFormat::Dpkg);
+        assert_eq!(inventory.host_arch.as_deref(), Some("aarch64"));
+        assert_eq!(
+            names(&inventory),
+            vec![
+                ("libc6", "2.35-0ubuntu3", "amd64"),
+                ("openssl", "3.0.2-0ubuntu1.10", "amd64")
+            ]
+        );
+
+        let rpm = "OPS_PKG_FORMAT rpm\n\
+            openssl\t1:3.0.7-24.el9\tx86_64\n\
+            gpg-pubkey\tfd431d51-4ae0493b\t(none)\n";
+        let inventory = parse_inventory(rpm).unwrap();
+        assert_eq!(
+            names(&inventory),
+            vec![
+                ("gpg-pubkey", "fd431d51-4ae0493b", ""),
+                ("openssl", "1:3.0.7-24.el9", "x86_64")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_apk_and_pacman() {
+        let apk = "OPS_PKG_FORMAT apk\n\
+            WARNING: opening repository: No such file\n\
+            musl-1.2.4-r2 x86_64 {musl} (MIT) [installed]\n\
+            ca-certificates-bundle-20230506-r0 x86_64 {ca-certificates} (MPL-2.0) [installed]\n";
+        let inventory = parse_inventory(apk).unwrap();
+        assert_eq!(
+            names(&inventory),
+            vec![
+                ("ca-certificates-bundle", "20230506-r0", "x86_64"),
+                ("musl", "1.2.4-r2", "x86_64"),
+            ]
+        );
+
+        let pacman = "OPS_PKG_FORMAT pacman\nopenssl 3.1.4-1\n";
+        assert_eq!(names(&parse_inventory(pacman).unwrap()), vec![("openssl", "3.1.4-1", "")]);
+        assert_eq!(parse_inventory(pacman).unwrap().host_arch, None);
+
+        assert!(parse_inventory("openssl 3.1.4-1\n").is_none());
+        assert!(parse_inventory("OPS_PKG_FORMAT brew\n").is_none());
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.0", "1.0.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.01", "1.1"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0~rc1", "1.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.1.1k", "1.1.1j"), Ordering::Greater);
+        assert_eq!(compare_versions("1:0.9", "2.0"), Ordering::Greater);
+        assert_eq!(compare_versions("3.0.2-0ubuntu1.10", "3.0.2-0ubuntu1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("3.0.2", "3.0.2-0ubuntu1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_version_matches_query() {
+        let query = PackageHostQuery {
+            name: "openssl".to_string(),
+            environment: None,
+            version_lt: Some("3.0.2".to_string()),
+            version_lte: None,
+            version_gt: None,
+            version_gte: Some("1.1.1".to_string()),
+        };
+        assert!(validate_query(&query).is_ok());
+        assert!(version_matches(&query, "1.1.1w"));
+        assert!(version_matches(&query, "3.0.1"));
+        assert!(!version_matches(&query, "3.0.2"));
+        assert!(!version_matches(&query, "1.0.2u"));
+
+        let invalid = PackageHostQuery {
+            name: " ".to_string(),
+            ..query
+        };
+        assert!(validate_query(&invalid).is_err());
+    }
+}
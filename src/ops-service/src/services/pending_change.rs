@@ -0,0 +1,138 @@
+//! 待审批的资源变更
+//!
+//! 资源变更以审批请求包装：变更内容与审批请求一同保存，审批通过后应用，
+//! 被拒绝、取消或超时后丢弃。处理后清空变更内容，避免凭据长期留存
+
+use sqlx::types::Json;
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::approval::{PendingChange, ResourceChange};
+use crate::repository::AssetRepository;
+
+/// 资源变更审批请求的类型
+pub const REQUEST_TYPE: &str = "resource_change";
+
+/// 变更状态
+pub const CHANGE_PENDING: &str = "pending";
+pub const CHANGE_APPLIED: &str = "applied";
+pub const CHANGE_DISCARDED: &str = "discarded";
+pub const CHANGE_FAILED: &str = "failed";
+
+/// 资源是否已有待审批的变更
+pub(crate) async fn has_pending(db: &PgPool, change: &ResourceChange) -> Result<bool> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM approval_pending_changes
+         WHERE resource_type = $1 AND resource_id = $2 AND status = 'pending')",
+    )
+    .bind(change.resource_type())
+    .bind(change.resource_id())
+    .fetch_one(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to check pending changes");
+        AppError::database("Failed to check pending changes")
+    })
+}
+
+/// 保存待审批的变更
+pub(crate) async fn insert(
+    db: &PgPool,
+    approval_id: Uuid,
+    change: &ResourceChange,
+    requested_by: Uuid,
+) -> Result<PendingChange> {
+    sqlx::query_as::<_, PendingChange>(
+        r#"
+        INSERT INTO approval_pending_changes (
+            approval_request_id, resource_type, resource_id, change_kind,
+            payload, summary, status, requested_by
+        ) VALUES ($1, $2, $3, $4, $5, $6, 'pending', $7)
+        RETURNING *
+        "#,
+    )
+    .bind(approval_id)
+    .bind(change.resource_type())
+    .bind(change.resource_id())
+    .bind(change.kind())
+    .bind(Json(change))
+    .bind(change.summary())
+    .bind(requested_by)
+    .fetch_one(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to save pending change");
+        AppError::database("Failed to save pending change")
+    })
+}
+
+/// 审批请求关联的变更
+pub(crate) async fn find_by_approval(
+    db: &PgPool,
+    approval_id: Uuid,
+) -> Result<Option<PendingChange>> {
+    sqlx::query_as::<_, PendingChange>(
+        "SELECT * FROM approval_pending_changes WHERE approval_request_id = $1",
+    )
+    .bind(approval_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to fetch pending change");
+        AppError::database("Failed to fetch pending change")
+    })
+}
+
+/// 认领待处理的变更并标记结果，同时清空变更内容
+///
+/// 只有仍为 pending 的变更会被更新，返回是否认领成功（并发处理时只有一方成功）
+pub(crate) async fn mark_resolved(
+    db: &PgPool,
+    id: Uuid,
+    status: &str,
+    error_message: Option<&str>,
+    resolved_by: Option<Uuid>,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE approval_pending_changes
+        SET status = $2, error_message = $3, resolved_by = $4,
+            resolved_at = NOW(), payload = NULL
+        WHERE id = $1 AND status = 'pending'
+        "#,
+    )
+    .bind(id)
+    .bind(status)
+    .bind(error_message)
+    .bind(resolved_by)
+    .execute(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to update pending change");
+        AppError::database("Failed to update pending change")
+    })?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// 应用变更
+pub(crate) async fn apply(db: &PgPool, change: &ResourceChange, applied_by: Uuid) -> Result<()> {
+    let repo = AssetRepository::new(db.clone());
+    match change {
+        ResourceChange::HostCredentialUpdate {
+            host_id,
+            credentials,
+        } => {
+            repo.update_host_credentials(*host_id, credentials, applied_by)
+                .await?
+                .ok_or_else(|| AppError::not_found("Host not found"))?;
+        }
+        ResourceChange::GroupDelete { group_id } => {
+            if !repo.delete_group(*group_id).await? {
+                return Err(AppError::not_found("Asset group not found"));
+            }
+        }
+    }
+    Ok(())
+}
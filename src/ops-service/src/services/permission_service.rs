@@ -1,7 +1,10 @@
 //! 权限检查服务
+//!
+//! 角色绑定与角色权限经由 [`crate::cache`] 缓存，角色相关的写操作在仓储层失效缓存
 
-use crate::{error::AppError, models::role::*, repository::role_repo::RoleRepository};
+use crate::{cache, error::AppError, models::role::*, repository::role_repo::RoleRepository};
 use sqlx::PgPool;
+use std::sync::Arc;
 use uuid::Uuid;
 
 pub struct PermissionService {
@@ -13,6 +16,26 @@ impl PermissionService {
         Self { db }
     }
 
+    /// 用户的角色绑定（缓存）
+    async fn role_bindings(&self, user_id: Uuid) -> Result<Arc<Vec<RoleBinding>>, AppError> {
+        cache::ROLE_BINDINGS
+            .get_or_load(user_id, || async {
+                let role_repo = RoleRepository::new(self.db.clone());
+                Ok(Arc::new(role_repo.get_user_role_bindings(user_id).await?))
+            })
+            .await
+    }
+
+    /// 角色的权限（缓存）
+    async fn role_permissions(&self, role_id: Uuid) -> Result<Arc<Vec<Permission>>, AppError> {
+        cache::ROLE_PERMISSIONS
+            .get_or_load(role_id, || async {
+                let role_repo = RoleRepository::new(self.db.clone());
+                Ok(Arc::new(role_repo.get_role_permissions(role_id).await?))
+            })
+            .await
+    }
+
     /// 检查用户是否拥有权限
     pub async fn check_permission(
         &self,
@@ -22,22 +45,22 @@ impl PermissionService {
         scope_type: Option<&str>,
         scope_value: Option<&str>,
     ) -> Result<bool, AppError> {
-        let role_repo = RoleRepository::new(self.db.clone());
-
         // 获取用户的角色绑定
-        let bindings = role_repo.get_user_role_bindings(user_id).await?;
+        let bindings = self.role_bindings(user_id).await?;
 
         // 检查每个角色绑定
-        for binding in bindings {
+        for binding in bindings.iter() {
             // 检查权限范围是否匹配
-            if !self.scope_matches(&binding, scope_type, scope_value) {
+            if !self.scope_matches(binding, scope_type, scope_value) {
                 continue;
             }
 
             // 检查角色是否拥有该权限
-            let has_perm = role_repo
-                .role_has_permission(binding.role_id, resource, action)
-                .await?;
+            let has_perm = self
+                .role_permissions(binding.role_id)
+                .await?
+                .iter()
+                .any(|perm| perm.resource == resource && perm.action == action);
 
             if has_perm {
                 return Ok(true);
@@ -113,15 +136,14 @@ impl PermissionService {
         user_id: Uuid,
         scope_type: &str,
     ) -> Result<Vec<String>, AppError> {
-        let role_repo = RoleRepository::new(self.db.clone());
-        let bindings = role_repo.get_user_role_bindings(user_id).await?;
+        let bindings = self.role_bindings(user_id).await?;
 
         let mut allowed_values = Vec::new();
 
-        for binding in bindings {
+        for binding in bindings.iter() {
             if binding.scope_type == scope_type {
-                if let Some(value) = binding.scope_value {
-                    allowed_values.push(value);
+                if let Some(value) = &binding.scope_value {
+                    allowed_values.push(value.clone());
                 }
             } else if binding.scope_type == "global" {
                 // 用户拥有全局访问权限
@@ -134,10 +156,9 @@ impl PermissionService {
 
     /// 检查用户是否是管理员
     pub async fn is_admin(&self, user_id: Uuid) -> Result<bool, AppError> {
-        let role_repo = RoleRepository::new(self.db.clone());
-        let bindings = role_repo.get_user_role_bindings(user_id).await?;
+        let bindings = self.role_bindings(user_id).await?;
 
-        for binding in bindings {
+        for binding in bindings.iter() {
             if binding.role_name == "admin" && binding.scope_type == "global" {
                 return Ok(true);
             }
@@ -151,20 +172,19 @@ impl PermissionService {
         &self,
         user_id: Uuid,
     ) -> Result<Vec<PermissionSummary>, AppError> {
-        let role_repo = RoleRepository::new(self.db.clone());
-        let bindings = role_repo.get_user_role_bindings(user_id).await?;
+        let bindings = self.role_bindings(user_id).await?;
 
         let mut permissions = Vec::new();
 
-        for binding in bindings {
-            let role_permissions = role_repo.get_role_permissions(binding.role_id).await?;
+        for binding in bindings.iter() {
+            let role_permissions = self.role_permissions(binding.role_id).await?;
 
-            for perm in role_permissions {
+            for perm in role_permissions.iter() {
                 // 添加权限范围信息
                 permissions.push(PermissionSummary {
                     resource: perm.resource.clone(),
                     action: perm.action.clone(),
-                    description: perm.description,
+                    description: perm.description.clone(),
                 });
             }
         }
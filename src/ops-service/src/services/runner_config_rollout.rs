@@ -0,0 +1,600 @@
+//! Runner 配置灰度发布
+//!
+//! 配置变更先下发给按比例或能力标签选出的金丝雀 Runner，其余 Runner 继续使用当前配置：
+//! - applying：等待在线的金丝雀 Runner 通过心跳上报已应用目标版本
+//! - baking：观察期内统计金丝雀 Runner 完成的构建，失败率超过阈值立即回滚
+//! - 观察期结束且失败率未超过阈值时写入配置并递增版本，全部 Runner 随心跳生效；
+//!   完成构建数不足 min_jobs 时继续观察，直到样本足够或手动处理
+//!
+//! 回滚只需结束发布记录：金丝雀 Runner 下次心跳即重新获得当前配置
+
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::runner_config::{
+    CanaryRunnerStatus, RunnerConfigRollout, RunnerDockerConfig, ROLLOUT_APPLYING, ROLLOUT_BAKING,
+    ROLLOUT_COMPLETED, ROLLOUT_ROLLED_BACK,
+};
+
+/// 观察期评估结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum BakeDecision {
+    /// 继续观察
+    Wait,
+    /// 观察通过，写入配置
+    Promote,
+    /// 失败率超过阈值，回滚
+    RollBack(String),
+}
+
+/// 按能力标签与比例选出金丝雀 Runner
+///
+/// 先过滤具备全部标签的 Runner，再按 seed 与名称的哈希排序取前 percent%（至少 1 个），
+/// 不同发布选出的 Runner 分散且同一发布结果稳定
+pub fn select_canaries(
+    runners: &[(String, Vec<String>)],
+    labels: &[String],
+    percent: Option<i32>,
+    seed: &str,
+) -> Vec<String> {
+    let mut matching: Vec<&String> = runners
+        .iter()
+        .filter(|(_, capabilities)| labels.iter().all(|l| capabilities.contains(l)))
+        .map(|(name, _)| name)
+        .collect();
+    if matching.is_empty() {
+        return Vec::new();
+    }
+
+    let count = match percent {
+        Some(percent) => {
+            let percent = percent.clamp(1, 100) as usize;
+            ((matching.len() * percent).div_ceil(100)).max(1)
+        }
+        None => matching.len(),
+    };
+
+    matching.sort_by_cached_key(|name| Sha256::digest(format!("{}:{}", seed, name)).to_vec());
+    let mut selected: Vec<String> = matching.into_iter().take(count).cloned().collect();
+    selected.sort();
+    selected
+}
+
+/// 评估观察期：样本足够且失败率超过阈值时立即回滚，观察期结束且样本足够时写入配置
+pub fn evaluate_bake(
+    elapsed_secs: i64,
+    bake_secs: i64,
+    jobs: i64,
+    failures: i64,
+    min_jobs: i64,
+    max_error_rate: f64,
+) -> BakeDecision {
+    if jobs > 0 && jobs >= min_jobs {
+        let rate = failures as f64 / jobs as f64;
+        if rate > max_error_rate {
+            return BakeDecision::RollBack(format!(
+                "Canary error rate {:.1}% exceeded {:.1}% ({} of {} builds failed)",
+                rate * 100.0,
+                max_error_rate * 100.0,
+                failures,
+                jobs
+            ));
+        }
+    }
+
+    if elapsed_secs < bake_secs || jobs < min_jobs {
+        BakeDecision::Wait
+    } else {
+        BakeDecision::Promote
+    }
+}
+
+/// 新建灰度发布的参数
+pub struct NewRunnerConfigRollout {
+    pub config_id: Uuid,
+    pub base_version: i64,
+    pub candidate: RunnerDockerConfig,
+    pub change_reason: Option<String>,
+    pub percent: Option<i32>,
+    pub labels: Vec<String>,
+    pub canary_runners: Vec<String>,
+    pub bake_secs: i64,
+    pub max_error_rate: f64,
+    pub min_jobs: i64,
+    pub auto_promote: bool,
+    pub created_by: Uuid,
+}
+
+/// Runner 配置灰度发布服务
+pub struct RunnerConfigRolloutService {
+    db: Pool<Postgres>,
+}
+
+impl RunnerConfigRolloutService {
+    pub fn new(db: Pool<Postgres>) -> Self {
+        Self { db }
+    }
+
+    /// 创建灰度发布（同一配置已有进行中的发布时返回校验错误）
+    pub async fn create(&self, rollout: NewRunnerConfigRollout) -> Result<RunnerConfigRollout> {
+        let candidate = serde_json::to_value(&rollout.candidate).map_err(|e| {
+            AppError::internal_error(&format!("Failed to serialize candidate config: {}", e))
+        })?;
+
+        sqlx::query_as::<_, RunnerConfigRollout>(
+            "INSERT INTO runner_config_rollouts
+             (config_id, base_version, target_version, candidate_config, change_reason,
+              percent, labels, canary_runners, bake_secs, max_error_rate, min_jobs,
+              auto_promote, status, created_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+             RETURNING *",
+        )
+        .bind(rollout.config_id)
+        .bind(rollout.base_version)
+        .bind(rollout.candidate.version)
+        .bind(sqlx::types::Json(candidate))
+        .bind(&rollout.change_reason)
+        .bind(rollout.percent)
+        .bind(sqlx::types::Json(&rollout.labels))
+        .bind(sqlx::types::Json(&rollout.canary_runners))
+        .bind(rollout.bake_secs)
+        .bind(rollout.max_error_rate)
+        .bind(rollout.min_jobs)
+        .bind(rollout.auto_promote)
+        .bind(ROLLOUT_APPLYING)
+        .bind(rollout.created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(d) if d.is_unique_violation() => {
+                AppError::validation("Runner config already has an active rollout")
+            }
+            _ => {
+                error!(error = %e, config_id = %rollout.config_id, "Failed to create rollout");
+                AppError::database("Failed to create rollout")
+            }
+        })
+    }
+
+    /// 获取灰度发布
+    pub async fn get(&self, id: Uuid) -> Result<Option<RunnerConfigRollout>> {
+        sqlx::query_as::<_, RunnerConfigRollout>(
+            "SELECT * FROM runner_config_rollouts WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, rollout_id = %id, "Failed to get rollout");
+            AppError::database("Failed to get rollout")
+        })
+    }
+
+    /// 列出配置的灰度发布（最近 50 条）
+    pub async fn list(&self, config_id: Uuid) -> Result<Vec<RunnerConfigRollout>> {
+        sqlx::query_as::<_, RunnerConfigRollout>(
+            "SELECT * FROM runner_config_rollouts
+             WHERE config_id = $1
+             ORDER BY created_at DESC
+             LIMIT 50",
+        )
+        .bind(config_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, config_id = %config_id, "Failed to list rollouts");
+            AppError::database("Failed to list rollouts")
+        })
+    }
+
+    /// 配置是否有进行中的灰度发布
+    pub async fn has_active(&self, config_id: Uuid) -> Result<bool> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM runner_config_rollouts
+                           WHERE config_id = $1 AND status IN ($2, $3))",
+        )
+        .bind(config_id)
+        .bind(ROLLOUT_APPLYING)
+        .bind(ROLLOUT_BAKING)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, config_id = %config_id, "Failed to check active rollout");
+            AppError::database("Failed to check active rollout")
+        })
+    }
+
+    /// Runner 所在的进行中灰度发布（Runner 不是金丝雀时返回 None）
+    pub async fn active_for_runner(
+        &self,
+        config_id: Uuid,
+        runner_name: &str,
+    ) -> Result<Option<RunnerConfigRollout>> {
+        sqlx::query_as::<_, RunnerConfigRollout>(
+            "SELECT * FROM runner_config_rollouts
+             WHERE config_id = $1 AND status IN ($2, $3)
+               AND canary_runners @> jsonb_build_array($4::text)",
+        )
+        .bind(config_id)
+        .bind(ROLLOUT_APPLYING)
+        .bind(ROLLOUT_BAKING)
+        .bind(runner_name)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, runner = %runner_name, "Failed to get runner rollout");
+            AppError::database("Failed to get runner rollout")
+        })
+    }
+
+    /// 金丝雀 Runner 的在线与配置应用状态
+    pub async fn canary_status(
+        &self,
+        rollout: &RunnerConfigRollout,
+    ) -> Result<Vec<CanaryRunnerStatus>> {
+        sqlx::query_as::<_, CanaryRunnerStatus>(
+            "SELECT c.name, r.status, r.last_heartbeat, r.applied_config_version,
+                    r.applied_config_at,
+                    COALESCE(r.last_heartbeat > NOW() - INTERVAL '2 minutes', false) AS online
+             FROM UNNEST($1::text[]) AS c(name)
+             LEFT JOIN runners r ON r.name = c.name
+             ORDER BY c.name",
+        )
+        .bind(&rollout.canary_runners.0)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, rollout_id = %rollout.id, "Failed to get canary status");
+            AppError::database("Failed to get canary status")
+        })
+    }
+
+    /// 执行一轮评估，返回状态发生变化的发布数量
+    pub async fn evaluate_once(&self) -> Result<usize> {
+        let rollouts = sqlx::query_as::<_, RunnerConfigRollout>(
+            "SELECT * FROM runner_config_rollouts
+             WHERE status IN ($1, $2)
+             ORDER BY created_at ASC",
+        )
+        .bind(ROLLOUT_APPLYING)
+        .bind(ROLLOUT_BAKING)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to list active rollouts");
+            AppError::database("Failed to list active rollouts")
+        })?;
+
+        let mut changed = 0;
+        for rollout in rollouts {
+            match self.evaluate(&rollout).await {
+                Ok(true) => changed += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    error!(error = %e, rollout_id = %rollout.id, "Failed to evaluate rollout");
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    async fn evaluate(&self, rollout: &RunnerConfigRollout) -> Result<bool> {
+        if rollout.status == ROLLOUT_APPLYING {
+            return self.start_baking_if_applied(rollout).await;
+        }
+
+        let Some(started) = rollout.baking_started_at else {
+            return Ok(false);
+        };
+
+        let (jobs, failures) = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COUNT(*) FILTER (WHERE status IN ('completed', 'failed')),
+                    COUNT(*) FILTER (WHERE status = 'failed')
+             FROM build_jobs
+             WHERE runner_name = ANY($1) AND completed_at >= $2",
+        )
+        .bind(&rollout.canary_runners.0)
+        .bind(started)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, rollout_id = %rollout.id, "Failed to count canary builds");
+            AppError::database("Failed to count canary builds")
+        })?;
+
+        let elapsed_secs = (Utc::now() - started).num_seconds();
+        let decision = evaluate_bake(
+            elapsed_secs,
+            rollout.bake_secs,
+            jobs,
+            failures,
+            rollout.min_jobs,
+            rollout.max_error_rate,
+        );
+
+        let status_reason = match &decision {
+            BakeDecision::Wait if elapsed_secs >= rollout.bake_secs => {
+                Some(format!("Waiting for {} canary builds ({} completed)", rollout.min_jobs, jobs))
+            }
+            BakeDecision::Promote if !rollout.auto_promote => {
+                Some("Bake passed, waiting for manual promotion".to_string())
+            }
+            _ => rollout.status_reason.clone(),
+        };
+        sqlx::query(
+            "UPDATE runner_config_rollouts
+             SET observed_jobs = $2, observed_failures = $3, status_reason = $4,
+                 updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(rollout.id)
+        .bind(jobs)
+        .bind(failures)
+        .bind(&status_reason)
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, rollout_id = %rollout.id, "Failed to update rollout");
+            AppError::database("Failed to update rollout")
+        })?;
+
+        match decision {
+            BakeDecision::Wait => Ok(false),
+            BakeDecision::Promote if !rollout.auto_promote => Ok(false),
+            BakeDecision::Promote => {
+                self.promote(rollout.id, None).await?;
+                Ok(true)
+            }
+            BakeDecision::RollBack(reason) => {
+                self.roll_back(rollout.id, &reason).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// 在线的金丝雀 Runner 均已应用目标版本时进入观察期
+    async fn start_baking_if_applied(&self, rollout: &RunnerConfigRollout) -> Result<bool> {
+        let canaries = self.canary_status(rollout).await?;
+        let online: Vec<&CanaryRunnerStatus> = canaries.iter().filter(|c| c.online).collect();
+        let applied = online
+            .iter()
+            .filter(|c| c.applied_config_version == Some(rollout.target_version))
+            .count();
+        if online.is_empty() || applied < online.len() {
+            return Ok(false);
+        }
+
+        let updated = sqlx::query(
+            "UPDATE runner_config_rollouts
+             SET status = $2, baking_started_at = NOW(), status_reason = $3, updated_at = NOW()
+             WHERE id = $1 AND status = $4",
+        )
+        .bind(rollout.id)
+        .bind(ROLLOUT_BAKING)
+        .bind(format!("Applied on {} of {} canary runners", applied, canaries.len()))
+        .bind(ROLLOUT_APPLYING)
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, rollout_id = %rollout.id, "Failed to start bake");
+            AppError::database("Failed to start bake")
+        })?
+        .rows_affected();
+
+        if updated > 0 {
+            info!(
+                rollout_id = %rollout.id,
+                canaries = applied,
+                bake_secs = rollout.bake_secs,
+                "Runner config rollout applied on canaries, baking"
+            );
+        }
+        Ok(updated > 0)
+    }
+
+    /// 写入候选配置并完成发布（配置在发布期间被直接修改时回滚发布）
+    pub async fn promote(
+        &self,
+        id: Uuid,
+        promoted_by: Option<Uuid>,
+    ) -> Result<RunnerConfigRollout> {
+        let map_err = |e: sqlx::Error| {
+            error!(error = %e, rollout_id = %id, "Failed to promote rollout");
+            AppError::database("Failed to promote rollout")
+        };
+
+        let mut tx = self.db.begin().await.map_err(map_err)?;
+
+        let rollout = sqlx::query_as::<_, RunnerConfigRollout>(
+            "SELECT * FROM runner_config_rollouts WHERE id = $1 FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| AppError::not_found("Rollout not found"))?;
+        if !rollout.is_active() {
+            return Err(AppError::validation("Rollout is not in progress"));
+        }
+        let candidate = rollout
+            .candidate()
+            .ok_or_else(|| AppError::internal_error("Invalid rollout candidate config"))?;
+
+        let current = sqlx::query_as::<_, RunnerDockerConfig>(
+            "SELECT id, name, version, enabled, default_image, default_timeout_secs,
+                    memory_limit_gb, cpu_shares, pids_limit, images_by_type,
+                    per_capability, per_runner, description, created_at, updated_at
+             FROM runner_docker_configs
+             WHERE id = $1
+             FOR UPDATE",
+        )
+        .bind(rollout.config_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| AppError::not_found("Runner config not found"))?;
+
+        if current.version != rollout.base_version {
+            drop(tx);
+            self.roll_back(id, "Runner config was changed during the rollout")
+                .await?;
+            return Err(AppError::validation(
+                "Runner config was changed during the rollout, rollout rolled back",
+            ));
+        }
+
+        let promoted = sqlx::query_as::<_, RunnerDockerConfig>(
+            "UPDATE runner_docker_configs
+             SET enabled = $2, default_image = $3, default_timeout_secs = $4,
+                 memory_limit_gb = $5, cpu_shares = $6, pids_limit = $7, images_by_type = $8,
+                 per_capability = $9, per_runner = $10, description = $11, version = $12,
+                 updated_at = NOW()
+             WHERE id = $1
+             RETURNING id, name, version, enabled, default_image, default_timeout_secs,
+                       memory_limit_gb, cpu_shares, pids_limit, images_by_type,
+                       per_capability, per_runner, description, created_at, updated_at",
+        )
+        .bind(rollout.config_id)
+        .bind(candidate.enabled)
+        .bind(&candidate.default_image)
+        .bind(candidate.default_timeout_secs)
+        .bind(candidate.memory_limit_gb)
+        .bind(candidate.cpu_shares)
+        .bind(candidate.pids_limit)
+        .bind(&candidate.images_by_type)
+        .bind(&candidate.per_capability)
+        .bind(&candidate.per_runner)
+        .bind(&candidate.description)
+        .bind(rollout.target_version)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_err)?;
+
+        let old_config_json = serde_json::to_value(&current).unwrap_or(serde_json::json!({}));
+        let new_config_json = serde_json::to_value(&promoted).unwrap_or(serde_json::json!({}));
+        sqlx::query(
+            "INSERT INTO runner_config_history
+             (config_id, old_config, new_config, change_reason, changed_by, version, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, NOW())",
+        )
+        .bind(rollout.config_id)
+        .bind(sqlx::types::Json(old_config_json))
+        .bind(sqlx::types::Json(new_config_json))
+        .bind(&rollout.change_reason)
+        .bind(promoted_by.or(rollout.created_by))
+        .bind(rollout.target_version)
+        .execute(&mut *tx)
+        .await
+        .map_err(map_err)?;
+
+        let status_reason = match promoted_by {
+            Some(_) => "Promoted manually",
+            None => "Bake passed, promoted automatically",
+        };
+        let completed = sqlx::query_as::<_, RunnerConfigRollout>(
+            "UPDATE runner_config_rollouts
+             SET status = $2, status_reason = $3, completed_at = NOW(), updated_at = NOW()
+             WHERE id = $1
+             RETURNING *",
+        )
+        .bind(id)
+        .bind(ROLLOUT_COMPLETED)
+        .bind(status_reason)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_err)?;
+
+        tx.commit().await.map_err(map_err)?;
+
+        info!(
+            rollout_id = %id,
+            config_id = %rollout.config_id,
+            version = rollout.target_version,
+            "Runner config rollout promoted"
+        );
+        Ok(completed)
+    }
+
+    /// 回滚发布：金丝雀 Runner 下次心跳重新获得当前配置
+    pub async fn roll_back(&self, id: Uuid, reason: &str) -> Result<RunnerConfigRollout> {
+        let rolled_back = sqlx::query_as::<_, RunnerConfigRollout>(
+            "UPDATE runner_config_rollouts
+             SET status = $2, status_reason = $3, completed_at = NOW(), updated_at = NOW()
+             WHERE id = $1 AND status IN ($4, $5)
+             RETURNING *",
+        )
+        .bind(id)
+        .bind(ROLLOUT_ROLLED_BACK)
+        .bind(reason)
+        .bind(ROLLOUT_APPLYING)
+        .bind(ROLLOUT_BAKING)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, rollout_id = %id, "Failed to roll back rollout");
+            AppError::database("Failed to roll back rollout")
+        })?
+        .ok_or_else(|| AppError::validation("Rollout is not in progress"))?;
+
+        warn!(rollout_id = %id, reason = %reason, "Runner config rollout rolled back");
+        Ok(rolled_back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runners() -> Vec<(String, Vec<String>)> {
+        (0..10)
+            .map(|i| {
+                let labels = if i % 2 == 0 {
+                    vec!["gpu".to_string()]
+                } else {
+                    vec![]
+                };
+                (format!("runner-{}", i), labels)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select_canaries_by_percent_and_labels() {
+        let runners = runners();
+
+        let selected = select_canaries(&runners, &[], Some(25), "seed");
+        assert_eq!(selected.len(), 3);
+        // 同一 seed 结果稳定
+        assert_eq!(selected, select_canaries(&runners, &[], Some(25), "seed"));
+
+        let gpu = select_canaries(&runners, &["gpu".to_string()], None, "seed");
+        assert_eq!(gpu.len(), 5);
+        assert!(gpu.iter().all(|name| {
+            let index: usize = name.trim_start_matches("runner-").parse().unwrap();
+            index % 2 == 0
+        }));
+
+        // 比例很小时至少选 1 个
+        assert_eq!(select_canaries(&runners, &["gpu".to_string()], Some(1), "x").len(), 1);
+        assert!(select_canaries(&runners, &["arm".to_string()], Some(50), "x").is_empty());
+    }
+
+    #[test]
+    fn test_evaluate_bake() {
+        // 观察期内、失败率未超过阈值
+        assert_eq!(evaluate_bake(60, 600, 10, 1, 5, 0.2), BakeDecision::Wait);
+        // 观察期结束
+        assert_eq!(evaluate_bake(600, 600, 10, 1, 5, 0.2), BakeDecision::Promote);
+        assert_eq!(evaluate_bake(600, 600, 0, 0, 0, 0.2), BakeDecision::Promote);
+        // 样本不足时延长观察
+        assert_eq!(evaluate_bake(900, 600, 3, 0, 5, 0.2), BakeDecision::Wait);
+        // 样本足够且失败率超过阈值时立即回滚
+        assert!(matches!(evaluate_bake(60, 600, 10, 3, 5, 0.2), BakeDecision::RollBack(_)));
+        // 样本不足时不因个别失败回滚
+        assert_eq!(evaluate_bake(60, 600, 2, 2, 5, 0.2), BakeDecision::Wait);
+    }
+}
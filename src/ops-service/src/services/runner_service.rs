@@ -3,14 +3,20 @@
 //! 负责根据构建类型和能力标签选择最合适的 Runner
 
 use anyhow::{Context, Result};
+use common::messages::PLUGIN_STEP_PREFIX;
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, Row};
+use sqlx::{PgConnection, PgPool, Row};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// 默认项目亲和性有效期（秒）
+pub const DEFAULT_AFFINITY_TTL_SECS: u64 = 24 * 3600;
+
 /// Runner 调度服务
 pub struct RunnerScheduler {
     db: PgPool,
+    /// 项目亲和性有效期，超过后按常规调度
+    affinity_ttl: std::time::Duration,
 }
 
 /// Runner 候选者信息
@@ -38,12 +44,34 @@ pub struct ScheduleResult {
     pub runner_name: String,
     /// 路由键
     pub routing_key: String,
+    /// 是否命中项目亲和性（粘性调度）
+    #[serde(default)]
+    pub sticky: bool,
+}
+
+/// 项目亲和性：某仓库分支最近一次成功构建所在的 Runner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectAffinity {
+    pub repository: String,
+    pub branch: String,
+    pub runner_id: Uuid,
+    pub runner_name: String,
+    pub last_success_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl RunnerScheduler {
     /// 创建新的调度服务
     pub fn new(db: PgPool) -> Self {
-        Self { db }
+        Self {
+            db,
+            affinity_ttl: std::time::Duration::from_secs(DEFAULT_AFFINITY_TTL_SECS),
+        }
+    }
+
+    /// 设置项目亲和性有效期
+    pub fn with_affinity_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.affinity_ttl = ttl;
+        self
     }
 
     /// 调度 Runner 执行构建任务
@@ -58,10 +86,47 @@ impl RunnerScheduler {
         &self,
         build_type: &str,
         required_capabilities: &[String],
+    ) -> Result<ScheduleResult> {
+        self.schedule(build_type, required_capabilities, None).await
+    }
+
+    /// 粘性调度：优先选择最近一次成功构建同一仓库分支的 Runner
+    ///
+    /// 该 Runner 离线、满载、能力不匹配或亲和性已过期时回退到常规调度
+    pub async fn schedule_build_sticky(
+        &self,
+        build_type: &str,
+        required_capabilities: &[String],
+        repository: &str,
+        branch: &str,
+    ) -> Result<ScheduleResult> {
+        let affinity = match self.get_affinity(repository, branch).await {
+            Ok(affinity) => affinity,
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    repository = %repository,
+                    branch = %branch,
+                    "Failed to load project affinity"
+                );
+                None
+            }
+        };
+
+        self.schedule(build_type, required_capabilities, affinity.as_ref())
+            .await
+    }
+
+    async fn schedule(
+        &self,
+        build_type: &str,
+        required_capabilities: &[String],
+        affinity: Option<&ProjectAffinity>,
     ) -> Result<ScheduleResult> {
         info!(
             build_type = %build_type,
             capabilities = ?required_capabilities,
+            preferred_runner = ?affinity.map(|a| &a.runner_name),
             "Scheduling build task"
         );
 
@@ -78,8 +143,24 @@ impl RunnerScheduler {
             return Err(anyhow::anyhow!("No available runners for build type: {}", build_type));
         }
 
+        // 亲和 Runner 仍在候选者中（在线且有空闲容量）时优先使用
+        let preferred =
+            affinity.and_then(|a| Self::select_preferred_runner(&candidates, a.runner_id));
+        let sticky = preferred.is_some();
+        if let (Some(affinity), None) = (affinity, &preferred) {
+            info!(
+                runner_name = %affinity.runner_name,
+                repository = %affinity.repository,
+                branch = %affinity.branch,
+                "Preferred runner unavailable, falling back to normal scheduling"
+            );
+        }
+
         // 选择最佳 Runner
-        let selected = self.select_best_runner(&candidates)?;
+        let selected = match preferred {
+            Some(selected) => selected,
+            None => self.select_best_runner(&candidates)?,
+        };
 
         // 生成定向路由键：build.<type>.<runner_name>
         // 这样只有目标 runner 会收到此任务
@@ -90,6 +171,7 @@ impl RunnerScheduler {
             runner_name = %selected.name,
             routing_key = %routing_key,
             load_score = %selected.load_score,
+            sticky = sticky,
             "Runner selected for build"
         );
 
@@ -97,17 +179,81 @@ impl RunnerScheduler {
             runner_id: selected.id,
             runner_name: selected.name.clone(),
             routing_key,
+            sticky,
         })
     }
 
+    /// 查询仓库分支未过期的项目亲和性
+    pub async fn get_affinity(
+        &self,
+        repository: &str,
+        branch: &str,
+    ) -> Result<Option<ProjectAffinity>> {
+        let row = sqlx::query(
+            "SELECT repository, branch, runner_id, runner_name, last_success_at
+             FROM project_runner_affinity
+             WHERE repository = $1 AND branch = $2
+             AND last_success_at > NOW() - make_interval(secs => $3)",
+        )
+        .bind(repository)
+        .bind(branch)
+        .bind(self.affinity_ttl.as_secs_f64())
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to query project affinity")?;
+
+        Ok(row.map(|row| ProjectAffinity {
+            repository: row.get("repository"),
+            branch: row.get("branch"),
+            runner_id: row.get("runner_id"),
+            runner_name: row.get("runner_name"),
+            last_success_at: row.get("last_success_at"),
+        }))
+    }
+
+    /// 记录构建成功后的项目亲和性
+    ///
+    /// 在状态更新事务内调用；构建作业或 Runner 不存在时不做任何修改
+    pub async fn record_affinity(
+        conn: &mut PgConnection,
+        build_job_id: Uuid,
+        runner_name: &str,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO project_runner_affinity
+                 (repository, branch, runner_id, runner_name, last_build_job_id, last_success_at)
+             SELECT b.repository, b.branch, r.id, r.name, b.id, NOW()
+             FROM build_jobs b, runners r
+             WHERE b.id = $1 AND r.name = $2
+             ON CONFLICT (repository, branch) DO UPDATE
+             SET runner_id = EXCLUDED.runner_id, runner_name = EXCLUDED.runner_name,
+                 last_build_job_id = EXCLUDED.last_build_job_id,
+                 last_success_at = EXCLUDED.last_success_at",
+        )
+        .bind(build_job_id)
+        .bind(runner_name)
+        .execute(conn)
+        .await
+        .context("Failed to record project affinity")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     /// 查找所有符合条件且可用的 Runner 候选者
     async fn find_candidates(
         &self,
         build_type: &str,
         required_capabilities: &[String],
     ) -> Result<Vec<RunnerCandidate>> {
+        // 插件能力需全部满足，其余能力标签匹配任意一个即可
+        let (required_plugins, required_capabilities): (Vec<String>, Vec<String>) =
+            required_capabilities
+                .iter()
+                .cloned()
+                .partition(|capability| capability.starts_with(PLUGIN_STEP_PREFIX));
+
         // 将构建类型也作为必需的能力标签之一
-        let mut all_required = required_capabilities.to_vec();
+        let mut all_required = required_capabilities;
         all_required.push(build_type.to_string());
         all_required.push("general".to_string()); // 通用能力
 
@@ -149,6 +295,16 @@ impl RunnerScheduler {
                 continue;
             }
 
+            if !has_required_plugins(&required_plugins, &capabilities) {
+                debug!(
+                    runner_id = %id,
+                    runner_name = %name,
+                    required_plugins = ?required_plugins,
+                    "Runner skipped: missing required plugins"
+                );
+                continue;
+            }
+
             // 检查是否还有可用容量
             if current_jobs >= max_concurrent_jobs {
                 debug!(
@@ -217,6 +373,14 @@ impl RunnerScheduler {
             .ok_or_else(|| anyhow::anyhow!("No runner candidates available"))
     }
 
+    /// 从候选者中取出亲和 Runner（不在候选者中返回 None）
+    fn select_preferred_runner(
+        candidates: &[RunnerCandidate],
+        runner_id: Uuid,
+    ) -> Option<RunnerCandidate> {
+        candidates.iter().find(|c| c.id == runner_id).cloned()
+    }
+
     /// 计算负载得分
     ///
     /// 得分 = 当前任务数 / 最大并发数
@@ -325,6 +489,13 @@ impl RunnerScheduler {
     }
 }
 
+/// Runner 是否安装了全部所需插件
+fn has_required_plugins(required_plugins: &[String], capabilities: &[String]) -> bool {
+    required_plugins
+        .iter()
+        .all(|plugin| capabilities.contains(plugin))
+}
+
 /// Runner 信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunnerInfo {
@@ -363,6 +534,15 @@ mod tests {
         assert_eq!(RunnerScheduler::calculate_load_score(10, 10), 1.0);
     }
 
+    #[test]
+    fn test_has_required_plugins() {
+        let capabilities = vec!["rust".to_string(), "plugin/codesign".to_string()];
+        assert!(has_required_plugins(&[], &capabilities));
+        assert!(has_required_plugins(&["plugin/codesign".to_string()], &capabilities));
+        // general 能力不代表安装了插件
+        assert!(!has_required_plugins(&["plugin/helm".to_string()], &["general".to_string()]));
+    }
+
     #[tokio::test]
     async fn test_select_best_runner() {
         let candidates = vec![
@@ -386,12 +566,20 @@ mod tests {
             },
         ];
 
-        let scheduler = RunnerScheduler {
-            db: sqlx::PgPool::connect_lazy("postgres://localhost/postgres").unwrap(),
-        };
+        let scheduler = RunnerScheduler::new(
+            sqlx::PgPool::connect_lazy("postgres://localhost/postgres").unwrap(),
+        );
 
         // 应该选择 runner-2，因为负载更低
         let selected = scheduler.select_best_runner(&candidates).unwrap();
         assert_eq!(selected.name, "runner-2");
+
+        // 亲和 Runner 在候选者中时优先于负载更低的 Runner
+        let preferred =
+            RunnerScheduler::select_preferred_runner(&candidates, candidates[0].id).unwrap();
+        assert_eq!(preferred.name, "runner-1");
+
+        // 亲和 Runner 离线或满载（不在候选者中）时回退
+        assert!(RunnerScheduler::select_preferred_runner(&candidates, Uuid::new_v4()).is_none());
     }
 }
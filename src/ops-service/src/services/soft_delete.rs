@@ -0,0 +1,236 @@
+//! 软删除与恢复
+//!
+//! 主机、作业模板与用户删除时只记录 deleted_at/deleted_by，常规查询与作业目标解析不再可见，
+//! 保留期内可恢复。超过保留期后由后台任务彻底删除；仍被作业、审计等记录引用的数据
+//! 删除失败时保留为软删除状态，下一轮再尝试
+
+use sqlx::{Pool, Postgres};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::{
+    cache,
+    config::SoftDeleteConfig,
+    error::{AppError, Result},
+    models::soft_delete::{
+        DeletedResource, DeletedResourceList, DeletedResourceQuery, SoftDeleteResource,
+    },
+};
+
+/// 软删除时需要同时更新的列
+fn delete_side_effects(resource: SoftDeleteResource) -> &'static str {
+    match resource {
+        // 模板的已有查询以 is_active 过滤
+        SoftDeleteResource::JobTemplate => ", is_active = false, updated_at = NOW()",
+        SoftDeleteResource::Host | SoftDeleteResource::User => ", updated_at = NOW()",
+    }
+}
+
+/// 恢复时需要同时更新的列
+fn restore_side_effects(resource: SoftDeleteResource) -> &'static str {
+    match resource {
+        SoftDeleteResource::JobTemplate => ", is_active = true, updated_at = NOW()",
+        SoftDeleteResource::Host | SoftDeleteResource::User => ", updated_at = NOW()",
+    }
+}
+
+/// 失效资源的查询缓存
+fn invalidate_cache(resource: SoftDeleteResource, id: Uuid) {
+    if resource == SoftDeleteResource::Host {
+        cache::HOSTS.invalidate(&id);
+    }
+}
+
+/// 软删除资源，资源不存在或已删除时返回 false
+pub async fn mark_deleted(
+    db: &Pool<Postgres>,
+    resource: SoftDeleteResource,
+    id: Uuid,
+    deleted_by: Uuid,
+) -> Result<bool> {
+    let result = sqlx::query(&format!(
+        "UPDATE {} SET deleted_at = NOW(), deleted_by = $2{}
+         WHERE id = $1 AND deleted_at IS NULL",
+        resource.table(),
+        delete_side_effects(resource)
+    ))
+    .bind(id)
+    .bind(deleted_by)
+    .execute(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, resource = resource.as_str(), "Failed to soft delete resource");
+        AppError::database("Failed to delete resource")
+    })?;
+    invalidate_cache(resource, id);
+    Ok(result.rows_affected() > 0)
+}
+
+/// 恢复软删除的资源，资源不存在或未删除时返回 false
+pub async fn restore(db: &Pool<Postgres>, resource: SoftDeleteResource, id: Uuid) -> Result<bool> {
+    let result = sqlx::query(&format!(
+        "UPDATE {} SET deleted_at = NULL, deleted_by = NULL{}
+         WHERE id = $1 AND deleted_at IS NOT NULL",
+        resource.table(),
+        restore_side_effects(resource)
+    ))
+    .bind(id)
+    .execute(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, resource = resource.as_str(), "Failed to restore resource");
+        AppError::database("Failed to restore resource")
+    })?;
+    invalidate_cache(resource, id);
+    Ok(result.rows_affected() > 0)
+}
+
+/// 查询单个已删除的资源
+pub async fn find_deleted(
+    db: &Pool<Postgres>,
+    resource: SoftDeleteResource,
+    id: Uuid,
+) -> Result<Option<DeletedResource>> {
+    sqlx::query_as::<_, DeletedResource>(&format!(
+        "SELECT id, {} AS name, deleted_at, deleted_by FROM {}
+         WHERE id = $1 AND deleted_at IS NOT NULL",
+        resource.name_column(),
+        resource.table()
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, resource = resource.as_str(), "Failed to fetch deleted resource");
+        AppError::database("Failed to fetch deleted resource")
+    })
+}
+
+/// 列出已删除的资源（按删除时间倒序）
+pub async fn list_deleted(
+    db: &Pool<Postgres>,
+    resource: SoftDeleteResource,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<DeletedResource>> {
+    sqlx::query_as::<_, DeletedResource>(&format!(
+        "SELECT id, {} AS name, deleted_at, deleted_by FROM {}
+         WHERE deleted_at IS NOT NULL
+         ORDER BY deleted_at DESC LIMIT $1 OFFSET $2",
+        resource.name_column(),
+        resource.table()
+    ))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, resource = resource.as_str(), "Failed to list deleted resources");
+        AppError::database("Failed to list deleted resources")
+    })
+}
+
+/// 已删除资源列表（附带保留天数）
+pub async fn deleted_list(
+    db: &Pool<Postgres>,
+    config: &SoftDeleteConfig,
+    resource: SoftDeleteResource,
+    query: &DeletedResourceQuery,
+) -> Result<DeletedResourceList> {
+    let items = list_deleted(db, resource, query.limit.clamp(1, 200), query.offset.max(0)).await?;
+    Ok(DeletedResourceList {
+        resource_type: resource,
+        retention_days: config.retention_days,
+        items,
+    })
+}
+
+/// 超过保留期的软删除数据清理器
+pub struct SoftDeletePurger {
+    db: Pool<Postgres>,
+    config: SoftDeleteConfig,
+}
+
+impl SoftDeletePurger {
+    pub fn new(db: Pool<Postgres>, config: SoftDeleteConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// 彻底删除一批超过保留期的数据，返回删除数量
+    ///
+    /// 仍被其他记录引用的数据跳过，不影响同批其他数据
+    pub async fn purge_expired(&self) -> Result<usize> {
+        let mut purged = 0;
+        for resource in SoftDeleteResource::ALL {
+            purged += self.purge_resource(resource).await?;
+        }
+        Ok(purged)
+    }
+
+    async fn purge_resource(&self, resource: SoftDeleteResource) -> Result<usize> {
+        let batch_size = self.config.purge_batch_size as usize;
+        let mut purged = 0;
+        let mut retained = 0;
+        // 仍被引用而保留的数据排在前面，以偏移量跳过，直到清除满一批或没有更多到期数据
+        while purged < batch_size {
+            let ids = sqlx::query_scalar::<_, Uuid>(&format!(
+                "SELECT id FROM {}
+                 WHERE deleted_at < NOW() - make_interval(days => $1)
+                 ORDER BY deleted_at, id LIMIT $2 OFFSET $3",
+                resource.table()
+            ))
+            .bind(self.config.retention_days as i32)
+            .bind((batch_size - purged) as i64)
+            .bind(retained as i64)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, resource = resource.as_str(), "Failed to find expired data");
+                AppError::database("Failed to find expired resources")
+            })?;
+            if ids.is_empty() {
+                break;
+            }
+
+            for id in ids {
+                // 删除前再次确认仍处于删除状态（期间可能已被恢复）
+                let result = sqlx::query(&format!(
+                    "DELETE FROM {} WHERE id = $1 AND deleted_at IS NOT NULL",
+                    resource.table()
+                ))
+                .bind(id)
+                .execute(&self.db)
+                .await;
+                match result {
+                    Ok(r) if r.rows_affected() > 0 => {
+                        purged += 1;
+                        invalidate_cache(resource, id);
+                    }
+                    Ok(_) => {}
+                    Err(e)
+                        if e.as_database_error()
+                            .is_some_and(|d| d.is_foreign_key_violation()) =>
+                    {
+                        retained += 1;
+                    }
+                    Err(e) => {
+                        error!(
+                            error = %e,
+                            resource = resource.as_str(),
+                            id = %id,
+                            "Failed to purge resource"
+                        );
+                        retained += 1;
+                    }
+                }
+            }
+        }
+        if retained > 0 {
+            warn!(
+                resource = resource.as_str(),
+                retained, "Expired resources could not be purged, kept as deleted"
+            );
+        }
+        Ok(purged)
+    }
+}
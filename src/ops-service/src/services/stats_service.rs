@@ -0,0 +1,427 @@
+//! Statistics service
+//! 运维看板统计：后台定期将作业、审批、Runner 数据预聚合到 stats_* 表，
+//! 接口只读取聚合结果，避免在业务表上执行重量级的即席查询
+
+use chrono::{Duration, NaiveDate, NaiveTime, Utc};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use tracing::{error, info, instrument};
+
+use crate::concurrency::{AcquireFailures, ConcurrencyStats};
+use crate::config::StatsConfig;
+use crate::error::{AppError, Result};
+use crate::models::stats::*;
+
+/// 按刷新窗口重新计算的聚合语句：先按起始日期清理，再从起始日期的 UTC 零点重新聚合
+const REFRESH_STATEMENTS: &[(&str, &str)] = &[
+    (
+        "DELETE FROM stats_jobs_daily WHERE day >= $1",
+        r#"
+        INSERT INTO stats_jobs_daily (day, job_type, status, job_count, avg_duration_secs)
+        SELECT
+            (created_at AT TIME ZONE 'UTC')::date,
+            job_type,
+            status,
+            COUNT(*),
+            AVG(EXTRACT(EPOCH FROM (completed_at - started_at)))::float8
+        FROM jobs
+        WHERE created_at >= $1
+        GROUP BY 1, 2, 3
+        "#,
+    ),
+    (
+        "DELETE FROM stats_job_recovery_daily WHERE day >= $1",
+        r#"
+        INSERT INTO stats_job_recovery_daily (
+            day, job_type, failed_jobs, recovered_jobs, avg_recovery_secs
+        )
+        SELECT
+            (f.completed_at AT TIME ZONE 'UTC')::date,
+            f.job_type,
+            COUNT(*),
+            COUNT(r.completed_at),
+            AVG(EXTRACT(EPOCH FROM (r.completed_at - f.completed_at)))::float8
+        FROM jobs f
+        LEFT JOIN LATERAL (
+            SELECT s.completed_at FROM jobs s
+            WHERE s.name = f.name
+              AND s.job_type = f.job_type
+              AND s.status = 'completed'
+              AND s.completed_at > f.completed_at
+            ORDER BY s.completed_at
+            LIMIT 1
+        ) r ON TRUE
+        WHERE f.status IN ('failed', 'partially_succeeded')
+          AND f.completed_at >= $1
+        GROUP BY 1, 2
+        "#,
+    ),
+    (
+        "DELETE FROM stats_host_failures_daily WHERE day >= $1",
+        r#"
+        INSERT INTO stats_host_failures_daily (
+            day, host_id, total_tasks, failed_tasks, warning_tasks
+        )
+        SELECT
+            (created_at AT TIME ZONE 'UTC')::date,
+            host_id,
+            COUNT(*),
+            COUNT(*) FILTER (WHERE status IN ('failed', 'timeout')),
+            COUNT(*) FILTER (WHERE status = 'warning')
+        FROM tasks
+        WHERE created_at >= $1
+        GROUP BY 1, 2
+        "#,
+    ),
+    (
+        "DELETE FROM stats_runner_daily WHERE day >= $1",
+        r#"
+        INSERT INTO stats_runner_daily (
+            day, runner_name, build_count, succeeded_count, failed_count, busy_secs
+        )
+        SELECT
+            (created_at AT TIME ZONE 'UTC')::date,
+            runner_name,
+            COUNT(*),
+            COUNT(*) FILTER (WHERE status = 'completed'),
+            COUNT(*) FILTER (WHERE status = 'failed'),
+            COALESCE(SUM(EXTRACT(EPOCH FROM (completed_at - started_at))), 0)::float8
+        FROM build_jobs
+        WHERE runner_name IS NOT NULL AND created_at >= $1
+        GROUP BY 1, 2
+        "#,
+    ),
+    (
+        "DELETE FROM stats_approval_daily WHERE day >= $1",
+        r#"
+        INSERT INTO stats_approval_daily (
+            day, completed_requests, approved_requests, rejected_requests,
+            avg_mins, p50_mins, p90_mins, p99_mins
+        )
+        SELECT
+            day,
+            COUNT(*),
+            COUNT(*) FILTER (WHERE status = 'approved'),
+            COUNT(*) FILTER (WHERE status = 'rejected'),
+            AVG(mins),
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY mins),
+            percentile_cont(0.9) WITHIN GROUP (ORDER BY mins),
+            percentile_cont(0.99) WITHIN GROUP (ORDER BY mins)
+        FROM (
+            SELECT
+                (completed_at AT TIME ZONE 'UTC')::date AS day,
+                status,
+                (EXTRACT(EPOCH FROM (completed_at - requested_at)) / 60)::float8 AS mins
+            FROM approval_requests
+            WHERE status IN ('approved', 'rejected')
+              AND completed_at >= $1
+              AND COALESCE((metadata->>'auto_approved')::boolean, FALSE) = FALSE
+        ) completed
+        GROUP BY day
+        "#,
+    ),
+];
+
+/// 统计服务
+pub struct StatsService {
+    db: Pool<Postgres>,
+    config: StatsConfig,
+}
+
+impl StatsService {
+    /// 创建新的统计服务
+    pub fn new(db: Pool<Postgres>, config: StatsConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// 获取配置（只读）
+    pub fn config(&self) -> &StatsConfig {
+        &self.config
+    }
+
+    /// 重新计算最近 `window_days` 天（含当天）的聚合数据
+    #[instrument(skip(self))]
+    pub async fn refresh(&self, window_days: u32) -> Result<()> {
+        let since_day = Utc::now().date_naive() - Duration::days(window_days.max(1) as i64 - 1);
+        let since = since_day.and_time(NaiveTime::MIN).and_utc();
+
+        let mut tx = self.db.begin().await.map_err(|e| {
+            error!(error = %e, "Failed to begin transaction");
+            AppError::database("Failed to begin transaction")
+        })?;
+
+        for (delete, insert) in REFRESH_STATEMENTS {
+            sqlx::query(delete)
+                .bind(since_day)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to clear stats window");
+                    AppError::database("Failed to refresh statistics")
+                })?;
+            sqlx::query(insert)
+                .bind(since)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to aggregate statistics");
+                    AppError::database("Failed to refresh statistics")
+                })?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            error!(error = %e, "Failed to commit transaction");
+            AppError::database("Failed to commit transaction")
+        })?;
+
+        info!(since = %since_day, "Statistics refreshed");
+        Ok(())
+    }
+
+    /// 记录一次并发使用率采样（含上次采样以来的获取许可失败次数），并清理超过保留期的采样
+    pub async fn record_concurrency_sample(
+        &self,
+        stats: &ConcurrencyStats,
+        failures: &AcquireFailures,
+    ) -> Result<()> {
+        let scopes = serde_json::json!({
+            "groups": stats.group_stats,
+            "environments": stats.environment_stats,
+            "acquire_failures": failures,
+        });
+
+        sqlx::query(
+            r#"
+            INSERT INTO stats_concurrency_samples (
+                global_limit, global_used, utilization_percent, scopes, acquire_failures
+            ) VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(stats.global_limit)
+        .bind(stats.global_used)
+        .bind(stats.global_utilization_percent)
+        .bind(sqlx::types::Json(&scopes))
+        .bind(failures.total() as i32)
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to record concurrency sample");
+            AppError::database("Failed to record concurrency sample")
+        })?;
+
+        sqlx::query(
+            "DELETE FROM stats_concurrency_samples
+             WHERE sampled_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(self.config.concurrency_retention_days as i32)
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to purge concurrency samples");
+            AppError::database("Failed to purge concurrency samples")
+        })?;
+
+        Ok(())
+    }
+
+    /// 每日作业数量（按类型与状态）
+    pub async fn jobs_daily(&self, query: &StatsQuery) -> Result<Vec<JobDailyStat>> {
+        sqlx::query_as::<_, JobDailyStat>(
+            r#"
+            SELECT day, job_type, status, job_count, avg_duration_secs
+            FROM stats_jobs_daily
+            WHERE day >= $1
+            ORDER BY day, job_type, status
+            "#,
+        )
+        .bind(Self::since(query))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch job statistics");
+            AppError::database("Failed to fetch job statistics")
+        })
+    }
+
+    /// 每日失败作业的恢复时间
+    pub async fn job_recovery(&self, query: &StatsQuery) -> Result<Vec<JobRecoveryStat>> {
+        sqlx::query_as::<_, JobRecoveryStat>(
+            r#"
+            SELECT day, job_type, failed_jobs, recovered_jobs, avg_recovery_secs
+            FROM stats_job_recovery_daily
+            WHERE day >= $1
+            ORDER BY day, job_type
+            "#,
+        )
+        .bind(Self::since(query))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch recovery statistics");
+            AppError::database("Failed to fetch recovery statistics")
+        })
+    }
+
+    /// 窗口内任务失败最多的主机
+    pub async fn top_failing_hosts(&self, query: &StatsQuery) -> Result<Vec<HostFailureStat>> {
+        sqlx::query_as::<_, HostFailureStat>(
+            r#"
+            SELECT
+                s.host_id,
+                h.identifier,
+                h.address,
+                SUM(s.total_tasks)::bigint AS total_tasks,
+                SUM(s.failed_tasks)::bigint AS failed_tasks,
+                SUM(s.warning_tasks)::bigint AS warning_tasks,
+                (SUM(s.failed_tasks)::float8 / NULLIF(SUM(s.total_tasks), 0)::float8)
+                    AS failure_rate
+            FROM stats_host_failures_daily s
+            JOIN assets_hosts h ON h.id = s.host_id
+            WHERE s.day >= $1
+            GROUP BY s.host_id, h.identifier, h.address
+            HAVING SUM(s.failed_tasks) > 0
+            ORDER BY failed_tasks DESC, failure_rate DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(Self::since(query))
+        .bind(query.limit())
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch host failure statistics");
+            AppError::database("Failed to fetch host failure statistics")
+        })
+    }
+
+    /// 窗口内最繁忙的 Runner（按累计构建时长）
+    pub async fn busiest_runners(&self, query: &StatsQuery) -> Result<Vec<RunnerUsageStat>> {
+        sqlx::query_as::<_, RunnerUsageStat>(
+            r#"
+            SELECT
+                runner_name,
+                SUM(build_count)::bigint AS build_count,
+                SUM(succeeded_count)::bigint AS succeeded_count,
+                SUM(failed_count)::bigint AS failed_count,
+                SUM(busy_secs)::float8 AS busy_secs
+            FROM stats_runner_daily
+            WHERE day >= $1
+            GROUP BY runner_name
+            ORDER BY busy_secs DESC, build_count DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(Self::since(query))
+        .bind(query.limit())
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch runner statistics");
+            AppError::database("Failed to fetch runner statistics")
+        })
+    }
+
+    /// 每日审批耗时分位数
+    pub async fn approval_turnaround(
+        &self,
+        query: &StatsQuery,
+    ) -> Result<Vec<ApprovalTurnaroundStat>> {
+        sqlx::query_as::<_, ApprovalTurnaroundStat>(
+            r#"
+            SELECT
+                day, completed_requests, approved_requests, rejected_requests,
+                avg_mins, p50_mins, p90_mins, p99_mins
+            FROM stats_approval_daily
+            WHERE day >= $1
+            ORDER BY day
+            "#,
+        )
+        .bind(Self::since(query))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch approval statistics");
+            AppError::database("Failed to fetch approval statistics")
+        })
+    }
+
+    /// 并发使用率历史
+    pub async fn concurrency_history(
+        &self,
+        query: &ConcurrencyHistoryQuery,
+    ) -> Result<Vec<ConcurrencySample>> {
+        sqlx::query_as::<_, ConcurrencySample>(
+            r#"
+            SELECT sampled_at, global_limit, global_used, utilization_percent, scopes,
+                   acquire_failures
+            FROM stats_concurrency_samples
+            WHERE sampled_at >= $1
+            ORDER BY sampled_at
+            "#,
+        )
+        .bind(query.since(Utc::now()))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch concurrency history");
+            AppError::database("Failed to fetch concurrency history")
+        })
+    }
+
+    /// 并发时间线：时间窗口内各分组/环境的运行中任务数、是否达到上限与获取许可失败次数
+    pub async fn concurrency_timeline(
+        &self,
+        query: &ConcurrencyTimelineQuery,
+    ) -> Result<ConcurrencyTimeline> {
+        let (from, to) = query
+            .window(Utc::now())
+            .map_err(|e| AppError::validation(&e))?;
+
+        let samples = sqlx::query_as::<_, ConcurrencySample>(
+            r#"
+            SELECT sampled_at, global_limit, global_used, utilization_percent, scopes,
+                   acquire_failures
+            FROM stats_concurrency_samples
+            WHERE sampled_at >= $1 AND sampled_at <= $2
+            ORDER BY sampled_at
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch concurrency timeline");
+            AppError::database("Failed to fetch concurrency timeline")
+        })?;
+
+        // 分组以 ID 记录，补充分组名称（已删除的分组无名称）
+        let group_ids: Vec<uuid::Uuid> = samples
+            .iter()
+            .filter_map(|sample| sample.scopes.0.get("groups")?.as_object())
+            .flat_map(|groups| groups.keys())
+            .filter_map(|id| uuid::Uuid::parse_str(id).ok())
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        let group_names: HashMap<String, String> = sqlx::query_as::<_, (uuid::Uuid, String)>(
+            "SELECT id, name FROM assets_groups WHERE id = ANY($1)",
+        )
+        .bind(&group_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to fetch group names");
+            AppError::database("Failed to fetch concurrency timeline")
+        })?
+        .into_iter()
+        .map(|(id, name)| (id.to_string(), name))
+        .collect();
+
+        Ok(ConcurrencyTimeline::from_samples(from, to, &samples, &group_names))
+    }
+
+    fn since(query: &StatsQuery) -> NaiveDate {
+        query.since(Utc::now().date_naive())
+    }
+}
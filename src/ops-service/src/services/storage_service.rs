@@ -30,6 +30,10 @@ pub struct StorageConfig {
     /// S3 存储配置
     #[serde(default)]
     pub s3: S3StorageConfig,
+
+    /// 产物保留天数（构建完成后超过该天数的构建日志会被清理），None 表示永久保留
+    #[serde(default)]
+    pub retention_days: Option<u32>,
 }
 
 impl Default for StorageConfig {
@@ -38,6 +42,7 @@ impl Default for StorageConfig {
             storage_type: StorageType::Local,
             local: LocalStorageConfig::default(),
             s3: S3StorageConfig::default(),
+            retention_days: None,
         }
     }
 }
@@ -167,10 +172,16 @@ impl StorageService {
                 .unwrap_or(3600),
         };
 
+        let retention_days = std::env::var("STORAGE_RETENTION_DAYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|days| *days > 0);
+
         let config = StorageConfig {
             storage_type,
             local,
             s3,
+            retention_days,
         };
 
         Ok(Self::new(config))
@@ -214,10 +225,6 @@ impl StorageService {
 
     /// 生成 S3 预签名 URL（使用真实的 AWS SigV4 签名）
     async fn generate_s3_presigned_url(&self, artifact_path: &str) -> Result<Option<String>> {
-        use s3::bucket::Bucket;
-        use s3::creds::Credentials;
-        use s3::Region;
-
         // 解析 S3 路径: s3://bucket/key/path
         let path = artifact_path
             .strip_prefix("s3://")
@@ -234,7 +241,42 @@ impl StorageService {
             "Generating S3 presigned URL with AWS SigV4 signature"
         );
 
-        // 检查凭证
+        let bucket_client = match self.s3_bucket(bucket)? {
+            Some(client) => client,
+            None => {
+                warn!("S3 credentials not configured, returning placeholder URL");
+                return self.generate_placeholder_s3_url(bucket, key);
+            }
+        };
+
+        let key_path = if key.starts_with('/') {
+            key.to_string()
+        } else {
+            format!("/{}", key)
+        };
+
+        let ttl_secs = self.config.s3.presign_ttl_secs.min(u32::MAX as u64) as u32;
+
+        let url = bucket_client
+            .presign_get(&key_path, ttl_secs, None)
+            .await
+            .context("Failed to generate S3 presigned URL")?;
+
+        debug!(
+            bucket = %bucket,
+            key = %key,
+            "Generated S3 presigned URL successfully"
+        );
+
+        Ok(Some(url))
+    }
+
+    /// 构造 S3 Bucket 客户端，未配置凭证（配置与 AWS_* 环境变量均缺失）时返回 None
+    fn s3_bucket(&self, bucket: &str) -> Result<Option<Box<s3::bucket::Bucket>>> {
+        use s3::bucket::Bucket;
+        use s3::creds::Credentials;
+        use s3::Region;
+
         let (access_key, secret_key) = match &self.s3_credentials {
             Some(creds) => (creds.access_key.clone(), creds.secret_key.clone()),
             None => {
@@ -244,10 +286,7 @@ impl StorageService {
                     std::env::var("AWS_SECRET_ACCESS_KEY").ok(),
                 ) {
                     (Some(access), Some(secret)) => (access, secret),
-                    _ => {
-                        warn!("S3 credentials not configured, returning placeholder URL");
-                        return self.generate_placeholder_s3_url(bucket, key);
-                    }
+                    _ => return Ok(None),
                 }
             }
         };
@@ -276,27 +315,144 @@ impl StorageService {
 
         let bucket_client = Bucket::new(bucket, region, credentials)
             .context("Failed to create S3 bucket client")?;
+        Ok(Some(bucket_client))
+    }
 
-        let key_path = if key.starts_with('/') {
-            key.to_string()
-        } else {
-            format!("/{}", key)
-        };
+    /// 解析 s3://bucket/key 形式的位置，返回 (bucket, key)
+    fn parse_s3_location(location: &str) -> Option<(&str, &str)> {
+        location
+            .strip_prefix("s3://")
+            .or_else(|| location.strip_prefix("minio://"))
+            .and_then(|path| path.split_once('/'))
+    }
 
-        let ttl_secs = self.config.s3.presign_ttl_secs.min(u32::MAX as u64) as u32;
+    /// 内容寻址对象的存储键：blobs/sha256/<前两位>/<完整哈希>
+    ///
+    /// 按哈希前缀分散目录，避免本地存储单目录文件过多
+    pub fn blob_key(sha256: &str) -> String {
+        format!("blobs/sha256/{}/{}", &sha256[..2.min(sha256.len())], sha256)
+    }
 
-        let url = bucket_client
-            .presign_get(&key_path, ttl_secs, None)
+    /// 写入对象，返回存储位置（S3 为 s3://bucket/key，本地为绝对路径）
+    pub async fn put_object(&self, key: &str, data: &[u8]) -> Result<String> {
+        match self.config.storage_type {
+            StorageType::S3 => {
+                let bucket = &self.config.s3.bucket;
+                let client = self
+                    .s3_bucket(bucket)?
+                    .ok_or_else(|| anyhow::anyhow!("S3 credentials not configured"))?;
+                client
+                    .put_object(format!("/{}", key), data)
+                    .await
+                    .context("Failed to upload object to S3")?;
+                Ok(format!("s3://{}/{}", bucket, key))
+            }
+            StorageType::Local => {
+                let path = self.resolve_path(key);
+                if let Some(parent) = Path::new(&path).parent() {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .context("Failed to create storage directory")?;
+                }
+                tokio::fs::write(&path, data)
+                    .await
+                    .context("Failed to write object")?;
+                Ok(path)
+            }
+        }
+    }
+
+    /// 读取 `put_object` 返回位置上的对象
+    pub async fn get_object(&self, location: &str) -> Result<Vec<u8>> {
+        if let Some((bucket, key)) = Self::parse_s3_location(location) {
+            let client = self
+                .s3_bucket(bucket)?
+                .ok_or_else(|| anyhow::anyhow!("S3 credentials not configured"))?;
+            let response = client
+                .get_object(format!("/{}", key))
+                .await
+                .context("Failed to download object from S3")?;
+            return Ok(response.to_vec());
+        }
+
+        tokio::fs::read(self.resolve_path(location))
             .await
-            .context("Failed to generate S3 presigned URL")?;
+            .context(format!("Failed to read object: {}", location))
+    }
 
-        debug!(
-            bucket = %bucket,
-            key = %key,
-            "Generated S3 presigned URL successfully"
-        );
+    /// 以流式方式将本地文件写入对象（S3 分段上传），返回存储位置，适用于大文件
+    pub async fn put_object_file(&self, key: &str, file: &Path) -> Result<String> {
+        match self.config.storage_type {
+            StorageType::S3 => {
+                let bucket = &self.config.s3.bucket;
+                let client = self
+                    .s3_bucket(bucket)?
+                    .ok_or_else(|| anyhow::anyhow!("S3 credentials not configured"))?;
+                let mut reader = tokio::fs::File::open(file)
+                    .await
+                    .context("Failed to open upload file")?;
+                client
+                    .put_object_stream(&mut reader, format!("/{}", key))
+                    .await
+                    .context("Failed to upload object to S3")?;
+                Ok(format!("s3://{}/{}", bucket, key))
+            }
+            StorageType::Local => {
+                let path = self.resolve_path(key);
+                if let Some(parent) = Path::new(&path).parent() {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .context("Failed to create storage directory")?;
+                }
+                tokio::fs::copy(file, &path)
+                    .await
+                    .context("Failed to write object")?;
+                Ok(path)
+            }
+        }
+    }
 
-        Ok(Some(url))
+    /// 以流式方式将 `put_object` 返回位置上的对象下载到本地文件
+    pub async fn get_object_to_file(&self, location: &str, file: &Path) -> Result<()> {
+        if let Some((bucket, key)) = Self::parse_s3_location(location) {
+            let client = self
+                .s3_bucket(bucket)?
+                .ok_or_else(|| anyhow::anyhow!("S3 credentials not configured"))?;
+            let mut writer = tokio::fs::File::create(file)
+                .await
+                .context("Failed to create download file")?;
+            client
+                .get_object_to_writer(format!("/{}", key), &mut writer)
+                .await
+                .context("Failed to download object from S3")?;
+            return Ok(());
+        }
+
+        tokio::fs::copy(self.resolve_path(location), file)
+            .await
+            .context(format!("Failed to read object: {}", location))?;
+        Ok(())
+    }
+
+    /// 删除 `put_object` 返回位置上的对象（对象不存在时视为成功）
+    pub async fn delete_object(&self, location: &str) -> Result<()> {
+        if let Some((bucket, key)) = Self::parse_s3_location(location) {
+            let client = self
+                .s3_bucket(bucket)?
+                .ok_or_else(|| anyhow::anyhow!("S3 credentials not configured"))?;
+            client
+                .delete_object(format!("/{}", key))
+                .await
+                .context("Failed to delete object from S3")?;
+            return Ok(());
+        }
+
+        match tokio::fs::remove_file(self.resolve_path(location)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context(format!("Failed to delete object: {}", location))
+            }
+            _ => Ok(()),
+        }
     }
 
     /// 生成占位符 S3 URL（当没有配置凭证时）
@@ -362,6 +518,19 @@ impl StorageService {
         }
     }
 
+    /// 写入、读回并删除一个探测对象，验证存储的连通性与读写权限
+    pub async fn probe(&self) -> Result<()> {
+        let key = format!("preflight/{}", uuid::Uuid::new_v4());
+        let payload = b"ops-service preflight";
+        let location = self.put_object(&key, payload).await?;
+        let read_back = self.get_object(&location).await;
+        self.delete_object(&location).await?;
+        if read_back? != payload {
+            anyhow::bail!("Probe object content mismatch");
+        }
+        Ok(())
+    }
+
     /// 获取存储类型
     pub fn storage_type(&self) -> StorageType {
         self.config.storage_type
@@ -412,6 +581,7 @@ mod tests {
                 base_url: None,
             },
             s3: S3StorageConfig::default(),
+            retention_days: None,
         });
 
         assert_eq!(service.resolve_path("my-app.tar.gz"), "/tmp/artifacts/my-app.tar.gz");
@@ -455,6 +625,7 @@ mod tests {
                 secret_key: None,
                 presign_ttl_secs: 1800,
             },
+            retention_days: None,
         });
 
         tokio::runtime::Runtime::new().unwrap().block_on(async {
@@ -470,4 +641,33 @@ mod tests {
             assert!(url.contains("path/to/file.tar.gz"));
         });
     }
+
+    #[test]
+    fn test_local_object_roundtrip() {
+        let base = std::env::temp_dir().join(format!("ops-storage-{}", uuid::Uuid::new_v4()));
+        let service = StorageService::new(StorageConfig {
+            storage_type: StorageType::Local,
+            local: LocalStorageConfig {
+                base_path: base.display().to_string(),
+                base_url: None,
+            },
+            s3: S3StorageConfig::default(),
+            retention_days: None,
+        });
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let location = service
+                .put_object("archive/a/b.bin", b"payload")
+                .await
+                .unwrap();
+            assert!(location.ends_with("archive/a/b.bin"));
+            assert_eq!(service.get_object(&location).await.unwrap(), b"payload");
+
+            service.delete_object(&location).await.unwrap();
+            assert!(service.get_object(&location).await.is_err());
+            // 重复删除不报错
+            service.delete_object(&location).await.unwrap();
+        });
+        let _ = std::fs::remove_dir_all(base);
+    }
 }
@@ -0,0 +1,100 @@
+//! 任务执行日志
+//!
+//! 每个任务在执行过程中按顺序追加阶段与时间点（获得许可、连接、认证、开始执行、输出完成、
+//! 结果落库、事件发布），以 `[阶段, 毫秒时间戳]` 数组存入 tasks.journal，随任务详情返回。
+//! 任务卡住时据最后一条记录即可判断停在哪一步。
+//!
+//! 记录经通道交给后台写入，不阻塞执行；写入失败只记录日志
+
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::models::job::{TaskJournalEntry, TaskJournalPhase};
+use crate::ssh::{ConnectionPhase, PhaseListener};
+
+/// 任务执行日志记录器（可克隆，全部句柄释放后写完剩余记录退出）
+#[derive(Clone)]
+pub struct TaskJournal {
+    sender: mpsc::UnboundedSender<(TaskJournalPhase, DateTime<Utc>)>,
+}
+
+impl TaskJournal {
+    /// 创建记录器并启动后台写入
+    pub fn start(db: PgPool, task_id: Uuid) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(first) = receiver.recv().await {
+                // 合并已到达的记录，一次追加
+                let mut batch = vec![first];
+                while let Ok(next) = receiver.try_recv() {
+                    batch.push(next);
+                }
+                let entries: Vec<_> = batch
+                    .into_iter()
+                    .map(|(phase, at)| TaskJournalEntry::compact(phase, at))
+                    .collect();
+                let result = sqlx::query("UPDATE tasks SET journal = journal || $2 WHERE id = $1")
+                    .bind(task_id)
+                    .bind(Json(entries))
+                    .execute(&db)
+                    .await;
+                if let Err(e) = result {
+                    warn!(error = %e, task_id = %task_id, "Failed to append task journal");
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    /// 记录到达某个阶段
+    pub fn record(&self, phase: TaskJournalPhase) {
+        let _ = self.sender.send((phase, Utc::now()));
+    }
+
+    /// 连接阶段监听：握手完成记为已连接，认证通过记为已认证，进入执行阶段记为开始执行
+    pub fn phase_listener(&self) -> PhaseListener {
+        let journal = self.clone();
+        Arc::new(move |phase, succeeded| {
+            if let Some(phase) = journal_phase(phase, succeeded) {
+                journal.record(phase);
+            }
+        })
+    }
+}
+
+/// 连接阶段变化对应的日志阶段
+fn journal_phase(phase: ConnectionPhase, succeeded: Option<bool>) -> Option<TaskJournalPhase> {
+    match (phase, succeeded) {
+        (ConnectionPhase::Handshake, Some(true)) => Some(TaskJournalPhase::Connected),
+        (ConnectionPhase::Auth, Some(true)) => Some(TaskJournalPhase::Authenticated),
+        (ConnectionPhase::Exec, None) => Some(TaskJournalPhase::ExecStarted),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_phase_mapping() {
+        assert_eq!(
+            journal_phase(ConnectionPhase::Handshake, Some(true)),
+            Some(TaskJournalPhase::Connected)
+        );
+        assert_eq!(journal_phase(ConnectionPhase::Handshake, Some(false)), None);
+        assert_eq!(journal_phase(ConnectionPhase::Handshake, None), None);
+        assert_eq!(
+            journal_phase(ConnectionPhase::Auth, Some(true)),
+            Some(TaskJournalPhase::Authenticated)
+        );
+        assert_eq!(journal_phase(ConnectionPhase::Exec, None), Some(TaskJournalPhase::ExecStarted));
+        assert_eq!(journal_phase(ConnectionPhase::Exec, Some(true)), None);
+        assert_eq!(journal_phase(ConnectionPhase::TcpConnect, Some(true)), None);
+    }
+}
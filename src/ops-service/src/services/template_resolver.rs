@@ -0,0 +1,412 @@
+//! 作业模板继承与组合
+//!
+//! 模板可继承一个基础模板（extends_template_id），并按顺序组合多个片段模板（includes）。
+//! 展开规则：
+//! - 内容：prepend + 各片段展开内容 + 本模板内容（为空时沿用基础模板展开内容）+ append，按行拼接
+//! - 参数定义：基础模板、片段、本模板依次深度合并，后者覆盖前者，`required` 取并集
+//! - 默认配置与适用范围：本模板未设置时继承基础模板
+//! - 风险等级与是否审批：取继承链与片段中的最高值，子模板不能降低风险
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::approval::{JobTemplate, ResolvedJobTemplate},
+};
+
+/// 继承与组合的最大嵌套深度
+pub const MAX_TEMPLATE_DEPTH: usize = 16;
+
+/// 启用中的模板及其引用关系
+pub struct TemplateGraph {
+    templates: HashMap<Uuid, JobTemplate>,
+}
+
+impl TemplateGraph {
+    pub fn new(templates: Vec<JobTemplate>) -> Self {
+        Self {
+            templates: templates.into_iter().map(|t| (t.id, t)).collect(),
+        }
+    }
+
+    /// 校验模板将要设置的引用：被引用模板必须存在，且不能形成环
+    ///
+    /// `template_id` 为 None 表示新建模板（尚无其他模板引用它，不会成环）
+    pub fn check_references(
+        &self,
+        template_id: Option<Uuid>,
+        extends: Option<Uuid>,
+        includes: &[Uuid],
+    ) -> Result<()> {
+        for reference in extends.iter().chain(includes) {
+            if !self.templates.contains_key(reference) {
+                return Err(AppError::validation(&format!(
+                    "Referenced template {} not found or inactive",
+                    reference
+                )));
+            }
+        }
+
+        let Some(template_id) = template_id else {
+            return Ok(());
+        };
+
+        // 从新的引用出发遍历，能回到本模板即成环（本模板的旧引用不参与遍历）
+        let mut pending: Vec<Uuid> = extends.iter().chain(includes).copied().collect();
+        let mut visited = Vec::new();
+        while let Some(current) = pending.pop() {
+            if current == template_id {
+                return Err(AppError::validation("Template inheritance cycle detected"));
+            }
+            if visited.contains(&current) {
+                continue;
+            }
+            visited.push(current);
+            if let Some(template) = self.templates.get(&current) {
+                pending.extend(template.extends_template_id);
+                pending.extend(template.includes.iter().copied());
+            }
+        }
+        Ok(())
+    }
+
+    /// 展开模板
+    pub fn resolve(&self, template_id: Uuid) -> Result<ResolvedJobTemplate> {
+        self.resolve_inner(template_id, &mut Vec::new())
+    }
+
+    fn resolve_inner(
+        &self,
+        template_id: Uuid,
+        stack: &mut Vec<Uuid>,
+    ) -> Result<ResolvedJobTemplate> {
+        // 写入时已做环检测，这里兜底并发更新产生的环
+        if stack.contains(&template_id) {
+            return Err(AppError::validation("Template inheritance cycle detected"));
+        }
+        if stack.len() >= MAX_TEMPLATE_DEPTH {
+            return Err(AppError::validation(&format!(
+                "Template nesting exceeds maximum depth of {}",
+                MAX_TEMPLATE_DEPTH
+            )));
+        }
+        let template = self.templates.get(&template_id).ok_or_else(|| {
+            AppError::validation(&format!(
+                "Referenced template {} not found or inactive",
+                template_id
+            ))
+        })?;
+
+        stack.push(template_id);
+        let base = template
+            .extends_template_id
+            .map(|base_id| self.resolve_inner(base_id, stack))
+            .transpose()?;
+        let snippets = template
+            .includes
+            .iter()
+            .map(|snippet_id| self.resolve_inner(*snippet_id, stack))
+            .collect::<Result<Vec<_>>>()?;
+        stack.pop();
+
+        let body = if template.template_content.trim().is_empty() {
+            base.as_ref()
+                .map(|b| b.template_content.clone())
+                .unwrap_or_default()
+        } else {
+            template.template_content.clone()
+        };
+        let mut fragments: Vec<&str> = Vec::new();
+        fragments.extend(template.prepend_content.as_deref());
+        fragments.extend(snippets.iter().map(|s| s.template_content.as_str()));
+        fragments.push(&body);
+        fragments.extend(template.append_content.as_deref());
+        let template_content = fragments
+            .into_iter()
+            .filter(|fragment| !fragment.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut parameters_schema = base
+            .as_ref()
+            .map(|b| b.parameters_schema.clone())
+            .unwrap_or_else(|| Value::Object(Default::default()));
+        for snippet in &snippets {
+            merge_schema(&mut parameters_schema, &snippet.parameters_schema);
+        }
+        merge_schema(&mut parameters_schema, &template.parameters_schema.0);
+
+        let mut risk_level = template.risk_level.clone();
+        let mut requires_approval = template.requires_approval;
+        for inherited in base.iter().chain(&snippets) {
+            if risk_rank(&inherited.risk_level) > risk_rank(&risk_level) {
+                risk_level = inherited.risk_level.clone();
+            }
+            requires_approval |= inherited.requires_approval;
+        }
+
+        let mut lineage = base.as_ref().map(|b| b.lineage.clone()).unwrap_or_default();
+        lineage.push(template.id);
+
+        let mut included_templates: Vec<Uuid> = base
+            .as_ref()
+            .map(|b| b.included_templates.clone())
+            .unwrap_or_default();
+        for snippet in &snippets {
+            for id in snippet
+                .included_templates
+                .iter()
+                .chain(std::iter::once(&snippet.template_id))
+            {
+                if !included_templates.contains(id) {
+                    included_templates.push(*id);
+                }
+            }
+        }
+
+        let applicable_environments = if template.applicable_environments.is_empty() {
+            base.as_ref()
+                .map(|b| b.applicable_environments.clone())
+                .unwrap_or_default()
+        } else {
+            template.applicable_environments.0.clone()
+        };
+        let applicable_groups = if template.applicable_groups.is_empty() {
+            base.as_ref()
+                .map(|b| b.applicable_groups.clone())
+                .unwrap_or_default()
+        } else {
+            template.applicable_groups.0.clone()
+        };
+
+        Ok(ResolvedJobTemplate {
+            template_id: template.id,
+            name: template.name.clone(),
+            template_type: template.template_type.clone(),
+            template_content,
+            parameters_schema,
+            default_timeout_secs: template
+                .default_timeout_secs
+                .or(base.as_ref().and_then(|b| b.default_timeout_secs)),
+            default_retry_times: template
+                .default_retry_times
+                .or(base.as_ref().and_then(|b| b.default_retry_times)),
+            default_concurrent_limit: template
+                .default_concurrent_limit
+                .or(base.as_ref().and_then(|b| b.default_concurrent_limit)),
+            risk_level,
+            requires_approval,
+            applicable_environments,
+            applicable_groups,
+            lineage,
+            included_templates,
+            exit_code_rules: template
+                .exit_code_rules
+                .as_ref()
+                .map(|r| r.0.clone())
+                .or(base.as_ref().and_then(|b| b.exit_code_rules.clone())),
+        })
+    }
+}
+
+/// 深度合并参数定义：对象逐键合并，`required` 数组取并集，其余值由 overlay 覆盖
+fn merge_schema(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match (base.get_mut(key), value) {
+                    (Some(Value::Array(existing)), Value::Array(items)) if key == "required" => {
+                        for item in items {
+                            if !existing.contains(item) {
+                                existing.push(item.clone());
+                            }
+                        }
+                    }
+                    (Some(existing @ Value::Object(_)), Value::Object(_)) => {
+                        merge_schema(existing, value)
+                    }
+                    _ => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (_, Value::Null) => {}
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// 风险等级排序（未知等级按 medium 处理）
+fn risk_rank(level: &str) -> u8 {
+    match level {
+        "low" => 0,
+        "high" => 2,
+        "critical" => 3,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::job::ExitCodeRules;
+    use chrono::Utc;
+    use sqlx::types::Json;
+
+    fn template(name: &str, content: &str) -> JobTemplate {
+        JobTemplate {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            template_type: "command".to_string(),
+            template_content: content.to_string(),
+            parameters_schema: Json(serde_json::json!({})),
+            default_timeout_secs: None,
+            default_retry_times: None,
+            default_concurrent_limit: None,
+            risk_level: "low".to_string(),
+            requires_approval: false,
+            applicable_environments: Json(vec![]),
+            applicable_groups: Json(vec![]),
+            is_active: true,
+            created_by: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            extends_template_id: None,
+            prepend_content: None,
+            append_content: None,
+            includes: Json(vec![]),
+            exit_code_rules: None,
+            deprecated_at: None,
+            deprecated_by: None,
+            deprecation_reason: None,
+            replacement_template_id: None,
+        }
+    }
+
+    #[test]
+    fn test_extends_inherits_defaults_and_wraps_content() {
+        let mut base = template("deploy", "kubectl apply -f {{manifest}}");
+        base.default_timeout_secs = Some(600);
+        base.default_retry_times = Some(2);
+        base.risk_level = "high".to_string();
+        base.applicable_environments = Json(vec!["production".to_string()]);
+        base.exit_code_rules = Some(Json(ExitCodeRules {
+            tool: Some("rsync".to_string()),
+            rules: vec![],
+        }));
+
+        let mut child = template("deploy-with-checks", "");
+        child.extends_template_id = Some(base.id);
+        child.prepend_content = Some("kubectl diff -f {{manifest}} || true".to_string());
+        child.append_content = Some("kubectl rollout status deploy/{{name}}".to_string());
+        child.default_retry_times = Some(0);
+
+        let (base_id, child_id) = (base.id, child.id);
+        let graph = TemplateGraph::new(vec![base, child]);
+        let resolved = graph.resolve(child_id).unwrap();
+
+        assert_eq!(
+            resolved.template_content,
+            "kubectl diff -f {{manifest}} || true\nkubectl apply -f {{manifest}}\nkubectl rollout status deploy/{{name}}"
+        );
+        assert_eq!(resolved.default_timeout_secs, Some(600));
+        assert_eq!(resolved.default_retry_times, Some(0));
+        // 子模板不能降低基础模板的风险等级
+        assert_eq!(resolved.risk_level, "high");
+        assert_eq!(resolved.applicable_environments, vec!["production".to_string()]);
+        assert_eq!(resolved.lineage, vec![base_id, child_id]);
+        assert_eq!(resolved.exit_code_rules.and_then(|r| r.tool), Some("rsync".to_string()));
+    }
+
+    #[test]
+    fn test_own_content_overrides_inherited_content() {
+        let base = template("base", "echo base");
+        let mut child = template("child", "echo child");
+        child.extends_template_id = Some(base.id);
+        let child_id = child.id;
+
+        let graph = TemplateGraph::new(vec![base, child]);
+        assert_eq!(graph.resolve(child_id).unwrap().template_content, "echo child");
+    }
+
+    #[test]
+    fn test_includes_compose_snippets_and_merge_schemas() {
+        let mut setup = template("setup", "cd {{dir}}");
+        setup.parameters_schema = Json(serde_json::json!({
+            "type": "object",
+            "properties": {"dir": {"type": "string"}},
+            "required": ["dir"]
+        }));
+        let mut verify = template("verify", "curl -f {{url}}");
+        verify.parameters_schema = Json(serde_json::json!({
+            "properties": {"url": {"type": "string"}},
+            "required": ["url"]
+        }));
+        verify.requires_approval = true;
+
+        let mut pipeline = template("pipeline", "make deploy");
+        pipeline.includes = Json(vec![setup.id, verify.id]);
+        pipeline.parameters_schema = Json(serde_json::json!({
+            "properties": {"dir": {"type": "string", "default": "/srv/app"}}
+        }));
+
+        let (setup_id, verify_id, pipeline_id) = (setup.id, verify.id, pipeline.id);
+        let graph = TemplateGraph::new(vec![setup, verify, pipeline]);
+        let resolved = graph.resolve(pipeline_id).unwrap();
+
+        assert_eq!(resolved.template_content, "cd {{dir}}\ncurl -f {{url}}\nmake deploy");
+        assert_eq!(resolved.included_templates, vec![setup_id, verify_id]);
+        assert!(resolved.requires_approval);
+        assert_eq!(
+            resolved.parameters_schema,
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "dir": {"type": "string", "default": "/srv/app"},
+                    "url": {"type": "string"}
+                },
+                "required": ["dir", "url"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let mut a = template("a", "echo a");
+        let mut b = template("b", "echo b");
+        let c = template("c", "echo c");
+        b.extends_template_id = Some(a.id);
+        let (a_id, b_id, c_id) = (a.id, b.id, c.id);
+
+        let graph = TemplateGraph::new(vec![a.clone(), b.clone(), c]);
+        // a 继承 b（b 已继承 a）成环；a 组合 c 不成环
+        assert!(graph.check_references(Some(a_id), Some(b_id), &[]).is_err());
+        assert!(graph.check_references(Some(a_id), None, &[b_id]).is_err());
+        assert!(graph.check_references(Some(a_id), None, &[a_id]).is_err());
+        assert!(graph
+            .check_references(Some(a_id), Some(c_id), &[c_id])
+            .is_ok());
+        assert!(graph
+            .check_references(None, Some(Uuid::new_v4()), &[])
+            .is_err());
+
+        // 已存储的环在展开时报错而不是无限递归
+        a.extends_template_id = Some(b_id);
+        let graph = TemplateGraph::new(vec![a, b]);
+        assert!(graph.resolve(a_id).is_err());
+    }
+
+    #[test]
+    fn test_missing_base_template() {
+        let mut child = template("child", "");
+        child.extends_template_id = Some(Uuid::new_v4());
+        let child_id = child.id;
+
+        let graph = TemplateGraph::new(vec![child]);
+        assert!(graph.resolve(child_id).is_err());
+    }
+}
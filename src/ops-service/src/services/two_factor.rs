@@ -0,0 +1,314 @@
+//! 双因素认证（TOTP）
+//!
+//! 绑定分两步：发起绑定生成密钥，用认证器中的验证码确认后启用并下发恢复码。
+//! 已启用的用户登录时须提供验证码；高风险操作（生产环境作业、审批高风险请求、凭证变更）
+//! 须在当前会话的二次验证有效期内。配置中指定的角色必须启用 TOTP。
+//! TOTP 密钥加密后落库（见 auth::credential_cipher）
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::{middleware::AuthContext, totp, CredentialCipher},
+    config::TwoFactorConfig,
+    error::AppError,
+    i18n,
+    models::auth::{TwoFactorEnrollment, TwoFactorStatus, UserTotp},
+    repository::auth_repo::AuthRepository,
+};
+
+/// 双因素认证服务
+pub struct TwoFactorService {
+    db: PgPool,
+    config: TwoFactorConfig,
+    cipher: CredentialCipher,
+}
+
+impl TwoFactorService {
+    pub fn new(db: PgPool, config: TwoFactorConfig, cipher: CredentialCipher) -> Self {
+        Self { db, config, cipher }
+    }
+
+    fn repo(&self) -> AuthRepository {
+        AuthRepository::new(self.db.clone())
+    }
+
+    /// 用户已启用的 TOTP 密钥
+    async fn enabled_totp(&self, user_id: Uuid) -> Result<Option<UserTotp>, AppError> {
+        Ok(self
+            .repo()
+            .find_user_totp(user_id)
+            .await?
+            .filter(|totp| totp.enabled_at.is_some()))
+    }
+
+    /// 用户是否已启用 TOTP
+    pub async fn is_enabled(&self, user_id: Uuid) -> Result<bool, AppError> {
+        Ok(self.enabled_totp(user_id).await?.is_some())
+    }
+
+    /// 当前用户的双因素认证状态
+    pub async fn status(&self, ctx: &AuthContext) -> Result<TwoFactorStatus, AppError> {
+        let repo = self.repo();
+        let totp = repo.find_user_totp(ctx.user_id).await?;
+        let enabled_at = totp.as_ref().and_then(|t| t.enabled_at);
+        let recovery_codes_remaining = match enabled_at {
+            Some(_) => repo.count_unused_recovery_codes(ctx.user_id).await?,
+            None => 0,
+        };
+
+        Ok(TwoFactorStatus {
+            enabled: enabled_at.is_some(),
+            enabled_at,
+            pending: totp.is_some() && enabled_at.is_none(),
+            required: self.config.is_required_for(&ctx.roles),
+            recovery_codes_remaining,
+            step_up_until: self.session_step_up_until(ctx).await?,
+        })
+    }
+
+    /// 发起绑定：生成新密钥（覆盖尚未确认的密钥）
+    pub async fn begin_enrollment(
+        &self,
+        user_id: Uuid,
+        account: &str,
+    ) -> Result<TwoFactorEnrollment, AppError> {
+        let secret = totp::generate_secret();
+        let encrypted = self.cipher.encrypt(&secret)?;
+        if !self.repo().upsert_pending_totp(user_id, &encrypted).await? {
+            return Err(already_enabled());
+        }
+
+        Ok(TwoFactorEnrollment {
+            otpauth_uri: totp::provisioning_uri(&self.config.issuer, account, &secret),
+            secret,
+        })
+    }
+
+    /// 确认绑定：验证码正确后启用 TOTP，返回恢复码明文
+    pub async fn confirm_enrollment(
+        &self,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<Vec<String>, AppError> {
+        let repo = self.repo();
+        let pending = repo
+            .find_user_totp(user_id)
+            .await?
+            .filter(|totp| totp.enabled_at.is_none())
+            .ok_or_else(|| {
+                AppError::BadRequest(i18n::t("error.two_factor.not_enrolling").to_string())
+            })?;
+
+        let secret = self.decode_secret(&pending)?;
+        let step =
+            totp::verify(&secret, code, Utc::now().timestamp(), self.config.skew_steps, None)
+                .ok_or_else(invalid_code)?;
+
+        let codes = totp::generate_recovery_codes(self.config.recovery_code_count);
+        let hashes = hash_recovery_codes(&codes);
+        repo.enable_user_totp(user_id, step, &hashes)
+            .await?
+            .ok_or_else(already_enabled)?;
+
+        tracing::info!(user_id = %user_id, "Two-factor authentication enabled");
+        Ok(codes)
+    }
+
+    /// 关闭 TOTP（需验证码；所属角色要求启用时拒绝）
+    pub async fn disable(&self, ctx: &AuthContext, code: &str) -> Result<(), AppError> {
+        if self.config.is_required_for(&ctx.roles) {
+            return Err(AppError::BadRequest(
+                i18n::t("error.two_factor.required_by_role").to_string(),
+            ));
+        }
+        self.require_valid_code(ctx.user_id, code).await?;
+        self.repo().delete_user_totp(ctx.user_id).await?;
+
+        tracing::info!(user_id = %ctx.user_id, "Two-factor authentication disabled");
+        Ok(())
+    }
+
+    /// 重新生成恢复码（需验证码，旧恢复码全部作废）
+    pub async fn regenerate_recovery_codes(
+        &self,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<Vec<String>, AppError> {
+        self.require_valid_code(user_id, code).await?;
+
+        let codes = totp::generate_recovery_codes(self.config.recovery_code_count);
+        self.repo()
+            .replace_recovery_codes(user_id, &hash_recovery_codes(&codes))
+            .await?;
+        Ok(codes)
+    }
+
+    /// 管理员重置用户的 TOTP（用户丢失认证器与恢复码时），返回用户是否曾绑定
+    pub async fn reset(&self, user_id: Uuid) -> Result<bool, AppError> {
+        self.repo().delete_user_totp(user_id).await
+    }
+
+    /// 校验 TOTP 验证码或恢复码
+    ///
+    /// 验证码的时间步只能使用一次；恢复码使用后作废。用户未启用 TOTP 时返回 false
+    pub async fn verify_code(&self, user_id: Uuid, code: &str) -> Result<bool, AppError> {
+        let Some(enabled) = self.enabled_totp(user_id).await? else {
+            return Ok(false);
+        };
+        let repo = self.repo();
+
+        if totp::looks_like_totp_code(code) {
+            let secret = self.decode_secret(&enabled)?;
+            let step = totp::verify(
+                &secret,
+                code,
+                Utc::now().timestamp(),
+                self.config.skew_steps,
+                enabled.last_used_step,
+            );
+            return match step {
+                // 并发请求使用同一验证码时只有一个能记录成功
+                Some(step) => repo.record_totp_step(user_id, step).await,
+                None => Ok(false),
+            };
+        }
+
+        let normalized = totp::normalize_recovery_code(code);
+        if normalized.is_empty() {
+            return Ok(false);
+        }
+        let used = repo
+            .consume_recovery_code(user_id, &AuthRepository::hash_token(&normalized))
+            .await?;
+        if used {
+            tracing::warn!(user_id = %user_id, "Two-factor recovery code used");
+        }
+        Ok(used)
+    }
+
+    /// 二次验证：验证码正确后在当前会话记录有效期
+    pub async fn step_up(&self, ctx: &AuthContext, code: &str) -> Result<DateTime<Utc>, AppError> {
+        let session_id = ctx.session_id.ok_or_else(|| {
+            AppError::BadRequest(i18n::t("error.two_factor.session_required").to_string())
+        })?;
+        self.require_valid_code(ctx.user_id, code).await?;
+
+        let step_up_until = self.step_up_deadline(Utc::now());
+        if !self
+            .repo()
+            .set_user_session_step_up(session_id, ctx.user_id, step_up_until)
+            .await?
+        {
+            return Err(AppError::Unauthorized);
+        }
+        Ok(step_up_until)
+    }
+
+    /// 登录或二次验证后的有效期截止时间
+    pub fn step_up_deadline(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now + chrono::Duration::seconds(self.config.step_up_window_secs as i64)
+    }
+
+    /// 高风险操作前检查二次验证
+    ///
+    /// 已启用 TOTP 或所属角色要求启用的用户，须在当前会话的二次验证有效期内；
+    /// 角色要求启用但尚未绑定的用户须先完成绑定
+    pub async fn require_step_up(&self, ctx: &AuthContext) -> Result<(), AppError> {
+        let enabled = self.is_enabled(ctx.user_id).await?;
+        if !enabled {
+            if self.config.is_required_for(&ctx.roles) {
+                return Err(AppError::TwoFactorRequired(
+                    "Two-factor enrollment required".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+
+        let verified = self
+            .session_step_up_until(ctx)
+            .await?
+            .is_some_and(|until| until > Utc::now());
+        if !verified {
+            return Err(AppError::TwoFactorRequired("Step-up verification required".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn session_step_up_until(
+        &self,
+        ctx: &AuthContext,
+    ) -> Result<Option<DateTime<Utc>>, AppError> {
+        let Some(session_id) = ctx.session_id else {
+            return Ok(None);
+        };
+        Ok(self
+            .repo()
+            .find_user_session(session_id)
+            .await?
+            .filter(|session| session.user_id == ctx.user_id && session.revoked_at.is_none())
+            .and_then(|session| session.step_up_until))
+    }
+
+    async fn require_valid_code(&self, user_id: Uuid, code: &str) -> Result<(), AppError> {
+        if !self.is_enabled(user_id).await? {
+            return Err(AppError::BadRequest(i18n::t("error.two_factor.not_enabled").to_string()));
+        }
+        if !self.verify_code(user_id, code).await? {
+            return Err(invalid_code());
+        }
+        Ok(())
+    }
+
+    /// 加密升级前以明文保存的 TOTP 密钥，返回加密的数量
+    pub async fn encrypt_legacy_secrets(&self) -> Result<usize, AppError> {
+        let repo = self.repo();
+        let mut encrypted = 0;
+        for totp in repo.list_user_totp().await? {
+            if CredentialCipher::is_encrypted(&totp.secret) {
+                continue;
+            }
+            let secret = self.cipher.encrypt(&totp.secret)?;
+            // 并发启动的实例只有一个能替换成功
+            if repo
+                .replace_totp_secret(totp.user_id, &totp.secret, &secret)
+                .await?
+            {
+                encrypted += 1;
+            }
+        }
+        if encrypted > 0 {
+            tracing::info!(count = encrypted, "Encrypted legacy TOTP secrets");
+        }
+        Ok(encrypted)
+    }
+
+    /// 解密并解码 TOTP 密钥
+    ///
+    /// 滚动升级期间旧版本实例仍可能写入明文密钥，明文按原样解码，下次启动时加密
+    fn decode_secret(&self, totp: &UserTotp) -> Result<Vec<u8>, AppError> {
+        let secret = if CredentialCipher::is_encrypted(&totp.secret) {
+            self.cipher.decrypt(&totp.secret)?
+        } else {
+            totp.secret.clone()
+        };
+        totp::base32_decode(&secret)
+            .ok_or_else(|| AppError::Internal("Invalid stored TOTP secret".to_string()))
+    }
+}
+
+fn already_enabled() -> AppError {
+    AppError::BadRequest(i18n::t("error.two_factor.already_enabled").to_string())
+}
+
+fn invalid_code() -> AppError {
+    AppError::BadRequest(i18n::t("error.two_factor.invalid_code").to_string())
+}
+
+fn hash_recovery_codes(codes: &[String]) -> Vec<String> {
+    codes
+        .iter()
+        .map(|code| AuthRepository::hash_token(&totp::normalize_recovery_code(code)))
+        .collect()
+}
@@ -0,0 +1,218 @@
+//! 查看类操作审计
+//!
+//! 生产环境的命令输出本身即敏感信息：订阅作业事件流、查看任务输出、下载证据包时记录审计。
+//! 事件流订阅时记录一条，断开时再记录一条（含订阅时长与推送的事件数）。
+//! 记录粒度由 `audit.view_level` 控制，审计写入失败只记录日志，不影响查看本身
+
+use futures::{Stream, StreamExt};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    config::ViewAuditLevel,
+    services::audit_service::{AuditAction, AuditLogParams, AuditService},
+};
+
+/// 查看类操作审计器
+#[derive(Clone)]
+pub struct ViewAuditor {
+    audit_service: Arc<AuditService>,
+    level: ViewAuditLevel,
+}
+
+/// 审计的查看对象
+#[derive(Debug, Clone)]
+pub struct ViewTarget {
+    pub resource_type: &'static str,
+    pub resource_id: Option<Uuid>,
+    /// 记录该操作所需的最低级别
+    pub level: ViewAuditLevel,
+}
+
+impl ViewTarget {
+    pub fn new(resource_type: &'static str, resource_id: Option<Uuid>) -> Self {
+        Self {
+            resource_type,
+            resource_id,
+            level: ViewAuditLevel::Standard,
+        }
+    }
+
+    /// 仅在 verbose 级别记录
+    pub fn verbose(mut self) -> Self {
+        self.level = ViewAuditLevel::Verbose;
+        self
+    }
+}
+
+impl ViewAuditor {
+    pub fn new(audit_service: Arc<AuditService>, level: ViewAuditLevel) -> Self {
+        Self {
+            audit_service,
+            level,
+        }
+    }
+
+    /// 当前级别是否记录该对象
+    pub fn records(&self, target: &ViewTarget) -> bool {
+        self.level != ViewAuditLevel::Off && self.level >= target.level
+    }
+
+    /// 记录一次查看
+    pub async fn record(
+        &self,
+        user_id: Uuid,
+        action: AuditAction,
+        target: &ViewTarget,
+        summary: &str,
+        details: serde_json::Value,
+    ) {
+        if self.records(target) {
+            Self::write(&self.audit_service, user_id, action, target, summary, details).await;
+        }
+    }
+
+    /// 包装事件流：订阅时记录，流被丢弃（客户端断开或权限撤销）时记录订阅时长与事件数
+    pub async fn track_stream<S>(
+        &self,
+        user_id: Uuid,
+        stream_name: &'static str,
+        target: ViewTarget,
+        stream: S,
+    ) -> impl Stream<Item = S::Item>
+    where
+        S: Stream,
+    {
+        let mut guard = if self.records(&target) {
+            Self::write(
+                &self.audit_service,
+                user_id,
+                AuditAction::StreamSubscribe,
+                &target,
+                &format!("Subscribed to {} stream", stream_name),
+                serde_json::json!({ "stream": stream_name }),
+            )
+            .await;
+            Some(SubscriptionGuard {
+                audit_service: self.audit_service.clone(),
+                user_id,
+                stream_name,
+                target,
+                started: Instant::now(),
+                events: 0,
+            })
+        } else {
+            None
+        };
+
+        stream.map(move |item| {
+            if let Some(guard) = guard.as_mut() {
+                guard.events += 1;
+            }
+            item
+        })
+    }
+
+    async fn write(
+        audit_service: &AuditService,
+        user_id: Uuid,
+        action: AuditAction,
+        target: &ViewTarget,
+        summary: &str,
+        details: serde_json::Value,
+    ) {
+        let params = AuditLogParams {
+            subject_id: user_id,
+            subject_type: "user",
+            subject_name: None,
+            action: action.as_str(),
+            resource_type: target.resource_type,
+            resource_id: target.resource_id,
+            resource_name: None,
+            changes: Some(details),
+            changes_summary: Some(summary),
+            source_ip: None,
+            user_agent: None,
+            trace_id: None,
+            result: "success",
+            error_message: None,
+        };
+        if let Err(e) = audit_service.log_action(params).await {
+            warn!(error = %e, action = action.as_str(), "Failed to record view audit");
+        }
+    }
+}
+
+/// 订阅期间存活，丢弃时记录断开
+struct SubscriptionGuard {
+    audit_service: Arc<AuditService>,
+    user_id: Uuid,
+    stream_name: &'static str,
+    target: ViewTarget,
+    started: Instant,
+    events: u64,
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let audit_service = self.audit_service.clone();
+        let user_id = self.user_id;
+        let target = self.target.clone();
+        let duration_secs = self.started.elapsed().as_secs();
+        let summary = format!(
+            "Closed {} stream after {}s ({} events)",
+            self.stream_name, duration_secs, self.events
+        );
+        let details = serde_json::json!({
+            "stream": self.stream_name,
+            "duration_secs": duration_secs,
+            "events": self.events,
+        });
+        handle.spawn(async move {
+            ViewAuditor::write(
+                &audit_service,
+                user_id,
+                AuditAction::StreamClose,
+                &target,
+                &summary,
+                details,
+            )
+            .await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auditor(level: ViewAuditLevel) -> ViewAuditor {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/postgres").unwrap();
+        ViewAuditor::new(Arc::new(AuditService::new(pool)), level)
+    }
+
+    #[tokio::test]
+    async fn test_level_controls_recorded_targets() {
+        let job = ViewTarget::new("job", Some(Uuid::new_v4()));
+        let approvals = ViewTarget::new("approval", None).verbose();
+
+        assert!(!auditor(ViewAuditLevel::Off).records(&job));
+        assert!(auditor(ViewAuditLevel::Standard).records(&job));
+        assert!(!auditor(ViewAuditLevel::Standard).records(&approvals));
+        assert!(auditor(ViewAuditLevel::Verbose).records(&approvals));
+    }
+
+    #[tokio::test]
+    async fn test_untracked_stream_passes_items_through() {
+        let target = ViewTarget::new("approval", None).verbose();
+        let stream = auditor(ViewAuditLevel::Standard)
+            .track_stream(Uuid::new_v4(), "approval", target, futures::stream::iter(1..=3))
+            .await;
+        assert_eq!(stream.collect::<Vec<_>>().await, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,298 @@
+//! 任务失败诊断
+//!
+//! 执行器在连接与执行过程中按阶段（DNS、TCP、握手、认证、执行）记录耗时，
+//! 任务失败时与 stderr 末尾若干行一起组装为结构化诊断信息，存入 tasks.diagnostics；
+//! 作业开启详细追踪时还记录连接协商日志
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// 诊断信息保留的 stderr 末尾行数
+pub const DIAGNOSTICS_STDERR_LINES: usize = 20;
+
+/// 连接/执行阶段（按发生顺序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionPhase {
+    /// DNS 解析
+    Dns,
+    /// TCP 连接
+    TcpConnect,
+    /// SSH 握手（含主机密钥验证）
+    Handshake,
+    /// 认证
+    Auth,
+    /// 打开通道并执行命令
+    Exec,
+}
+
+/// 单个阶段的耗时
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    pub phase: ConnectionPhase,
+    pub duration_ms: u64,
+    pub succeeded: bool,
+}
+
+/// 任务失败诊断信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskDiagnostics {
+    /// 最后进入的阶段（未进入任何阶段时为空，如执行前的参数错误）
+    pub phase_reached: Option<ConnectionPhase>,
+    /// 失败的阶段（命令以非零退出码结束时为空）
+    pub failed_phase: Option<ConnectionPhase>,
+    pub timings: Vec<PhaseTiming>,
+    /// stderr 末尾若干行（已脱敏）
+    pub stderr_tail: Vec<String>,
+    pub error: Option<String>,
+    pub collected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct CollectorState {
+    current: Option<(ConnectionPhase, Instant)>,
+    timings: Vec<PhaseTiming>,
+    /// 是否记录协商日志
+    verbose: bool,
+    notes: Vec<TraceNote>,
+}
+
+/// 连接协商日志（详细追踪）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceNote {
+    /// 记录时所处的阶段
+    pub phase: Option<ConnectionPhase>,
+    pub message: String,
+}
+
+/// 阶段变化通知：进入阶段时结果为 None，结束时为阶段是否成功
+pub type PhaseListener = Arc<dyn Fn(ConnectionPhase, Option<bool>) + Send + Sync>;
+
+#[derive(Clone)]
+struct Listener(PhaseListener);
+
+impl std::fmt::Debug for Listener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PhaseListener")
+    }
+}
+
+/// 阶段耗时收集器（可克隆，克隆共享同一份记录）
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsCollector {
+    state: Arc<Mutex<CollectorState>>,
+    listener: Option<Listener>,
+}
+
+impl DiagnosticsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 同时记录连接协商日志的收集器（作业开启详细追踪时使用）
+    pub fn verbose() -> Self {
+        let collector = Self::default();
+        if let Ok(mut state) = collector.state.lock() {
+            state.verbose = true;
+        }
+        collector
+    }
+
+    /// 阶段变化时通知监听者（任务执行日志）
+    pub fn with_listener(mut self, listener: PhaseListener) -> Self {
+        self.listener = Some(Listener(listener));
+        self
+    }
+
+    fn notify(&self, phase: ConnectionPhase, succeeded: Option<bool>) {
+        if let Some(Listener(listener)) = &self.listener {
+            listener(phase, succeeded);
+        }
+    }
+
+    /// 记录协商日志；未开启详细追踪时不生成内容
+    pub fn note(&self, message: impl FnOnce() -> String) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.verbose {
+                let phase = state.current.map(|(phase, _)| phase);
+                state.notes.push(TraceNote {
+                    phase,
+                    message: message(),
+                });
+            }
+        }
+    }
+
+    /// 已记录的协商日志
+    pub fn notes(&self) -> Vec<TraceNote> {
+        self.state
+            .lock()
+            .map(|state| state.notes.clone())
+            .unwrap_or_default()
+    }
+
+    /// 进入阶段
+    pub fn begin(&self, phase: ConnectionPhase) {
+        if let Ok(mut state) = self.state.lock() {
+            state.current = Some((phase, Instant::now()));
+        }
+        self.notify(phase, None);
+    }
+
+    /// 结束当前阶段
+    pub fn end(&self, succeeded: bool) {
+        let ended = self.state.lock().ok().and_then(|mut state| {
+            let (phase, started) = state.current.take()?;
+            state.timings.push(PhaseTiming {
+                phase,
+                duration_ms: started.elapsed().as_millis() as u64,
+                succeeded,
+            });
+            Some(phase)
+        });
+        if let Some(phase) = ended {
+            self.notify(phase, Some(succeeded));
+        }
+    }
+
+    /// 记录一个阶段：按返回结果标记成功或失败
+    pub async fn measure<T, E>(
+        &self,
+        phase: ConnectionPhase,
+        fut: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        self.begin(phase);
+        let result = fut.await;
+        self.end(result.is_ok());
+        result
+    }
+
+    /// 已记录的阶段耗时；未结束的阶段（超时、取消）记为失败
+    pub fn timings(&self) -> Vec<PhaseTiming> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+        let mut timings = state.timings.clone();
+        if let Some((phase, started)) = state.current {
+            timings.push(PhaseTiming {
+                phase,
+                duration_ms: started.elapsed().as_millis() as u64,
+                succeeded: false,
+            });
+        }
+        timings
+    }
+
+    /// 组装诊断信息
+    pub fn build(&self, error: Option<String>, stderr: &str) -> TaskDiagnostics {
+        let timings = self.timings();
+        TaskDiagnostics {
+            phase_reached: timings.last().map(|t| t.phase),
+            failed_phase: timings.iter().find(|t| !t.succeeded).map(|t| t.phase),
+            stderr_tail: stderr_tail(stderr, DIAGNOSTICS_STDERR_LINES),
+            timings,
+            error,
+            collected_at: Utc::now(),
+        }
+    }
+}
+
+/// stderr 末尾非空的若干行
+pub fn stderr_tail(stderr: &str, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = stderr
+        .lines()
+        .rev()
+        .filter(|line| !line.trim().is_empty())
+        .take(max_lines)
+        .map(str::to_string)
+        .collect();
+    lines.reverse();
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collector_records_failed_phase() {
+        let collector = DiagnosticsCollector::new();
+        let _ = collector
+            .measure(ConnectionPhase::Dns, async { Ok::<_, ()>(()) })
+            .await;
+        let _ = collector
+            .measure(ConnectionPhase::TcpConnect, async { Ok::<_, ()>(()) })
+            .await;
+        let _ = collector
+            .measure(ConnectionPhase::Auth, async { Err::<(), _>("denied") })
+            .await;
+
+        let diagnostics = collector.build(Some("SSH认证失败".to_string()), "");
+        assert_eq!(diagnostics.phase_reached, Some(ConnectionPhase::Auth));
+        assert_eq!(diagnostics.failed_phase, Some(ConnectionPhase::Auth));
+        assert_eq!(diagnostics.timings.len(), 3);
+        assert!(diagnostics.stderr_tail.is_empty());
+    }
+
+    #[test]
+    fn test_unfinished_phase_counts_as_failed() {
+        let collector = DiagnosticsCollector::new();
+        collector.begin(ConnectionPhase::Handshake);
+
+        let diagnostics = collector.build(None, "");
+        assert_eq!(diagnostics.failed_phase, Some(ConnectionPhase::Handshake));
+    }
+
+    #[tokio::test]
+    async fn test_listener_notified_on_phase_changes() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = seen.clone();
+        let collector = DiagnosticsCollector::new().with_listener(Arc::new(move |phase, ok| {
+            recorder.lock().unwrap().push((phase, ok));
+        }));
+        let _ = collector
+            .measure(ConnectionPhase::Handshake, async { Ok::<_, ()>(()) })
+            .await;
+        let _ = collector
+            .measure(ConnectionPhase::Auth, async { Err::<(), _>("denied") })
+            .await;
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                (ConnectionPhase::Handshake, None),
+                (ConnectionPhase::Handshake, Some(true)),
+                (ConnectionPhase::Auth, None),
+                (ConnectionPhase::Auth, Some(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_notes_recorded_only_when_verbose() {
+        let collector = DiagnosticsCollector::new();
+        collector.note(|| "ignored".to_string());
+        assert!(collector.notes().is_empty());
+
+        let collector = DiagnosticsCollector::verbose();
+        collector.note(|| "resolved".to_string());
+        collector.begin(ConnectionPhase::Handshake);
+        collector.note(|| "handshake".to_string());
+
+        let notes = collector.notes();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].phase, None);
+        assert_eq!(notes[1].phase, Some(ConnectionPhase::Handshake));
+        assert_eq!(notes[1].message, "handshake");
+    }
+
+    #[test]
+    fn test_stderr_tail_keeps_last_lines() {
+        let stderr = "line 1\n\nline 2\nline 3\n";
+        assert_eq!(stderr_tail(stderr, 2), vec!["line 2", "line 3"]);
+        assert_eq!(stderr_tail(stderr, 10), vec!["line 1", "line 2", "line 3"]);
+    }
+}
@@ -0,0 +1,105 @@
+//! 远端输出编码转换
+//! 按主机配置的编码（utf-8/gbk/自动检测）将输出转换为 UTF-8，用于存储与推送
+
+use encoding_rs::{Encoding, GBK, UTF_8};
+
+use super::OutputEncoding;
+
+/// 转换为 UTF-8 后的输出
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// 实际使用的原始编码（utf-8/gbk）
+    pub encoding: &'static str,
+}
+
+/// 确定输出的实际编码
+///
+/// 自动检测时综合所有输出流判断：均为合法 UTF-8（允许末尾截断的多字节字符）则按 UTF-8，
+/// 否则均可无损按 GBK 解码时按 GBK，都不满足时回退到 UTF-8
+pub fn resolve_encoding(encoding: OutputEncoding, streams: &[&[u8]]) -> &'static Encoding {
+    match encoding {
+        OutputEncoding::Utf8 => UTF_8,
+        OutputEncoding::Gbk => GBK,
+        OutputEncoding::Auto => {
+            if streams.iter().all(|s| is_utf8_prefix(s)) {
+                UTF_8
+            } else if streams.iter().all(|s| {
+                GBK.decode_without_bom_handling_and_without_replacement(s)
+                    .is_some()
+            }) {
+                GBK
+            } else {
+                UTF_8
+            }
+        }
+    }
+}
+
+/// 按指定编码解码（无效字节替换为 U+FFFD）
+pub fn decode(bytes: &[u8], encoding: &'static Encoding) -> String {
+    encoding.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+/// 解码标准输出与标准错误
+pub fn decode_output(encoding: OutputEncoding, stdout: &[u8], stderr: &[u8]) -> DecodedOutput {
+    let resolved = resolve_encoding(encoding, &[stdout, stderr]);
+    DecodedOutput {
+        stdout: decode(stdout, resolved),
+        stderr: decode(stderr, resolved),
+        encoding: encoding_label(resolved),
+    }
+}
+
+/// 编码名称（小写，与主机配置取值一致）
+pub fn encoding_label(encoding: &'static Encoding) -> &'static str {
+    if encoding == GBK {
+        "gbk"
+    } else {
+        "utf-8"
+    }
+}
+
+/// 是否为合法 UTF-8（增量输出可能在多字节字符中间截断，末尾不完整的字符视为合法）
+fn is_utf8_prefix(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "中文输出" 的 GBK 编码
+    const GBK_BYTES: &[u8] = &[0xD6, 0xD0, 0xCE, 0xC4, 0xCA, 0xE4, 0xB3, 0xF6];
+
+    #[test]
+    fn test_decode_gbk_output() {
+        let decoded = decode_output(OutputEncoding::Gbk, GBK_BYTES, b"");
+        assert_eq!(decoded.stdout, "中文输出");
+        assert_eq!(decoded.encoding, "gbk");
+
+        // 按 UTF-8 处理时为替换字符
+        let lossy = decode_output(OutputEncoding::Utf8, GBK_BYTES, b"");
+        assert!(lossy.stdout.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn test_auto_detect_encoding() {
+        let decoded = decode_output(OutputEncoding::Auto, GBK_BYTES, b"error");
+        assert_eq!(decoded.stdout, "中文输出");
+        assert_eq!(decoded.stderr, "error");
+        assert_eq!(decoded.encoding, "gbk");
+
+        let utf8 = "中文输出".as_bytes();
+        let decoded = decode_output(OutputEncoding::Auto, utf8, b"");
+        assert_eq!(decoded.stdout, "中文输出");
+        assert_eq!(decoded.encoding, "utf-8");
+
+        // 增量输出在多字节字符中间截断时仍识别为 UTF-8
+        assert_eq!(resolve_encoding(OutputEncoding::Auto, &[&utf8[..4]]), UTF_8);
+    }
+}
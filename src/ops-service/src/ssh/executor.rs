@@ -3,8 +3,10 @@
 //!
 //! 使用 russh 库实现真实的 SSH 连接和命令执行
 
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::time::timeout;
 use tracing::{debug, error, info, warn};
 
@@ -13,13 +15,17 @@ use base64::{engine::general_purpose, Engine as _};
 
 use russh::client;
 use russh::client::Config;
-use russh::keys::PrivateKeyWithHashAlg;
-use russh::ChannelMsg;
 use russh::keys::decode_secret_key;
-use russh::keys::PublicKeyBase64;
-use sha2::Digest;
 use russh::keys::ssh_key::PublicKey;
+use russh::keys::PrivateKeyWithHashAlg;
+use russh::keys::PublicKeyBase64;
+use russh::ChannelMsg;
+use russh_sftp::client::SftpSession;
 
+use super::diagnostics::{ConnectionPhase, DiagnosticsCollector};
+use super::encoding::{decode, decode_output, resolve_encoding};
+use super::host_key::{fingerprint, verify_host_key, HostKeyFailure, PresentedHostKey};
+use super::output_limit::OutputCapture;
 use crate::error::AppError;
 
 // 重新导出 common 的类型
@@ -28,8 +34,12 @@ pub use common::{execution::ExecutionResult, ssh::*};
 /// SSH客户端
 pub struct SSHClient {
     config: SshConfig,
+    diagnostics: DiagnosticsCollector,
 }
 
+/// SFTP 上传脚本文件的分块大小
+const SFTP_CHUNK_SIZE: usize = 32 * 1024;
+
 /// 进度回调函数类型
 /// 参数: (当前输出片段, 是否完成)
 pub type ProgressCallback = Arc<dyn Fn(String, bool) + Send + Sync>;
@@ -37,7 +47,16 @@ pub type ProgressCallback = Arc<dyn Fn(String, bool) + Send + Sync>;
 impl SSHClient {
     /// 从 common 的 SshConfig 创建 SSH 客户端
     pub fn new(config: SshConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            diagnostics: DiagnosticsCollector::new(),
+        }
+    }
+
+    /// 使用外部的阶段耗时收集器（任务失败时由调用方组装诊断信息）
+    pub fn with_diagnostics(mut self, diagnostics: DiagnosticsCollector) -> Self {
+        self.diagnostics = diagnostics;
+        self
     }
 
     /// 从 host, username 和 password 创建客户端
@@ -60,6 +79,38 @@ impl SSHClient {
         &self.config
     }
 
+    /// 按配置的输出编码转换为 UTF-8 并组装执行结果
+    fn build_result(
+        &self,
+        exit_code: i32,
+        output: &OutputCapture,
+        duration_secs: f64,
+        timed_out: bool,
+    ) -> ExecutionResult {
+        let decoded =
+            decode_output(self.config.output_encoding, &output.stdout(), &output.stderr());
+        ExecutionResult {
+            exit_code,
+            stdout: decoded.stdout,
+            stderr: decoded.stderr,
+            duration_secs,
+            timed_out,
+            output_encoding: Some(decoded.encoding.to_string()),
+            output_limit_exceeded: output.exceeded(),
+        }
+    }
+
+    /// 输出超过上限：终止远端命令（服务端不支持信号时由随后关闭通道结束）
+    async fn abort_oversized(&self, channel: &russh::Channel<client::Msg>, output: &OutputCapture) {
+        warn!(
+            host = %self.config.host,
+            output_bytes = output.total_bytes(),
+            max_output_bytes = self.config.max_output_bytes,
+            "Command output exceeded limit, terminating"
+        );
+        let _ = channel.signal(russh::Sig::KILL).await;
+    }
+
     /// 创建带验证策略的会话处理器
     fn create_session(&self) -> SSHSession {
         SSHSession {
@@ -67,9 +118,189 @@ impl SSHClient {
             known_hosts: self.config.known_hosts.clone(),
             host: self.config.host.clone(),
             port: self.config.port,
+            host_key_failure: Arc::new(std::sync::Mutex::new(None)),
+            presented_key: Arc::new(std::sync::Mutex::new(None)),
+            diagnostics: self.diagnostics.clone(),
+        }
+    }
+
+    /// 仅完成 SSH 握手，返回服务器提供的主机密钥（不认证、不执行命令）
+    ///
+    /// 主机密钥未通过验证时同样返回服务器提供的密钥，由调用方与固定的指纹比对
+    pub async fn probe_host_key(&self) -> Result<PresentedHostKey, AppError> {
+        let session = self.create_session();
+        let presented_key = session.presented_key.clone();
+        let result = self.connect_session(session).await;
+        let presented = presented_key.lock().ok().and_then(|mut slot| slot.take());
+
+        match (result, presented) {
+            (Ok(handle), Some(presented)) => {
+                let _ = handle
+                    .disconnect(russh::Disconnect::ByApplication, "", "")
+                    .await;
+                Ok(presented)
+            }
+            (Err(AppError::SshHostKeyVerificationError(_)), Some(presented)) => Ok(presented),
+            (Err(e), _) => Err(e),
+            (Ok(_), None) => Err(AppError::SshConnectionError(format!(
+                "SSH握手未收到主机密钥: {}:{}",
+                self.config.host, self.config.port
+            ))),
         }
     }
 
+    /// 建立连接并完成主机密钥验证
+    async fn connect(&self) -> Result<client::Handle<SSHSession>, AppError> {
+        self.connect_session(self.create_session()).await
+    }
+
+    /// 使用指定的会话处理器建立连接
+    ///
+    /// DNS 解析与 TCP 连接受连接超时约束，SSH 握手受握手超时约束，各阶段分别计时
+    async fn connect_session(
+        &self,
+        session: SSHSession,
+    ) -> Result<client::Handle<SSHSession>, AppError> {
+        // 创建 SSH 客户端配置
+        let client_config = Arc::new(Config {
+            preferred: russh::Preferred::default(),
+            ..Default::default()
+        });
+
+        let connect_timeout = Duration::from_secs(self.config.connect_timeout_secs);
+        let handshake_timeout = Duration::from_secs(self.config.handshake_timeout_secs);
+        let host_key_failure = session.host_key_failure.clone();
+
+        let addr = self
+            .diagnostics
+            .measure(ConnectionPhase::Dns, async {
+                timeout(
+                    connect_timeout,
+                    tokio::net::lookup_host((self.config.host.as_str(), self.config.port)),
+                )
+                .await
+                .map_err(|_| {
+                    AppError::SshConnectionError(format!("DNS解析超时: {}", self.config.host))
+                })?
+                .map_err(|e| {
+                    AppError::SshConnectionError(format!(
+                        "DNS解析失败: {}: {}",
+                        self.config.host, e
+                    ))
+                })?
+                .next()
+                .ok_or_else(|| {
+                    AppError::SshConnectionError(format!("DNS解析无结果: {}", self.config.host))
+                })
+            })
+            .await?;
+        self.diagnostics
+            .note(|| format!("Resolved {} to {}", self.config.host, addr));
+
+        let stream = self
+            .diagnostics
+            .measure(ConnectionPhase::TcpConnect, async {
+                timeout(connect_timeout, tokio::net::TcpStream::connect(addr))
+                    .await
+                    .map_err(|_| {
+                        AppError::SshConnectionError(format!(
+                            "TCP连接超时: {}@{}",
+                            self.config.host, self.config.port
+                        ))
+                    })?
+                    .map_err(|e| {
+                        error!(error = %e, "TCP连接失败");
+                        AppError::SshConnectionError(format!("TCP连接失败: {}", e))
+                    })
+            })
+            .await?;
+        self.diagnostics
+            .note(|| format!("TCP connection established to {}", addr));
+
+        let handle = self
+            .diagnostics
+            .measure(ConnectionPhase::Handshake, async {
+                timeout(handshake_timeout, client::connect_stream(client_config, stream, session))
+                    .await
+                    .map_err(|_| {
+                        AppError::SshConnectionError(format!(
+                            "SSH握手超时: {}@{}:{}",
+                            self.config.username, self.config.host, self.config.port
+                        ))
+                    })?
+                    .map_err(|e| {
+                        // 主机密钥被拒绝时 check_server_key 已记录了失败详情
+                        if let Some(failure) = host_key_failure
+                            .lock()
+                            .ok()
+                            .and_then(|mut slot| slot.take())
+                        {
+                            error!(error = %e, failure = %failure, "SSH主机密钥验证失败");
+                            return AppError::SshHostKeyVerificationError(failure);
+                        }
+                        error!(error = %e, "SSH连接失败");
+                        AppError::SshConnectionError(format!("SSH连接失败: {}", e))
+                    })
+            })
+            .await?;
+        self.diagnostics
+            .note(|| "SSH handshake completed".to_string());
+        Ok(handle)
+    }
+
+    /// 使用配置的凭据认证
+    async fn authenticate(&self, handle: &mut client::Handle<SSHSession>) -> Result<(), AppError> {
+        self.diagnostics
+            .measure(ConnectionPhase::Auth, async {
+                let auth_result = match Self::convert_auth(&self.config.auth) {
+                    InternalSshAuth::Password(password) => {
+                        self.diagnostics.note(|| {
+                            format!("Authenticating as {} with password", self.config.username)
+                        });
+                        handle
+                            .authenticate_password(self.config.username.clone(), &password)
+                            .await
+                    }
+                    InternalSshAuth::Key {
+                        private_key,
+                        passphrase,
+                    } => {
+                        let key = decode_secret_key(&private_key, passphrase.as_deref()).map_err(
+                            |e| {
+                                error!(error = %e, "加载SSH私钥失败");
+                                AppError::SshConnectionError(format!("加载私钥失败: {}", e))
+                            },
+                        )?;
+                        self.diagnostics.note(|| {
+                            format!(
+                                "Authenticating as {} with {} public key",
+                                self.config.username,
+                                key.algorithm().as_str()
+                            )
+                        });
+
+                        handle
+                            .authenticate_publickey(
+                                self.config.username.clone(),
+                                PrivateKeyWithHashAlg::new(Arc::new(key), None),
+                            )
+                            .await
+                    }
+                };
+
+                if !auth_result.map(|result| result.success()).unwrap_or(false) {
+                    error!("SSH认证失败");
+                    self.diagnostics
+                        .note(|| "Authentication rejected by server".to_string());
+                    return Err(AppError::SshAuthenticationError("SSH认证失败".to_string()));
+                }
+                self.diagnostics
+                    .note(|| "Authentication succeeded".to_string());
+                Ok(())
+            })
+            .await
+    }
+
     /// 将 common 的 SshAuth 转换为内部使用的认证方式
     fn convert_auth(auth: &SshAuth) -> InternalSshAuth {
         match auth {
@@ -96,79 +327,16 @@ impl SSHClient {
             "Executing SSH command"
         );
 
-        // 创建 SSH 客户端配置
-        let client_config = Arc::new(Config {
-            preferred: russh::Preferred::default(),
-            ..Default::default()
-        });
-
         // 建立连接
-        let overall_timeout =
-            std::cmp::min(self.config.connect_timeout_secs, self.config.handshake_timeout_secs);
-        let session = self.create_session();
-
-        let mut handle = timeout(
-            Duration::from_secs(overall_timeout),
-            client::connect(client_config, (self.config.host.clone(), self.config.port), session),
-        )
-        .await
-        .map_err(|_| {
-            if self.config.connect_timeout_secs <= self.config.handshake_timeout_secs {
-                AppError::SshConnectionError(format!(
-                    "TCP连接超时: {}@{}",
-                    self.config.host, self.config.port
-                ))
-            } else {
-                AppError::SshConnectionError(format!(
-                    "SSH握手超时: {}@{}:{}",
-                    self.config.username, self.config.host, self.config.port
-                ))
-            }
-        })?
-        .map_err(|e| {
-            error!(error = %e, "SSH连接失败");
-            if e.to_string().contains("Host key") || e.to_string().contains("fingerprint") {
-                AppError::SshConnectionError(format!("主机密钥验证失败: {}", e))
-            } else {
-                AppError::SshConnectionError(format!("SSH连接失败: {}", e))
-            }
-        })?;
+        let mut handle = self.connect().await?;
 
         // 认证
-        let auth = Self::convert_auth(&self.config.auth);
-        let auth_result = match auth {
-            InternalSshAuth::Password(password) => {
-                handle
-                    .authenticate_password(self.config.username.clone(), &password)
-                    .await
-            }
-            InternalSshAuth::Key {
-                private_key,
-                passphrase,
-            } => {
-                // 使用 load_secret_key 加载私钥
-                let key = decode_secret_key(&private_key, passphrase.as_deref()).map_err(|e| {
-                    error!(error = %e, "加载SSH私钥失败");
-                    AppError::SshConnectionError(format!("加载私钥失败: {}", e))
-                })?;
-
-                handle
-                    .authenticate_publickey(
-                        self.config.username.clone(),
-                        PrivateKeyWithHashAlg::new(Arc::new(key), None),
-                    )
-                    .await
-            }
-        };
-
-        if !auth_result.map(|result| result.success()).unwrap_or(false) {
-            error!("SSH认证失败");
-            return Err(AppError::SshAuthenticationError("SSH认证失败".to_string()));
-        }
+        self.authenticate(&mut handle).await?;
 
         info!("SSH认证成功，准备执行命令");
 
         // 执行命令
+        self.diagnostics.begin(ConnectionPhase::Exec);
         let mut channel = handle.channel_open_session().await.map_err(|e| {
             error!(error = %e, "打开SSH通道失败");
             AppError::SshConnectionError(format!("打开SSH通道失败: {}", e))
@@ -179,8 +347,7 @@ impl SSHClient {
             AppError::SshExecutionError(format!("执行命令失败: {}", e))
         })?;
 
-        let mut stdout = Vec::new();
-        let mut stderr = Vec::new();
+        let mut output = OutputCapture::new(self.config.max_output_bytes);
         let mut exit_code = 0;
 
         // 读取输出
@@ -190,14 +357,18 @@ impl SSHClient {
             let msg = timeout(command_timeout, channel.wait()).await;
 
             match msg {
-                Ok(Some(ChannelMsg::Data { ref data })) => {
-                    stdout.extend_from_slice(data);
+                Ok(Some(ChannelMsg::Data { ref data })) if output.push_stdout(data) => {
+                    self.abort_oversized(&channel, &output).await;
+                    exit_code = -1;
+                    break;
                 }
-                Ok(Some(ChannelMsg::ExtendedData { ref data, ext })) => {
-                    if ext == 1 {
-                        // SSH_EXTENDED_DATA_STDERR
-                        stderr.extend_from_slice(data);
-                    }
+                // SSH_EXTENDED_DATA_STDERR
+                Ok(Some(ChannelMsg::ExtendedData { ref data, ext: 1 }))
+                    if output.push_stderr(data) =>
+                {
+                    self.abort_oversized(&channel, &output).await;
+                    exit_code = -1;
+                    break;
                 }
                 Ok(Some(ChannelMsg::ExitStatus { exit_status })) => {
                     exit_code = exit_status as i32;
@@ -226,23 +397,17 @@ impl SSHClient {
 
         let duration_secs = start_time.elapsed().as_secs_f64();
         let timed_out = exit_code == 124;
+        self.diagnostics.end(!timed_out);
 
         info!(
             host = %self.config.host,
             exit_code = exit_code,
             duration_secs = duration_secs,
-            stdout_len = stdout.len(),
-            stderr_len = stderr.len(),
+            output_bytes = output.total_bytes(),
             "Command executed"
         );
 
-        Ok(ExecutionResult {
-            exit_code,
-            stdout: String::from_utf8_lossy(&stdout).to_string(),
-            stderr: String::from_utf8_lossy(&stderr).to_string(),
-            duration_secs,
-            timed_out,
-        })
+        Ok(self.build_result(exit_code, &output, duration_secs, timed_out))
     }
 
     /// 执行命令并支持增量输出推送
@@ -261,78 +426,16 @@ impl SSHClient {
             "Executing SSH command with progress"
         );
 
-        // 创建 SSH 客户端配置
-        let client_config = Arc::new(Config {
-            preferred: russh::Preferred::default(),
-            ..Default::default()
-        });
-
         // 建立连接
-        let overall_timeout =
-            std::cmp::min(self.config.connect_timeout_secs, self.config.handshake_timeout_secs);
-        let session = self.create_session();
-
-        let mut handle = timeout(
-            Duration::from_secs(overall_timeout),
-            client::connect(client_config, (self.config.host.clone(), self.config.port), session),
-        )
-        .await
-        .map_err(|_| {
-            if self.config.connect_timeout_secs <= self.config.handshake_timeout_secs {
-                AppError::SshConnectionError(format!(
-                    "TCP连接超时: {}@{}",
-                    self.config.host, self.config.port
-                ))
-            } else {
-                AppError::SshConnectionError(format!(
-                    "SSH握手超时: {}@{}:{}",
-                    self.config.username, self.config.host, self.config.port
-                ))
-            }
-        })?
-        .map_err(|e| {
-            error!(error = %e, "SSH连接失败");
-            if e.to_string().contains("Host key") || e.to_string().contains("fingerprint") {
-                AppError::SshConnectionError(format!("主机密钥验证失败: {}", e))
-            } else {
-                AppError::SshConnectionError(format!("SSH连接失败: {}", e))
-            }
-        })?;
+        let mut handle = self.connect().await?;
 
         // 认证
-        let auth = Self::convert_auth(&self.config.auth);
-        let auth_result = match auth {
-            InternalSshAuth::Password(password) => {
-                handle
-                    .authenticate_password(self.config.username.clone(), &password)
-                    .await
-            }
-            InternalSshAuth::Key {
-                private_key,
-                passphrase,
-            } => {
-                let key = decode_secret_key(&private_key, passphrase.as_deref()).map_err(|e| {
-                    error!(error = %e, "加载SSH私钥失败");
-                    AppError::SshConnectionError(format!("加载私钥失败: {}", e))
-                })?;
-
-                handle
-                    .authenticate_publickey(
-                        self.config.username.clone(),
-                        PrivateKeyWithHashAlg::new(Arc::new(key), None),
-                    )
-                    .await
-            }
-        };
-
-        if !auth_result.map(|result| result.success()).unwrap_or(false) {
-            error!("SSH认证失败");
-            return Err(AppError::SshAuthenticationError("SSH认证失败".to_string()));
-        }
+        self.authenticate(&mut handle).await?;
 
         info!("SSH认证成功，准备执行命令");
 
         // 执行命令
+        self.diagnostics.begin(ConnectionPhase::Exec);
         let mut channel = handle.channel_open_session().await.map_err(|e| {
             error!(error = %e, "打开SSH通道失败");
             AppError::SshConnectionError(format!("打开SSH通道失败: {}", e))
@@ -343,8 +446,7 @@ impl SSHClient {
             AppError::SshExecutionError(format!("执行命令失败: {}", e))
         })?;
 
-        let mut stdout = Vec::new();
-        let mut stderr = Vec::new();
+        let mut output = OutputCapture::new(self.config.max_output_bytes);
         let mut exit_code = 0;
         let mut last_callback_time = std::time::Instant::now();
         let callback_interval = Duration::from_millis(500); // 每500ms推送一次
@@ -357,22 +459,30 @@ impl SSHClient {
 
             match msg {
                 Ok(Some(ChannelMsg::Data { ref data })) => {
-                    stdout.extend_from_slice(data);
+                    if output.push_stdout(data) {
+                        self.abort_oversized(&channel, &output).await;
+                        exit_code = -1;
+                        break;
+                    }
 
                     // 增量推送输出
                     if let Some(ref callback) = progress_callback {
                         let now = std::time::Instant::now();
                         if now.duration_since(last_callback_time) >= callback_interval {
-                            let output = String::from_utf8_lossy(&stdout).to_string();
-                            callback(output, false);
+                            let stdout = output.stdout();
+                            let encoding =
+                                resolve_encoding(self.config.output_encoding, &[&stdout]);
+                            callback(decode(&stdout, encoding), false);
                             last_callback_time = now;
                         }
                     }
                 }
-                Ok(Some(ChannelMsg::ExtendedData { ref data, ext })) => {
-                    if ext == 1 {
-                        stderr.extend_from_slice(data);
-                    }
+                Ok(Some(ChannelMsg::ExtendedData { ref data, ext: 1 }))
+                    if output.push_stderr(data) =>
+                {
+                    self.abort_oversized(&channel, &output).await;
+                    exit_code = -1;
+                    break;
                 }
                 Ok(Some(ChannelMsg::ExitStatus { exit_status })) => {
                     exit_code = exit_status as i32;
@@ -401,19 +511,18 @@ impl SSHClient {
 
         let duration_secs = start_time.elapsed().as_secs_f64();
         let timed_out = exit_code == 124;
+        self.diagnostics.end(!timed_out);
 
         // 最终输出推送（标记为完成）
         if let Some(ref callback) = progress_callback {
-            let final_output = if stderr.is_empty() {
-                String::from_utf8_lossy(&stdout).to_string()
-            } else if stdout.is_empty() {
-                String::from_utf8_lossy(&stderr).to_string()
+            let decoded =
+                decode_output(self.config.output_encoding, &output.stdout(), &output.stderr());
+            let final_output = if decoded.stderr.is_empty() {
+                decoded.stdout
+            } else if decoded.stdout.is_empty() {
+                decoded.stderr
             } else {
-                format!(
-                    "{}\n{}",
-                    String::from_utf8_lossy(&stdout),
-                    String::from_utf8_lossy(&stderr)
-                )
+                format!("{}\n{}", decoded.stdout, decoded.stderr)
             };
             callback(final_output, true);
         }
@@ -422,18 +531,11 @@ impl SSHClient {
             host = %self.config.host,
             exit_code = exit_code,
             duration_secs = duration_secs,
-            stdout_len = stdout.len(),
-            stderr_len = stderr.len(),
+            output_bytes = output.total_bytes(),
             "Command executed with progress"
         );
 
-        Ok(ExecutionResult {
-            exit_code,
-            stdout: String::from_utf8_lossy(&stdout).to_string(),
-            stderr: String::from_utf8_lossy(&stderr).to_string(),
-            duration_secs,
-            timed_out,
-        })
+        Ok(self.build_result(exit_code, &output, duration_secs, timed_out))
     }
 
     /// 执行脚本（通过上传临时脚本文件）
@@ -452,82 +554,16 @@ impl SSHClient {
             "Executing SSH script"
         );
 
-        // 创建 SSH 客户端配置
-        let client_config = Arc::new(Config {
-            preferred: russh::Preferred::default(),
-            ..Default::default()
-        });
-
         // 建立连接
-        let overall_timeout =
-            std::cmp::min(self.config.connect_timeout_secs, self.config.handshake_timeout_secs);
-        let session = self.create_session();
-
-        let mut handle = timeout(
-            Duration::from_secs(overall_timeout),
-            client::connect(client_config, (self.config.host.clone(), self.config.port), session),
-        )
-        .await
-        .map_err(|_| {
-            if self.config.connect_timeout_secs <= self.config.handshake_timeout_secs {
-                AppError::SshConnectionError(format!(
-                    "TCP连接超时: {}@{}",
-                    self.config.host, self.config.port
-                ))
-            } else {
-                AppError::SshConnectionError(format!(
-                    "SSH握手超时: {}@{}:{}",
-                    self.config.username, self.config.host, self.config.port
-                ))
-            }
-        })?
-        .map_err(|e| {
-            error!(error = %e, "SSH连接失败");
-            if e.to_string().contains("Host key") || e.to_string().contains("fingerprint") {
-                AppError::SshConnectionError(format!("主机密钥验证失败: {}", e))
-            } else {
-                AppError::SshConnectionError(format!("SSH连接失败: {}", e))
-            }
-        })?;
+        let mut handle = self.connect().await?;
 
         // 认证
-        let auth = Self::convert_auth(&self.config.auth);
-        let auth_result = match auth {
-            InternalSshAuth::Password(password) => {
-                handle
-                    .authenticate_password(self.config.username.clone(), &password)
-                    .await
-            }
-            InternalSshAuth::Key {
-                private_key,
-                passphrase,
-            } => {
-                let key = decode_secret_key(&private_key, passphrase.as_deref()).map_err(|e| {
-                    error!(error = %e, "加载SSH私钥失败");
-                    AppError::SshConnectionError(format!("加载私钥失败: {}", e))
-                })?;
-
-                handle
-                    .authenticate_publickey(
-                        self.config.username.clone(),
-                        PrivateKeyWithHashAlg::new(Arc::new(key), None),
-                    )
-                    .await
-            }
-        };
-
-        if !auth_result.map(|result| result.success()).unwrap_or(false) {
-            error!("SSH认证失败");
-            return Err(AppError::SshAuthenticationError("SSH认证失败".to_string()));
-        }
+        self.authenticate(&mut handle).await?;
 
         // 生成临时脚本文件路径
-        let temp_script_path = if let Some(path) = script_path {
-            path.to_string()
-        } else {
-            // 使用 /tmp 目录和随机名称
-            format!("/tmp/ops_script_{}.sh", uuid::Uuid::new_v4().to_string().replace("-", ""))
-        };
+        let temp_script_path = script_path
+            .map(str::to_string)
+            .unwrap_or_else(Self::temp_script_path);
 
         // 创建脚本执行命令（包含上传和执行）
         // 使用 base64 编码脚本内容以避免转义问题
@@ -538,6 +574,7 @@ impl SSHClient {
         );
 
         // 执行命令
+        self.diagnostics.begin(ConnectionPhase::Exec);
         let mut channel = handle.channel_open_session().await.map_err(|e| {
             error!(error = %e, "打开SSH通道失败");
             AppError::SshConnectionError(format!("打开SSH通道失败: {}", e))
@@ -548,24 +585,198 @@ impl SSHClient {
             AppError::SshExecutionError(format!("执行脚本失败: {}", e))
         })?;
 
-        let mut stdout = Vec::new();
-        let mut stderr = Vec::new();
-        let mut exit_code = 0;
-
         // 读取输出
         let command_timeout = Duration::from_secs(self.config.command_timeout_secs);
+        let (output, exit_code) = self.read_script_output(&mut channel, command_timeout).await;
+
+        // 关闭通道和连接
+        let _ = channel.close().await;
+        let _ = handle
+            .disconnect(russh::Disconnect::ByApplication, "", "")
+            .await;
+
+        let duration_secs = start_time.elapsed().as_secs_f64();
+        let timed_out = exit_code == 124;
+        self.diagnostics.end(!timed_out);
+
+        info!(
+            host = %self.config.host,
+            exit_code = exit_code,
+            duration_secs = duration_secs,
+            output_bytes = output.total_bytes(),
+            "Script executed"
+        );
+
+        Ok(self.build_result(exit_code, &output, duration_secs, timed_out))
+    }
+
+    /// 执行本地脚本文件（大脚本或二进制内容）
+    ///
+    /// 文件经 SFTP 分块上传到目标主机后执行，不经过命令行、不整体读入内存；
+    /// 上传整体受命令超时约束
+    pub async fn execute_script_file(
+        &self,
+        local_path: &Path,
+        script_path: Option<&str>,
+    ) -> Result<ExecutionResult, AppError> {
+        let start_time = std::time::Instant::now();
+        let script_size = tokio::fs::metadata(local_path)
+            .await
+            .map_err(|e| AppError::SshExecutionError(format!("读取本地脚本文件失败: {}", e)))?
+            .len();
+
+        debug!(
+            host = %self.config.host,
+            port = %self.config.port,
+            user = %self.config.username,
+            script_size = script_size,
+            "Executing SSH script file"
+        );
+
+        let mut handle = self.connect().await?;
+        self.authenticate(&mut handle).await?;
+
+        let remote_path = script_path
+            .map(str::to_string)
+            .unwrap_or_else(Self::temp_script_path);
+        let command_timeout = Duration::from_secs(self.config.command_timeout_secs);
+
+        self.diagnostics.begin(ConnectionPhase::Exec);
+        let upload =
+            timeout(command_timeout, Self::upload_file(&handle, local_path, &remote_path)).await;
+        let upload = match upload {
+            Ok(result) => result,
+            Err(_) => {
+                warn!(remote_path = %remote_path, "脚本文件上传超时");
+                let _ = handle
+                    .disconnect(russh::Disconnect::ByApplication, "", "")
+                    .await;
+                self.diagnostics.end(false);
+                let duration_secs = start_time.elapsed().as_secs_f64();
+                let output = OutputCapture::new(self.config.max_output_bytes);
+                return Ok(self.build_result(124, &output, duration_secs, true));
+            }
+        };
+        if let Err(e) = upload {
+            let _ = handle
+                .disconnect(russh::Disconnect::ByApplication, "", "")
+                .await;
+            self.diagnostics.end(false);
+            return Err(e);
+        }
+
+        let mut channel = handle.channel_open_session().await.map_err(|e| {
+            error!(error = %e, "打开SSH通道失败");
+            AppError::SshConnectionError(format!("打开SSH通道失败: {}", e))
+        })?;
+        let command =
+            format!("chmod +x '{}' && sh '{}'; rm -f '{}'", remote_path, remote_path, remote_path);
+        channel.exec(true, command.as_str()).await.map_err(|e| {
+            error!(error = %e, "执行脚本失败");
+            AppError::SshExecutionError(format!("执行脚本失败: {}", e))
+        })?;
+
+        let (output, exit_code) = self.read_script_output(&mut channel, command_timeout).await;
+
+        let _ = channel.close().await;
+        let _ = handle
+            .disconnect(russh::Disconnect::ByApplication, "", "")
+            .await;
+
+        let duration_secs = start_time.elapsed().as_secs_f64();
+        let timed_out = exit_code == 124;
+        self.diagnostics.end(!timed_out);
+
+        info!(
+            host = %self.config.host,
+            exit_code = exit_code,
+            duration_secs = duration_secs,
+            script_size = script_size,
+            output_bytes = output.total_bytes(),
+            "Script file executed"
+        );
+
+        Ok(self.build_result(exit_code, &output, duration_secs, timed_out))
+    }
+
+    /// 目标主机上的临时脚本路径（/tmp 目录和随机名称）
+    fn temp_script_path() -> String {
+        format!("/tmp/ops_script_{}.sh", uuid::Uuid::new_v4().simple())
+    }
+
+    /// 经 SFTP 子系统分块上传本地文件，返回上传的字节数
+    async fn upload_file(
+        handle: &client::Handle<SSHSession>,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<u64, AppError> {
+        let sftp_err = |e: &dyn std::fmt::Display| {
+            error!(error = %e, remote_path = %remote_path, "SFTP 上传失败");
+            AppError::SshExecutionError(format!("SFTP 上传失败: {}", e))
+        };
+
+        let channel = handle.channel_open_session().await.map_err(|e| {
+            error!(error = %e, "打开SSH通道失败");
+            AppError::SshConnectionError(format!("打开SSH通道失败: {}", e))
+        })?;
+        channel
+            .request_subsystem(true, "sftp")
+            .await
+            .map_err(|e| sftp_err(&e))?;
+        let sftp = SftpSession::new(channel.into_stream())
+            .await
+            .map_err(|e| sftp_err(&e))?;
+
+        let mut local = tokio::fs::File::open(local_path)
+            .await
+            .map_err(|e| sftp_err(&e))?;
+        let mut remote = sftp.create(remote_path).await.map_err(|e| sftp_err(&e))?;
+        let mut buf = vec![0u8; SFTP_CHUNK_SIZE];
+        let mut uploaded = 0u64;
+        loop {
+            let n = local.read(&mut buf).await.map_err(|e| sftp_err(&e))?;
+            if n == 0 {
+                break;
+            }
+            remote
+                .write_all(&buf[..n])
+                .await
+                .map_err(|e| sftp_err(&e))?;
+            uploaded += n as u64;
+        }
+        remote.shutdown().await.map_err(|e| sftp_err(&e))?;
+        let _ = sftp.close().await;
+
+        debug!(remote_path = %remote_path, uploaded, "Script file uploaded over SFTP");
+        Ok(uploaded)
+    }
+
+    /// 读取脚本执行通道的输出，返回 (输出, 退出码)
+    ///
+    /// 超时退出码为 124；输出超过上限时终止脚本，退出码为 -1
+    async fn read_script_output(
+        &self,
+        channel: &mut russh::Channel<client::Msg>,
+        command_timeout: Duration,
+    ) -> (OutputCapture, i32) {
+        let mut output = OutputCapture::new(self.config.max_output_bytes);
+        let mut exit_code = 0;
 
         loop {
             let msg = timeout(command_timeout, channel.wait()).await;
 
             match msg {
-                Ok(Some(ChannelMsg::Data { ref data })) => {
-                    stdout.extend_from_slice(data);
+                Ok(Some(ChannelMsg::Data { ref data })) if output.push_stdout(data) => {
+                    self.abort_oversized(channel, &output).await;
+                    exit_code = -1;
+                    break;
                 }
-                Ok(Some(ChannelMsg::ExtendedData { ref data, ext })) => {
-                    if ext == 1 {
-                        stderr.extend_from_slice(data);
-                    }
+                Ok(Some(ChannelMsg::ExtendedData { ref data, ext: 1 }))
+                    if output.push_stderr(data) =>
+                {
+                    self.abort_oversized(channel, &output).await;
+                    exit_code = -1;
+                    break;
                 }
                 Ok(Some(ChannelMsg::ExitStatus { exit_status })) => {
                     exit_code = exit_status as i32;
@@ -586,31 +797,7 @@ impl SSHClient {
             }
         }
 
-        // 关闭通道和连接
-        let _ = channel.close().await;
-        let _ = handle
-            .disconnect(russh::Disconnect::ByApplication, "", "")
-            .await;
-
-        let duration_secs = start_time.elapsed().as_secs_f64();
-        let timed_out = exit_code == 124;
-
-        info!(
-            host = %self.config.host,
-            exit_code = exit_code,
-            duration_secs = duration_secs,
-            stdout_len = stdout.len(),
-            stderr_len = stderr.len(),
-            "Script executed"
-        );
-
-        Ok(ExecutionResult {
-            exit_code,
-            stdout: String::from_utf8_lossy(&stdout).to_string(),
-            stderr: String::from_utf8_lossy(&stderr).to_string(),
-            duration_secs,
-            timed_out,
-        })
+        (output, exit_code)
     }
 }
 
@@ -620,6 +807,12 @@ struct SSHSession {
     known_hosts: Option<std::collections::HashMap<String, String>>,
     host: String,
     port: u16,
+    /// 主机密钥被拒绝时的失败详情（连接失败后由 SSHClient 取出）
+    host_key_failure: Arc<std::sync::Mutex<Option<Box<HostKeyFailure>>>>,
+    /// 服务器提供的主机密钥（验证前记录，供握手探测使用）
+    presented_key: Arc<std::sync::Mutex<Option<PresentedHostKey>>>,
+    /// 详细追踪时记录服务端主机密钥
+    diagnostics: DiagnosticsCollector,
 }
 
 impl client::Handler for SSHSession {
@@ -629,83 +822,65 @@ impl client::Handler for SSHSession {
         &mut self,
         server_public_key: &PublicKey,
     ) -> impl std::future::Future<Output = Result<bool, Self::Error>> + Send {
-        let verification_mode = self.verification_mode.clone();
-        let known_hosts = self.known_hosts.clone();
-        let host = self.host.clone();
-        let port = self.port;
+        let host_key = format!("{}:{}", self.host, self.port);
+        let key_type = server_public_key.algorithm().as_str().to_string();
         let key_data = server_public_key.public_key_base64();
+        if let Ok(mut slot) = self.presented_key.lock() {
+            *slot = Some(PresentedHostKey {
+                key_type: key_type.clone(),
+                public_key: key_data.clone(),
+                fingerprint: fingerprint(&key_data).unwrap_or_default(),
+            });
+        }
+        self.diagnostics.note(|| {
+            format!(
+                "Server host key {} {} (verification: {:?})",
+                key_type,
+                fingerprint(&key_data).unwrap_or_default(),
+                self.verification_mode
+            )
+        });
 
-        async move {
-            match verification_mode {
-                HostKeyVerification::Disabled => {
-                    warn!(
-                        host = %host,
-                        port = port,
-                        "Host key verification DISABLED - accepting all keys"
-                    );
-                    Ok(true)
-                }
-                HostKeyVerification::Accept => {
-                    let mut hasher = sha2::Sha256::new();
-                    hasher.update(key_data.as_bytes());
-                    let fingerprint = hex::encode(hasher.finalize());
-                    let host_key = format!("{}:{}", host, port);
-
-                    if let Some(known_hosts) = &known_hosts {
-                        if let Some(stored_fingerprint) = known_hosts.get(&host_key) {
-                            if stored_fingerprint == &fingerprint {
-                                debug!(host = %host_key, "Host key verified");
-                                return Ok(true);
-                            }
-
-                            error!(
-                                host = %host_key,
-                                expected = %stored_fingerprint,
-                                actual = %fingerprint,
-                                "Host key mismatch - POSSIBLE SECURITY BREACH"
-                            );
-                            return Ok(false);
-                        }
-                    }
+        let result = verify_host_key(
+            &self.verification_mode,
+            self.known_hosts.as_ref(),
+            &self.host,
+            self.port,
+            &key_type,
+            &key_data,
+        );
 
-                    info!(
+        let accepted = match result {
+            Ok(()) => {
+                match self.verification_mode {
+                    HostKeyVerification::Disabled => warn!(
                         host = %host_key,
-                        fingerprint = %fingerprint,
-                        "First time connecting - accepting host key"
-                    );
-                    Ok(true)
-                }
-                HostKeyVerification::Strict => {
-                    let mut hasher = sha2::Sha256::new();
-                    hasher.update(key_data.as_bytes());
-                    let fingerprint = hex::encode(hasher.finalize());
-                    let host_key = format!("{}:{}", host, port);
-
-                    if let Some(known_hosts) = &known_hosts {
-                        if let Some(stored_fingerprint) = known_hosts.get(&host_key) {
-                            if stored_fingerprint == &fingerprint {
-                                debug!(host = %host_key, "Host key verified (strict mode)");
-                                return Ok(true);
-                            }
-
-                            error!(
-                                host = %host_key,
-                                expected = %stored_fingerprint,
-                                actual = %fingerprint,
-                                "Host key mismatch - REJECTING CONNECTION"
-                            );
-                            return Ok(false);
-                        }
-                    }
-
-                    error!(
+                        "Host key verification DISABLED - accepting all keys"
+                    ),
+                    _ => debug!(
                         host = %host_key,
-                        "Unknown host in strict mode - rejecting connection"
-                    );
-                    Ok(false)
+                        fingerprint = %fingerprint(&key_data).unwrap_or_default(),
+                        "Host key accepted"
+                    ),
                 }
+                true
             }
-        }
+            Err(failure) => {
+                error!(
+                    host = %host_key,
+                    kind = failure.kind.as_str(),
+                    expected = ?failure.expected_fingerprint,
+                    actual = %failure.presented_fingerprint,
+                    "Host key verification failed - REJECTING CONNECTION"
+                );
+                if let Ok(mut slot) = self.host_key_failure.lock() {
+                    *slot = Some(failure);
+                }
+                false
+            }
+        };
+
+        std::future::ready(Ok(accepted))
     }
 }
 
@@ -0,0 +1,206 @@
+//! SSH 主机密钥指纹与验证失败报告
+//!
+//! 指纹统一使用 OpenSSH 格式（`SHA256:` + 无填充 base64，基于解码后的公钥数据），
+//! 与 `ssh-keygen -lf` 的输出一致，便于运维人员带外核对。
+//! 早期版本存储的十六进制指纹仍可用于匹配。
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use super::HostKeyVerification;
+
+/// 主机密钥验证失败类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostKeyFailureKind {
+    /// 已固定的密钥与服务器提供的密钥不一致
+    Mismatch,
+    /// 严格模式下主机没有已固定的密钥
+    Unknown,
+}
+
+impl HostKeyFailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HostKeyFailureKind::Mismatch => "mismatch",
+            HostKeyFailureKind::Unknown => "unknown",
+        }
+    }
+}
+
+/// 主机密钥验证失败详情
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostKeyFailure {
+    pub host: String,
+    pub port: u16,
+    pub kind: HostKeyFailureKind,
+    /// 已固定的指纹（未知主机时为空）
+    pub expected_fingerprint: Option<String>,
+    /// 服务器本次提供的指纹
+    pub presented_fingerprint: String,
+    /// 服务器密钥类型（如 ssh-ed25519）
+    pub key_type: String,
+    /// 服务器公钥（base64），重新固定时写入 known_hosts
+    pub public_key: String,
+}
+
+impl std::fmt::Display for HostKeyFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.expected_fingerprint {
+            Some(expected) => write!(
+                f,
+                "主机密钥不匹配 {}:{}（期望 {}，实际 {} {}）",
+                self.host, self.port, expected, self.key_type, self.presented_fingerprint
+            ),
+            None => write!(
+                f,
+                "未知主机 {}:{}（实际 {} {}）",
+                self.host, self.port, self.key_type, self.presented_fingerprint
+            ),
+        }
+    }
+}
+
+/// 服务器在握手中提供的主机密钥
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PresentedHostKey {
+    /// 服务器密钥类型（如 ssh-ed25519）
+    pub key_type: String,
+    /// 服务器公钥（base64）
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+/// 计算公钥的 OpenSSH SHA256 指纹
+///
+/// 公钥无法解码时返回 None
+pub fn fingerprint(public_key_base64: &str) -> Option<String> {
+    let blob = general_purpose::STANDARD.decode(public_key_base64).ok()?;
+    Some(format!(
+        "SHA256:{}",
+        general_purpose::STANDARD_NO_PAD.encode(Sha256::digest(&blob))
+    ))
+}
+
+/// 判断已固定的指纹是否与公钥匹配
+///
+/// 兼容早期的两种十六进制格式：解码后公钥的 SHA-256，以及 base64 字符串本身的 SHA-256
+pub fn fingerprint_matches(stored: &str, public_key_base64: &str) -> bool {
+    let stored = stored.trim();
+    if stored.starts_with("SHA256:") {
+        return fingerprint(public_key_base64).is_some_and(|fp| fp == stored);
+    }
+
+    let stored = stored.to_ascii_lowercase();
+    let legacy_string = hex::encode(Sha256::digest(public_key_base64.as_bytes()));
+    let legacy_blob = general_purpose::STANDARD
+        .decode(public_key_base64)
+        .map(|blob| hex::encode(Sha256::digest(&blob)))
+        .ok();
+    stored == legacy_string || legacy_blob.is_some_and(|fp| fp == stored)
+}
+
+/// 按验证策略校验服务器提供的主机密钥
+///
+/// `known_hosts` 的键为 `host:port`，值为已固定的指纹
+pub fn verify_host_key(
+    mode: &HostKeyVerification,
+    known_hosts: Option<&HashMap<String, String>>,
+    host: &str,
+    port: u16,
+    key_type: &str,
+    public_key_base64: &str,
+) -> Result<(), Box<HostKeyFailure>> {
+    if *mode == HostKeyVerification::Disabled {
+        return Ok(());
+    }
+
+    let presented = fingerprint(public_key_base64).unwrap_or_default();
+    let failure = |kind, expected_fingerprint| {
+        Box::new(HostKeyFailure {
+            host: host.to_string(),
+            port,
+            kind,
+            expected_fingerprint,
+            presented_fingerprint: presented.clone(),
+            key_type: key_type.to_string(),
+            public_key: public_key_base64.to_string(),
+        })
+    };
+
+    match known_hosts.and_then(|hosts| hosts.get(&format!("{}:{}", host, port))) {
+        Some(stored) if fingerprint_matches(stored, public_key_base64) => Ok(()),
+        Some(stored) => Err(failure(HostKeyFailureKind::Mismatch, Some(stored.clone()))),
+        None if *mode == HostKeyVerification::Strict => {
+            Err(failure(HostKeyFailureKind::Unknown, None))
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ssh-ed25519 公钥示例
+    const KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+    const OTHER_KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIHJ5DOtSm1xJ7uMnrDzyPXTTxV0Dt/eYcDAFBHTv9USf";
+
+    fn known(fingerprint: &str) -> HashMap<String, String> {
+        HashMap::from([("10.0.0.5:22".to_string(), fingerprint.to_string())])
+    }
+
+    #[test]
+    fn test_fingerprint_format_and_legacy_matching() {
+        let fp = fingerprint(KEY).unwrap();
+        assert!(fp.starts_with("SHA256:"));
+        assert!(!fp.ends_with('='));
+        assert!(fingerprint(KEY).is_some_and(|again| again == fp));
+        assert!(fingerprint("not base64!").is_none());
+
+        assert!(fingerprint_matches(&fp, KEY));
+        assert!(!fingerprint_matches(&fp, OTHER_KEY));
+        assert!(fingerprint_matches(&hex::encode(Sha256::digest(KEY.as_bytes())), KEY));
+        let blob = general_purpose::STANDARD.decode(KEY).unwrap();
+        assert!(fingerprint_matches(&hex::encode(Sha256::digest(&blob)).to_uppercase(), KEY));
+    }
+
+    #[test]
+    fn test_verify_host_key_reports_mismatch() {
+        let expected = fingerprint(KEY).unwrap();
+        let hosts = known(&expected);
+        let result = verify_host_key(
+            &HostKeyVerification::Accept,
+            Some(&hosts),
+            "10.0.0.5",
+            22,
+            "ssh-ed25519",
+            OTHER_KEY,
+        );
+
+        let failure = result.unwrap_err();
+        assert_eq!(failure.kind, HostKeyFailureKind::Mismatch);
+        assert_eq!(failure.expected_fingerprint.as_deref(), Some(expected.as_str()));
+        assert_eq!(failure.presented_fingerprint, fingerprint(OTHER_KEY).unwrap());
+        assert_eq!(failure.public_key, OTHER_KEY);
+        assert!(failure.to_string().contains(&expected));
+    }
+
+    #[test]
+    fn test_verify_host_key_modes() {
+        let hosts = known(&fingerprint(KEY).unwrap());
+        let verify = |mode, hosts: Option<&HashMap<String, String>>| {
+            verify_host_key(&mode, hosts, "10.0.0.5", 22, "ssh-ed25519", KEY)
+        };
+
+        assert!(verify(HostKeyVerification::Strict, Some(&hosts)).is_ok());
+        assert!(verify(HostKeyVerification::Accept, None).is_ok());
+        assert!(verify(HostKeyVerification::Disabled, None).is_ok());
+
+        let failure = verify(HostKeyVerification::Strict, None).unwrap_err();
+        assert_eq!(failure.kind, HostKeyFailureKind::Unknown);
+        assert!(failure.expected_fingerprint.is_none());
+    }
+}
@@ -1,10 +1,18 @@
 //! SSH执行模块
 //! P2 阶段：SSH连接管理和命令执行
 
+pub mod diagnostics;
+pub mod encoding;
 pub mod executor;
+pub mod host_key;
+pub mod output_limit;
 
 // 重新导出 common 的类型
 pub use common::{execution::ExecutionResult, ssh::*};
 
 // 重新导出执行器
+pub use diagnostics::{
+    ConnectionPhase, DiagnosticsCollector, PhaseListener, TaskDiagnostics, TraceNote,
+};
 pub use executor::SSHClient;
+pub use host_key::{HostKeyFailure, HostKeyFailureKind, PresentedHostKey};
@@ -0,0 +1,147 @@
+//! 命令输出大小保护
+//!
+//! stdout 与 stderr 合计超过上限后调用方停止读取并终止命令，避免超大输出耗尽内存；
+//! 每个输出流只保留开头与末尾各半个上限，中间部分以省略标记代替
+
+use std::collections::VecDeque;
+
+/// 输出流缓冲（开头 + 末尾）
+#[derive(Debug, Default)]
+struct StreamBuffer {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    omitted: u64,
+}
+
+impl StreamBuffer {
+    fn push(&mut self, data: &[u8], keep: usize) {
+        let room = keep.saturating_sub(self.head.len()).min(data.len());
+        self.head.extend_from_slice(&data[..room]);
+        self.tail.extend(&data[room..]);
+        let overflow = self.tail.len().saturating_sub(keep);
+        if overflow > 0 {
+            self.tail.drain(..overflow);
+            self.omitted += overflow as u64;
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head.clone();
+        if self.omitted > 0 {
+            bytes.extend_from_slice(
+                format!("\n... [{} bytes omitted: output limit exceeded] ...\n", self.omitted)
+                    .as_bytes(),
+            );
+        }
+        bytes.extend(self.tail.iter());
+        bytes
+    }
+}
+
+/// 受大小限制的命令输出
+#[derive(Debug)]
+pub struct OutputCapture {
+    /// 输出上限（字节，0 表示不限制）
+    max_bytes: u64,
+    total_bytes: u64,
+    stdout: StreamBuffer,
+    stderr: StreamBuffer,
+}
+
+impl OutputCapture {
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            total_bytes: 0,
+            stdout: StreamBuffer::default(),
+            stderr: StreamBuffer::default(),
+        }
+    }
+
+    /// 追加标准输出，返回是否已超过上限
+    pub fn push_stdout(&mut self, data: &[u8]) -> bool {
+        let keep = self.keep();
+        self.stdout.push(data, keep);
+        self.record(data.len())
+    }
+
+    /// 追加标准错误，返回是否已超过上限
+    pub fn push_stderr(&mut self, data: &[u8]) -> bool {
+        let keep = self.keep();
+        self.stderr.push(data, keep);
+        self.record(data.len())
+    }
+
+    /// 是否超过上限
+    pub fn exceeded(&self) -> bool {
+        self.max_bytes > 0 && self.total_bytes > self.max_bytes
+    }
+
+    /// 已读取的输出字节数（含省略部分）
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// 保留的标准输出（超过上限时为开头 + 省略标记 + 末尾）
+    pub fn stdout(&self) -> Vec<u8> {
+        self.stdout.to_bytes()
+    }
+
+    /// 保留的标准错误
+    pub fn stderr(&self) -> Vec<u8> {
+        self.stderr.to_bytes()
+    }
+
+    /// 每个输出流开头与末尾各自保留的字节数
+    fn keep(&self) -> usize {
+        if self.max_bytes == 0 {
+            usize::MAX
+        } else {
+            usize::try_from(self.max_bytes / 2).unwrap_or(usize::MAX)
+        }
+    }
+
+    fn record(&mut self, len: usize) -> bool {
+        self.total_bytes += len as u64;
+        self.exceeded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_within_limit_kept_intact() {
+        let mut capture = OutputCapture::new(16);
+        assert!(!capture.push_stdout(b"hello "));
+        assert!(!capture.push_stdout(b"world"));
+        assert!(!capture.push_stderr(b"warn"));
+
+        assert!(!capture.exceeded());
+        assert_eq!(capture.stdout(), b"hello world");
+        assert_eq!(capture.stderr(), b"warn");
+    }
+
+    #[test]
+    fn test_output_over_limit_keeps_head_and_tail() {
+        let mut capture = OutputCapture::new(8);
+        assert!(!capture.push_stdout(b"abcd"));
+        assert!(capture.push_stdout(b"efghijklmn"));
+
+        assert!(capture.exceeded());
+        assert_eq!(capture.total_bytes(), 14);
+        let stdout = String::from_utf8(capture.stdout()).unwrap();
+        assert!(stdout.starts_with("abcd\n... [6 bytes omitted"));
+        assert!(stdout.ends_with("] ...\nklmn"));
+    }
+
+    #[test]
+    fn test_zero_limit_is_unbounded() {
+        let mut capture = OutputCapture::new(0);
+        let data = vec![b'x'; 4096];
+        assert!(!capture.push_stdout(&data));
+        assert!(!capture.push_stderr(&data));
+        assert_eq!(capture.stdout().len(), 4096);
+    }
+}
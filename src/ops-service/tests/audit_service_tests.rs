@@ -244,9 +244,16 @@ fn test_audit_action_names_consistency() {
         ("user.create", AuditAction::UserCreate),
         ("user.update", AuditAction::UserUpdate),
         ("user.delete", AuditAction::UserDelete),
+        ("user.restore", AuditAction::UserRestore),
         ("user.login", AuditAction::UserLogin),
         ("user.logout", AuditAction::UserLogout),
         ("user.password_change", AuditAction::UserPasswordChange),
+        ("user.session_revoke", AuditAction::UserSessionRevoke),
+        ("user.two_factor_enable", AuditAction::UserTwoFactorEnable),
+        ("user.two_factor_disable", AuditAction::UserTwoFactorDisable),
+        ("user.two_factor_reset", AuditAction::UserTwoFactorReset),
+        ("user.two_factor_recovery_codes", AuditAction::UserTwoFactorRecoveryCodes),
+        ("user.two_factor_step_up", AuditAction::UserTwoFactorStepUp),
         // 资产相关
         ("asset.group.create", AuditAction::AssetGroupCreate),
         ("asset.group.update", AuditAction::AssetGroupUpdate),
@@ -254,16 +261,44 @@ fn test_audit_action_names_consistency() {
         ("asset.host.create", AuditAction::HostCreate),
         ("asset.host.update", AuditAction::HostUpdate),
         ("asset.host.delete", AuditAction::HostDelete),
+        ("asset.host.restore", AuditAction::HostRestore),
+        ("asset.host.maintenance_set", AuditAction::HostMaintenanceSet),
+        ("asset.host.maintenance_clear", AuditAction::HostMaintenanceClear),
+        ("asset.host.credential_rotation_start", AuditAction::HostCredentialRotationStart),
+        ("asset.host.credential_rotate", AuditAction::HostCredentialRotate),
+        ("asset.host.credential_update", AuditAction::HostCredentialUpdate),
+        ("asset.host.environment_change", AuditAction::HostEnvironmentChange),
+        ("asset.advisory.import", AuditAction::AdvisoryImport),
         // 作业相关
         ("job.create", AuditAction::JobCreate),
         ("job.cancel", AuditAction::JobCancel),
         ("job.retry", AuditAction::JobRetry),
+        ("job.task_cancel", AuditAction::JobTaskCancel),
+        ("job.task_skip", AuditAction::JobTaskSkip),
+        ("job.task_rerun", AuditAction::JobTaskRerun),
         ("job.execute", AuditAction::JobExecute),
         ("job.output_view", AuditAction::JobOutputView),
+        ("job.evidence_download", AuditAction::JobEvidenceDownload),
+        ("job.budget_exceeded", AuditAction::JobBudgetExceeded),
+        ("job.dispatch_paused", AuditAction::JobDispatchPaused),
+        ("job.dispatch_resume", AuditAction::JobDispatchResume),
+        ("job_tag.create", AuditAction::JobTagCreate),
+        ("job_tag.update", AuditAction::JobTagUpdate),
+        ("job_tag.delete", AuditAction::JobTagDelete),
+        ("job_baseline.set", AuditAction::JobOutputBaselineSet),
+        ("job_baseline.delete", AuditAction::JobOutputBaselineDelete),
+        ("job_template.delete", AuditAction::JobTemplateDelete),
+        ("job_template.restore", AuditAction::JobTemplateRestore),
+        ("job_template.deprecate", AuditAction::JobTemplateDeprecate),
+        ("job_template.undeprecate", AuditAction::JobTemplateUndeprecate),
+        ("environment_policy.update", AuditAction::EnvironmentPolicyUpdate),
+        ("environment_policy.delete", AuditAction::EnvironmentPolicyDelete),
         // 构建相关
         ("build.create", AuditAction::BuildCreate),
         ("build.execute", AuditAction::BuildExecute),
         ("artifact.download", AuditAction::ArtifactDownload),
+        ("artifact.promotion.request", AuditAction::ArtifactPromotionRequest),
+        ("artifact.promote", AuditAction::ArtifactPromote),
         // 权限相关
         ("role.create", AuditAction::RoleCreate),
         ("role.update", AuditAction::RoleUpdate),
@@ -275,9 +310,21 @@ fn test_audit_action_names_consistency() {
         ("approval.approve", AuditAction::ApprovalApprove),
         ("approval.reject", AuditAction::ApprovalReject),
         ("approval.cancel", AuditAction::ApprovalCancel),
+        ("approval.auto_approve", AuditAction::ApprovalAutoApprove),
         ("approval_group.create", AuditAction::ApprovalGroupCreate),
         ("approval_group.update", AuditAction::ApprovalGroupUpdate),
         ("approval_group.delete", AuditAction::ApprovalGroupDelete),
+        // Runner 配置相关
+        ("runner_config.rollout.start", AuditAction::RunnerConfigRolloutStart),
+        ("runner_config.rollout.promote", AuditAction::RunnerConfigRolloutPromote),
+        ("runner_config.rollout.rollback", AuditAction::RunnerConfigRolloutRollback),
+        // Runner 相关
+        ("runner.command", AuditAction::RunnerCommand),
+        // 查看类操作
+        ("audit.stream_subscribe", AuditAction::StreamSubscribe),
+        ("audit.stream_close", AuditAction::StreamClose),
+        // 系统管理
+        ("system.load_test_start", AuditAction::SystemLoadTestStart),
     ];
 
     for (expected, action) in all_actions {
@@ -3,8 +3,7 @@
 //! 测试应用错误类型的各种行为
 
 use axum::http::StatusCode;
-use ops_service::error::{AppError, ErrorResponse};
-use serde_json;
+use ops_service::error::{AppError, ErrorCode, ErrorResponse};
 
 // ==================== 错误状态码测试 ====================
 
@@ -229,27 +228,33 @@ fn test_from_config_error() {
 fn test_error_response_serialization() {
     let error_response = ErrorResponse {
         error: ops_service::error::ErrorDetail {
-            code: 404,
+            code: ErrorCode::ResourceNotFound,
+            status: 404,
             message: "Resource not found".to_string(),
-            request_id: "req-123".to_string(),
+            detail: Some("Job not found".to_string()),
+            correlation_id: "req-123".to_string(),
         },
     };
 
     let json = serde_json::to_string(&error_response).unwrap();
     let json_obj: serde_json::Value = serde_json::from_str(&json).unwrap();
 
-    assert_eq!(json_obj["error"]["code"], 404);
+    assert_eq!(json_obj["error"]["code"], "RESOURCE_NOT_FOUND");
+    assert_eq!(json_obj["error"]["status"], 404);
     assert_eq!(json_obj["error"]["message"], "Resource not found");
-    assert_eq!(json_obj["error"]["request_id"], "req-123");
+    assert_eq!(json_obj["error"]["detail"], "Job not found");
+    assert_eq!(json_obj["error"]["correlation_id"], "req-123");
 }
 
 #[test]
 fn test_error_response_structure() {
     let error_response = ErrorResponse {
         error: ops_service::error::ErrorDetail {
-            code: 400,
+            code: ErrorCode::BadRequest,
+            status: 400,
             message: "Bad request".to_string(),
-            request_id: "abc-123".to_string(),
+            detail: None,
+            correlation_id: "abc-123".to_string(),
         },
     };
 
@@ -258,9 +263,11 @@ fn test_error_response_structure() {
 
     assert!(json_obj.is_object());
     assert!(json_obj.get("error").is_some());
-    assert_eq!(json_obj["error"]["code"], 400);
+    assert_eq!(json_obj["error"]["code"], "BAD_REQUEST");
+    assert_eq!(json_obj["error"]["status"], 400);
     assert_eq!(json_obj["error"]["message"], "Bad request");
-    assert_eq!(json_obj["error"]["request_id"], "abc-123");
+    assert!(json_obj["error"].get("detail").is_none());
+    assert_eq!(json_obj["error"]["correlation_id"], "abc-123");
 }
 
 // ==================== 错误传播测试 ====================
@@ -310,9 +317,9 @@ fn test_error_matching() {
     let error = AppError::Unauthorized;
 
     match error {
-        AppError::Unauthorized => assert!(true),
-        AppError::Forbidden => assert!(false),
-        _ => assert!(false),
+        AppError::Unauthorized => {}
+        AppError::Forbidden => panic!("expected Unauthorized, got Forbidden"),
+        _ => panic!("expected Unauthorized"),
     }
 }
 
@@ -322,7 +329,7 @@ fn test_error_matching_with_values() {
 
     match error {
         AppError::NotFound(resource) => assert_eq!(resource, "User"),
-        _ => assert!(false),
+        _ => panic!("expected NotFound"),
     }
 }
 
@@ -333,7 +340,7 @@ fn test_error_matching_if_let() {
     if let AppError::Validation(msg) = error {
         assert_eq!(msg, "Email required");
     } else {
-        assert!(false);
+        panic!("expected Validation");
     }
 }
 
@@ -380,9 +387,30 @@ fn test_error_code_consistency() {
         let code = error.code();
         let status = error.status_code();
         assert_eq!(code, status.as_u16());
+        assert_eq!(error.error_code().status_code(), status);
     }
 }
 
+#[test]
+fn test_two_factor_required_error() {
+    let error = AppError::TwoFactorRequired("Step-up verification required".to_string());
+    assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(error.error_code(), ErrorCode::TwoFactorRequired);
+    assert_eq!(error.error_code().as_str(), "TWO_FACTOR_REQUIRED");
+    assert_eq!(error.detail(), Some("Step-up verification required".to_string()));
+}
+
+#[test]
+fn test_error_catalog_is_complete_and_unique() {
+    let catalog = ops_service::error::error_catalog();
+    assert_eq!(catalog.len(), ErrorCode::ALL.len());
+
+    let mut codes: Vec<&str> = catalog.iter().map(|e| e.code.as_str()).collect();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), catalog.len());
+}
+
 #[test]
 fn test_error_result_type() {
     type TestResult = ops_service::error::Result<String>;
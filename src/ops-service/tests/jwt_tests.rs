@@ -2,10 +2,17 @@
 //!
 //! 测试 JWT token 生成、验证和刷新功能
 
-use ops_service::auth::jwt::JwtService;
+use chrono::{Duration, Utc};
+use ops_service::auth::jwt::{Claims, JwtService};
+use ops_service::auth::KeyRing;
 use ops_service::config::{
-    AppConfig, ConcurrencyConfig, DatabaseConfig, LoggingConfig, MetricsConfig, RabbitMqConfig,
-    RunnerDockerConfig, SecurityConfig, ServerConfig, SshConfig,
+    AdvisoryConfig, AnomalyConfig, AppConfig, ApprovalConfig, ArchiveConfig,
+    ArtifactPromotionConfig, AuditConfig, BlobStoreConfig, ConcurrencyConfig, DatabaseConfig,
+    EvidenceConfig, HostKeyDriftConfig, I18nConfig, JobBudgetConfig, JobDispatchConfig,
+    JwtAlgorithm, JwtConfig, LoadTestConfig, LoggingConfig, MaintenanceConfig, MetricsConfig,
+    NetworkBackoffConfig, NotificationConfig, OutputConfig, PayloadLimitsConfig, QueryCacheConfig,
+    RabbitMqConfig, RsaKeyConfig, RunnerDockerConfig, SecurityConfig, ServerConfig,
+    SoftDeleteConfig, SshConfig, StatsConfig, TwoFactorConfig,
 };
 use secrecy::SecretString;
 use uuid::Uuid;
@@ -25,6 +32,7 @@ fn create_test_config() -> AppConfig {
             idle_timeout_secs: 600,
             max_lifetime_secs: 1800,
             auto_create_if_missing: true,
+            auto_migrate: true,
         },
         logging: LoggingConfig {
             level: "info".to_string(),
@@ -37,6 +45,7 @@ fn create_test_config() -> AppConfig {
             rate_limit_rps: 100,
             trust_proxy: true,
             allowed_ips: None,
+            ip_allowlist: Default::default(),
             password_min_length: 8,
             password_require_uppercase: true,
             password_require_digit: true,
@@ -45,6 +54,7 @@ fn create_test_config() -> AppConfig {
             login_lockout_duration_secs: 1800,
             runner_api_key: None,
             runner_webhook_hmac_secret: None,
+            credential_encryption_key: None,
             runner_webhook_max_skew_secs: 300,
             runner_webhook_nonce_ttl_secs: 600,
             login_rate_limit_max_attempts: 10,
@@ -60,6 +70,7 @@ fn create_test_config() -> AppConfig {
             command_timeout_secs: 300,
             host_key_verification: "accept".to_string(),
             known_hosts_file: None,
+            max_output_bytes: 64 * 1024 * 1024,
         },
         concurrency: ConcurrencyConfig {
             global_limit: 100,
@@ -77,9 +88,35 @@ fn create_test_config() -> AppConfig {
             runner_exchange: "ops.runner".to_string(),
             pool_size: 5,
             publish_timeout_secs: 10,
+            reconnect_initial_backoff_ms: 500,
+            reconnect_max_backoff_secs: 30,
+            unavailable_threshold_secs: 60,
         },
         runner_docker: RunnerDockerConfig::default(),
         metrics: MetricsConfig::default(),
+        output: OutputConfig::default(),
+        approval: ApprovalConfig::default(),
+        stats: StatsConfig::default(),
+        archive: ArchiveConfig::default(),
+        anomaly: AnomalyConfig::default(),
+        blob_store: BlobStoreConfig::default(),
+        evidence: EvidenceConfig::default(),
+        maintenance: MaintenanceConfig::default(),
+        audit: AuditConfig::default(),
+        job_budget: JobBudgetConfig::default(),
+        network_backoff: NetworkBackoffConfig::default(),
+        job_dispatch: JobDispatchConfig::default(),
+        advisory: AdvisoryConfig::default(),
+        artifact_promotion: ArtifactPromotionConfig::default(),
+        load_test: LoadTestConfig::default(),
+        i18n: I18nConfig::default(),
+        notifications: NotificationConfig::default(),
+        host_key_drift: HostKeyDriftConfig::default(),
+        query_cache: QueryCacheConfig::default(),
+        jwt: JwtConfig::default(),
+        two_factor: TwoFactorConfig::default(),
+        soft_delete: SoftDeleteConfig::default(),
+        payload_limits: PayloadLimitsConfig::default(),
     }
 }
 
@@ -461,3 +498,81 @@ fn test_validate_token_method() {
     assert_eq!(claims.sub, user_id.to_string());
     assert_eq!(claims.token_type, "refresh");
 }
+
+#[test]
+fn test_rotated_key_accepted_within_grace_period() {
+    let mut config = create_test_config();
+    config.jwt.rotation_interval_secs = 3600;
+    config.jwt.grace_period_secs = 7200;
+    let keys = KeyRing::from_config(&config).unwrap();
+
+    let now = Utc::now();
+    let claims = Claims {
+        sub: Uuid::new_v4().to_string(),
+        username: "testuser".to_string(),
+        token_type: "access".to_string(),
+        roles: vec![],
+        scopes: vec![],
+        iat: now.timestamp(),
+        exp: (now + Duration::days(1)).timestamp(),
+        jti: Uuid::new_v4().to_string(),
+        impersonator: None,
+        sid: None,
+    };
+    let token = keys.encode(&claims, now).unwrap();
+    let kid = jsonwebtoken::decode_header(&token).unwrap().kid.unwrap();
+    assert!(kid.starts_with("hs-3600-"));
+
+    let accepted_at = |offset_hours: i64| {
+        keys.decode::<Claims>(&token, now + Duration::hours(offset_hours))
+            .is_some()
+    };
+    assert!(accepted_at(0));
+    // 下一周期已换用新密钥，旧令牌在宽限期内仍有效
+    assert_ne!(keys.active_kid(now + Duration::hours(2)), kid);
+    assert!(accepted_at(2));
+    // 超过宽限期后拒绝
+    assert!(!accepted_at(4));
+    // 尚未生效的密钥签发的令牌同样拒绝
+    assert!(!accepted_at(-2));
+}
+
+#[test]
+fn test_retired_secret_still_validates() {
+    let old_config = create_test_config();
+    let old_service = JwtService::from_config(&old_config).unwrap();
+    let user_id = Uuid::new_v4();
+    let token = old_service
+        .generate_access_token(&user_id, "testuser", vec![], vec![])
+        .unwrap();
+
+    // 更换 jwt_secret 后，未保留旧密钥时旧令牌失效
+    let mut config = create_test_config();
+    config.security.jwt_secret =
+        SecretString::from("rotated_secret_key_32_characters_long".to_string());
+    let service = JwtService::from_config(&config).unwrap();
+    assert!(service.validate_access_token(&token).is_err());
+
+    config.jwt.retired_secrets = Some(old_config.security.jwt_secret.clone());
+    let service = JwtService::from_config(&config).unwrap();
+    assert_eq!(service.validate_access_token(&token).unwrap().sub, user_id.to_string());
+
+    // HS256 密钥不通过 JWKS 发布
+    assert!(service.jwks().keys.is_empty());
+}
+
+#[test]
+fn test_rs256_requires_active_key() {
+    let mut config = create_test_config();
+    config.jwt.algorithm = JwtAlgorithm::Rs256;
+    assert!(JwtService::from_config(&config).is_err());
+
+    config.jwt.rsa_keys.insert(
+        "k1".to_string(),
+        RsaKeyConfig {
+            private_key_file: "/nonexistent/jwt.pem".to_string(),
+            activates_at: None,
+        },
+    );
+    assert!(JwtService::from_config(&config).is_err());
+}
@@ -6,7 +6,6 @@ use ops_service::models::asset::*;
 use ops_service::models::auth::*;
 use ops_service::models::role::*;
 use ops_service::models::user::*;
-use serde_json;
 use uuid::Uuid;
 
 // ==================== User 模型测试 ====================
@@ -106,6 +105,7 @@ fn test_user_response_excludes_sensitive_data() {
         full_name: Some("Test User".to_string()),
         department: Some("Engineering".to_string()),
         must_change_password: false,
+        locale: None,
         created_at: chrono::Utc::now(),
     };
 
@@ -248,6 +248,52 @@ fn test_create_host_request_with_all_fields() {
     assert_eq!(req.tags[0], "linux");
 }
 
+#[test]
+fn test_create_host_request_connection_types() {
+    let base = r#""identifier":"app-pod","address":"10.0.0.1",
+        "group_id":"00000000-0000-0000-0000-000000000001","environment":"dev""#;
+
+    let req: CreateHostRequest = serde_json::from_str(&format!("{{{}}}", base)).unwrap();
+    assert_eq!(req.connection_type, ConnectionType::Ssh);
+    assert!(req.validate().is_ok());
+
+    // docker 目标必须指定容器，且名称不能携带命令行参数
+    let req: CreateHostRequest =
+        serde_json::from_str(&format!(r#"{{{},"connection_type":"docker"}}"#, base)).unwrap();
+    assert!(req.validate().is_err());
+    let req: CreateHostRequest = serde_json::from_str(&format!(
+        r#"{{{},"connection_type":"docker","connection_options":{{"container":"--privileged"}}}}"#,
+        base
+    ))
+    .unwrap();
+    assert!(req.validate().is_err());
+    let req: CreateHostRequest = serde_json::from_str(&format!(
+        r#"{{{},"connection_type":"docker","connection_options":{{"container":"web_1"}}}}"#,
+        base
+    ))
+    .unwrap();
+    assert!(req.validate().is_ok());
+
+    // kubernetes 目标必须指定 API Server 与 Pod
+    let req: CreateHostRequest = serde_json::from_str(&format!(
+        r#"{{{},"connection_type":"kubernetes","connection_options":{{"pod":"app-0"}}}}"#,
+        base
+    ))
+    .unwrap();
+    assert!(req.validate().is_err());
+    let req: CreateHostRequest = serde_json::from_str(&format!(
+        r#"{{{},"connection_type":"kubernetes","connection_token":"t",
+            "connection_options":{{"api_server":"https://k8s:6443","pod":"app-0"}}}}"#,
+        base
+    ))
+    .unwrap();
+    assert!(req.validate().is_ok());
+    assert_eq!(req.connection_options.unwrap().namespace(), "default");
+
+    assert!(serde_json::from_str::<ConnectionType>(r#""winrm""#).is_err());
+    assert_eq!("kubernetes".parse::<ConnectionType>(), Ok(ConnectionType::Kubernetes));
+}
+
 #[test]
 fn test_update_host_request_with_version() {
     let json = r#"{
@@ -279,6 +325,29 @@ fn test_host_list_filters() {
     assert_eq!(filters.search, Some("web".to_string()));
 }
 
+#[test]
+fn test_set_host_maintenance_request() {
+    let now = chrono::Utc::now();
+
+    let req: SetHostMaintenanceRequest =
+        serde_json::from_str(r#"{"reason":"kernel upgrade"}"#).unwrap();
+    assert!(req.until.is_none());
+    assert_eq!(req.reason, Some("kernel upgrade".to_string()));
+    assert!(req.validate(now).is_ok());
+
+    let req = SetHostMaintenanceRequest {
+        until: Some(now - chrono::Duration::minutes(1)),
+        reason: None,
+    };
+    assert!(req.validate(now).is_err());
+
+    let req = SetHostMaintenanceRequest {
+        until: Some(now + chrono::Duration::hours(2)),
+        reason: None,
+    };
+    assert!(req.validate(now).is_ok());
+}
+
 #[test]
 fn test_create_group_request() {
     let parent_id = Uuid::new_v4();
@@ -321,6 +390,49 @@ fn test_update_group_request_partial() {
     assert!(req.environment.is_none());
 }
 
+#[test]
+fn test_group_concurrency_metadata_validation() {
+    let json = r#"{
+        "name":"db-cluster",
+        "environment":"prod",
+        "concurrent_limit":2,
+        "is_production":true
+    }"#;
+    let req: CreateGroupRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(req.concurrent_limit, Some(2));
+    assert!(req.is_production);
+    assert!(req.validate().is_ok());
+
+    let req: CreateGroupRequest =
+        serde_json::from_str(r#"{"name":"bad","environment":"dev","concurrent_limit":0}"#).unwrap();
+    assert!(!req.is_production);
+    assert!(req.validate().is_err());
+
+    // 更新时 0 表示清除分组上限
+    let req: UpdateGroupRequest = serde_json::from_str(r#"{"concurrent_limit":0}"#).unwrap();
+    assert!(req.validate().is_ok());
+    let req: UpdateGroupRequest = serde_json::from_str(r#"{"concurrent_limit":-1}"#).unwrap();
+    assert!(req.validate().is_err());
+}
+
+#[test]
+fn test_group_critical_flag_deserialization() {
+    let req: CreateGroupRequest =
+        serde_json::from_str(r#"{"name":"core-db","environment":"prod","is_critical":true}"#)
+            .unwrap();
+    assert!(req.is_critical);
+
+    let req: CreateGroupRequest =
+        serde_json::from_str(r#"{"name":"web","environment":"dev"}"#).unwrap();
+    assert!(!req.is_critical);
+
+    // 更新时未提供则保持原值
+    let req: UpdateGroupRequest = serde_json::from_str(r#"{"is_critical":false}"#).unwrap();
+    assert_eq!(req.is_critical, Some(false));
+    let req: UpdateGroupRequest = serde_json::from_str(r#"{"name":"renamed"}"#).unwrap();
+    assert!(req.is_critical.is_none());
+}
+
 // ==================== 模型序列化测试 ====================
 
 #[test]
@@ -334,6 +446,7 @@ fn test_user_with_roles_serialization() {
             full_name: Some("Admin User".to_string()),
             department: None,
             must_change_password: false,
+            locale: Some("zh-CN".to_string()),
             created_at: chrono::Utc::now(),
         },
         roles: vec!["admin".to_string(), "editor".to_string()],
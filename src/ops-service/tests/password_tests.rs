@@ -4,8 +4,13 @@
 
 use ops_service::auth::password::PasswordHasher;
 use ops_service::config::{
-    AppConfig, ConcurrencyConfig, DatabaseConfig, LoggingConfig, MetricsConfig, RabbitMqConfig,
-    RunnerDockerConfig, SecurityConfig, ServerConfig, SshConfig,
+    AdvisoryConfig, AnomalyConfig, AppConfig, ApprovalConfig, ArchiveConfig,
+    ArtifactPromotionConfig, AuditConfig, BlobStoreConfig, ConcurrencyConfig, DatabaseConfig,
+    EvidenceConfig, HostKeyDriftConfig, I18nConfig, JobBudgetConfig, JobDispatchConfig, JwtConfig,
+    LoadTestConfig, LoggingConfig, MaintenanceConfig, MetricsConfig, NetworkBackoffConfig,
+    NotificationConfig, OutputConfig, PayloadLimitsConfig, QueryCacheConfig, RabbitMqConfig,
+    RunnerDockerConfig, SecurityConfig, ServerConfig, SoftDeleteConfig, SshConfig, StatsConfig,
+    TwoFactorConfig,
 };
 use secrecy::SecretString;
 
@@ -24,6 +29,7 @@ fn create_test_config() -> AppConfig {
             idle_timeout_secs: 600,
             max_lifetime_secs: 1800,
             auto_create_if_missing: true,
+            auto_migrate: true,
         },
         logging: LoggingConfig {
             level: "info".to_string(),
@@ -36,6 +42,7 @@ fn create_test_config() -> AppConfig {
             rate_limit_rps: 100,
             trust_proxy: true,
             allowed_ips: None,
+            ip_allowlist: Default::default(),
             password_min_length: 8,
             password_require_uppercase: true,
             password_require_digit: true,
@@ -44,6 +51,7 @@ fn create_test_config() -> AppConfig {
             login_lockout_duration_secs: 1800,
             runner_api_key: None,
             runner_webhook_hmac_secret: None,
+            credential_encryption_key: None,
             runner_webhook_max_skew_secs: 300,
             runner_webhook_nonce_ttl_secs: 600,
             login_rate_limit_max_attempts: 10,
@@ -59,6 +67,7 @@ fn create_test_config() -> AppConfig {
             command_timeout_secs: 300,
             host_key_verification: "accept".to_string(),
             known_hosts_file: None,
+            max_output_bytes: 64 * 1024 * 1024,
         },
         concurrency: ConcurrencyConfig {
             global_limit: 100,
@@ -76,9 +85,35 @@ fn create_test_config() -> AppConfig {
             runner_exchange: "ops.runner".to_string(),
             pool_size: 5,
             publish_timeout_secs: 10,
+            reconnect_initial_backoff_ms: 500,
+            reconnect_max_backoff_secs: 30,
+            unavailable_threshold_secs: 60,
         },
         runner_docker: RunnerDockerConfig::default(),
         metrics: MetricsConfig::default(),
+        output: OutputConfig::default(),
+        approval: ApprovalConfig::default(),
+        stats: StatsConfig::default(),
+        archive: ArchiveConfig::default(),
+        anomaly: AnomalyConfig::default(),
+        blob_store: BlobStoreConfig::default(),
+        evidence: EvidenceConfig::default(),
+        maintenance: MaintenanceConfig::default(),
+        audit: AuditConfig::default(),
+        job_budget: JobBudgetConfig::default(),
+        network_backoff: NetworkBackoffConfig::default(),
+        job_dispatch: JobDispatchConfig::default(),
+        advisory: AdvisoryConfig::default(),
+        artifact_promotion: ArtifactPromotionConfig::default(),
+        load_test: LoadTestConfig::default(),
+        i18n: I18nConfig::default(),
+        notifications: NotificationConfig::default(),
+        host_key_drift: HostKeyDriftConfig::default(),
+        query_cache: QueryCacheConfig::default(),
+        jwt: JwtConfig::default(),
+        two_factor: TwoFactorConfig::default(),
+        soft_delete: SoftDeleteConfig::default(),
+        payload_limits: PayloadLimitsConfig::default(),
     }
 }
 
@@ -4,16 +4,23 @@
 
 use ops_service::auth::password::PasswordHasher;
 use ops_service::config::{
-    AppConfig, ConcurrencyConfig, DatabaseConfig, LoggingConfig, MetricsConfig, RabbitMqConfig,
-    RunnerDockerConfig, SecurityConfig, ServerConfig, SshConfig,
+    AdvisoryConfig, AnomalyConfig, AppConfig, ApprovalConfig, ArchiveConfig,
+    ArtifactPromotionConfig, AuditConfig, BlobStoreConfig, ConcurrencyConfig, DatabaseConfig,
+    EvidenceConfig, HostKeyDriftConfig, I18nConfig, JobBudgetConfig, JobDispatchConfig, JwtConfig,
+    LoadTestConfig, LoggingConfig, MaintenanceConfig, MetricsConfig, NetworkBackoffConfig,
+    NotificationConfig, OutputConfig, PayloadLimitsConfig, QueryCacheConfig, RabbitMqConfig,
+    RunnerDockerConfig, SecurityConfig, ServerConfig, SoftDeleteConfig, SshConfig, StatsConfig,
+    TwoFactorConfig,
 };
 use ops_service::models::asset::*;
 use ops_service::models::role::*;
+use ops_service::models::soft_delete::SoftDeleteResource;
 use ops_service::models::user::*;
 use ops_service::repository::asset_repo::AssetRepository;
 use ops_service::repository::audit_repo::AuditRepository;
 use ops_service::repository::role_repo::RoleRepository;
 use ops_service::repository::user_repo::UserRepository;
+use ops_service::services::soft_delete;
 use secrecy::{ExposeSecret, SecretString};
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -37,13 +44,16 @@ fn create_test_config() -> AppConfig {
             idle_timeout_secs: 300,
             max_lifetime_secs: 1800,
             auto_create_if_missing: true,
+            auto_migrate: true,
         },
         logging: LoggingConfig {
             level: "debug".to_string(),
             format: "pretty".to_string(),
         },
         security: SecurityConfig {
-            jwt_secret: SecretString::from("test-secret-key-for-testing-only-min-32-chars".to_string()),
+            jwt_secret: SecretString::from(
+                "test-secret-key-for-testing-only-min-32-chars".to_string(),
+            ),
             access_token_exp_secs: 300,
             refresh_token_exp_secs: 3600,
             password_min_length: 8,
@@ -55,8 +65,10 @@ fn create_test_config() -> AppConfig {
             rate_limit_rps: 1000,
             trust_proxy: false,
             allowed_ips: None,
+            ip_allowlist: Default::default(),
             runner_api_key: None,
             runner_webhook_hmac_secret: None,
+            credential_encryption_key: None,
             runner_webhook_max_skew_secs: 300,
             runner_webhook_nonce_ttl_secs: 600,
             login_rate_limit_max_attempts: 10,
@@ -72,6 +84,7 @@ fn create_test_config() -> AppConfig {
             command_timeout_secs: 300,
             host_key_verification: "accept".to_string(),
             known_hosts_file: None,
+            max_output_bytes: 64 * 1024 * 1024,
         },
         concurrency: ConcurrencyConfig {
             global_limit: 100,
@@ -89,9 +102,35 @@ fn create_test_config() -> AppConfig {
             runner_exchange: "ops.runner".to_string(),
             pool_size: 5,
             publish_timeout_secs: 10,
+            reconnect_initial_backoff_ms: 500,
+            reconnect_max_backoff_secs: 30,
+            unavailable_threshold_secs: 60,
         },
         runner_docker: RunnerDockerConfig::default(),
         metrics: MetricsConfig::default(),
+        output: OutputConfig::default(),
+        approval: ApprovalConfig::default(),
+        stats: StatsConfig::default(),
+        archive: ArchiveConfig::default(),
+        anomaly: AnomalyConfig::default(),
+        blob_store: BlobStoreConfig::default(),
+        evidence: EvidenceConfig::default(),
+        maintenance: MaintenanceConfig::default(),
+        audit: AuditConfig::default(),
+        job_budget: JobBudgetConfig::default(),
+        network_backoff: NetworkBackoffConfig::default(),
+        job_dispatch: JobDispatchConfig::default(),
+        advisory: AdvisoryConfig::default(),
+        artifact_promotion: ArtifactPromotionConfig::default(),
+        load_test: LoadTestConfig::default(),
+        i18n: I18nConfig::default(),
+        notifications: NotificationConfig::default(),
+        host_key_drift: HostKeyDriftConfig::default(),
+        query_cache: QueryCacheConfig::default(),
+        jwt: JwtConfig::default(),
+        two_factor: TwoFactorConfig::default(),
+        soft_delete: SoftDeleteConfig::default(),
+        payload_limits: PayloadLimitsConfig::default(),
     }
 }
 
@@ -99,7 +138,7 @@ fn create_test_config() -> AppConfig {
 async fn setup_test_db() -> PgPool {
     let config = create_test_config();
 
-    let pool = sqlx::PgPool::connect(&config.database.url.expose_secret())
+    let pool = sqlx::PgPool::connect(config.database.url.expose_secret())
         .await
         .expect("Failed to connect to test database");
 
@@ -337,7 +376,10 @@ async fn test_user_repository_delete() {
         .await
         .unwrap();
 
-    let deleted = repo.delete(user.id).await.unwrap();
+    // 用户删除为软删除，常规查询不再可见
+    let deleted = soft_delete::mark_deleted(&pool, SoftDeleteResource::User, user.id, user.id)
+        .await
+        .unwrap();
     assert!(deleted);
 
     let found = repo.find_by_id(&user.id).await.unwrap();
@@ -479,12 +521,16 @@ async fn test_asset_repository_create_group() {
         description: Some("Web server group".to_string()),
         environment: "production".to_string(),
         parent_id: None,
+        concurrent_limit: None,
+        is_production: false,
+        is_critical: true,
     };
 
     let group = repo.create_group(&req, Uuid::new_v4()).await.unwrap();
 
     assert_eq!(group.name, "web-servers");
     assert_eq!(group.environment, "production");
+    assert!(group.is_critical);
 
     // 查找组
     let found = repo.get_group(group.id).await.unwrap();
@@ -504,6 +550,9 @@ async fn test_asset_repository_create_host() {
         description: None,
         environment: "dev".to_string(),
         parent_id: None,
+        concurrent_limit: None,
+        is_production: false,
+        is_critical: false,
     };
     let group = repo.create_group(&group_req, Uuid::new_v4()).await.unwrap();
 
@@ -527,6 +576,10 @@ async fn test_asset_repository_create_host() {
         ssh_key_passphrase: None,
         host_key_verification: None,
         known_hosts: None,
+        output_encoding: None,
+        connection_type: Default::default(),
+        connection_options: None,
+        connection_token: None,
     };
 
     let host = repo.create_host(&host_req, Uuid::new_v4()).await.unwrap();
@@ -553,6 +606,9 @@ async fn test_asset_repository_list_hosts() {
         description: None,
         environment: "test".to_string(),
         parent_id: None,
+        concurrent_limit: None,
+        is_production: false,
+        is_critical: false,
     };
     let group = repo.create_group(&group_req, Uuid::new_v4()).await.unwrap();
 
@@ -577,6 +633,10 @@ async fn test_asset_repository_list_hosts() {
             ssh_key_passphrase: None,
             host_key_verification: None,
             known_hosts: None,
+            output_encoding: None,
+            connection_type: Default::default(),
+            connection_options: None,
+            connection_token: None,
         };
         repo.create_host(&host_req, Uuid::new_v4()).await.unwrap();
     }
@@ -632,10 +692,13 @@ async fn test_audit_repository_insert_and_query() {
         resource_type: Some("user".to_string()),
         resource_id: None,
         action: None,
+        action_prefix: None,
         result: None,
+        source_ip: None,
         start_time: None,
         end_time: None,
         trace_id: None,
+        search: None,
     };
 
     let logs = repo.query_audit_logs(&filters, 10, 0).await.unwrap();
@@ -680,12 +743,108 @@ async fn test_audit_repository_count() {
         resource_type: Some("user".to_string()),
         resource_id: None,
         action: None,
+        action_prefix: None,
         result: None,
+        source_ip: None,
         start_time: None,
         end_time: None,
         trace_id: None,
+        search: None,
     };
 
     let count = repo.count_audit_logs(&filters).await.unwrap();
     assert_eq!(count, 5);
 }
+
+#[tokio::test]
+#[ignore = "需要数据库连接"]
+async fn test_audit_repository_keyset_and_facets() {
+    use ops_service::models::audit::*;
+
+    let pool = setup_test_db().await;
+    let repo = AuditRepository::new(pool.clone());
+
+    let subject_id = Uuid::new_v4();
+    let base = chrono::Utc::now();
+    let actions = [
+        "job.create",
+        "job.create",
+        "job.cancel",
+        "user.login",
+        "job_tag.create",
+    ];
+    for (i, action) in actions.iter().enumerate() {
+        let log = AuditLog {
+            id: Uuid::new_v4(),
+            subject_id,
+            subject_type: "user".to_string(),
+            subject_name: Some("auditor".to_string()),
+            action: action.to_string(),
+            resource_type: action.split('.').next().unwrap().to_string(),
+            resource_id: None,
+            resource_name: Some(format!("deploy-web-{}", i)),
+            changes: None,
+            changes_summary: None,
+            source_ip: Some(if i % 2 == 0 { "10.0.0.1" } else { "10.0.0.2" }.to_string()),
+            user_agent: None,
+            trace_id: None,
+            request_id: None,
+            result: if i == 2 { "failure" } else { "success" }.to_string(),
+            error_message: None,
+            occurred_at: base - chrono::Duration::seconds(i as i64),
+        };
+        repo.insert_audit_log(&log).await.unwrap();
+    }
+
+    // 键集分页：两页拼接后与一次性查询一致
+    let filters = AuditLogFilters {
+        subject_id: Some(subject_id),
+        ..Default::default()
+    };
+    let all = repo.query_audit_logs(&filters, 10, 0).await.unwrap();
+    assert_eq!(all.len(), 5);
+    let first = repo.query_audit_logs(&filters, 3, 0).await.unwrap();
+    let second = repo
+        .query_audit_logs_after(&filters, &AuditCursor::after(&first[2]), 3)
+        .await
+        .unwrap();
+    let paged: Vec<Uuid> = first
+        .iter()
+        .chain(second.iter())
+        .map(|log| log.id)
+        .collect();
+    assert_eq!(paged, all.iter().map(|log| log.id).collect::<Vec<_>>());
+
+    // 操作前缀不匹配 job_tag.*，来源 IP 与全文检索
+    let filters = AuditLogFilters {
+        subject_id: Some(subject_id),
+        action_prefix: Some("job".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(repo.count_audit_logs(&filters).await.unwrap(), 3);
+    let filters = AuditLogFilters {
+        subject_id: Some(subject_id),
+        source_ip: Some("10.0.0.2".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(repo.count_audit_logs(&filters).await.unwrap(), 2);
+    let filters = AuditLogFilters {
+        subject_id: Some(subject_id),
+        search: Some("WEB-3".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(repo.count_audit_logs(&filters).await.unwrap(), 1);
+
+    // 分面忽略自身维度的过滤
+    let filters = AuditLogFilters {
+        subject_id: Some(subject_id),
+        action: Some("job.create".to_string()),
+        ..Default::default()
+    };
+    let facets = repo.audit_facets(&filters, 10).await.unwrap();
+    assert_eq!(facets.action.len(), 4);
+    assert_eq!(facets.action[0].value, "job.create");
+    assert_eq!(facets.action[0].count, 2);
+    assert_eq!(facets.result.len(), 1);
+    assert_eq!(facets.result[0].count, 2);
+}